@@ -0,0 +1,88 @@
+//! Assembly of a small JSON response: the general path, formatting `Date`
+//! for every response, against the small-response path with the cached
+//! value.
+//!
+//! Run with `cargo bench --bench response_assembly`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use axum::body::Body;
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode};
+
+#[allow(dead_code)]
+#[path = "../src/http/date.rs"]
+mod date;
+#[allow(dead_code)]
+#[path = "../src/http/small_response.rs"]
+mod small_response;
+
+const ITERATIONS: u32 = 1_000_000;
+
+const HEADERS: [(&str, &str); 3] = [
+    ("Content-Type", "application/json"),
+    ("X-Request-ID", "4bf92f3577b34da6a3ce929d0e0e4736"),
+    ("Cache-Control", "no-store"),
+];
+
+fn body() -> Vec<u8> {
+    br#"{"id":42,"name":"Ada","active":true}"#.to_vec()
+}
+
+fn general() -> axum::response::Response {
+    let mut map = HeaderMap::with_capacity(HEADERS.len() + 2);
+    for (name, value) in HEADERS {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            map.append(name, value);
+        }
+    }
+    map.insert(header::DATE, date::format_now());
+    let body = body();
+    map.insert(header::CONTENT_LENGTH, HeaderValue::from(body.len()));
+    let mut res = axum::response::Response::new(Body::from(body));
+    *res.status_mut() = StatusCode::OK;
+    *res.headers_mut() = map;
+    res
+}
+
+fn small() -> axum::response::Response {
+    small_response::build(
+        StatusCode::OK,
+        HEADERS.into_iter(),
+        body(),
+        date::header_value(),
+    )
+}
+
+fn run(name: &str, assemble: fn() -> axum::response::Response) {
+    for _ in 0..ITERATIONS / 10 {
+        black_box(assemble());
+    }
+    let started = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(assemble());
+    }
+    let elapsed = started.elapsed();
+    println!(
+        "{:<8} {:>8.1} ns/response",
+        name,
+        elapsed.as_nanos() as f64 / ITERATIONS as f64
+    );
+}
+
+fn main() {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_time()
+        .build()
+        .expect("runtime");
+    let _guard = runtime.enter();
+    date::header_value();
+    std::thread::sleep(Duration::from_millis(10));
+
+    run("general", general);
+    run("small", small);
+}
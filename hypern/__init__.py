@@ -7,11 +7,28 @@ from ._hypern import (
     BlockingExecutor,
     SSEEvent,
     SSEStream,
+    sse_stats,
+    request_decompression_stats,
+    dispatch_timing_stats,
+    stream_drain_stats,
+    disconnect_stats,
+    panic_stats,
+    ServerMetrics,
+    server_metrics,
+    large_response_stats,
+    listener_options,
+    generate_request_id,
     StreamingResponse,
     FormData,
     UploadedFile,
+    MultipartStream,
+    MultipartPart,
+    NdjsonReader,
+    CsvReader,
     Request,
+    AuthInfo,
     Response,
+    HeaderPolicy,
     Route,
     # Database
     ConnectionPool,
@@ -24,14 +41,24 @@ from ._hypern import (
     HealthCheck,
     ReloadConfig,
     ReloadManager,
+    # Testing
+    TestClient,
+    TestResponse,
     # Logging
     LogConfig,
     # Utils (Rust-accelerated)
     PageInfo,
     paginate,
+    # Request context
+    current_context,
+    current_request_id,
+    request_context_var,
 )
 from .application import Hypern, create_app, hypern
 
+# ContextVar holding the current request's context dict (None outside handlers)
+context = request_context_var()
+
 # Dependency Injection - standalone decorator
 from .di import inject
 
@@ -58,6 +85,7 @@ from .blocking import (
 from .exceptions import (
     BadRequest,
     Conflict,
+    ErrorCode,
     ExceptionHandler,
     Forbidden,
     HTTPException,
@@ -70,6 +98,13 @@ from .exceptions import (
     UnprocessableEntity,
     error_boundary,
     exception_handler,
+    # Request body errors (raised by Request.json)
+    RequestBodyError,
+    UnsupportedMediaTypeError,
+    PayloadTooLargeError,
+    BodyDecodeError,
+    # Raised by Hypern.start when the listener can't be acquired
+    HypernBindError,
 )
 
 # Middleware (Rust-based)
@@ -83,18 +118,27 @@ from .middleware import (
     RequestIdMiddleware,
     LogMiddleware,
     BasicAuthMiddleware,
+    IdempotencyMiddleware,
+    SingleflightMiddleware,
+    singleflight_stats,
     # Utilities
     MiddlewareStack,
     after_request,
     before_request,
     middleware,
+    load_middleware_config,
+    merge_middleware_config,
 )
 
 # Database module
 from .database import Database, db as get_database
 
+# Generator-backed streaming responses
+from .streaming import Stream, stream
+
 # Router module
-from .router import RouteBuilder, Router
+from .router import RouteBuilder, RouteGroup, Router
+from .lazy import LazyHandler
 from .validation import (
     ValidationError,
     Validator,
@@ -150,17 +194,29 @@ from .realtime import (
     ChannelStats,
     Subscriber,
     TopicMatcher,
+    ChannelClosed,
+    SubscriptionError,
+    PublishError,
+    MessageSchema,
     PresenceTracker,
     PresenceInfo,
     PresenceDiff,
+    PresenceUpdate,
     RealtimeBroadcast,
     BroadcastConfig,
     BroadcastStats,
     BroadcastSubscriber,
     BackpressurePolicy,
+    ClientQueueConfig,
+    ClientQueueStats,
+    OverflowPolicy,
+    realtime_poll_stats,
+    RealtimeIdentity,
+    realtime_identity,
     HeartbeatMonitor,
     HeartbeatConfig,
     HeartbeatStats,
+    HeartbeatClientStats,
     RealtimeHub,
 )
 
@@ -173,11 +229,21 @@ __all__ = [
     "create_app",
     "hypern",
     "Request",
+    "AuthInfo",
     "Response",
+    "HeaderPolicy",
     "Route",
+    # Request context
+    "context",
+    "current_context",
+    "current_request_id",
     # File Uploads
     "FormData",
     "UploadedFile",
+    "MultipartStream",
+    "MultipartPart",
+    "NdjsonReader",
+    "CsvReader",
     # Dependency Injection
     "Context",
     "DIContainer",
@@ -202,7 +268,20 @@ __all__ = [
     # Streaming/SSE
     "SSEEvent",
     "SSEStream",
+    "sse_stats",
+    "request_decompression_stats",
+    "dispatch_timing_stats",
+    "stream_drain_stats",
+    "disconnect_stats",
+    "panic_stats",
+    "ServerMetrics",
+    "server_metrics",
+    "large_response_stats",
+    "listener_options",
+    "generate_request_id",
     "StreamingResponse",
+    "Stream",
+    "stream",
     # Database
     "ConnectionPool",
     "PoolConfig",
@@ -213,6 +292,8 @@ __all__ = [
     # Router
     "Router",
     "RouteBuilder",
+    "RouteGroup",
+    "LazyHandler",
     # Middleware (Rust-based)
     "CorsMiddleware",
     "RateLimitMiddleware",
@@ -222,13 +303,19 @@ __all__ = [
     "RequestIdMiddleware",
     "LogMiddleware",
     "BasicAuthMiddleware",
+    "IdempotencyMiddleware",
+    "SingleflightMiddleware",
+    "singleflight_stats",
     # Middleware utilities
     "MiddlewareStack",
     "middleware",
     "before_request",
     "after_request",
+    "load_middleware_config",
+    "merge_middleware_config",
     # Exceptions
     "HTTPException",
+    "ErrorCode",
     "BadRequest",
     "Unauthorized",
     "Forbidden",
@@ -239,6 +326,11 @@ __all__ = [
     "TooManyRequests",
     "InternalServerError",
     "ServiceUnavailable",
+    "RequestBodyError",
+    "UnsupportedMediaTypeError",
+    "PayloadTooLargeError",
+    "BodyDecodeError",
+    "HypernBindError",
     "ExceptionHandler",
     "exception_handler",
     "error_boundary",
@@ -283,6 +375,9 @@ __all__ = [
     "HealthCheck",
     "ReloadConfig",
     "ReloadManager",
+    # Testing
+    "TestClient",
+    "TestResponse",
     # Logging
     "LogConfig",
     # Database
@@ -293,17 +388,29 @@ __all__ = [
     "ChannelStats",
     "Subscriber",
     "TopicMatcher",
+    "ChannelClosed",
+    "SubscriptionError",
+    "PublishError",
+    "MessageSchema",
     "PresenceTracker",
     "PresenceInfo",
     "PresenceDiff",
+    "PresenceUpdate",
     "RealtimeBroadcast",
     "BroadcastConfig",
     "BroadcastStats",
     "BroadcastSubscriber",
     "BackpressurePolicy",
+    "ClientQueueConfig",
+    "ClientQueueStats",
+    "OverflowPolicy",
+    "realtime_poll_stats",
+    "RealtimeIdentity",
+    "realtime_identity",
     "HeartbeatMonitor",
     "HeartbeatConfig",
     "HeartbeatStats",
+    "HeartbeatClientStats",
     "RealtimeHub",
     # Utils
     "PageInfo",
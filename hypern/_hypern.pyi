@@ -2,11 +2,234 @@ from __future__ import annotations
 
 from dataclasses import dataclass
 from enum import Enum
-from typing import Any, Callable, Dict, List, Optional
+from types import ModuleType
+from typing import Any, Awaitable, Callable, Dict, Generator, Iterable, List, Optional, Sequence, Tuple, Type, TypeVar, Union
+
+T = TypeVar("T")
 
 
 class Request:
-    pass
+    @property
+    def context(self) -> Optional[Dict[str, Any]]:
+        """Request context dict; same values as ``current_context()`` in the handler."""
+        ...
+    def json(self, force: bool = False, max_bytes: Optional[int] = None) -> Any:
+        """
+        Parse the body as JSON, transcoding per the Content-Type charset.
+
+        Raises UnsupportedMediaTypeError (415) for non-JSON content types
+        unless ``force=True``, PayloadTooLargeError (413) above ``max_bytes``
+        or the route's ``max_json_bytes``, and BodyDecodeError (400) for
+        invalid encodings or malformed JSON.
+        """
+        ...
+    def is_json(self) -> bool: ...
+    def query_list(self, name: str, strip_brackets: bool = True) -> List[str]:
+        """Every value of a repeated query parameter, in order; ``name[]`` keys match too unless ``strip_brackets=False``."""
+        ...
+    def query_params_multi(self, strip_brackets: bool = True) -> Dict[str, List[str]]:
+        """All query parameters as lists of values, keys in the order first seen."""
+        ...
+    def param_typed(self, name: str) -> Optional[Any]:
+        """
+        Path parameter converted by its ``{name:type}`` constraint: int, float,
+        ``uuid.UUID`` or ``datetime.date``. Unconstrained parameters are str.
+        """
+        ...
+    def params_typed(self) -> Dict[str, Any]:
+        """All path parameters, converted as in ``param_typed()``."""
+        ...
+    @property
+    def raw_path(self) -> str:
+        """Path exactly as received, before percent-decoding and normalization."""
+        ...
+    @property
+    def scheme(self) -> Optional[str]:
+        """"http" or "https" for the connection itself, ignoring proxy headers."""
+        ...
+    @property
+    def peer_addr(self) -> Optional[tuple[str, int]]: ...
+    @property
+    def alpn_protocol(self) -> Optional[str]: ...
+    @property
+    def peer_cert(self) -> Optional[Dict[str, Any]]:
+        """Verified client certificate (subject, issuer, serial, sans, not_before, not_after, fingerprint)."""
+        ...
+    def dep(self, name: str) -> Any:
+        """Resolve a dependency registered with ``Server.provide``."""
+        ...
+    def state(self, key: str, default: Any = None) -> Any:
+        """Middleware state value marked visible to handlers, or ``default``."""
+        ...
+    def state_str(self, key: str, default: Optional[str] = None) -> Optional[str]:
+        """``state(key)`` as a str; TypeError if it has another type."""
+        ...
+    def state_int(self, key: str, default: Optional[int] = None) -> Optional[int]:
+        """``state(key)`` as an int; TypeError if it has another type."""
+        ...
+    def state_bool(self, key: str, default: Optional[bool] = None) -> Optional[bool]:
+        """``state(key)`` as a bool; TypeError if it has another type."""
+        ...
+    @property
+    def auth(self) -> AuthInfo:
+        """Who the auth middleware authenticated."""
+        ...
+    @property
+    def route_meta(self) -> Dict[str, str]:
+        """``metadata`` of the matched route."""
+        ...
+    def flag(self, name: str) -> bool:
+        """Whether the feature flag is on for this request; False for flags never set."""
+        ...
+    def if_none_match(self) -> List[str]:
+        """Entity tags of If-None-Match as sent (``'"a"'``, ``'W/"b"'`` or ``"*"``); empty without the header."""
+        ...
+    def deadline_remaining(self) -> Optional[float]:
+        """Seconds left before the route times out (0.0 once passed), or None without a timeout."""
+        ...
+    def is_disconnected(self) -> bool:
+        """True once the client has closed the connection; sync handlers should poll it in long loops."""
+        ...
+    def wait_disconnected(self) -> Awaitable[None]:
+        """Awaitable completing when the client closes the connection."""
+        ...
+    def multipart_stream(
+        self,
+        max_part_size: Optional[int] = None,
+        max_total_size: Optional[int] = None,
+        chunk_size: int = 65536,
+    ) -> MultipartStream:
+        """
+        Iterate over the parts of a multipart/form-data body as they are parsed.
+
+        Parts are consumed in order; moving to the next part skips the rest of
+        the current one. Raises UnsupportedMediaTypeError (415) for other
+        content types; iteration raises BodyDecodeError (400) with the byte
+        offset for malformed or truncated bodies and PayloadTooLargeError (413)
+        above ``max_part_size`` or ``max_total_size`` (default: the route's
+        ``max_body_size``).
+        """
+        ...
+    def ndjson(
+        self,
+        force: bool = False,
+        max_bad_lines: int = 0,
+        max_line_bytes: int = 1048576,
+        max_records: int = 1000000,
+        chunk_size: int = 65536,
+    ) -> NdjsonReader:
+        """
+        Iterate over an NDJSON body, one parsed JSON document per line.
+
+        Blank lines are skipped, and so are up to ``max_bad_lines`` lines that
+        are not valid JSON; the reader's ``bad_lines`` lists them afterwards.
+        Raises UnsupportedMediaTypeError (415) unless the content type is
+        NDJSON (or ``force`` is set); iteration raises BodyDecodeError (400)
+        past ``max_bad_lines`` and PayloadTooLargeError (413) over a limit.
+        """
+        ...
+    def csv(
+        self,
+        delimiter: str = ",",
+        has_header: bool = True,
+        force: bool = False,
+        max_field_bytes: int = 1048576,
+        max_rows: int = 1000000,
+        chunk_size: int = 65536,
+    ) -> CsvReader:
+        """
+        Iterate over the rows of a CSV body: dicts keyed by the header row, or
+        lists of strings when ``has_header`` is False.
+
+        Quoted fields may hold delimiters, doubled quotes and newlines, and a
+        leading BOM is dropped. Raises UnsupportedMediaTypeError (415) unless
+        the content type is CSV (or ``force`` is set); iteration raises
+        BodyDecodeError (400) for malformed rows and PayloadTooLargeError (413)
+        over a limit.
+        """
+        ...
+
+class AuthInfo:
+    """Authentication state the middleware chain left for the handler; falsy when unauthenticated."""
+    @property
+    def user_id(self) -> Optional[str]: ...
+    @property
+    def roles(self) -> List[str]: ...
+    @property
+    def is_authenticated(self) -> bool: ...
+    def has_role(self, role: str) -> bool: ...
+    def __bool__(self) -> bool: ...
+
+class NdjsonReader:
+    """Iterator of the documents of an NDJSON body, from ``Request.ndjson()``."""
+    @property
+    def bad_lines(self) -> List[Tuple[int, str]]:
+        """Lines skipped as invalid JSON, as ``(line number, error)``."""
+        ...
+    @property
+    def lines(self) -> int: ...
+    @property
+    def records(self) -> int: ...
+    def __iter__(self) -> NdjsonReader: ...
+    def __next__(self) -> Any: ...
+
+class CsvReader:
+    """Iterator of the rows of a CSV body, from ``Request.csv()``."""
+    @property
+    def header(self) -> Optional[List[str]]: ...
+    @property
+    def rows(self) -> int: ...
+    def __iter__(self) -> CsvReader: ...
+    def __next__(self) -> Union[Dict[str, str], List[str]]: ...
+
+class MultipartStream:
+    """Async iterator of ``MultipartPart`` objects, from ``Request.multipart_stream()``."""
+    def __aiter__(self) -> MultipartStream: ...
+    def __anext__(self) -> Awaitable[MultipartPart]: ...
+
+class MultipartPart:
+    """One part of a multipart body, read as it is parsed."""
+    @property
+    def name(self) -> Optional[str]: ...
+    @property
+    def filename(self) -> Optional[str]:
+        """Original filename, None for plain fields."""
+        ...
+    @property
+    def content_type(self) -> Optional[str]: ...
+    @property
+    def headers(self) -> Dict[str, str]:
+        """All part headers, names lowercased."""
+        ...
+    def read(self, n: int = -1) -> Awaitable[bytes]:
+        """Up to ``n`` bytes of the part body (all that is left when negative); ``b""`` at its end."""
+        ...
+    def __aiter__(self) -> MultipartPart: ...
+    def __anext__(self) -> Awaitable[bytes]: ...
+
+class RequestBodyError(ValueError):
+    status_code: int
+
+class UnsupportedMediaTypeError(RequestBodyError): ...
+class PayloadTooLargeError(RequestBodyError): ...
+class BodyDecodeError(RequestBodyError): ...
+
+class HypernBindError(OSError):
+    """The server could not acquire its listening socket."""
+    host: str
+    port: int
+
+def current_context() -> Optional[Dict[str, Any]]:
+    """Copy of the current request's context dict, or None outside a handler."""
+    ...
+
+def current_request_id() -> Optional[str]:
+    """ID of the request being handled, or None outside a handler."""
+    ...
+
+def request_context_var() -> Any:
+    """The ``hypern.context`` ContextVar."""
+    ...
 
 class Response:
     def status(self, status: int) -> Response: ...
@@ -29,12 +252,35 @@ class Response:
     def content_type(self, mime_type: str) -> Response: ...
     def vary(self, header: str) -> Response: ...
     def etag(self, value: str) -> Response: ...
+    def with_etag(self, etag: str, status_if_match: int = 304) -> Response:
+        """Set the ETag and answer ``status_if_match`` with no body when If-None-Match matches it (weakly)."""
+        ...
     def location(self, url: str) -> Response: ...
     def links(self, links: Dict[str, str]) -> Response: ...
     # SSE/Streaming methods
     def sse(self, events: List["SSEEvent"]) -> Response: ...
     def sse_event(self, data: str, event: Optional[str] = None, id: Optional[str] = None) -> Response: ...
     def sse_headers(self) -> Response: ...
+    def sse_stream(self, generator: Any, buffer_size: int = 16) -> Response:
+        """Stream SSE events from a generator or async generator as they are yielded."""
+        ...
+    def stream(self, source: Any, content_type: Optional[str] = None, buffer_size: int = 16) -> Response:
+        """Stream a generator, async generator or iterable of bytes/str as the body."""
+        ...
+    def sse_live(
+        self, buffer_size: int = 100, keepalive_secs: Optional[float] = None, connected: bool = False
+    ) -> "SSEStream":
+        """Start a live SSE response; headers are sent right away and events as they arrive."""
+        ...
+    def stream_live(
+        self,
+        content_type: Optional[str] = None,
+        buffer_size: int = 100,
+        high_watermark: int = 65536,
+        low_watermark: Optional[int] = None,
+    ) -> "StreamingResponse":
+        """Start a live chunked response fed by the returned stream; headers are sent right away."""
+        ...
     
 @dataclass
 class Server:
@@ -45,22 +291,184 @@ class Server:
 
     def add_route(self, route: Route) -> None: ...
     def set_router(self, router: Router) -> None: ...
-    def use_middleware(self, middleware: Any) -> None: ...
+    def use_middleware(
+        self,
+        middleware: Any,
+        priority: Optional[int] = None,
+        before: Optional[str] = None,
+        after: Optional[str] = None,
+        methods: str | List[str] | None = None,
+    ) -> None:
+        """Register a builtin middleware; lower priority runs earlier, anchors are resolved at start()."""
+        ...
     def start(self, host: str, port: int, num_processes: int, workers_threads: int, max_blocking_threads: int, max_connections: int) -> None: ...
     def enable_http2(self) -> None: ...
+    def set_header_policy(self, policy: HeaderPolicy) -> None: ...
     def set_reload_config(self, config: "ReloadConfig") -> None: ...
     def set_log_config(self, config: "LogConfig") -> None: ...
     def get_reload_manager(self) -> Optional["ReloadManager"]: ...
     def get_health_check(self) -> Optional["HealthCheck"]: ...
+    def add_health_check(self, name: str, check: Callable[[], Any], critical: bool = True) -> None:
+        """Register a check run by the deep health probe; a failing critical check makes it answer 503."""
+        ...
+    def set_error_handler(self, status: int, handler: Callable[..., Any]) -> None:
+        """Answer framework errors with this status (router 404/405, middleware short-circuits) with handler(req, res)."""
+        ...
     def graceful_reload(self) -> None: ...
     def hot_reload(self) -> None: ...
+    def __init__(
+        self,
+        cpu_affinity: str | List[List[int]] | None = None,
+        allowed_hosts: Optional[List[str]] = None,
+        force_https: bool = False,
+        hsts_preload: bool = False,
+        trusted_proxies: Optional[List[str]] = None,
+        https_exempt_paths: Optional[List[str]] = None,
+        decompress_requests: bool = False,
+        max_decompressed_size: int = 10485760,
+        tls_cert_path: Optional[str] = None,
+        tls_key_path: Optional[str] = None,
+        tls_client_ca_path: Optional[str] = None,
+        tls_require_client_cert: bool = False,
+        server_timing: bool = False,
+        gil_metrics: bool = False,
+        gil_hold_warn_ms: float = 100.0,
+        metrics_publish_interval: float = 1.0,
+        warmup_paths: Optional[List[str]] = None,
+        eager_import: bool = False,
+        warmup_strict: bool = False,
+        merge_slashes: bool = True,
+        path_decoding: str = "unreserved",
+        strict_path_encoding: bool = False,
+        stream_threshold_bytes: int = 1048576,
+        expect_continue: bool = True,
+        backlog: int = 1024,
+        reuse_port: bool = False,
+        tcp_nodelay: bool = True,
+        keepalive_idle: Optional[int] = 60,
+        keepalive_interval: Optional[int] = None,
+        keepalive_count: Optional[int] = None,
+        memory_debug: bool = False,
+        default_auth: str = "public",
+        max_connections_per_ip: Optional[int] = None,
+        header_read_timeout: Optional[float] = 30.0,
+        connection_limit_exempt: Optional[List[str]] = None,
+        max_header_bytes: Optional[int] = 65536,
+        max_header_count: Optional[int] = 100,
+        max_uri_length: Optional[int] = 8192,
+        error_format: str = "auto",
+    ) -> None: ...
+    def worker_status(self) -> Dict[str, Any]: ...
+    def stats(self) -> Dict[str, Any]: ...
+    def describe(self) -> Dict[str, Any]:
+        """Effective configuration with secrets redacted: version, pid, allocator, listen,
+        worker_pids, routes, middleware, features, logging, databases and config."""
+        ...
+    def configure_middleware(
+        self,
+        config: Optional[Union[Dict[str, Any], List[Dict[str, Any]]]] = None,
+        isolate_errors: bool = False,
+        slow_threshold_ms: Optional[int] = None,
+    ) -> None:
+        """Set chain options and register the middleware described by `config`; raises ValueError listing every problem."""
+        ...
+    def describe_middleware(self) -> Dict[str, Any]:
+        """Resolved middleware order: {"before": [{name, priority, anchors}], "after": [...], "error_handlers": n}."""
+        ...
+    def middleware_stats(self) -> Tuple[int, int, int]: ...
+    def middleware_instrumentation(self) -> Dict[str, Dict[str, float]]: ...
+    def set_sse_keepalive(self, secs: Optional[float] = None) -> None: ...
+    def set_realtime_poll(
+        self,
+        manager: ChannelManager,
+        path: str = "/realtime/poll",
+        max_wait_secs: float = 25.0,
+        max_polls_per_client: int = 4,
+        require_auth_for_realtime: bool = False,
+        on_connect: Optional[Callable[[RealtimeIdentity, str], Any]] = None,
+    ) -> None: ...
+    @staticmethod
+    def provide(
+        name: str,
+        factory: Callable[..., Any],
+        scope: str = "app",
+        teardown: Optional[Callable[[Any], Any]] = None,
+    ) -> None: ...
+    @staticmethod
+    def update_config(settings: Dict[str, Any], source: Optional[str] = None) -> int: ...
+    @staticmethod
+    def set_flag(
+        name: str,
+        enabled: bool = True,
+        percentage: Optional[float] = None,
+        sticky_by: str = "user_id",
+        off_status: int = 404,
+        source: Optional[str] = None,
+    ) -> int: ...
+    @staticmethod
+    def invalidate_route_cache(path: str) -> int:
+        """Drop the memoized responses of the routes at ``path`` in this process; returns how many matched."""
+        ...
+    @staticmethod
+    def live_config() -> Dict[str, Any]: ...
 
 class Route:
     path: str
     function: Callable[[Request, Response], Any]
     method: str
+    """Canonical spec: ``"GET"``, ``"GET,POST"`` or ``"*"``; assigning re-validates"""
+    methods: List[str]
+    """The bound methods; ``["*"]`` for a wildcard route"""
     doc: str | None = None
+    max_json_bytes: int | None = None
+    host: str | None = None
+    tags: List[str] = []
+    timeout: float | None
+    max_body_size: int | None
+    cache_ttl: int | None
+    log: bool
+    metadata: Dict[str, str]
+    coalesce: bool
+    response_fields: Dict[str, Any] | None
+    """``{"include": [...] | None, "exclude": [...], "strict": bool}``"""
+    retry: RetryPolicy | None
+    retry_unsafe: bool
+    auth: str | List[str] | None
+    """``"public"``, ``"optional"``, ``"required"`` or roles; None uses the server's ``default_auth``"""
+    feature_flag: str | None
+    """Flag that must be on for requests to reach the handler"""
+    etag_provider: str | None
+    """ETag provider answering matching conditional GETs with 304 before the handler"""
+    memoize_ttl_secs: float | None
+    """Seconds the first 200 response to a GET is kept and served without the handler"""
+    memoize_vary: List[str]
+    """Request headers whose values get their own memoized response"""
 
+    def __init__(
+        self,
+        path: str,
+        function: Callable[..., Any],
+        method: str | List[str] | None = None,
+        doc: str | None = None,
+        max_json_bytes: int | None = None,
+        host: str | None = None,
+        tags: List[str] | None = None,
+        timeout: float | str | None = None,
+        max_body_size: int | str | None = None,
+        cache_ttl: int | str | None = None,
+        log: bool = True,
+        metadata: Dict[str, str] | None = None,
+        coalesce: bool = False,
+        response_fields: Dict[str, Any] | None = None,
+        methods: str | List[str] | None = None,
+        retry: RetryPolicy | None = None,
+        retry_unsafe: bool = False,
+        auth: str | List[str] | None = None,
+        feature_flag: str | None = None,
+        etag_provider: str | None = None,
+        memoize_ttl_secs: float | str | None = None,
+        memoize_vary: List[str] | None = None,
+    ) -> None: ...
     def matches(self, path: str, method: str) -> str: ...
     def clone_route(self) -> Route: ...
     def update_path(self, new_path: str) -> None: ...
@@ -71,20 +479,77 @@ class Route:
     def normalized_path(self) -> str: ...
     def same_handler(self, other: Route) -> bool: ...
 
+class RetryPolicy:
+    """When, and how often, a route's handler runs again after a transient failure."""
+
+    max_attempts: int
+    on_statuses: List[int]
+    on_exceptions: List[str]
+    backoff_ms: int
+    jitter: bool
+
+    def __init__(
+        self,
+        max_attempts: int = 3,
+        on_statuses: List[int] | None = None,
+        on_exceptions: List[str | Type[BaseException]] | None = None,
+        backoff_ms: int = 50,
+        jitter: bool = True,
+    ) -> None: ...
+
 class Router:
+    path: str
     routes: List[Route]
-
-    def add_route(self, route: Route) -> None: ...
-    def remove_route(self, path: str, method: str) -> bool: ...
+    generation: int
+    strict_routes: bool
+
+    def __init__(self, path: str, strict_routes: bool = False) -> None: ...
+    def add_route(self, route: Route) -> None:
+        """Raises ValueError for a duplicate template, or under strict_routes
+        for one that differs only in parameter names or clashes with a
+        catch-all; those are logged as warnings otherwise."""
+        ...
+    def check(self) -> Dict[str, Any]:
+        """{"ok": bool, "errors": [conflict], "warnings": [conflict]}; each
+        conflict has kind ("duplicate", "ambiguous" or "catch_all"), method,
+        host, path, handler, other_path, other_handler and message."""
+        ...
+    def remove_route(self, method: str | List[str], path: str, host: str | None = None) -> bool: ...
+    def remove_prefix(self, prefix: str, host: str | None = None) -> bool: ...
     def get_route(self, path: str, method) -> Route | None: ...
     def get_routes_by_path(self, path: str) -> List[Route]: ...
     def get_routes_by_method(self, method: str) -> List[Route]: ...
     def extend_route(self, routes: List[Route]) -> None: ...
+    def route(self, path: str, methods: str | List[str], **options: Any) -> Callable[[Callable], Callable]:
+        """Decorator adding the handler with its arguments filled by name:
+        path parameters, the request/response, provided dependencies, and
+        query parameters for arguments with a default. Options are those of
+        Route. Raises ValueError for an argument none of these fills."""
+        ...
+    def get(self, path: str, **options: Any) -> Callable[[Callable], Callable]: ...
+    def post(self, path: str, **options: Any) -> Callable[[Callable], Callable]: ...
+    def put(self, path: str, **options: Any) -> Callable[[Callable], Callable]: ...
+    def patch(self, path: str, **options: Any) -> Callable[[Callable], Callable]: ...
+    def delete(self, path: str, **options: Any) -> Callable[[Callable], Callable]: ...
 
 @dataclass
 class SocketHeld:
     socket: Any
 
+class HeaderPolicy:
+    """Default, removed and Server headers applied to every response."""
+
+    default_headers: List[Tuple[str, str]]
+    remove_headers: List[str]
+    server_header: Optional[str]
+
+    def __init__(
+        self,
+        default_headers: Optional[List[Tuple[str, str]]] = None,
+        remove_headers: Optional[List[str]] = None,
+        server_header: Optional[str] = "Hypern",
+    ) -> None: ...
+
 @dataclass
 class HeaderMap:
 
@@ -309,22 +774,249 @@ class SSEStream:
     def send_data(self, data: str) -> bool: ...
     def send_event(self, event_name: str, data: str) -> bool: ...
     def keepalive(self) -> bool: ...
+    def flush_headers(self) -> bool:
+        """Send a `: connected` comment so proxies and clients see the stream open."""
+        ...
     def close(self) -> None: ...
     def is_closed(self) -> bool: ...
     def event_count(self) -> int: ...
 
+def feature_flag_stats() -> Dict[str, Dict[str, Any]]:
+    """Per-flag ``on``/``off`` evaluation counts and settings in this process."""
+    ...
+
+def set_etag_provider(name: str, provider: str | int | Callable[[], str | int | None] | None) -> None:
+    """Set an ETag provider's token, or a callable returning it; None unsets it."""
+    ...
+
+def current_etag(name: str) -> Optional[str]:
+    """The provider's current ETag, quoted, or None."""
+    ...
+
+def route_memo_stats() -> Dict[str, Dict[str, int]]:
+    """Per-path ``hits``, ``misses``, ``stores``, ``invalidations`` and ``entries`` of memoized routes in this process."""
+    ...
+
+def sse_stats() -> Dict[str, Any]:
+    """SSE gauges for this worker: active, keepalive_registered, keepalives_sent, keepalive_interval_secs."""
+    ...
+
+def request_decompression_stats() -> Dict[str, Any]:
+    """Request decompression counters for this worker: enabled, max_decompressed_size, decompressed, compressed_bytes, inflated_bytes, rejected."""
+    ...
+
+class WsLimits:
+    """Inbound limits of a WebSocket route."""
+
+    max_message_size: int
+    max_decompressed_size: int
+    max_fragments: int
+    permessage_deflate: bool
+    server_max_window_bits: int
+    max_messages_per_second: Optional[float]
+    rate_limit_action: str
+
+    def __init__(
+        self,
+        max_message_size: int = 1048576,
+        max_decompressed_size: Optional[int] = None,
+        max_fragments: int = 128,
+        permessage_deflate: bool = False,
+        server_max_window_bits: int = 15,
+        max_messages_per_second: Optional[float] = None,
+        rate_limit_action: str = "close",
+    ) -> None: ...
+    def negotiate(self, offers: Optional[str] = None) -> Optional[str]:
+        """Sec-WebSocket-Extensions response accepting a permessage-deflate offer, or None."""
+        ...
+
+class WsInboundGuard:
+    """Applies a route's WsLimits to the frames of one connection."""
+
+    close_code: Optional[int]
+    delay: float
+    compressed: bool
+    limits: WsLimits
+
+    def __init__(self, limits: WsLimits, extensions: Optional[str] = None) -> None: ...
+    def feed(self, opcode: int, payload: bytes, fin: bool = True, rsv1: bool = False) -> Optional[Any]:
+        """The complete message (a WsMessage), None mid-message, or the Close message to send on a violation."""
+        ...
+
+def websocket_inbound_stats() -> Dict[str, Any]:
+    """Inbound WebSocket limit counters for this worker: closed (by reason), throttled, inflated."""
+    ...
+
+def dispatch_timing_stats() -> Dict[str, Any]:
+    """Per-stage handler timing histograms for this worker: server_timing plus route, queue, app and write, each with count, sum_ms and cumulative (le_ms, count) buckets."""
+    ...
+
+def stream_drain_stats() -> Dict[str, Any]:
+    """Drain counters for this worker: live, closed_by_drain, rejected_while_draining, connections_closed_by_drain."""
+    ...
+
+def disconnect_stats() -> Dict[str, Any]:
+    """Client disconnect counters for this worker: aborted, aborted_streams."""
+    ...
+
+def panic_stats() -> Dict[str, Any]:
+    """Panics caught on the request path in this worker: panics_total."""
+    ...
+
+def inject_panic(path: Optional[str]) -> None:
+    """Make requests for ``path`` panic in the worker (None clears); only in builds with the test-hooks feature."""
+    ...
+
+class PanicMiddleware:
+    """Middleware that panics on every request; only in builds with the test-hooks feature."""
+
+    def __init__(self) -> None: ...
+
+class ServerMetrics:
+    """Request rates over a sliding window and the slowest and most erroring routes."""
+
+    def __init__(
+        self,
+        bucket_secs: float = 1.0,
+        retain_secs: float = 300.0,
+        top_k: int = 10,
+        manual_clock: bool = False,
+    ) -> None: ...
+    def record(self, method: str, route: Optional[str], status: int, duration_ms: float) -> None: ...
+    def rate(self, window_secs: float = 60.0) -> Dict[str, Any]:
+        """window_secs, requests, errors, request_rate, error_rate and avg_ms over the last window_secs."""
+        ...
+    def top_routes(self, k: Optional[int] = None) -> Dict[str, List[Dict[str, Any]]]:
+        """slowest (by avg_ms) and erroring (by 5xx count) routes: method, route, count, errors, avg_ms, max_ms."""
+        ...
+    def gil(self) -> Dict[str, Any]:
+        """enabled, warn_ms and per call site (handler, executor, row_conversion): acquisitions, wait_ms, hold_ms, max_wait_ms, max_hold_ms, long_holds and cumulative hold_histogram (le_ms, count) pairs, le_ms None for +Inf."""
+        ...
+    def static_cache(self) -> List[Dict[str, Any]]:
+        """File cache counters of each live StaticFileHandler: prefix, entries, bytes, max_bytes, hits, misses, evictions."""
+        ...
+    def snapshot(self, cluster: bool = False, per_worker: bool = False) -> Dict[str, Any]:
+        """
+        total_requests, total_errors, rate_1m, rate_5m, slowest, erroring, gil and static_cache.
+
+        ``cluster=True`` (server collector only) merges every live worker's
+        published totals and adds ``workers``; ``per_worker=True`` adds a
+        ``per_worker`` list of pid, age_secs, total_requests, total_errors,
+        rate_1m and rate_5m.
+        """
+        ...
+    def publish(self) -> bool:
+        """Publish this process's totals for the other workers now; False when there is nowhere to publish."""
+        ...
+    def reset(self) -> None: ...
+    def advance(self, secs: float) -> None:
+        """Move a manual clock forward."""
+        ...
+    def render(self, cluster: bool = False, per_worker: bool = False) -> str:
+        """Prometheus text exposition of the rates and slowest routes, as gauges, and the GIL wait and hold metrics; ``cluster``/``per_worker`` as in ``snapshot``."""
+        ...
+
+def server_metrics() -> ServerMetrics:
+    """The collector this worker records every response in."""
+    ...
+
+def large_response_stats() -> Dict[str, Any]:
+    """Buffered responses streamed because of their size, for this worker: threshold_bytes, streamed, bytes_streamed."""
+    ...
+
+def listener_options() -> Optional[Dict[str, Any]]:
+    """Options read back from this worker's listening socket (None outside a worker): pid, reuse_port, tcp_nodelay, keepalive, keepalive_idle, keepalive_interval, keepalive_count."""
+    ...
+
+def generate_request_id() -> str:
+    """A new request ID from the server's generator: 32 hex characters, unique within the process."""
+    ...
+
 class StreamingResponse:
     """Streaming response for large data transfers."""
     content_type: str
+    high_watermark: int
+    low_watermark: int
     
-    def __init__(self, buffer_size: int = 100, content_type: str = "application/octet-stream") -> None: ...
+    def __init__(
+        self,
+        content_type: str = "application/octet-stream",
+        buffer_size: int = 100,
+        high_watermark: int = 65536,
+        low_watermark: Optional[int] = None,
+    ) -> None: ...
     def write(self, data: bytes) -> bool: ...
     def write_str(self, data: str) -> bool: ...
     def write_line(self, data: str) -> bool: ...
+    def write_async(self, data: bytes) -> Awaitable[bool]:
+        """Write, waiting for room in the buffer and then for drain() above the high watermark; False once closed."""
+        ...
+    def drain(self) -> Awaitable[None]:
+        """Wait until buffered bytes are at or below the low watermark after going above the high one."""
+        ...
+    def buffered_chunks(self) -> int:
+        """Chunks written that the server has not sent on yet."""
+        ...
+    def buffered_bytes(self) -> int:
+        """Bytes written that the server has not sent on yet."""
+        ...
+    def set_write_buffer_limits(self, high: int = 65536, low: Optional[int] = None) -> None:
+        """Set both watermarks; low defaults to high // 4."""
+        ...
     def flush(self) -> None: ...
     def close(self) -> None: ...
     def is_closed(self) -> bool: ...
 
+class StaticFileHandler:
+    """Static file handler with memory-mapped caching and SPA fallback."""
+    prefix: str
+    directory: Optional[str]
+
+    def __init__(
+        self,
+        directory: str,
+        prefix: str = "/static",
+        index: str = "index.html",
+        spa: bool = False,
+        cache_max_age: Optional[int] = None,
+        spa_fallback: bool = False,
+        fallback_exclude_prefixes: Optional[List[str]] = None,
+        max_cache_bytes: int = 64 * 1024 * 1024,
+        max_file_cache_bytes: int = 4 * 1024 * 1024,
+        overlay: Optional[Dict[str, Union[bytes, str]]] = None,
+    ) -> None: ...
+    @staticmethod
+    def from_bundle(
+        files: Dict[str, Union[bytes, str]],
+        directory: Optional[str] = None,
+        prefix: str = "/static",
+        index: str = "index.html",
+        cache_max_age: Optional[int] = None,
+        spa_fallback: bool = False,
+        fallback_exclude_prefixes: Optional[List[str]] = None,
+        max_cache_bytes: int = 64 * 1024 * 1024,
+        max_file_cache_bytes: int = 4 * 1024 * 1024,
+    ) -> "StaticFileHandler":
+        """Serve files from memory, ahead of an optional directory; entries are pinned in the file cache."""
+        ...
+    def serve_file(
+        self,
+        path: str,
+        if_none_match: Optional[str] = None,
+        method: str = "GET",
+        accept: Optional[str] = None,
+    ) -> tuple[bytes, str, str, int]: ...
+    def response_headers(
+        self, path: str, method: str = "GET", accept: Optional[str] = None
+    ) -> List[tuple[str, str]]: ...
+    def clear_cache_py(self) -> None: ...
+    def cache_stats(self) -> Dict[str, int]:
+        """File cache counters: entries, bytes, max_bytes, hits, misses, evictions."""
+        ...
+    def cached_paths(self) -> List[str]:
+        """Paths in the file cache, least recently served first."""
+        ...
+
 
 class CorsMiddleware:
     """
@@ -339,7 +1031,9 @@ class CorsMiddleware:
         allowed_headers: Optional[List[str]] = None,
         expose_headers: Optional[List[str]] = None,
         allow_credentials: bool = False,
-        max_age: int = 86400
+        max_age: int = 86400,
+        max_age_overrides: Optional[Dict[str, int]] = None,
+        allow_private_network: bool = False,
     ) -> None: ...
     
     @staticmethod
@@ -358,8 +1052,23 @@ class RateLimitMiddleware:
         window_secs: int = 60,
         algorithm: str = "sliding",
         key_header: Optional[str] = None,
-        skip_paths: Optional[List[str]] = None
-    ) -> None: ...
+        skip_paths: Optional[List[str]] = None,
+        key: Optional[Union[str, Callable[[Any], Optional[str]], List[Any]]] = None,
+        tiers: Optional[Dict[str, Tuple[int, int]]] = None,
+        tier: Optional[Union[Dict[str, str], Callable[[Any, str], Optional[str]]]] = None,
+        limit_internal: bool = False,
+    ) -> None:
+        """
+        Args:
+            key: "ip", "user" (authenticated user id, else the IP),
+                "header:<name>", a callable taking the context (slower), or a
+                list of these combined into one key. Overrides key_header.
+            tiers: Tier name -> (max_requests, window_secs)
+            tier: Key prefix -> tier name (longest prefix wins), or a
+                callable taking the context and key that returns a tier name
+            limit_internal: Also count requests to the built-in health probes
+        """
+        ...
 
 
 class SecurityHeadersMiddleware:
@@ -435,7 +1144,9 @@ class BasicAuthMiddleware:
     def __init__(
         self,
         realm: str = "Restricted",
-        users: Optional[Dict[str, str]] = None
+        users: Optional[Dict[str, str]] = None,
+        roles: Optional[Dict[str, List[str]]] = None,
+        optional: bool = False,
     ) -> None: ...
 
 class CircuitBreakerMiddleware:
@@ -474,6 +1185,50 @@ class CacheMiddleware:
     def invalidate(self, route: str, params: str) -> None: ...
     def clear(self) -> None: ...
 
+
+class IdempotencyMiddleware:
+    """
+    Idempotency-Key middleware.
+
+    Runs a POST/PATCH carrying an `Idempotency-Key` header once; repeats with
+    the same key, route and user get the stored response replayed with
+    `Idempotency-Replayed: true`.
+    """
+
+    def __init__(
+        self,
+        ttl_seconds: int = 86400,
+        methods: Optional[List[str]] = None,
+        header_name: str = "Idempotency-Key",
+        max_key_length: int = 255,
+        max_body_size: int = 1048576,
+        wait_secs: Optional[float] = None,
+        in_flight_timeout_secs: int = 60,
+    ) -> None: ...
+
+class SingleflightMiddleware:
+    """
+    Request coalescing middleware.
+
+    Identical concurrent GET/HEAD requests (same method, path, query and vary
+    headers) wait for the first one and get a copy of its response instead
+    of running the handler again.
+    """
+
+    def __init__(
+        self,
+        max_wait_secs: float = 10.0,
+        max_body_size: int = 1048576,
+        vary_headers: Optional[List[str]] = None,
+    ) -> None: ...
+    def stats(self) -> Dict[str, int]:
+        """Counters: leaders, coalesced, timed_out, not_shared and in_flight."""
+        ...
+
+def singleflight_stats() -> Dict[str, int]:
+    """Coalescing counters of routes registered with coalesce=True, for this process."""
+    ...
+
 class LogConfig:
     """
     Configuration for the Rust-level logging system.
@@ -489,6 +1244,11 @@ class LogConfig:
         log_response: bool = True,
         queue_size: int = 10000,
         skip_paths: Optional[List[str]] = None,
+        format: Optional[str] = None,
+        redact_patterns: Optional[List[str]] = None,
+        max_field_len: Optional[int] = None,
+        redact_headers: Optional[List[str]] = None,
+        sink: Optional[Dict[str, Any]] = None,
     ) -> None:
         """
         Create a new log configuration.
@@ -499,9 +1259,70 @@ class LogConfig:
             log_response: Enable logging of outgoing responses with status and duration
             queue_size: Internal bounded log queue capacity
             skip_paths: Paths to exclude from request/response logging
+            format: Response line template with {method}, {path}, {status},
+                {duration_ms}, {bytes_out}, {client_ip}, {request_id},
+                {user_agent}, {referer}, {host}, {protocol} and {time}
+                placeholders, or a preset name, "combined" or "short";
+                None keeps the colored layout
+            redact_patterns: Regexes whose matches are replaced with
+                "[REDACTED]" in messages, paths, request IDs and logged
+                header values
+            max_field_len: Longest logged field in characters; longer
+                fields are cut and end in an ellipsis
+            redact_headers: Headers whose values are always masked where
+                headers are logged (default: authorization, cookie,
+                set-cookie, x-api-key)
+            sink: Collector to ship entries to as newline-delimited JSON,
+                a dict with "type" ("tcp" or "unix") and "address", and
+                optionally "format" ("json"), "compression" ("none" or
+                "gzip", one gzip member per batch), "buffer_max_bytes"
+                (default 65536), "reconnect_backoff_ms" (default 500),
+                "flush_timeout_ms" (default 2000) and "stderr" (also write
+                the usual lines to stderr, default False). Entries go to
+                stderr while the collector is unreachable.
+
+        Raises:
+            ValueError: If the template has an unknown placeholder, a
+                redact pattern is not a valid regex or the sink has an
+                unknown key or value
         """
         ...
     
+    @property
+    def format(self) -> Optional[str]:
+        """Response line template, with a preset name expanded."""
+        ...
+    
+    @property
+    def redact_patterns(self) -> List[str]:
+        """Redaction regexes, as given."""
+        ...
+    
+    @property
+    def max_field_len(self) -> Optional[int]:
+        """Longest logged field in characters, None for no limit."""
+        ...
+    
+    @property
+    def redact_headers(self) -> List[str]:
+        """Headers whose values are masked."""
+        ...
+    
+    @property
+    def sink(self) -> Optional[Dict[str, Any]]:
+        """Sink settings with defaults filled in, None when not shipping."""
+        ...
+    
+    @staticmethod
+    def combined() -> "LogConfig":
+        """Apache combined access lines, without separate request lines."""
+        ...
+    
+    @staticmethod
+    def short() -> "LogConfig":
+        """Short access lines with the duration, without separate request lines."""
+        ...
+    
     @staticmethod
     def disabled() -> "LogConfig":
         """Disable all logging."""
@@ -570,6 +1391,17 @@ class ReloadConfig:
     startup_grace_secs: int
     health_probes_enabled: bool
     health_path_prefix: str
+    log_health_probes: bool
+    stream_shutdown_event: Optional[str]
+    stream_retry_ms: int
+    stream_grace_ms: int
+    deep_check_interval_ms: int
+    deep_check_timeout_ms: int
+    watchdog_stuck_ms: Optional[int]
+    watchdog_idle_ms: Optional[int]
+    watchdog_dump_stacks: bool
+    watchdog_restart: bool
+    watchdog_restart_grace_ms: int
     
     def __init__(
         self,
@@ -578,9 +1410,160 @@ class ReloadConfig:
         startup_grace_secs: int = 2,
         health_probes_enabled: bool = True,
         health_path_prefix: str = "/_health",
+        log_health_probes: bool = False,
+        stream_shutdown_event: Optional[str] = "server-shutdown",
+        stream_retry_ms: int = 1000,
+        stream_grace_ms: int = 500,
+        deep_check_interval_ms: int = 5000,
+        deep_check_timeout_ms: int = 2000,
+        watchdog_stuck_ms: Optional[int] = None,
+        watchdog_idle_ms: Optional[int] = None,
+        watchdog_dump_stacks: bool = False,
+        watchdog_restart: bool = False,
+        watchdog_restart_grace_ms: int = 5000,
     ) -> None: ...
 
 
+class TestResponse:
+    """A complete response received by TestClient."""
+
+    @property
+    def status(self) -> int: ...
+    @property
+    def headers(self) -> Dict[str, str]:
+        """Headers by lowercased name; repeated headers are joined with ", "."""
+        ...
+    def header_values(self, name: str) -> List[str]:
+        """All values of a header, in the order they were set."""
+        ...
+    @property
+    def content(self) -> bytes: ...
+    @property
+    def text(self) -> str: ...
+    def json(self) -> Any:
+        """The body parsed as JSON; raises ValueError when it is not JSON."""
+        ...
+
+
+class TestClient:
+    """
+    Sends requests through a server's full pipeline (Host checks, middleware,
+    routing, handler, error handlers, response assembly) in this process,
+    without a socket or worker processes. Streamed bodies are collected until
+    the stream ends.
+    """
+
+    def __init__(self, server: Server, timeout: float = 30.0) -> None: ...
+    def request(
+        self,
+        method: str,
+        path: str,
+        headers: Optional[Dict[str, str]] = None,
+        query: Optional[Union[Dict[str, Any], List[Tuple[str, Any]]]] = None,
+        json: Any = None,
+        data: Optional[Union[bytes, str, Dict[str, Any]]] = None,
+        files: Optional[Dict[str, Any]] = None,
+    ) -> TestResponse: ...
+    def get(self, path: str, headers: Optional[Dict[str, str]] = None, query: Any = None) -> TestResponse: ...
+    def head(self, path: str, headers: Optional[Dict[str, str]] = None, query: Any = None) -> TestResponse: ...
+    def options(self, path: str, headers: Optional[Dict[str, str]] = None, query: Any = None) -> TestResponse: ...
+    def post(
+        self, path: str, headers: Optional[Dict[str, str]] = None, query: Any = None,
+        json: Any = None, data: Any = None, files: Optional[Dict[str, Any]] = None,
+    ) -> TestResponse: ...
+    def put(
+        self, path: str, headers: Optional[Dict[str, str]] = None, query: Any = None,
+        json: Any = None, data: Any = None, files: Optional[Dict[str, Any]] = None,
+    ) -> TestResponse: ...
+    def patch(
+        self, path: str, headers: Optional[Dict[str, str]] = None, query: Any = None,
+        json: Any = None, data: Any = None, files: Optional[Dict[str, Any]] = None,
+    ) -> TestResponse: ...
+    def delete(
+        self, path: str, headers: Optional[Dict[str, str]] = None, query: Any = None,
+        json: Any = None, data: Any = None,
+    ) -> TestResponse: ...
+
+
+class ClientResponse:
+    """Response from HttpClient, read in full before it is returned."""
+
+    @property
+    def status(self) -> int: ...
+    @property
+    def url(self) -> str:
+        """Final URL, after any redirects."""
+        ...
+    @property
+    def ok(self) -> bool: ...
+    @property
+    def headers(self) -> Dict[str, str]:
+        """Headers by lowercased name; repeated headers are joined with ", "."""
+        ...
+    @property
+    def content(self) -> bytes:
+        """The body, decompressed."""
+        ...
+    @property
+    def text(self) -> str: ...
+    def json(self) -> Any: ...
+    def bytes(self) -> bytes: ...
+
+
+class ClientFuture(Awaitable[ClientResponse]):
+    """Awaitable for a request running on the shared runtime."""
+
+
+class HttpClient:
+    """
+    HTTP client sharing one connection pool per process. Sync methods release
+    the GIL until the body has been read; ``*_async`` methods return awaitables.
+    Idempotent requests that fail to connect or time out are retried
+    ``retries`` times with jittered backoff. Timeouts raise TimeoutError and
+    connection failures raise ConnectionError.
+    """
+
+    def __init__(
+        self,
+        base_url: Optional[str] = None,
+        timeout: float = 30.0,
+        max_connections: int = 20,
+        retries: int = 0,
+        backoff: float = 0.1,
+        propagate_trace: bool = True,
+    ) -> None: ...
+    def request(
+        self,
+        method: str,
+        url: str,
+        headers: Optional[Dict[str, str]] = None,
+        params: Optional[Dict[str, str]] = None,
+        json: Any = None,
+        data: Optional[Union[bytes, str, Dict[str, Any]]] = None,
+        timeout: Optional[float] = None,
+    ) -> ClientResponse: ...
+    def request_async(
+        self,
+        method: str,
+        url: str,
+        headers: Optional[Dict[str, str]] = None,
+        params: Optional[Dict[str, str]] = None,
+        json: Any = None,
+        data: Optional[Union[bytes, str, Dict[str, Any]]] = None,
+        timeout: Optional[float] = None,
+    ) -> ClientFuture: ...
+    def get(self, url: str, headers: Optional[Dict[str, str]] = None, params: Optional[Dict[str, str]] = None, json: Any = None, data: Any = None, timeout: Optional[float] = None) -> ClientResponse: ...
+    def post(self, url: str, headers: Optional[Dict[str, str]] = None, params: Optional[Dict[str, str]] = None, json: Any = None, data: Any = None, timeout: Optional[float] = None) -> ClientResponse: ...
+    def put(self, url: str, headers: Optional[Dict[str, str]] = None, params: Optional[Dict[str, str]] = None, json: Any = None, data: Any = None, timeout: Optional[float] = None) -> ClientResponse: ...
+    def patch(self, url: str, headers: Optional[Dict[str, str]] = None, params: Optional[Dict[str, str]] = None, json: Any = None, data: Any = None, timeout: Optional[float] = None) -> ClientResponse: ...
+    def delete(self, url: str, headers: Optional[Dict[str, str]] = None, params: Optional[Dict[str, str]] = None, json: Any = None, data: Any = None, timeout: Optional[float] = None) -> ClientResponse: ...
+    def get_async(self, url: str, headers: Optional[Dict[str, str]] = None, params: Optional[Dict[str, str]] = None, json: Any = None, data: Any = None, timeout: Optional[float] = None) -> ClientFuture: ...
+    def post_async(self, url: str, headers: Optional[Dict[str, str]] = None, params: Optional[Dict[str, str]] = None, json: Any = None, data: Any = None, timeout: Optional[float] = None) -> ClientFuture: ...
+    def put_async(self, url: str, headers: Optional[Dict[str, str]] = None, params: Optional[Dict[str, str]] = None, json: Any = None, data: Any = None, timeout: Optional[float] = None) -> ClientFuture: ...
+    def patch_async(self, url: str, headers: Optional[Dict[str, str]] = None, params: Optional[Dict[str, str]] = None, json: Any = None, data: Any = None, timeout: Optional[float] = None) -> ClientFuture: ...
+    def delete_async(self, url: str, headers: Optional[Dict[str, str]] = None, params: Optional[Dict[str, str]] = None, json: Any = None, data: Any = None, timeout: Optional[float] = None) -> ClientFuture: ...
+
+
 class ReloadManager:
     """
     Manager for zero-downtime reloads with health probes.
@@ -635,6 +1618,10 @@ class PoolConfig:
     max_lifetime_secs: Optional[int]
     test_before_acquire: bool
     keepalive_secs: Optional[int]
+    ssl_mode: Optional[str]
+    ssl_root_cert_path: Optional[str]
+    ssl_client_cert: Optional[str]
+    ssl_client_key: Optional[str]
     
     def __init__(
         self,
@@ -646,7 +1633,22 @@ class PoolConfig:
         max_lifetime_secs: Optional[int] = None,
         test_before_acquire: bool = False,
         keepalive_secs: Optional[int] = None,
-    ) -> None: ...
+        ssl_mode: Optional[str] = None,
+        ssl_root_cert_path: Optional[str] = None,
+        ssl_client_cert: Optional[str] = None,
+        ssl_client_key: Optional[str] = None,
+    ) -> None:
+        """
+        Raises:
+            ValueError: If ssl_mode is not disable, prefer, require,
+                verify-ca or verify-full
+        """
+        ...
+    
+    @property
+    def effective_ssl_mode(self) -> str:
+        """The TLS mode used, after applying the URL's sslmode."""
+        ...
 
 
 class PoolStatus:
@@ -701,6 +1703,37 @@ class ConnectionPool:
         """Initialize the connection pool for a specific alias with the given configuration."""
         ...
 
+    @staticmethod
+    def set_alias_resolver(resolver: Optional[Callable[[str, str, Dict[str, Any]], str]] = None) -> None:
+        """Map logical aliases to physical pools; called once per request and alias."""
+        ...
+
+    @staticmethod
+    def has_alias_resolver() -> bool: ...
+
+    @staticmethod
+    def register_pool_factory(factory: Optional[Callable[[str], Optional[PoolConfig]]] = None, max_pools: int = 64) -> None:
+        """Create missing pools on first use; the LRU idle pool is closed past max_pools."""
+        ...
+
+    @staticmethod
+    def lazy_pools() -> List[str]:
+        """Factory-created pool aliases, least recently used first."""
+        ...
+
+    @staticmethod
+    def begin_test_mode(alias: str = "default") -> None:
+        """Share one pinned connection, inside one transaction, across all sessions for alias."""
+        ...
+
+    @staticmethod
+    def rollback_test_mode(alias: str = "default") -> None:
+        """Roll back the test transaction and return the pinned connection to the pool."""
+        ...
+
+    @staticmethod
+    def in_test_mode(alias: str = "default") -> bool: ...
+
 
 class DbSession:
     """
@@ -709,6 +1742,8 @@ class DbSession:
     Provides methods for executing SQL queries within a request context.
     """
     request_id: str
+    alias: str
+    pool_alias: str
     
     def begin(self) -> None:
         """Begin a database transaction."""
@@ -722,22 +1757,54 @@ class DbSession:
         """Rollback the current transaction."""
         ...
     
-    def query(self, sql: str, params: Optional[List[Any]] = None) -> List[Dict[str, Any]]:
-        """Execute a SELECT query and return results as list of dicts."""
+    def query(self, sql: str, params: Optional[List[Any] | Dict[str, Any]] = None, gil_batch_size: int = 1000) -> List[Dict[str, Any]]:
+        """Execute a SELECT query and return results as list of dicts, releasing the GIL every gil_batch_size rows (0: never)."""
         ...
     
-    def query_one(self, sql: str, params: Optional[List[Any]] = None) -> Dict[str, Any]:
+    def query_one(self, sql: str, params: Optional[List[Any] | Dict[str, Any]] = None) -> Dict[str, Any]:
         """Execute a SELECT query and return a single result as dict."""
         ...
     
-    def execute(self, sql: str, params: Optional[List[Any]] = None) -> int:
+    def query_as(self, sql: str, params: Optional[List[Any] | Dict[str, Any]], cls: Type[T], strict: bool = True, gil_batch_size: int = 1000) -> List[T]:
+        """Execute a SELECT query and construct ``cls`` from each row's columns as keywords."""
+        ...
+    
+    def query_tuples(self, sql: str, params: Optional[List[Any] | Dict[str, Any]] = None, gil_batch_size: int = 1000) -> List[Tuple[Any, ...]]:
+        """Execute a SELECT query and return rows as tuples in column order."""
+        ...
+    
+    def execute(self, sql: str, params: Optional[List[Any] | Dict[str, Any]] = None) -> int:
         """Execute INSERT, UPDATE, DELETE and return affected row count."""
         ...
     
-    def execute_many(self, sql: str, params_list: List[List[Any]]) -> int:
+    def execute_many(self, sql: str, params_list: List[List[Any] | Dict[str, Any]]) -> int:
         """Execute a batch of INSERT/UPDATE/DELETE statements."""
         ...
     
+    def copy_in(self, table: str, columns: List[str], rows: Iterable[Sequence[Any]], format: str = "binary", chunk_size: int = 5000) -> int:
+        """Bulk-load rows with COPY FROM STDIN; returns the number of rows copied."""
+        ...
+    
+    def copy_out(self, query: str, csv: bool = False, header: bool = False, batch_size: int = 1000) -> CopyOutRows:
+        """Export a query with COPY TO STDOUT as row tuples or CSV lines."""
+        ...
+    
+    def stream(self, sql: str, params: Optional[List[Any] | Dict[str, Any]] = None, chunk_size: int = 1000, prefetch: int = 2) -> RowStream:
+        """Iterate a query's rows in chunks through a server-side cursor, ``prefetch`` chunks ahead."""
+        ...
+    
+    def query_async(self, sql: str, params: Optional[List[Any] | Dict[str, Any]] = None, gil_batch_size: int = 1000) -> DbFuture:
+        """Awaitable ``query()``; concurrent calls on one session run in call order."""
+        ...
+    
+    def query_one_async(self, sql: str, params: Optional[List[Any] | Dict[str, Any]] = None) -> DbFuture:
+        """Awaitable ``query_one()``."""
+        ...
+    
+    def execute_async(self, sql: str, params: Optional[List[Any] | Dict[str, Any]] = None) -> DbFuture:
+        """Awaitable ``execute()``."""
+        ...
+    
     def set_auto_commit(self, auto_commit: bool) -> None:
         """Set auto-commit behavior."""
         ...
@@ -751,16 +1818,43 @@ class DbSession:
         ...
 
 
+class DbFuture:
+    """Awaitable result of a ``DbSession`` ``*_async`` call."""
+    def __await__(self) -> Generator[Any, None, Any]: ...
+
+
+class CopyOutRows:
+    """
+    Iterator over the rows of ``DbSession.copy_out()``.
+    
+    Yields row tuples, or CSV lines as ``str``. Rows are fetched in batches
+    while iterating; the session's connection is busy until the iterator is
+    exhausted or closed.
+    """
+    
+    def __iter__(self) -> "CopyOutRows":
+        ...
+    
+    def __next__(self) -> Tuple[Any, ...] | str:
+        ...
+    
+    def close(self) -> None:
+        """Stop the export without reading the remaining rows."""
+        ...
+
+
 class RowStream:
     """
-    Streaming row iterator that yields chunks of rows lazily.
+    Iterator over a query's rows in chunks (lists of dicts).
     
-    This allows Python to iterate over large result sets without loading everything into memory.
-    Use in a for loop to iterate over chunks.
+    A background task fetches the chunks at most ``prefetch`` ahead of the
+    consumer, so large results are never held in memory at once. Iterate
+    with ``for`` or ``async for``. A database error is raised by the call
+    that reaches it; closing or dropping the stream stops the fetch and
+    releases the connection.
     
     Example:
-        stream = transaction.stream_data("SELECT * FROM large_table", [], chunk_size=1000)
-        for chunk in stream:
+        for chunk in session.stream("SELECT * FROM large_table", chunk_size=1000):
             for row in chunk:
                 process(row)
     """
@@ -770,7 +1864,19 @@ class RowStream:
         ...
     
     def __next__(self) -> List[Dict[str, Any]]:
-        """Get the next chunk of rows."""
+        """Wait for the next chunk with the GIL released."""
+        ...
+    
+    def __aiter__(self) -> "RowStream":
+        """Return the async iterator."""
+        ...
+    
+    def __anext__(self) -> DbFuture:
+        """Awaitable of the next chunk."""
+        ...
+    
+    def close(self) -> None:
+        """Stop fetching without reading the remaining rows."""
         ...
     
     def is_exhausted(self) -> bool:
@@ -778,11 +1884,19 @@ class RowStream:
         ...
     
     def chunk_count(self) -> int:
-        """Get the total number of chunks (available after streaming completes)."""
+        """Number of chunks returned so far."""
+        ...
+    
+    def prefetched(self) -> int:
+        """Chunks fetched and waiting to be taken."""
+        ...
+    
+    def peak_prefetched(self) -> int:
+        """Most chunks that were waiting at once."""
         ...
 
 
-def get_db(request_id: str) -> DbSession:
+def get_db(request_id: str, alias: str = "default", context: Optional[Dict[str, Any]] = None) -> DbSession:
     """
     Get or create a database session for the given request ID.
     
@@ -811,8 +1925,8 @@ class AnyPool:
     """Generic connection pool interface for multiple database types."""
     
     def __init__(self, url: str, max_connections: int = 16) -> None: ...
-    def query(self, sql: str, params: Optional[List[Any]] = None) -> List[Dict[str, Any]]: ...
-    def query_one(self, sql: str, params: Optional[List[Any]] = None) -> Dict[str, Any]: ...
+    def query(self, sql: str, params: Optional[List[Any] | Dict[str, Any]] = None) -> List[Dict[str, Any]]: ...
+    def query_one(self, sql: str, params: Optional[List[Any] | Dict[str, Any]] = None) -> Dict[str, Any]: ...
     def execute(self, sql: str, params: Optional[List[Any]] = None) -> int: ...
     def close(self) -> None: ...
 
@@ -827,6 +1941,12 @@ class ChannelStats:
     total_messages: int
     dropped_messages: int
     metadata: dict[str, str]
+    max_subscribers: Optional[int]
+    denied_subscriptions: int
+    denied_publishes: int
+    invalid_messages: int
+    replay_size: int
+    last_seq: int
 
 class Subscriber:
     """Subscriber handle that receives messages from a channel."""
@@ -834,9 +1954,43 @@ class Subscriber:
     client_id: str
     received_count: int
     missed_count: int
+    queue_stats: Optional[ClientQueueStats]
     
     def try_recv(self) -> Optional[str]: ...
     def drain(self) -> List[str]: ...
+    def recv_async(self, timeout: Optional[float] = None) -> Awaitable[Optional[str]]:
+        """Next message, or None after ``timeout`` seconds; raises ChannelClosed."""
+        ...
+    def __aiter__(self) -> Subscriber: ...
+    def __anext__(self) -> Awaitable[str]: ...
+
+class ChannelClosed(Exception):
+    """The channel was removed while a subscriber was waiting on it."""
+
+class SubscriptionError(Exception):
+    """A subscription was refused: the channel is full or the subscribe hook denied it."""
+
+class PublishError(Exception):
+    """A publish was refused by the channel's message schema or the publish hook."""
+
+class MessageSchema:
+    """Rules every message published on a channel must follow."""
+    fields: List[Tuple[str, str]]
+    max_bytes: Optional[int]
+    json: bool
+
+    def __init__(
+        self,
+        fields: Optional[Dict[str, str]] = None,
+        max_bytes: Optional[int] = None,
+        json: bool = True,
+    ) -> None:
+        """``fields`` maps required top-level fields to "string", "number",
+        "integer", "boolean", "object", "array", "null" or "any"."""
+        ...
+    def validate(self, message: str) -> Optional[str]:
+        """Why ``message`` would be rejected, or None when it is valid."""
+        ...
 
 class TopicMatcher:
     """Pattern-based topic matching for pub/sub routing."""
@@ -855,20 +2009,85 @@ class ChannelManager:
     """High-performance channel manager for pub/sub messaging."""
     topic_matcher: TopicMatcher
     
-    def __init__(self, default_buffer_size: int = 256) -> None: ...
+    def __init__(
+        self,
+        default_buffer_size: int = 256,
+        auto_create: bool = False,
+        auto_create_pattern: Optional[str | List[str]] = None,
+        default_ttl_secs: Optional[float] = None,
+        handoff_key: Optional[str] = None,
+        handoff_max_bytes: int = 4194304,
+    ) -> None: ...
     def create_channel(
         self,
         name: str,
         buffer_size: Optional[int] = None,
         metadata: Optional[Dict[str, str]] = None,
+        max_subscribers: Optional[int] = None,
+        replay_size: Optional[int] = None,
+        idle_ttl_secs: Optional[float] = None,
+        message_schema: Optional[MessageSchema] = None,
+        envelope: bool = False,
     ) -> bool: ...
+    def set_channel_hook(self, hook: Optional[Callable[[str, str], Any]]) -> None:
+        """Observe ``hook(event, channel_name)`` for "created", "auto_created" and "expired" events."""
+        ...
+    def sweep_idle(self) -> int:
+        """Remove idle channels now; returns the number removed."""
+        ...
+    def lifecycle_stats(self) -> Dict[str, int]:
+        """Counts of created, auto_created and expired channels, and restored_channels / restored_messages."""
+        ...
     def remove_channel(self, name: str) -> bool: ...
     def has_channel(self, name: str) -> bool: ...
-    def subscribe(self, channel_name: str, client_id: str) -> Subscriber: ...
+    def set_subscribe_hook(
+        self, hook: Optional[Callable[[str, str, Dict[str, Any]], bool]]
+    ) -> None:
+        """Vet subscriptions with ``hook(channel_name, client_id, metadata)``; runs with the GIL, keep it fast."""
+        ...
+    def set_publish_hook(
+        self, hook: Optional[Callable[[str, Optional[str], str], bool]]
+    ) -> None:
+        """Vet publishes with ``hook(channel_name, client_id, message)``; runs with the GIL, keep it fast."""
+        ...
+    def subscribe(
+        self,
+        channel_name: str,
+        client_id: str,
+        metadata: Optional[Dict[str, Any]] = None,
+        bypass_hooks: bool = False,
+        queue: Optional[ClientQueueConfig] = None,
+    ) -> Subscriber: ...
     def unsubscribe(self, channel_name: str, client_id: str) -> bool: ...
-    def publish(self, channel_name: str, message: str) -> int: ...
-    def publish_to_topic(self, topic: str, message: str) -> int: ...
+    def publish(
+        self,
+        channel_name: str,
+        message: str,
+        client_id: Optional[str] = None,
+        bypass_hooks: bool = False,
+        priority: Priority | str | None = None,
+    ) -> int: ...
+    def publish_to_topic(
+        self,
+        topic: str,
+        message: str,
+        client_id: Optional[str] = None,
+        bypass_hooks: bool = False,
+        priority: Priority | str | None = None,
+    ) -> int: ...
     def get_stats(self, channel_name: str) -> ChannelStats: ...
+    def messages_since(
+        self, channel_name: str, cursor: int = 0, max_low: Optional[int] = None
+    ) -> Tuple[List[str], int]:
+        """Retained messages published after ``cursor`` and the cursor to continue from; ``max_low`` keeps only the newest low-priority ones."""
+        ...
+    def last_seq(self, channel_name: str) -> int: ...
+    def export_replay(self, max_bytes: Optional[int] = None) -> bytes:
+        """Sequence counters and newest retained messages, as handed to the next generation on a graceful reload."""
+        ...
+    def import_replay(self, data: bytes) -> Tuple[int, int]:
+        """Restore an ``export_replay`` snapshot into unused channels; returns ``(channels, messages)`` restored."""
+        ...
     def list_channels(self) -> List[str]: ...
     def get_subscribers(self, channel_name: str) -> List[str]: ...
     def channel_count(self) -> int: ...
@@ -883,21 +2102,32 @@ class PresenceInfo:
     """Information about a connected client's presence."""
     client_id: str
     channel: str
-    metadata: Dict[str, str]
+    metadata: Dict[str, Any]
     joined_at: float
     last_seen: float
+    last_update: float
+    version: int
     
     def __init__(
         self,
         client_id: str,
         channel: str,
-        metadata: Optional[Dict[str, str]] = None,
+        metadata: Optional[Dict[str, Any]] = None,
     ) -> None: ...
 
+class PresenceUpdate:
+    """A metadata change of one client; ``changes`` holds only the changed keys (None = removed)."""
+    client_id: str
+    channel: str
+    changes: Dict[str, Any]
+    version: int
+    updated_at: float
+
 class PresenceDiff:
-    """Diff of presence changes (joins and leaves)."""
+    """Diff of presence changes (joins, leaves and metadata updates)."""
     joins: List[PresenceInfo]
     leaves: List[str]
+    updated: List[PresenceUpdate]
     
     def __init__(self) -> None: ...
     def has_changes(self) -> bool: ...
@@ -911,11 +2141,12 @@ class PresenceTracker:
         self,
         channel: str,
         client_id: str,
-        metadata: Optional[Dict[str, str]] = None,
+        metadata: Optional[Dict[str, Any]] = None,
     ) -> PresenceInfo: ...
     def untrack(self, channel: str, client_id: str) -> bool: ...
     def untrack_all(self, client_id: str) -> List[str]: ...
-    def update(self, channel: str, client_id: str, metadata: Dict[str, str]) -> bool: ...
+    def update(self, channel: str, client_id: str, metadata: Dict[str, Any]) -> bool: ...
+    def update_client(self, client_id: str, partial_meta: Dict[str, Any]) -> bool: ...
     def touch(self, channel: str, client_id: str) -> bool: ...
     def list(self, channel: str) -> List[PresenceInfo]: ...
     def get(self, channel: str, client_id: str) -> Optional[PresenceInfo]: ...
@@ -943,6 +2174,8 @@ class BroadcastConfig:
     policy: BackpressurePolicy
     dedup_enabled: bool
     dedup_window: int
+    message_schema: Optional[MessageSchema]
+    envelope: bool
     
     def __init__(
         self,
@@ -950,6 +2183,8 @@ class BroadcastConfig:
         policy: BackpressurePolicy = BackpressurePolicy.DropOldest,
         dedup_enabled: bool = False,
         dedup_window: int = 1000,
+        message_schema: Optional[MessageSchema] = None,
+        envelope: bool = False,
     ) -> None: ...
 
 class BroadcastStats:
@@ -957,6 +2192,7 @@ class BroadcastStats:
     total_sent: int
     total_dropped: int
     total_deduped: int
+    total_invalid: int
     active_subscribers: int
     channel_count: int
 
@@ -965,9 +2201,15 @@ class BroadcastSubscriber:
     channel_name: str
     received_count: int
     lagged_count: int
+    queue_stats: Optional[ClientQueueStats]
     
     def try_recv(self) -> Optional[str]: ...
     def drain(self) -> List[str]: ...
+    def recv_async(self, timeout: Optional[float] = None) -> Awaitable[Optional[str]]:
+        """Next message, or None after ``timeout`` seconds; raises ChannelClosed."""
+        ...
+    def __aiter__(self) -> BroadcastSubscriber: ...
+    def __anext__(self) -> Awaitable[str]: ...
 
 class RealtimeBroadcast:
     """Backpressure-aware broadcast system."""
@@ -975,8 +2217,16 @@ class RealtimeBroadcast:
     def __init__(self) -> None: ...
     def create(self, name: str, config: Optional[BroadcastConfig] = None) -> bool: ...
     def remove(self, name: str) -> bool: ...
-    def subscribe(self, name: str) -> BroadcastSubscriber: ...
-    def send(self, name: str, message: str, message_id: Optional[str] = None) -> int: ...
+    def subscribe(
+        self, name: str, queue: Optional[ClientQueueConfig] = None
+    ) -> BroadcastSubscriber: ...
+    def send(
+        self,
+        name: str,
+        message: str,
+        message_id: Optional[str] = None,
+        priority: Priority | str | None = None,
+    ) -> int: ...
     def send_many(self, names: List[str], message: str) -> Dict[str, int]: ...
     def stats(self, name: str) -> BroadcastStats: ...
     def global_stats(self) -> BroadcastStats: ...
@@ -985,6 +2235,75 @@ class RealtimeBroadcast:
     def clear(self) -> None: ...
 
 
+# ============================================================================
+# Realtime: Per-client queues
+# ============================================================================
+
+class OverflowPolicy(Enum):
+    """What a client queue does with a message that arrives while it is full."""
+    DropOldest = 0
+    DropNewest = 1
+    Disconnect = 2
+    CoalesceByKey = 3
+
+class Priority(Enum):
+    """How urgently a message should reach clients; client queues deliver more urgent messages first."""
+    High = 0
+    Normal = 1
+    Low = 2
+
+class ClientQueueConfig:
+    """Capacity and overflow policy of a per-client queue."""
+    capacity: int
+    policy: OverflowPolicy
+    coalesce_key: Optional[str]
+    max_burst: int
+    
+    def __init__(
+        self,
+        capacity: int = 64,
+        policy: OverflowPolicy = OverflowPolicy.DropOldest,
+        coalesce_key: Optional[str] = None,
+        max_burst: int = 8,
+    ) -> None: ...
+
+class ClientQueueStats:
+    """Counters for one client queue."""
+    queued: int
+    high_water: int
+    forwarded: int
+    dropped: int
+    coalesced: int
+    reordered: int
+    capacity: int
+    disconnected: bool
+
+def realtime_poll_stats() -> Dict[str, Any]:
+    """Long-poll counters for this worker: active, held, immediate, expired, rejected."""
+    ...
+
+
+class RealtimeIdentity:
+    """
+    Who a realtime connection belongs to.
+
+    ``client_id`` is ``{user_id}:{nonce}`` for a user the auth middleware
+    authenticated and ``anon:{nonce}`` otherwise; clients never choose it.
+    """
+
+    @property
+    def user_id(self) -> Optional[str]: ...
+    @property
+    def client_id(self) -> str: ...
+    @property
+    def is_authenticated(self) -> bool: ...
+    def to_dict(self) -> Dict[str, Optional[str]]: ...
+
+def realtime_identity(request: Request) -> RealtimeIdentity:
+    """Resolve the identity of a realtime connection opened by ``request``."""
+    ...
+
+
 # ============================================================================
 # Realtime: Heartbeat
 # ============================================================================
@@ -996,6 +2315,9 @@ class HeartbeatConfig:
     max_retries: int
     sse_retry_ms: int
     send_keepalive: bool
+    adaptive: bool
+    max_timeout_secs: Optional[float]
+    jitter_window: int
     
     def __init__(
         self,
@@ -1004,6 +2326,9 @@ class HeartbeatConfig:
         max_retries: int = 5,
         sse_retry_ms: int = 3000,
         send_keepalive: bool = True,
+        adaptive: bool = False,
+        max_timeout_secs: Optional[float] = None,
+        jitter_window: int = 8,
     ) -> None: ...
 
 class HeartbeatStats:
@@ -1013,6 +2338,16 @@ class HeartbeatStats:
     total_pongs: int
     total_timeouts: int
     timed_out_clients: int
+    clients: Dict[str, HeartbeatClientStats]
+
+class HeartbeatClientStats:
+    """Observed timing of one monitored client."""
+    interval_secs: float
+    mean_interval_secs: Optional[float]
+    jitter_secs: Optional[float]
+    effective_timeout_secs: float
+    samples: int
+    overridden: bool
 
 class HeartbeatMonitor:
     """Server-side heartbeat monitor for SSE and WebSocket connections."""
@@ -1020,6 +2355,11 @@ class HeartbeatMonitor:
     
     def __init__(self, config: Optional[HeartbeatConfig] = None) -> None: ...
     def register(self, client_id: str, last_event_id: Optional[str] = None) -> None: ...
+    def set_client_config(self, client_id: str, interval_secs: float, max_missed: int) -> bool: ...
+    def get_client_config(self, client_id: str) -> Optional[Dict[str, float]]: ...
+    def clear_client_config(self, client_id: str) -> bool: ...
+    def client_stats(self, client_id: str) -> Optional[HeartbeatClientStats]: ...
+    def loop_interval_secs(self) -> float: ...
     def unregister(self, client_id: str) -> bool: ...
     def ping(self, client_id: str) -> bool: ...
     def pong(self, client_id: str) -> bool: ...
@@ -1190,6 +2530,10 @@ def fast_hash_bytes(data: bytes) -> int:
     """Compute xxHash3-64 of raw bytes (non-cryptographic)."""
     ...
 
+def json_dumps(obj: Any, detach: Optional[bool] = None) -> bytes:
+    """Serialize like ``res.json()``; large objects are written with the GIL released (``detach`` forces a path)."""
+    ...
+
 
 # ============================================================================
 # Utils: Time Helpers
@@ -1231,6 +2575,34 @@ def sec_to_ms(sec: int) -> int:
     """Convert seconds to milliseconds."""
     ...
 
+def parse_duration(s: str) -> float:
+    """Parse '500ms', '30s', '2m', '1h' or '1d' to seconds (bare numbers are seconds)."""
+    ...
+
+def parse_size(s: str) -> int:
+    """Parse '512KB', '2MB' or '1GB' (powers of 1024) to bytes (bare numbers are bytes)."""
+    ...
+
+# ============================================================================
+# Utils: CPU
+# ============================================================================
+
+def cpu_count() -> int:
+    """Number of CPUs usable by this process, honouring cgroup CPU quotas."""
+    ...
+
+def cgroup_cpu_quota(root: Optional[str] = None) -> Optional[float]:
+    """CPU limit from the cgroup mounted at root, or None when unlimited."""
+    ...
+
+def cpu_affinity_layout(
+    num_workers: int,
+    affinity: str | List[List[int]] | None = None,
+    cores: Optional[List[int]] = None,
+) -> List[List[int]]:
+    """Core set each worker would be pinned to."""
+    ...
+
 # ------------------------------- GRPC Helpers --------------------------------
 class GrpcConfig:
     """Configuration for gRPC clients and servers."""
@@ -1264,3 +2636,50 @@ class RedisPool:
     def incr(self, key: str) -> int: ...
     def publish(self, channel: str, message: str) -> int: ...
     def ping(self) -> bool: ...
+
+# -------------------- MemoryCache --------------------
+
+class MemoryCache:
+
+    """Per-process LRU cache with TTL and atomic counters."""
+
+    def __init__(self, max_entries: int = 10000, ttl: Optional[float] = None) -> None: ...
+    @property
+    def max_entries(self) -> int: ...
+    def get(self, key: str, default: Any = None) -> Any: ...
+    def set(self, key: str, value: Union[str, bytes, int, float, bool], ttl: Optional[float] = None) -> None: ...
+    def delete(self, key: str) -> bool: ...
+    def get_or_set(
+        self,
+        key: str,
+        factory: Callable[[], Union[str, bytes, int, float, bool]],
+        ttl: Optional[float] = None,
+    ) -> Union[str, bytes, int, float, bool]: ...
+    def incr(self, key: str, delta: int = 1, ttl: Optional[float] = None) -> int: ...
+    def decr(self, key: str, delta: int = 1, ttl: Optional[float] = None) -> int: ...
+    def get_many(self, keys: List[str]) -> Dict[str, Any]: ...
+    def set_many(self, items: Dict[str, Union[str, bytes, int, float, bool]], ttl: Optional[float] = None) -> None: ...
+    def purge_expired(self) -> int: ...
+    def clear(self) -> None: ...
+    def stats(self) -> Dict[str, int]: ...
+    def __len__(self) -> int: ...
+    def __contains__(self, key: str) -> bool: ...
+
+# -------------------- Submodules --------------------
+# Every class/function above is also reachable from its submodule,
+# e.g. ``hypern._hypern.db.DbSession`` or ``hypern._hypern.realtime.ChannelManager``.
+
+core: ModuleType
+http: ModuleType
+routing: ModuleType
+middleware: ModuleType
+logging: ModuleType
+realtime: ModuleType
+db: ModuleType
+fast_path: ModuleType
+client: ModuleType
+cache: ModuleType
+telemetry: ModuleType
+redis: ModuleType
+grpc: ModuleType
+utils: ModuleType
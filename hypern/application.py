@@ -2,6 +2,7 @@ from __future__ import annotations
 
 import asyncio
 import functools
+import os
 import signal
 from typing import (
     Any, Callable, Dict, List, Optional, Type, TypeVar, Union, 
@@ -12,14 +13,24 @@ from typing_extensions import Annotated, Doc
 
 from hypern._hypern import Route as RustRoute
 from hypern._hypern import Router as RustRouter
-from hypern._hypern import Server
-from hypern.exceptions import ExceptionHandler
+from hypern._hypern import (
+    Server,
+    TestClient,
+    current_etag,
+    feature_flag_stats,
+    route_memo_stats,
+    set_etag_provider,
+)
+from hypern.exceptions import ExceptionHandler, http_exception
 from hypern.router import Router
 from hypern._hypern import DIContainer, TaskExecutor, TaskResult
 from hypern._hypern import SSEStream, StreamingResponse
 from hypern._hypern import HealthCheck, ReloadConfig, ReloadManager
+from hypern._hypern import HeaderPolicy
 from hypern._hypern import LogConfig
 from hypern.di import inject as _standalone_inject
+from hypern.lazy import LazyHandler
+from hypern.streaming import is_stream_result as _is_stream_result, send_stream as _send_stream
 
 from hypern.database import Database as _Database, finalize_db as _finalize_db
 from hypern._hypern import get_db as _get_db
@@ -36,6 +47,13 @@ HandlerType = Callable[..., Union[None, Awaitable[None]]]
 Middleware = Union[Callable, object]
 
 
+def _method_names(methods: Union[str, List[str]]) -> Optional[frozenset]:
+    """Upper-cased names of a method spec; None for ``"*"`` (every method)."""
+    names = methods.split(",") if isinstance(methods, str) else list(methods)
+    names = [name.strip().upper() for name in names]
+    return None if "*" in names else frozenset(names)
+
+
 class Hypern:
     """
     Example:
@@ -71,9 +89,11 @@ class Hypern:
         task_workers: int = 4,
         task_queue_size: int = 1000,
         log_config: Optional[LogConfig] = None,
+        strict_routes: bool = False,
     ) -> None:
-        # Core routing
-        self._router = RustRouter(path="/")
+        # Core routing; strict_routes rejects ambiguous templates instead of
+        # warning (see Router.check())
+        self._router = RustRouter(path="/", strict_routes=strict_routes)
         self._routers: List[Router] = []
         
         # Middleware (Rust middleware instances or callables)
@@ -105,6 +125,8 @@ class Hypern:
         # OpenAPI (lazy-loaded)
         self._openapi: Optional['OpenAPIGenerator'] = None
         self._openapi_enabled = False
+        # Server ``default_auth``, recorded when the server is built
+        self._default_auth = "public"
         
         # Graceful shutdown
         self._shutdown_event: Optional[asyncio.Event] = None
@@ -125,11 +147,23 @@ class Hypern:
         
         # Reload / health configuration
         self._reload_config: Optional[ReloadConfig] = None
+        self._health_checks: List[tuple] = []
         self._reload_manager: Optional[ReloadManager] = None
+        self._header_policy: Optional[HeaderPolicy] = None
         
         # Logging configuration
         self._log_config: Optional[LogConfig] = log_config
         
+        # Rust middleware chain options
+        self._middleware_options: Dict[str, Any] = {}
+        self._middleware_config: Optional[Dict[str, Any]] = None
+        self._middleware_placement: Dict[int, Dict[str, Any]] = {}
+        # Methods each method-bound middleware runs for, by id
+        self._middleware_methods: Dict[int, frozenset] = {}
+        
+        # Long-poll endpoint for realtime channels
+        self._realtime_poll: Optional[Dict[str, Any]] = None
+        
         if routes is not None:
             self._router.extend_route(routes)
   
@@ -225,6 +259,155 @@ class Hypern:
         """
         return _standalone_inject(*names)
     
+    def provide(
+        self,
+        name: str,
+        factory: Callable[..., Any],
+        scope: str = "app",
+        teardown: Optional[Callable[[Any], Any]] = None,
+    ) -> 'Hypern':
+        """
+        Register a dependency resolved in handlers with ``req.dep(name)``.
+        
+        The factory's required parameters name other provided dependencies.
+        ``scope="app"`` creates one instance per worker at startup (coroutine
+        factories are awaited) and tears it down at shutdown;
+        ``scope="request"`` creates it on first use in a request and tears it
+        down when the handler completes.
+        
+        Example:
+            app.provide("pool", create_pool, teardown=lambda p: p.close())
+            app.provide("repo", lambda pool: UserRepo(pool), scope="request")
+            
+            @app.get("/users")
+            def list_users(req, res, ctx):
+                res.json(req.dep("repo").all())
+        """
+        Server.provide(name, factory, scope=scope, teardown=teardown)
+        return self
+    
+    def update_config(self, settings: Dict[str, Any], source: Optional[str] = None) -> int:
+        """
+        Change hot-reloadable settings while the server runs.
+        
+        Accepts ``log_level``, ``log_format``, ``log_skip_paths``,
+        ``slow_threshold_ms``, ``max_in_flight`` (requests a worker serves
+        at once before answering 503; ``None`` for no cap),
+        ``rate_limit`` (a dict of ``max_requests``, ``window_secs`` and
+        ``skip_paths`` for every rate-limit middleware's default limit),
+        ``max_connections``, ``max_connections_per_ip`` and
+        ``header_read_timeout`` (connection limits as in ``start()``) and
+        ``feature_flags`` (see ``set_flag``).
+        Any other key raises ``ValueError`` naming it, and nothing is
+        applied. The calling worker applies the update at once and the
+        others on their next request. Each update is logged at WARN with
+        ``source``, the pid and the changed values.
+        
+        Example:
+            @app.post("/admin/config")
+            def reconfigure(req, res, ctx):
+                generation = app.update_config(req.json(), source="admin api")
+                res.json({"generation": generation})
+        
+        Returns:
+            The new config generation
+        """
+        return Server.update_config(settings, source)
+    
+    def set_flag(
+        self,
+        name: str,
+        enabled: bool = True,
+        percentage: Optional[float] = None,
+        sticky_by: str = "user_id",
+        off_status: int = 404,
+        source: Optional[str] = None,
+    ) -> int:
+        """
+        Turn a feature flag on or off, or roll it out to a percentage of
+        requests, in every worker without a restart.
+        
+        Routes registered with ``feature_flag=name`` answer ``off_status``
+        (404 or 503) without running the handler while the flag is off for
+        a request; handlers check flags with ``req.flag(name)``. A partial
+        rollout buckets requests by a hash of ``sticky_by``: ``"user_id"``
+        (the authenticated user, else the client IP, else the request),
+        ``"client_ip"`` or ``"request_id"``, so a user keeps seeing the same
+        side. Flags never set are off.
+        
+        Example:
+            app.set_flag("new_checkout", percentage=25)
+            
+            @app.post("/checkout/v2", feature_flag="new_checkout")
+            def checkout(req, res, ctx):
+                ...
+        
+        Returns:
+            The new config generation
+        """
+        return Server.set_flag(name, enabled, percentage, sticky_by, off_status, source)
+    
+    def flag_stats(self) -> Dict[str, Dict[str, Any]]:
+        """Per-flag ``on``/``off`` evaluation counts and settings in this worker."""
+        return feature_flag_stats()
+    
+    def set_etag_provider(self, name: str, provider: Any) -> None:
+        """
+        Set the version token behind ``etag_provider=name`` routes.
+        
+        ``provider`` is a string or integer token, a callable returning the
+        current one (or None), or None to unset it. A GET or HEAD whose
+        If-None-Match matches the token is answered 304 before the handler
+        runs; other successful responses carry it as their ETag. Tokens are
+        kept per process: set them before ``start`` or use a callable reading
+        shared state.
+        
+        Example:
+            app.set_etag_provider("dashboard_v", lambda: stats.version)
+            
+            @app.get("/dashboard", etag_provider="dashboard_v")
+            def dashboard(req, res, ctx):
+                res.json(aggregate())
+        """
+        set_etag_provider(name, provider)
+    
+    def current_etag(self, name: str) -> Optional[str]:
+        """The quoted ETag the provider ``name`` answers with now, or None."""
+        return current_etag(name)
+    
+    def invalidate_route_cache(self, path: str) -> int:
+        """
+        Drop the kept responses of ``memoize_ttl_secs`` routes at ``path``.
+        
+        ``path`` is the route path as registered, e.g. ``"/config"``; the
+        next request to it runs the handler again. Memoized responses are
+        kept per process, so this reaches the calling worker only; the TTL
+        bounds how long other workers keep theirs.
+        
+        Example:
+            @app.get("/settings", memoize_ttl_secs=60)
+            def settings(req, res, ctx):
+                res.json(load_settings())
+            
+            @app.put("/settings")
+            def save_settings(req, res, ctx):
+                store_settings(req.json())
+                app.invalidate_route_cache("/settings")
+                res.status(204)
+        
+        Returns:
+            How many memoized routes are registered at ``path``
+        """
+        return Server.invalidate_route_cache(path)
+    
+    def route_memo_stats(self) -> Dict[str, Dict[str, int]]:
+        """Per-path ``hits``, ``misses``, ``stores``, ``invalidations`` and ``entries`` of memoized routes in this worker."""
+        return route_memo_stats()
+    
+    def live_config(self) -> Dict[str, Any]:
+        """Hot-reloadable settings as they stand in this worker."""
+        return Server.live_config()
+    
     def background(
         self, 
         delay_seconds: Optional[float] = None
@@ -354,8 +537,13 @@ class Hypern:
         """
         Decorator to register a WebSocket handler.
         
+        Options limiting inbound messages (see ``WsLimits``):
+        ``max_message_size``, ``max_decompressed_size``, ``max_fragments``,
+        ``permessage_deflate``, ``server_max_window_bits``,
+        ``max_messages_per_second`` and ``rate_limit_action``.
+        
         Example:
-            @app.ws("/chat")
+            @app.ws("/chat", permessage_deflate=True, max_message_size=64 * 1024)
             async def chat(ws):
                 await ws.accept()
                 while True:
@@ -400,6 +588,11 @@ class Hypern:
         log_response: bool = True,
         queue_size: int = 10_000,
         skip_paths: Optional[List[str]] = None,
+        format: Optional[str] = None,
+        redact_patterns: Optional[List[str]] = None,
+        max_field_len: Optional[int] = None,
+        redact_headers: Optional[List[str]] = None,
+        sink: Optional[Dict[str, Any]] = None,
     ) -> 'Hypern':
         """
         Configure logging behavior from the Rust layer.
@@ -413,11 +606,32 @@ class Hypern:
             log_response: Log outgoing responses (status, duration) (default: True)
             queue_size: Internal log queue capacity (default: 10000)
             skip_paths: Paths to exclude from request/response logging
+            format: Access line template for responses, e.g.
+                "{method} {path} {status} {duration_ms}", or a preset name,
+                "combined" or "short" (default: colored layout)
+            redact_patterns: Regexes replaced with "[REDACTED]" in messages,
+                paths and logged header values
+            max_field_len: Cut logged fields longer than this many characters
+                (default: no limit)
+            redact_headers: Headers whose values are never logged (default:
+                authorization, cookie, set-cookie, x-api-key)
+            sink: Ship entries as JSON lines to a collector instead of
+                stderr, e.g. {"type": "tcp", "address": "127.0.0.1:5170",
+                "compression": "gzip"}; see LogConfig for all keys
         
         Example:
             # Default: info level with request/response logging
             app.setup_logging()
             
+            # Apache combined access lines, one per response
+            app.setup_logging(log_request=False, format="combined")
+            
+            # Mask email addresses and cap field length
+            app.setup_logging(redact_patterns=[r"[\w.+-]+@[\w-]+\.[\w.]+"], max_field_len=2048)
+            
+            # Ship to a local collector, gzip-compressed
+            app.setup_logging(sink={"type": "unix", "address": "/run/vector.sock", "compression": "gzip"})
+            
             # Verbose debug logging
             app.setup_logging(level="debug")
             
@@ -438,15 +652,153 @@ class Hypern:
         }
         if skip_paths is not None:
             kwargs["skip_paths"] = skip_paths
+        if format is not None:
+            kwargs["format"] = format
+        if redact_patterns is not None:
+            kwargs["redact_patterns"] = redact_patterns
+        if max_field_len is not None:
+            kwargs["max_field_len"] = max_field_len
+        if redact_headers is not None:
+            kwargs["redact_headers"] = redact_headers
+        if sink is not None:
+            kwargs["sink"] = sink
         self._log_config = LogConfig(**kwargs)
         return self
     
+    def setup_middleware(
+        self,
+        isolate_errors: bool = False,
+        slow_threshold_ms: Optional[int] = None,
+    ) -> 'Hypern':
+        """
+        Configure how the Rust middleware chain handles failures.
+        
+        Args:
+            isolate_errors: Skip non-critical middleware that panics or fails
+                with a 5xx error instead of failing the request. Critical
+                middleware (e.g. BasicAuthMiddleware) is never skipped.
+            slow_threshold_ms: Log a warning when a single middleware takes
+                longer than this many milliseconds
+        
+        Example:
+            app.setup_middleware(isolate_errors=True, slow_threshold_ms=50)
+        """
+        self._middleware_options = {
+            "isolate_errors": isolate_errors,
+            "slow_threshold_ms": slow_threshold_ms,
+        }
+        return self
+    
+    def configure_middleware(
+        self,
+        config: Union[Dict[str, Any], str, os.PathLike],
+        env: Optional[str] = None,
+    ) -> 'Hypern':
+        """
+        Assemble the builtin middleware chain from a declarative config.
+        
+        ``config`` is a dict with a ``middleware`` list, or the path of a
+        TOML/JSON file holding one. Each entry has a ``type`` (cors,
+        rate_limit, security_headers, compression, request_id, log,
+        basic_auth, timeout, idempotency, singleflight) and that
+        middleware's constructor options, plus optional ``paths``,
+        ``methods``, ``priority``, ``before``/``after`` and ``enabled``.
+        Overrides under ``env.<name>`` are merged in for ``env``. The
+        config is checked when the server is built; a ``ValueError`` then
+        lists every problem found.
+        
+        Example:
+            app.configure_middleware({
+                "middleware": [
+                    {"type": "request_id"},
+                    {"type": "cors", "allowed_origins": ["https://example.com"]},
+                    {"type": "rate_limit", "max_requests": 100, "paths": ["/api"]},
+                    {"type": "basic_auth", "users": {"admin": "secret"},
+                     "paths": ["/admin"], "methods": ["POST", "DELETE"]},
+                ],
+                "env": {"dev": {"middleware": [{"type": "rate_limit", "enabled": False}]}},
+            }, env=os.environ.get("APP_ENV"))
+        """
+        from hypern.middleware import load_middleware_config, resolve_middleware_config
+        
+        if isinstance(config, dict):
+            self._middleware_config = resolve_middleware_config(config, env)
+        else:
+            self._middleware_config = load_middleware_config(config, env)
+        return self
+    
+    def realtime_poll(
+        self,
+        manager: Any,
+        path: str = "/realtime/poll",
+        max_wait_secs: float = 25.0,
+        max_polls_per_client: int = 4,
+        require_auth_for_realtime: bool = False,
+        on_connect: Optional[Callable[[Any, str], Any]] = None,
+    ) -> 'Hypern':
+        """
+        Serve a long-poll fallback for clients that cannot use SSE or WebSockets.
+        
+        ``GET {path}?channel=X&cursor=N&wait=S`` answers with
+        ``{"messages": [...], "next_cursor": M}``: the channel's retained
+        messages published after ``cursor``, or, when there are none, the
+        first message published within ``wait`` seconds. Clients pass
+        ``next_cursor`` back on the next poll. The wait happens in Rust and
+        holds no thread. Only channels created with a ``replay_size`` can be
+        polled; the channel's subscribe hook is asked with
+        ``{"transport": "poll", "user_id": ...}`` as metadata.
+        
+        A poll's client ID comes from the auth middleware state, as
+        ``{user_id}:{nonce}`` or ``anon:{nonce}`` (see ``RealtimeIdentity``);
+        a ``client_id`` query parameter is ignored.
+        
+        Args:
+            manager: ChannelManager whose channels are served
+            path: Endpoint path
+            max_wait_secs: Cap on ``wait`` (and its default); keep it below the
+                idle timeout of proxies in front of the server
+            max_polls_per_client: Polls one client may hold at once, told apart
+                by authenticated user or the peer address; extra polls get
+                a 429
+            require_auth_for_realtime: Answer polls no auth middleware
+                authenticated with a 401, before the channel is looked up
+            on_connect: Called with the ``RealtimeIdentity`` and channel name
+                of every poll that is served
+        
+        Example:
+            manager = ChannelManager()
+            manager.create_channel("news", replay_size=100)
+            app.realtime_poll(manager)
+            
+            # GET /realtime/poll?channel=news&cursor=0&wait=25
+        """
+        self._realtime_poll = {
+            "manager": getattr(manager, "_inner", manager),
+            "path": path,
+            "max_wait_secs": max_wait_secs,
+            "max_polls_per_client": max_polls_per_client,
+            "require_auth_for_realtime": require_auth_for_realtime,
+            "on_connect": on_connect,
+        }
+        return self
+    
     def setup_reload(
         self,
         drain_timeout_secs: int = 30,
         startup_grace_secs: int = 2,
         health_probes: bool = True,
         health_path: str = "/_health",
+        log_health_probes: bool = False,
+        stream_shutdown_event: Optional[str] = "server-shutdown",
+        stream_retry_ms: int = 1000,
+        stream_grace_ms: int = 500,
+        deep_check_interval_ms: int = 5000,
+        deep_check_timeout_ms: int = 2000,
+        watchdog_stuck_ms: Optional[int] = None,
+        watchdog_idle_ms: Optional[int] = None,
+        watchdog_dump_stacks: bool = False,
+        watchdog_restart: bool = False,
+        watchdog_restart_grace_ms: int = 5000,
     ) -> 'Hypern':
         """
         Configure zero-downtime reload and health probes.
@@ -456,12 +808,33 @@ class Hypern:
             startup_grace_secs: Seconds to wait before marking new workers as healthy
             health_probes: Whether to enable built-in health probe endpoints
             health_path: Path prefix for health probes (default "/health")
+            log_health_probes: Whether probe requests appear in the access log
+            stream_shutdown_event: SSE event sent to open streams when draining
+                (None closes them without a final event)
+            stream_retry_ms: Reconnect hint (``retry:``) in the shutdown event
+            stream_grace_ms: Milliseconds open streams may keep flushing after
+                the shutdown event before they are closed
+            deep_check_interval_ms: How long a deep probe result is reused
+                before the checks run again
+            deep_check_timeout_ms: How long a deep probe waits for its checks;
+                checks still running then fail
+            watchdog_stuck_ms: Age at which a handler that has not returned
+                counts as stuck; None (the default) disables the watchdog
+            watchdog_idle_ms: How long no handler may complete while one is
+                stuck before the worker is marked unhealthy (defaults to
+                ``watchdog_stuck_ms``)
+            watchdog_dump_stacks: Log every Python thread's stack when the
+                watchdog trips
+            watchdog_restart: Replace a worker that is still stuck after
+                ``watchdog_restart_grace_ms``
         
         Health probe endpoints (when enabled):
             - GET {health_path}          → Full health status JSON
             - GET {health_path}/live     → Liveness probe (is process alive?)
             - GET {health_path}/ready    → Readiness probe (accepting traffic?)
             - GET {health_path}/startup  → Startup probe (finished starting?)
+            - GET {health_path}/deep     → Pings database pools and runs
+              ``health_check`` functions (503 if a critical one fails)
         
         Reload signals (Unix):
             - SIGUSR1 → Graceful reload (drain in-flight requests, then restart workers)
@@ -490,6 +863,54 @@ class Hypern:
             startup_grace_secs=startup_grace_secs,
             health_probes_enabled=health_probes,
             health_path_prefix=health_path,
+            log_health_probes=log_health_probes,
+            stream_shutdown_event=stream_shutdown_event,
+            stream_retry_ms=stream_retry_ms,
+            stream_grace_ms=stream_grace_ms,
+            deep_check_interval_ms=deep_check_interval_ms,
+            deep_check_timeout_ms=deep_check_timeout_ms,
+            watchdog_stuck_ms=watchdog_stuck_ms,
+            watchdog_idle_ms=watchdog_idle_ms,
+            watchdog_dump_stacks=watchdog_dump_stacks,
+            watchdog_restart=watchdog_restart,
+            watchdog_restart_grace_ms=watchdog_restart_grace_ms,
+        )
+        return self
+    
+    def header_policy(
+        self,
+        default_headers: Optional[Union[Dict[str, str], List[tuple]]] = None,
+        remove_headers: Optional[List[str]] = None,
+        server_header: Optional[str] = "Hypern",
+    ) -> 'Hypern':
+        """
+        Set headers for every response the server sends.
+        
+        Applied after handlers and middleware, so 404s, errors, health probes
+        and static files get them too. A header the handler set wins over the
+        one middleware added, which wins over the default.
+        
+        Args:
+            default_headers: Headers added to responses that don't carry them,
+                as a dict or (name, value) pairs
+            remove_headers: Header names stripped from every response, even
+                when a handler set them
+            server_header: ``Server`` value for responses without one; None
+                leaves the header out entirely
+        
+        Example:
+            app.header_policy(
+                default_headers={"X-Frame-Options": "DENY", "X-Api-Version": "3"},
+                remove_headers=["X-Powered-By"],
+                server_header=None,
+            )
+        """
+        if isinstance(default_headers, dict):
+            default_headers = list(default_headers.items())
+        self._header_policy = HeaderPolicy(
+            default_headers=default_headers,
+            remove_headers=remove_headers,
+            server_header=server_header,
         )
         return self
     
@@ -589,14 +1010,35 @@ class Hypern:
         
         return self
     
-    def add_route(self, method: str, endpoint: str, handler: Callable[..., Any]):
+    def add_route(
+        self, method: Union[str, List[str]], endpoint: str, handler: Callable[..., Any], **options
+    ):
         """
         Add a route to the router.
         
         Args:
-            method: The HTTP method (GET, POST, PUT, DELETE, etc.)
+            method: The HTTP method (GET, POST, ...), a list of methods
+                served by one route, or ``"*"`` for any method not claimed
+                by an exact route on the same path
             endpoint: The endpoint path (e.g., "/users/:id")
             handler: The function that handles requests
+            **options: Route options; ``max_json_bytes`` caps bodies parsed
+                by ``req.json()`` below the transport body limit, ``host``
+                restricts the route to one Host header, ``tags`` are
+                recorded for OpenAPI; ``timeout``, ``max_body_size``,
+                ``cache_ttl``, ``log``, ``metadata``, ``coalesce``,
+                ``response_fields``, ``retry`` and ``retry_unsafe`` set the
+                per-route config (see ``Route``); ``auth`` is ``"required"``,
+                ``"optional"``, ``"public"`` or a list of roles, checked
+                after the Rust middleware and before the handler;
+                ``feature_flag`` names a flag (see ``set_flag``) the
+                request must have on to reach the handler;
+                ``etag_provider`` names a version token (see
+                ``set_etag_provider``) answering conditional GETs with 304
+                before the handler; ``memoize_ttl_secs`` keeps the first 200
+                response to a GET for that long (see
+                ``invalidate_route_cache``), one per value of the
+                ``memoize_vary`` request headers
         """
         # Normalize path to start with /
         if endpoint and not endpoint.startswith("/"):
@@ -604,7 +1046,28 @@ class Hypern:
         if not endpoint:
             endpoint = "/"
         
-        route = RustRoute(path=endpoint, function=handler, method=method.upper())
+        route = RustRoute(
+            path=endpoint,
+            function=handler,
+            method=method,
+            max_json_bytes=options.get("max_json_bytes"),
+            host=options.get("host"),
+            tags=options.get("tags"),
+            timeout=options.get("timeout"),
+            max_body_size=options.get("max_body_size"),
+            cache_ttl=options.get("cache_ttl"),
+            log=options.get("log", True),
+            metadata=options.get("metadata"),
+            coalesce=options.get("coalesce", False),
+            response_fields=options.get("response_fields"),
+            retry=options.get("retry"),
+            retry_unsafe=options.get("retry_unsafe", False),
+            auth=options.get("auth"),
+            feature_flag=options.get("feature_flag"),
+            etag_provider=options.get("etag_provider"),
+            memoize_ttl_secs=options.get("memoize_ttl_secs"),
+            memoize_vary=options.get("memoize_vary"),
+        )
         self._router.add_route(route=route)
     
     def get_routes(self) -> list:
@@ -619,12 +1082,72 @@ class Hypern:
                 print(f"{r['method']} {r['path']} -> {r['handler']}")
         """
         return self._router.get_routes_info_py()
+    
+    def remove_route(
+        self, method: Union[str, List[str]], path: str, host: Optional[str] = None
+    ) -> bool:
+        """
+        Remove a route, also while the server is running.
+        
+        Requests already being handled finish normally; later requests to
+        the path get 404. When called from
+        a handler, only the worker process handling the call is affected.
+        
+        Args:
+            method: The method, method list or ``"*"`` the route was added with
+            path: The path template it was registered with (e.g. "/users/:id")
+            host: Only remove the route bound to this host
+        
+        Returns:
+            True if a route was removed
+        """
+        if path and not path.startswith("/"):
+            path = "/" + path
+        return self._router.remove_route(method, path or "/", host)
+    
+    def remove_prefix(self, prefix: str, host: Optional[str] = None) -> bool:
+        """
+        Remove every route under a path prefix, e.g. to unmount a router.
+        
+        Example:
+            app.remove_prefix("/tenants/acme")
+        
+        Returns:
+            True if any route was removed
+        """
+        return self._router.remove_prefix(prefix, host)
         
         # Register with OpenAPI if enabled
         # Note: OpenAPI registration happens during spec generation
         # if self._openapi_enabled and self._openapi:
         #     self._openapi.add_route(method, endpoint, handler)
     
+    def api_route(
+        self,
+        path: str,
+        methods: Union[str, List[str]],
+        middleware: Optional[List[Callable]] = None,
+        **options
+    ):
+        """
+        Register one route for a group of methods, or ``"*"`` for any method.
+        
+        Requests with a method the path has no route for get 405 with an
+        ``Allow`` header listing the methods it does answer. A ``"*"`` route
+        answers every method not claimed by an exact route on the same path,
+        including non-standard ones such as ``PROPFIND``.
+        
+        Example:
+            @app.api_route("/items", ["GET", "POST"])
+            async def items(req, res, ctx):
+                res.json({"method": req.method})
+        """
+        def decorator(handler: Callable[..., Any]):
+            wrapped = self._wrap_handler(handler, middleware)
+            self.add_route(methods, path, wrapped, **options)
+            return handler
+        return decorator
+    
     def get(self, path: str, middleware: Optional[List[Callable]] = None, **options):
         """
         Register a GET route.
@@ -634,11 +1157,7 @@ class Hypern:
             async def get_users(req, res, ctx):
                 res.json([{"id": 1}])
         """
-        def decorator(handler: Callable[..., Any]):
-            wrapped = self._wrap_handler(handler, middleware)
-            self.add_route("GET", path, wrapped)
-            return handler
-        return decorator
+        return self.api_route(path, "GET", middleware, **options)
     
     def post(self, path: str, middleware: Optional[List[Callable]] = None, **options):
         """
@@ -650,67 +1169,39 @@ class Hypern:
                 body = req.json()
                 res.status(201).json(body)
         """
-        def decorator(handler: Callable[..., Any]):
-            wrapped = self._wrap_handler(handler, middleware)
-            self.add_route("POST", path, wrapped)
-            return handler
-        return decorator
+        return self.api_route(path, "POST", middleware, **options)
     
     def put(self, path: str, middleware: Optional[List[Callable]] = None, **options):
         """Register a PUT route."""
-        def decorator(handler: Callable[..., Any]):
-            wrapped = self._wrap_handler(handler, middleware)
-            self.add_route("PUT", path, wrapped)
-            return handler
-        return decorator
+        return self.api_route(path, "PUT", middleware, **options)
     
     def delete(self, path: str, middleware: Optional[List[Callable]] = None, **options):
         """Register a DELETE route."""
-        def decorator(handler: Callable[..., Any]):
-            wrapped = self._wrap_handler(handler, middleware)
-            self.add_route("DELETE", path, wrapped)
-            return handler
-        return decorator
+        return self.api_route(path, "DELETE", middleware, **options)
     
     def patch(self, path: str, middleware: Optional[List[Callable]] = None, **options):
         """Register a PATCH route."""
-        def decorator(handler: Callable[..., Any]):
-            wrapped = self._wrap_handler(handler, middleware)
-            self.add_route("PATCH", path, wrapped)
-            return handler
-        return decorator
+        return self.api_route(path, "PATCH", middleware, **options)
     
     def options(self, path: str, middleware: Optional[List[Callable]] = None, **options):
         """Register an OPTIONS route."""
-        def decorator(handler: Callable[..., Any]):
-            wrapped = self._wrap_handler(handler, middleware)
-            self.add_route("OPTIONS", path, wrapped)
-            return handler
-        return decorator
+        return self.api_route(path, "OPTIONS", middleware, **options)
     
     def head(self, path: str, middleware: Optional[List[Callable]] = None, **options):
         """Register a HEAD route."""
-        def decorator(handler: Callable[..., Any]):
-            wrapped = self._wrap_handler(handler, middleware)
-            self.add_route("HEAD", path, wrapped)
-            return handler
-        return decorator
+        return self.api_route(path, "HEAD", middleware, **options)
     
     def all(self, path: str, middleware: Optional[List[Callable]] = None, **options):
         """
-        Register a route for all HTTP methods.
+        Register a route for any method not claimed by an exact route on
+        the same path.
         
         Example:
             @app.all("/api/*")
             async def api_handler(req, res, ctx):
                 res.json({"method": req.method})
         """
-        def decorator(handler: Callable[..., Any]):
-            wrapped = self._wrap_handler(handler, middleware)
-            for method in ["GET", "POST", "PUT", "DELETE", "PATCH", "OPTIONS", "HEAD"]:
-                self.add_route(method, path, wrapped)
-            return handler
-        return decorator
+        return self.api_route(path, "*", middleware, **options)
     
     def route(self, path: str):
         """
@@ -748,8 +1239,7 @@ class Hypern:
                 return self_rb
             
             def all(self_rb, handler):
-                for method in ["GET", "POST", "PUT", "DELETE", "PATCH"]:
-                    self_rb.app.add_route(method, self_rb.path, handler)
+                self_rb.app.add_route("*", self_rb.path, handler)
                 return self_rb
         
         return AppRouteBuilder(self, path)
@@ -833,15 +1323,28 @@ class Hypern:
     def use(
         self, 
         path_or_middleware: Union[str, Middleware, Callable, Router], 
-        middleware_or_router: Optional[Union[Middleware, Callable, Router]] = None
+        middleware_or_router: Optional[Union[Middleware, Callable, Router]] = None,
+        *,
+        priority: Optional[int] = None,
+        before: Optional[str] = None,
+        after: Optional[str] = None,
+        methods: Optional[Union[str, List[str]]] = None,
     ) -> 'Hypern':
         """
         Use middleware or mount a router.
         
+        Rust middleware runs in order of priority (lower first), then in
+        registration order; each builtin has a sensible default. ``priority``
+        overrides it, and ``before`` / ``after`` name a middleware this one
+        must run before or after. Anchors are resolved when the server
+        starts, which raises ``ValueError`` on unknown names or cycles.
+        ``methods`` limits middleware to a list of methods (or ``"*"``).
+        
         Example:
             # Global middleware
             app.use(cors_middleware)
             app.use(LoggingMiddleware())
+            app.use(RateLimitMiddleware(), after="basic_auth")
             
             # Mounted router
             api = Router(prefix="/api")
@@ -849,8 +1352,17 @@ class Hypern:
             
             # Path-specific middleware
             app.use("/admin", auth_middleware)
+            
+            # Only for writes
+            app.use(csrf_middleware, methods=["POST", "PUT", "DELETE"])
         """
         from hypern.middleware import MiddlewareStack
+        
+        if methods is not None:
+            target = middleware_or_router if isinstance(path_or_middleware, str) else path_or_middleware
+            bound = _method_names(methods)
+            if bound is not None:
+                self._middleware_methods[id(target)] = bound
 
         if isinstance(path_or_middleware, str):
             path = path_or_middleware
@@ -877,6 +1389,14 @@ class Hypern:
             else:
                 # Global middleware
                 self._register_middleware(target)
+                placement = {
+                    "priority": priority,
+                    "before": before,
+                    "after": after,
+                    "methods": methods,
+                }
+                if any(value is not None for value in placement.values()):
+                    self._middleware_placement[id(target)] = placement
         
         return self
 
@@ -933,8 +1453,9 @@ class Hypern:
         # Wrap each handler so it gets ctx injection, error handling, etc.
         for method, path, handler, options in router._routes:
             full_path = prefix + path if prefix else path
-            wrapped = self._wrap_handler(handler)
-            self.add_route(method, full_path, wrapped)
+            options = dict(options)
+            wrapped = self._wrap_handler(handler, options.pop("middleware", None))
+            self.add_route(method, full_path, wrapped, **options)
     
     def on_startup(self, handler: Callable) -> Callable:
         """
@@ -962,6 +1483,24 @@ class Hypern:
         self._shutdown_handlers.append(handler)
         return handler
     
+    def health_check(self, name: Optional[str] = None, critical: bool = True) -> Callable:
+        """
+        Register a check for the deep health probe (``{health_path}/deep``).
+        
+        The check takes no arguments and fails by raising or returning a
+        false value. A failing critical check makes the probe answer 503;
+        other failures only mark it "degraded".
+        
+        Example:
+            @app.health_check("redis", critical=False)
+            def redis_ping():
+                return redis_client.ping()
+        """
+        def decorator(check: Callable) -> Callable:
+            self._health_checks.append((name or check.__name__, check, critical))
+            return check
+        return decorator
+    
     def before_request(self, handler: Callable) -> Callable:
         """
         Register a before-request handler.
@@ -986,31 +1525,52 @@ class Hypern:
         self._after_handlers.append(handler)
         return handler
     
-    def errorhandler(self, exc_class: Type[Exception]) -> Callable:
+    def errorhandler(self, exc_class: Union[Type[Exception], int]) -> Callable:
         """
-        Register an exception handler.
+        Register an exception handler, for an exception class or a status.
+        
+        Handlers for ``HTTPException`` classes and statuses also answer the
+        errors the server generates once a request is read, such as router
+        404s and 405s, instead of the default error envelope; the exception
+        they get carries the envelope's ``code`` (an ``ErrorCode`` value).
         
         Example:
             @app.errorhandler(NotFound)
             def handle_not_found(req, res, error):
                 res.status(404).json({"error": "Not found"})
             
+            @app.errorhandler(405)
+            def handle_wrong_method(req, res, error):
+                res.status(405).json({"error": error.code})
+            
             @app.errorhandler(Exception)
             def handle_all(req, res, error):
                 res.status(500).json({"error": "Server error"})
         """
         return self._exception_handler.handle(exc_class)
     
-    def register_error_handler(self, exc_class: Type[Exception], handler: Callable):
+    def register_error_handler(self, exc_class: Union[Type[Exception], int], handler: Callable):
         """Register an exception handler programmatically."""
         self._exception_handler.add_handler(exc_class, handler)
     
+    async def _handle_framework_error(self, req, res):
+        """Answer an error the server generated with the app's handler for it"""
+        meta = req.route_meta
+        exc = http_exception(int(meta["error_status"]), meta["error_message"])
+        exc.code = meta["error_code"]
+        res.status(exc.status_code)
+        await self._exception_handler.handle_exception(req, res, exc)
+    
     def _wrap_handler(
         self, 
         handler: Callable, 
         middleware: Optional[List[Callable]] = None
     ) -> Callable:
         """Wrap a handler with middleware, context injection, error handling, and auto DB finalization."""
+        # "package.module:function" strings are imported on first call
+        if isinstance(handler, str):
+            handler = LazyHandler(handler)
+        lazy = isinstance(handler, LazyHandler)
         
         @functools.wraps(handler)
         async def wrapped(req, res):
@@ -1031,10 +1591,17 @@ class Hypern:
                 
                 async def execute_handler():
                     try:
-                        if asyncio.iscoroutinefunction(handler):
-                            await handler(req, res, ctx)
+                        target = handler.resolve() if lazy else handler
+                        if asyncio.iscoroutinefunction(target):
+                            result = await target(req, res, ctx)
+                        else:
+                            result = target(req, res, ctx)
+                        # Generators (and async generator handlers) stream the body;
+                        # other return values become the response (dict → JSON, ...)
+                        if _is_stream_result(result):
+                            _send_stream(res, result)
                         else:
-                            handler(req, res, ctx)
+                            res._apply_return(req, result)
                     except Exception as e:
                         # Mark DB session as having error for rollback
                         if ctx:
@@ -1051,6 +1618,11 @@ class Hypern:
                 
                 # Add applicable global/path-specific middleware from self._middleware
                 for mw_entry in self._middleware:
+                    bound = self._middleware_methods.get(
+                        id(mw_entry[1] if isinstance(mw_entry, tuple) else mw_entry)
+                    )
+                    if bound is not None and req.method not in bound:
+                        continue
                     if isinstance(mw_entry, tuple):
                         path_prefix, mw = mw_entry
                         # Path matching for path-specific middleware
@@ -1176,6 +1748,79 @@ class Hypern:
             **kwargs
         )
     
+    def _build_server(self, sse_keepalive_secs: Optional[float] = None, **server_options) -> Server:
+        """Create a Server with this app's routes, middleware and settings."""
+        server = Server(**server_options)
+        # Routes without their own ``auth`` are documented with this default
+        self._default_auth = server_options.get("default_auth", "public")
+        server.set_router(router=self._router)
+        
+        # Configure reload / health probes
+        if self._reload_config is not None:
+            server.set_reload_config(self._reload_config)
+        else:
+            # Default: enable health probes
+            server.set_reload_config(ReloadConfig())
+        for name, check, critical in self._health_checks:
+            server.add_health_check(name, check, critical=critical)
+        for status in range(400, 600):
+            if self._exception_handler.handles_status(status):
+                server.set_error_handler(status, self._handle_framework_error)
+        if self._header_policy is not None:
+            server.set_header_policy(self._header_policy)
+        
+        # Configure logging
+        if self._log_config is not None:
+            server.set_log_config(self._log_config)
+        else:
+            # Default: info level with request/response logging
+            server.set_log_config(LogConfig())
+        
+        server.configure_middleware(self._middleware_config, **self._middleware_options)
+        if self._realtime_poll is not None:
+            server.set_realtime_poll(**self._realtime_poll)
+        server.set_sse_keepalive(sse_keepalive_secs)
+        
+        # Register Rust middleware
+        for mw in self._middleware:
+            # Skip path-specific middleware tuples and Python callables
+            if isinstance(mw, tuple) or callable(mw):
+                continue
+                
+            # Register Rust middleware objects (CORS, SecurityHeaders, etc.)
+            try:
+                server.use_middleware(mw, **self._middleware_placement.get(id(mw), {}))
+            except Exception:
+                # Silently skip non-Rust middleware (e.g., MiddlewareStack, Python middleware)
+                pass
+        
+        return server
+
+    def test_client(
+        self, timeout: float = 30.0, sse_keepalive_secs: Optional[float] = None, **server_options
+    ) -> TestClient:
+        """
+        Create a client that sends requests through the full pipeline in this
+        process, without a socket or worker processes.
+
+        Startup handlers run first; shutdown handlers do not run. Keyword
+        arguments are the server options of ``start()`` (``allowed_hosts``,
+        ``decompress_requests``, ...).
+
+        Example:
+            client = app.test_client()
+            res = client.get("/users", query={"page": 2})
+            assert res.status == 200
+            assert res.json()["page"] == 2
+        """
+        loop = asyncio.new_event_loop()
+        try:
+            loop.run_until_complete(self._run_startup_handlers())
+        finally:
+            loop.close()
+        server = self._build_server(sse_keepalive_secs=sse_keepalive_secs, **server_options)
+        return TestClient(server, timeout=timeout)
+    
     def start(
         self,
         host: str = '0.0.0.0',
@@ -1184,6 +1829,46 @@ class Hypern:
         workers_threads: int = 1,
         max_blocking_threads: int = 16,
         max_connections: int = 10000,
+        cpu_affinity: Union[str, List[List[int]], None] = None,
+        sse_keepalive_secs: Optional[float] = None,
+        allowed_hosts: Optional[List[str]] = None,
+        force_https: bool = False,
+        hsts_preload: bool = False,
+        trusted_proxies: Optional[List[str]] = None,
+        https_exempt_paths: Optional[List[str]] = None,
+        decompress_requests: bool = False,
+        max_decompressed_size: int = 10 * 1024 * 1024,
+        tls_cert_path: Optional[str] = None,
+        tls_key_path: Optional[str] = None,
+        tls_client_ca_path: Optional[str] = None,
+        tls_require_client_cert: bool = False,
+        server_timing: bool = False,
+        gil_metrics: bool = False,
+        gil_hold_warn_ms: float = 100.0,
+        metrics_publish_interval: float = 1.0,
+        warmup_paths: Optional[List[str]] = None,
+        eager_import: bool = False,
+        warmup_strict: bool = False,
+        merge_slashes: bool = True,
+        path_decoding: str = "unreserved",
+        strict_path_encoding: bool = False,
+        stream_threshold_bytes: int = 1024 * 1024,
+        expect_continue: bool = True,
+        backlog: int = 1024,
+        reuse_port: bool = False,
+        tcp_nodelay: bool = True,
+        keepalive_idle: Optional[int] = 60,
+        keepalive_interval: Optional[int] = None,
+        keepalive_count: Optional[int] = None,
+        memory_debug: bool = False,
+        default_auth: str = "public",
+        max_connections_per_ip: Optional[int] = None,
+        header_read_timeout: Optional[float] = 30.0,
+        connection_limit_exempt: Optional[List[str]] = None,
+        max_header_bytes: Optional[int] = 64 * 1024,
+        max_header_count: Optional[int] = 100,
+        max_uri_length: Optional[int] = 8 * 1024,
+        error_format: str = "auto",
     ):
         """
         Start the server with full configuration.
@@ -1191,10 +1876,117 @@ class Hypern:
         Args:
             host: The host to bind to
             port: The port to listen on
-            num_processes: Number of worker processes
+            num_processes: Number of worker processes (0 = one per usable CPU,
+                honouring container CPU quotas)
             workers_threads: Number of worker threads per process
             max_blocking_threads: Max blocking threads for Python handlers
-            max_connections: Max concurrent connections
+            max_connections: Max concurrent connections per worker; further
+                ones are closed on accept
+            cpu_affinity: Pin workers to CPUs (Linux only) - "auto" pins worker i
+                to core i, or pass explicit core sets like [[0, 1], [2, 3]]
+            sse_keepalive_secs: Send ": keepalive" to live SSE responses idle
+                this long (None disables; override per response)
+            allowed_hosts: Hostnames accepted in the Host header, exact or
+                "*.example.com" wildcards; other hosts get a 400 (health
+                probes are exempt). None accepts any host
+            force_https: Answer requests that did not arrive over TLS with a
+                308 to the same path and query on ``https://``; health probes
+                and https_exempt_paths are left alone. Needs allowed_hosts,
+                as the redirect only names a host from that list
+            hsts_preload: Send a preload-eligible ``Strict-Transport-Security``
+                header (two years, includeSubDomains, preload) on every HTTPS
+                response and none over plain HTTP
+            trusted_proxies: Networks such as ``"10.0.0.0/8"`` whose
+                ``X-Forwarded-Proto`` tells force_https and hsts_preload
+                whether the client used HTTPS; ignored from other peers
+            https_exempt_paths: Path prefixes force_https serves over plain
+                HTTP (default: ``["/.well-known/acme-challenge/"]``)
+            decompress_requests: Inflate gzip/deflate request bodies before
+                parsing; unknown Content-Encodings get a 415
+            max_decompressed_size: Largest inflated body in bytes; bigger
+                bodies get a 413
+            tls_cert_path: PEM certificate chain; together with tls_key_path
+                the server speaks HTTPS
+            tls_key_path: PEM private key for tls_cert_path
+            tls_client_ca_path: PEM CA bundle used to request and verify
+                client certificates (available as ``req.peer_cert``)
+            tls_require_client_cert: Refuse TLS clients without a valid
+                certificate signed by tls_client_ca_path
+            server_timing: Add a ``Server-Timing`` header with the route,
+                queue, app and write stage durations, shown by browser
+                devtools
+            gil_metrics: Measure how long handlers, executor workers and
+                database row conversion wait for and hold the GIL; reported
+                by ``server_metrics().gil()``
+            gil_hold_warn_ms: With gil_metrics, log a warning (at most once a
+                second per call site) when a single hold lasts longer
+            metrics_publish_interval: Seconds between the request metrics
+                snapshots each worker publishes, merged across workers by
+                ``server_metrics().snapshot(cluster=True)``; 0 turns it off
+            warmup_paths: Paths each worker requests in-process (GET, through
+                middleware and routing, responses discarded) before it reports
+                healthy; the requests carry an ``X-Hypern-Warmup: 1`` header
+            eager_import: Import every handler's module when a worker starts,
+                including handlers registered as ``"package.module:function"``
+            warmup_strict: Keep a worker whose warm-up failed in the starting
+                state so its startup and readiness probes stay 503
+            merge_slashes: Collapse duplicate slashes in request paths before
+                routing
+            path_decoding: Percent-escapes decoded before routing,
+                "unreserved" (letters, digits and ``-._~``) or "all"; ``%2F``
+                is never decoded, so ``/a%2Fb`` is a single path segment
+            strict_path_encoding: Answer 400 to paths with malformed
+                percent-escapes or invalid UTF-8 instead of keeping them as
+                literal text
+            stream_threshold_bytes: Response bodies larger than this are
+                written in slices of the buffer rather than one frame, keeping
+                the Content-Length; 0 disables
+            expect_continue: Check ``Expect: 100-continue`` requests (route,
+                body limit, header-only middleware such as Basic auth) before
+                the client sends the body; failures get their final status
+                without the upload
+            backlog: Pending connection queue length of the listening socket
+            reuse_port: Give each worker its own SO_REUSEPORT listener so the
+                kernel balances connections across workers; falls back to a
+                shared listener where unsupported
+            tcp_nodelay: Disable Nagle's algorithm on accepted connections
+            keepalive_idle: Seconds a connection is idle before TCP keepalive
+                probes start; None disables keepalive
+            keepalive_interval: Seconds between keepalive probes (None keeps
+                the system default)
+            keepalive_count: Unanswered probes before the connection is
+                dropped (None keeps the system default)
+            memory_debug: Check what each request leaves behind once it is
+                done (references to its Request/Response kept by the handler,
+                pool buffers, open database sessions, streaming bodies) and
+                log suspects at ERROR; listed in ``stats()["leak_suspects"]``.
+                Costs a refcount check per request, so debugging only
+            default_auth: Auth requirement of routes registered without
+                ``auth`` ("public", "optional" or "required"); a route opts
+                out with ``auth="public"``
+            max_connections_per_ip: Max concurrent connections per worker
+                from one client address; further ones are closed on accept.
+                None for no cap
+            header_read_timeout: Seconds a client has to send a request's
+                headers, from the connection or the first byte of a
+                kept-alive request; slower clients are disconnected. None
+                for no limit
+            connection_limit_exempt: Networks such as ``"10.0.0.0/8"`` (load
+                balancers, health checkers) not subject to
+                max_connections_per_ip
+            max_header_bytes: Size of a request's header fields as sent;
+                larger ones get a 431 (Request Header Fields Too Large).
+                None leaves only hyper's own bound
+            max_header_count: Header fields a request may have before it
+                gets a 431
+            max_uri_length: Length of a request's path and query before it
+                gets a 414 (URI Too Long)
+            error_format: Body of the errors the server generates itself
+                (404, 405, 413, 431, 503, ...): "auto" sends
+                ``{"error": {"status", "code", "message"}}`` to clients whose
+                Accept names JSON and the message as plain text to others;
+                "json" or "text" always use one. Error handlers registered
+                for a status still win (see ``errorhandler``)
         """
         self._running = True
         self._setup_signal_handlers()
@@ -1211,35 +2003,48 @@ class Hypern:
             self._scheduler.start()
         
         try:
-            server = Server()
-            server.set_router(router=self._router)
-            
-            # Configure reload / health probes
-            if self._reload_config is not None:
-                server.set_reload_config(self._reload_config)
-            else:
-                # Default: enable health probes
-                server.set_reload_config(ReloadConfig())
-            
-            # Configure logging
-            if self._log_config is not None:
-                server.set_log_config(self._log_config)
-            else:
-                # Default: info level with request/response logging
-                server.set_log_config(LogConfig())
-            
-            # Register Rust middleware
-            for mw in self._middleware:
-                # Skip path-specific middleware tuples and Python callables
-                if isinstance(mw, tuple) or callable(mw):
-                    continue
-                    
-                # Register Rust middleware objects (CORS, SecurityHeaders, etc.)
-                try:
-                    server.use_middleware(mw)
-                except Exception:
-                    # Silently skip non-Rust middleware (e.g., MiddlewareStack, Python middleware)
-                    pass
+            server = self._build_server(
+                sse_keepalive_secs=sse_keepalive_secs,
+                cpu_affinity=cpu_affinity,
+                allowed_hosts=allowed_hosts,
+                force_https=force_https,
+                hsts_preload=hsts_preload,
+                trusted_proxies=trusted_proxies,
+                https_exempt_paths=https_exempt_paths,
+                decompress_requests=decompress_requests,
+                max_decompressed_size=max_decompressed_size,
+                tls_cert_path=tls_cert_path,
+                tls_key_path=tls_key_path,
+                tls_client_ca_path=tls_client_ca_path,
+                tls_require_client_cert=tls_require_client_cert,
+                server_timing=server_timing,
+                gil_metrics=gil_metrics,
+                gil_hold_warn_ms=gil_hold_warn_ms,
+                metrics_publish_interval=metrics_publish_interval,
+                warmup_paths=warmup_paths,
+                eager_import=eager_import,
+                warmup_strict=warmup_strict,
+                merge_slashes=merge_slashes,
+                path_decoding=path_decoding,
+                strict_path_encoding=strict_path_encoding,
+                stream_threshold_bytes=stream_threshold_bytes,
+                expect_continue=expect_continue,
+                backlog=backlog,
+                reuse_port=reuse_port,
+                tcp_nodelay=tcp_nodelay,
+                keepalive_idle=keepalive_idle,
+                keepalive_interval=keepalive_interval,
+                keepalive_count=keepalive_count,
+                memory_debug=memory_debug,
+                default_auth=default_auth,
+                max_connections_per_ip=max_connections_per_ip,
+                header_read_timeout=header_read_timeout,
+                connection_limit_exempt=connection_limit_exempt,
+                max_header_bytes=max_header_bytes,
+                max_header_count=max_header_count,
+                max_uri_length=max_uri_length,
+                error_format=error_format,
+            )
             
             server.start(
                 host=host,
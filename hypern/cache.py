@@ -0,0 +1,23 @@
+"""In-memory caching for Hypern.
+
+Provides ``MemoryCache``, a thread-safe LRU cache with per-entry TTL and
+atomic counters, implemented in Rust. Values are limited to ``str``,
+``bytes``, ``int``, ``float`` and ``bool``.
+
+The cache lives in the current process only: each worker has its own copy.
+Use :class:`hypern.redis.RedisPool` for data shared between workers.
+
+Example::
+
+    from hypern.cache import MemoryCache
+
+    cache = MemoryCache(max_entries=1000, ttl=60)
+    cache.set("greeting", "hello")
+    cache.get("greeting")                                  # "hello"
+    cache.get_or_set("config", load_config_json, ttl=300)
+    cache.incr("hits")                                     # 1
+"""
+
+from hypern._hypern import MemoryCache
+
+__all__ = ["MemoryCache"]
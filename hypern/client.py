@@ -1,17 +1,20 @@
 """
-Built-in async HTTP client backed by Rust/reqwest.
+Built-in HTTP client backed by Rust/reqwest.
 
-Provides connection pooling and GIL-free I/O at the Rust level.
+All clients in a process share one connection pool; requests run with the
+GIL released, or as awaitables via the ``*_async`` methods.
 
 Example::
 
     from hypern.client import HttpClient
 
-    client = HttpClient(base_url="https://api.example.com", timeout=30)
+    client = HttpClient(base_url="https://api.example.com", timeout=30, retries=2)
     response = client.get("/users", params={"page": "1"})
     data = response.json()
+
+    response = await client.post_async("/users", json={"name": "Alice"})
 """
 
-from hypern._hypern import HttpClient, ClientResponse
+from hypern._hypern import HttpClient, ClientResponse, ClientFuture
 
-__all__ = ["HttpClient", "ClientResponse"]
+__all__ = ["HttpClient", "ClientResponse", "ClientFuture"]
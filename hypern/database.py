@@ -2,7 +2,7 @@ from __future__ import annotations
 from typing import Protocol, runtime_checkable
 from collections import OrderedDict
 
-from typing import Any, Dict, List, Optional, Union
+from typing import Any, Callable, Dict, Iterable, List, Optional, Sequence, Tuple, Type, TypeVar, Union
 from contextlib import contextmanager
 
 from hypern._hypern import (
@@ -10,12 +10,16 @@ from hypern._hypern import (
     PoolConfig as _PoolConfig,
     PoolStatus as _PoolStatus,
     DbSession as _DbSession,
+    CopyOutRows,
+    RowStream,
     AnyPool as _AnyPool,
     get_db as _get_db,
     finalize_db as _finalize_db,
     finalize_db_all as _finalize_db_all,
 )
 
+T = TypeVar("T")
+
 
 class Database:
     """
@@ -48,6 +52,10 @@ class Database:
         test_before_acquire: bool = False,
         keepalive_secs: Optional[int] = None,
         alias: str = "default",
+        ssl_mode: Optional[str] = None,
+        ssl_root_cert_path: Optional[str] = None,
+        ssl_client_cert: Optional[str] = None,
+        ssl_client_key: Optional[str] = None,
     ) -> None:
         """
         Configure a database connection pool with an alias (lazy initialization).
@@ -69,6 +77,12 @@ class Database:
             test_before_acquire: If True, test connections before acquiring them (default: False, PostgreSQL only)
             keepalive_secs: TCP keepalive interval in seconds (default: None, PostgreSQL only)
             alias: Database alias for identification (default: "default")
+            ssl_mode: ``disable``, ``prefer``, ``require``, ``verify-ca`` or ``verify-full``
+                (default: the URL's ``sslmode``, else ``prefer``; PostgreSQL only)
+            ssl_root_cert_path: CA bundle the server certificate must chain to, needed by
+                ``verify-ca`` and ``verify-full`` (default: the URL's ``sslrootcert``; PostgreSQL only)
+            ssl_client_cert: Client certificate for mutual TLS (PostgreSQL only)
+            ssl_client_key: Private key of ``ssl_client_cert`` (PostgreSQL only)
         
         Raises:
             RuntimeError: If database with this alias is already configured
@@ -87,6 +101,10 @@ class Database:
             'max_lifetime_secs': max_lifetime_secs,
             'test_before_acquire': test_before_acquire,
             'keepalive_secs': keepalive_secs,
+            'ssl_mode': ssl_mode,
+            'ssl_root_cert_path': ssl_root_cert_path,
+            'ssl_client_cert': ssl_client_cert,
+            'ssl_client_key': ssl_client_key,
             'alias': alias,
             'driver': driver,
         }
@@ -113,6 +131,10 @@ class Database:
                 max_lifetime_secs=config_data['max_lifetime_secs'],
                 test_before_acquire=config_data['test_before_acquire'],
                 keepalive_secs=config_data['keepalive_secs'],
+                ssl_mode=config_data['ssl_mode'],
+                ssl_root_cert_path=config_data['ssl_root_cert_path'],
+                ssl_client_cert=config_data['ssl_client_cert'],
+                ssl_client_key=config_data['ssl_client_key'],
             )
             _ConnectionPool.initialize_with_alias(config, alias)
         else:
@@ -122,6 +144,41 @@ class Database:
         
         cls._initialized_pools[alias] = True
     
+    @classmethod
+    def set_alias_resolver(cls, resolver: Optional[Callable[[str, str, Dict[str, Any]], str]]) -> None:
+        """
+        Route logical aliases to physical pools per request.
+        
+        The resolver is called as ``resolver(request_id, alias, context)`` the
+        first time a request asks for ``alias`` and returns the physical alias
+        to use; the result is reused for the rest of the request. ``context``
+        is the request context dict (``request_id``, ``path``, ``client_ip``,
+        propagated middleware state, and ``headers`` when ``db()`` is given the
+        request). Exceptions propagate, so raising ``NotFound``/``Forbidden``
+        answers with 404/403.
+        
+        Args:
+            resolver: The resolver callable, or None to remove it
+        """
+        _ConnectionPool.set_alias_resolver(resolver)
+    
+    @classmethod
+    def register_pool_factory(
+        cls,
+        factory: Optional[Callable[[str], Optional[_PoolConfig]]],
+        max_pools: int = 64,
+    ) -> None:
+        """
+        Create physical PostgreSQL pools on first use.
+        
+        Args:
+            factory: Called as ``factory(alias)`` for an alias without a pool;
+                returns a ``PoolConfig``. None removes it.
+            max_pools: Cap on factory-created pools. When reached, the least
+                recently used idle pool is closed before creating a new one.
+        """
+        _ConnectionPool.register_pool_factory(factory, max_pools)
+    
     @classmethod
     def is_configured(cls, alias: str = "default") -> bool:
         """Check if the database with the specified alias is configured."""
@@ -140,6 +197,62 @@ class Database:
         """
         return _ConnectionPool.status_for_alias(alias)
     
+    @classmethod
+    def begin_test_mode(cls, alias: str = "default") -> None:
+        """
+        Run every session for ``alias`` inside one transaction, for tests.
+        
+        One connection is taken from the pool and pinned in an open
+        transaction; sessions opened afterwards, across any number of
+        requests, share it one at a time. Their ``begin``/``commit``/
+        ``rollback`` become savepoints, and a raw ``COMMIT`` or ``BEGIN``
+        raises instead of ending the test transaction. Call
+        ``rollback_test_mode`` to discard everything written.
+        
+        Args:
+            alias: Database alias (default: "default"); PostgreSQL only
+        """
+        cls._ensure_initialized(alias)
+        _ConnectionPool.begin_test_mode(alias)
+    
+    @classmethod
+    def rollback_test_mode(cls, alias: str = "default") -> None:
+        """
+        Roll back the transaction begun by ``begin_test_mode``.
+        
+        Raises RuntimeError while a session still holds the pinned
+        connection; finalize it first.
+        """
+        _ConnectionPool.rollback_test_mode(alias)
+    
+    @classmethod
+    def in_test_mode(cls, alias: str = "default") -> bool:
+        """Check if ``alias`` is in test mode."""
+        return _ConnectionPool.in_test_mode(alias)
+    
+    @classmethod
+    @contextmanager
+    def test_transaction(cls, alias: str = "default"):
+        """
+        Context manager for ``begin_test_mode`` and ``rollback_test_mode``.
+        
+        Example:
+            # conftest.py
+            @pytest.fixture(autouse=True)
+            def db_isolation():
+                with Database.test_transaction():
+                    yield
+            
+            def test_signup(client):
+                client.post("/users", json={"email": "a@example.com"})
+                # the row is gone again once the test ends
+        """
+        cls.begin_test_mode(alias)
+        try:
+            yield
+        finally:
+            cls.rollback_test_mode(alias)
+    
     @classmethod
     def close(cls, alias: Optional[str] = None) -> None:
         """
@@ -185,6 +298,11 @@ class DbSession:
         """Get the request ID associated with this session."""
         return self._session.request_id
     
+    @property
+    def pool_alias(self) -> str:
+        """Get the physical pool alias this session uses (see ``Database.set_alias_resolver``)."""
+        return self._session.pool_alias
+    
     def begin(self) -> "DbSession":
         """
         Begin a database transaction.
@@ -227,14 +345,19 @@ class DbSession:
     def query(
         self,
         sql: str,
-        params: Optional[List[Any]] = None
+        params: Optional[Union[List[Any], Dict[str, Any]]] = None,
+        gil_batch_size: int = 1000
     ) -> List[Dict[str, Any]]:
         """
         Execute a SELECT query and return results as a list of dictionaries.
         
         Args:
-            sql: SQL query with $1, $2, etc. placeholders
-            params: List of parameter values
+            sql: SQL query with $1, $2, etc. or :name placeholders
+            params: List of parameter values, or a dict for :name placeholders
+            gil_batch_size: Rows converted per batch; between batches the
+                GIL is released briefly and signals are handled, so other
+                threads keep running during large results. 0 converts all
+                rows in one go.
         
         Returns:
             List of dictionaries, one per row
@@ -244,20 +367,25 @@ class DbSession:
                 "SELECT * FROM users WHERE status = $1",
                 ["active"]
             )
+            
+            users = session.query(
+                "SELECT * FROM users WHERE status = :status",
+                {"status": "active"}
+            )
         """
-        return self._session.query(sql, params)
+        return self._session.query(sql, params, gil_batch_size)
     
     def query_one(
         self,
         sql: str,
-        params: Optional[List[Any]] = None
+        params: Optional[Union[List[Any], Dict[str, Any]]] = None
     ) -> Dict[str, Any]:
         """
         Execute a SELECT query and return a single result.
         
         Args:
-            sql: SQL query with $1, $2, etc. placeholders
-            params: List of parameter values
+            sql: SQL query with $1, $2, etc. or :name placeholders
+            params: List of parameter values, or a dict for :name placeholders
         
         Returns:
             Dictionary representing the row
@@ -273,17 +401,76 @@ class DbSession:
         """
         return self._session.query_one(sql, params)
     
+    def query_as(
+        self,
+        sql: str,
+        params: Optional[Union[List[Any], Dict[str, Any]]],
+        cls: Type[T],
+        strict: bool = True,
+        gil_batch_size: int = 1000
+    ) -> List[T]:
+        """
+        Execute a SELECT query and construct ``cls`` from each row.
+        
+        Columns are passed to ``cls`` as keyword arguments, skipping the
+        intermediate dict. The mapping from columns to parameters is
+        resolved once per query from ``cls``'s signature, so any class whose
+        ``__init__`` takes the columns as keywords works: dataclasses,
+        pydantic models, ``__slots__`` classes.
+        
+        Args:
+            sql: SQL query with $1, $2, etc. or :name placeholders
+            params: List of parameter values, a dict for :name placeholders, or None
+            cls: Class to construct for each row
+            strict: If True, a column with no matching parameter raises
+                TypeError; if False, such columns are ignored
+            gil_batch_size: Rows converted per GIL hold, as for ``query()``
+        
+        Returns:
+            List of ``cls`` instances, one per row
+        
+        Raises:
+            TypeError: If a required parameter has no column, or (when
+                strict) a column has no parameter
+        
+        Example:
+            @dataclass
+            class User:
+                id: int
+                name: str
+            
+            users = session.query_as("SELECT id, name FROM users", None, User)
+        """
+        return self._session.query_as(sql, params, cls, strict, gil_batch_size)
+    
+    def query_tuples(
+        self,
+        sql: str,
+        params: Optional[Union[List[Any], Dict[str, Any]]] = None,
+        gil_batch_size: int = 1000
+    ) -> List[Tuple[Any, ...]]:
+        """
+        Execute a SELECT query and return each row as a tuple in column order.
+        
+        Cheaper than ``query()`` when column names are not needed.
+        
+        Example:
+            for user_id, name in session.query_tuples("SELECT id, name FROM users"):
+                ...
+        """
+        return self._session.query_tuples(sql, params, gil_batch_size)
+    
     def execute(
         self,
         sql: str,
-        params: Optional[List[Any]] = None
+        params: Optional[Union[List[Any], Dict[str, Any]]] = None
     ) -> int:
         """
         Execute an INSERT, UPDATE, or DELETE query.
         
         Args:
-            sql: SQL query with $1, $2, etc. placeholders
-            params: List of parameter values
+            sql: SQL query with $1, $2, etc. or :name placeholders
+            params: List of parameter values, or a dict for :name placeholders
         
         Returns:
             Number of rows affected
@@ -299,14 +486,14 @@ class DbSession:
     def execute_many(
         self,
         sql: str,
-        params_list: List[List[Any]]
+        params_list: List[Union[List[Any], Dict[str, Any]]]
     ) -> int:
         """
         Execute a batch of INSERT, UPDATE, or DELETE queries.
         
         Args:
-            sql: SQL query with $1, $2, etc. placeholders
-            params_list: List of parameter lists, one per execution
+            sql: SQL query with $1, $2, etc. or :name placeholders
+            params_list: List of parameter lists (or dicts), one per execution
         
         Returns:
             Total number of rows affected
@@ -322,6 +509,120 @@ class DbSession:
         """
         return self._session.execute_many(sql, params_list)
     
+    def copy_in(
+        self,
+        table: str,
+        columns: List[str],
+        rows: Iterable[Sequence[Any]],
+        format: str = "binary",
+        chunk_size: int = 5000
+    ) -> int:
+        """
+        Bulk-load rows into a table with ``COPY ... FROM STDIN``.
+        
+        Much faster than ``execute_many()`` for large loads. ``rows`` may be
+        a generator; it is read ``chunk_size`` rows at a time. Runs in the
+        session's transaction if one is open; on error the copy is aborted
+        and the connection stays usable.
+        
+        Args:
+            table: Table name, optionally schema-qualified
+            columns: Target columns, in the order of each row's values
+            rows: Iterable of row sequences; ``None`` is NULL
+            format: "binary" or "text"
+            chunk_size: Rows converted per chunk sent to the server
+        
+        Returns:
+            Number of rows copied
+        
+        Example:
+            copied = session.copy_in(
+                "events",
+                ["id", "name"],
+                ((i, f"event {i}") for i in range(100_000)),
+            )
+        """
+        return self._session.copy_in(table, columns, rows, format, chunk_size)
+    
+    def copy_out(
+        self,
+        query: str,
+        csv: bool = False,
+        header: bool = False,
+        batch_size: int = 1000
+    ) -> CopyOutRows:
+        """
+        Export a query's results with ``COPY (query) TO STDOUT``.
+        
+        Returns an iterator of row tuples, or of CSV lines when ``csv`` is
+        True. The session's connection is busy until the iterator is
+        exhausted or closed.
+        
+        Example:
+            for line in session.copy_out("SELECT * FROM events", csv=True):
+                out.write(line)
+        """
+        return self._session.copy_out(query, csv, header, batch_size)
+    
+    def stream(
+        self,
+        sql: str,
+        params: Optional[Union[List[Any], Dict[str, Any]]] = None,
+        chunk_size: int = 1000,
+        prefetch: int = 2
+    ) -> RowStream:
+        """
+        Iterate a query's rows in chunks without loading them all.
+        
+        Rows are read through a server-side cursor by a background task at
+        most ``prefetch`` chunks ahead of the caller. Each chunk is a list
+        of dicts; iterate with ``for`` or ``async for``. A database error is
+        raised when iteration reaches it. The session's connection is busy
+        until the stream is exhausted, closed or dropped.
+        
+        Example:
+            for chunk in session.stream("SELECT * FROM events", chunk_size=500):
+                for row in chunk:
+                    process(row)
+        """
+        return self._session.stream(sql, params, chunk_size, prefetch)
+    
+    async def query_async(
+        self,
+        sql: str,
+        params: Optional[Union[List[Any], Dict[str, Any]]] = None,
+        gil_batch_size: int = 1000
+    ) -> List[Dict[str, Any]]:
+        """
+        Awaitable form of ``query()``.
+        
+        Concurrent calls on one session share its connection and run one
+        at a time, in the order they were made.
+        
+        Example:
+            users, orders = await asyncio.gather(
+                session.query_async("SELECT * FROM users"),
+                session.query_async("SELECT * FROM orders"),
+            )
+        """
+        return await self._session.query_async(sql, params, gil_batch_size)
+    
+    async def query_one_async(
+        self,
+        sql: str,
+        params: Optional[Union[List[Any], Dict[str, Any]]] = None
+    ) -> Dict[str, Any]:
+        """Awaitable form of ``query_one()``."""
+        return await self._session.query_one_async(sql, params)
+    
+    async def execute_async(
+        self,
+        sql: str,
+        params: Optional[Union[List[Any], Dict[str, Any]]] = None
+    ) -> int:
+        """Awaitable form of ``execute()``."""
+        return await self._session.execute_async(sql, params)
+    
     def set_auto_commit(self, auto_commit: bool) -> "DbSession":
         """
         Set whether to auto-commit the transaction on request end.
@@ -376,7 +677,11 @@ class DbSession:
         return f"DbSession(request_id='{self.request_id}', state='{self.state}')"
 
 
-def db(ctx_or_request_id: Union[str, Any], alias: str = "default") -> Union["DbSession", "AnySession"]:
+def db(
+    ctx_or_request_id: Union[str, Any],
+    alias: str = "default",
+    request: Any = None,
+) -> Union["DbSession", "AnySession"]:
     """
     Get a database session for the current request with the specified alias.
     
@@ -386,11 +691,15 @@ def db(ctx_or_request_id: Union[str, Any], alias: str = "default") -> Union["DbS
     Args:
         ctx_or_request_id: Either a Context object or a string request ID
         alias: Database alias to use (default: "default")
+        request: The current request; when an alias resolver is set, its
+            headers are passed to the resolver as ``context["headers"]``
     
     Returns:
         DbSession (PostgreSQL) or AnySession (MySQL/SQLite)
     """
-    Database._ensure_initialized(alias)
+    # Logical aliases handled by the resolver need no pool of their own
+    if Database.is_configured(alias) or not _ConnectionPool.has_alias_resolver():
+        Database._ensure_initialized(alias)
     
     config = Database._databases.get(alias, {})
     driver = config.get('driver', 'postgres')
@@ -404,7 +713,12 @@ def db(ctx_or_request_id: Union[str, Any], alias: str = "default") -> Union["DbS
     else:
         request_id = getattr(ctx_or_request_id, 'request_id', str(ctx_or_request_id))
     
-    return DbSession(_get_db(request_id, alias))
+    context = None
+    if request is not None:
+        context = dict(request.context or {})
+        context["headers"] = request.headers
+    
+    return DbSession(_get_db(request_id, alias, context))
 
 
 def finalize_db(ctx_or_request_id: Union[str, Any], alias: Optional[str] = None) -> None:
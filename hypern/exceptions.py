@@ -1,8 +1,43 @@
 from __future__ import annotations
 
-from typing import Any, Callable, Dict, Optional, Type
+from enum import Enum
+from typing import Any, Callable, Dict, Optional, Type, Union
 import orjson
 
+from ._hypern import (
+    BodyDecodeError,
+    HypernBindError,
+    PayloadTooLargeError,
+    RequestBodyError,
+    UnsupportedMediaTypeError,
+)
+
+
+class ErrorCode(str, Enum):
+    """
+    Stable ``code`` of the errors the server generates itself, as sent in
+    the error envelope ``{"error": {"status", "code", "message"}}``.
+    """
+
+    BAD_REQUEST = "bad_request"
+    UNAUTHORIZED = "unauthorized"
+    FORBIDDEN = "forbidden"
+    NOT_FOUND = "not_found"
+    METHOD_NOT_ALLOWED = "method_not_allowed"
+    PAYLOAD_TOO_LARGE = "payload_too_large"
+    URI_TOO_LONG = "uri_too_long"
+    UNSUPPORTED_MEDIA_TYPE = "unsupported_media_type"
+    EXPECTATION_FAILED = "expectation_failed"
+    TOO_MANY_REQUESTS = "too_many_requests"
+    HEADER_FIELDS_TOO_LARGE = "header_fields_too_large"
+    TOO_MANY_HEADER_FIELDS = "too_many_header_fields"
+    INTERNAL_ERROR = "internal_error"
+    SERVICE_DRAINING = "service_draining"
+    OVERLOADED = "overloaded"
+    SERVICE_UNAVAILABLE = "service_unavailable"
+    GATEWAY_TIMEOUT = "gateway_timeout"
+    ERROR = "error"
+
 
 class HTTPException(Exception):
     """
@@ -13,6 +48,9 @@ class HTTPException(Exception):
         raise HTTPException(400, "Invalid input", {"field": "email", "error": "Invalid format"})
     """
     
+    # ErrorCode of an error the server generated, None for raised ones
+    code: Optional[str] = None
+    
     def __init__(
         self,
         status_code: int = 500,
@@ -128,6 +166,28 @@ class ServiceUnavailable(HTTPException):
         super().__init__(503, detail, data, headers)
 
 
+_STATUS_EXCEPTIONS: Dict[int, Type[HTTPException]] = {
+    400: BadRequest,
+    401: Unauthorized,
+    403: Forbidden,
+    404: NotFound,
+    405: MethodNotAllowed,
+    409: Conflict,
+    422: UnprocessableEntity,
+    429: TooManyRequests,
+    500: InternalServerError,
+    503: ServiceUnavailable,
+}
+
+
+def http_exception(status_code: int, detail: Optional[str] = None) -> HTTPException:
+    """The HTTPException for a status, of its convenience class when it has one."""
+    exc_class = _STATUS_EXCEPTIONS.get(status_code)
+    if exc_class is None:
+        return HTTPException(status_code, detail)
+    return exc_class(detail) if detail else exc_class()
+
+
 class ExceptionHandler:
     """
     Exception handler registry for the application.
@@ -139,6 +199,10 @@ class ExceptionHandler:
         def handle_not_found(req, res, exc):
             res.status(404).json({"error": "Resource not found"})
         
+        @handler.handle(429)
+        def handle_rate_limited(req, res, exc):
+            res.status(429).json({"error": "Slow down"})
+        
         @handler.handle(Exception)
         def handle_all(req, res, exc):
             res.status(500).json({"error": "Something went wrong"})
@@ -146,18 +210,23 @@ class ExceptionHandler:
     
     def __init__(self):
         self._handlers: Dict[Type[Exception], Callable] = {}
+        # HTTPException handlers registered by status code
+        self._status_handlers: Dict[int, Callable] = {}
         self._default_handler: Optional[Callable] = None
     
-    def handle(self, exc_class: Type[Exception]) -> Callable:
-        """Decorator to register an exception handler."""
+    def handle(self, exc_class: Union[Type[Exception], int]) -> Callable:
+        """Decorator to register an exception handler, for a class or a status code."""
         def decorator(func: Callable) -> Callable:
-            self._handlers[exc_class] = func
+            self.add_handler(exc_class, func)
             return func
         return decorator
     
-    def add_handler(self, exc_class: Type[Exception], handler: Callable) -> None:
+    def add_handler(self, exc_class: Union[Type[Exception], int], handler: Callable) -> None:
         """Add an exception handler programmatically."""
-        self._handlers[exc_class] = handler
+        if isinstance(exc_class, int):
+            self._status_handlers[exc_class] = handler
+        else:
+            self._handlers[exc_class] = handler
     
     def set_default_handler(self, handler: Callable) -> None:
         """Set the default handler for unhandled exceptions."""
@@ -170,6 +239,10 @@ class ExceptionHandler:
         if exc_type in self._handlers:
             return self._handlers[exc_type]
         
+        # HTTP errors by status
+        if isinstance(exc, HTTPException) and exc.status_code in self._status_handlers:
+            return self._status_handlers[exc.status_code]
+        
         # Then, try to find handler for parent classes
         for handler_type, handler in self._handlers.items():
             if isinstance(exc, handler_type):
@@ -177,8 +250,31 @@ class ExceptionHandler:
         
         return self._default_handler
     
+    def handles_status(self, status_code: int) -> bool:
+        """
+        Whether HTTP errors with this status have a handler of their own,
+        registered for the status or an HTTPException class; catch-all
+        handlers for other exception classes do not count.
+        """
+        if status_code in self._status_handlers:
+            return True
+        exc = http_exception(status_code)
+        return any(
+            issubclass(exc_class, HTTPException) and isinstance(exc, exc_class)
+            for exc_class in self._handlers
+        )
+    
     async def handle_exception(self, req, res, exc: Exception) -> None:
         """Handle an exception using registered handlers."""
+        # Lets a route's RetryPolicy match on the exception type
+        res._record_exception(exc)
+        # Body errors from req.json() carry their own status code; unless the
+        # app handles them explicitly, treat them as HTTP errors so catch-all
+        # Exception handlers don't turn a 413/415 into a 500
+        if isinstance(exc, RequestBodyError) and not any(
+            issubclass(exc_class, RequestBodyError) for exc_class in self._handlers
+        ):
+            exc = HTTPException(exc.status_code, str(exc))
         handler = self.get_handler(exc)
         
         if handler:
@@ -197,6 +293,10 @@ class ExceptionHandler:
             for key, value in exc.headers.items():
                 res.header(key, value)
             res.status(exc.status_code).json(exc.to_dict())
+        elif isinstance(exc, RequestBodyError):
+            # Body errors raised by req.json() carry their own status code
+            http_exc = HTTPException(exc.status_code, str(exc))
+            res.status(http_exc.status_code).json(http_exc.to_dict())
         else:
             # Generic error response
             res.status(500).json({
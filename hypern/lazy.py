@@ -0,0 +1,49 @@
+"""
+Route handlers imported on first use.
+
+Register a handler by its import string instead of the function to keep its
+module (and everything it imports) out of application startup::
+
+    app.get("/reports/monthly")("myapp.reports:monthly_report")
+
+The module is imported by the first request to the route. Pass
+``eager_import=True`` to ``app.start()`` to import every handler module when
+each worker starts instead, so the first request does not pay for it.
+"""
+
+from __future__ import annotations
+
+import importlib
+from typing import Any, Callable, Optional
+
+__all__ = ["LazyHandler"]
+
+
+class LazyHandler:
+    """A ``"package.module:function"`` reference resolved on first call."""
+
+    def __init__(self, target: str):
+        module, sep, attr = target.partition(":")
+        if not sep or not module or not attr:
+            raise ValueError(
+                f"Lazy handler must look like 'package.module:function', got {target!r}"
+            )
+        # Read by eager_import to find the module to load at worker start
+        self.__module__ = module
+        self.__name__ = attr
+        self.__qualname__ = attr
+        self.__doc__ = None
+        self._target: Optional[Callable[..., Any]] = None
+
+    def resolve(self) -> Callable[..., Any]:
+        """Import the module and return the handler function."""
+        if self._target is None:
+            module = importlib.import_module(self.__module__)
+            self._target = getattr(module, self.__name__)
+        return self._target
+
+    def __call__(self, *args: Any, **kwargs: Any) -> Any:
+        return self.resolve()(*args, **kwargs)
+
+    def __repr__(self) -> str:
+        return f"LazyHandler('{self.__module__}:{self.__name__}')"
@@ -1,7 +1,11 @@
 
 from __future__ import annotations
 
-from typing import Callable, List
+import copy
+import json
+import os
+import tomllib
+from typing import Any, Callable, Dict, List, Optional, Union
 
 from hypern._hypern import (
     CorsMiddleware,
@@ -14,6 +18,9 @@ from hypern._hypern import (
     BasicAuthMiddleware,
     CircuitBreakerMiddleware,
     CacheMiddleware,
+    IdempotencyMiddleware,
+    SingleflightMiddleware,
+    singleflight_stats,
 )
 
 class MiddlewareStack:
@@ -96,6 +103,101 @@ def after_request(func: Callable) -> Callable:
     return func
 
 
+def merge_middleware_config(base: Dict[str, Any], override: Dict[str, Any]) -> Dict[str, Any]:
+    """
+    Merge a middleware config on top of another, returning a new dict.
+    
+    Entries are matched by ``name``, else by ``type``: a matching override
+    entry updates the base entry's keys (so ``{"type": "log", "enabled":
+    False}`` switches logging off), others are appended. Top-level keys such
+    as ``isolate_errors`` are replaced.
+    
+    Example:
+        base = {"middleware": [{"type": "rate_limit", "max_requests": 100}]}
+        prod = {"middleware": [{"type": "rate_limit", "max_requests": 1000}]}
+        merge_middleware_config(base, prod)
+    """
+    merged = copy.deepcopy(base)
+    for key, value in override.items():
+        if key != "middleware":
+            merged[key] = copy.deepcopy(value)
+    
+    entries = merged.setdefault("middleware", [])
+    for entry in override.get("middleware", []):
+        key = _entry_key(entry)
+        matches = [existing for existing in entries if _entry_key(existing) == key]
+        if len(matches) > 1:
+            raise ValueError(
+                f"Override for {key!r} matches {len(matches)} middleware entries; "
+                "give them distinct 'name's"
+            )
+        if matches:
+            matches[0].update(copy.deepcopy(entry))
+        else:
+            entries.append(copy.deepcopy(entry))
+    return merged
+
+
+def _entry_key(entry: Any) -> Any:
+    if isinstance(entry, dict):
+        return entry.get("name", entry.get("type"))
+    return None
+
+
+def resolve_middleware_config(config: Dict[str, Any], env: Optional[str] = None) -> Dict[str, Any]:
+    """
+    Apply the ``env`` section for ``env`` to a middleware config.
+    
+    A config may carry per-environment overrides under ``env``, keyed by
+    environment name; the one for ``env`` is merged on top of the rest with
+    :func:`merge_middleware_config`. Environments without a section use the
+    base config unchanged.
+    """
+    base = {key: value for key, value in config.items() if key != "env"}
+    overrides = config.get("env") or {}
+    if not isinstance(overrides, dict):
+        raise ValueError("'env' must map environment names to middleware configs")
+    if env is not None and env in overrides:
+        return merge_middleware_config(base, overrides[env])
+    return base
+
+
+def load_middleware_config(
+    path: Union[str, os.PathLike], env: Optional[str] = None
+) -> Dict[str, Any]:
+    """
+    Load a middleware config from a TOML or JSON file.
+    
+    The format follows the file extension (``.toml`` or ``.json``), and the
+    ``env`` section for ``env`` is applied (see
+    :func:`resolve_middleware_config`).
+    
+    Example (``middleware.toml``):
+        [[middleware]]
+        type = "rate_limit"
+        max_requests = 100
+        paths = ["/api"]
+        
+        [[env.production.middleware]]
+        type = "rate_limit"
+        max_requests = 1000
+    
+        app.configure_middleware(load_middleware_config("middleware.toml", env="production"))
+    """
+    path = os.fspath(path)
+    if path.endswith(".toml"):
+        with open(path, "rb") as f:
+            config = tomllib.load(f)
+    elif path.endswith(".json"):
+        with open(path, "r", encoding="utf-8") as f:
+            config = json.load(f)
+    else:
+        raise ValueError(f"Unsupported middleware config file '{path}': expected .toml or .json")
+    if not isinstance(config, dict):
+        raise ValueError(f"Middleware config '{path}' must contain a table/object at the top level")
+    return resolve_middleware_config(config, env)
+
+
 __all__ = [
     # Rust Middleware
     'CorsMiddleware',
@@ -108,10 +210,16 @@ __all__ = [
     'BasicAuthMiddleware',
     'CircuitBreakerMiddleware',
     'CacheMiddleware',
+    'IdempotencyMiddleware',
+    'SingleflightMiddleware',
+    'singleflight_stats',
     
     # Utilities
     'MiddlewareStack',
     'middleware',
     'before_request',
     'after_request',
+    'load_middleware_config',
+    'merge_middleware_config',
+    'resolve_middleware_config',
 ]
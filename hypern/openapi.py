@@ -7,6 +7,16 @@ from typing import Any, Callable, Dict, List, Optional, Type, Union, get_type_hi
 import orjson
 
 
+# Schemas for {name:type} route parameter constraints
+PATH_PARAM_SCHEMAS: Dict[str, Dict[str, Any]] = {
+    "str": {"type": "string"},
+    "int": {"type": "integer"},
+    "float": {"type": "number"},
+    "uuid": {"type": "string", "format": "uuid"},
+    "date": {"type": "string", "format": "date"},
+}
+
+
 @dataclass
 class APIParameter:
     """Represents an API parameter (path, query, header, cookie)."""
@@ -300,6 +310,7 @@ class OpenAPIGenerator:
         method: str,
         handler: Callable,
         tags: Optional[List[str]] = None,
+        auth: Optional[Union[str, List[str]]] = None,
     ) -> APIEndpoint:
         """
         Generate an APIEndpoint from a route handler.
@@ -308,6 +319,7 @@ class OpenAPIGenerator:
         - Docstring (summary and description)
         - Type hints (parameters and response)
         - Decorator metadata
+        - The route's ``auth`` requirement
         """
         endpoint = APIEndpoint(path=path, method=method.lower())
         
@@ -322,13 +334,13 @@ class OpenAPIGenerator:
         endpoint.tags = tags or self._get_handler_attr(handler, "_tags", [])
         
         # Extract parameters from path
-        path_params = re.findall(r":(\w+)", path)
-        for param in path_params:
+        path_params = re.findall(r"\{\*?(\w+)(?::(\w+))?\}|:(\w+)", path)
+        for braced, param_type, param in path_params:
             endpoint.parameters.append(APIParameter(
-                name=param,
+                name=braced or param,
                 location="path",
                 required=True,
-                schema={"type": "string"},
+                schema=dict(PATH_PARAM_SCHEMAS.get(param_type, {"type": "string"})),
             ))
         
         # Extract type hints
@@ -391,9 +403,30 @@ class OpenAPIGenerator:
         # Extract security requirements
         if self._get_handler_attr(handler, "_requires_auth", False):
             endpoint.security = [{"bearerAuth": []}]
+        if auth == "required":
+            endpoint.security = self._security_requirements([])
+        elif auth == "optional":
+            # An empty requirement lets anonymous callers through
+            endpoint.security = [{}] + self._security_requirements([])
+        elif isinstance(auth, list):
+            endpoint.security = self._security_requirements(auth)
+        if auth in ("required", "optional") or isinstance(auth, list):
+            endpoint.responses[401] = APIResponse(
+                status_code=401,
+                description="Authentication required",
+                schema={"type": "object", "properties": {"error": {"type": "string"}}},
+            )
+        if isinstance(auth, list):
+            endpoint.responses[403] = APIResponse(
+                status_code=403,
+                description="Missing a required role",
+                schema={"type": "object", "properties": {"error": {"type": "string"}}},
+            )
         
         # Extract RBAC metadata for description
         required_roles = self._get_handler_attr(handler, "_required_roles", None)
+        if required_roles is None and isinstance(auth, list):
+            required_roles = auth
         required_permissions = self._get_handler_attr(handler, "_required_permissions", None)
         rbac_notes = []
         if required_roles:
@@ -411,7 +444,7 @@ class OpenAPIGenerator:
         endpoint.operation_id = self._get_handler_attr(
             handler,
             "_operation_id",
-            f"{method.lower()}_{path.replace('/', '_').replace(':', '_')}",
+            method.lower() + "_" + re.sub(r"\W", "_", path),
         )
         
         return endpoint
@@ -460,7 +493,8 @@ class OpenAPIGenerator:
         paths: Dict[str, Dict[str, Any]] = {}
         for endpoint in self.endpoints:
             # Convert path format from :param to {param}
-            openapi_path = re.sub(r":(\w+)", r"{\1}", endpoint.path)
+            openapi_path = re.sub(r"\{\*?(\w+)(?::\w+)?\}", r"{\1}", endpoint.path)
+            openapi_path = re.sub(r":(\w+)", r"{\1}", openapi_path)
             
             if openapi_path not in paths:
                 paths[openapi_path] = {}
@@ -538,16 +572,30 @@ class OpenAPIGenerator:
         
         return spec
     
+    def _security_requirements(self, scopes: List[str]) -> List[Dict[str, List[str]]]:
+        """One alternative per registered scheme, ``bearerAuth`` if there are none."""
+        names = list(self.security_schemes) or ["bearerAuth"]
+        return [{name: list(scopes)} for name in names]
+    
     def _extract_routes_from_app(self, app) -> None:
         """Extract routes from a Hypern app."""
         if hasattr(app, "router") and hasattr(app.router, "routes"):
+            default_auth = getattr(app, "_default_auth", "public")
             for route in app.router.routes:
-                endpoint = self.endpoint_from_route(
-                    path=route.path,
-                    method=route.method,
-                    handler=route.function if hasattr(route, "function") else lambda: None,
-                )
-                self.endpoints.append(endpoint)
+                auth = getattr(route, "auth", None)
+                # One operation per method of a group; "*" routes have no
+                # fixed method to document
+                for method in route.methods:
+                    if method == "*":
+                        continue
+                    endpoint = self.endpoint_from_route(
+                        path=route.path,
+                        method=method,
+                        handler=route.function if hasattr(route, "function") else lambda: None,
+                        tags=list(getattr(route, "tags", None) or []) or None,
+                        auth=default_auth if auth is None else auth,
+                    )
+                    self.endpoints.append(endpoint)
     
     def to_json(self, indent: int = 2) -> str:
         """Convert spec to JSON string."""
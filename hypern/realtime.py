@@ -53,7 +53,7 @@ from __future__ import annotations
 
 import asyncio
 import json
-from typing import Any, Callable, Dict, List, Optional
+from typing import Any, Callable, Dict, List, Optional, Tuple, Union
 
 from ._hypern import (
     # Channel / Topic
@@ -61,23 +61,40 @@ from ._hypern import (
     ChannelStats,
     Subscriber,
     TopicMatcher,
+    ChannelClosed,
+    SubscriptionError,
+    PublishError,
+    MessageSchema,
     # Presence
     PresenceTracker as _PresenceTracker,
     PresenceInfo,
     PresenceDiff,
+    PresenceUpdate,
     # Broadcast
     RealtimeBroadcast as _RealtimeBroadcast,
     BroadcastConfig,
     BroadcastStats,
     BroadcastSubscriber,
     BackpressurePolicy,
+    # Per-client queues
+    ClientQueueConfig,
+    ClientQueueStats,
+    OverflowPolicy,
+    Priority,
+    # Long polling
+    realtime_poll_stats,
+    # Client identity
+    RealtimeIdentity,
+    realtime_identity as _realtime_identity,
     # Heartbeat
     HeartbeatMonitor as _HeartbeatMonitor,
     HeartbeatConfig,
     HeartbeatStats,
+    HeartbeatClientStats,
     # SSE types (for integration)
     SSEEvent,
 )
+from .exceptions import Unauthorized
 
 class ChannelManager:
     """
@@ -88,6 +105,17 @@ class ChannelManager:
 
     Args:
         default_buffer_size: Default broadcast buffer per channel (default: 256).
+        auto_create: Create missing channels on ``subscribe`` and ``publish``
+            instead of raising KeyError.
+        auto_create_pattern: Topic pattern (or list of patterns) a name must
+            match to be auto-created; None allows any name.
+        default_ttl_secs: Remove auto-created channels after this long with
+            no subscribers and no publishes (default: never).
+        handoff_key: Keep replay history across graceful reloads: a draining
+            worker saves it and the next generation's manager created with
+            the same key restores it before serving (default: no handoff).
+        handoff_max_bytes: Cap on the message text saved (default: 4 MiB);
+            the oldest messages are dropped first.
 
     Topic patterns:
         - ``"chat:general"`` — exact match
@@ -101,19 +129,114 @@ class ChannelManager:
         sub = manager.subscribe("chat:general", "user-1")
         manager.publish("chat:general", "Hello!")
         msg = sub.try_recv()  # "Hello!"
+
+    Access control::
+
+        manager.create_channel("room:42", max_subscribers=100)
+        manager.set_subscribe_hook(
+            lambda channel, client_id, meta: channel in meta.get("rooms", ())
+        )
+        manager.subscribe("room:42", "user-1", {"rooms": ["room:42"]})
+
+    Channels on demand::
+
+        manager = ChannelManager(auto_create=True, auto_create_pattern="chat:*", default_ttl_secs=300)
+        sub = manager.subscribe("chat:room-7", "user-1")  # created with the defaults
     """
 
-    def __init__(self, default_buffer_size: int = 256):
-        self._inner = _ChannelManager(default_buffer_size)
+    def __init__(
+        self,
+        default_buffer_size: int = 256,
+        auto_create: bool = False,
+        auto_create_pattern: Optional[Union[str, List[str]]] = None,
+        default_ttl_secs: Optional[float] = None,
+        handoff_key: Optional[str] = None,
+        handoff_max_bytes: int = 4 * 1024 * 1024,
+    ):
+        self._inner = _ChannelManager(
+            default_buffer_size,
+            auto_create,
+            auto_create_pattern,
+            default_ttl_secs,
+            handoff_key,
+            handoff_max_bytes,
+        )
 
     def create_channel(
         self,
         name: str,
         buffer_size: Optional[int] = None,
         metadata: Optional[Dict[str, str]] = None,
+        max_subscribers: Optional[int] = None,
+        replay_size: Optional[int] = None,
+        idle_ttl_secs: Optional[float] = None,
+        message_schema: Optional[MessageSchema] = None,
+        envelope: bool = False,
     ) -> bool:
-        """Create a new channel. Returns False if it already exists."""
-        return self._inner.create_channel(name, buffer_size, metadata)
+        """Create a new channel. Returns False if it already exists.
+
+        ``replay_size`` keeps that many of the newest messages for
+        ``messages_since`` and long polls (see ``Hypern.realtime_poll``).
+        Channels created here are never removed as idle unless
+        ``idle_ttl_secs`` is given.
+
+        With ``message_schema``, messages that fail it raise
+        ``PublishError``. With ``envelope``, subscribers receive
+        ``{"seq", "ts", "channel", "data"}`` instead of the bare message.
+        """
+        return self._inner.create_channel(
+            name,
+            buffer_size,
+            metadata,
+            max_subscribers,
+            replay_size,
+            idle_ttl_secs,
+            message_schema,
+            envelope,
+        )
+
+    def set_channel_hook(self, hook: Optional[Callable[[str, str], Any]]) -> None:
+        """
+        Observe channel lifecycle with ``hook(event, channel_name)``.
+
+        ``event`` is ``"created"``, ``"auto_created"`` or ``"expired"``.
+        Exceptions from the hook are logged and ignored. Pass None to
+        remove the hook.
+        """
+        self._inner.set_channel_hook(hook)
+
+    def sweep_idle(self) -> int:
+        """Remove idle channels now. Returns the number removed."""
+        return self._inner.sweep_idle()
+
+    def lifecycle_stats(self) -> Dict[str, int]:
+        """Counts of ``created``, ``auto_created`` and ``expired`` channels,
+        and ``restored_channels`` / ``restored_messages`` from a handoff."""
+        return self._inner.lifecycle_stats()
+
+    def set_subscribe_hook(
+        self, hook: Optional[Callable[[str, str, Dict[str, Any]], bool]]
+    ) -> None:
+        """
+        Vet subscriptions with ``hook(channel_name, client_id, metadata)``.
+
+        The hook returns True to accept. It runs with the GIL held on the
+        subscribing thread, so it must be fast (no network calls). A False
+        return or an exception makes ``subscribe`` raise SubscriptionError.
+        Pass None to remove the hook.
+        """
+        self._inner.set_subscribe_hook(hook)
+
+    def set_publish_hook(
+        self, hook: Optional[Callable[[str, Optional[str], str], bool]]
+    ) -> None:
+        """
+        Vet publishes with ``hook(channel_name, client_id, message)``.
+
+        Same rules as ``set_subscribe_hook``; a refusal makes ``publish``
+        raise PublishError. Pass None to remove the hook.
+        """
+        self._inner.set_publish_hook(hook)
 
     def remove_channel(self, name: str) -> bool:
         return self._inner.remove_channel(name)
@@ -121,27 +244,122 @@ class ChannelManager:
     def has_channel(self, name: str) -> bool:
         return self._inner.has_channel(name)
 
-    def subscribe(self, channel_name: str, client_id: str) -> "Subscriber":
-        return self._inner.subscribe(channel_name, client_id)
+    def subscribe(
+        self,
+        channel_name: str,
+        client_id: str,
+        metadata: Optional[Dict[str, Any]] = None,
+        bypass_hooks: bool = False,
+        queue: Optional["ClientQueueConfig"] = None,
+    ) -> "Subscriber":
+        """
+        Subscribe a client to a channel.
+
+        Args:
+            channel_name: Channel to subscribe to.
+            client_id: Unique client identifier.
+            metadata: Passed to the subscribe hook, e.g. the user's roles.
+            bypass_hooks: Skip the subscribe hook for server-internal
+                subscriptions; ``max_subscribers`` still applies.
+            queue: Deliver through a per-client queue with its own capacity
+                and overflow policy; see ``ClientQueueConfig``.
+
+        Raises:
+            KeyError: The channel does not exist and may not be auto-created.
+            SubscriptionError: The channel is full or the hook refused.
+        """
+        return self._inner.subscribe(channel_name, client_id, metadata, bypass_hooks, queue)
 
     def unsubscribe(self, channel_name: str, client_id: str) -> bool:
         return self._inner.unsubscribe(channel_name, client_id)
 
-    def publish(self, channel_name: str, message: str) -> int:
-        """Publish a message. Returns the number of receivers."""
-        return self._inner.publish(channel_name, message)
+    def publish(
+        self,
+        channel_name: str,
+        message: str,
+        client_id: Optional[str] = None,
+        bypass_hooks: bool = False,
+        priority: Union["Priority", str, None] = None,
+    ) -> int:
+        """Publish a message. Returns the number of receivers.
+
+        ``priority`` is a ``Priority`` or ``"high"``, ``"normal"`` (default)
+        or ``"low"``; per-client queues deliver more urgent messages first.
+        """
+        return self._inner.publish(channel_name, message, client_id, bypass_hooks, priority)
 
-    def publish_json(self, channel_name: str, data: Any) -> int:
+    def publish_json(
+        self,
+        channel_name: str,
+        data: Any,
+        client_id: Optional[str] = None,
+        bypass_hooks: bool = False,
+        priority: Union["Priority", str, None] = None,
+    ) -> int:
         """Publish a JSON-serialized message to a channel."""
-        return self._inner.publish(channel_name, json.dumps(data, separators=(",", ":")))
+        return self._inner.publish(
+            channel_name,
+            json.dumps(data, separators=(",", ":")),
+            client_id,
+            bypass_hooks,
+            priority,
+        )
+
+    def publish_to_topic(
+        self,
+        topic: str,
+        message: str,
+        client_id: Optional[str] = None,
+        bypass_hooks: bool = False,
+        priority: Union["Priority", str, None] = None,
+    ) -> int:
+        """Publish to all channels matching a topic pattern.
 
-    def publish_to_topic(self, topic: str, message: str) -> int:
-        """Publish to all channels matching a topic pattern."""
-        return self._inner.publish_to_topic(topic, message)
+        Channels the publish hook refuses are skipped.
+        """
+        return self._inner.publish_to_topic(topic, message, client_id, bypass_hooks, priority)
 
     def get_stats(self, channel_name: str) -> "ChannelStats":
         return self._inner.get_stats(channel_name)
 
+    def messages_since(
+        self, channel_name: str, cursor: int = 0, max_low: Optional[int] = None
+    ) -> Tuple[List[str], int]:
+        """
+        Retained messages published after ``cursor``.
+
+        Returns ``(messages, next_cursor)``; pass ``next_cursor`` back to
+        continue from there. Only the channel's newest ``replay_size``
+        messages are retained. With ``max_low``, only the newest ``max_low``
+        low-priority messages are returned.
+        """
+        return self._inner.messages_since(channel_name, cursor, max_low)
+
+    def last_seq(self, channel_name: str) -> int:
+        """Sequence number of the latest message published on a channel."""
+        return self._inner.last_seq(channel_name)
+
+    def export_replay(self, max_bytes: Optional[int] = None) -> bytes:
+        """
+        Snapshot every channel's sequence counter and newest retained
+        messages, as saved for the next generation on a graceful reload.
+
+        ``max_bytes`` caps the message text (default: ``handoff_max_bytes``);
+        older messages are dropped first, sequence counters always kept.
+        """
+        return self._inner.export_replay(max_bytes)
+
+    def import_replay(self, data: bytes) -> Tuple[int, int]:
+        """
+        Restore an ``export_replay`` snapshot so sequence numbers continue
+        where it left off.
+
+        Only channels that exist and have not published yet are restored.
+        Returns ``(channels, messages)`` restored; raises ValueError for a
+        corrupt snapshot.
+        """
+        return self._inner.import_replay(data)
+
     def list_channels(self) -> List[str]:
         return self._inner.list_channels()
 
@@ -166,24 +384,20 @@ class ChannelManager:
         poll_interval: float = 0.01,
     ) -> None:
         """
-        Subscribe and continuously poll for messages asynchronously.
+        Subscribe and deliver messages until the channel is removed.
 
         Args:
             channel_name: Channel to subscribe to.
             client_id: Unique client identifier.
             callback: Called with each message string.
-            poll_interval: Seconds between polls (default: 0.01).
+            poll_interval: Unused; messages are awaited, not polled.
         """
         sub = self._inner.subscribe(channel_name, client_id)
         try:
-            while True:
-                msg = sub.try_recv()
-                if msg is not None:
-                    result = callback(msg)
-                    if asyncio.iscoroutine(result):
-                        await result
-                else:
-                    await asyncio.sleep(poll_interval)
+            async for msg in sub:
+                result = callback(msg)
+                if asyncio.iscoroutine(result):
+                    await result
         finally:
             self._inner.unsubscribe(channel_name, client_id)
 
@@ -204,14 +418,15 @@ class PresenceTracker:
         tracker.track("room:lobby", "alice", {"name": "Alice"})
         tracker.track("room:lobby", "bob", {"name": "Bob"})
         members = tracker.list("room:lobby")  # [PresenceInfo, PresenceInfo]
-        diff = tracker.flush_diff("room:lobby")  # PresenceDiff(joins=2, leaves=0)
+        tracker.update("alice", {"status": "typing", "cursor": {"line": 4}})
+        diff = tracker.flush_diff("room:lobby")  # PresenceDiff(joins=2, leaves=0, updated=1)
     """
 
     def __init__(self):
         self._inner = _PresenceTracker()
 
     def track(
-        self, channel: str, client_id: str, metadata: Optional[Dict[str, str]] = None
+        self, channel: str, client_id: str, metadata: Optional[Dict[str, Any]] = None
     ) -> "PresenceInfo":
         return self._inner.track(channel, client_id, metadata)
 
@@ -221,8 +436,23 @@ class PresenceTracker:
     def untrack_all(self, client_id: str) -> List[str]:
         return self._inner.untrack_all(client_id)
 
-    def update(self, channel: str, client_id: str, metadata: Dict[str, str]) -> bool:
-        return self._inner.update(channel, client_id, metadata)
+    def update(self, *args: Any) -> bool:
+        """
+        Merge metadata keys into a client's presence without a leave/join.
+
+        ``update(client_id, partial_meta)`` updates the client in every
+        channel it is in; ``update(channel, client_id, partial_meta)`` in
+        one. Keys set to ``None`` are removed, other keys are set; values
+        may be any JSON-compatible value. Each update bumps the entry's
+        ``version``, and the keys that changed appear in the next diff's
+        ``updated`` list.
+
+        Returns:
+            False when the client is not present
+        """
+        if len(args) == 2:
+            return self._inner.update_client(*args)
+        return self._inner.update(*args)
 
     def touch(self, channel: str, client_id: str) -> bool:
         return self._inner.touch(channel, client_id)
@@ -257,9 +487,9 @@ class PresenceTracker:
     def track_json(
         self, channel: str, client_id: str, metadata: Any
     ) -> "PresenceInfo":
-        """Track with JSON-serializable metadata (converted to str dict)."""
-        str_meta = {str(k): str(v) for k, v in metadata.items()} if metadata else None
-        return self._inner.track(channel, client_id, str_meta)
+        """Track with JSON-serializable metadata (keys converted to str)."""
+        meta = {str(k): v for k, v in metadata.items()} if metadata else None
+        return self._inner.track(channel, client_id, meta)
 
     def list_as_dicts(self, channel: str) -> List[Dict[str, Any]]:
         """List presence info as plain dicts (useful for JSON serialization)."""
@@ -270,6 +500,8 @@ class PresenceTracker:
                 "metadata": info.metadata,
                 "joined_at": info.joined_at,
                 "last_seen": info.last_seen,
+                "last_update": info.last_update,
+                "version": info.version,
             }
             for info in self._inner.list(channel)
         ]
@@ -286,6 +518,14 @@ class PresenceTracker:
                 for info in diff.joins
             ],
             "leaves": diff.leaves,
+            "updated": [
+                {
+                    "client_id": update.client_id,
+                    "changes": update.changes,
+                    "version": update.version,
+                }
+                for update in diff.updated
+            ],
         }
 
     def __repr__(self) -> str:
@@ -317,25 +557,33 @@ class RealtimeBroadcast:
     def remove(self, name: str) -> bool:
         return self._inner.remove(name)
 
-    def subscribe(self, name: str) -> "BroadcastSubscriber":
-        return self._inner.subscribe(name)
+    def subscribe(
+        self, name: str, queue: Optional["ClientQueueConfig"] = None
+    ) -> "BroadcastSubscriber":
+        return self._inner.subscribe(name, queue)
 
     def send(
-        self, name: str, message: str, message_id: Optional[str] = None
+        self,
+        name: str,
+        message: str,
+        message_id: Optional[str] = None,
+        priority: Union["Priority", str, None] = None,
     ) -> int:
-        return self._inner.send(name, message, message_id)
+        return self._inner.send(name, message, message_id, priority)
 
     def send_json(
         self,
         name: str,
         data: Any,
         message_id: Optional[str] = None,
+        priority: Union["Priority", str, None] = None,
     ) -> int:
         """Send a JSON-serialized message to a broadcast channel."""
         return self._inner.send(
             name,
             json.dumps(data, separators=(",", ":")),
             message_id,
+            priority,
         )
 
     def send_many(self, names: List[str], message: str) -> Dict[str, int]:
@@ -363,22 +611,18 @@ class RealtimeBroadcast:
         poll_interval: float = 0.01,
     ) -> None:
         """
-        Subscribe and poll for messages asynchronously.
+        Subscribe and deliver messages until the channel is removed.
 
         Args:
             name: Broadcast channel name.
             callback: Called with each message.
-            poll_interval: Seconds between polls (default: 0.01).
+            poll_interval: Unused; messages are awaited, not polled.
         """
         rx = self._inner.subscribe(name)
-        while True:
-            msg = rx.try_recv()
-            if msg is not None:
-                result = callback(msg)
-                if asyncio.iscoroutine(result):
-                    await result
-            else:
-                await asyncio.sleep(poll_interval)
+        async for msg in rx:
+            result = callback(msg)
+            if asyncio.iscoroutine(result):
+                await result
 
     def __repr__(self) -> str:
         return repr(self._inner)
@@ -426,10 +670,11 @@ class HeartbeatMonitor:
 
         Args:
             on_ping: Called with client_id when a ping should be sent.
-            on_timeout: Called with client_id on timeout detection.
+            on_timeout: Called with client_id once the client has been
+                silent past its effective timeout (per-client overrides and
+                adaptive widening included).
             on_dead: Called with client_id when a client is evicted.
         """
-        interval = self.config.interval_secs
         while True:
             # Ping clients
             for client_id in self.clients_needing_ping():
@@ -453,7 +698,8 @@ class HeartbeatMonitor:
                     if asyncio.iscoroutine(result):
                         await result
 
-            await asyncio.sleep(interval)
+            # Clients with a shorter interval of their own set the pace
+            await asyncio.sleep(self.loop_interval_secs())
 
     def make_sse_event(
         self,
@@ -480,6 +726,34 @@ class HeartbeatMonitor:
         )
 
 
+def realtime_identity(req: Any, require_auth: bool = False) -> RealtimeIdentity:
+    """
+    Resolve who a realtime connection opened by ``req`` belongs to.
+
+    The client ID is ``{user_id}:{nonce}`` when the auth middleware
+    authenticated the request and ``anon:{nonce}`` otherwise, with a fresh
+    nonce per call. Call it once per connection and use its ``client_id``
+    instead of any ID the client sends.
+
+    Args:
+        req: The request opening the SSE stream or WebSocket.
+        require_auth: Raise ``Unauthorized`` (a 401) for a request no auth
+            middleware authenticated, before anything is subscribed.
+
+    Example::
+
+        @app.get("/events")
+        def events(req, res, ctx):
+            identity = realtime_identity(req, require_auth=True)
+            sub = hub.join_as("chat:general", identity)
+            ...
+    """
+    identity = _realtime_identity(req)
+    if require_auth and not identity.is_authenticated:
+        raise Unauthorized("Authentication required")
+    return identity
+
+
 class RealtimeHub:
     """
     Convenience wrapper that bundles all realtime components together.
@@ -520,6 +794,7 @@ class RealtimeHub:
         name: str,
         buffer_size: Optional[int] = None,
         broadcast_config: Optional[BroadcastConfig] = None,
+        max_subscribers: Optional[int] = None,
     ) -> None:
         """
         Create a channel with optional broadcast support.
@@ -528,8 +803,9 @@ class RealtimeHub:
             name: Channel name.
             buffer_size: Optional buffer size override.
             broadcast_config: If provided, also creates a broadcast channel.
+            max_subscribers: Optional cap on the channel's subscribers.
         """
-        self.channels.create_channel(name, buffer_size)
+        self.channels.create_channel(name, buffer_size, max_subscribers=max_subscribers)
         if broadcast_config is not None:
             self.broadcast.create(name, broadcast_config)
 
@@ -545,16 +821,40 @@ class RealtimeHub:
         Args:
             channel: Channel name.
             client_id: Unique client ID.
-            metadata: Optional presence metadata.
+            metadata: Optional presence metadata, also passed to the
+                subscribe hook.
 
         Returns:
             A Subscriber handle for receiving messages.
+
+        Raises:
+            SubscriptionError: The channel is full or the subscribe hook
+                refused; the client is not tracked.
         """
-        sub = self.channels.subscribe(channel, client_id)
+        sub = self.channels.subscribe(channel, client_id, metadata)
         self.presence.track(channel, client_id, metadata)
         self.heartbeat.register(client_id)
         return sub
 
+    def join_as(
+        self,
+        channel: str,
+        identity: RealtimeIdentity,
+        metadata: Optional[Dict[str, str]] = None,
+    ) -> "Subscriber":
+        """
+        Join a channel under a resolved identity (see ``realtime_identity``).
+
+        The client is tracked as ``identity.client_id``; an authenticated
+        identity also adds ``user_id`` to the presence and subscribe hook
+        metadata, replacing any the caller passed.
+        """
+        metadata = dict(metadata or {})
+        metadata.pop("user_id", None)
+        if identity.user_id is not None:
+            metadata["user_id"] = identity.user_id
+        return self.join(channel, identity.client_id, metadata)
+
     def leave(self, channel: str, client_id: str) -> None:
         """
         Leave a channel: unsubscribe + untrack presence + unregister heartbeat.
@@ -576,13 +876,17 @@ class RealtimeHub:
         self.heartbeat.unregister(client_id)
         return channels
 
-    def publish(self, channel: str, message: str) -> int:
+    def publish(
+        self, channel: str, message: str, priority: Union["Priority", str, None] = None
+    ) -> int:
         """Publish a message to a channel."""
-        return self.channels.publish(channel, message)
+        return self.channels.publish(channel, message, priority=priority)
 
-    def publish_json(self, channel: str, data: Any) -> int:
+    def publish_json(
+        self, channel: str, data: Any, priority: Union["Priority", str, None] = None
+    ) -> int:
         """Publish a JSON-serialized message to a channel."""
-        return self.channels.publish_json(channel, data)
+        return self.channels.publish_json(channel, data, priority=priority)
 
     def get_presence(self, channel: str) -> List["PresenceInfo"]:
         """Get presence info for a channel."""
@@ -608,20 +912,36 @@ __all__ = [
     "ChannelStats",
     "Subscriber",
     "TopicMatcher",
+    "ChannelClosed",
+    "SubscriptionError",
+    "PublishError",
+    "MessageSchema",
     # Presence
     "PresenceTracker",
     "PresenceInfo",
     "PresenceDiff",
+    "PresenceUpdate",
     # Broadcast
     "RealtimeBroadcast",
     "BroadcastConfig",
     "BroadcastStats",
     "BroadcastSubscriber",
     "BackpressurePolicy",
+    # Per-client queues
+    "ClientQueueConfig",
+    "ClientQueueStats",
+    "OverflowPolicy",
+    "Priority",
+    # Long polling
+    "realtime_poll_stats",
+    # Client identity
+    "RealtimeIdentity",
+    "realtime_identity",
     # Heartbeat
     "HeartbeatMonitor",
     "HeartbeatConfig",
     "HeartbeatStats",
+    "HeartbeatClientStats",
     # Hub
     "RealtimeHub",
 ]
@@ -2,13 +2,65 @@ from __future__ import annotations
 
 import functools
 import inspect
-from typing import Any, Callable, Dict, List, Optional, Tuple
+from typing import Any, Callable, Dict, List, Optional, Tuple, Union
 
+from hypern._hypern import RetryPolicy
 from hypern._hypern import Route as RustRoute
 from hypern._hypern import Router as RustRouter
 
 
-class Router:
+MethodSpec = Union[str, List[str]]
+
+
+def _method_decorator(method: str, doc: str) -> Callable:
+    """Build a ``get``/``post``/... decorator bound to one method spec."""
+    def register(self, path: str, middleware: Optional[List[Callable]] = None, **options):
+        return self.api_route(path, method, middleware, **options)
+    register.__name__ = method.lower() if method != "*" else "all"
+    register.__doc__ = doc
+    return register
+
+
+class _MethodRoutes:
+    """Route decorators shared by Router and RouteGroup."""
+    
+    def api_route(
+        self,
+        path: str,
+        methods: MethodSpec,
+        middleware: Optional[List[Callable]] = None,
+        **options
+    ):
+        """
+        Register a route for a group of methods, or ``"*"`` for any method.
+        
+        The route is stored once for the whole group. A ``"*"`` route
+        answers every method without an exact route on the same path,
+        including non-standard ones such as ``PROPFIND``.
+        
+        Example:
+            @api.api_route("/items", ["GET", "POST"])
+            def items(req, res, ctx):
+                res.json({"method": req.method})
+        """
+        def decorator(handler: Callable) -> Callable:
+            self._add_route(methods, path, handler, middleware, **options)
+            return handler
+        return decorator
+    
+    get = _method_decorator("GET", "Register a GET route.")
+    post = _method_decorator("POST", "Register a POST route.")
+    put = _method_decorator("PUT", "Register a PUT route.")
+    delete = _method_decorator("DELETE", "Register a DELETE route.")
+    patch = _method_decorator("PATCH", "Register a PATCH route.")
+    options = _method_decorator("OPTIONS", "Register an OPTIONS route.")
+    head = _method_decorator("HEAD", "Register a HEAD route.")
+    all = _method_decorator(
+        "*", "Register a route for any method not claimed by an exact route on the path."
+    )
+
+
+class Router(_MethodRoutes):
     """
     Router class.
     
@@ -34,14 +86,14 @@ class Router:
         app.use("/api/v1", api)
     """
     
-    def __init__(self, prefix: str = ""):
+    def __init__(self, prefix: str = "", strict_routes: bool = False):
         self.prefix = prefix.rstrip("/")
         self._routes: List[Tuple[str, str, Callable, Dict[str, Any]]] = []
         self._middleware: List[Callable] = []
         self._before_handlers: List[Callable] = []
         self._after_handlers: List[Callable] = []
         self._error_handlers: Dict[type, Callable] = {}
-        self._rust_router = RustRouter(path=prefix)
+        self._rust_router = RustRouter(path=prefix, strict_routes=strict_routes)
     
     def _normalize_path(self, path: str) -> str:
         """Normalize path by ensuring it starts with /."""
@@ -67,9 +119,34 @@ class Router:
         """
         return RouteBuilder(self, path)
     
+    def group(
+        self,
+        prefix: str = "",
+        middlewares: Optional[List[Callable]] = None,
+        tags: Optional[List[str]] = None,
+        host: Optional[str] = None,
+    ) -> 'RouteGroup':
+        """
+        Create a group of routes sharing a prefix, middleware and metadata.
+        
+        Args:
+            prefix: Path prefix for every route in the group
+            middlewares: Middleware run before each route's own middleware
+            tags: OpenAPI tags recorded for every route in the group
+            host: Only match requests whose Host header is this host
+        
+        Example:
+            admin = api.group("/admin", middlewares=[require_admin], tags=["admin"])
+            
+            @admin.get("/users/:id")
+            def get_user(req, res, ctx):
+                res.json({"id": req.param("id")})
+        """
+        return RouteGroup(self, prefix, middlewares, tags, host)
+    
     def _add_route(
         self,
-        method: str,
+        method: MethodSpec,
         path: str,
         handler: Callable,
         middleware: Optional[List[Callable]] = None,
@@ -84,17 +161,38 @@ class Router:
         if middleware:
             wrapped_handler = self._wrap_with_middleware(handler, middleware)
         
-        # Store route info
-        self._routes.append((method.upper(), converted_path, wrapped_handler, options))
-        
         # Add to Rust router
         route = RustRoute(
             path=converted_path,
             function=wrapped_handler,
-            method=method.upper(),
-            doc=handler.__doc__
+            method=method,
+            doc=handler.__doc__,
+            max_json_bytes=options.get("max_json_bytes"),
+            host=options.get("host"),
+            tags=options.get("tags"),
+            timeout=options.get("timeout"),
+            max_body_size=options.get("max_body_size"),
+            cache_ttl=options.get("cache_ttl"),
+            log=options.get("log", True),
+            metadata=options.get("metadata"),
+            coalesce=options.get("coalesce", False),
+            response_fields=options.get("response_fields"),
+            retry=options.get("retry"),
+            retry_unsafe=options.get("retry_unsafe", False),
+            auth=options.get("auth"),
+            feature_flag=options.get("feature_flag"),
+            etag_provider=options.get("etag_provider"),
+            memoize_ttl_secs=options.get("memoize_ttl_secs"),
+            memoize_vary=options.get("memoize_vary"),
         )
         self._rust_router.add_route(route)
+        
+        # Store route info under the canonical method spec ("GET", "GET,POST"
+        # or "*"); the app applies route middleware when mounting
+        route_options = dict(options)
+        if middleware:
+            route_options["middleware"] = list(middleware)
+        self._routes.append((route.method, converted_path, handler, route_options))
     
     def _wrap_with_middleware(self, handler: Callable, middleware: List[Callable]) -> Callable:
         """Wrap a handler with middleware chain."""
@@ -121,63 +219,6 @@ class Router:
         
         return wrapped
     
-    def get(self, path: str, middleware: Optional[List[Callable]] = None, **options):
-        """Register a GET route."""
-        def decorator(handler: Callable) -> Callable:
-            self._add_route("GET", path, handler, middleware, **options)
-            return handler
-        return decorator
-    
-    def post(self, path: str, middleware: Optional[List[Callable]] = None, **options):
-        """Register a POST route."""
-        def decorator(handler: Callable) -> Callable:
-            self._add_route("POST", path, handler, middleware, **options)
-            return handler
-        return decorator
-    
-    def put(self, path: str, middleware: Optional[List[Callable]] = None, **options):
-        """Register a PUT route."""
-        def decorator(handler: Callable) -> Callable:
-            self._add_route("PUT", path, handler, middleware, **options)
-            return handler
-        return decorator
-    
-    def delete(self, path: str, middleware: Optional[List[Callable]] = None, **options):
-        """Register a DELETE route."""
-        def decorator(handler: Callable) -> Callable:
-            self._add_route("DELETE", path, handler, middleware, **options)
-            return handler
-        return decorator
-    
-    def patch(self, path: str, middleware: Optional[List[Callable]] = None, **options):
-        """Register a PATCH route."""
-        def decorator(handler: Callable) -> Callable:
-            self._add_route("PATCH", path, handler, middleware, **options)
-            return handler
-        return decorator
-    
-    def options(self, path: str, middleware: Optional[List[Callable]] = None, **options):
-        """Register an OPTIONS route."""
-        def decorator(handler: Callable) -> Callable:
-            self._add_route("OPTIONS", path, handler, middleware, **options)
-            return handler
-        return decorator
-    
-    def head(self, path: str, middleware: Optional[List[Callable]] = None, **options):
-        """Register a HEAD route."""
-        def decorator(handler: Callable) -> Callable:
-            self._add_route("HEAD", path, handler, middleware, **options)
-            return handler
-        return decorator
-    
-    def all(self, path: str, middleware: Optional[List[Callable]] = None, **options):
-        """Register a route for all HTTP methods."""
-        def decorator(handler: Callable) -> Callable:
-            for method in ["GET", "POST", "PUT", "DELETE", "PATCH", "OPTIONS", "HEAD"]:
-                self._add_route(method, path, handler, middleware, **options)
-            return handler
-        return decorator
-    
     def use(self, middleware: Callable) -> 'Router':
         """
         Add middleware to this router.
@@ -253,6 +294,97 @@ class Router:
         """Get the underlying Rust router."""
         return self._rust_router
 
+    def check(self) -> Dict[str, Any]:
+        """
+        Report conflicting routes in this router.
+
+        Returns:
+            ``{"ok": bool, "errors": [...], "warnings": [...]}``; see
+            ``hypern._hypern.Router.check``
+        """
+        return self._rust_router.check()
+
+
+class RouteGroup(_MethodRoutes):
+    """
+    Routes registered under a shared prefix, middleware, tags and host.
+    
+    Groups are created with ``Router.group()`` and nest: a child group
+    appends its prefix, middleware and tags to its parent's, and a child
+    ``host`` replaces the parent's. Group middleware runs before the
+    route's own middleware.
+    
+    Example:
+        v1 = api.group("/v1", tags=["v1"])
+        admin = v1.group("/admin", middlewares=[require_admin])
+        
+        @admin.get("/users")      # GET /v1/admin/users
+        def list_users(req, res, ctx):
+            ...
+    """
+    
+    def __init__(
+        self,
+        router: Router,
+        prefix: str = "",
+        middlewares: Optional[List[Callable]] = None,
+        tags: Optional[List[str]] = None,
+        host: Optional[str] = None,
+    ):
+        self.router = router
+        self.prefix = router._normalize_path(prefix).rstrip("/") if prefix else ""
+        self.middlewares: List[Callable] = list(middlewares or [])
+        self.tags: List[str] = list(tags or [])
+        self.host = host
+    
+    def group(
+        self,
+        prefix: str = "",
+        middlewares: Optional[List[Callable]] = None,
+        tags: Optional[List[str]] = None,
+        host: Optional[str] = None,
+    ) -> 'RouteGroup':
+        """Create a nested group inside this one."""
+        child = RouteGroup(self.router, prefix, middlewares, tags, host or self.host)
+        child.prefix = self.prefix + child.prefix
+        child.middlewares = self.middlewares + child.middlewares
+        child.tags = self.tags + [t for t in child.tags if t not in self.tags]
+        return child
+    
+    def route(self, path: str) -> 'RouteBuilder':
+        """Create a route builder for chaining HTTP methods within the group."""
+        return RouteBuilder(self, path)
+    
+    def add_route(
+        self,
+        method: MethodSpec,
+        path: str,
+        handler: Callable,
+        middleware: Optional[List[Callable]] = None,
+        **options
+    ):
+        """Register a handler under the group's prefix, middleware and metadata."""
+        self._add_route(method, path, handler, middleware, **options)
+    
+    def _add_route(
+        self,
+        method: MethodSpec,
+        path: str,
+        handler: Callable,
+        middleware: Optional[List[Callable]] = None,
+        **options
+    ):
+        path = self.router._normalize_path(path)
+        full_path = self.prefix + path if path != "/" or not self.prefix else self.prefix
+        
+        tags = options.pop("tags", None) or []
+        options["tags"] = self.tags + [t for t in tags if t not in self.tags]
+        if options.get("host") is None and self.host is not None:
+            options["host"] = self.host
+        
+        chain = self.middlewares + list(middleware or [])
+        self.router._add_route(method, full_path, handler, chain or None, **options)
+
 
 class RouteBuilder:
     """
@@ -266,7 +398,7 @@ class RouteBuilder:
             .delete(delete_user)
     """
     
-    def __init__(self, router: Router, path: str):
+    def __init__(self, router: Union[Router, RouteGroup], path: str):
         self.router = router
         self.path = path
     
@@ -306,13 +438,14 @@ class RouteBuilder:
         return self
     
     def all(self, handler: Callable, **options) -> 'RouteBuilder':
-        """Add handler for all methods."""
-        for method in ["GET", "POST", "PUT", "DELETE", "PATCH", "OPTIONS", "HEAD"]:
-            self.router._add_route(method, self.path, handler, **options)
+        """Add handler for any method without its own handler."""
+        self.router._add_route("*", self.path, handler, **options)
         return self
 
 
 __all__ = [
     'Router',
+    'RouteGroup',
     'RouteBuilder',
+    'RetryPolicy',
 ]
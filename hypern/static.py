@@ -0,0 +1,84 @@
+"""
+Static file bundles — assets served from memory instead of the filesystem.
+
+A bundle is a dict of path (relative to the handler's prefix) to bytes, as
+taken by ``StaticFileHandler.from_bundle`` and the ``overlay`` argument of
+``StaticFileHandler``. Build one from a directory at startup, or embed a
+whole UI in a Python package as a zip archive, optionally base64-encoded.
+
+Example::
+
+    from hypern.static import StaticFileHandler, load_base64_bundle, load_bundle
+
+    # Assets shipped inside the package
+    admin = StaticFileHandler.from_bundle(load_bundle("admin/dist"), prefix="/admin")
+
+    # A UI embedded as a string generated with dump_base64_bundle()
+    from myapp._ui import UI
+    ui = StaticFileHandler.from_bundle(load_base64_bundle(UI), prefix="/ui")
+
+    # Files on disk, with a config.js generated at startup taking precedence
+    site = StaticFileHandler(
+        "public",
+        prefix="/",
+        overlay={"config.js": f"window.API = {api_url!r};"},
+    )
+"""
+
+from __future__ import annotations
+
+import base64
+import io
+import os
+import zipfile
+from typing import Dict, Union
+
+from hypern._hypern import StaticFileHandler
+
+__all__ = [
+    "StaticFileHandler",
+    "load_bundle",
+    "load_zip_bundle",
+    "load_base64_bundle",
+    "dump_base64_bundle",
+]
+
+
+def load_bundle(directory: Union[str, os.PathLike]) -> Dict[str, bytes]:
+    """Read every file under ``directory`` into a bundle, keyed by its
+    ``/``-separated path relative to the directory."""
+    root = os.fspath(directory)
+    if not os.path.isdir(root):
+        raise ValueError(f"Directory does not exist: {root}")
+    files = {}
+    for dirpath, _, filenames in os.walk(root):
+        for filename in filenames:
+            path = os.path.join(dirpath, filename)
+            key = os.path.relpath(path, root).replace(os.sep, "/")
+            with open(path, "rb") as f:
+                files[key] = f.read()
+    return files
+
+
+def load_zip_bundle(archive: Union[bytes, str, os.PathLike]) -> Dict[str, bytes]:
+    """Read the files of a zip archive, given as bytes or a path, into a
+    bundle; directory entries are skipped."""
+    source = io.BytesIO(archive) if isinstance(archive, bytes) else os.fspath(archive)
+    with zipfile.ZipFile(source) as zf:
+        return {info.filename: zf.read(info) for info in zf.infolist() if not info.is_dir()}
+
+
+def load_base64_bundle(text: str) -> Dict[str, bytes]:
+    """Read a bundle from the base64-encoded zip archive of
+    ``dump_base64_bundle``."""
+    return load_zip_bundle(base64.b64decode(text))
+
+
+def dump_base64_bundle(directory: Union[str, os.PathLike]) -> str:
+    """Compress ``directory`` into a base64-encoded zip archive, for
+    embedding in a Python module and loading with ``load_base64_bundle``."""
+    buffer = io.BytesIO()
+    with zipfile.ZipFile(buffer, "w", zipfile.ZIP_DEFLATED) as zf:
+        for key, data in sorted(load_bundle(directory).items()):
+            zf.writestr(key, data)
+    return base64.b64encode(buffer.getvalue()).decode("ascii")
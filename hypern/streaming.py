@@ -0,0 +1,80 @@
+"""
+Streaming responses from generators.
+
+A handler can return a generator or async generator instead of writing to
+``res``; the body is streamed from it chunk by chunk (``bytes`` or ``str``).
+Wrap the generator with ``stream()`` to set the content type::
+
+    from hypern import stream
+
+    @app.get("/export.csv")
+    def export(req, res, ctx):
+        def rows():
+            yield "id,name\\n"
+            for user in users:
+                yield f"{user.id},{user.name}\\n"
+        return stream(rows(), content_type="text/csv")
+
+If the generator raises after the first chunk, the status line has already
+been sent: the error is logged and the connection is cut so the client sees
+a truncated body rather than a complete-looking one.
+"""
+
+from __future__ import annotations
+
+import inspect
+from typing import Any, AsyncIterable, Iterable, Optional, Union
+
+__all__ = ["Stream", "stream", "is_stream_result", "send_stream"]
+
+
+class Stream:
+    """A generator paired with the response metadata to stream it with."""
+
+    __slots__ = ("source", "content_type", "buffer_size")
+
+    def __init__(
+        self,
+        source: Union[Iterable[Any], AsyncIterable[Any]],
+        content_type: Optional[str] = None,
+        buffer_size: int = 16,
+    ):
+        self.source = source
+        self.content_type = content_type
+        self.buffer_size = buffer_size
+
+    def __repr__(self) -> str:
+        return f"Stream({self.source!r}, content_type={self.content_type!r})"
+
+
+def stream(
+    source: Union[Iterable[Any], AsyncIterable[Any]],
+    content_type: str = "application/octet-stream",
+    buffer_size: int = 16,
+) -> Stream:
+    """
+    Mark a generator to be returned from a handler as a streamed body.
+
+    Args:
+        source: Generator, async generator or iterable yielding bytes/str
+        content_type: Content-Type of the response
+        buffer_size: Chunks buffered ahead of the client
+    """
+    return Stream(source, content_type, buffer_size)
+
+
+def is_stream_result(value: Any) -> bool:
+    """Whether a handler's return value should be streamed."""
+    return (
+        isinstance(value, Stream)
+        or inspect.isgenerator(value)
+        or inspect.isasyncgen(value)
+    )
+
+
+def send_stream(res: Any, value: Any) -> None:
+    """Stream a handler's generator return value through ``res``."""
+    if isinstance(value, Stream):
+        res.stream(value.source, content_type=value.content_type, buffer_size=value.buffer_size)
+    else:
+        res.stream(value)
@@ -12,6 +12,8 @@ Categories
 **Crypto / IDs**   — SHA-256, HMAC-SHA-256, Base64, UUIDs, random tokens.
 **Time helpers**   — timestamps, ISO formatting, relative time.
 **Hashing**        — xxHash3-64 fast non-cryptographic hashing.
+**JSON**           — the serializer behind ``res.json()``.
+**CPU**            — container-aware CPU counts and worker affinity layouts.
 
 Example::
 
@@ -66,6 +68,7 @@ from hypern._hypern import (
     uuid_v7,
     fast_hash,
     fast_hash_bytes,
+    json_dumps,
     # ── Time helpers ───────────────────────────────────────────────────────
     now_ms,
     now_sec,
@@ -76,6 +79,12 @@ from hypern._hypern import (
     elapsed_ms,
     ms_to_sec,
     sec_to_ms,
+    parse_duration,
+    parse_size,
+    # ── CPU ────────────────────────────────────────────────────────────────
+    cpu_count,
+    cgroup_cpu_quota,
+    cpu_affinity_layout,
 )
 
 __all__ = [
@@ -113,6 +122,7 @@ __all__ = [
     "uuid_v7",
     "fast_hash",
     "fast_hash_bytes",
+    "json_dumps",
     # Time
     "now_ms",
     "now_sec",
@@ -123,4 +133,10 @@ __all__ = [
     "elapsed_ms",
     "ms_to_sec",
     "sec_to_ms",
+    "parse_duration",
+    "parse_size",
+    # CPU
+    "cpu_count",
+    "cgroup_cpu_quota",
+    "cpu_affinity_layout",
 ]
@@ -35,7 +35,9 @@ import asyncio
 import enum
 import json
 import uuid
-from typing import Any, Callable, Dict, List, Optional, Set, Union
+from typing import Any, Callable, Dict, List, Optional, Set, Tuple, Union
+
+from hypern._hypern import WsInboundGuard, WsLimits, WsMessageType
 
 
 class WebSocketState(enum.Enum):
@@ -99,6 +101,9 @@ class WebSocket:
 
     Args:
         id: Unique connection identifier (auto-generated if omitted).
+        limits: Inbound limits of the route (default: ``WsLimits()``).
+        extensions: The ``Sec-WebSocket-Extensions`` response sent with the
+            upgrade, from ``limits.negotiate()``.
 
     Example:
         @app.ws("/echo")
@@ -109,7 +114,12 @@ class WebSocket:
                 await ws.send_text(f"echo: {msg}")
     """
 
-    def __init__(self, id: Optional[str] = None):
+    def __init__(
+        self,
+        id: Optional[str] = None,
+        limits: Optional[WsLimits] = None,
+        extensions: Optional[str] = None,
+    ):
         self.id: str = id or uuid.uuid4().hex
         self.state: WebSocketState = WebSocketState.CONNECTING
         self.path: str = ""
@@ -118,12 +128,15 @@ class WebSocket:
         self.client_host: Optional[str] = None
         self.client_port: Optional[int] = None
         self.extra: Dict[str, Any] = {}
+        # Sent back in the upgrade response
+        self.extensions: Optional[str] = extensions
 
         # Internal queues
         self._recv_queue: asyncio.Queue[WebSocketMessage] = asyncio.Queue()
         self._send_queue: asyncio.Queue[Union[WebSocketMessage, None]] = asyncio.Queue()
         self._close_code: int = 1000
         self._close_reason: str = ""
+        self._guard = WsInboundGuard(limits or WsLimits(), extensions)
 
     async def accept(
         self,
@@ -236,6 +249,38 @@ class WebSocket:
         """
         self._recv_queue.put_nowait(WebSocketMessage(msg_type, data))
 
+    def feed_frame(
+        self, opcode: int, payload: bytes, fin: bool = True, rsv1: bool = False
+    ) -> Optional[Tuple[int, str]]:
+        """
+        Push a raw frame from the transport layer through the route's limits.
+
+        Complete text and binary messages are queued for ``receive()``, and a
+        close frame from the client disconnects. Returns ``(code, reason)``
+        for the close frame the transport must send when the frame broke a
+        limit; the handler then sees ``WebSocketDisconnect`` with that code.
+        """
+        message = self._guard.feed(opcode, payload, fin, rsv1)
+        if message is None:
+            return None
+        if message.msg_type == WsMessageType.Text:
+            self.feed_message("text", bytes(message.data).decode())
+        elif message.msg_type == WsMessageType.Binary:
+            self.feed_message("bytes", bytes(message.data))
+        elif message.msg_type == WsMessageType.Close:
+            data = bytes(message.data)
+            code = int.from_bytes(data[:2], "big") if data else 1005
+            reason = data[2:].decode()
+            self.feed_disconnect(code, reason)
+            if self._guard.close_code is not None:
+                return code, reason
+        return None
+
+    @property
+    def throttle_delay(self) -> float:
+        """Seconds the transport should wait before reading the next frame."""
+        return self._guard.delay
+
     def feed_disconnect(self, code: int = 1000, reason: str = "") -> None:
         """Signal a disconnect from the transport side."""
         self._close_code = code
@@ -342,12 +387,36 @@ class WebSocketRoom:
 
 
 class WebSocketRoute:
-    """Represents a registered WebSocket route."""
+    """
+    Represents a registered WebSocket route.
+
+    The inbound limit options (``max_message_size``, ``max_decompressed_size``,
+    ``max_fragments``, ``permessage_deflate``, ``server_max_window_bits``,
+    ``max_messages_per_second``, ``rate_limit_action``) are validated at
+    registration and collected in ``limits``.
+    """
+
+    LIMIT_OPTIONS = (
+        "max_message_size",
+        "max_decompressed_size",
+        "max_fragments",
+        "permessage_deflate",
+        "server_max_window_bits",
+        "max_messages_per_second",
+        "rate_limit_action",
+    )
 
     def __init__(self, path: str, handler: Callable, **options):
         self.path = path
         self.handler = handler
         self.options = options
+        self.limits = WsLimits(
+            **{name: options[name] for name in self.LIMIT_OPTIONS if name in options}
+        )
+
+    def connect(self, id: Optional[str] = None, extensions_offer: Optional[str] = None) -> WebSocket:
+        """Create the WebSocket for a new connection, negotiating compression."""
+        return WebSocket(id, self.limits, self.limits.negotiate(extensions_offer))
 
     def __repr__(self) -> str:
         return f"<WebSocketRoute path={self.path!r}>"
@@ -404,4 +473,5 @@ __all__ = [
     "WebSocketRoom",
     "WebSocketRoute",
     "WebSocketRouter",
+    "WsLimits",
 ]
@@ -0,0 +1,525 @@
+//! Per-process in-memory key-value cache.
+//!
+//! `MemoryCache` keeps entries in a sharded `DashMap`, so readers and writers
+//! of different keys rarely contend. Recency is tracked with a per-entry tick
+//! bumped on every hit; the LRU index is only locked when a new key is
+//! inserted or removed, and entries touched since they were indexed are
+//! re-queued at eviction time instead of on every read.
+//!
+//! Values are plain data (str, bytes, int, float, bool) so the cache never
+//! holds Python objects and can be shared across threads and interpreters.
+//! Nothing is shared between worker processes: each process has its own copy.
+
+use dashmap::mapref::entry::Entry as MapEntry;
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyBytes, PyDict, PyFloat, PyInt, PyString};
+use pyo3::IntoPyObjectExt;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// A value that can be stored in the cache
+#[derive(Debug, Clone)]
+enum CacheValue {
+    Str(String),
+    Bytes(Vec<u8>),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+impl CacheValue {
+    fn from_py(value: &Bound<'_, PyAny>) -> PyResult<Self> {
+        if value.is_instance_of::<PyBool>() {
+            Ok(CacheValue::Bool(value.extract()?))
+        } else if value.is_instance_of::<PyInt>() {
+            Ok(CacheValue::Int(value.extract()?))
+        } else if value.is_instance_of::<PyFloat>() {
+            Ok(CacheValue::Float(value.extract()?))
+        } else if value.is_instance_of::<PyString>() {
+            Ok(CacheValue::Str(value.extract()?))
+        } else if value.is_instance_of::<PyBytes>() {
+            Ok(CacheValue::Bytes(value.extract()?))
+        } else {
+            Err(PyTypeError::new_err(format!(
+                "MemoryCache values must be str, bytes, int, float or bool, not {}",
+                value.get_type().name()?
+            )))
+        }
+    }
+
+    fn to_py(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        match self {
+            CacheValue::Str(s) => s.into_py_any(py),
+            CacheValue::Bytes(b) => PyBytes::new(py, b).into_py_any(py),
+            CacheValue::Int(i) => i.into_py_any(py),
+            CacheValue::Float(f) => f.into_py_any(py),
+            CacheValue::Bool(b) => b.into_py_any(py),
+        }
+    }
+}
+
+struct Entry {
+    value: CacheValue,
+    expires_at: Option<Instant>,
+    /// Tick of the last read or write
+    last_used: AtomicU64,
+    /// Tick under which the entry is filed in the LRU index
+    slot: AtomicU64,
+}
+
+impl Entry {
+    fn new(value: CacheValue, expires_at: Option<Instant>, tick: u64) -> Self {
+        Self {
+            value,
+            expires_at,
+            last_used: AtomicU64::new(tick),
+            slot: AtomicU64::new(tick),
+        }
+    }
+
+    fn expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|at| now >= at)
+    }
+}
+
+/// Reasons `incr`/`decr` can fail, raised once the GIL is held again
+enum IncrError {
+    NotInteger,
+    Overflow,
+}
+
+/// Thread-safe LRU cache with per-entry TTL, local to the current process.
+///
+/// Each worker process has its own independent cache; use Redis for data that
+/// must be shared between workers or survive a restart.
+#[pyclass]
+pub struct MemoryCache {
+    map: DashMap<String, Entry>,
+    /// LRU order: slot tick -> key, oldest first
+    index: Mutex<BTreeMap<u64, String>>,
+    clock: AtomicU64,
+    max_entries: usize,
+    default_ttl: Option<Duration>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+    expirations: AtomicU64,
+}
+
+impl MemoryCache {
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    fn ttl_or_default(&self, ttl: Option<f64>) -> PyResult<Option<Duration>> {
+        match ttl {
+            Some(secs) => parse_ttl(secs).map(Some),
+            None => Ok(self.default_ttl),
+        }
+    }
+
+    fn lookup(&self, key: &str) -> Option<CacheValue> {
+        let now = Instant::now();
+        let expired = match self.map.get(key) {
+            Some(entry) if !entry.expired(now) => {
+                entry.last_used.store(self.tick(), Ordering::Relaxed);
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Some(entry.value.clone());
+            }
+            Some(_) => true,
+            None => false,
+        };
+        if expired {
+            self.remove_expired(key);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        None
+    }
+
+    fn remove_expired(&self, key: &str) {
+        let now = Instant::now();
+        if let Some((_, entry)) = self.map.remove_if(key, |_, entry| entry.expired(now)) {
+            self.expirations.fetch_add(1, Ordering::Relaxed);
+            self.index
+                .lock()
+                .remove(&entry.slot.load(Ordering::Relaxed));
+        }
+    }
+
+    /// Insert or overwrite `key`
+    fn store(&self, key: String, value: CacheValue, ttl: Option<Duration>) {
+        let tick = self.tick();
+        let expires_at = ttl.map(|ttl| Instant::now() + ttl);
+        match self.map.entry(key.clone()) {
+            MapEntry::Occupied(mut occupied) => {
+                let entry = occupied.get_mut();
+                entry.value = value;
+                entry.expires_at = expires_at;
+                entry.last_used.store(tick, Ordering::Relaxed);
+                return;
+            }
+            MapEntry::Vacant(vacant) => {
+                vacant.insert(Entry::new(value, expires_at, tick));
+            }
+        }
+        self.index_new(tick, key);
+    }
+
+    /// Insert `key` unless a live entry exists; returns the value now cached
+    fn store_absent(&self, key: String, value: CacheValue, ttl: Option<Duration>) -> CacheValue {
+        let tick = self.tick();
+        let now = Instant::now();
+        let expires_at = ttl.map(|ttl| now + ttl);
+        match self.map.entry(key.clone()) {
+            MapEntry::Occupied(mut occupied) => {
+                let entry = occupied.get_mut();
+                if !entry.expired(now) {
+                    entry.last_used.store(tick, Ordering::Relaxed);
+                    return entry.value.clone();
+                }
+                entry.value = value.clone();
+                entry.expires_at = expires_at;
+                entry.last_used.store(tick, Ordering::Relaxed);
+                return value;
+            }
+            MapEntry::Vacant(vacant) => {
+                vacant.insert(Entry::new(value.clone(), expires_at, tick));
+            }
+        }
+        self.index_new(tick, key);
+        value
+    }
+
+    fn add(&self, key: String, delta: i64, ttl: Option<Duration>) -> Result<i64, IncrError> {
+        let tick = self.tick();
+        let now = Instant::now();
+        let expires_at = ttl.map(|ttl| now + ttl);
+        match self.map.entry(key.clone()) {
+            MapEntry::Occupied(mut occupied) => {
+                let entry = occupied.get_mut();
+                let total = if entry.expired(now) {
+                    entry.expires_at = expires_at;
+                    delta
+                } else {
+                    match entry.value {
+                        CacheValue::Int(current) => {
+                            current.checked_add(delta).ok_or(IncrError::Overflow)?
+                        }
+                        _ => return Err(IncrError::NotInteger),
+                    }
+                };
+                entry.value = CacheValue::Int(total);
+                entry.last_used.store(tick, Ordering::Relaxed);
+                return Ok(total);
+            }
+            MapEntry::Vacant(vacant) => {
+                vacant.insert(Entry::new(CacheValue::Int(delta), expires_at, tick));
+            }
+        }
+        self.index_new(tick, key);
+        Ok(delta)
+    }
+
+    fn remove(&self, key: &str) -> bool {
+        let Some((_, entry)) = self.map.remove(key) else {
+            return false;
+        };
+        self.index
+            .lock()
+            .remove(&entry.slot.load(Ordering::Relaxed));
+        !entry.expired(Instant::now())
+    }
+
+    /// File a newly inserted key in the LRU index and evict down to capacity
+    fn index_new(&self, tick: u64, key: String) {
+        let mut index = self.index.lock();
+        index.insert(tick, key);
+        while self.map.len() > self.max_entries {
+            let Some((slot, key)) = index.pop_first() else {
+                break;
+            };
+            let now = Instant::now();
+            let expired = match self.map.get(&key) {
+                // Deleted or re-inserted since this slot was filed
+                None => continue,
+                Some(entry) if entry.slot.load(Ordering::Relaxed) != slot => continue,
+                Some(entry) => {
+                    let last_used = entry.last_used.load(Ordering::Relaxed);
+                    let expired = entry.expired(now);
+                    if last_used > slot && !expired {
+                        // Touched since it was filed: move it to the back
+                        entry.slot.store(last_used, Ordering::Relaxed);
+                        index.insert(last_used, key);
+                        continue;
+                    }
+                    expired
+                }
+            };
+            let removed = self
+                .map
+                .remove_if(&key, |_, entry| entry.slot.load(Ordering::Relaxed) == slot);
+            if removed.is_some() {
+                let counter = if expired {
+                    &self.expirations
+                } else {
+                    &self.evictions
+                };
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+fn parse_ttl(secs: f64) -> PyResult<Duration> {
+    if secs.is_finite() && secs > 0.0 {
+        Ok(Duration::from_secs_f64(secs))
+    } else {
+        Err(PyValueError::new_err(
+            "ttl must be a positive number of seconds",
+        ))
+    }
+}
+
+fn incr_error(key: &str, error: IncrError) -> PyErr {
+    match error {
+        IncrError::NotInteger => {
+            PyTypeError::new_err(format!("value of '{}' is not an integer", key))
+        }
+        IncrError::Overflow => {
+            PyValueError::new_err(format!("incrementing '{}' would overflow", key))
+        }
+    }
+}
+
+#[pymethods]
+impl MemoryCache {
+    /// Create an in-memory cache.
+    ///
+    /// Args:
+    ///     max_entries: Number of entries kept before the least recently used
+    ///         one is evicted (default: 10000)
+    ///     ttl: Default time-to-live in seconds for entries stored without
+    ///         their own ``ttl`` (default: no expiry)
+    #[new]
+    #[pyo3(signature = (max_entries = 10000, ttl = None))]
+    pub fn new(max_entries: usize, ttl: Option<f64>) -> PyResult<Self> {
+        if max_entries == 0 {
+            return Err(PyValueError::new_err("max_entries must be at least 1"));
+        }
+        Ok(Self {
+            map: DashMap::new(),
+            index: Mutex::new(BTreeMap::new()),
+            clock: AtomicU64::new(0),
+            max_entries,
+            default_ttl: ttl.map(parse_ttl).transpose()?,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+            expirations: AtomicU64::new(0),
+        })
+    }
+
+    /// Return the value stored under `key`, or `default` if it is missing or expired.
+    #[pyo3(signature = (key, default = None))]
+    pub fn get(
+        &self,
+        py: Python<'_>,
+        key: &str,
+        default: Option<Py<PyAny>>,
+    ) -> PyResult<Option<Py<PyAny>>> {
+        match py.detach(|| self.lookup(key)) {
+            Some(value) => value.to_py(py).map(Some),
+            None => Ok(default),
+        }
+    }
+
+    /// Store `value` under `key`.
+    ///
+    /// Args:
+    ///     key: Cache key
+    ///     value: str, bytes, int, float or bool
+    ///     ttl: Time-to-live in seconds (default: the cache's ``ttl``)
+    #[pyo3(signature = (key, value, ttl = None))]
+    pub fn set(
+        &self,
+        py: Python<'_>,
+        key: String,
+        value: &Bound<'_, PyAny>,
+        ttl: Option<f64>,
+    ) -> PyResult<()> {
+        let value = CacheValue::from_py(value)?;
+        let ttl = self.ttl_or_default(ttl)?;
+        py.detach(|| self.store(key, value, ttl));
+        Ok(())
+    }
+
+    /// Remove `key`. Returns True if a live entry was removed.
+    pub fn delete(&self, py: Python<'_>, key: &str) -> bool {
+        py.detach(|| self.remove(key))
+    }
+
+    /// Return the cached value for `key`, computing it with `factory()` on a miss.
+    ///
+    /// If several threads miss at once, each may call `factory`, but only the
+    /// first result is stored and every caller gets that value back.
+    ///
+    /// Args:
+    ///     key: Cache key
+    ///     factory: Zero-argument callable producing the value
+    ///     ttl: Time-to-live in seconds (default: the cache's ``ttl``)
+    #[pyo3(signature = (key, factory, ttl = None))]
+    pub fn get_or_set(
+        &self,
+        py: Python<'_>,
+        key: String,
+        factory: &Bound<'_, PyAny>,
+        ttl: Option<f64>,
+    ) -> PyResult<Py<PyAny>> {
+        let ttl = self.ttl_or_default(ttl)?;
+        if let Some(value) = py.detach(|| self.lookup(&key)) {
+            return value.to_py(py);
+        }
+        let value = CacheValue::from_py(&factory.call0()?)?;
+        py.detach(|| self.store_absent(key, value, ttl)).to_py(py)
+    }
+
+    /// Atomically add `delta` to the integer under `key` and return the result.
+    ///
+    /// A missing or expired key starts from 0 and takes `ttl`; an existing
+    /// entry keeps its expiry. Raises TypeError if the value is not an int.
+    #[pyo3(signature = (key, delta = 1, ttl = None))]
+    pub fn incr(&self, py: Python<'_>, key: String, delta: i64, ttl: Option<f64>) -> PyResult<i64> {
+        let ttl = self.ttl_or_default(ttl)?;
+        let name = key.clone();
+        py.detach(|| self.add(key, delta, ttl))
+            .map_err(|e| incr_error(&name, e))
+    }
+
+    /// Atomically subtract `delta` from the integer under `key` and return the result.
+    #[pyo3(signature = (key, delta = 1, ttl = None))]
+    pub fn decr(&self, py: Python<'_>, key: String, delta: i64, ttl: Option<f64>) -> PyResult<i64> {
+        let delta = delta
+            .checked_neg()
+            .ok_or_else(|| incr_error(&key, IncrError::Overflow))?;
+        self.incr(py, key, delta, ttl)
+    }
+
+    /// Return a dict of the keys in `keys` that are cached; missing keys are left out.
+    pub fn get_many<'py>(
+        &self,
+        py: Python<'py>,
+        keys: Vec<String>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let found: Vec<(String, CacheValue)> = py.detach(|| {
+            keys.into_iter()
+                .filter_map(|key| self.lookup(&key).map(|value| (key, value)))
+                .collect()
+        });
+        let result = PyDict::new(py);
+        for (key, value) in found {
+            result.set_item(key, value.to_py(py)?)?;
+        }
+        Ok(result)
+    }
+
+    /// Store every key/value pair of `items` with the same `ttl`.
+    #[pyo3(signature = (items, ttl = None))]
+    pub fn set_many(
+        &self,
+        py: Python<'_>,
+        items: &Bound<'_, PyDict>,
+        ttl: Option<f64>,
+    ) -> PyResult<()> {
+        let ttl = self.ttl_or_default(ttl)?;
+        let entries = items
+            .iter()
+            .map(|(key, value)| Ok((key.extract::<String>()?, CacheValue::from_py(&value)?)))
+            .collect::<PyResult<Vec<_>>>()?;
+        py.detach(|| {
+            for (key, value) in entries {
+                self.store(key, value, ttl);
+            }
+        });
+        Ok(())
+    }
+
+    /// Drop expired entries now instead of waiting for them to be read or evicted.
+    ///
+    /// Returns the number of entries removed.
+    pub fn purge_expired(&self, py: Python<'_>) -> usize {
+        py.detach(|| {
+            let now = Instant::now();
+            let mut slots = Vec::new();
+            self.map.retain(|_, entry| {
+                let expired = entry.expired(now);
+                if expired {
+                    slots.push(entry.slot.load(Ordering::Relaxed));
+                }
+                !expired
+            });
+            let mut index = self.index.lock();
+            for slot in &slots {
+                index.remove(slot);
+            }
+            self.expirations
+                .fetch_add(slots.len() as u64, Ordering::Relaxed);
+            slots.len()
+        })
+    }
+
+    /// Remove every entry. Statistics are kept.
+    pub fn clear(&self, py: Python<'_>) {
+        py.detach(|| {
+            let mut index = self.index.lock();
+            self.map.clear();
+            index.clear();
+        })
+    }
+
+    /// Cache statistics for this process.
+    ///
+    /// Returns a dict with `hits`, `misses`, `evictions` (entries dropped to
+    /// stay under `max_entries`), `expirations`, `size` and `max_entries`.
+    pub fn stats<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let stats = PyDict::new(py);
+        stats.set_item("hits", self.hits.load(Ordering::Relaxed))?;
+        stats.set_item("misses", self.misses.load(Ordering::Relaxed))?;
+        stats.set_item("evictions", self.evictions.load(Ordering::Relaxed))?;
+        stats.set_item("expirations", self.expirations.load(Ordering::Relaxed))?;
+        stats.set_item("size", self.map.len())?;
+        stats.set_item("max_entries", self.max_entries)?;
+        Ok(stats)
+    }
+
+    #[getter]
+    pub fn max_entries(&self) -> usize {
+        self.max_entries
+    }
+
+    fn __len__(&self) -> usize {
+        self.map.len()
+    }
+
+    fn __contains__(&self, key: &str) -> bool {
+        let now = Instant::now();
+        self.map.get(key).is_some_and(|entry| !entry.expired(now))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "MemoryCache(size={}, max_entries={})",
+            self.map.len(),
+            self.max_entries
+        )
+    }
+}
+
+/// Register cache classes.
+pub fn register_all(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<MemoryCache>()?;
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use std::future::Future;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use pyo3::exceptions::{PyRuntimeError, PyStopIteration};
+use pyo3::prelude::*;
+
+use super::response::ClientResponse;
+use super::ClientError;
+use crate::core::global::{get_asyncio, get_runtime};
+
+type Ready = Result<ClientResponse, ClientError>;
+
+#[derive(Default)]
+struct Pending {
+    ready: Option<Ready>,
+    /// asyncio future (and its loop) a task is parked on until `ready` is set
+    waker: Option<(Py<PyAny>, Py<PyAny>)>,
+    finished: bool,
+}
+
+/// Awaitable for a request running on the shared runtime.
+///
+/// Under asyncio the awaiting task parks on a future woken when the
+/// response has been read; without a running loop (handlers stepped by
+/// the server) each step just polls.
+#[pyclass]
+pub struct ClientFuture {
+    pending: Arc<Mutex<Pending>>,
+}
+
+impl ClientFuture {
+    pub(super) fn spawn<F>(request: F) -> Self
+    where
+        F: Future<Output = Ready> + Send + 'static,
+    {
+        let pending = Arc::new(Mutex::new(Pending::default()));
+        let target = pending.clone();
+        get_runtime().spawn(async move {
+            let ready = request.await;
+            let waker = {
+                let mut target = target.lock();
+                target.ready = Some(ready);
+                target.waker.take()
+            };
+            if let Some((event_loop, future)) = waker {
+                Python::attach(|py| {
+                    event_loop
+                        .bind(py)
+                        .call_method1("call_soon_threadsafe", (WakeTask(future),))
+                        .map(|_| ())
+                })
+                .unwrap_or_else(|err| {
+                    crate::hlog_debug!("Failed to wake task awaiting a response: {}", err);
+                });
+            }
+        });
+        Self { pending }
+    }
+}
+
+#[pymethods]
+impl ClientFuture {
+    fn __await__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let mut pending = self.pending.lock();
+        if let Some(ready) = pending.ready.take() {
+            pending.finished = true;
+            drop(pending);
+            let response = Py::new(py, ready?)?;
+            return Err(PyStopIteration::new_err((response,)));
+        }
+        if pending.finished {
+            return Err(PyRuntimeError::new_err("ClientFuture was already awaited"));
+        }
+
+        let event_loop = get_asyncio(py).bind(py).call_method0("_get_running_loop")?;
+        if event_loop.is_none() {
+            return Ok(py.None());
+        }
+        let future = event_loop.call_method0("create_future")?;
+        future.setattr("_asyncio_future_blocking", true)?;
+        pending.waker = Some((event_loop.unbind(), future.clone().unbind()));
+        Ok(future.unbind())
+    }
+}
+
+/// Loop callback resuming the task parked on a `ClientFuture`
+#[pyclass]
+struct WakeTask(Py<PyAny>);
+
+#[pymethods]
+impl WakeTask {
+    fn __call__(&self, py: Python<'_>) -> PyResult<()> {
+        let future = self.0.bind(py);
+        if !future.call_method0("done")?.is_truthy()? {
+            future.call_method1("set_result", (py.None(),))?;
+        }
+        Ok(())
+    }
+}
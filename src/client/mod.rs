@@ -1,60 +1,210 @@
+//! Outbound HTTP client for handlers.
+//!
+//! Every `HttpClient` in a process sends through one shared reqwest client,
+//! so connections to a host are pooled across clients, handlers and threads.
+//! Each client caps its own in-flight requests per host (`max_connections`),
+//! which also caps the connections it holds open to that host. Sync methods
+//! release the GIL until the whole body has been read; `*_async` methods
+//! return an awaitable running on the shared runtime.
+//!
+//! Idempotent requests that fail to connect or time out are retried up to
+//! `retries` times with a jittered, doubling backoff. Inside a handler,
+//! requests carry a `traceparent` continuing the current request's trace.
+
+mod future;
+mod response;
+
 use std::collections::HashMap;
+use std::error::Error as _;
+use std::future::Future;
 use std::sync::Arc;
 use std::time::Duration;
 
+use bytes::Bytes;
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use pyo3::exceptions::{PyConnectionError, PyRuntimeError, PyTimeoutError, PyValueError};
 use pyo3::prelude::*;
-use pyo3::types::PyDict;
-
-/// Response from an HTTP request
-#[pyclass(name = "ClientResponse")]
-pub struct ClientResponse {
-    #[pyo3(get)]
-    pub status: u16,
-    headers: HashMap<String, String>,
-    body: Vec<u8>,
+use pyo3::types::{PyBytes, PyDict, PyString};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use reqwest::{Method, Url};
+use tokio::sync::Semaphore;
+
+use crate::core::global::get_runtime;
+use crate::core::request_scope::current_trace_id;
+use crate::utils::json::serialize_py_to_json;
+
+pub use future::ClientFuture;
+pub use response::ClientResponse;
+
+/// The process's reqwest client, tagged with the pid that built it
+static SHARED: Mutex<Option<(u32, reqwest::Client)>> = Mutex::new(None);
+
+/// Client shared by every `HttpClient` in this process. A forked worker
+/// builds its own rather than reuse connections opened by its parent.
+fn shared_client() -> PyResult<reqwest::Client> {
+    let pid = std::process::id();
+    let mut shared = SHARED.lock();
+    if let Some((owner, client)) = shared.as_ref() {
+        if *owner == pid {
+            return Ok(client.clone());
+        }
+    }
+    let client = reqwest::Client::builder()
+        .build()
+        .map_err(|e| PyRuntimeError::new_err(format!("Failed to build HTTP client: {}", e)))?;
+    *shared = Some((pid, client.clone()));
+    Ok(client)
 }
 
-#[pymethods]
-impl ClientResponse {
-    /// Get response headers as a dict
-    pub fn headers(&self, py: Python<'_>) -> PyResult<Py<PyDict>> {
-        let dict = PyDict::new(py);
-        for (k, v) in &self.headers {
-            dict.set_item(k, v)?;
+/// Failure of a request, converted to a Python exception once the GIL is held
+pub(crate) enum ClientError {
+    Timeout(String),
+    Connect(String),
+    Other(String),
+}
+
+impl From<ClientError> for PyErr {
+    fn from(err: ClientError) -> Self {
+        match err {
+            ClientError::Timeout(msg) => PyTimeoutError::new_err(msg),
+            ClientError::Connect(msg) => PyConnectionError::new_err(msg),
+            ClientError::Other(msg) => PyRuntimeError::new_err(msg),
         }
-        Ok(dict.unbind())
     }
+}
 
-    /// Get body as text
-    pub fn text(&self) -> PyResult<String> {
-        String::from_utf8(self.body.clone())
-            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+impl ClientError {
+    fn from_reqwest(err: &reqwest::Error, request: &Outgoing, attempts: u32) -> Self {
+        let mut msg = if err.is_timeout() {
+            format!(
+                "{} {} timed out after {:.3}s",
+                request.method,
+                request.url,
+                request.timeout.as_secs_f64()
+            )
+        } else {
+            // reqwest's own message omits the cause, e.g. "connection refused"
+            let mut msg = format!("{} {} failed: {}", request.method, request.url, err);
+            let mut source = err.source();
+            while let Some(cause) = source {
+                msg.push_str(&format!(": {}", cause));
+                source = cause.source();
+            }
+            msg
+        };
+        if attempts > 1 {
+            msg.push_str(&format!(" ({} attempts)", attempts));
+        }
+        if err.is_timeout() {
+            Self::Timeout(msg)
+        } else if err.is_connect() {
+            Self::Connect(msg)
+        } else {
+            Self::Other(msg)
+        }
     }
+}
+
+/// A request resolved from Python arguments, ready to send without the GIL
+struct Outgoing {
+    method: Method,
+    url: Url,
+    headers: HeaderMap,
+    body: Option<Bytes>,
+    timeout: Duration,
+}
 
-    /// Get body as JSON (returns a Python dict/list)
-    pub fn json(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
-        let text = self.text()?;
-        let json_mod = py.import("json")?;
-        let result = json_mod.call_method1("loads", (text,))?;
-        Ok(result.unbind())
+impl Outgoing {
+    /// Methods safe to send again after a failure
+    fn idempotent(&self) -> bool {
+        matches!(
+            self.method,
+            Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
+        )
     }
+}
+
+/// Sleep before retry `attempt` (1-based): between half and all of
+/// `backoff * 2^(attempt - 1)`, so clients failing together spread out.
+fn retry_delay(backoff: Duration, attempt: u32) -> Duration {
+    let ceiling = backoff.saturating_mul(1 << (attempt - 1).min(16));
+    ceiling.mul_f64(0.5 + rand::random::<f64>() / 2.0)
+}
 
-    /// Get body as bytes
-    pub fn bytes(&self, py: Python<'_>) -> Py<pyo3::types::PyBytes> {
-        pyo3::types::PyBytes::new(py, &self.body).unbind()
+async fn send(
+    client: reqwest::Client,
+    limit: Arc<Semaphore>,
+    request: Outgoing,
+    retries: u32,
+    backoff: Duration,
+) -> Result<ClientResponse, ClientError> {
+    let retries = if request.idempotent() { retries } else { 0 };
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let result = {
+            let _permit = limit
+                .acquire()
+                .await
+                .map_err(|e| ClientError::Other(e.to_string()))?;
+            receive(&client, &request).await
+        };
+        match result {
+            Ok(response) => return Ok(response),
+            Err(err) if attempt <= retries && (err.is_connect() || err.is_timeout()) => {
+                crate::hlog_debug!(
+                    "Retrying {} {} after attempt {}: {}",
+                    request.method,
+                    request.url,
+                    attempt,
+                    err
+                );
+                tokio::time::sleep(retry_delay(backoff, attempt)).await;
+            }
+            Err(err) => return Err(ClientError::from_reqwest(&err, &request, attempt)),
+        }
     }
+}
 
-    fn __repr__(&self) -> String {
-        format!("<ClientResponse status={}>", self.status)
+/// One attempt, including reading the body, all within the request's timeout
+async fn receive(
+    client: &reqwest::Client,
+    request: &Outgoing,
+) -> Result<ClientResponse, reqwest::Error> {
+    let mut builder = client
+        .request(request.method.clone(), request.url.clone())
+        .headers(request.headers.clone())
+        .timeout(request.timeout);
+    if let Some(body) = &request.body {
+        builder = builder.body(body.clone());
     }
+    let response = builder.send().await?;
+    let status = response.status().as_u16();
+    let url = response.url().to_string();
+    let headers = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            let value = value.to_str().ok()?;
+            Some((name.as_str().to_string(), value.to_string()))
+        })
+        .collect();
+    let body = response.bytes().await?;
+    Ok(ClientResponse::new(status, url, headers, body))
 }
 
-/// Async HTTP client backed by reqwest with Rust-level connection pooling
+/// HTTP client backed by reqwest with a process-wide connection pool
 #[pyclass(name = "HttpClient")]
 pub struct HttpClient {
-    client: Arc<reqwest::Client>,
     base_url: Option<String>,
-    rt: tokio::runtime::Runtime,
+    timeout: Duration,
+    max_connections: usize,
+    retries: u32,
+    backoff: Duration,
+    propagate_trace: bool,
+    /// In-flight limit per `scheme://host:port`
+    hosts: DashMap<String, Arc<Semaphore>>,
 }
 
 #[pymethods]
@@ -63,139 +213,242 @@ impl HttpClient {
     ///
     /// Args:
     ///     base_url: Optional base URL prepended to all requests
-    ///     timeout: Request timeout in seconds (default: 30)
-    ///     max_connections: Max idle connections per host (default: 20)
+    ///     timeout: Default request timeout in seconds, body included (default: 30)
+    ///     max_connections: Max concurrent requests per host (default: 20)
+    ///     retries: Retries for idempotent requests that fail to connect or time out
+    ///     backoff: Base delay in seconds between retries, doubled each time
+    ///     propagate_trace: Send the current request's trace as `traceparent`
     #[new]
-    #[pyo3(signature = (base_url = None, timeout = 30, max_connections = 20))]
+    #[pyo3(signature = (
+        base_url = None,
+        timeout = 30.0,
+        max_connections = 20,
+        retries = 0,
+        backoff = 0.1,
+        propagate_trace = true
+    ))]
     pub fn new(
         base_url: Option<String>,
-        timeout: u64,
+        timeout: f64,
         max_connections: usize,
+        retries: u32,
+        backoff: f64,
+        propagate_trace: bool,
     ) -> PyResult<Self> {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(timeout))
-            .pool_max_idle_per_host(max_connections)
-            .build()
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
-        let rt = tokio::runtime::Builder::new_current_thread()
-            .enable_all()
-            .build()
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        if max_connections == 0 {
+            return Err(PyValueError::new_err("max_connections must be at least 1"));
+        }
+        if !(backoff >= 0.0 && backoff.is_finite()) {
+            return Err(PyValueError::new_err(
+                "backoff must be a non-negative number",
+            ));
+        }
         Ok(Self {
-            client: Arc::new(client),
             base_url,
-            rt,
+            timeout: seconds(timeout, "timeout")?,
+            max_connections,
+            retries,
+            backoff: Duration::from_secs_f64(backoff),
+            propagate_trace,
+            hosts: DashMap::new(),
         })
     }
 
+    /// Send a request, releasing the GIL until the response has been read
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (method, url, headers = None, params = None, json = None, data = None, timeout = None))]
+    pub fn request(
+        &self,
+        py: Python<'_>,
+        method: &str,
+        url: &str,
+        headers: Option<HashMap<String, String>>,
+        params: Option<HashMap<String, String>>,
+        json: Option<&Bound<'_, PyAny>>,
+        data: Option<&Bound<'_, PyAny>>,
+        timeout: Option<f64>,
+    ) -> PyResult<ClientResponse> {
+        let request = self.prepare(py, method, url, headers, params, json, data, timeout)?;
+        py.detach(|| get_runtime().block_on(request))
+            .map_err(PyErr::from)
+    }
+
+    /// Send a request on the shared runtime; await the result for a `ClientResponse`
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (method, url, headers = None, params = None, json = None, data = None, timeout = None))]
+    pub fn request_async(
+        &self,
+        py: Python<'_>,
+        method: &str,
+        url: &str,
+        headers: Option<HashMap<String, String>>,
+        params: Option<HashMap<String, String>>,
+        json: Option<&Bound<'_, PyAny>>,
+        data: Option<&Bound<'_, PyAny>>,
+        timeout: Option<f64>,
+    ) -> PyResult<ClientFuture> {
+        let request = self.prepare(py, method, url, headers, params, json, data, timeout)?;
+        Ok(ClientFuture::spawn(request))
+    }
+
     /// Send a GET request
-    #[pyo3(signature = (url, headers = None, params = None))]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (url, headers = None, params = None, json = None, data = None, timeout = None))]
     pub fn get(
         &self,
-        url: String,
+        py: Python<'_>,
+        url: &str,
         headers: Option<HashMap<String, String>>,
         params: Option<HashMap<String, String>>,
+        json: Option<&Bound<'_, PyAny>>,
+        data: Option<&Bound<'_, PyAny>>,
+        timeout: Option<f64>,
     ) -> PyResult<ClientResponse> {
-        let full_url = self.build_url(&url);
-        let mut req = self.client.get(&full_url);
-        if let Some(h) = headers {
-            for (k, v) in h {
-                req = req.header(k, v);
-            }
-        }
-        if let Some(p) = params {
-            req = req.query(&p);
-        }
-        self.rt.block_on(send_request(req))
+        self.request(py, "GET", url, headers, params, json, data, timeout)
     }
 
     /// Send a POST request
-    #[pyo3(signature = (url, headers = None, json = None, body = None))]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (url, headers = None, params = None, json = None, data = None, timeout = None))]
     pub fn post(
         &self,
-        url: String,
+        py: Python<'_>,
+        url: &str,
         headers: Option<HashMap<String, String>>,
-        json: Option<String>,
-        body: Option<Vec<u8>>,
+        params: Option<HashMap<String, String>>,
+        json: Option<&Bound<'_, PyAny>>,
+        data: Option<&Bound<'_, PyAny>>,
+        timeout: Option<f64>,
     ) -> PyResult<ClientResponse> {
-        let full_url = self.build_url(&url);
-        let mut req = self.client.post(&full_url);
-        if let Some(h) = headers {
-            for (k, v) in h {
-                req = req.header(k, v);
-            }
-        }
-        if let Some(j) = json {
-            req = req.header("content-type", "application/json").body(j);
-        } else if let Some(b) = body {
-            req = req.body(b);
-        }
-        self.rt.block_on(send_request(req))
+        self.request(py, "POST", url, headers, params, json, data, timeout)
     }
 
     /// Send a PUT request
-    #[pyo3(signature = (url, headers = None, json = None, body = None))]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (url, headers = None, params = None, json = None, data = None, timeout = None))]
     pub fn put(
         &self,
-        url: String,
+        py: Python<'_>,
+        url: &str,
         headers: Option<HashMap<String, String>>,
-        json: Option<String>,
-        body: Option<Vec<u8>>,
+        params: Option<HashMap<String, String>>,
+        json: Option<&Bound<'_, PyAny>>,
+        data: Option<&Bound<'_, PyAny>>,
+        timeout: Option<f64>,
     ) -> PyResult<ClientResponse> {
-        let full_url = self.build_url(&url);
-        let mut req = self.client.put(&full_url);
-        if let Some(h) = headers {
-            for (k, v) in h {
-                req = req.header(k, v);
-            }
-        }
-        if let Some(j) = json {
-            req = req.header("content-type", "application/json").body(j);
-        } else if let Some(b) = body {
-            req = req.body(b);
-        }
-        self.rt.block_on(send_request(req))
+        self.request(py, "PUT", url, headers, params, json, data, timeout)
     }
 
     /// Send a PATCH request
-    #[pyo3(signature = (url, headers = None, json = None, body = None))]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (url, headers = None, params = None, json = None, data = None, timeout = None))]
     pub fn patch(
         &self,
-        url: String,
+        py: Python<'_>,
+        url: &str,
         headers: Option<HashMap<String, String>>,
-        json: Option<String>,
-        body: Option<Vec<u8>>,
+        params: Option<HashMap<String, String>>,
+        json: Option<&Bound<'_, PyAny>>,
+        data: Option<&Bound<'_, PyAny>>,
+        timeout: Option<f64>,
     ) -> PyResult<ClientResponse> {
-        let full_url = self.build_url(&url);
-        let mut req = self.client.patch(&full_url);
-        if let Some(h) = headers {
-            for (k, v) in h {
-                req = req.header(k, v);
-            }
-        }
-        if let Some(j) = json {
-            req = req.header("content-type", "application/json").body(j);
-        } else if let Some(b) = body {
-            req = req.body(b);
-        }
-        self.rt.block_on(send_request(req))
+        self.request(py, "PATCH", url, headers, params, json, data, timeout)
     }
 
     /// Send a DELETE request
-    #[pyo3(signature = (url, headers = None))]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (url, headers = None, params = None, json = None, data = None, timeout = None))]
     pub fn delete(
         &self,
-        url: String,
+        py: Python<'_>,
+        url: &str,
         headers: Option<HashMap<String, String>>,
+        params: Option<HashMap<String, String>>,
+        json: Option<&Bound<'_, PyAny>>,
+        data: Option<&Bound<'_, PyAny>>,
+        timeout: Option<f64>,
     ) -> PyResult<ClientResponse> {
-        let full_url = self.build_url(&url);
-        let mut req = self.client.delete(&full_url);
-        if let Some(h) = headers {
-            for (k, v) in h {
-                req = req.header(k, v);
-            }
-        }
-        self.rt.block_on(send_request(req))
+        self.request(py, "DELETE", url, headers, params, json, data, timeout)
+    }
+
+    /// Send a GET request without blocking the event loop
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (url, headers = None, params = None, json = None, data = None, timeout = None))]
+    pub fn get_async(
+        &self,
+        py: Python<'_>,
+        url: &str,
+        headers: Option<HashMap<String, String>>,
+        params: Option<HashMap<String, String>>,
+        json: Option<&Bound<'_, PyAny>>,
+        data: Option<&Bound<'_, PyAny>>,
+        timeout: Option<f64>,
+    ) -> PyResult<ClientFuture> {
+        self.request_async(py, "GET", url, headers, params, json, data, timeout)
+    }
+
+    /// Send a POST request without blocking the event loop
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (url, headers = None, params = None, json = None, data = None, timeout = None))]
+    pub fn post_async(
+        &self,
+        py: Python<'_>,
+        url: &str,
+        headers: Option<HashMap<String, String>>,
+        params: Option<HashMap<String, String>>,
+        json: Option<&Bound<'_, PyAny>>,
+        data: Option<&Bound<'_, PyAny>>,
+        timeout: Option<f64>,
+    ) -> PyResult<ClientFuture> {
+        self.request_async(py, "POST", url, headers, params, json, data, timeout)
+    }
+
+    /// Send a PUT request without blocking the event loop
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (url, headers = None, params = None, json = None, data = None, timeout = None))]
+    pub fn put_async(
+        &self,
+        py: Python<'_>,
+        url: &str,
+        headers: Option<HashMap<String, String>>,
+        params: Option<HashMap<String, String>>,
+        json: Option<&Bound<'_, PyAny>>,
+        data: Option<&Bound<'_, PyAny>>,
+        timeout: Option<f64>,
+    ) -> PyResult<ClientFuture> {
+        self.request_async(py, "PUT", url, headers, params, json, data, timeout)
+    }
+
+    /// Send a PATCH request without blocking the event loop
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (url, headers = None, params = None, json = None, data = None, timeout = None))]
+    pub fn patch_async(
+        &self,
+        py: Python<'_>,
+        url: &str,
+        headers: Option<HashMap<String, String>>,
+        params: Option<HashMap<String, String>>,
+        json: Option<&Bound<'_, PyAny>>,
+        data: Option<&Bound<'_, PyAny>>,
+        timeout: Option<f64>,
+    ) -> PyResult<ClientFuture> {
+        self.request_async(py, "PATCH", url, headers, params, json, data, timeout)
+    }
+
+    /// Send a DELETE request without blocking the event loop
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (url, headers = None, params = None, json = None, data = None, timeout = None))]
+    pub fn delete_async(
+        &self,
+        py: Python<'_>,
+        url: &str,
+        headers: Option<HashMap<String, String>>,
+        params: Option<HashMap<String, String>>,
+        json: Option<&Bound<'_, PyAny>>,
+        data: Option<&Bound<'_, PyAny>>,
+        timeout: Option<f64>,
+    ) -> PyResult<ClientFuture> {
+        self.request_async(py, "DELETE", url, headers, params, json, data, timeout)
     }
 
     fn __repr__(&self) -> String {
@@ -220,29 +473,148 @@ impl HttpClient {
             None => url.to_string(),
         }
     }
+
+    /// Per-host in-flight limit for `url`
+    fn host_limit(&self, url: &Url) -> Arc<Semaphore> {
+        let key = format!(
+            "{}://{}:{}",
+            url.scheme(),
+            url.host_str().unwrap_or_default(),
+            url.port_or_known_default().unwrap_or_default()
+        );
+        self.hosts
+            .entry(key)
+            .or_insert_with(|| Arc::new(Semaphore::new(self.max_connections)))
+            .clone()
+    }
+
+    /// Resolve the Python arguments while the GIL is held and return the
+    /// request as a future that needs neither the GIL nor `self`.
+    #[allow(clippy::too_many_arguments)]
+    fn prepare(
+        &self,
+        py: Python<'_>,
+        method: &str,
+        url: &str,
+        headers: Option<HashMap<String, String>>,
+        params: Option<HashMap<String, String>>,
+        json: Option<&Bound<'_, PyAny>>,
+        data: Option<&Bound<'_, PyAny>>,
+        timeout: Option<f64>,
+    ) -> PyResult<impl Future<Output = Result<ClientResponse, ClientError>> + Send + 'static> {
+        let method = Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+            .map_err(|_| PyValueError::new_err(format!("Invalid HTTP method: {:?}", method)))?;
+        let full_url = self.build_url(url);
+        let mut url = Url::parse(&full_url)
+            .map_err(|e| PyValueError::new_err(format!("Invalid URL {:?}: {}", full_url, e)))?;
+        if let Some(params) = params.filter(|p| !p.is_empty()) {
+            url.query_pairs_mut().extend_pairs(params);
+        }
+
+        let mut header_map = HeaderMap::new();
+        for (name, value) in headers.unwrap_or_default() {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| PyValueError::new_err(format!("Invalid header name: {:?}", name)))?;
+            let value = HeaderValue::from_str(&value).map_err(|_| {
+                PyValueError::new_err(format!("Invalid value for header {:?}", name.as_str()))
+            })?;
+            header_map.append(name, value);
+        }
+
+        let json = json.filter(|j| !j.is_none());
+        let data = data.filter(|d| !d.is_none());
+        let (body, content_type) = match (json, data) {
+            (Some(_), Some(_)) => {
+                return Err(PyValueError::new_err("Pass either json or data, not both"));
+            }
+            (Some(json), None) => (
+                Some(Bytes::from(serialize_py_to_json(json)?)),
+                Some("application/json"),
+            ),
+            (None, Some(data)) => encode_data(data)?,
+            (None, None) => (None, None),
+        };
+        if let Some(content_type) = content_type {
+            if !header_map.contains_key(CONTENT_TYPE) {
+                header_map.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+            }
+        }
+
+        if self.propagate_trace && !header_map.contains_key("traceparent") {
+            if let Some(trace_id) = current_trace_id(py)? {
+                let span_id = rand::random::<u64>().max(1);
+                let traceparent = format!("00-{}-{:016x}-01", trace_id, span_id);
+                if let Ok(value) = HeaderValue::from_str(&traceparent) {
+                    header_map.insert("traceparent", value);
+                }
+            }
+        }
+
+        let request = Outgoing {
+            method,
+            url,
+            headers: header_map,
+            body,
+            timeout: match timeout {
+                Some(timeout) => seconds(timeout, "timeout")?,
+                None => self.timeout,
+            },
+        };
+        let limit = self.host_limit(&request.url);
+        Ok(send(
+            shared_client()?,
+            limit,
+            request,
+            self.retries,
+            self.backoff,
+        ))
+    }
 }
 
-async fn send_request(req: reqwest::RequestBuilder) -> PyResult<ClientResponse> {
-    let resp = req
-        .send()
-        .await
-        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
-
-    let status = resp.status().as_u16();
-    let mut headers = HashMap::new();
-    for (k, v) in resp.headers().iter() {
-        if let Ok(val) = v.to_str() {
-            headers.insert(k.as_str().to_string(), val.to_string());
+fn seconds(value: f64, name: &str) -> PyResult<Duration> {
+    if value > 0.0 && value.is_finite() {
+        Ok(Duration::from_secs_f64(value))
+    } else {
+        Err(PyValueError::new_err(format!(
+            "{} must be a positive number of seconds",
+            name
+        )))
+    }
+}
+
+/// Body and default content type for `data`: bytes and str are sent as
+/// they are, a dict is form-encoded.
+fn encode_data(data: &Bound<'_, PyAny>) -> PyResult<(Option<Bytes>, Option<&'static str>)> {
+    if let Ok(bytes) = data.cast::<PyBytes>() {
+        return Ok((Some(Bytes::copy_from_slice(bytes.as_bytes())), None));
+    }
+    if let Ok(text) = data.cast::<PyString>() {
+        let text = text.to_str()?;
+        return Ok((
+            Some(Bytes::copy_from_slice(text.as_bytes())),
+            Some("text/plain; charset=utf-8"),
+        ));
+    }
+    if let Ok(form) = data.cast::<PyDict>() {
+        let mut encoded = form_urlencoded::Serializer::new(String::new());
+        for (key, value) in form.iter() {
+            encoded.append_pair(
+                &key.str()?.to_string_lossy(),
+                &value.str()?.to_string_lossy(),
+            );
         }
+        return Ok((
+            Some(Bytes::from(encoded.finish())),
+            Some("application/x-www-form-urlencoded"),
+        ));
     }
-    let body = resp
-        .bytes()
-        .await
-        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+    Err(PyValueError::new_err("data must be bytes, str or a dict"))
+}
 
-    Ok(ClientResponse {
-        status,
-        headers,
-        body: body.to_vec(),
-    })
+/// Register HTTP client classes.
+pub fn register_all(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<HttpClient>()?;
+    m.add_class::<ClientResponse>()?;
+    m.add_class::<ClientFuture>()?;
+    Ok(())
 }
@@ -0,0 +1,87 @@
+use bytes::Bytes;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+
+use crate::utils::json::parse_json_to_py;
+
+/// Response from an HTTP request, read in full before it is returned
+#[pyclass(name = "ClientResponse")]
+pub struct ClientResponse {
+    #[pyo3(get)]
+    pub status: u16,
+    /// Final URL, after any redirects
+    #[pyo3(get)]
+    pub url: String,
+    headers: Vec<(String, String)>,
+    body: Bytes,
+}
+
+impl ClientResponse {
+    pub(super) fn new(
+        status: u16,
+        url: String,
+        headers: Vec<(String, String)>,
+        body: Bytes,
+    ) -> Self {
+        Self {
+            status,
+            url,
+            headers,
+            body,
+        }
+    }
+}
+
+#[pymethods]
+impl ClientResponse {
+    /// Response headers; repeated names are joined with ", "
+    #[getter]
+    pub fn headers<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        for (name, value) in &self.headers {
+            match dict.get_item(name)? {
+                Some(existing) => {
+                    let joined = format!("{}, {}", existing.extract::<String>()?, value);
+                    dict.set_item(name, joined)?;
+                }
+                None => dict.set_item(name, value)?,
+            }
+        }
+        Ok(dict)
+    }
+
+    /// Body decoded as UTF-8
+    #[getter]
+    pub fn text(&self) -> PyResult<String> {
+        std::str::from_utf8(&self.body)
+            .map(str::to_owned)
+            .map_err(|e| PyValueError::new_err(format!("Response body is not UTF-8: {}", e)))
+    }
+
+    /// Raw (decompressed) body
+    #[getter]
+    pub fn content<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.body)
+    }
+
+    /// True for 2xx statuses
+    #[getter]
+    pub fn ok(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Body parsed as JSON (returns a Python dict/list)
+    pub fn json(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        parse_json_to_py(py, &self.body)
+    }
+
+    /// Get body as bytes
+    pub fn bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        self.content(py)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<ClientResponse status={}>", self.status)
+    }
+}
@@ -5,6 +5,8 @@ use std::{
     thread, time,
 };
 
+use crate::telemetry::gil::{self, Site};
+
 pub(crate) struct BlockingTask {
     inner: Box<dyn FnOnce(Python) + Send + 'static>,
 }
@@ -120,16 +122,20 @@ impl BlockingRunner {
 
 fn blocking_worker(queue: channel::Receiver<BlockingTask>) {
     Python::attach(|py| {
-        while let Ok(task) = py.detach(|| queue.recv()) {
+        while let (Ok(task), ready) = py.detach(|| (queue.recv(), gil::now())) {
+            let acquired = gil::now();
             task.run(py);
+            gil::record(Site::Handler, ready, acquired);
         }
     });
 }
 
 fn blocking_worker_idle(queue: channel::Receiver<BlockingTask>, timeout: time::Duration) {
     Python::attach(|py| {
-        while let Ok(task) = py.detach(|| queue.recv_timeout(timeout)) {
+        while let (Ok(task), ready) = py.detach(|| (queue.recv_timeout(timeout), gil::now())) {
+            let acquired = gil::now();
             task.run(py);
+            gil::record(Site::Handler, ready, acquired);
         }
     });
 }
@@ -4,14 +4,18 @@
 //! without the GIL bottleneck that `concurrent.futures.ThreadPoolExecutor` suffers from.
 //!
 //! Key design decisions:
-//! - **Pre-spawned thread pool**: threads call `Python::attach` once and reuse
-//!   the sub-interpreter context, avoiding repeated thread-start overhead.
+//! - **Pre-spawned thread pool**: threads call `Python::attach` once and keep
+//!   their thread state in the main interpreter, avoiding repeated
+//!   thread-start overhead.
 //! - **GIL release on wait**: the calling Python thread releases the GIL while
 //!   waiting for results, so other Python threads can progress.
 //! - **crossbeam channels**: lock-free MPMC queue for task dispatch — zero
 //!   allocation per send in the hot path.
 //! - **Batch / map / parallel**: first-class support for data-parallel patterns
 //!   that automatically partition work across all pool threads.
+//! - **Request deadlines**: work submitted from a handler carries the request's
+//!   deadline; work whose deadline has passed is never started and raises
+//!   `TimeoutError` instead.
 
 use crossbeam_channel as channel;
 use pyo3::prelude::*;
@@ -20,13 +24,17 @@ use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
+use crate::core::deadline::{self, Deadline};
 use crate::core::global::get_builtins;
+use crate::telemetry::gil::{self, Site};
 
 /// A unit of work sent to a pool thread.
 struct WorkItem {
     callable: Py<PyAny>,
     args: Py<PyTuple>,
     kwargs: Option<Py<PyDict>>,
+    /// Deadline of the request that submitted the work
+    deadline: Option<Deadline>,
     result_tx: channel::Sender<WorkResult>,
 }
 
@@ -34,6 +42,20 @@ struct WorkItem {
 enum WorkResult {
     Ok(Py<PyAny>),
     Err(String),
+    /// Skipped because the request deadline passed before a thread picked it up
+    Expired,
+}
+
+fn expired_error() -> PyErr {
+    pyo3::exceptions::PyTimeoutError::new_err(format!("work not started: {}", deadline::EXCEEDED))
+}
+
+/// Deadline of the calling request, failing fast when it has already passed
+fn submit_deadline(py: Python<'_>) -> PyResult<Option<Deadline>> {
+    match deadline::current(py) {
+        Some(deadline) if deadline.expired() => Err(expired_error()),
+        deadline => Ok(deadline),
+    }
 }
 
 /// A high-performance, GIL-releasing thread pool executor.
@@ -126,6 +148,7 @@ impl BlockingExecutor {
                 "BlockingExecutor is shut down",
             ));
         }
+        let deadline = submit_deadline(py)?;
 
         let args_owned: Py<PyTuple> = args.clone().unbind();
         let kwargs_owned: Option<Py<PyDict>> = kwargs.map(|k| k.clone().unbind());
@@ -137,6 +160,7 @@ impl BlockingExecutor {
                 callable,
                 args: args_owned,
                 kwargs: kwargs_owned,
+                deadline,
                 result_tx,
             })
             .map_err(|_| {
@@ -153,6 +177,7 @@ impl BlockingExecutor {
         match outcome {
             Ok(WorkResult::Ok(obj)) => Ok(obj),
             Ok(WorkResult::Err(msg)) => Err(pyo3::exceptions::PyRuntimeError::new_err(msg)),
+            Ok(WorkResult::Expired) => Err(expired_error()),
             Err(msg) => Err(pyo3::exceptions::PyRuntimeError::new_err(msg)),
         }
     }
@@ -167,6 +192,7 @@ impl BlockingExecutor {
                 "BlockingExecutor is shut down",
             ));
         }
+        let deadline = submit_deadline(py)?;
 
         let n = tasks.len();
         let mut receivers: Vec<channel::Receiver<WorkResult>> = Vec::with_capacity(n);
@@ -203,6 +229,7 @@ impl BlockingExecutor {
                     callable,
                     args,
                     kwargs,
+                    deadline,
                     result_tx,
                 })
                 .map_err(|_| {
@@ -226,6 +253,7 @@ impl BlockingExecutor {
                 Ok(WorkResult::Err(msg)) => {
                     return Err(pyo3::exceptions::PyRuntimeError::new_err(msg));
                 }
+                Ok(WorkResult::Expired) => return Err(expired_error()),
                 Err(msg) => {
                     return Err(pyo3::exceptions::PyRuntimeError::new_err(msg));
                 }
@@ -249,6 +277,7 @@ impl BlockingExecutor {
                 "BlockingExecutor is shut down",
             ));
         }
+        let deadline = submit_deadline(py)?;
 
         let total = items.len();
         if total == 0 {
@@ -302,6 +331,7 @@ impl BlockingExecutor {
                     callable: wrapper.clone_ref(py),
                     args,
                     kwargs: None,
+                    deadline,
                     result_tx,
                 })
                 .map_err(|_| {
@@ -345,6 +375,7 @@ impl BlockingExecutor {
                 Ok(WorkResult::Err(msg)) => {
                     return Err(pyo3::exceptions::PyRuntimeError::new_err(msg));
                 }
+                Ok(WorkResult::Expired) => return Err(expired_error()),
                 Err(msg) => {
                     return Err(pyo3::exceptions::PyRuntimeError::new_err(msg));
                 }
@@ -423,9 +454,9 @@ impl BlockingExecutor {
 
 /// The main loop executed by each worker thread.
 ///
-/// Calls `Python::attach` once to bind a Python sub-interpreter, then loops
-/// receiving work items. The GIL is released (`py.detach`) while blocking on the
-/// channel, so no GIL contention is added by idle workers.
+/// Calls `Python::attach` once to bind a thread state in the main interpreter,
+/// then loops receiving work items. The GIL is released (`py.detach`) while
+/// blocking on the channel, so no GIL contention is added by idle workers.
 fn worker_loop(
     rx: channel::Receiver<WorkItem>,
     live_threads: Arc<AtomicUsize>,
@@ -434,17 +465,20 @@ fn worker_loop(
     Python::attach(|py| {
         loop {
             // Release GIL while waiting for work.
-            let item = py.detach(|| {
+            let (item, ready) = py.detach(|| {
                 if !running.load(Ordering::Acquire) {
-                    return Err(());
+                    return (Err(()), None);
                 }
-                rx.recv_timeout(Duration::from_millis(200)).map_err(|_| ())
+                let item = rx.recv_timeout(Duration::from_millis(200)).map_err(|_| ());
+                (item, gil::now())
             });
 
             match item {
                 Ok(work) => {
                     // Execute the callable with GIL held.
+                    let acquired = gil::now();
                     let result = execute_work(py, &work);
+                    gil::record(Site::Executor, ready, acquired);
                     // Send result back (ignore if receiver dropped).
                     let _ = work.result_tx.send(result);
                 }
@@ -464,6 +498,9 @@ fn worker_loop(
 /// Execute a single work item, returning a `WorkResult`.
 #[inline]
 fn execute_work(py: Python<'_>, work: &WorkItem) -> WorkResult {
+    if work.deadline.is_some_and(|d| d.expired()) {
+        return WorkResult::Expired;
+    }
     let callable = work.callable.bind(py);
     let args = work.args.bind(py);
 
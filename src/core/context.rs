@@ -1,6 +1,5 @@
 use std::collections::HashMap;
 use std::sync::Arc;
-use xxhash_rust::xxh3::xxh3_64;
 
 use dashmap::DashMap;
 use parking_lot::RwLock;
@@ -155,7 +154,7 @@ impl Context {
     #[new]
     pub fn new() -> Self {
         let now = std::time::Instant::now();
-        let request_id = format!("{:016x}", xxh3_64(&now.elapsed().as_nanos().to_le_bytes()));
+        let request_id = crate::core::request_id::next();
 
         Self {
             values: Arc::new(DashMap::new()),
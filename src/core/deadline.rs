@@ -0,0 +1,72 @@
+//! Request deadlines shared with downstream work.
+//!
+//! When a route runs under a timeout (its own `timeout=` or the one set by
+//! `TimeoutMiddleware`), the worker attaches a [`Deadline`] to the request and
+//! publishes it under the request ID while the handler runs. Database
+//! sessions and the `BlockingExecutor` look it up by the current request, so
+//! work started on behalf of a request that has already timed out fails fast
+//! instead of holding a connection or a pool thread.
+
+use dashmap::DashMap;
+use pyo3::prelude::*;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Suffix of error messages raised when a deadline cuts work short
+pub const EXCEEDED: &str = "request deadline exceeded";
+
+/// Point in time by which a request must have produced its response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline(Instant);
+
+impl Deadline {
+    pub fn after(timeout: Duration) -> Self {
+        Self(Instant::now() + timeout)
+    }
+
+    /// Time left, zero once the deadline has passed
+    pub fn remaining(&self) -> Duration {
+        self.0.saturating_duration_since(Instant::now())
+    }
+
+    pub fn expired(&self) -> bool {
+        Instant::now() >= self.0
+    }
+}
+
+/// Deadlines of requests whose handler is running, by request ID
+static ACTIVE: OnceLock<DashMap<String, Deadline>> = OnceLock::new();
+
+fn active() -> &'static DashMap<String, Deadline> {
+    ACTIVE.get_or_init(DashMap::new)
+}
+
+/// Publish `deadline` for `request_id` until [`release`] is called
+pub fn register(request_id: &str, deadline: Deadline) {
+    active().insert(request_id.to_string(), deadline);
+}
+
+pub fn release(request_id: &str) {
+    if let Some(map) = ACTIVE.get() {
+        map.remove(request_id);
+    }
+}
+
+/// Deadline of the request `request_id`, if its handler runs under one
+pub fn for_request(request_id: &str) -> Option<Deadline> {
+    let map = ACTIVE.get()?;
+    if map.is_empty() {
+        return None;
+    }
+    map.get(request_id).map(|entry| *entry)
+}
+
+/// Deadline of the request whose handler is calling in, found through the
+/// `hypern.context` ContextVar
+pub fn current(py: Python<'_>) -> Option<Deadline> {
+    if ACTIVE.get().is_none_or(|map| map.is_empty()) {
+        return None;
+    }
+    let request_id = crate::core::request_scope::current_request_id(py).ok()??;
+    for_request(&request_id)
+}
@@ -0,0 +1,188 @@
+//! Deep health endpoint (`{health_path_prefix}/deep`).
+//!
+//! The other probes only report the process state. The deep probe runs the
+//! checks registered with `Server.add_health_check` and pings every
+//! initialized connection pool, all concurrently. The result is cached for
+//! `deep_check_interval_ms`, so probes hitting it often don't load the
+//! dependencies, and each run is bounded by `deep_check_timeout_ms`: a check
+//! still running then is reported as timed out (a Python check keeps its
+//! thread until it returns). Answers 200 when every critical check passes,
+//! 503 otherwise.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::body::Body;
+use parking_lot::RwLock;
+use pyo3::prelude::*;
+use serde_json::{json, Map, Value};
+
+use crate::core::global::get_asyncio;
+use crate::core::reload::ReloadManager;
+use crate::database::pool::ConnectionPoolManager;
+
+static CHECKS: RwLock<Vec<Arc<CustomCheck>>> = RwLock::new(Vec::new());
+/// Last run, shared by the requests within the interval
+static CACHE: tokio::sync::Mutex<Option<Report>> = tokio::sync::Mutex::const_new(None);
+
+/// A Python callable run by the deep probe. It passes when it returns
+/// None or a truthy value; awaitables are run to completion first.
+pub struct CustomCheck {
+    pub name: String,
+    pub check: Py<PyAny>,
+    pub critical: bool,
+}
+
+impl CustomCheck {
+    fn call(&self) -> Result<(), String> {
+        Python::attach(|py| {
+            let mut result = self.check.bind(py).call0().map_err(|e| e.to_string())?;
+            if result.hasattr("__await__").unwrap_or(false) {
+                result = get_asyncio(py)
+                    .bind(py)
+                    .call_method1("run", (result,))
+                    .map_err(|e| e.to_string())?;
+            }
+            if result.is_none() || result.is_truthy().unwrap_or(false) {
+                Ok(())
+            } else {
+                Err("check returned a false value".to_string())
+            }
+        })
+    }
+}
+
+/// Set the custom checks of this process; inherited by forked workers
+pub fn install(checks: Vec<Arc<CustomCheck>>) {
+    *CHECKS.write() = checks;
+}
+
+struct Outcome {
+    name: String,
+    kind: &'static str,
+    critical: bool,
+    error: Option<String>,
+    latency: Duration,
+}
+
+struct Report {
+    at: Instant,
+    healthy: bool,
+    body: Value,
+}
+
+type Check = Pin<Box<dyn Future<Output = Outcome> + Send>>;
+
+async fn timed<F>(
+    name: String,
+    kind: &'static str,
+    critical: bool,
+    limit: Duration,
+    check: F,
+) -> Outcome
+where
+    F: Future<Output = Result<(), String>>,
+{
+    let started = Instant::now();
+    let error = match tokio::time::timeout(limit, check).await {
+        Ok(Ok(())) => None,
+        Ok(Err(err)) => Some(err),
+        Err(_) => Some(format!("timed out after {} ms", limit.as_millis())),
+    };
+    Outcome {
+        name,
+        kind,
+        critical,
+        error,
+        latency: started.elapsed(),
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 1_000_000.0).round() / 1000.0
+}
+
+async fn run(limit: Duration) -> Report {
+    let started = Instant::now();
+    let mut checks: Vec<Check> = Vec::new();
+    for alias in ConnectionPoolManager::aliases() {
+        let name = format!("postgres:{}", alias);
+        checks.push(Box::pin(timed(name, "pool", true, limit, async move {
+            ConnectionPoolManager::ping(&alias).await
+        })));
+    }
+    for check in CHECKS.read().iter().cloned() {
+        let name = check.name.clone();
+        let critical = check.critical;
+        checks.push(Box::pin(timed(
+            name,
+            "custom",
+            critical,
+            limit,
+            async move {
+                tokio::task::spawn_blocking(move || check.call())
+                    .await
+                    .map_err(|e| e.to_string())?
+            },
+        )));
+    }
+    let outcomes = futures_util::future::join_all(checks).await;
+
+    let healthy = outcomes.iter().all(|o| !o.critical || o.error.is_none());
+    let degraded = outcomes.iter().any(|o| o.error.is_some());
+    let mut results = Map::new();
+    for outcome in outcomes {
+        results.insert(
+            outcome.name,
+            json!({
+                "kind": outcome.kind,
+                "critical": outcome.critical,
+                "status": if outcome.error.is_none() { "pass" } else { "fail" },
+                "latency_ms": millis(outcome.latency),
+                "error": outcome.error,
+            }),
+        );
+    }
+    let status = match (healthy, degraded) {
+        (false, _) => "fail",
+        (true, true) => "degraded",
+        (true, false) => "pass",
+    };
+    Report {
+        at: Instant::now(),
+        healthy,
+        body: json!({
+            "status": status,
+            "duration_ms": millis(started.elapsed()),
+            "checks": results,
+        }),
+    }
+}
+
+/// Answer a deep probe, from the cache while it is fresh
+pub async fn respond(rm: &ReloadManager) -> axum::http::Response<Body> {
+    let interval = Duration::from_millis(rm.config().deep_check_interval_ms);
+    let limit = Duration::from_millis(rm.config().deep_check_timeout_ms);
+
+    // Requests arriving during a run wait for it rather than start another
+    let mut cache = CACHE.lock().await;
+    if cache
+        .as_ref()
+        .is_none_or(|report| report.at.elapsed() >= interval)
+    {
+        *cache = Some(run(limit).await);
+    }
+    let report = cache.as_ref().expect("deep health report stored above");
+
+    let mut body = report.body.clone();
+    body["process"] = json!(rm.health().status().as_str());
+    body["cache_age_secs"] = json!(millis(report.at.elapsed()) / 1000.0);
+    axum::http::Response::builder()
+        .status(if report.healthy { 200 } else { 503 })
+        .header(axum::http::header::CONTENT_TYPE, "application/json")
+        .header(axum::http::header::CACHE_CONTROL, "no-store")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
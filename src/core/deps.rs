@@ -0,0 +1,481 @@
+//! Named dependencies provided to handlers through `Request.dep()`.
+//!
+//! Factories are registered with `Server.provide(name, factory, scope)`.
+//! A factory's required parameters name the dependencies it needs, so
+//! `provide("repo", lambda db: Repo(db))` depends on `"db"`; dependencies must
+//! be provided first, which also rules out cycles at registration.
+//!
+//! App-scoped factories run once per worker at startup (coroutine factories
+//! are awaited on the worker's event loop) and are torn down when the worker
+//! stops. Request-scoped factories run lazily on first `dep()` in a request;
+//! their instances live in that request's slots and are torn down, newest
+//! first, when the request completes.
+
+use ahash::AHashMap;
+use parking_lot::{Mutex, RwLock};
+use pyo3::exceptions::{PyRuntimeError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyTuple};
+use std::sync::{Arc, OnceLock};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    App,
+    Request,
+}
+
+impl Scope {
+    fn parse(scope: &str) -> PyResult<Self> {
+        match scope {
+            "app" => Ok(Scope::App),
+            "request" => Ok(Scope::Request),
+            _ => Err(PyValueError::new_err(format!(
+                "Unknown dependency scope '{}': expected 'app' or 'request'",
+                scope
+            ))),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Scope::App => "app",
+            Scope::Request => "request",
+        }
+    }
+}
+
+/// A factory parameter filled from another provider
+#[derive(Clone, Copy)]
+struct Param {
+    provider: usize,
+    keyword: bool,
+}
+
+struct Provider {
+    name: String,
+    factory: Py<PyAny>,
+    scope: Scope,
+    params: Vec<Param>,
+    teardown: Option<Py<PyAny>>,
+    is_async: bool,
+    /// App-scoped instance for this worker
+    instance: Arc<OnceLock<Py<PyAny>>>,
+}
+
+#[derive(Default)]
+struct Registry {
+    providers: Vec<Provider>,
+    index: AHashMap<String, usize>,
+}
+
+static REGISTRY: OnceLock<RwLock<Registry>> = OnceLock::new();
+
+fn registry() -> &'static RwLock<Registry> {
+    REGISTRY.get_or_init(|| RwLock::new(Registry::default()))
+}
+
+/// What resolving a provider needs, copied out so no lock is held while
+/// Python code runs
+struct Snapshot {
+    name: String,
+    factory: Py<PyAny>,
+    scope: Scope,
+    params: Vec<Param>,
+    is_async: bool,
+    instance: Arc<OnceLock<Py<PyAny>>>,
+}
+
+fn snapshot(py: Python<'_>, index: usize) -> Snapshot {
+    let registry = registry().read();
+    let provider = &registry.providers[index];
+    Snapshot {
+        name: provider.name.clone(),
+        factory: provider.factory.clone_ref(py),
+        scope: provider.scope,
+        params: provider.params.clone(),
+        is_async: provider.is_async,
+        instance: provider.instance.clone(),
+    }
+}
+
+/// Index of a provider by name
+pub fn index_of(name: &str) -> Option<usize> {
+    registry().read().index.get(name).copied()
+}
+
+/// Register (or replace) a provider.
+pub fn provide(
+    py: Python<'_>,
+    name: String,
+    factory: Py<PyAny>,
+    scope: &str,
+    teardown: Option<Py<PyAny>>,
+) -> PyResult<()> {
+    let scope = Scope::parse(scope)?;
+    let bound = factory.bind(py);
+    if !bound.is_callable() {
+        return Err(PyTypeError::new_err(format!(
+            "Factory for dependency '{}' must be callable",
+            name
+        )));
+    }
+    let inspect = py.import("inspect")?;
+    let is_async = inspect
+        .call_method1("iscoroutinefunction", (bound,))?
+        .is_truthy()?;
+    if is_async && scope == Scope::Request {
+        return Err(PyTypeError::new_err(format!(
+            "Request-scoped dependency '{}' must have a synchronous factory",
+            name
+        )));
+    }
+    let wanted = factory_params(py, bound)?;
+
+    let mut registry = registry().write();
+    let mut params = Vec::with_capacity(wanted.len());
+    for (dep, keyword) in wanted {
+        let provider = *registry.index.get(&dep).ok_or_else(|| {
+            PyValueError::new_err(format!(
+                "Dependency '{}' requires '{}', which has not been provided",
+                name, dep
+            ))
+        })?;
+        if scope == Scope::App && registry.providers[provider].scope == Scope::Request {
+            return Err(PyValueError::new_err(format!(
+                "App-scoped dependency '{}' cannot depend on request-scoped '{}'",
+                name, dep
+            )));
+        }
+        params.push(Param { provider, keyword });
+    }
+
+    let existing = registry.index.get(&name).copied();
+    if let Some(index) = existing {
+        // Replacing a provider can close a cycle through its dependents
+        for param in &params {
+            if let Some(path) = path_to(&registry, param.provider, index) {
+                let mut names: Vec<&str> = vec![name.as_str()];
+                names.extend(path.iter().map(|&i| registry.providers[i].name.as_str()));
+                return Err(PyValueError::new_err(format!(
+                    "Circular dependency: {}",
+                    names.join(" -> ")
+                )));
+            }
+        }
+        if scope == Scope::Request {
+            if let Some(dependent) = registry.providers.iter().find(|p| {
+                p.scope == Scope::App && p.params.iter().any(|param| param.provider == index)
+            }) {
+                return Err(PyValueError::new_err(format!(
+                    "'{}' cannot become request-scoped: app-scoped '{}' depends on it",
+                    name, dependent.name
+                )));
+            }
+        }
+    }
+
+    let provider = Provider {
+        name: name.clone(),
+        factory,
+        scope,
+        params,
+        teardown,
+        is_async,
+        instance: Arc::new(OnceLock::new()),
+    };
+    match existing {
+        Some(index) => registry.providers[index] = provider,
+        None => {
+            let index = registry.providers.len();
+            registry.index.insert(name, index);
+            registry.providers.push(provider);
+        }
+    }
+    Ok(())
+}
+
+/// Required parameters of a factory: `(name, keyword_only)`
+fn factory_params(py: Python<'_>, factory: &Bound<'_, PyAny>) -> PyResult<Vec<(String, bool)>> {
+    let inspect = py.import("inspect")?;
+    let parameter = inspect.getattr("Parameter")?;
+    let empty = parameter.getattr("empty")?;
+    let keyword_only = parameter.getattr("KEYWORD_ONLY")?;
+    let variadic = [
+        parameter.getattr("VAR_POSITIONAL")?,
+        parameter.getattr("VAR_KEYWORD")?,
+    ];
+
+    let signature = match inspect.call_method1("signature", (factory,)) {
+        Ok(signature) => signature,
+        // Builtins without a signature take no dependencies
+        Err(_) => return Ok(Vec::new()),
+    };
+    let mut params = Vec::new();
+    for param in signature
+        .getattr("parameters")?
+        .call_method0("values")?
+        .try_iter()?
+    {
+        let param = param?;
+        let kind = param.getattr("kind")?;
+        if variadic.iter().any(|v| kind.eq(v).unwrap_or(false))
+            || !param.getattr("default")?.is(&empty)
+        {
+            continue;
+        }
+        params.push((param.getattr("name")?.extract()?, kind.eq(&keyword_only)?));
+    }
+    Ok(params)
+}
+
+/// Dependency path from `from` to `to` (inclusive), if any
+fn path_to(registry: &Registry, from: usize, to: usize) -> Option<Vec<usize>> {
+    if from == to {
+        return Some(vec![to]);
+    }
+    registry.providers[from].params.iter().find_map(|param| {
+        path_to(registry, param.provider, to).map(|mut path| {
+            path.insert(0, from);
+            path
+        })
+    })
+}
+
+/// Call a factory with its resolved dependencies
+fn call_factory(
+    py: Python<'_>,
+    snapshot: &Snapshot,
+    request: Option<&RequestDeps>,
+) -> PyResult<Py<PyAny>> {
+    let mut args = Vec::with_capacity(snapshot.params.len());
+    let kwargs = PyDict::new(py);
+    for param in &snapshot.params {
+        let name = registry().read().providers[param.provider].name.clone();
+        let value = resolve(py, param.provider, request)?;
+        if param.keyword {
+            kwargs.set_item(name, value)?;
+        } else {
+            args.push(value);
+        }
+    }
+    let args = PyTuple::new(py, args)?;
+    let result = snapshot.factory.bind(py).call(args, Some(&kwargs))?;
+    Ok(result.unbind())
+}
+
+/// Resolve a provider by index for the given request (if any)
+pub fn resolve(py: Python<'_>, index: usize, request: Option<&RequestDeps>) -> PyResult<Py<PyAny>> {
+    let snapshot = snapshot(py, index);
+    match snapshot.scope {
+        Scope::App => {
+            if let Some(instance) = snapshot.instance.get() {
+                return Ok(instance.clone_ref(py));
+            }
+            // Not started in a worker (e.g. used from a script): create lazily
+            let mut value = call_factory(py, &snapshot, None)?;
+            if snapshot.is_async {
+                value = run_coroutine(py, value.bind(py), None)?;
+            }
+            let _ = snapshot.instance.set(value);
+            Ok(snapshot.instance.get().unwrap().clone_ref(py))
+        }
+        Scope::Request => {
+            let request = request.ok_or_else(|| {
+                PyRuntimeError::new_err(format!(
+                    "Request-scoped dependency '{}' can only be resolved inside a request",
+                    snapshot.name
+                ))
+            })?;
+            if let Some(instance) = request.get(py, index, &snapshot.name)? {
+                return Ok(instance);
+            }
+            let value = call_factory(py, &snapshot, Some(request))?;
+            request.store(py, index, value)
+        }
+    }
+}
+
+/// Resolve a provider by name
+pub fn resolve_name(
+    py: Python<'_>,
+    name: &str,
+    request: Option<&RequestDeps>,
+) -> PyResult<Py<PyAny>> {
+    let index = index_of(name).ok_or_else(|| {
+        PyValueError::new_err(format!("Dependency '{}' has not been provided", name))
+    })?;
+    resolve(py, index, request)
+}
+
+fn run_coroutine(
+    py: Python<'_>,
+    coro: &Bound<'_, PyAny>,
+    event_loop: Option<&Bound<'_, PyAny>>,
+) -> PyResult<Py<PyAny>> {
+    match event_loop {
+        Some(event_loop) => Ok(event_loop
+            .call_method1("run_until_complete", (coro,))?
+            .unbind()),
+        None => {
+            let event_loop = py.import("asyncio")?.call_method0("new_event_loop")?;
+            let result = event_loop.call_method1("run_until_complete", (coro,));
+            event_loop.call_method0("close")?;
+            Ok(result?.unbind())
+        }
+    }
+}
+
+fn call_teardown(
+    py: Python<'_>,
+    index: usize,
+    instance: Py<PyAny>,
+    event_loop: Option<&Bound<'_, PyAny>>,
+) {
+    let (name, teardown) = {
+        let registry = registry().read();
+        let provider = &registry.providers[index];
+        (
+            provider.name.clone(),
+            provider.teardown.as_ref().map(|t| t.clone_ref(py)),
+        )
+    };
+    let Some(teardown) = teardown else {
+        return;
+    };
+    let result = teardown.call1(py, (instance,)).and_then(|result| {
+        let result = result.into_bound(py);
+        if result.hasattr("__await__")? {
+            run_coroutine(py, &result, event_loop)?;
+        }
+        Ok(())
+    });
+    if let Err(err) = result {
+        crate::hlog_error!("Teardown of dependency '{}' failed: {}", name, err);
+    }
+}
+
+/// Create every app-scoped dependency for this worker, in registration order.
+pub fn start_app_scope(py: Python<'_>, event_loop: &Bound<'_, PyAny>) -> PyResult<()> {
+    let count = registry().read().providers.len();
+    for index in 0..count {
+        let snapshot = snapshot(py, index);
+        if snapshot.scope != Scope::App || snapshot.instance.get().is_some() {
+            continue;
+        }
+        let mut value = call_factory(py, &snapshot, None)?;
+        if snapshot.is_async {
+            value = run_coroutine(py, value.bind(py), Some(event_loop))?;
+        }
+        let _ = snapshot.instance.set(value);
+    }
+    Ok(())
+}
+
+/// Tear down app-scoped dependencies, newest first.
+pub fn shutdown_app_scope(py: Python<'_>, event_loop: &Bound<'_, PyAny>) {
+    let instances: Vec<(usize, Py<PyAny>)> = {
+        let mut registry = registry().write();
+        registry
+            .providers
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, p)| {
+                let cell = std::mem::take(&mut p.instance);
+                Arc::try_unwrap(cell)
+                    .ok()
+                    .and_then(OnceLock::into_inner)
+                    .map(|instance| (index, instance))
+            })
+            .collect()
+    };
+    for (index, instance) in instances.into_iter().rev() {
+        call_teardown(py, index, instance, Some(event_loop));
+    }
+}
+
+#[derive(Default)]
+struct RequestSlots {
+    instances: Vec<Option<Py<PyAny>>>,
+    /// Provider indices in creation order, for teardown
+    created: Vec<usize>,
+    closed: bool,
+}
+
+/// Request-scoped instances for one request
+#[derive(Default)]
+pub struct RequestDeps {
+    slots: Mutex<RequestSlots>,
+}
+
+impl RequestDeps {
+    fn get(&self, py: Python<'_>, index: usize, name: &str) -> PyResult<Option<Py<PyAny>>> {
+        let slots = self.slots.lock();
+        if slots.closed {
+            return Err(PyRuntimeError::new_err(format!(
+                "Request-scoped dependency '{}' used after the request completed",
+                name
+            )));
+        }
+        Ok(slots
+            .instances
+            .get(index)
+            .and_then(|slot| slot.as_ref().map(|i| i.clone_ref(py))))
+    }
+
+    fn store(&self, py: Python<'_>, index: usize, value: Py<PyAny>) -> PyResult<Py<PyAny>> {
+        let mut slots = self.slots.lock();
+        debug_assert!(
+            !slots.closed,
+            "request-scoped instance stored after teardown"
+        );
+        if slots.instances.len() <= index {
+            slots.instances.resize_with(index + 1, || None);
+        }
+        if let Some(existing) = &slots.instances[index] {
+            // A factory resolved itself re-entrantly; keep the first instance
+            return Ok(existing.clone_ref(py));
+        }
+        slots.instances[index] = Some(value.clone_ref(py));
+        slots.created.push(index);
+        Ok(value)
+    }
+
+    /// Tear down this request's instances, newest first. Runs once.
+    pub fn teardown(&self) {
+        let (created, mut instances) = {
+            let mut slots = self.slots.lock();
+            if slots.closed {
+                return;
+            }
+            slots.closed = true;
+            (
+                std::mem::take(&mut slots.created),
+                std::mem::take(&mut slots.instances),
+            )
+        };
+        if created.is_empty() {
+            return;
+        }
+        Python::attach(|py| {
+            for index in created.into_iter().rev() {
+                if let Some(instance) = instances[index].take() {
+                    call_teardown(py, index, instance, None);
+                }
+            }
+        });
+        debug_assert!(
+            instances.iter().all(Option::is_none),
+            "request-scoped instance outlived its request"
+        );
+    }
+}
+
+/// Names and scopes of provided dependencies, in registration order.
+pub fn providers() -> Vec<(String, &'static str)> {
+    registry()
+        .read()
+        .providers
+        .iter()
+        .map(|p| (p.name.clone(), p.scope.as_str()))
+        .collect()
+}
@@ -83,6 +83,10 @@ pub fn set_global_runtime(
         .expect("Global runtime already set");
 }
 
+pub(crate) fn try_global_runtime() -> Option<Arc<RuntimeWrapper>> {
+    RUNTIME.get().cloned()
+}
+
 pub(crate) fn get_global_runtime() -> Arc<RuntimeWrapper> {
     RUNTIME
         .get()
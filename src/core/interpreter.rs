@@ -1,16 +1,31 @@
+use crate::core::deadline;
 use crate::core::global::{get_asyncio, get_global_runtime};
+use crate::core::request_scope::{self, RequestScope};
+use crate::core::watchdog;
+use crate::http::error_envelope::{self, ErrorCode};
 use crate::http::request::Request;
 use crate::http::response::{Response, ResponseSlot};
+use crate::http::timing::HandlerTiming;
 use crate::memory::arena::reset_arena;
-use crate::runtime::future_into_py;
+use crate::memory::debug::{self as memory_debug, RequestAudit};
+use crate::routing::inject::ArgPlan;
+use crate::runtime::{future_into_py, HandlerCall};
 use dashmap::DashMap;
 use pyo3::prelude::*;
 use pyo3::types::PyTuple;
 use pyo3::IntoPyObjectExt;
-use std::sync::OnceLock;
+use std::cell::Cell;
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
 
 static HANDLER_REGISTRY: OnceLock<DashMap<u64, (Py<PyAny>, bool)>> = OnceLock::new();
 
+thread_local! {
+    // When the handler on this Python thread was picked up; the args builder
+    // and completion callback of one dispatch run on the same thread
+    static HANDLER_STARTED: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
 pub fn register_handler(hash: u64, handler: Py<PyAny>) {
     let is_async = Python::attach(|py| {
         let inspect = get_asyncio(py).bind(py);
@@ -40,7 +55,14 @@ fn get_handler(py: Python, route_hash: u64) -> Option<Py<PyAny>> {
     registry.get(&route_hash).map(|entry| entry.0.clone_ref(py))
 }
 
-pub async fn http_execute(route_hash: u64, request: Request) -> axum::response::Response {
+/// Run the handler for `route_hash`, with its arguments filled by `args`
+/// when it has a plan; the timing is `None` when no handler is registered or
+/// a path or query value does not convert
+pub async fn http_execute(
+    route_hash: u64,
+    request: Request,
+    args: Option<Arc<ArgPlan>>,
+) -> (axum::response::Response, Option<HandlerTiming>) {
     let response_slot = ResponseSlot::new();
     let (tx, rx) = tokio::sync::oneshot::channel();
 
@@ -51,21 +73,69 @@ pub async fn http_execute(route_hash: u64, request: Request) -> axum::response::
             crate::hlog_warn!("No handler found for hash: {}", route_hash);
             response_slot.set_status(404);
             response_slot.set_body(b"Not Found".to_vec());
-            return response_slot.into_response();
+            return (response_slot.into_response(), None);
+        }
+    };
+
+    // Converted before dispatch so a bad value never reaches Python
+    let bound = match args.as_ref().map(|plan| plan.bind(&request)).transpose() {
+        Ok(bound) => bound,
+        Err(message) => {
+            let accept = request.header("accept");
+            return (
+                error_envelope::response(
+                    axum::http::StatusCode::BAD_REQUEST,
+                    ErrorCode::BadRequest,
+                    message,
+                    accept.as_deref(),
+                ),
+                None,
+            );
         }
     };
 
-    let response = Response::new(response_slot.clone());
+    let scope = request.scope().unwrap_or_else(|| {
+        let scope = Arc::new(RequestScope::from_request(&request));
+        request.set_scope(scope.clone());
+        scope
+    });
+
+    // Database sessions and executors find the deadline by request ID
+    let deadline_request_id = request.deadline().map(|d| {
+        deadline::register(&scope.request_id, d);
+        scope.request_id.clone()
+    });
+
+    let watched = watchdog::dispatched(&scope.request_id, request.method_name(), request.path());
+    let deps = request.deps();
+    let handler_deps = deps.clone();
+    let handler_slot = response_slot.clone();
+    let disconnect = request.disconnect();
+    let handler_disconnect = disconnect.clone();
+    let response = Response::new(response_slot.clone()).with_fields(request.response_fields());
     let rt_ref = get_global_runtime().handler();
+    // Off unless Server(memory_debug=True); dropped with the response body
+    let audit =
+        memory_debug::enabled().then(|| RequestAudit::new(&scope.request_id, request.path()));
+    let handler_audit = audit.clone();
+    let complete_audit = audit.clone();
 
     // Direct call to blocking runner - minimized GIL scope
+    let dispatched = Instant::now();
     future_into_py(
         &rt_ref,
         is_async,
+        disconnect.clone(),
         move |py| {
             // This closure runs under GIL - minimize work here
+            HANDLER_STARTED.set(Some(Instant::now()));
             let handler = get_handler(py, route_hash).expect("Handler must exist");
 
+            // Publish hypern.context for the handler; cleared in on_complete
+            request_scope::enter(py, &scope);
+            // A disconnect from here on expires the request's deadline
+            handler_disconnect.handler_started(&scope.request_id);
+
             // Use raw PyO3 API to avoid intermediate conversions
             let req_any = request
                 .into_bound_py_any(py)
@@ -75,29 +145,85 @@ pub async fn http_execute(route_hash: u64, request: Request) -> axum::response::
                 .into_bound_py_any(py)
                 .expect("Failed to convert response")
                 .unbind();
+            let audited = handler_audit
+                .as_ref()
+                .map(|_| (req_any.clone_ref(py), res_any.clone_ref(py)));
 
             // Create tuple using raw C API for speed - avoids PyTuple::new allocation overhead
-            unsafe {
+            let target = unsafe {
                 let tuple = pyo3::ffi::PyTuple_New(2);
-                pyo3::ffi::PyTuple_SetItem(tuple, 0, req_any.into_ptr());
-                pyo3::ffi::PyTuple_SetItem(tuple, 1, res_any.into_ptr());
+                pyo3::ffi::PyTuple_SetItem(tuple, 0, req_any.clone_ref(py).into_ptr());
+                pyo3::ffi::PyTuple_SetItem(tuple, 1, res_any.clone_ref(py).into_ptr());
                 // Safety: we just created a valid tuple above
-                let args = <pyo3::Bound<'_, PyTuple> as Clone>::clone(
+                <pyo3::Bound<'_, PyTuple> as Clone>::clone(
                     &Bound::from_owned_ptr(py, tuple).cast::<PyTuple>().unwrap(),
                 )
-                .unbind();
-                (handler, args)
+                .unbind()
+            };
+            if let (Some(audit), Some((req, res))) = (&handler_audit, audited) {
+                audit.handler_started(py, req, res);
             }
+            let (args, kwargs) = match (&args, bound) {
+                (Some(plan), Some(bound)) => {
+                    match plan.kwargs(py, bound, &req_any, &res_any, &handler_deps) {
+                        Ok(kwargs) => (PyTuple::empty(py).unbind(), Some(kwargs.unbind())),
+                        // A dependency failed to resolve; the handler is not called
+                        Err(err) => {
+                            err.print(py);
+                            handler_slot.set_status(500);
+                            handler_slot.set_body(b"Internal Server Error".to_vec());
+                            handler_slot.mark_ready();
+                            return None;
+                        }
+                    }
+                }
+                _ => (target.clone_ref(py), None),
+            };
+            Some(HandlerCall {
+                handler,
+                args,
+                kwargs,
+                target,
+            })
         },
         move || {
+            let finished = Instant::now();
+            watchdog::finished(watched);
+            request_scope::exit();
+            if let Some(request_id) = &deadline_request_id {
+                deadline::release(request_id);
+            }
+            disconnect.handler_finished();
+            deps.teardown();
+            if let Some(audit) = complete_audit {
+                audit.handler_finished();
+            }
             // Reset the thread-local arena after each request
             reset_arena();
-            let _ = tx.send(());
+            let started = HANDLER_STARTED.take().unwrap_or(finished);
+            let _ = tx.send((started, finished));
         },
     );
 
-    // Wait for completion via oneshot
-    let _ = rx.await;
+    // Wait for completion via oneshot. A streaming body goes out as soon as
+    // the handler starts it, so the client gets the status and headers while
+    // the handler may still be producing data; the handler has no timing then.
+    let timing = tokio::select! {
+        biased;
+        done = rx => done.ok().map(|(started, finished)| HandlerTiming {
+            dispatched,
+            started,
+            finished,
+        }),
+        _ = response_slot.stream_started() => {
+            watchdog::streaming(watched);
+            None
+        }
+    };
 
-    response_slot.into_response()
+    let response = response_slot.into_response();
+    match audit {
+        Some(audit) => (memory_debug::hold_until_sent(response, audit), timing),
+        None => (response, timing),
+    }
 }
@@ -0,0 +1,648 @@
+//! Settings that change while the server runs.
+//!
+//! `Server.update_config()` takes a dict of the keys in `HOT_KEYS` and
+//! refuses anything else. An update is validated as a whole before any of it
+//! is applied. The calling process applies it at once and publishes the
+//! merged settings to a shared-memory slot mapped before the workers fork;
+//! each worker compares the slot's generation with the one it last applied
+//! when a request arrives and catches up when it is behind. A call from any
+//! worker's handler therefore reaches every worker, including workers
+//! respawned by a reload.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::RwLock;
+use serde_json::{Map, Value};
+
+use crate::http::admission;
+use crate::logging::access::AccessFormat;
+use crate::logging::{LogLevel, LogQueue};
+use crate::middleware::MiddlewareChain;
+use crate::routing::flags::{self, FlagUpdate, StickyBy};
+
+/// Keys `update_config` accepts
+pub const HOT_KEYS: [&str; 10] = [
+    "log_level",
+    "log_format",
+    "log_skip_paths",
+    "slow_threshold_ms",
+    "max_in_flight",
+    "rate_limit",
+    "max_connections",
+    "max_connections_per_ip",
+    "header_read_timeout",
+    "feature_flags",
+];
+
+const RATE_LIMIT_KEYS: [&str; 3] = ["max_requests", "window_secs", "skip_paths"];
+
+const FLAG_KEYS: [&str; 4] = ["enabled", "percentage", "sticky_by", "off_status"];
+
+const LOG_LEVELS: [&str; 6] = ["trace", "debug", "info", "warn", "error", "off"];
+
+/// New limits for the rate-limit middleware; None keeps the current value
+#[derive(Clone, Debug, Default)]
+pub struct RateLimitUpdate {
+    pub max_requests: Option<u32>,
+    pub window: Option<Duration>,
+    pub skip_paths: Option<Vec<String>>,
+}
+
+/// A validated update. Each field is None when the update leaves it alone;
+/// the inner Option of a clearable setting is None to clear it.
+#[derive(Clone, Default)]
+pub struct LiveSettings {
+    pub log_level: Option<LogLevel>,
+    pub log_format: Option<Option<AccessFormat>>,
+    pub log_skip_paths: Option<Vec<String>>,
+    pub slow_threshold_ms: Option<Option<u64>>,
+    pub max_in_flight: Option<Option<u64>>,
+    pub rate_limit: Option<RateLimitUpdate>,
+    pub max_connections: Option<Option<usize>>,
+    pub max_connections_per_ip: Option<Option<usize>>,
+    pub header_read_timeout: Option<Option<Duration>>,
+    /// Flags by name; None turns a flag off
+    pub feature_flags: Option<Vec<(String, Option<FlagUpdate>)>>,
+}
+
+impl LiveSettings {
+    /// Validate `update`, listing every problem found
+    pub fn parse(update: &Map<String, Value>) -> Result<Self, String> {
+        let unknown: Vec<String> = update
+            .keys()
+            .filter(|key| !HOT_KEYS.contains(&key.as_str()))
+            .map(|key| format!("'{}'", key))
+            .collect();
+        if !unknown.is_empty() {
+            return Err(format!(
+                "Not hot-reloadable: {} (hot-reloadable settings: {})",
+                unknown.join(", "),
+                HOT_KEYS.join(", ")
+            ));
+        }
+
+        let mut settings = Self::default();
+        let mut problems = Vec::new();
+        for (key, value) in update {
+            let parsed = match key.as_str() {
+                "log_level" => parse_level(value).map(|level| settings.log_level = Some(level)),
+                "log_format" => {
+                    parse_format(value).map(|format| settings.log_format = Some(format))
+                }
+                "log_skip_paths" => {
+                    parse_paths(value).map(|paths| settings.log_skip_paths = Some(paths))
+                }
+                "slow_threshold_ms" => {
+                    parse_optional_count(value).map(|ms| settings.slow_threshold_ms = Some(ms))
+                }
+                "max_in_flight" => parse_optional_count(value)
+                    .and_then(|cap| match cap {
+                        Some(0) => Err("expected a positive integer or None".to_string()),
+                        cap => Ok(cap),
+                    })
+                    .map(|cap| settings.max_in_flight = Some(cap)),
+                "rate_limit" => {
+                    parse_rate_limit(value).map(|update| settings.rate_limit = Some(update))
+                }
+                "max_connections" => {
+                    parse_limit(value).map(|max| settings.max_connections = Some(max))
+                }
+                "max_connections_per_ip" => {
+                    parse_limit(value).map(|max| settings.max_connections_per_ip = Some(max))
+                }
+                "header_read_timeout" => {
+                    parse_timeout(value).map(|timeout| settings.header_read_timeout = Some(timeout))
+                }
+                "feature_flags" => {
+                    parse_flags(value).map(|flags| settings.feature_flags = Some(flags))
+                }
+                _ => unreachable!("checked against HOT_KEYS"),
+            };
+            if let Err(problem) = parsed {
+                problems.push(format!("{}: {}", key, problem));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(settings)
+        } else {
+            Err(format!(
+                "Invalid runtime config ({} problem{}):\n  - {}",
+                problems.len(),
+                if problems.len() == 1 { "" } else { "s" },
+                problems.join("\n  - ")
+            ))
+        }
+    }
+
+    fn changes_logging(&self) -> bool {
+        self.log_level.is_some() || self.log_format.is_some() || self.log_skip_paths.is_some()
+    }
+}
+
+fn parse_level(value: &Value) -> Result<LogLevel, String> {
+    match value.as_str() {
+        Some(level) if LOG_LEVELS.contains(&level.to_lowercase().as_str()) => {
+            Ok(LogLevel::from_str(level))
+        }
+        _ => Err(format!("expected one of: {}", LOG_LEVELS.join(", "))),
+    }
+}
+
+fn parse_format(value: &Value) -> Result<Option<AccessFormat>, String> {
+    match value {
+        Value::Null => Ok(None),
+        Value::String(template) => AccessFormat::parse(template).map(Some),
+        _ => Err("expected a format string or None".to_string()),
+    }
+}
+
+fn parse_paths(value: &Value) -> Result<Vec<String>, String> {
+    let paths: Option<Vec<String>> = value.as_array().and_then(|items| {
+        items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .filter(|p| p.starts_with('/'))
+                    .map(str::to_string)
+            })
+            .collect()
+    });
+    paths.ok_or_else(|| "expected a list of paths starting with '/'".to_string())
+}
+
+fn parse_optional_count(value: &Value) -> Result<Option<u64>, String> {
+    match value {
+        Value::Null => Ok(None),
+        value => value
+            .as_u64()
+            .map(Some)
+            .ok_or_else(|| "expected a non-negative integer or None".to_string()),
+    }
+}
+
+fn parse_limit(value: &Value) -> Result<Option<usize>, String> {
+    match value {
+        Value::Null => Ok(None),
+        value => value
+            .as_u64()
+            .filter(|max| *max > 0)
+            .map(|max| Some(max as usize))
+            .ok_or_else(|| "expected a positive integer or None".to_string()),
+    }
+}
+
+fn parse_timeout(value: &Value) -> Result<Option<Duration>, String> {
+    match value {
+        Value::Null => Ok(None),
+        value => value
+            .as_f64()
+            .filter(|secs| secs.is_finite() && *secs > 0.0)
+            .map(|secs| Some(Duration::from_secs_f64(secs)))
+            .ok_or_else(|| "expected a positive number of seconds or None".to_string()),
+    }
+}
+
+fn parse_rate_limit(value: &Value) -> Result<RateLimitUpdate, String> {
+    let Some(fields) = value.as_object() else {
+        return Err(format!(
+            "expected a dict with any of: {}",
+            RATE_LIMIT_KEYS.join(", ")
+        ));
+    };
+    let mut update = RateLimitUpdate::default();
+    for (key, value) in fields {
+        match key.as_str() {
+            "max_requests" => {
+                let max = value
+                    .as_u64()
+                    .and_then(|max| u32::try_from(max).ok())
+                    .filter(|max| *max > 0);
+                update.max_requests = Some(max.ok_or("max_requests: expected a positive integer")?);
+            }
+            "window_secs" => {
+                let secs = value.as_u64().filter(|secs| *secs > 0);
+                update.window = Some(Duration::from_secs(
+                    secs.ok_or("window_secs: expected a positive integer")?,
+                ));
+            }
+            "skip_paths" => {
+                update.skip_paths =
+                    Some(parse_paths(value).map_err(|e| format!("skip_paths: {}", e))?)
+            }
+            _ => {
+                return Err(format!(
+                    "unknown key '{}' (expected any of: {})",
+                    key,
+                    RATE_LIMIT_KEYS.join(", ")
+                ))
+            }
+        }
+    }
+    Ok(update)
+}
+
+fn parse_flags(value: &Value) -> Result<Vec<(String, Option<FlagUpdate>)>, String> {
+    let Some(specs) = value.as_object() else {
+        return Err("expected a dict of flag name to settings".to_string());
+    };
+    specs
+        .iter()
+        .map(|(name, spec)| {
+            let update = parse_flag(spec).map_err(|e| format!("'{}': {}", name, e))?;
+            Ok((name.clone(), update))
+        })
+        .collect()
+}
+
+/// A flag's settings: True/False, a percentage, None (off) or a dict of
+/// `FLAG_KEYS`
+fn parse_flag(spec: &Value) -> Result<Option<FlagUpdate>, String> {
+    let percentage = |value: &Value| {
+        value
+            .as_f64()
+            .filter(|p| (0.0..=100.0).contains(p))
+            .map(|p| p.round() as u8)
+            .ok_or_else(|| "percentage: expected a number from 0 to 100".to_string())
+    };
+    let fields = match spec {
+        Value::Null => return Ok(None),
+        Value::Bool(on) => {
+            return Ok(Some(FlagUpdate {
+                percentage: Some(if *on { 100 } else { 0 }),
+                ..FlagUpdate::default()
+            }))
+        }
+        Value::Number(_) => {
+            return Ok(Some(FlagUpdate {
+                percentage: Some(percentage(spec)?),
+                ..FlagUpdate::default()
+            }))
+        }
+        Value::Object(fields) => fields,
+        _ => return Err("expected True, False, a percentage, None or a dict".to_string()),
+    };
+    let mut update = FlagUpdate::default();
+    for (key, value) in fields {
+        match key.as_str() {
+            "enabled" => {
+                let on = value.as_bool().ok_or("enabled: expected True or False")?;
+                // An explicit percentage wins over enabled
+                update.percentage.get_or_insert(if on { 100 } else { 0 });
+            }
+            "percentage" => update.percentage = Some(percentage(value)?),
+            "sticky_by" => {
+                update.sticky_by = Some(
+                    value
+                        .as_str()
+                        .and_then(StickyBy::parse)
+                        .ok_or("sticky_by: expected 'user_id', 'client_ip' or 'request_id'")?,
+                )
+            }
+            "off_status" => {
+                update.off_status = Some(
+                    value
+                        .as_u64()
+                        .and_then(|status| u16::try_from(status).ok())
+                        .filter(|status| flags::OFF_STATUSES.contains(status))
+                        .ok_or("off_status: expected 404 or 503")?,
+                )
+            }
+            _ => {
+                return Err(format!(
+                    "unknown key '{}' (expected any of: {})",
+                    key,
+                    FLAG_KEYS.join(", ")
+                ))
+            }
+        }
+    }
+    Ok(Some(update))
+}
+
+// ---------------------------------------------------------------------------
+// Process state
+// ---------------------------------------------------------------------------
+
+/// Middleware chain of the server serving in this process
+static CHAIN: RwLock<Option<Arc<MiddlewareChain>>> = RwLock::new(None);
+
+/// Requests a worker serves at once before answering 503 (0 for no cap)
+static MAX_IN_FLIGHT: AtomicU64 = AtomicU64::new(0);
+
+/// Generation of the shared settings this process has applied
+static APPLIED: AtomicU64 = AtomicU64::new(0);
+
+/// Generations of updates made in a process without a shared slot
+static LOCAL_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Make `chain` the one updates reconfigure, and map the shared slot
+/// before workers fork
+pub fn install(chain: Arc<MiddlewareChain>) {
+    *CHAIN.write() = Some(chain);
+    shared::slot();
+}
+
+/// Whether a worker already serving `in_flight` requests is at the
+/// `max_in_flight` cap
+#[inline]
+pub fn at_capacity(in_flight: u64) -> bool {
+    let cap = MAX_IN_FLIGHT.load(Ordering::Relaxed);
+    cap != 0 && in_flight >= cap
+}
+
+/// Apply settings published by another process, if any are newer than the
+/// ones this process has
+#[inline]
+pub fn sync() {
+    let Some(slot) = shared::slot() else {
+        return;
+    };
+    if slot.generation() != APPLIED.load(Ordering::Acquire) {
+        catch_up(slot);
+    }
+}
+
+#[cold]
+fn catch_up(slot: &shared::Slot) {
+    let (generation, merged) = slot.read();
+    // Requests racing here apply the same settings; applying twice is harmless
+    if APPLIED.swap(generation, Ordering::AcqRel) == generation {
+        return;
+    }
+    match LiveSettings::parse(&merged) {
+        Ok(settings) => {
+            apply(&settings);
+            crate::hlog_debug!("Applied runtime config generation {}", generation);
+        }
+        Err(err) => {
+            crate::hlog_error!("Ignoring runtime config generation {}: {}", generation, err)
+        }
+    }
+}
+
+/// Validate and apply `update` here, then publish it to the other workers.
+/// Returns the new config generation.
+pub fn update(update: Map<String, Value>, source: &str) -> Result<u64, String> {
+    let settings = LiveSettings::parse(&update)?;
+    let generation = match shared::slot() {
+        Some(slot) => slot.publish(&update)?,
+        None => LOCAL_GENERATION.fetch_add(1, Ordering::AcqRel) + 1,
+    };
+    apply(&settings);
+    if shared::slot().is_some() {
+        APPLIED.fetch_max(generation, Ordering::AcqRel);
+    }
+
+    let changes: Vec<String> = update
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect();
+    crate::hlog_warn!(
+        "Runtime config generation {} applied by {} (pid {}): {}",
+        generation,
+        source,
+        std::process::id(),
+        changes.join(", ")
+    );
+    Ok(generation)
+}
+
+fn apply(settings: &LiveSettings) {
+    if settings.changes_logging() {
+        if let Some(mut config) = LogQueue::config() {
+            if let Some(level) = settings.log_level {
+                config.level = level;
+            }
+            if let Some(format) = &settings.log_format {
+                config.format = format.clone();
+            }
+            if let Some(paths) = &settings.log_skip_paths {
+                config.skip_paths = paths.clone();
+            }
+            LogQueue::update_config(config);
+        }
+    }
+    if let Some(cap) = settings.max_in_flight {
+        MAX_IN_FLIGHT.store(cap.unwrap_or(0), Ordering::Relaxed);
+    }
+    if let Some(max) = settings.max_connections {
+        admission::set_max_connections(max);
+    }
+    if let Some(max) = settings.max_connections_per_ip {
+        admission::set_max_per_ip(max);
+    }
+    if let Some(timeout) = settings.header_read_timeout {
+        admission::set_header_read_timeout(timeout);
+    }
+    for (name, update) in settings.feature_flags.iter().flatten() {
+        flags::set(name, update.as_ref());
+    }
+    if let Some(chain) = CHAIN.read().as_ref() {
+        chain.reconfigure(settings);
+    }
+}
+
+/// Settings as they stand in this process
+pub fn current() -> Map<String, Value> {
+    let mut current = Map::new();
+    if let Some(config) = LogQueue::config() {
+        current.insert(
+            "log_level".into(),
+            config.level.as_str().to_lowercase().into(),
+        );
+        current.insert(
+            "log_format".into(),
+            config.format.map(|f| f.template().to_string()).into(),
+        );
+        current.insert("log_skip_paths".into(), config.skip_paths.into());
+    }
+    let cap = MAX_IN_FLIGHT.load(Ordering::Relaxed);
+    current.insert("max_in_flight".into(), (cap != 0).then_some(cap).into());
+    current.insert(
+        "max_connections".into(),
+        admission::max_connections().into(),
+    );
+    current.insert(
+        "max_connections_per_ip".into(),
+        admission::max_connections_per_ip().into(),
+    );
+    current.insert(
+        "header_read_timeout".into(),
+        admission::header_read_timeout()
+            .map(|timeout| timeout.as_secs_f64())
+            .into(),
+    );
+    current.insert("feature_flags".into(), flags::current().into());
+    if let Some(chain) = CHAIN.read().as_ref() {
+        current.insert(
+            "slow_threshold_ms".into(),
+            chain
+                .slow_threshold()
+                .map(|threshold| threshold.as_millis() as u64)
+                .into(),
+        );
+    }
+    current
+}
+
+// ---------------------------------------------------------------------------
+// Shared slot
+// ---------------------------------------------------------------------------
+
+#[cfg(unix)]
+mod shared {
+    use std::cell::UnsafeCell;
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+    use std::sync::OnceLock;
+
+    use serde_json::{Map, Value};
+
+    /// Room for the merged settings as JSON
+    const SLOT_BYTES: usize = 64 * 1024;
+
+    /// Lives in a `MAP_SHARED` mapping, so every forked worker sees the
+    /// same memory. Writers and readers hold `lock` only to copy the bytes.
+    #[repr(C)]
+    pub struct Slot {
+        generation: AtomicU64,
+        lock: AtomicU64,
+        len: AtomicUsize,
+        data: UnsafeCell<[u8; SLOT_BYTES]>,
+    }
+
+    // Access to `data` is serialized by `lock`
+    unsafe impl Sync for Slot {}
+
+    struct SlotGuard<'a>(&'a Slot);
+
+    impl Drop for SlotGuard<'_> {
+        fn drop(&mut self) {
+            self.0.lock.store(0, Ordering::Release);
+        }
+    }
+
+    static SLOT: OnceLock<Option<&'static Slot>> = OnceLock::new();
+
+    /// The shared slot, mapped on first use (None if mapping failed)
+    pub fn slot() -> Option<&'static Slot> {
+        *SLOT.get_or_init(|| {
+            // SAFETY: a fresh anonymous mapping, zero-filled by the kernel,
+            // which is a valid empty `Slot`; it is never unmapped
+            let ptr = unsafe {
+                libc::mmap(
+                    std::ptr::null_mut(),
+                    std::mem::size_of::<Slot>(),
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+                    -1,
+                    0,
+                )
+            };
+            if ptr == libc::MAP_FAILED {
+                crate::hlog_warn!(
+                    "Could not map shared runtime config; updates stay in the calling worker: {}",
+                    std::io::Error::last_os_error()
+                );
+                return None;
+            }
+            Some(unsafe { &*(ptr as *const Slot) })
+        })
+    }
+
+    impl Slot {
+        pub fn generation(&self) -> u64 {
+            self.generation.load(Ordering::Acquire)
+        }
+
+        fn lock(&self) -> SlotGuard<'_> {
+            let mut spins = 0u32;
+            while self
+                .lock
+                .compare_exchange_weak(0, 1, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+            {
+                spins += 1;
+                if spins.is_multiple_of(64) {
+                    std::thread::yield_now();
+                } else {
+                    std::hint::spin_loop();
+                }
+            }
+            SlotGuard(self)
+        }
+
+        fn merged(&self, _guard: &SlotGuard<'_>) -> Map<String, Value> {
+            let len = self.len.load(Ordering::Relaxed);
+            // SAFETY: the guard excludes writers
+            let data = unsafe { &(&*self.data.get())[..len] };
+            match serde_json::from_slice(data) {
+                Ok(Value::Object(map)) => map,
+                _ => Map::new(),
+            }
+        }
+
+        /// The current generation and merged settings
+        pub fn read(&self) -> (u64, Map<String, Value>) {
+            let guard = self.lock();
+            (self.generation(), self.merged(&guard))
+        }
+
+        /// Merge `update` into the shared settings; returns the new generation
+        pub fn publish(&self, update: &Map<String, Value>) -> Result<u64, String> {
+            let guard = self.lock();
+            let mut merged = self.merged(&guard);
+            for (key, value) in update {
+                match (merged.get_mut(key), value) {
+                    // Rate-limit fields and flags not named in the update keep their value
+                    (Some(Value::Object(current)), Value::Object(fields)) => {
+                        current.extend(fields.clone());
+                    }
+                    _ => {
+                        merged.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            let bytes = Value::Object(merged).to_string().into_bytes();
+            if bytes.len() > SLOT_BYTES {
+                return Err(format!(
+                    "Runtime config is {} bytes as JSON, over the {}-byte limit",
+                    bytes.len(),
+                    SLOT_BYTES
+                ));
+            }
+            // SAFETY: the guard excludes readers and other writers
+            unsafe { (&mut *self.data.get())[..bytes.len()].copy_from_slice(&bytes) };
+            self.len.store(bytes.len(), Ordering::Relaxed);
+            Ok(self.generation.fetch_add(1, Ordering::AcqRel) + 1)
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod shared {
+    use serde_json::{Map, Value};
+
+    pub enum Slot {}
+
+    impl Slot {
+        pub fn generation(&self) -> u64 {
+            match *self {}
+        }
+
+        pub fn read(&self) -> (u64, Map<String, Value>) {
+            match *self {}
+        }
+
+        pub fn publish(&self, _update: &Map<String, Value>) -> Result<u64, String> {
+            match *self {}
+        }
+    }
+
+    /// Workers are not forked here; updates stay in this process
+    pub fn slot() -> Option<&'static Slot> {
+        None
+    }
+}
@@ -1,12 +1,44 @@
 pub mod blocking;
 pub mod blocking_executor;
 pub mod context;
+pub mod deadline;
+pub mod deep_health;
+pub mod deps;
+pub mod describe;
 pub mod global;
 pub mod interpreter;
+pub mod live_config;
 pub mod multiprocess;
 pub mod reload;
+pub mod request_id;
+pub mod request_scope;
 pub mod runtime;
 pub mod server;
 pub mod socket;
 pub mod tasks;
+pub mod test_client;
+pub mod warmup;
+pub mod watchdog;
 pub mod worker;
+
+use pyo3::prelude::*;
+
+/// Register server, DI, task and reload classes.
+pub fn register_all(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<server::Server>()?;
+    m.add_class::<context::Context>()?;
+    m.add_class::<context::DIContainer>()?;
+    m.add_class::<tasks::TaskExecutor>()?;
+    m.add_class::<tasks::TaskResult>()?;
+    m.add_class::<tasks::TaskStatus>()?;
+    m.add_class::<blocking_executor::BlockingExecutor>()?;
+    m.add_class::<reload::PyHealthCheck>()?;
+    m.add_class::<reload::PyReloadConfig>()?;
+    m.add_class::<reload::PyReloadManager>()?;
+    m.add_class::<test_client::TestClient>()?;
+    m.add_class::<test_client::TestResponse>()?;
+    request_id::register(m)?;
+    request_scope::register(m)?;
+    socket::register(m)?;
+    Ok(())
+}
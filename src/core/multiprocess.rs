@@ -4,14 +4,25 @@ use std::sync::Arc;
 use crate::core::reload::ReloadManager;
 use crate::middleware::MiddlewareChain;
 use crate::routing::router::Router;
+#[cfg(unix)]
+use crate::socket::BindFailure;
 use crate::socket::SocketHeld;
 
-/// Spawn worker processes using fork() - Now uses Axum
+/// How long `spawn_workers` waits for every worker to open its listener
+#[cfg(unix)]
+const LISTENER_REPORT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Spawn worker processes using fork() - Now uses Axum. `worker_ids` are
+/// the workers' indexes, `0..n` at startup.
+///
+/// Returns once every worker has opened its listener. If one could not (the
+/// port was taken after the parent's check, say), the workers are stopped
+/// and its error is returned.
 #[cfg(unix)]
 pub fn spawn_workers(
     py: Python<'_>,
     socket_held: SocketHeld,
-    num_workers: usize,
+    worker_ids: std::ops::Range<usize>,
     worker_threads: usize,
     max_blocking_threads: usize,
     max_connections: usize,
@@ -19,12 +30,23 @@ pub fn spawn_workers(
     middleware: Arc<MiddlewareChain>,
     handlers: Vec<(u64, Py<PyAny>)>,
     reload_manager: ReloadManager,
-) -> Vec<libc::pid_t> {
+    cpu_affinity: &[Vec<usize>],
+) -> PyResult<Vec<libc::pid_t>> {
     use std::process;
 
-    let mut child_pids = Vec::with_capacity(num_workers);
+    let mut child_pids = Vec::with_capacity(worker_ids.len());
+
+    // Each worker writes one line here once its listener is open or failed
+    let mut report_fds: [libc::c_int; 2] = [0; 2];
+    if unsafe { libc::pipe(report_fds.as_mut_ptr()) } != 0 {
+        return Err(pyo3::exceptions::PyOSError::new_err(format!(
+            "Failed to create the worker startup pipe: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    let [report_read, report_write] = report_fds;
 
-    for worker_id in 0..num_workers {
+    for worker_id in worker_ids {
         // Clone handlers with GIL before fork
         let handlers_clone: Vec<(u64, Py<PyAny>)> = Python::attach(|py| {
             handlers
@@ -38,9 +60,20 @@ pub fn spawn_workers(
 
             match pid {
                 -1 => {
-                    panic!("Failed to fork worker {}", worker_id);
+                    let err = std::io::Error::last_os_error();
+                    libc::close(report_read);
+                    libc::close(report_write);
+                    terminate_workers(&child_pids);
+                    wait_for_workers(&child_pids);
+                    return Err(pyo3::exceptions::PyOSError::new_err(format!(
+                        "Failed to fork worker {}: {}",
+                        worker_id + 1,
+                        err
+                    )));
                 }
                 0 => {
+                    libc::close(report_read);
+
                     // Child process - use Axum worker
                     use crate::core::worker::run_worker;
                     use crate::logging::LogQueue;
@@ -48,14 +81,40 @@ pub fn spawn_workers(
                     // Re-initialize the log queue for this child process
                     // (the parent's consumer thread doesn't survive fork)
                     LogQueue::reinit_after_fork();
+                    crate::core::request_id::reinit_after_fork();
+
+                    // Pin before the runtime spawns threads so they inherit the mask
+                    if let Some(cores) = cpu_affinity.get(worker_id) {
+                        if !crate::utils::cpu::pin_current_process(cores) {
+                            crate::hlog_warn!(
+                                "Failed to pin worker {} to CPUs {:?}",
+                                worker_id + 1,
+                                cores
+                            );
+                        }
+                    }
 
                     // Each child gets its own ReloadManager instance
                     let child_reload = ReloadManager::new(reload_manager.config().clone());
 
-                    // Run the Axum worker (this blocks forever)
+                    let listener = socket_held.for_worker();
+                    report_listener(report_write, &listener);
+                    let socket = match listener {
+                        Ok(socket) => socket,
+                        Err(err) => {
+                            crate::hlog_error!(
+                                "Worker {} failed to open its listener: {}",
+                                worker_id + 1,
+                                err
+                            );
+                            process::exit(1);
+                        }
+                    };
+
+                    // Run the Axum worker until it is stopped by a signal
                     let _ = run_worker(
                         py,
-                        socket_held.try_clone().expect("Failed to open socket"),
+                        socket,
                         worker_threads,
                         max_blocking_threads,
                         max_connections,
@@ -65,7 +124,8 @@ pub fn spawn_workers(
                         worker_id,
                         child_reload,
                     );
-                    // Should never reach here
+                    // Ship what is still queued before the process goes away
+                    LogQueue::shutdown();
                     process::exit(0);
                 }
                 child_pid => {
@@ -81,7 +141,98 @@ pub fn spawn_workers(
         }
     }
 
-    child_pids
+    unsafe { libc::close(report_write) };
+    let reports = read_listener_reports(report_read, child_pids.len());
+    unsafe { libc::close(report_read) };
+    if let Err(err) = reports {
+        terminate_workers(&child_pids);
+        wait_for_workers(&child_pids);
+        return Err(err);
+    }
+
+    Ok(child_pids)
+}
+
+/// Tell the parent whether this worker's listener is open, then close the
+/// pipe
+#[cfg(unix)]
+fn report_listener(fd: libc::c_int, listener: &Result<SocketHeld, BindFailure>) {
+    let line = match listener {
+        Ok(_) => "ok\n".to_string(),
+        Err(failure) => format!("err {}\n", failure.encode()),
+    };
+    // Shorter than PIPE_BUF, so lines from different workers never interleave
+    unsafe {
+        libc::write(fd, line.as_ptr() as *const libc::c_void, line.len());
+        libc::close(fd);
+    }
+}
+
+/// Wait for `workers` report lines; the first failure becomes the error
+#[cfg(unix)]
+fn read_listener_reports(fd: libc::c_int, workers: usize) -> PyResult<()> {
+    use pyo3::exceptions::{PyOSError, PyRuntimeError};
+    use std::time::Instant;
+
+    let deadline = Instant::now() + LISTENER_REPORT_TIMEOUT;
+    let mut pending = Vec::new();
+    let mut reports = 0;
+    while reports < workers {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(PyRuntimeError::new_err(format!(
+                "{} of {} workers did not open their listener within {}s",
+                workers - reports,
+                workers,
+                LISTENER_REPORT_TIMEOUT.as_secs()
+            )));
+        }
+
+        let mut poll_fd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ready = unsafe { libc::poll(&mut poll_fd, 1, remaining.as_millis() as libc::c_int) };
+        if ready == 0 {
+            continue;
+        }
+        let mut chunk = [0u8; 512];
+        let read = if ready > 0 {
+            unsafe { libc::read(fd, chunk.as_mut_ptr() as *mut libc::c_void, chunk.len()) }
+        } else {
+            -1
+        };
+        if read < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(PyOSError::new_err(format!(
+                "Failed to read worker startup reports: {}",
+                err
+            )));
+        }
+        if read == 0 {
+            return Err(PyRuntimeError::new_err(
+                "A worker exited before opening its listener",
+            ));
+        }
+
+        pending.extend_from_slice(&chunk[..read as usize]);
+        while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            reports += 1;
+            if let Some(report) = line.strip_prefix("err ") {
+                return Err(match BindFailure::decode(report) {
+                    Some(failure) => failure.into_pyerr(),
+                    None => PyOSError::new_err(report.trim_end().to_string()),
+                });
+            }
+        }
+    }
+    Ok(())
 }
 
 #[cfg(unix)]
@@ -133,6 +284,36 @@ pub fn wait_for_workers(pids: &[libc::pid_t]) {
     }
 }
 
+/// Wait for draining workers to exit on their own, returning as soon as
+/// they all have. Workers still running after `timeout` are reported and
+/// sent SIGTERM; `wait_for_workers` reaps them.
+#[cfg(unix)]
+pub fn drain_workers(pids: &[libc::pid_t], timeout: std::time::Duration) {
+    let deadline = std::time::Instant::now() + timeout;
+    let mut running = pids.to_vec();
+    loop {
+        // -1 means the worker was already reaped
+        running.retain(|&pid| {
+            let mut status: libc::c_int = 0;
+            unsafe { libc::waitpid(pid, &mut status, libc::WNOHANG) == 0 }
+        });
+        if running.is_empty() || std::time::Instant::now() >= deadline {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    if !running.is_empty() {
+        let listed: Vec<String> = running.iter().map(|pid| pid.to_string()).collect();
+        crate::hlog_warn!(
+            "Drain timeout of {}s reached with workers still running (PIDs {}), terminating them",
+            timeout.as_secs(),
+            listed.join(", ")
+        );
+        terminate_workers(&running);
+    }
+}
+
 /// Signal all workers to terminate
 #[cfg(unix)]
 pub fn terminate_workers(pids: &[libc::pid_t]) {
@@ -149,7 +330,7 @@ pub fn terminate_workers(pids: &[libc::pid_t]) {
 pub fn spawn_workers(
     py: Python<'_>,
     socket_held: SocketHeld,
-    num_workers: usize,
+    worker_ids: std::ops::Range<usize>,
     worker_threads: usize,
     max_blocking_threads: usize,
     max_connections: usize,
@@ -157,23 +338,24 @@ pub fn spawn_workers(
     middleware: Arc<MiddlewareChain>,
     handlers: Vec<(u64, Py<PyAny>)>,
     reload_manager: ReloadManager,
-) -> Vec<std::thread::JoinHandle<()>> {
+    _cpu_affinity: &[Vec<usize>],
+) -> PyResult<Vec<std::thread::JoinHandle<()>>> {
     use std::sync::atomic::{AtomicUsize, Ordering};
     use std::thread;
 
     crate::hlog_info!(
         "Starting {} thread-based workers (non-Unix mode)",
-        num_workers
+        worker_ids.len()
     );
 
     // Shared counter for load balancing
     static WORKER_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
-    let mut handles = Vec::with_capacity(num_workers);
+    let mut handles = Vec::with_capacity(worker_ids.len());
 
-    for worker_id in 0..num_workers {
+    for worker_id in worker_ids {
         // Clone all necessary data for the thread
-        let socket = socket_held.try_clone().expect("Failed to clone socket");
+        let socket = socket_held.for_worker()?;
         let router = router.clone();
         let middleware = middleware.clone();
         let rm = ReloadManager::new(reload_manager.config().clone());
@@ -236,18 +418,22 @@ pub fn spawn_workers(
                         crate::hlog_info!("Thread worker {} marked healthy", worker_id);
                     });
 
-                    // Serve with connection limits
-                    let server = axum::serve(listener, app).with_graceful_shutdown(async {
+                    let listener = crate::http::connection::HypernListener::new(
+                        listener,
+                        crate::http::tls::server_config(),
+                    )
+                    .expect("Failed to create listener");
+
+                    // Serve with connection limits; keep-alive connections
+                    // close once a drain starts
+                    crate::http::serve::serve(listener, app, rm.clone(), async {
                         // Wait for shutdown signal
                         tokio::signal::ctrl_c()
                             .await
                             .expect("Failed to install Ctrl+C handler");
                         crate::hlog_info!("Worker {} received shutdown signal", worker_id);
-                    });
-
-                    if let Err(e) = server.await {
-                        crate::hlog_error!("Worker {} server error: {}", worker_id, e);
-                    }
+                    })
+                    .await;
                 });
             })
             .expect("Failed to spawn worker thread");
@@ -256,7 +442,7 @@ pub fn spawn_workers(
         crate::hlog_info!("Spawned thread worker {} (thread-based)", worker_id + 1);
     }
 
-    handles
+    Ok(handles)
 }
 
 /// Wait for all worker threads to complete
@@ -187,6 +187,30 @@ pub struct ReloadConfig {
     pub health_probes_enabled: bool,
     /// Path prefix for health probes (default `/health`).
     pub health_path_prefix: String,
+    /// Whether health probe requests are written to the access log.
+    pub log_health_probes: bool,
+    /// SSE event name sent to open streams when draining (None sends nothing).
+    pub stream_shutdown_event: Option<String>,
+    /// Reconnect hint (`retry:`) in the shutdown event, in milliseconds.
+    pub stream_retry_ms: u64,
+    /// Milliseconds an open stream may keep flushing before it is closed.
+    pub stream_grace_ms: u64,
+    /// Milliseconds a deep probe result is reused before the checks run again.
+    pub deep_check_interval_ms: u64,
+    /// Milliseconds a deep probe may take before pending checks fail.
+    pub deep_check_timeout_ms: u64,
+    /// Age in milliseconds at which an unfinished handler counts as stuck
+    /// (None disables the watchdog).
+    pub watchdog_stuck_ms: Option<u64>,
+    /// Milliseconds without any handler completing before stuck handlers
+    /// mark the worker unhealthy (None uses `watchdog_stuck_ms`).
+    pub watchdog_idle_ms: Option<u64>,
+    /// Whether the watchdog logs every Python thread's stack when it trips.
+    pub watchdog_dump_stacks: bool,
+    /// Whether a worker still stuck after the grace period exits to be replaced.
+    pub watchdog_restart: bool,
+    /// Milliseconds a tripped worker may recover before it is replaced.
+    pub watchdog_restart_grace_ms: u64,
 }
 
 impl Default for ReloadConfig {
@@ -197,6 +221,17 @@ impl Default for ReloadConfig {
             startup_grace_secs: 2,
             health_probes_enabled: true,
             health_path_prefix: "/_health".to_string(),
+            log_health_probes: false,
+            stream_shutdown_event: Some("server-shutdown".to_string()),
+            stream_retry_ms: 1000,
+            stream_grace_ms: 500,
+            deep_check_interval_ms: 5000,
+            deep_check_timeout_ms: 2000,
+            watchdog_stuck_ms: None,
+            watchdog_idle_ms: None,
+            watchdog_dump_stacks: false,
+            watchdog_restart: false,
+            watchdog_restart_grace_ms: 5000,
         }
     }
 }
@@ -238,6 +273,8 @@ struct ReloadManagerInner {
     draining: AtomicBool,
     /// Notified when in-flight count reaches zero.
     drain_complete: Notify,
+    /// Mirrors `draining` for tasks that await the start of a drain.
+    drain_tx: watch::Sender<bool>,
 }
 
 impl ReloadManager {
@@ -252,6 +289,7 @@ impl ReloadManager {
                 signal_rx,
                 draining: AtomicBool::new(false),
                 drain_complete: Notify::new(),
+                drain_tx: watch::channel(false).0,
             }),
         }
     }
@@ -301,9 +339,16 @@ impl ReloadManager {
         {
             crate::hlog_info!("Starting connection drain");
             self.inner.health.mark_draining();
+            self.inner.drain_tx.send_replace(true);
         }
     }
 
+    /// Resolves once a drain has started.
+    pub async fn drain_started(&self) {
+        let mut rx = self.inner.drain_tx.subscribe();
+        let _ = rx.wait_for(|draining| *draining).await;
+    }
+
     pub fn is_draining(&self) -> bool {
         self.inner.draining.load(Ordering::Acquire)
     }
@@ -341,6 +386,7 @@ impl ReloadManager {
     /// Reset after a reload cycle completes.
     pub fn reset_after_reload(&self) {
         self.inner.draining.store(false, Ordering::Release);
+        self.inner.drain_tx.send_replace(false);
         self.inner.health.set_status(HealthStatus::Healthy);
         let _ = self.inner.signal_tx.send(ReloadSignal::None);
         crate::hlog_info!("Reload cycle complete, status reset to healthy");
@@ -439,13 +485,36 @@ impl PyReloadConfig {
         startup_grace_secs = 2,
         health_probes_enabled = true,
         health_path_prefix = "/_health".to_string(),
+        log_health_probes = false,
+        stream_shutdown_event = Some("server-shutdown".to_string()),
+        stream_retry_ms = 1000,
+        stream_grace_ms = 500,
+        deep_check_interval_ms = 5000,
+        deep_check_timeout_ms = 2000,
+        watchdog_stuck_ms = None,
+        watchdog_idle_ms = None,
+        watchdog_dump_stacks = false,
+        watchdog_restart = false,
+        watchdog_restart_grace_ms = 5000,
     ))]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         drain_timeout_secs: u64,
         health_poll_interval_ms: u64,
         startup_grace_secs: u64,
         health_probes_enabled: bool,
         health_path_prefix: String,
+        log_health_probes: bool,
+        stream_shutdown_event: Option<String>,
+        stream_retry_ms: u64,
+        stream_grace_ms: u64,
+        deep_check_interval_ms: u64,
+        deep_check_timeout_ms: u64,
+        watchdog_stuck_ms: Option<u64>,
+        watchdog_idle_ms: Option<u64>,
+        watchdog_dump_stacks: bool,
+        watchdog_restart: bool,
+        watchdog_restart_grace_ms: u64,
     ) -> Self {
         Self {
             inner: ReloadConfig {
@@ -454,6 +523,17 @@ impl PyReloadConfig {
                 startup_grace_secs,
                 health_probes_enabled,
                 health_path_prefix,
+                log_health_probes,
+                stream_shutdown_event,
+                stream_retry_ms,
+                stream_grace_ms,
+                deep_check_interval_ms,
+                deep_check_timeout_ms,
+                watchdog_stuck_ms,
+                watchdog_idle_ms,
+                watchdog_dump_stacks,
+                watchdog_restart,
+                watchdog_restart_grace_ms,
             },
         }
     }
@@ -483,6 +563,61 @@ impl PyReloadConfig {
         self.inner.health_path_prefix.clone()
     }
 
+    #[getter]
+    pub fn log_health_probes(&self) -> bool {
+        self.inner.log_health_probes
+    }
+
+    #[getter]
+    pub fn stream_shutdown_event(&self) -> Option<String> {
+        self.inner.stream_shutdown_event.clone()
+    }
+
+    #[getter]
+    pub fn stream_retry_ms(&self) -> u64 {
+        self.inner.stream_retry_ms
+    }
+
+    #[getter]
+    pub fn stream_grace_ms(&self) -> u64 {
+        self.inner.stream_grace_ms
+    }
+
+    #[getter]
+    pub fn deep_check_interval_ms(&self) -> u64 {
+        self.inner.deep_check_interval_ms
+    }
+
+    #[getter]
+    pub fn deep_check_timeout_ms(&self) -> u64 {
+        self.inner.deep_check_timeout_ms
+    }
+
+    #[getter]
+    pub fn watchdog_stuck_ms(&self) -> Option<u64> {
+        self.inner.watchdog_stuck_ms
+    }
+
+    #[getter]
+    pub fn watchdog_idle_ms(&self) -> Option<u64> {
+        self.inner.watchdog_idle_ms
+    }
+
+    #[getter]
+    pub fn watchdog_dump_stacks(&self) -> bool {
+        self.inner.watchdog_dump_stacks
+    }
+
+    #[getter]
+    pub fn watchdog_restart(&self) -> bool {
+        self.inner.watchdog_restart
+    }
+
+    #[getter]
+    pub fn watchdog_restart_grace_ms(&self) -> u64 {
+        self.inner.watchdog_restart_grace_ms
+    }
+
     pub fn __repr__(&self) -> String {
         format!(
             "ReloadConfig(drain_timeout={}s, health_probes={})",
@@ -0,0 +1,60 @@
+//! Request ID generation.
+//!
+//! An ID is a 64-bit tag drawn once per process followed by a 64-bit
+//! counter, both as fixed-width hex (32 characters). The counter never
+//! repeats within a process, so two IDs from one worker cannot collide;
+//! workers draw their own tag after fork, so IDs from different workers only
+//! collide if their tags do. The middleware chain, the request context and
+//! `Context` all take IDs from here.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use pyo3::prelude::*;
+
+/// This process's tag; 0 until first drawn
+static TAG: AtomicU64 = AtomicU64::new(0);
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Random tag mixed with the pid, so forked workers differ even if they
+/// inherited the parent's RNG state
+fn draw_tag() -> u64 {
+    let tag = rand::random::<u64>() ^ (std::process::id() as u64).rotate_left(40);
+    tag.max(1)
+}
+
+fn tag() -> u64 {
+    let tag = TAG.load(Ordering::Acquire);
+    if tag != 0 {
+        return tag;
+    }
+    match TAG.compare_exchange(0, draw_tag(), Ordering::AcqRel, Ordering::Acquire) {
+        Ok(_) => TAG.load(Ordering::Acquire),
+        Err(current) => current,
+    }
+}
+
+/// Draw a fresh tag in a forked worker
+pub fn reinit_after_fork() {
+    TAG.store(draw_tag(), Ordering::Release);
+}
+
+/// Next request ID for this process
+pub fn next() -> String {
+    let seq = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{:016x}{:016x}", tag(), seq)
+}
+
+/// Generate a request ID.
+///
+/// Uses the same generator as the server, e.g. for log records or jobs
+/// started outside a request. IDs are 32 hex characters and unique within
+/// the process.
+#[pyfunction]
+pub fn generate_request_id() -> String {
+    next()
+}
+
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(generate_request_id, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,196 @@
+//! Request-scoped context bridged into Python `contextvars`.
+//!
+//! Before a handler runs, the dispatch layer publishes a small dict
+//! (`request_id`, `trace_id`, `path`, `client_ip` and any middleware state
+//! marked `propagate=True`) in the `hypern.context` ContextVar, and resets it
+//! once the handler has finished, including when it raised.
+//!
+//! Handlers execute on the blocking thread that entered the scope, so
+//! libraries reading the ContextVar (logging filters, ORMs) see the values.
+//! Code that cannot share the interpreter's contextvars — e.g. work handed to
+//! another interpreter — should read the same dict from `req.context`, which
+//! is attached to every request regardless.
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::cell::RefCell;
+use std::sync::{Arc, OnceLock};
+
+use crate::http::request::Request;
+use crate::middleware::{MiddlewareContext, StateValue};
+
+static CONTEXT_VAR: OnceLock<Py<PyAny>> = OnceLock::new();
+
+thread_local! {
+    /// Token returned by `ContextVar.set` for the handler running on this thread
+    static ACTIVE_TOKEN: RefCell<Option<Py<PyAny>>> = const { RefCell::new(None) };
+}
+
+/// The `hypern.context` ContextVar (default `None`)
+pub fn context_var(py: Python<'_>) -> &Py<PyAny> {
+    CONTEXT_VAR.get_or_init(|| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("default", py.None()).unwrap();
+        py.import("contextvars")
+            .and_then(|m| m.getattr("ContextVar"))
+            .and_then(|cls| cls.call(("hypern.context",), Some(&kwargs)))
+            .expect("Failed to create hypern.context ContextVar")
+            .unbind()
+    })
+}
+
+/// Values published to the handler for one request
+#[derive(Debug, Clone, Default)]
+pub struct RequestScope {
+    pub request_id: String,
+    pub trace_id: Option<String>,
+    pub path: String,
+    pub client_ip: Option<String>,
+    /// User the auth middleware authenticated; not in the context dict
+    pub user_id: Option<String>,
+    pub state: Vec<(String, StateValue)>,
+}
+
+impl RequestScope {
+    /// Build the scope after the middleware chain has run
+    pub fn from_middleware(ctx: &MiddlewareContext, request: &Request) -> Self {
+        let (state, trace_id) = ctx.propagated_state();
+        let request_id = match ctx.get_state("request_id") {
+            Some(StateValue::String(id)) => id,
+            _ => ctx.request_id.to_string(),
+        };
+        Self {
+            request_id,
+            trace_id: trace_id.or_else(|| trace_id_from_headers(request)),
+            path: request.path().to_string(),
+            client_ip: request.ip(),
+            user_id: ctx.user_id(),
+            state,
+        }
+    }
+
+    /// Build the scope for requests that bypassed the middleware chain
+    pub fn from_request(request: &Request) -> Self {
+        let request_id = request
+            .header("x-request-id")
+            .unwrap_or_else(crate::core::request_id::next);
+        Self {
+            request_id,
+            trace_id: trace_id_from_headers(request),
+            path: request.path().to_string(),
+            client_ip: request.ip(),
+            user_id: None,
+            state: Vec::new(),
+        }
+    }
+
+    /// Materialize the scope as the dict handlers see
+    pub fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        // Propagated state first so the reserved keys below always win
+        for (key, value) in &self.state {
+            dict.set_item(key, state_value_to_py(py, value)?)?;
+        }
+        dict.set_item("request_id", &self.request_id)?;
+        dict.set_item("trace_id", &self.trace_id)?;
+        dict.set_item("path", &self.path)?;
+        dict.set_item("client_ip", &self.client_ip)?;
+        Ok(dict)
+    }
+}
+
+/// Trace ID from a W3C `traceparent` header (`version-traceid-spanid-flags`)
+fn trace_id_from_headers(request: &Request) -> Option<String> {
+    let traceparent = request.header("traceparent")?;
+    let trace_id = traceparent.split('-').nth(1)?;
+    (trace_id.len() == 32 && trace_id.bytes().all(|b| b.is_ascii_hexdigit()))
+        .then(|| trace_id.to_string())
+}
+
+pub(crate) fn state_value_to_py(py: Python<'_>, value: &StateValue) -> PyResult<Py<PyAny>> {
+    use pyo3::IntoPyObjectExt;
+    match value {
+        StateValue::String(s) => s.into_py_any(py),
+        StateValue::Int(i) => i.into_py_any(py),
+        StateValue::Float(f) => f.into_py_any(py),
+        StateValue::Bool(b) => b.into_py_any(py),
+        StateValue::Bytes(b) => pyo3::types::PyBytes::new(py, b).into_py_any(py),
+    }
+}
+
+/// Publish `scope` in `hypern.context` for the handler about to run on this thread.
+///
+/// Must be paired with [`exit`] on the same thread.
+pub fn enter(py: Python<'_>, scope: &Arc<RequestScope>) {
+    let result = scope
+        .to_dict(py)
+        .and_then(|dict| context_var(py).bind(py).call_method1("set", (dict,)));
+    match result {
+        Ok(token) => ACTIVE_TOKEN.with(|t| *t.borrow_mut() = Some(token.unbind())),
+        Err(e) => crate::hlog_warn!("Failed to set request context: {}", e),
+    }
+}
+
+/// Restore `hypern.context` to its value before [`enter`].
+pub fn exit() {
+    let Some(token) = ACTIVE_TOKEN.with(|t| t.borrow_mut().take()) else {
+        return;
+    };
+    Python::attach(|py| {
+        let var = context_var(py).bind(py);
+        // A handler that switched contexts makes the token unusable; clear instead
+        if var.call_method1("reset", (token,)).is_err() {
+            let _ = var.call_method1("set", (py.None(),));
+        }
+    });
+}
+
+/// The context dict of the current request, or ``None`` outside a handler.
+///
+/// Returns a copy; mutating it does not affect other readers.
+#[pyfunction]
+pub fn current_context(py: Python<'_>) -> PyResult<Option<Bound<'_, PyDict>>> {
+    let value = context_var(py).bind(py).call_method0("get")?;
+    match value.cast::<PyDict>() {
+        Ok(dict) => Ok(Some(dict.copy()?)),
+        Err(_) => Ok(None),
+    }
+}
+
+/// ID of the request being handled, or ``None`` outside a handler.
+#[pyfunction]
+pub fn current_request_id(py: Python<'_>) -> PyResult<Option<String>> {
+    let value = context_var(py).bind(py).call_method0("get")?;
+    match value.cast::<PyDict>() {
+        Ok(dict) => match dict.get_item("request_id")? {
+            Some(id) => id.extract(),
+            None => Ok(None),
+        },
+        Err(_) => Ok(None),
+    }
+}
+
+/// Trace ID of the request being handled, for continuing its trace downstream.
+pub fn current_trace_id(py: Python<'_>) -> PyResult<Option<String>> {
+    let value = context_var(py).bind(py).call_method0("get")?;
+    match value.cast::<PyDict>() {
+        Ok(dict) => match dict.get_item("trace_id")? {
+            Some(id) if !id.is_none() => id.extract(),
+            _ => Ok(None),
+        },
+        Err(_) => Ok(None),
+    }
+}
+
+/// The ``hypern.context`` ContextVar itself, for ``copy_context`` or custom readers.
+#[pyfunction]
+pub fn request_context_var(py: Python<'_>) -> Py<PyAny> {
+    context_var(py).clone_ref(py)
+}
+
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(current_context, m)?)?;
+    m.add_function(wrap_pyfunction!(current_request_id, m)?)?;
+    m.add_function(wrap_pyfunction!(request_context_var, m)?)?;
+    Ok(())
+}
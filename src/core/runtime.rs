@@ -1,6 +1,10 @@
 use crate::core::blocking::BlockingRunner;
+use crate::core::global::get_asyncio;
+use crate::http::disconnect::Disconnect;
+use crate::http::infer;
+use pyo3::exceptions::PyStopIteration;
 use pyo3::prelude::*;
-use pyo3::types::PyTuple;
+use pyo3::types::{PyDict, PyTuple};
 use std::future::Future;
 use std::sync::Arc;
 use tokio::runtime::Builder as RuntimeBuilder;
@@ -134,21 +138,52 @@ pub(crate) fn init_runtime_mt(
     )
 }
 
+/// A handler and what to call it with
+pub struct HandlerCall {
+    pub handler: Py<PyAny>,
+    pub args: Py<PyTuple>,
+    /// Arguments filled by name for a handler with an argument plan
+    pub kwargs: Option<Py<PyDict>>,
+    /// `(req, res)`, which a value the handler returns is applied to
+    pub target: Py<PyTuple>,
+}
+
+impl HandlerCall {
+    /// Call the handler; null when it raised
+    fn call(&self) -> *mut pyo3::ffi::PyObject {
+        let kwargs = self
+            .kwargs
+            .as_ref()
+            .map_or(std::ptr::null_mut(), |kwargs| kwargs.as_ptr());
+        unsafe { pyo3::ffi::PyObject_Call(self.handler.as_ptr(), self.args.as_ptr(), kwargs) }
+    }
+}
+
+/// Run a handler on the blocking pool. A handler whose client disconnected
+/// while it was queued is not called; an async one still running when the
+/// client goes away gets `asyncio.CancelledError` at its next `await`.
+/// `args_builder` returns None when it answered the request itself.
 #[inline]
-pub fn future_into_py<F, C>(rt: &RuntimeRef, is_async: bool, args_builder: F, on_complete: C)
-where
-    F: FnOnce(Python) -> (Py<PyAny>, Py<PyTuple>) + Send + 'static,
+pub fn future_into_py<F, C>(
+    rt: &RuntimeRef,
+    is_async: bool,
+    disconnect: Disconnect,
+    args_builder: F,
+    on_complete: C,
+) where
+    F: FnOnce(Python) -> Option<HandlerCall> + Send + 'static,
     C: FnOnce() + Send + 'static,
 {
     if is_async {
         // For async handlers: call and step coroutine on blocking thread
         rt.spawn_blocking(move |py| {
-            let (handler, args) = args_builder(py);
+            let Some(call) = args_builder(py).filter(|_| !disconnect.is_set()) else {
+                on_complete();
+                return;
+            };
 
             // Call handler to get coroutine using raw C API for minimum overhead
-            let coro_ptr = unsafe {
-                pyo3::ffi::PyObject_Call(handler.as_ptr(), args.as_ptr(), std::ptr::null_mut())
-            };
+            let coro_ptr = call.call();
 
             if coro_ptr.is_null() {
                 unsafe {
@@ -175,10 +210,20 @@ where
             }
 
             let none_ptr = unsafe { pyo3::ffi::Py_None() };
+            let mut cancelled = false;
 
             // Step coroutine to completion
             // Most handlers complete in 1-2 steps; optimize for that case
             loop {
+                if !cancelled && disconnect.is_set() {
+                    // Thrown once; a handler that catches it may still finish
+                    cancelled = true;
+                    if !throw_cancelled(py, coro_ptr) {
+                        break;
+                    }
+                    continue;
+                }
+
                 let result_ptr = unsafe {
                     pyo3::ffi::PyObject_CallFunctionObjArgs(
                         send_method,
@@ -188,13 +233,17 @@ where
                 };
 
                 if result_ptr.is_null() {
-                    // Check if it's StopIteration (normal completion)
-                    unsafe {
-                        if pyo3::ffi::PyErr_ExceptionMatches(pyo3::ffi::PyExc_StopIteration) != 0 {
-                            pyo3::ffi::PyErr_Clear();
-                        } else {
-                            pyo3::ffi::PyErr_Print();
+                    // StopIteration is normal completion and carries the
+                    // handler's return value
+                    match PyErr::take(py) {
+                        Some(err) if err.is_instance_of::<PyStopIteration>(py) => {
+                            if let (false, Ok(value)) = (cancelled, err.value(py).getattr("value"))
+                            {
+                                infer::handler_returned(call.target.bind(py), &value);
+                            }
                         }
+                        Some(err) => err.print(py),
+                        None => {}
                     }
                     break;
                 }
@@ -217,17 +266,44 @@ where
     } else {
         // For sync handlers: run directly on blocking thread using raw C API
         rt.spawn_blocking(move |py| {
-            let (handler, args) = args_builder(py);
+            let Some(call) = args_builder(py).filter(|_| !disconnect.is_set()) else {
+                on_complete();
+                return;
+            };
             unsafe {
-                let result =
-                    pyo3::ffi::PyObject_Call(handler.as_ptr(), args.as_ptr(), std::ptr::null_mut());
+                let result = call.call();
                 if result.is_null() {
                     pyo3::ffi::PyErr_Print();
                 } else {
-                    pyo3::ffi::Py_DECREF(result);
+                    let value = Bound::from_owned_ptr(py, result);
+                    infer::handler_returned(call.target.bind(py), &value);
                 }
             }
             on_complete();
         });
     }
 }
+
+/// Throw `asyncio.CancelledError` into a handler coroutine; false once the
+/// coroutine has ended
+fn throw_cancelled(py: Python<'_>, coro_ptr: *mut pyo3::ffi::PyObject) -> bool {
+    // Safety: the caller holds a reference to the coroutine
+    let coro = unsafe { Bound::from_borrowed_ptr(py, coro_ptr) };
+    let asyncio = get_asyncio(py).bind(py);
+    let thrown = asyncio
+        .getattr("CancelledError")
+        .and_then(|error| error.call0())
+        .and_then(|error| coro.call_method1("throw", (error,)));
+    match thrown {
+        Ok(_) => true,
+        Err(err) => {
+            let cancelled = asyncio
+                .getattr("CancelledError")
+                .is_ok_and(|error| err.matches(py, error).unwrap_or(false));
+            if !cancelled && !err.is_instance_of::<PyStopIteration>(py) {
+                err.print(py);
+            }
+            false
+        }
+    }
+}
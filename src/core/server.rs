@@ -1,11 +1,43 @@
+use crate::core::deep_health;
+use crate::core::describe;
+use crate::core::global::{get_event_loop, set_global_runtime, try_global_runtime};
+use crate::core::interpreter;
+use crate::core::live_config;
 use crate::core::multiprocess::{spawn_workers, terminate_workers, wait_for_workers};
 use crate::core::reload::{PyHealthCheck, PyReloadConfig, PyReloadManager, ReloadConfig, ReloadManager};
+use crate::core::warmup::{self, WarmupConfig};
+use crate::core::worker::AppState;
+use crate::database::pool::ConnectionPoolManager;
+use crate::http::admission::{self, AdmissionConfig};
+use crate::http::allowed_hosts::{self, AllowedHosts};
+use crate::http::decompression;
+use crate::http::error_envelope::{self, ErrorFormat};
+use crate::http::expect;
+use crate::http::header_limits::{self, HeaderLimits};
+use crate::http::header_policy::{self, HeaderPolicy};
+use crate::http::https::{self, HttpsPolicy};
+use crate::http::path;
+use crate::http::response;
+use crate::http::timing;
+use crate::http::tls::{self, TlsFiles};
 use crate::logging::{LogConfig, LogQueue, PyLogConfig};
-use crate::middleware::MiddlewareChain;
+use crate::memory::debug as memory_debug;
+use crate::middleware::{Anchor, MiddlewareChain, MiddlewareInfo};
+use crate::realtime::channel::ChannelManager;
+use crate::realtime::poll::{self, PollEndpoint};
+use crate::routing::auth::{self, AuthRequirement};
+use crate::routing::memo;
 use crate::routing::router::Router;
-use crate::socket::SocketHeld;
+use crate::socket::{self, SocketHeld, SocketOptions};
+use crate::utils::cpu::{self, CpuAffinity};
+use crate::telemetry::{cluster, gil};
+use crate::telemetry::server_metrics::server_metrics;
+use crate::utils::json::json_value_to_py;
 use crate::{hlog_info, hlog_warn};
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 #[pyclass]
@@ -16,20 +48,517 @@ pub struct Server {
     reload_config: ReloadConfig,
     reload_manager: Option<ReloadManager>,
     log_config: LogConfig,
+    cpu_affinity: CpuAffinity,
+    allowed_hosts: Option<AllowedHosts>,
+    /// `force_https`, `hsts_preload` and the trusted proxies
+    https: HttpsPolicy,
+    decompress_requests: bool,
+    max_decompressed_size: usize,
+    tls: Option<TlsFiles>,
+    server_timing: bool,
+    gil_metrics: bool,
+    gil_hold_warn_ms: f64,
+    /// Seconds between two metrics snapshots of a worker; 0 when off
+    metrics_publish_interval: f64,
+    header_policy: HeaderPolicy,
+    warmup: WarmupConfig,
+    merge_slashes: bool,
+    path_decoding: path::Decoding,
+    strict_path_encoding: bool,
+    stream_threshold_bytes: usize,
+    expect_continue: bool,
+    memory_debug: bool,
+    /// Requirement of routes registered without `auth`
+    default_auth: AuthRequirement,
+    /// Per-IP cap, header timeout and exempt networks
+    admission: AdmissionConfig,
+    /// Size of request heads: header bytes, field count and URI length
+    header_limits: HeaderLimits,
+    error_format: ErrorFormat,
+    /// App handlers answering framework errors, by status
+    error_handlers: BTreeMap<u16, Py<PyAny>>,
+    /// Effective once started: `reuse_port` is cleared if the platform refused it
+    socket_options: SocketOptions,
+    realtime_poll: Option<Arc<PollEndpoint>>,
+    /// Custom checks of the deep health probe, in registration order
+    health_checks: Vec<Arc<deep_health::CustomCheck>>,
+    /// Address and process layout, once started
+    listen: Option<describe::Listen>,
+    /// Core set per worker chosen at start (empty when not pinned)
+    worker_layout: Vec<Vec<usize>>,
+    worker_pids: Vec<i64>,
 }
 
 #[pymethods]
 impl Server {
+    /// Create a server.
+    ///
+    /// Args:
+    ///     cpu_affinity: Worker CPU pinning - "auto" pins worker i to core i
+    ///         (mod core count), a list like [[0, 1], [2, 3]] gives explicit
+    ///         core sets per worker, None disables pinning (Linux only)
+    ///     allowed_hosts: Hostnames requests may name in Host, e.g.
+    ///         ["example.com", "*.example.com", "10.0.0.5"]; other hosts get
+    ///         a 400 before middleware and routing. None disables the check
+    ///     force_https: Answer requests that did not arrive over TLS with a
+    ///         308 to the same path and query on `https://`, except health
+    ///         probes and `https_exempt_paths`; needs `allowed_hosts`
+    ///         (default: False)
+    ///     hsts_preload: Send `Strict-Transport-Security: max-age=63072000;
+    ///         includeSubDomains; preload` on every HTTPS response, replacing
+    ///         other values, and never over plain HTTP (default: False)
+    ///     trusted_proxies: Networks whose `X-Forwarded-Proto` says whether
+    ///         the client used HTTPS, for `force_https` and `hsts_preload`;
+    ///         the header is ignored from other peers
+    ///     https_exempt_paths: Path prefixes `force_https` leaves on plain
+    ///         HTTP (default: ["/.well-known/acme-challenge/"])
+    ///     decompress_requests: Inflate gzip/deflate request bodies before
+    ///         parsing; unknown encodings get a 415 (default: False)
+    ///     max_decompressed_size: Cap on an inflated body in bytes; larger
+    ///         bodies get a 413 (default: 10 MiB)
+    ///     tls_cert_path: PEM certificate chain; with `tls_key_path` the
+    ///         server speaks HTTPS
+    ///     tls_key_path: PEM private key for `tls_cert_path`
+    ///     tls_client_ca_path: PEM CA bundle; clients are asked for a
+    ///         certificate signed by it, exposed as `Request.peer_cert`
+    ///     tls_require_client_cert: Reject clients without a valid
+    ///         certificate instead of treating it as optional (default: False)
+    ///     server_timing: Add a `Server-Timing` header with the route, queue,
+    ///         app and write stage durations to handler responses (default: False)
+    ///     gil_metrics: Measure how long handlers, executor workers and row
+    ///         conversion wait for and hold the GIL, reported under
+    ///         `stats()["metrics"]["gil"]` (default: False)
+    ///     gil_hold_warn_ms: With `gil_metrics`, log a warning (at most once a
+    ///         second per call site) when one hold lasts longer (default: 100)
+    ///     metrics_publish_interval: Seconds between two snapshots each
+    ///         worker publishes of its request metrics, which
+    ///         `server_metrics().snapshot(cluster=True)` merges across
+    ///         workers; 0 turns publishing off (default: 1)
+    ///     warmup_paths: Paths each worker requests in-process with GET, through
+    ///         middleware and routing, before it reports healthy
+    ///     eager_import: Import every handler's module when the worker starts
+    ///         instead of on its first request (default: False)
+    ///     warmup_strict: Keep a worker whose warm-up failed in the starting
+    ///         state, so startup and readiness probes stay 503 (default: False)
+    ///     merge_slashes: Collapse `//` in request paths before routing
+    ///         (default: True)
+    ///     path_decoding: Percent-escapes decoded before routing: "unreserved"
+    ///         (letters, digits, `-._~`) or "all"; `%2F` is never decoded, so
+    ///         `/a%2Fb` stays one segment (default: "unreserved")
+    ///     strict_path_encoding: Reject paths with malformed escapes or
+    ///         invalid UTF-8 with 400 instead of keeping them literally
+    ///         (default: False)
+    ///     stream_threshold_bytes: Buffered response bodies larger than this
+    ///         are written in slices of the buffer instead of one frame, still
+    ///         with a Content-Length; 0 disables (default: 1 MiB)
+    ///     expect_continue: Check `Expect: 100-continue` requests (route,
+    ///         body limit, header-only middleware such as Basic auth) before
+    ///         the client is told to send the body; failures get their final
+    ///         status at once (default: True)
+    ///     backlog: Pending connection queue length of the listening socket
+    ///         (default: 1024)
+    ///     reuse_port: Each worker binds its own SO_REUSEPORT listener so the
+    ///         kernel spreads connections across workers; falls back to one
+    ///         shared listener where unsupported (default: False)
+    ///     tcp_nodelay: Disable Nagle's algorithm on accepted connections
+    ///         (default: True)
+    ///     keepalive_idle: Seconds a connection is idle before TCP keepalive
+    ///         probes start; None disables keepalive (default: 60)
+    ///     keepalive_interval: Seconds between keepalive probes (default:
+    ///         system setting)
+    ///     keepalive_count: Unanswered probes before the connection is dropped
+    ///         (default: system setting)
+    ///     memory_debug: Account for what each request leaves behind (extra
+    ///         references to its Request/Response, pool buffers, database
+    ///         sessions, streaming bodies) and log suspects at ERROR; reported
+    ///         under `stats()["leak_suspects"]`. For debugging only (default: False)
+    ///     default_auth: Auth requirement of routes registered without
+    ///         `auth`: "public", "optional" or "required"; routes opt out
+    ///         with `auth="public"` (default: "public")
+    ///     max_connections_per_ip: Open connections a worker keeps from one
+    ///         client address; further ones are closed on accept. None for
+    ///         no cap (default: None)
+    ///     header_read_timeout: Seconds a client has to send the headers of
+    ///         a request, counted from the connection or the first byte of a
+    ///         kept-alive request; slower connections are closed. None for
+    ///         no limit (default: 30)
+    ///     connection_limit_exempt: Networks not subject to
+    ///         `max_connections_per_ip`, e.g. ["10.0.0.0/8"] for load
+    ///         balancers and health checkers
+    ///     max_header_bytes: Size of a request's header fields, as sent;
+    ///         larger ones get a 431. None leaves hyper's own bound
+    ///         (default: 64 KiB)
+    ///     max_header_count: Header fields of a request before it gets a 431
+    ///         (default: 100)
+    ///     max_uri_length: Length of a request target, path and query,
+    ///         before it gets a 414 (default: 8 KiB)
+    ///     error_format: Body of the errors the server generates itself
+    ///         (404, 405, 413, 431, 503, ...) - "auto" sends the JSON
+    ///         envelope to clients whose Accept names JSON and plain text to
+    ///         others, "json" and "text" always use one (default: "auto")
     #[new]
-    pub fn new() -> Self {
-        Self {
+    #[pyo3(signature = (
+        cpu_affinity=None,
+        allowed_hosts=None,
+        force_https=false,
+        hsts_preload=false,
+        trusted_proxies=None,
+        https_exempt_paths=None,
+        decompress_requests=false,
+        max_decompressed_size=decompression::DEFAULT_MAX_DECOMPRESSED_SIZE,
+        tls_cert_path=None,
+        tls_key_path=None,
+        tls_client_ca_path=None,
+        tls_require_client_cert=false,
+        server_timing=false,
+        gil_metrics=false,
+        gil_hold_warn_ms=100.0,
+        metrics_publish_interval=cluster::DEFAULT_PUBLISH_INTERVAL_SECS,
+        warmup_paths=None,
+        eager_import=false,
+        warmup_strict=false,
+        merge_slashes=true,
+        path_decoding="unreserved",
+        strict_path_encoding=false,
+        stream_threshold_bytes=response::DEFAULT_STREAM_THRESHOLD,
+        expect_continue=true,
+        backlog=socket::DEFAULT_BACKLOG,
+        reuse_port=false,
+        tcp_nodelay=true,
+        keepalive_idle=Some(socket::DEFAULT_KEEPALIVE_IDLE),
+        keepalive_interval=None,
+        keepalive_count=None,
+        memory_debug=false,
+        default_auth="public",
+        max_connections_per_ip=None,
+        header_read_timeout=Some(admission::DEFAULT_HEADER_READ_TIMEOUT),
+        connection_limit_exempt=None,
+        max_header_bytes=Some(header_limits::DEFAULT_MAX_HEADER_BYTES),
+        max_header_count=Some(header_limits::DEFAULT_MAX_HEADER_COUNT),
+        max_uri_length=Some(header_limits::DEFAULT_MAX_URI_LENGTH),
+        error_format="auto",
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        cpu_affinity: Option<&Bound<'_, PyAny>>,
+        allowed_hosts: Option<Vec<String>>,
+        force_https: bool,
+        hsts_preload: bool,
+        trusted_proxies: Option<Vec<String>>,
+        https_exempt_paths: Option<Vec<String>>,
+        decompress_requests: bool,
+        max_decompressed_size: usize,
+        tls_cert_path: Option<String>,
+        tls_key_path: Option<String>,
+        tls_client_ca_path: Option<String>,
+        tls_require_client_cert: bool,
+        server_timing: bool,
+        gil_metrics: bool,
+        gil_hold_warn_ms: f64,
+        metrics_publish_interval: f64,
+        warmup_paths: Option<Vec<String>>,
+        eager_import: bool,
+        warmup_strict: bool,
+        merge_slashes: bool,
+        path_decoding: &str,
+        strict_path_encoding: bool,
+        stream_threshold_bytes: usize,
+        expect_continue: bool,
+        backlog: i32,
+        reuse_port: bool,
+        tcp_nodelay: bool,
+        keepalive_idle: Option<u64>,
+        keepalive_interval: Option<u64>,
+        keepalive_count: Option<u32>,
+        memory_debug: bool,
+        default_auth: &str,
+        max_connections_per_ip: Option<usize>,
+        header_read_timeout: Option<f64>,
+        connection_limit_exempt: Option<Vec<String>>,
+        max_header_bytes: Option<usize>,
+        max_header_count: Option<usize>,
+        max_uri_length: Option<usize>,
+        error_format: &str,
+    ) -> PyResult<Self> {
+        let path_decoding = path::Decoding::parse(path_decoding).ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err(format!(
+                "path_decoding must be 'unreserved' or 'all', got '{}'",
+                path_decoding
+            ))
+        })?;
+        if !gil_hold_warn_ms.is_finite() || gil_hold_warn_ms < 0.0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "gil_hold_warn_ms must be a non-negative number",
+            ));
+        }
+        if !metrics_publish_interval.is_finite() || metrics_publish_interval < 0.0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "metrics_publish_interval must be a non-negative number",
+            ));
+        }
+        let default_auth = AuthRequirement::parse(default_auth).ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err(format!(
+                "default_auth must be 'public', 'optional' or 'required', got '{}'",
+                default_auth
+            ))
+        })?;
+        let allowed_hosts = allowed_hosts.map(AllowedHosts::new).transpose()?;
+        let https = HttpsPolicy::from_options(
+            force_https,
+            hsts_preload,
+            trusted_proxies,
+            https_exempt_paths,
+            allowed_hosts.as_ref(),
+        )?;
+        let warmup_paths = warmup_paths.unwrap_or_default();
+        if let Some(path) = warmup_paths.iter().find(|path| !path.starts_with('/')) {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "warmup path '{}' must start with '/'",
+                path
+            )));
+        }
+        Ok(Self {
             router: Arc::new(Router::default()),
             http2: false,
             rust_middleware: Arc::new(MiddlewareChain::new()),
             reload_config: ReloadConfig::default(),
             reload_manager: None,
             log_config: LogConfig::default(),
-        }
+            cpu_affinity: CpuAffinity::from_py(cpu_affinity)?,
+            allowed_hosts,
+            https,
+            decompress_requests,
+            max_decompressed_size,
+            tls: TlsFiles::from_options(
+                tls_cert_path,
+                tls_key_path,
+                tls_client_ca_path,
+                tls_require_client_cert,
+            )?,
+            server_timing,
+            gil_metrics,
+            gil_hold_warn_ms,
+            metrics_publish_interval,
+            header_policy: HeaderPolicy::default(),
+            warmup: WarmupConfig {
+                paths: warmup_paths,
+                eager_import,
+                strict: warmup_strict,
+            },
+            merge_slashes,
+            path_decoding,
+            strict_path_encoding,
+            stream_threshold_bytes,
+            expect_continue,
+            memory_debug,
+            default_auth,
+            admission: AdmissionConfig::from_options(
+                max_connections_per_ip,
+                header_read_timeout,
+                connection_limit_exempt,
+            )?,
+            header_limits: HeaderLimits::from_options(
+                max_header_bytes,
+                max_header_count,
+                max_uri_length,
+            )?,
+            socket_options: SocketOptions::new(
+                backlog,
+                reuse_port,
+                tcp_nodelay,
+                keepalive_idle,
+                keepalive_interval,
+                keepalive_count,
+            )?,
+            error_format: ErrorFormat::parse(error_format)?,
+            error_handlers: BTreeMap::new(),
+            realtime_poll: None,
+            health_checks: Vec::new(),
+            listen: None,
+            worker_layout: Vec::new(),
+            worker_pids: Vec::new(),
+        })
+    }
+
+    /// Worker layout chosen at start.
+    ///
+    /// Returns a dict with `num_workers`, `pids`, `affinity` (core set per
+    /// worker, empty when not pinned), `cpu_count` and `cgroup_cpu_quota`.
+    pub fn worker_status<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let status = PyDict::new(py);
+        status.set_item("num_workers", self.worker_pids.len())?;
+        status.set_item("pids", self.worker_pids.clone())?;
+        status.set_item("affinity", self.worker_layout.clone())?;
+        status.set_item("cpu_count", cpu::effective_cpus())?;
+        status.set_item("cgroup_cpu_quota", cpu::cgroup_cpu_limit())?;
+        Ok(status)
+    }
+
+    /// Server configuration summary.
+    ///
+    /// Returns a dict with `routes`, `http2`, `num_workers`, `allowed_hosts`
+    /// (None when Host validation is disabled), `force_https`,
+    /// `hsts_preload`, `trusted_proxies`, `https_exempt_paths`,
+    /// `decompress_requests`, `max_decompressed_size`, `tls`, `tls_client_auth` ("none",
+    /// "optional" or "required"), `server_timing`, `gil_metrics`,
+    /// `gil_hold_warn_ms`, `header_policy`, `warmup_paths`,
+    /// `eager_import`, `stream_threshold_bytes`, `expect_continue` and the
+    /// socket options `backlog`, `reuse_port`, `tcp_nodelay`,
+    /// `keepalive_idle`, `keepalive_interval` and `keepalive_count`
+    /// (`reuse_port` is False after start if the platform did not support
+    /// it), `realtime_poll` (the long-poll path, None when not served),
+    /// `memory_debug`, `default_auth`, `max_connections_per_ip`,
+    /// `header_read_timeout`, `connection_limit_exempt`,
+    /// `max_header_bytes`, `max_header_count`, `max_uri_length`,
+    /// `metrics_publish_interval`, `metrics`, this process's
+    /// `ServerMetrics.snapshot()`, `cluster_metrics`, the same merged across
+    /// live workers (`snapshot(cluster=True)`),
+    /// and `leak_suspects`, the most recent (up to 100) requests
+    /// `memory_debug` flagged in this process, each a dict with
+    /// `request_id`, `path`, `leaks` and `arena_bytes`.
+    pub fn stats<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let config = Value::Object(self.config_json());
+        let stats = json_value_to_py(py, &config)?
+            .into_bound(py)
+            .cast_into::<PyDict>()?;
+        stats.set_item("metrics", server_metrics().snapshot(py, false, false)?)?;
+        stats.set_item(
+            "cluster_metrics",
+            server_metrics().snapshot(py, true, false)?,
+        )?;
+        stats.set_item("leak_suspects", memory_debug::suspects(py)?)?;
+        Ok(stats)
+    }
+
+    /// Effective configuration, as logged once by the parent and each worker
+    /// at startup.
+    ///
+    /// Returns a dict with `version`, `pid`, `allocator`, `listen` (host,
+    /// port and process layout passed to `start`, None before), `worker_pids`,
+    /// `routes`, `middleware` (`before` and `after` names in execution
+    /// order), `features`, `logging`, `databases` (initialized pools) and
+    /// `config` (as in `stats()`). Passwords and other secrets are replaced
+    /// with "***".
+    pub fn describe<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let mut snapshot = self.snapshot();
+        describe::redact(&mut snapshot);
+        Ok(json_value_to_py(py, &snapshot)?.into_bound(py))
+    }
+
+    /// Register a dependency for `Request.dep(name)`.
+    ///
+    /// The factory's required parameters name the dependencies it is built
+    /// from; they must already be provided. Registration fails on cycles and
+    /// on app-scoped dependencies that need request-scoped ones.
+    ///
+    /// Args:
+    ///     name: Name handlers resolve with `req.dep(name)`
+    ///     factory: Callable (or coroutine function, app scope only) creating the value
+    ///     scope: "app" (one per worker, created at startup) or "request"
+    ///         (created on first use, torn down when the handler completes)
+    ///     teardown: Optional callable receiving the instance on teardown
+    #[staticmethod]
+    #[pyo3(signature = (name, factory, scope="app", teardown=None))]
+    pub fn provide(
+        py: Python<'_>,
+        name: String,
+        factory: Py<PyAny>,
+        scope: &str,
+        teardown: Option<Py<PyAny>>,
+    ) -> PyResult<()> {
+        crate::core::deps::provide(py, name, factory, scope, teardown)
+    }
+
+    /// Change hot-reloadable settings of the running server.
+    ///
+    /// The update applies in the calling process at once and in every other
+    /// worker on its next request. Keys other than the hot-reloadable ones
+    /// are rejected by name and nothing is applied.
+    ///
+    /// Args:
+    ///     settings: Any of `log_level`, `log_format`, `log_skip_paths`,
+    ///         `slow_threshold_ms`, `max_in_flight`, `rate_limit` (a dict
+    ///         of `max_requests`, `window_secs` and `skip_paths`),
+    ///         `max_connections`, `max_connections_per_ip`,
+    ///         `header_read_timeout` and `feature_flags` (a dict of flag
+    ///         name to True, False, a percentage or a dict of `enabled`,
+    ///         `percentage`, `sticky_by` and `off_status`)
+    ///     source: Who made the change, recorded in the audit log entry
+    ///
+    /// Returns:
+    ///     The new config generation
+    #[staticmethod]
+    #[pyo3(signature = (settings, source=None))]
+    pub fn update_config(settings: &Bound<'_, PyDict>, source: Option<String>) -> PyResult<u64> {
+        let Value::Object(update) = crate::utils::json::py_to_json_value(settings.as_any())? else {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "settings must be a dict",
+            ));
+        };
+        live_config::update(update, source.as_deref().unwrap_or("update_config"))
+            .map_err(pyo3::exceptions::PyValueError::new_err)
+    }
+
+    /// Turn a feature flag on or off, or roll it out to a percentage of
+    /// requests, in every worker.
+    ///
+    /// Routes registered with `feature_flag=name` answer `off_status`
+    /// without running their handler while the flag is off for a request,
+    /// and handlers read it with `request.flag(name)`. The change goes
+    /// through the runtime config, as the `feature_flags` key.
+    ///
+    /// Args:
+    ///     name: The flag
+    ///     enabled: On for every request (True) or none (False)
+    ///     percentage: On for this percentage of requests instead, 0-100
+    ///     sticky_by: What a partial rollout buckets requests by:
+    ///         "user_id" (the authenticated user, else the client IP, else
+    ///         the request), "client_ip" or "request_id"
+    ///     off_status: 404 (default) or 503 for gated routes while off
+    ///     source: Who made the change, recorded in the audit log entry
+    ///
+    /// Returns:
+    ///     The new config generation
+    #[staticmethod]
+    #[pyo3(signature = (name, enabled=true, percentage=None, sticky_by="user_id", off_status=404, source=None))]
+    pub fn set_flag(
+        name: &str,
+        enabled: bool,
+        percentage: Option<f64>,
+        sticky_by: &str,
+        off_status: u16,
+        source: Option<String>,
+    ) -> PyResult<u64> {
+        let percentage = match percentage {
+            Some(percentage) => serde_json::json!(percentage),
+            None => serde_json::json!(if enabled { 100 } else { 0 }),
+        };
+        let spec = serde_json::json!({
+            "percentage": percentage,
+            "sticky_by": sticky_by,
+            "off_status": off_status,
+        });
+        let mut flags = serde_json::Map::new();
+        flags.insert(name.to_string(), spec);
+        let mut update = serde_json::Map::new();
+        update.insert("feature_flags".into(), Value::Object(flags));
+        live_config::update(update, source.as_deref().unwrap_or("set_flag"))
+            .map_err(pyo3::exceptions::PyValueError::new_err)
+    }
+
+    /// Drop the memoized responses of the routes at `path` (as registered,
+    /// e.g. "/config" or "/users/{id}") in this process, so their next
+    /// request runs the handler. Returns how many memoized routes matched.
+    #[staticmethod]
+    pub fn invalidate_route_cache(path: &str) -> usize {
+        memo::invalidate(path)
+    }
+
+    /// Hot-reloadable settings as they stand in this process
+    #[staticmethod]
+    pub fn live_config<'py>(py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let current = Value::Object(live_config::current());
+        Ok(json_value_to_py(py, &current)?.into_bound(py))
     }
 
     /// Configure logging behavior.
@@ -45,6 +574,11 @@ impl Server {
         self.http2 = true;
     }
 
+    /// Set the default, removed and `Server` headers of every response.
+    pub fn set_header_policy(&mut self, policy: HeaderPolicy) {
+        self.header_policy = policy;
+    }
+
     /// Configure reload behavior.
     pub fn set_reload_config(&mut self, config: PyReloadConfig) {
         self.reload_config = config.inner;
@@ -64,6 +598,78 @@ impl Server {
         })
     }
 
+    /// Register a check run by the deep health probe (`{health_path}/deep`).
+    ///
+    /// The check is called without arguments on a blocking thread; it
+    /// passes when it returns None or a truthy value (coroutines are run to
+    /// completion) and fails when it returns a false value or raises.
+    ///
+    /// Args:
+    ///     name: Name of the check in the probe's `checks`
+    ///     check: Callable or coroutine function
+    ///     critical: Whether a failure makes the probe answer 503; other
+    ///         failures only mark it "degraded" (default: True)
+    #[pyo3(signature = (name, check, critical=true))]
+    pub fn add_health_check(
+        &mut self,
+        py: Python<'_>,
+        name: String,
+        check: Py<PyAny>,
+        critical: bool,
+    ) -> PyResult<()> {
+        if !check.bind(py).is_callable() {
+            return Err(pyo3::exceptions::PyTypeError::new_err(format!(
+                "health check '{}' is not callable",
+                name
+            )));
+        }
+        if self.health_checks.iter().any(|c| c.name == name) {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "health check '{}' is already registered",
+                name
+            )));
+        }
+        self.health_checks.push(Arc::new(deep_health::CustomCheck {
+            name,
+            check,
+            critical,
+        }));
+        Ok(())
+    }
+
+    /// Answer the errors the server generates with `status` once a request
+    /// has been read (router 404s and 405s, routes hidden by a feature flag,
+    /// middleware short-circuits) with `handler` instead of the envelope.
+    ///
+    /// The handler is called like a route handler, with the request's
+    /// `route_meta` holding `error_status`, `error_code` and
+    /// `error_message`.
+    ///
+    /// Args:
+    ///     status: An error status, 400 to 599
+    ///     handler: Callable or coroutine function taking (req, res)
+    pub fn set_error_handler(
+        &mut self,
+        py: Python<'_>,
+        status: u16,
+        handler: Py<PyAny>,
+    ) -> PyResult<()> {
+        if !(400..=599).contains(&status) {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "error handler status must be between 400 and 599, not {}",
+                status
+            )));
+        }
+        if !handler.bind(py).is_callable() {
+            return Err(pyo3::exceptions::PyTypeError::new_err(format!(
+                "error handler for {} is not callable",
+                status
+            )));
+        }
+        self.error_handlers.insert(status, handler);
+        Ok(())
+    }
+
     /// Trigger a graceful reload (SIGUSR1 to workers).
     pub fn graceful_reload(&self) {
         if let Some(ref rm) = self.reload_manager {
@@ -78,40 +684,243 @@ impl Server {
         }
     }
 
-    /// Register a Rust middleware to run before request handlers
-    pub fn use_middleware(&mut self, middleware: &Bound<'_, PyAny>) -> PyResult<()> {
-        use crate::middleware::{
-            PyBasicAuthMiddleware, PyCompressionMiddleware, PyCorsMiddleware, PyLogMiddleware,
-            PyRateLimitMiddleware, PyRequestIdMiddleware, PySecurityHeadersMiddleware,
-            PyTimeoutMiddleware,
-        };
+    /// Register a Rust middleware to run before request handlers.
+    ///
+    /// Middleware runs in order of priority (lower first), registration
+    /// order breaking ties; each builtin has a default priority. Anchors
+    /// are resolved when the server starts, which fails on names that are
+    /// not registered and on contradicting anchors.
+    ///
+    /// Args:
+    ///     middleware: A builtin middleware instance
+    ///     priority: Position in the chain instead of the middleware's default
+    ///     before: Name of a middleware this one must run before (optional)
+    ///     after: Name of a middleware this one must run after (optional)
+    ///     methods: Only run for these methods; a list, `"GET,POST"` or
+    ///         `"*"` (default: every method)
+    #[pyo3(signature = (middleware, priority=None, before=None, after=None, methods=None))]
+    pub fn use_middleware(
+        &mut self,
+        middleware: &Bound<'_, PyAny>,
+        priority: Option<i32>,
+        before: Option<String>,
+        after: Option<String>,
+        methods: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<()> {
+        let anchors: Vec<Anchor> = before
+            .map(Anchor::Before)
+            .into_iter()
+            .chain(after.map(Anchor::After))
+            .collect();
+        let methods = methods
+            .map(crate::http::method::MethodSet::extract)
+            .transpose()?;
+        let placement = (priority, anchors, methods);
 
-        // Check if it's a Rust middleware type and register it
-        if let Ok(req_id) = middleware.extract::<PyRequestIdMiddleware>() {
-            self.register_boxed_middleware(req_id.inner.clone());
-        } else if let Ok(cors) = middleware.extract::<PyCorsMiddleware>() {
-            self.register_boxed_middleware(cors.inner.clone());
-        } else if let Ok(sec) = middleware.extract::<PySecurityHeadersMiddleware>() {
-            self.register_boxed_middleware(sec.inner.clone());
-        } else if let Ok(comp) = middleware.extract::<PyCompressionMiddleware>() {
-            self.register_boxed_middleware(comp.inner.clone());
-        } else if let Ok(rate) = middleware.extract::<PyRateLimitMiddleware>() {
-            self.register_boxed_middleware(rate.inner.clone());
-        } else if let Ok(timeout) = middleware.extract::<PyTimeoutMiddleware>() {
-            self.register_boxed_middleware(timeout.inner.clone());
-        } else if let Ok(log) = middleware.extract::<PyLogMiddleware>() {
-            self.register_boxed_middleware(log.inner.clone());
-        } else if let Ok(auth) = middleware.extract::<PyBasicAuthMiddleware>() {
-            self.register_boxed_middleware(auth.inner.clone());
-        } else {
+        let Some((middleware, recorder)) = crate::middleware::builtin_middleware(middleware) else {
             return Err(pyo3::exceptions::PyTypeError::new_err(
                 "Middleware must be a Rust middleware type (CORS, SecurityHeaders, RequestId, etc.)"
             ));
+        };
+        self.register_boxed_middleware(middleware, placement);
+        if let Some(recorder) = recorder {
+            Arc::get_mut(&mut self.rust_middleware)
+                .expect("Cannot modify middleware after server start")
+                .use_after_boxed(recorder);
+        }
+
+        Ok(())
+    }
+
+    /// Configure middleware error isolation and slow-middleware warnings,
+    /// and optionally register a chain described by `config`.
+    ///
+    /// `config` is a dict with a `middleware` list (or the list itself).
+    /// Each entry names a builtin `type` (cors, rate_limit, security_headers,
+    /// compression, request_id, log, basic_auth, timeout, idempotency,
+    /// singleflight) and passes its other keys to that constructor, except
+    /// `name`, `enabled` (default true), `paths` (prefixes it runs for),
+    /// `methods`, `priority`, `before` and `after`. The dict may also set
+    /// `isolate_errors` and `slow_threshold_ms`, which win over the
+    /// arguments. Nothing is registered unless every entry is valid; the
+    /// `ValueError` lists every problem found.
+    ///
+    /// Args:
+    ///     config: Declarative middleware chain (optional)
+    ///     isolate_errors: Treat panics and 5xx errors in non-critical middleware
+    ///         as Continue instead of failing the request (default: False)
+    ///     slow_threshold_ms: Warn when a single middleware takes longer (optional)
+    #[pyo3(signature = (config=None, isolate_errors=false, slow_threshold_ms=None))]
+    pub fn configure_middleware(
+        &mut self,
+        config: Option<&Bound<'_, PyAny>>,
+        isolate_errors: bool,
+        slow_threshold_ms: Option<u64>,
+    ) -> PyResult<()> {
+        let config = config
+            .map(crate::middleware::config::MiddlewareConfig::from_py)
+            .transpose()?
+            .unwrap_or_default();
+        let chain = Arc::get_mut(&mut self.rust_middleware)
+            .expect("Cannot modify middleware after server start");
+        chain.set_isolate_errors(config.isolate_errors.unwrap_or(isolate_errors));
+        chain.set_slow_threshold(
+            config
+                .slow_threshold_ms
+                .or(slow_threshold_ms)
+                .map(std::time::Duration::from_millis),
+        );
+        for entry in config.entries {
+            self.register_boxed_middleware(
+                entry.middleware,
+                (entry.priority, entry.anchors, entry.methods),
+            );
+            if let Some(recorder) = entry.recorder {
+                Arc::get_mut(&mut self.rust_middleware)
+                    .expect("Cannot modify middleware after server start")
+                    .use_after_boxed(recorder);
+            }
         }
+        Ok(())
+    }
 
+    /// Default keepalive interval for live SSE responses.
+    ///
+    /// Args:
+    ///     secs: Seconds of inactivity before the shared keepalive task sends
+    ///         a `: keepalive` comment; None or 0 disables it
+    #[pyo3(signature = (secs=None))]
+    pub fn set_sse_keepalive(&mut self, secs: Option<f64>) {
+        crate::http::sse_keepalive::set_default_interval(
+            crate::http::sse_keepalive::interval_from_secs(secs),
+        );
+    }
+
+    /// Serve long polls on the channels of `manager`.
+    ///
+    /// `GET <path>?channel=X&cursor=N&wait=S` returns
+    /// `{"messages": [...], "next_cursor": M}` with the channel's retained
+    /// messages after `cursor`, waiting up to `wait` seconds for one when
+    /// there are none. Only channels created with a `replay_size` can be
+    /// polled. Requests pass through middleware like any other route.
+    ///
+    /// Args:
+    ///     manager: ChannelManager whose channels are served
+    ///     path: Endpoint path (default: "/realtime/poll")
+    ///     max_wait_secs: Cap on `wait`, and its default; keep it below the
+    ///         idle timeout of proxies in front of the server (default: 25)
+    ///     max_polls_per_client: Polls one client may hold at once; more get
+    ///         a 429. Clients are told apart by authenticated user, else by
+    ///         peer address (default: 4)
+    ///     require_auth_for_realtime: Answer polls no auth middleware
+    ///         authenticated with a 401 (default: False)
+    ///     on_connect: Called with the `RealtimeIdentity` and channel name of
+    ///         every poll that is served
+    #[pyo3(signature = (manager, path="/realtime/poll", max_wait_secs=poll::DEFAULT_MAX_WAIT_SECS, max_polls_per_client=poll::DEFAULT_MAX_POLLS_PER_CLIENT, require_auth_for_realtime=false, on_connect=None))]
+    pub fn set_realtime_poll(
+        &mut self,
+        manager: PyRef<'_, ChannelManager>,
+        path: &str,
+        max_wait_secs: f64,
+        max_polls_per_client: usize,
+        require_auth_for_realtime: bool,
+        on_connect: Option<Py<PyAny>>,
+    ) -> PyResult<()> {
+        if !path.starts_with('/') {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "poll path '{}' must start with '/'",
+                path
+            )));
+        }
+        if !max_wait_secs.is_finite() || max_wait_secs <= 0.0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "max_wait_secs must be a positive number",
+            ));
+        }
+        if max_polls_per_client == 0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "max_polls_per_client must be at least 1",
+            ));
+        }
+        self.realtime_poll = Some(Arc::new(PollEndpoint::new(
+            manager.source(),
+            path.to_string(),
+            std::time::Duration::from_secs_f64(max_wait_secs),
+            max_polls_per_client,
+            require_auth_for_realtime,
+            on_connect,
+        )));
         Ok(())
     }
 
+    /// Resolved middleware order, as it runs once the server starts.
+    ///
+    /// Returns `{"before": [...], "after": [...], "error_handlers": n}`;
+    /// each middleware is a dict with `name`, `priority` and `anchors`
+    /// (`("before" | "after", name)` pairs). Raises `ValueError` when the
+    /// anchors cannot be resolved.
+    pub fn describe_middleware<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let chain = self
+            .rust_middleware
+            .describe()
+            .map_err(pyo3::exceptions::PyValueError::new_err)?;
+        let list = |infos: &[MiddlewareInfo]| -> PyResult<Vec<Bound<'py, PyDict>>> {
+            infos
+                .iter()
+                .map(|info| {
+                    let entry = PyDict::new(py);
+                    entry.set_item("name", info.name)?;
+                    entry.set_item("priority", info.priority)?;
+                    let anchors: Vec<(&str, &str)> = info
+                        .anchors
+                        .iter()
+                        .map(|anchor| match anchor {
+                            Anchor::Before(name) => ("before", name.as_str()),
+                            Anchor::After(name) => ("after", name.as_str()),
+                        })
+                        .collect();
+                    entry.set_item("anchors", anchors)?;
+                    Ok(entry)
+                })
+                .collect()
+        };
+        let out = PyDict::new(py);
+        out.set_item("before", list(&chain.before)?)?;
+        out.set_item("after", list(&chain.after)?)?;
+        out.set_item("error_handlers", chain.error_handlers)?;
+        Ok(out)
+    }
+
+    /// Get middleware statistics
+    pub fn middleware_stats(&self) -> (usize, usize, usize) {
+        self.rust_middleware.stats()
+    }
+
+    /// Per-middleware timing and error counters for this process.
+    ///
+    /// Returns a dict keyed by middleware name with `calls`, `errors`,
+    /// `total_ms`, `avg_ms` and `max_ms`.
+    pub fn middleware_instrumentation<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let stats = PyDict::new(py);
+        for (name, snap) in self.rust_middleware.metrics().snapshot() {
+            let entry = PyDict::new(py);
+            entry.set_item("calls", snap.calls)?;
+            entry.set_item("errors", snap.errors)?;
+            entry.set_item("total_ms", snap.total_us as f64 / 1000.0)?;
+            entry.set_item("avg_ms", snap.avg_us() / 1000.0)?;
+            entry.set_item("max_ms", snap.max_us as f64 / 1000.0)?;
+            stats.set_item(name, entry)?;
+        }
+        Ok(stats)
+    }
+
+    /// Start the server. `num_processes=0` sizes the worker count from the
+    /// CPUs actually available, honouring cgroup CPU quotas.
+    ///
+    /// Raises `HypernBindError` (an `OSError`) when the address cannot be
+    /// bound, before any worker is forked, or when a worker cannot open its
+    /// listener; the other workers are stopped first. Raises `ValueError`
+    /// when routes conflict under `strict_routes` (see `Router.check()`).
     #[pyo3(signature = (host, port, num_processes=1, workers_threads=1, max_blocking_threads=16, max_connections=10000))]
     pub fn start(
         &mut self,
@@ -123,15 +932,35 @@ impl Server {
         max_blocking_threads: usize,
         max_connections: usize,
     ) -> PyResult<()> {
-        // Initialize the log queue
-        LogQueue::init(self.log_config.clone());
+        // Fails under strict_routes before anything is bound
+        self.router.validate()?;
+
+        // Inherited by forked workers
+        self.install_process_config()?;
+        admission::set_max_connections(Some(max_connections));
+        crate::realtime::handoff::begin();
+
+        let num_processes = if num_processes == 0 {
+            cpu::effective_cpus()
+        } else {
+            num_processes
+        };
+        self.worker_layout = self.cpu_affinity.layout(num_processes, &cpu::allowed_cpus());
+        self.log_worker_layout(num_processes);
 
         // Collect handlers before fork
-        let raw_socket = SocketHeld::new(host.clone(), port)?;
-        let mut handlers: Vec<(u64, Py<PyAny>)> = Vec::new();
-        for route in self.router.iter() {
-            handlers.push((route.handler_hash(), route.function.clone_ref(py)));
-        }
+        let raw_socket = SocketHeld::new(host.clone(), port, &self.socket_options)?;
+        self.socket_options = raw_socket.options.clone();
+        self.listen = Some(describe::Listen {
+            host: host.clone(),
+            port,
+            num_processes,
+            workers_threads,
+            max_blocking_threads,
+            max_connections,
+        });
+        describe::announce(self.snapshot());
+        let handlers = self.handlers(py);
 
         let router = self.router.clone();
         let middleware = self.rust_middleware.clone();
@@ -141,10 +970,11 @@ impl Server {
         self.reload_manager = Some(reload_manager.clone());
 
         // Spawn worker processes using fork
-        let pids = spawn_workers(
+        #[cfg_attr(not(unix), allow(unused_mut))]
+        let mut pids = spawn_workers(
             py,
             raw_socket,
-            num_processes,
+            0..num_processes,
             workers_threads,
             max_blocking_threads,
             max_connections,
@@ -152,7 +982,9 @@ impl Server {
             middleware,
             handlers,
             reload_manager.clone(),
-        );
+            &self.worker_layout,
+        )?;
+        self.record_worker_pids(&pids);
 
         hlog_info!("All {} workers started", pids.len());
 
@@ -204,24 +1036,25 @@ impl Server {
                         unsafe { libc::kill(pid, libc::SIGUSR1); }
                     }
 
-                    // Wait for drain timeout, then SIGTERM workers
+                    // Wait until they have drained and exited, terminating
+                    // any still running at the drain timeout
                     let drain_secs = reload_manager.config().drain_timeout_secs;
-                    std::thread::sleep(std::time::Duration::from_secs(drain_secs));
-
-                    // Terminate old workers gracefully
-                    terminate_workers(&pids);
+                    crate::core::multiprocess::drain_workers(
+                        &pids,
+                        std::time::Duration::from_secs(drain_secs),
+                    );
                     wait_for_workers(&pids);
 
-                    // Respawn workers
+                    // Respawn workers, which read the replay history the
+                    // old generation saved
+                    crate::realtime::handoff::next_generation();
                     let new_rm = ReloadManager::new(self.reload_config.clone());
-                    let new_handlers: Vec<(u64, Py<PyAny>)> = Python::attach(|py| {
-                        self.router.iter().map(|r| (r.handler_hash(), r.function.clone_ref(py))).collect()
-                    });
-                    let new_socket = SocketHeld::new(host.clone(), port)?;
+                    let new_handlers = Python::attach(|py| self.handlers(py));
+                    let new_socket = SocketHeld::new(host.clone(), port, &self.socket_options)?;
                     let new_pids = spawn_workers(
                         py,
                         new_socket,
-                        num_processes,
+                        0..num_processes,
                         workers_threads,
                         max_blocking_threads,
                         max_connections,
@@ -229,7 +1062,9 @@ impl Server {
                         self.rust_middleware.clone(),
                         new_handlers,
                         new_rm.clone(),
-                    );
+                        &self.worker_layout,
+                    )?;
+                    self.record_worker_pids(&new_pids);
 
                     self.reload_manager = Some(new_rm.clone());
                     hlog_info!("Graceful reload complete – {} new workers started", new_pids.len());
@@ -256,14 +1091,12 @@ impl Server {
 
                     // Respawn workers
                     let new_rm = ReloadManager::new(self.reload_config.clone());
-                    let new_handlers: Vec<(u64, Py<PyAny>)> = Python::attach(|py| {
-                        self.router.iter().map(|r| (r.handler_hash(), r.function.clone_ref(py))).collect()
-                    });
-                    let new_socket = SocketHeld::new(host.clone(), port)?;
+                    let new_handlers = Python::attach(|py| self.handlers(py));
+                    let new_socket = SocketHeld::new(host.clone(), port, &self.socket_options)?;
                     let new_pids = spawn_workers(
                         py,
                         new_socket,
-                        num_processes,
+                        0..num_processes,
                         workers_threads,
                         max_blocking_threads,
                         max_connections,
@@ -271,7 +1104,9 @@ impl Server {
                         self.rust_middleware.clone(),
                         new_handlers,
                         new_rm.clone(),
-                    );
+                        &self.worker_layout,
+                    )?;
+                    self.record_worker_pids(&new_pids);
 
                     self.reload_manager = Some(new_rm.clone());
                     hlog_info!("Hot reload complete – {} new workers started", new_pids.len());
@@ -282,26 +1117,54 @@ impl Server {
 
                 if SHUTDOWN.load(Ordering::SeqCst) {
                     hlog_info!("Received shutdown signal, stopping workers...");
-                    // Send SIGUSR1 to workers for brief drain before termination
+                    // Send SIGUSR1 so workers drain before exiting
                     for &pid in &pids {
                         unsafe { libc::kill(pid, libc::SIGUSR1); }
                     }
-                    // Brief grace period for in-flight requests
-                    std::thread::sleep(std::time::Duration::from_secs(2));
-                    terminate_workers(&pids);
+                    let drain_secs = reload_manager.config().drain_timeout_secs;
+                    crate::core::multiprocess::drain_workers(
+                        &pids,
+                        std::time::Duration::from_secs(drain_secs),
+                    );
                     break;
                 }
 
                 // Check if any worker has exited
-                unsafe {
-                    let mut status: libc::c_int = 0;
-                    let pid = libc::waitpid(-1, &mut status, libc::WNOHANG);
-                    if pid > 0 {
-                        // A worker exited, shutdown all workers
-                        hlog_warn!("Worker {} exited, shutting down...", pid);
-                        terminate_workers(&pids);
-                        break;
+                let mut status: libc::c_int = 0;
+                let pid = unsafe { libc::waitpid(-1, &mut status, libc::WNOHANG) };
+                if pid > 0 {
+                    // Replace a worker its watchdog recycled
+                    let recycled = libc::WIFEXITED(status)
+                        && libc::WEXITSTATUS(status) == crate::core::watchdog::EXIT_CODE;
+                    if let Some(index) = pids.iter().position(|&p| p == pid).filter(|_| recycled) {
+                        hlog_warn!(
+                            "Worker {} (PID {}) was stuck, starting a replacement",
+                            index + 1,
+                            pid
+                        );
+                        let new_handlers = Python::attach(|py| self.handlers(py));
+                        let new_socket = SocketHeld::new(host.clone(), port, &self.socket_options)?;
+                        let replacement = spawn_workers(
+                            py,
+                            new_socket,
+                            index..index + 1,
+                            workers_threads,
+                            max_blocking_threads,
+                            max_connections,
+                            self.router.clone(),
+                            self.rust_middleware.clone(),
+                            new_handlers,
+                            reload_manager.clone(),
+                            &self.worker_layout,
+                        )?;
+                        pids[index] = replacement[0];
+                        self.record_worker_pids(&pids);
+                        continue;
                     }
+                    // A worker exited, shutdown all workers
+                    hlog_warn!("Worker {} exited, shutting down...", pid);
+                    terminate_workers(&pids);
+                    break;
                 }
 
                 // Use shorter sleep for more responsive shutdown
@@ -310,20 +1173,119 @@ impl Server {
         }
         // Wait for all workers to finish
         wait_for_workers(&pids);
+        crate::realtime::handoff::discard();
+        LogQueue::shutdown();
 
         Ok(())
     }
 }
 
 impl Server {
+    /// Install the process-wide settings requests are served with
+    fn install_process_config(&mut self) -> PyResult<()> {
+        // Initialize the log queue
+        LogQueue::init(self.log_config.clone());
+        allowed_hosts::install(self.allowed_hosts.clone());
+        https::install(self.https.clone());
+        decompression::configure(self.decompress_requests, self.max_decompressed_size);
+        let tls_config = self.tls.as_ref().map(|files| files.load(self.http2)).transpose()?;
+        tls::install(tls_config.map(Arc::new));
+        timing::configure(self.server_timing);
+        gil::configure(
+            self.gil_metrics,
+            std::time::Duration::from_secs_f64(self.gil_hold_warn_ms / 1000.0),
+        );
+        cluster::configure(
+            (self.metrics_publish_interval > 0.0)
+                .then(|| std::time::Duration::from_secs_f64(self.metrics_publish_interval)),
+        );
+        header_policy::install(self.header_policy.clone());
+        warmup::configure(self.warmup.clone());
+        path::configure(self.merge_slashes, self.path_decoding, self.strict_path_encoding);
+        response::configure_stream_threshold(self.stream_threshold_bytes);
+        expect::configure(self.expect_continue);
+        memory_debug::configure(self.memory_debug);
+        auth::configure(&self.default_auth);
+        admission::configure(&self.admission);
+        header_limits::configure(&self.header_limits);
+        error_envelope::configure(self.error_format, self.error_handlers.keys().copied());
+        socket::configure(&self.socket_options);
+        poll::install(self.realtime_poll.clone());
+        deep_health::install(self.health_checks.clone());
+        if let Some(chain) = Arc::get_mut(&mut self.rust_middleware) {
+            chain
+                .resolve()
+                .map_err(pyo3::exceptions::PyValueError::new_err)?;
+        }
+        live_config::install(self.rust_middleware.clone());
+        Ok(())
+    }
+
+    /// Install this server's settings and handlers, replacing those of
+    /// another server set up in the same process
+    pub(crate) fn activate(&mut self, py: Python<'_>) -> PyResult<()> {
+        self.install_process_config()?;
+        for (hash, handler) in self.handlers(py) {
+            interpreter::register_handler(hash, handler);
+        }
+        Ok(())
+    }
+
+    /// Route handlers and error handlers, by handler hash
+    fn handlers(&self, py: Python<'_>) -> Vec<(u64, Py<PyAny>)> {
+        let routes = self
+            .router
+            .iter()
+            .map(|route| (route.handler_hash(), route.function.clone_ref(py)));
+        let errors = self
+            .error_handlers
+            .iter()
+            .map(|(&status, handler)| {
+                (error_envelope::handler_hash(status), handler.clone_ref(py))
+            });
+        routes.chain(errors).collect()
+    }
+
+    /// Set this process up to serve requests itself, as a worker would,
+    /// for `TestClient`. Returns the state the worker's Axum app runs on.
+    pub(crate) fn in_process_state(&mut self, py: Python<'_>) -> PyResult<AppState> {
+        self.activate(py)?;
+        let ev_loop = get_event_loop(py).bind(py);
+        crate::core::deps::start_app_scope(py, ev_loop)?;
+        if try_global_runtime().is_none() {
+            set_global_runtime(2, 16, 16, 60, Arc::new(ev_loop.clone().unbind()));
+        }
+
+        // No startup grace: the app is ready as soon as the client exists
+        let reload_manager = ReloadManager::new(self.reload_config.clone());
+        reload_manager.health().mark_healthy();
+        self.reload_manager = Some(reload_manager.clone());
+        Ok(AppState {
+            router: self.router.clone(),
+            middleware: self.rust_middleware.clone(),
+            reload_manager,
+        })
+    }
+
     /// Internal method to register a boxed middleware (not exposed to Python)
     fn register_boxed_middleware(
         &mut self,
         middleware: Arc<dyn crate::middleware::RustMiddleware>,
+        (priority, anchors, methods): (
+            Option<i32>,
+            Vec<Anchor>,
+            Option<crate::http::method::MethodSet>,
+        ),
     ) {
+        let middleware = match methods {
+            Some(methods) => Arc::new(crate::middleware::MethodMiddleware::new(
+                middleware, methods,
+            )),
+            None => middleware,
+        };
         Arc::get_mut(&mut self.rust_middleware)
             .expect("Cannot modify middleware after server start")
-            .use_before_boxed(middleware);
+            .use_before_anchored(middleware, priority, anchors);
     }
 
     /// Add a pure Rust middleware that runs before handlers (no GIL overhead)
@@ -351,8 +1313,147 @@ impl Server {
         self.rust_middleware = Arc::new(chain);
     }
 
-    /// Get middleware statistics
-    pub fn middleware_stats(&self) -> (usize, usize, usize) {
-        self.rust_middleware.stats()
+    /// Configuration part of `stats()` and `describe()`
+    fn config_json(&self) -> Map<String, Value> {
+        let client_auth = match &self.tls {
+            Some(files) if files.require_client_cert => "required",
+            Some(files) if files.client_ca_path.is_some() => "optional",
+            _ => "none",
+        };
+        let options = &self.socket_options;
+        let config = json!({
+            "routes": self.router.routes_count(),
+            "http2": self.http2,
+            "num_workers": self.worker_pids.len(),
+            "allowed_hosts": self.allowed_hosts.as_ref().map(|h| h.patterns().to_vec()),
+            "force_https": self.https.force_https,
+            "hsts_preload": self.https.hsts_preload,
+            "trusted_proxies": self
+                .https
+                .trusted_proxies
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            "https_exempt_paths": self.https.exempt_paths,
+            "decompress_requests": self.decompress_requests,
+            "max_decompressed_size": self.max_decompressed_size,
+            "tls": self.tls.is_some(),
+            "tls_client_auth": client_auth,
+            "server_timing": self.server_timing,
+            "gil_metrics": self.gil_metrics,
+            "gil_hold_warn_ms": self.gil_hold_warn_ms,
+            "metrics_publish_interval": self.metrics_publish_interval,
+            "header_policy": self.header_policy.to_json(),
+            "warmup_paths": self.warmup.paths,
+            "eager_import": self.warmup.eager_import,
+            "stream_threshold_bytes": self.stream_threshold_bytes,
+            "expect_continue": self.expect_continue,
+            "memory_debug": self.memory_debug,
+            "default_auth": self.default_auth.name(),
+            "max_connections_per_ip": self.admission.max_connections_per_ip,
+            "header_read_timeout": self
+                .admission
+                .header_read_timeout
+                .map(|timeout| timeout.as_secs_f64()),
+            "connection_limit_exempt": self
+                .admission
+                .exempt
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            "max_header_bytes": self.header_limits.max_header_bytes,
+            "max_header_count": self.header_limits.max_header_count,
+            "max_uri_length": self.header_limits.max_uri_length,
+            "error_format": self.error_format.as_str(),
+            "error_handlers": self.error_handlers.keys().collect::<Vec<_>>(),
+            "backlog": options.backlog,
+            "reuse_port": options.reuse_port,
+            "tcp_nodelay": options.tcp_nodelay,
+            "keepalive_idle": options.keepalive_idle,
+            "keepalive_interval": options.keepalive_interval,
+            "keepalive_count": options.keepalive_count,
+            "realtime_poll": self.realtime_poll.as_ref().map(|endpoint| endpoint.path()),
+        });
+        match config {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Unredacted `describe()` snapshot
+    fn snapshot(&self) -> Value {
+        let (before, after) = match self.rust_middleware.describe() {
+            Ok(chain) => (
+                chain.before.iter().map(|info| info.name).collect(),
+                chain.after.iter().map(|info| info.name).collect(),
+            ),
+            Err(_) => (
+                self.rust_middleware.before_names(),
+                self.rust_middleware.after_names(),
+            ),
+        };
+        let compression = before.iter().chain(&after).any(|name| *name == "compression");
+        json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "pid": std::process::id(),
+            "allocator": describe::allocator(),
+            "listen": self.listen.as_ref().map(|listen| listen.to_json()),
+            "worker_pids": self.worker_pids,
+            "routes": self.router.routes_count(),
+            "middleware": { "before": before, "after": after },
+            "features": {
+                "tls": self.tls.is_some(),
+                "http2": self.http2,
+                "compression": compression,
+                "decompression": self.decompress_requests,
+                "server_timing": self.server_timing,
+                "gil_metrics": self.gil_metrics,
+                "memory_debug": self.memory_debug,
+                "expect_continue": self.expect_continue,
+                "realtime_poll": self.realtime_poll.is_some(),
+                "health_probes": self.reload_config.health_probes_enabled,
+            },
+            "logging": {
+                "level": self.log_config.level.as_str(),
+                "log_request": self.log_config.log_request,
+                "log_response": self.log_config.log_response,
+                "redact_patterns": self.log_config.redact_patterns.len(),
+                "max_field_len": self.log_config.max_field_len,
+            },
+            "databases": ConnectionPoolManager::describe(),
+            "config": self.config_json(),
+        })
+    }
+
+    fn log_worker_layout(&self, num_workers: usize) {
+        let quota = cpu::cgroup_cpu_limit()
+            .map(|q| format!("{:.2}", q))
+            .unwrap_or_else(|| "none".to_string());
+        hlog_info!(
+            "Starting {} workers ({} usable CPUs, cgroup quota: {})",
+            num_workers,
+            cpu::effective_cpus(),
+            quota
+        );
+        if self.worker_layout.is_empty() {
+            return;
+        }
+        if cfg!(target_os = "linux") {
+            for (worker_id, cores) in self.worker_layout.iter().enumerate() {
+                hlog_info!("Worker {} pinned to CPUs {:?}", worker_id + 1, cores);
+            }
+        } else {
+            hlog_warn!("cpu_affinity is only supported on Linux; workers will not be pinned");
+        }
+    }
+
+    #[cfg(unix)]
+    fn record_worker_pids(&mut self, pids: &[libc::pid_t]) {
+        self.worker_pids = pids.iter().map(|&pid| pid as i64).collect();
+    }
+
+    #[cfg(not(unix))]
+    fn record_worker_pids(&mut self, _handles: &[std::thread::JoinHandle<()>]) {
+        self.worker_pids = Vec::new();
     }
 }
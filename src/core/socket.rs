@@ -1,89 +1,490 @@
+use pyo3::create_exception;
+use pyo3::exceptions::{PyOSError, PyValueError};
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 
 use socket2::{Domain, Protocol, Socket, Type};
 use std::{
+    fmt, io,
     net::{IpAddr, SocketAddr},
+    sync::atomic::{AtomicBool, AtomicI64, Ordering},
     time::Duration,
 };
 
-#[derive(Debug)]
-pub struct SocketHeld {
-    pub socket: Socket,
-}
+create_exception!(
+    _hypern,
+    HypernBindError,
+    PyOSError,
+    "The server could not acquire its listening socket (address in use, permission denied, ...)."
+);
 
-impl SocketHeld {
-    pub fn new(ip: String, port: u16) -> PyResult<SocketHeld> {
-        let ip: IpAddr = ip.parse()?;
-        let socket = if ip.is_ipv4() {
-            Socket::new(Domain::IPV4, Type::STREAM, Some(Protocol::TCP))?
-        } else {
-            Socket::new(Domain::IPV6, Type::STREAM, Some(Protocol::TCP))?
-        };
+/// Default listen backlog
+pub const DEFAULT_BACKLOG: i32 = 1024;
+/// Default idle time before TCP keepalive probes start, in seconds
+pub const DEFAULT_KEEPALIVE_IDLE: u64 = 60;
 
-        let address = SocketAddr::new(ip, port);
+/// Applied to every accepted connection; set before workers start
+static ACCEPT_NODELAY: AtomicBool = AtomicBool::new(true);
+/// This worker's listening socket, -1 outside a worker
+static LISTENER_FD: AtomicI64 = AtomicI64::new(-1);
 
-        #[cfg(not(target_os = "windows"))]
-        socket.set_reuse_port(true)?;
-
-        // TCP tuning
-        socket.set_tcp_nodelay(true)?; // Disable Nagle
-        socket.set_reuse_address(true)?;
-
-        // set keepalive with 60s interval
-        let keepalive = socket2::TcpKeepalive::new().with_time(Duration::from_secs(60));
-        socket.set_keepalive(true)?;
-        socket.set_tcp_keepalive(&keepalive)?;
-        // Use a small linger timeout to allow graceful close
-        // This gives time for FIN/ACK handshake instead of RST
-        socket.set_linger(Some(Duration::from_secs(1)))?;
+/// Listener tuning chosen on the Server constructor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocketOptions {
+    /// Pending connection queue length passed to `listen`
+    pub backlog: i32,
+    /// Each worker binds its own SO_REUSEPORT socket instead of sharing one
+    pub reuse_port: bool,
+    /// Disable Nagle's algorithm on accepted connections
+    pub tcp_nodelay: bool,
+    /// Idle seconds before keepalive probes; None disables TCP keepalive
+    pub keepalive_idle: Option<u64>,
+    /// Seconds between probes; None keeps the system default
+    pub keepalive_interval: Option<u64>,
+    /// Unanswered probes before the connection is dropped; None keeps the
+    /// system default
+    pub keepalive_count: Option<u32>,
+}
 
-        // Set Increase buffer sizes
-        socket.set_recv_buffer_size(256 * 1024)?; // 256KB
-        socket.set_send_buffer_size(256 * 1024)?; // 256KB
+impl Default for SocketOptions {
+    fn default() -> Self {
+        Self {
+            backlog: DEFAULT_BACKLOG,
+            reuse_port: false,
+            tcp_nodelay: true,
+            keepalive_idle: Some(DEFAULT_KEEPALIVE_IDLE),
+            keepalive_interval: None,
+            keepalive_count: None,
+        }
+    }
+}
 
-        // Linux-specific optimizations
-        #[cfg(target_os = "linux")]
+impl SocketOptions {
+    pub fn new(
+        backlog: i32,
+        reuse_port: bool,
+        tcp_nodelay: bool,
+        keepalive_idle: Option<u64>,
+        keepalive_interval: Option<u64>,
+        keepalive_count: Option<u32>,
+    ) -> PyResult<Self> {
+        if backlog < 1 {
+            return Err(PyValueError::new_err("backlog must be at least 1"));
+        }
+        if keepalive_idle == Some(0) || keepalive_interval == Some(0) || keepalive_count == Some(0)
         {
-            use std::os::unix::io::AsRawFd;
-            let fd = socket.as_raw_fd();
-
-            // Enable TCP_FASTOPEN
-            unsafe {
-                let enable: libc::c_int = 5; // Queue length
-                libc::setsockopt(
-                    fd,
-                    libc::IPPROTO_TCP,
-                    libc::TCP_FASTOPEN,
-                    &enable as *const _ as *const libc::c_void,
-                    std::mem::size_of::<libc::c_int>() as libc::socklen_t,
-                );
+            return Err(PyValueError::new_err(
+                "keepalive_idle, keepalive_interval and keepalive_count must be at least 1",
+            ));
+        }
+        if keepalive_idle.is_none() && (keepalive_interval.is_some() || keepalive_count.is_some()) {
+            return Err(PyValueError::new_err(
+                "keepalive_interval and keepalive_count require keepalive_idle",
+            ));
+        }
+        Ok(Self {
+            backlog,
+            reuse_port,
+            tcp_nodelay,
+            keepalive_idle,
+            keepalive_interval,
+            keepalive_count,
+        })
+    }
 
-                // Enable TCP_QUICKACK
-                let enable: libc::c_int = 1;
-                libc::setsockopt(
-                    fd,
-                    libc::IPPROTO_TCP,
-                    libc::TCP_QUICKACK,
-                    &enable as *const _ as *const libc::c_void,
-                    std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+    fn keepalive(&self) -> Option<socket2::TcpKeepalive> {
+        let idle = self.keepalive_idle?;
+        #[allow(unused_mut)]
+        let mut keepalive = socket2::TcpKeepalive::new().with_time(Duration::from_secs(idle));
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "android",
+            target_os = "macos",
+            target_os = "freebsd"
+        ))]
+        {
+            if let Some(interval) = self.keepalive_interval {
+                keepalive = keepalive.with_interval(Duration::from_secs(interval));
+            }
+            if let Some(count) = self.keepalive_count {
+                keepalive = keepalive.with_retries(count);
+            }
+        }
+        #[cfg(not(any(
+            target_os = "linux",
+            target_os = "android",
+            target_os = "macos",
+            target_os = "freebsd"
+        )))]
+        {
+            if self.keepalive_interval.is_some() || self.keepalive_count.is_some() {
+                crate::hlog_warn!(
+                    "keepalive_interval and keepalive_count are not supported on this platform"
                 );
             }
         }
+        Some(keepalive)
+    }
+}
+
+/// Set the per-connection options; inherited by forked workers
+pub fn configure(options: &SocketOptions) {
+    ACCEPT_NODELAY.store(options.tcp_nodelay, Ordering::Relaxed);
+}
+
+/// Apply the per-connection options to a freshly accepted stream
+pub fn configure_stream(stream: &tokio::net::TcpStream) {
+    let nodelay = ACCEPT_NODELAY.load(Ordering::Relaxed);
+    if let Err(err) = stream.set_nodelay(nodelay) {
+        crate::hlog_debug!("Failed to set TCP_NODELAY on accepted connection: {}", err);
+    }
+}
+
+/// A failed socket call while acquiring the listener. Plain data, so a
+/// forked worker can report it to the parent without touching Python.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindFailure {
+    pub address: SocketAddr,
+    /// OS error number, when the failure came from the OS
+    pub errno: Option<i32>,
+    pub message: String,
+}
+
+impl BindFailure {
+    /// Failure of `operation` ("bind", "listen", "set SO_REUSEPORT on", ...)
+    pub fn new(address: SocketAddr, operation: &str, err: io::Error) -> Self {
+        let port = address.port();
+        let message = match err.raw_os_error() {
+            Some(libc::EADDRINUSE) => format!(
+                "Address already in use: {} (another process is listening on port {})",
+                address, port
+            ),
+            Some(libc::EACCES) if port < 1024 => format!(
+                "Permission denied for port {} on {} (ports below 1024 need root or CAP_NET_BIND_SERVICE)",
+                port,
+                address.ip()
+            ),
+            Some(libc::EACCES) => format!("Permission denied for {}", address),
+            Some(libc::EADDRNOTAVAIL) => format!(
+                "Address not available: {} is not an address of this host",
+                address.ip()
+            ),
+            _ => format!("Failed to {} {}: {}", operation, address, err),
+        };
+        Self {
+            address,
+            errno: err.raw_os_error(),
+            message,
+        }
+    }
 
-        socket.set_nonblocking(true)?;
-        socket.bind(&address.into())?;
+    /// One line for the worker startup pipe
+    pub fn encode(&self) -> String {
+        format!(
+            "{} {} {}",
+            self.address,
+            self.errno.unwrap_or(0),
+            self.message.replace('\n', " ")
+        )
+    }
 
-        socket.listen(8192)?;
+    pub fn decode(line: &str) -> Option<Self> {
+        let mut parts = line.trim_end().splitn(3, ' ');
+        let address = parts.next()?.parse().ok()?;
+        let errno = parts.next()?.parse::<i32>().ok()?;
+        Some(Self {
+            address,
+            errno: (errno != 0).then_some(errno),
+            message: parts.next().unwrap_or_default().to_string(),
+        })
+    }
 
-        Ok(SocketHeld { socket })
+    /// `HypernBindError` with `errno`, `strerror`, `host` and `port` set
+    pub fn into_pyerr(self) -> PyErr {
+        let err = match self.errno {
+            Some(errno) => HypernBindError::new_err((errno, self.message)),
+            None => HypernBindError::new_err(self.message),
+        };
+        Python::attach(|py| {
+            let value = err.value(py);
+            let _ = value.setattr("host", self.address.ip().to_string());
+            let _ = value.setattr("port", self.address.port());
+        });
+        err
+    }
+}
+
+impl fmt::Display for BindFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<BindFailure> for PyErr {
+    fn from(failure: BindFailure) -> Self {
+        failure.into_pyerr()
+    }
+}
+
+#[derive(Debug)]
+pub struct SocketHeld {
+    pub socket: Socket,
+    /// Effective options; `reuse_port` is cleared when the platform refused it
+    pub options: SocketOptions,
+    address: SocketAddr,
+}
+
+impl SocketHeld {
+    /// Bind `ip:port`.
+    ///
+    /// With `reuse_port` the socket is bound but not listening: it only
+    /// reserves the address, and each worker opens its own listener through
+    /// `for_worker`. Where SO_REUSEPORT is unavailable this falls back to a
+    /// single listener shared by every worker.
+    pub fn new(ip: String, port: u16, options: &SocketOptions) -> PyResult<SocketHeld> {
+        let ip: IpAddr = ip
+            .parse()
+            .map_err(|_| PyValueError::new_err(format!("Invalid host address: {}", ip)))?;
+        let address = SocketAddr::new(ip, port);
+        let mut options = options.clone();
+
+        let socket = new_socket(address, &options)?;
+        if options.reuse_port && !set_reuse_port(&socket) {
+            options.reuse_port = false;
+        }
+        socket
+            .bind(&address.into())
+            .map_err(|err| BindFailure::new(address, "bind", err))?;
+        // Workers bind the port actually assigned when asked for port 0
+        let address = socket
+            .local_addr()
+            .ok()
+            .and_then(|addr| addr.as_socket())
+            .unwrap_or(address);
+        if !options.reuse_port {
+            socket
+                .listen(options.backlog)
+                .map_err(|err| BindFailure::new(address, "listen on", err))?;
+        }
+
+        Ok(SocketHeld {
+            socket,
+            options,
+            address,
+        })
+    }
+
+    /// Listener for one worker: its own SO_REUSEPORT socket, or a handle on
+    /// the shared one
+    pub fn for_worker(&self) -> Result<SocketHeld, BindFailure> {
+        let address = self.address;
+        if !self.options.reuse_port {
+            let socket = self
+                .socket
+                .try_clone()
+                .map_err(|err| BindFailure::new(address, "share the listener for", err))?;
+            return Ok(SocketHeld {
+                socket,
+                options: self.options.clone(),
+                address,
+            });
+        }
+        let socket = new_socket(address, &self.options)?;
+        if !set_reuse_port(&socket) {
+            return Err(BindFailure {
+                address,
+                errno: None,
+                message: format!(
+                    "SO_REUSEPORT could not be set on the worker socket for {}",
+                    address
+                ),
+            });
+        }
+        socket
+            .bind(&address.into())
+            .map_err(|err| BindFailure::new(address, "bind", err))?;
+        socket
+            .listen(self.options.backlog)
+            .map_err(|err| BindFailure::new(address, "listen on", err))?;
+        Ok(SocketHeld {
+            socket,
+            options: self.options.clone(),
+            address: self.address,
+        })
     }
 
     pub fn try_clone(&self) -> PyResult<SocketHeld> {
         let copied = self.socket.try_clone()?;
-        Ok(SocketHeld { socket: copied })
+        Ok(SocketHeld {
+            socket: copied,
+            options: self.options.clone(),
+            address: self.address,
+        })
     }
 
     pub fn get_socket(&self) -> Socket {
         self.socket.try_clone().unwrap()
     }
 }
+
+/// Unbound socket with the listener tuning applied
+fn new_socket(address: SocketAddr, options: &SocketOptions) -> Result<Socket, BindFailure> {
+    configure_socket(address, options)
+        .map_err(|err| BindFailure::new(address, "configure the socket for", err))
+}
+
+fn configure_socket(address: SocketAddr, options: &SocketOptions) -> io::Result<Socket> {
+    let socket = if address.is_ipv4() {
+        Socket::new(Domain::IPV4, Type::STREAM, Some(Protocol::TCP))?
+    } else {
+        Socket::new(Domain::IPV6, Type::STREAM, Some(Protocol::TCP))?
+    };
+
+    // TCP tuning; accepted connections inherit these on most platforms
+    socket.set_tcp_nodelay(options.tcp_nodelay)?;
+    socket.set_reuse_address(true)?;
+
+    match options.keepalive() {
+        Some(keepalive) => {
+            socket.set_keepalive(true)?;
+            socket.set_tcp_keepalive(&keepalive)?;
+        }
+        None => socket.set_keepalive(false)?,
+    }
+    // Use a small linger timeout to allow graceful close
+    // This gives time for FIN/ACK handshake instead of RST
+    socket.set_linger(Some(Duration::from_secs(1)))?;
+
+    // Set Increase buffer sizes
+    socket.set_recv_buffer_size(256 * 1024)?; // 256KB
+    socket.set_send_buffer_size(256 * 1024)?; // 256KB
+
+    // Linux-specific optimizations
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::io::AsRawFd;
+        let fd = socket.as_raw_fd();
+
+        // Enable TCP_FASTOPEN
+        unsafe {
+            let enable: libc::c_int = 5; // Queue length
+            libc::setsockopt(
+                fd,
+                libc::IPPROTO_TCP,
+                libc::TCP_FASTOPEN,
+                &enable as *const _ as *const libc::c_void,
+                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+            );
+
+            // Enable TCP_QUICKACK
+            let enable: libc::c_int = 1;
+            libc::setsockopt(
+                fd,
+                libc::IPPROTO_TCP,
+                libc::TCP_QUICKACK,
+                &enable as *const _ as *const libc::c_void,
+                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+            );
+        }
+    }
+
+    socket.set_nonblocking(true)?;
+    Ok(socket)
+}
+
+/// Enable SO_REUSEPORT, false (with a warning) where it is unsupported
+fn set_reuse_port(socket: &Socket) -> bool {
+    #[cfg(all(
+        unix,
+        not(any(target_os = "solaris", target_os = "illumos", target_os = "cygwin"))
+    ))]
+    {
+        match socket.set_reuse_port(true) {
+            Ok(()) => true,
+            Err(err) => {
+                crate::hlog_warn!(
+                    "SO_REUSEPORT unavailable ({}); workers share one listener",
+                    err
+                );
+                false
+            }
+        }
+    }
+    #[cfg(not(all(
+        unix,
+        not(any(target_os = "solaris", target_os = "illumos", target_os = "cygwin"))
+    )))]
+    {
+        let _ = socket;
+        crate::hlog_warn!(
+            "SO_REUSEPORT is not supported on this platform; workers share one listener"
+        );
+        false
+    }
+}
+
+/// Remember the listener this worker accepts on, for `listener_options`
+pub fn record_listener(listener: &std::net::TcpListener) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::io::AsRawFd;
+        LISTENER_FD.store(listener.as_raw_fd() as i64, Ordering::Relaxed);
+    }
+    #[cfg(not(unix))]
+    let _ = listener;
+}
+
+/// Options read back with getsockopt from this worker's listening socket.
+///
+/// Returns None outside a worker, else a dict with `pid`, `reuse_port`,
+/// `tcp_nodelay`, `keepalive` and, where the platform reports them,
+/// `keepalive_idle`, `keepalive_interval` and `keepalive_count`.
+#[pyfunction]
+pub fn listener_options(py: Python<'_>) -> PyResult<Option<Bound<'_, PyDict>>> {
+    #[cfg(unix)]
+    {
+        use socket2::SockRef;
+        use std::os::unix::io::BorrowedFd;
+
+        let fd = LISTENER_FD.load(Ordering::Relaxed);
+        if fd < 0 {
+            return Ok(None);
+        }
+        // The listener lives as long as the worker that recorded it
+        let fd = unsafe { BorrowedFd::borrow_raw(fd as i32) };
+        let socket = SockRef::from(&fd);
+
+        let options = PyDict::new(py);
+        options.set_item("pid", std::process::id())?;
+        #[cfg(not(any(target_os = "solaris", target_os = "illumos", target_os = "cygwin")))]
+        options.set_item("reuse_port", socket.reuse_port()?)?;
+        options.set_item("tcp_nodelay", socket.tcp_nodelay()?)?;
+        options.set_item("keepalive", socket.keepalive()?)?;
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "android",
+            target_os = "macos",
+            target_os = "freebsd"
+        ))]
+        {
+            options.set_item("keepalive_idle", socket.tcp_keepalive_time()?.as_secs())?;
+            options.set_item(
+                "keepalive_interval",
+                socket.tcp_keepalive_interval()?.as_secs(),
+            )?;
+            options.set_item("keepalive_count", socket.tcp_keepalive_retries()?)?;
+        }
+        Ok(Some(options))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = py;
+        Ok(None)
+    }
+}
+
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(listener_options, m)?)?;
+    m.add("HypernBindError", m.py().get_type::<HypernBindError>())?;
+    Ok(())
+}
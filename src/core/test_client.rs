@@ -0,0 +1,526 @@
+//! In-process test client.
+//!
+//! `TestClient(server)` sets the process up the way a worker is set up
+//! (handlers registered, app-scoped dependencies started, the server's
+//! settings installed) and sends requests straight into the Axum app a
+//! worker serves, without a socket. Requests go through the same handler as
+//! network ones: Host checks, decompression, "before" middleware and its
+//! short-circuits, routing, per-route limits, the Python handler, "after"
+//! middleware, the header policy and the access log. Streamed bodies are
+//! collected until the stream ends.
+//!
+//! Handlers and server settings are installed process-wide, so a client
+//! used after another one reinstalls its own first.
+
+use axum::body::Body;
+use axum::extract::ConnectInfo;
+use axum::http::{header, HeaderName, HeaderValue, Method, Request};
+use pyo3::exceptions::{PyTimeoutError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList, PyString, PyTuple};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tower::ServiceExt;
+
+use crate::core::global::get_global_runtime;
+use crate::core::server::Server;
+use crate::core::worker::build_axum_router_public;
+use crate::http::connection::ConnectionInfo;
+use crate::http::response::content_types;
+use crate::utils::{parse_json_to_py, py_to_json_value, serialize_json_value};
+
+/// Host header sent when the request does not set one
+const DEFAULT_HOST: &str = "testserver";
+
+/// Peer address handlers see as the client
+const CLIENT_ADDR: ([u8; 4], u16) = ([127, 0, 0, 1], 50000);
+
+/// Boundary of `files=` bodies
+const MULTIPART_BOUNDARY: &str = "hypern-test-client-boundary";
+
+/// The client whose server's settings and handlers are installed
+static ACTIVE: AtomicU64 = AtomicU64::new(0);
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Sends requests through a server's full pipeline in this process.
+///
+/// Example:
+///     client = app.test_client()
+///     res = client.post("/users", json={"name": "Ada"})
+///     assert res.status == 201
+///     assert res.json()["name"] == "Ada"
+#[pyclass]
+pub struct TestClient {
+    id: u64,
+    server: Py<Server>,
+    app: axum::Router,
+    timeout: Duration,
+}
+
+#[pymethods]
+impl TestClient {
+    /// Create a client for `server`.
+    ///
+    /// Args:
+    ///     server: A configured `Server`, as `Hypern.test_client()` builds
+    ///     timeout: Seconds to wait for a response and its whole body before
+    ///         raising `TimeoutError` (default: 30)
+    #[new]
+    #[pyo3(signature = (server, timeout=30.0))]
+    pub fn new(py: Python<'_>, server: Bound<'_, Server>, timeout: f64) -> PyResult<Self> {
+        if !timeout.is_finite() || timeout <= 0.0 {
+            return Err(PyValueError::new_err("timeout must be a positive number"));
+        }
+        let state = server.borrow_mut().in_process_state(py)?;
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        ACTIVE.store(id, Ordering::Relaxed);
+        Ok(Self {
+            id,
+            server: server.unbind(),
+            app: build_axum_router_public(state),
+            timeout: Duration::from_secs_f64(timeout),
+        })
+    }
+
+    /// Send a request and wait for the complete response.
+    ///
+    /// Args:
+    ///     method: HTTP method
+    ///     path: Request path, optionally with a query string
+    ///     headers: Request headers
+    ///     query: Query parameters (a dict or a list of pairs), appended to
+    ///         any query string in `path`
+    ///     json: Body serialized as JSON
+    ///     data: Body as bytes or str, or a dict sent as a URL-encoded form
+    ///         (or as the text fields of a multipart body with `files`)
+    ///     files: Multipart file fields: name -> content, (filename, content)
+    ///         or (filename, content, content_type)
+    #[pyo3(signature = (method, path, headers=None, query=None, json=None, data=None, files=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn request(
+        &self,
+        py: Python<'_>,
+        method: &str,
+        path: &str,
+        headers: Option<&Bound<'_, PyDict>>,
+        query: Option<&Bound<'_, PyAny>>,
+        json: Option<&Bound<'_, PyAny>>,
+        data: Option<&Bound<'_, PyAny>>,
+        files: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<TestResponse> {
+        let request = build_request(method, path, headers, query, json, data, files)?;
+        // Settings and handlers are process-wide; switch them back to this
+        // client's server if another client was used in between
+        if ACTIVE.swap(self.id, Ordering::Relaxed) != self.id {
+            self.server.bind(py).borrow_mut().activate(py)?;
+        }
+        let app = self.app.clone();
+        let timeout = self.timeout;
+        let runtime = get_global_runtime();
+        let outcome = py.detach(|| {
+            runtime.inner.block_on(async move {
+                tokio::time::timeout(timeout, async move {
+                    let response = app.oneshot(request).await.unwrap_or_else(|e| match e {});
+                    let (parts, body) = response.into_parts();
+                    axum::body::to_bytes(body, usize::MAX)
+                        .await
+                        .map(|content| (parts, content))
+                })
+                .await
+            })
+        });
+        let (parts, content) = match outcome {
+            Ok(Ok(response)) => response,
+            Ok(Err(e)) => {
+                return Err(PyValueError::new_err(format!(
+                    "Failed to read response body: {}",
+                    e
+                )))
+            }
+            Err(_) => {
+                return Err(PyTimeoutError::new_err(format!(
+                    "No complete response within {:?}",
+                    timeout
+                )))
+            }
+        };
+        let headers = parts
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.as_str().to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                )
+            })
+            .collect();
+        Ok(TestResponse {
+            status: parts.status.as_u16(),
+            headers,
+            content: content.to_vec(),
+        })
+    }
+
+    #[pyo3(signature = (path, headers=None, query=None))]
+    pub fn get(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        headers: Option<&Bound<'_, PyDict>>,
+        query: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<TestResponse> {
+        self.request(py, "GET", path, headers, query, None, None, None)
+    }
+
+    #[pyo3(signature = (path, headers=None, query=None))]
+    pub fn head(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        headers: Option<&Bound<'_, PyDict>>,
+        query: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<TestResponse> {
+        self.request(py, "HEAD", path, headers, query, None, None, None)
+    }
+
+    #[pyo3(signature = (path, headers=None, query=None))]
+    pub fn options(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        headers: Option<&Bound<'_, PyDict>>,
+        query: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<TestResponse> {
+        self.request(py, "OPTIONS", path, headers, query, None, None, None)
+    }
+
+    #[pyo3(signature = (path, headers=None, query=None, json=None, data=None, files=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn post(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        headers: Option<&Bound<'_, PyDict>>,
+        query: Option<&Bound<'_, PyAny>>,
+        json: Option<&Bound<'_, PyAny>>,
+        data: Option<&Bound<'_, PyAny>>,
+        files: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<TestResponse> {
+        self.request(py, "POST", path, headers, query, json, data, files)
+    }
+
+    #[pyo3(signature = (path, headers=None, query=None, json=None, data=None, files=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn put(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        headers: Option<&Bound<'_, PyDict>>,
+        query: Option<&Bound<'_, PyAny>>,
+        json: Option<&Bound<'_, PyAny>>,
+        data: Option<&Bound<'_, PyAny>>,
+        files: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<TestResponse> {
+        self.request(py, "PUT", path, headers, query, json, data, files)
+    }
+
+    #[pyo3(signature = (path, headers=None, query=None, json=None, data=None, files=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn patch(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        headers: Option<&Bound<'_, PyDict>>,
+        query: Option<&Bound<'_, PyAny>>,
+        json: Option<&Bound<'_, PyAny>>,
+        data: Option<&Bound<'_, PyAny>>,
+        files: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<TestResponse> {
+        self.request(py, "PATCH", path, headers, query, json, data, files)
+    }
+
+    #[pyo3(signature = (path, headers=None, query=None, json=None, data=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn delete(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        headers: Option<&Bound<'_, PyDict>>,
+        query: Option<&Bound<'_, PyAny>>,
+        json: Option<&Bound<'_, PyAny>>,
+        data: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<TestResponse> {
+        self.request(py, "DELETE", path, headers, query, json, data, None)
+    }
+}
+
+/// A complete response received by `TestClient`.
+#[pyclass(frozen)]
+pub struct TestResponse {
+    status: u16,
+    /// In the order they were set, names lowercased
+    headers: Vec<(String, String)>,
+    content: Vec<u8>,
+}
+
+#[pymethods]
+impl TestResponse {
+    #[getter]
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// Response headers by lowercased name; repeated headers are joined
+    /// with ", "
+    #[getter]
+    pub fn headers<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let headers = PyDict::new(py);
+        for (name, value) in &self.headers {
+            match headers.get_item(name)? {
+                Some(existing) => headers.set_item(
+                    name,
+                    format!("{}, {}", existing.extract::<String>()?, value),
+                )?,
+                None => headers.set_item(name, value)?,
+            }
+        }
+        Ok(headers)
+    }
+
+    /// All values of a header, in the order they were set
+    pub fn header_values(&self, name: &str) -> Vec<String> {
+        self.headers
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+
+    /// The body as bytes
+    #[getter]
+    pub fn content<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.content)
+    }
+
+    /// The body decoded as UTF-8, invalid sequences replaced
+    #[getter]
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.content).into_owned()
+    }
+
+    /// The body parsed as JSON; raises `ValueError` when it is not JSON
+    pub fn json(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        parse_json_to_py(py, &self.content)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<TestResponse [{}]>", self.status)
+    }
+}
+
+fn build_request(
+    method: &str,
+    path: &str,
+    headers: Option<&Bound<'_, PyDict>>,
+    query: Option<&Bound<'_, PyAny>>,
+    json: Option<&Bound<'_, PyAny>>,
+    data: Option<&Bound<'_, PyAny>>,
+    files: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Request<Body>> {
+    let method = Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+        .map_err(|_| PyValueError::new_err(format!("Invalid HTTP method '{}'", method)))?;
+    if !path.starts_with('/') {
+        return Err(PyValueError::new_err(format!(
+            "Request path '{}' must start with '/'",
+            path
+        )));
+    }
+
+    let mut uri = path.to_string();
+    if let Some(query) = query {
+        let mut serializer = form_urlencoded::Serializer::new(String::new());
+        for (key, value) in pairs(query)? {
+            serializer.append_pair(&key, &value);
+        }
+        let encoded = serializer.finish();
+        if !encoded.is_empty() {
+            uri.push(if uri.contains('?') { '&' } else { '?' });
+            uri.push_str(&encoded);
+        }
+    }
+
+    let (body, content_type) = match (json, data, files) {
+        (Some(_), Some(_), _) | (Some(_), _, Some(_)) => {
+            return Err(PyValueError::new_err(
+                "Pass json, or data and files, not both",
+            ))
+        }
+        (Some(json), None, None) => (
+            serialize_json_value(&py_to_json_value(json)?)?,
+            Some(content_types::JSON.to_string()),
+        ),
+        (None, data, Some(files)) => (
+            multipart_body(data, files)?,
+            Some(format!(
+                "{}; boundary={}",
+                content_types::MULTIPART,
+                MULTIPART_BOUNDARY
+            )),
+        ),
+        (None, Some(data), None) => {
+            if data.is_instance_of::<PyDict>() {
+                let mut serializer = form_urlencoded::Serializer::new(String::new());
+                for (key, value) in pairs(data)? {
+                    serializer.append_pair(&key, &value);
+                }
+                (
+                    serializer.finish().into_bytes(),
+                    Some(content_types::FORM.to_string()),
+                )
+            } else {
+                (raw_bytes(data, "data", "bytes, str or a dict")?, None)
+            }
+        }
+        (None, None, None) => (Vec::new(), None),
+    };
+
+    let mut builder = Request::builder().method(method).uri(uri.as_str());
+    let mut has_host = false;
+    let mut has_content_type = false;
+    if let Some(headers) = headers {
+        for (name, value) in headers.iter() {
+            let name = name.extract::<String>()?;
+            let value = value.extract::<String>()?;
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| PyValueError::new_err(format!("Invalid header name '{}'", name)))?;
+            let value = HeaderValue::from_str(&value).map_err(|_| {
+                PyValueError::new_err(format!("Invalid value for header '{}'", name))
+            })?;
+            has_host |= name == header::HOST;
+            has_content_type |= name == header::CONTENT_TYPE;
+            builder = builder.header(name, value);
+        }
+    }
+    if !has_host {
+        builder = builder.header(header::HOST, DEFAULT_HOST);
+    }
+    if let Some(content_type) = content_type.filter(|_| !has_content_type) {
+        builder = builder.header(header::CONTENT_TYPE, content_type);
+    }
+    if !body.is_empty() {
+        builder = builder.header(header::CONTENT_LENGTH, body.len());
+    }
+    // What a connection accepted by the worker's listener would carry
+    builder = builder.extension(ConnectInfo(ConnectionInfo::plain(SocketAddr::from(
+        CLIENT_ADDR,
+    ))));
+    builder
+        .body(Body::from(body))
+        .map_err(|e| PyValueError::new_err(format!("Invalid request: {}", e)))
+}
+
+/// Key/value pairs of a dict or a list of pairs; values are converted with
+/// `str()`, and a list value in a dict repeats the key
+fn pairs(obj: &Bound<'_, PyAny>) -> PyResult<Vec<(String, String)>> {
+    let mut pairs = Vec::new();
+    if let Ok(dict) = obj.cast::<PyDict>() {
+        for (key, value) in dict.iter() {
+            let key = key.str()?.to_string();
+            match value.cast::<PyList>() {
+                Ok(values) => {
+                    for value in values.iter() {
+                        pairs.push((key.clone(), value.str()?.to_string()));
+                    }
+                }
+                Err(_) => pairs.push((key, value.str()?.to_string())),
+            }
+        }
+        return Ok(pairs);
+    }
+    for item in obj.try_iter()? {
+        let (key, value): (Bound<'_, PyAny>, Bound<'_, PyAny>) = item?.extract()?;
+        pairs.push((key.str()?.to_string(), value.str()?.to_string()));
+    }
+    Ok(pairs)
+}
+
+fn raw_bytes(obj: &Bound<'_, PyAny>, option: &str, expected: &str) -> PyResult<Vec<u8>> {
+    if let Ok(bytes) = obj.cast::<PyBytes>() {
+        return Ok(bytes.as_bytes().to_vec());
+    }
+    if let Ok(text) = obj.cast::<PyString>() {
+        return Ok(text.to_str()?.as_bytes().to_vec());
+    }
+    Err(PyValueError::new_err(format!(
+        "{} must be {}, got {}",
+        option,
+        expected,
+        obj.get_type().name()?
+    )))
+}
+
+fn multipart_body(data: Option<&Bound<'_, PyAny>>, files: &Bound<'_, PyDict>) -> PyResult<Vec<u8>> {
+    let mut body = Vec::new();
+    if let Some(data) = data {
+        if !data.is_instance_of::<PyDict>() {
+            return Err(PyValueError::new_err(
+                "data must be a dict when sent with files",
+            ));
+        }
+        for (name, value) in pairs(data)? {
+            body.extend_from_slice(
+                format!(
+                    "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n",
+                    MULTIPART_BOUNDARY, name
+                )
+                .as_bytes(),
+            );
+            body.extend_from_slice(value.as_bytes());
+            body.extend_from_slice(b"\r\n");
+        }
+    }
+    for (name, value) in files.iter() {
+        let name = name.extract::<String>()?;
+        let (filename, content, content_type) = match value.cast::<PyTuple>() {
+            Ok(parts) => match parts.len() {
+                2 => (
+                    parts.get_item(0)?.extract::<String>()?,
+                    parts.get_item(1)?,
+                    content_types::OCTET_STREAM.to_string(),
+                ),
+                3 => (
+                    parts.get_item(0)?.extract::<String>()?,
+                    parts.get_item(1)?,
+                    parts.get_item(2)?.extract::<String>()?,
+                ),
+                _ => {
+                    return Err(PyValueError::new_err(format!(
+                        "files['{}'] must be content, (filename, content) or (filename, content, content_type)",
+                        name
+                    )))
+                }
+            },
+            Err(_) => (
+                name.clone(),
+                value.clone(),
+                content_types::OCTET_STREAM.to_string(),
+            ),
+        };
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                MULTIPART_BOUNDARY, name, filename, content_type
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(&raw_bytes(
+            &content,
+            &format!("files['{}']", name),
+            "bytes or str",
+        )?);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", MULTIPART_BOUNDARY).as_bytes());
+    Ok(body)
+}
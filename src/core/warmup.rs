@@ -0,0 +1,164 @@
+//! Worker warm-up before the first real request.
+//!
+//! Once a worker has started its app-scoped dependencies it can import the
+//! modules behind every handler (`eager_import`) and replay a few GET
+//! requests in-process, through middleware, routing and the handler, with
+//! the responses discarded. The worker is only marked healthy after warm-up
+//! finishes; with `strict`, a failure keeps it in `starting` so the startup
+//! and readiness probes stay at 503.
+
+use axum::body::Body;
+use parking_lot::RwLock;
+use pyo3::prelude::*;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::core::reload::ReloadManager;
+use crate::middleware::MiddlewareChain;
+use crate::routing::router::Router as HypernRouter;
+
+/// Header set on synthetic warm-up requests so handlers can tell them apart
+pub const WARMUP_HEADER: &str = "x-hypern-warmup";
+
+/// Longest a single warm-up request may take before it counts as failed
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Default)]
+pub struct WarmupConfig {
+    /// Paths requested with GET before the worker reports healthy
+    pub paths: Vec<String>,
+    /// Import each handler's module at worker start
+    pub eager_import: bool,
+    /// Keep the worker in `starting` when any warm-up step fails
+    pub strict: bool,
+}
+
+impl WarmupConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.eager_import || !self.paths.is_empty()
+    }
+}
+
+static CONFIG: RwLock<Option<Arc<WarmupConfig>>> = RwLock::new(None);
+
+/// Set the warm-up run by workers forked after this call
+pub fn configure(config: WarmupConfig) {
+    *CONFIG.write() = config.is_enabled().then(|| Arc::new(config));
+}
+
+pub fn config() -> Option<Arc<WarmupConfig>> {
+    CONFIG.read().clone()
+}
+
+/// Import the module defining each handler (after unwrapping decorators).
+///
+/// Returns the number of handlers whose module failed to import.
+pub fn import_handlers(py: Python<'_>, worker_id: usize, handlers: &[Py<PyAny>]) -> usize {
+    let started = Instant::now();
+    let mut failures = 0;
+    for handler in handlers {
+        if let Err(err) = import_handler(py, handler.bind(py)) {
+            failures += 1;
+            crate::hlog_warn!("Worker {} failed to import handler: {}", worker_id, err);
+        }
+    }
+    crate::hlog_info!(
+        "Worker {} imported {} handlers in {:.1}ms",
+        worker_id,
+        handlers.len(),
+        started.elapsed().as_secs_f64() * 1000.0
+    );
+    failures
+}
+
+fn import_handler(py: Python<'_>, handler: &Bound<'_, PyAny>) -> PyResult<()> {
+    let target = py.import("inspect")?.call_method1("unwrap", (handler,))?;
+    let Ok(module) = target.getattr("__module__") else {
+        return Ok(());
+    };
+    let Ok(module) = module.extract::<String>() else {
+        return Ok(());
+    };
+    py.import("importlib")?
+        .call_method1("import_module", (module,))?;
+    Ok(())
+}
+
+/// Send a GET for each warm-up path through the full request pipeline.
+///
+/// Returns the number of paths that did not answer with a 2xx or 3xx.
+pub async fn replay(
+    worker_id: usize,
+    paths: &[String],
+    router: Arc<HypernRouter>,
+    middleware: Arc<MiddlewareChain>,
+    reload_manager: ReloadManager,
+) -> usize {
+    let mut failures = 0;
+    for path in paths {
+        let request = match axum::http::Request::builder()
+            .method(axum::http::Method::GET)
+            .uri(path.as_str())
+            .header(axum::http::header::HOST, "localhost")
+            .header(WARMUP_HEADER, "1")
+            .body(Body::empty())
+        {
+            Ok(request) => request,
+            Err(err) => {
+                failures += 1;
+                crate::hlog_warn!(
+                    "Worker {} warm-up path '{}' is invalid: {}",
+                    worker_id,
+                    path,
+                    err
+                );
+                continue;
+            }
+        };
+        let started = Instant::now();
+        let response = tokio::time::timeout(
+            REQUEST_TIMEOUT,
+            crate::core::worker::handle_request_standalone(
+                request,
+                router.clone(),
+                middleware.clone(),
+                reload_manager.clone(),
+            ),
+        )
+        .await;
+        let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+        match response {
+            Ok(response)
+                if response.status().is_success() || response.status().is_redirection() =>
+            {
+                crate::hlog_info!(
+                    "Worker {} warm-up GET {} -> {} in {:.1}ms",
+                    worker_id,
+                    path,
+                    response.status().as_u16(),
+                    elapsed_ms
+                );
+            }
+            Ok(response) => {
+                failures += 1;
+                crate::hlog_warn!(
+                    "Worker {} warm-up GET {} failed with {} in {:.1}ms",
+                    worker_id,
+                    path,
+                    response.status().as_u16(),
+                    elapsed_ms
+                );
+            }
+            Err(_) => {
+                failures += 1;
+                crate::hlog_warn!(
+                    "Worker {} warm-up GET {} did not respond within {:?}",
+                    worker_id,
+                    path,
+                    REQUEST_TIMEOUT
+                );
+            }
+        }
+    }
+    failures
+}
@@ -0,0 +1,275 @@
+//! Watchdog for workers whose Python handlers stopped making progress.
+//!
+//! Health probes are answered from Rust, so a worker deadlocked inside
+//! Python (a lock-ordering bug in a handler, say) would keep passing them
+//! while serving nothing. Every handler dispatch is recorded here until its
+//! Python call returns; a monitor task checks the oldest one and the time
+//! since any handler last returned. When both pass their thresholds
+//! (`watchdog_stuck_ms`, `watchdog_idle_ms`) the worker is marked unhealthy,
+//! so readiness fails, and the stuck requests are logged at ERROR with their
+//! IDs and paths. A slow handler alone does not trip it while others still
+//! complete.
+//!
+//! With `watchdog_dump_stacks` the stack of every Python thread goes to the
+//! log too, read under the GIL when it can be taken within a few seconds.
+//! With `watchdog_restart` a worker still stuck after
+//! `watchdog_restart_grace_ms` exits with [`EXIT_CODE`] and the parent
+//! starts a replacement. A worker that recovers first is marked healthy
+//! again.
+//!
+//! Handlers that started a streaming response are not counted: a long-lived
+//! stream is not a stuck request.
+
+use dashmap::DashMap;
+use pyo3::prelude::*;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{LazyLock, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::core::reload::{HealthStatus, ReloadManager};
+
+/// Exit status of a worker the watchdog recycles; the parent replaces it
+pub const EXIT_CODE: i32 = 75;
+
+/// How long the stack dump waits for the GIL
+const STACK_DUMP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Handler dispatches whose Python call has not returned, by token
+static IN_FLIGHT: LazyLock<DashMap<u64, Dispatch>> = LazyLock::new(DashMap::new);
+static NEXT_TOKEN: AtomicU64 = AtomicU64::new(1);
+/// Nanoseconds after `EPOCH` at which a handler last returned
+static LAST_COMPLETED: AtomicU64 = AtomicU64::new(0);
+static EPOCH: OnceLock<Instant> = OnceLock::new();
+/// A stack dump is waiting for the GIL
+static DUMPING: AtomicBool = AtomicBool::new(false);
+/// Set once the monitor runs; dispatches are not recorded before
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+struct Dispatch {
+    request_id: String,
+    method: String,
+    path: String,
+    started: Instant,
+    streaming: bool,
+}
+
+fn now_nanos() -> u64 {
+    EPOCH.get_or_init(Instant::now).elapsed().as_nanos() as u64
+}
+
+/// Record a handler dispatch; pass the token to [`finished`] once the
+/// Python call returns. Token 0 means the watchdog is off.
+pub fn dispatched(request_id: &str, method: &str, path: &str) -> u64 {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return 0;
+    }
+    let token = NEXT_TOKEN.fetch_add(1, Ordering::Relaxed);
+    IN_FLIGHT.insert(
+        token,
+        Dispatch {
+            request_id: request_id.to_string(),
+            method: method.to_string(),
+            path: path.to_string(),
+            started: Instant::now(),
+            streaming: false,
+        },
+    );
+    token
+}
+
+/// The handler started a streaming response and may keep running
+pub fn streaming(token: u64) {
+    if token == 0 {
+        return;
+    }
+    if let Some(mut dispatch) = IN_FLIGHT.get_mut(&token) {
+        dispatch.streaming = true;
+    }
+}
+
+/// The handler's Python call returned
+pub fn finished(token: u64) {
+    if token == 0 {
+        return;
+    }
+    IN_FLIGHT.remove(&token);
+    LAST_COMPLETED.store(now_nanos(), Ordering::Relaxed);
+}
+
+/// Thresholds from the worker's `ReloadConfig`; None when the watchdog is off
+fn thresholds(rm: &ReloadManager) -> Option<(Duration, Duration)> {
+    let config = rm.config();
+    let stuck = config.watchdog_stuck_ms?;
+    let idle = config.watchdog_idle_ms.unwrap_or(stuck);
+    Some((Duration::from_millis(stuck), Duration::from_millis(idle)))
+}
+
+/// Watch this worker's handlers until the process exits
+pub async fn run(rm: ReloadManager, worker_id: usize) {
+    let Some((stuck_after, idle_after)) = thresholds(&rm) else {
+        return;
+    };
+    LAST_COMPLETED.store(now_nanos(), Ordering::Relaxed);
+    ENABLED.store(true, Ordering::Relaxed);
+    let tick =
+        (stuck_after.min(idle_after) / 4).clamp(Duration::from_millis(10), Duration::from_secs(1));
+    let mut tripped = false;
+    let mut restart_at: Option<Instant> = None;
+    loop {
+        tokio::time::sleep(tick).await;
+        let idle = Duration::from_nanos(now_nanos() - LAST_COMPLETED.load(Ordering::Relaxed));
+        let mut stuck: Vec<(Duration, String)> = IN_FLIGHT
+            .iter()
+            .filter(|d| !d.streaming && d.started.elapsed() >= stuck_after)
+            .map(|d| {
+                let age = d.started.elapsed();
+                (
+                    age,
+                    format!(
+                        "{} {} ({}, {:.1}s)",
+                        d.method,
+                        d.path,
+                        d.request_id,
+                        age.as_secs_f64()
+                    ),
+                )
+            })
+            .collect();
+
+        if stuck.is_empty() || idle < idle_after {
+            if tripped {
+                tripped = false;
+                restart_at = None;
+                if rm.health().status() == HealthStatus::Unhealthy {
+                    rm.health().mark_healthy();
+                }
+                crate::hlog_warn!(
+                    "Worker {} watchdog: handlers completing again, marked healthy",
+                    worker_id
+                );
+            }
+            continue;
+        }
+
+        if !tripped {
+            tripped = true;
+            // Leave a draining or starting worker's status alone
+            if rm.health().status() == HealthStatus::Healthy {
+                rm.health().mark_unhealthy();
+            }
+            stuck.sort_by_key(|(age, _)| std::cmp::Reverse(*age));
+            let stuck: Vec<String> = stuck.into_iter().map(|(_, line)| line).collect();
+            crate::hlog_error!(
+                "Worker {} watchdog: no handler completed for {:.1}s, marked unhealthy; {} stuck request(s): {}",
+                worker_id,
+                idle.as_secs_f64(),
+                stuck.len(),
+                stuck.join(", ")
+            );
+            if rm.config().watchdog_dump_stacks {
+                dump_stacks(worker_id).await;
+            }
+            if rm.config().watchdog_restart {
+                let grace = Duration::from_millis(rm.config().watchdog_restart_grace_ms);
+                restart_at = Some(Instant::now() + grace);
+            }
+        }
+
+        if restart_at.is_some_and(|at| Instant::now() >= at) {
+            crate::hlog_error!(
+                "Worker {} watchdog: still stuck, exiting to be replaced",
+                worker_id
+            );
+            // Give the log consumer a moment; the stuck threads never finish,
+            // so skip destructors and atexit handlers
+            crate::logging::LogQueue::shutdown();
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            unsafe { libc::_exit(EXIT_CODE) };
+        }
+    }
+}
+
+/// Log the stack of every Python thread, from a thread of its own so that a
+/// GIL that is never released does not block the monitor
+async fn dump_stacks(worker_id: usize) {
+    if DUMPING.swap(true, Ordering::AcqRel) {
+        crate::hlog_error!(
+            "Worker {} watchdog: an earlier stack dump is still waiting for the GIL",
+            worker_id
+        );
+        return;
+    }
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let spawned = std::thread::Builder::new()
+        .name("hypern-watchdog-stacks".to_string())
+        .spawn(move || {
+            let stacks = Python::attach(|py| format_stacks(py).map_err(|e| e.to_string()));
+            DUMPING.store(false, Ordering::Release);
+            let _ = tx.send(stacks);
+        });
+    if let Err(err) = spawned {
+        DUMPING.store(false, Ordering::Release);
+        crate::hlog_error!(
+            "Worker {} watchdog: failed to start the stack dump: {}",
+            worker_id,
+            err
+        );
+        return;
+    }
+    match tokio::time::timeout(STACK_DUMP_TIMEOUT, rx).await {
+        Ok(Ok(Ok(stacks))) => {
+            crate::hlog_error!(
+                "Worker {} watchdog: Python thread stacks:\n{}",
+                worker_id,
+                stacks
+            )
+        }
+        Ok(Ok(Err(err))) => {
+            crate::hlog_error!(
+                "Worker {} watchdog: failed to read Python stacks: {}",
+                worker_id,
+                err
+            )
+        }
+        Ok(Err(_)) => {}
+        Err(_) => crate::hlog_error!(
+            "Worker {} watchdog: Python stacks unavailable, the GIL was not released within {}s",
+            worker_id,
+            STACK_DUMP_TIMEOUT.as_secs()
+        ),
+    }
+}
+
+/// `faulthandler`-style listing of every thread's stack, innermost call last
+fn format_stacks(py: Python<'_>) -> PyResult<String> {
+    let traceback = py.import("traceback")?;
+    let names = py
+        .import("threading")?
+        .call_method0("enumerate")?
+        .try_iter()?
+        .map(|thread| {
+            let thread = thread?;
+            Ok((
+                thread.getattr("ident")?.extract::<u64>()?,
+                thread.getattr("name")?.extract::<String>()?,
+            ))
+        })
+        .collect::<PyResult<std::collections::HashMap<u64, String>>>()?;
+    let frames = py.import("sys")?.call_method0("_current_frames")?;
+    let mut out = String::new();
+    for item in frames.call_method0("items")?.try_iter()? {
+        let (ident, frame): (u64, Bound<'_, PyAny>) = item?.extract()?;
+        let name = names
+            .get(&ident)
+            .cloned()
+            .unwrap_or_else(|| "<unknown>".to_string());
+        out.push_str(&format!("Thread {} ({}):\n", ident, name));
+        for line in traceback
+            .call_method1("format_stack", (frame,))?
+            .try_iter()?
+        {
+            out.push_str(&line?.extract::<String>()?);
+        }
+    }
+    Ok(out)
+}
@@ -1,20 +1,40 @@
 use axum::{body::Body, extract::State, http::Request, response::IntoResponse, Router};
 use pyo3::prelude::*;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
 
+use crate::core::deadline::Deadline;
+use crate::core::deep_health;
 use crate::core::interpreter::http_execute;
 use crate::core::reload::ReloadManager;
+use crate::core::request_scope::RequestScope;
+use crate::http::conditional;
+use crate::http::connection::{ConnectionInfo, HypernListener};
+use crate::http::disconnect::{AbortGuard, Aborted, Disconnect};
+use crate::http::error_envelope::{self, ErrorCode};
 use crate::http::method::HttpMethod;
-use crate::http::request::Request as HypernRequest;
+use crate::http::request::{BodyTooLarge, Request as HypernRequest};
+use crate::http::response::RaisedException;
+use crate::http::stream_drain::LiveStream;
+use crate::http::timing::{self, HandlerTiming, RequestTimer};
+use crate::logging::access::AccessDetails;
+use crate::middleware::singleflight::{self, Join};
 use crate::middleware::{
-    middleware_response_to_hyper, MiddlewareChain, MiddlewareContext, MiddlewareResult,
+    middleware_response_to_hyper, CapturedResponse, MiddlewareChain, MiddlewareContext,
+    MiddlewareResult, StateValue,
 };
+use crate::routing::auth;
+use crate::routing::etags::{self, EtagProvider};
+use crate::routing::flags;
+use crate::routing::retry::{self, RetryPolicy};
+use crate::routing::route::Route;
 use crate::routing::router::Router as HypernRouter;
 use crate::socket::SocketHeld;
 use crate::{
     core::global::{get_event_loop, set_global_runtime},
-    http::response::response_404,
+    http::response::response_unmatched,
 };
 
 /// Shared application state for Axum handlers
@@ -30,67 +50,136 @@ pub fn build_axum_router_public(state: AppState) -> axum::Router {
     build_axum_router(state)
 }
 
-/// Convert HypernRouter routes to Axum Router with health probe routes
+/// Convert HypernRouter routes to Axum Router
 fn build_axum_router(state: AppState) -> axum::Router {
-    let health_prefix = state.reload_manager.config().health_path_prefix.clone();
-    let probes_enabled = state.reload_manager.config().health_probes_enabled;
-
-    let mut router = Router::new();
-
-    if probes_enabled {
-        // Health probe routes
-        let live_path = format!("{}/live", health_prefix);
-        let ready_path = format!("{}/ready", health_prefix);
-        let startup_path = format!("{}/startup", health_prefix);
-        let status_path = health_prefix.clone();
-
-        router = router
-            .route(
-                &live_path,
-                axum::routing::get({
-                    let rm = state.reload_manager.clone();
-                    move || {
-                        let rm = rm.clone();
-                        async move { health_liveness(rm) }
-                    }
-                }),
-            )
-            .route(
-                &ready_path,
-                axum::routing::get({
-                    let rm = state.reload_manager.clone();
-                    move || {
-                        let rm = rm.clone();
-                        async move { health_readiness(rm) }
-                    }
-                }),
-            )
-            .route(
-                &startup_path,
-                axum::routing::get({
-                    let rm = state.reload_manager.clone();
-                    move || {
-                        let rm = rm.clone();
-                        async move { health_startup(rm) }
-                    }
-                }),
+    // Covers probes, errors and middleware responses as well as handlers
+    Router::new()
+        .fallback(handle_request)
+        .with_state(state)
+        .layer(axum::middleware::map_response(
+            crate::http::header_policy::apply,
+        ))
+}
+
+// -- health probe handlers --
+
+/// A built-in health endpoint under `health_path_prefix`
+#[derive(Clone, Copy)]
+enum Probe {
+    Status,
+    Live,
+    Ready,
+    Startup,
+    Deep,
+}
+
+impl Probe {
+    /// The probe `req` asks for. An app route under the prefix that matches
+    /// the request takes precedence, with its middleware.
+    fn classify(state: &AppState, req: &Request<Body>) -> Option<Self> {
+        let config = state.reload_manager.config();
+        if !config.health_probes_enabled
+            || !matches!(
+                *req.method(),
+                axum::http::Method::GET | axum::http::Method::HEAD
             )
-            .route(
-                &status_path,
-                axum::routing::get({
-                    let rm = state.reload_manager.clone();
-                    move || {
-                        let rm = rm.clone();
-                        async move { health_status(rm) }
-                    }
-                }),
-            );
+        {
+            return None;
+        }
+        let prefix = config.health_path_prefix.as_str();
+        let path = req.uri().path();
+        let probe = match path.strip_prefix(prefix)? {
+            "" => Self::Status,
+            "/live" => Self::Live,
+            "/ready" => Self::Ready,
+            "/startup" => Self::Startup,
+            "/deep" => Self::Deep,
+            _ => return None,
+        };
+        let host = req.headers().get("host").and_then(|h| h.to_str().ok());
+        let shadowed = state
+            .router
+            .find_matching_route_for_host(host, path, req.method().as_str())
+            .is_some_and(|(route, _)| route.path.starts_with(prefix));
+        (!shadowed).then_some(probe)
     }
 
-    router.fallback(handle_request).with_state(state)
+    async fn respond(self, rm: &ReloadManager) -> axum::http::Response<Body> {
+        match self {
+            Self::Status => health_status(rm.clone()).into_response(),
+            Self::Live => health_liveness(rm.clone()).into_response(),
+            Self::Ready => health_readiness(rm.clone()).into_response(),
+            Self::Startup => health_startup(rm.clone()).into_response(),
+            Self::Deep => deep_health::respond(rm).await,
+        }
+    }
 }
 
-// -- health probe handlers --
+/// Answer a probe as an internal request: host checks, draining, load
+/// shedding and the access log (unless `log_health_probes`) don't apply,
+/// and only middleware with `applies_to_internal` runs
+async fn serve_probe(
+    state: &AppState,
+    req: Request<Body>,
+    probe: Probe,
+) -> axum::http::Response<Body> {
+    let started = std::time::Instant::now();
+    let method = req.method().to_string();
+    let path = req.uri().path().to_string();
+    let logged = state.reload_manager.config().log_health_probes;
+    if logged {
+        crate::logging::log_request(&method, &path, None);
+    }
+
+    let response = if state.middleware.has_internal() {
+        let headers = req
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+            })
+            .collect();
+        let mut mw_ctx = MiddlewareContext::new(
+            &path,
+            HttpMethod::from_str(&method).unwrap_or(HttpMethod::GET),
+            headers,
+            req.uri().query().unwrap_or(""),
+            None,
+        );
+        mw_ctx.internal = true;
+        match state.middleware.execute_before(&mw_ctx).await {
+            MiddlewareResult::Continue() => {
+                let res = probe.respond(&state.reload_manager).await;
+                let _ = state.middleware.execute_after(&mw_ctx).await;
+                with_middleware_headers(&mw_ctx, res)
+            }
+            MiddlewareResult::Response(response) => {
+                error_envelope::short_circuit(response, error_envelope::accept(req.headers()))
+            }
+            MiddlewareResult::Error(err) => {
+                match state.middleware.execute_error(&mw_ctx, &err).await {
+                    Some(response) => middleware_response_to_hyper(response),
+                    None => middleware_response_to_hyper(err.to_response()),
+                }
+            }
+        }
+    } else {
+        probe.respond(&state.reload_manager).await
+    };
+
+    if logged {
+        crate::logging::log_response(
+            &method,
+            &path,
+            response.status().as_u16(),
+            timing::ms(started.elapsed()),
+            None,
+            None,
+            None,
+        );
+    }
+    response
+}
 
 fn health_liveness(rm: ReloadManager) -> impl IntoResponse {
     let code = rm.health().liveness_code();
@@ -134,47 +223,272 @@ fn health_status(rm: ReloadManager) -> impl IntoResponse {
 
 /// Main request handler that dispatches to Python handlers
 async fn handle_request(State(state): State<AppState>, req: Request<Body>) -> impl IntoResponse {
+    let mut timer = RequestTimer::start();
+
+    // Pick up settings another worker changed with `Server.update_config`
+    crate::core::live_config::sync();
+
+    // Oversized heads get a 431 or 414 before anything copies their headers
+    if let Some(response) = crate::http::header_limits::check(&req) {
+        return response;
+    }
+
+    // Health probes answer whatever the Host, drain or load
+    if let Some(probe) = Probe::classify(&state, &req) {
+        return serve_probe(&state, req, probe).await;
+    }
+
+    // Reject forged Host headers before any middleware or routing
+    if let Some(response) = crate::http::allowed_hosts::reject_disallowed(&req) {
+        return response;
+    }
+
+    // Send plain HTTP requests to https:// under `force_https`
+    if let Some(response) = crate::http::https::redirect(&req) {
+        return response;
+    }
+
     // If draining, reject new requests with 503
     if state.reload_manager.is_draining() {
-        return axum::http::Response::builder()
-            .status(503)
-            .header("Content-Type", "application/json")
-            .header("Connection", "close")
-            .header("Retry-After", "5")
-            .body(Body::from(r#"{"error":"service_draining","message":"Server is reloading, please retry"}"#))
-            .unwrap();
+        crate::http::stream_drain::record_rejected();
+        let mut response = error_envelope::response(
+            axum::http::StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::ServiceDraining,
+            "Server is reloading, please retry",
+            error_envelope::accept(req.headers()),
+        );
+        let headers = response.headers_mut();
+        headers.insert(
+            axum::http::header::CONNECTION,
+            axum::http::HeaderValue::from_static("close"),
+        );
+        headers.insert(
+            axum::http::header::RETRY_AFTER,
+            axum::http::HeaderValue::from_static("5"),
+        );
+        return response;
+    }
+
+    // Shed load over the `max_in_flight` cap; the connection stays open
+    if crate::core::live_config::at_capacity(state.reload_manager.health().in_flight()) {
+        let mut response = error_envelope::response(
+            axum::http::StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::Overloaded,
+            "Server is at capacity, please retry",
+            error_envelope::accept(req.headers()),
+        );
+        response
+            .headers_mut()
+            .insert(
+                axum::http::header::RETRY_AFTER,
+                axum::http::HeaderValue::from_static("1"),
+            );
+        return response;
     }
 
+    // Refuse `Expect: 100-continue` requests before the client sends the body
+    let (parts, body) = req.into_parts();
+    if let Some(response) =
+        crate::http::expect::check(&parts, &state.router, &state.middleware).await
+    {
+        return response;
+    }
+    let mut req = Request::from_parts(parts, body);
+
     // Track in-flight request
     state.reload_manager.health().increment_in_flight();
     let rm = state.reload_manager.clone();
 
     // Capture method and path for logging before consuming request
-    let method_str = req.method().to_string();
-    let path_str = req.uri().path().to_string();
-    let start = std::time::Instant::now();
+    let method = req.method().to_string();
+    let path = req.uri().path().to_string();
+
+    // Routes registered with log=False stay out of the access log
+    let logged = state.router.should_log(
+        req.headers().get("host").and_then(|h| h.to_str().ok()),
+        &path,
+        &method,
+    );
 
     // Log incoming request
-    crate::logging::log_request(&method_str, &path_str, None);
+    if logged {
+        crate::logging::log_request(&method, &path, None);
+    }
+    // Request details a configured access format may print
+    let access = (logged && crate::logging::access_format_enabled()).then(|| access_details(&req));
+    let (request_id, access) = match access {
+        Some((request_id, access)) => (request_id, Some(access)),
+        None => (None, None),
+    };
 
-    // Execute the actual handler and ensure we decrement on exit
-    let response = handle_request_inner(&state, req).await;
+    // The handler watches the token; the guard signals it, logs 499 and
+    // releases the in-flight slot if hyper drops this future because the
+    // client went away
+    let token = Disconnect::new();
+    req.extensions_mut().insert(token.clone());
+    let guard = AbortGuard::arm(
+        Aborted {
+            token,
+            method,
+            path,
+            logged,
+            started: timer.started_at(),
+            request_id,
+            access,
+        },
+        rm,
+    );
+
+    // Execute the actual handler and ensure we decrement on exit; a panic
+    // anywhere in dispatch becomes a 500 so the bookkeeping below still runs
+    let client_request_id = req.headers().get("x-request-id").cloned();
+    let http1 = req.version() < axum::http::Version::HTTP_2;
+    let caught = crate::http::panic::catch(
+        client_request_id.as_ref().and_then(|v| v.to_str().ok()),
+        handle_request_inner(&state, req, &mut timer),
+    )
+    .await;
+    let panicked = caught.is_err();
+    let mut response = caught.unwrap_or_else(|response| response);
+    let (aborted, rm) = guard.disarm();
+
+    // A request in flight when the drain started is the last one on its
+    // connection
+    if http1 && rm.is_draining() {
+        response.headers_mut().insert(
+            axum::http::header::CONNECTION,
+            axum::http::HeaderValue::from_static("close"),
+        );
+    }
+
+    // Stage timings exist only for requests that reached a handler; a panic
+    // may have left them half-updated, so none are recorded for it
+    let stages = if panicked { None } else { timer.finish() };
+    if let Some(stages) = stages {
+        stages.record();
+        if timing::server_timing_enabled() {
+            if let Ok(value) = axum::http::HeaderValue::from_str(&stages.server_timing()) {
+                response.headers_mut().insert("server-timing", value);
+            }
+        }
+    }
 
     // Log response
     let status = response.status().as_u16();
-    let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
-    crate::logging::log_response(&method_str, &path_str, status, duration_ms, None);
+    let elapsed = timer.started_at().elapsed();
+    crate::telemetry::server_metrics::record(&aborted.method, timer.route(), status, elapsed);
+    let duration_ms = timing::ms(elapsed);
+    if aborted.logged {
+        let (request_id, access) = match aborted.access.clone() {
+            Some(mut access) => {
+                let headers = response.headers();
+                access.bytes_out = headers
+                    .get(axum::http::header::CONTENT_LENGTH)
+                    .and_then(|v| v.to_str().ok()?.parse().ok())
+                    .or_else(|| axum::body::HttpBody::size_hint(response.body()).exact());
+                let request_id = headers
+                    .get("x-request-id")
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string)
+                    .or_else(|| aborted.request_id.clone());
+                (request_id, Some(access))
+            }
+            None => (None, None),
+        };
+        crate::logging::log_response(
+            &aborted.method,
+            &aborted.path,
+            status,
+            duration_ms,
+            request_id.as_deref(),
+            stages,
+            access,
+        );
+    }
 
-    // Decrement in-flight and notify drain if needed
-    rm.on_request_complete();
+    // Decrement in-flight and notify drain if needed; streaming bodies hold
+    // their slot until they end or the drain closes them, and record an
+    // abort if the client leaves first
+    crate::http::stream_drain::track(response, rm, aborted)
+}
 
-    response
+/// The request's ID header and the details access log formats can print.
+/// The client IP follows `Request.ip()`: forwarding headers first, then the
+/// peer address.
+fn access_details(req: &Request<Body>) -> (Option<String>, AccessDetails) {
+    let headers = req.headers();
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    let client_ip = header("x-forwarded-for")
+        .and_then(|v| v.split(',').next().map(|ip| ip.trim().to_string()))
+        .or_else(|| header("x-real-ip"))
+        .or_else(|| {
+            req.extensions()
+                .get::<axum::extract::ConnectInfo<ConnectionInfo>>()
+                .and_then(|info| info.0.peer_addr())
+                .map(|addr| addr.ip().to_string())
+        });
+    let protocol = match req.version() {
+        axum::http::Version::HTTP_09 => "HTTP/0.9",
+        axum::http::Version::HTTP_10 => "HTTP/1.0",
+        axum::http::Version::HTTP_2 => "HTTP/2.0",
+        axum::http::Version::HTTP_3 => "HTTP/3.0",
+        _ => "HTTP/1.1",
+    };
+    let details = AccessDetails {
+        bytes_out: None,
+        client_ip,
+        user_agent: header("user-agent"),
+        referer: header("referer"),
+        host: header("host").or_else(|| req.uri().host().map(str::to_string)),
+        protocol: Some(protocol),
+    };
+    (header("x-request-id"), details)
 }
 
 /// Inner request handler logic (separated for clean in-flight tracking)
-async fn handle_request_inner(state: &AppState, req: Request<Body>) -> axum::http::Response<Body> {
+async fn handle_request_inner(
+    state: &AppState,
+    req: Request<Body>,
+    timer: &mut RequestTimer,
+) -> axum::http::Response<Body> {
+    // Route on the normalized path; unsafe or malformed paths never reach routing
+    let path = match crate::http::path::normalize(req.uri().path()) {
+        Ok(path) => path,
+        Err(err) => return err.to_response(),
+    };
+    #[cfg(feature = "test-hooks")]
+    crate::http::panic::maybe_inject(&path.routing);
+    // Inflate gzip/deflate bodies so parsing and body limits see decoded
+    // bytes; the request is already in flight, so a drain waits for it
+    let req = match crate::http::decompression::decode_request(req).await {
+        Ok(req) => req,
+        Err(response) => return response,
+    };
+    // A route's body limit applies while the body is read, so an oversized
+    // upload is refused before it is buffered whole
+    let body_limit = route_body_limit(&state.router, &req, &path.routing);
+    let accept = body_limit.and_then(|_| error_envelope::accept(req.headers()).map(str::to_string));
+    if let Some(limit) = body_limit {
+        if declared_length(req.headers()).is_some_and(|len| len > limit) {
+            return body_too_large(limit, accept.as_deref());
+        }
+    }
     // Convert Axum request to Hypern request
-    let fast_req = HypernRequest::from_axum(req).await;
+    let fast_req = match HypernRequest::from_axum(req, path, body_limit).await {
+        Ok(fast_req) => fast_req,
+        Err(BodyTooLarge(limit)) => return body_too_large(limit, accept.as_deref()),
+    };
+    // Only host-constrained routes need the Host header for matching
+    let host = if state.router.has_host_routes() {
+        fast_req.header("host")
+    } else {
+        None
+    };
 
     // Fast path: if no middleware, skip middleware context creation entirely
     let has_before_middleware = !state.middleware.is_empty_before();
@@ -196,7 +510,9 @@ async fn handle_request_inner(state: &AppState, req: Request<Body>) -> axum::htt
         match state.middleware.execute_before(&mw_ctx).await {
             MiddlewareResult::Continue() => {}
             MiddlewareResult::Response(response) => {
-                return middleware_response_to_hyper(response);
+                let accept = fast_req.header("accept");
+                let response = error_envelope::short_circuit(response, accept.as_deref());
+                return error_envelope::handle(response, fast_req).await;
             }
             MiddlewareResult::Error(err) => {
                 if let Some(response) = state.middleware.execute_error(&mw_ctx, &err).await {
@@ -207,56 +523,469 @@ async fn handle_request_inner(state: &AppState, req: Request<Body>) -> axum::htt
         }
 
         // Match route and execute handler
-        let response = if let Some((route, params)) = state
-            .router
-            .find_matching_route(fast_req.path(), fast_req.method().as_str())
-        {
-            fast_req.set_path_params(params.clone());
+        let poll = crate::realtime::poll::endpoint_for(
+            fast_req.method().as_str(),
+            fast_req.routing_path(),
+        );
+        let response = if let Some(poll) = poll {
+            // The poll's identity comes from the user auth middleware set
+            fast_req.set_scope(Arc::new(RequestScope::from_middleware(&mw_ctx, &fast_req)));
+            fast_req.set_middleware_state(mw_ctx.state.clone());
+            let res = poll.respond(&fast_req, &state.reload_manager).await;
+            if has_after_middleware {
+                let _ = state.middleware.execute_after(&mw_ctx).await;
+            }
+            with_middleware_headers(&mw_ctx, res)
+        } else if let Some((route, params)) = state.router.find_matching_route_for_host(
+            host.as_deref(),
+            fast_req.routing_path(),
+            fast_req.method_name(),
+        ) {
+            timer.route_matched(&route.path);
+            bind_path_params(&fast_req, &route, params.clone());
             mw_ctx.set_params(params);
+            for (key, value) in &route.config.metadata {
+                mw_ctx.set_state(
+                    format!("route_meta_{}", key),
+                    StateValue::String(value.clone()),
+                );
+            }
+            fast_req.set_scope(Arc::new(RequestScope::from_middleware(&mw_ctx, &fast_req)));
+            fast_req.set_middleware_state(mw_ctx.state.clone());
 
-            let route_hash = route.handler_hash();
-            let res = http_execute(route_hash, fast_req).await;
+            // Set by TimeoutMiddleware; a route's own timeout takes precedence
+            let default_timeout = match mw_ctx.get_state("request_timeout_ms") {
+                Some(StateValue::Int(ms)) if ms > 0 => Some(Duration::from_millis(ms as u64)),
+                _ => None,
+            };
+            // A route behind a flag that is off looks absent, so it goes first
+            let denied = flags::check(route.config.feature_flag.as_ref(), &fast_req)
+                .or_else(|| auth::check(route.config.auth.as_ref(), Some(&mw_ctx)));
+            let res = match denied {
+                Some(denied) => error_envelope::handle(denied, fast_req).await,
+                None => execute_route(&route, fast_req, timer, default_timeout).await,
+            };
 
             if has_after_middleware {
+                let res = capture_response(&mw_ctx, res).await;
+                record_stage_state(&mw_ctx, timer);
                 let _ = state.middleware.execute_after(&mw_ctx).await;
-            }
-
-            // Apply middleware response headers
-            let headers_to_add = mw_ctx.get_response_headers();
-            if !headers_to_add.is_empty() {
-                let (mut parts, body) = res.into_parts();
-                for (name, value) in headers_to_add {
-                    if let (Ok(name), Ok(value)) = (
-                        axum::http::HeaderName::from_bytes(name.as_bytes()),
-                        axum::http::HeaderValue::from_str(&value),
-                    ) {
-                        parts.headers.insert(name, value);
-                    }
-                }
-                axum::http::Response::from_parts(parts, body)
+                with_middleware_headers(&mw_ctx, res)
             } else {
-                res
+                with_middleware_headers(&mw_ctx, res)
             }
+        } else if mw_ctx.response_capture_limit().is_some() {
+            // Let the middleware that asked for the response release its state
+            let res = capture_response(&mw_ctx, unmatched(state, host.as_deref(), fast_req).await)
+                .await;
+            let _ = state.middleware.execute_after(&mw_ctx).await;
+            res
         } else {
-            response_404()
+            unmatched(state, host.as_deref(), fast_req).await
         };
 
         response
     } else {
         // Fast path: no middleware - go straight to route handler
-        if let Some((route, params)) = state
-            .router
-            .find_matching_route(fast_req.path(), fast_req.method().as_str())
-        {
-            fast_req.set_path_params(params);
-            let route_hash = route.handler_hash();
-            http_execute(route_hash, fast_req).await
+        let poll = crate::realtime::poll::endpoint_for(
+            fast_req.method().as_str(),
+            fast_req.routing_path(),
+        );
+        if let Some(poll) = poll {
+            poll.respond(&fast_req, &state.reload_manager).await
+        } else if let Some((route, params)) = state.router.find_matching_route_for_host(
+            host.as_deref(),
+            fast_req.routing_path(),
+            fast_req.method_name(),
+        ) {
+            timer.route_matched(&route.path);
+            if let Some(denied) = flags::check(route.config.feature_flag.as_ref(), &fast_req)
+                .or_else(|| auth::check(route.config.auth.as_ref(), None))
+            {
+                return error_envelope::handle(denied, fast_req).await;
+            }
+            bind_path_params(&fast_req, &route, params);
+            execute_route(&route, fast_req, timer, None).await
         } else {
-            response_404()
+            unmatched(state, host.as_deref(), fast_req).await
         }
     }
 }
 
+/// 404 for an unknown path, 405 with `Allow` when only the method is wrong,
+/// answered by the app's error handler for the status when it has one
+async fn unmatched(
+    state: &AppState,
+    host: Option<&str>,
+    fast_req: HypernRequest,
+) -> axum::http::Response<Body> {
+    let allowed = state.router.allowed_methods(host, fast_req.routing_path());
+    let response = response_unmatched(&allowed, fast_req.header("accept").as_deref());
+    error_envelope::handle(response, fast_req).await
+}
+
+/// Apply the response headers set by middleware; headers the handler set
+/// itself are kept
+fn with_middleware_headers(
+    mw_ctx: &MiddlewareContext,
+    res: axum::http::Response<Body>,
+) -> axum::http::Response<Body> {
+    let headers_to_add = mw_ctx.get_response_headers();
+    if headers_to_add.is_empty() {
+        return res;
+    }
+    let (mut parts, body) = res.into_parts();
+    let from_handler = parts.headers.clone();
+    for (name, value) in headers_to_add {
+        if let (Ok(name), Ok(value)) = (
+            axum::http::HeaderName::from_bytes(name.as_bytes()),
+            axum::http::HeaderValue::from_str(&value),
+        ) {
+            if !from_handler.contains_key(&name) {
+                parts.headers.insert(name, value);
+            }
+        }
+    }
+    axum::http::Response::from_parts(parts, body)
+}
+
+/// Hand the handler response to "after" middleware if one asked for it
+/// (`MiddlewareContext::capture_response`). Bodies within the requested limit
+/// are buffered and put back; larger or streamed ones pass through untouched.
+async fn capture_response(
+    mw_ctx: &MiddlewareContext,
+    res: axum::http::Response<Body>,
+) -> axum::http::Response<Body> {
+    let Some(limit) = mw_ctx.response_capture_limit() else {
+        return res;
+    };
+    let (parts, body) = res.into_parts();
+    let headers = parts
+        .headers
+        .iter()
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|value| (name.as_str().to_string(), value.to_string()))
+        })
+        .collect();
+    let status = parts.status.as_u16();
+
+    let buffered = axum::body::HttpBody::size_hint(&body)
+        .exact()
+        .is_some_and(|len| len <= limit as u64);
+    if !buffered {
+        mw_ctx.set_captured_response(CapturedResponse {
+            status,
+            headers,
+            body: None,
+        });
+        return axum::http::Response::from_parts(parts, body);
+    }
+    match axum::body::to_bytes(body, limit).await {
+        Ok(bytes) => {
+            mw_ctx.set_captured_response(CapturedResponse {
+                status,
+                headers,
+                body: Some(bytes.clone()),
+            });
+            axum::http::Response::from_parts(parts, Body::from(bytes))
+        }
+        Err(e) => {
+            crate::hlog_error!("Failed to read response body: {}", e);
+            axum::http::Response::builder()
+                .status(500)
+                .body(Body::from("Internal Server Error"))
+                .unwrap()
+        }
+    }
+}
+
+/// Attach the matched path parameters, converting `{name:type}` ones
+fn bind_path_params(fast_req: &HypernRequest, route: &Route, params: HashMap<String, String>) {
+    if !route.param_types.is_empty() {
+        if let Some(typed) = route.typed_params(&params) {
+            fast_req.set_typed_params(typed);
+        }
+    }
+    fast_req.set_path_params(params);
+}
+
+/// Run the matched route's handler, answering conditional GETs from the
+/// route's ETag provider before it and from `Response.with_etag` after it,
+/// and memoized routes from their kept response
+async fn execute_route(
+    route: &Route,
+    fast_req: HypernRequest,
+    timer: &mut RequestTimer,
+    default_timeout: Option<Duration>,
+) -> axum::http::Response<Body> {
+    let etag = route
+        .config
+        .etag_provider
+        .as_deref()
+        .and_then(EtagProvider::current);
+    if let Some(not_modified) = etags::check(etag.as_deref(), &fast_req) {
+        return not_modified;
+    }
+    let method = fast_req.method_name().to_string();
+    let if_none_match = fast_req.header("if-none-match");
+    let memo = route
+        .config
+        .memo
+        .as_ref()
+        .and_then(|memo| Some((memo, memo.key(&fast_req)?)));
+    let kept = memo.as_ref().and_then(|(memo, key)| memo.lookup(key));
+    let mut res = match (kept, memo) {
+        (Some(kept), _) => kept,
+        (None, Some((memo, key))) => {
+            let res = coalesce_route(route, fast_req, timer, default_timeout).await;
+            memo.store(key, &method, res).await
+        }
+        (None, None) => coalesce_route(route, fast_req, timer, default_timeout).await,
+    };
+    etags::tag(&mut res, etag.as_deref(), &method);
+    conditional::apply(res, if_none_match.as_deref())
+}
+
+/// Run the matched route's handler, coalescing identical concurrent
+/// requests on routes registered with `coalesce=True`
+async fn coalesce_route(
+    route: &Route,
+    fast_req: HypernRequest,
+    timer: &mut RequestTimer,
+    default_timeout: Option<Duration>,
+) -> axum::http::Response<Body> {
+    let method = fast_req.method().as_str().to_string();
+    if !route.config.coalesce || !singleflight::coalescable(&method) {
+        return run_route(route, fast_req, timer, default_timeout).await;
+    }
+    let flights = singleflight::process();
+    let key = flights.key(
+        &method,
+        fast_req.path(),
+        fast_req.query_string(),
+        |name| fast_req.header(name),
+    );
+    match flights.join(&key) {
+        Join::Leader(id) => {
+            let res = run_route(route, fast_req, timer, default_timeout).await;
+            flights.share(&key, id, res).await
+        }
+        Join::Follower(outcome) => match flights.wait(outcome).await {
+            Some(shared) => singleflight::to_response(&shared),
+            None => run_route(route, fast_req, timer, default_timeout).await,
+        },
+    }
+}
+
+/// The `max_body_size` of the route a request with a body goes to; requests
+/// without a body skip the lookup
+fn route_body_limit(router: &HypernRouter, req: &Request<Body>, path: &str) -> Option<usize> {
+    let headers = req.headers();
+    let has_body = declared_length(headers).is_some_and(|len| len > 0)
+        || headers.contains_key(axum::http::header::TRANSFER_ENCODING);
+    if !has_body {
+        return None;
+    }
+    let host = if router.has_host_routes() {
+        headers
+            .get(axum::http::header::HOST)
+            .and_then(|host| host.to_str().ok())
+    } else {
+        None
+    };
+    let (route, _) = router.find_matching_route_for_host(host, path, req.method().as_str())?;
+    route.config.max_body_size
+}
+
+/// The request's Content-Length, if it has a valid one
+fn declared_length(headers: &axum::http::HeaderMap) -> Option<usize> {
+    headers
+        .get(axum::http::header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+fn body_too_large(limit: usize, accept: Option<&str>) -> axum::http::Response<Body> {
+    error_envelope::response(
+        axum::http::StatusCode::PAYLOAD_TOO_LARGE,
+        ErrorCode::PayloadTooLarge,
+        format!("Request body exceeds {} bytes", limit),
+        accept,
+    )
+}
+
+/// Run the matched route's handler within the limits of its `RouteConfig`
+async fn run_route(
+    route: &Route,
+    fast_req: HypernRequest,
+    timer: &mut RequestTimer,
+    default_timeout: Option<Duration>,
+) -> axum::http::Response<Body> {
+    let config = route.config.clone();
+    let accept = fast_req.header("accept");
+    if let Some(limit) = route.max_json_bytes {
+        fast_req.set_max_json_bytes(limit);
+    }
+    let cacheable = matches!(fast_req.method().as_str(), "GET" | "HEAD");
+    fast_req.set_route_config(config.clone());
+
+    let timeout = config.timeout().or(default_timeout);
+    if let Some(limit) = timeout {
+        fast_req.set_deadline(Deadline::after(limit));
+    }
+
+    let retry = config.retry.clone().filter(|_| {
+        config.retry_unsafe
+            || HttpMethod::from_str(fast_req.method_name()).is_some_and(|m| m.is_idempotent())
+    });
+    let execution = async {
+        match retry {
+            Some(policy) => execute_with_retry(route, fast_req, &policy).await,
+            None => http_execute(route.handler_hash(), fast_req, route.config.args.clone()).await,
+        }
+    };
+    let (mut res, handler_timing) = match timeout {
+        // The handler keeps running on its Python thread; only the response is abandoned
+        Some(limit) => match tokio::time::timeout(limit, execution).await {
+            Ok(result) => result,
+            Err(_) => {
+                return error_envelope::response(
+                    axum::http::StatusCode::GATEWAY_TIMEOUT,
+                    ErrorCode::GatewayTimeout,
+                    format!("Handler did not respond within {:?}", limit),
+                    accept.as_deref(),
+                )
+            }
+        },
+        None => execution.await,
+    };
+    if let Some(handler_timing) = handler_timing {
+        timer.handler_done(handler_timing);
+    }
+
+    if let Some(ttl) = config.cache_ttl_secs {
+        if cacheable
+            && res.status().is_success()
+            && !res
+                .headers()
+                .contains_key(axum::http::header::CACHE_CONTROL)
+        {
+            if let Ok(value) = axum::http::HeaderValue::from_str(&format!("max-age={}", ttl)) {
+                res.headers_mut()
+                    .insert(axum::http::header::CACHE_CONTROL, value);
+            }
+        }
+    }
+    res
+}
+
+/// Run the handler, and again while `policy` matches the failure and the
+/// backoff fits before the request's deadline. A streamed response is never
+/// retried: its status and headers may already be on the wire.
+async fn execute_with_retry(
+    route: &Route,
+    request: HypernRequest,
+    policy: &RetryPolicy,
+) -> (axum::http::Response<Body>, Option<HandlerTiming>) {
+    let deadline = request.deadline();
+    let mut attempt = 1;
+    loop {
+        let (res, timing) = http_execute(
+            route.handler_hash(),
+            request.replay(),
+            route.config.args.clone(),
+        )
+        .await;
+        let streamed = timing.is_none() || res.extensions().get::<LiveStream>().is_some();
+        let reason = policy.reason(
+            res.status().as_u16(),
+            res.extensions().get::<RaisedException>(),
+        );
+        let Some(reason) = reason.filter(|_| !streamed) else {
+            if attempt > 1 {
+                crate::hlog_info!(
+                    "{} {} attempt={} of {} completed with status {}",
+                    request.method_name(),
+                    request.path(),
+                    attempt,
+                    policy.max_attempts,
+                    res.status().as_u16()
+                );
+            }
+            return (res, timing);
+        };
+        if attempt == policy.max_attempts {
+            crate::hlog_warn!(
+                "{} {} attempt={} of {} failed with {}; giving up",
+                request.method_name(),
+                request.path(),
+                attempt,
+                policy.max_attempts,
+                reason
+            );
+            return (res, timing);
+        }
+        let delay = policy.delay(attempt);
+        if deadline.is_some_and(|d| d.remaining() <= delay) {
+            crate::hlog_warn!(
+                "{} {} attempt={} of {} failed with {}; no time left before the deadline",
+                request.method_name(),
+                request.path(),
+                attempt,
+                policy.max_attempts,
+                reason
+            );
+            return (res, timing);
+        }
+        crate::hlog_warn!(
+            "{} {} attempt={} of {} failed with {}; retrying in {}ms",
+            request.method_name(),
+            request.path(),
+            attempt,
+            policy.max_attempts,
+            reason,
+            delay.as_millis()
+        );
+        drop(res);
+        retry::count();
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Expose the finished stages to "after" middleware as `timing.route_ms`,
+/// `timing.queue_ms` and `timing.app_ms` state values
+fn record_stage_state(mw_ctx: &MiddlewareContext, timer: &RequestTimer) {
+    if let Some((route, queue, app)) = timer.handler_stages() {
+        mw_ctx.set_state("timing.route_ms", StateValue::Float(timing::ms(route)));
+        mw_ctx.set_state("timing.queue_ms", StateValue::Float(timing::ms(queue)));
+        mw_ctx.set_state("timing.app_ms", StateValue::Float(timing::ms(app)));
+    }
+}
+
+/// Block the signal thread until no requests or streams are in flight, or
+/// until `timeout` passes, reporting what was left in flight then
+fn wait_for_in_flight(rm: &ReloadManager, worker_id: usize, timeout: std::time::Duration) {
+    let deadline = std::time::Instant::now() + timeout;
+    while rm.health().in_flight() > 0 && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    let remaining = rm.health().in_flight();
+    if remaining > 0 {
+        crate::hlog_warn!(
+            "Worker {} (PID {}) drain timeout reached with {} requests still in-flight",
+            worker_id,
+            std::process::id(),
+            remaining
+        );
+    }
+}
+
 /// Run the Axum-based worker process
 pub fn run_worker(
     py: Python<'_>,
@@ -270,12 +999,37 @@ pub fn run_worker(
     worker_id: usize,
     reload_manager: ReloadManager,
 ) -> PyResult<()> {
+    let warmup = crate::core::warmup::config();
+    let eager_handlers: Vec<Py<PyAny>> = match &warmup {
+        Some(config) if config.eager_import => {
+            handlers.iter().map(|(_, handler)| handler.clone_ref(py)).collect()
+        }
+        _ => Vec::new(),
+    };
+
     // Register handlers in this process's interpreter pool
     for (hash, handler) in handlers {
         crate::core::interpreter::register_handler(hash, handler);
     }
 
     let ev_loop = get_event_loop(py).bind(py);
+
+    // App-scoped dependencies, created before the first request is served
+    if let Err(err) = crate::core::deps::start_app_scope(py, ev_loop) {
+        crate::hlog_error!("Worker {} failed to start dependencies: {}", worker_id, err);
+        return Err(err);
+    }
+
+    // Import handler modules now rather than on their first request
+    let mut warmup_failures = 0;
+    if !eager_handlers.is_empty() {
+        warmup_failures += crate::core::warmup::import_handlers(py, worker_id, &eager_handlers);
+    }
+
+    // Pub/sub history saved by the previous generation, restored before
+    // the worker can be marked healthy
+    crate::realtime::handoff::restore(worker_id);
+
     // Use max_blocking_threads for py_threads to maximize Python concurrency
     set_global_runtime(
         worker_threads,
@@ -341,18 +1095,22 @@ pub fn run_worker(
             // SIGUSR1 = graceful: drain in-flight first
             if sig == libc::SIGUSR1 {
                 rm_for_signal.start_drain();
-                // We don't block the signal thread; the async runtime handles drain.
-                // Just give it a brief moment, then signal shutdown.
-                std::thread::sleep(std::time::Duration::from_secs(
-                    rm_for_signal.config().drain_timeout_secs,
-                ));
+                // The async runtime finishes requests and closes open streams;
+                // shut down once nothing is in flight or the timeout passes.
+                wait_for_in_flight(
+                    &rm_for_signal,
+                    worker_id,
+                    std::time::Duration::from_secs(rm_for_signal.config().drain_timeout_secs),
+                );
+                // Nothing publishes any more; hand history to the next generation
+                crate::realtime::handoff::save(worker_id);
             } else if sig == libc::SIGUSR2 {
                 // Hot reload: immediate
                 rm_for_signal.signal_hot_reload();
             } else {
                 // SIGINT/SIGTERM: normal shutdown with brief drain
                 rm_for_signal.start_drain();
-                std::thread::sleep(std::time::Duration::from_secs(2));
+                wait_for_in_flight(&rm_for_signal, worker_id, std::time::Duration::from_secs(2));
             }
         }
 
@@ -364,9 +1122,13 @@ pub fn run_worker(
         }
 
         std::thread::sleep(std::time::Duration::from_millis(100));
+        // stop() from another thread leaves the loop asleep in select();
+        // scheduling it wakes the loop up
         Python::attach(|py| {
             let loop_ref = loop_for_signal.bind(py);
-            let _ = loop_ref.call_method0("stop");
+            if let Ok(stop) = loop_ref.getattr("stop") {
+                let _ = loop_ref.call_method1("call_soon_threadsafe", (stop,));
+            }
         });
     });
 
@@ -383,20 +1145,53 @@ pub fn run_worker(
         .build()
         .expect("Failed to build Tokio runtime");
 
-    // Mark healthy after startup grace period
+    // Mark healthy after warm-up and the startup grace period
     let rm_startup = reload_manager.clone();
     let startup_grace = reload_manager.config().startup_grace_secs;
+    let warmup_router = router.clone();
+    let warmup_middleware = middleware.clone();
     rt.spawn(async move {
-        tokio::time::sleep(std::time::Duration::from_secs(startup_grace)).await;
+        let grace_ends = tokio::time::Instant::now() + Duration::from_secs(startup_grace);
+        let mut strict = false;
+        if let Some(config) = warmup {
+            strict = config.strict;
+            if !config.paths.is_empty() {
+                warmup_failures += crate::core::warmup::replay(
+                    worker_id,
+                    &config.paths,
+                    warmup_router,
+                    warmup_middleware,
+                    rm_startup.clone(),
+                )
+                .await;
+            }
+        }
+        tokio::time::sleep_until(grace_ends).await;
+        if strict && warmup_failures > 0 {
+            crate::hlog_error!(
+                "Worker {} failed {} warm-up step(s); staying in starting state",
+                worker_id,
+                warmup_failures
+            );
+            return;
+        }
         rm_startup.health().mark_healthy();
         crate::hlog_info!("Worker {} marked healthy after {}s grace period", worker_id, startup_grace);
     });
 
+    // Totals for `snapshot(cluster=True)` in the other workers
+    rt.spawn(crate::telemetry::cluster::publish_periodically());
+
+    // Marks the worker unhealthy when its Python handlers stop completing
+    rt.spawn(crate::core::watchdog::run(reload_manager.clone(), worker_id));
+
     rt.spawn(async move {
-        let listener = TcpListener::from_std(std::net::TcpListener::from(socket_held.get_socket()))
-            .expect("Failed to convert listener");
+        let listener = std::net::TcpListener::from(socket_held.get_socket());
+        crate::socket::record_listener(&listener);
+        let listener = TcpListener::from_std(listener).expect("Failed to convert listener");
 
         // Build Axum application with state including reload manager
+        let drain_rm = rm_for_drain.clone();
         let state = AppState {
             router,
             middleware,
@@ -408,22 +1203,28 @@ pub fn run_worker(
 
         crate::hlog_info!("Axum worker {} started", worker_id);
 
-        // Serve with Axum
-        axum::serve(listener, app)
-            .with_graceful_shutdown(async {
-                shutdown_rx.await.ok();
-            })
-            .await
-            .expect("Server error");
+        // TLS (when configured) is terminated by the listener
+        let listener = HypernListener::new(listener, crate::http::tls::server_config())
+            .expect("Failed to create listener");
+
+        // Each connection's details reach handlers via ConnectInfo; keep-alive
+        // connections close once a drain starts
+        crate::http::serve::serve(listener, app, drain_rm, async {
+            shutdown_rx.await.ok();
+        })
+        .await;
 
         crate::hlog_info!("Worker {} Axum server stopped", worker_id);
     });
 
+    crate::core::describe::log_worker(worker_id);
     crate::hlog_info!("Worker {} started", worker_id);
 
     // Keep event loop alive in this worker process until stopped by signal
     let _ = ev_loop.call_method0("run_forever");
 
+    crate::core::deps::shutdown_app_scope(py, ev_loop);
+
     crate::hlog_info!("Worker {} stopped", worker_id);
     Ok(())
 }
@@ -440,5 +1241,5 @@ pub async fn handle_request_standalone(
         middleware,
         reload_manager,
     };
-    handle_request_inner(&state, req).await
+    handle_request_inner(&state, req, &mut RequestTimer::start()).await
 }
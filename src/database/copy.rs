@@ -0,0 +1,525 @@
+//! Bulk loads and exports with PostgreSQL `COPY`.
+//!
+//! Both directions run on the session's connection in a task on the database
+//! runtime, so they take part in an open transaction and queue behind other
+//! operations on the session like any query. `copy_in` feeds the task chunks
+//! of converted rows over a bounded channel: a million-row iterator is read
+//! one chunk at a time and never held in Rust all at once. Dropping the
+//! channel before the final chunk aborts the copy (`CopyFail`), which leaves
+//! the connection usable; inside a transaction the session is marked failed
+//! so it rolls back.
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+use bytes::{BufMut, Bytes, BytesMut};
+use deadpool_postgres::Object;
+use futures_util::{SinkExt, StreamExt};
+use parking_lot::Mutex;
+use pyo3::exceptions::{PyRuntimeError, PyStopIteration, PyValueError};
+use pyo3::prelude::*;
+use tokio::sync::{mpsc, oneshot};
+use tokio_postgres::binary_copy::{BinaryCopyInWriter, BinaryCopyOutRow, BinaryCopyOutStream};
+use tokio_postgres::types::{ToSql, Type};
+
+use super::pool::get_db_runtime;
+use super::request_context::{format_db_error, session_error, DatabaseContextInner};
+use super::row_converter::{DynParam, RowConverter, RowPlan};
+use crate::core::deadline;
+
+/// Rows converted per chunk handed to the copy task unless set per call
+pub const DEFAULT_COPY_CHUNK_SIZE: usize = 5000;
+
+/// Chunks queued between the converting thread and the copy task
+const CHANNEL_DEPTH: usize = 2;
+
+/// Wire format of a `COPY ... FROM STDIN`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyFormat {
+    /// Values encoded by type, as for query parameters
+    Binary,
+    /// Tab-separated text, escaped and parsed by the server
+    Text,
+}
+
+impl CopyFormat {
+    pub fn parse(format: &str) -> PyResult<Self> {
+        match format.to_ascii_lowercase().as_str() {
+            "binary" => Ok(Self::Binary),
+            "text" => Ok(Self::Text),
+            other => Err(PyValueError::new_err(format!(
+                "Unknown COPY format '{}': expected 'binary' or 'text'",
+                other
+            ))),
+        }
+    }
+}
+
+/// Quote an identifier, each part of a `schema.table` name separately
+fn quote_ident(name: &str) -> String {
+    name.split('.')
+        .map(|part| format!("\"{}\"", part.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Table, columns and format of a `copy_in`
+pub struct CopyTarget {
+    table: String,
+    columns: String,
+    format: CopyFormat,
+}
+
+impl CopyTarget {
+    pub fn new(table: &str, columns: &[String], format: CopyFormat) -> PyResult<Self> {
+        if table.is_empty() {
+            return Err(PyValueError::new_err("copy_in requires a table name"));
+        }
+        if columns.is_empty() {
+            return Err(PyValueError::new_err(
+                "copy_in requires at least one column",
+            ));
+        }
+        Ok(Self {
+            table: quote_ident(table),
+            columns: columns
+                .iter()
+                .map(|c| quote_ident(c))
+                .collect::<Vec<_>>()
+                .join(", "),
+            format,
+        })
+    }
+
+    fn copy_sql(&self) -> String {
+        let format = match self.format {
+            CopyFormat::Binary => "binary",
+            CopyFormat::Text => "text",
+        };
+        format!(
+            "COPY {} ({}) FROM STDIN (FORMAT {})",
+            self.table, self.columns, format
+        )
+    }
+
+    /// Query whose result columns have the target columns' types
+    fn types_sql(&self) -> String {
+        format!("SELECT {} FROM {} LIMIT 0", self.columns, self.table)
+    }
+}
+
+/// What the converting thread sends the copy task
+pub enum CopyChunk {
+    Rows(Vec<Vec<DynParam>>),
+    /// All rows were sent; complete the copy
+    Finish,
+}
+
+/// Append `value` escaped for the text format: backslash, tab, newline and
+/// carriage return would otherwise end the field or row
+fn push_escaped(out: &mut BytesMut, value: &str) {
+    for byte in value.bytes() {
+        match byte {
+            b'\\' => out.put_slice(b"\\\\"),
+            b'\t' => out.put_slice(b"\\t"),
+            b'\n' => out.put_slice(b"\\n"),
+            b'\r' => out.put_slice(b"\\r"),
+            _ => out.put_u8(byte),
+        }
+    }
+}
+
+fn float_text(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// One row in the text format, `\N` for NULL
+fn push_text_row(out: &mut BytesMut, row: &[DynParam]) {
+    for (i, value) in row.iter().enumerate() {
+        if i > 0 {
+            out.put_u8(b'\t');
+        }
+        match value {
+            DynParam::Null => out.put_slice(b"\\N"),
+            DynParam::Bool(v) => out.put_u8(if *v { b't' } else { b'f' }),
+            DynParam::I16(v) => push_escaped(out, &v.to_string()),
+            DynParam::I32(v) => push_escaped(out, &v.to_string()),
+            DynParam::I64(v) => push_escaped(out, &v.to_string()),
+            DynParam::F32(v) => push_escaped(out, &float_text(*v as f64)),
+            DynParam::F64(v) => push_escaped(out, &float_text(*v)),
+            DynParam::Decimal(v) => push_escaped(out, &v.to_string()),
+            DynParam::Text(v) => push_escaped(out, v),
+            DynParam::Bytes(v) => {
+                // bytea hex input; the backslash is escaped like any other
+                let mut hex = String::with_capacity(2 + v.len() * 2);
+                hex.push_str("\\x");
+                for byte in v {
+                    let _ = write!(hex, "{:02x}", byte);
+                }
+                push_escaped(out, &hex);
+            }
+            DynParam::Date(v) => push_escaped(out, &v.format("%Y-%m-%d").to_string()),
+            DynParam::Time(v) => push_escaped(out, &v.format("%H:%M:%S%.f").to_string()),
+            DynParam::Timestamp(v) => {
+                push_escaped(out, &v.format("%Y-%m-%d %H:%M:%S%.f").to_string())
+            }
+            DynParam::Json(v) => push_escaped(out, &v.to_string()),
+        }
+    }
+    out.put_u8(b'\n');
+}
+
+/// What the copy-out task sends the Python iterator
+pub enum CopyOutChunk {
+    Rows(Vec<BinaryCopyOutRow>),
+    Lines(Vec<Bytes>),
+}
+
+impl DatabaseContextInner {
+    /// Run `COPY ... FROM STDIN`, writing the rows received on `chunks`
+    /// until [`CopyChunk::Finish`]. Returns the number of rows copied.
+    pub async fn copy_in(
+        &self,
+        target: &CopyTarget,
+        chunks: mpsc::Receiver<CopyChunk>,
+    ) -> Result<u64, String> {
+        let result = self.run_copy_in(target, chunks).await;
+        if result.is_err() && self.in_transaction() {
+            // The failed COPY aborted the transaction
+            self.set_error();
+        }
+        result
+    }
+
+    async fn run_copy_in(
+        &self,
+        target: &CopyTarget,
+        chunks: mpsc::Receiver<CopyChunk>,
+    ) -> Result<u64, String> {
+        let conn = self.lock_connection().await?;
+        self.autocommit(&conn, self.write_copy(&conn, target, chunks))
+            .await
+    }
+
+    async fn write_copy(
+        &self,
+        conn: &Object,
+        target: &CopyTarget,
+        mut chunks: mpsc::Receiver<CopyChunk>,
+    ) -> Result<u64, String> {
+        let fail = |e: tokio_postgres::Error| format!("COPY failed: {}", format_db_error(&e));
+        let types: Vec<Type> = match target.format {
+            CopyFormat::Binary => conn
+                .prepare(&target.types_sql())
+                .await
+                .map_err(fail)?
+                .columns()
+                .iter()
+                .map(|column| column.type_().clone())
+                .collect(),
+            CopyFormat::Text => Vec::new(),
+        };
+        let sink = conn
+            .copy_in::<_, Bytes>(&target.copy_sql())
+            .await
+            .map_err(fail)?;
+
+        match target.format {
+            CopyFormat::Binary => {
+                let writer = BinaryCopyInWriter::new(sink, &types);
+                let mut writer = std::pin::pin!(writer);
+                while let Some(chunk) = chunks.recv().await {
+                    match chunk {
+                        CopyChunk::Rows(rows) => {
+                            self.check_deadline()?;
+                            for row in &rows {
+                                let values: Vec<&(dyn ToSql + Sync)> =
+                                    row.iter().map(|v| v as &(dyn ToSql + Sync)).collect();
+                                writer.as_mut().write(&values).await.map_err(fail)?;
+                            }
+                        }
+                        CopyChunk::Finish => return writer.finish().await.map_err(fail),
+                    }
+                }
+            }
+            CopyFormat::Text => {
+                let mut sink = std::pin::pin!(sink);
+                while let Some(chunk) = chunks.recv().await {
+                    match chunk {
+                        CopyChunk::Rows(rows) => {
+                            self.check_deadline()?;
+                            let mut buf = BytesMut::new();
+                            for row in &rows {
+                                push_text_row(&mut buf, row);
+                            }
+                            sink.send(buf.freeze()).await.map_err(fail)?;
+                        }
+                        CopyChunk::Finish => return sink.as_mut().finish().await.map_err(fail),
+                    }
+                }
+            }
+        }
+        // The sender went away without finishing; dropping the sink aborts
+        Err("COPY aborted before all rows were sent".to_string())
+    }
+
+    /// Run `COPY (query) TO STDOUT`, sending rows (binary) or lines (CSV)
+    /// in chunks of `chunk_size` until the export ends or `out` is dropped
+    pub async fn copy_out(
+        &self,
+        query: &str,
+        csv: Option<bool>,
+        chunk_size: usize,
+        columns: oneshot::Sender<Vec<(String, Type)>>,
+        out: mpsc::Sender<Result<CopyOutChunk, String>>,
+    ) {
+        let fail = |e: tokio_postgres::Error| format!("COPY failed: {}", format_db_error(&e));
+        let result: Result<(), String> = async {
+            let conn = self.lock_connection().await?;
+            let export = async {
+                let chunk_size = chunk_size.max(1);
+                match csv {
+                    None => {
+                        let statement = conn.prepare(query).await.map_err(fail)?;
+                        let types: Vec<Type> = statement
+                            .columns()
+                            .iter()
+                            .map(|column| column.type_().clone())
+                            .collect();
+                        let _ = columns.send(
+                            statement
+                                .columns()
+                                .iter()
+                                .map(|column| (column.name().to_string(), column.type_().clone()))
+                                .collect(),
+                        );
+                        let sql = format!("COPY ({}) TO STDOUT (FORMAT binary)", query);
+                        let stream = conn.copy_out(&sql).await.map_err(fail)?;
+                        let mut rows = std::pin::pin!(BinaryCopyOutStream::new(stream, &types));
+                        let mut chunk = Vec::with_capacity(chunk_size);
+                        while let Some(row) = rows.next().await {
+                            chunk.push(row.map_err(fail)?);
+                            if chunk.len() == chunk_size {
+                                let full =
+                                    std::mem::replace(&mut chunk, Vec::with_capacity(chunk_size));
+                                if out.send(Ok(CopyOutChunk::Rows(full))).await.is_err() {
+                                    return Ok(());
+                                }
+                            }
+                        }
+                        if !chunk.is_empty() {
+                            let _ = out.send(Ok(CopyOutChunk::Rows(chunk))).await;
+                        }
+                    }
+                    Some(header) => {
+                        drop(columns);
+                        let sql =
+                            format!("COPY ({}) TO STDOUT (FORMAT csv, HEADER {})", query, header);
+                        let stream = conn.copy_out(&sql).await.map_err(fail)?;
+                        let mut lines = std::pin::pin!(stream);
+                        let mut chunk = Vec::with_capacity(chunk_size);
+                        // The server sends one CopyData message per row
+                        while let Some(line) = lines.next().await {
+                            chunk.push(line.map_err(fail)?);
+                            if chunk.len() == chunk_size {
+                                let full =
+                                    std::mem::replace(&mut chunk, Vec::with_capacity(chunk_size));
+                                if out.send(Ok(CopyOutChunk::Lines(full))).await.is_err() {
+                                    return Ok(());
+                                }
+                            }
+                        }
+                        if !chunk.is_empty() {
+                            let _ = out.send(Ok(CopyOutChunk::Lines(chunk))).await;
+                        }
+                    }
+                }
+                Ok(())
+            };
+            self.autocommit(&conn, export).await
+        }
+        .await;
+        if let Err(message) = result {
+            if self.in_transaction() {
+                self.set_error();
+            }
+            let _ = out.send(Err(message)).await;
+        }
+    }
+
+    fn check_deadline(&self) -> Result<(), String> {
+        match self.deadline() {
+            Some(deadline) if deadline.expired() => {
+                Err(format!("COPY failed: {}", deadline::EXCEEDED))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Run `copy_in` on `ctx`, converting `rows` on this thread while the copy
+/// task writes the previous chunk. A Python error while reading `rows`
+/// aborts the copy and is raised in preference to the task's result.
+pub fn copy_in(
+    py: Python<'_>,
+    ctx: Arc<DatabaseContextInner>,
+    target: CopyTarget,
+    rows: &Bound<'_, PyAny>,
+    width: usize,
+    chunk_size: usize,
+) -> PyResult<u64> {
+    let (tx, rx) = mpsc::channel(CHANNEL_DEPTH);
+    let task = get_db_runtime().spawn(async move { ctx.copy_in(&target, rx).await });
+
+    let fed = feed_rows(py, rows, width, chunk_size, &tx);
+    if fed.is_ok() {
+        let _ = py.detach(|| tx.blocking_send(CopyChunk::Finish));
+    }
+    drop(tx);
+
+    let result = py
+        .detach(|| get_db_runtime().block_on(task))
+        .map_err(|e| PyRuntimeError::new_err(format!("COPY task failed: {}", e)))?;
+    fed?;
+    result.map_err(session_error)
+}
+
+/// Start `copy_out` on `ctx` and return the iterator over its rows.
+/// `csv` is `Some(header)` for CSV lines, `None` for row tuples.
+pub fn copy_out(
+    py: Python<'_>,
+    ctx: Arc<DatabaseContextInner>,
+    query: String,
+    csv: Option<bool>,
+    chunk_size: usize,
+) -> CopyOutRows {
+    let (columns_tx, columns_rx) = oneshot::channel();
+    let (tx, rx) = mpsc::channel(CHANNEL_DEPTH);
+    get_db_runtime().spawn(async move {
+        ctx.copy_out(&query, csv, chunk_size, columns_tx, tx).await;
+    });
+    // Dropped unsent in CSV mode or on error; the error arrives on `rx`
+    let columns = py.detach(|| columns_rx.blocking_recv()).unwrap_or_default();
+    CopyOutRows::new(columns, rx)
+}
+
+/// Convert the rows of `rows` (an iterable of sequences) in chunks of
+/// `chunk_size` and hand each to the copy task. Stops early without error
+/// when the task is gone; its result carries the reason.
+fn feed_rows(
+    py: Python<'_>,
+    rows: &Bound<'_, PyAny>,
+    width: usize,
+    chunk_size: usize,
+    chunks: &mpsc::Sender<CopyChunk>,
+) -> PyResult<()> {
+    let chunk_size = chunk_size.max(1);
+    let mut iter = rows.try_iter()?;
+    loop {
+        let mut chunk = Vec::with_capacity(chunk_size);
+        for row in iter.by_ref().take(chunk_size) {
+            let values: Vec<Py<PyAny>> = row?.extract()?;
+            if values.len() != width {
+                return Err(PyValueError::new_err(format!(
+                    "copy_in row has {} values for {} columns",
+                    values.len(),
+                    width
+                )));
+            }
+            chunk.push(RowConverter::convert_params_from_py(py, &values)?);
+        }
+        if chunk.is_empty() {
+            return Ok(());
+        }
+        if py
+            .detach(|| chunks.blocking_send(CopyChunk::Rows(chunk)))
+            .is_err()
+        {
+            return Ok(());
+        }
+        py.check_signals()?;
+    }
+}
+
+/// Iterator over the rows of `DbSession.copy_out()`: tuples in column
+/// order, or CSV lines as `str`. Rows arrive in chunks while iterating, so
+/// the export is never held in memory at once; dropping the iterator early
+/// stops it.
+#[pyclass]
+pub struct CopyOutRows {
+    chunks: Mutex<mpsc::Receiver<Result<CopyOutChunk, String>>>,
+    /// Column names and types for converting binary rows
+    columns: Arc<Vec<(String, Type)>>,
+    buffered: Mutex<VecDeque<Py<PyAny>>>,
+}
+
+impl CopyOutRows {
+    pub fn new(
+        columns: Vec<(String, Type)>,
+        chunks: mpsc::Receiver<Result<CopyOutChunk, String>>,
+    ) -> Self {
+        Self {
+            chunks: Mutex::new(chunks),
+            columns: Arc::new(columns),
+            buffered: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    fn convert(&self, py: Python<'_>, chunk: CopyOutChunk) -> PyResult<VecDeque<Py<PyAny>>> {
+        match chunk {
+            CopyOutChunk::Rows(rows) => {
+                let plan = RowPlan::from_columns(
+                    py,
+                    self.columns.iter().map(|(name, ty)| (name.as_str(), ty)),
+                )?;
+                rows.iter().map(|row| plan.tuple(row)).collect()
+            }
+            CopyOutChunk::Lines(lines) => lines
+                .iter()
+                .map(|line| {
+                    let text = std::str::from_utf8(line)
+                        .map_err(|e| PyValueError::new_err(format!("COPY output: {}", e)))?;
+                    Ok(pyo3::types::PyString::new(py, text).into_any().unbind())
+                })
+                .collect(),
+        }
+    }
+}
+
+#[pymethods]
+impl CopyOutRows {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        if let Some(row) = self.buffered.lock().pop_front() {
+            return Ok(row);
+        }
+        let received = py.detach(|| self.chunks.lock().blocking_recv());
+        match received {
+            None => Err(PyStopIteration::new_err(())),
+            Some(Err(message)) => Err(session_error(message)),
+            Some(Ok(chunk)) => {
+                let mut rows = self.convert(py, chunk)?;
+                let first = rows.pop_front();
+                *self.buffered.lock() = rows;
+                first.ok_or_else(|| PyStopIteration::new_err(()))
+            }
+        }
+    }
+
+    /// Stop the export and free the session's connection without reading
+    /// the remaining rows
+    fn close(&self) {
+        self.chunks.lock().close();
+        self.buffered.lock().clear();
+    }
+}
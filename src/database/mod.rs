@@ -1,14 +1,40 @@
 pub mod any_pool;
 pub mod config;
 pub mod connection;
+pub mod copy;
+pub mod named_params;
 pub mod operation;
 pub mod pool;
 pub mod request_context;
 pub mod row_converter;
+pub mod tenant;
+pub mod test_mode;
+pub mod tls;
 pub mod transaction;
 
 // Re-exports
 pub use any_pool::AnyPool;
+pub use copy::CopyOutRows;
 pub use operation::RowStream;
 pub use pool::{ConnectionPool, PoolConfig, PoolStatus};
-pub use request_context::{finalize_db, finalize_db_all, get_db, DbSession};
+pub use request_context::{finalize_db, finalize_db_all, get_db, DbFuture, DbSession};
+
+use pyo3::prelude::*;
+
+/// Register pool, session and transaction classes plus the session functions.
+pub fn register_all(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<ConnectionPool>()?;
+    m.add_class::<PoolConfig>()?;
+    m.add_class::<PoolStatus>()?;
+    m.add_class::<DbSession>()?;
+    m.add_class::<DbFuture>()?;
+    m.add_class::<RowStream>()?;
+    m.add_class::<CopyOutRows>()?;
+    m.add_class::<AnyPool>()?;
+    m.add_class::<config::DatabaseConfig>()?;
+    m.add_class::<transaction::DatabaseTransaction>()?;
+    m.add_function(wrap_pyfunction!(get_db, m)?)?;
+    m.add_function(wrap_pyfunction!(finalize_db, m)?)?;
+    m.add_function(wrap_pyfunction!(finalize_db_all, m)?)?;
+    Ok(())
+}
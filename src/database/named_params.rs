@@ -0,0 +1,237 @@
+//! Named SQL parameters (`:name`) rewritten to PostgreSQL positional form (`$n`).
+//!
+//! The scanner skips string literals, quoted identifiers, dollar-quoted bodies,
+//! comments and `::type` casts so only real placeholders are rewritten. A `:`
+//! inside an array subscript (`arr[1:n]`) or right after an identifier, digit
+//! or `]` is a slice bound, not a placeholder; `ARRAY[...]` constructors still
+//! take placeholders. Parsed queries are cached by SQL text.
+
+use dashmap::DashMap;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::sync::{Arc, OnceLock};
+
+/// Maximum number of distinct SQL texts kept in the rewrite cache
+const MAX_CACHED_QUERIES: usize = 1024;
+
+static NAMED_QUERY_CACHE: OnceLock<DashMap<String, Arc<NamedQuery>>> = OnceLock::new();
+
+/// A query rewritten from `:name` to `$n` placeholders
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedQuery {
+    /// SQL with positional placeholders
+    pub sql: String,
+    /// Parameter names in positional order (`names[0]` is `$1`)
+    pub names: Vec<String>,
+}
+
+impl NamedQuery {
+    /// Parse a query, reusing the cached rewrite when the SQL was seen before
+    pub fn cached(sql: &str) -> Arc<NamedQuery> {
+        let cache = NAMED_QUERY_CACHE.get_or_init(DashMap::new);
+        if let Some(query) = cache.get(sql) {
+            return query.clone();
+        }
+        let query = Arc::new(parse_named_query(sql));
+        if cache.len() >= MAX_CACHED_QUERIES {
+            cache.clear();
+        }
+        cache.insert(sql.to_string(), query.clone());
+        query
+    }
+
+    /// Order dict values by placeholder position.
+    ///
+    /// Missing or unexpected keys raise a ValueError naming the offending keys.
+    pub fn bind(&self, params: &Bound<'_, PyDict>) -> PyResult<Vec<Py<PyAny>>> {
+        let mut values = Vec::with_capacity(self.names.len());
+        let mut missing = Vec::new();
+        for name in &self.names {
+            match params.get_item(name)? {
+                Some(value) => values.push(value.unbind()),
+                None => missing.push(name.as_str()),
+            }
+        }
+
+        let mut unexpected = Vec::new();
+        for key in params.keys() {
+            let key: String = key.extract()?;
+            if !self.names.contains(&key) {
+                unexpected.push(key);
+            }
+        }
+
+        if missing.is_empty() && unexpected.is_empty() {
+            return Ok(values);
+        }
+
+        let mut problems = Vec::new();
+        if !missing.is_empty() {
+            problems.push(format!("missing named parameters: {}", missing.join(", ")));
+        }
+        if !unexpected.is_empty() {
+            problems.push(format!("unexpected named parameters: {}", unexpected.join(", ")));
+        }
+        Err(PyValueError::new_err(format!(
+            "Invalid query parameters ({})",
+            problems.join("; ")
+        )))
+    }
+}
+
+/// Rewrite `:name` placeholders to `$n`, reusing the same index for repeated names
+pub fn parse_named_query(sql: &str) -> NamedQuery {
+    let bytes = sql.as_bytes();
+    let mut out = String::with_capacity(sql.len() + 8);
+    let mut names: Vec<String> = Vec::new();
+    let mut last = 0;
+    let mut i = 0;
+    // Open brackets, innermost last: true for subscripts, false for ARRAY[...]
+    let mut brackets: Vec<bool> = Vec::new();
+
+    while i < bytes.len() {
+        let next = bytes.get(i + 1).copied();
+        match bytes[i] {
+            b'\'' => {
+                let backslash_escapes = i > 0
+                    && matches!(bytes[i - 1], b'E' | b'e')
+                    && (i < 2 || !is_ident_char(bytes[i - 2]));
+                i = skip_quoted(bytes, i, b'\'', backslash_escapes);
+            }
+            b'"' => i = skip_quoted(bytes, i, b'"', false),
+            b'-' if next == Some(b'-') => {
+                i = bytes[i..]
+                    .iter()
+                    .position(|&b| b == b'\n')
+                    .map_or(bytes.len(), |p| i + p + 1);
+            }
+            b'/' if next == Some(b'*') => i = skip_block_comment(bytes, i),
+            b'$' => i = skip_dollar_quoted(bytes, i),
+            b'[' => {
+                brackets.push(is_subscript(&bytes[..i]));
+                i += 1;
+            }
+            b']' => {
+                brackets.pop();
+                i += 1;
+            }
+            b':' if next == Some(b':') => i += 2,
+            b':' if next.is_some_and(is_ident_start)
+                && brackets.last() != Some(&true)
+                && (i == 0 || !(is_ident_char(bytes[i - 1]) || bytes[i - 1] == b']')) =>
+            {
+                let start = i + 1;
+                let mut end = start;
+                while end < bytes.len() && is_ident_char(bytes[end]) {
+                    end += 1;
+                }
+                let name = &sql[start..end];
+                let index = match names.iter().position(|n| n == name) {
+                    Some(index) => index,
+                    None => {
+                        names.push(name.to_string());
+                        names.len() - 1
+                    }
+                };
+                out.push_str(&sql[last..i]);
+                out.push('$');
+                out.push_str(&(index + 1).to_string());
+                last = end;
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+
+    out.push_str(&sql[last..]);
+    NamedQuery { sql: out, names }
+}
+
+fn is_ident_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_'
+}
+
+fn is_ident_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// Whether a `[` after `before` subscripts a value rather than opening an
+/// `ARRAY[...]` constructor
+fn is_subscript(before: &[u8]) -> bool {
+    let before = before.trim_ascii_end();
+    match before.last() {
+        Some(b']' | b')' | b'"') => true,
+        Some(&b) if is_ident_char(b) => {
+            let word_start = before
+                .iter()
+                .rposition(|&b| !is_ident_char(b))
+                .map_or(0, |p| p + 1);
+            !before[word_start..].eq_ignore_ascii_case(b"array")
+        }
+        _ => false,
+    }
+}
+
+/// Skip a quoted literal starting at `start`; doubled quotes are escapes
+fn skip_quoted(bytes: &[u8], start: usize, quote: u8, backslash_escapes: bool) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        if backslash_escapes && bytes[i] == b'\\' {
+            i += 2;
+            continue;
+        }
+        if bytes[i] == quote {
+            if bytes.get(i + 1) == Some(&quote) {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+/// Skip a (possibly nested) `/* ... */` comment
+fn skip_block_comment(bytes: &[u8], start: usize) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+    while i + 1 < bytes.len() {
+        match (bytes[i], bytes[i + 1]) {
+            (b'/', b'*') => {
+                depth += 1;
+                i += 2;
+            }
+            (b'*', b'/') => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Skip a `$tag$ ... $tag$` body; positional `$1` and lone `$` are left alone
+fn skip_dollar_quoted(bytes: &[u8], start: usize) -> usize {
+    let mut end = start + 1;
+    if end < bytes.len() && !is_ident_start(bytes[end]) && bytes[end] != b'$' {
+        return start + 1;
+    }
+    while end < bytes.len() && is_ident_char(bytes[end]) {
+        end += 1;
+    }
+    if bytes.get(end) != Some(&b'$') {
+        return start + 1;
+    }
+    let tag = &bytes[start..=end];
+    let body = end + 1;
+    bytes[body..]
+        .windows(tag.len())
+        .position(|w| w == tag)
+        .map_or(bytes.len(), |p| body + p + tag.len())
+}
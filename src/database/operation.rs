@@ -1,9 +1,10 @@
-use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
-use futures_core::Stream;
+use futures_util::StreamExt;
 use pyo3::{
+    exceptions::PyStopAsyncIteration,
     prelude::*,
     types::{
         PyBool, PyDate, PyDateAccess, PyDateTime, PyDict, PyFloat, PyInt, PyList, PyString, PyTime,
@@ -14,20 +15,145 @@ use serde_json::to_string;
 use sqlx::{
     postgres::{PgArguments, PgRow},
     types::{Json, JsonValue},
-    Column, Row, ValueRef,
+    Arguments, Column, Row, ValueRef,
 };
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, Mutex};
 
-/// A streaming row iterator that yields chunks of rows lazily.
-/// This allows Python to iterate over large result sets without loading everything into memory.
+use super::pool::get_db_runtime;
+use super::request_context::{session_error, DbFuture, Ready};
+use super::row_converter::RowConverter;
+
+/// Chunks fetched ahead of the consumer unless set per call
+pub const DEFAULT_PREFETCH: usize = 2;
+
+/// Rows per chunk of a stream unless set per call
+pub const DEFAULT_STREAM_CHUNK_SIZE: usize = 1000;
+
+/// Rows a fetch task hands a [`RowStream`], converted to dicts when Python
+/// takes them
+pub enum RowChunk {
+    Sqlx(Vec<PgRow>),
+    Postgres(Vec<tokio_postgres::Row>),
+}
+
+impl RowChunk {
+    fn into_py(self, py: Python<'_>) -> PyResult<Vec<Py<PyAny>>> {
+        match self {
+            RowChunk::Sqlx(rows) => rows
+                .iter()
+                .map(|row| ParameterBinder.bind_result(py, row))
+                .collect(),
+            RowChunk::Postgres(rows) => RowConverter::rows_to_py_dicts(py, &rows, 0),
+        }
+    }
+}
+
+type Received = Option<Result<RowChunk, String>>;
+
+/// Counters shared by a stream and its fetch task
+#[derive(Default)]
+struct StreamState {
+    /// Chunks fetched and not yet taken
+    prefetched: AtomicUsize,
+    peak_prefetched: AtomicUsize,
+    taken: AtomicUsize,
+    exhausted: AtomicBool,
+    /// Set by `close()`; a chunk the task was already fetching is dropped
+    closed: AtomicBool,
+}
+
+impl StreamState {
+    /// Turn what the channel gave into the next chunk, None at the end
+    fn take(&self, py: Python<'_>, received: Received) -> PyResult<Option<Vec<Py<PyAny>>>> {
+        if self.closed.load(Ordering::Relaxed) {
+            self.exhausted.store(true, Ordering::Relaxed);
+            return Ok(None);
+        }
+        match received {
+            Some(Ok(chunk)) => {
+                self.prefetched.fetch_sub(1, Ordering::Relaxed);
+                self.taken.fetch_add(1, Ordering::Relaxed);
+                chunk.into_py(py).map(Some)
+            }
+            Some(Err(message)) => {
+                self.exhausted.store(true, Ordering::Relaxed);
+                Err(session_error(message))
+            }
+            None => {
+                self.exhausted.store(true, Ordering::Relaxed);
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Sending half of a [`RowStream`], held by the task fetching its rows
+pub struct ChunkSender {
+    tx: mpsc::Sender<Result<RowChunk, String>>,
+    state: Arc<StreamState>,
+}
+
+impl ChunkSender {
+    /// Wait for room for one more chunk; None once the stream was closed
+    /// or dropped, when the task should stop and release its connection.
+    /// Reserving before fetching keeps at most `prefetch` chunks in memory.
+    pub async fn reserve(&self) -> Option<ChunkPermit<'_>> {
+        let permit = self.tx.reserve().await.ok()?;
+        Some(ChunkPermit {
+            permit,
+            state: &self.state,
+        })
+    }
+
+    /// Hand the consumer an error, raised by its next `__next__`
+    pub async fn fail(&self, message: String) {
+        let _ = self.tx.send(Err(message)).await;
+    }
+}
+
+/// Room for one chunk in a [`RowStream`]
+pub struct ChunkPermit<'a> {
+    permit: mpsc::Permit<'a, Result<RowChunk, String>>,
+    state: &'a StreamState,
+}
+
+impl ChunkPermit<'_> {
+    pub fn send(self, chunk: RowChunk) {
+        let prefetched = self.state.prefetched.fetch_add(1, Ordering::Relaxed) + 1;
+        self.state
+            .peak_prefetched
+            .fetch_max(prefetched, Ordering::Relaxed);
+        self.permit.send(Ok(chunk));
+    }
+}
+
+/// Iterator over the rows of a query in chunks (lists of dicts), fetched
+/// by a task on the database runtime while Python works through the
+/// previous ones. At most `prefetch` chunks are held ahead of the consumer;
+/// the task waits for it otherwise. Closing or dropping the stream stops
+/// the task, which releases the connection. A database error is raised by
+/// the `__next__` (or `__anext__`) that reaches it.
 #[pyclass]
 pub struct RowStream {
-    /// The chunks that have been fetched (we still collect, but can be improved with channels)
-    chunks: std::sync::Mutex<Vec<Vec<Py<PyAny>>>>,
-    /// Current chunk index
-    current_index: std::sync::Mutex<usize>,
-    /// Whether the stream has been fully consumed
-    exhausted: std::sync::Mutex<bool>,
+    chunks: Arc<tokio::sync::Mutex<mpsc::Receiver<Result<RowChunk, String>>>>,
+    state: Arc<StreamState>,
+}
+
+impl RowStream {
+    /// A stream and the sender its fetch task fills
+    pub fn channel(prefetch: usize) -> (ChunkSender, Self) {
+        let (tx, rx) = mpsc::channel(prefetch.max(1));
+        let state = Arc::new(StreamState::default());
+        let sender = ChunkSender {
+            tx,
+            state: state.clone(),
+        };
+        let stream = Self {
+            chunks: Arc::new(tokio::sync::Mutex::new(rx)),
+            state,
+        };
+        (sender, stream)
+    }
 }
 
 #[pymethods]
@@ -36,40 +162,58 @@ impl RowStream {
         slf
     }
 
-    fn __next__(&self, py: Python<'_>) -> Option<Vec<Py<PyAny>>> {
-        let chunks_guard = self.chunks.lock().unwrap();
-        let mut index = self.current_index.lock().unwrap();
-        let mut is_exhausted = self.exhausted.lock().unwrap();
+    /// The next chunk, waiting for it with the GIL released
+    fn __next__(&self, py: Python<'_>) -> PyResult<Option<Vec<Py<PyAny>>>> {
+        let received = py.detach(|| self.chunks.blocking_lock().blocking_recv());
+        self.state.take(py, received)
+    }
 
-        if *index >= chunks_guard.len() {
-            *is_exhausted = true;
-            return None;
-        }
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
 
-        let chunk: Vec<Py<PyAny>> = chunks_guard[*index]
-            .iter()
-            .map(|item| item.clone_ref(py))
-            .collect();
-        *index += 1;
-        Some(chunk)
+    /// Awaitable of the next chunk; concurrent calls get chunks in call order
+    fn __anext__(&self) -> DbFuture {
+        let chunks = self.chunks.clone();
+        let state = self.state.clone();
+        DbFuture::spawn(async move {
+            let received = chunks.lock().await.recv().await;
+            Box::new(move |py: Python<'_>| match state.take(py, received)? {
+                Some(chunk) => Ok(chunk.into_pyobject(py)?.into_any().unbind()),
+                None => Err(PyStopAsyncIteration::new_err(())),
+            }) as Ready
+        })
+    }
+
+    /// Stop fetching and drop the chunks fetched ahead; the connection is
+    /// released without reading the remaining rows
+    fn close(&self, py: Python<'_>) {
+        self.state.closed.store(true, Ordering::Relaxed);
+        py.detach(|| {
+            let mut chunks = self.chunks.blocking_lock();
+            chunks.close();
+            while chunks.try_recv().is_ok() {}
+        });
+        self.state.prefetched.store(0, Ordering::Relaxed);
     }
 
     fn is_exhausted(&self) -> bool {
-        *self.exhausted.lock().unwrap()
+        self.state.exhausted.load(Ordering::Relaxed)
     }
 
+    /// Chunks returned so far
     fn chunk_count(&self) -> usize {
-        self.chunks.lock().unwrap().len()
+        self.state.taken.load(Ordering::Relaxed)
     }
-}
 
-impl RowStream {
-    pub fn new(chunks: Vec<Vec<Py<PyAny>>>) -> Self {
-        Self {
-            chunks: std::sync::Mutex::new(chunks),
-            current_index: std::sync::Mutex::new(0),
-            exhausted: std::sync::Mutex::new(false),
-        }
+    /// Chunks fetched and waiting to be taken
+    fn prefetched(&self) -> usize {
+        self.state.prefetched.load(Ordering::Relaxed)
+    }
+
+    /// Most chunks that were waiting at once
+    fn peak_prefetched(&self) -> usize {
+        self.state.peak_prefetched.load(Ordering::Relaxed)
     }
 }
 
@@ -82,20 +226,25 @@ impl ParameterBinder {
         query: &'q str,
         params: Vec<Py<PyAny>>,
     ) -> Result<sqlx::query::Query<'q, sqlx::Postgres, PgArguments>, PyErr> {
-        let mut query_builder = sqlx::query(query);
+        Ok(sqlx::query_with(query, self.bind_arguments(py, params)?))
+    }
+
+    /// Encode `params` for a query, owned so it can outlive the caller
+    fn bind_arguments(&self, py: Python<'_>, params: Vec<Py<PyAny>>) -> PyResult<PgArguments> {
+        let mut arguments = PgArguments::default();
 
         for param in params {
             let p = param.bind(py);
-            query_builder = if p.is_none() {
-                query_builder.bind(None::<Option<String>>)
+            let added = if p.is_none() {
+                arguments.add(None::<Option<String>>)
             } else if p.is_instance_of::<PyString>() {
-                query_builder.bind(p.extract::<String>()?)
+                arguments.add(p.extract::<String>()?)
             } else if p.is_instance_of::<PyBool>() {
-                query_builder.bind(p.extract::<bool>()?)
+                arguments.add(p.extract::<bool>()?)
             } else if p.is_instance_of::<PyInt>() {
-                query_builder.bind(p.extract::<i64>()?)
+                arguments.add(p.extract::<i64>()?)
             } else if p.is_instance_of::<PyFloat>() {
-                query_builder.bind(p.extract::<f64>()?)
+                arguments.add(p.extract::<f64>()?)
             } else if p.is_instance_of::<PyDateTime>() {
                 let dt = p.cast::<PyDateTime>()?;
                 let naive_dt = NaiveDateTime::new(
@@ -113,7 +262,7 @@ impl ParameterBinder {
                     )
                     .unwrap(),
                 );
-                query_builder.bind(naive_dt)
+                arguments.add(naive_dt)
             } else if p.is_instance_of::<PyDate>() {
                 let date = p.cast::<PyDate>()?;
                 let naive_date = NaiveDate::from_ymd_opt(
@@ -122,7 +271,7 @@ impl ParameterBinder {
                     date.get_day() as u32,
                 )
                 .unwrap();
-                query_builder.bind(naive_date)
+                arguments.add(naive_date)
             } else if p.is_instance_of::<PyTime>() {
                 let time = p.cast::<PyTime>()?;
                 let naive_time = NaiveTime::from_hms_nano_opt(
@@ -132,26 +281,27 @@ impl ParameterBinder {
                     time.get_microsecond() as u32 * 1000,
                 )
                 .unwrap();
-                query_builder.bind(naive_time)
+                arguments.add(naive_time)
             } else if p.is_instance_of::<PyDict>() {
                 let dict = p.cast::<PyDict>()?;
                 let json_value: JsonValue =
                     serde_json::from_str(&dict.to_string()).unwrap_or(JsonValue::Null);
-                query_builder.bind(Json(json_value))
+                arguments.add(Json(json_value))
             } else if p.is_instance_of::<PyList>() {
                 let list = p.cast::<PyList>()?;
                 let json_value: JsonValue =
                     serde_json::from_str(&list.to_string()).unwrap_or(JsonValue::Null);
-                query_builder.bind(Json(json_value))
+                arguments.add(Json(json_value))
             } else {
                 return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
                     "Unsupported parameter type: {:?}",
                     p.get_type()
                 )));
             };
+            added.map_err(|e| PyErr::new::<pyo3::exceptions::PyTypeError, _>(e.to_string()))?;
         }
 
-        Ok(query_builder)
+        Ok(arguments)
     }
 
     fn bind_result(&self, py: Python<'_>, row: &PgRow) -> Result<Py<PyAny>, PyErr> {
@@ -285,43 +435,51 @@ impl DatabaseOperations {
         Ok(result)
     }
 
-    pub async fn stream_data(
+    /// Stream `query`'s rows in the transaction. The fetch task holds the
+    /// transaction until the stream is exhausted, closed or dropped.
+    pub fn stream_data(
         &self,
         py: Python<'_>,
         transaction: Arc<Mutex<Option<sqlx::Transaction<'static, sqlx::Postgres>>>>,
         query: &str,
         params: Vec<Py<PyAny>>,
         chunk_size: usize,
+        prefetch: usize,
     ) -> PyResult<RowStream> {
-        let query_builder = ParameterBinder.bind_parameters(py, query, params)?;
-        let mut guard = transaction.lock().await.take().unwrap();
-        let mut stream = query_builder.fetch(&mut *guard);
-        let mut chunks: Vec<Vec<Py<PyAny>>> = Vec::new();
-        let mut current_chunk: Vec<Py<PyAny>> = Vec::new();
-
-        while let Some(row_result) = std::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
-            match row_result {
-                Ok(row) => {
-                    let row_data: Py<PyAny> = ParameterBinder.bind_result(py, &row)?;
-                    current_chunk.push(row_data);
-
-                    if current_chunk.len() >= chunk_size {
-                        chunks.push(current_chunk);
-                        current_chunk = Vec::new();
+        let arguments = ParameterBinder.bind_arguments(py, params)?;
+        let sql = query.to_string();
+        let chunk_size = chunk_size.max(1);
+        let (sender, stream) = RowStream::channel(prefetch);
+        get_db_runtime().spawn(async move {
+            let mut guard = transaction.lock().await;
+            let Some(transaction) = guard.as_mut() else {
+                sender.fail("No active transaction".to_string()).await;
+                return;
+            };
+            let mut rows = sqlx::query_with(&sql, arguments).fetch(&mut **transaction);
+            while let Some(permit) = sender.reserve().await {
+                let mut chunk = Vec::with_capacity(chunk_size);
+                while chunk.len() < chunk_size {
+                    match rows.next().await {
+                        Some(Ok(row)) => chunk.push(row),
+                        Some(Err(e)) => {
+                            drop(permit);
+                            sender.fail(e.to_string()).await;
+                            return;
+                        }
+                        None => break,
                     }
                 }
-                Err(e) => {
-                    return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
-                        e.to_string(),
-                    ));
+                let last = chunk.len() < chunk_size;
+                if !chunk.is_empty() {
+                    permit.send(RowChunk::Sqlx(chunk));
+                }
+                if last {
+                    return;
                 }
             }
-        }
-
-        if !current_chunk.is_empty() {
-            chunks.push(current_chunk);
-        }
-        Ok(RowStream::new(chunks))
+        });
+        Ok(stream)
     }
 
     pub async fn bulk_change(
@@ -1,15 +1,30 @@
 use dashmap::DashMap;
 use deadpool_postgres::Object;
-use pyo3::exceptions::PyRuntimeError;
+use parking_lot::Mutex;
+use pyo3::exceptions::{PyRuntimeError, PyStopIteration, PyTimeoutError, PyValueError};
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex, OnceLock};
-use tokio_postgres::Row;
-
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::MappedMutexGuard;
+use tokio_postgres::{NoTls, Row};
+
+use super::copy::{self, CopyFormat, CopyOutRows, CopyTarget, DEFAULT_COPY_CHUNK_SIZE};
+use super::named_params::NamedQuery;
+use super::operation::{
+    ChunkSender, RowChunk, RowStream, DEFAULT_PREFETCH, DEFAULT_STREAM_CHUNK_SIZE,
+};
 use super::pool::{get_db_runtime, ConnectionPoolManager};
-use super::row_converter::{DynParam, RowConverter};
-
-fn format_db_error(e: &tokio_postgres::Error) -> String {
+use super::row_converter::{DynParam, RowConverter, RowMapping, DEFAULT_GIL_BATCH_SIZE};
+use super::tenant;
+use super::test_mode::{self, Lease};
+use crate::core::deadline::{self, Deadline};
+use crate::core::global::get_asyncio;
+use crate::telemetry::gil::{self, Site};
+
+pub(super) fn format_db_error(e: &tokio_postgres::Error) -> String {
     if let Some(db_error) = e.as_db_error() {
         // Extract detailed PostgreSQL error information
         let mut msg = format!(
@@ -61,8 +76,13 @@ pub enum ContextState {
 pub struct DatabaseContextInner {
     request_id: String,
     alias: String,
-    /// The database connection (acquired lazily)
-    connection: Mutex<Option<Object>>,
+    /// Physical pool backing `alias` for this request (see `tenant`)
+    pool_alias: String,
+    /// The database connection (acquired lazily). Held for the whole of each
+    /// operation, so concurrent calls on one session queue in arrival order.
+    connection: tokio::sync::Mutex<Option<Lease>>,
+    /// Whether the connection is the one pinned by database test mode
+    pinned: Mutex<bool>,
     /// Current state
     state: Mutex<ContextState>,
     /// Whether to auto-commit on success
@@ -71,18 +91,28 @@ pub struct DatabaseContextInner {
     has_error: Mutex<bool>,
     /// Whether we're in a transaction
     in_transaction: Mutex<bool>,
+    /// Deadline of the request that opened the session
+    deadline: Mutex<Option<Deadline>>,
 }
 
 impl DatabaseContextInner {
     pub fn new(request_id: String, alias: String) -> Self {
+        let pool_alias = alias.clone();
+        Self::with_pool_alias(request_id, alias, pool_alias)
+    }
+
+    pub fn with_pool_alias(request_id: String, alias: String, pool_alias: String) -> Self {
         Self {
             request_id,
             alias,
-            connection: Mutex::new(None),
+            pool_alias,
+            connection: tokio::sync::Mutex::new(None),
+            pinned: Mutex::new(false),
             state: Mutex::new(ContextState::Idle),
             auto_commit: Mutex::new(true),
             has_error: Mutex::new(false),
             in_transaction: Mutex::new(false),
+            deadline: Mutex::new(None),
         }
     }
 
@@ -94,154 +124,313 @@ impl DatabaseContextInner {
         &self.alias
     }
 
+    pub fn pool_alias(&self) -> &str {
+        &self.pool_alias
+    }
+
     pub fn state(&self) -> ContextState {
-        *self.state.lock().unwrap()
+        *self.state.lock()
     }
 
     pub fn set_auto_commit(&self, auto_commit: bool) {
-        *self.auto_commit.lock().unwrap() = auto_commit;
+        *self.auto_commit.lock() = auto_commit;
     }
 
     pub fn set_error(&self) {
-        *self.has_error.lock().unwrap() = true;
+        *self.has_error.lock() = true;
     }
 
     pub fn has_error(&self) -> bool {
-        *self.has_error.lock().unwrap()
+        *self.has_error.lock()
     }
 
-    fn take_connection(&self) -> Option<Object> {
-        self.connection.lock().unwrap().take()
+    pub fn in_transaction(&self) -> bool {
+        *self.in_transaction.lock()
     }
 
-    fn put_connection(&self, conn: Object) {
-        *self.connection.lock().unwrap() = Some(conn);
+    /// Cap queries at the remaining budget of the request's timeout
+    pub fn set_deadline(&self, deadline: Option<Deadline>) {
+        *self.deadline.lock() = deadline;
     }
 
-    fn has_connection(&self) -> bool {
-        self.connection.lock().unwrap().is_some()
+    pub fn deadline(&self) -> Option<Deadline> {
+        *self.deadline.lock()
     }
 
-    async fn ensure_connection(&self) -> Result<(), String> {
-        if self.has_connection() {
-            return Ok(());
+    /// Exclusive use of the connection, acquired from the pool on first use
+    /// (or the pinned one, in database test mode). Callers wait their turn
+    /// rather than finding the connection missing.
+    pub(super) async fn lock_connection(&self) -> Result<MappedMutexGuard<'_, Object>, String> {
+        let mut connection = self.connection.lock().await;
+        if connection.is_none() {
+            if let Some(pinned) = test_mode::pinned(&self.pool_alias) {
+                let conn = pinned.acquire(&self.request_id).await?;
+                *connection = Some(Lease::Pinned(conn));
+                *self.pinned.lock() = true;
+                *self.state.lock() = ContextState::Connected;
+                return Ok(tokio::sync::MutexGuard::map(connection, |conn| {
+                    &mut **conn.as_mut().expect("just set")
+                }));
+            }
+            let pool =
+                ConnectionPoolManager::get_pool_by_alias(&self.pool_alias).ok_or_else(|| {
+                    format!(
+                        "Connection pool for alias '{}' not initialized",
+                        self.pool_alias
+                    )
+                })?;
+            tenant::touch(&self.pool_alias);
+
+            let conn = pool
+                .get()
+                .await
+                .map_err(|e| format!("Failed to acquire connection: {}", e))?;
+            *connection = Some(Lease::Pooled(conn));
+            *self.state.lock() = ContextState::Connected;
         }
+        tokio::sync::MutexGuard::try_map(connection, |conn| conn.as_mut().map(|conn| &mut **conn))
+            .map_err(|_| "No connection available".to_string())
+    }
 
-        let pool = ConnectionPoolManager::get_pool_by_alias(&self.alias)
-            .ok_or_else(|| format!("Connection pool for alias '{}' not initialized", self.alias))?;
+    fn is_pinned(&self) -> bool {
+        *self.pinned.lock()
+    }
 
-        let conn = pool
-            .get()
-            .await
-            .map_err(|e| format!("Failed to acquire connection: {}", e))?;
+    /// `BEGIN`, `COMMIT` and `ROLLBACK` for this session's transactions,
+    /// savepoints named `name` on a pinned connection
+    fn transaction_sql(&self, name: &str) -> [String; 3] {
+        test_mode::transaction_sql(self.is_pinned(), name)
+    }
 
-        self.put_connection(conn);
-        *self.state.lock().unwrap() = ContextState::Connected;
+    /// Refuse transaction control statements on a pinned connection
+    fn check_statement(&self, sql: &str) -> Result<(), String> {
+        if self.is_pinned() {
+            test_mode::check_statement(sql)?;
+        }
         Ok(())
     }
 
+    /// Run `op` as autocommit would: on a pinned connection outside a
+    /// session transaction it gets a savepoint, released on success and
+    /// rolled back to on failure so the outer transaction stays usable
+    pub(super) async fn autocommit<T>(
+        &self,
+        conn: &Object,
+        op: impl Future<Output = Result<T, String>>,
+    ) -> Result<T, String> {
+        if !self.is_pinned() || self.in_transaction() {
+            return op.await;
+        }
+        let [begin, commit, rollback] = test_mode::transaction_sql(true, "hypern_stmt");
+        let fail = |e: tokio_postgres::Error| format!("Savepoint failed: {}", format_db_error(&e));
+        conn.batch_execute(&begin).await.map_err(fail)?;
+        let result = op.await;
+        let end = if result.is_ok() { commit } else { rollback };
+        conn.batch_execute(&end).await.map_err(fail)?;
+        result
+    }
+
     pub async fn begin(&self) -> Result<(), String> {
-        self.ensure_connection().await?;
+        let conn = self.lock_connection().await?;
 
-        let in_tx = *self.in_transaction.lock().unwrap();
-        if in_tx {
+        if *self.in_transaction.lock() {
             return Err("Transaction already active".to_string());
         }
 
-        let conn = self
-            .take_connection()
-            .ok_or_else(|| "No connection available".to_string())?;
-
-        let result = conn.execute("BEGIN", &[]).await;
-        self.put_connection(conn);
-
-        result.map_err(|e| format!("Failed to begin transaction: {}", e))?;
+        let [begin, _, _] = self.transaction_sql("hypern_tx");
+        conn.batch_execute(&begin)
+            .await
+            .map_err(|e| format!("Failed to begin transaction: {}", e))?;
 
-        *self.in_transaction.lock().unwrap() = true;
-        *self.state.lock().unwrap() = ContextState::InTransaction;
+        *self.in_transaction.lock() = true;
+        *self.state.lock() = ContextState::InTransaction;
         Ok(())
     }
 
     pub async fn commit(&self) -> Result<(), String> {
-        let in_tx = *self.in_transaction.lock().unwrap();
-        if !in_tx {
-            return Err("No active transaction to commit".to_string());
-        }
-
-        let conn = self
-            .take_connection()
-            .ok_or_else(|| "No connection available".to_string())?;
-
-        let result = conn.execute("COMMIT", &[]).await;
-        self.put_connection(conn);
-
-        result.map_err(|e| format!("Failed to commit transaction: {}", e))?;
-
-        *self.in_transaction.lock().unwrap() = false;
-        *self.state.lock().unwrap() = ContextState::Committed;
-        Ok(())
+        self.end_transaction("commit", ContextState::Committed)
+            .await
     }
 
     pub async fn rollback(&self) -> Result<(), String> {
-        let in_tx = *self.in_transaction.lock().unwrap();
-        if !in_tx {
-            return Err("No active transaction to rollback".to_string());
-        }
+        self.end_transaction("rollback", ContextState::RolledBack)
+            .await
+    }
 
-        let conn = self
-            .take_connection()
+    /// Run `COMMIT` or `ROLLBACK` (`action`), or their savepoint forms in
+    /// test mode, and record the resulting state
+    async fn end_transaction(&self, action: &str, state: ContextState) -> Result<(), String> {
+        let connection = self.connection.lock().await;
+        if !*self.in_transaction.lock() {
+            return Err(format!("No active transaction to {}", action));
+        }
+        let conn = connection
+            .as_ref()
             .ok_or_else(|| "No connection available".to_string())?;
 
-        let result = conn.execute("ROLLBACK", &[]).await;
-        self.put_connection(conn);
-
-        result.map_err(|e| format!("Failed to rollback transaction: {}", e))?;
+        let [_, commit, rollback] = self.transaction_sql("hypern_tx");
+        let sql = if state == ContextState::Committed {
+            commit
+        } else {
+            rollback
+        };
+        conn.batch_execute(&sql)
+            .await
+            .map_err(|e| format!("Failed to {} transaction: {}", action, e))?;
 
-        *self.in_transaction.lock().unwrap() = false;
-        *self.state.lock().unwrap() = ContextState::RolledBack;
+        *self.in_transaction.lock() = false;
+        *self.state.lock() = state;
         Ok(())
     }
 
     pub async fn query(&self, sql: &str, params: &[DynParam]) -> Result<Vec<Row>, String> {
-        self.ensure_connection().await?;
-
         let param_refs: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = params
             .iter()
             .map(|p| p as &(dyn tokio_postgres::types::ToSql + Sync))
             .collect();
 
-        let conn = self
-            .take_connection()
-            .ok_or_else(|| "No connection available".to_string())?;
+        let conn = self.lock_connection().await?;
+        self.check_statement(sql)?;
+        let op = self.within_deadline(&conn, "Query failed", conn.query(sql, &param_refs));
+        self.autocommit(&conn, op).await
+    }
 
-        let result = conn.query(sql, &param_refs).await;
-        self.put_connection(conn);
+    pub async fn execute(&self, sql: &str, params: &[DynParam]) -> Result<u64, String> {
+        let param_refs: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = params
+            .iter()
+            .map(|p| p as &(dyn tokio_postgres::types::ToSql + Sync))
+            .collect();
 
-        result.map_err(|e| format!("Query failed: {}", format_db_error(&e)))
+        let conn = self.lock_connection().await?;
+        self.check_statement(sql)?;
+        let op = self.within_deadline(&conn, "Execute failed", conn.execute(sql, &param_refs));
+        self.autocommit(&conn, op).await
     }
 
-    pub async fn execute(&self, sql: &str, params: &[DynParam]) -> Result<u64, String> {
-        self.ensure_connection().await?;
+    /// Run `sql` through a server-side cursor, fetching `chunk_size` rows
+    /// whenever `out` has room. Outside a transaction the cursor gets one of
+    /// its own, committed once the stream ends. The connection is held until
+    /// then, or until the stream is closed or dropped.
+    pub async fn stream(
+        &self,
+        sql: &str,
+        params: &[DynParam],
+        chunk_size: usize,
+        out: ChunkSender,
+    ) {
+        let result = self.run_stream(sql, params, chunk_size, &out).await;
+        if let Err(message) = result {
+            if self.in_transaction() {
+                self.set_error();
+            }
+            out.fail(message).await;
+        }
+    }
+
+    async fn run_stream(
+        &self,
+        sql: &str,
+        params: &[DynParam],
+        chunk_size: usize,
+        out: &ChunkSender,
+    ) -> Result<(), String> {
+        let fail = |e: tokio_postgres::Error| format!("Query failed: {}", format_db_error(&e));
+        let conn = self.lock_connection().await?;
+        let own_transaction = !self.in_transaction();
+        let [begin, commit, rollback] = self.transaction_sql("hypern_stream");
+        if own_transaction {
+            conn.batch_execute(&begin).await.map_err(fail)?;
+        }
+        let cursor = format!(
+            "hypern_stream_{}",
+            STREAM_CURSORS.fetch_add(1, Ordering::Relaxed)
+        );
+        let result = self
+            .fetch_cursor(&conn, &cursor, sql, params, chunk_size, out)
+            .await;
+        let end = match (&result, own_transaction) {
+            (Ok(()), true) => format!("CLOSE {}; {}", cursor, commit),
+            (Err(_), true) => rollback,
+            (Ok(()), false) => format!("CLOSE {}", cursor),
+            // The error aborted the caller's transaction, cursor included
+            (Err(_), false) => return result,
+        };
+        conn.batch_execute(&end).await.map_err(fail)?;
+        result
+    }
 
+    async fn fetch_cursor(
+        &self,
+        conn: &Object,
+        cursor: &str,
+        sql: &str,
+        params: &[DynParam],
+        chunk_size: usize,
+        out: &ChunkSender,
+    ) -> Result<(), String> {
         let param_refs: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = params
             .iter()
             .map(|p| p as &(dyn tokio_postgres::types::ToSql + Sync))
             .collect();
+        let declare = format!("DECLARE {} NO SCROLL CURSOR FOR {}", cursor, sql);
+        self.within_deadline(conn, "Query failed", conn.execute(&declare, &param_refs))
+            .await?;
+        let fetch = format!("FETCH FORWARD {} FROM {}", chunk_size, cursor);
+        let fetch = self
+            .within_deadline(conn, "Query failed", conn.prepare(&fetch))
+            .await?;
+        while let Some(permit) = out.reserve().await {
+            let rows = self
+                .within_deadline(conn, "Query failed", conn.query(&fetch, &[]))
+                .await?;
+            let last = rows.len() < chunk_size;
+            if !rows.is_empty() {
+                permit.send(RowChunk::Postgres(rows));
+            }
+            if last {
+                break;
+            }
+        }
+        Ok(())
+    }
 
-        let conn = self
-            .take_connection()
-            .ok_or_else(|| "No connection available".to_string())?;
-
-        let result = conn.execute(sql, &param_refs).await;
-        self.put_connection(conn);
+    /// Await `op`, cancelling it server-side if the request's deadline passes
+    /// first. The cancelled statement is awaited too, so the connection goes
+    /// back to the pool idle rather than mid-query.
+    async fn within_deadline<T>(
+        &self,
+        conn: &Object,
+        label: &str,
+        op: impl Future<Output = Result<T, tokio_postgres::Error>>,
+    ) -> Result<T, String> {
+        let Some(deadline) = self.deadline() else {
+            return op
+                .await
+                .map_err(|e| format!("{}: {}", label, format_db_error(&e)));
+        };
+        if deadline.expired() {
+            return Err(format!("{}: {}", label, deadline::EXCEEDED));
+        }
 
-        result.map_err(|e| format!("Execute failed: {}", format_db_error(&e)))
+        tokio::pin!(op);
+        tokio::select! {
+            result = &mut op => result.map_err(|e| format!("{}: {}", label, format_db_error(&e))),
+            _ = tokio::time::sleep(deadline.remaining()) => {
+                if let Err(e) = conn.cancel_token().cancel_query(NoTls).await {
+                    crate::hlog_warn!("Failed to cancel query past its deadline: {}", e);
+                }
+                let _ = op.await;
+                // A cancelled statement aborts any open transaction
+                self.set_error();
+                Err(format!("{}: {}", label, deadline::EXCEEDED))
+            }
+        }
     }
 
     pub async fn finalize(&self) -> Result<(), String> {
-        let in_tx = *self.in_transaction.lock().unwrap();
-        let has_error = *self.has_error.lock().unwrap();
-        let auto_commit = *self.auto_commit.lock().unwrap();
+        let in_tx = *self.in_transaction.lock();
+        let has_error = *self.has_error.lock();
+        let auto_commit = *self.auto_commit.lock();
 
         if in_tx {
             if has_error {
@@ -253,18 +442,20 @@ impl DatabaseContextInner {
             }
         }
 
-        let conn = self.connection.lock().unwrap().take();
+        // Waits for operations still queued on the connection
+        let conn = self.connection.lock().await.take();
 
         // Spawn a task to drop the connection, so it doesn't block
-        // This allows deadpool to recycle properly
-        if let Some(conn) = conn {
+        // This allows deadpool to recycle properly. A pinned connection is
+        // released at once, ready for the next session in test mode.
+        if let Some(Lease::Pooled(conn)) = conn {
             tokio::spawn(async move {
                 // Connection drops here when the task completes
                 drop(conn);
             });
         }
 
-        *self.state.lock().unwrap() = ContextState::Closed;
+        *self.state.lock() = ContextState::Closed;
 
         // Remove from global context map
         let contexts = get_contexts();
@@ -285,6 +476,117 @@ impl DatabaseContextInner {
     }
 }
 
+/// Cursor names of streams opened in this process
+static STREAM_CURSORS: AtomicU64 = AtomicU64::new(0);
+
+/// `TimeoutError` for work cut short by the request deadline, else `RuntimeError`
+pub(super) fn session_error(message: String) -> PyErr {
+    if message.ends_with(deadline::EXCEEDED) {
+        PyTimeoutError::new_err(message)
+    } else {
+        PyRuntimeError::new_err(message)
+    }
+}
+
+/// Result of a session operation, converted once the awaiting side has the GIL
+pub(super) type Ready = Box<dyn FnOnce(Python<'_>) -> PyResult<Py<PyAny>> + Send>;
+
+#[derive(Default)]
+struct Pending {
+    ready: Option<Ready>,
+    /// asyncio future (and its loop) a task is parked on until `ready` is set
+    waker: Option<(Py<PyAny>, Py<PyAny>)>,
+    finished: bool,
+}
+
+/// Awaitable for a session operation running on the shared runtime.
+///
+/// Under asyncio the awaiting task parks on a future woken when the
+/// operation completes; without a running loop (handlers stepped by the
+/// server) each step just polls.
+#[pyclass]
+pub struct DbFuture {
+    pending: Arc<Mutex<Pending>>,
+}
+
+impl DbFuture {
+    pub(super) fn spawn<F>(operation: F) -> Self
+    where
+        F: Future<Output = Ready> + Send + 'static,
+    {
+        let pending = Arc::new(Mutex::new(Pending::default()));
+        let target = pending.clone();
+        get_db_runtime().spawn(async move {
+            let ready = operation.await;
+            let waker = {
+                let mut target = target.lock();
+                target.ready = Some(ready);
+                target.waker.take()
+            };
+            if let Some((event_loop, future)) = waker {
+                Python::attach(|py| {
+                    event_loop
+                        .bind(py)
+                        .call_method1("call_soon_threadsafe", (WakeTask(future),))
+                        .map(|_| ())
+                })
+                .unwrap_or_else(|err| {
+                    crate::hlog_debug!("Failed to wake task awaiting a query: {}", err);
+                });
+            }
+        });
+        Self { pending }
+    }
+}
+
+#[pymethods]
+impl DbFuture {
+    fn __await__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let mut pending = self.pending.lock();
+        if let Some(ready) = pending.ready.take() {
+            pending.finished = true;
+            drop(pending);
+            let value = ready(py)?;
+            return Err(PyStopIteration::new_err((value,)));
+        }
+        if pending.finished {
+            return Err(PyRuntimeError::new_err("DbFuture was already awaited"));
+        }
+
+        let event_loop = get_asyncio(py).bind(py).call_method0("_get_running_loop")?;
+        if event_loop.is_none() {
+            return Ok(py.None());
+        }
+        let future = event_loop.call_method0("create_future")?;
+        future.setattr("_asyncio_future_blocking", true)?;
+        pending.waker = Some((event_loop.unbind(), future.clone().unbind()));
+        Ok(future.unbind())
+    }
+}
+
+/// Loop callback resuming the task parked on a `DbFuture`
+#[pyclass]
+struct WakeTask(Py<PyAny>);
+
+#[pymethods]
+impl WakeTask {
+    fn __call__(&self, py: Python<'_>) -> PyResult<()> {
+        let future = self.0.bind(py);
+        if !future.call_method0("done")?.is_truthy()? {
+            future.call_method1("set_result", (py.None(),))?;
+        }
+        Ok(())
+    }
+}
+
 #[pyclass(from_py_object)]
 #[derive(Clone)]
 pub struct DbSession {
@@ -299,6 +601,27 @@ impl DbSession {
     pub fn context(&self) -> &Arc<DatabaseContextInner> {
         &self.context
     }
+
+    /// Resolve SQL and parameters: a dict binds `:name` placeholders, a
+    /// sequence binds `$n` placeholders positionally.
+    fn prepare(
+        py: Python<'_>,
+        sql: &str,
+        params: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<(String, Vec<DynParam>)> {
+        let Some(params) = params.filter(|p| !p.is_none()) else {
+            return Ok((sql.to_string(), Vec::new()));
+        };
+        if let Ok(named) = params.cast::<PyDict>() {
+            let query = NamedQuery::cached(sql);
+            let values = query.bind(named)?;
+            let converted = RowConverter::convert_params_from_py(py, &values)?;
+            return Ok((query.sql.clone(), converted));
+        }
+        let values: Vec<Py<PyAny>> = params.extract()?;
+        let converted = RowConverter::convert_params_from_py(py, &values)?;
+        Ok((sql.to_string(), converted))
+    }
 }
 
 #[pymethods]
@@ -308,47 +631,59 @@ impl DbSession {
         self.context.request_id().to_string()
     }
 
-    fn begin(&self) -> PyResult<()> {
+    #[getter]
+    fn alias(&self) -> String {
+        self.context.alias().to_string()
+    }
+
+    /// Physical pool alias chosen by the alias resolver
+    #[getter]
+    fn pool_alias(&self) -> String {
+        self.context.pool_alias().to_string()
+    }
+
+    fn begin(&self, py: Python<'_>) -> PyResult<()> {
         let ctx = self.context.clone();
-        get_db_runtime()
-            .block_on(async move { ctx.begin().await })
-            .map_err(|e| PyRuntimeError::new_err(e))
+        py.detach(|| get_db_runtime().block_on(async move { ctx.begin().await }))
+            .map_err(session_error)
     }
 
-    fn commit(&self) -> PyResult<()> {
+    fn commit(&self, py: Python<'_>) -> PyResult<()> {
         let ctx = self.context.clone();
-        get_db_runtime()
-            .block_on(async move { ctx.commit().await })
-            .map_err(|e| PyRuntimeError::new_err(e))
+        py.detach(|| get_db_runtime().block_on(async move { ctx.commit().await }))
+            .map_err(session_error)
     }
 
-    fn rollback(&self) -> PyResult<()> {
+    fn rollback(&self, py: Python<'_>) -> PyResult<()> {
         let ctx = self.context.clone();
-        get_db_runtime()
-            .block_on(async move { ctx.rollback().await })
-            .map_err(|e| PyRuntimeError::new_err(e))
+        py.detach(|| get_db_runtime().block_on(async move { ctx.rollback().await }))
+            .map_err(session_error)
     }
 
-    #[pyo3(signature = (sql, params=None))]
+    /// Run a query and return each row as a dict.
+    ///
+    /// Rows are converted in batches of `gil_batch_size`, releasing the GIL
+    /// and handling signals between batches; 0 converts them in one go.
+    #[pyo3(signature = (sql, params=None, gil_batch_size=DEFAULT_GIL_BATCH_SIZE))]
     fn query(
         &self,
         py: Python<'_>,
         sql: &str,
-        params: Option<Vec<Py<PyAny>>>,
+        params: Option<&Bound<'_, PyAny>>,
+        gil_batch_size: usize,
     ) -> PyResult<Vec<Py<PyAny>>> {
         let ctx = self.context.clone();
-        let sql = sql.to_string();
-        let params = params.unwrap_or_default();
-
-        let converted_params = RowConverter::convert_params_from_py(py, &params)?;
-
-        let rows = get_db_runtime()
-            .block_on(async move { ctx.query(&sql, &converted_params).await })
-            .map_err(|e| PyRuntimeError::new_err(e))?;
-
-        rows.iter()
-            .map(|row| RowConverter::row_to_py_dict(py, row))
-            .collect()
+        let (sql, converted_params) = Self::prepare(py, sql, params)?;
+
+        gil::detach_then(
+            py,
+            Site::RowConversion,
+            || get_db_runtime().block_on(async move { ctx.query(&sql, &converted_params).await }),
+            |rows| {
+                let rows = rows.map_err(session_error)?;
+                RowConverter::rows_to_py_dicts(py, &rows, gil_batch_size)
+            },
+        )
     }
 
     #[pyo3(signature = (sql, params=None))]
@@ -356,37 +691,166 @@ impl DbSession {
         &self,
         py: Python<'_>,
         sql: &str,
-        params: Option<Vec<Py<PyAny>>>,
+        params: Option<&Bound<'_, PyAny>>,
     ) -> PyResult<Py<PyAny>> {
         let ctx = self.context.clone();
-        let sql = sql.to_string();
-        let params = params.unwrap_or_default();
+        let (sql, converted_params) = Self::prepare(py, sql, params)?;
+
+        gil::detach_then(
+            py,
+            Site::RowConversion,
+            || get_db_runtime().block_on(async move { ctx.query(&sql, &converted_params).await }),
+            |rows| {
+                let rows = rows.map_err(session_error)?;
+                let row = rows
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| PyRuntimeError::new_err("No rows returned"))?;
+                RowConverter::row_to_py_dict(py, &row)
+            },
+        )
+    }
+
+    /// Run a query and construct `cls` from each row.
+    ///
+    /// Columns are passed as keyword arguments; the column-to-parameter
+    /// mapping is worked out once per query from `cls`'s signature.
+    ///
+    /// Args:
+    ///     sql: SQL query with $1, $2, etc. or :name placeholders
+    ///     params: Parameter values, a dict for :name placeholders, or None
+    ///     cls: Dataclass or any class taking the columns as keywords
+    ///     strict: Raise TypeError for columns without a matching
+    ///         parameter; when False they are left out
+    ///     gil_batch_size: Rows converted between GIL releases, as for
+    ///         `query()`
+    #[pyo3(signature = (sql, params, cls, strict=true, gil_batch_size=DEFAULT_GIL_BATCH_SIZE))]
+    fn query_as(
+        &self,
+        py: Python<'_>,
+        sql: &str,
+        params: Option<&Bound<'_, PyAny>>,
+        cls: &Bound<'_, PyAny>,
+        strict: bool,
+        gil_batch_size: usize,
+    ) -> PyResult<Vec<Py<PyAny>>> {
+        let ctx = self.context.clone();
+        let (sql, converted_params) = Self::prepare(py, sql, params)?;
+
+        gil::detach_then(
+            py,
+            Site::RowConversion,
+            || get_db_runtime().block_on(async move { ctx.query(&sql, &converted_params).await }),
+            |rows| {
+                let rows = rows.map_err(session_error)?;
+                RowMapping::build_all(cls, &rows, strict, gil_batch_size)
+            },
+        )
+    }
 
-        let converted_params = RowConverter::convert_params_from_py(py, &params)?;
+    /// Run a query and return each row as a tuple in column order
+    #[pyo3(signature = (sql, params=None, gil_batch_size=DEFAULT_GIL_BATCH_SIZE))]
+    fn query_tuples(
+        &self,
+        py: Python<'_>,
+        sql: &str,
+        params: Option<&Bound<'_, PyAny>>,
+        gil_batch_size: usize,
+    ) -> PyResult<Vec<Py<PyAny>>> {
+        let ctx = self.context.clone();
+        let (sql, converted_params) = Self::prepare(py, sql, params)?;
+
+        gil::detach_then(
+            py,
+            Site::RowConversion,
+            || get_db_runtime().block_on(async move { ctx.query(&sql, &converted_params).await }),
+            |rows| {
+                let rows = rows.map_err(session_error)?;
+                RowConverter::rows_to_py_tuples(py, &rows, gil_batch_size)
+            },
+        )
+    }
 
-        let rows = get_db_runtime()
-            .block_on(async move { ctx.query(&sql, &converted_params).await })
-            .map_err(|e| PyRuntimeError::new_err(e))?;
+    #[pyo3(signature = (sql, params=None))]
+    fn execute(
+        &self,
+        py: Python<'_>,
+        sql: &str,
+        params: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<u64> {
+        let ctx = self.context.clone();
+        let (sql, converted_params) = Self::prepare(py, sql, params)?;
 
-        let row = rows
-            .into_iter()
-            .next()
-            .ok_or_else(|| PyRuntimeError::new_err("No rows returned"))?;
+        py.detach(|| {
+            get_db_runtime().block_on(async move { ctx.execute(&sql, &converted_params).await })
+        })
+        .map_err(session_error)
+    }
 
-        RowConverter::row_to_py_dict(py, &row)
+    /// Awaitable form of `query()`.
+    ///
+    /// Concurrent calls on one session share its connection and run one at
+    /// a time, in the order they were made.
+    #[pyo3(signature = (sql, params=None, gil_batch_size=DEFAULT_GIL_BATCH_SIZE))]
+    fn query_async(
+        &self,
+        py: Python<'_>,
+        sql: &str,
+        params: Option<&Bound<'_, PyAny>>,
+        gil_batch_size: usize,
+    ) -> PyResult<DbFuture> {
+        let ctx = self.context.clone();
+        let (sql, converted_params) = Self::prepare(py, sql, params)?;
+        Ok(DbFuture::spawn(async move {
+            let result = ctx.query(&sql, &converted_params).await;
+            Box::new(move |py: Python<'_>| {
+                let rows = result.map_err(session_error)?;
+                let dicts = RowConverter::rows_to_py_dicts(py, &rows, gil_batch_size)?;
+                Ok(dicts.into_pyobject(py)?.into_any().unbind())
+            }) as Ready
+        }))
     }
 
+    /// Awaitable form of `query_one()`
     #[pyo3(signature = (sql, params=None))]
-    fn execute(&self, py: Python<'_>, sql: &str, params: Option<Vec<Py<PyAny>>>) -> PyResult<u64> {
+    fn query_one_async(
+        &self,
+        py: Python<'_>,
+        sql: &str,
+        params: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<DbFuture> {
         let ctx = self.context.clone();
-        let sql = sql.to_string();
-        let params = params.unwrap_or_default();
-
-        let converted_params = RowConverter::convert_params_from_py(py, &params)?;
+        let (sql, converted_params) = Self::prepare(py, sql, params)?;
+        Ok(DbFuture::spawn(async move {
+            let result = ctx.query(&sql, &converted_params).await;
+            Box::new(move |py: Python<'_>| {
+                let row = result
+                    .map_err(session_error)?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| PyRuntimeError::new_err("No rows returned"))?;
+                RowConverter::row_to_py_dict(py, &row)
+            }) as Ready
+        }))
+    }
 
-        get_db_runtime()
-            .block_on(async move { ctx.execute(&sql, &converted_params).await })
-            .map_err(|e| PyRuntimeError::new_err(e))
+    /// Awaitable form of `execute()`
+    #[pyo3(signature = (sql, params=None))]
+    fn execute_async(
+        &self,
+        py: Python<'_>,
+        sql: &str,
+        params: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<DbFuture> {
+        let ctx = self.context.clone();
+        let (sql, converted_params) = Self::prepare(py, sql, params)?;
+        Ok(DbFuture::spawn(async move {
+            let result = ctx.execute(&sql, &converted_params).await;
+            Box::new(move |py: Python<'_>| {
+                let affected = result.map_err(session_error)?;
+                Ok(affected.into_pyobject(py)?.into_any().unbind())
+            }) as Ready
+        }))
     }
 
     #[pyo3(signature = (sql, params_list))]
@@ -394,20 +858,22 @@ impl DbSession {
         &self,
         py: Python<'_>,
         sql: &str,
-        params_list: Vec<Vec<Py<PyAny>>>,
+        params_list: Vec<Bound<'_, PyAny>>,
     ) -> PyResult<u64> {
         let ctx = self.context.clone();
-        let sql = sql.to_string();
 
         let mut total_affected = 0u64;
         for params in params_list {
-            let converted_params = RowConverter::convert_params_from_py(py, &params)?;
-            let sql_clone = sql.clone();
+            let (sql_clone, converted_params) = Self::prepare(py, sql, Some(&params))?;
             let ctx_clone = ctx.clone();
 
-            let affected = get_db_runtime()
-                .block_on(async move { ctx_clone.execute(&sql_clone, &converted_params).await })
-                .map_err(|e| PyRuntimeError::new_err(e))?;
+            let affected = py
+                .detach(|| {
+                    get_db_runtime().block_on(async move {
+                        ctx_clone.execute(&sql_clone, &converted_params).await
+                    })
+                })
+                .map_err(session_error)?;
 
             total_affected += affected;
         }
@@ -415,6 +881,105 @@ impl DbSession {
         Ok(total_affected)
     }
 
+    /// Iterate a query's rows in chunks without loading them all.
+    ///
+    /// Rows come from a server-side cursor, `chunk_size` at a time, fetched
+    /// by a background task at most `prefetch` chunks ahead of the caller.
+    /// Iterate with `for` or `async for`; each chunk is a list of dicts. The
+    /// session's connection is busy until the stream is exhausted, closed
+    /// or dropped. Outside a transaction the cursor runs in one of its own.
+    ///
+    /// Args:
+    ///     sql: Query to stream
+    ///     params: Sequence for `$n` placeholders or dict for `:name` ones
+    ///     chunk_size: Rows per chunk
+    ///     prefetch: Chunks fetched ahead of the caller
+    #[pyo3(signature = (sql, params=None, chunk_size=DEFAULT_STREAM_CHUNK_SIZE, prefetch=DEFAULT_PREFETCH))]
+    fn stream(
+        &self,
+        py: Python<'_>,
+        sql: &str,
+        params: Option<&Bound<'_, PyAny>>,
+        chunk_size: usize,
+        prefetch: usize,
+    ) -> PyResult<RowStream> {
+        if chunk_size == 0 || prefetch == 0 {
+            return Err(PyValueError::new_err(
+                "chunk_size and prefetch must be positive",
+            ));
+        }
+        let ctx = self.context.clone();
+        let (sql, converted_params) = Self::prepare(py, sql, params)?;
+        let (sender, stream) = RowStream::channel(prefetch);
+        get_db_runtime().spawn(async move {
+            ctx.stream(&sql, &converted_params, chunk_size, sender)
+                .await;
+        });
+        Ok(stream)
+    }
+
+    /// Bulk-load rows into a table with `COPY ... FROM STDIN`.
+    ///
+    /// `rows` may be any iterable of sequences, including a generator; it is
+    /// read `chunk_size` rows at a time, so the data is never held in full.
+    /// Runs in the session's transaction if one is open. On any error the
+    /// copy is aborted, the connection stays usable and an open transaction
+    /// is marked failed.
+    ///
+    /// Args:
+    ///     table: Table name, optionally schema-qualified
+    ///     columns: Target columns, in the order of each row's values
+    ///     rows: Iterable of row sequences; `None` is NULL
+    ///     format: "binary" (values encoded by column type) or "text"
+    ///     chunk_size: Rows converted per chunk sent to the server
+    ///
+    /// Returns:
+    ///     Number of rows copied
+    #[pyo3(signature = (table, columns, rows, format="binary", chunk_size=DEFAULT_COPY_CHUNK_SIZE))]
+    fn copy_in(
+        &self,
+        py: Python<'_>,
+        table: &str,
+        columns: Vec<String>,
+        rows: &Bound<'_, PyAny>,
+        format: &str,
+        chunk_size: usize,
+    ) -> PyResult<u64> {
+        let target = CopyTarget::new(table, &columns, CopyFormat::parse(format)?)?;
+        copy::copy_in(
+            py,
+            self.context.clone(),
+            target,
+            rows,
+            columns.len(),
+            chunk_size,
+        )
+    }
+
+    /// Export a query's results with `COPY (query) TO STDOUT`.
+    ///
+    /// Rows are fetched in batches while iterating. The session's connection
+    /// is busy until the iterator is exhausted or closed, so finish with it
+    /// before running other statements on the session.
+    ///
+    /// Args:
+    ///     query: SELECT to export (COPY does not take parameters)
+    ///     csv: Yield CSV lines as `str` instead of row tuples
+    ///     header: Start CSV output with a header line
+    ///     batch_size: Rows fetched per batch
+    #[pyo3(signature = (query, csv=false, header=false, batch_size=1000))]
+    fn copy_out(
+        &self,
+        py: Python<'_>,
+        query: &str,
+        csv: bool,
+        header: bool,
+        batch_size: usize,
+    ) -> CopyOutRows {
+        let csv = csv.then_some(header);
+        copy::copy_out(py, self.context.clone(), query.to_string(), csv, batch_size)
+    }
+
     fn set_auto_commit(&self, auto_commit: bool) -> PyResult<()> {
         self.context.set_auto_commit(auto_commit);
         Ok(())
@@ -431,6 +996,14 @@ impl DbSession {
     }
 
     fn __repr__(&self) -> String {
+        if self.context.pool_alias() != self.context.alias() {
+            return format!(
+                "DbSession(request_id='{}', alias='{}', pool_alias='{}')",
+                self.context.request_id(),
+                self.context.alias(),
+                self.context.pool_alias()
+            );
+        }
         format!(
             "DbSession(request_id='{}', alias='{}')",
             self.context.request_id(),
@@ -440,9 +1013,18 @@ impl DbSession {
 }
 
 pub fn create_request_context(request_id: &str, alias: &str) -> Arc<DatabaseContextInner> {
-    let ctx = Arc::new(DatabaseContextInner::new(
+    create_request_context_for_pool(request_id, alias, alias)
+}
+
+pub fn create_request_context_for_pool(
+    request_id: &str,
+    alias: &str,
+    pool_alias: &str,
+) -> Arc<DatabaseContextInner> {
+    let ctx = Arc::new(DatabaseContextInner::with_pool_alias(
         request_id.to_string(),
         alias.to_string(),
+        pool_alias.to_string(),
     ));
 
     let contexts = get_contexts();
@@ -458,6 +1040,24 @@ pub fn get_request_context(request_id: &str, alias: &str) -> Option<Arc<Database
     get_contexts().get(request_id)?.get(alias).cloned()
 }
 
+/// Sessions still registered under `request_id`
+pub fn open_sessions(request_id: &str) -> usize {
+    get_contexts()
+        .get(request_id)
+        .map_or(0, |sessions| sessions.len())
+}
+
+/// Whether any open session, or database test mode, is using the pool
+/// `pool_alias`
+pub fn pool_in_use(pool_alias: &str) -> bool {
+    test_mode::is_active(pool_alias)
+        || get_contexts().iter().any(|request| {
+            request
+                .values()
+                .any(|ctx| ctx.pool_alias() == pool_alias && ctx.state() != ContextState::Closed)
+        })
+}
+
 pub async fn finalize_request_context(request_id: &str, alias: &str) -> Result<(), String> {
     // Clone the context out of the DashMap first
     let ctx_opt = {
@@ -484,11 +1084,33 @@ pub async fn finalize_all_request_contexts(request_id: &str) -> Result<(), Strin
     Ok(())
 }
 
+/// Session for `alias` in this request, created on first use.
+///
+/// A session opened by a handler whose route runs under a timeout cancels
+/// queries still running when that timeout expires.
+///
+/// With an alias resolver installed, the first call per request and alias
+/// resolves the physical pool (passing `context`, or the current request
+/// context when omitted); later calls reuse that session without calling
+/// the resolver again.
 #[pyfunction]
-pub fn get_db(request_id: &str, alias: &str) -> PyResult<DbSession> {
-    let ctx = get_request_context(request_id, alias)
-        .unwrap_or_else(|| create_request_context(request_id, alias));
+#[pyo3(signature = (request_id, alias="default", context=None))]
+pub fn get_db(
+    py: Python<'_>,
+    request_id: &str,
+    alias: &str,
+    context: Option<&Bound<'_, PyDict>>,
+) -> PyResult<DbSession> {
+    if let Some(ctx) = get_request_context(request_id, alias) {
+        return Ok(DbSession::new(ctx));
+    }
+
+    let pool_alias = tenant::resolve_alias(py, request_id, alias, context)?;
+    tenant::ensure_pool(py, &pool_alias)?;
 
+    let ctx = create_request_context_for_pool(request_id, alias, &pool_alias);
+    // Opened from a handler running under a timeout: queries share its budget
+    ctx.set_deadline(deadline::current(py));
     Ok(DbSession::new(ctx))
 }
 
@@ -498,7 +1120,7 @@ pub fn finalize_db(request_id: &str, alias: &str) -> PyResult<()> {
     let alias = alias.to_string();
     get_db_runtime()
         .block_on(async move { finalize_request_context(&request_id, &alias).await })
-        .map_err(|e| PyRuntimeError::new_err(e))
+        .map_err(session_error)
 }
 
 #[pyfunction]
@@ -506,5 +1128,5 @@ pub fn finalize_db_all(request_id: &str) -> PyResult<()> {
     let request_id = request_id.to_string();
     get_db_runtime()
         .block_on(async move { finalize_all_request_contexts(&request_id).await })
-        .map_err(|e| PyRuntimeError::new_err(e))
+        .map_err(session_error)
 }
@@ -1,12 +1,15 @@
 use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use pyo3::exceptions::PyTypeError;
 use pyo3::prelude::*;
 use pyo3::types::{
-    PyBool, PyBytes, PyDate, PyDateAccess, PyDateTime, PyDict, PyList, PyTime, PyTimeAccess,
+    PyBool, PyBytes, PyDate, PyDateAccess, PyDateTime, PyDict, PyList, PyString, PyTime,
+    PyTimeAccess, PyTuple,
 };
 use rust_decimal::Decimal;
 use serde_json::Value as JsonValue;
-use tokio_postgres::types::{ToSql, Type};
-use tokio_postgres::Row;
+use tokio_postgres::binary_copy::BinaryCopyOutRow;
+use tokio_postgres::types::{FromSql, ToSql, Type};
+use tokio_postgres::{Column, Row};
 
 /// Wrapper for dynamic PostgreSQL parameters that implements ToSql
 #[derive(Debug)]
@@ -121,141 +124,297 @@ impl ToSql for DynParam {
     postgres_types::to_sql_checked!();
 }
 
-/// Utility struct for row conversion operations
-pub struct RowConverter;
+/// Rows converted between GIL releases unless a query sets `gil_batch_size`
+pub const DEFAULT_GIL_BATCH_SIZE: usize = 1000;
 
-impl RowConverter {
-    /// Convert a PostgreSQL row to a Python dictionary
-    pub fn row_to_py_dict(py: Python<'_>, row: &Row) -> PyResult<Py<PyAny>> {
-        let dict = PyDict::new(py);
+/// How a column becomes a Python value, picked once per result set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Converter {
+    Bool,
+    Int2,
+    Int4,
+    Int8,
+    Float4,
+    Float8,
+    Text,
+    Bytea,
+    Date,
+    Time,
+    Timestamp,
+    Json,
+    Numeric,
+    /// Unknown types, read as strings
+    Other,
+}
 
-        for (i, column) in row.columns().iter().enumerate() {
-            let name = column.name();
-            let ty = column.type_();
+impl Converter {
+    fn for_type(ty: &Type) -> Self {
+        match *ty {
+            Type::BOOL => Self::Bool,
+            Type::INT2 => Self::Int2,
+            Type::INT4 => Self::Int4,
+            Type::INT8 => Self::Int8,
+            Type::FLOAT4 => Self::Float4,
+            Type::FLOAT8 => Self::Float8,
+            Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME => Self::Text,
+            Type::BYTEA => Self::Bytea,
+            Type::DATE => Self::Date,
+            Type::TIME => Self::Time,
+            Type::TIMESTAMP | Type::TIMESTAMPTZ => Self::Timestamp,
+            Type::JSON | Type::JSONB => Self::Json,
+            Type::NUMERIC => Self::Numeric,
+            _ => Self::Other,
+        }
+    }
+}
 
-            let value: Py<PyAny> = match *ty {
-                Type::BOOL => match row.try_get::<_, Option<bool>>(i) {
-                    Ok(Some(v)) => PyBool::new(py, v).to_owned().into_any().unbind(),
-                    Ok(None) => py.None(),
-                    Err(_) => py.None(),
-                },
-                Type::INT2 => match row.try_get::<_, Option<i16>>(i) {
-                    Ok(Some(v)) => v.into_pyobject(py)?.into_any().unbind(),
-                    Ok(None) => py.None(),
-                    Err(_) => py.None(),
-                },
-                Type::INT4 => match row.try_get::<_, Option<i32>>(i) {
-                    Ok(Some(v)) => v.into_pyobject(py)?.into_any().unbind(),
-                    Ok(None) => py.None(),
-                    Err(_) => py.None(),
-                },
-                Type::INT8 => match row.try_get::<_, Option<i64>>(i) {
-                    Ok(Some(v)) => v.into_pyobject(py)?.into_any().unbind(),
-                    Ok(None) => py.None(),
-                    Err(_) => py.None(),
-                },
-                Type::FLOAT4 => match row.try_get::<_, Option<f32>>(i) {
-                    Ok(Some(v)) => v.into_pyobject(py)?.into_any().unbind(),
-                    Ok(None) => py.None(),
-                    Err(_) => py.None(),
-                },
-                Type::FLOAT8 => match row.try_get::<_, Option<f64>>(i) {
-                    Ok(Some(v)) => v.into_pyobject(py)?.into_any().unbind(),
-                    Ok(None) => py.None(),
-                    Err(_) => py.None(),
-                },
-                Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME => {
-                    match row.try_get::<_, Option<String>>(i) {
-                        Ok(Some(v)) => v.into_pyobject(py)?.into_any().unbind(),
-                        Ok(None) => py.None(),
-                        Err(_) => py.None(),
-                    }
-                }
-                Type::BYTEA => match row.try_get::<_, Option<Vec<u8>>>(i) {
-                    Ok(Some(v)) => PyBytes::new(py, &v).into_any().unbind(),
-                    Ok(None) => py.None(),
-                    Err(_) => py.None(),
-                },
-                Type::DATE => match row.try_get::<_, Option<NaiveDate>>(i) {
-                    Ok(Some(v)) => PyDate::new(py, v.year(), v.month() as u8, v.day() as u8)?
-                        .into_any()
-                        .unbind(),
-                    Ok(None) => py.None(),
-                    Err(_) => py.None(),
-                },
-                Type::TIME => match row.try_get::<_, Option<NaiveTime>>(i) {
-                    Ok(Some(v)) => PyTime::new(
-                        py,
-                        v.hour() as u8,
-                        v.minute() as u8,
-                        v.second() as u8,
-                        v.nanosecond() / 1000,
-                        None,
-                    )?
+/// Rows a [`RowPlan`] reads: query results and binary COPY output
+pub trait SqlRow {
+    fn value_at<'a, T: FromSql<'a>>(&'a self, i: usize) -> Result<T, tokio_postgres::Error>;
+}
+
+impl SqlRow for Row {
+    #[inline]
+    fn value_at<'a, T: FromSql<'a>>(&'a self, i: usize) -> Result<T, tokio_postgres::Error> {
+        self.try_get(i)
+    }
+}
+
+impl SqlRow for BinaryCopyOutRow {
+    #[inline]
+    fn value_at<'a, T: FromSql<'a>>(&'a self, i: usize) -> Result<T, tokio_postgres::Error> {
+        self.try_get(i)
+    }
+}
+
+/// Per-result-set conversion state: each column's converter and its name
+/// as an interned string shared by every row's dict
+pub struct RowPlan<'py> {
+    py: Python<'py>,
+    converters: Vec<Converter>,
+    names: Vec<Bound<'py, PyString>>,
+    /// `decimal.Decimal`, looked up once when a column needs it
+    decimal: Option<Bound<'py, PyAny>>,
+}
+
+impl<'py> RowPlan<'py> {
+    pub fn new(py: Python<'py>, columns: &[Column]) -> PyResult<Self> {
+        Self::from_columns(
+            py,
+            columns.iter().map(|column| (column.name(), column.type_())),
+        )
+    }
+
+    /// Plan for columns given by name and type
+    pub fn from_columns<'c>(
+        py: Python<'py>,
+        columns: impl Iterator<Item = (&'c str, &'c Type)> + Clone,
+    ) -> PyResult<Self> {
+        let converters: Vec<Converter> = columns
+            .clone()
+            .map(|(_, ty)| Converter::for_type(ty))
+            .collect();
+        let decimal = if converters.contains(&Converter::Numeric) {
+            Some(py.import("decimal")?.getattr("Decimal")?)
+        } else {
+            None
+        };
+        Ok(Self {
+            py,
+            converters,
+            names: columns
+                .map(|(name, _)| PyString::intern(py, name))
+                .collect(),
+            decimal,
+        })
+    }
+
+    /// The row as a dict keyed by column name
+    pub fn dict(&self, row: &impl SqlRow) -> PyResult<Py<PyAny>> {
+        let dict = PyDict::new(self.py);
+        for (i, name) in self.names.iter().enumerate() {
+            dict.set_item(name, self.value(row, i)?)?;
+        }
+        Ok(dict.into_any().unbind())
+    }
+
+    /// The row as a tuple in column order
+    pub fn tuple(&self, row: &impl SqlRow) -> PyResult<Py<PyAny>> {
+        let values = (0..self.converters.len())
+            .map(|i| self.value(row, i))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(PyTuple::new(self.py, values)?.into_any().unbind())
+    }
+
+    /// Column `i` of `row` as a Python value
+    pub fn value(&self, row: &impl SqlRow, i: usize) -> PyResult<Py<PyAny>> {
+        let py = self.py;
+        let value: Py<PyAny> = match self.converters[i] {
+            Converter::Bool => match row.value_at::<Option<bool>>(i) {
+                Ok(Some(v)) => PyBool::new(py, v).to_owned().into_any().unbind(),
+                Ok(None) => py.None(),
+                Err(_) => py.None(),
+            },
+            Converter::Int2 => match row.value_at::<Option<i16>>(i) {
+                Ok(Some(v)) => v.into_pyobject(py)?.into_any().unbind(),
+                Ok(None) => py.None(),
+                Err(_) => py.None(),
+            },
+            Converter::Int4 => match row.value_at::<Option<i32>>(i) {
+                Ok(Some(v)) => v.into_pyobject(py)?.into_any().unbind(),
+                Ok(None) => py.None(),
+                Err(_) => py.None(),
+            },
+            Converter::Int8 => match row.value_at::<Option<i64>>(i) {
+                Ok(Some(v)) => v.into_pyobject(py)?.into_any().unbind(),
+                Ok(None) => py.None(),
+                Err(_) => py.None(),
+            },
+            Converter::Float4 => match row.value_at::<Option<f32>>(i) {
+                Ok(Some(v)) => v.into_pyobject(py)?.into_any().unbind(),
+                Ok(None) => py.None(),
+                Err(_) => py.None(),
+            },
+            Converter::Float8 => match row.value_at::<Option<f64>>(i) {
+                Ok(Some(v)) => v.into_pyobject(py)?.into_any().unbind(),
+                Ok(None) => py.None(),
+                Err(_) => py.None(),
+            },
+            Converter::Text | Converter::Other => match row.value_at::<Option<&str>>(i) {
+                Ok(Some(v)) => PyString::new(py, v).into_any().unbind(),
+                Ok(None) => py.None(),
+                Err(_) => py.None(),
+            },
+            Converter::Bytea => match row.value_at::<Option<&[u8]>>(i) {
+                Ok(Some(v)) => PyBytes::new(py, v).into_any().unbind(),
+                Ok(None) => py.None(),
+                Err(_) => py.None(),
+            },
+            Converter::Date => match row.value_at::<Option<NaiveDate>>(i) {
+                Ok(Some(v)) => PyDate::new(py, v.year(), v.month() as u8, v.day() as u8)?
                     .into_any()
                     .unbind(),
+                Ok(None) => py.None(),
+                Err(_) => py.None(),
+            },
+            Converter::Time => match row.value_at::<Option<NaiveTime>>(i) {
+                Ok(Some(v)) => PyTime::new(
+                    py,
+                    v.hour() as u8,
+                    v.minute() as u8,
+                    v.second() as u8,
+                    v.nanosecond() / 1000,
+                    None,
+                )?
+                .into_any()
+                .unbind(),
+                Ok(None) => py.None(),
+                Err(_) => py.None(),
+            },
+            Converter::Timestamp => match row.value_at::<Option<NaiveDateTime>>(i) {
+                Ok(Some(v)) => PyDateTime::new(
+                    py,
+                    v.year(),
+                    v.month() as u8,
+                    v.day() as u8,
+                    v.hour() as u8,
+                    v.minute() as u8,
+                    v.second() as u8,
+                    v.nanosecond() / 1000,
+                    None,
+                )?
+                .into_any()
+                .unbind(),
+                Ok(None) => py.None(),
+                Err(_) => py.None(),
+            },
+            Converter::Json => match row.value_at::<Option<JsonValue>>(i) {
+                Ok(Some(v)) => RowConverter::json_to_py(py, &v)?,
+                Ok(None) => py.None(),
+                Err(_) => py.None(),
+            },
+            Converter::Numeric => {
+                // Python Decimal keeps the precision a float would lose
+                match row.value_at::<Option<Decimal>>(i) {
+                    Ok(Some(v)) => match &self.decimal {
+                        Some(decimal) => decimal.call1((v.to_string(),))?.unbind(),
+                        None => py.None(),
+                    },
                     Ok(None) => py.None(),
-                    Err(_) => py.None(),
-                },
-                Type::TIMESTAMP | Type::TIMESTAMPTZ => {
-                    match row.try_get::<_, Option<NaiveDateTime>>(i) {
-                        Ok(Some(v)) => PyDateTime::new(
-                            py,
-                            v.year(),
-                            v.month() as u8,
-                            v.day() as u8,
-                            v.hour() as u8,
-                            v.minute() as u8,
-                            v.second() as u8,
-                            v.nanosecond() / 1000,
-                            None,
-                        )?
-                        .into_any()
-                        .unbind(),
-                        Ok(None) => py.None(),
-                        Err(_) => py.None(),
-                    }
-                }
-                Type::JSON | Type::JSONB => match row.try_get::<_, Option<JsonValue>>(i) {
-                    Ok(Some(v)) => Self::json_to_py(py, &v)?,
-                    Ok(None) => py.None(),
-                    Err(_) => py.None(),
-                },
-                Type::NUMERIC => {
-                    // Handle NUMERIC/DECIMAL - convert to string first, then to Python Decimal
-                    match row.try_get::<_, Option<Decimal>>(i) {
-                        Ok(Some(v)) => {
-                            // Convert to Python Decimal for precision
-                            let decimal_module = py.import("decimal")?;
-                            let py_decimal =
-                                decimal_module.call_method1("Decimal", (v.to_string(),))?;
-                            py_decimal.into_any().unbind()
-                        }
-                        Ok(None) => py.None(),
-                        Err(_) => {
-                            // Fallback: try as string
-                            match row.try_get::<_, Option<String>>(i) {
-                                Ok(Some(v)) => v.into_pyobject(py)?.into_any().unbind(),
-                                Ok(None) => py.None(),
-                                Err(_) => py.None(),
-                            }
+                    Err(_) => {
+                        // Fallback: try as string
+                        match row.value_at::<Option<&str>>(i) {
+                            Ok(Some(v)) => PyString::new(py, v).into_any().unbind(),
+                            Ok(None) => py.None(),
+                            Err(_) => py.None(),
                         }
                     }
                 }
-                _ => {
-                    // Try to get as string for unknown types
-                    match row.try_get::<_, Option<String>>(i) {
-                        Ok(Some(v)) => v.into_pyobject(py)?.into_any().unbind(),
-                        Ok(None) => py.None(),
-                        Err(_) => py.None(),
-                    }
-                }
-            };
+            }
+        };
+        Ok(value)
+    }
+}
 
-            dict.set_item(name, value)?;
+/// Convert every row with `convert`. Every `batch_size` rows the GIL is
+/// released briefly so other Python threads can run, and pending signals
+/// are handled, so a `KeyboardInterrupt` stops a long conversion. A batch
+/// size of 0 converts all rows in one hold.
+pub fn convert_rows<T>(
+    py: Python<'_>,
+    rows: &[Row],
+    batch_size: usize,
+    mut convert: impl FnMut(&Row) -> PyResult<T>,
+) -> PyResult<Vec<T>> {
+    let mut converted = Vec::with_capacity(rows.len());
+    for (n, row) in rows.iter().enumerate() {
+        if batch_size > 0 && n > 0 && n % batch_size == 0 {
+            py.check_signals()?;
+            py.detach(std::thread::yield_now);
         }
+        converted.push(convert(row)?);
+    }
+    Ok(converted)
+}
 
-        Ok(dict.into_any().unbind())
+/// Utility struct for row conversion operations
+pub struct RowConverter;
+
+impl RowConverter {
+    /// Convert a PostgreSQL row to a Python dictionary
+    pub fn row_to_py_dict(py: Python<'_>, row: &Row) -> PyResult<Py<PyAny>> {
+        RowPlan::new(py, row.columns())?.dict(row)
+    }
+
+    /// Convert a PostgreSQL row to a Python tuple in column order
+    pub fn row_to_py_tuple(py: Python<'_>, row: &Row) -> PyResult<Py<PyAny>> {
+        RowPlan::new(py, row.columns())?.tuple(row)
+    }
+
+    /// Convert every row to a dict, resolving the converters once
+    pub fn rows_to_py_dicts(
+        py: Python<'_>,
+        rows: &[Row],
+        batch_size: usize,
+    ) -> PyResult<Vec<Py<PyAny>>> {
+        let Some(first) = rows.first() else {
+            return Ok(Vec::new());
+        };
+        let plan = RowPlan::new(py, first.columns())?;
+        convert_rows(py, rows, batch_size, |row| plan.dict(row))
+    }
+
+    /// Convert every row to a tuple, resolving the converters once
+    pub fn rows_to_py_tuples(
+        py: Python<'_>,
+        rows: &[Row],
+        batch_size: usize,
+    ) -> PyResult<Vec<Py<PyAny>>> {
+        let Some(first) = rows.first() else {
+            return Ok(Vec::new());
+        };
+        let plan = RowPlan::new(py, first.columns())?;
+        convert_rows(py, rows, batch_size, |row| plan.tuple(row))
     }
 
     /// Convert JSON value to Python object
@@ -361,3 +520,112 @@ impl RowConverter {
         Ok(result)
     }
 }
+
+/// Column-to-field mapping for building one class per row, resolved once per
+/// result set
+pub struct RowMapping<'py> {
+    cls: Bound<'py, PyAny>,
+    plan: RowPlan<'py>,
+    /// Column index and constructor keyword for each column passed on
+    fields: Vec<(usize, Bound<'py, PyString>)>,
+}
+
+impl<'py> RowMapping<'py> {
+    /// Map `columns` onto the keyword parameters of `cls`.
+    ///
+    /// Fails with TypeError when a required parameter has no column, or, if
+    /// `strict`, when a column has no parameter. Classes whose signature
+    /// cannot be read, or that take `**kwargs`, receive every column.
+    pub fn resolve(cls: &Bound<'py, PyAny>, columns: &[Column], strict: bool) -> PyResult<Self> {
+        let py = cls.py();
+        let class_name = cls
+            .getattr("__name__")
+            .and_then(|name| name.extract::<String>())
+            .unwrap_or_else(|_| cls.to_string());
+
+        let mut accepts_any = false;
+        let mut params: Vec<(String, bool)> = Vec::new();
+        let inspect = py.import("inspect")?;
+        match inspect.call_method1("signature", (cls,)) {
+            Ok(signature) => {
+                let parameter = inspect.getattr("Parameter")?;
+                let empty = parameter.getattr("empty")?;
+                let var_keyword = parameter.getattr("VAR_KEYWORD")?;
+                let keyword_kinds = [
+                    parameter.getattr("POSITIONAL_OR_KEYWORD")?,
+                    parameter.getattr("KEYWORD_ONLY")?,
+                ];
+                for param in signature
+                    .getattr("parameters")?
+                    .call_method0("values")?
+                    .try_iter()?
+                {
+                    let param = param?;
+                    let kind = param.getattr("kind")?;
+                    if kind.eq(&var_keyword)? {
+                        accepts_any = true;
+                    } else if keyword_kinds.iter().any(|k| kind.eq(k).unwrap_or(false)) {
+                        let required = param.getattr("default")?.is(&empty);
+                        params.push((param.getattr("name")?.extract()?, required));
+                    }
+                }
+            }
+            // Builtins and some extension types have no signature
+            Err(_) => accepts_any = true,
+        }
+
+        for (name, required) in &params {
+            if *required && !columns.iter().any(|c| c.name() == name) {
+                return Err(PyTypeError::new_err(format!(
+                    "{} field '{}' is required but the result has no column '{}'",
+                    class_name, name, name
+                )));
+            }
+        }
+
+        let mut fields = Vec::with_capacity(columns.len());
+        for (i, column) in columns.iter().enumerate() {
+            let known = params.iter().any(|(name, _)| name == column.name());
+            if known || accepts_any {
+                fields.push((i, PyString::intern(py, column.name())));
+            } else if strict {
+                return Err(PyTypeError::new_err(format!(
+                    "column '{}' has no matching field on {} (pass strict=False to ignore extra columns)",
+                    column.name(),
+                    class_name
+                )));
+            }
+        }
+
+        Ok(Self {
+            cls: cls.clone(),
+            plan: RowPlan::new(py, columns)?,
+            fields,
+        })
+    }
+
+    /// Construct the class from one row
+    pub fn build(&self, row: &Row) -> PyResult<Py<PyAny>> {
+        let py = self.cls.py();
+        let kwargs = PyDict::new(py);
+        for (i, name) in &self.fields {
+            kwargs.set_item(name, self.plan.value(row, *i)?)?;
+        }
+        Ok(self.cls.call((), Some(&kwargs))?.unbind())
+    }
+
+    /// Construct the class from every row, resolving the mapping once;
+    /// `batch_size` as for [`convert_rows`]
+    pub fn build_all(
+        cls: &Bound<'py, PyAny>,
+        rows: &[Row],
+        strict: bool,
+        batch_size: usize,
+    ) -> PyResult<Vec<Py<PyAny>>> {
+        let Some(first) = rows.first() else {
+            return Ok(Vec::new());
+        };
+        let mapping = Self::resolve(cls, first.columns(), strict)?;
+        convert_rows(cls.py(), rows, batch_size, |row| mapping.build(row))
+    }
+}
@@ -0,0 +1,194 @@
+//! Tenant-aware alias resolution for request-scoped sessions.
+//!
+//! An optional Python resolver maps the logical alias a handler asks for
+//! (`"default"`) to a physical pool alias (`"tenant_42"`). Physical pools that
+//! do not exist yet are created on first use from a Python pool factory;
+//! those lazily created pools are capped, and the least recently used idle
+//! one is closed to make room for a new tenant.
+
+use deadpool_postgres::Pool;
+use parking_lot::Mutex;
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use super::pool::{ConnectionPoolManager, PoolConfig};
+
+/// Default cap on pools created by the pool factory
+pub const DEFAULT_MAX_POOLS: usize = 64;
+
+struct TenantRouting {
+    resolver: Option<Py<PyAny>>,
+    factory: Option<Py<PyAny>>,
+    max_pools: usize,
+    /// Factory-created pools and when each was last used
+    lazy_pools: HashMap<String, Instant>,
+}
+
+static ROUTING: OnceLock<Mutex<TenantRouting>> = OnceLock::new();
+
+fn routing() -> &'static Mutex<TenantRouting> {
+    ROUTING.get_or_init(|| {
+        Mutex::new(TenantRouting {
+            resolver: None,
+            factory: None,
+            max_pools: DEFAULT_MAX_POOLS,
+            lazy_pools: HashMap::new(),
+        })
+    })
+}
+
+pub fn set_alias_resolver(resolver: Option<Py<PyAny>>) {
+    routing().lock().resolver = resolver;
+}
+
+pub fn has_alias_resolver() -> bool {
+    routing().lock().resolver.is_some()
+}
+
+pub fn set_pool_factory(factory: Option<Py<PyAny>>, max_pools: usize) {
+    let mut routing = routing().lock();
+    routing.factory = factory;
+    routing.max_pools = max_pools.max(1);
+}
+
+/// Factory-created pool aliases, least recently used first
+pub fn lazy_pools() -> Vec<String> {
+    let routing = routing().lock();
+    let mut pools: Vec<_> = routing.lazy_pools.iter().collect();
+    pools.sort_by_key(|(_, used)| **used);
+    pools.into_iter().map(|(alias, _)| alias.clone()).collect()
+}
+
+/// Mark a factory-created pool as used now
+pub fn touch(alias: &str) {
+    if let Some(used) = routing().lock().lazy_pools.get_mut(alias) {
+        *used = Instant::now();
+    }
+}
+
+/// Forget a pool closed outside the tenant layer
+pub fn forget(alias: &str) {
+    routing().lock().lazy_pools.remove(alias);
+}
+
+/// Physical pool alias for `alias` in this request.
+///
+/// Without a resolver the logical alias is used as is. The resolver is called
+/// as `resolver(request_id, alias, context)`; `context` defaults to the
+/// current request context (see `hypern.current_context`). Exceptions it
+/// raises propagate unchanged so handlers can map them to 403/404.
+pub fn resolve_alias(
+    py: Python<'_>,
+    request_id: &str,
+    alias: &str,
+    context: Option<&Bound<'_, PyDict>>,
+) -> PyResult<String> {
+    let Some(resolver) = routing()
+        .lock()
+        .resolver
+        .as_ref()
+        .map(|r| r.clone_ref(py))
+    else {
+        return Ok(alias.to_string());
+    };
+
+    let context = match context {
+        Some(context) => context.clone(),
+        None => match crate::core::request_scope::current_context(py)? {
+            Some(context) => context,
+            None => {
+                let context = PyDict::new(py);
+                context.set_item("request_id", request_id)?;
+                context
+            }
+        },
+    };
+
+    resolver
+        .call1(py, (request_id, alias, context))?
+        .extract::<String>(py)
+        .map_err(|_| {
+            pyo3::exceptions::PyTypeError::new_err(format!(
+                "Alias resolver must return a str for alias '{}'",
+                alias
+            ))
+        })
+}
+
+/// Make sure a pool exists for `alias`, creating it from the factory if needed.
+///
+/// Without a factory this is a no-op; a missing pool then surfaces when the
+/// session first acquires a connection, as before.
+pub fn ensure_pool(py: Python<'_>, alias: &str) -> PyResult<()> {
+    if ConnectionPoolManager::get_pool_by_alias(alias).is_some() {
+        touch(alias);
+        return Ok(());
+    }
+    let Some(factory) = routing()
+        .lock()
+        .factory
+        .as_ref()
+        .map(|f| f.clone_ref(py))
+    else {
+        return Ok(());
+    };
+
+    let config = factory.call1(py, (alias,))?;
+    if config.is_none(py) {
+        return Err(PyRuntimeError::new_err(format!(
+            "Pool factory returned no configuration for alias '{}'",
+            alias
+        )));
+    }
+    let config: PoolConfig = config.extract(py)?;
+
+    let mut routing = routing().lock();
+    // Another thread may have created it while the factory ran
+    if ConnectionPoolManager::get_pool_by_alias(alias).is_some() {
+        return Ok(());
+    }
+    if routing.lazy_pools.len() >= routing.max_pools {
+        let victim = lru_idle_pool(&routing.lazy_pools).ok_or_else(|| {
+            PyRuntimeError::new_err(format!(
+                "Tenant pool limit ({}) reached and no idle pool can be closed",
+                routing.max_pools
+            ))
+        })?;
+        routing.lazy_pools.remove(&victim);
+        ConnectionPoolManager::close_alias(&victim);
+        crate::hlog_info!(
+            "Closed idle tenant pool '{}' to make room for '{}'",
+            victim,
+            alias
+        );
+    }
+
+    ConnectionPoolManager::initialize_sync_with_alias(&config, alias)
+        .map_err(PyRuntimeError::new_err)?;
+    routing.lazy_pools.insert(alias.to_string(), Instant::now());
+    Ok(())
+}
+
+/// Least recently used pool with no checked-out connections and no open session
+fn lru_idle_pool(pools: &HashMap<String, Instant>) -> Option<String> {
+    let mut candidates: Vec<_> = pools.iter().collect();
+    candidates.sort_by_key(|(_, used)| **used);
+    candidates
+        .into_iter()
+        .map(|(alias, _)| alias)
+        .find(|alias| {
+            let idle = ConnectionPoolManager::get_pool_by_alias(alias).is_none_or(|p| is_idle(&p));
+            idle && !super::request_context::pool_in_use(alias)
+        })
+        .cloned()
+}
+
+/// No connection checked out and nobody waiting for one
+fn is_idle(pool: &Pool) -> bool {
+    let status = pool.status();
+    status.waiting == 0 && status.available == status.size
+}
@@ -0,0 +1,196 @@
+//! Transactional test isolation.
+//!
+//! `ConnectionPoolManager::begin_test_mode(alias)` takes one connection from
+//! the alias's pool, opens a transaction on it and pins it: every session
+//! opened for that alias afterwards uses the pinned connection, one session
+//! at a time, instead of a pooled one. Inside it a session's `begin`,
+//! `commit` and `rollback` become savepoints, and statements run outside a
+//! session transaction each get a savepoint of their own, so a failing one
+//! leaves the outer transaction usable as it would under autocommit.
+//! `rollback_test_mode(alias)` rolls the outer transaction back, leaving the
+//! database as it was before the test.
+//!
+//! Outside test mode a session pays one atomic load when it acquires its
+//! connection; nothing else checks for it.
+
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use deadpool_postgres::{Object, Pool};
+use parking_lot::Mutex;
+use tokio::sync::OwnedMutexGuard;
+
+/// How long a session waits for another session to give the pinned
+/// connection back
+const PINNED_WAIT: Duration = Duration::from_secs(10);
+
+/// Whether any alias is in test mode
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Pinned connections, by pool alias
+static PINNED: Mutex<Option<HashMap<String, Arc<Pinned>>>> = Mutex::new(None);
+
+/// The connection all sessions of an alias share while in test mode
+pub struct Pinned {
+    connection: Arc<tokio::sync::Mutex<Object>>,
+    /// Request whose session holds the connection
+    holder: Mutex<Option<String>>,
+}
+
+impl Pinned {
+    /// Exclusive use of the connection until the returned lease is dropped
+    pub async fn acquire(&self, request_id: &str) -> Result<OwnedMutexGuard<Object>, String> {
+        let waiting = self.connection.clone().lock_owned();
+        let guard = tokio::time::timeout(PINNED_WAIT, waiting)
+            .await
+            .map_err(|_| {
+                format!(
+                    "Database test mode: the pinned connection is still held by request '{}'; \
+                     finalize that session before opening another",
+                    self.holder.lock().as_deref().unwrap_or("?")
+                )
+            })?;
+        *self.holder.lock() = Some(request_id.to_string());
+        Ok(guard)
+    }
+}
+
+/// A session's connection: from the pool, or the pinned one in test mode.
+/// Pooled is the common case, so it is not boxed.
+#[allow(clippy::large_enum_variant)]
+pub enum Lease {
+    Pooled(Object),
+    Pinned(OwnedMutexGuard<Object>),
+}
+
+impl Lease {
+    pub fn is_pinned(&self) -> bool {
+        matches!(self, Self::Pinned(_))
+    }
+}
+
+impl Deref for Lease {
+    type Target = Object;
+
+    fn deref(&self) -> &Object {
+        match self {
+            Self::Pooled(conn) => conn,
+            Self::Pinned(conn) => conn,
+        }
+    }
+}
+
+impl DerefMut for Lease {
+    fn deref_mut(&mut self) -> &mut Object {
+        match self {
+            Self::Pooled(conn) => conn,
+            Self::Pinned(conn) => conn,
+        }
+    }
+}
+
+/// The pinned connection for `pool_alias`, None outside test mode
+pub fn pinned(pool_alias: &str) -> Option<Arc<Pinned>> {
+    if !ACTIVE.load(Ordering::Relaxed) {
+        return None;
+    }
+    PINNED.lock().as_ref()?.get(pool_alias).cloned()
+}
+
+pub fn is_active(pool_alias: &str) -> bool {
+    pinned(pool_alias).is_some()
+}
+
+/// Pin a connection from `pool` for `alias` and open the outer transaction
+pub async fn begin(alias: &str, pool: &Pool) -> Result<(), String> {
+    if is_active(alias) {
+        return Err(format!("Database test mode already active for '{}'", alias));
+    }
+    let conn = pool
+        .get()
+        .await
+        .map_err(|e| format!("Failed to acquire connection: {}", e))?;
+    conn.batch_execute("BEGIN")
+        .await
+        .map_err(|e| format!("Failed to begin test transaction: {}", e))?;
+
+    let pinned = Arc::new(Pinned {
+        connection: Arc::new(tokio::sync::Mutex::new(conn)),
+        holder: Mutex::new(None),
+    });
+    PINNED
+        .lock()
+        .get_or_insert_with(HashMap::new)
+        .insert(alias.to_string(), pinned);
+    ACTIVE.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Roll back everything done since `begin` and return the connection to
+/// its pool. Fails while a session still holds the connection.
+pub async fn rollback(alias: &str) -> Result<(), String> {
+    let pinned = {
+        let mut all = PINNED.lock();
+        let pinned = all
+            .as_ref()
+            .and_then(|all| all.get(alias).cloned())
+            .ok_or_else(|| format!("Database test mode is not active for '{}'", alias))?;
+        if pinned.connection.try_lock().is_err() {
+            return Err(format!(
+                "Database test mode: request '{}' still holds the pinned connection for '{}'; \
+                 finalize its session first",
+                pinned.holder.lock().as_deref().unwrap_or("?"),
+                alias
+            ));
+        }
+        let all = all.as_mut().expect("checked above");
+        all.remove(alias);
+        ACTIVE.store(!all.is_empty(), Ordering::Relaxed);
+        pinned
+    };
+    let conn = pinned.connection.lock().await;
+    conn.batch_execute("ROLLBACK")
+        .await
+        .map_err(|e| format!("Failed to roll back test transaction: {}", e))
+}
+
+/// Statements a session runs for a transaction named `name`: `BEGIN`,
+/// `COMMIT` and `ROLLBACK`, or savepoints on a pinned connection
+pub fn transaction_sql(pinned: bool, name: &str) -> [String; 3] {
+    if pinned {
+        [
+            format!("SAVEPOINT {}", name),
+            format!("RELEASE SAVEPOINT {}", name),
+            format!("ROLLBACK TO SAVEPOINT {0}; RELEASE SAVEPOINT {0}", name),
+        ]
+    } else {
+        ["BEGIN".into(), "COMMIT".into(), "ROLLBACK".into()]
+    }
+}
+
+/// Refuse statements that would end the outer transaction
+pub fn check_statement(sql: &str) -> Result<(), String> {
+    let mut words = sql
+        .split(|c: char| c.is_whitespace() || c == ';')
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_uppercase);
+    let first = words.next().unwrap_or_default();
+    let second = words.next().unwrap_or_default();
+    let escapes = match first.as_str() {
+        "BEGIN" | "COMMIT" | "END" | "ABORT" => true,
+        "START" | "PREPARE" => second == "TRANSACTION",
+        "ROLLBACK" => second != "TO",
+        _ => false,
+    };
+    if escapes {
+        return Err(format!(
+            "{} is not allowed in database test mode: it would end the test's outer \
+             transaction. Use the session's begin(), commit() and rollback() instead",
+            first
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,248 @@
+//! TLS for Postgres connections.
+//!
+//! The modes follow libpq's `sslmode`:
+//!
+//! - `disable`: plaintext only
+//! - `prefer`: TLS when the server offers it, without checking the
+//!   certificate; plaintext otherwise (the default)
+//! - `require`: TLS, without checking the certificate, unless a root
+//!   certificate is given, in which case it behaves like `verify-ca`
+//! - `verify-ca`: TLS with a certificate chaining to `ssl_root_cert_path`
+//! - `verify-full`: as `verify-ca`, and the certificate must name the host
+//!   connected to
+//!
+//! Options come from `PoolConfig` or from the URL's query string
+//! (`sslmode`, `sslrootcert`, `sslcert`, `sslkey`); `PoolConfig` wins.
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::{ring, WebPkiSupportedAlgorithms};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{
+    CertificateError, ClientConfig, DigitallySignedStruct, Error, RootCertStore, SignatureScheme,
+};
+use std::sync::Arc;
+use tokio_postgres_rustls::MakeRustlsConnect;
+
+/// libpq `sslmode` values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SslMode {
+    Disable,
+    #[default]
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+impl SslMode {
+    pub fn parse(mode: &str) -> Result<Self, String> {
+        match mode.to_ascii_lowercase().as_str() {
+            "disable" => Ok(Self::Disable),
+            "prefer" => Ok(Self::Prefer),
+            "require" => Ok(Self::Require),
+            "verify-ca" => Ok(Self::VerifyCa),
+            "verify-full" => Ok(Self::VerifyFull),
+            other => Err(format!(
+                "Invalid ssl_mode '{}'; expected disable, prefer, require, verify-ca or verify-full",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Disable => "disable",
+            Self::Prefer => "prefer",
+            Self::Require => "require",
+            Self::VerifyCa => "verify-ca",
+            Self::VerifyFull => "verify-full",
+        }
+    }
+
+    /// Whether connections fail without TLS
+    pub fn requires_tls(&self) -> bool {
+        !matches!(self, Self::Disable | Self::Prefer)
+    }
+
+    /// The negotiation mode tokio-postgres knows about; certificate checks
+    /// are up to the connector
+    pub fn negotiation(&self) -> deadpool_postgres::SslMode {
+        match self {
+            Self::Disable => deadpool_postgres::SslMode::Disable,
+            Self::Prefer => deadpool_postgres::SslMode::Prefer,
+            _ => deadpool_postgres::SslMode::Require,
+        }
+    }
+}
+
+/// Resolved TLS settings of a pool
+#[derive(Debug, Clone, Default)]
+pub struct TlsOptions {
+    pub mode: SslMode,
+    pub root_cert_path: Option<String>,
+    pub client_cert_path: Option<String>,
+    pub client_key_path: Option<String>,
+}
+
+impl TlsOptions {
+    /// Build the connector; certificate files are read here, so a missing
+    /// or unreadable file fails pool creation
+    pub fn connector(&self) -> Result<MakeRustlsConnect, String> {
+        let provider = Arc::new(ring::default_provider());
+        let builder = ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(|e| format!("Invalid TLS configuration: {}", e))?;
+
+        let verify_ca = match self.mode {
+            SslMode::VerifyCa | SslMode::VerifyFull => true,
+            SslMode::Require => self.root_cert_path.is_some(),
+            _ => false,
+        };
+        let verifier: Arc<dyn ServerCertVerifier> = if verify_ca {
+            let path = self.root_cert_path.as_deref().ok_or_else(|| {
+                format!(
+                    "ssl_mode '{}' needs ssl_root_cert_path (sslrootcert in the URL)",
+                    self.mode.as_str()
+                )
+            })?;
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(path, "sslrootcert")? {
+                roots
+                    .add(cert)
+                    .map_err(|e| format!("Invalid root certificate in '{}': {}", path, e))?;
+            }
+            let webpki =
+                WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+                    .build()
+                    .map_err(|e| format!("Invalid root certificates in '{}': {}", path, e))?;
+            if self.mode == SslMode::VerifyFull {
+                webpki
+            } else {
+                Arc::new(IgnoreHostname(webpki))
+            }
+        } else {
+            Arc::new(AcceptAnyCert(provider.signature_verification_algorithms))
+        };
+        let builder = builder
+            .dangerous()
+            .with_custom_certificate_verifier(verifier);
+
+        let config = match (&self.client_cert_path, &self.client_key_path) {
+            (Some(cert_path), Some(key_path)) => {
+                let certs = load_certs(cert_path, "sslcert")?;
+                let key = PrivateKeyDer::from_pem_file(key_path)
+                    .map_err(|e| format!("Failed to read sslkey '{}': {}", key_path, e))?;
+                builder
+                    .with_client_auth_cert(certs, key)
+                    .map_err(|e| format!("Invalid client certificate: {}", e))?
+            }
+            (None, None) => builder.with_no_client_auth(),
+            _ => {
+                return Err(
+                    "A client certificate needs both ssl_client_cert and ssl_client_key"
+                        .to_string(),
+                )
+            }
+        };
+        Ok(MakeRustlsConnect::new(config))
+    }
+}
+
+fn load_certs(path: &str, option: &str) -> Result<Vec<CertificateDer<'static>>, String> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|iter| iter.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("Failed to read {} '{}': {}", option, path, e))?;
+    if certs.is_empty() {
+        return Err(format!("No certificates found in {} '{}'", option, path));
+    }
+    Ok(certs)
+}
+
+/// `verify-ca`: the chain must check out, the name on it is not looked at
+#[derive(Debug)]
+struct IgnoreHostname(Arc<WebPkiServerVerifier>);
+
+impl ServerCertVerifier for IgnoreHostname {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, Error> {
+        // The name is checked last, so a name error means the chain passed
+        match self
+            .0
+            .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+        {
+            Err(Error::InvalidCertificate(
+                CertificateError::NotValidForName | CertificateError::NotValidForNameContext { .. },
+            )) => Ok(ServerCertVerified::assertion()),
+            result => result,
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        self.0.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        self.0.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.supported_verify_schemes()
+    }
+}
+
+/// `prefer`/`require`: any certificate, as long as the server holds its key
+#[derive(Debug)]
+struct AcceptAnyCert(WebPkiSupportedAlgorithms);
+
+impl ServerCertVerifier for AcceptAnyCert {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.0)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.0)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.supported_schemes()
+    }
+}
@@ -2,7 +2,7 @@ use pyo3::prelude::*;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use crate::database::operation::{DatabaseOperations, RowStream};
+use crate::database::operation::{DatabaseOperations, RowStream, DEFAULT_PREFETCH};
 use crate::database::pool::get_db_runtime;
 
 #[pyclass(from_py_object)]
@@ -48,22 +48,25 @@ impl DatabaseTransaction {
         Ok(result)
     }
 
-    /// Stream data in chunks. Returns a RowStream iterator that yields chunks lazily.
+    /// Stream data in chunks. Returns a RowStream iterator that yields
+    /// chunks as they are fetched, `prefetch` chunks ahead at most.
+    #[pyo3(signature = (query, params, chunk_size, prefetch=DEFAULT_PREFETCH))]
     fn stream_data(
         &self,
         py: Python<'_>,
         query: &str,
         params: Vec<Py<PyAny>>,
         chunk_size: usize,
+        prefetch: usize,
     ) -> PyResult<RowStream> {
-        let transaction = self.transaction.clone();
-        let operations = self.operations.clone();
-        let result = get_db_runtime().block_on(async move {
-            operations
-                .stream_data(py, transaction, query, params, chunk_size)
-                .await
-        })?;
-        Ok(result)
+        self.operations.stream_data(
+            py,
+            self.transaction.clone(),
+            query,
+            params,
+            chunk_size,
+            prefetch,
+        )
     }
 
     fn bulk_change(
@@ -3,3 +3,11 @@ pub mod json_cache;
 pub mod static_files;
 
 pub use static_files::StaticFileHandler;
+
+use pyo3::prelude::*;
+
+/// Register fast-path classes.
+pub fn register_all(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<StaticFileHandler>()?;
+    Ok(())
+}
@@ -1,33 +1,356 @@
 //! Static file serving with memory-mapped files for zero-copy performance.
+//!
+//! Each handler keeps the files it served in an LRU cache with a byte
+//! budget (`max_cache_bytes`). An entry costs its file size plus its key,
+//! ETag and content type and a fixed allowance for the bookkeeping around
+//! them; the least recently served entries are evicted to make room. Files
+//! larger than `max_file_cache_bytes` are mapped from disk on every request
+//! and never cached.
+//!
+//! Files can also be served from memory: a bundle mounted with
+//! `StaticFileHandler.from_bundle`, or an overlay over a directory that wins
+//! over the files on disk. Their entries are pinned in the cache, counting
+//! against the same budget without ever being evicted.
 
 use ahash::AHashMap;
 use memmap2::Mmap;
-use parking_lot::RwLock;
+use parking_lot::Mutex;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Weak};
+use xxhash_rust::xxh3::xxh3_64;
+
+const DEFAULT_MAX_CACHE_BYTES: usize = 64 * 1024 * 1024;
+const DEFAULT_MAX_FILE_CACHE_BYTES: usize = 4 * 1024 * 1024;
+const DEFAULT_MAX_FILE_SIZE: usize = 50 * 1024 * 1024;
+/// Approximate cost of an entry beyond its file bytes and strings: the map
+/// and recency slots, the `CachedFile` and its `Arc`s
+const ENTRY_OVERHEAD: usize = 128;
+
+/// Caches of live handlers, for `ServerMetrics`
+static CACHES: LazyLock<Mutex<Vec<Weak<FileCache>>>> = LazyLock::new(Default::default);
+
+/// Bytes of a served file
+pub enum FileData {
+    /// Mapped from disk
+    Mapped(Mmap),
+    /// Held in memory, for bundles and overlays
+    Memory(Vec<u8>),
+}
+
+impl std::ops::Deref for FileData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            FileData::Mapped(mmap) => mmap,
+            FileData::Memory(data) => data,
+        }
+    }
+}
 
 /// Cached static file
 pub struct CachedFile {
-    pub data: Arc<Mmap>,
+    pub data: Arc<FileData>,
     pub content_type: String,
     pub size: usize,
     pub etag: String,
 }
 
 impl CachedFile {
+    /// An in-memory file; the ETag hashes the content so every worker and
+    /// restart agrees on it
+    fn in_memory(path: &str, data: Vec<u8>) -> Self {
+        Self {
+            content_type: guess_content_type(Path::new(path)),
+            size: data.len(),
+            etag: format!("\"{:x}-{:x}\"", data.len(), xxh3_64(&data)),
+            data: Arc::new(FileData::Memory(data)),
+        }
+    }
+
     pub fn as_bytes(&self) -> &[u8] {
         &self.data[..]
     }
+
+    /// Bytes the entry for `key` counts against the cache budget
+    fn cost(&self, key: &str) -> usize {
+        self.size + key.len() + self.etag.len() + self.content_type.len() + ENTRY_OVERHEAD
+    }
+}
+
+struct Entry {
+    file: Arc<CachedFile>,
+    cost: usize,
+    /// Position in `Lru::order`
+    tick: u64,
+}
+
+#[derive(Default)]
+struct Lru {
+    entries: AHashMap<String, Entry>,
+    /// Keys by last access, oldest first
+    order: BTreeMap<u64, String>,
+    /// In-memory files, never evicted
+    pinned: AHashMap<String, Arc<CachedFile>>,
+    pinned_bytes: usize,
+    /// Cost of every entry, pinned ones included
+    bytes: usize,
+    next_tick: u64,
+}
+
+impl Lru {
+    fn tick(&mut self) -> u64 {
+        self.next_tick += 1;
+        self.next_tick
+    }
+
+    fn remove(&mut self, key: &str) -> Option<Entry> {
+        let entry = self.entries.remove(key)?;
+        self.order.remove(&entry.tick);
+        self.bytes -= entry.cost;
+        Some(entry)
+    }
+
+    /// Evict the least recently served entries until `cost` more bytes fit
+    /// in `max_bytes`, returning how many were evicted
+    fn make_room(&mut self, cost: usize, max_bytes: usize) -> u64 {
+        let mut evicted = 0;
+        while self.bytes + cost > max_bytes {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&oldest) {
+                self.bytes -= entry.cost;
+                evicted += 1;
+            }
+        }
+        evicted
+    }
+}
+
+/// Cache counters of one handler
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub bytes: usize,
+    pub max_bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    /// Prometheus name, type and help of each of `metric_values`
+    pub const METRICS: [(&'static str, &'static str, &'static str); 5] = [
+        (
+            "hypern_static_cache_entries",
+            "gauge",
+            "Files in the static file cache",
+        ),
+        (
+            "hypern_static_cache_bytes",
+            "gauge",
+            "Bytes the static file cache accounts for",
+        ),
+        (
+            "hypern_static_cache_hits_total",
+            "counter",
+            "Static file cache hits",
+        ),
+        (
+            "hypern_static_cache_misses_total",
+            "counter",
+            "Static file cache misses",
+        ),
+        (
+            "hypern_static_cache_evictions_total",
+            "counter",
+            "Files evicted to keep the static file cache within its budget",
+        ),
+    ];
+
+    pub fn metric_values(&self) -> [u64; 5] {
+        [
+            self.entries as u64,
+            self.bytes as u64,
+            self.hits,
+            self.misses,
+            self.evictions,
+        ]
+    }
+
+    pub fn to_dict<'py>(self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("entries", self.entries)?;
+        dict.set_item("bytes", self.bytes)?;
+        dict.set_item("max_bytes", self.max_bytes)?;
+        dict.set_item("hits", self.hits)?;
+        dict.set_item("misses", self.misses)?;
+        dict.set_item("evictions", self.evictions)?;
+        Ok(dict)
+    }
+}
+
+/// Byte-budgeted LRU of served files
+pub struct FileCache {
+    lru: Mutex<Lru>,
+    max_bytes: usize,
+    /// Larger files are never cached
+    max_file_bytes: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+    /// URL prefix of the owning handler, labelling its metrics
+    prefix: String,
+}
+
+impl FileCache {
+    fn new(prefix: &str, max_bytes: usize, max_file_bytes: usize) -> Arc<Self> {
+        let cache = Arc::new(Self {
+            lru: Mutex::default(),
+            max_bytes,
+            max_file_bytes,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+            prefix: prefix.to_string(),
+        });
+        let mut caches = CACHES.lock();
+        caches.retain(|cache| cache.strong_count() > 0);
+        caches.push(Arc::downgrade(&cache));
+        cache
+    }
+
+    fn get(&self, key: &str) -> Option<Arc<CachedFile>> {
+        let mut lru = self.lru.lock();
+        if let Some(file) = lru.pinned.get(key).cloned() {
+            drop(lru);
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Some(file);
+        }
+        let tick = lru.tick();
+        let Some(entry) = lru.entries.get_mut(key) else {
+            drop(lru);
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        let previous = std::mem::replace(&mut entry.tick, tick);
+        let file = entry.file.clone();
+        if let Some(key) = lru.order.remove(&previous) {
+            lru.order.insert(tick, key);
+        }
+        drop(lru);
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(file)
+    }
+
+    /// Cache `file` unless it is over the per-file limit, evicting the least
+    /// recently served entries to stay within the budget
+    fn insert(&self, key: String, file: Arc<CachedFile>) {
+        let cost = file.cost(&key);
+        if file.size > self.max_file_bytes || cost > self.max_bytes {
+            return;
+        }
+        let mut lru = self.lru.lock();
+        if lru.pinned.contains_key(&key) {
+            return;
+        }
+        lru.remove(&key);
+        let evicted = lru.make_room(cost, self.max_bytes);
+        self.evictions.fetch_add(evicted, Ordering::Relaxed);
+        let tick = lru.tick();
+        lru.order.insert(tick, key.clone());
+        lru.bytes += cost;
+        lru.entries.insert(key, Entry { file, cost, tick });
+    }
+
+    /// Hold an in-memory file under `key`, ahead of any file on disk. It
+    /// counts against the budget like a cached file but is never evicted,
+    /// so a large bundle leaves less room for files read from disk.
+    fn pin(&self, key: String, file: Arc<CachedFile>) {
+        let cost = file.cost(&key);
+        let mut lru = self.lru.lock();
+        lru.remove(&key);
+        if let Some(previous) = lru.pinned.remove(&key) {
+            let previous = previous.cost(&key);
+            lru.bytes -= previous;
+            lru.pinned_bytes -= previous;
+        }
+        let evicted = lru.make_room(cost, self.max_bytes);
+        self.evictions.fetch_add(evicted, Ordering::Relaxed);
+        lru.bytes += cost;
+        lru.pinned_bytes += cost;
+        lru.pinned.insert(key, file);
+    }
+
+    /// Pinned files, to carry over to a rebuilt cache
+    fn pinned(&self) -> Vec<(String, Arc<CachedFile>)> {
+        let lru = self.lru.lock();
+        lru.pinned
+            .iter()
+            .map(|(key, file)| (key.clone(), file.clone()))
+            .collect()
+    }
+
+    /// Whether pinned files sit under the directory `path`
+    fn has_pinned_dir(&self, path: &str) -> bool {
+        let dir = format!("{}/", path.trim_end_matches('/'));
+        self.lru
+            .lock()
+            .pinned
+            .keys()
+            .any(|key| key.starts_with(&dir))
+    }
+
+    /// Drop the files cached from disk; pinned files stay
+    fn clear(&self) {
+        let mut lru = self.lru.lock();
+        lru.entries.clear();
+        lru.order.clear();
+        lru.bytes = lru.pinned_bytes;
+    }
+
+    /// Cached paths, least recently served first; pinned files are not
+    /// listed
+    fn keys(&self) -> Vec<String> {
+        self.lru.lock().order.values().cloned().collect()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let lru = self.lru.lock();
+        CacheStats {
+            entries: lru.entries.len() + lru.pinned.len(),
+            bytes: lru.bytes,
+            max_bytes: self.max_bytes,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Cache stats of every live handler, labelled with its URL prefix
+pub fn cache_stats() -> Vec<(String, CacheStats)> {
+    CACHES
+        .lock()
+        .iter()
+        .filter_map(Weak::upgrade)
+        .map(|cache| (cache.prefix.clone(), cache.stats()))
+        .collect()
 }
 
 /// Static file handler with memory-mapped caching
 #[pyclass]
 pub struct StaticFileHandler {
-    root_path: PathBuf,
-    cache: RwLock<AHashMap<String, Arc<CachedFile>>>,
-    max_cache_size: usize,
+    /// Directory served, `None` for a bundle alone
+    root_path: Option<PathBuf>,
+    cache: Arc<FileCache>,
+    /// Larger files are refused
     max_file_size: usize,
     /// URL prefix for this handler
     prefix: String,
@@ -35,6 +358,11 @@ pub struct StaticFileHandler {
     index_file: String,
     /// SPA fallback mode: serve index.html for any path that doesn't match a file
     spa_mode: bool,
+    /// Limit the fallback to GET/HEAD navigations outside
+    /// `fallback_exclude_prefixes`, instead of every missing path
+    fallback_checks: bool,
+    /// Path prefixes that never receive the SPA fallback (e.g. "/api")
+    fallback_exclude_prefixes: Vec<String>,
     /// Cache-Control max-age in seconds
     cache_max_age: Option<u32>,
 }
@@ -42,33 +370,87 @@ pub struct StaticFileHandler {
 impl StaticFileHandler {
     pub fn new(root_path: impl AsRef<Path>) -> Self {
         Self {
-            root_path: root_path.as_ref().to_path_buf(),
-            cache: RwLock::new(AHashMap::new()),
-            max_cache_size: 1000,
-            max_file_size: 50 * 1024 * 1024, // 50MB max
+            root_path: Some(root_path.as_ref().to_path_buf()),
+            cache: FileCache::new(
+                "/static",
+                DEFAULT_MAX_CACHE_BYTES,
+                DEFAULT_MAX_FILE_CACHE_BYTES,
+            ),
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
             prefix: "/static".to_string(),
             index_file: "index.html".to_string(),
             spa_mode: false,
+            fallback_checks: false,
+            fallback_exclude_prefixes: default_fallback_excludes(),
             cache_max_age: None,
         }
     }
 
-    pub fn with_limits(mut self, max_cache_size: usize, max_file_size: usize) -> Self {
-        self.max_cache_size = max_cache_size;
+    /// Cache budget, per-file cache limit and largest file served, in bytes
+    pub fn with_limits(
+        mut self,
+        max_cache_bytes: usize,
+        max_file_cache_bytes: usize,
+        max_file_size: usize,
+    ) -> Self {
+        self.rebuild_cache(max_cache_bytes, max_file_cache_bytes);
         self.max_file_size = max_file_size;
         self
     }
 
     pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
         self.prefix = prefix.into();
+        self.rebuild_cache(self.cache.max_bytes, self.cache.max_file_bytes);
         self
     }
 
+    /// Serve `files`, keyed by path relative to the prefix, from memory ahead
+    /// of the directory
+    pub fn with_overlay(
+        self,
+        files: impl IntoIterator<Item = (String, Vec<u8>)>,
+    ) -> Result<Self, StaticFileError> {
+        for (path, data) in files {
+            let key = Self::normalize_path(&path)?;
+            if key.is_empty() {
+                return Err(StaticFileError::InvalidPath);
+            }
+            let file = Arc::new(CachedFile::in_memory(&key, data));
+            self.cache.pin(key, file);
+        }
+        Ok(self)
+    }
+
+    /// A handler serving `files` from memory, without a directory
+    pub fn from_bundle(
+        files: impl IntoIterator<Item = (String, Vec<u8>)>,
+    ) -> Result<Self, StaticFileError> {
+        let mut handler = Self::new("");
+        handler.root_path = None;
+        handler.with_overlay(files)
+    }
+
+    /// Replace the cache, keeping its pinned files
+    fn rebuild_cache(&mut self, max_bytes: usize, max_file_bytes: usize) {
+        let cache = FileCache::new(&self.prefix, max_bytes, max_file_bytes);
+        for (key, file) in self.cache.pinned() {
+            cache.pin(key, file);
+        }
+        self.cache = cache;
+    }
+
     pub fn with_spa(mut self, spa: bool) -> Self {
         self.spa_mode = spa;
         self
     }
 
+    /// Limit the fallback to navigations outside `prefixes`
+    pub fn with_fallback_exclude_prefixes(mut self, prefixes: Vec<String>) -> Self {
+        self.fallback_checks = true;
+        self.fallback_exclude_prefixes = prefixes;
+        self
+    }
+
     pub fn with_cache_max_age(mut self, seconds: u32) -> Self {
         self.cache_max_age = Some(seconds);
         self
@@ -77,26 +459,106 @@ impl StaticFileHandler {
     /// Serve a static file, using cache if available
     pub fn serve(&self, path: &str) -> Result<Arc<CachedFile>, StaticFileError> {
         // Normalize and validate path
-        let clean_path = self.normalize_path(path)?;
+        let clean_path = Self::normalize_path(path)?;
 
         // Check cache
-        if let Some(cached) = self.cache.read().get(&clean_path) {
-            return Ok(cached.clone());
+        if let Some(cached) = self.cache.get(&clean_path) {
+            return Ok(cached);
         }
 
-        // Load from disk
+        // Load from disk; cached unless over the per-file limit
         let file = self.load_file(&clean_path)?;
+        self.cache.insert(clean_path, file.clone());
+
+        Ok(file)
+    }
+
+    /// Resolve a request path to a file, applying the SPA fallback when allowed.
+    ///
+    /// Returns the file and whether it is the fallback document. The fallback
+    /// is loaded through `serve`, so it is cached under its own key and never
+    /// under the requested path.
+    pub fn resolve(
+        &self,
+        request_path: &str,
+        method: &str,
+        accept: Option<&str>,
+    ) -> Result<(Arc<CachedFile>, bool), StaticFileError> {
+        let file_path = request_path
+            .strip_prefix(self.prefix.as_str())
+            .unwrap_or(request_path)
+            .trim_start_matches('/');
+
+        let file_path = if file_path.is_empty() {
+            self.index_file.as_str()
+        } else {
+            file_path
+        };
 
-        // Cache if within limits
-        if self.cache.read().len() < self.max_cache_size {
-            self.cache.write().insert(clean_path, file.clone());
+        match self.serve(file_path) {
+            Ok(file) => Ok((file, false)),
+            Err(StaticFileError::NotFound)
+                if self.should_fallback(request_path, file_path, method, accept) =>
+            {
+                self.serve(&self.index_file).map(|file| (file, true))
+            }
+            Err(e) => Err(e),
         }
+    }
 
-        Ok(file)
+    /// Decide whether a missing path should be answered with the index file.
+    ///
+    /// Plain `spa` mode answers every missing path. With the fallback checks
+    /// only GET/HEAD navigations qualify: the client must accept HTML or the
+    /// path must have no extension, so missing assets like `/app/main.js`
+    /// still 404. Excluded prefixes and existing directories never fall back.
+    fn should_fallback(
+        &self,
+        request_path: &str,
+        file_path: &str,
+        method: &str,
+        accept: Option<&str>,
+    ) -> bool {
+        if !self.spa_mode {
+            return false;
+        }
+        if !self.fallback_checks {
+            return true;
+        }
+        if !method.eq_ignore_ascii_case("GET") && !method.eq_ignore_ascii_case("HEAD") {
+            return false;
+        }
+        let relative = format!("/{}", file_path);
+        let excluded = self.fallback_exclude_prefixes.iter().any(|prefix| {
+            let prefix = prefix.trim_end_matches('/');
+            [request_path, relative.as_str()].iter().any(|p| {
+                p.strip_prefix(prefix)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+        });
+        if excluded {
+            return false;
+        }
+        let is_dir = self
+            .root_path
+            .as_ref()
+            .is_some_and(|root| root.join(file_path).is_dir());
+        if is_dir || self.cache.has_pinned_dir(file_path) {
+            return false;
+        }
+        let accepts_html = accept.is_some_and(|a| {
+            let a = a.to_ascii_lowercase();
+            a.contains("text/html") || a.contains("application/xhtml+xml")
+        });
+        let has_extension = Path::new(file_path)
+            .file_name()
+            .and_then(|name| Path::new(name).extension())
+            .is_some();
+        accepts_html || !has_extension
     }
 
     /// Normalize path and prevent directory traversal
-    fn normalize_path(&self, path: &str) -> Result<String, StaticFileError> {
+    fn normalize_path(path: &str) -> Result<String, StaticFileError> {
         let path = path.trim_start_matches('/');
 
         // Prevent directory traversal
@@ -109,7 +571,10 @@ impl StaticFileHandler {
 
     /// Load a file from disk with memory mapping
     fn load_file(&self, path: &str) -> Result<Arc<CachedFile>, StaticFileError> {
-        let full_path = self.root_path.join(path);
+        let Some(root_path) = &self.root_path else {
+            return Err(StaticFileError::NotFound);
+        };
+        let full_path = root_path.join(path);
 
         // Check if file exists
         if !full_path.exists() || !full_path.is_file() {
@@ -130,7 +595,7 @@ impl StaticFileHandler {
         let mmap = unsafe { Mmap::map(&file).map_err(|_| StaticFileError::IoError)? };
 
         // Determine content type
-        let content_type = self.guess_content_type(&full_path);
+        let content_type = guess_content_type(&full_path);
 
         // Generate ETag
         let etag = format!(
@@ -146,47 +611,20 @@ impl StaticFileHandler {
         );
 
         Ok(Arc::new(CachedFile {
-            data: Arc::new(mmap),
+            data: Arc::new(FileData::Mapped(mmap)),
             content_type,
             size,
             etag,
         }))
     }
 
-    /// Guess content type from file extension
-    fn guess_content_type(&self, path: &Path) -> String {
-        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
-
-        match ext.to_lowercase().as_str() {
-            "html" | "htm" => "text/html; charset=utf-8",
-            "css" => "text/css; charset=utf-8",
-            "js" | "mjs" => "application/javascript; charset=utf-8",
-            "json" => "application/json; charset=utf-8",
-            "png" => "image/png",
-            "jpg" | "jpeg" => "image/jpeg",
-            "gif" => "image/gif",
-            "svg" => "image/svg+xml",
-            "ico" => "image/x-icon",
-            "woff" => "font/woff",
-            "woff2" => "font/woff2",
-            "ttf" => "font/ttf",
-            "eot" => "application/vnd.ms-fontobject",
-            "pdf" => "application/pdf",
-            "txt" => "text/plain; charset=utf-8",
-            "xml" => "application/xml; charset=utf-8",
-            "mp4" => "video/mp4",
-            "webm" => "video/webm",
-            "mp3" => "audio/mpeg",
-            "wav" => "audio/wav",
-            "zip" => "application/zip",
-            _ => "application/octet-stream",
-        }
-        .to_string()
-    }
-
     /// Clear the cache
     pub fn clear_cache(&self) {
-        self.cache.write().clear();
+        self.cache.clear();
+    }
+
+    pub fn cache(&self) -> &FileCache {
+        &self.cache
     }
 }
 
@@ -212,6 +650,67 @@ impl std::fmt::Display for StaticFileError {
 
 impl std::error::Error for StaticFileError {}
 
+/// Guess content type from file extension
+fn guess_content_type(path: &Path) -> String {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+
+    match ext.to_lowercase().as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "application/javascript; charset=utf-8",
+        "json" => "application/json; charset=utf-8",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "eot" => "application/vnd.ms-fontobject",
+        "pdf" => "application/pdf",
+        "txt" => "text/plain; charset=utf-8",
+        "xml" => "application/xml; charset=utf-8",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
+    .to_string()
+}
+
+/// Entries of a bundle dict; str values are encoded as UTF-8
+fn bundle_files(files: &Bound<'_, PyDict>) -> PyResult<Vec<(String, Vec<u8>)>> {
+    files
+        .iter()
+        .map(|(path, data)| {
+            let path: String = path.extract()?;
+            let data = match data.extract::<String>() {
+                Ok(text) => text.into_bytes(),
+                Err(_) => data.extract::<Vec<u8>>().map_err(|_| {
+                    pyo3::exceptions::PyTypeError::new_err(format!(
+                        "Bundle entry '{}' must be bytes or str",
+                        path
+                    ))
+                })?,
+            };
+            Ok((path, data))
+        })
+        .collect()
+}
+
+fn bundle_error(_: StaticFileError) -> PyErr {
+    pyo3::exceptions::PyValueError::new_err(
+        "Bundle paths must be non-empty and must not contain '..'",
+    )
+}
+
+fn default_fallback_excludes() -> Vec<String> {
+    vec!["/api".to_string(), "/assets".to_string()]
+}
+
 #[pymethods]
 impl StaticFileHandler {
     /// Create a new static file handler
@@ -220,16 +719,32 @@ impl StaticFileHandler {
     ///     directory: Path to the directory containing static files
     ///     prefix: URL prefix for static files (default: "/static")
     ///     index: Index file name (default: "index.html")
-    ///     spa: Enable SPA fallback mode (default: false)
+    ///     spa: Serve the index file for every missing path (default: false)
     ///     cache_max_age: Cache-Control max-age in seconds (optional)
+    ///     spa_fallback: Serve the index file only for GET/HEAD navigations to
+    ///         missing paths outside `fallback_exclude_prefixes` (default: false)
+    ///     fallback_exclude_prefixes: Path prefixes that never fall back
+    ///         (default: ["/api", "/assets"]); passing them also turns on the
+    ///         navigation checks for `spa`
+    ///     max_cache_bytes: Memory budget of the file cache (default: 64 MiB)
+    ///     max_file_cache_bytes: Files larger than this are read from disk on
+    ///         every request instead of cached (default: 4 MiB)
+    ///     overlay: Files served from memory ahead of the directory, as a dict
+    ///         of relative path to bytes or str (optional)
     #[new]
-    #[pyo3(signature = (directory, prefix="/static", index="index.html", spa=false, cache_max_age=None))]
+    #[pyo3(signature = (directory, prefix="/static", index="index.html", spa=false, cache_max_age=None, spa_fallback=false, fallback_exclude_prefixes=None, max_cache_bytes=DEFAULT_MAX_CACHE_BYTES, max_file_cache_bytes=DEFAULT_MAX_FILE_CACHE_BYTES, overlay=None))]
+    #[allow(clippy::too_many_arguments)]
     pub fn py_new(
         directory: &str,
         prefix: &str,
         index: &str,
         spa: bool,
         cache_max_age: Option<u32>,
+        spa_fallback: bool,
+        fallback_exclude_prefixes: Option<Vec<String>>,
+        max_cache_bytes: usize,
+        max_file_cache_bytes: usize,
+        overlay: Option<&Bound<'_, PyDict>>,
     ) -> PyResult<Self> {
         let root = PathBuf::from(directory);
         if !root.exists() || !root.is_dir() {
@@ -238,38 +753,99 @@ impl StaticFileHandler {
                 directory
             )));
         }
-        Ok(Self {
-            root_path: root,
-            cache: RwLock::new(AHashMap::new()),
-            max_cache_size: 1000,
-            max_file_size: 50 * 1024 * 1024,
+        let handler = Self {
+            root_path: Some(root),
+            cache: FileCache::new(prefix, max_cache_bytes, max_file_cache_bytes),
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
             prefix: prefix.to_string(),
             index_file: index.to_string(),
-            spa_mode: spa,
+            spa_mode: spa || spa_fallback,
+            fallback_checks: spa_fallback || fallback_exclude_prefixes.is_some(),
+            fallback_exclude_prefixes: fallback_exclude_prefixes
+                .unwrap_or_else(default_fallback_excludes),
             cache_max_age,
-        })
+        };
+        match overlay {
+            Some(files) => handler
+                .with_overlay(bundle_files(files)?)
+                .map_err(bundle_error),
+            None => Ok(handler),
+        }
+    }
+
+    /// Create a handler serving files from memory
+    ///
+    /// Entries get the same ETags, conditional responses and cache headers
+    /// as files on disk, with the content type inferred from the extension.
+    /// They are pinned in the file cache: `cache_stats()` counts them, and
+    /// they are never evicted. `hypern.static.load_bundle` builds `files`
+    /// from a directory, `load_zip_bundle` and `load_base64_bundle` from an
+    /// embedded archive.
+    ///
+    /// Args:
+    ///     files: Dict of path relative to the prefix to bytes or str
+    ///     directory: Directory serving paths missing from `files` (optional)
+    ///     prefix, index, cache_max_age, spa_fallback, fallback_exclude_prefixes,
+    ///     max_cache_bytes, max_file_cache_bytes: As for the constructor
+    #[staticmethod]
+    #[pyo3(name = "from_bundle", signature = (files, directory=None, prefix="/static", index="index.html", cache_max_age=None, spa_fallback=false, fallback_exclude_prefixes=None, max_cache_bytes=DEFAULT_MAX_CACHE_BYTES, max_file_cache_bytes=DEFAULT_MAX_FILE_CACHE_BYTES))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn py_from_bundle(
+        files: &Bound<'_, PyDict>,
+        directory: Option<&str>,
+        prefix: &str,
+        index: &str,
+        cache_max_age: Option<u32>,
+        spa_fallback: bool,
+        fallback_exclude_prefixes: Option<Vec<String>>,
+        max_cache_bytes: usize,
+        max_file_cache_bytes: usize,
+    ) -> PyResult<Self> {
+        if let Some(directory) = directory {
+            return Self::py_new(
+                directory,
+                prefix,
+                index,
+                false,
+                cache_max_age,
+                spa_fallback,
+                fallback_exclude_prefixes,
+                max_cache_bytes,
+                max_file_cache_bytes,
+                Some(files),
+            );
+        }
+        let handler = Self {
+            root_path: None,
+            cache: FileCache::new(prefix, max_cache_bytes, max_file_cache_bytes),
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            prefix: prefix.to_string(),
+            index_file: index.to_string(),
+            spa_mode: spa_fallback,
+            fallback_checks: true,
+            fallback_exclude_prefixes: fallback_exclude_prefixes
+                .unwrap_or_else(default_fallback_excludes),
+            cache_max_age,
+        };
+        handler
+            .with_overlay(bundle_files(files)?)
+            .map_err(bundle_error)
     }
 
     /// Serve a file by path, returns (body_bytes, content_type, etag, status_code)
-    #[pyo3(signature = (path, if_none_match=None))]
+    ///
+    /// `method` and `accept` decide whether a missing path receives the SPA
+    /// fallback; see `response_headers` for the matching cache headers.
+    #[pyo3(signature = (path, if_none_match=None, method="GET", accept=None))]
     pub fn serve_file(
         &self,
         path: &str,
         if_none_match: Option<&str>,
+        method: &str,
+        accept: Option<&str>,
     ) -> PyResult<(Vec<u8>, String, String, u16)> {
-        // Strip prefix from path
-        let file_path = path.strip_prefix(&self.prefix).unwrap_or(path);
-        let file_path = file_path.trim_start_matches('/');
-
-        // If path is empty, try index
-        let file_path = if file_path.is_empty() {
-            &self.index_file
-        } else {
-            file_path
-        };
-
-        match self.serve(file_path) {
-            Ok(cached) => {
+        match self.resolve(path, method, accept) {
+            Ok((cached, _)) => {
                 // Check If-None-Match for conditional requests
                 if let Some(inm) = if_none_match {
                     if inm == cached.etag {
@@ -278,27 +854,52 @@ impl StaticFileHandler {
                 }
                 Ok((cached.as_bytes().to_vec(), cached.content_type.clone(), cached.etag.clone(), 200))
             }
-            Err(StaticFileError::NotFound) if self.spa_mode => {
-                // SPA fallback: serve index.html
-                match self.serve(&self.index_file) {
-                    Ok(cached) => Ok((cached.as_bytes().to_vec(), cached.content_type.clone(), cached.etag.clone(), 200)),
-                    Err(_) => Err(pyo3::exceptions::PyFileNotFoundError::new_err("index.html not found")),
-                }
-            }
             Err(e) => Err(pyo3::exceptions::PyFileNotFoundError::new_err(e.to_string())),
         }
     }
 
+    /// Response headers for a path, as a list of (name, value) pairs
+    ///
+    /// The SPA fallback document is always sent with `no-cache` so a new
+    /// deployment is picked up on the next navigation.
+    #[pyo3(signature = (path, method="GET", accept=None))]
+    pub fn response_headers(
+        &self,
+        path: &str,
+        method: &str,
+        accept: Option<&str>,
+    ) -> PyResult<Vec<(String, String)>> {
+        let (cached, is_fallback) = self
+            .resolve(path, method, accept)
+            .map_err(|e| pyo3::exceptions::PyFileNotFoundError::new_err(e.to_string()))?;
+
+        let cache_control = if is_fallback {
+            "no-cache, no-store, must-revalidate".to_string()
+        } else if let Some(max_age) = self.cache_max_age {
+            format!("public, max-age={}", max_age)
+        } else {
+            "no-cache".to_string()
+        };
+
+        Ok(vec![
+            ("Content-Type".to_string(), cached.content_type.clone()),
+            ("ETag".to_string(), cached.etag.clone()),
+            ("Cache-Control".to_string(), cache_control),
+        ])
+    }
+
     /// Get the URL prefix
     #[getter]
     pub fn prefix(&self) -> &str {
         &self.prefix
     }
 
-    /// Get the root directory path
+    /// Get the root directory path, `None` for a bundle alone
     #[getter]
-    pub fn directory(&self) -> String {
-        self.root_path.to_string_lossy().to_string()
+    pub fn directory(&self) -> Option<String> {
+        self.root_path
+            .as_ref()
+            .map(|root| root.to_string_lossy().to_string())
     }
 
     /// Clear the file cache
@@ -306,12 +907,25 @@ impl StaticFileHandler {
         self.clear_cache();
     }
 
+    /// File cache counters: `entries`, `bytes` (including the per-entry
+    /// overhead), `max_bytes`, `hits`, `misses` and `evictions`
+    pub fn cache_stats<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        self.cache.stats().to_dict(py)
+    }
+
+    /// Paths held in the file cache, least recently served first
+    pub fn cached_paths(&self) -> Vec<String> {
+        self.cache.keys()
+    }
+
     fn __repr__(&self) -> String {
+        let directory = match &self.root_path {
+            Some(root) => format!("'{}'", root.display()),
+            None => "None".to_string(),
+        };
         format!(
-            "StaticFileHandler(directory='{}', prefix='{}', spa={})",
-            self.root_path.display(),
-            self.prefix,
-            self.spa_mode
+            "StaticFileHandler(directory={}, prefix='{}', spa={})",
+            directory, self.prefix, self.spa_mode
         )
     }
 }
@@ -84,3 +84,10 @@ impl GrpcServer {
         )
     }
 }
+
+/// Register gRPC classes.
+pub fn register_all(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<GrpcConfig>()?;
+    m.add_class::<GrpcServer>()?;
+    Ok(())
+}
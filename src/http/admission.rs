@@ -0,0 +1,379 @@
+//! Connection admission in the accept loop.
+//!
+//! Every accepted connection is counted before any byte of it is read. One
+//! beyond `max_connections` (open connections of the worker) or beyond
+//! `max_connections_per_ip` (open connections from one peer address) is
+//! closed at once. Addresses in the exempt CIDRs, such as health checkers
+//! and load balancers, are not subject to the per-IP cap.
+//!
+//! `header_read_timeout` bounds how long a client may take to send the
+//! headers of a request, which is what a slowloris client drags out: the
+//! clock starts with the connection for the first request, and with the
+//! first byte of each later one, so idle keep-alive connections are left
+//! alone. A connection missing the deadline is closed.
+//!
+//! Limits apply per worker process. Rejections are counted by reason in
+//! `ServerMetrics`.
+
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use tokio::time::Sleep;
+
+/// Default `header_read_timeout` in seconds
+pub const DEFAULT_HEADER_READ_TIMEOUT: f64 = 30.0;
+
+/// Open connections of this worker before new ones are closed (0 for no cap)
+static MAX_CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
+/// Open connections from one address before new ones are closed (0 for no cap)
+static MAX_PER_IP: AtomicUsize = AtomicUsize::new(0);
+/// Time allowed for a request's headers in ms (0 for no limit)
+static HEADER_TIMEOUT_MS: AtomicU64 = AtomicU64::new(0);
+static EXEMPT: RwLock<Option<Arc<Vec<Cidr>>>> = RwLock::new(None);
+
+static OPEN: AtomicUsize = AtomicUsize::new(0);
+static PER_IP: LazyLock<DashMap<IpAddr, usize>> = LazyLock::new(DashMap::new);
+static REJECTED: [AtomicU64; 3] = [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)];
+
+/// Why a connection was closed by the server
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rejection {
+    MaxConnections,
+    PerIp,
+    HeaderTimeout,
+}
+
+impl Rejection {
+    pub const ALL: [Rejection; 3] = [
+        Rejection::MaxConnections,
+        Rejection::PerIp,
+        Rejection::HeaderTimeout,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Rejection::MaxConnections => "max_connections",
+            Rejection::PerIp => "max_connections_per_ip",
+            Rejection::HeaderTimeout => "header_read_timeout",
+        }
+    }
+
+    fn count(self) {
+        REJECTED[self as usize].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Connections closed for `reason` since the process started
+pub fn rejected_total(reason: Rejection) -> u64 {
+    REJECTED[reason as usize].load(Ordering::Relaxed)
+}
+
+/// An IPv4 or IPv6 network, `10.0.0.0/8` or `fd00::/8`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    /// Parse a network, or a single address as a /32 (/128)
+    pub fn parse(text: &str) -> Option<Self> {
+        let (addr, prefix) = match text.trim().split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix.parse::<u8>().ok()?)),
+            None => (text.trim(), None),
+        };
+        let network: IpAddr = addr.parse().ok()?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max);
+        (prefix <= max).then_some(Self { network, prefix })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for Cidr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix)
+    }
+}
+
+/// Limits given to the `Server` constructor
+#[derive(Clone, Debug)]
+pub struct AdmissionConfig {
+    pub max_connections_per_ip: Option<usize>,
+    pub header_read_timeout: Option<Duration>,
+    pub exempt: Vec<Cidr>,
+}
+
+impl AdmissionConfig {
+    pub fn from_options(
+        max_connections_per_ip: Option<usize>,
+        header_read_timeout: Option<f64>,
+        exempt: Option<Vec<String>>,
+    ) -> PyResult<Self> {
+        if max_connections_per_ip == Some(0) {
+            return Err(PyValueError::new_err(
+                "max_connections_per_ip must be a positive integer or None",
+            ));
+        }
+        let header_read_timeout = match header_read_timeout {
+            Some(secs) if !secs.is_finite() || secs <= 0.0 => {
+                return Err(PyValueError::new_err(
+                    "header_read_timeout must be a positive number of seconds or None",
+                ))
+            }
+            secs => secs.map(Duration::from_secs_f64),
+        };
+        let exempt = exempt
+            .unwrap_or_default()
+            .iter()
+            .map(|text| {
+                Cidr::parse(text).ok_or_else(|| {
+                    PyValueError::new_err(format!(
+                        "invalid network '{}' in connection_limit_exempt",
+                        text
+                    ))
+                })
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(Self {
+            max_connections_per_ip,
+            header_read_timeout,
+            exempt,
+        })
+    }
+}
+
+/// Install the constructor limits for this process
+pub fn configure(config: &AdmissionConfig) {
+    set_max_per_ip(config.max_connections_per_ip);
+    set_header_read_timeout(config.header_read_timeout);
+    *EXEMPT.write() = Some(Arc::new(config.exempt.clone()));
+}
+
+pub fn set_max_connections(max: Option<usize>) {
+    MAX_CONNECTIONS.store(max.unwrap_or(0), Ordering::Relaxed);
+}
+
+pub fn set_max_per_ip(max: Option<usize>) {
+    MAX_PER_IP.store(max.unwrap_or(0), Ordering::Relaxed);
+}
+
+pub fn set_header_read_timeout(timeout: Option<Duration>) {
+    let ms = timeout.map_or(0, |t| (t.as_millis() as u64).max(1));
+    HEADER_TIMEOUT_MS.store(ms, Ordering::Relaxed);
+}
+
+fn limit(value: usize) -> Option<usize> {
+    (value != 0).then_some(value)
+}
+
+pub fn max_connections() -> Option<usize> {
+    limit(MAX_CONNECTIONS.load(Ordering::Relaxed))
+}
+
+pub fn max_connections_per_ip() -> Option<usize> {
+    limit(MAX_PER_IP.load(Ordering::Relaxed))
+}
+
+pub fn header_read_timeout() -> Option<Duration> {
+    match HEADER_TIMEOUT_MS.load(Ordering::Relaxed) {
+        0 => None,
+        ms => Some(Duration::from_millis(ms)),
+    }
+}
+
+/// Open connections of this worker
+pub fn open_connections() -> usize {
+    OPEN.load(Ordering::Relaxed)
+}
+
+fn is_exempt(ip: IpAddr) -> bool {
+    EXEMPT
+        .read()
+        .as_ref()
+        .is_some_and(|exempt| exempt.iter().any(|cidr| cidr.contains(ip)))
+}
+
+/// A connection let in; releases its place when dropped
+#[derive(Debug)]
+pub struct Admission {
+    /// Counted in the per-IP table
+    ip: Option<IpAddr>,
+}
+
+impl Drop for Admission {
+    fn drop(&mut self) {
+        OPEN.fetch_sub(1, Ordering::Relaxed);
+        if let Some(ip) = self.ip {
+            if let Some(mut count) = PER_IP.get_mut(&ip) {
+                *count = count.saturating_sub(1);
+            }
+            PER_IP.remove_if(&ip, |_, count| *count == 0);
+        }
+    }
+}
+
+/// Count a connection from `peer`, or say why it must be closed
+pub fn admit(peer: SocketAddr) -> Result<Admission, Rejection> {
+    // Limits changed in another worker apply before the next connection
+    crate::core::live_config::sync();
+    let open = OPEN.fetch_add(1, Ordering::Relaxed);
+    // Dropping releases the place taken above on every early return
+    let mut admission = Admission { ip: None };
+    if max_connections().is_some_and(|max| open >= max) {
+        Rejection::MaxConnections.count();
+        return Err(Rejection::MaxConnections);
+    }
+    let ip = peer.ip().to_canonical();
+    if is_exempt(ip) {
+        return Ok(admission);
+    }
+    // Counted even without a cap, so one enabled at runtime sees every
+    // open connection
+    let mut count = PER_IP.entry(ip).or_insert(0);
+    if max_connections_per_ip().is_some_and(|max| *count >= max) {
+        drop(count);
+        PER_IP.remove_if(&ip, |_, count| *count == 0);
+        Rejection::PerIp.count();
+        return Err(Rejection::PerIp);
+    }
+    *count += 1;
+    admission.ip = Some(ip);
+    Ok(admission)
+}
+
+const HEADER_END: &[u8; 4] = b"\r\n\r\n";
+
+enum Phase {
+    /// Reading headers until the deadline
+    Headers(Pin<Box<Sleep>>),
+    /// Between requests; the clock starts with the next byte
+    Idle,
+    /// Reading a body, or waiting for the response to it
+    Body,
+}
+
+/// Deadline for the headers of each request on one connection
+pub struct HeaderTimer {
+    timeout: Duration,
+    phase: Phase,
+    /// Bytes of `\r\n\r\n` matched at the end of the last read
+    matched: usize,
+    /// HTTP/2 connections are only timed until the preface
+    http1: bool,
+}
+
+impl HeaderTimer {
+    /// A timer started now, None when there is no timeout
+    pub fn start(http1: bool) -> Option<Self> {
+        let timeout = header_read_timeout()?;
+        Some(Self {
+            timeout,
+            phase: Phase::Headers(Box::pin(tokio::time::sleep(timeout))),
+            matched: 0,
+            http1,
+        })
+    }
+
+    fn expired(peer: Option<SocketAddr>) -> io::Error {
+        Rejection::HeaderTimeout.count();
+        crate::hlog_debug!(
+            "Closing connection from {}: request headers not received in time",
+            peer.map_or_else(|| "unknown".to_string(), |p| p.to_string())
+        );
+        io::Error::new(
+            io::ErrorKind::TimedOut,
+            "request headers not received in time",
+        )
+    }
+
+    /// Check the deadline before a read; an error closes the connection
+    pub fn before_read(&mut self, peer: Option<SocketAddr>) -> io::Result<()> {
+        match &self.phase {
+            Phase::Headers(sleep) if sleep.is_elapsed() => Err(Self::expired(peer)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Look at the bytes a read returned for the end of the headers
+    pub fn after_read(&mut self, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        if matches!(self.phase, Phase::Idle) {
+            self.phase = Phase::Headers(Box::pin(tokio::time::sleep(self.timeout)));
+            self.matched = 0;
+        }
+        if !matches!(self.phase, Phase::Headers(_)) {
+            return;
+        }
+        for &byte in data {
+            self.matched = if byte == HEADER_END[self.matched] {
+                self.matched + 1
+            } else if byte == HEADER_END[0] {
+                1
+            } else {
+                0
+            };
+            if self.matched == HEADER_END.len() {
+                self.phase = Phase::Body;
+                self.matched = 0;
+                return;
+            }
+        }
+    }
+
+    /// Wait for the deadline while a read is pending
+    pub fn poll_expired(
+        &mut self,
+        cx: &mut Context<'_>,
+        peer: Option<SocketAddr>,
+    ) -> io::Result<()> {
+        match &mut self.phase {
+            Phase::Headers(sleep) => match sleep.as_mut().poll(cx) {
+                Poll::Ready(()) => Err(Self::expired(peer)),
+                Poll::Pending => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Note a write; the next request's clock starts once a final
+    /// (non-1xx) HTTP/1 response has gone out
+    pub fn on_write(&mut self, data: &[u8]) {
+        if !self.http1 || !matches!(self.phase, Phase::Body) {
+            return;
+        }
+        if is_final_response(data) {
+            self.phase = Phase::Idle;
+        }
+    }
+}
+
+/// Whether a write starts the head of a final (non-1xx) HTTP/1 response
+pub fn is_final_response(data: &[u8]) -> bool {
+    data.starts_with(b"HTTP/1.") && data.get(9) != Some(&b'1')
+}
@@ -0,0 +1,144 @@
+//! Host header validation.
+//!
+//! When the server is given an `allowed_hosts` list, every request routed to
+//! the application must name one of those hosts in `Host` (or the HTTP/2
+//! `:authority`). The check runs in Rust before middleware and routing, so a
+//! request with a forged Host never reaches handler code. Health probe routes
+//! are served by their own Axum routes and are not subject to it.
+
+use ahash::AHashSet;
+use axum::body::Body;
+use axum::http::{header, Request, Response, StatusCode, Version};
+use parking_lot::RwLock;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::sync::Arc;
+
+use crate::routing::route::normalize_host;
+
+static ALLOWED_HOSTS: RwLock<Option<Arc<AllowedHosts>>> = RwLock::new(None);
+
+/// Hostnames a server answers for
+#[derive(Clone, Debug, Default)]
+pub struct AllowedHosts {
+    /// Patterns as configured, for introspection
+    patterns: Vec<String>,
+    exact: AHashSet<String>,
+    /// `*.example.com` stored as `.example.com`
+    suffixes: Vec<String>,
+    /// A bare `*` pattern allows every host
+    any: bool,
+}
+
+impl AllowedHosts {
+    /// Parse exact hostnames, IP literals and `*.domain` wildcards
+    pub fn new(patterns: Vec<String>) -> PyResult<Self> {
+        let mut hosts = Self::default();
+        for pattern in &patterns {
+            let trimmed = pattern.trim();
+            if trimmed == "*" {
+                hosts.any = true;
+            } else if let Some(domain) = trimmed.strip_prefix("*.") {
+                let domain = normalize_pattern(domain);
+                if domain.is_empty() || domain.contains('*') {
+                    return Err(invalid_pattern(pattern));
+                }
+                hosts.suffixes.push(format!(".{}", domain));
+            } else {
+                let host = normalize_pattern(trimmed);
+                if host.is_empty() || host.contains('*') {
+                    return Err(invalid_pattern(pattern));
+                }
+                hosts.exact.insert(host);
+            }
+        }
+        hosts.patterns = patterns;
+        Ok(hosts)
+    }
+
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    /// Whether a Host header value (port and case ignored) is allowed
+    pub fn is_allowed(&self, host: &str) -> bool {
+        if self.any {
+            return true;
+        }
+        let host = normalize_host(host);
+        if host.is_empty() {
+            return false;
+        }
+        self.exact.contains(&host)
+            || self
+                .suffixes
+                .iter()
+                .any(|suffix| host.len() > suffix.len() && host.ends_with(suffix.as_str()))
+    }
+}
+
+/// Lowercase a configured host; bare IPv6 literals are kept whole
+fn normalize_pattern(pattern: &str) -> String {
+    if !pattern.starts_with('[') && pattern.matches(':').count() > 1 {
+        pattern.to_ascii_lowercase()
+    } else {
+        normalize_host(pattern)
+    }
+}
+
+fn invalid_pattern(pattern: &str) -> PyErr {
+    PyValueError::new_err(format!(
+        "Invalid allowed host '{}': use a hostname, an IP address, '*.domain' or '*'",
+        pattern
+    ))
+}
+
+/// Install the allowed hosts for this process (`None` disables the check)
+pub fn install(hosts: Option<AllowedHosts>) {
+    *ALLOWED_HOSTS.write() = hosts.map(Arc::new);
+}
+
+/// Host named by the request: the HTTP/2 authority, else the Host header
+pub fn request_host(req: &Request<Body>) -> Option<&str> {
+    req.uri()
+        .authority()
+        .map(|a| a.host())
+        .or_else(|| req.headers().get(header::HOST)?.to_str().ok())
+        .filter(|h| !h.trim().is_empty())
+}
+
+/// Whether the installed list allows `host`; false when there is no list
+pub fn allows(host: &str) -> bool {
+    ALLOWED_HOSTS
+        .read()
+        .as_ref()
+        .is_some_and(|allowed| allowed.is_allowed(host))
+}
+
+/// A 400 response if the request's host is not allowed, `None` otherwise.
+///
+/// An absent or empty Host is rejected on HTTP/1.1, where it is mandatory;
+/// HTTP/2 requests carry the host in the URI authority instead.
+pub fn reject_disallowed(req: &Request<Body>) -> Option<Response<Body>> {
+    let guard = ALLOWED_HOSTS.read();
+    let allowed = guard.as_ref()?;
+
+    let ok = match request_host(req) {
+        Some(host) => allowed.is_allowed(host),
+        None => req.version() < Version::HTTP_11,
+    };
+    if ok {
+        return None;
+    }
+    Some(invalid_host())
+}
+
+/// The 400 sent for a host outside the list
+pub fn invalid_host() -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::BAD_REQUEST)
+        .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .header(header::CONNECTION, "close")
+        .body(Body::from("Invalid Host header"))
+        .unwrap()
+}
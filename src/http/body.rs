@@ -0,0 +1,139 @@
+//! Request body decoding: charset transcoding and the body error types.
+//!
+//! Errors carry a `status_code` class attribute so the default exception
+//! handler can answer with 400/413/415 instead of a generic 500.
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::borrow::Cow;
+
+create_exception!(
+    _hypern,
+    RequestBodyError,
+    PyValueError,
+    "The request body could not be read (maps to 400)."
+);
+create_exception!(
+    _hypern,
+    UnsupportedMediaTypeError,
+    RequestBodyError,
+    "The body's content type or charset is not accepted (maps to 415)."
+);
+create_exception!(
+    _hypern,
+    PayloadTooLargeError,
+    RequestBodyError,
+    "The body exceeds the configured size limit (maps to 413)."
+);
+create_exception!(
+    _hypern,
+    BodyDecodeError,
+    RequestBodyError,
+    "The body is not valid for its declared encoding or format (maps to 400)."
+);
+
+/// Extract the `charset` parameter from a Content-Type value, lowercased
+pub fn content_type_charset(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"').to_ascii_lowercase())
+    })
+}
+
+/// Transcode `bytes` from `charset` to UTF-8.
+///
+/// UTF-8 input is borrowed; a leading UTF-8 BOM is stripped. Supported
+/// charsets are UTF-8/ASCII, ISO-8859-1 and UTF-16 (LE/BE, BOM-detected).
+pub fn decode_to_utf8<'a>(bytes: &'a [u8], charset: Option<&str>) -> PyResult<Cow<'a, [u8]>> {
+    let Some(charset) = charset else {
+        return Ok(Cow::Borrowed(strip_utf8_bom(bytes)));
+    };
+    match charset {
+        "utf-8" | "utf8" | "us-ascii" | "ascii" => {
+            let bytes = strip_utf8_bom(bytes);
+            std::str::from_utf8(bytes).map_err(|e| {
+                BodyDecodeError::new_err(format!(
+                    "Request body is not valid {} (invalid byte at offset {})",
+                    charset,
+                    e.valid_up_to()
+                ))
+            })?;
+            Ok(Cow::Borrowed(bytes))
+        }
+        "iso-8859-1" | "latin-1" | "latin1" | "l1" => Ok(Cow::Owned(
+            bytes
+                .iter()
+                .map(|&b| b as char)
+                .collect::<String>()
+                .into_bytes(),
+        )),
+        "utf-16" | "utf-16le" | "utf-16be" => decode_utf16(bytes, charset).map(Cow::Owned),
+        other => Err(UnsupportedMediaTypeError::new_err(format!(
+            "Unsupported request body charset '{}'",
+            other
+        ))),
+    }
+}
+
+fn strip_utf8_bom(bytes: &[u8]) -> &[u8] {
+    bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes)
+}
+
+fn decode_utf16(bytes: &[u8], charset: &str) -> PyResult<Vec<u8>> {
+    // A BOM wins over the declared byte order; plain "utf-16" defaults to big-endian
+    let (little_endian, body) = match bytes {
+        [0xFF, 0xFE, rest @ ..] => (true, rest),
+        [0xFE, 0xFF, rest @ ..] => (false, rest),
+        _ => (charset == "utf-16le", bytes),
+    };
+    if body.len() % 2 != 0 {
+        return Err(BodyDecodeError::new_err(format!(
+            "Request body is not valid {} (odd number of bytes)",
+            charset
+        )));
+    }
+    let units: Vec<u16> = body
+        .chunks_exact(2)
+        .map(|pair| {
+            if little_endian {
+                u16::from_le_bytes([pair[0], pair[1]])
+            } else {
+                u16::from_be_bytes([pair[0], pair[1]])
+            }
+        })
+        .collect();
+    String::from_utf16(&units)
+        .map(String::into_bytes)
+        .map_err(|_| {
+            BodyDecodeError::new_err(format!(
+                "Request body is not valid {} (unpaired surrogate)",
+                charset
+            ))
+        })
+}
+
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    let errors = [
+        ("RequestBodyError", py.get_type::<RequestBodyError>(), 400),
+        (
+            "UnsupportedMediaTypeError",
+            py.get_type::<UnsupportedMediaTypeError>(),
+            415,
+        ),
+        (
+            "PayloadTooLargeError",
+            py.get_type::<PayloadTooLargeError>(),
+            413,
+        ),
+        ("BodyDecodeError", py.get_type::<BodyDecodeError>(), 400),
+    ];
+    for (name, ty, status) in errors {
+        ty.setattr("status_code", status)?;
+        m.add(name, ty)?;
+    }
+    Ok(())
+}
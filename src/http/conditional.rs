@@ -0,0 +1,124 @@
+//! Conditional GET: entity tags and `If-None-Match` (RFC 9110 §8.8.3, §13.1.2).
+//!
+//! `If-None-Match` compares tags weakly: `W/"v1"` and `"v1"` match, since
+//! both name the same representation as far as a cache is concerned. The
+//! header holds a list of tags or `*`, which matches any current
+//! representation.
+
+use axum::body::Body;
+use axum::http::{header, HeaderMap, HeaderValue, Response, StatusCode};
+
+/// Response extension asking the dispatch layer to answer `status` when the
+/// request's `If-None-Match` matches the response's ETag
+/// (`Response.with_etag`)
+#[derive(Clone, Copy, Debug)]
+pub struct ConditionalEtag(pub u16);
+
+/// Headers a 304 repeats from the response it stands for (RFC 9110 §15.4.5)
+const NOT_MODIFIED_HEADERS: [header::HeaderName; 6] = [
+    header::CACHE_CONTROL,
+    header::CONTENT_LOCATION,
+    header::DATE,
+    header::ETAG,
+    header::EXPIRES,
+    header::VARY,
+];
+
+/// The tags of an `If-None-Match` value, as sent: `"a"`, `W/"b"` or `*`.
+/// Commas inside a quoted tag do not split it.
+pub fn parse_list(value: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in value.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            ',' if !quoted => {
+                push_tag(&mut tags, &current);
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+    push_tag(&mut tags, &current);
+    tags
+}
+
+fn push_tag(tags: &mut Vec<String>, tag: &str) {
+    let tag = tag.trim();
+    if !tag.is_empty() {
+        tags.push(tag.to_string());
+    }
+}
+
+/// `etag` as a header value: quoted unless it already is, weak or strong
+pub fn quote(etag: &str) -> String {
+    if etag.starts_with('"') || etag.starts_with("W/\"") {
+        etag.to_string()
+    } else {
+        format!("\"{}\"", etag)
+    }
+}
+
+/// The tag without its weakness indicator
+fn opaque(tag: &str) -> &str {
+    tag.strip_prefix("W/").unwrap_or(tag)
+}
+
+/// Weak comparison: the opaque tags match, whether either is weak or not
+pub fn weak_eq(a: &str, b: &str) -> bool {
+    opaque(a) == opaque(b)
+}
+
+/// Whether an `If-None-Match` value matches the current `etag`; `*`
+/// matches any representation
+pub fn none_match(if_none_match: &str, etag: &str) -> bool {
+    parse_list(if_none_match)
+        .iter()
+        .any(|tag| tag == "*" || weak_eq(tag, etag))
+}
+
+/// 304 Not Modified for the representation tagged `etag`
+pub fn not_modified(etag: &str) -> Response<Body> {
+    let mut res = Response::builder()
+        .status(StatusCode::NOT_MODIFIED)
+        .body(Body::empty())
+        .unwrap();
+    if let Ok(value) = HeaderValue::from_str(etag) {
+        res.headers_mut().insert(header::ETAG, value);
+    }
+    res
+}
+
+/// Answer the status a handler asked for with `Response.with_etag` when
+/// `if_none_match` matches the response's ETag; the body is dropped and
+/// only the headers a 304 carries are kept
+pub fn apply(res: Response<Body>, if_none_match: Option<&str>) -> Response<Body> {
+    let Some(ConditionalEtag(status)) = res.extensions().get::<ConditionalEtag>().copied() else {
+        return res;
+    };
+    let matched = if_none_match.is_some_and(|inm| {
+        res.headers()
+            .get(header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .is_some_and(|etag| none_match(inm, etag))
+    });
+    if !matched {
+        return res;
+    }
+    let mut headers = HeaderMap::new();
+    for name in NOT_MODIFIED_HEADERS {
+        for value in res.headers().get_all(&name) {
+            headers.append(name.clone(), value.clone());
+        }
+    }
+    let mut conditional = Response::builder()
+        .status(StatusCode::from_u16(status).unwrap_or(StatusCode::NOT_MODIFIED))
+        .body(Body::empty())
+        .unwrap();
+    *conditional.headers_mut() = headers;
+    conditional
+}
@@ -0,0 +1,399 @@
+//! Per-connection metadata and the worker listener that produces it.
+//!
+//! [`HypernListener`] accepts plaintext connections, or completes the TLS
+//! handshake first when a TLS config is installed. Each connection yields one
+//! [`ConnectionInfo`] which Axum attaches to every request on it, so the
+//! client certificate is parsed once per connection rather than per request.
+//! Connections are let in, and their request headers timed, by
+//! [`admission`](crate::http::admission); the size of HTTP/1 request heads
+//! is checked by [`header_limits`](crate::http::header_limits).
+
+use axum::extract::connect_info::Connected;
+use axum::serve::{IncomingStream, Listener};
+use rustls::ServerConfig;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+
+use crate::http::admission::{self, Admission, HeaderTimer};
+use crate::http::header_limits::{self, HeadGuard};
+use crate::http::tls::PeerCert;
+
+/// Handshakes taking longer than this are dropped
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Completed handshakes waiting for the server to pick them up
+const ACCEPT_BACKLOG: usize = 1024;
+
+#[derive(Debug)]
+struct Details {
+    tls: bool,
+    peer_addr: Option<SocketAddr>,
+    alpn_protocol: Option<String>,
+    peer_cert: Option<PeerCert>,
+}
+
+/// Transport details of the connection a request arrived on
+#[derive(Clone, Debug)]
+pub struct ConnectionInfo(Arc<Details>);
+
+impl ConnectionInfo {
+    pub fn plain(peer_addr: SocketAddr) -> Self {
+        Self(Arc::new(Details {
+            tls: false,
+            peer_addr: Some(peer_addr),
+            alpn_protocol: None,
+            peer_cert: None,
+        }))
+    }
+
+    fn tls(peer_addr: SocketAddr, stream: &TlsStream<TcpStream>) -> Self {
+        let (_, session) = stream.get_ref();
+        Self(Arc::new(Details {
+            tls: true,
+            peer_addr: Some(peer_addr),
+            alpn_protocol: session
+                .alpn_protocol()
+                .map(|p| String::from_utf8_lossy(p).into_owned()),
+            peer_cert: session
+                .peer_certificates()
+                .and_then(|chain| chain.first())
+                .and_then(|cert| PeerCert::parse(cert)),
+        }))
+    }
+
+    /// `"https"` for TLS connections, `"http"` otherwise
+    pub fn scheme(&self) -> &'static str {
+        if self.0.tls {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    pub fn is_tls(&self) -> bool {
+        self.0.tls
+    }
+
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.0.peer_addr
+    }
+
+    /// Protocol negotiated via ALPN (`"h2"`, `"http/1.1"`), TLS only
+    pub fn alpn_protocol(&self) -> Option<&str> {
+        self.0.alpn_protocol.as_deref()
+    }
+
+    /// Verified client certificate, when client auth is configured
+    pub fn peer_cert(&self) -> Option<&PeerCert> {
+        self.0.peer_cert.as_ref()
+    }
+}
+
+impl Connected<IncomingStream<'_, HypernListener>> for ConnectionInfo {
+    fn connect_info(stream: IncomingStream<'_, HypernListener>) -> Self {
+        stream.remote_addr().clone()
+    }
+}
+
+enum Transport {
+    Plain(TcpStream),
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+impl Transport {
+    fn pinned(&mut self) -> Pin<&mut (dyn AsyncReadWrite + Unpin)> {
+        match self {
+            Transport::Plain(s) => Pin::new(s),
+            Transport::Tls(s) => Pin::new(s.as_mut()),
+        }
+    }
+}
+
+/// A plaintext or TLS client connection, holding its place among the
+/// worker's open connections
+pub struct HypernStream {
+    io: Transport,
+    peer_addr: Option<SocketAddr>,
+    header_timer: Option<HeaderTimer>,
+    /// HTTP/1 only
+    head_guard: Option<HeadGuard>,
+    _admission: Admission,
+}
+
+impl HypernStream {
+    fn new(io: Transport, info: &ConnectionInfo, admission: Admission) -> Self {
+        let http1 = info.alpn_protocol() != Some("h2");
+        Self {
+            io,
+            peer_addr: info.peer_addr(),
+            header_timer: HeaderTimer::start(http1),
+            head_guard: http1.then(HeadGuard::start).flatten(),
+            _admission: admission,
+        }
+    }
+
+    fn io(self: Pin<&mut Self>) -> Pin<&mut (dyn AsyncReadWrite + Unpin)> {
+        self.get_mut().io.pinned()
+    }
+
+    /// Write the reply to a head over the limits, shut our side down and
+    /// discard what the client still sends, then report end of stream so
+    /// hyper drops the connection
+    fn poll_reject(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let peer = this.peer_addr;
+        let Some(rejected) = this.head_guard.as_mut().and_then(HeadGuard::rejected) else {
+            return Poll::Ready(Ok(()));
+        };
+        let mut io = this.io.pinned();
+        while !rejected.pending_reply().is_empty() {
+            let n = ready!(io.as_mut().poll_write(cx, rejected.pending_reply()))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            rejected.written += n;
+        }
+        if !rejected.shut_down {
+            ready!(io.as_mut().poll_flush(cx))?;
+            ready!(io.as_mut().poll_shutdown(cx))?;
+            rejected.shut_down = true;
+        }
+        let mut scratch = [0u8; 8192];
+        while rejected.discarded < header_limits::LINGER_BYTES {
+            let mut buf = ReadBuf::new(&mut scratch);
+            match io.as_mut().poll_read(cx, &mut buf) {
+                Poll::Ready(Ok(())) if buf.filled().is_empty() => break,
+                Poll::Ready(Ok(())) => rejected.discarded += buf.filled().len(),
+                // Gone already; nothing left to protect the reply from
+                Poll::Ready(Err(_)) => break,
+                Poll::Pending => {
+                    if let Some(timer) = this.header_timer.as_mut() {
+                        timer.poll_expired(cx, peer)?;
+                    }
+                    return Poll::Pending;
+                }
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+trait AsyncReadWrite: AsyncRead + AsyncWrite {}
+
+impl<T: AsyncRead + AsyncWrite> AsyncReadWrite for T {}
+
+impl AsyncRead for HypernStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if self.head_guard.as_ref().is_some_and(HeadGuard::is_rejected) {
+            return self.poll_reject(cx);
+        }
+        let peer = self.peer_addr;
+        if let Some(timer) = self.header_timer.as_mut() {
+            timer.before_read(peer)?;
+        }
+        let filled = buf.filled().len();
+        let result = self.as_mut().io().poll_read(cx, buf);
+        if let (Poll::Ready(Ok(())), Some(guard)) = (&result, self.head_guard.as_mut()) {
+            if !guard.after_read(&buf.filled()[filled..]) {
+                buf.set_filled(filled);
+                return self.poll_reject(cx);
+            }
+        }
+        if let Some(timer) = self.header_timer.as_mut() {
+            match &result {
+                Poll::Ready(Ok(())) => timer.after_read(&buf.filled()[filled..]),
+                Poll::Pending => timer.poll_expired(cx, peer)?,
+                Poll::Ready(Err(_)) => {}
+            }
+        }
+        result
+    }
+}
+
+impl AsyncWrite for HypernStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if let Some(timer) = self.header_timer.as_mut() {
+            timer.on_write(buf);
+        }
+        if let Some(guard) = self.head_guard.as_mut() {
+            guard.on_write(buf);
+        }
+        self.io().poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        if let Some(first) = bufs.iter().find(|buf| !buf.is_empty()) {
+            if let Some(timer) = self.header_timer.as_mut() {
+                timer.on_write(first);
+            }
+            if let Some(guard) = self.head_guard.as_mut() {
+                guard.on_write(first);
+            }
+        }
+        self.io().poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        match &self.io {
+            Transport::Plain(s) => s.is_write_vectored(),
+            Transport::Tls(s) => s.is_write_vectored(),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.io().poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.io().poll_shutdown(cx)
+    }
+}
+
+/// Worker listener yielding [`ConnectionInfo`] as the connection address
+pub enum HypernListener {
+    Plain(TcpListener),
+    /// Handshakes run in their own tasks so a slow client cannot stall accept
+    Tls {
+        handshaken: mpsc::Receiver<(HypernStream, ConnectionInfo)>,
+        local_addr: SocketAddr,
+    },
+}
+
+impl HypernListener {
+    /// Wrap a bound listener; must be called inside the Tokio runtime when
+    /// `tls` is set.
+    pub fn new(listener: TcpListener, tls: Option<Arc<ServerConfig>>) -> io::Result<Self> {
+        let Some(config) = tls else {
+            return Ok(HypernListener::Plain(listener));
+        };
+        let local_addr = listener.local_addr()?;
+        let (tx, rx) = mpsc::channel(ACCEPT_BACKLOG);
+        tokio::spawn(accept_tls(listener, TlsAcceptor::from(config), tx));
+        Ok(HypernListener::Tls {
+            handshaken: rx,
+            local_addr,
+        })
+    }
+}
+
+async fn accept_tls(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    tx: mpsc::Sender<(HypernStream, ConnectionInfo)>,
+) {
+    loop {
+        let accepted = tokio::select! {
+            accepted = TcpListener::accept(&listener) => accepted,
+            // The server stopped; release the socket
+            _ = tx.closed() => return,
+        };
+        let (stream, peer_addr) = match accepted {
+            Ok(conn) => conn,
+            Err(err) => {
+                handle_accept_error(err).await;
+                continue;
+            }
+        };
+        // Rejected connections are closed before the handshake
+        let Ok(admission) = admission::admit(peer_addr) else {
+            continue;
+        };
+        crate::socket::configure_stream(&stream);
+        let acceptor = acceptor.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                Ok(Ok(tls)) => {
+                    let info = ConnectionInfo::tls(peer_addr, &tls);
+                    let stream = HypernStream::new(Transport::Tls(Box::new(tls)), &info, admission);
+                    let _ = tx.send((stream, info)).await;
+                }
+                Ok(Err(err)) => {
+                    crate::hlog_debug!("TLS handshake with {} failed: {}", peer_addr, err);
+                }
+                Err(_) => {
+                    crate::hlog_debug!("TLS handshake with {} timed out", peer_addr);
+                }
+            }
+        });
+    }
+}
+
+/// Per-connection errors are the client's problem; anything else (e.g. out of
+/// file descriptors) gets a short back-off, as Axum's own listener does
+async fn handle_accept_error(err: io::Error) {
+    if matches!(
+        err.kind(),
+        io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::ConnectionReset
+    ) {
+        return;
+    }
+    crate::hlog_error!("Accept error: {}", err);
+    tokio::time::sleep(Duration::from_secs(1)).await;
+}
+
+impl Listener for HypernListener {
+    type Io = HypernStream;
+    type Addr = ConnectionInfo;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self {
+            HypernListener::Plain(listener) => loop {
+                match TcpListener::accept(listener).await {
+                    Ok((stream, peer_addr)) => {
+                        // Dropping the stream closes a rejected connection
+                        let Ok(admission) = admission::admit(peer_addr) else {
+                            continue;
+                        };
+                        crate::socket::configure_stream(&stream);
+                        let info = ConnectionInfo::plain(peer_addr);
+                        return (
+                            HypernStream::new(Transport::Plain(stream), &info, admission),
+                            info,
+                        );
+                    }
+                    Err(err) => handle_accept_error(err).await,
+                }
+            },
+            HypernListener::Tls { handshaken, .. } => match handshaken.recv().await {
+                Some(conn) => conn,
+                None => std::future::pending().await,
+            },
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        let addr = match self {
+            HypernListener::Plain(listener) => listener.local_addr()?,
+            HypernListener::Tls { local_addr, .. } => *local_addr,
+        };
+        Ok(ConnectionInfo(Arc::new(Details {
+            tls: matches!(self, HypernListener::Tls { .. }),
+            peer_addr: Some(addr),
+            alpn_protocol: None,
+            peer_cert: None,
+        })))
+    }
+}
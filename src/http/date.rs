@@ -0,0 +1,65 @@
+//! `Date` header value shared by every response.
+//!
+//! The value is formatted once a second by a tokio task instead of once per
+//! response. The task starts on the first response a process sends, so a
+//! forked worker runs its own.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use arc_swap::ArcSwap;
+use axum::http::HeaderValue;
+
+static CURRENT: OnceLock<ArcSwap<HeaderValue>> = OnceLock::new();
+/// Process whose runtime runs the refresh task
+static REFRESHER: AtomicU32 = AtomicU32::new(0);
+
+/// The current time as an IMF-fixdate (`Sun, 06 Nov 1994 08:49:37 GMT`)
+pub fn format_now() -> HeaderValue {
+    let text = chrono::Utc::now()
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string();
+    HeaderValue::from_str(&text).expect("an HTTP date is visible ASCII")
+}
+
+fn current() -> &'static ArcSwap<HeaderValue> {
+    CURRENT.get_or_init(|| ArcSwap::from_pointee(format_now()))
+}
+
+/// The `Date` header value for a response sent now
+#[inline]
+pub fn header_value() -> HeaderValue {
+    if REFRESHER.load(Ordering::Relaxed) != std::process::id() {
+        start_refresh();
+    }
+    HeaderValue::clone(&current().load())
+}
+
+/// Refresh the value at each whole second from the current tokio runtime;
+/// without one the value is refreshed on the next call made from a runtime
+fn start_refresh() {
+    let Ok(handle) = tokio::runtime::Handle::try_current() else {
+        current().store(Arc::new(format_now()));
+        return;
+    };
+    let pid = std::process::id();
+    if REFRESHER.swap(pid, Ordering::AcqRel) == pid {
+        return;
+    }
+    current().store(Arc::new(format_now()));
+    let into_second = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(Duration::ZERO, |now| {
+            Duration::from_nanos(now.subsec_nanos() as u64)
+        });
+    let first_tick = tokio::time::Instant::now() + (Duration::from_secs(1) - into_second);
+    handle.spawn(async move {
+        let mut ticks = tokio::time::interval_at(first_tick, Duration::from_secs(1));
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticks.tick().await;
+            current().store(Arc::new(format_now()));
+        }
+    });
+}
@@ -0,0 +1,207 @@
+//! Transparent request body decompression.
+//!
+//! When enabled on the server, bodies sent with `Content-Encoding: gzip`
+//! (or `x-gzip`) or `deflate` are inflated before the request reaches
+//! routing, so `Request.json()`, forms and route body limits all see the
+//! decoded bytes. Inflation stops at `max_decompressed_size` to guard against
+//! zip bombs and runs on the blocking pool, so a large body does not hold up
+//! other requests on the runtime; unknown encodings are rejected with 415.
+
+use axum::body::{to_bytes, Body, Bytes};
+use axum::http::{header, HeaderValue, Request, Response, StatusCode};
+use flate2::read::{DeflateDecoder, MultiGzDecoder, ZlibDecoder};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+use crate::http::error_envelope::{self, ErrorCode};
+
+/// Default cap on an inflated body; matches the transport body limit
+pub const DEFAULT_MAX_DECOMPRESSED_SIZE: usize = 10 * 1024 * 1024;
+/// Largest compressed body read before inflating
+const MAX_COMPRESSED_SIZE: usize = 10 * 1024 * 1024;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static MAX_DECOMPRESSED: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_DECOMPRESSED_SIZE);
+
+static DECOMPRESSED: AtomicU64 = AtomicU64::new(0);
+static COMPRESSED_BYTES: AtomicU64 = AtomicU64::new(0);
+static INFLATED_BYTES: AtomicU64 = AtomicU64::new(0);
+static REJECTED: AtomicU64 = AtomicU64::new(0);
+
+/// Enable or disable request decompression for this process
+pub fn configure(enabled: bool, max_decompressed_size: usize) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+    MAX_DECOMPRESSED.store(max_decompressed_size.max(1), Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn max_decompressed_size() -> usize {
+    MAX_DECOMPRESSED.load(Ordering::Relaxed)
+}
+
+#[derive(Clone, Copy)]
+enum Coding {
+    Gzip,
+    Deflate,
+}
+
+enum DecodeError {
+    TooLarge,
+    Unsupported(String),
+    Corrupt,
+}
+
+/// Inflate a compressed request body in place.
+///
+/// Returns the request unchanged when decompression is disabled or the body
+/// is not encoded; otherwise the body is replaced, `Content-Encoding` is
+/// removed and `Content-Length` updated. Errors become ready-made 400/413/415
+/// responses.
+pub async fn decode_request(req: Request<Body>) -> Result<Request<Body>, Response<Body>> {
+    if !is_enabled() || !req.headers().contains_key(header::CONTENT_ENCODING) {
+        return Ok(req);
+    }
+
+    let accept = error_envelope::accept(req.headers()).map(str::to_owned);
+    let accept = accept.as_deref();
+    let codings = match parse_codings(req.headers().get(header::CONTENT_ENCODING)) {
+        Ok(codings) => codings,
+        Err(err) => return Err(reject(err, accept)),
+    };
+
+    let (mut parts, body) = req.into_parts();
+    parts.headers.remove(header::CONTENT_ENCODING);
+    if codings.is_empty() {
+        // Only `identity`
+        return Ok(Request::from_parts(parts, body));
+    }
+
+    let compressed = match to_bytes(body, MAX_COMPRESSED_SIZE).await {
+        Ok(bytes) => bytes,
+        Err(_) => return Err(reject(DecodeError::TooLarge, accept)),
+    };
+
+    let limit = max_decompressed_size();
+    let input = compressed.clone();
+    let inflated = tokio::task::spawn_blocking(move || {
+        // Codings are listed in the order they were applied
+        codings
+            .into_iter()
+            .rev()
+            .try_fold(input, |data, coding| inflate(coding, &data, limit))
+    })
+    .await
+    .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()));
+    let data = match inflated {
+        Ok(data) => data,
+        Err(err) => return Err(reject(err, accept)),
+    };
+
+    DECOMPRESSED.fetch_add(1, Ordering::Relaxed);
+    COMPRESSED_BYTES.fetch_add(compressed.len() as u64, Ordering::Relaxed);
+    INFLATED_BYTES.fetch_add(data.len() as u64, Ordering::Relaxed);
+
+    parts
+        .headers
+        .insert(header::CONTENT_LENGTH, HeaderValue::from(data.len()));
+    Ok(Request::from_parts(parts, Body::from(data)))
+}
+
+fn parse_codings(value: Option<&HeaderValue>) -> Result<Vec<Coding>, DecodeError> {
+    let value = value
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| DecodeError::Unsupported("<invalid>".to_string()))?;
+
+    let mut codings = Vec::new();
+    for token in value.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        match token.to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => codings.push(Coding::Gzip),
+            "deflate" => codings.push(Coding::Deflate),
+            "identity" => {}
+            _ => return Err(DecodeError::Unsupported(token.to_string())),
+        }
+    }
+    Ok(codings)
+}
+
+fn inflate(coding: Coding, data: &[u8], limit: usize) -> Result<Bytes, DecodeError> {
+    let reader: Box<dyn Read + '_> = match coding {
+        Coding::Gzip => Box::new(MultiGzDecoder::new(data)),
+        // HTTP deflate is zlib-wrapped, but some clients send raw deflate
+        Coding::Deflate if is_zlib_header(data) => Box::new(ZlibDecoder::new(data)),
+        Coding::Deflate => Box::new(DeflateDecoder::new(data)),
+    };
+
+    // Read one byte past the cap so an exactly-full body is still accepted
+    let mut out = Vec::with_capacity(data.len().saturating_mul(4).min(limit));
+    reader
+        .take(limit as u64 + 1)
+        .read_to_end(&mut out)
+        .map_err(|_| DecodeError::Corrupt)?;
+    if out.len() > limit {
+        return Err(DecodeError::TooLarge);
+    }
+    Ok(Bytes::from(out))
+}
+
+fn is_zlib_header(data: &[u8]) -> bool {
+    match data {
+        [cmf, flg, ..] => cmf & 0x0f == 8 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0,
+        _ => false,
+    }
+}
+
+fn reject(err: DecodeError, accept: Option<&str>) -> Response<Body> {
+    REJECTED.fetch_add(1, Ordering::Relaxed);
+    let (status, code, message) = match err {
+        DecodeError::TooLarge => (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorCode::PayloadTooLarge,
+            format!(
+                "Decompressed request body exceeds {} bytes",
+                max_decompressed_size()
+            ),
+        ),
+        DecodeError::Unsupported(coding) => (
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ErrorCode::UnsupportedMediaType,
+            format!("Unsupported Content-Encoding: {}", coding),
+        ),
+        DecodeError::Corrupt => (
+            StatusCode::BAD_REQUEST,
+            ErrorCode::BadRequest,
+            "Malformed compressed request body".to_string(),
+        ),
+    };
+    let mut response = error_envelope::response(status, code, message, accept);
+    response
+        .headers_mut()
+        .insert(header::CONNECTION, HeaderValue::from_static("close"));
+    response
+}
+
+/// Request decompression counters for this worker process.
+///
+/// Returns a dict with `enabled`, `max_decompressed_size`, `decompressed`
+/// (requests inflated), `compressed_bytes`, `inflated_bytes` and `rejected`.
+#[pyfunction]
+pub fn request_decompression_stats(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let stats = PyDict::new(py);
+    stats.set_item("enabled", is_enabled())?;
+    stats.set_item("max_decompressed_size", max_decompressed_size())?;
+    stats.set_item("decompressed", DECOMPRESSED.load(Ordering::Relaxed))?;
+    stats.set_item("compressed_bytes", COMPRESSED_BYTES.load(Ordering::Relaxed))?;
+    stats.set_item("inflated_bytes", INFLATED_BYTES.load(Ordering::Relaxed))?;
+    stats.set_item("rejected", REJECTED.load(Ordering::Relaxed))?;
+    Ok(stats)
+}
+
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(request_decompression_stats, m)?)?;
+    Ok(())
+}
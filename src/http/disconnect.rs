@@ -0,0 +1,222 @@
+//! Client disconnects while a request is being served.
+//!
+//! Hyper drops the service future when the client goes away before the
+//! response is written, and drops the response body when the connection
+//! closes mid-stream. The worker turns either into a signal on the request's
+//! [`Disconnect`] token: `Request.is_disconnected()` starts returning True,
+//! `Request.wait_disconnected()` resolves, an async handler still running is
+//! cancelled at its next `await`, and database sessions and executors see the
+//! request's deadline as passed so pending work fails fast. Sync handlers
+//! cannot be interrupted and should poll `is_disconnected()` in long loops.
+//!
+//! The request is logged with status 499 ("client closed request") and
+//! counted in [`disconnect_stats`] instead of whatever the handler would
+//! have returned.
+
+use parking_lot::{Condvar, Mutex};
+use pyo3::exceptions::PyStopIteration;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::core::deadline::{self, Deadline};
+use crate::core::global::get_asyncio;
+use crate::core::reload::ReloadManager;
+use crate::logging::access::AccessDetails;
+
+/// Status logged for requests the client abandoned, as nginx does
+pub const CLIENT_CLOSED_REQUEST: u16 = 499;
+
+static ABORTED: AtomicU64 = AtomicU64::new(0);
+static ABORTED_STREAMS: AtomicU64 = AtomicU64::new(0);
+
+/// How long one `wait_disconnected()` step blocks with the GIL released
+const WAIT_STEP: Duration = Duration::from_millis(5);
+
+#[derive(Default)]
+struct Inner {
+    disconnected: AtomicBool,
+    /// Request ID while the handler runs, so the signal can expire its deadline
+    running: Mutex<Option<String>>,
+    cond: Condvar,
+}
+
+/// Shared flag set when the client of a request disconnects
+#[derive(Clone, Default)]
+pub struct Disconnect(Arc<Inner>);
+
+impl Disconnect {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.0.disconnected.load(Ordering::Acquire)
+    }
+
+    /// Mark the client gone, wake waiters and cut the running handler's
+    /// downstream work short
+    pub fn signal(&self) {
+        if self.0.disconnected.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let running = self.0.running.lock();
+        if let Some(request_id) = running.as_deref() {
+            deadline::register(request_id, Deadline::after(Duration::ZERO));
+        }
+        self.0.cond.notify_all();
+    }
+
+    /// The handler for `request_id` started running
+    pub fn handler_started(&self, request_id: &str) {
+        *self.0.running.lock() = Some(request_id.to_string());
+    }
+
+    /// The handler finished; drops the expired deadline a signal published
+    pub fn handler_finished(&self) {
+        let request_id = self.0.running.lock().take();
+        if let Some(request_id) = request_id {
+            if self.is_set() {
+                deadline::release(&request_id);
+            }
+        }
+    }
+
+    /// Block until the client disconnects or `timeout` passes; true when it
+    /// disconnected
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let mut running = self.0.running.lock();
+        if !self.is_set() {
+            self.0.cond.wait_for(&mut running, timeout);
+        }
+        self.is_set()
+    }
+}
+
+/// What is logged and counted when a request is abandoned
+pub struct Aborted {
+    pub token: Disconnect,
+    pub method: String,
+    pub path: String,
+    pub logged: bool,
+    pub started: Instant,
+    pub request_id: Option<String>,
+    pub access: Option<AccessDetails>,
+}
+
+impl Aborted {
+    /// Signal the token, count the abort and log it as 499
+    pub fn record(self, streaming: bool) {
+        self.token.signal();
+        if streaming {
+            ABORTED_STREAMS.fetch_add(1, Ordering::Relaxed);
+        } else {
+            ABORTED.fetch_add(1, Ordering::Relaxed);
+        }
+        let elapsed = self.started.elapsed();
+        crate::telemetry::server_metrics::record(
+            &self.method,
+            None,
+            CLIENT_CLOSED_REQUEST,
+            elapsed,
+        );
+        if self.logged {
+            crate::logging::log_response(
+                &self.method,
+                &self.path,
+                CLIENT_CLOSED_REQUEST,
+                crate::http::timing::ms(elapsed),
+                self.request_id.as_deref(),
+                None,
+                self.access,
+            );
+        }
+    }
+}
+
+/// Held by the worker while it produces a response. Dropped before being
+/// disarmed means hyper gave up on the request because the client left:
+/// the abort is recorded and the in-flight slot released.
+pub struct AbortGuard(Option<(Aborted, ReloadManager)>);
+
+impl AbortGuard {
+    pub fn arm(aborted: Aborted, rm: ReloadManager) -> Self {
+        Self(Some((aborted, rm)))
+    }
+
+    /// The response is ready; the caller takes over the in-flight slot
+    pub fn disarm(mut self) -> (Aborted, ReloadManager) {
+        self.0.take().expect("abort guard disarmed twice")
+    }
+}
+
+impl Drop for AbortGuard {
+    fn drop(&mut self) {
+        if let Some((aborted, rm)) = self.0.take() {
+            aborted.record(false);
+            rm.on_request_complete();
+        }
+    }
+}
+
+/// Awaitable returned by `Request.wait_disconnected()`.
+///
+/// Handlers are stepped without an event loop, so each step blocks briefly
+/// with the GIL released instead of registering a wakeup; under a running
+/// asyncio loop it yields without blocking.
+#[pyclass]
+pub struct DisconnectWait(Disconnect);
+
+impl DisconnectWait {
+    pub fn new(token: Disconnect) -> Self {
+        Self(token)
+    }
+}
+
+#[pymethods]
+impl DisconnectWait {
+    fn __await__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<()> {
+        if self.0.is_set() {
+            return Err(PyStopIteration::new_err(()));
+        }
+        let in_loop = !get_asyncio(py)
+            .bind(py)
+            .call_method0("_get_running_loop")?
+            .is_none();
+        if !in_loop {
+            let token = self.0.clone();
+            if py.detach(|| token.wait_timeout(WAIT_STEP)) {
+                return Err(PyStopIteration::new_err(()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Client disconnect counters for this worker process.
+///
+/// Returns a dict with `aborted` (requests whose client left before the
+/// response was sent) and `aborted_streams` (streaming responses whose
+/// client left before the body ended).
+#[pyfunction]
+pub fn disconnect_stats(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let stats = PyDict::new(py);
+    stats.set_item("aborted", ABORTED.load(Ordering::Relaxed))?;
+    stats.set_item("aborted_streams", ABORTED_STREAMS.load(Ordering::Relaxed))?;
+    Ok(stats)
+}
+
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(disconnect_stats, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,294 @@
+//! Error envelope of the responses the framework generates itself.
+//!
+//! Router 404s and 405s, the 503s of draining and load shedding, the 413,
+//! 414 and 431 of the request limits, handler timeouts and middleware
+//! short-circuits without a body share one shape. Under the default
+//! `error_format="auto"`, clients whose Accept names JSON get
+//!
+//! ```text
+//! {"error": {"status": 404, "code": "not_found", "message": "Not Found"}}
+//! ```
+//!
+//! and other clients, curl's `*/*` included, the message as plain text.
+//! Codes are the stable strings of [`ErrorCode`]. Responses written by
+//! handlers are never rewritten.
+//!
+//! Apps replace the envelope per status through their error handler
+//! registry: `Server.set_error_handler` registers a handler for a status,
+//! and errors found once the request has been read (router misses, routes
+//! hidden by a feature flag, middleware short-circuits) are answered by it.
+//! Errors sent before the request is read (limits, draining, load
+//! shedding) always get the envelope.
+
+use axum::body::Body;
+use axum::http::{header, HeaderMap, Response, StatusCode};
+use parking_lot::RwLock;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+use crate::core::interpreter::http_execute;
+use crate::http::request::{negotiate, Request};
+use crate::middleware::{middleware_response_to_hyper, MiddlewareResponse};
+use crate::routing::route::RouteConfig;
+
+static FORMAT: AtomicU8 = AtomicU8::new(ErrorFormat::Auto as u8);
+
+/// Statuses the app answers with its own error handler
+static HANDLED: RwLock<Vec<u16>> = RwLock::new(Vec::new());
+
+/// Stable `code` of a framework error
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// 400, e.g. a corrupt compressed body
+    BadRequest,
+    /// 401 from a middleware short-circuit
+    Unauthorized,
+    /// 403 from a middleware short-circuit
+    Forbidden,
+    /// 404 from the router, or a route hidden by its feature flag
+    NotFound,
+    /// 405 for a path with routes for other methods only
+    MethodNotAllowed,
+    /// 413 over a body limit
+    PayloadTooLarge,
+    /// 414 over `max_uri_length`
+    UriTooLong,
+    /// 415 for an unknown `Content-Encoding`
+    UnsupportedMediaType,
+    /// 417 for an `Expect` other than `100-continue`
+    ExpectationFailed,
+    /// 429 from a middleware short-circuit
+    TooManyRequests,
+    /// 431 over `max_header_bytes`
+    HeaderFieldsTooLarge,
+    /// 431 over `max_header_count`
+    TooManyHeaderFields,
+    /// 500 from a middleware short-circuit
+    InternalError,
+    /// 503 while the server drains for a reload or shutdown
+    ServiceDraining,
+    /// 503 over `max_in_flight`
+    Overloaded,
+    /// 503 from a middleware short-circuit
+    ServiceUnavailable,
+    /// 504 when a handler misses its timeout
+    GatewayTimeout,
+    /// Any other status
+    Error,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::BadRequest => "bad_request",
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::Forbidden => "forbidden",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::MethodNotAllowed => "method_not_allowed",
+            ErrorCode::PayloadTooLarge => "payload_too_large",
+            ErrorCode::UriTooLong => "uri_too_long",
+            ErrorCode::UnsupportedMediaType => "unsupported_media_type",
+            ErrorCode::ExpectationFailed => "expectation_failed",
+            ErrorCode::TooManyRequests => "too_many_requests",
+            ErrorCode::HeaderFieldsTooLarge => "header_fields_too_large",
+            ErrorCode::TooManyHeaderFields => "too_many_header_fields",
+            ErrorCode::InternalError => "internal_error",
+            ErrorCode::ServiceDraining => "service_draining",
+            ErrorCode::Overloaded => "overloaded",
+            ErrorCode::ServiceUnavailable => "service_unavailable",
+            ErrorCode::GatewayTimeout => "gateway_timeout",
+            ErrorCode::Error => "error",
+        }
+    }
+
+    /// The code of a response that only has a status
+    pub fn for_status(status: StatusCode) -> Self {
+        match status.as_u16() {
+            400 => ErrorCode::BadRequest,
+            401 => ErrorCode::Unauthorized,
+            403 => ErrorCode::Forbidden,
+            404 => ErrorCode::NotFound,
+            405 => ErrorCode::MethodNotAllowed,
+            413 => ErrorCode::PayloadTooLarge,
+            414 => ErrorCode::UriTooLong,
+            415 => ErrorCode::UnsupportedMediaType,
+            417 => ErrorCode::ExpectationFailed,
+            429 => ErrorCode::TooManyRequests,
+            431 => ErrorCode::HeaderFieldsTooLarge,
+            500 => ErrorCode::InternalError,
+            503 => ErrorCode::ServiceUnavailable,
+            504 => ErrorCode::GatewayTimeout,
+            _ => ErrorCode::Error,
+        }
+    }
+}
+
+/// How error bodies are written
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// JSON for clients whose Accept names it, plain text otherwise
+    #[default]
+    Auto,
+    Json,
+    Text,
+}
+
+impl ErrorFormat {
+    pub fn parse(value: &str) -> PyResult<Self> {
+        match value {
+            "auto" => Ok(ErrorFormat::Auto),
+            "json" => Ok(ErrorFormat::Json),
+            "text" => Ok(ErrorFormat::Text),
+            other => Err(PyValueError::new_err(format!(
+                "error_format must be 'auto', 'json' or 'text', not '{}'",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorFormat::Auto => "auto",
+            ErrorFormat::Json => "json",
+            ErrorFormat::Text => "text",
+        }
+    }
+
+    fn load() -> Self {
+        match FORMAT.load(Ordering::Relaxed) {
+            1 => ErrorFormat::Json,
+            2 => ErrorFormat::Text,
+            _ => ErrorFormat::Auto,
+        }
+    }
+
+    fn is_json(self, accept: Option<&str>) -> bool {
+        match self {
+            ErrorFormat::Json => true,
+            ErrorFormat::Text => false,
+            ErrorFormat::Auto => {
+                accept.and_then(|accept| negotiate(accept, &["text", "json"])) == Some(1)
+            }
+        }
+    }
+}
+
+/// Install the format and the statuses with an app error handler for this
+/// process
+pub fn configure(format: ErrorFormat, handled: impl IntoIterator<Item = u16>) {
+    FORMAT.store(format as u8, Ordering::Relaxed);
+    *HANDLED.write() = handled.into_iter().collect();
+}
+
+/// Response extension marking an error built here
+#[derive(Clone, Debug)]
+pub struct FrameworkError {
+    pub status: StatusCode,
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+/// The Accept header of a request not yet converted
+pub fn accept(headers: &HeaderMap) -> Option<&str> {
+    headers.get(header::ACCEPT)?.to_str().ok()
+}
+
+/// The envelope for `status` in the format the client accepts
+pub fn response(
+    status: StatusCode,
+    code: ErrorCode,
+    message: impl Into<String>,
+    accept: Option<&str>,
+) -> Response<Body> {
+    let message = message.into();
+    let (content_type, body) = if ErrorFormat::load().is_json(accept) {
+        let body = serde_json::json!({
+            "error": {"status": status.as_u16(), "code": code.as_str(), "message": &message}
+        });
+        ("application/json", body.to_string())
+    } else {
+        ("text/plain; charset=utf-8", message.clone())
+    };
+    let mut response = Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, content_type)
+        .body(Body::from(body))
+        .unwrap();
+    response.extensions_mut().insert(FrameworkError {
+        status,
+        code,
+        message,
+    });
+    response
+}
+
+/// A response a middleware answered with; an error status without a body
+/// gets the envelope, keeping the middleware's other headers. Handler
+/// responses a middleware plays back are left alone.
+pub fn short_circuit(response: MiddlewareResponse, accept: Option<&str>) -> Response<Body> {
+    let status = StatusCode::from_u16(response.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let is_error = status.is_client_error() || status.is_server_error();
+    if response.replayed || !response.body.is_empty() || !is_error {
+        return middleware_response_to_hyper(response);
+    }
+    let mut envelope = self::response(
+        status,
+        ErrorCode::for_status(status),
+        status.canonical_reason().unwrap_or("Error"),
+        accept,
+    );
+    for (name, value) in &response.headers {
+        if name.eq_ignore_ascii_case("content-type") || name.eq_ignore_ascii_case("content-length")
+        {
+            continue;
+        }
+        if let (Ok(name), Ok(value)) = (
+            header::HeaderName::from_bytes(name.as_bytes()),
+            header::HeaderValue::from_str(value),
+        ) {
+            envelope.headers_mut().append(name, value);
+        }
+    }
+    envelope
+}
+
+/// Handler hash of the app's error handler for `status`
+pub fn handler_hash(status: u16) -> u64 {
+    xxhash_rust::xxh3::xxh3_64(b"hypern:error_handler") ^ u64::from(status)
+}
+
+/// Answer an error built here with the app's handler for its status, when
+/// there is one; other responses are returned as they are.
+///
+/// The handler finds the error in `Request.route_meta` under
+/// `error_status`, `error_code` and `error_message`.
+pub async fn handle(response: Response<Body>, request: Request) -> Response<Body> {
+    let Some(error) = response.extensions().get::<FrameworkError>() else {
+        return response;
+    };
+    let status = error.status.as_u16();
+    if !HANDLED.read().contains(&status) {
+        return response;
+    }
+    let metadata = HashMap::from([
+        ("error_status".to_string(), status.to_string()),
+        ("error_code".to_string(), error.code.as_str().to_string()),
+        ("error_message".to_string(), error.message.clone()),
+    ]);
+    request.set_route_config(Arc::new(RouteConfig {
+        metadata,
+        ..RouteConfig::default()
+    }));
+    let (mut handled, _) = http_execute(handler_hash(status), request, None).await;
+    // A 405 still names the methods the path answers
+    if let Some(allow) = response.headers().get(header::ALLOW) {
+        handled
+            .headers_mut()
+            .entry(header::ALLOW)
+            .or_insert_with(|| allow.clone());
+    }
+    handled
+}
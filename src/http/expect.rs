@@ -0,0 +1,137 @@
+//! `Expect: 100-continue` handling.
+//!
+//! Hyper sends the interim `100 Continue` the first time a request body is
+//! read. Before that, a request that asked for it is checked against what is
+//! known without the body: the route exists, the declared Content-Length fits
+//! the body limit, and middleware that only looks at headers (Basic auth)
+//! accepts it. A request failing a check gets its final status at once; the
+//! client never sends the body, and since the body is left unread the
+//! connection is closed after the response.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use axum::body::Body;
+use axum::http::{header, request::Parts, Response, StatusCode, Version};
+
+use crate::http::error_envelope::{self, ErrorCode};
+use crate::http::method::HttpMethod;
+use crate::http::request::MAX_BODY_SIZE;
+use crate::http::response::response_unmatched;
+use crate::middleware::{MiddlewareChain, MiddlewareContext, MiddlewareResult};
+use crate::routing::router::Router;
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Enable or disable the pre-body checks for this process
+pub fn configure(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Final response for a request head whose body should not be asked for,
+/// None to go on and read it
+pub async fn check(
+    req: &Parts,
+    router: &Router,
+    middleware: &MiddlewareChain,
+) -> Option<Response<Body>> {
+    let expect = req.headers.get(header::EXPECT)?;
+    if !ENABLED.load(Ordering::Relaxed) {
+        return None;
+    }
+    if !expect.as_bytes().eq_ignore_ascii_case(b"100-continue") {
+        return Some(final_response(
+            req,
+            error_envelope::response(
+                StatusCode::EXPECTATION_FAILED,
+                ErrorCode::ExpectationFailed,
+                "Only 'Expect: 100-continue' is supported",
+                error_envelope::accept(&req.headers),
+            ),
+        ));
+    }
+
+    // Malformed paths are answered later without reading the body either
+    let path = crate::http::path::normalize(req.uri.path()).ok()?;
+    let method = req.method.as_str();
+    let host = if router.has_host_routes() {
+        req.headers.get(header::HOST).and_then(|h| h.to_str().ok())
+    } else {
+        None
+    };
+    let limit = match router.find_matching_route_for_host(host, &path.routing, method) {
+        Some((route, _)) => route
+            .config
+            .max_body_size
+            .map_or(MAX_BODY_SIZE, |limit| limit.min(MAX_BODY_SIZE)),
+        None if crate::realtime::poll::endpoint_for(method, &path.routing).is_some() => {
+            MAX_BODY_SIZE
+        }
+        None => {
+            let allowed = router.allowed_methods(host, &path.routing);
+            let accept = error_envelope::accept(&req.headers);
+            return Some(final_response(req, response_unmatched(&allowed, accept)));
+        }
+    };
+
+    let content_length = req
+        .headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if content_length.is_some_and(|len| len > limit) {
+        return Some(final_response(
+            req,
+            error_envelope::response(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                ErrorCode::PayloadTooLarge,
+                format!("Request body exceeds {} bytes", limit),
+                error_envelope::accept(&req.headers),
+            ),
+        ));
+    }
+
+    if middleware.is_empty_before() {
+        return None;
+    }
+    let headers: HashMap<String, String> = req
+        .headers
+        .iter()
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|value| (name.as_str().to_string(), value.to_string()))
+        })
+        .collect();
+    let ctx = MiddlewareContext::new(
+        &path.decoded,
+        HttpMethod::from_str(method).unwrap_or(HttpMethod::GET),
+        headers,
+        req.uri.query().unwrap_or(""),
+        None,
+    );
+    match middleware.execute_before_body(&ctx).await {
+        MiddlewareResult::Continue() => None,
+        MiddlewareResult::Response(response) => Some(final_response(
+            req,
+            error_envelope::short_circuit(response, error_envelope::accept(&req.headers)),
+        )),
+        MiddlewareResult::Error(err) => Some(final_response(
+            req,
+            error_envelope::short_circuit(err.to_response(), None),
+        )),
+    }
+}
+
+/// An HTTP/1 connection with the unread body still on it cannot be reused
+fn final_response(req: &Parts, mut response: Response<Body>) -> Response<Body> {
+    if req.version >= Version::HTTP_2 {
+        return response;
+    }
+    response.headers_mut().insert(
+        header::CONNECTION,
+        header::HeaderValue::from_static("close"),
+    );
+    response
+}
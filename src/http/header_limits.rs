@@ -0,0 +1,341 @@
+//! Request header limits.
+//!
+//! `max_header_bytes` caps the header section of a request (the field lines
+//! after the request line), `max_header_count` its number of fields and
+//! `max_uri_length` the request target. Axum's server does not expose
+//! hyper's own parser limits (about 400 KiB of buffered head and 100 fields
+//! on HTTP/1, a 16 MiB header list on HTTP/2), so those stay in force as
+//! outer bounds and these are enforced twice:
+//!
+//! - On HTTP/1 connections, [`HeadGuard`] counts the bytes of each request
+//!   head as they are read. A head going over a limit is never handed to
+//!   hyper: the client is sent a 431 or 414 directly and the connection is
+//!   closed, so a 2 MB cookie costs no more memory than the limit. Input
+//!   still arriving is read and discarded for a while first, so closing
+//!   with unread data does not reset the connection before the client has
+//!   read the response.
+//! - Every request, HTTP/2 included, is checked by [`check`] before
+//!   middleware, routing or the copy of its headers into `Request`, and
+//!   gets the same status on a connection that stays open.
+//!
+//! Rejections are counted by the limit that was exceeded in `ServerMetrics`.
+
+use axum::body::Body;
+use axum::http::{Request, Response, StatusCode};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use crate::http::error_envelope::{self, ErrorCode};
+
+/// Default `max_header_bytes`
+pub const DEFAULT_MAX_HEADER_BYTES: usize = 64 * 1024;
+/// Default `max_header_count`, hyper's own HTTP/1 cap
+pub const DEFAULT_MAX_HEADER_COUNT: usize = 100;
+/// Default `max_uri_length`
+pub const DEFAULT_MAX_URI_LENGTH: usize = 8 * 1024;
+
+/// Room on the request line for the method and ` HTTP/1.1`
+const REQUEST_LINE_SLACK: usize = 32;
+
+/// Input discarded after a rejection before the connection is dropped
+pub const LINGER_BYTES: usize = 1024 * 1024;
+
+/// Configured limits, 0 for none
+static MAX_HEADER_BYTES: AtomicUsize = AtomicUsize::new(0);
+static MAX_HEADER_COUNT: AtomicUsize = AtomicUsize::new(0);
+static MAX_URI_LENGTH: AtomicUsize = AtomicUsize::new(0);
+
+static REJECTED: [AtomicU64; 3] = [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)];
+
+/// The limit a request went over
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    HeaderBytes,
+    HeaderCount,
+    UriLength,
+}
+
+impl Limit {
+    pub const ALL: [Limit; 3] = [Limit::HeaderBytes, Limit::HeaderCount, Limit::UriLength];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Limit::HeaderBytes => "max_header_bytes",
+            Limit::HeaderCount => "max_header_count",
+            Limit::UriLength => "max_uri_length",
+        }
+    }
+
+    fn status(self) -> StatusCode {
+        match self {
+            Limit::UriLength => StatusCode::URI_TOO_LONG,
+            _ => StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+        }
+    }
+
+    fn code(self) -> ErrorCode {
+        match self {
+            Limit::HeaderBytes => ErrorCode::HeaderFieldsTooLarge,
+            Limit::HeaderCount => ErrorCode::TooManyHeaderFields,
+            Limit::UriLength => ErrorCode::UriTooLong,
+        }
+    }
+
+    fn message(self) -> &'static str {
+        match self {
+            Limit::HeaderBytes => "Request header fields too large",
+            Limit::HeaderCount => "Too many request header fields",
+            Limit::UriLength => "URI too long",
+        }
+    }
+
+    fn count(self) {
+        REJECTED[self as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The whole response, for writing to a connection hyper never saw
+    /// the request of
+    fn raw_response(self) -> Vec<u8> {
+        let status = self.status();
+        let message = self.message();
+        format!(
+            "HTTP/1.1 {} {}\r\ncontent-type: text/plain; charset=utf-8\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            status.as_str(),
+            status.canonical_reason().unwrap_or_default(),
+            message.len(),
+            message
+        )
+        .into_bytes()
+    }
+}
+
+/// Requests rejected for going over `limit` since the process started
+pub fn rejected_total(limit: Limit) -> u64 {
+    REJECTED[limit as usize].load(Ordering::Relaxed)
+}
+
+/// Limits given to the `Server` constructor; None for no limit
+#[derive(Clone, Copy, Debug)]
+pub struct HeaderLimits {
+    pub max_header_bytes: Option<usize>,
+    pub max_header_count: Option<usize>,
+    pub max_uri_length: Option<usize>,
+}
+
+impl HeaderLimits {
+    pub fn from_options(
+        max_header_bytes: Option<usize>,
+        max_header_count: Option<usize>,
+        max_uri_length: Option<usize>,
+    ) -> PyResult<Self> {
+        for (name, value) in [
+            ("max_header_bytes", max_header_bytes),
+            ("max_header_count", max_header_count),
+            ("max_uri_length", max_uri_length),
+        ] {
+            if value == Some(0) {
+                return Err(PyValueError::new_err(format!(
+                    "{} must be a positive integer or None",
+                    name
+                )));
+            }
+        }
+        Ok(Self {
+            max_header_bytes,
+            max_header_count,
+            max_uri_length,
+        })
+    }
+}
+
+/// Install the limits for this process
+pub fn configure(limits: &HeaderLimits) {
+    MAX_HEADER_BYTES.store(limits.max_header_bytes.unwrap_or(0), Ordering::Relaxed);
+    MAX_HEADER_COUNT.store(limits.max_header_count.unwrap_or(0), Ordering::Relaxed);
+    MAX_URI_LENGTH.store(limits.max_uri_length.unwrap_or(0), Ordering::Relaxed);
+}
+
+fn load(limit: &AtomicUsize) -> usize {
+    match limit.load(Ordering::Relaxed) {
+        0 => usize::MAX,
+        max => max,
+    }
+}
+
+/// A 431 or 414 response if the request goes over a limit, `None` otherwise.
+/// Heads rejected while being read get their message as plain text, as
+/// their Accept header may not have arrived.
+///
+/// Header bytes are counted as on the wire: `name: value` plus CRLF per field.
+pub fn check(req: &Request<Body>) -> Option<Response<Body>> {
+    let uri_length = req.uri().path_and_query().map_or(0, |pq| pq.as_str().len());
+    let headers = req.headers();
+    let header_bytes: usize = headers
+        .iter()
+        .map(|(name, value)| name.as_str().len() + value.len() + 4)
+        .sum();
+    let limit = if uri_length > load(&MAX_URI_LENGTH) {
+        Limit::UriLength
+    } else if headers.len() > load(&MAX_HEADER_COUNT) {
+        Limit::HeaderCount
+    } else if header_bytes > load(&MAX_HEADER_BYTES) {
+        Limit::HeaderBytes
+    } else {
+        return None;
+    };
+    limit.count();
+    Some(error_envelope::response(
+        limit.status(),
+        limit.code(),
+        limit.message(),
+        error_envelope::accept(headers),
+    ))
+}
+
+/// Limits of the request heads read on one HTTP/1 connection
+pub struct HeadGuard {
+    max_header_bytes: usize,
+    max_header_count: usize,
+    max_request_line: usize,
+    /// Reading a head; false from its end until the response goes out
+    in_head: bool,
+    on_request_line: bool,
+    /// Bytes of the current line, CR and LF excluded
+    line: usize,
+    header_bytes: usize,
+    header_count: usize,
+    /// Set once a limit is exceeded
+    rejected: Option<Rejected>,
+}
+
+/// Progress answering a head over the limits
+pub struct Rejected {
+    reply: Vec<u8>,
+    /// Bytes of `reply` written
+    pub written: usize,
+    /// Our side of the connection is shut down
+    pub shut_down: bool,
+    /// Bytes read and discarded since
+    pub discarded: usize,
+}
+
+impl Rejected {
+    /// The part of the reply still to be written
+    pub fn pending_reply(&self) -> &[u8] {
+        &self.reply[self.written..]
+    }
+}
+
+impl HeadGuard {
+    /// A guard with the installed limits, None when there are none
+    pub fn start() -> Option<Self> {
+        let max_header_bytes = load(&MAX_HEADER_BYTES);
+        let max_header_count = load(&MAX_HEADER_COUNT);
+        let max_uri_length = load(&MAX_URI_LENGTH);
+        if max_header_bytes == usize::MAX
+            && max_header_count == usize::MAX
+            && max_uri_length == usize::MAX
+        {
+            return None;
+        }
+        Some(Self {
+            max_header_bytes,
+            max_header_count,
+            max_request_line: max_uri_length.saturating_add(REQUEST_LINE_SLACK),
+            in_head: true,
+            on_request_line: true,
+            line: 0,
+            header_bytes: 0,
+            header_count: 0,
+            rejected: None,
+        })
+    }
+
+    /// Count the bytes a read returned; false when they take the head over
+    /// a limit, in which case they must not be passed on
+    pub fn after_read(&mut self, data: &[u8]) -> bool {
+        if self.rejected.is_some() {
+            return false;
+        }
+        for &byte in data {
+            if !self.in_head {
+                return true;
+            }
+            let limit = self.step(byte);
+            if let Some(limit) = limit {
+                limit.count();
+                crate::hlog_debug!("Rejecting request head over {}", limit.as_str());
+                self.rejected = Some(Rejected {
+                    reply: limit.raw_response(),
+                    written: 0,
+                    shut_down: false,
+                    discarded: 0,
+                });
+                return false;
+            }
+        }
+        true
+    }
+
+    fn step(&mut self, byte: u8) -> Option<Limit> {
+        match byte {
+            b'\n' => {
+                if self.on_request_line {
+                    // Empty lines before the request line are ignored
+                    self.on_request_line = self.line == 0;
+                } else if self.line == 0 {
+                    self.end_of_head();
+                    return None;
+                } else {
+                    self.header_count += 1;
+                    self.header_bytes += 2;
+                    if self.header_count > self.max_header_count {
+                        return Some(Limit::HeaderCount);
+                    }
+                }
+                self.line = 0;
+            }
+            b'\r' => {}
+            _ => {
+                self.line += 1;
+                if self.on_request_line {
+                    if self.line > self.max_request_line {
+                        return Some(Limit::UriLength);
+                    }
+                } else {
+                    self.header_bytes += 1;
+                    if self.header_bytes > self.max_header_bytes {
+                        return Some(Limit::HeaderBytes);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn end_of_head(&mut self) {
+        self.in_head = false;
+        self.on_request_line = true;
+        self.line = 0;
+        self.header_bytes = 0;
+        self.header_count = 0;
+    }
+
+    /// Whether the connection is answering a rejected head
+    pub fn is_rejected(&self) -> bool {
+        self.rejected.is_some()
+    }
+
+    pub fn rejected(&mut self) -> Option<&mut Rejected> {
+        self.rejected.as_mut()
+    }
+
+    /// Note a write; the next head is counted once a final HTTP/1 response
+    /// has gone out
+    pub fn on_write(&mut self, data: &[u8]) {
+        if !self.in_head && crate::http::admission::is_final_response(data) {
+            self.in_head = true;
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! Server-wide response header policy.
+//!
+//! Applied to every response as it leaves the Axum router, after handlers
+//! and middleware, so error responses, health probes and responses
+//! produced by middleware get it too. Headers already on the response
+//! (set by the handler or by middleware) win over the defaults; removal
+//! runs last and strips a header whoever set it.
+//!
+//! `Date` can be given a default but not removed: the HTTP/1 connection
+//! adds it to any response without one.
+//!
+//! `hsts_preload` is enforced here as well, after the policy, so it also
+//! covers the `Strict-Transport-Security` header (see [`crate::http::https`]).
+
+use axum::body::Body;
+use axum::http::header::{DATE, SERVER};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Response};
+use parking_lot::RwLock;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::sync::{Arc, LazyLock};
+
+use crate::http::https::Transport;
+
+const DEFAULT_SERVER: &str = "Hypern";
+
+static POLICY: LazyLock<RwLock<Arc<HeaderPolicy>>> =
+    LazyLock::new(|| RwLock::new(Arc::new(HeaderPolicy::default())));
+
+/// Default, removed and `Server` headers for every response.
+///
+/// Args:
+///     default_headers: (name, value) pairs added to responses that don't
+///         already carry the header
+///     remove_headers: Header names stripped from every response, including
+///         ones set by handlers; `Date` can't be removed
+///     server_header: `Server` value for responses that don't set one;
+///         None suppresses the header entirely (default: "Hypern")
+#[pyclass(name = "HeaderPolicy", from_py_object)]
+#[derive(Clone, Debug)]
+pub struct HeaderPolicy {
+    default_headers: Vec<(HeaderName, HeaderValue)>,
+    remove_headers: Vec<HeaderName>,
+    server_header: Option<HeaderValue>,
+}
+
+impl Default for HeaderPolicy {
+    fn default() -> Self {
+        Self {
+            default_headers: Vec::new(),
+            remove_headers: Vec::new(),
+            server_header: Some(HeaderValue::from_static(DEFAULT_SERVER)),
+        }
+    }
+}
+
+#[pymethods]
+impl HeaderPolicy {
+    #[new]
+    #[pyo3(signature = (default_headers=None, remove_headers=None, server_header=Some(DEFAULT_SERVER.to_string())))]
+    pub fn new(
+        default_headers: Option<Vec<(String, String)>>,
+        remove_headers: Option<Vec<String>>,
+        server_header: Option<String>,
+    ) -> PyResult<Self> {
+        let default_headers = default_headers
+            .unwrap_or_default()
+            .iter()
+            .map(|(name, value)| Ok((header_name(name)?, header_value(name, value)?)))
+            .collect::<PyResult<Vec<_>>>()?;
+        let remove_headers = remove_headers
+            .unwrap_or_default()
+            .iter()
+            .map(|name| header_name(name))
+            .collect::<PyResult<Vec<_>>>()?;
+        if remove_headers.contains(&DATE) {
+            return Err(PyValueError::new_err(
+                "the Date header can't be removed; the connection adds it when missing",
+            ));
+        }
+        let server_header = server_header
+            .map(|value| header_value("Server", &value))
+            .transpose()?;
+        Ok(Self {
+            default_headers,
+            remove_headers,
+            server_header,
+        })
+    }
+
+    #[getter]
+    fn default_headers(&self) -> Vec<(String, String)> {
+        self.default_headers
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.as_str().to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                )
+            })
+            .collect()
+    }
+
+    #[getter]
+    fn remove_headers(&self) -> Vec<String> {
+        self.remove_headers
+            .iter()
+            .map(|name| name.as_str().to_string())
+            .collect()
+    }
+
+    #[getter]
+    fn server_header(&self) -> Option<String> {
+        self.server_header
+            .as_ref()
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "HeaderPolicy(default_headers={}, remove_headers={:?}, server_header={:?})",
+            self.default_headers.len(),
+            self.remove_headers(),
+            self.server_header()
+        )
+    }
+}
+
+impl HeaderPolicy {
+    /// Add the defaults missing from `headers`, then strip the removed ones
+    pub fn apply(&self, headers: &mut HeaderMap) {
+        for (name, value) in &self.default_headers {
+            if !headers.contains_key(name) {
+                headers.insert(name.clone(), value.clone());
+            }
+        }
+        match &self.server_header {
+            Some(value) => {
+                headers.entry(SERVER).or_insert_with(|| value.clone());
+            }
+            None => {
+                headers.remove(SERVER);
+            }
+        }
+        for name in &self.remove_headers {
+            headers.remove(name);
+        }
+    }
+
+    /// The policy as reported by `Server.stats()`
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "default_headers": self.default_headers(),
+            "remove_headers": self.remove_headers(),
+            "server_header": self.server_header(),
+        })
+    }
+}
+
+fn header_name(name: &str) -> PyResult<HeaderName> {
+    HeaderName::from_bytes(name.trim().as_bytes())
+        .map_err(|_| PyValueError::new_err(format!("invalid header name '{}'", name)))
+}
+
+fn header_value(name: &str, value: &str) -> PyResult<HeaderValue> {
+    HeaderValue::from_str(value)
+        .map_err(|_| PyValueError::new_err(format!("invalid value for header '{}'", name)))
+}
+
+/// Set the policy of this process; inherited by forked workers
+pub fn install(policy: HeaderPolicy) {
+    *POLICY.write() = Arc::new(policy);
+}
+
+/// Apply the installed policy to an outgoing response
+pub async fn apply(transport: Transport, mut response: Response<Body>) -> Response<Body> {
+    let policy = POLICY.read().clone();
+    policy.apply(response.headers_mut());
+    transport.apply(response.headers_mut());
+    response
+}
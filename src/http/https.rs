@@ -0,0 +1,206 @@
+//! HTTPS enforcement.
+//!
+//! With `force_https`, a request that did not arrive over TLS gets a 308 to
+//! the same path and query on `https://`, before middleware and routing.
+//! When the peer is one of the `trusted_proxies`, the last `X-Forwarded-Proto`
+//! entry (the one that proxy appended) decides instead of the connection;
+//! from any other peer the header is ignored. Health probes are served by their own routes and never
+//! redirected, and paths under the exempt prefixes (ACME HTTP-01 challenges
+//! by default) pass through. The redirect names the request's host only
+//! when `allowed_hosts` accepts it, which is why `force_https` requires it.
+//!
+//! With `hsts_preload`, responses to secure requests carry a
+//! `Strict-Transport-Security` header eligible for browser preload lists,
+//! replacing a weaker one set by a handler or the header policy; responses
+//! over plain HTTP, where browsers ignore it, lose the header.
+
+use axum::body::Body;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::header::{self, STRICT_TRANSPORT_SECURITY};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, HeaderValue, Request, Response, StatusCode};
+use parking_lot::RwLock;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use crate::http::admission::Cidr;
+use crate::http::allowed_hosts::{self, AllowedHosts};
+use crate::http::connection::ConnectionInfo;
+use crate::routing::route::normalize_host;
+
+/// Exempt from `force_https` unless other prefixes are configured
+pub const ACME_CHALLENGE_PREFIX: &str = "/.well-known/acme-challenge/";
+
+/// Two years, subdomains and preload, as preload lists require
+pub const PRELOAD_HSTS: &str = "max-age=63072000; includeSubDomains; preload";
+
+static POLICY: RwLock<Option<Arc<HttpsPolicy>>> = RwLock::new(None);
+
+/// `force_https`, `hsts_preload` and the proxies trusted to report the scheme
+#[derive(Clone, Debug, Default)]
+pub struct HttpsPolicy {
+    pub force_https: bool,
+    pub hsts_preload: bool,
+    pub trusted_proxies: Vec<Cidr>,
+    /// Path prefixes served over plain HTTP despite `force_https`
+    pub exempt_paths: Vec<String>,
+}
+
+impl HttpsPolicy {
+    pub fn from_options(
+        force_https: bool,
+        hsts_preload: bool,
+        trusted_proxies: Option<Vec<String>>,
+        exempt_paths: Option<Vec<String>>,
+        allowed_hosts: Option<&AllowedHosts>,
+    ) -> PyResult<Self> {
+        if force_https && allowed_hosts.is_none() {
+            return Err(PyValueError::new_err(
+                "force_https needs allowed_hosts, so redirects never point at a host the client chose",
+            ));
+        }
+        let trusted_proxies = trusted_proxies
+            .unwrap_or_default()
+            .iter()
+            .map(|text| {
+                Cidr::parse(text).ok_or_else(|| {
+                    PyValueError::new_err(format!("invalid network '{}' in trusted_proxies", text))
+                })
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        let exempt_paths =
+            exempt_paths.unwrap_or_else(|| vec![ACME_CHALLENGE_PREFIX.to_string()]);
+        if let Some(path) = exempt_paths.iter().find(|path| !path.starts_with('/')) {
+            return Err(PyValueError::new_err(format!(
+                "https exempt path '{}' must start with '/'",
+                path
+            )));
+        }
+        Ok(Self {
+            force_https,
+            hsts_preload,
+            trusted_proxies,
+            exempt_paths,
+        })
+    }
+
+    /// Whether the client reached the server over HTTPS
+    fn is_secure(&self, headers: &HeaderMap, connection: Option<&ConnectionInfo>) -> bool {
+        let Some(connection) = connection else {
+            return false;
+        };
+        let trusted = connection.peer_addr().is_some_and(|addr| {
+            self.trusted_proxies
+                .iter()
+                .any(|cidr| cidr.contains(addr.ip()))
+        });
+        if trusted {
+            // The last entry is the one the trusted proxy appended; earlier
+            // ones came from the client
+            let proto = headers
+                .get_all("x-forwarded-proto")
+                .iter()
+                .next_back()
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.rsplit(',').next())
+                .map(str::trim)
+                .filter(|proto| !proto.is_empty());
+            if let Some(proto) = proto {
+                return proto.eq_ignore_ascii_case("https");
+            }
+        }
+        connection.is_tls()
+    }
+
+    fn is_exempt(&self, path: &str) -> bool {
+        self.exempt_paths
+            .iter()
+            .any(|prefix| path.starts_with(prefix.as_str()))
+    }
+}
+
+/// Install the policy for this process
+pub fn install(policy: HttpsPolicy) {
+    *POLICY.write() = Some(Arc::new(policy));
+}
+
+fn connection(extensions: &axum::http::Extensions) -> Option<&ConnectionInfo> {
+    extensions
+        .get::<ConnectInfo<ConnectionInfo>>()
+        .map(|ConnectInfo(info)| info)
+}
+
+/// A 308 to the https URL for plain requests under `force_https`, `None`
+/// for requests that may go on.
+///
+/// Runs after the Host check, so a missing host is the only way the
+/// request's host can fail `allowed_hosts` here; it gets the same 400.
+pub fn redirect(req: &Request<Body>) -> Option<Response<Body>> {
+    let policy = POLICY.read().clone()?;
+    if !policy.force_https
+        || policy.is_exempt(req.uri().path())
+        || policy.is_secure(req.headers(), connection(req.extensions()))
+    {
+        return None;
+    }
+
+    let host = allowed_hosts::request_host(req).filter(|host| allowed_hosts::allows(host));
+    let Some(host) = host else {
+        return Some(allowed_hosts::invalid_host());
+    };
+    let host = normalize_host(host);
+    let path_and_query = req.uri().path_and_query().map_or("/", |pq| pq.as_str());
+    let location = if host.contains(':') {
+        format!("https://[{}]{}", host, path_and_query)
+    } else {
+        format!("https://{}{}", host, path_and_query)
+    };
+
+    Some(
+        Response::builder()
+            .status(StatusCode::PERMANENT_REDIRECT)
+            .header(header::LOCATION, location)
+            .header(header::CONTENT_LENGTH, 0)
+            .body(Body::empty())
+            .unwrap(),
+    )
+}
+
+/// Whether a request was made over HTTPS, for `hsts_preload`; extracted by
+/// the header policy layer before the response is produced
+pub struct Transport {
+    /// `None` when `hsts_preload` is off
+    secure: Option<bool>,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Transport {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Infallible> {
+        let policy = POLICY.read().clone();
+        let secure = policy
+            .filter(|policy| policy.hsts_preload)
+            .map(|policy| policy.is_secure(&parts.headers, connection(&parts.extensions)));
+        Ok(Self { secure })
+    }
+}
+
+impl Transport {
+    /// Set or strip `Strict-Transport-Security` under `hsts_preload`
+    pub fn apply(&self, headers: &mut HeaderMap) {
+        match self.secure {
+            Some(true) => {
+                headers.insert(
+                    STRICT_TRANSPORT_SECURITY,
+                    HeaderValue::from_static(PRELOAD_HSTS),
+                );
+            }
+            Some(false) => {
+                headers.remove(STRICT_TRANSPORT_SECURITY);
+            }
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Responses inferred from what a handler returns.
+//!
+//! Handlers may write to `res` or return the response instead:
+//!
+//! | Returned                          | Response                                |
+//! |-----------------------------------|-----------------------------------------|
+//! | `dict`, `list`                    | 200 JSON, through the fast serializer   |
+//! | `str`                             | 200 `text/plain`                        |
+//! | `bytes`, `bytearray`              | 200 `application/octet-stream`          |
+//! | `None`                            | 204, unless the handler wrote to `res`  |
+//! | `(body, status)`                  | body as above, with `status`            |
+//! | `(body, status, headers)`         | plus a dict or list of header pairs     |
+//! | `Response`, `SSEStream`, `StreamingResponse` | passed through, already written |
+//! | `MiddlewareResponse`              | its status, headers and body            |
+//! | `SSEEvent`                        | a single-event `text/event-stream`      |
+//!
+//! Anything else raises `TypeError` naming the type and the route. `str`
+//! and `bytes` keep a Content-Type the handler already set. Every dispatch
+//! path goes through `apply`: raw `Server` handlers, sync and async, when
+//! their call returns, and `Hypern` app handlers inside the app's error
+//! handling so the `TypeError` reaches its exception handlers.
+
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use pyo3::types::{PyByteArray, PyBytes, PyDict, PyList, PyString, PyTuple};
+
+use crate::http::request::Request;
+use crate::http::response::{content_types, Response, ResponseSlot};
+use crate::http::streaming::{SSEEvent, SSEStream, StreamingResponse};
+use crate::middleware::chain::MiddlewareResponse;
+
+const EXPECTED: &str =
+    "dict, list, str, bytes, None, a (body, status[, headers]) tuple or a Response";
+
+/// Write the response `value`, returned by the handler of `request`, to `res`
+pub fn apply(
+    request: &Request,
+    res: &Bound<'_, Response>,
+    value: &Bound<'_, PyAny>,
+) -> PyResult<()> {
+    let slot = res.borrow().slot();
+    let Ok(tuple) = value.cast::<PyTuple>() else {
+        return write_body(request, res, &slot, value, false);
+    };
+    let (body, status, headers) = match tuple.len() {
+        2 => (tuple.get_item(0)?, tuple.get_item(1)?, None),
+        3 => (tuple.get_item(0)?, tuple.get_item(1)?, Some(tuple.get_item(2)?)),
+        len => {
+            return Err(PyTypeError::new_err(format!(
+                "handler for {} returned a tuple of {} items; expected (body, status) or (body, status, headers)",
+                route(request),
+                len
+            )))
+        }
+    };
+    let status = match status.extract::<u16>() {
+        Ok(code) if (100..=599).contains(&code) => code,
+        _ => {
+            return Err(PyTypeError::new_err(format!(
+                "handler for {} returned status {}; expected an int from 100 to 599",
+                route(request),
+                status.repr()?
+            )))
+        }
+    };
+    write_body(request, res, &slot, &body, true)?;
+    slot.set_status(status);
+    if let Some(headers) = headers {
+        for (name, value) in header_pairs(request, &headers)? {
+            slot.add_header(name, value);
+        }
+    }
+    Ok(())
+}
+
+fn write_body(
+    request: &Request,
+    res: &Bound<'_, Response>,
+    slot: &ResponseSlot,
+    body: &Bound<'_, PyAny>,
+    in_tuple: bool,
+) -> PyResult<()> {
+    if body.is_none() {
+        // Handlers that wrote to `res` return None too
+        if in_tuple || slot.is_untouched() {
+            if !in_tuple {
+                slot.set_status(204);
+            }
+            slot.set_body(Vec::new());
+            slot.mark_ready();
+        }
+    } else if body.is_instance_of::<PyDict>() || body.is_instance_of::<PyList>() {
+        let json = res.borrow().serialize_json(body)?;
+        slot.remove_header("Content-Type");
+        slot.add_header("Content-Type".to_string(), content_types::JSON.to_string());
+        slot.set_body(json);
+        slot.mark_ready();
+    } else if let Ok(text) = body.cast::<PyString>() {
+        write_buffered(
+            slot,
+            text.to_str()?.as_bytes().to_vec(),
+            content_types::TEXT,
+        );
+    } else if let Ok(bytes) = body.cast::<PyBytes>() {
+        write_buffered(slot, bytes.as_bytes().to_vec(), content_types::OCTET_STREAM);
+    } else if let Ok(bytes) = body.cast::<PyByteArray>() {
+        write_buffered(slot, bytes.to_vec(), content_types::OCTET_STREAM);
+    } else if let Ok(middleware) = body.extract::<MiddlewareResponse>() {
+        slot.set_status(middleware.status);
+        for (name, value) in middleware.headers {
+            slot.add_header(name, value);
+        }
+        slot.set_body(middleware.body);
+        slot.mark_ready();
+    } else if let Ok(event) = body.cast::<SSEEvent>() {
+        for (name, value) in crate::http::streaming::sse_headers() {
+            slot.add_header(name, value);
+        }
+        slot.set_body_str(event.borrow().format());
+        slot.mark_ready();
+    } else if !(body.is_instance_of::<Response>()
+        || body.is_instance_of::<SSEStream>()
+        || body.is_instance_of::<StreamingResponse>())
+    {
+        return Err(PyTypeError::new_err(format!(
+            "handler for {} returned {}; expected {}",
+            route(request),
+            type_name(body),
+            EXPECTED
+        )));
+    }
+    Ok(())
+}
+
+fn write_buffered(slot: &ResponseSlot, body: Vec<u8>, content_type: &str) {
+    if slot.get_header("Content-Type").is_none() {
+        slot.add_header("Content-Type".to_string(), content_type.to_string());
+    }
+    slot.set_body(body);
+    slot.mark_ready();
+}
+
+/// Headers of a returned tuple: a dict or a list of `(name, value)` pairs
+fn header_pairs(request: &Request, headers: &Bound<'_, PyAny>) -> PyResult<Vec<(String, String)>> {
+    let pairs = match headers.cast::<PyDict>() {
+        Ok(dict) => dict.items().extract::<Vec<(String, String)>>(),
+        Err(_) => headers.extract::<Vec<(String, String)>>(),
+    };
+    pairs.map_err(|_| {
+        PyTypeError::new_err(format!(
+            "handler for {} returned headers of type {}; expected a dict or a list of (name, value) pairs of str",
+            route(request),
+            type_name(headers)
+        ))
+    })
+}
+
+fn route(request: &Request) -> String {
+    format!("{} {}", request.method_name(), request.path())
+}
+
+fn type_name(value: &Bound<'_, PyAny>) -> String {
+    value
+        .get_type()
+        .qualname()
+        .map(|name| name.to_string())
+        .unwrap_or_else(|_| "object".to_string())
+}
+
+/// Apply the return value of a raw handler called with `(req, res)`. A
+/// value that cannot be a response is logged and answered with a 500.
+pub fn handler_returned(args: &Bound<'_, PyTuple>, value: &Bound<'_, PyAny>) {
+    let py = value.py();
+    let (Ok(req), Ok(res)) = (args.get_item(0), args.get_item(1)) else {
+        return;
+    };
+    let (Ok(req), Ok(res)) = (req.cast::<Request>(), res.cast::<Response>()) else {
+        return;
+    };
+    if let Err(err) = apply(req.get(), res, value) {
+        err.print(py);
+        let slot = res.borrow().slot();
+        slot.set_status(500);
+        slot.set_body(b"Internal Server Error".to_vec());
+        slot.mark_ready();
+    }
+}
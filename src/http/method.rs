@@ -88,6 +88,15 @@ impl HttpMethod {
             _ => None,
         }
     }
+
+    /// Whether repeating the request has the same effect as sending it once
+    #[inline]
+    pub fn is_idempotent(&self) -> bool {
+        !matches!(
+            self,
+            HttpMethod::POST | HttpMethod::PATCH | HttpMethod::CONNECT
+        )
+    }
 }
 
 impl From<&Method> for HttpMethod {
@@ -124,3 +133,129 @@ impl HttpMethod {
         }
     }
 }
+
+/// Methods a route or middleware is bound to: a fixed group such as
+/// `GET,POST`, or `*` for any method
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MethodSet {
+    Any,
+    /// Distinct methods in declaration order of [`HttpMethod`]
+    Only(Vec<HttpMethod>),
+}
+
+/// Methods a route can be registered for
+const ROUTABLE: [HttpMethod; 7] = [
+    HttpMethod::GET,
+    HttpMethod::POST,
+    HttpMethod::PUT,
+    HttpMethod::DELETE,
+    HttpMethod::PATCH,
+    HttpMethod::HEAD,
+    HttpMethod::OPTIONS,
+];
+
+impl MethodSet {
+    /// Parse `"GET"`, `"get,post"` or `"*"`
+    pub fn parse(spec: &str) -> PyResult<Self> {
+        Self::from_names(spec.split(','))
+    }
+
+    /// Parse a Python method name, comma-separated names, a list of names
+    /// or `"*"`
+    pub fn extract(spec: &Bound<'_, PyAny>) -> PyResult<Self> {
+        match spec.extract::<String>() {
+            Ok(text) => Self::parse(&text),
+            Err(_) => Self::from_names(spec.extract::<Vec<String>>().map_err(|_| {
+                pyo3::exceptions::PyTypeError::new_err(
+                    "methods must be a method name, a list of names or \"*\"",
+                )
+            })?),
+        }
+    }
+
+    fn from_names<I: IntoIterator<Item = S>, S: AsRef<str>>(names: I) -> PyResult<Self> {
+        let mut methods = Vec::new();
+        for name in names {
+            let name = name.as_ref().trim();
+            if name == "*" {
+                return Ok(MethodSet::Any);
+            }
+            match HttpMethod::from_str(name).filter(|m| ROUTABLE.contains(m)) {
+                Some(method) => methods.push(method),
+                None => {
+                    return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                        "Unknown HTTP method: {}",
+                        name
+                    )))
+                }
+            }
+        }
+        if methods.is_empty() {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "At least one HTTP method is required",
+            ));
+        }
+        Ok(MethodSet::Only(
+            ROUTABLE
+                .into_iter()
+                .filter(|m| methods.contains(m))
+                .collect(),
+        ))
+    }
+
+    /// The bound methods, or None for any method
+    pub fn methods(&self) -> Option<&[HttpMethod]> {
+        match self {
+            MethodSet::Any => None,
+            MethodSet::Only(methods) => Some(methods),
+        }
+    }
+
+    #[inline]
+    pub fn contains(&self, method: HttpMethod) -> bool {
+        match self {
+            MethodSet::Any => true,
+            MethodSet::Only(methods) => methods.contains(&method),
+        }
+    }
+
+    /// Whether a request method name is in the set; non-standard methods
+    /// only match `*`
+    pub fn contains_str(&self, method: &str) -> bool {
+        match self {
+            MethodSet::Any => true,
+            MethodSet::Only(methods) => {
+                HttpMethod::from_str(method).is_some_and(|m| methods.contains(&m))
+            }
+        }
+    }
+
+    /// Methods bound by both sets. `*` only overlaps `*`, since a route for
+    /// an exact method is matched before a `*` route on the same path.
+    pub fn overlap(&self, other: &MethodSet) -> Option<MethodSet> {
+        match (self, other) {
+            (MethodSet::Any, MethodSet::Any) => Some(MethodSet::Any),
+            (MethodSet::Only(ours), MethodSet::Only(theirs)) => {
+                let shared: Vec<HttpMethod> = ours
+                    .iter()
+                    .copied()
+                    .filter(|m| theirs.contains(m))
+                    .collect();
+                (!shared.is_empty()).then_some(MethodSet::Only(shared))
+            }
+            _ => None,
+        }
+    }
+
+    /// Canonical spelling: `*` or `GET,POST`
+    pub fn label(&self) -> String {
+        match self {
+            MethodSet::Any => "*".to_string(),
+            MethodSet::Only(methods) => methods
+                .iter()
+                .map(HttpMethod::as_str)
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+}
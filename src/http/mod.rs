@@ -1,7 +1,69 @@
+pub mod admission;
+pub mod allowed_hosts;
+pub mod body;
+pub mod conditional;
+pub mod connection;
+pub mod date;
+pub mod decompression;
+pub mod disconnect;
+pub mod error_envelope;
+pub mod expect;
+pub mod header_limits;
+pub mod header_policy;
 pub mod headers;
+pub mod https;
+pub mod infer;
 pub mod method;
 pub mod multipart;
+pub mod multipart_stream;
+pub mod panic;
+pub mod path;
+pub mod records;
 pub mod request;
 pub mod response;
+pub mod response_fields;
+pub mod serve;
+pub mod small_response;
+pub mod sse_keepalive;
+pub mod stream_drain;
 pub mod streaming;
+pub mod timing;
+pub mod tls;
+pub mod urlencoded;
 pub mod websocket;
+pub mod ws_limits;
+
+use pyo3::prelude::*;
+
+/// Register request/response, upload, streaming and WebSocket classes.
+pub fn register_all(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<request::Request>()?;
+    m.add_class::<request::AuthInfo>()?;
+    m.add_class::<response::Response>()?;
+    m.add_class::<headers::HeaderMap>()?;
+    m.add_class::<header_policy::HeaderPolicy>()?;
+    m.add_class::<method::HttpMethod>()?;
+    m.add_class::<multipart::FormData>()?;
+    m.add_class::<multipart::UploadedFile>()?;
+    m.add_class::<multipart_stream::MultipartStream>()?;
+    m.add_class::<multipart_stream::MultipartPart>()?;
+    m.add_class::<records::NdjsonReader>()?;
+    m.add_class::<records::CsvReader>()?;
+    m.add_class::<streaming::SSEEvent>()?;
+    m.add_class::<streaming::SSEStream>()?;
+    m.add_class::<streaming::SSEGenerator>()?;
+    m.add_class::<streaming::StreamingResponse>()?;
+    m.add_class::<websocket::RustWebSocket>()?;
+    m.add_class::<websocket::WsMessage>()?;
+    m.add_class::<websocket::WsMessageType>()?;
+    body::register(m)?;
+    decompression::register(m)?;
+    disconnect::register(m)?;
+    panic::register(m)?;
+    response::register(m)?;
+    sse_keepalive::register(m)?;
+    stream_drain::register(m)?;
+    timing::register(m)?;
+    ws_limits::register(m)?;
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use parking_lot::RwLock;
 use pyo3::prelude::*;
 use pyo3::types::{PyBytes, PyDict, PyList};
@@ -8,6 +8,8 @@ use std::io::Write;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use crate::http::body::{BodyDecodeError, PayloadTooLargeError};
+
 #[pyclass(from_py_object)]
 #[derive(Clone)]
 pub struct UploadedFile {
@@ -148,9 +150,12 @@ impl UploadedFile {
 
 #[pyclass]
 pub struct FormData {
-    /// Text fields from the form
+    /// Text fields from the form (last value for repeated names)
     fields: HashMap<String, String>,
 
+    /// Every text field in order, repeats included
+    field_pairs: Vec<(String, String)>,
+
     /// Uploaded files
     files: HashMap<String, Vec<UploadedFile>>,
 }
@@ -168,6 +173,29 @@ impl FormData {
         self.fields.get(key).cloned().or(default)
     }
 
+    /// Every value of a repeated text field, in order.
+    ///
+    /// Args:
+    ///     key: Field name
+    ///     strip_brackets: Also match `key[]` (`tag[]=a&tag[]=b`)
+    #[pyo3(signature = (key, strip_brackets=true))]
+    pub fn get_list(&self, key: &str, strip_brackets: bool) -> Vec<String> {
+        crate::http::urlencoded::values(&self.field_pairs, key, strip_brackets)
+    }
+
+    /// All text fields as lists of values, names in the order first seen.
+    ///
+    /// Args:
+    ///     strip_brackets: Group `key[]` fields under `key`
+    #[pyo3(signature = (strip_brackets=true))]
+    pub fn fields_multi<'py>(
+        &self,
+        py: Python<'py>,
+        strip_brackets: bool,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        crate::http::urlencoded::grouped_dict(py, &self.field_pairs, strip_brackets)
+    }
+
     /// Get a single uploaded file by field name
     pub fn file(&self, key: &str) -> Option<UploadedFile> {
         self.files.get(key).and_then(|v| v.first().cloned())
@@ -244,11 +272,13 @@ impl FormData {
     pub fn new() -> Self {
         Self {
             fields: HashMap::new(),
+            field_pairs: Vec::new(),
             files: HashMap::new(),
         }
     }
 
     pub fn add_field(&mut self, name: String, value: String) {
+        self.field_pairs.push((name.clone(), value.clone()));
         self.fields.insert(name, value);
     }
 
@@ -263,116 +293,385 @@ impl Default for FormData {
     }
 }
 
-/// consider streaming directly to disk.
-pub fn parse_multipart(body: &Bytes, boundary: &str) -> FormData {
+/// Parse a buffered multipart body into fields and files.
+///
+/// Runs the incremental parser over the whole body; parts without a
+/// `name` are skipped.
+pub fn parse_multipart(body: &Bytes, boundary: &str) -> Result<FormData, MultipartError> {
     let mut form_data = FormData::new();
+    let chunk_size = body.len().max(1);
+    let mut reader = MultipartReader::new(
+        body.clone(),
+        boundary,
+        MultipartLimits::default(),
+        chunk_size,
+    );
+    let mut current: Option<(PartHeaders, BytesMut)> = None;
 
-    let boundary_bytes = format!("--{}", boundary);
-    let parts = split_multipart(body, boundary_bytes.as_bytes());
-
-    for part in parts {
-        if part.is_empty() {
+    loop {
+        let event = reader.next_event()?;
+        if let Event::Data(chunk) = event {
+            if let Some((_, content)) = current.as_mut() {
+                content.extend_from_slice(&chunk);
+            }
             continue;
         }
+        if let Some((headers, content)) = current.take() {
+            add_part(&mut form_data, headers, content.freeze());
+        }
+        match event {
+            Event::Part(headers) => current = Some((headers, BytesMut::new())),
+            _ => break,
+        }
+    }
 
-        // Find header/body separator
-        if let Some(sep_pos) = find_double_crlf(&part) {
-            let header_bytes = &part[..sep_pos];
-            let body_bytes = &part[sep_pos + 4..]; // Skip \r\n\r\n
-
-            // Parse headers
-            let headers = parse_part_headers(header_bytes);
-
-            // Get content disposition
-            if let Some(disposition) = headers.get("content-disposition") {
-                let (name, filename) = parse_content_disposition(disposition);
-
-                if let Some(name) = name {
-                    if let Some(filename) = filename {
-                        // This is a file upload
-                        let content_type = headers
-                            .get("content-type")
-                            .cloned()
-                            .unwrap_or_else(|| "application/octet-stream".to_string());
-
-                        // Remove trailing \r\n if present
-                        let body_bytes = if body_bytes.ends_with(b"\r\n") {
-                            &body_bytes[..body_bytes.len() - 2]
-                        } else {
-                            body_bytes
-                        };
-
-                        let file = UploadedFile::new(
-                            name.clone(),
-                            filename,
-                            content_type,
-                            Bytes::copy_from_slice(body_bytes),
-                        );
-                        form_data.add_file(name, file);
-                    } else {
-                        // This is a text field
-                        let value = String::from_utf8_lossy(body_bytes)
-                            .trim_end_matches("\r\n")
-                            .to_string();
-                        form_data.add_field(name, value);
-                    }
-                }
-            }
+    Ok(form_data)
+}
+
+fn add_part(form_data: &mut FormData, headers: PartHeaders, content: Bytes) {
+    let Some(name) = headers.name else {
+        return;
+    };
+    match headers.filename {
+        Some(filename) => {
+            let content_type = headers
+                .content_type
+                .unwrap_or_else(|| "application/octet-stream".to_string());
+            let file = UploadedFile::new(name.clone(), filename, content_type, content);
+            form_data.add_file(name, file);
+        }
+        None => {
+            let value = String::from_utf8_lossy(&content).into_owned();
+            form_data.add_field(name, value);
         }
     }
+}
+
+/// Largest header block of a single part
+const MAX_PART_HEADER_BYTES: usize = 16 * 1024;
 
-    form_data
+/// Why a multipart body was rejected
+#[derive(Debug, Clone)]
+pub enum MultipartError {
+    /// The body doesn't follow the multipart syntax at `offset`
+    Malformed { offset: u64, reason: String },
+    /// A part or the whole body went over its limit
+    TooLarge(String),
 }
 
-/// Split multipart body by boundary
-fn split_multipart(body: &Bytes, boundary: &[u8]) -> Vec<Vec<u8>> {
-    let mut parts = Vec::new();
-    let mut start = 0;
-
-    // Find first boundary
-    if let Some(pos) = find_bytes(body, boundary) {
-        start = pos + boundary.len();
-        // Skip CRLF after boundary
-        if body.len() > start + 2 && &body[start..start + 2] == b"\r\n" {
-            start += 2;
+impl std::fmt::Display for MultipartError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MultipartError::Malformed { offset, reason } => write!(
+                f,
+                "Malformed multipart body at byte offset {}: {}",
+                offset, reason
+            ),
+            MultipartError::TooLarge(message) => f.write_str(message),
         }
     }
+}
 
-    loop {
-        // Find next boundary
-        if let Some(end) = find_bytes(&body[start..], boundary) {
-            let part = body[start..start + end].to_vec();
-            parts.push(part);
-            start = start + end + boundary.len();
-
-            // Check for final boundary (--boundary--)
-            if body.len() > start && body[start..].starts_with(b"--") {
-                break;
+impl From<MultipartError> for PyErr {
+    fn from(err: MultipartError) -> PyErr {
+        match err {
+            MultipartError::Malformed { .. } => BodyDecodeError::new_err(err.to_string()),
+            MultipartError::TooLarge(_) => PayloadTooLargeError::new_err(err.to_string()),
+        }
+    }
+}
+
+/// Size limits checked as the body is parsed
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MultipartLimits {
+    /// Largest body of a single part
+    pub max_part_size: Option<usize>,
+    /// Largest multipart body
+    pub max_total_size: Option<usize>,
+}
+
+/// Headers of one part
+#[derive(Debug, Clone, Default)]
+pub struct PartHeaders {
+    /// `name` from Content-Disposition
+    pub name: Option<String>,
+    /// `filename` from Content-Disposition; set for file uploads
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    /// Every header, names lowercased
+    pub headers: Vec<(String, String)>,
+}
+
+/// What the parser found next
+#[derive(Debug)]
+pub enum Event {
+    /// A part starts; its body follows as `Data`
+    Part(PartHeaders),
+    /// A slice of the current part's body
+    Data(Bytes),
+    /// The closing boundary
+    End,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Before the first boundary
+    Preamble,
+    /// Right after a boundary: `--` ends the body, CRLF starts a part
+    Delimiter,
+    Headers,
+    Body,
+    Done,
+}
+
+/// Incremental multipart/form-data parser.
+///
+/// Input is fed in chunks of any size; `next_event` returns what can be
+/// told from the input so far and None when it needs more. Part bodies
+/// come out as they are scanned, holding back only the bytes that could
+/// be the start of the next boundary, so a part is never buffered whole.
+pub struct MultipartParser {
+    /// `\r\n--boundary`; the opening boundary may come without the CRLF
+    delimiter: Vec<u8>,
+    buf: BytesMut,
+    /// Body offset of `buf[0]`
+    offset: u64,
+    state: State,
+    eof: bool,
+    limits: MultipartLimits,
+    received: usize,
+    part_size: usize,
+    part_name: Option<String>,
+}
+
+impl MultipartParser {
+    pub fn new(boundary: &str, limits: MultipartLimits) -> Self {
+        let mut delimiter = Vec::with_capacity(boundary.len() + 4);
+        delimiter.extend_from_slice(b"\r\n--");
+        delimiter.extend_from_slice(boundary.as_bytes());
+        Self {
+            delimiter,
+            buf: BytesMut::new(),
+            offset: 0,
+            state: State::Preamble,
+            eof: false,
+            limits,
+            received: 0,
+            part_size: 0,
+            part_name: None,
+        }
+    }
+
+    /// Add the next chunk of the body
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), MultipartError> {
+        self.received += chunk.len();
+        if let Some(max) = self.limits.max_total_size {
+            if self.received > max {
+                return Err(MultipartError::TooLarge(format!(
+                    "Multipart body exceeds max_total_size ({} bytes)",
+                    max
+                )));
             }
+        }
+        // The epilogue after the closing boundary is ignored
+        if self.state != State::Done {
+            self.buf.extend_from_slice(chunk);
+        }
+        Ok(())
+    }
+
+    /// Mark the end of the body; missing boundaries are errors from now on
+    pub fn finish(&mut self) {
+        self.eof = true;
+    }
 
-            // Skip CRLF after boundary
-            if body.len() > start + 2 && &body[start..start + 2] == b"\r\n" {
-                start += 2;
+    pub fn is_finished(&self) -> bool {
+        self.eof
+    }
+
+    /// The next event, or None when more input is needed
+    pub fn next_event(&mut self) -> Result<Option<Event>, MultipartError> {
+        loop {
+            match self.state {
+                State::Preamble => {
+                    let dash_boundary = &self.delimiter[2..];
+                    if self.offset == 0 && self.buf.starts_with(dash_boundary) {
+                        self.consume(dash_boundary.len());
+                        self.state = State::Delimiter;
+                        continue;
+                    }
+                    if self.offset == 0
+                        && self.buf.len() < dash_boundary.len()
+                        && dash_boundary.starts_with(&self.buf)
+                        && !self.eof
+                    {
+                        return Ok(None);
+                    }
+                    if let Some(pos) = find_bytes(&self.buf, &self.delimiter) {
+                        self.consume(pos + self.delimiter.len());
+                        self.state = State::Delimiter;
+                        continue;
+                    }
+                    if self.eof {
+                        return Err(self.malformed(self.buf.len(), "no opening boundary"));
+                    }
+                    let keep = self.delimiter.len() - 1;
+                    if self.buf.len() > keep {
+                        self.consume(self.buf.len() - keep);
+                    }
+                    return Ok(None);
+                }
+                State::Delimiter => {
+                    // Transport padding before the line break
+                    let padding = self
+                        .buf
+                        .iter()
+                        .take_while(|&&b| b == b' ' || b == b'\t')
+                        .count();
+                    self.consume(padding);
+                    if self.buf.len() < 2 {
+                        if self.eof {
+                            return Err(self.truncated());
+                        }
+                        return Ok(None);
+                    }
+                    if self.buf.starts_with(b"--") {
+                        self.state = State::Done;
+                        self.offset += self.buf.len() as u64;
+                        self.buf.clear();
+                        return Ok(Some(Event::End));
+                    }
+                    if !self.buf.starts_with(b"\r\n") {
+                        return Err(self.malformed(0, "expected CRLF or '--' after boundary"));
+                    }
+                    self.consume(2);
+                    self.state = State::Headers;
+                }
+                State::Headers => {
+                    let block_offset = self.offset;
+                    let block = if self.buf.starts_with(b"\r\n") {
+                        self.consume(2);
+                        Bytes::new()
+                    } else if let Some(pos) = find_bytes(&self.buf, b"\r\n\r\n") {
+                        let block = self.consume(pos + 4);
+                        block.slice(..pos)
+                    } else if self.buf.len() > MAX_PART_HEADER_BYTES {
+                        return Err(self.malformed(
+                            0,
+                            &format!("part headers exceed {} bytes", MAX_PART_HEADER_BYTES),
+                        ));
+                    } else if self.eof {
+                        return Err(self.truncated());
+                    } else {
+                        return Ok(None);
+                    };
+                    let headers = parse_part_headers(&block, block_offset)?;
+                    self.state = State::Body;
+                    self.part_size = 0;
+                    self.part_name = headers.name.clone();
+                    return Ok(Some(Event::Part(headers)));
+                }
+                State::Body => {
+                    if let Some(pos) = find_bytes(&self.buf, &self.delimiter) {
+                        let data = self.consume(pos);
+                        self.consume(self.delimiter.len());
+                        self.state = State::Delimiter;
+                        if data.is_empty() {
+                            continue;
+                        }
+                        return self.data(data).map(Some);
+                    }
+                    if self.eof {
+                        return Err(self.truncated());
+                    }
+                    // Hold back what could be the start of the delimiter
+                    let keep = self.delimiter.len() - 1;
+                    if self.buf.len() <= keep {
+                        return Ok(None);
+                    }
+                    let data = self.consume(self.buf.len() - keep);
+                    return self.data(data).map(Some);
+                }
+                State::Done => return Ok(Some(Event::End)),
             }
-        } else {
-            // No more boundaries
-            if start < body.len() {
-                parts.push(body[start..].to_vec());
+        }
+    }
+
+    fn data(&mut self, data: Bytes) -> Result<Event, MultipartError> {
+        self.part_size += data.len();
+        if let Some(max) = self.limits.max_part_size {
+            if self.part_size > max {
+                return Err(MultipartError::TooLarge(format!(
+                    "Multipart part '{}' exceeds max_part_size ({} bytes)",
+                    self.part_name.as_deref().unwrap_or(""),
+                    max
+                )));
             }
-            break;
         }
+        Ok(Event::Data(data))
     }
 
-    parts
+    fn consume(&mut self, n: usize) -> Bytes {
+        self.offset += n as u64;
+        self.buf.split_to(n).freeze()
+    }
+
+    fn malformed(&self, at: usize, reason: &str) -> MultipartError {
+        MultipartError::Malformed {
+            offset: self.offset + at as u64,
+            reason: reason.to_string(),
+        }
+    }
+
+    /// The body ended before the closing boundary
+    pub fn truncated(&self) -> MultipartError {
+        let reason = match (self.state, &self.part_name) {
+            (State::Body, Some(name)) => format!(
+                "body ended inside part '{}' before the closing boundary",
+                name
+            ),
+            (State::Headers, _) => "body ended inside part headers".to_string(),
+            _ => "body ended before the closing boundary".to_string(),
+        };
+        self.malformed(self.buf.len(), &reason)
+    }
 }
 
-fn find_double_crlf(data: &[u8]) -> Option<usize> {
-    for i in 0..data.len().saturating_sub(3) {
-        if &data[i..i + 4] == b"\r\n\r\n" {
-            return Some(i);
+/// A parser reading a request body in fixed-size chunks
+pub struct MultipartReader {
+    parser: MultipartParser,
+    body: Bytes,
+    pos: usize,
+    chunk_size: usize,
+}
+
+impl MultipartReader {
+    pub fn new(body: Bytes, boundary: &str, limits: MultipartLimits, chunk_size: usize) -> Self {
+        Self {
+            parser: MultipartParser::new(boundary, limits),
+            body,
+            pos: 0,
+            chunk_size: chunk_size.max(1),
+        }
+    }
+
+    /// The next event, pulling body chunks as the parser needs them
+    pub fn next_event(&mut self) -> Result<Event, MultipartError> {
+        loop {
+            if let Some(event) = self.parser.next_event()? {
+                return Ok(event);
+            }
+            if self.pos < self.body.len() {
+                let end = (self.pos + self.chunk_size).min(self.body.len());
+                self.parser.feed(&self.body[self.pos..end])?;
+                self.pos = end;
+            } else if self.parser.is_finished() {
+                return Err(self.parser.truncated());
+            } else {
+                self.parser.finish();
+            }
         }
     }
-    None
 }
 
 /// Find bytes in slice
@@ -382,20 +681,64 @@ fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
         .position(|window| window == needle)
 }
 
-/// Parse part headers
-fn parse_part_headers(header_bytes: &[u8]) -> HashMap<String, String> {
-    let mut headers = HashMap::new();
-    let header_str = String::from_utf8_lossy(header_bytes);
-
-    for line in header_str.split("\r\n") {
-        if let Some(colon_pos) = line.find(':') {
-            let name = line[..colon_pos].trim().to_lowercase();
-            let value = line[colon_pos + 1..].trim().to_string();
-            headers.insert(name, value);
+/// Parse a part's header block, which starts at body offset `offset`
+fn parse_part_headers(block: &[u8], offset: u64) -> Result<PartHeaders, MultipartError> {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    let mut line_offset = offset;
+
+    for line in block.split_inclusive(|&b| b == b'\n') {
+        let start = line_offset;
+        line_offset += line.len() as u64;
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let text = String::from_utf8_lossy(line);
+        // Obsolete line folding continues the previous value
+        if text.starts_with([' ', '\t']) {
+            match headers.last_mut() {
+                Some((_, value)) => {
+                    value.push(' ');
+                    value.push_str(text.trim());
+                    continue;
+                }
+                None => {
+                    return Err(MultipartError::Malformed {
+                        offset: start,
+                        reason: "part header starts with whitespace".to_string(),
+                    })
+                }
+            }
+        }
+        match text.split_once(':') {
+            Some((name, value))
+                if !name.is_empty() && !name.contains(|c: char| c.is_whitespace()) =>
+            {
+                headers.push((name.to_ascii_lowercase(), value.trim().to_string()));
+            }
+            _ => {
+                return Err(MultipartError::Malformed {
+                    offset: start,
+                    reason: format!("invalid part header line {:?}", text),
+                })
+            }
         }
     }
 
-    headers
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+    };
+    let (name, filename) = header("content-disposition")
+        .map(|value| parse_content_disposition(&value))
+        .unwrap_or((None, None));
+    let content_type = header("content-type");
+    Ok(PartHeaders {
+        name,
+        filename,
+        content_type,
+        headers,
+    })
 }
 
 /// Parse Content-Disposition header to extract name and filename
@@ -403,21 +746,61 @@ fn parse_content_disposition(value: &str) -> (Option<String>, Option<String>) {
     let mut name = None;
     let mut filename = None;
 
-    for part in value.split(';') {
-        let part = part.trim();
-
-        if part.starts_with("name=") {
-            let val = part[5..].trim_matches('"');
-            name = Some(val.to_string());
-        } else if part.starts_with("filename=") {
-            let val = part[9..].trim_matches('"');
-            filename = Some(val.to_string());
+    for (key, val) in disposition_params(value) {
+        if key.eq_ignore_ascii_case("name") {
+            name = Some(val);
+        } else if key.eq_ignore_ascii_case("filename") {
+            filename = Some(val);
         }
     }
 
     (name, filename)
 }
 
+/// `key=value` parameters after the disposition type; quoted values may
+/// contain `;` and backslash escapes
+fn disposition_params(value: &str) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    let mut chars = value.chars().peekable();
+    // Skip the disposition type
+    for c in chars.by_ref() {
+        if c == ';' {
+            break;
+        }
+    }
+    loop {
+        let key: String = chars.by_ref().take_while(|&c| c != '=').collect();
+        let key = key.trim().to_string();
+        if key.is_empty() {
+            break;
+        }
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        let mut val = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => val.extend(chars.next()),
+                    '"' => break,
+                    c => val.push(c),
+                }
+            }
+            for c in chars.by_ref() {
+                if c == ';' {
+                    break;
+                }
+            }
+        } else {
+            val = chars.by_ref().take_while(|&c| c != ';').collect();
+            val = val.trim().to_string();
+        }
+        params.push((key, val));
+    }
+    params
+}
+
 /// Extract boundary from Content-Type header
 pub fn extract_boundary(content_type: &str) -> Option<String> {
     for part in content_type.split(';') {
@@ -0,0 +1,283 @@
+//! `Request.multipart_stream()`: multipart parts as an async iterator.
+//!
+//! Parts come out of the incremental parser in `multipart` one at a time,
+//! each one's body as a sequence of chunks, so a handler can hash, scan or
+//! forward an upload without the whole part ever being collected. Parts
+//! share one parser and are consumed in order: moving on to the next part
+//! skips whatever is left of the current one, and reading a part that was
+//! skipped gives no data.
+//!
+//! The request body is already in memory (bodies are read up to
+//! `MAX_BODY_SIZE` before the handler runs); the parser scans it in
+//! `chunk_size` slices and the limits are checked as it goes. Errors
+//! (malformed boundaries or headers, a truncated body, a limit) are raised
+//! from the `await` that hit them and again from any later one.
+
+use bytes::{Bytes, BytesMut};
+use parking_lot::Mutex;
+use pyo3::exceptions::{PyStopAsyncIteration, PyStopIteration};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+use pyo3::IntoPyObjectExt;
+use std::sync::Arc;
+
+use super::multipart::{Event, MultipartError, MultipartReader, PartHeaders};
+
+struct StreamState {
+    reader: MultipartReader,
+    /// Number of parts started; the current part's index
+    current: usize,
+    /// The current part has no more data
+    part_done: bool,
+    /// Data read from the current part but not returned yet
+    leftover: Option<Bytes>,
+    /// Headers of the next part, read while finishing the current one
+    next_part: Option<PartHeaders>,
+    finished: bool,
+    error: Option<MultipartError>,
+}
+
+impl StreamState {
+    fn next_event(&mut self) -> Result<Event, MultipartError> {
+        if let Some(err) = &self.error {
+            return Err(err.clone());
+        }
+        self.reader.next_event().inspect_err(|err| {
+            self.error = Some(err.clone());
+        })
+    }
+
+    /// The next chunk of the current part, None at its end
+    fn next_chunk(&mut self) -> Result<Option<Bytes>, MultipartError> {
+        if let Some(chunk) = self.leftover.take() {
+            return Ok(Some(chunk));
+        }
+        if self.part_done {
+            return Ok(None);
+        }
+        match self.next_event()? {
+            Event::Data(chunk) => Ok(Some(chunk)),
+            Event::Part(headers) => {
+                self.next_part = Some(headers);
+                self.part_done = true;
+                Ok(None)
+            }
+            Event::End => {
+                self.finished = true;
+                self.part_done = true;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Skip the rest of the current part and start the next one
+    fn next_part(&mut self) -> Result<Option<PartHeaders>, MultipartError> {
+        while self.next_chunk()?.is_some() {}
+        let headers = match self.next_part.take() {
+            Some(headers) => headers,
+            None if self.finished => return Ok(None),
+            None => match self.next_event()? {
+                Event::Part(headers) => headers,
+                Event::End => {
+                    self.finished = true;
+                    return Ok(None);
+                }
+                Event::Data(_) => unreachable!("part data after the part ended"),
+            },
+        };
+        self.current += 1;
+        self.part_done = false;
+        Ok(Some(headers))
+    }
+}
+
+/// Async iterator over the parts of a multipart/form-data body.
+///
+/// Returned by `Request.multipart_stream()`; `async for part in stream`
+/// yields `MultipartPart` objects in body order.
+#[pyclass(name = "MultipartStream")]
+pub struct MultipartStream {
+    state: Arc<Mutex<StreamState>>,
+}
+
+impl MultipartStream {
+    pub fn new(reader: MultipartReader) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(StreamState {
+                reader,
+                current: 0,
+                part_done: true,
+                leftover: None,
+                next_part: None,
+                finished: false,
+                error: None,
+            })),
+        }
+    }
+}
+
+#[pymethods]
+impl MultipartStream {
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// The next part, skipping what is left of the current one
+    fn __anext__(&self, py: Python<'_>) -> PyResult<Ready> {
+        let mut state = self.state.lock();
+        let result = match state.next_part() {
+            Ok(Some(headers)) => MultipartPart {
+                index: state.current,
+                headers,
+                state: self.state.clone(),
+            }
+            .into_py_any(py),
+            Ok(None) => Err(PyStopAsyncIteration::new_err(())),
+            Err(err) => Err(err.into()),
+        };
+        Ok(Ready::new(result))
+    }
+
+    fn __repr__(&self) -> String {
+        let state = self.state.lock();
+        format!(
+            "MultipartStream(parts_started={}, finished={})",
+            state.current, state.finished
+        )
+    }
+}
+
+/// One part of a multipart body, read as it is parsed.
+///
+/// `await part.read(n)` returns up to `n` bytes (all that is left when `n`
+/// is negative), `b""` at the end of the part; `async for chunk in part`
+/// yields the body in the chunks it was scanned in.
+#[pyclass(name = "MultipartPart")]
+pub struct MultipartPart {
+    index: usize,
+    headers: PartHeaders,
+    state: Arc<Mutex<StreamState>>,
+}
+
+impl MultipartPart {
+    fn read_bytes(&self, n: isize) -> Result<Bytes, MultipartError> {
+        let mut state = self.state.lock();
+        if state.current != self.index {
+            return Ok(Bytes::new());
+        }
+        let limit = usize::try_from(n).unwrap_or(usize::MAX);
+        let mut out = BytesMut::new();
+        while out.len() < limit {
+            let Some(mut chunk) = state.next_chunk()? else {
+                break;
+            };
+            let wanted = limit - out.len();
+            if chunk.len() > wanted {
+                state.leftover = Some(chunk.split_off(wanted));
+            }
+            if out.is_empty() && chunk.len() == wanted {
+                return Ok(chunk);
+            }
+            out.extend_from_slice(&chunk);
+        }
+        Ok(out.freeze())
+    }
+}
+
+#[pymethods]
+impl MultipartPart {
+    /// Field name from Content-Disposition
+    #[getter]
+    fn name(&self) -> Option<String> {
+        self.headers.name.clone()
+    }
+
+    /// Original filename, None for plain fields
+    #[getter]
+    fn filename(&self) -> Option<String> {
+        self.headers.filename.clone()
+    }
+
+    /// Content-Type of the part, None when not given
+    #[getter]
+    fn content_type(&self) -> Option<String> {
+        self.headers.content_type.clone()
+    }
+
+    /// All part headers, names lowercased
+    #[getter]
+    fn headers<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        for (name, value) in &self.headers.headers {
+            dict.set_item(name, value)?;
+        }
+        Ok(dict)
+    }
+
+    /// Read up to `n` bytes of the part body; all that is left when `n`
+    /// is negative
+    #[pyo3(signature = (n=-1))]
+    fn read(&self, py: Python<'_>, n: isize) -> Ready {
+        let result = self
+            .read_bytes(n)
+            .map(|bytes| PyBytes::new(py, &bytes).into_any().unbind())
+            .map_err(PyErr::from);
+        Ready::new(result)
+    }
+
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// The next chunk of the part body
+    fn __anext__(&self, py: Python<'_>) -> Ready {
+        let mut state = self.state.lock();
+        let result = if state.current != self.index {
+            Err(PyStopAsyncIteration::new_err(()))
+        } else {
+            match state.next_chunk() {
+                Ok(Some(chunk)) => Ok(PyBytes::new(py, &chunk).into_any().unbind()),
+                Ok(None) => Err(PyStopAsyncIteration::new_err(())),
+                Err(err) => Err(err.into()),
+            }
+        };
+        Ready::new(result)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "MultipartPart(name={:?}, filename={:?}, content_type={:?})",
+            self.headers.name, self.headers.filename, self.headers.content_type
+        )
+    }
+}
+
+/// Awaitable that is already complete; the body is parsed from memory, so
+/// nothing needs to wait
+#[pyclass]
+struct Ready(Mutex<Option<PyResult<Py<PyAny>>>>);
+
+impl Ready {
+    fn new(result: PyResult<Py<PyAny>>) -> Self {
+        Self(Mutex::new(Some(result)))
+    }
+}
+
+#[pymethods]
+impl Ready {
+    fn __await__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self) -> PyResult<()> {
+        match self.0.lock().take() {
+            Some(Ok(value)) => Err(PyStopIteration::new_err((value,))),
+            Some(Err(err)) => Err(err),
+            None => Err(PyStopIteration::new_err(())),
+        }
+    }
+}
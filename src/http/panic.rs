@@ -0,0 +1,170 @@
+//! Rust panics on the request path.
+//!
+//! A panic while a request is dispatched (routing, a middleware, the
+//! response conversion) would otherwise unwind into hyper and drop the
+//! connection. The worker runs dispatch under [`catch`] instead: the panic
+//! becomes a plain 500, its message and backtrace are logged at ERROR with
+//! the request ID, and the in-flight and drain bookkeeping run as for any
+//! other response. Panics caught inside the middleware chain are counted
+//! here too.
+//!
+//! Builds with the `test-hooks` feature can make chosen paths panic, to
+//! exercise all of this; release builds carry no trigger.
+
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+#[cfg(feature = "test-hooks")]
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Once;
+
+use axum::body::Body;
+use futures_util::FutureExt;
+#[cfg(feature = "test-hooks")]
+use parking_lot::RwLock;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+static PANICS: AtomicU64 = AtomicU64::new(0);
+static HOOK: Once = Once::new();
+
+/// Paths whose requests panic on purpose, for exercising this module
+#[cfg(feature = "test-hooks")]
+static INJECTED: RwLock<Vec<String>> = RwLock::new(Vec::new());
+#[cfg(feature = "test-hooks")]
+static HAS_INJECTED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// Backtrace of the last panic on this thread, taken by the hook
+    static BACKTRACE: RefCell<Option<Backtrace>> = const { RefCell::new(None) };
+    /// Set while this thread polls a dispatch future under [`catch`]
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Keep the backtrace of every panic for the thread that catches it. The
+/// previous hook still reports panics outside dispatch; ones inside are
+/// logged by [`catch`] instead.
+fn install_hook() {
+    HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if CATCHING.get() {
+                BACKTRACE.with(|bt| *bt.borrow_mut() = Some(Backtrace::force_capture()));
+            } else {
+                previous(info);
+            }
+        }));
+    });
+}
+
+/// Extract a readable message from a panic payload
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// Count a panic caught elsewhere on the request path
+pub fn count() {
+    PANICS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Panics caught on the request path since the process started
+pub fn panics_total() -> u64 {
+    PANICS.load(Ordering::Relaxed)
+}
+
+/// Panic if `path` was registered with `inject_panic`
+#[cfg(feature = "test-hooks")]
+pub fn maybe_inject(path: &str) {
+    if HAS_INJECTED.load(Ordering::Relaxed) && INJECTED.read().iter().any(|p| p == path) {
+        panic!("injected panic for {}", path);
+    }
+}
+
+/// Run the dispatch future, turning a panic into a 500 response, returned as
+/// `Err`. The client's `X-Request-ID` identifies the request in the log, or
+/// a fresh ID without one; either is echoed on the 500.
+pub async fn catch<F>(
+    request_id: Option<&str>,
+    dispatch: F,
+) -> Result<axum::http::Response<Body>, axum::http::Response<Body>>
+where
+    F: Future<Output = axum::http::Response<Body>>,
+{
+    install_hook();
+    let mut dispatch = std::pin::pin!(dispatch);
+    let polled = std::future::poll_fn(|cx| {
+        CATCHING.set(true);
+        let poll = dispatch.as_mut().poll(cx);
+        CATCHING.set(false);
+        poll
+    });
+    // The future is dropped on panic, but whatever it borrowed mutably may
+    // be half-updated; callers tell from `Err` not to read it
+    match AssertUnwindSafe(polled).catch_unwind().await {
+        Ok(response) => Ok(response),
+        Err(payload) => {
+            CATCHING.set(false);
+            count();
+            let request_id = request_id
+                .map(str::to_string)
+                .unwrap_or_else(crate::core::request_id::next);
+            let backtrace = BACKTRACE
+                .with(|bt| bt.borrow_mut().take())
+                .map(|bt| bt.to_string())
+                .unwrap_or_default();
+            crate::hlog_error!(
+                "Panic while handling request {}: {}\n{}",
+                request_id,
+                panic_message(payload.as_ref()),
+                backtrace
+            );
+            Err(axum::http::Response::builder()
+                .status(500)
+                .header("content-type", "text/plain")
+                .header("x-request-id", &request_id)
+                .body(Body::from("Internal Server Error"))
+                .unwrap())
+        }
+    }
+}
+
+/// Make requests for `path` panic inside the worker, or clear every
+/// injected path with None. For testing panic handling only.
+#[cfg(feature = "test-hooks")]
+#[pyfunction]
+#[pyo3(signature = (path))]
+pub fn inject_panic(path: Option<String>) {
+    let mut injected = INJECTED.write();
+    match path {
+        Some(path) => injected.push(path),
+        None => injected.clear(),
+    }
+    HAS_INJECTED.store(!injected.is_empty(), Ordering::Relaxed);
+}
+
+/// Panic counters for this worker process.
+///
+/// Returns a dict with `panics_total`: panics caught on the request path
+/// and answered with a 500 instead of taking the worker down.
+#[pyfunction]
+pub fn panic_stats(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let stats = PyDict::new(py);
+    stats.set_item("panics_total", panics_total())?;
+    Ok(stats)
+}
+
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    #[cfg(feature = "test-hooks")]
+    m.add_function(wrap_pyfunction!(inject_panic, m)?)?;
+    m.add_function(wrap_pyfunction!(panic_stats, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,237 @@
+//! Request path normalization before routing.
+//!
+//! The router matches on a normalized form of the raw path:
+//!
+//! - percent-escapes of unreserved characters (`A-Z a-z 0-9 - . _ ~`) are
+//!   decoded and other escapes upper-cased, so `/%7Euser` and `/~user` match
+//!   the same route (with `path_decoding="all"` every escape except `%2F` is
+//!   decoded);
+//! - `%2F` is never decoded for matching: `/a%2Fb` is one segment, not two,
+//!   and only reaches a handler as a path parameter;
+//! - duplicate slashes are collapsed (unless `merge_slashes=False`);
+//! - `.` and `..` segments are resolved per RFC 3986, and paths climbing
+//!   above the root are rejected with 400.
+//!
+//! Malformed escapes such as `%ZZ` are kept as literal text, or rejected with 400
+//! in strict mode, which also refuses escapes that decode to invalid UTF-8.
+//! Handlers see the fully decoded path as `Request.path` and the path as
+//! received as `Request.raw_path`; path parameters are delivered decoded.
+
+use axum::body::Body;
+use axum::http::{header, Response, StatusCode};
+use percent_encoding::percent_decode_str;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+static MERGE_SLASHES: AtomicBool = AtomicBool::new(true);
+static DECODING: AtomicU8 = AtomicU8::new(Decoding::Unreserved as u8);
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+/// Which percent-escapes are decoded in the path the router matches on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Decoding {
+    /// Only escapes of unreserved characters
+    Unreserved = 0,
+    /// Every escape except `%2F`
+    All = 1,
+}
+
+impl Decoding {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "unreserved" => Some(Self::Unreserved),
+            "all" => Some(Self::All),
+            _ => None,
+        }
+    }
+}
+
+/// Set the normalization policy for this process
+pub fn configure(merge_slashes: bool, decoding: Decoding, strict: bool) {
+    MERGE_SLASHES.store(merge_slashes, Ordering::Relaxed);
+    DECODING.store(decoding as u8, Ordering::Relaxed);
+    STRICT.store(strict, Ordering::Relaxed);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathError {
+    /// A `%` not followed by two hex digits (strict mode)
+    MalformedEncoding,
+    /// Escapes decoding to bytes that are not UTF-8 (strict mode)
+    InvalidUtf8,
+    /// `..` segments climbing above `/`
+    EscapesRoot,
+}
+
+impl PathError {
+    pub fn to_response(self) -> Response<Body> {
+        let message = match self {
+            PathError::MalformedEncoding => "Malformed percent-encoding in request path",
+            PathError::InvalidUtf8 => "Request path does not decode to valid UTF-8",
+            PathError::EscapesRoot => "Request path escapes the root directory",
+        };
+        let body = serde_json::json!({ "error": "bad_request", "message": message }).to_string();
+        Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    }
+}
+
+/// A request path in the forms the server and handlers use
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizedPath {
+    /// Path the router matches on; `%2F` and other reserved escapes stay encoded
+    pub routing: String,
+    /// Fully decoded path exposed as `Request.path`
+    pub decoded: String,
+}
+
+/// Normalize `raw` with the configured policy
+pub fn normalize(raw: &str) -> Result<NormalizedPath, PathError> {
+    let decoding = if DECODING.load(Ordering::Relaxed) == Decoding::All as u8 {
+        Decoding::All
+    } else {
+        Decoding::Unreserved
+    };
+    normalize_with(
+        raw,
+        MERGE_SLASHES.load(Ordering::Relaxed),
+        decoding,
+        STRICT.load(Ordering::Relaxed),
+    )
+}
+
+fn normalize_with(
+    raw: &str,
+    merge_slashes: bool,
+    decoding: Decoding,
+    strict: bool,
+) -> Result<NormalizedPath, PathError> {
+    // Most paths need no work at all
+    if !raw.contains('%') && !raw.contains("//") && !raw.contains("/.") && raw.starts_with('/') {
+        return Ok(NormalizedPath {
+            routing: raw.to_string(),
+            decoded: raw.to_string(),
+        });
+    }
+
+    let unescaped = unescape(raw, decoding, strict)?;
+    let routing = remove_dot_segments(&unescaped, merge_slashes)?;
+    let decoded_bytes: Vec<u8> = percent_decode_str(&routing).collect();
+    let decoded = match String::from_utf8(decoded_bytes) {
+        Ok(decoded) => decoded,
+        Err(_) if strict => return Err(PathError::InvalidUtf8),
+        Err(err) => String::from_utf8_lossy(err.as_bytes()).into_owned(),
+    };
+    Ok(NormalizedPath { routing, decoded })
+}
+
+/// Decode a matched path parameter
+pub fn decode_param(value: &str) -> String {
+    if value.contains('%') {
+        percent_decode_str(value).decode_utf8_lossy().into_owned()
+    } else {
+        value.to_string()
+    }
+}
+
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~')
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Decode the escapes `decoding` allows and upper-case the ones kept
+fn unescape(raw: &str, decoding: Decoding, strict: bool) -> Result<String, PathError> {
+    let bytes = raw.as_bytes();
+    let mut out: Vec<u8> = Vec::with_capacity(bytes.len() + 1);
+    if !raw.starts_with('/') {
+        out.push(b'/');
+    }
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        if byte != b'%' {
+            out.push(byte);
+            i += 1;
+            continue;
+        }
+        let escaped = bytes
+            .get(i + 1)
+            .and_then(|&hi| hex_value(hi))
+            .zip(bytes.get(i + 2).and_then(|&lo| hex_value(lo)));
+        let Some((hi, lo)) = escaped else {
+            if strict {
+                return Err(PathError::MalformedEncoding);
+            }
+            // A stray `%` is data; escape it so it is never decoded later
+            out.extend_from_slice(b"%25");
+            i += 1;
+            continue;
+        };
+        let value = hi << 4 | lo;
+        let decode = match decoding {
+            Decoding::Unreserved => is_unreserved(value),
+            // Bytes that would change the path's structure stay encoded
+            Decoding::All => {
+                !value.is_ascii_control() && !matches!(value, b'/' | b'%' | b'?' | b'#')
+            }
+        };
+        if decode {
+            out.push(value);
+        } else {
+            out.push(b'%');
+            out.extend_from_slice(format!("{:02X}", value).as_bytes());
+        }
+        i += 3;
+    }
+    match String::from_utf8(out) {
+        Ok(path) => Ok(path),
+        Err(_) if strict => Err(PathError::InvalidUtf8),
+        Err(err) => Ok(String::from_utf8_lossy(err.as_bytes()).into_owned()),
+    }
+}
+
+/// Resolve `.` and `..` segments (RFC 3986 section 5.2.4), optionally
+/// dropping empty segments
+fn remove_dot_segments(path: &str, merge_slashes: bool) -> Result<String, PathError> {
+    let mut segments: Vec<&str> = Vec::new();
+    let mut parts = path[1..].split('/').peekable();
+    let mut trailing_slash = false;
+    while let Some(segment) = parts.next() {
+        let last = parts.peek().is_none();
+        match segment {
+            "." => trailing_slash = last,
+            ".." => {
+                if segments.pop().is_none() {
+                    return Err(PathError::EscapesRoot);
+                }
+                trailing_slash = last;
+            }
+            "" if last => trailing_slash = true,
+            "" if merge_slashes => {}
+            _ => {
+                segments.push(segment);
+                trailing_slash = false;
+            }
+        }
+    }
+    let mut normalized = String::with_capacity(path.len());
+    for segment in &segments {
+        normalized.push('/');
+        normalized.push_str(segment);
+    }
+    if trailing_slash || segments.is_empty() {
+        normalized.push('/');
+    }
+    Ok(normalized)
+}
@@ -0,0 +1,465 @@
+//! `Request.ndjson()` and `Request.csv()`: one-record-per-line bodies as
+//! iterators.
+//!
+//! Both parsers read the body through `BodyChunks`, a `Read` over a
+//! sequence of byte chunks. Today the chunks are `chunk_size` slices of the
+//! buffered body; a streaming body can hand them over as they arrive
+//! without changing the parsers. Records are parsed one `next()` at a time,
+//! so a handler that stops early never pays for the rest of the body.
+//!
+//! Limits are checked as the body is read: the longest NDJSON line, the
+//! largest CSV field and the number of records. Going over one raises
+//! `PayloadTooLargeError`; malformed input raises `BodyDecodeError` naming
+//! the line. NDJSON can instead skip up to `max_bad_lines` unparseable
+//! lines, which are listed in `bad_lines` afterwards.
+
+use std::io::Read;
+
+use bytes::{Buf, Bytes};
+use parking_lot::Mutex;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyString};
+
+use super::body::{BodyDecodeError, PayloadTooLargeError};
+use crate::utils::json::json_value_to_py;
+
+/// NDJSON content types, compared on the lowercased essence
+pub const NDJSON_TYPES: [&str; 5] = [
+    "application/x-ndjson",
+    "application/ndjson",
+    "application/jsonl",
+    "application/x-jsonlines",
+    "application/jsonlines",
+];
+
+/// CSV content types
+pub const CSV_TYPES: [&str; 2] = ["text/csv", "application/csv"];
+
+/// Whether the essence of `content_type` is one of `types`
+pub fn is_mime(content_type: &str, types: &[&str]) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    types.contains(&essence.as_str())
+}
+
+/// The body as a sequence of chunks, readable with `Read`
+pub struct BodyChunks {
+    chunks: Box<dyn Iterator<Item = Bytes> + Send>,
+    current: Bytes,
+}
+
+impl BodyChunks {
+    /// `chunk_size` slices of a body already in memory, without a leading
+    /// UTF-8 BOM
+    pub fn buffered(body: Bytes, chunk_size: usize) -> Self {
+        let mut body = body;
+        if body.starts_with(b"\xEF\xBB\xBF") {
+            body.advance(3);
+        }
+        let chunks = std::iter::from_fn(move || {
+            if body.is_empty() {
+                return None;
+            }
+            let len = body.len().min(chunk_size);
+            Some(body.split_to(len))
+        });
+        Self {
+            chunks: Box::new(chunks),
+            current: Bytes::new(),
+        }
+    }
+
+    /// The unread part of the current chunk, pulling the next one when it
+    /// is used up; empty at the end of the body
+    fn fill(&mut self) -> &mut Bytes {
+        while self.current.is_empty() {
+            match self.chunks.next() {
+                Some(chunk) => self.current = chunk,
+                None => break,
+            }
+        }
+        &mut self.current
+    }
+}
+
+impl Read for BodyChunks {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let current = self.fill();
+        let len = current.len().min(buf.len());
+        buf[..len].copy_from_slice(&current[..len]);
+        current.advance(len);
+        Ok(len)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// NDJSON
+// ---------------------------------------------------------------------------
+
+/// Limits of `Request.ndjson()`
+#[derive(Clone, Copy, Debug)]
+pub struct NdjsonLimits {
+    pub max_line_bytes: usize,
+    pub max_records: usize,
+    pub max_bad_lines: usize,
+}
+
+struct NdjsonState {
+    body: BodyChunks,
+    /// The line being assembled across chunks
+    pending: Vec<u8>,
+    /// Number of the last line read (1-based)
+    line: usize,
+    records: usize,
+    bad_lines: Vec<(usize, String)>,
+    limits: NdjsonLimits,
+    error: Option<PyErr>,
+    done: bool,
+}
+
+impl NdjsonState {
+    /// The next line without its terminator, None at the end of the body
+    fn next_line(&mut self) -> PyResult<Option<Vec<u8>>> {
+        loop {
+            let max = self.limits.max_line_bytes;
+            let chunk = self.body.fill();
+            if chunk.is_empty() {
+                if self.pending.is_empty() {
+                    return Ok(None);
+                }
+                self.line += 1;
+                return Ok(Some(std::mem::take(&mut self.pending)));
+            }
+            let end = chunk.iter().position(|&b| b == b'\n');
+            let take = end.unwrap_or(chunk.len());
+            if self.pending.len() + take > max {
+                return Err(PayloadTooLargeError::new_err(format!(
+                    "NDJSON line {} is over the {} byte limit",
+                    self.line + 1,
+                    max
+                )));
+            }
+            self.pending.extend_from_slice(&chunk[..take]);
+            if end.is_some() {
+                chunk.advance(take + 1);
+                self.line += 1;
+                return Ok(Some(std::mem::take(&mut self.pending)));
+            }
+            chunk.advance(take);
+        }
+    }
+
+    fn next_record(&mut self, py: Python<'_>) -> PyResult<Option<Py<PyAny>>> {
+        while let Some(mut line) = self.next_line()? {
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            let value = match simd_json::serde::from_slice::<serde_json::Value>(&mut line) {
+                Ok(value) => value,
+                Err(err) => {
+                    self.bad_lines.push((self.line, err.to_string()));
+                    if self.bad_lines.len() > self.limits.max_bad_lines {
+                        return Err(BodyDecodeError::new_err(format!(
+                            "NDJSON line {} is not valid JSON: {} ({} bad line{} allowed)",
+                            self.line,
+                            err,
+                            self.limits.max_bad_lines,
+                            if self.limits.max_bad_lines == 1 {
+                                ""
+                            } else {
+                                "s"
+                            }
+                        )));
+                    }
+                    continue;
+                }
+            };
+            self.records += 1;
+            if self.records > self.limits.max_records {
+                return Err(PayloadTooLargeError::new_err(format!(
+                    "NDJSON body has more than {} records",
+                    self.limits.max_records
+                )));
+            }
+            return json_value_to_py(py, &value).map(Some);
+        }
+        Ok(None)
+    }
+}
+
+/// Iterator over the JSON documents of an NDJSON body, one per line.
+///
+/// Blank lines are skipped. Lines that are not valid JSON are skipped too,
+/// up to `max_bad_lines`, and listed in `bad_lines` as `(line, error)`.
+#[pyclass(name = "NdjsonReader")]
+pub struct NdjsonReader {
+    state: Mutex<NdjsonState>,
+}
+
+impl NdjsonReader {
+    pub fn new(body: BodyChunks, limits: NdjsonLimits) -> Self {
+        Self {
+            state: Mutex::new(NdjsonState {
+                body,
+                pending: Vec::new(),
+                line: 0,
+                records: 0,
+                bad_lines: Vec::new(),
+                limits,
+                error: None,
+                done: false,
+            }),
+        }
+    }
+}
+
+#[pymethods]
+impl NdjsonReader {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<Option<Py<PyAny>>> {
+        let mut state = self.state.lock();
+        if let Some(err) = &state.error {
+            return Err(err.clone_ref(py));
+        }
+        if state.done {
+            return Ok(None);
+        }
+        match state.next_record(py) {
+            Ok(Some(record)) => Ok(Some(record)),
+            Ok(None) => {
+                state.done = true;
+                Ok(None)
+            }
+            Err(err) => {
+                state.error = Some(err.clone_ref(py));
+                Err(err)
+            }
+        }
+    }
+
+    /// Lines skipped as invalid JSON, as `(line number, error)`
+    #[getter]
+    fn bad_lines(&self) -> Vec<(usize, String)> {
+        self.state.lock().bad_lines.clone()
+    }
+
+    /// Number of lines read so far, blank and bad ones included
+    #[getter]
+    fn lines(&self) -> usize {
+        self.state.lock().line
+    }
+
+    /// Number of records returned so far
+    #[getter]
+    fn records(&self) -> usize {
+        self.state.lock().records
+    }
+}
+
+// ---------------------------------------------------------------------------
+// CSV
+// ---------------------------------------------------------------------------
+
+/// Limits of `Request.csv()`
+#[derive(Clone, Copy, Debug)]
+pub struct CsvLimits {
+    pub max_field_bytes: usize,
+    pub max_rows: usize,
+}
+
+struct CsvState {
+    reader: csv::Reader<BodyChunks>,
+    record: csv::ByteRecord,
+    has_header: bool,
+    /// Column names, read with the first row
+    header: Option<Vec<Py<PyString>>>,
+    rows: usize,
+    limits: CsvLimits,
+    error: Option<PyErr>,
+    done: bool,
+}
+
+fn csv_error(err: csv::Error) -> PyErr {
+    BodyDecodeError::new_err(format!("Malformed CSV body: {}", err))
+}
+
+impl CsvState {
+    /// Check the fields of the record just read and decode them
+    fn fields<'a>(&self, record: &'a csv::ByteRecord) -> PyResult<Vec<&'a str>> {
+        let line = record.position().map_or(0, |pos| pos.line());
+        record
+            .iter()
+            .enumerate()
+            .map(|(column, field)| {
+                if field.len() > self.limits.max_field_bytes {
+                    return Err(PayloadTooLargeError::new_err(format!(
+                        "CSV field {} on line {} is over the {} byte limit",
+                        column + 1,
+                        line,
+                        self.limits.max_field_bytes
+                    )));
+                }
+                std::str::from_utf8(field).map_err(|_| {
+                    BodyDecodeError::new_err(format!(
+                        "CSV field {} on line {} is not valid UTF-8",
+                        column + 1,
+                        line
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    fn read_header(&mut self, py: Python<'_>) -> PyResult<()> {
+        let names = self.reader.byte_headers().map_err(csv_error)?.clone();
+        let header = self
+            .fields(&names)?
+            .into_iter()
+            .map(|name| PyString::intern(py, name).unbind())
+            .collect();
+        self.header = Some(header);
+        Ok(())
+    }
+
+    fn next_row(&mut self, py: Python<'_>) -> PyResult<Option<Py<PyAny>>> {
+        if self.has_header && self.header.is_none() {
+            self.read_header(py)?;
+        }
+        let mut record = std::mem::take(&mut self.record);
+        let read = self.reader.read_byte_record(&mut record);
+        let row = match read {
+            Ok(false) => Ok(None),
+            Ok(true) => self.build_row(py, &record).map(Some),
+            Err(err) => Err(csv_error(err)),
+        };
+        self.record = record;
+        row
+    }
+
+    fn build_row(&mut self, py: Python<'_>, record: &csv::ByteRecord) -> PyResult<Py<PyAny>> {
+        self.rows += 1;
+        if self.rows > self.limits.max_rows {
+            return Err(PayloadTooLargeError::new_err(format!(
+                "CSV body has more than {} rows",
+                self.limits.max_rows
+            )));
+        }
+        let fields = self.fields(record)?;
+        match &self.header {
+            Some(header) => {
+                let row = PyDict::new(py);
+                for (name, value) in header.iter().zip(fields) {
+                    row.set_item(name.bind(py), value)?;
+                }
+                Ok(row.into_any().unbind())
+            }
+            None => Ok(PyList::new(py, fields)?.into_any().unbind()),
+        }
+    }
+}
+
+/// Iterator over the rows of a CSV body: dicts keyed by the header row,
+/// or lists of strings without one.
+///
+/// Quoted fields may contain the delimiter, doubled quotes and newlines.
+/// Every row must have as many fields as the first.
+#[pyclass(name = "CsvReader")]
+pub struct CsvReader {
+    state: Mutex<CsvState>,
+}
+
+impl CsvReader {
+    pub fn new(
+        body: BodyChunks,
+        delimiter: &str,
+        has_header: bool,
+        limits: CsvLimits,
+    ) -> PyResult<Self> {
+        let delimiter = match delimiter.as_bytes() {
+            [byte] if byte.is_ascii() => *byte,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "delimiter must be a single ASCII character, got {:?}",
+                    delimiter
+                )))
+            }
+        };
+        let reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(has_header)
+            .from_reader(body);
+        Ok(Self {
+            state: Mutex::new(CsvState {
+                reader,
+                record: csv::ByteRecord::new(),
+                has_header,
+                header: None,
+                rows: 0,
+                limits,
+                error: None,
+                done: false,
+            }),
+        })
+    }
+}
+
+#[pymethods]
+impl CsvReader {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<Option<Py<PyAny>>> {
+        let mut state = self.state.lock();
+        if let Some(err) = &state.error {
+            return Err(err.clone_ref(py));
+        }
+        if state.done {
+            return Ok(None);
+        }
+        match state.next_row(py) {
+            Ok(Some(row)) => Ok(Some(row)),
+            Ok(None) => {
+                state.done = true;
+                Ok(None)
+            }
+            Err(err) => {
+                state.error = Some(err.clone_ref(py));
+                Err(err)
+            }
+        }
+    }
+
+    /// Column names from the header row (None without one)
+    #[getter]
+    fn header(&self, py: Python<'_>) -> PyResult<Option<Vec<Py<PyString>>>> {
+        let mut state = self.state.lock();
+        if !state.has_header {
+            return Ok(None);
+        }
+        if state.header.is_none() {
+            state.read_header(py)?;
+        }
+        Ok(state
+            .header
+            .as_ref()
+            .map(|names| names.iter().map(|name| name.clone_ref(py)).collect()))
+    }
+
+    /// Number of rows returned so far, not counting the header
+    #[getter]
+    fn rows(&self) -> usize {
+        self.state.lock().rows
+    }
+}
@@ -1,17 +1,58 @@
+use crate::core::request_scope::RequestScope;
+use crate::http::response_fields::ResponseFields;
+use crate::routing::route::RouteConfig;
+use crate::core::deps::RequestDeps;
+use crate::core::deadline::Deadline;
+use crate::http::connection::ConnectionInfo;
+use crate::http::disconnect::{Disconnect, DisconnectWait};
+use crate::http::body::{
+    content_type_charset, decode_to_utf8, BodyDecodeError, PayloadTooLargeError,
+    UnsupportedMediaTypeError,
+};
 use crate::http::headers::HeaderMap;
 use crate::http::method::HttpMethod;
+use crate::http::path::NormalizedPath;
+use crate::http::urlencoded;
+use crate::middleware::{MiddlewareState, StateValue};
+use crate::routing::params::TypedValue;
 use ahash::AHashMap;
 use bytes::Bytes;
+use pyo3::exceptions::PyTypeError;
 use pyo3::prelude::*;
 use pyo3::types::{PyBytes, PyDict};
+use pyo3::IntoPyObjectExt;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use xxhash_rust::xxh3::xxh3_64;
 
+/// Largest request body read into memory
+pub const MAX_BODY_SIZE: usize = 10 * 1024 * 1024;
+
+/// Index of the first of `types` an Accept header names, else of the first
+/// one when it accepts anything (`*/*`). Short names like "json" stand for
+/// their media type.
+pub fn negotiate<T: AsRef<str>>(accept: &str, types: &[T]) -> Option<usize> {
+    let named = types.iter().position(|t| {
+        let t = t.as_ref();
+        let media_type = match t.to_lowercase().as_str() {
+            "html" => "text/html",
+            "json" => "application/json",
+            "xml" => "application/xml",
+            "text" => "text/plain",
+            _ => t,
+        };
+        accept.contains(media_type)
+    });
+    named.or_else(|| (!types.is_empty() && accept.contains("*/*")).then_some(0))
+}
+
 /// Query parameters with lazy parsing
 #[derive(Clone, Debug, Default)]
 pub struct QueryParams {
     raw: Arc<str>,
+    /// Every pair in order, repeats included
+    pairs: Option<Arc<Vec<(String, String)>>>,
+    /// Last value for each key
     parsed: Option<AHashMap<String, String>>,
 }
 
@@ -19,19 +60,19 @@ impl QueryParams {
     pub fn new(raw: &str) -> Self {
         Self {
             raw: Arc::from(raw),
+            pairs: None,
             parsed: None,
         }
     }
 
+    pub fn pairs(&mut self) -> &Arc<Vec<(String, String)>> {
+        self.pairs
+            .get_or_insert_with(|| Arc::new(urlencoded::parse(self.raw.as_bytes())))
+    }
+
     pub fn parse(&mut self) -> &AHashMap<String, String> {
         if self.parsed.is_none() {
-            let map: AHashMap<String, String> = if self.raw.is_empty() {
-                AHashMap::new()
-            } else {
-                form_urlencoded::parse(self.raw.as_bytes())
-                    .into_owned()
-                    .collect()
-            };
+            let map: AHashMap<String, String> = self.pairs().iter().cloned().collect();
             self.parsed = Some(map);
         }
         self.parsed.as_ref().unwrap()
@@ -42,31 +83,115 @@ impl QueryParams {
     }
 }
 
+fn state_type_name(value: &StateValue) -> &'static str {
+    match value {
+        StateValue::String(_) => "str",
+        StateValue::Int(_) => "int",
+        StateValue::Float(_) => "float",
+        StateValue::Bool(_) => "bool",
+        StateValue::Bytes(_) => "bytes",
+    }
+}
+
+/// Authentication state the middleware chain left for the handler
+#[pyclass(frozen, skip_from_py_object)]
+#[derive(Debug, Clone, Default)]
+pub struct AuthInfo {
+    #[pyo3(get)]
+    pub user_id: Option<String>,
+    #[pyo3(get)]
+    pub roles: Vec<String>,
+    #[pyo3(get)]
+    pub is_authenticated: bool,
+}
+
+#[pymethods]
+impl AuthInfo {
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    fn __bool__(&self) -> bool {
+        self.is_authenticated
+    }
+
+    fn __repr__(&self) -> String {
+        let authenticated = if self.is_authenticated {
+            "True"
+        } else {
+            "False"
+        };
+        format!(
+            "AuthInfo(user_id={:?}, roles={:?}, is_authenticated={})",
+            self.user_id, self.roles, authenticated
+        )
+    }
+}
+
 /// Zero-copy request structure for high-performance request handling.
 #[pyclass(frozen, from_py_object)]
 pub struct Request {
     path: Arc<str>,
+    /// Path as received, before percent-decoding and normalization
+    raw_path: Arc<str>,
+    /// Normalized path the router matches on (`%2F` stays encoded)
+    routing_path: Arc<str>,
     method: HttpMethod,
+    /// Name of a non-standard method (`PROPFIND`), which `method` folds to GET
+    extension_method: Option<Arc<str>>,
     headers: Arc<HeaderMap>,
     #[pyo3(get)]
     query_string: String,
     query_params: parking_lot::RwLock<QueryParams>,
     path_params: parking_lot::RwLock<HashMap<String, String>>,
+    /// `{name:type}` parameters converted at dispatch
+    typed_params: OnceLock<Arc<HashMap<String, TypedValue>>>,
     body: parking_lot::RwLock<Option<Bytes>>,
     route_hash: u64,
+    scope: OnceLock<Arc<RequestScope>>,
+    /// State of the middleware chain that ran for the request, shared rather
+    /// than copied; handlers only see the keys marked visible
+    middleware_state: OnceLock<Arc<parking_lot::RwLock<Option<MiddlewareState>>>>,
+    /// Route-level cap on bodies parsed by `json()`
+    max_json_bytes: OnceLock<usize>,
+    /// Per-route settings of the matched route
+    route_config: OnceLock<Arc<RouteConfig>>,
+    /// Set when the route runs under a timeout
+    deadline: OnceLock<Deadline>,
+    /// Parsed `json()` result, indexed by the `force` flag
+    json_cache: parking_lot::Mutex<[Option<Py<PyAny>>; 2]>,
+    /// Request-scoped dependency instances, torn down on completion
+    deps: Arc<RequestDeps>,
+    /// Transport details, shared by every request on the connection
+    connection: Option<ConnectionInfo>,
+    /// Set when the client goes away before the response is sent
+    disconnect: Disconnect,
 }
 
 impl Clone for Request {
     fn clone(&self) -> Self {
         Self {
             path: self.path.clone(),
+            raw_path: self.raw_path.clone(),
+            routing_path: self.routing_path.clone(),
             method: self.method,
+            extension_method: self.extension_method.clone(),
             headers: self.headers.clone(),
             query_string: self.query_string.clone(),
             query_params: parking_lot::RwLock::new(self.query_params.read().clone()),
             path_params: parking_lot::RwLock::new(self.path_params.read().clone()),
+            typed_params: self.typed_params.clone(),
             body: parking_lot::RwLock::new(self.body.read().clone()),
             route_hash: self.route_hash,
+            scope: self.scope.clone(),
+            middleware_state: self.middleware_state.clone(),
+            max_json_bytes: self.max_json_bytes.clone(),
+            route_config: self.route_config.clone(),
+            deadline: self.deadline.clone(),
+            json_cache: parking_lot::Mutex::new([None, None]),
+            deps: self.deps.clone(),
+            connection: self.connection.clone(),
+            disconnect: self.disconnect.clone(),
         }
     }
 }
@@ -79,18 +204,31 @@ impl Request {
         query_string: &str,
         body: Option<Bytes>,
     ) -> Self {
-        let path_arc = Arc::from(path);
+        let path_arc: Arc<str> = Arc::from(path);
         let route_hash = xxh3_64(path.as_bytes()) ^ (method as u64);
 
         Self {
+            raw_path: path_arc.clone(),
+            routing_path: path_arc.clone(),
             path: path_arc,
             method,
+            extension_method: None,
             headers: Arc::new(headers),
             query_string: query_string.to_string(),
             query_params: parking_lot::RwLock::new(QueryParams::new(query_string)),
             path_params: parking_lot::RwLock::new(HashMap::new()),
+            typed_params: OnceLock::new(),
             body: parking_lot::RwLock::new(body),
             route_hash,
+            scope: OnceLock::new(),
+            middleware_state: OnceLock::new(),
+            max_json_bytes: OnceLock::new(),
+            route_config: OnceLock::new(),
+            deadline: OnceLock::new(),
+            json_cache: parking_lot::Mutex::new([None, None]),
+            deps: Arc::default(),
+            connection: None,
+            disconnect: Disconnect::new(),
         }
     }
 
@@ -103,14 +241,32 @@ impl Request {
         *self.path_params.write() = params;
     }
 
+    /// Attach the converted `{name:type}` parameters; the first call wins
+    pub fn set_typed_params(&self, params: HashMap<String, TypedValue>) {
+        let _ = self.typed_params.set(Arc::new(params));
+    }
+
     pub fn path(&self) -> &str {
         &self.path
     }
 
+    /// Normalized path used for route matching
+    pub fn routing_path(&self) -> &str {
+        &self.routing_path
+    }
+
     pub fn method(&self) -> HttpMethod {
         self.method
     }
 
+    /// The method as sent, including non-standard ones routed to `*` routes
+    #[inline]
+    pub fn method_name(&self) -> &str {
+        self.extension_method
+            .as_deref()
+            .unwrap_or_else(|| self.method.as_str())
+    }
+
     #[inline]
     pub fn take_body(&self) -> Option<Bytes> {
         self.body.write().take()
@@ -133,6 +289,95 @@ impl Request {
     pub fn query_string(&self) -> &str {
         &self.query_string
     }
+
+    /// Attach the request scope published to the handler; the first call wins
+    pub fn set_scope(&self, scope: Arc<RequestScope>) {
+        let _ = self.scope.set(scope);
+    }
+
+    pub fn scope(&self) -> Option<Arc<RequestScope>> {
+        self.scope.get().cloned()
+    }
+
+    /// Share the middleware chain's state with the handler; the first call wins
+    pub fn set_middleware_state(&self, state: Arc<parking_lot::RwLock<Option<MiddlewareState>>>) {
+        let _ = self.middleware_state.set(state);
+    }
+
+    /// Handler-visible middleware state value for `key`
+    fn visible_state(&self, key: &str) -> Option<StateValue> {
+        let state = self.middleware_state.get()?.read();
+        state.as_ref()?.visible_value(key).cloned()
+    }
+
+    /// Visible state value of the type `expected`, `default` when unset
+    fn typed_state<T>(
+        &self,
+        key: &str,
+        default: Option<T>,
+        expected: &str,
+        extract: impl FnOnce(StateValue) -> Result<T, StateValue>,
+    ) -> PyResult<Option<T>> {
+        match self.visible_state(key).map(extract) {
+            None => Ok(default),
+            Some(Ok(value)) => Ok(Some(value)),
+            Some(Err(other)) => Err(PyTypeError::new_err(format!(
+                "state {:?} is {}, not {}",
+                key,
+                state_type_name(&other),
+                expected
+            ))),
+        }
+    }
+
+    /// Apply the matched route's `max_json_bytes` limit; the first call wins
+    pub fn set_max_json_bytes(&self, limit: usize) {
+        let _ = self.max_json_bytes.set(limit);
+    }
+
+    /// Attach the matched route's `RouteConfig`; the first call wins
+    pub fn set_route_config(&self, config: Arc<RouteConfig>) {
+        let _ = self.route_config.set(config);
+    }
+
+    /// Response field filter of the matched route
+    pub fn response_fields(&self) -> Option<Arc<ResponseFields>> {
+        self.route_config
+            .get()
+            .and_then(|config| config.response_fields.clone())
+    }
+
+    /// Attach the deadline the handler runs under; the first call wins
+    pub fn set_deadline(&self, deadline: Deadline) {
+        let _ = self.deadline.set(deadline);
+    }
+
+    pub fn deadline(&self) -> Option<Deadline> {
+        self.deadline.get().copied()
+    }
+
+    pub fn connection(&self) -> Option<&ConnectionInfo> {
+        self.connection.as_ref()
+    }
+
+    /// Request-scoped dependency instances, shared with clones of this request
+    pub fn deps(&self) -> Arc<RequestDeps> {
+        self.deps.clone()
+    }
+
+    /// A copy for dispatching the handler again, with its own
+    /// request-scoped dependencies (the previous run tore its own down)
+    pub fn replay(&self) -> Self {
+        Self {
+            deps: Arc::default(),
+            ..self.clone()
+        }
+    }
+
+    /// Token the worker signals when the client disconnects
+    pub fn disconnect(&self) -> Disconnect {
+        self.disconnect.clone()
+    }
 }
 
 #[pymethods]
@@ -142,9 +387,16 @@ impl Request {
         &self.path
     }
 
+    /// Path exactly as sent by the client, before percent-decoding and
+    /// normalization (`path` is the decoded, normalized form)
+    #[getter(raw_path)]
+    fn py_raw_path(&self) -> &str {
+        &self.raw_path
+    }
+
     #[getter(method)]
     fn py_method(&self) -> &str {
-        self.method.as_str()
+        self.method_name()
     }
 
     #[getter(query_string)]
@@ -170,31 +422,71 @@ impl Request {
         self.query_params.write().get(name).cloned()
     }
 
+    /// Every value of a repeated query parameter, in order.
+    ///
+    /// Args:
+    ///     name: Parameter name
+    ///     strip_brackets: Also match `name[]` (`?tag[]=a&tag[]=b`)
+    #[pyo3(signature = (name, strip_brackets=true))]
+    pub fn query_list(&self, name: &str, strip_brackets: bool) -> Vec<String> {
+        let pairs = self.query_params.write().pairs().clone();
+        urlencoded::values(&pairs, name, strip_brackets)
+    }
+
+    /// All query parameters as lists of values, keys in the order first seen.
+    ///
+    /// Args:
+    ///     strip_brackets: Group `name[]` keys under `name`
+    #[pyo3(signature = (strip_brackets=true))]
+    pub fn query_params_multi<'py>(
+        &self,
+        py: Python<'py>,
+        strip_brackets: bool,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let pairs = self.query_params.write().pairs().clone();
+        urlencoded::grouped_dict(py, &pairs, strip_brackets)
+    }
+
     pub fn param(&self, name: &str) -> Option<String> {
         self.path_params.read().get(name).cloned()
     }
 
+    /// Path parameter converted by its route constraint: `int` and `float`
+    /// as numbers, `uuid` as `uuid.UUID`, `date` as `datetime.date`.
+    /// Unconstrained parameters are returned as strings, like `param()`.
+    pub fn param_typed<'py>(
+        &self,
+        py: Python<'py>,
+        name: &str,
+    ) -> PyResult<Option<Bound<'py, PyAny>>> {
+        if let Some(value) = self.typed_params.get().and_then(|typed| typed.get(name)) {
+            return value.to_py(py).map(Some);
+        }
+        self.param(name)
+            .map(|value| value.into_bound_py_any(py))
+            .transpose()
+    }
+
+    /// All path parameters, constrained ones converted as in `param_typed()`
+    pub fn params_typed<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        let typed = self.typed_params.get();
+        for (name, value) in self.path_params.read().iter() {
+            match typed.and_then(|typed| typed.get(name)) {
+                Some(typed) => dict.set_item(name, typed.to_py(py)?)?,
+                None => dict.set_item(name, value)?,
+            }
+        }
+        Ok(dict)
+    }
+
     pub fn header(&self, name: &str) -> Option<String> {
         self.headers.get(name).cloned()
     }
 
     pub fn accepts(&self, types: Vec<String>) -> Option<String> {
         let accept = self.headers.get("accept")?;
-
-        for t in types {
-            let check = match t.to_lowercase().as_str() {
-                "html" => "text/html",
-                "json" => "application/json",
-                "xml" => "application/xml",
-                "text" => "text/plain",
-                _ => &t,
-            };
-
-            if accept.contains(check) || accept.contains("*/*") {
-                return Some(t);
-            }
-        }
-        None
+        negotiate(accept, &types).map(|index| types[index].clone())
     }
 
     pub fn accepts_json(&self) -> bool {
@@ -205,6 +497,116 @@ impl Request {
         self.accepts(vec!["html".to_string()]).is_some()
     }
 
+    /// Request context dict (request_id, trace_id, path, client_ip, propagated
+    /// middleware state); the same values `hypern.current_context()` returns
+    /// inside the handler, available even where contextvars are not shared.
+    #[getter]
+    pub fn context<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        self.scope.get().map(|scope| scope.to_dict(py)).transpose()
+    }
+
+    /// Value a middleware stored for the handler under `key`, or `default`.
+    /// Only keys the middleware marked visible (or propagated) are readable;
+    /// the rest of the chain's state, such as raw credentials, is not.
+    #[pyo3(signature = (key, default=None))]
+    pub fn state(
+        &self,
+        py: Python<'_>,
+        key: &str,
+        default: Option<Py<PyAny>>,
+    ) -> PyResult<Option<Py<PyAny>>> {
+        match self.visible_state(key) {
+            Some(value) => crate::core::request_scope::state_value_to_py(py, &value).map(Some),
+            None => Ok(default),
+        }
+    }
+
+    /// `state(key)` as a str; raises TypeError when the value is another type
+    #[pyo3(signature = (key, default=None))]
+    pub fn state_str(&self, key: &str, default: Option<String>) -> PyResult<Option<String>> {
+        self.typed_state(key, default, "str", |value| match value {
+            StateValue::String(s) => Ok(s),
+            other => Err(other),
+        })
+    }
+
+    /// `state(key)` as an int; raises TypeError when the value is another type
+    #[pyo3(signature = (key, default=None))]
+    pub fn state_int(&self, key: &str, default: Option<i64>) -> PyResult<Option<i64>> {
+        self.typed_state(key, default, "int", |value| match value {
+            StateValue::Int(i) => Ok(i),
+            other => Err(other),
+        })
+    }
+
+    /// `state(key)` as a bool; raises TypeError when the value is another type
+    #[pyo3(signature = (key, default=None))]
+    pub fn state_bool(&self, key: &str, default: Option<bool>) -> PyResult<Option<bool>> {
+        self.typed_state(key, default, "bool", |value| match value {
+            StateValue::Bool(b) => Ok(b),
+            other => Err(other),
+        })
+    }
+
+    /// Who the auth middleware authenticated; unauthenticated when no
+    /// middleware did, or none ran
+    #[getter]
+    pub fn auth(&self) -> AuthInfo {
+        let state = self.middleware_state.get().map(|state| state.read());
+        match state.as_deref() {
+            Some(Some(state)) => AuthInfo {
+                user_id: state.user_id.clone(),
+                roles: state.roles.clone(),
+                is_authenticated: state.is_authenticated,
+            },
+            _ => AuthInfo::default(),
+        }
+    }
+
+    /// Metadata of the matched route (`Route(..., metadata={...})`); empty
+    /// when the route has none.
+    #[getter]
+    pub fn route_meta(&self) -> HashMap<String, String> {
+        self.route_config
+            .get()
+            .map(|config| config.metadata.clone())
+            .unwrap_or_default()
+    }
+
+    /// Whether the feature flag `name` is on for this request (see
+    /// `Server.set_flag`); False for flags that were never set.
+    pub fn flag(&self, name: &str) -> bool {
+        crate::routing::flags::lookup(name)
+            .is_some_and(|flag| crate::routing::flags::evaluate_for(&flag, self))
+    }
+
+    /// Seconds left before the route's timeout answers 504, or None when the
+    /// route has no timeout. Returns 0.0 once the deadline has passed, so
+    /// long-running handlers can check it and stop early.
+    pub fn deadline_remaining(&self) -> Option<f64> {
+        self.deadline.get().map(|d| d.remaining().as_secs_f64())
+    }
+
+    /// True once the client has closed the connection. Sync handlers cannot
+    /// be cancelled, so long loops and generators should check it and stop.
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnect.is_set()
+    }
+
+    /// Awaitable that completes when the client closes the connection, for
+    /// async handlers to race against their own work
+    pub fn wait_disconnected(&self) -> DisconnectWait {
+        DisconnectWait::new(self.disconnect.clone())
+    }
+
+    /// Resolve a dependency registered with `Server.provide` / `Hypern.provide`.
+    ///
+    /// App-scoped dependencies are shared by the worker; request-scoped ones
+    /// are created on first use and torn down when the handler completes.
+    pub fn dep(&self, py: Python<'_>, name: &str) -> PyResult<Py<PyAny>> {
+        crate::core::deps::resolve_name(py, name, Some(&self.deps))
+    }
+
     #[getter]
     pub fn ip(&self) -> Option<String> {
         // Check X-Forwarded-For first (for proxies)
@@ -239,7 +641,40 @@ impl Request {
         if let Some(proto) = self.headers.get("x-forwarded-proto") {
             return proto.to_lowercase() == "https";
         }
-        false
+        self.connection.as_ref().is_some_and(|c| c.is_tls())
+    }
+
+    /// Scheme of the connection itself ("http" or "https"), ignoring proxy
+    /// headers; None when the request did not come from a listener.
+    #[getter]
+    pub fn scheme(&self) -> Option<&'static str> {
+        self.connection.as_ref().map(|c| c.scheme())
+    }
+
+    /// Remote `(host, port)` of the TCP connection
+    #[getter]
+    pub fn peer_addr(&self) -> Option<(String, u16)> {
+        let addr = self.connection.as_ref()?.peer_addr()?;
+        Some((addr.ip().to_string(), addr.port()))
+    }
+
+    /// Protocol negotiated via ALPN on TLS connections ("h2", "http/1.1")
+    #[getter]
+    pub fn alpn_protocol(&self) -> Option<String> {
+        Some(self.connection.as_ref()?.alpn_protocol()?.to_string())
+    }
+
+    /// Verified client certificate on mTLS connections: a dict with
+    /// `subject`, `issuer`, `serial`, `sans`, `not_before`, `not_after` and
+    /// `fingerprint` (SHA-256 hex). None on plaintext connections or when the
+    /// client sent no certificate.
+    #[getter]
+    pub fn peer_cert<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        self.connection
+            .as_ref()
+            .and_then(|c| c.peer_cert())
+            .map(|cert| cert.to_dict(py))
+            .transpose()
     }
 
     #[getter]
@@ -287,6 +722,15 @@ impl Request {
         }
     }
 
+    /// The entity tags of If-None-Match as sent (`"a"`, `W/"b"` or `*`);
+    /// empty without the header
+    pub fn if_none_match(&self) -> Vec<String> {
+        self.headers
+            .get("if-none-match")
+            .map(|value| crate::http::conditional::parse_list(value))
+            .unwrap_or_default()
+    }
+
     pub fn fresh(&self, etag: Option<&str>, last_modified: Option<&str>) -> bool {
         // Check If-None-Match (ETag)
         if let Some(if_none_match) = self.headers.get("if-none-match") {
@@ -354,15 +798,60 @@ impl Request {
         }
     }
 
-    fn json<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+    /// Parse the body as JSON.
+    ///
+    /// Args:
+    ///     force: Parse even when the Content-Type is not JSON
+    ///     max_bytes: Reject bodies larger than this (the route's
+    ///         `max_json_bytes` applies too; the smaller limit wins)
+    ///
+    /// Raises UnsupportedMediaTypeError (415) for non-JSON content types or
+    /// unknown charsets, PayloadTooLargeError (413) over the limit, and
+    /// BodyDecodeError (400) for invalid encodings or malformed JSON.
+    #[pyo3(signature = (force=false, max_bytes=None))]
+    fn json<'py>(
+        &self,
+        py: Python<'py>,
+        force: bool,
+        max_bytes: Option<usize>,
+    ) -> PyResult<Bound<'py, PyAny>> {
         let body = self.body.read();
-        match body.as_ref() {
-            Some(bytes) => {
-                let result = crate::utils::parse_json_to_py(py, bytes)?;
-                Ok(result.into_bound(py))
+        let Some(bytes) = body.as_ref() else {
+            return Ok(py.None().into_bound(py));
+        };
+
+        let content_type = self.content_type().unwrap_or_default();
+        if !force && !self.is_json() {
+            return Err(UnsupportedMediaTypeError::new_err(format!(
+                "Expected a JSON content type, got '{}' (pass force=True to parse anyway)",
+                content_type
+            )));
+        }
+
+        let limit = match (max_bytes, self.max_json_bytes.get()) {
+            (Some(a), Some(&b)) => Some(a.min(b)),
+            (a, b) => a.or(b.copied()),
+        };
+        if let Some(limit) = limit {
+            if bytes.len() > limit {
+                return Err(PayloadTooLargeError::new_err(format!(
+                    "JSON body of {} bytes exceeds the {} byte limit",
+                    bytes.len(),
+                    limit
+                )));
             }
-            None => Ok(py.None().into_bound(py)),
         }
+
+        if let Some(cached) = &self.json_cache.lock()[force as usize] {
+            return Ok(cached.bind(py).clone());
+        }
+
+        let charset = content_type_charset(&content_type);
+        let utf8 = decode_to_utf8(bytes, charset.as_deref())?;
+        let result = crate::utils::parse_json_to_py(py, &utf8)
+            .map_err(|e| BodyDecodeError::new_err(e.value(py).to_string()))?;
+        self.json_cache.lock()[force as usize] = Some(result.clone_ref(py));
+        Ok(result.into_bound(py))
     }
 
     pub fn content_type(&self) -> Option<String> {
@@ -375,8 +864,14 @@ impl Request {
             .unwrap_or(false)
     }
 
+    /// True for `application/json` and `+json` types such as `application/problem+json`
     pub fn is_json(&self) -> bool {
-        self.is_content_type("application/json")
+        self.content_type()
+            .map(|ct| {
+                let mime = ct.split(';').next().unwrap_or("").trim().to_lowercase();
+                mime == "application/json" || mime.ends_with("+json")
+            })
+            .unwrap_or(false)
     }
 
     pub fn is_form(&self) -> bool {
@@ -400,7 +895,7 @@ impl Request {
             if let Some(boundary) = crate::http::multipart::extract_boundary(&content_type) {
                 Ok(crate::http::multipart::parse_multipart(
                     body_bytes, &boundary,
-                ))
+                )?)
             } else {
                 Err(pyo3::exceptions::PyValueError::new_err(
                     "Missing boundary in multipart content-type",
@@ -409,10 +904,8 @@ impl Request {
         } else if content_type.contains("application/x-www-form-urlencoded") {
             // Parse URL-encoded form data
             let mut form_data = crate::http::multipart::FormData::new();
-            let body_str = String::from_utf8_lossy(body_bytes);
-
-            for pair in form_urlencoded::parse(body_str.as_bytes()) {
-                form_data.add_field(pair.0.to_string(), pair.1.to_string());
+            for (name, value) in urlencoded::parse(body_bytes) {
+                form_data.add_field(name, value);
             }
 
             Ok(form_data)
@@ -423,6 +916,147 @@ impl Request {
         }
     }
 
+    /// Iterate over the parts of a multipart/form-data body as they are
+    /// parsed, without collecting them.
+    ///
+    /// Args:
+    ///     max_part_size: Largest body of a single part in bytes
+    ///     max_total_size: Largest multipart body in bytes (default: the
+    ///         route's max_body_size, if set)
+    ///     chunk_size: Size of the slices the body is scanned in, and so the
+    ///         largest chunk a part yields (default: 65536)
+    ///
+    /// Raises:
+    ///     UnsupportedMediaTypeError: If the body isn't multipart/form-data
+    #[pyo3(signature = (max_part_size=None, max_total_size=None, chunk_size=65536))]
+    pub fn multipart_stream(
+        &self,
+        max_part_size: Option<usize>,
+        max_total_size: Option<usize>,
+        chunk_size: usize,
+    ) -> PyResult<crate::http::multipart_stream::MultipartStream> {
+        use crate::http::multipart::{extract_boundary, MultipartLimits, MultipartReader};
+
+        if chunk_size == 0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "chunk_size must be greater than 0",
+            ));
+        }
+        let content_type = self.content_type().unwrap_or_default();
+        if !content_type
+            .to_ascii_lowercase()
+            .contains("multipart/form-data")
+        {
+            return Err(UnsupportedMediaTypeError::new_err(
+                "Request content-type is not multipart/form-data",
+            ));
+        }
+        let boundary = extract_boundary(&content_type).ok_or_else(|| {
+            BodyDecodeError::new_err("Missing boundary in multipart content-type")
+        })?;
+        let limits = MultipartLimits {
+            max_part_size,
+            max_total_size: max_total_size.or_else(|| {
+                self.route_config
+                    .get()
+                    .and_then(|config| config.max_body_size)
+            }),
+        };
+        let body = self.body.read().clone().unwrap_or_default();
+        Ok(crate::http::multipart_stream::MultipartStream::new(
+            MultipartReader::new(body, &boundary, limits, chunk_size),
+        ))
+    }
+
+    /// Iterate over an NDJSON body, one parsed JSON document per line.
+    ///
+    /// Args:
+    ///     force: Parse even when the Content-Type is not NDJSON
+    ///     max_bad_lines: Lines that are not valid JSON to skip before
+    ///         raising; skipped lines are listed in the reader's `bad_lines`
+    ///     max_line_bytes: Longest line accepted (default: 1 MiB)
+    ///     max_records: Most documents accepted (default: 1,000,000)
+    ///     chunk_size: Size of the slices the body is scanned in
+    ///
+    /// Raises UnsupportedMediaTypeError (415) for other content types,
+    /// PayloadTooLargeError (413) over a limit and BodyDecodeError (400)
+    /// past `max_bad_lines`; limits and bad lines are found as the
+    /// iterator advances.
+    #[pyo3(signature = (
+        force=false,
+        max_bad_lines=0,
+        max_line_bytes=1048576,
+        max_records=1000000,
+        chunk_size=65536
+    ))]
+    pub fn ndjson(
+        &self,
+        force: bool,
+        max_bad_lines: usize,
+        max_line_bytes: usize,
+        max_records: usize,
+        chunk_size: usize,
+    ) -> PyResult<crate::http::records::NdjsonReader> {
+        use crate::http::records::{NdjsonLimits, NdjsonReader, NDJSON_TYPES};
+
+        let body = self.record_body("NDJSON", &NDJSON_TYPES, force, chunk_size)?;
+        Ok(NdjsonReader::new(
+            body,
+            NdjsonLimits {
+                max_line_bytes,
+                max_records,
+                max_bad_lines,
+            },
+        ))
+    }
+
+    /// Iterate over the rows of a CSV body.
+    ///
+    /// Args:
+    ///     delimiter: Field separator, a single ASCII character
+    ///     has_header: Treat the first row as column names and return each
+    ///         row as a dict keyed by them (otherwise as a list)
+    ///     force: Parse even when the Content-Type is not CSV
+    ///     max_field_bytes: Largest field accepted (default: 1 MiB)
+    ///     max_rows: Most rows accepted, not counting the header
+    ///         (default: 1,000,000)
+    ///     chunk_size: Size of the slices the body is scanned in
+    ///
+    /// Raises UnsupportedMediaTypeError (415) for other content types,
+    /// PayloadTooLargeError (413) over a limit and BodyDecodeError (400)
+    /// for malformed rows, as the iterator reaches them.
+    #[pyo3(signature = (
+        delimiter=",",
+        has_header=true,
+        force=false,
+        max_field_bytes=1048576,
+        max_rows=1000000,
+        chunk_size=65536
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn csv(
+        &self,
+        delimiter: &str,
+        has_header: bool,
+        force: bool,
+        max_field_bytes: usize,
+        max_rows: usize,
+        chunk_size: usize,
+    ) -> PyResult<crate::http::records::CsvReader> {
+        use crate::http::records::{CsvLimits, CsvReader, CSV_TYPES};
+
+        let body = self.record_body("CSV", &CSV_TYPES, force, chunk_size)?;
+        CsvReader::new(
+            body,
+            delimiter,
+            has_header,
+            CsvLimits {
+                max_field_bytes,
+                max_rows,
+            },
+        )
+    }
+
     pub fn file(&self, name: &str) -> PyResult<Option<crate::http::multipart::UploadedFile>> {
         let form = self.form()?;
         Ok(form.file(name))
@@ -435,29 +1069,58 @@ impl Request {
 }
 
 impl Request {
-    pub async fn from_axum(req: axum::http::Request<axum::body::Body>) -> Self {
-        use axum::body::to_bytes;
-        use percent_encoding::percent_decode_str;
+    /// The body as chunks for `ndjson()` and `csv()`, transcoded to UTF-8
+    fn record_body(
+        &self,
+        format: &str,
+        types: &[&str],
+        force: bool,
+        chunk_size: usize,
+    ) -> PyResult<crate::http::records::BodyChunks> {
+        if chunk_size == 0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "chunk_size must be greater than 0",
+            ));
+        }
+        let content_type = self.content_type().unwrap_or_default();
+        if !force && !crate::http::records::is_mime(&content_type, types) {
+            return Err(UnsupportedMediaTypeError::new_err(format!(
+                "Expected a {} content type, got '{}' (pass force=True to parse anyway)",
+                format, content_type
+            )));
+        }
+        let body = self.body.read().clone().unwrap_or_default();
+        let body = match content_type_charset(&content_type) {
+            Some(charset) => match decode_to_utf8(&body, Some(&charset))? {
+                std::borrow::Cow::Borrowed(_) => body,
+                std::borrow::Cow::Owned(utf8) => Bytes::from(utf8),
+            },
+            None => body,
+        };
+        Ok(crate::http::records::BodyChunks::buffered(body, chunk_size))
+    }
 
+    /// Build a request from an axum request whose path was normalized to `path`
+    ///
+    /// With a route `body_limit`, a body growing past it is refused while it
+    /// is read, chunked ones included, instead of being buffered whole.
+    pub async fn from_axum(
+        req: axum::http::Request<axum::body::Body>,
+        path: NormalizedPath,
+        body_limit: Option<usize>,
+    ) -> Result<Self, BodyTooLarge> {
         let (parts, body) = req.into_parts();
 
-        let (path, query_string) = parts.uri.path_and_query().map_or_else(
-            || ("/".to_string(), String::new()),
-            |pq| {
-                let raw_path = pq.path();
-                // Fast path: if no percent-encoded chars, avoid allocation
-                let path = if raw_path.contains('%') {
-                    let path_bytes: Vec<u8> = percent_decode_str(raw_path).collect();
-                    String::from_utf8_lossy(&path_bytes).into_owned()
-                } else {
-                    raw_path.to_string()
-                };
-                (path, pq.query().unwrap_or("").to_string())
-            },
-        );
+        let raw_path = parts.uri.path();
+        let query_string = parts.uri.query().unwrap_or("").to_string();
 
         let method = HttpMethod::from_axum(&parts.method);
         let headers = HeaderMap::from_axum(&parts.headers);
+        let connection = parts
+            .extensions
+            .get::<axum::extract::ConnectInfo<ConnectionInfo>>()
+            .map(|info| info.0.clone());
+        let disconnect = parts.extensions.get::<Disconnect>().cloned();
 
         // Skip body reading for methods that typically don't have a body
         // This avoids an unnecessary await + allocation for GET/HEAD/DELETE/OPTIONS
@@ -472,17 +1135,52 @@ impl Request {
                     .map(|len| len > 0)
                     .unwrap_or(false);
                 if has_body {
-                    to_bytes(body, 10 * 1024 * 1024).await.ok()
+                    read_body(body, body_limit).await?
                 } else {
                     None
                 }
             }
             _ => {
                 // POST, PUT, PATCH - read body
-                to_bytes(body, 10 * 1024 * 1024).await.ok()
+                read_body(body, body_limit).await?
             }
         };
 
-        Self::new(&path, method, headers, &query_string, body_bytes)
+        let mut request = Self::new(&path.decoded, method, headers, &query_string, body_bytes);
+        if path.routing != path.decoded {
+            request.routing_path = Arc::from(path.routing);
+        }
+        if raw_path != &*request.path {
+            request.raw_path = Arc::from(raw_path);
+        }
+        if HttpMethod::from_bytes(parts.method.as_str().as_bytes()).is_none() {
+            request.extension_method = Some(Arc::from(parts.method.as_str()));
+        }
+        request.connection = connection;
+        if let Some(disconnect) = disconnect {
+            request.disconnect = disconnect;
+        }
+        Ok(request)
+    }
+}
+
+/// A request body went past its route's `max_body_size`, the limit
+pub struct BodyTooLarge(pub usize);
+
+/// Buffer a request body. Past a route limit the read stops with
+/// `BodyTooLarge`; past `MAX_BODY_SIZE` the body is dropped, as without one.
+async fn read_body(
+    body: axum::body::Body,
+    limit: Option<usize>,
+) -> Result<Option<Bytes>, BodyTooLarge> {
+    use axum::body::to_bytes;
+
+    let Some(limit) = limit.filter(|&limit| limit <= MAX_BODY_SIZE) else {
+        return Ok(to_bytes(body, MAX_BODY_SIZE).await.ok());
+    };
+    match to_bytes(body, limit).await.map_err(axum::Error::into_inner) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(err) if err.is::<http_body_util::LengthLimitError>() => Err(BodyTooLarge(limit)),
+        Err(_) => Ok(None),
     }
 }
@@ -1,16 +1,21 @@
 use axum::body::Body;
-use axum::http::header::SERVER;
 use axum::http::{HeaderMap, HeaderName, HeaderValue};
 use bytes::Bytes;
 use futures_util::StreamExt;
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
 use smallvec::SmallVec;
-use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, Notify};
 use tokio_stream::wrappers::ReceiverStream;
 
+use crate::http::conditional::{self, ConditionalEtag};
+use crate::http::error_envelope::{self, ErrorCode};
+use crate::http::response_fields::ResponseFields;
+use crate::http::stream_drain::LiveStream;
+use crate::http::{date, small_response};
+
 type SmallString = smartstring::SmartString<smartstring::LazyCompact>;
 
 /// Common content types
@@ -31,14 +36,68 @@ pub mod content_types {
     pub const PDF: &str = "application/pdf";
 }
 
+/// Default size above which buffered bodies are written as a stream
+pub const DEFAULT_STREAM_THRESHOLD: usize = 1024 * 1024;
+/// Size of the slices a large buffered body is written in
+const LARGE_BODY_CHUNK: usize = 64 * 1024;
+
+static STREAM_THRESHOLD: AtomicUsize = AtomicUsize::new(DEFAULT_STREAM_THRESHOLD);
+static STREAMED_FOR_SIZE: AtomicU64 = AtomicU64::new(0);
+static BYTES_STREAMED_FOR_SIZE: AtomicU64 = AtomicU64::new(0);
+
+/// Set the body size above which buffered responses are streamed; 0 disables
+pub fn configure_stream_threshold(threshold: usize) {
+    STREAM_THRESHOLD.store(threshold, Ordering::Relaxed);
+}
+
+pub fn stream_threshold() -> usize {
+    STREAM_THRESHOLD.load(Ordering::Relaxed)
+}
+
+/// Whether a buffered body of `len` bytes is written as a stream
+fn streams_for_size(len: usize) -> bool {
+    let threshold = stream_threshold();
+    threshold > 0 && len > threshold
+}
+
+/// Counters for buffered responses streamed because of their size.
+///
+/// Returns a dict with `threshold_bytes`, `streamed` (responses written as a
+/// stream) and `bytes_streamed` (their total body size).
+#[pyfunction]
+pub fn large_response_stats(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let stats = PyDict::new(py);
+    stats.set_item("threshold_bytes", stream_threshold())?;
+    stats.set_item("streamed", STREAMED_FOR_SIZE.load(Ordering::Relaxed))?;
+    stats.set_item(
+        "bytes_streamed",
+        BYTES_STREAMED_FOR_SIZE.load(Ordering::Relaxed),
+    )?;
+    Ok(stats)
+}
+
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(large_response_stats, m)?)?;
+    Ok(())
+}
+
 /// Body kind for supporting both buffered and streaming responses
 pub enum BodyKind {
     /// Traditional buffered body
     Buffered(Vec<u8>),
     /// Streaming body via mpsc channel (for SSE, chunked transfer, etc.)
     Streaming(mpsc::Receiver<Bytes>),
+    /// Live SSE body fed by an `SSEStream` held by the handler
+    Sse(crate::http::streaming::SSEBody),
+    /// Body produced by a Python generator (see `Response.stream`)
+    Generator(crate::http::streaming::StreamingBody),
 }
 
+/// Response extension naming the exception the handler raised and its
+/// base classes, most derived first
+#[derive(Clone, Debug)]
+pub struct RaisedException(pub Vec<String>);
+
 pub struct ResponseSlot {
     /// HTTP status code
     status: AtomicU16,
@@ -52,6 +111,14 @@ pub struct ResponseSlot {
     sent: AtomicBool,
     /// Whether this is a streaming response
     is_streaming: AtomicBool,
+    /// Signalled when a streaming body is marked ready, so the response can
+    /// go out while the handler is still running
+    stream_started: Notify,
+    /// Exception recorded by the app's exception handling
+    raised: parking_lot::Mutex<Vec<String>>,
+    /// Status to answer when If-None-Match matches the ETag (`with_etag`);
+    /// 0 when the response is unconditional
+    conditional: AtomicU16,
 }
 
 impl ResponseSlot {
@@ -59,10 +126,13 @@ impl ResponseSlot {
         Arc::new(Self {
             status: AtomicU16::new(200),
             headers: parking_lot::RwLock::new(SmallVec::new()),
-            body: parking_lot::RwLock::new(BodyKind::Buffered(Vec::with_capacity(8192))),
+            body: parking_lot::RwLock::new(BodyKind::Buffered(Vec::new())),
             ready: AtomicBool::new(false),
             sent: AtomicBool::new(false),
             is_streaming: AtomicBool::new(false),
+            stream_started: Notify::new(),
+            raised: parking_lot::Mutex::new(Vec::new()),
+            conditional: AtomicU16::new(0),
         })
     }
 
@@ -126,7 +196,7 @@ impl ResponseSlot {
     pub fn get_body_len(&self) -> usize {
         match &*self.body.read() {
             BodyKind::Buffered(buf) => buf.len(),
-            BodyKind::Streaming(_) => 0,
+            BodyKind::Streaming(_) | BodyKind::Sse(_) | BodyKind::Generator(_) => 0,
         }
     }
 
@@ -136,6 +206,18 @@ impl ResponseSlot {
         self.is_streaming.store(true, Ordering::Release);
     }
 
+    /// Set the body to a live SSE stream
+    pub fn set_sse_body(&self, body: crate::http::streaming::SSEBody) {
+        *self.body.write() = BodyKind::Sse(body);
+        self.is_streaming.store(true, Ordering::Release);
+    }
+
+    /// Set the body to a generator-backed stream
+    pub fn set_generator_body(&self, body: crate::http::streaming::StreamingBody) {
+        *self.body.write() = BodyKind::Generator(body);
+        self.is_streaming.store(true, Ordering::Release);
+    }
+
     /// Check if this is a streaming response
     pub fn is_streaming(&self) -> bool {
         self.is_streaming.load(Ordering::Acquire)
@@ -144,6 +226,14 @@ impl ResponseSlot {
     #[inline]
     pub fn mark_ready(&self) {
         self.ready.store(true, Ordering::Release);
+        if self.is_streaming() {
+            self.stream_started.notify_one();
+        }
+    }
+
+    /// Resolves once a streaming body has been marked ready
+    pub async fn stream_started(&self) {
+        self.stream_started.notified().await
     }
 
     #[inline]
@@ -151,6 +241,16 @@ impl ResponseSlot {
         self.ready.load(Ordering::Acquire)
     }
 
+    /// Nothing written yet: default status, no headers or cookies, empty
+    /// buffered body, not finished
+    pub fn is_untouched(&self) -> bool {
+        !self.is_ready()
+            && !self.is_streaming()
+            && self.get_status() == 200
+            && self.headers.read().is_empty()
+            && self.get_body_len() == 0
+    }
+
     #[inline]
     pub fn mark_sent(&self) {
         self.sent.store(true, Ordering::Release);
@@ -161,11 +261,58 @@ impl ResponseSlot {
         self.sent.load(Ordering::Acquire)
     }
 
+    /// Record the raised exception's class names, most derived first
+    pub fn set_raised(&self, names: Vec<String>) {
+        *self.raised.lock() = names;
+    }
+
+    /// Answer `status` instead when the request's If-None-Match matches the
+    /// response's ETag
+    pub fn set_conditional(&self, status: u16) {
+        self.conditional.store(status, Ordering::Release);
+    }
+
     pub fn into_response(self: Arc<Self>) -> axum::response::Response {
-        let status = self.status.load(Ordering::Acquire);
-        let _is_streaming = self.is_streaming.load(Ordering::Acquire);
+        let status = axum::http::StatusCode::from_u16(self.status.load(Ordering::Acquire))
+            .unwrap_or(axum::http::StatusCode::OK);
         let headers = self.headers.read();
 
+        // Take body from the lock
+        let body_kind = std::mem::replace(&mut *self.body.write(), BodyKind::Buffered(Vec::new()));
+
+        let mut res = match body_kind {
+            BodyKind::Buffered(body)
+                if small_response::fits(headers.len(), body.len())
+                    && !streams_for_size(body.len()) =>
+            {
+                small_response::build(
+                    status,
+                    headers.iter().map(|(k, v)| (k.as_str(), v.as_str())),
+                    body,
+                    date::header_value(),
+                )
+            }
+            body_kind => Self::assemble(status, &headers, body_kind),
+        };
+        let raised = std::mem::take(&mut *self.raised.lock());
+        if !raised.is_empty() {
+            res.extensions_mut().insert(RaisedException(raised));
+        }
+        match self.conditional.load(Ordering::Acquire) {
+            0 => {}
+            status => {
+                res.extensions_mut().insert(ConditionalEtag(status));
+            }
+        }
+        res
+    }
+
+    /// General assembly for large buffered and streaming bodies
+    fn assemble(
+        status: axum::http::StatusCode,
+        headers: &[(SmallString, SmallString)],
+        body_kind: BodyKind,
+    ) -> axum::response::Response {
         let mut header_map = HeaderMap::with_capacity(headers.len() + 2);
         for (key, value) in headers.iter() {
             if let (Ok(name), Ok(val)) = (
@@ -175,17 +322,21 @@ impl ResponseSlot {
                 header_map.append(name, val);
             }
         }
-        header_map
-            .entry(SERVER)
-            .or_insert(HeaderValue::from_static("Hypern"));
+        if !header_map.contains_key(axum::http::header::DATE) {
+            header_map.insert(axum::http::header::DATE, date::header_value());
+        }
 
-        // Take body from the lock
-        let body_kind = std::mem::replace(
-            &mut *self.body.write(),
-            BodyKind::Buffered(Vec::new()),
-        );
+        let live = match body_kind {
+            BodyKind::Buffered(_) => None,
+            BodyKind::Sse(_) => Some(LiveStream::Sse),
+            BodyKind::Streaming(_) | BodyKind::Generator(_) => Some(LiveStream::Chunked),
+        };
 
         let http_body = match body_kind {
+            BodyKind::Buffered(_) if !small_response::status_allows_body(status) => {
+                header_map.remove(axum::http::header::CONTENT_LENGTH);
+                Body::empty()
+            }
             BodyKind::Buffered(body_data) => {
                 let body_len = body_data.len();
                 // Set Content-Length explicitly for buffered responses
@@ -193,7 +344,20 @@ impl ResponseSlot {
                     axum::http::header::CONTENT_LENGTH,
                     HeaderValue::from(body_len),
                 );
-                Body::from(body_data)
+                // Large bodies go out in slices of the same buffer instead of
+                // one frame, so the connection never holds a second copy.
+                // Middleware has already run: Python middleware wraps the
+                // handler and Rust "after" middleware only adds headers.
+                if streams_for_size(body_len) {
+                    STREAMED_FOR_SIZE.fetch_add(1, Ordering::Relaxed);
+                    BYTES_STREAMED_FOR_SIZE.fetch_add(body_len as u64, Ordering::Relaxed);
+                    Body::from_stream(crate::http::streaming::StreamingBody::from_buffer(
+                        Bytes::from(body_data),
+                        LARGE_BODY_CHUNK,
+                    ))
+                } else {
+                    Body::from(body_data)
+                }
             }
             BodyKind::Streaming(receiver) => {
                 // For streaming responses, use chunked transfer encoding
@@ -205,12 +369,30 @@ impl ResponseSlot {
                 let stream = ReceiverStream::new(receiver);
                 Body::from_stream(stream.map(|b| Ok::<_, std::io::Error>(b)))
             }
+            BodyKind::Sse(sse_body) => {
+                header_map.insert(
+                    axum::http::header::TRANSFER_ENCODING,
+                    HeaderValue::from_static("chunked"),
+                );
+                header_map.remove(axum::http::header::CONTENT_LENGTH);
+                Body::from_stream(sse_body)
+            }
+            BodyKind::Generator(body) => {
+                header_map.insert(
+                    axum::http::header::TRANSFER_ENCODING,
+                    HeaderValue::from_static("chunked"),
+                );
+                header_map.remove(axum::http::header::CONTENT_LENGTH);
+                Body::from_stream(body)
+            }
         };
 
         let mut res = axum::response::Response::new(http_body);
-        *res.status_mut() =
-            axum::http::StatusCode::from_u16(status).unwrap_or(axum::http::StatusCode::OK);
+        *res.status_mut() = status;
         *res.headers_mut() = header_map;
+        if let Some(live) = live {
+            res.extensions_mut().insert(live);
+        }
         res
     }
 }
@@ -220,10 +402,13 @@ impl Default for ResponseSlot {
         Self {
             status: AtomicU16::new(200),
             headers: parking_lot::RwLock::new(SmallVec::new()),
-            body: parking_lot::RwLock::new(BodyKind::Buffered(Vec::with_capacity(4096))),
+            body: parking_lot::RwLock::new(BodyKind::Buffered(Vec::new())),
             ready: AtomicBool::new(false),
             sent: AtomicBool::new(false),
             is_streaming: AtomicBool::new(false),
+            stream_started: Notify::new(),
+            raised: parking_lot::Mutex::new(Vec::new()),
+            conditional: AtomicU16::new(0),
         }
     }
 }
@@ -233,6 +418,8 @@ impl Default for ResponseSlot {
 #[pyclass]
 pub struct Response {
     slot: Arc<ResponseSlot>,
+    /// The route's `response_fields`, applied to JSON bodies
+    fields: Option<Arc<ResponseFields>>,
 }
 
 #[pymethods]
@@ -355,6 +542,28 @@ impl Response {
         pyself
     }
 
+    /// Set the ETag and answer `status_if_match` (304 by default) with an
+    /// empty body when the request's If-None-Match matches it, weakly
+    #[pyo3(signature = (etag, status_if_match=304))]
+    pub fn with_etag<'py>(
+        pyself: PyRef<'py, Self>,
+        etag: &str,
+        status_if_match: u16,
+    ) -> PyResult<PyRef<'py, Self>> {
+        if !(200..600).contains(&status_if_match) {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "status_if_match must be an HTTP status, got {}",
+                status_if_match
+            )));
+        }
+        pyself.slot.remove_header("ETag");
+        pyself
+            .slot
+            .add_header("ETag".to_string(), conditional::quote(etag));
+        pyself.slot.set_conditional(status_if_match);
+        Ok(pyself)
+    }
+
     /// Set Last-Modified header
     pub fn last_modified<'py>(pyself: PyRef<'py, Self>, date: &str) -> PyRef<'py, Self> {
         pyself
@@ -443,7 +652,7 @@ impl Response {
             pyself.slot.set_body(Vec::new());
         } else {
             // Try to serialize as JSON
-            let json_bytes = crate::utils::serialize_py_to_json(body)?;
+            let json_bytes = pyself.serialize_json(body)?;
             pyself
                 .slot
                 .add_header("Content-Type".to_string(), content_types::JSON.to_string());
@@ -458,7 +667,7 @@ impl Response {
         pyself: PyRef<'py, Self>,
         data: &Bound<'_, PyAny>,
     ) -> PyResult<PyRef<'py, Self>> {
-        let json_bytes = crate::utils::serialize_py_to_json(data)?;
+        let json_bytes = pyself.serialize_json(data)?;
         pyself
             .slot
             .add_header("Content-Type".to_string(), content_types::JSON.to_string());
@@ -533,7 +742,7 @@ impl Response {
     /// providing memory-efficient streaming without buffering all events.
     ///
     /// Note: This collects all events into memory. For true streaming,
-    /// use `sse_stream_live()` instead.
+    /// use `sse_stream()` instead.
     #[pyo3(name = "sse_collect")]
     pub fn sse_collect<'py>(
         pyself: PyRef<'py, Self>,
@@ -566,9 +775,10 @@ impl Response {
         Ok(pyself)
     }
 
-    /// True streaming SSE: creates an mpsc channel, spawns a Tokio task that
-    /// iterates the Python generator and sends each formatted event through
-    /// the channel. The response body streams events as they are produced.
+    /// True streaming SSE: the generator is iterated on the blocking pool and
+    /// each event is written as soon as it is yielded, with backpressure.
+    /// Headers go out before the first event, and the generator is closed if
+    /// the client disconnects. Async generators are accepted too.
     ///
     /// Usage:
     /// ```python
@@ -578,57 +788,13 @@ impl Response {
     ///
     /// res.sse_stream(event_generator())
     /// ```
+    #[pyo3(signature = (generator, buffer_size=16))]
     pub fn sse_stream<'py>(
         pyself: PyRef<'py, Self>,
         generator: &Bound<'_, pyo3::PyAny>,
+        buffer_size: usize,
     ) -> PyResult<PyRef<'py, Self>> {
-        let (tx, rx) = mpsc::channel::<Bytes>(128);
-
-        // Collect generator items eagerly while we hold the GIL
-        let mut event_bytes_list: Vec<Bytes> = Vec::new();
-        let py_iter = generator.try_iter()?;
-        for item in py_iter {
-            let item = item?;
-            let formatted = if let Ok(event) = item.extract::<crate::http::streaming::SSEEvent>() {
-                Bytes::from(event.format().into_bytes())
-            } else if let Ok(s) = item.extract::<String>() {
-                let event = crate::http::streaming::SSEEvent::data(s);
-                Bytes::from(event.format().into_bytes())
-            } else if let Ok(dict) = item.cast::<pyo3::types::PyDict>() {
-                let data = dict.get_item("data")?
-                    .map(|v| v.extract::<String>())
-                    .transpose()?
-                    .unwrap_or_default();
-                let event_type = dict.get_item("event")?
-                    .map(|v| v.extract::<String>())
-                    .transpose()?;
-                let id = dict.get_item("id")?
-                    .map(|v| v.extract::<String>())
-                    .transpose()?;
-                let retry = dict.get_item("retry")?
-                    .map(|v| v.extract::<u64>())
-                    .transpose()?;
-                let event = crate::http::streaming::SSEEvent {
-                    id, event: event_type, data, retry,
-                };
-                Bytes::from(event.format().into_bytes())
-            } else {
-                let data_str = item.str()?.to_string();
-                let event = crate::http::streaming::SSEEvent::data(data_str);
-                Bytes::from(event.format().into_bytes())
-            };
-            event_bytes_list.push(formatted);
-        }
-
-        // Spawn a task to send events through the channel
-        tokio::spawn(async move {
-            for event_bytes in event_bytes_list {
-                if tx.send(event_bytes).await.is_err() {
-                    break;
-                }
-            }
-        });
-
+        let body = crate::http::streaming::sse_from_generator(generator, buffer_size)?;
         pyself
             .slot
             .add_header("Content-Type".to_string(), "text/event-stream".to_string());
@@ -641,11 +807,158 @@ impl Response {
         pyself
             .slot
             .add_header("X-Accel-Buffering".to_string(), "no".to_string());
-        pyself.slot.set_streaming_body(rx);
+        pyself.slot.set_generator_body(body);
         pyself.slot.mark_ready();
         Ok(pyself)
     }
 
+    /// Stream the chunks of a generator as the response body.
+    ///
+    /// Accepts a generator, async generator or any iterable yielding `bytes`
+    /// or `str`. Chunks are written as they are produced, with backpressure;
+    /// if the generator raises, the error is logged and the body is cut off
+    /// (the status line has already been sent).
+    ///
+    /// Args:
+    ///     source: The generator or iterable to stream
+    ///     content_type: Content-Type header (default: application/octet-stream)
+    ///     buffer_size: Chunks buffered ahead of the client (default: 16)
+    ///
+    /// Usage:
+    /// ```python
+    /// async def rows():
+    ///     for row in fetch_rows():
+    ///         yield json.dumps(row) + "\n"
+    ///
+    /// res.stream(rows(), content_type="application/x-ndjson")
+    /// ```
+    #[pyo3(signature = (source, content_type=None, buffer_size=16))]
+    pub fn stream<'py>(
+        pyself: PyRef<'py, Self>,
+        source: &Bound<'_, PyAny>,
+        content_type: Option<&str>,
+        buffer_size: usize,
+    ) -> PyResult<PyRef<'py, Self>> {
+        let body = crate::http::streaming::stream_from_generator(source, buffer_size)?;
+        if content_type.is_some() || pyself.slot.get_header("Content-Type").is_none() {
+            pyself.slot.remove_header("Content-Type");
+            pyself.slot.add_header(
+                "Content-Type".to_string(),
+                content_type.unwrap_or(content_types::OCTET_STREAM).to_string(),
+            );
+        }
+        pyself.slot.set_generator_body(body);
+        pyself.slot.mark_ready();
+        Ok(pyself)
+    }
+
+    /// Live SSE: start a streaming response and return the `SSEStream` that
+    /// feeds it. Events sent from any thread are written as they arrive; the
+    /// response ends when the stream is closed or dropped.
+    ///
+    /// Idle streams get a `: keepalive` comment from the server's shared
+    /// keepalive task (see `Server.set_sse_keepalive`).
+    ///
+    /// The status and headers are sent as soon as this is called, even if
+    /// the handler keeps running to produce events; headers set afterwards
+    /// are not sent.
+    ///
+    /// Args:
+    ///     buffer_size: Events buffered before `send` starts returning False
+    ///     keepalive_secs: Override the server default; 0 disables keepalives
+    ///     connected: Send a `: connected` comment at once, for proxies that
+    ///         hold the headers back until body bytes arrive
+    ///
+    /// Usage:
+    /// ```python
+    /// stream = res.sse_live(keepalive_secs=15)
+    /// stream.send_event("ready", "{}")
+    /// ```
+    #[pyo3(signature = (buffer_size=100, keepalive_secs=None, connected=false))]
+    pub fn sse_live(
+        &self,
+        buffer_size: usize,
+        keepalive_secs: Option<f64>,
+        connected: bool,
+    ) -> crate::http::streaming::SSEStream {
+        let keepalive = match keepalive_secs {
+            Some(secs) => crate::http::sse_keepalive::interval_from_secs(Some(secs)),
+            None => crate::http::sse_keepalive::default_interval(),
+        };
+        let (stream, body) =
+            crate::http::streaming::SSEBody::with_keepalive(buffer_size.max(1), keepalive);
+
+        for (name, value) in crate::http::streaming::sse_headers() {
+            self.slot.add_header(name, value);
+        }
+        if connected {
+            stream.send_comment("connected");
+        }
+        self.slot.set_sse_body(body);
+        self.slot.mark_ready();
+        stream
+    }
+
+    /// Live stream: start a chunked response and return the
+    /// `StreamingResponse` that feeds it. Chunks written from any thread are
+    /// sent as the client reads them; the response ends when the stream is
+    /// closed or dropped.
+    ///
+    /// The stream keeps count of what the client has not taken yet
+    /// (`buffered_bytes()`); past `high_watermark` bytes it pauses until no
+    /// more than `low_watermark` are left, which `await stream.drain()` and
+    /// `await stream.write_async(...)` wait for.
+    ///
+    /// The status and headers are sent as soon as this is called; headers
+    /// set afterwards are not sent.
+    ///
+    /// Args:
+    ///     content_type: Content-Type header (default: the one already set,
+    ///         else application/octet-stream)
+    ///     buffer_size: Chunks queued before `write` starts returning False
+    ///     high_watermark: Buffered bytes above which the stream pauses
+    ///         (default: 65536)
+    ///     low_watermark: Buffered bytes at which a paused stream resumes
+    ///         (default: a quarter of `high_watermark`)
+    ///
+    /// Usage:
+    /// ```python
+    /// stream = res.stream_live("text/csv")
+    /// for row in rows:
+    ///     await stream.write_async(row.encode())
+    /// stream.close()
+    /// ```
+    #[pyo3(signature = (
+        content_type=None,
+        buffer_size=100,
+        high_watermark=crate::http::streaming::DEFAULT_HIGH_WATERMARK,
+        low_watermark=None,
+    ))]
+    pub fn stream_live(
+        &self,
+        content_type: Option<&str>,
+        buffer_size: usize,
+        high_watermark: usize,
+        low_watermark: Option<usize>,
+    ) -> PyResult<crate::http::streaming::StreamingResponse> {
+        let watermarks = crate::http::streaming::watermarks(high_watermark, low_watermark)?;
+        let (stream, body) = crate::http::streaming::StreamingBody::new(
+            buffer_size,
+            content_type.unwrap_or(content_types::OCTET_STREAM),
+            watermarks,
+        );
+        if content_type.is_some() || self.slot.get_header("Content-Type").is_none() {
+            self.slot.remove_header("Content-Type");
+            self.slot.add_header(
+                "Content-Type".to_string(),
+                stream.content_type().to_string(),
+            );
+        }
+        self.slot.set_generator_body(body);
+        self.slot.mark_ready();
+        Ok(stream)
+    }
+
     /// Send a single SSE event as a response
     pub fn sse_event<'py>(
         pyself: PyRef<'py, Self>,
@@ -996,11 +1309,47 @@ impl Response {
     pub fn finished(&self) -> bool {
         self.slot.is_ready()
     }
+
+    /// Record the exception the handler raised, so a route's RetryPolicy
+    /// can match on it; called by the app's exception handling
+    fn _record_exception(&self, exc: &Bound<'_, PyAny>) -> PyResult<()> {
+        let names = exc
+            .get_type()
+            .mro()
+            .iter()
+            .map(|cls| cls.getattr("__name__")?.extract::<String>())
+            .collect::<PyResult<Vec<_>>>()?;
+        self.slot.set_raised(names);
+        Ok(())
+    }
+
+    /// Write the handler's return value as the response (see
+    /// `http::infer`); called by the app's handler wrapper
+    fn _apply_return(
+        slf: &Bound<'_, Self>,
+        request: &Bound<'_, crate::http::request::Request>,
+        value: &Bound<'_, PyAny>,
+    ) -> PyResult<()> {
+        crate::http::infer::apply(request.get(), slf, value)
+    }
 }
 
 impl Response {
     pub fn new(slot: Arc<ResponseSlot>) -> Self {
-        Self { slot }
+        Self { slot, fields: None }
+    }
+
+    /// Filter JSON bodies by the route's `response_fields`
+    pub fn with_fields(mut self, fields: Option<Arc<ResponseFields>>) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    pub(crate) fn serialize_json(&self, data: &Bound<'_, PyAny>) -> PyResult<Vec<u8>> {
+        match self.fields {
+            Some(ref fields) => crate::utils::serialize_json_value(&fields.to_json_value(data)?),
+            None => crate::utils::json_tree::serialize(data),
+        }
     }
 
     pub fn slot(&self) -> Arc<ResponseSlot> {
@@ -1064,14 +1413,6 @@ impl PyEmptyAwaitable {
     }
 }
 
-pub fn response_404() -> axum::response::Response<Body> {
-    axum::response::Response::builder()
-        .status(404)
-        .header("content-type", "text/plain")
-        .body(Body::from("Not Found"))
-        .unwrap()
-}
-
 pub fn response_500() -> axum::response::Response {
     axum::response::Response::builder()
         .status(500)
@@ -1080,10 +1421,25 @@ pub fn response_500() -> axum::response::Response {
         .unwrap()
 }
 
-pub fn response_405() -> axum::response::Response {
-    axum::response::Response::builder()
-        .status(405)
-        .header("content-type", "text/plain")
-        .body(Body::from("Method Not Allowed"))
-        .unwrap()
+/// Response for a request no route matched: 405 listing the methods the
+/// path does answer in `Allow`, 404 when it has no routes
+pub fn response_unmatched(allowed: &[&str], accept: Option<&str>) -> axum::response::Response {
+    if allowed.is_empty() {
+        return error_envelope::response(
+            axum::http::StatusCode::NOT_FOUND,
+            ErrorCode::NotFound,
+            "Not Found",
+            accept,
+        );
+    }
+    let mut response = error_envelope::response(
+        axum::http::StatusCode::METHOD_NOT_ALLOWED,
+        ErrorCode::MethodNotAllowed,
+        "Method Not Allowed",
+        accept,
+    );
+    if let Ok(allow) = HeaderValue::from_str(&allowed.join(", ")) {
+        response.headers_mut().insert(axum::http::header::ALLOW, allow);
+    }
+    response
 }
@@ -0,0 +1,244 @@
+//! Per-route response field filtering.
+//!
+//! `Route(..., response_fields={"include": [...], "exclude": [...]})`
+//! whitelists or drops keys of what `res.json()`/`res.send()` serialize.
+//! Fields are top-level keys or one level of nesting (`user.email`); a
+//! response that is a list is filtered item by item, and so is a nested
+//! value that is a list of dicts. Keys are skipped while the Python object
+//! is converted, so nothing is copied to be filtered afterwards. Responses
+//! that are neither dicts nor lists pass through untouched.
+//!
+//! Include fields missing from a response are ignored unless the spec sets
+//! `"strict": True`, in which case serializing raises `ValueError`.
+
+use ahash::{AHashMap, AHashSet};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyString};
+use serde_json::{Map, Value as JsonValue};
+
+use crate::utils::py_to_json_value;
+
+/// Keys of one level: whole fields and fields of nested objects
+#[derive(Debug, Clone, Default)]
+struct FieldSet {
+    fields: AHashSet<String>,
+    nested: AHashMap<String, AHashSet<String>>,
+}
+
+impl FieldSet {
+    fn parse(paths: &[String], option: &str) -> Result<Self, String> {
+        let mut set = Self::default();
+        for path in paths {
+            let parts: Vec<&str> = path.split('.').collect();
+            match parts.as_slice() {
+                [field] if !field.is_empty() => {
+                    set.fields.insert(field.to_string());
+                }
+                [parent, field] if !parent.is_empty() && !field.is_empty() => {
+                    set.nested
+                        .entry(parent.to_string())
+                        .or_default()
+                        .insert(field.to_string());
+                }
+                _ => {
+                    return Err(format!(
+                        "Invalid response_fields {} entry '{}': expected 'field' or 'parent.field'",
+                        option, path
+                    ))
+                }
+            }
+        }
+        Ok(set)
+    }
+}
+
+/// Filter applied to the children of a nested field
+struct Nested<'a> {
+    include: Option<&'a AHashSet<String>>,
+    exclude: Option<&'a AHashSet<String>>,
+}
+
+/// A route's `response_fields` spec
+#[derive(Debug, Clone)]
+pub struct ResponseFields {
+    include: Option<FieldSet>,
+    exclude: FieldSet,
+    strict: bool,
+    /// The spec as given, for `Route.response_fields`
+    include_paths: Option<Vec<String>>,
+    exclude_paths: Vec<String>,
+}
+
+impl ResponseFields {
+    pub fn new(
+        include: Option<Vec<String>>,
+        exclude: Vec<String>,
+        strict: bool,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            include: include
+                .as_deref()
+                .map(|paths| FieldSet::parse(paths, "include"))
+                .transpose()?,
+            exclude: FieldSet::parse(&exclude, "exclude")?,
+            strict,
+            include_paths: include,
+            exclude_paths: exclude,
+        })
+    }
+
+    /// Parse `{"include": [...], "exclude": [...], "strict": bool}`
+    pub fn from_spec(spec: &Bound<'_, PyDict>) -> PyResult<Self> {
+        let mut include = None;
+        let mut exclude = Vec::new();
+        let mut strict = false;
+        for (key, value) in spec.iter() {
+            match key.extract::<String>()?.as_str() {
+                "include" => include = Some(value.extract::<Vec<String>>()?),
+                "exclude" => exclude = value.extract::<Vec<String>>()?,
+                "strict" => strict = value.extract::<bool>()?,
+                other => {
+                    return Err(PyValueError::new_err(format!(
+                        "Unknown response_fields key '{}'; expected include, exclude or strict",
+                        other
+                    )))
+                }
+            }
+        }
+        Self::new(include, exclude, strict).map_err(PyValueError::new_err)
+    }
+
+    /// The spec as a dict
+    pub fn to_spec<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let spec = PyDict::new(py);
+        spec.set_item("include", self.include_paths.clone())?;
+        spec.set_item("exclude", self.exclude_paths.clone())?;
+        spec.set_item("strict", self.strict)?;
+        Ok(spec)
+    }
+
+    /// Convert a response body to JSON, leaving out filtered fields
+    pub fn to_json_value(&self, obj: &Bound<'_, PyAny>) -> PyResult<JsonValue> {
+        if let Ok(dict) = obj.cast::<PyDict>() {
+            return self.object(dict);
+        }
+        if let Ok(list) = obj.cast::<PyList>() {
+            let mut items = Vec::with_capacity(list.len());
+            for item in list.iter() {
+                items.push(match item.cast::<PyDict>() {
+                    Ok(dict) => self.object(dict)?,
+                    Err(_) => py_to_json_value(&item)?,
+                });
+            }
+            return Ok(JsonValue::Array(items));
+        }
+        py_to_json_value(obj)
+    }
+
+    fn object(&self, dict: &Bound<'_, PyDict>) -> PyResult<JsonValue> {
+        if self.strict {
+            if let Some(include) = &self.include {
+                for field in include.fields.iter().chain(include.nested.keys()) {
+                    if !dict.contains(field)? {
+                        return Err(missing_field(field));
+                    }
+                }
+            }
+        }
+        let mut map = Map::with_capacity(dict.len());
+        for (key, value) in dict.iter() {
+            let key = key_string(&key)?;
+            if self.exclude.fields.contains(&key) {
+                continue;
+            }
+            let nested = match &self.include {
+                // Listed whole: only exclusions apply below it
+                Some(include) if include.fields.contains(&key) => None,
+                Some(include) => match include.nested.get(&key) {
+                    Some(fields) => Some(fields),
+                    None => continue,
+                },
+                None => None,
+            };
+            let nested = Nested {
+                include: nested,
+                exclude: self.exclude.nested.get(&key),
+            };
+            let value = if nested.include.is_none() && nested.exclude.is_none() {
+                py_to_json_value(&value)?
+            } else {
+                self.nested_value(&key, &value, &nested)?
+            };
+            map.insert(key, value);
+        }
+        Ok(JsonValue::Object(map))
+    }
+
+    fn nested_value(
+        &self,
+        parent: &str,
+        value: &Bound<'_, PyAny>,
+        nested: &Nested<'_>,
+    ) -> PyResult<JsonValue> {
+        if let Ok(dict) = value.cast::<PyDict>() {
+            return self.nested_object(parent, dict, nested);
+        }
+        if let Ok(list) = value.cast::<PyList>() {
+            let mut items = Vec::with_capacity(list.len());
+            for item in list.iter() {
+                items.push(match item.cast::<PyDict>() {
+                    Ok(dict) => self.nested_object(parent, dict, nested)?,
+                    Err(_) => py_to_json_value(&item)?,
+                });
+            }
+            return Ok(JsonValue::Array(items));
+        }
+        py_to_json_value(value)
+    }
+
+    fn nested_object(
+        &self,
+        parent: &str,
+        dict: &Bound<'_, PyDict>,
+        nested: &Nested<'_>,
+    ) -> PyResult<JsonValue> {
+        if self.strict {
+            if let Some(include) = nested.include {
+                for field in include {
+                    if !dict.contains(field)? {
+                        return Err(missing_field(&format!("{}.{}", parent, field)));
+                    }
+                }
+            }
+        }
+        let mut map = Map::with_capacity(dict.len());
+        for (key, value) in dict.iter() {
+            let key = key_string(&key)?;
+            if nested.exclude.is_some_and(|exclude| exclude.contains(&key))
+                || nested
+                    .include
+                    .is_some_and(|include| !include.contains(&key))
+            {
+                continue;
+            }
+            map.insert(key, py_to_json_value(&value)?);
+        }
+        Ok(JsonValue::Object(map))
+    }
+}
+
+fn key_string(key: &Bound<'_, PyAny>) -> PyResult<String> {
+    if key.is_instance_of::<PyString>() {
+        key.extract::<String>()
+    } else {
+        Ok(key.str()?.to_string())
+    }
+}
+
+fn missing_field(field: &str) -> PyErr {
+    PyValueError::new_err(format!(
+        "response_fields includes '{}', which the response does not have",
+        field
+    ))
+}
@@ -0,0 +1,105 @@
+//! The worker's accept loop.
+//!
+//! Works like `axum::serve` with graceful shutdown, except that each
+//! connection also closes gracefully when the worker starts draining, not
+//! only when it stops: an idle HTTP/1 connection is closed at once, one with
+//! a request in flight is closed after its response, and an HTTP/2
+//! connection gets GOAWAY with the last stream it processed. Otherwise
+//! keep-alive clients would go on sending requests until the drain timeout.
+//!
+//! Connections accepted while draining are left open; their requests are
+//! refused with 503 and `Connection: close` instead of being cut off unread.
+
+use axum::body::Body;
+use axum::extract::ConnectInfo;
+use axum::serve::Listener;
+use axum::Router;
+use hyper::body::Incoming;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto::Builder;
+use hyper_util::service::TowerToHyperService;
+use std::future::Future;
+use std::pin::pin;
+use tokio::sync::watch;
+use tower::ServiceExt;
+
+use crate::core::reload::ReloadManager;
+use crate::http::connection::{ConnectionInfo, HypernListener, HypernStream};
+
+/// Serve `app` on connections from `listener` until `shutdown` resolves,
+/// then wait for open connections to finish their requests
+pub async fn serve(
+    mut listener: HypernListener,
+    app: Router,
+    rm: ReloadManager,
+    shutdown: impl Future<Output = ()>,
+) {
+    // Connections hold a receiver each; `closed` resolves when all are gone
+    let (open_tx, open_rx) = watch::channel(());
+    let (stop_tx, stop_rx) = watch::channel(false);
+    let mut shutdown = pin!(shutdown);
+    loop {
+        let (io, info) = tokio::select! {
+            conn = listener.accept() => conn,
+            _ = &mut shutdown => break,
+        };
+        tokio::spawn(serve_connection(
+            io,
+            info,
+            app.clone(),
+            rm.clone(),
+            stop_rx.clone(),
+            open_rx.clone(),
+        ));
+    }
+    drop(listener);
+    drop(open_rx);
+    stop_tx.send_replace(true);
+    open_tx.closed().await;
+}
+
+async fn serve_connection(
+    io: HypernStream,
+    info: ConnectionInfo,
+    app: Router,
+    rm: ReloadManager,
+    mut stop: watch::Receiver<bool>,
+    _open: watch::Receiver<()>,
+) {
+    let accepted_while_draining = rm.is_draining();
+    let service = app.map_request(move |mut req: axum::http::Request<Incoming>| {
+        req.extensions_mut().insert(ConnectInfo(info.clone()));
+        req.map(Body::new)
+    });
+    let mut builder = Builder::new(TokioExecutor::new());
+    // CONNECT protocol needed for HTTP/2 websockets
+    builder.http2().enable_connect_protocol();
+    let service = TowerToHyperService::new(service);
+    let mut conn = pin!(builder.serve_connection_with_upgrades(TokioIo::new(io), service));
+    let mut drain = pin!(async {
+        if accepted_while_draining {
+            std::future::pending::<()>().await;
+        }
+        rm.drain_started().await;
+    });
+    let mut closing = false;
+    loop {
+        tokio::select! {
+            result = conn.as_mut() => {
+                if let Err(err) = result {
+                    crate::hlog_debug!("Connection closed with error: {}", err);
+                }
+                return;
+            }
+            _ = &mut drain, if !closing => {
+                crate::http::stream_drain::record_connection_closed();
+                conn.as_mut().graceful_shutdown();
+                closing = true;
+            }
+            _ = stop.wait_for(|stop| *stop), if !closing => {
+                conn.as_mut().graceful_shutdown();
+                closing = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! One-pass assembly of small buffered responses.
+//!
+//! Most handler responses are a short JSON or text body with a handful of
+//! headers. Those skip the general assembly in `ResponseSlot::into_response`
+//! (stream threshold, live-stream tagging, transfer encodings): the header
+//! map is sized once, the body buffer becomes the response body without a
+//! copy and the cached `Date` value is used.
+
+use axum::body::Body;
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode};
+
+/// Largest body assembled on this path
+pub const MAX_BODY: usize = 4096;
+/// Most headers a response may set to be assembled on this path
+pub const MAX_HEADERS: usize = 8;
+
+/// Whether a response with `headers` and a `body_len` body takes this path
+#[inline]
+pub fn fits(header_count: usize, body_len: usize) -> bool {
+    header_count <= MAX_HEADERS && body_len <= MAX_BODY
+}
+
+/// Informational, 204 and 304 responses carry neither a body nor a
+/// Content-Length (RFC 9110 §6.4.1, §8.6)
+#[inline]
+pub fn status_allows_body(status: StatusCode) -> bool {
+    !(status.is_informational()
+        || status == StatusCode::NO_CONTENT
+        || status == StatusCode::NOT_MODIFIED)
+}
+
+/// Build the response. Headers keep their order and repeats; ones with an
+/// invalid name or value are dropped. `Date` is set unless the handler set
+/// one, and `Content-Length` from the body; a status without a body drops
+/// both the body and any Content-Length.
+pub fn build<'a>(
+    status: StatusCode,
+    headers: impl ExactSizeIterator<Item = (&'a str, &'a str)>,
+    body: Vec<u8>,
+    date: HeaderValue,
+) -> axum::response::Response {
+    let mut map = HeaderMap::with_capacity(headers.len() + 2);
+    for (name, value) in headers {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            map.append(name, value);
+        }
+    }
+    if !map.contains_key(header::DATE) {
+        map.insert(header::DATE, date);
+    }
+
+    let body = if status_allows_body(status) {
+        map.insert(header::CONTENT_LENGTH, HeaderValue::from(body.len()));
+        Body::from(body)
+    } else {
+        map.remove(header::CONTENT_LENGTH);
+        Body::empty()
+    };
+
+    let mut res = axum::response::Response::new(body);
+    *res.status_mut() = status;
+    *res.headers_mut() = map;
+    res
+}
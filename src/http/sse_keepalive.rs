@@ -0,0 +1,248 @@
+//! Server-owned SSE keepalive.
+//!
+//! Live SSE streams register a weak handle here. A single shared tokio task
+//! wakes on a short tick, sends `: keepalive` to every stream that has been
+//! idle for its interval, and drops entries whose stream was closed or whose
+//! client went away. No per-stream timers or Python threads are involved.
+
+use bytes::Bytes;
+use dashmap::DashMap;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::WeakSender;
+
+use crate::http::streaming::SSEEvent;
+
+/// Longest sleep between sweeps; the tick is a quarter of the shortest interval
+const MAX_TICK_MS: u64 = 1000;
+/// Shortest sleep between sweeps
+const MIN_TICK_MS: u64 = 10;
+
+/// Server-wide default keepalive interval in ms (0 = disabled)
+static DEFAULT_INTERVAL_MS: AtomicU64 = AtomicU64::new(0);
+static REGISTRY: OnceLock<KeepaliveRegistry> = OnceLock::new();
+static EPOCH: OnceLock<Instant> = OnceLock::new();
+
+/// Milliseconds since the first call; used for stream activity stamps
+pub fn now_ms() -> u64 {
+    EPOCH.get_or_init(Instant::now).elapsed().as_millis() as u64
+}
+
+/// Set the server-wide default keepalive interval (`None` or 0 disables it)
+pub fn set_default_interval(interval: Option<Duration>) {
+    let ms = interval.map_or(0, |d| d.as_millis() as u64);
+    DEFAULT_INTERVAL_MS.store(ms, Ordering::Relaxed);
+}
+
+/// Server-wide default keepalive interval
+pub fn default_interval() -> Option<Duration> {
+    match DEFAULT_INTERVAL_MS.load(Ordering::Relaxed) {
+        0 => None,
+        ms => Some(Duration::from_millis(ms)),
+    }
+}
+
+/// Convert a Python-facing seconds value to an interval (`None`/<=0 disables)
+pub fn interval_from_secs(secs: Option<f64>) -> Option<Duration> {
+    secs.filter(|s| *s > 0.0).map(Duration::from_secs_f64)
+}
+
+struct KeepaliveEntry {
+    sender: WeakSender<Bytes>,
+    closed: Arc<AtomicBool>,
+    last_activity: Arc<AtomicU64>,
+    interval_ms: u64,
+}
+
+/// Registry of live SSE streams served by the shared keepalive task
+pub struct KeepaliveRegistry {
+    streams: DashMap<u64, KeepaliveEntry>,
+    next_id: AtomicU64,
+    running: AtomicBool,
+    tick_ms: AtomicU64,
+    /// SSE response bodies currently attached to a connection
+    active: AtomicI64,
+    keepalives_sent: AtomicU64,
+}
+
+impl KeepaliveRegistry {
+    fn new() -> Self {
+        Self {
+            streams: DashMap::new(),
+            next_id: AtomicU64::new(1),
+            running: AtomicBool::new(false),
+            tick_ms: AtomicU64::new(MAX_TICK_MS),
+            active: AtomicI64::new(0),
+            keepalives_sent: AtomicU64::new(0),
+        }
+    }
+
+    pub fn global() -> &'static KeepaliveRegistry {
+        REGISTRY.get_or_init(KeepaliveRegistry::new)
+    }
+
+    /// Register a stream for keepalives every `interval` of inactivity
+    pub fn register(
+        &'static self,
+        sender: WeakSender<Bytes>,
+        closed: Arc<AtomicBool>,
+        last_activity: Arc<AtomicU64>,
+        interval: Duration,
+    ) {
+        let interval_ms = (interval.as_millis() as u64).max(1);
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.streams.insert(
+            id,
+            KeepaliveEntry {
+                sender,
+                closed,
+                last_activity,
+                interval_ms,
+            },
+        );
+        self.tick_ms.fetch_min(
+            (interval_ms / 4).clamp(MIN_TICK_MS, MAX_TICK_MS),
+            Ordering::Relaxed,
+        );
+        if !self.running.swap(true, Ordering::AcqRel) {
+            spawn_task(self.run());
+        }
+    }
+
+    /// Streams currently tracked for keepalives
+    pub fn registered(&self) -> usize {
+        self.streams.len()
+    }
+
+    /// SSE response bodies currently attached to a connection
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::Relaxed).max(0) as usize
+    }
+
+    pub fn keepalives_sent(&self) -> u64 {
+        self.keepalives_sent.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn connection_opened(&self) {
+        self.active.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn connection_closed(&self) {
+        self.active.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    fn recompute_tick(&self) {
+        let tick = self
+            .streams
+            .iter()
+            .map(|e| (e.interval_ms / 4).clamp(MIN_TICK_MS, MAX_TICK_MS))
+            .min()
+            .unwrap_or(MAX_TICK_MS);
+        self.tick_ms.fetch_min(tick, Ordering::Relaxed);
+    }
+
+    /// One pass over all streams: send due keepalives, drop dead entries
+    fn sweep(&self, keepalive: &Bytes) {
+        let now = now_ms();
+        self.streams.retain(|_, entry| {
+            if entry.closed.load(Ordering::Acquire) {
+                return false;
+            }
+            let Some(sender) = entry.sender.upgrade() else {
+                return false;
+            };
+            let idle = now.saturating_sub(entry.last_activity.load(Ordering::Relaxed));
+            if idle < entry.interval_ms {
+                return !sender.is_closed();
+            }
+            match sender.try_send(keepalive.clone()) {
+                Ok(()) => {
+                    entry.last_activity.store(now, Ordering::Relaxed);
+                    self.keepalives_sent.fetch_add(1, Ordering::Relaxed);
+                    true
+                }
+                // A full buffer means the client is still draining real events
+                Err(TrySendError::Full(_)) => true,
+                Err(TrySendError::Closed(_)) => false,
+            }
+        });
+    }
+
+    async fn run(&'static self) {
+        let keepalive = Bytes::from(SSEEvent::comment("keepalive"));
+        loop {
+            let tick = self.tick_ms.load(Ordering::Relaxed);
+            tokio::time::sleep(Duration::from_millis(tick)).await;
+            self.sweep(&keepalive);
+
+            if self.streams.is_empty() {
+                // Stop while idle; a concurrent register may have raced the
+                // store, in which case keep running if nobody else took over
+                self.tick_ms.store(MAX_TICK_MS, Ordering::Relaxed);
+                self.running.store(false, Ordering::Release);
+                if self.streams.is_empty() || self.running.swap(true, Ordering::AcqRel) {
+                    return;
+                }
+                self.recompute_tick();
+            }
+        }
+    }
+}
+
+/// Spawn on the current runtime, the worker runtime, or the shared runtime
+fn spawn_task<F>(fut: F)
+where
+    F: std::future::Future<Output = ()> + Send + 'static,
+{
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        handle.spawn(fut);
+    } else if let Some(rt) = crate::core::global::try_global_runtime() {
+        rt.inner.spawn(fut);
+    } else {
+        crate::core::global::get_runtime().spawn(fut);
+    }
+}
+
+/// Decrements the active SSE connection gauge when the response body drops
+pub struct ActiveSseGuard;
+
+impl ActiveSseGuard {
+    pub fn open() -> Self {
+        KeepaliveRegistry::global().connection_opened();
+        Self
+    }
+}
+
+impl Drop for ActiveSseGuard {
+    fn drop(&mut self) {
+        KeepaliveRegistry::global().connection_closed();
+    }
+}
+
+/// SSE connection gauges for this worker process.
+///
+/// Returns a dict with `active` (SSE responses attached to a connection),
+/// `keepalive_registered` (streams tracked by the keepalive task),
+/// `keepalives_sent` and `keepalive_interval_secs` (server default).
+#[pyfunction]
+pub fn sse_stats(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let registry = KeepaliveRegistry::global();
+    let stats = PyDict::new(py);
+    stats.set_item("active", registry.active())?;
+    stats.set_item("keepalive_registered", registry.registered())?;
+    stats.set_item("keepalives_sent", registry.keepalives_sent())?;
+    stats.set_item(
+        "keepalive_interval_secs",
+        default_interval().map(|d| d.as_secs_f64()),
+    )?;
+    Ok(stats)
+}
+
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(sse_stats, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,211 @@
+//! Ending long-lived streaming responses when the worker drains.
+//!
+//! SSE and chunked responses outlive their handler, so the worker keeps them
+//! counted as in-flight until the body ends. When a drain starts, each open
+//! stream gets a final event (SSE only), is allowed `stream_grace_ms` to
+//! flush, and is then closed, letting `wait_for_drain` finish before the
+//! drain timeout.
+
+use axum::body::{Body, BodyDataStream};
+use bytes::Bytes;
+use futures_core::Stream;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::Sleep;
+
+use crate::core::reload::ReloadManager;
+use crate::http::disconnect::Aborted;
+use crate::http::streaming::SSEEvent;
+
+static LIVE: AtomicI64 = AtomicI64::new(0);
+static CLOSED_BY_DRAIN: AtomicU64 = AtomicU64::new(0);
+static REJECTED_WHILE_DRAINING: AtomicU64 = AtomicU64::new(0);
+static CONNECTIONS_CLOSED_BY_DRAIN: AtomicU64 = AtomicU64::new(0);
+
+/// Response extension marking a body that stays open after the handler
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LiveStream {
+    Sse,
+    Chunked,
+}
+
+/// Streaming responses currently open
+pub fn live() -> u64 {
+    LIVE.load(Ordering::Relaxed).max(0) as u64
+}
+
+/// Count a request refused because the worker is draining
+pub fn record_rejected() {
+    REJECTED_WHILE_DRAINING.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Count a connection told to close because the worker is draining
+pub fn record_connection_closed() {
+    CONNECTIONS_CLOSED_BY_DRAIN.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Wrap a streaming response so it holds its in-flight slot until the body
+/// ends and closes when the worker drains; a body dropped while still open
+/// records `aborted`. Other responses complete the request immediately.
+pub fn track(
+    response: axum::http::Response<Body>,
+    rm: ReloadManager,
+    aborted: Aborted,
+) -> axum::http::Response<Body> {
+    let Some(kind) = response.extensions().get::<LiveStream>().copied() else {
+        rm.on_request_complete();
+        return response;
+    };
+    let config = rm.config();
+    let final_event = match (kind, &config.stream_shutdown_event) {
+        (LiveStream::Sse, Some(event)) => Some(Bytes::from(
+            SSEEvent::new(
+                r#"{"reason":"draining"}"#.to_string(),
+                None,
+                Some(event.clone()),
+                Some(config.stream_retry_ms),
+            )
+            .to_bytes(),
+        )),
+        _ => None,
+    };
+    let grace = Duration::from_millis(config.stream_grace_ms);
+    let drain_rm = rm.clone();
+    let (parts, body) = response.into_parts();
+    let body = DrainingBody {
+        inner: body.into_data_stream(),
+        drain: Box::pin(async move { drain_rm.drain_started().await }),
+        phase: Phase::Open,
+        final_event,
+        grace,
+        aborted: Some(aborted),
+        _slot: InFlightSlot::open(rm),
+    };
+    axum::http::Response::from_parts(parts, Body::from_stream(body))
+}
+
+/// Releases the request's in-flight slot when the body is dropped
+struct InFlightSlot(ReloadManager);
+
+impl InFlightSlot {
+    fn open(rm: ReloadManager) -> Self {
+        LIVE.fetch_add(1, Ordering::Relaxed);
+        Self(rm)
+    }
+}
+
+impl Drop for InFlightSlot {
+    fn drop(&mut self) {
+        LIVE.fetch_sub(1, Ordering::Relaxed);
+        self.0.on_request_complete();
+    }
+}
+
+enum Phase {
+    Open,
+    /// Drain started; the body ends when the grace period runs out
+    Closing(Pin<Box<Sleep>>),
+    Done,
+}
+
+struct DrainingBody {
+    inner: BodyDataStream,
+    drain: Pin<Box<dyn Future<Output = ()> + Send>>,
+    phase: Phase,
+    final_event: Option<Bytes>,
+    grace: Duration,
+    /// Recorded if the client goes away before the body ends
+    aborted: Option<Aborted>,
+    _slot: InFlightSlot,
+}
+
+impl Drop for DrainingBody {
+    fn drop(&mut self) {
+        if matches!(self.phase, Phase::Open) {
+            if let Some(aborted) = self.aborted.take() {
+                aborted.record(true);
+            }
+        }
+    }
+}
+
+impl Stream for DrainingBody {
+    type Item = Result<Bytes, axum::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            match &mut this.phase {
+                Phase::Open => {
+                    if this.drain.as_mut().poll(cx).is_ready() {
+                        CLOSED_BY_DRAIN.fetch_add(1, Ordering::Relaxed);
+                        this.phase = Phase::Closing(Box::pin(tokio::time::sleep(this.grace)));
+                        if let Some(event) = this.final_event.take() {
+                            return Poll::Ready(Some(Ok(event)));
+                        }
+                        continue;
+                    }
+                    return match Pin::new(&mut this.inner).poll_next(cx) {
+                        Poll::Ready(None) => {
+                            this.phase = Phase::Done;
+                            Poll::Ready(None)
+                        }
+                        Poll::Ready(Some(Err(err))) => {
+                            // The body failed on our side, not the client's
+                            this.phase = Phase::Done;
+                            Poll::Ready(Some(Err(err)))
+                        }
+                        other => other,
+                    };
+                }
+                Phase::Closing(deadline) => {
+                    if deadline.as_mut().poll(cx).is_ready() {
+                        this.phase = Phase::Done;
+                        return Poll::Ready(None);
+                    }
+                    return match Pin::new(&mut this.inner).poll_next(cx) {
+                        Poll::Ready(None) => {
+                            this.phase = Phase::Done;
+                            Poll::Ready(None)
+                        }
+                        other => other,
+                    };
+                }
+                Phase::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+/// Streaming connection counters for this worker process.
+///
+/// Returns a dict with `live` (SSE and chunked responses still open),
+/// `closed_by_drain` (streams ended because the worker drained),
+/// `rejected_while_draining` (requests refused with 503 during a drain) and
+/// `connections_closed_by_drain` (keep-alive and HTTP/2 connections closed
+/// when the drain started, after any request in flight on them).
+#[pyfunction]
+pub fn stream_drain_stats(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let stats = PyDict::new(py);
+    stats.set_item("live", live())?;
+    stats.set_item("closed_by_drain", CLOSED_BY_DRAIN.load(Ordering::Relaxed))?;
+    stats.set_item(
+        "rejected_while_draining",
+        REJECTED_WHILE_DRAINING.load(Ordering::Relaxed),
+    )?;
+    stats.set_item(
+        "connections_closed_by_drain",
+        CONNECTIONS_CLOSED_BY_DRAIN.load(Ordering::Relaxed),
+    )?;
+    Ok(stats)
+}
+
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(stream_drain_stats, m)?)?;
+    Ok(())
+}
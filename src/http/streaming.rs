@@ -2,13 +2,20 @@ use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use bytes::Bytes;
 use futures_core::Stream;
+use parking_lot::{Condvar, Mutex};
+use pyo3::exceptions::{PyStopIteration, PyValueError};
 use pyo3::prelude::*;
+use tokio::sync::mpsc::error::TrySendError;
 use tokio::sync::mpsc::{self, Receiver, Sender};
 
+use crate::core::global::get_asyncio;
+use crate::http::sse_keepalive::{self, ActiveSseGuard, KeepaliveRegistry};
 use crate::memory::arena::with_arena;
+use crate::memory::debug::StreamTag;
 
 /// SSE Event structure
 #[pyclass(from_py_object)]
@@ -117,6 +124,8 @@ pub struct SSEStream {
     sender: Sender<Bytes>,
     closed: Arc<AtomicBool>,
     event_count: AtomicU64,
+    /// Last write (event or keepalive), see `sse_keepalive::now_ms`
+    last_activity: Arc<AtomicU64>,
     /// The SSE body for this stream (kept for proper ownership)
     #[pyo3(get)]
     body_handle: Option<usize>,
@@ -128,6 +137,7 @@ impl Clone for SSEStream {
             sender: self.sender.clone(),
             closed: self.closed.clone(),
             event_count: AtomicU64::new(self.event_count.load(Ordering::SeqCst)),
+            last_activity: self.last_activity.clone(),
             body_handle: self.body_handle,
         }
     }
@@ -146,6 +156,7 @@ impl SSEStream {
             sender,
             closed,
             event_count: AtomicU64::new(0),
+            last_activity: Arc::new(AtomicU64::new(sse_keepalive::now_ms())),
             body_handle: None,
         }
     }
@@ -160,6 +171,7 @@ impl SSEStream {
         match self.sender.try_send(bytes) {
             Ok(_) => {
                 self.event_count.fetch_add(1, Ordering::SeqCst);
+                self.touch();
                 Ok(true)
             }
             Err(_) => Ok(false),
@@ -180,21 +192,20 @@ impl SSEStream {
 
     /// Send a keepalive comment
     pub fn keepalive(&self) -> PyResult<bool> {
-        if self.closed.load(Ordering::SeqCst) {
-            return Ok(false);
-        }
+        Ok(self.send_comment("keepalive"))
+    }
 
-        let comment = SSEEvent::comment("keepalive");
-        let bytes = Bytes::from(comment);
-        match self.sender.try_send(bytes) {
-            Ok(_) => Ok(true),
-            Err(_) => Ok(false),
-        }
+    /// Send a `: connected` comment now, so the client and any proxy in
+    /// between see the stream open before the first event
+    pub fn flush_headers(&self) -> PyResult<bool> {
+        Ok(self.send_comment("connected"))
     }
 
     /// Close the stream
     pub fn close(&self) {
         self.closed.store(true, Ordering::SeqCst);
+        // Wake a body parked on an empty channel so the response ends now
+        let _ = self.sender.try_send(Bytes::new());
     }
 
     /// Check if stream is closed
@@ -208,25 +219,73 @@ impl SSEStream {
     }
 }
 
+impl SSEStream {
+    /// Queue a comment line; false when closed or the buffer is full
+    pub(crate) fn send_comment(&self, text: &str) -> bool {
+        if self.closed.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        let bytes = Bytes::from(SSEEvent::comment(text));
+        match self.sender.try_send(bytes) {
+            Ok(_) => {
+                self.touch();
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn touch(&self) {
+        self.last_activity
+            .store(sse_keepalive::now_ms(), Ordering::Relaxed);
+    }
+}
+
 /// SSE Response body that implements Stream
 pub struct SSEBody {
     receiver: Receiver<Bytes>,
     closed: Arc<AtomicBool>,
+    _active: ActiveSseGuard,
+    _tag: Option<StreamTag>,
 }
 
 impl SSEBody {
+    /// Create a stream/body pair using the server's default keepalive interval
     pub fn new(buffer_size: usize) -> (SSEStream, Self) {
+        Self::with_keepalive(buffer_size, sse_keepalive::default_interval())
+    }
+
+    /// Create a stream/body pair; with `Some(interval)` the shared keepalive
+    /// task sends `: keepalive` whenever the stream is idle that long.
+    pub fn with_keepalive(buffer_size: usize, keepalive: Option<Duration>) -> (SSEStream, Self) {
         let (sender, receiver) = mpsc::channel(buffer_size);
         let closed = Arc::new(AtomicBool::new(false));
+        let last_activity = Arc::new(AtomicU64::new(sse_keepalive::now_ms()));
+
+        if let Some(interval) = keepalive {
+            KeepaliveRegistry::global().register(
+                sender.downgrade(),
+                closed.clone(),
+                last_activity.clone(),
+                interval,
+            );
+        }
 
         let stream = SSEStream {
             sender,
             closed: closed.clone(),
             event_count: AtomicU64::new(0),
+            last_activity,
             body_handle: None,
         };
 
-        let body = Self { receiver, closed };
+        let body = Self {
+            receiver,
+            closed,
+            _active: ActiveSseGuard::open(),
+            _tag: StreamTag::current(),
+        };
 
         (stream, body)
     }
@@ -236,24 +295,138 @@ impl Stream for SSEBody {
     type Item = Result<Bytes, std::io::Error>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        if self.closed.load(Ordering::SeqCst) {
-            return Poll::Ready(None);
-        }
-
+        // Events queued before `close()` still go out; the empty chunk it
+        // sends (or an empty queue) ends the body
+        let closed = self.closed.load(Ordering::SeqCst);
         match Pin::new(&mut self.receiver).poll_recv(cx) {
+            Poll::Ready(Some(bytes)) if bytes.is_empty() && closed => Poll::Ready(None),
             Poll::Ready(Some(bytes)) => Poll::Ready(Some(Ok(bytes))),
             Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending if closed => Poll::Ready(None),
             Poll::Pending => Poll::Pending,
         }
     }
 }
 
+/// Default `high_watermark` of a `StreamingResponse`, as asyncio's transports
+pub const DEFAULT_HIGH_WATERMARK: usize = 64 * 1024;
+
+/// How long one `drain()` step blocks with the GIL released
+const DRAIN_STEP: Duration = Duration::from_millis(5);
+
+/// What a live stream has queued that hyper has not taken from the body yet.
+///
+/// Counted up when the producer queues a chunk and down when the body hands
+/// it to hyper, which polls the body only as the socket accepts writes, so a
+/// slow client keeps the counts up. Like an asyncio transport, the stream
+/// pauses once more than `high` bytes are queued and resumes once no more
+/// than `low` are.
+struct FlowState {
+    chunks: usize,
+    bytes: usize,
+    high: usize,
+    low: usize,
+    paused: bool,
+    /// The body is gone: the response ended or the client left
+    detached: bool,
+}
+
+struct Flow {
+    state: Mutex<FlowState>,
+    /// Notified whenever the body takes a chunk or goes away
+    cond: Condvar,
+}
+
+impl Flow {
+    fn new(high: usize, low: usize, detached: bool) -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(FlowState {
+                chunks: 0,
+                bytes: 0,
+                high,
+                low,
+                paused: false,
+                detached,
+            }),
+            cond: Condvar::new(),
+        })
+    }
+
+    fn queued(&self, len: usize) {
+        let mut state = self.state.lock();
+        state.chunks += 1;
+        state.bytes += len;
+        state.paused |= state.bytes > state.high;
+    }
+
+    fn taken(&self, len: usize) {
+        let mut state = self.state.lock();
+        state.chunks = state.chunks.saturating_sub(1);
+        state.bytes = state.bytes.saturating_sub(len);
+        if state.bytes <= state.low {
+            state.paused = false;
+        }
+        self.cond.notify_all();
+    }
+
+    /// Nothing left will be taken; queued chunks are discarded
+    fn detach(&self) {
+        let mut state = self.state.lock();
+        *state = FlowState {
+            chunks: 0,
+            bytes: 0,
+            paused: false,
+            detached: true,
+            ..*state
+        };
+        self.cond.notify_all();
+    }
+
+    fn set_limits(&self, high: usize, low: usize) {
+        let mut state = self.state.lock();
+        state.high = high;
+        state.low = low;
+        if state.bytes > high {
+            state.paused = true;
+        } else if state.bytes <= low {
+            state.paused = false;
+        }
+        self.cond.notify_all();
+    }
+
+    fn is_paused(&self) -> bool {
+        self.state.lock().paused
+    }
+
+    /// Block until the body takes a chunk or `timeout` passes
+    fn wait(&self, timeout: Duration) {
+        let mut state = self.state.lock();
+        if !state.detached {
+            self.cond.wait_for(&mut state, timeout);
+        }
+    }
+}
+
+/// Validated `(high, low)` watermarks; `low` defaults to a quarter of
+/// `high`, as in asyncio
+pub fn watermarks(high: usize, low: Option<usize>) -> PyResult<(usize, usize)> {
+    let low = low.unwrap_or(high / 4);
+    if low > high {
+        return Err(PyValueError::new_err(format!(
+            "high_watermark ({}) must be >= low_watermark ({})",
+            high, low
+        )));
+    }
+    Ok((high, low))
+}
+
 /// Streaming response builder
 #[pyclass(from_py_object)]
 pub struct StreamingResponse {
     sender: Sender<Bytes>,
     closed: Arc<AtomicBool>,
     content_type: String,
+    flow: Arc<Flow>,
 }
 
 impl Clone for StreamingResponse {
@@ -262,6 +435,7 @@ impl Clone for StreamingResponse {
             sender: self.sender.clone(),
             closed: self.closed.clone(),
             content_type: self.content_type.clone(),
+            flow: self.flow.clone(),
         }
     }
 }
@@ -270,29 +444,35 @@ impl Clone for StreamingResponse {
 impl StreamingResponse {
     /// Create a new streaming response
     #[new]
-    #[pyo3(signature = (content_type="application/octet-stream", buffer_size=100))]
-    pub fn py_new(content_type: &str, buffer_size: usize) -> Self {
-        let (sender, _receiver) = mpsc::channel(buffer_size);
+    #[pyo3(signature = (
+        content_type="application/octet-stream",
+        buffer_size=100,
+        high_watermark=DEFAULT_HIGH_WATERMARK,
+        low_watermark=None,
+    ))]
+    pub fn py_new(
+        content_type: &str,
+        buffer_size: usize,
+        high_watermark: usize,
+        low_watermark: Option<usize>,
+    ) -> PyResult<Self> {
+        let (sender, _receiver) = mpsc::channel(buffer_size.max(1));
         let closed = Arc::new(AtomicBool::new(false));
+        let (high, low) = watermarks(high_watermark, low_watermark)?;
 
-        Self {
+        Ok(Self {
             sender,
             closed,
             content_type: content_type.to_string(),
-        }
+            // No body reads from this one
+            flow: Flow::new(high, low, true),
+        })
     }
 
-    /// Write bytes to the stream
+    /// Write bytes to the stream; False when it is closed or its buffer
+    /// is full
     pub fn write(&self, data: Vec<u8>) -> PyResult<bool> {
-        if self.closed.load(Ordering::SeqCst) {
-            return Ok(false);
-        }
-
-        let bytes = Bytes::from(data);
-        match self.sender.try_send(bytes) {
-            Ok(_) => Ok(true),
-            Err(_) => Ok(false),
-        }
+        Ok(self.try_write(Bytes::from(data)).is_ok())
     }
 
     /// Write string to the stream
@@ -307,14 +487,68 @@ impl StreamingResponse {
         self.write(line.into_bytes())
     }
 
+    /// Write bytes, waiting for room in the buffer, then for `drain()` if
+    /// the stream is above its high watermark. Resolves to False when the
+    /// stream is closed or the client left.
+    pub fn write_async(&self, data: Vec<u8>) -> FlowWait {
+        FlowWait {
+            response: self.clone(),
+            pending: Mutex::new(Some(Bytes::from(data))),
+            written: Some(true),
+        }
+    }
+
+    /// Wait until the buffered bytes are at or below the low watermark, if
+    /// the stream went above its high watermark; resolves at once otherwise
+    pub fn drain(&self) -> FlowWait {
+        FlowWait {
+            response: self.clone(),
+            pending: Mutex::new(None),
+            written: None,
+        }
+    }
+
+    /// Chunks written that the server has not sent on yet
+    pub fn buffered_chunks(&self) -> usize {
+        self.flow.state.lock().chunks
+    }
+
+    /// Bytes written that the server has not sent on yet
+    pub fn buffered_bytes(&self) -> usize {
+        self.flow.state.lock().bytes
+    }
+
+    /// Buffered bytes above which the stream pauses
+    #[getter]
+    pub fn high_watermark(&self) -> usize {
+        self.flow.state.lock().high
+    }
+
+    /// Buffered bytes at or below which a paused stream resumes
+    #[getter]
+    pub fn low_watermark(&self) -> usize {
+        self.flow.state.lock().low
+    }
+
+    /// Set both watermarks, as `asyncio.WriteTransport.set_write_buffer_limits`;
+    /// `low` defaults to a quarter of `high`
+    #[pyo3(signature = (high=DEFAULT_HIGH_WATERMARK, low=None))]
+    pub fn set_write_buffer_limits(&self, high: usize, low: Option<usize>) -> PyResult<()> {
+        let (high, low) = watermarks(high, low)?;
+        self.flow.set_limits(high, low);
+        Ok(())
+    }
+
     /// Flush (no-op for now, but kept for API compatibility)
     pub fn flush(&self) -> PyResult<()> {
         Ok(())
     }
 
-    /// Close the stream
+    /// Close the stream; chunks already written are still sent
     pub fn close(&self) {
         self.closed.store(true, Ordering::SeqCst);
+        // Wake a body parked on an empty channel so the response ends now
+        let _ = self.sender.try_send(Bytes::new());
     }
 
     /// Check if closed
@@ -329,45 +563,422 @@ impl StreamingResponse {
     }
 }
 
+impl StreamingResponse {
+    /// Queue a chunk, counting it until the body takes it
+    fn try_write(&self, bytes: Bytes) -> Result<(), TrySendError<Bytes>> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(TrySendError::Closed(bytes));
+        }
+        // An empty chunk would read as the end of a closed stream
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        let len = bytes.len();
+        // Counted first so the body never takes a chunk not yet counted
+        self.flow.queued(len);
+        self.sender
+            .try_send(bytes)
+            .inspect_err(|_| self.flow.taken(len))
+    }
+}
+
+/// Awaitable returned by `StreamingResponse.drain()` and `write_async()`.
+///
+/// Like `Request.wait_disconnected()`, each step blocks briefly with the GIL
+/// released when handlers are stepped without an event loop, and yields
+/// without blocking under a running asyncio loop.
+#[pyclass]
+pub struct FlowWait {
+    response: StreamingResponse,
+    /// Chunk `write_async()` has yet to queue
+    pending: Mutex<Option<Bytes>>,
+    /// Resolved value: None for `drain()`, True for a written chunk
+    written: Option<bool>,
+}
+
+impl FlowWait {
+    /// The resolved value, or None while still waiting
+    fn poll(&self) -> Option<Option<bool>> {
+        let mut pending = self.pending.lock();
+        if let Some(chunk) = pending.take() {
+            match self.response.try_write(chunk) {
+                Ok(()) => {}
+                Err(TrySendError::Full(chunk)) => {
+                    *pending = Some(chunk);
+                    return None;
+                }
+                Err(TrySendError::Closed(_)) => return Some(Some(false)),
+            }
+        }
+        match self.response.flow.is_paused() {
+            true => None,
+            false => Some(self.written),
+        }
+    }
+}
+
+#[pymethods]
+impl FlowWait {
+    fn __await__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<()> {
+        if let Some(value) = self.poll() {
+            return Err(PyStopIteration::new_err((value,)));
+        }
+        let in_loop = !get_asyncio(py)
+            .bind(py)
+            .call_method0("_get_running_loop")?
+            .is_none();
+        if !in_loop {
+            let flow = self.response.flow.clone();
+            py.detach(|| flow.wait(DRAIN_STEP));
+            if let Some(value) = self.poll() {
+                return Err(PyStopIteration::new_err((value,)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where a `StreamingBody` gets its chunks
+enum Feed {
+    /// Chunks sent by a producer through a channel
+    Channel(Receiver<Bytes>),
+    /// A complete buffer handed out in slices
+    Buffer { data: Bytes, chunk_size: usize },
+}
+
 /// Streaming body that implements Stream
 pub struct StreamingBody {
-    receiver: Receiver<Bytes>,
+    feed: Feed,
     closed: Arc<AtomicBool>,
+    /// Set by the producer when it stopped on an error
+    failed: Arc<AtomicBool>,
+    finished: bool,
+    /// Buffered counts of a live stream, see [`StreamingResponse::drain`]
+    flow: Option<Arc<Flow>>,
+    _tag: Option<StreamTag>,
 }
 
 impl StreamingBody {
-    pub fn new(buffer_size: usize, content_type: impl Into<String>) -> (StreamingResponse, Self) {
-        let (sender, receiver) = mpsc::channel(buffer_size);
+    /// Create a live stream/body pair; the watermarks come from
+    /// [`watermarks`]
+    pub fn new(
+        buffer_size: usize,
+        content_type: impl Into<String>,
+        (high_watermark, low_watermark): (usize, usize),
+    ) -> (StreamingResponse, Self) {
+        let (sender, receiver) = mpsc::channel(buffer_size.max(1));
         let closed = Arc::new(AtomicBool::new(false));
+        let flow = Flow::new(high_watermark, low_watermark, false);
 
         let response = StreamingResponse {
             sender,
             closed: closed.clone(),
             content_type: content_type.into(),
+            flow: flow.clone(),
         };
 
-        let body = Self { receiver, closed };
+        let body = Self {
+            feed: Feed::Channel(receiver),
+            closed,
+            failed: Arc::new(AtomicBool::new(false)),
+            finished: false,
+            flow: Some(flow),
+            _tag: StreamTag::current(),
+        };
 
         (response, body)
     }
+
+    /// Stream an already buffered body in `chunk_size` slices.
+    ///
+    /// Slices share the buffer, so nothing is copied and memory is released
+    /// only when the last slice has been written.
+    pub fn from_buffer(data: Bytes, chunk_size: usize) -> Self {
+        Self {
+            feed: Feed::Buffer {
+                data,
+                chunk_size: chunk_size.max(1),
+            },
+            closed: Arc::new(AtomicBool::new(false)),
+            failed: Arc::new(AtomicBool::new(false)),
+            finished: false,
+            flow: None,
+            _tag: StreamTag::current(),
+        }
+    }
+}
+
+impl Drop for StreamingBody {
+    fn drop(&mut self) {
+        if let Some(flow) = &self.flow {
+            flow.detach();
+        }
+    }
 }
 
 impl Stream for StreamingBody {
     type Item = Result<Bytes, std::io::Error>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        if self.closed.load(Ordering::SeqCst) {
+        if self.finished {
             return Poll::Ready(None);
         }
 
-        match Pin::new(&mut self.receiver).poll_recv(cx) {
-            Poll::Ready(Some(bytes)) => Poll::Ready(Some(Ok(bytes))),
-            Poll::Ready(None) => Poll::Ready(None),
+        // Chunks written before `close()` still go out; the empty chunk it
+        // sends (or an empty queue) ends the body
+        let closed = self.closed.load(Ordering::SeqCst);
+        let receiver = match &mut self.feed {
+            Feed::Channel(receiver) => receiver,
+            Feed::Buffer { data, chunk_size } => {
+                if data.is_empty() {
+                    self.finished = true;
+                    return Poll::Ready(None);
+                }
+                let len = (*chunk_size).min(data.len());
+                return Poll::Ready(Some(Ok(data.split_to(len))));
+            }
+        };
+        match Pin::new(receiver).poll_recv(cx) {
+            Poll::Ready(Some(bytes)) if bytes.is_empty() && closed => {
+                self.finished = true;
+                Poll::Ready(None)
+            }
+            Poll::Ready(Some(bytes)) => {
+                if let Some(flow) = &self.flow {
+                    flow.taken(bytes.len());
+                }
+                Poll::Ready(Some(Ok(bytes)))
+            }
+            Poll::Pending if closed => {
+                self.finished = true;
+                Poll::Ready(None)
+            }
+            Poll::Ready(None) => {
+                self.finished = true;
+                // Erroring the body aborts the connection instead of sending the
+                // final chunk, so clients see a truncated response, not a short one
+                if self.failed.load(Ordering::Acquire) {
+                    Poll::Ready(Some(Err(std::io::Error::other(
+                        "response stream producer failed",
+                    ))))
+                } else {
+                    Poll::Ready(None)
+                }
+            }
             Poll::Pending => Poll::Pending,
         }
     }
 }
 
+// ─── Generator-backed streaming ───
+
+/// Where the chunks of a generator-backed stream come from
+enum ChunkSource {
+    Sync(Py<PyAny>),
+    Async(Py<PyAny>),
+}
+
+impl ChunkSource {
+    fn from_py(source: &Bound<'_, PyAny>) -> PyResult<Self> {
+        if source.hasattr("__anext__")? {
+            return Ok(Self::Async(source.clone().unbind()));
+        }
+        match source.try_iter() {
+            Ok(iter) => Ok(Self::Sync(iter.into_any().unbind())),
+            Err(_) => Err(pyo3::exceptions::PyTypeError::new_err(format!(
+                "Cannot stream a '{}': expected a generator, async generator or iterable",
+                source.get_type().name()?
+            ))),
+        }
+    }
+
+    /// Next chunk converted by `convert`, `None` once exhausted
+    fn next_chunk(&self, py: Python<'_>, convert: ChunkConverter) -> PyResult<Option<Bytes>> {
+        let item = match self {
+            Self::Sync(iter) => match iter.bind(py).call_method0("__next__") {
+                Ok(item) => item,
+                Err(e) if e.is_instance_of::<pyo3::exceptions::PyStopIteration>(py) => {
+                    return Ok(None)
+                }
+                Err(e) => return Err(e),
+            },
+            Self::Async(agen) => {
+                // Step the __anext__ awaitable like handler coroutines are stepped
+                let awaitable = agen.bind(py).call_method0("__anext__")?;
+                let awaitable = match awaitable.call_method0("__await__") {
+                    Ok(iter) => iter,
+                    Err(_) => awaitable,
+                };
+                loop {
+                    match awaitable.call_method1("send", (py.None(),)) {
+                        Ok(_) => std::thread::yield_now(),
+                        Err(e) if e.is_instance_of::<pyo3::exceptions::PyStopIteration>(py) => {
+                            break e.value(py).getattr("value")?;
+                        }
+                        Err(e)
+                            if e.is_instance_of::<pyo3::exceptions::PyStopAsyncIteration>(
+                                py,
+                            ) =>
+                        {
+                            return Ok(None)
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+        };
+        convert(&item).map(Some)
+    }
+
+    /// Release the generator's resources after the client went away
+    fn close(&self, py: Python<'_>) {
+        match self {
+            Self::Sync(iter) => {
+                let _ = iter.bind(py).call_method0("close");
+            }
+            Self::Async(agen) => {
+                if let Ok(awaitable) = agen.bind(py).call_method0("aclose") {
+                    // aclose() finishes in one step unless a finally block awaits
+                    let _ = awaitable.call_method1("send", (py.None(),));
+                }
+            }
+        }
+    }
+}
+
+fn chunk_to_bytes(item: &Bound<'_, PyAny>) -> PyResult<Bytes> {
+    if let Ok(bytes) = item.cast::<pyo3::types::PyBytes>() {
+        return Ok(Bytes::copy_from_slice(bytes.as_bytes()));
+    }
+    if let Ok(text) = item.cast::<pyo3::types::PyString>() {
+        return Ok(Bytes::from(text.to_str()?.to_owned()));
+    }
+    if let Ok(data) = item.extract::<Vec<u8>>() {
+        return Ok(Bytes::from(data));
+    }
+    Err(pyo3::exceptions::PyTypeError::new_err(format!(
+        "Streamed chunks must be bytes or str, got '{}'",
+        item.get_type().name()?
+    )))
+}
+
+/// Turns one yielded item into the bytes written to the body
+type ChunkConverter = fn(&Bound<'_, PyAny>) -> PyResult<Bytes>;
+
+/// Stream the chunks of a Python generator, async generator or iterable.
+///
+/// Iteration runs on the blocking pool, one chunk at a time: the producer
+/// waits (without the GIL) while `buffer_size` chunks are in flight, stops
+/// and closes the generator when the client disconnects, and on an exception
+/// logs it and aborts the body so the client sees a truncated response.
+pub fn stream_from_generator(
+    source: &Bound<'_, PyAny>,
+    buffer_size: usize,
+) -> PyResult<StreamingBody> {
+    spawn_producer(source, buffer_size, chunk_to_bytes)
+}
+
+/// Stream a generator of SSE events, formatting each item as it is yielded.
+///
+/// Items may be `SSEEvent`s, dicts with `data`/`event`/`id`/`retry` keys, or
+/// any other value sent as the `data` field.
+pub fn sse_from_generator(
+    source: &Bound<'_, PyAny>,
+    buffer_size: usize,
+) -> PyResult<StreamingBody> {
+    spawn_producer(source, buffer_size, sse_event_bytes)
+}
+
+fn sse_event_bytes(item: &Bound<'_, PyAny>) -> PyResult<Bytes> {
+    let event = if let Ok(event) = item.extract::<SSEEvent>() {
+        event
+    } else if let Ok(s) = item.extract::<String>() {
+        SSEEvent::data(s)
+    } else if let Ok(dict) = item.cast::<pyo3::types::PyDict>() {
+        SSEEvent {
+            id: dict.get_item("id")?.map(|v| v.extract()).transpose()?,
+            event: dict.get_item("event")?.map(|v| v.extract()).transpose()?,
+            data: dict
+                .get_item("data")?
+                .map(|v| v.extract())
+                .transpose()?
+                .unwrap_or_default(),
+            retry: dict.get_item("retry")?.map(|v| v.extract()).transpose()?,
+        }
+    } else {
+        SSEEvent::data(item.str()?.to_string())
+    };
+    Ok(Bytes::from(event.format().into_bytes()))
+}
+
+fn spawn_producer(
+    source: &Bound<'_, PyAny>,
+    buffer_size: usize,
+    convert: ChunkConverter,
+) -> PyResult<StreamingBody> {
+    let chunks = ChunkSource::from_py(source)?;
+    let name = source
+        .getattr("__qualname__")
+        .and_then(|n| n.extract::<String>())
+        .unwrap_or_else(|_| "generator".to_string());
+
+    let (sender, receiver) = mpsc::channel(buffer_size.max(1));
+    let failed = Arc::new(AtomicBool::new(false));
+    let body = StreamingBody {
+        feed: Feed::Channel(receiver),
+        closed: Arc::new(AtomicBool::new(false)),
+        failed: failed.clone(),
+        finished: false,
+        flow: None,
+        _tag: StreamTag::current(),
+    };
+
+    let produce = move |py: Python<'_>| {
+        loop {
+            let chunk = match chunks.next_chunk(py, convert) {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(e) => {
+                    crate::hlog_error!(
+                        "Streaming response '{}' raised after headers were sent; body truncated: {}",
+                        name,
+                        e
+                    );
+                    failed.store(true, Ordering::Release);
+                    break;
+                }
+            };
+            if chunk.is_empty() {
+                continue;
+            }
+            if py.detach(|| sender.blocking_send(chunk)).is_err() {
+                chunks.close(py);
+                break;
+            }
+        }
+    };
+
+    match crate::core::global::try_global_runtime() {
+        Some(rt) => {
+            use crate::core::runtime::Runtime;
+            rt.handler().spawn_blocking(produce);
+        }
+        None => {
+            std::thread::spawn(move || Python::attach(produce));
+        }
+    }
+
+    Ok(body)
+}
+
 /// Create SSE response headers
 pub fn sse_headers() -> Vec<(String, String)> {
     vec![
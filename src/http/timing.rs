@@ -0,0 +1,228 @@
+//! Per-stage request timing.
+//!
+//! A routed request is split into four stages:
+//!
+//! - `route`: from the request reaching the worker (body read, before
+//!   middleware) to the route match
+//! - `queue`: waiting for a Python thread to pick the handler up
+//! - `app`: running the Python handler
+//! - `write`: building the response after the handler returns (after
+//!   middleware, header application)
+//!
+//! Stage durations feed per-stage histograms read by
+//! `dispatch_timing_stats()`, the response log entry and, when enabled on the
+//! server, a `Server-Timing` response header. Recording costs a few
+//! `Instant::now()` reads and one atomic add per stage.
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Histogram bucket upper bounds in milliseconds
+const BUCKETS_MS: [f64; 14] = [
+    0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0,
+];
+
+static SERVER_TIMING: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable the `Server-Timing` header for this process
+pub fn configure(server_timing: bool) {
+    SERVER_TIMING.store(server_timing, Ordering::Relaxed);
+}
+
+pub fn server_timing_enabled() -> bool {
+    SERVER_TIMING.load(Ordering::Relaxed)
+}
+
+struct StageHistogram {
+    // Non-cumulative counts, the last slot being +Inf
+    buckets: [AtomicU64; BUCKETS_MS.len() + 1],
+    count: AtomicU64,
+    sum_us: AtomicU64,
+}
+
+impl StageHistogram {
+    const fn new() -> Self {
+        Self {
+            buckets: [const { AtomicU64::new(0) }; BUCKETS_MS.len() + 1],
+            count: AtomicU64::new(0),
+            sum_us: AtomicU64::new(0),
+        }
+    }
+
+    fn observe(&self, duration: Duration) {
+        let ms = duration.as_secs_f64() * 1000.0;
+        let slot = BUCKETS_MS.partition_point(|&bound| bound < ms);
+        self.buckets[slot].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_us
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("count", self.count.load(Ordering::Relaxed))?;
+        dict.set_item(
+            "sum_ms",
+            self.sum_us.load(Ordering::Relaxed) as f64 / 1000.0,
+        )?;
+        // Cumulative (le, count) pairs as in Prometheus; +Inf is None
+        let mut cumulative = 0;
+        let mut buckets = Vec::with_capacity(self.buckets.len());
+        for (i, count) in self.buckets.iter().enumerate() {
+            cumulative += count.load(Ordering::Relaxed);
+            buckets.push((BUCKETS_MS.get(i).copied(), cumulative));
+        }
+        dict.set_item("buckets", buckets)?;
+        Ok(dict)
+    }
+}
+
+static HISTOGRAMS: [StageHistogram; 4] = [const { StageHistogram::new() }; 4];
+const STAGE_NAMES: [&str; 4] = ["route", "queue", "app", "write"];
+
+/// Handler execution marks reported by the interpreter
+#[derive(Clone, Copy, Debug)]
+pub struct HandlerTiming {
+    /// Handed to the Python thread pool
+    pub dispatched: Instant,
+    /// Picked up by a Python thread holding the GIL
+    pub started: Instant,
+    /// Handler returned (or its coroutine finished)
+    pub finished: Instant,
+}
+
+/// Durations of each stage of one request
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StageTimings {
+    pub route: Duration,
+    pub queue: Duration,
+    pub app: Duration,
+    pub write: Duration,
+    /// Request arrival to the end of `write`
+    pub total: Duration,
+}
+
+impl StageTimings {
+    fn stages(&self) -> [Duration; 4] {
+        [self.route, self.queue, self.app, self.write]
+    }
+
+    /// Add this request to the per-stage histograms
+    pub fn record(&self) {
+        for (histogram, duration) in HISTOGRAMS.iter().zip(self.stages()) {
+            histogram.observe(duration);
+        }
+    }
+
+    /// `Server-Timing` value, e.g. `route;dur=0.05, queue;dur=1.2, ...`
+    pub fn server_timing(&self) -> String {
+        let mut value = String::with_capacity(96);
+        for (name, duration) in STAGE_NAMES.iter().zip(self.stages()) {
+            let _ = write!(value, "{};dur={:.3}, ", name, ms(duration));
+        }
+        let _ = write!(value, "total;dur={:.3}", ms(self.total));
+        value
+    }
+
+    /// Short form for the response log line
+    pub fn summary(&self) -> String {
+        format!(
+            "route={:.2}ms queue={:.2}ms app={:.2}ms write={:.2}ms",
+            ms(self.route),
+            ms(self.queue),
+            ms(self.app),
+            ms(self.write)
+        )
+    }
+}
+
+/// Milliseconds as a float
+pub fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Marks taken while a request moves through the worker
+pub struct RequestTimer {
+    start: Instant,
+    matched: Option<Instant>,
+    /// Template of the matched route
+    route: Option<String>,
+    handler: Option<HandlerTiming>,
+}
+
+impl RequestTimer {
+    pub fn start() -> Self {
+        Self {
+            start: Instant::now(),
+            matched: None,
+            route: None,
+            handler: None,
+        }
+    }
+
+    pub fn started_at(&self) -> Instant {
+        self.start
+    }
+
+    #[inline]
+    pub fn route_matched(&mut self, route: &str) {
+        self.matched = Some(Instant::now());
+        self.route = Some(route.to_string());
+    }
+
+    pub fn route(&self) -> Option<&str> {
+        self.route.as_deref()
+    }
+
+    #[inline]
+    pub fn handler_done(&mut self, timing: HandlerTiming) {
+        self.handler = Some(timing);
+    }
+
+    /// Stage durations so far; `None` unless a handler ran
+    pub fn handler_stages(&self) -> Option<(Duration, Duration, Duration)> {
+        let matched = self.matched?;
+        let handler = self.handler?;
+        Some((
+            matched.duration_since(self.start),
+            handler.started.duration_since(handler.dispatched),
+            handler.finished.duration_since(handler.started),
+        ))
+    }
+
+    /// Close the `write` stage; `None` unless a handler ran
+    pub fn finish(&self) -> Option<StageTimings> {
+        let (route, queue, app) = self.handler_stages()?;
+        let now = Instant::now();
+        Some(StageTimings {
+            route,
+            queue,
+            app,
+            write: now.saturating_duration_since(self.handler?.finished),
+            total: now.duration_since(self.start),
+        })
+    }
+}
+
+/// Per-stage handler timing histograms for this worker process.
+///
+/// Returns a dict with `server_timing` and one entry per stage (`route`,
+/// `queue`, `app`, `write`), each holding `count`, `sum_ms` and `buckets` as
+/// cumulative `(le_ms, count)` pairs, the last with `le_ms` None (+Inf).
+#[pyfunction]
+pub fn dispatch_timing_stats(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let stats = PyDict::new(py);
+    stats.set_item("server_timing", server_timing_enabled())?;
+    for (name, histogram) in STAGE_NAMES.iter().zip(HISTOGRAMS.iter()) {
+        stats.set_item(*name, histogram.to_dict(py)?)?;
+    }
+    Ok(stats)
+}
+
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(dispatch_timing_stats, m)?)?;
+    Ok(())
+}
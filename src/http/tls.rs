@@ -0,0 +1,218 @@
+//! TLS termination and client certificate details.
+//!
+//! A server given `tls_cert_path`/`tls_key_path` terminates TLS in the worker
+//! listener. With `tls_client_ca_path` it also asks clients for a certificate
+//! signed by that CA (mandatory when `tls_require_client_cert` is set). The
+//! client certificate is parsed once per connection into a [`PeerCert`] that
+//! every request on the connection shares.
+
+use parking_lot::RwLock;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rustls::crypto::ring;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use x509_parser::prelude::{FromDer, GeneralName, X509Certificate};
+
+static TLS_CONFIG: RwLock<Option<Arc<ServerConfig>>> = RwLock::new(None);
+
+/// Install the TLS config for this process (`None` serves plaintext)
+pub fn install(config: Option<Arc<ServerConfig>>) {
+    *TLS_CONFIG.write() = config;
+}
+
+/// TLS config for worker listeners, if TLS is enabled
+pub fn server_config() -> Option<Arc<ServerConfig>> {
+    TLS_CONFIG.read().clone()
+}
+
+/// Certificate files for TLS termination
+#[derive(Clone, Debug)]
+pub struct TlsFiles {
+    pub cert_path: String,
+    pub key_path: String,
+    pub client_ca_path: Option<String>,
+    pub require_client_cert: bool,
+}
+
+impl TlsFiles {
+    /// Validate server options; `None` when TLS is not configured
+    pub fn from_options(
+        cert_path: Option<String>,
+        key_path: Option<String>,
+        client_ca_path: Option<String>,
+        require_client_cert: bool,
+    ) -> PyResult<Option<Self>> {
+        match (cert_path, key_path) {
+            (Some(cert_path), Some(key_path)) => {
+                if require_client_cert && client_ca_path.is_none() {
+                    return Err(PyValueError::new_err(
+                        "tls_require_client_cert needs tls_client_ca_path",
+                    ));
+                }
+                let files = Self {
+                    cert_path,
+                    key_path,
+                    client_ca_path,
+                    require_client_cert,
+                };
+                // Fail at construction rather than in every worker
+                files.load(false)?;
+                Ok(Some(files))
+            }
+            (None, None) if client_ca_path.is_none() && !require_client_cert => Ok(None),
+            _ => Err(PyValueError::new_err(
+                "TLS needs both tls_cert_path and tls_key_path",
+            )),
+        }
+    }
+
+    /// Build the rustls config, offering h2 via ALPN when `http2` is set
+    pub fn load(&self, http2: bool) -> PyResult<ServerConfig> {
+        let provider = Arc::new(ring::default_provider());
+        let certs = load_certs(&self.cert_path)?;
+        let key = PrivateKeyDer::from_pem_file(&self.key_path).map_err(|e| {
+            PyValueError::new_err(format!("Failed to read TLS key '{}': {}", self.key_path, e))
+        })?;
+
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(tls_error)?;
+        let builder = match &self.client_ca_path {
+            Some(ca_path) => {
+                let invalid_ca = |e: &dyn std::fmt::Display| {
+                    PyValueError::new_err(format!("Invalid client CA '{}': {}", ca_path, e))
+                };
+                let mut roots = RootCertStore::empty();
+                for cert in load_certs(ca_path)? {
+                    roots.add(cert).map_err(|e| invalid_ca(&e))?;
+                }
+                let verifier =
+                    WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+                let verifier = if self.require_client_cert {
+                    verifier.build()
+                } else {
+                    verifier.allow_unauthenticated().build()
+                }
+                .map_err(|e| invalid_ca(&e))?;
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        let mut config = builder.with_single_cert(certs, key).map_err(tls_error)?;
+        config.alpn_protocols = if http2 {
+            vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+        } else {
+            vec![b"http/1.1".to_vec()]
+        };
+        Ok(config)
+    }
+}
+
+fn load_certs(path: &str) -> PyResult<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|iter| iter.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| {
+            PyValueError::new_err(format!("Failed to read certificates '{}': {}", path, e))
+        })?;
+    if certs.is_empty() {
+        return Err(PyValueError::new_err(format!(
+            "No certificates found in '{}'",
+            path
+        )));
+    }
+    Ok(certs)
+}
+
+fn tls_error(err: rustls::Error) -> PyErr {
+    PyValueError::new_err(format!("Invalid TLS configuration: {}", err))
+}
+
+/// A verified client certificate, parsed once per connection
+#[derive(Debug)]
+pub struct PeerCert {
+    pub subject: String,
+    pub issuer: String,
+    pub serial: String,
+    /// Subject alternative names as `DNS:`, `IP:`, `email:` or `URI:` entries
+    pub sans: Vec<String>,
+    /// Validity bounds as Unix timestamps
+    pub not_before: i64,
+    pub not_after: i64,
+    /// SHA-256 of the DER encoding, lowercase hex
+    pub fingerprint: String,
+}
+
+impl PeerCert {
+    pub fn parse(der: &[u8]) -> Option<Self> {
+        let (_, cert) = X509Certificate::from_der(der).ok()?;
+        let sans = cert
+            .subject_alternative_name()
+            .ok()
+            .flatten()
+            .map(|ext| {
+                ext.value
+                    .general_names
+                    .iter()
+                    .filter_map(format_general_name)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let fingerprint = Sha256::digest(der)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+
+        Some(Self {
+            subject: cert.subject().to_string(),
+            issuer: cert.issuer().to_string(),
+            serial: cert.raw_serial_as_string(),
+            sans,
+            not_before: cert.validity().not_before.timestamp(),
+            not_after: cert.validity().not_after.timestamp(),
+            fingerprint,
+        })
+    }
+
+    /// `subject`, `issuer`, `serial`, `sans`, `not_before`/`not_after`
+    /// (ISO 8601, UTC) and `fingerprint` (SHA-256 hex)
+    pub fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("subject", &self.subject)?;
+        dict.set_item("issuer", &self.issuer)?;
+        dict.set_item("serial", &self.serial)?;
+        dict.set_item("sans", &self.sans)?;
+        dict.set_item("not_before", iso8601(self.not_before))?;
+        dict.set_item("not_after", iso8601(self.not_after))?;
+        dict.set_item("fingerprint", &self.fingerprint)?;
+        Ok(dict)
+    }
+}
+
+fn format_general_name(name: &GeneralName<'_>) -> Option<String> {
+    match name {
+        GeneralName::DNSName(dns) => Some(format!("DNS:{}", dns)),
+        GeneralName::RFC822Name(email) => Some(format!("email:{}", email)),
+        GeneralName::URI(uri) => Some(format!("URI:{}", uri)),
+        GeneralName::IPAddress(bytes) => {
+            let ip = match bytes.len() {
+                4 => std::net::IpAddr::from(<[u8; 4]>::try_from(*bytes).ok()?),
+                16 => std::net::IpAddr::from(<[u8; 16]>::try_from(*bytes).ok()?),
+                _ => return None,
+            };
+            Some(format!("IP:{}", ip))
+        }
+        _ => None,
+    }
+}
+
+fn iso8601(timestamp: i64) -> Option<String> {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|dt| dt.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+}
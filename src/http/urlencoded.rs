@@ -0,0 +1,68 @@
+//! `application/x-www-form-urlencoded` parsing shared by query strings and
+//! form bodies.
+//!
+//! Pairs keep their order and repeats. `+` decodes to a space and
+//! percent-escapes to UTF-8 (invalid sequences become U+FFFD), as
+//! `form_urlencoded` does. A trailing `[]` on a key (`tag[]=a&tag[]=b`, the
+//! PHP convention) can be stripped so those values group with plain `tag`.
+
+use ahash::AHashMap;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Decoded `(key, value)` pairs in the order they appear
+pub fn parse(raw: &[u8]) -> Vec<(String, String)> {
+    if raw.is_empty() {
+        return Vec::new();
+    }
+    form_urlencoded::parse(raw).into_owned().collect()
+}
+
+/// `key` without its `[]` suffix when `strip_brackets` is set
+pub fn key_name(key: &str, strip_brackets: bool) -> &str {
+    if strip_brackets {
+        key.strip_suffix("[]").unwrap_or(key)
+    } else {
+        key
+    }
+}
+
+/// Every value given for `name`, in order
+pub fn values(pairs: &[(String, String)], name: &str, strip_brackets: bool) -> Vec<String> {
+    let name = key_name(name, strip_brackets);
+    pairs
+        .iter()
+        .filter(|(key, _)| key_name(key, strip_brackets) == name)
+        .map(|(_, value)| value.clone())
+        .collect()
+}
+
+/// Values grouped by key, keys in the order first seen
+pub fn grouped(pairs: &[(String, String)], strip_brackets: bool) -> Vec<(String, Vec<String>)> {
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    let mut index: AHashMap<&str, usize> = AHashMap::new();
+    for (key, value) in pairs {
+        let key = key_name(key, strip_brackets);
+        match index.get(key) {
+            Some(&i) => groups[i].1.push(value.clone()),
+            None => {
+                index.insert(key, groups.len());
+                groups.push((key.to_string(), vec![value.clone()]));
+            }
+        }
+    }
+    groups
+}
+
+/// `grouped` as a Python dict of lists
+pub fn grouped_dict<'py>(
+    py: Python<'py>,
+    pairs: &[(String, String)],
+    strip_brackets: bool,
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    for (key, values) in grouped(pairs, strip_brackets) {
+        dict.set_item(key, values)?;
+    }
+    Ok(dict)
+}
@@ -0,0 +1,675 @@
+//! Limits on inbound WebSocket messages.
+//!
+//! Each WebSocket route carries a [`WsLimits`] built from the options given
+//! at registration. The transport negotiates `permessage-deflate` (RFC 7692)
+//! with [`WsLimits::negotiate`] during the upgrade, then passes every frame
+//! it reads through a per-connection [`WsInboundGuard`], which reassembles
+//! fragmented messages, inflates compressed ones and enforces:
+//!
+//! - `max_message_size` on the bytes received for one message, and
+//!   `max_fragments` on its frame count (close 1009)
+//! - `max_decompressed_size` while inflating, so a compression bomb stops at
+//!   the cap rather than after it has been inflated (close 1009)
+//! - strict UTF-8 in text messages and close reasons, checked as fragments
+//!   arrive (close 1007)
+//! - `max_messages_per_second`, closing the connection (1008) or asking the
+//!   transport to wait before reading on (`rate_limit_action="throttle"`)
+//!
+//! Framing errors close with 1002. Every close is counted by reason, see
+//! `websocket_inbound_stats()`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use flate2::{Decompress, FlushDecompress, Status};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use super::websocket::{WsMessage, WsMessageType};
+
+/// Default cap on one inbound message
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+/// Default cap on the frames of one fragmented message
+pub const DEFAULT_MAX_FRAGMENTS: usize = 128;
+/// Largest LZ77 window permessage-deflate allows
+const MAX_WINDOW_BITS: u8 = 15;
+/// Output reserved per inflate step
+const INFLATE_CHUNK: usize = 32 * 1024;
+/// Appended to a compressed message before inflating (RFC 7692 7.2.2)
+const DEFLATE_TAIL: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_BINARY: u8 = 0x2;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xa;
+
+/// Why a guard closed its connection
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Violation {
+    MessageTooBig,
+    TooManyFragments,
+    DecompressedTooBig,
+    InvalidUtf8,
+    InvalidCompressed,
+    Protocol(&'static str),
+    RateLimited,
+}
+
+impl Violation {
+    const ALL: [Self; 7] = [
+        Self::MessageTooBig,
+        Self::TooManyFragments,
+        Self::DecompressedTooBig,
+        Self::InvalidUtf8,
+        Self::InvalidCompressed,
+        Self::Protocol(""),
+        Self::RateLimited,
+    ];
+
+    fn code(self) -> u16 {
+        match self {
+            Self::MessageTooBig | Self::TooManyFragments | Self::DecompressedTooBig => 1009,
+            Self::InvalidUtf8 | Self::InvalidCompressed => 1007,
+            Self::Protocol(_) => 1002,
+            Self::RateLimited => 1008,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::MessageTooBig => "message_too_big",
+            Self::TooManyFragments => "too_many_fragments",
+            Self::DecompressedTooBig => "decompressed_too_big",
+            Self::InvalidUtf8 => "invalid_utf8",
+            Self::InvalidCompressed => "invalid_compressed",
+            Self::Protocol(_) => "protocol_error",
+            Self::RateLimited => "rate_limited",
+        }
+    }
+
+    fn reason(self) -> String {
+        match self {
+            Self::MessageTooBig => "Message too big".to_string(),
+            Self::TooManyFragments => "Too many fragments".to_string(),
+            Self::DecompressedTooBig => "Decompressed message too big".to_string(),
+            Self::InvalidUtf8 => "Invalid UTF-8".to_string(),
+            Self::InvalidCompressed => "Invalid compressed data".to_string(),
+            Self::Protocol(detail) => format!("Protocol error: {}", detail),
+            Self::RateLimited => "Message rate limit exceeded".to_string(),
+        }
+    }
+
+    fn index(self) -> usize {
+        Self::ALL
+            .iter()
+            .position(|v| std::mem::discriminant(v) == std::mem::discriminant(&self))
+            .unwrap_or(0)
+    }
+}
+
+static CLOSED: [AtomicU64; 7] = [const { AtomicU64::new(0) }; 7];
+static THROTTLED: AtomicU64 = AtomicU64::new(0);
+static INFLATED: AtomicU64 = AtomicU64::new(0);
+
+/// Inbound limits of one WebSocket route
+#[pyclass(from_py_object)]
+#[derive(Clone, Debug)]
+pub struct WsLimits {
+    /// Largest message accepted, in bytes received (compressed, if deflated)
+    #[pyo3(get)]
+    pub max_message_size: usize,
+    /// Largest message after inflating
+    #[pyo3(get)]
+    pub max_decompressed_size: usize,
+    /// Most frames one message may be split into
+    #[pyo3(get)]
+    pub max_fragments: usize,
+    /// Whether to accept a client's permessage-deflate offer
+    #[pyo3(get)]
+    pub permessage_deflate: bool,
+    /// Largest LZ77 window the server compresses with, sent in the handshake
+    #[pyo3(get)]
+    pub server_max_window_bits: u8,
+    /// Data messages allowed per second per connection, None for no limit
+    #[pyo3(get)]
+    pub max_messages_per_second: Option<f64>,
+    /// "close" or "throttle" once a client sends faster
+    #[pyo3(get)]
+    pub rate_limit_action: String,
+}
+
+#[pymethods]
+impl WsLimits {
+    /// Args:
+    ///     max_message_size: Largest message in bytes as received, after
+    ///         joining its fragments
+    ///     max_decompressed_size: Largest compressed message once inflated
+    ///         (default: max_message_size)
+    ///     max_fragments: Most frames in one fragmented message
+    ///     permessage_deflate: Accept permessage-deflate offers
+    ///     server_max_window_bits: Server LZ77 window, 8 to 15
+    ///     max_messages_per_second: Data messages allowed per second per
+    ///         connection, with bursts of up to one second's worth
+    ///     rate_limit_action: "close" closes faster clients with 1008,
+    ///         "throttle" delays reading their next frame instead
+    #[new]
+    #[pyo3(signature = (
+        max_message_size=DEFAULT_MAX_MESSAGE_SIZE,
+        max_decompressed_size=None,
+        max_fragments=DEFAULT_MAX_FRAGMENTS,
+        permessage_deflate=false,
+        server_max_window_bits=MAX_WINDOW_BITS,
+        max_messages_per_second=None,
+        rate_limit_action="close"
+    ))]
+    pub fn new(
+        max_message_size: usize,
+        max_decompressed_size: Option<usize>,
+        max_fragments: usize,
+        permessage_deflate: bool,
+        server_max_window_bits: u8,
+        max_messages_per_second: Option<f64>,
+        rate_limit_action: &str,
+    ) -> PyResult<Self> {
+        if max_message_size == 0 {
+            return Err(PyValueError::new_err("max_message_size must be at least 1"));
+        }
+        if max_decompressed_size == Some(0) {
+            return Err(PyValueError::new_err(
+                "max_decompressed_size must be at least 1",
+            ));
+        }
+        if max_fragments == 0 {
+            return Err(PyValueError::new_err("max_fragments must be at least 1"));
+        }
+        if !(8..=MAX_WINDOW_BITS).contains(&server_max_window_bits) {
+            return Err(PyValueError::new_err(
+                "server_max_window_bits must be between 8 and 15",
+            ));
+        }
+        if max_messages_per_second.is_some_and(|rate| !(rate > 0.0 && rate.is_finite())) {
+            return Err(PyValueError::new_err(
+                "max_messages_per_second must be a positive number",
+            ));
+        }
+        if !matches!(rate_limit_action, "close" | "throttle") {
+            return Err(PyValueError::new_err(format!(
+                "rate_limit_action must be 'close' or 'throttle', got '{}'",
+                rate_limit_action
+            )));
+        }
+        Ok(Self {
+            max_message_size,
+            max_decompressed_size: max_decompressed_size.unwrap_or(max_message_size),
+            max_fragments,
+            permessage_deflate,
+            server_max_window_bits,
+            max_messages_per_second,
+            rate_limit_action: rate_limit_action.to_string(),
+        })
+    }
+
+    /// The `Sec-WebSocket-Extensions` value accepting the first acceptable
+    /// permessage-deflate offer in `offers` (the request header), or None
+    /// to decline compression
+    #[pyo3(signature = (offers=None))]
+    pub fn negotiate(&self, offers: Option<&str>) -> Option<String> {
+        if !self.permessage_deflate {
+            return None;
+        }
+        offers?
+            .split(',')
+            .find_map(DeflateOffer::parse)
+            .map(|offer| offer.accept(self.server_max_window_bits))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "WsLimits(max_message_size={}, max_decompressed_size={}, max_fragments={}, \
+             permessage_deflate={}, server_max_window_bits={}, max_messages_per_second={:?}, \
+             rate_limit_action={:?})",
+            self.max_message_size,
+            self.max_decompressed_size,
+            self.max_fragments,
+            if self.permessage_deflate {
+                "True"
+            } else {
+                "False"
+            },
+            self.server_max_window_bits,
+            self.max_messages_per_second,
+            self.rate_limit_action
+        )
+    }
+}
+
+/// Parameters of one permessage-deflate offer or response
+#[derive(Default)]
+struct DeflateOffer {
+    server_no_context_takeover: bool,
+    client_no_context_takeover: bool,
+    server_max_window_bits: Option<u8>,
+}
+
+impl DeflateOffer {
+    /// None for other extensions and offers with unknown, repeated or
+    /// invalid parameters, which the server must decline
+    fn parse(offer: &str) -> Option<Self> {
+        let mut parts = offer.split(';').map(str::trim);
+        if !parts.next()?.eq_ignore_ascii_case("permessage-deflate") {
+            return None;
+        }
+        let mut parsed = Self::default();
+        let mut client_window = false;
+        for param in parts.filter(|p| !p.is_empty()) {
+            let (name, value) = match param.split_once('=') {
+                Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                None => (param, None),
+            };
+            let bits = || {
+                value
+                    .and_then(|v| v.parse::<u8>().ok())
+                    .filter(|bits| (8..=MAX_WINDOW_BITS).contains(bits))
+            };
+            match name.to_ascii_lowercase().as_str() {
+                "server_no_context_takeover" if value.is_none() => {
+                    if std::mem::replace(&mut parsed.server_no_context_takeover, true) {
+                        return None;
+                    }
+                }
+                "client_no_context_takeover" if value.is_none() => {
+                    if std::mem::replace(&mut parsed.client_no_context_takeover, true) {
+                        return None;
+                    }
+                }
+                "server_max_window_bits" => {
+                    if parsed.server_max_window_bits.is_some() {
+                        return None;
+                    }
+                    parsed.server_max_window_bits = Some(bits()?);
+                }
+                // Inbound messages are inflated with the full window, so
+                // whatever the client uses is fine and goes unanswered
+                "client_max_window_bits" => {
+                    if std::mem::replace(&mut client_window, true)
+                        || (value.is_some() && bits().is_none())
+                    {
+                        return None;
+                    }
+                }
+                _ => return None,
+            }
+        }
+        Some(parsed)
+    }
+
+    fn accept(&self, server_max_window_bits: u8) -> String {
+        let mut response = "permessage-deflate".to_string();
+        if self.server_no_context_takeover {
+            response.push_str("; server_no_context_takeover");
+        }
+        if self.client_no_context_takeover {
+            response.push_str("; client_no_context_takeover");
+        }
+        let bits = self
+            .server_max_window_bits
+            .map_or(server_max_window_bits, |offered| {
+                offered.min(server_max_window_bits)
+            });
+        if bits < MAX_WINDOW_BITS {
+            response.push_str(&format!("; server_max_window_bits={}", bits));
+        }
+        response
+    }
+}
+
+/// A message whose frames are still arriving
+struct Partial {
+    text: bool,
+    compressed: bool,
+    data: Vec<u8>,
+    fragments: usize,
+    /// Prefix of `data` known to be valid UTF-8
+    valid_up_to: usize,
+}
+
+/// Inflater for a connection with permessage-deflate
+struct Inflater {
+    decoder: Decompress,
+    /// The client resets its compressor after every message
+    reset_each: bool,
+}
+
+impl Inflater {
+    fn inflate(&mut self, data: &[u8], limit: usize) -> Result<Vec<u8>, Violation> {
+        let mut input = Vec::with_capacity(data.len() + DEFLATE_TAIL.len());
+        input.extend_from_slice(data);
+        input.extend_from_slice(&DEFLATE_TAIL);
+
+        let start = self.decoder.total_in();
+        let mut out = Vec::new();
+        let mut ended = false;
+        loop {
+            // Room for one byte past the cap, so an exactly-full message passes
+            let room = INFLATE_CHUNK.min(limit + 1 - out.len());
+            out.reserve_exact(room);
+            let consumed = (self.decoder.total_in() - start) as usize;
+            let before = (self.decoder.total_in(), out.len());
+            let status = self
+                .decoder
+                .decompress_vec(&input[consumed..], &mut out, FlushDecompress::Sync)
+                .map_err(|_| Violation::InvalidCompressed)?;
+            if out.len() > limit {
+                return Err(Violation::DecompressedTooBig);
+            }
+            if status == Status::StreamEnd {
+                ended = true;
+                break;
+            }
+            let all_in = (self.decoder.total_in() - start) as usize == input.len();
+            let progressed = before != (self.decoder.total_in(), out.len());
+            if (all_in && out.len() < out.capacity()) || !progressed {
+                break;
+            }
+        }
+        if ended || self.reset_each {
+            self.decoder.reset(false);
+        }
+        Ok(out)
+    }
+}
+
+/// Token bucket of a connection's data messages
+struct RateLimit {
+    rate: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimit {
+    /// Seconds to wait before this message is within the rate
+    fn take(&mut self) -> f64 {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.updated = now;
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            0.0
+        } else {
+            -self.tokens / self.rate
+        }
+    }
+}
+
+/// Applies a route's [`WsLimits`] to the frames of one connection.
+///
+/// `feed` returns each complete message, control frames included, and
+/// None while a fragmented message is still arriving. When a frame breaks a
+/// limit it returns the Close message the transport should send instead;
+/// the guard is closed from then on.
+#[pyclass]
+pub struct WsInboundGuard {
+    limits: WsLimits,
+    inflater: Option<Inflater>,
+    partial: Option<Partial>,
+    rate: Option<RateLimit>,
+    close_code: Option<u16>,
+    delay: f64,
+}
+
+#[pymethods]
+impl WsInboundGuard {
+    /// Args:
+    ///     limits: The route's limits
+    ///     extensions: The `Sec-WebSocket-Extensions` response the upgrade
+    ///         was answered with (see `WsLimits.negotiate`), None without
+    #[new]
+    #[pyo3(signature = (limits, extensions=None))]
+    pub fn new(limits: WsLimits, extensions: Option<&str>) -> Self {
+        let inflater = extensions
+            .and_then(DeflateOffer::parse)
+            .map(|params| Inflater {
+                decoder: Decompress::new(false),
+                reset_each: params.client_no_context_takeover,
+            });
+        let rate = limits.max_messages_per_second.map(|rate| RateLimit {
+            rate,
+            tokens: rate,
+            updated: Instant::now(),
+        });
+        Self {
+            limits,
+            inflater,
+            partial: None,
+            rate,
+            close_code: None,
+            delay: 0.0,
+        }
+    }
+
+    /// Pass one frame through the limits.
+    ///
+    /// Args:
+    ///     opcode: Frame opcode (0 continuation, 1 text, 2 binary, 8 close,
+    ///         9 ping, 10 pong)
+    ///     payload: Unmasked payload
+    ///     fin: Whether this is the last frame of its message
+    ///     rsv1: The per-message compressed bit
+    #[pyo3(signature = (opcode, payload, fin=true, rsv1=false))]
+    pub fn feed(&mut self, opcode: u8, payload: &[u8], fin: bool, rsv1: bool) -> Option<WsMessage> {
+        if self.close_code.is_some() {
+            return None;
+        }
+        self.delay = 0.0;
+        match self.frame(opcode, payload, fin, rsv1) {
+            Ok(message) => message,
+            Err(violation) => Some(self.close(violation)),
+        }
+    }
+
+    /// Close code the guard sent, None while open
+    #[getter]
+    fn close_code(&self) -> Option<u16> {
+        self.close_code
+    }
+
+    /// Seconds the transport should wait before reading the next frame, set
+    /// when a throttled connection went over its message rate
+    #[getter]
+    fn delay(&self) -> f64 {
+        self.delay
+    }
+
+    /// Whether permessage-deflate is in use
+    #[getter]
+    fn compressed(&self) -> bool {
+        self.inflater.is_some()
+    }
+
+    #[getter]
+    fn limits(&self) -> WsLimits {
+        self.limits.clone()
+    }
+}
+
+impl WsInboundGuard {
+    fn frame(
+        &mut self,
+        opcode: u8,
+        payload: &[u8],
+        fin: bool,
+        rsv1: bool,
+    ) -> Result<Option<WsMessage>, Violation> {
+        match opcode {
+            OP_CLOSE | OP_PING | OP_PONG => {
+                if !fin || rsv1 || payload.len() > 125 {
+                    return Err(Violation::Protocol("invalid control frame"));
+                }
+                if opcode == OP_CLOSE {
+                    check_close_payload(payload)?;
+                }
+                let msg_type = match opcode {
+                    OP_CLOSE => WsMessageType::Close,
+                    OP_PING => WsMessageType::Ping,
+                    _ => WsMessageType::Pong,
+                };
+                Ok(Some(WsMessage {
+                    msg_type,
+                    data: payload.to_vec(),
+                }))
+            }
+            OP_TEXT | OP_BINARY => {
+                if self.partial.is_some() {
+                    return Err(Violation::Protocol("expected a continuation frame"));
+                }
+                if rsv1 && self.inflater.is_none() {
+                    return Err(Violation::Protocol(
+                        "compressed frame without permessage-deflate",
+                    ));
+                }
+                self.partial = Some(Partial {
+                    text: opcode == OP_TEXT,
+                    compressed: rsv1,
+                    data: Vec::new(),
+                    fragments: 0,
+                    valid_up_to: 0,
+                });
+                self.append(payload, fin)
+            }
+            OP_CONTINUATION => {
+                if self.partial.is_none() {
+                    return Err(Violation::Protocol("continuation frame without a message"));
+                }
+                if rsv1 {
+                    return Err(Violation::Protocol(
+                        "compressed bit on a continuation frame",
+                    ));
+                }
+                self.append(payload, fin)
+            }
+            _ => Err(Violation::Protocol("unknown opcode")),
+        }
+    }
+
+    fn append(&mut self, payload: &[u8], fin: bool) -> Result<Option<WsMessage>, Violation> {
+        let partial = self.partial.as_mut().expect("checked by caller");
+        partial.fragments += 1;
+        if partial.fragments > self.limits.max_fragments {
+            return Err(Violation::TooManyFragments);
+        }
+        if partial.data.len() + payload.len() > self.limits.max_message_size {
+            return Err(Violation::MessageTooBig);
+        }
+        partial.data.extend_from_slice(payload);
+        if partial.text && !partial.compressed {
+            partial.valid_up_to = check_utf8(&partial.data, partial.valid_up_to, fin)?;
+        }
+        if !fin {
+            return Ok(None);
+        }
+
+        let partial = self.partial.take().expect("checked above");
+        let data = if partial.compressed {
+            let inflater = self.inflater.as_mut().expect("checked on the first frame");
+            let inflated = inflater.inflate(&partial.data, self.limits.max_decompressed_size)?;
+            INFLATED.fetch_add(1, Ordering::Relaxed);
+            if partial.text {
+                check_utf8(&inflated, 0, true)?;
+            }
+            inflated
+        } else {
+            partial.data
+        };
+        if let Some(rate) = &mut self.rate {
+            let wait = rate.take();
+            if wait > 0.0 {
+                if self.limits.rate_limit_action == "close" {
+                    return Err(Violation::RateLimited);
+                }
+                THROTTLED.fetch_add(1, Ordering::Relaxed);
+                self.delay = wait;
+            }
+        }
+        Ok(Some(WsMessage {
+            msg_type: if partial.text {
+                WsMessageType::Text
+            } else {
+                WsMessageType::Binary
+            },
+            data,
+        }))
+    }
+
+    fn close(&mut self, violation: Violation) -> WsMessage {
+        CLOSED[violation.index()].fetch_add(1, Ordering::Relaxed);
+        let code = violation.code();
+        self.close_code = Some(code);
+        self.partial = None;
+        let mut data = code.to_be_bytes().to_vec();
+        data.extend_from_slice(violation.reason().as_bytes());
+        WsMessage {
+            msg_type: WsMessageType::Close,
+            data,
+        }
+    }
+}
+
+/// Extend the valid UTF-8 prefix of `data` from `from`. A sequence cut off
+/// at the end is allowed until the last fragment.
+fn check_utf8(data: &[u8], from: usize, fin: bool) -> Result<usize, Violation> {
+    match std::str::from_utf8(&data[from..]) {
+        Ok(_) => Ok(data.len()),
+        Err(e) if e.error_len().is_none() && !fin => Ok(from + e.valid_up_to()),
+        Err(_) => Err(Violation::InvalidUtf8),
+    }
+}
+
+/// A close payload is empty, or a sendable status code and a UTF-8 reason
+fn check_close_payload(payload: &[u8]) -> Result<(), Violation> {
+    match payload {
+        [] => Ok(()),
+        [_] => Err(Violation::Protocol("truncated close code")),
+        [high, low, reason @ ..] => {
+            let code = u16::from_be_bytes([*high, *low]);
+            if !matches!(code, 1000..=1003 | 1007..=1011 | 3000..=4999) {
+                return Err(Violation::Protocol("invalid close code"));
+            }
+            std::str::from_utf8(reason).map_err(|_| Violation::InvalidUtf8)?;
+            Ok(())
+        }
+    }
+}
+
+/// Inbound WebSocket limit counters for this worker process.
+///
+/// Returns a dict with `closed` (connections closed, by reason:
+/// `message_too_big`, `too_many_fragments`, `decompressed_too_big`,
+/// `invalid_utf8`, `invalid_compressed`, `protocol_error`, `rate_limited`),
+/// `throttled` (messages delayed) and `inflated` (compressed messages
+/// accepted).
+#[pyfunction]
+pub fn websocket_inbound_stats(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let closed = PyDict::new(py);
+    for violation in Violation::ALL {
+        closed.set_item(
+            violation.name(),
+            CLOSED[violation.index()].load(Ordering::Relaxed),
+        )?;
+    }
+    let stats = PyDict::new(py);
+    stats.set_item("closed", closed)?;
+    stats.set_item("throttled", THROTTLED.load(Ordering::Relaxed))?;
+    stats.set_item("inflated", INFLATED.load(Ordering::Relaxed))?;
+    Ok(stats)
+}
+
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<WsLimits>()?;
+    m.add_class::<WsInboundGuard>()?;
+    m.add_function(wrap_pyfunction!(websocket_inbound_stats, m)?)?;
+    Ok(())
+}
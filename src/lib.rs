@@ -19,6 +19,7 @@ pub use crate::core::runtime;
 pub use crate::core::socket;
 
 // Core performance modules
+pub mod cache;
 pub mod client;
 pub mod core;
 pub mod database;
@@ -61,14 +62,17 @@ pub use crate::grpc::{GrpcConfig, GrpcServer};
 pub use crate::http::streaming::{SSEEvent, SSEGenerator, SSEStream, StreamingResponse};
 
 pub use crate::http::websocket::{RustWebSocket, WsMessage, WsMessageType};
+pub use crate::http::ws_limits::{WsInboundGuard, WsLimits};
 
 // Realtime exports
 pub use crate::realtime::broadcast::{
     BackpressurePolicy, BroadcastConfig, BroadcastStats, BroadcastSubscriber, RealtimeBroadcast,
 };
 pub use crate::realtime::channel::{ChannelManager, ChannelStats, Subscriber, TopicMatcher};
-pub use crate::realtime::heartbeat::{HeartbeatConfig, HeartbeatMonitor, HeartbeatStats};
-pub use crate::realtime::presence::{PresenceDiff, PresenceInfo, PresenceTracker};
+pub use crate::realtime::heartbeat::{
+    HeartbeatClientStats, HeartbeatConfig, HeartbeatMonitor, HeartbeatStats,
+};
+pub use crate::realtime::presence::{PresenceDiff, PresenceInfo, PresenceTracker, PresenceUpdate};
 pub use crate::core::reload::{PyHealthCheck, PyReloadConfig, PyReloadManager};
 pub use crate::logging::PyLogConfig;
 
@@ -90,118 +94,62 @@ pub use fast_path::static_files::StaticFileHandler;
 pub use memory::pool::{RequestPool, ResponsePool};
 pub use utils::pagination::PageInfo;
 
+/// Create `_hypern.<name>`, fill it with `register`, and mirror its members
+/// onto the top-level module so the flat names keep working.
+///
+/// Re-registering the same object under a name is a no-op; a different
+/// object under an existing name is reported as an error.
+fn register_submodule(
+    py: Python<'_>,
+    parent: &Bound<'_, PyModule>,
+    name: &str,
+    register: impl FnOnce(&Bound<'_, PyModule>) -> PyResult<()>,
+) -> PyResult<()> {
+    let qualified = format!("hypern._hypern.{}", name);
+    let sub = PyModule::new(py, name)?;
+    sub.setattr("__name__", &qualified)?;
+    register(&sub)?;
+
+    for (key, value) in sub.dict().iter() {
+        let key: String = key.extract()?;
+        if key.starts_with("__") {
+            continue;
+        }
+        match parent.getattr(key.as_str()) {
+            Ok(existing) if existing.is(&value) => {}
+            Ok(_) => {
+                return Err(pyo3::exceptions::PyRuntimeError::new_err(format!(
+                    "Duplicate registration of '{}' from submodule '{}'",
+                    key, name
+                )));
+            }
+            Err(_) => parent.add(key.as_str(), value)?,
+        }
+    }
+
+    parent.add_submodule(&sub)?;
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item(qualified, &sub)?;
+    Ok(())
+}
+
 #[pymodule(gil_used = false)]
-fn _hypern(_py: Python, module: &Bound<PyModule>) -> PyResult<()> {
-    // Original classes (backwards compatible)
-    module.add_class::<Server>()?;
-    module.add_class::<Route>()?;
-    module.add_class::<Router>()?;
-    module.add_class::<Response>()?;
-
-    // Request handling
-    module.add_class::<Request>()?;
-    module.add_class::<HeaderMap>()?;
-
-    // File uploads
-    module.add_class::<FormData>()?;
-    module.add_class::<UploadedFile>()?;
-
-    // Dependency Injection
-    module.add_class::<Context>()?;
-    module.add_class::<DIContainer>()?;
-
-    // Background Tasks
-    module.add_class::<TaskExecutor>()?;
-    module.add_class::<TaskResult>()?;
-    module.add_class::<TaskStatus>()?;
-
-    // Blocking Executor (GIL-free parallel execution)
-    module.add_class::<BlockingExecutor>()?;
-
-    // HTTP Client
-    module.add_class::<HttpClient>()?;
-    module.add_class::<ClientResponse>()?;
-
-    // Telemetry / Metrics
-    module.add_class::<MetricsRegistry>()?;
-
-    // Redis
-    module.add_class::<RedisPool>()?;
-
-    // gRPC
-    module.add_class::<GrpcConfig>()?;
-    module.add_class::<GrpcServer>()?;
-
-    // Streaming/SSE
-    module.add_class::<SSEEvent>()?;
-    module.add_class::<SSEStream>()?;
-    module.add_class::<SSEGenerator>()?;
-    module.add_class::<StreamingResponse>()?;
-
-    // WebSocket (Rust-backed)
-    module.add_class::<RustWebSocket>()?;
-    module.add_class::<WsMessage>()?;
-    module.add_class::<WsMessageType>()?;
-
-    // Realtime: Channel/Topic
-    module.add_class::<ChannelManager>()?;
-    module.add_class::<ChannelStats>()?;
-    module.add_class::<Subscriber>()?;
-    module.add_class::<TopicMatcher>()?;
-
-    // Realtime: Presence
-    module.add_class::<PresenceTracker>()?;
-    module.add_class::<PresenceInfo>()?;
-    module.add_class::<PresenceDiff>()?;
-
-    // Realtime: Broadcast
-    module.add_class::<RealtimeBroadcast>()?;
-    module.add_class::<BroadcastConfig>()?;
-    module.add_class::<BroadcastStats>()?;
-    module.add_class::<BroadcastSubscriber>()?;
-    module.add_class::<BackpressurePolicy>()?;
-
-    // Realtime: Heartbeat
-    module.add_class::<HeartbeatMonitor>()?;
-    module.add_class::<HeartbeatConfig>()?;
-    module.add_class::<HeartbeatStats>()?;
-
-    // Reload / Health
-    module.add_class::<PyHealthCheck>()?;
-    module.add_class::<PyReloadConfig>()?;
-    module.add_class::<PyReloadManager>()?;
-
-    // Rust Middleware
-    module.add_class::<PyCorsMiddleware>()?;
-    module.add_class::<PyRateLimitMiddleware>()?;
-    module.add_class::<PySecurityHeadersMiddleware>()?;
-    module.add_class::<PyTimeoutMiddleware>()?;
-    module.add_class::<PyCompressionMiddleware>()?;
-    module.add_class::<PyRequestIdMiddleware>()?;
-    module.add_class::<PyLogMiddleware>()?;
-    module.add_class::<PyBasicAuthMiddleware>()?;
-    module.add_class::<PyCircuitBreakerMiddleware>()?;
-    module.add_class::<PyCacheMiddleware>()?;
-
-    // Logging
-    module.add_class::<PyLogConfig>()?;
-
-    // Static file handler
-    module.add_class::<StaticFileHandler>()?;
-
-    // Database
-    module.add_class::<ConnectionPool>()?;
-    module.add_class::<PoolConfig>()?;
-    module.add_class::<PoolStatus>()?;
-    module.add_class::<DbSession>()?;
-    module.add_class::<RowStream>()?;
-    module.add_class::<AnyPool>()?;
-    module.add_function(wrap_pyfunction!(get_db, module)?)?;
-    module.add_function(wrap_pyfunction!(finalize_db, module)?)?;
-    module.add_function(wrap_pyfunction!(finalize_db_all, module)?)?;
-
-    // Utils: string, pagination, crypto, time
-    crate::utils::register_utils(module)?;
+fn _hypern(py: Python, module: &Bound<PyModule>) -> PyResult<()> {
+    register_submodule(py, module, "core", crate::core::register_all)?;
+    register_submodule(py, module, "http", crate::http::register_all)?;
+    register_submodule(py, module, "routing", crate::routing::register_all)?;
+    register_submodule(py, module, "middleware", crate::middleware::register_all)?;
+    register_submodule(py, module, "logging", crate::logging::register_all)?;
+    register_submodule(py, module, "realtime", crate::realtime::register_all)?;
+    register_submodule(py, module, "db", crate::database::register_all)?;
+    register_submodule(py, module, "fast_path", crate::fast_path::register_all)?;
+    register_submodule(py, module, "client", crate::client::register_all)?;
+    register_submodule(py, module, "cache", crate::cache::register_all)?;
+    register_submodule(py, module, "telemetry", crate::telemetry::register_all)?;
+    register_submodule(py, module, "redis", crate::redis::register_all)?;
+    register_submodule(py, module, "grpc", crate::grpc::register_all)?;
+    register_submodule(py, module, "utils", crate::utils::register_utils)?;
 
     Ok(())
 }
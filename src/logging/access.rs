@@ -0,0 +1,226 @@
+//! Access log format strings.
+//!
+//! `LogConfig(format=...)` replaces the colored response line with a line
+//! rendered from a template such as `"{method} {path} {status}"`. The
+//! template is split into literal and placeholder segments once, when the
+//! config is created, so rendering a line is a walk over the segments.
+//! Unknown placeholders are rejected then rather than per request; values
+//! that aren't known for a response are rendered as `-`.
+
+use std::fmt::Write;
+
+use super::LogEntry;
+
+/// Apache combined log format
+pub const COMBINED: &str = "{client_ip} - - [{time}] \"{method} {path} {protocol}\" {status} {bytes_out} \"{referer}\" \"{user_agent}\"";
+/// One short line per response
+pub const SHORT: &str =
+    "{client_ip} {method} {path} {protocol} {status} {bytes_out} - {duration_ms} ms";
+
+const FIELDS: [(&str, Field); 12] = [
+    ("method", Field::Method),
+    ("path", Field::Path),
+    ("status", Field::Status),
+    ("duration_ms", Field::DurationMs),
+    ("bytes_out", Field::BytesOut),
+    ("client_ip", Field::ClientIp),
+    ("request_id", Field::RequestId),
+    ("user_agent", Field::UserAgent),
+    ("referer", Field::Referer),
+    ("host", Field::Host),
+    ("protocol", Field::Protocol),
+    ("time", Field::Time),
+];
+
+/// Request details captured for formatted access lines, beyond the method,
+/// path and status every response entry has
+#[derive(Debug, Clone, Default)]
+pub struct AccessDetails {
+    pub bytes_out: Option<u64>,
+    pub client_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub referer: Option<String>,
+    pub host: Option<String>,
+    /// `HTTP/1.1`, `HTTP/2.0`
+    pub protocol: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Method,
+    Path,
+    Status,
+    DurationMs,
+    BytesOut,
+    ClientIp,
+    RequestId,
+    UserAgent,
+    Referer,
+    Host,
+    Protocol,
+    /// Common log format time, `10/Oct/2026:13:55:36 +0000`
+    Time,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Field(Field),
+}
+
+/// A parsed access log template
+#[derive(Debug, Clone)]
+pub struct AccessFormat {
+    template: String,
+    segments: Vec<Segment>,
+}
+
+impl AccessFormat {
+    /// Parse a template or a preset name (`"combined"`, `"short"`).
+    /// `{{` and `}}` stand for literal braces.
+    pub fn parse(format: &str) -> Result<Self, String> {
+        let template = match format {
+            "combined" => COMBINED,
+            "short" => SHORT,
+            other => other,
+        };
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('{') if name.is_empty() => {
+                                literal.push('{');
+                                break;
+                            }
+                            Some('}') => {
+                                let field = FIELDS
+                                    .iter()
+                                    .find(|(known, _)| *known == name)
+                                    .map(|(_, field)| *field)
+                                    .ok_or_else(|| unknown_placeholder(&name))?;
+                                if !literal.is_empty() {
+                                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                                }
+                                segments.push(Segment::Field(field));
+                                break;
+                            }
+                            Some(c) => name.push(c),
+                            None => {
+                                return Err(format!(
+                                    "unclosed placeholder '{{{}' in log format",
+                                    name
+                                ))
+                            }
+                        }
+                    }
+                }
+                '}' => {
+                    if chars.next() != Some('}') {
+                        return Err(
+                            "single '}' in log format; write '}}' for a literal brace".to_string()
+                        );
+                    }
+                    literal.push('}');
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self {
+            template: template.to_string(),
+            segments,
+        })
+    }
+
+    /// The template, with a preset name expanded
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    /// Render the line for a response entry
+    pub fn render(&self, entry: &LogEntry) -> String {
+        let mut out = String::with_capacity(self.template.len() + 64);
+        let access = &entry.access;
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => out.push_str(text),
+                Segment::Field(field) => match field {
+                    Field::Method => push_opt(&mut out, entry.method.as_deref()),
+                    Field::Path => push_opt(&mut out, entry.path.as_deref()),
+                    Field::Status => match entry.status {
+                        Some(status) => {
+                            let _ = write!(out, "{}", status);
+                        }
+                        None => out.push('-'),
+                    },
+                    Field::DurationMs => match entry.duration_ms {
+                        Some(ms) => {
+                            let _ = write!(out, "{:.3}", ms);
+                        }
+                        None => out.push('-'),
+                    },
+                    Field::BytesOut => match access.bytes_out {
+                        Some(bytes) => {
+                            let _ = write!(out, "{}", bytes);
+                        }
+                        None => out.push('-'),
+                    },
+                    Field::ClientIp => push_opt(&mut out, access.client_ip.as_deref()),
+                    Field::RequestId => push_opt(&mut out, entry.request_id.as_deref()),
+                    Field::UserAgent => push_opt(&mut out, access.user_agent.as_deref()),
+                    Field::Referer => push_opt(&mut out, access.referer.as_deref()),
+                    Field::Host => push_opt(&mut out, access.host.as_deref()),
+                    Field::Protocol => push_opt(&mut out, access.protocol),
+                    Field::Time => out.push_str(&clf_time(entry.timestamp)),
+                },
+            }
+        }
+        out
+    }
+}
+
+fn unknown_placeholder(name: &str) -> String {
+    let known: Vec<&str> = FIELDS.iter().map(|(name, _)| *name).collect();
+    format!(
+        "unknown placeholder '{{{}}}' in log format; expected one of {}",
+        name,
+        known.join(", ")
+    )
+}
+
+/// Append a value, `-` when missing or empty. Quotes, backslashes and
+/// control characters are escaped so client-supplied headers can't break
+/// the line apart.
+fn push_opt(out: &mut String, value: Option<&str>) {
+    match value {
+        Some(value) if !value.is_empty() => {
+            for c in value.chars() {
+                match c {
+                    '"' => out.push_str("\\\""),
+                    '\\' => out.push_str("\\\\"),
+                    c if c.is_control() => {
+                        let _ = write!(out, "\\x{:02x}", c as u32);
+                    }
+                    c => out.push(c),
+                }
+            }
+        }
+        _ => out.push('-'),
+    }
+}
+
+fn clf_time(ts: f64) -> String {
+    use chrono::{DateTime, TimeZone, Utc};
+    let dt: DateTime<Utc> = Utc
+        .timestamp_opt(ts as i64, 0)
+        .single()
+        .unwrap_or_else(Utc::now);
+    dt.format("%d/%b/%Y:%H:%M:%S +0000").to_string()
+}
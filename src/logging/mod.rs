@@ -1,11 +1,22 @@
 use crossbeam_channel::{bounded, Sender, Receiver};
 use parking_lot::RwLock;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 use std::fmt;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::http::timing::StageTimings;
+
+pub mod access;
+pub mod redact;
+pub mod sink;
+
+use access::{AccessDetails, AccessFormat};
+use regex::Regex;
+use sink::{Sink, SinkConfig};
+
 // ---------------------------------------------------------------------------
 // Log Level
 // ---------------------------------------------------------------------------
@@ -79,6 +90,10 @@ pub struct LogEntry {
     pub status: Option<u16>,
     pub duration_ms: Option<f64>,
     pub worker_id: Option<usize>,
+    /// Per-stage durations for responses from a handler
+    pub stages: Option<StageTimings>,
+    /// Request details for formatted access lines
+    pub access: AccessDetails,
 }
 
 impl LogEntry {
@@ -97,6 +112,8 @@ impl LogEntry {
             status: None,
             duration_ms: None,
             worker_id: None,
+            stages: None,
+            access: AccessDetails::default(),
         }
     }
 
@@ -129,6 +146,8 @@ impl LogEntry {
             status: None,
             duration_ms: None,
             worker_id: None,
+            stages: None,
+            access: AccessDetails::default(),
         }
     }
 
@@ -153,11 +172,35 @@ impl LogEntry {
             status: Some(status),
             duration_ms: Some(duration_ms),
             worker_id: None,
+            stages: None,
+            access: AccessDetails::default(),
+        }
+    }
+
+    pub fn with_stages(mut self, stages: Option<StageTimings>) -> Self {
+        self.stages = stages;
+        self
+    }
+
+    pub fn with_access(mut self, access: Option<AccessDetails>) -> Self {
+        if let Some(access) = access {
+            self.access = access;
+        }
+        self
+    }
+
+    /// The line written for this entry: the access format for responses
+    /// when one is configured, the colored layout otherwise
+    fn format_line(&self, config: &LogConfig) -> String {
+        match config.format {
+            Some(ref format) if self.target.as_deref() == Some("response") => format.render(self),
+            _ => self.format_colored(config.level <= LogLevel::Debug),
         }
     }
 
     /// Format the log entry as a colored string for terminal output.
-    fn format_colored(&self) -> String {
+    /// Stage timings are included when `detailed` (debug level and below).
+    fn format_colored(&self, detailed: bool) -> String {
         let reset = "\x1b[0m";
         let dim = "\x1b[2m";
         let color = self.level.color_code();
@@ -182,6 +225,10 @@ impl LogEntry {
             let status = self.status.unwrap_or(0);
             let dur = self.duration_ms.unwrap_or(0.0);
             let rid = self.request_id.as_deref().unwrap_or("-");
+            let stages = match self.stages {
+                Some(ref stages) if detailed => format!(" {dim}{}{reset}", stages.summary()),
+                _ => String::new(),
+            };
             let status_color = match status {
                 200..=299 => "\x1b[32m",  // green
                 300..=399 => "\x1b[36m",  // cyan
@@ -190,7 +237,7 @@ impl LogEntry {
                 _ => "\x1b[37m",          // white
             };
             return format!(
-                "{dim}{ts}{reset} {color}{:<5}{reset} \x1b[35m<--{reset} {method} {path} {status_color}{status}{reset} {dim}{dur:.2}ms{reset} {dim}[{rid}]{reset}",
+                "{dim}{ts}{reset} {color}{:<5}{reset} \x1b[35m<--{reset} {method} {path} {status_color}{status}{reset} {dim}{dur:.2}ms{reset}{stages} {dim}[{rid}]{reset}",
                 self.level.as_str(),
             );
         }
@@ -241,6 +288,16 @@ pub struct LogConfig {
     pub queue_size: usize,
     /// Paths to skip logging for (e.g., health check endpoints).
     pub skip_paths: Vec<String>,
+    /// Access line template for responses; None keeps the colored layout.
+    pub format: Option<AccessFormat>,
+    /// Matches replaced with `[REDACTED]` in logged fields.
+    pub redact_patterns: Vec<Regex>,
+    /// Longest logged field, in characters; longer ones end in an ellipsis.
+    pub max_field_len: Option<usize>,
+    /// Headers whose values are never logged (lowercase).
+    pub redact_headers: Vec<String>,
+    /// Collector entries are shipped to; read when the logger starts.
+    pub sink: Option<SinkConfig>,
 }
 
 impl Default for LogConfig {
@@ -257,6 +314,14 @@ impl Default for LogConfig {
                 "/_health/startup".to_string(),
                 "/favicon.ico".to_string(),
             ],
+            format: None,
+            redact_patterns: Vec::new(),
+            max_field_len: None,
+            redact_headers: redact::DEFAULT_HEADERS
+                .iter()
+                .map(|h| h.to_string())
+                .collect(),
+            sink: None,
         }
     }
 }
@@ -277,6 +342,8 @@ struct LogQueueInner {
     sender: Sender<LogEntry>,
     config: Arc<RwLock<LogConfig>>,
     running: Arc<AtomicBool>,
+    /// Disconnected once the consumer has written everything
+    finished: Receiver<()>,
 }
 
 pub struct LogQueue;
@@ -291,12 +358,15 @@ impl LogQueue {
         let queue_size = config.queue_size;
         let (sender, receiver) = bounded::<LogEntry>(queue_size);
         let running = Arc::new(AtomicBool::new(true));
+        let sink = config.sink.clone().map(Sink::new);
         let cfg = Arc::new(RwLock::new(config));
+        let (done, finished) = bounded::<()>(0);
 
         let inner = LogQueueInner {
             sender,
             config: cfg.clone(),
             running: running.clone(),
+            finished,
         };
 
         // Store globally before spawning consumer
@@ -306,7 +376,8 @@ impl LogQueue {
         std::thread::Builder::new()
             .name("hypern-logger".into())
             .spawn(move || {
-                log_consumer(receiver, cfg, running);
+                log_consumer(receiver, cfg, running, sink);
+                drop(done);
             })
             .expect("Failed to spawn logger thread");
     }
@@ -319,15 +390,22 @@ impl LogQueue {
         }
     }
 
-    /// Shut down the log queue, flushing remaining entries.
+    /// Shut down the log queue, flushing remaining entries. With a sink,
+    /// waits up to its flush timeout for the last batch to be shipped.
     pub fn shutdown() {
-        let guard = LOG_QUEUE.read();
-        if let Some(ref inner) = *guard {
-            inner.running.store(false, Ordering::SeqCst);
+        // Dropping the old sender lets the consumer thread exit
+        let Some(inner) = LOG_QUEUE.write().take() else {
+            return;
+        };
+        inner.running.store(false, Ordering::SeqCst);
+        let flush_timeout = inner.config.read().sink.as_ref().map(|s| s.flush_timeout);
+        drop(inner.sender);
+        if let Some(timeout) = flush_timeout {
+            // Draining the queue comes first; allow for it on top of the flush
+            let _ = inner
+                .finished
+                .recv_timeout(timeout + std::time::Duration::from_millis(200));
         }
-        drop(guard);
-        // Drop the old sender so the consumer thread exits
-        *LOG_QUEUE.write() = None;
     }
 
     /// Get a copy of the current log config.
@@ -355,13 +433,16 @@ impl LogQueue {
 
 /// Send a log entry to the queue (non-blocking, drops if full).
 #[inline]
-pub fn log_entry(entry: LogEntry) {
+pub fn log_entry(mut entry: LogEntry) {
     let guard = LOG_QUEUE.read();
     if let Some(ref inner) = *guard {
         let cfg = inner.config.read();
         if entry.level < cfg.level {
             return;
         }
+        if cfg.redacts() {
+            cfg.scrub_entry(&mut entry);
+        }
         drop(cfg);
         // Don't block if queue is full – drop the message
         let _ = inner.sender.try_send(entry);
@@ -391,6 +472,20 @@ pub fn log_request(method: &str, path: &str, request_id: Option<&str>) {
     log_entry(LogEntry::request(method, path, request_id));
 }
 
+/// Whether response lines use an access format, so the request details it
+/// may need are worth capturing
+#[inline]
+pub fn access_format_enabled() -> bool {
+    let guard = LOG_QUEUE.read();
+    match *guard {
+        Some(ref inner) => {
+            let cfg = inner.config.read();
+            cfg.log_response && cfg.format.is_some()
+        }
+        None => false,
+    }
+}
+
 /// Convenience: log a response.
 #[inline]
 pub fn log_response(
@@ -399,6 +494,8 @@ pub fn log_response(
     status: u16,
     duration_ms: f64,
     request_id: Option<&str>,
+    stages: Option<StageTimings>,
+    access: Option<AccessDetails>,
 ) {
     {
         let guard = LOG_QUEUE.read();
@@ -411,41 +508,57 @@ pub fn log_response(
             return;
         }
     }
-    log_entry(LogEntry::response(method, path, status, duration_ms, request_id));
+    log_entry(
+        LogEntry::response(method, path, status, duration_ms, request_id)
+            .with_stages(stages)
+            .with_access(access),
+    );
 }
 
-/// Consumer thread: drains the queue and writes to stderr.
+/// Consumer thread: drains the queue and writes to stderr, or ships to the
+/// sink.
 fn log_consumer(
     receiver: Receiver<LogEntry>,
     config: Arc<RwLock<LogConfig>>,
     running: Arc<AtomicBool>,
+    mut sink: Option<Sink>,
 ) {
     use std::io::Write;
 
     let stderr = std::io::stderr();
+    let write = |entry: LogEntry, sink: &mut Option<Sink>| {
+        let cfg = config.read();
+        if entry.level < cfg.level {
+            return;
+        }
+        if sink.as_ref().is_none_or(Sink::tees_stderr) {
+            let line = entry.format_line(&cfg);
+            let mut handle = stderr.lock();
+            let _ = writeln!(handle, "{}", line);
+        }
+        drop(cfg);
+        if let Some(sink) = sink.as_mut() {
+            sink.push(&entry);
+        }
+    };
 
     while running.load(Ordering::SeqCst) {
-        match receiver.recv_timeout(std::time::Duration::from_millis(100)) {
-            Ok(entry) => {
-                let cfg = config.read();
-                if entry.level >= cfg.level {
-                    let line = entry.format_colored();
-                    let mut handle = stderr.lock();
-                    let _ = writeln!(handle, "{}", line);
-                }
-            }
-            Err(crossbeam_channel::RecvTimeoutError::Timeout) => continue,
+        match receiver.recv_timeout(sink::FLUSH_INTERVAL) {
+            Ok(entry) => write(entry, &mut sink),
+            Err(crossbeam_channel::RecvTimeoutError::Timeout) => {}
             Err(crossbeam_channel::RecvTimeoutError::Disconnected) => break,
         }
+        if let Some(sink) = sink.as_mut() {
+            sink.tick();
+        }
     }
 
     // Flush remaining entries
     for entry in receiver.try_iter() {
-        let cfg = config.read();
-        if entry.level >= cfg.level {
-            let line = entry.format_colored();
-            eprintln!("{}", line);
-        }
+        write(entry, &mut sink);
+    }
+    if let Some(sink) = sink.as_mut() {
+        sink.close();
     }
 }
 
@@ -514,6 +627,25 @@ impl PyLogConfig {
     ///     log_response: Enable logging of outgoing responses (default: true)
     ///     queue_size: Internal log queue capacity (default: 10000)
     ///     skip_paths: Paths to exclude from request/response logging
+    ///     format: Response line template with `{method}`, `{path}`,
+    ///         `{status}`, `{duration_ms}`, `{bytes_out}`, `{client_ip}`,
+    ///         `{request_id}`, `{user_agent}`, `{referer}`, `{host}`,
+    ///         `{protocol}` and `{time}` placeholders, or a preset name,
+    ///         "combined" or "short"; None keeps the colored layout
+    ///     redact_patterns: Regexes whose matches are replaced with
+    ///         "[REDACTED]" in messages, paths, request IDs and logged
+    ///         header values
+    ///     max_field_len: Longest logged field in characters; longer fields
+    ///         are cut and end in an ellipsis (default: no limit)
+    ///     redact_headers: Headers whose values are always masked where
+    ///         headers are logged (default: authorization, cookie,
+    ///         set-cookie, x-api-key)
+    ///     sink: Collector to ship entries to as JSON lines, a dict with
+    ///         "type" ("tcp" or "unix"), "address", and optionally
+    ///         "format" ("json"), "compression" ("none" or "gzip"),
+    ///         "buffer_max_bytes", "reconnect_backoff_ms",
+    ///         "flush_timeout_ms" and "stderr" (also write to stderr);
+    ///         entries go to stderr while it is unreachable
     #[new]
     #[pyo3(signature = (
         level = "info",
@@ -521,25 +653,99 @@ impl PyLogConfig {
         log_response = true,
         queue_size = 10_000,
         skip_paths = None,
+        format = None,
+        redact_patterns = None,
+        max_field_len = None,
+        redact_headers = None,
+        sink = None,
     ))]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         level: &str,
         log_request: bool,
         log_response: bool,
         queue_size: usize,
         skip_paths: Option<Vec<String>>,
-    ) -> Self {
+        format: Option<&str>,
+        redact_patterns: Option<Vec<String>>,
+        max_field_len: Option<usize>,
+        redact_headers: Option<Vec<String>>,
+        sink: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Self> {
         let mut config = LogConfig {
             level: LogLevel::from_str(level),
             log_request,
             log_response,
             queue_size,
+            format: format
+                .map(AccessFormat::parse)
+                .transpose()
+                .map_err(pyo3::exceptions::PyValueError::new_err)?,
+            redact_patterns: redact::compile(&redact_patterns.unwrap_or_default())
+                .map_err(pyo3::exceptions::PyValueError::new_err)?,
+            max_field_len,
+            sink: sink.map(SinkConfig::from_dict).transpose()?,
             ..LogConfig::default()
         };
         if let Some(paths) = skip_paths {
             config.skip_paths = paths;
         }
-        Self { inner: config }
+        if let Some(headers) = redact_headers {
+            config.redact_headers = headers.iter().map(|h| h.to_ascii_lowercase()).collect();
+        }
+        Ok(Self { inner: config })
+    }
+
+    /// Access log lines in Apache combined format, without separate
+    /// request lines.
+    #[staticmethod]
+    pub fn combined() -> Self {
+        Self::preset(access::COMBINED)
+    }
+
+    /// Short access log lines with the duration, without separate request
+    /// lines.
+    #[staticmethod]
+    pub fn short() -> Self {
+        Self::preset(access::SHORT)
+    }
+
+    /// Response line template, None for the colored layout
+    #[getter]
+    pub fn format(&self) -> Option<String> {
+        self.inner.format.as_ref().map(|f| f.template().to_string())
+    }
+
+    /// Redaction regexes, as given
+    #[getter]
+    pub fn redact_patterns(&self) -> Vec<String> {
+        self.inner
+            .redact_patterns
+            .iter()
+            .map(|pattern| pattern.as_str().to_string())
+            .collect()
+    }
+
+    /// Longest logged field in characters, None for no limit
+    #[getter]
+    pub fn max_field_len(&self) -> Option<usize> {
+        self.inner.max_field_len
+    }
+
+    /// Headers whose values are masked
+    #[getter]
+    pub fn redact_headers(&self) -> Vec<String> {
+        self.inner.redact_headers.clone()
+    }
+
+    /// Sink settings with defaults filled in, None when not shipping
+    #[getter]
+    pub fn sink<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        self.inner
+            .sink
+            .as_ref()
+            .map(|sink| sink.to_dict(py))
+            .transpose()
     }
 
     /// Disable all logging.
@@ -552,6 +758,7 @@ impl PyLogConfig {
                 log_response: false,
                 queue_size: 1,
                 skip_paths: vec![],
+                ..LogConfig::default()
             },
         }
     }
@@ -584,10 +791,29 @@ impl PyLogConfig {
 
     fn __repr__(&self) -> String {
         format!(
-            "LogConfig(level='{}', log_request={}, log_response={})",
+            "LogConfig(level='{}', log_request={}, log_response={}, format={:?})",
             self.inner.level.as_str().to_lowercase(),
             self.inner.log_request,
             self.inner.log_response,
+            self.format(),
         )
     }
 }
+
+impl PyLogConfig {
+    fn preset(template: &str) -> Self {
+        Self {
+            inner: LogConfig {
+                log_request: false,
+                format: Some(AccessFormat::parse(template).expect("preset log formats parse")),
+                ..LogConfig::default()
+            },
+        }
+    }
+}
+
+/// Register logging classes.
+pub fn register_all(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyLogConfig>()?;
+    Ok(())
+}
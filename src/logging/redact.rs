@@ -0,0 +1,87 @@
+//! Redaction and truncation of logged fields.
+//!
+//! `LogConfig(redact_patterns=...)` replaces every match of the given
+//! regexes with `[REDACTED]` in the message, path, request ID and the
+//! header values captured for access lines; `max_field_len` cuts each of
+//! those fields to that many characters and appends an ellipsis. Both run
+//! when an entry is queued, and only when configured.
+//!
+//! Header values named in `redact_headers` (authorization, cookie,
+//! set-cookie and x-api-key by default) are masked wherever headers are
+//! logged, whether or not patterns are set.
+
+use regex::Regex;
+use std::borrow::Cow;
+
+use super::{LogConfig, LogEntry};
+
+/// Replacement for redacted text and masked headers
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Headers masked unless `redact_headers` says otherwise
+pub const DEFAULT_HEADERS: [&str; 4] = ["authorization", "cookie", "set-cookie", "x-api-key"];
+
+/// Compile `redact_patterns`
+pub fn compile(patterns: &[String]) -> Result<Vec<Regex>, String> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            Regex::new(pattern).map_err(|e| format!("Invalid redact pattern '{}': {}", pattern, e))
+        })
+        .collect()
+}
+
+impl LogConfig {
+    /// Whether queued entries need scrubbing
+    #[inline]
+    pub fn redacts(&self) -> bool {
+        !self.redact_patterns.is_empty() || self.max_field_len.is_some()
+    }
+
+    /// Apply the patterns, then the length limit, to one field
+    pub fn scrub(&self, field: &mut String) {
+        for pattern in &self.redact_patterns {
+            if let Cow::Owned(replaced) = pattern.replace_all(field, REDACTED) {
+                *field = replaced;
+            }
+        }
+        if let Some(max) = self.max_field_len {
+            if let Some((cut, _)) = field.char_indices().nth(max) {
+                field.truncate(cut);
+                field.push('…');
+            }
+        }
+    }
+
+    fn scrub_opt(&self, field: &mut Option<String>) {
+        if let Some(field) = field {
+            self.scrub(field);
+        }
+    }
+
+    /// Scrub every free-text field of an entry
+    pub fn scrub_entry(&self, entry: &mut LogEntry) {
+        self.scrub(&mut entry.message);
+        self.scrub_opt(&mut entry.path);
+        self.scrub_opt(&mut entry.request_id);
+        self.scrub_opt(&mut entry.access.user_agent);
+        self.scrub_opt(&mut entry.access.referer);
+        self.scrub_opt(&mut entry.access.host);
+    }
+
+    /// Whether values of this header are always masked
+    pub fn masks_header(&self, name: &str) -> bool {
+        self.redact_headers
+            .iter()
+            .any(|header| header.eq_ignore_ascii_case(name))
+    }
+
+    /// A header value as it may be logged
+    pub fn header_value<'a>(&self, name: &str, value: &'a str) -> &'a str {
+        if self.masks_header(name) {
+            REDACTED
+        } else {
+            value
+        }
+    }
+}
@@ -0,0 +1,456 @@
+//! Network log sink.
+//!
+//! With `LogConfig(sink={...})` the logger thread ships entries to a
+//! collector as newline-delimited JSON over TCP or a unix socket, instead of
+//! (or, with `"stderr": True`, as well as) writing them to stderr. Entries
+//! are batched and written when a batch reaches `buffer_max_bytes` or every
+//! [`FLUSH_INTERVAL`]; with `"compression": "gzip"` each batch is one gzip
+//! member, so the stream as a whole decompresses as one gzip file.
+//!
+//! Only the logger thread talks to the collector, so producers never wait on
+//! it. While the collector is unreachable, batches go to stderr instead,
+//! with a warning at most every [`WARN_INTERVAL`], and the connection is
+//! retried after `reconnect_backoff_ms`, doubling up to [`MAX_BACKOFF`].
+//! A batch whose write fails is written to stderr as well, so entries are
+//! shipped at least once, never dropped. On shutdown the last batch is
+//! flushed within `flush_timeout_ms`.
+
+use flate2::write::GzEncoder;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use super::{format_timestamp, LogEntry, LogLevel};
+
+/// Longest a batch waits before it is written
+pub const FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+/// Minimum time between two "sink unavailable" warnings
+const WARN_INTERVAL: Duration = Duration::from_secs(10);
+/// Upper bound of the reconnect backoff
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+/// Bound on connecting to and writing a batch to the collector
+const IO_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Where and how entries are shipped
+#[derive(Debug, Clone, PartialEq)]
+pub struct SinkConfig {
+    pub address: SinkAddress,
+    pub gzip: bool,
+    /// Batch size that triggers a write
+    pub buffer_max_bytes: usize,
+    /// Wait before the first reconnect attempt
+    pub reconnect_backoff: Duration,
+    /// Bound on flushing the last batch at shutdown
+    pub flush_timeout: Duration,
+    /// Write the usual stderr lines too
+    pub stderr: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SinkAddress {
+    Tcp(String),
+    #[cfg(unix)]
+    Unix(std::path::PathBuf),
+}
+
+impl std::fmt::Display for SinkAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tcp(address) => write!(f, "tcp://{}", address),
+            #[cfg(unix)]
+            Self::Unix(path) => write!(f, "unix://{}", path.display()),
+        }
+    }
+}
+
+impl SinkConfig {
+    /// Parse the `sink` dict of `LogConfig`
+    pub fn from_dict(spec: &Bound<'_, PyDict>) -> PyResult<Self> {
+        const KEYS: [&str; 8] = [
+            "type",
+            "address",
+            "format",
+            "compression",
+            "buffer_max_bytes",
+            "reconnect_backoff_ms",
+            "flush_timeout_ms",
+            "stderr",
+        ];
+        for key in spec.keys() {
+            let key: String = key.extract()?;
+            if !KEYS.contains(&key.as_str()) {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown log sink option '{}'; expected one of {}",
+                    key,
+                    KEYS.join(", ")
+                )));
+            }
+        }
+        let get = |key: &str| spec.get_item(key);
+        let address: String = get("address")?
+            .ok_or_else(|| PyValueError::new_err("Log sink requires an address"))?
+            .extract()?;
+        let kind: String = get("type")?
+            .ok_or_else(|| PyValueError::new_err("Log sink requires a type, 'tcp' or 'unix'"))?
+            .extract()?;
+        let address = match kind.as_str() {
+            "tcp" => SinkAddress::Tcp(address),
+            #[cfg(unix)]
+            "unix" => SinkAddress::Unix(address.into()),
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown log sink type '{}'; expected 'tcp' or 'unix'",
+                    other
+                )))
+            }
+        };
+        if let Some(format) = get("format")? {
+            let format: String = format.extract()?;
+            if format != "json" {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown log sink format '{}'; expected 'json'",
+                    format
+                )));
+            }
+        }
+        let gzip = match get("compression")? {
+            None => false,
+            Some(compression) => match compression.extract::<String>()?.as_str() {
+                "none" => false,
+                "gzip" => true,
+                other => {
+                    return Err(PyValueError::new_err(format!(
+                        "Unknown log sink compression '{}'; expected 'none' or 'gzip'",
+                        other
+                    )))
+                }
+            },
+        };
+        let millis = |key: &str, default: u64| -> PyResult<Duration> {
+            let ms = get(key)?.map(|v| v.extract::<u64>()).transpose()?;
+            Ok(Duration::from_millis(ms.unwrap_or(default)))
+        };
+        let buffer_max_bytes = get("buffer_max_bytes")?
+            .map(|v| v.extract::<usize>())
+            .transpose()?
+            .unwrap_or(64 * 1024);
+        if buffer_max_bytes == 0 {
+            return Err(PyValueError::new_err(
+                "Log sink buffer_max_bytes must be positive",
+            ));
+        }
+        Ok(Self {
+            address,
+            gzip,
+            buffer_max_bytes,
+            reconnect_backoff: millis("reconnect_backoff_ms", 500)?.max(Duration::from_millis(1)),
+            flush_timeout: millis("flush_timeout_ms", 2000)?,
+            stderr: get("stderr")?
+                .map(|v| v.extract::<bool>())
+                .transpose()?
+                .unwrap_or(false),
+        })
+    }
+
+    /// The `sink` dict, with defaults filled in
+    pub fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        match &self.address {
+            SinkAddress::Tcp(address) => {
+                dict.set_item("type", "tcp")?;
+                dict.set_item("address", address)?;
+            }
+            #[cfg(unix)]
+            SinkAddress::Unix(path) => {
+                dict.set_item("type", "unix")?;
+                dict.set_item("address", path.to_string_lossy())?;
+            }
+        }
+        dict.set_item("format", "json")?;
+        dict.set_item("compression", if self.gzip { "gzip" } else { "none" })?;
+        dict.set_item("buffer_max_bytes", self.buffer_max_bytes)?;
+        dict.set_item(
+            "reconnect_backoff_ms",
+            self.reconnect_backoff.as_millis() as u64,
+        )?;
+        dict.set_item("flush_timeout_ms", self.flush_timeout.as_millis() as u64)?;
+        dict.set_item("stderr", self.stderr)?;
+        Ok(dict)
+    }
+}
+
+enum Connection {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixStream),
+}
+
+impl Connection {
+    fn open(address: &SinkAddress) -> std::io::Result<Self> {
+        let connection = match address {
+            SinkAddress::Tcp(address) => {
+                let mut last = None;
+                let mut stream = None;
+                for addr in address.to_socket_addrs()? {
+                    match TcpStream::connect_timeout(&addr, IO_TIMEOUT) {
+                        Ok(connected) => {
+                            stream = Some(connected);
+                            break;
+                        }
+                        Err(err) => last = Some(err),
+                    }
+                }
+                let stream = stream.ok_or_else(|| {
+                    last.unwrap_or_else(|| {
+                        std::io::Error::new(std::io::ErrorKind::NotFound, "no address")
+                    })
+                })?;
+                stream.set_nodelay(true)?;
+                Self::Tcp(stream)
+            }
+            #[cfg(unix)]
+            SinkAddress::Unix(path) => Self::Unix(std::os::unix::net::UnixStream::connect(path)?),
+        };
+        connection.set_write_timeout(IO_TIMEOUT)?;
+        Ok(connection)
+    }
+
+    fn set_write_timeout(&self, timeout: Duration) -> std::io::Result<()> {
+        // A zero timeout is rejected; the deadline has passed anyway
+        let timeout = Some(timeout.max(Duration::from_millis(1)));
+        match self {
+            Self::Tcp(stream) => stream.set_write_timeout(timeout),
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.set_write_timeout(timeout),
+        }
+    }
+
+    /// Whether the collector closed its end
+    fn is_closed(&mut self) -> bool {
+        match self {
+            Self::Tcp(stream) => peer_closed(stream, TcpStream::set_nonblocking),
+            #[cfg(unix)]
+            Self::Unix(stream) => {
+                peer_closed(stream, std::os::unix::net::UnixStream::set_nonblocking)
+            }
+        }
+    }
+
+    fn write_all(&mut self, data: &[u8]) -> std::io::Result<()> {
+        match self {
+            Self::Tcp(stream) => stream.write_all(data).and_then(|_| stream.flush()),
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.write_all(data).and_then(|_| stream.flush()),
+        }
+    }
+}
+
+/// The collector never sends anything, so a readable socket means EOF or an
+/// error
+fn peer_closed<S: Read>(stream: &mut S, nonblocking: fn(&S, bool) -> std::io::Result<()>) -> bool {
+    if nonblocking(stream, true).is_err() {
+        return true;
+    }
+    let mut buf = [0u8; 64];
+    let read = stream.read(&mut buf);
+    let _ = nonblocking(stream, false);
+    match read {
+        Ok(0) => true,
+        Ok(_) => false,
+        Err(err) => err.kind() != std::io::ErrorKind::WouldBlock,
+    }
+}
+
+/// The logger thread's side of the sink
+pub struct Sink {
+    config: SinkConfig,
+    connection: Option<Connection>,
+    /// JSON lines not yet written
+    batch: Vec<u8>,
+    last_flush: Instant,
+    next_attempt: Instant,
+    backoff: Duration,
+    /// Batches are going to stderr
+    falling_back: bool,
+    last_warning: Option<Instant>,
+}
+
+impl Sink {
+    pub fn new(config: SinkConfig) -> Self {
+        let now = Instant::now();
+        Self {
+            backoff: config.reconnect_backoff,
+            batch: Vec::with_capacity(config.buffer_max_bytes.min(1 << 20)),
+            config,
+            connection: None,
+            last_flush: now,
+            next_attempt: now,
+            falling_back: false,
+            last_warning: None,
+        }
+    }
+
+    /// Whether stderr lines are written besides the shipped ones
+    pub fn tees_stderr(&self) -> bool {
+        self.config.stderr
+    }
+
+    /// Queue `entry` for the next batch
+    pub fn push(&mut self, entry: &LogEntry) {
+        // Serializing a map of strings and numbers cannot fail
+        let _ = serde_json::to_writer(&mut self.batch, &entry_json(entry));
+        self.batch.push(b'\n');
+        if self.batch.len() >= self.config.buffer_max_bytes {
+            self.flush(Instant::now() + IO_TIMEOUT);
+        }
+    }
+
+    /// Write the batch once it has waited [`FLUSH_INTERVAL`]
+    pub fn tick(&mut self) {
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush(Instant::now() + IO_TIMEOUT);
+        }
+    }
+
+    /// Write the last batch within the configured flush timeout
+    pub fn close(&mut self) {
+        let deadline = Instant::now() + self.config.flush_timeout;
+        // One more attempt even while backing off: this is the last chance
+        self.next_attempt = Instant::now();
+        self.flush(deadline);
+    }
+
+    fn flush(&mut self, deadline: Instant) {
+        self.last_flush = Instant::now();
+        if self.batch.is_empty() {
+            return;
+        }
+        let batch = std::mem::take(&mut self.batch);
+        let shipped = self.ship(&batch, deadline);
+        match shipped {
+            Ok(()) => {
+                if self.falling_back {
+                    self.falling_back = false;
+                    self.notice(
+                        LogLevel::Info,
+                        format!("Log sink {} reconnected", self.config.address),
+                    );
+                }
+            }
+            Err(err) => {
+                self.connection = None;
+                self.falling_back = true;
+                if self
+                    .last_warning
+                    .is_none_or(|at| at.elapsed() >= WARN_INTERVAL)
+                {
+                    self.last_warning = Some(Instant::now());
+                    self.notice(
+                        LogLevel::Warn,
+                        format!(
+                            "Log sink {} unavailable ({}); writing logs to stderr",
+                            self.config.address, err
+                        ),
+                    );
+                }
+                // With `stderr` on, these entries are there already
+                if !self.config.stderr {
+                    let _ = std::io::stderr().lock().write_all(&batch);
+                }
+            }
+        }
+        // Keep the allocation for the next batch
+        self.batch = batch;
+        self.batch.clear();
+    }
+
+    fn ship(&mut self, batch: &[u8], deadline: Instant) -> std::io::Result<()> {
+        let compressed = match self.config.gzip {
+            true => Some(gzip(batch)?),
+            false => None,
+        };
+        let connection = self.connect()?;
+        connection.set_write_timeout(deadline.saturating_duration_since(Instant::now()))?;
+        connection.write_all(compressed.as_deref().unwrap_or(batch))
+    }
+
+    /// The open connection, reconnecting when the backoff allows
+    fn connect(&mut self) -> std::io::Result<&mut Connection> {
+        if self.connection.as_mut().is_some_and(Connection::is_closed) {
+            self.connection = None;
+        }
+        if self.connection.is_none() {
+            if Instant::now() < self.next_attempt {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotConnected,
+                    "waiting to reconnect",
+                ));
+            }
+            match Connection::open(&self.config.address) {
+                Ok(connection) => {
+                    self.backoff = self.config.reconnect_backoff;
+                    self.connection = Some(connection);
+                }
+                Err(err) => {
+                    self.next_attempt = Instant::now() + self.backoff;
+                    self.backoff =
+                        (self.backoff * 2).min(MAX_BACKOFF.max(self.config.reconnect_backoff));
+                    return Err(err);
+                }
+            }
+        }
+        self.connection
+            .as_mut()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotConnected, "not connected"))
+    }
+
+    /// A line about the sink itself, on stderr
+    fn notice(&self, level: LogLevel, message: String) {
+        let line = LogEntry::new(level, message)
+            .with_target("log_sink")
+            .format_colored(false);
+        eprintln!("{}", line);
+    }
+}
+
+fn gzip(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(
+        Vec::with_capacity(data.len() / 4),
+        flate2::Compression::fast(),
+    );
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+/// One shipped line; fields an entry does not have are left out
+fn entry_json(entry: &LogEntry) -> serde_json::Value {
+    let mut line = serde_json::Map::new();
+    line.insert("time".into(), format_timestamp(entry.timestamp).into());
+    line.insert("level".into(), entry.level.as_str().into());
+    line.insert("message".into(), entry.message.as_str().into());
+    line.insert("pid".into(), std::process::id().into());
+    let optional = [
+        ("target", entry.target.as_deref()),
+        ("request_id", entry.request_id.as_deref()),
+        ("method", entry.method.as_deref()),
+        ("path", entry.path.as_deref()),
+    ];
+    for (key, value) in optional {
+        if let Some(value) = value {
+            line.insert(key.into(), value.into());
+        }
+    }
+    if let Some(status) = entry.status {
+        line.insert("status".into(), status.into());
+    }
+    if let Some(duration_ms) = entry.duration_ms {
+        line.insert("duration_ms".into(), duration_ms.into());
+    }
+    if let Some(worker_id) = entry.worker_id {
+        line.insert("worker_id".into(), worker_id.into());
+    }
+    serde_json::Value::Object(line)
+}
@@ -35,6 +35,11 @@ impl ThreadArena {
         self.arena.alloc_str(s)
     }
 
+    /// Bytes allocated since the last reset
+    pub fn bytes_allocated(&self) -> usize {
+        self.bytes_allocated
+    }
+
     /// Reset the arena, deallocating all memory at once
     pub fn reset(&mut self) {
         self.arena.reset();
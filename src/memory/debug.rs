@@ -0,0 +1,300 @@
+//! Per-request memory accounting for `Server(memory_debug=True)`.
+//!
+//! Each handler dispatch records the pool buffers checked out and arena
+//! bytes allocated on its thread, and the reference counts of the Request
+//! and Response objects handed to it. Once both the handler and the response
+//! body are done, whatever the request still holds is reported: references
+//! the handler kept (it stashed the object somewhere), pool buffers never
+//! returned, database sessions left open and streaming bodies still alive
+//! under its request ID. Suspects are logged at ERROR and kept for
+//! `Server.stats()["leak_suspects"]`.
+//!
+//! With the mode off every hook is one relaxed load of a static.
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::task::{Context, Poll};
+
+use axum::body::{Body, BodyDataStream};
+use bytes::Bytes;
+use dashmap::DashMap;
+use futures_core::Stream;
+use parking_lot::Mutex;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+
+use crate::memory::arena::with_arena;
+
+/// Suspects kept for `stats()`, oldest dropped first
+const MAX_SUSPECTS: usize = 100;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SUSPECTS: Mutex<VecDeque<LeakSuspect>> = Mutex::new(VecDeque::new());
+/// Streaming bodies alive per request ID
+static LIVE_STREAMS: OnceLock<DashMap<Arc<str>, usize>> = OnceLock::new();
+
+thread_local! {
+    /// Audit of the handler running on this thread
+    static CURRENT: RefCell<Option<Arc<RequestAudit>>> = const { RefCell::new(None) };
+    /// Pool buffers (count, bytes) checked out and not yet returned on this thread
+    static POOL_OUT: Cell<(i64, i64)> = const { Cell::new((0, 0)) };
+}
+
+pub fn configure(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+fn live_streams() -> &'static DashMap<Arc<str>, usize> {
+    LIVE_STREAMS.get_or_init(DashMap::new)
+}
+
+/// A pool buffer of `bytes` capacity was handed out
+#[inline]
+pub fn pool_checkout(bytes: usize) {
+    if enabled() {
+        POOL_OUT.with(|out| {
+            let (count, total) = out.get();
+            out.set((count + 1, total + bytes as i64));
+        });
+    }
+}
+
+/// A pool buffer of `bytes` capacity came back
+#[inline]
+pub fn pool_return(bytes: usize) {
+    if enabled() {
+        POOL_OUT.with(|out| {
+            let (count, total) = out.get();
+            out.set((count - 1, total - bytes as i64));
+        });
+    }
+}
+
+/// Counts a streaming body against the request whose handler created it
+pub struct StreamTag {
+    request_id: Arc<str>,
+}
+
+impl StreamTag {
+    /// Tag for a body created by the handler on this thread, if audited
+    pub fn current() -> Option<Self> {
+        if !enabled() {
+            return None;
+        }
+        let request_id = CURRENT.with(|current| {
+            current
+                .borrow()
+                .as_ref()
+                .map(|audit| audit.request_id.clone())
+        })?;
+        *live_streams().entry(request_id.clone()).or_insert(0) += 1;
+        Some(Self { request_id })
+    }
+}
+
+impl Drop for StreamTag {
+    fn drop(&mut self) {
+        live_streams().remove_if_mut(&self.request_id, |_, count| {
+            *count -= 1;
+            *count == 0
+        });
+    }
+}
+
+/// Python objects handed to the handler, with their counts at dispatch
+struct Handles {
+    request: Py<PyAny>,
+    response: Py<PyAny>,
+    refs: (isize, isize),
+    pool: (i64, i64),
+}
+
+/// Accounting for one request; reported when the last reference drops, i.e.
+/// when both the handler and the response body are done
+pub struct RequestAudit {
+    request_id: Arc<str>,
+    path: String,
+    handles: Mutex<Option<Handles>>,
+    findings: Mutex<Findings>,
+}
+
+#[derive(Default)]
+struct Findings {
+    leaks: Vec<String>,
+    arena_bytes: usize,
+    pool_bytes: i64,
+}
+
+impl RequestAudit {
+    pub fn new(request_id: &str, path: &str) -> Arc<Self> {
+        Arc::new(Self {
+            request_id: Arc::from(request_id),
+            path: path.to_string(),
+            handles: Mutex::new(None),
+            findings: Mutex::new(Findings::default()),
+        })
+    }
+
+    /// Record the handler's arguments once its argument tuple holds them
+    pub fn handler_started(
+        self: &Arc<Self>,
+        py: Python<'_>,
+        request: Py<PyAny>,
+        response: Py<PyAny>,
+    ) {
+        let refs = (
+            request.bind(py).get_refcnt(),
+            response.bind(py).get_refcnt(),
+        );
+        *self.handles.lock() = Some(Handles {
+            request,
+            response,
+            refs,
+            pool: POOL_OUT.with(Cell::get),
+        });
+        CURRENT.with(|current| *current.borrow_mut() = Some(self.clone()));
+    }
+
+    /// Compare against the dispatch counts; runs on the handler's thread
+    /// after it returned and before the arena is reset
+    pub fn handler_finished(&self) {
+        CURRENT.with(|current| current.borrow_mut().take());
+        let Some(handles) = self.handles.lock().take() else {
+            return;
+        };
+        let mut findings = self.findings.lock();
+        Python::attach(|py| {
+            let kept = [
+                (
+                    "Request",
+                    handles.request.bind(py).get_refcnt() - handles.refs.0,
+                ),
+                (
+                    "Response",
+                    handles.response.bind(py).get_refcnt() - handles.refs.1,
+                ),
+            ];
+            for (name, delta) in kept {
+                if delta != 0 {
+                    findings
+                        .leaks
+                        .push(format!("{} object refcount {:+}", name, delta));
+                }
+            }
+            drop(handles.request);
+            drop(handles.response);
+        });
+
+        let (count, bytes) = POOL_OUT.with(Cell::get);
+        let (buffers, pool_bytes) = (count - handles.pool.0, bytes - handles.pool.1);
+        findings.pool_bytes = pool_bytes;
+        if buffers != 0 {
+            findings.leaks.push(format!(
+                "{} pool buffers ({} bytes) not returned",
+                buffers,
+                pool_bytes.max(0)
+            ));
+        }
+        findings.arena_bytes = with_arena(|arena| arena.bytes_allocated());
+    }
+}
+
+impl Drop for RequestAudit {
+    fn drop(&mut self) {
+        let findings = std::mem::take(self.findings.get_mut());
+        let mut leaks = findings.leaks;
+        let sessions = crate::database::request_context::open_sessions(&self.request_id);
+        if sessions > 0 {
+            leaks.push(format!("{} database sessions still open", sessions));
+        }
+        let streams = live_streams()
+            .get(&self.request_id)
+            .map_or(0, |count| *count);
+        if streams > 0 {
+            leaks.push(format!("{} streaming bodies still alive", streams));
+        }
+
+        if leaks.is_empty() {
+            crate::hlog_debug!(
+                "Request {} {}: {} arena bytes, {} pool bytes, nothing leaked",
+                self.request_id,
+                self.path,
+                findings.arena_bytes,
+                findings.pool_bytes
+            );
+            return;
+        }
+        crate::hlog_error!(
+            "Possible leak in request {} {}: {}",
+            self.request_id,
+            self.path,
+            leaks.join("; ")
+        );
+        let mut suspects = SUSPECTS.lock();
+        if suspects.len() == MAX_SUSPECTS {
+            suspects.pop_front();
+        }
+        suspects.push_back(LeakSuspect {
+            request_id: self.request_id.to_string(),
+            path: std::mem::take(&mut self.path),
+            leaks,
+            arena_bytes: findings.arena_bytes,
+        });
+    }
+}
+
+struct LeakSuspect {
+    request_id: String,
+    path: String,
+    leaks: Vec<String>,
+    arena_bytes: usize,
+}
+
+/// Recent suspects as dicts with `request_id`, `path`, `leaks` and `arena_bytes`
+pub fn suspects<'py>(py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+    let list = PyList::empty(py);
+    for suspect in SUSPECTS.lock().iter() {
+        let entry = PyDict::new(py);
+        entry.set_item("request_id", &suspect.request_id)?;
+        entry.set_item("path", &suspect.path)?;
+        entry.set_item("leaks", &suspect.leaks)?;
+        entry.set_item("arena_bytes", suspect.arena_bytes)?;
+        list.append(entry)?;
+    }
+    Ok(list)
+}
+
+/// Keep `audit` alive until the response body has been sent or dropped
+pub fn hold_until_sent(
+    response: axum::response::Response,
+    audit: Arc<RequestAudit>,
+) -> axum::response::Response {
+    response.map(|body| {
+        Body::from_stream(AuditedBody {
+            inner: body.into_data_stream(),
+            _audit: audit,
+        })
+    })
+}
+
+/// Fields drop in order, so the body (and any stream tag in it) goes first
+struct AuditedBody {
+    inner: BodyDataStream,
+    _audit: Arc<RequestAudit>,
+}
+
+impl Stream for AuditedBody {
+    type Item = Result<Bytes, axum::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner).poll_next(cx)
+    }
+}
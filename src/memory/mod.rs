@@ -1,5 +1,6 @@
 // Memory management with object pooling and arena allocation
 pub mod arena;
+pub mod debug;
 pub mod pool;
 
 pub use pool::{ObjectPool, RequestPool, ResponsePool};
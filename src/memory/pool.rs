@@ -1,5 +1,7 @@
 use parking_lot::Mutex;
 
+use super::debug;
+
 /// Generic object pool for reusable objects
 pub struct ObjectPool<T> {
     pool: Mutex<Vec<T>>,
@@ -62,10 +64,12 @@ impl RequestPool {
     pub fn get_buffer(&self) -> Vec<u8> {
         let mut buf = self.buffers.get();
         buf.clear();
+        debug::pool_checkout(buf.capacity());
         buf
     }
 
     pub fn return_buffer(&self, buf: Vec<u8>) {
+        debug::pool_return(buf.capacity());
         self.buffers.put(buf);
     }
 }
@@ -93,20 +97,24 @@ impl ResponsePool {
     pub fn get_body_buffer(&self) -> Vec<u8> {
         let mut buf = self.buffers.get();
         buf.clear();
+        debug::pool_checkout(buf.capacity());
         buf
     }
 
     pub fn return_body_buffer(&self, buf: Vec<u8>) {
+        debug::pool_return(buf.capacity());
         self.buffers.put(buf);
     }
 
     pub fn get_header_buffer(&self) -> Vec<(String, String)> {
         let mut buf = self.header_buffers.get();
         buf.clear();
+        debug::pool_checkout(buf.capacity() * std::mem::size_of::<(String, String)>());
         buf
     }
 
     pub fn return_header_buffer(&self, buf: Vec<(String, String)>) {
+        debug::pool_return(buf.capacity() * std::mem::size_of::<(String, String)>());
         self.header_buffers.put(buf);
     }
 }
@@ -5,13 +5,16 @@ use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use arc_swap::ArcSwap;
 use dashmap::DashMap;
 use parking_lot::RwLock;
+use pyo3::prelude::*;
 
-use crate::http::method::HttpMethod;
+use crate::core::live_config::LiveSettings;
+use crate::http::method::{HttpMethod, MethodSet};
 
 use super::chain::{
-    MiddlewareContext, MiddlewareResponse, MiddlewareResult, RustMiddleware, StateValue,
+    priority, MiddlewareContext, MiddlewareResponse, MiddlewareResult, RustMiddleware, StateValue,
 };
 
 /// Configuration for CORS middleware
@@ -29,6 +32,12 @@ pub struct CorsConfig {
     pub allow_credentials: bool,
     /// Max age for preflight cache (in seconds)
     pub max_age: u32,
+    /// Max age overrides as (path prefix, seconds); the longest matching
+    /// prefix wins
+    pub max_age_overrides: Vec<(String, u32)>,
+    /// Answer Private Network Access preflights
+    /// (`Access-Control-Request-Private-Network: true`)
+    pub allow_private_network: bool,
 }
 
 impl Default for CorsConfig {
@@ -51,6 +60,8 @@ impl Default for CorsConfig {
             expose_headers: vec![],
             allow_credentials: false,
             max_age: 86400, // 24 hours
+            max_age_overrides: vec![],
+            allow_private_network: false,
         }
     }
 }
@@ -96,6 +107,16 @@ impl CorsConfig {
         self.max_age = seconds;
         self
     }
+
+    pub fn max_age_for(mut self, path_prefix: impl Into<String>, seconds: u32) -> Self {
+        self.max_age_overrides.push((path_prefix.into(), seconds));
+        self
+    }
+
+    pub fn allow_private_network(mut self, allow: bool) -> Self {
+        self.allow_private_network = allow;
+        self
+    }
 }
 
 /// CORS middleware for handling Cross-Origin Resource Sharing
@@ -128,8 +149,76 @@ impl CorsMiddleware {
             .join(", ")
     }
 
-    fn headers_string(&self) -> String {
-        self.config.allowed_headers.join(", ")
+    fn any_origin(&self) -> bool {
+        self.config.allowed_origins.iter().any(|o| o == "*")
+    }
+
+    /// `Access-Control-Allow-Origin` value; `*` is not allowed with
+    /// credentials, so the origin is reflected instead
+    fn allow_origin_value(&self, origin: &str) -> String {
+        if self.any_origin() && !self.config.allow_credentials {
+            "*".to_string()
+        } else {
+            origin.to_string()
+        }
+    }
+
+    /// `Access-Control-Allow-Headers` value for a preflight.
+    ///
+    /// With credentials a literal `*` means the header named `*`, so a
+    /// wildcard config echoes the requested headers instead.
+    fn allow_headers_value(&self, requested: Option<String>) -> Option<String> {
+        let wildcard = self.config.allowed_headers.iter().any(|h| h == "*");
+        if wildcard && self.config.allow_credentials {
+            requested.filter(|h| !h.trim().is_empty())
+        } else {
+            Some(self.config.allowed_headers.join(", "))
+        }
+    }
+
+    fn max_age_for(&self, path: &str) -> u32 {
+        self.config
+            .max_age_overrides
+            .iter()
+            .filter(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.config.max_age, |(_, seconds)| *seconds)
+    }
+
+    fn preflight_response(&self, ctx: &MiddlewareContext, origin: &str) -> MiddlewareResponse {
+        let mut response = MiddlewareResponse::new(204);
+        let mut push = |name: &str, value: String| response.headers.push((name.to_string(), value));
+
+        push(
+            "Access-Control-Allow-Origin",
+            self.allow_origin_value(origin),
+        );
+        push("Access-Control-Allow-Methods", self.methods_string());
+        if let Some(headers) =
+            self.allow_headers_value(ctx.get_header("access-control-request-headers"))
+        {
+            push("Access-Control-Allow-Headers", headers);
+        }
+        push(
+            "Access-Control-Max-Age",
+            self.max_age_for(&ctx.get_path()).to_string(),
+        );
+        if self.config.allow_credentials {
+            push("Access-Control-Allow-Credentials", "true".to_string());
+        }
+        let private_network = ctx
+            .get_header("access-control-request-private-network")
+            .is_some_and(|v| v.eq_ignore_ascii_case("true"));
+        if private_network && self.config.allow_private_network {
+            push("Access-Control-Allow-Private-Network", "true".to_string());
+        }
+        // The answer depends on these request headers; keep shared caches
+        // from serving it to a different preflight
+        push(
+            "Vary",
+            "Origin, Access-Control-Request-Method, Access-Control-Request-Headers".to_string(),
+        );
+        response
     }
 }
 
@@ -138,6 +227,10 @@ impl RustMiddleware for CorsMiddleware {
         "cors"
     }
 
+    fn priority(&self) -> i32 {
+        priority::CORS
+    }
+
     fn execute<'a>(
         &'a self,
         ctx: &'a MiddlewareContext,
@@ -157,16 +250,19 @@ impl RustMiddleware for CorsMiddleware {
                 ));
             }
 
-            // Add CORS headers to response
-            let allowed_origin = if self.config.allowed_origins.contains(&"*".to_string())
-                && !self.config.allow_credentials
+            // A preflight carries Access-Control-Request-Method; any other
+            // OPTIONS request is an ordinary request for the route
+            if ctx.method == HttpMethod::OPTIONS
+                && ctx.get_header("access-control-request-method").is_some()
             {
-                "*".to_string()
-            } else {
-                origin.clone()
-            };
+                return MiddlewareResult::Response(self.preflight_response(ctx, &origin));
+            }
 
-            ctx.add_response_header("Access-Control-Allow-Origin", allowed_origin);
+            // Add CORS headers to response
+            ctx.add_response_header(
+                "Access-Control-Allow-Origin",
+                self.allow_origin_value(&origin),
+            );
 
             if self.config.allow_credentials {
                 ctx.add_response_header("Access-Control-Allow-Credentials", "true");
@@ -179,40 +275,6 @@ impl RustMiddleware for CorsMiddleware {
                 );
             }
 
-            // Handle preflight OPTIONS request
-            if ctx.method == HttpMethod::OPTIONS {
-                let mut response = MiddlewareResponse::new(204);
-                response.headers.push((
-                    "Access-Control-Allow-Origin".to_string(),
-                    if self.config.allowed_origins.contains(&"*".to_string()) {
-                        "*".to_string()
-                    } else {
-                        origin
-                    },
-                ));
-                response.headers.push((
-                    "Access-Control-Allow-Methods".to_string(),
-                    self.methods_string(),
-                ));
-                response.headers.push((
-                    "Access-Control-Allow-Headers".to_string(),
-                    self.headers_string(),
-                ));
-                response.headers.push((
-                    "Access-Control-Max-Age".to_string(),
-                    self.config.max_age.to_string(),
-                ));
-
-                if self.config.allow_credentials {
-                    response.headers.push((
-                        "Access-Control-Allow-Credentials".to_string(),
-                        "true".to_string(),
-                    ));
-                }
-
-                return MiddlewareResult::Response(response);
-            }
-
             MiddlewareResult::Continue()
         })
     }
@@ -229,6 +291,40 @@ pub enum RateLimitAlgorithm {
     TokenBucket { bucket_size: u32, refill_rate: f64 },
 }
 
+/// How the rate limiter tells clients apart
+#[derive(Clone)]
+pub enum RateLimitKey {
+    /// Client IP from X-Forwarded-For or X-Real-IP
+    Ip,
+    /// Value of a request header, falling back to the IP when it is absent
+    Header(String),
+    /// Authenticated user id (`ctx.state.user_id`), falling back to the IP
+    /// for anonymous requests
+    AuthenticatedUser,
+    /// The parts' keys joined with `|`
+    Composite(Vec<RateLimitKey>),
+    /// Python callable given the `MiddlewareContext`, returning the key or
+    /// None for the IP. Takes the GIL on every request, so it is slower.
+    PythonCallable(Arc<Py<PyAny>>),
+}
+
+/// Limits applied to a class of clients, e.g. premium API keys
+#[derive(Clone, Copy)]
+pub struct RateLimitTier {
+    pub max_requests: u32,
+    pub window: Duration,
+}
+
+/// How a client's tier is chosen
+#[derive(Clone)]
+pub enum RateLimitTierResolver {
+    /// (key prefix, tier name); the longest matching prefix wins
+    Prefix(Vec<(String, String)>),
+    /// Python callable given the context and the client key, returning a
+    /// tier name or None for the default limits
+    PythonCallable(Arc<Py<PyAny>>),
+}
+
 /// Configuration for rate limiting
 #[derive(Clone)]
 pub struct RateLimitConfig {
@@ -238,10 +334,16 @@ pub struct RateLimitConfig {
     pub window: Duration,
     /// Algorithm to use
     pub algorithm: RateLimitAlgorithm,
-    /// Key extractor - how to identify clients (default: IP from X-Forwarded-For or peer)
-    pub key_header: Option<String>,
+    /// How to identify clients (default: IP from X-Forwarded-For or X-Real-IP)
+    pub key: RateLimitKey,
+    /// Named tiers with their own limits
+    pub tiers: HashMap<String, RateLimitTier>,
+    /// Picks the tier for a client; clients without one get the defaults
+    pub tier_resolver: Option<RateLimitTierResolver>,
     /// Skip rate limiting for certain paths
     pub skip_paths: Vec<String>,
+    /// Also count built-in endpoints such as the health probes
+    pub limit_internal: bool,
 }
 
 impl Default for RateLimitConfig {
@@ -250,8 +352,11 @@ impl Default for RateLimitConfig {
             max_requests: 100,
             window: Duration::from_secs(60),
             algorithm: RateLimitAlgorithm::SlidingWindow,
-            key_header: None,
+            key: RateLimitKey::Ip,
+            tiers: HashMap::new(),
+            tier_resolver: None,
             skip_paths: vec!["/health".to_string(), "/metrics".to_string()],
+            limit_internal: false,
         }
     }
 }
@@ -271,7 +376,22 @@ impl RateLimitConfig {
     }
 
     pub fn with_key_header(mut self, header: impl Into<String>) -> Self {
-        self.key_header = Some(header.into());
+        self.key = RateLimitKey::Header(header.into());
+        self
+    }
+
+    pub fn with_key(mut self, key: RateLimitKey) -> Self {
+        self.key = key;
+        self
+    }
+
+    pub fn with_tier(mut self, name: impl Into<String>, tier: RateLimitTier) -> Self {
+        self.tiers.insert(name.into(), tier);
+        self
+    }
+
+    pub fn with_tier_resolver(mut self, resolver: RateLimitTierResolver) -> Self {
+        self.tier_resolver = Some(resolver);
         self
     }
 
@@ -279,6 +399,11 @@ impl RateLimitConfig {
         self.skip_paths.push(path.into());
         self
     }
+
+    pub fn with_limit_internal(mut self, limit_internal: bool) -> Self {
+        self.limit_internal = limit_internal;
+        self
+    }
 }
 
 /// Per-client rate limit state
@@ -302,28 +427,91 @@ impl RateLimitState {
     }
 }
 
+/// Call a key or tier callable, logging failures as a fallback to the default
+fn call_python_key(
+    callable: &Py<PyAny>,
+    ctx: &MiddlewareContext,
+    key: Option<&str>,
+) -> Option<String> {
+    Python::attach(|py| {
+        let ctx = Py::new(py, ctx.clone())?;
+        let result = match key {
+            Some(key) => callable.call1(py, (ctx, key))?,
+            None => callable.call1(py, (ctx,))?,
+        };
+        result.extract::<Option<String>>(py)
+    })
+    .unwrap_or_else(|e| {
+        crate::hlog_warn!("Rate limit callable failed, using the default: {}", e);
+        None
+    })
+}
+
+/// The rate-limit settings `Server.update_config` may change
+#[derive(Clone)]
+struct LiveLimits {
+    limit: RateLimitTier,
+    skip_paths: Vec<String>,
+}
+
 /// Rate limiting middleware
 pub struct RateLimitMiddleware {
     config: RateLimitConfig,
+    /// Default limit and skip paths, read per request so runtime updates
+    /// apply to the next request
+    live: ArcSwap<LiveLimits>,
     clients: DashMap<String, Arc<RateLimitState>>,
 }
 
 impl RateLimitMiddleware {
     pub fn new(config: RateLimitConfig) -> Self {
+        let live = LiveLimits {
+            limit: RateLimitTier {
+                max_requests: config.max_requests,
+                window: config.window,
+            },
+            skip_paths: config.skip_paths.clone(),
+        };
         Self {
             config,
+            live: ArcSwap::from_pointee(live),
             clients: DashMap::new(),
         }
     }
 
+    /// The default limit as currently configured
+    pub fn limit(&self) -> (u32, Duration) {
+        let live = self.live.load();
+        (live.limit.max_requests, live.limit.window)
+    }
+
     fn get_client_key(&self, ctx: &MiddlewareContext) -> String {
-        // Try custom header first
-        if let Some(header) = &self.config.key_header {
-            if let Some(value) = ctx.get_header(header) {
-                return value.clone();
+        self.key_for(&self.config.key, ctx)
+    }
+
+    fn key_for(&self, key: &RateLimitKey, ctx: &MiddlewareContext) -> String {
+        match key {
+            RateLimitKey::Ip => Self::client_ip(ctx),
+            RateLimitKey::Header(header) => ctx
+                .get_header(header)
+                .unwrap_or_else(|| Self::client_ip(ctx)),
+            // Tagged so a user id never shares a bucket with an IP
+            RateLimitKey::AuthenticatedUser => match ctx.user_id() {
+                Some(user_id) => format!("user:{}", user_id),
+                None => Self::client_ip(ctx),
+            },
+            RateLimitKey::Composite(parts) => parts
+                .iter()
+                .map(|part| self.key_for(part, ctx))
+                .collect::<Vec<_>>()
+                .join("|"),
+            RateLimitKey::PythonCallable(callable) => {
+                call_python_key(callable, ctx, None).unwrap_or_else(|| Self::client_ip(ctx))
             }
         }
+    }
 
+    fn client_ip(ctx: &MiddlewareContext) -> String {
         // Try X-Forwarded-For
         if let Some(xff) = ctx.get_header("x-forwarded-for") {
             // Take first IP (client IP)
@@ -344,44 +532,63 @@ impl RateLimitMiddleware {
         "unknown".to_string()
     }
 
-    fn check_fixed_window(&self, state: &RateLimitState) -> (bool, u64) {
+    /// The client's tier, if a resolver names one that is configured
+    fn tier_for(&self, ctx: &MiddlewareContext, key: &str) -> Option<RateLimitTier> {
+        let name = match self.config.tier_resolver.as_ref()? {
+            RateLimitTierResolver::Prefix(prefixes) => prefixes
+                .iter()
+                .filter(|(prefix, _)| key.starts_with(prefix.as_str()))
+                .max_by_key(|(prefix, _)| prefix.len())
+                .map(|(_, name)| name.clone())?,
+            RateLimitTierResolver::PythonCallable(callable) => {
+                call_python_key(callable, ctx, Some(key))?
+            }
+        };
+        let tier = self.config.tiers.get(&name).copied();
+        if tier.is_none() {
+            crate::hlog_warn!("Unknown rate limit tier '{}', using the default", name);
+        }
+        tier
+    }
+
+    fn check_fixed_window(&self, state: &RateLimitState, limit: RateLimitTier) -> (bool, u64) {
         let now = Instant::now();
         let mut window_start = state.window_start.write();
 
         // Check if window has expired
-        if now.duration_since(*window_start) >= self.config.window {
+        if now.duration_since(*window_start) >= limit.window {
             *window_start = now;
             state.count.store(1, Ordering::SeqCst);
-            return (true, self.config.max_requests as u64 - 1);
+            return (true, limit.max_requests as u64 - 1);
         }
 
         let count = state.count.fetch_add(1, Ordering::SeqCst) + 1;
-        let remaining = (self.config.max_requests as u64).saturating_sub(count);
-        (count <= self.config.max_requests as u64, remaining)
+        let remaining = (limit.max_requests as u64).saturating_sub(count);
+        (count <= limit.max_requests as u64, remaining)
     }
 
-    fn check_sliding_window(&self, state: &RateLimitState) -> (bool, u64) {
+    fn check_sliding_window(&self, state: &RateLimitState, limit: RateLimitTier) -> (bool, u64) {
         let now = Instant::now();
         let mut window_start = state.window_start.write();
         let elapsed = now.duration_since(*window_start);
 
-        if elapsed >= self.config.window {
+        if elapsed >= limit.window {
             // Window fully expired, reset
             *window_start = now;
             state.count.store(1, Ordering::SeqCst);
-            return (true, self.config.max_requests as u64 - 1);
+            return (true, limit.max_requests as u64 - 1);
         }
 
         // Calculate weighted count based on position in window
-        let window_ratio = elapsed.as_secs_f64() / self.config.window.as_secs_f64();
+        let window_ratio = elapsed.as_secs_f64() / limit.window.as_secs_f64();
         let prev_count = state.count.load(Ordering::SeqCst);
         let weighted_count = (prev_count as f64 * (1.0 - window_ratio)) as u64;
 
         let new_count = weighted_count + 1;
         state.count.store(new_count, Ordering::SeqCst);
 
-        let remaining = (self.config.max_requests as u64).saturating_sub(new_count);
-        (new_count <= self.config.max_requests as u64, remaining)
+        let remaining = (limit.max_requests as u64).saturating_sub(new_count);
+        (new_count <= limit.max_requests as u64, remaining)
     }
 
     fn check_token_bucket(
@@ -414,6 +621,33 @@ impl RustMiddleware for RateLimitMiddleware {
         "rate_limit"
     }
 
+    fn priority(&self) -> i32 {
+        priority::RATE_LIMIT
+    }
+
+    fn applies_to_internal(&self) -> bool {
+        self.config.limit_internal
+    }
+
+    fn reconfigure(&self, settings: &LiveSettings) {
+        let Some(update) = &settings.rate_limit else {
+            return;
+        };
+        self.live.rcu(|current| {
+            let mut live = LiveLimits::clone(current);
+            if let Some(max_requests) = update.max_requests {
+                live.limit.max_requests = max_requests;
+            }
+            if let Some(window) = update.window {
+                live.limit.window = window;
+            }
+            if let Some(paths) = &update.skip_paths {
+                live.skip_paths = paths.clone();
+            }
+            live
+        });
+    }
+
     fn execute<'a>(
         &'a self,
         ctx: &'a MiddlewareContext,
@@ -421,22 +655,32 @@ impl RustMiddleware for RateLimitMiddleware {
         Box::pin(async move {
             // Skip certain paths
             let path = ctx.get_path();
-            if self.config.skip_paths.iter().any(|p| path.starts_with(p)) {
+            let live = self.live.load();
+            if live.skip_paths.iter().any(|p| path.starts_with(p)) {
                 return MiddlewareResult::Continue();
             }
 
             let client_key = self.get_client_key(ctx);
+            let tier = self.tier_for(ctx, &client_key);
+            let limit = tier.unwrap_or(live.limit);
 
             // Get or create client state
             let state = self
                 .clients
-                .entry(client_key.clone())
+                .entry(client_key)
                 .or_insert_with(|| Arc::new(RateLimitState::new()))
                 .clone();
 
             let (allowed, remaining) = match self.config.algorithm {
-                RateLimitAlgorithm::FixedWindow => self.check_fixed_window(&state),
-                RateLimitAlgorithm::SlidingWindow => self.check_sliding_window(&state),
+                RateLimitAlgorithm::FixedWindow => self.check_fixed_window(&state, limit),
+                RateLimitAlgorithm::SlidingWindow => self.check_sliding_window(&state, limit),
+                // A tier's bucket holds its max_requests, refilled over its window
+                RateLimitAlgorithm::TokenBucket { .. } if tier.is_some() => self
+                    .check_token_bucket(
+                        &state,
+                        limit.max_requests,
+                        limit.max_requests as f64 / limit.window.as_secs_f64(),
+                    ),
                 RateLimitAlgorithm::TokenBucket {
                     bucket_size,
                     refill_rate,
@@ -444,18 +688,15 @@ impl RustMiddleware for RateLimitMiddleware {
             };
 
             // Add rate limit headers
-            ctx.add_response_header("X-RateLimit-Limit", self.config.max_requests.to_string());
+            ctx.add_response_header("X-RateLimit-Limit", limit.max_requests.to_string());
             ctx.add_response_header("X-RateLimit-Remaining", remaining.to_string());
-            ctx.add_response_header(
-                "X-RateLimit-Reset",
-                (self.config.window.as_secs()).to_string(),
-            );
+            ctx.add_response_header("X-RateLimit-Reset", (limit.window.as_secs()).to_string());
 
             if !allowed {
-                ctx.add_response_header("Retry-After", self.config.window.as_secs().to_string());
+                ctx.add_response_header("Retry-After", limit.window.as_secs().to_string());
                 return MiddlewareResult::Response(
                     MiddlewareResponse::too_many_requests("Rate limit exceeded")
-                        .with_header("Retry-After", self.config.window.as_secs().to_string()),
+                        .with_header("Retry-After", limit.window.as_secs().to_string()),
                 );
             }
 
@@ -544,6 +785,10 @@ impl RustMiddleware for LogMiddleware {
         "logger"
     }
 
+    fn priority(&self) -> i32 {
+        priority::LOGGER
+    }
+
     fn execute<'a>(
         &'a self,
         ctx: &'a MiddlewareContext,
@@ -563,9 +808,15 @@ impl RustMiddleware for LogMiddleware {
             crate::logging::log_request(method, &path, Some(request_id));
 
             if self.config.log_headers {
+                let log_config = crate::logging::LogQueue::config().unwrap_or_default();
                 let headers = ctx.headers.read();
                 for (key, value) in headers.iter() {
-                    crate::hlog_debug!("[{}] Header: {}={}", request_id, key, value);
+                    crate::hlog_debug!(
+                        "[{}] Header: {}={}",
+                        request_id,
+                        key,
+                        log_config.header_value(key, value)
+                    );
                 }
             }
 
@@ -596,6 +847,10 @@ impl RustMiddleware for LogAfterMiddleware {
         "logger_after"
     }
 
+    fn priority(&self) -> i32 {
+        priority::LOGGER_AFTER
+    }
+
     fn execute<'a>(
         &'a self,
         ctx: &'a MiddlewareContext,
@@ -658,6 +913,10 @@ impl RustMiddleware for RequestIdMiddleware {
         "request_id"
     }
 
+    fn priority(&self) -> i32 {
+        priority::REQUEST_ID
+    }
+
     fn execute<'a>(
         &'a self,
         ctx: &'a MiddlewareContext,
@@ -674,7 +933,7 @@ impl RustMiddleware for RequestIdMiddleware {
             ctx.add_response_header(&self.header_name, &request_id);
 
             // Store in state for other middleware/handlers
-            ctx.set_state("request_id", StateValue::String(request_id));
+            ctx.set_visible_state("request_id", StateValue::String(request_id));
 
             MiddlewareResult::Continue()
         })
@@ -760,6 +1019,10 @@ impl RustMiddleware for SecurityHeadersMiddleware {
         "security_headers"
     }
 
+    fn priority(&self) -> i32 {
+        priority::SECURITY_HEADERS
+    }
+
     fn execute<'a>(
         &'a self,
         ctx: &'a MiddlewareContext,
@@ -822,6 +1085,10 @@ impl RustMiddleware for TimeoutMiddleware {
         "timeout"
     }
 
+    fn priority(&self) -> i32 {
+        priority::TIMEOUT
+    }
+
     fn execute<'a>(
         &'a self,
         ctx: &'a MiddlewareContext,
@@ -834,7 +1101,7 @@ impl RustMiddleware for TimeoutMiddleware {
                 "request_deadline",
                 StateValue::Int(deadline.elapsed().as_nanos() as i64),
             );
-            ctx.set_state(
+            ctx.set_visible_state(
                 "request_timeout_ms",
                 StateValue::Int(timeout.as_millis() as i64),
             );
@@ -887,6 +1154,10 @@ impl RustMiddleware for CompressionMiddleware {
         "compression"
     }
 
+    fn priority(&self) -> i32 {
+        priority::COMPRESSION
+    }
+
     fn execute<'a>(
         &'a self,
         ctx: &'a MiddlewareContext,
@@ -901,11 +1172,11 @@ impl RustMiddleware for CompressionMiddleware {
 
             // Store compression preference in state
             if supports_br {
-                ctx.set_state("compression", StateValue::String("br".to_string()));
+                ctx.set_visible_state("compression", StateValue::String("br".to_string()));
             } else if supports_gzip {
-                ctx.set_state("compression", StateValue::String("gzip".to_string()));
+                ctx.set_visible_state("compression", StateValue::String("gzip".to_string()));
             } else if supports_deflate {
-                ctx.set_state("compression", StateValue::String("deflate".to_string()));
+                ctx.set_visible_state("compression", StateValue::String("deflate".to_string()));
             }
 
             ctx.set_state(
@@ -924,6 +1195,11 @@ pub struct BasicAuthMiddleware {
     credentials: HashMap<String, String>,
     /// Realm for WWW-Authenticate header
     realm: String,
+    /// Username -> roles granted on successful authentication
+    roles: HashMap<String, Vec<String>>,
+    /// Let requests without credentials through unauthenticated, leaving
+    /// the decision to the route's `auth` requirement
+    optional: bool,
 }
 
 impl BasicAuthMiddleware {
@@ -931,6 +1207,8 @@ impl BasicAuthMiddleware {
         Self {
             credentials: HashMap::new(),
             realm: realm.into(),
+            roles: HashMap::new(),
+            optional: false,
         }
     }
 
@@ -939,6 +1217,20 @@ impl BasicAuthMiddleware {
         self
     }
 
+    pub fn with_roles(mut self, username: impl Into<String>, roles: Vec<String>) -> Self {
+        self.roles.insert(username.into(), roles);
+        self
+    }
+
+    pub fn optional(mut self, optional: bool) -> Self {
+        self.optional = optional;
+        self
+    }
+
+    fn challenge(&self) -> String {
+        format!("Basic realm=\"{}\"", self.realm)
+    }
+
     fn decode_basic_auth(&self, header: &str) -> Option<(String, String)> {
         let encoded = header.strip_prefix("Basic ")?;
         let decoded = base64_decode(encoded)?;
@@ -979,6 +1271,18 @@ impl RustMiddleware for BasicAuthMiddleware {
         "basic_auth"
     }
 
+    fn priority(&self) -> i32 {
+        priority::AUTH
+    }
+
+    fn is_critical(&self) -> bool {
+        true
+    }
+
+    fn runs_before_body(&self) -> bool {
+        true
+    }
+
     fn execute<'a>(
         &'a self,
         ctx: &'a MiddlewareContext,
@@ -986,12 +1290,15 @@ impl RustMiddleware for BasicAuthMiddleware {
         Box::pin(async move {
             let auth_header = match ctx.get_header("authorization") {
                 Some(h) => h,
+                None if self.optional => {
+                    // Routes requiring auth answer 401 with this challenge
+                    ctx.set_state("auth_challenge", StateValue::String(self.challenge()));
+                    return MiddlewareResult::Continue();
+                }
                 None => {
                     return MiddlewareResult::Response(
-                        MiddlewareResponse::unauthorized("Authentication required").with_header(
-                            "WWW-Authenticate",
-                            format!("Basic realm=\"{}\"", self.realm),
-                        ),
+                        MiddlewareResponse::unauthorized("Authentication required")
+                            .with_header("WWW-Authenticate", self.challenge()),
                     );
                 }
             };
@@ -1008,14 +1315,13 @@ impl RustMiddleware for BasicAuthMiddleware {
             // Check credentials
             match self.credentials.get(&username) {
                 Some(stored_password) if stored_password == &password => {
-                    ctx.set_authenticated(&username, vec![]);
+                    let roles = self.roles.get(&username).cloned().unwrap_or_default();
+                    ctx.set_authenticated(&username, roles);
                     MiddlewareResult::Continue()
                 }
                 _ => MiddlewareResult::Response(
-                    MiddlewareResponse::unauthorized("Invalid credentials").with_header(
-                        "WWW-Authenticate",
-                        format!("Basic realm=\"{}\"", self.realm),
-                    ),
+                    MiddlewareResponse::unauthorized("Invalid credentials")
+                        .with_header("WWW-Authenticate", self.challenge()),
                 ),
             }
         })
@@ -1023,14 +1329,14 @@ impl RustMiddleware for BasicAuthMiddleware {
 }
 
 /// Wrapper that makes any middleware path-specific
-pub struct PathMiddleware<M: RustMiddleware> {
-    inner: M,
+pub struct PathMiddleware<M: RustMiddleware + ?Sized> {
+    inner: Arc<M>,
     paths: Vec<String>,
     exact: bool,
 }
 
-impl<M: RustMiddleware> PathMiddleware<M> {
-    pub fn new(middleware: M, paths: Vec<String>) -> Self {
+impl<M: RustMiddleware + ?Sized> PathMiddleware<M> {
+    pub fn new(middleware: Arc<M>, paths: Vec<String>) -> Self {
         Self {
             inner: middleware,
             paths,
@@ -1044,17 +1350,38 @@ impl<M: RustMiddleware> PathMiddleware<M> {
     }
 }
 
-impl<M: RustMiddleware> RustMiddleware for PathMiddleware<M> {
+impl<M: RustMiddleware + ?Sized> RustMiddleware for PathMiddleware<M> {
     fn name(&self) -> &'static str {
         self.inner.name()
     }
 
+    fn is_critical(&self) -> bool {
+        self.inner.is_critical()
+    }
+
+    fn runs_before_body(&self) -> bool {
+        self.inner.runs_before_body()
+    }
+
+    fn priority(&self) -> i32 {
+        self.inner.priority()
+    }
+
     fn applies_to(&self, path: &str) -> bool {
-        if self.exact {
+        let matched = if self.exact {
             self.paths.iter().any(|p| p == path)
         } else {
             self.paths.iter().any(|p| path.starts_with(p))
-        }
+        };
+        matched && self.inner.applies_to(path)
+    }
+
+    fn applies_to_method(&self, method: HttpMethod) -> bool {
+        self.inner.applies_to_method(method)
+    }
+
+    fn reconfigure(&self, settings: &LiveSettings) {
+        self.inner.reconfigure(settings)
     }
 
     fn execute<'a>(
@@ -1066,13 +1393,13 @@ impl<M: RustMiddleware> RustMiddleware for PathMiddleware<M> {
 }
 
 /// Wrapper that makes any middleware method-specific
-pub struct MethodMiddleware<M: RustMiddleware> {
-    inner: M,
-    methods: Vec<HttpMethod>,
+pub struct MethodMiddleware<M: RustMiddleware + ?Sized> {
+    inner: Arc<M>,
+    methods: MethodSet,
 }
 
-impl<M: RustMiddleware> MethodMiddleware<M> {
-    pub fn new(middleware: M, methods: Vec<HttpMethod>) -> Self {
+impl<M: RustMiddleware + ?Sized> MethodMiddleware<M> {
+    pub fn new(middleware: Arc<M>, methods: MethodSet) -> Self {
         Self {
             inner: middleware,
             methods,
@@ -1080,13 +1407,33 @@ impl<M: RustMiddleware> MethodMiddleware<M> {
     }
 }
 
-impl<M: RustMiddleware> RustMiddleware for MethodMiddleware<M> {
+impl<M: RustMiddleware + ?Sized> RustMiddleware for MethodMiddleware<M> {
     fn name(&self) -> &'static str {
         self.inner.name()
     }
 
+    fn is_critical(&self) -> bool {
+        self.inner.is_critical()
+    }
+
+    fn runs_before_body(&self) -> bool {
+        self.inner.runs_before_body()
+    }
+
+    fn priority(&self) -> i32 {
+        self.inner.priority()
+    }
+
+    fn applies_to(&self, path: &str) -> bool {
+        self.inner.applies_to(path)
+    }
+
     fn applies_to_method(&self, method: HttpMethod) -> bool {
-        self.methods.contains(&method)
+        self.methods.contains(method) && self.inner.applies_to_method(method)
+    }
+
+    fn reconfigure(&self, settings: &LiveSettings) {
+        self.inner.reconfigure(settings)
     }
 
     fn execute<'a>(
@@ -1252,6 +1599,10 @@ impl RustMiddleware for CircuitBreakerMiddleware {
         "circuit_breaker"
     }
 
+    fn priority(&self) -> i32 {
+        priority::CIRCUIT_BREAKER
+    }
+
     fn execute<'a>(
         &'a self,
         ctx: &'a MiddlewareContext,
@@ -1283,7 +1634,7 @@ impl RustMiddleware for CircuitBreakerMiddleware {
                         cb.success_count.store(0, Ordering::SeqCst);
                         drop(state);
                         // Allow the request through in half-open state
-                        ctx.set_state(
+                        ctx.set_visible_state(
                             "circuit_breaker_state",
                             StateValue::String("half_open".to_string()),
                         );
@@ -1300,7 +1651,7 @@ impl RustMiddleware for CircuitBreakerMiddleware {
                 }
                 CircuitState::HalfOpen => {
                     drop(state);
-                    ctx.set_state(
+                    ctx.set_visible_state(
                         "circuit_breaker_state",
                         StateValue::String("half_open".to_string()),
                     );
@@ -1308,7 +1659,7 @@ impl RustMiddleware for CircuitBreakerMiddleware {
                 }
                 CircuitState::Closed => {
                     drop(state);
-                    ctx.set_state(
+                    ctx.set_visible_state(
                         "circuit_breaker_state",
                         StateValue::String("closed".to_string()),
                     );
@@ -1383,6 +1734,10 @@ impl RustMiddleware for CacheMiddleware {
         "cache"
     }
 
+    fn priority(&self) -> i32 {
+        priority::CACHE
+    }
+
     fn execute<'a>(
         &'a self,
         ctx: &'a MiddlewareContext,
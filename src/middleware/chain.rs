@@ -1,13 +1,24 @@
 use pyo3::prelude::*;
-use std::collections::HashMap;
+use pyo3::types::PyDict;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::future::Future;
+use std::panic::AssertUnwindSafe;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use bytes::Bytes;
+use dashmap::DashMap;
+use futures_util::FutureExt;
 use parking_lot::RwLock;
 
+use crate::core::live_config::LiveSettings;
 use crate::http::method::HttpMethod;
+use crate::http::panic::panic_message;
+use crate::http::urlencoded;
+use crate::{hlog_error, hlog_warn};
 
 /// The result of middleware execution
 #[pyclass]
@@ -30,6 +41,8 @@ pub struct MiddlewareResponse {
     pub headers: Vec<(String, String)>,
     #[pyo3(get, set)]
     pub body: Vec<u8>,
+    /// A handler response played back, sent exactly as the handler wrote it
+    pub replayed: bool,
 }
 
 #[pymethods]
@@ -40,6 +53,7 @@ impl MiddlewareResponse {
             status,
             headers: Vec::new(),
             body: Vec::new(),
+            replayed: false,
         }
     }
 
@@ -74,6 +88,20 @@ impl MiddlewareResponse {
 }
 
 impl MiddlewareResponse {
+    /// A handler response played back by a middleware
+    pub fn replay(
+        status: u16,
+        headers: Vec<(String, String)>,
+        body: impl Into<Vec<u8>>,
+    ) -> Self {
+        Self {
+            status,
+            headers,
+            body: body.into(),
+            replayed: true,
+        }
+    }
+
     pub fn with_status(mut self, status: u16) -> Self {
         self.status = status;
         self
@@ -194,11 +222,37 @@ pub struct MiddlewareContext {
     // Response modifications (accumulated by middleware)
     pub response_headers: Arc<RwLock<Vec<(String, String)>>>,
 
+    /// Handler response requested by "after" middleware
+    pub response_capture: Arc<RwLock<ResponseCapture>>,
+
     // Timing information
     pub start_time: std::time::Instant,
 
     // Request ID for tracing
     pub request_id: Arc<str>,
+
+    /// Built-in endpoint (health probe) rather than an app route; only
+    /// middleware with `applies_to_internal` runs for it
+    pub internal: bool,
+}
+
+/// Handler response as seen by "after" middleware
+#[derive(Debug, Clone)]
+pub struct CapturedResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /// None when the body was not buffered: streamed, or over the limit
+    pub body: Option<Bytes>,
+}
+
+/// Whether "after" middleware asked to see the handler response
+#[derive(Debug, Clone, Default)]
+pub enum ResponseCapture {
+    #[default]
+    NotRequested,
+    /// Buffer bodies up to this many bytes
+    Requested(usize),
+    Captured(CapturedResponse),
 }
 
 /// Mutable state that can be set by middleware and read by handlers
@@ -223,6 +277,24 @@ pub struct MiddlewareState {
     /// Trace/correlation ID
     #[pyo3(get, set)]
     pub trace_id: Option<String>,
+
+    /// Keys whose values are copied into the handler's `hypern.context`
+    pub propagate: HashSet<String>,
+
+    /// Keys handlers can read with `request.state()`; propagated keys are
+    /// readable too, everything else stays internal to the middleware chain
+    pub visible: HashSet<String>,
+}
+
+impl MiddlewareState {
+    /// The value of `key` if it was marked handler-visible
+    pub fn visible_value(&self, key: &str) -> Option<&StateValue> {
+        if self.visible.contains(key) || self.propagate.contains(key) {
+            self.values.get(key)
+        } else {
+            None
+        }
+    }
 }
 
 /// A value that can be stored in middleware state
@@ -309,6 +381,33 @@ impl MiddlewareContext {
             .and_then(|qp| qp.get(name).cloned())
     }
 
+    /// Every value of a repeated query parameter, in order.
+    ///
+    /// Read from the current query string; `set_query` and `remove_query`
+    /// do not change it.
+    ///
+    /// Args:
+    ///     name: Parameter name
+    ///     strip_brackets: Also match `name[]` (`?tag[]=a&tag[]=b`)
+    #[pyo3(name = "get_query_list", signature = (name, strip_brackets=true))]
+    pub fn get_query_list_py(&self, name: &str, strip_brackets: bool) -> Vec<String> {
+        self.get_query_list(name, strip_brackets)
+    }
+
+    /// All query parameters as lists of values, keys in the order first seen.
+    ///
+    /// Args:
+    ///     strip_brackets: Group `name[]` keys under `name`
+    #[pyo3(signature = (strip_brackets=true))]
+    pub fn query_params_multi<'py>(
+        &self,
+        py: Python<'py>,
+        strip_brackets: bool,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let pairs = urlencoded::parse(self.query_string.read().as_bytes());
+        urlencoded::grouped_dict(py, &pairs, strip_brackets)
+    }
+
     /// Set a query parameter
     #[pyo3(name = "set_query")]
     pub fn set_query_py(&self, name: String, value: String) {
@@ -376,14 +475,41 @@ impl MiddlewareContext {
     }
 
     /// Set a state value
-    #[pyo3(name = "set_state")]
-    pub fn set_state_py(&self, key: String, value: StateValue) {
+    ///
+    /// With `propagate=True` the value is also exposed to the handler through
+    /// `hypern.current_context()`; with `visible=True` through
+    /// `request.state()` only. Values are internal to the chain otherwise.
+    #[pyo3(name = "set_state", signature = (key, value, propagate=false, visible=false))]
+    pub fn set_state_py(&self, key: String, value: StateValue, propagate: bool, visible: bool) {
         self.ensure_state();
         if let Some(ref mut state) = *self.state.write() {
+            if propagate {
+                state.propagate.insert(key.clone());
+            }
+            if visible {
+                state.visible.insert(key.clone());
+            }
             state.values.insert(key, value);
         }
     }
 
+    /// Let handlers read `key` with `request.state()`
+    pub fn expose_state(&self, key: String) {
+        self.ensure_state();
+        if let Some(ref mut state) = *self.state.write() {
+            state.visible.insert(key);
+        }
+    }
+
+    /// Keep `key` internal to the chain, even if an earlier middleware
+    /// exposed or propagated it
+    pub fn hide_state(&self, key: &str) {
+        if let Some(ref mut state) = *self.state.write() {
+            state.visible.remove(key);
+            state.propagate.remove(key);
+        }
+    }
+
     /// Get a state value
     pub fn get_state(&self, key: &str) -> Option<StateValue> {
         self.state
@@ -453,12 +579,8 @@ impl MiddlewareContext {
         query_string: &str,
         body: Option<Bytes>,
     ) -> Self {
-        use xxhash_rust::xxh3::xxh3_64;
-
-        // Generate request ID using fast hash - avoid format! allocation
         let now = std::time::Instant::now();
-        let id_seed = format!("{}{}{:?}", path, query_string, now);
-        let request_id = format!("{:016x}", xxh3_64(id_seed.as_bytes()));
+        let request_id = crate::core::request_id::next();
 
         // Don't parse query params eagerly - defer to first access
         Self {
@@ -471,8 +593,10 @@ impl MiddlewareContext {
             body: Arc::new(RwLock::new(body)),
             state: Arc::new(RwLock::new(None)), // Lazy - initialized on demand
             response_headers: Arc::new(RwLock::new(Vec::new())),
+            response_capture: Arc::new(RwLock::new(ResponseCapture::NotRequested)),
             start_time: now,
             request_id: Arc::from(request_id),
+            internal: false,
         }
     }
 
@@ -481,13 +605,8 @@ impl MiddlewareContext {
         let mut qp = self.query_params.write();
         if qp.is_none() {
             let qs = self.query_string.read();
-            let parsed: HashMap<String, String> = if qs.is_empty() {
-                HashMap::new()
-            } else {
-                form_urlencoded::parse(qs.as_bytes())
-                    .into_owned()
-                    .collect()
-            };
+            let parsed: HashMap<String, String> =
+                urlencoded::parse(qs.as_bytes()).into_iter().collect();
             *qp = Some(parsed);
         }
     }
@@ -526,6 +645,12 @@ impl MiddlewareContext {
             .and_then(|qp| qp.get(name).cloned())
     }
 
+    /// Every value of a repeated query parameter, in order
+    pub fn get_query_list(&self, name: &str, strip_brackets: bool) -> Vec<String> {
+        let pairs = urlencoded::parse(self.query_string.read().as_bytes());
+        urlencoded::values(&pairs, name, strip_brackets)
+    }
+
     /// Set a query parameter
     pub fn set_query(&self, name: impl Into<String>, value: impl Into<String>) {
         self.ensure_query_params();
@@ -595,6 +720,36 @@ impl MiddlewareContext {
         self.response_headers.read().clone()
     }
 
+    /// Ask for the handler response to be handed to "after" middleware,
+    /// with bodies of up to `max_body` bytes buffered
+    pub fn capture_response(&self, max_body: usize) {
+        let mut capture = self.response_capture.write();
+        match *capture {
+            ResponseCapture::Requested(limit) if limit >= max_body => {}
+            _ => *capture = ResponseCapture::Requested(max_body),
+        }
+    }
+
+    /// Body limit requested through `capture_response`, if any
+    pub fn response_capture_limit(&self) -> Option<usize> {
+        match *self.response_capture.read() {
+            ResponseCapture::Requested(limit) => Some(limit),
+            _ => None,
+        }
+    }
+
+    pub fn set_captured_response(&self, response: CapturedResponse) {
+        *self.response_capture.write() = ResponseCapture::Captured(response);
+    }
+
+    /// The handler response, once captured
+    pub fn captured_response(&self) -> Option<CapturedResponse> {
+        match &*self.response_capture.read() {
+            ResponseCapture::Captured(response) => Some(response.clone()),
+            _ => None,
+        }
+    }
+
     /// Set a state value
     pub fn set_state(&self, key: impl Into<String>, value: StateValue) {
         self.ensure_state();
@@ -603,6 +758,41 @@ impl MiddlewareContext {
         }
     }
 
+    /// Set a state value that is also propagated into the handler context
+    pub fn set_propagated_state(&self, key: impl Into<String>, value: StateValue) {
+        let key = key.into();
+        self.ensure_state();
+        if let Some(ref mut state) = *self.state.write() {
+            state.propagate.insert(key.clone());
+            state.values.insert(key, value);
+        }
+    }
+
+    /// Set a state value that handlers can read with `request.state()`
+    pub fn set_visible_state(&self, key: impl Into<String>, value: StateValue) {
+        let key = key.into();
+        self.ensure_state();
+        if let Some(ref mut state) = *self.state.write() {
+            state.visible.insert(key.clone());
+            state.values.insert(key, value);
+        }
+    }
+
+    /// State values marked for propagation, plus the trace ID if one was set
+    pub fn propagated_state(&self) -> (Vec<(String, StateValue)>, Option<String>) {
+        match self.state.read().as_ref() {
+            Some(state) => (
+                state
+                    .propagate
+                    .iter()
+                    .filter_map(|k| state.values.get(k).map(|v| (k.clone(), v.clone())))
+                    .collect(),
+                state.trace_id.clone(),
+            ),
+            None => (Vec::new(), None),
+        }
+    }
+
     /// Set the user as authenticated
     pub fn set_authenticated(&self, user_id: impl Into<String>, roles: Vec<String>) {
         self.ensure_state();
@@ -656,19 +846,326 @@ pub trait RustMiddleware: Send + Sync {
     fn applies_to_method(&self, _method: HttpMethod) -> bool {
         true
     }
+
+    /// Optional: Also run for built-in endpoints such as the health probes,
+    /// which skip the chain otherwise. Default returns false
+    fn applies_to_internal(&self) -> bool {
+        false
+    }
+
+    /// Optional: Critical middleware (auth, csrf) is never skipped, even when
+    /// the chain isolates errors. Default returns false
+    fn is_critical(&self) -> bool {
+        false
+    }
+
+    /// Optional: Middleware deciding from the request head alone also runs
+    /// before an `Expect: 100-continue` body is sent, so a rejection costs
+    /// the client no upload. Default returns false
+    fn runs_before_body(&self) -> bool {
+        false
+    }
+
+    /// Optional: Position in the chain when registered without an explicit
+    /// priority; lower runs earlier. Default returns `priority::DEFAULT`
+    fn priority(&self) -> i32 {
+        priority::DEFAULT
+    }
+
+    /// Optional: Take settings changed at runtime by `Server.update_config`.
+    /// Default ignores them
+    fn reconfigure(&self, _settings: &LiveSettings) {}
 }
 
 /// A boxed middleware for type erasure
 pub type BoxedMiddleware = Arc<dyn RustMiddleware>;
 
+/// Default priorities of the builtin middleware; lower runs earlier.
+///
+/// Request ids and logging come first so every later rejection carries
+/// them, CORS answers preflights before auth can refuse them, and auth runs
+/// before anything keyed by the authenticated user. Middleware without a
+/// priority of its own sits at `DEFAULT`, after `TIMEOUT` and before `AUTH`.
+pub mod priority {
+    pub const REQUEST_ID: i32 = -500;
+    pub const LOGGER: i32 = -400;
+    pub const SECURITY_HEADERS: i32 = -300;
+    pub const CORS: i32 = -200;
+    pub const TIMEOUT: i32 = -100;
+    pub const DEFAULT: i32 = 0;
+    pub const AUTH: i32 = 100;
+    pub const RATE_LIMIT: i32 = 200;
+    pub const CIRCUIT_BREAKER: i32 = 300;
+    pub const IDEMPOTENCY: i32 = 400;
+    pub const CACHE: i32 = 500;
+    pub const SINGLEFLIGHT: i32 = 550;
+    pub const COMPRESSION: i32 = 600;
+    /// "After" middleware reporting on the finished response
+    pub const LOGGER_AFTER: i32 = 900;
+}
+
+/// Placement of a middleware relative to another one, by `RustMiddleware::name()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Anchor {
+    /// Run before the named middleware
+    Before(String),
+    /// Run after the named middleware
+    After(String),
+}
+
+impl Anchor {
+    fn target(&self) -> &str {
+        match self {
+            Anchor::Before(name) | Anchor::After(name) => name,
+        }
+    }
+}
+
+/// A registered middleware with its ordering constraints
+#[derive(Clone)]
+struct Entry {
+    middleware: BoxedMiddleware,
+    priority: i32,
+    anchors: Vec<Anchor>,
+    /// Registration order, breaking ties between equal priorities
+    seq: usize,
+}
+
+/// One middleware in the resolved order
+#[derive(Debug, Clone)]
+pub struct MiddlewareInfo {
+    pub name: &'static str,
+    pub priority: i32,
+    pub anchors: Vec<Anchor>,
+}
+
+/// Resolved order of a chain, see `MiddlewareChain::describe`
+#[derive(Debug, Clone)]
+pub struct ChainDescription {
+    pub before: Vec<MiddlewareInfo>,
+    pub after: Vec<MiddlewareInfo>,
+    pub error_handlers: usize,
+}
+
+fn describe_entries(entries: &[Entry], order: &[usize]) -> Vec<MiddlewareInfo> {
+    order
+        .iter()
+        .map(|&i| MiddlewareInfo {
+            name: entries[i].middleware.name(),
+            priority: entries[i].priority,
+            anchors: entries[i].anchors.clone(),
+        })
+        .collect()
+}
+
+/// Kahn's algorithm taking the lowest `(priority, seq)` of the ready
+/// entries first. Returns the order and, for entries left out by a cycle,
+/// their count of unplaced predecessors
+fn topological(
+    entries: &[Entry],
+    successors: &[Vec<usize>],
+    predecessors: &[Vec<usize>],
+    priorities: &[i32],
+) -> (Vec<usize>, Vec<usize>) {
+    let mut waiting: Vec<usize> = predecessors.iter().map(Vec::len).collect();
+    let mut ready: BinaryHeap<Reverse<(i32, usize, usize)>> = (0..entries.len())
+        .filter(|&i| waiting[i] == 0)
+        .map(|i| Reverse((priorities[i], entries[i].seq, i)))
+        .collect();
+    let mut order = Vec::with_capacity(entries.len());
+    while let Some(Reverse((_, _, i))) = ready.pop() {
+        order.push(i);
+        for &next in &successors[i] {
+            waiting[next] -= 1;
+            if waiting[next] == 0 {
+                ready.push(Reverse((priorities[next], entries[next].seq, next)));
+            }
+        }
+    }
+    (order, waiting)
+}
+
+/// Execution order of `entries`: anchors are hard constraints, otherwise
+/// lower priority first and registration order for ties
+fn resolve_order(entries: &[Entry]) -> Result<Vec<usize>, String> {
+    let n = entries.len();
+    let mut successors: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for (i, entry) in entries.iter().enumerate() {
+        for anchor in &entry.anchors {
+            let targets: Vec<usize> = (0..n)
+                .filter(|&j| j != i && entries[j].middleware.name() == anchor.target())
+                .collect();
+            if targets.is_empty() {
+                return Err(format!(
+                    "Middleware '{}' is anchored {} '{}', which is not registered",
+                    entry.middleware.name(),
+                    if matches!(anchor, Anchor::Before(_)) {
+                        "before"
+                    } else {
+                        "after"
+                    },
+                    anchor.target()
+                ));
+            }
+            for j in targets {
+                let (first, then) = match anchor {
+                    Anchor::Before(_) => (i, j),
+                    Anchor::After(_) => (j, i),
+                };
+                successors[first].push(then);
+                predecessors[then].push(first);
+            }
+        }
+    }
+
+    let priorities: Vec<i32> = entries.iter().map(|entry| entry.priority).collect();
+    let (order, waiting) = topological(entries, &successors, &predecessors, &priorities);
+    if order.len() == n {
+        // Middleware anchored before another runs as early as that one
+        // would, rather than waiting behind everything of lower priority
+        let mut effective = priorities;
+        for &i in order.iter().rev() {
+            for &next in &successors[i] {
+                effective[i] = effective[i].min(effective[next]);
+            }
+        }
+        return Ok(topological(entries, &successors, &predecessors, &effective).0);
+    }
+
+    // Every node left has an unplaced predecessor; walking them back must
+    // revisit a node, and the walk from its first visit is the cycle
+    let mut path = vec![(0..n).find(|&i| waiting[i] > 0).unwrap_or(0)];
+    loop {
+        let current = *path.last().unwrap_or(&0);
+        let prev = predecessors[current]
+            .iter()
+            .copied()
+            .find(|&p| waiting[p] > 0)
+            .unwrap_or(current);
+        if let Some(start) = path.iter().position(|&i| i == prev) {
+            let mut cycle: Vec<&str> = path[start..]
+                .iter()
+                .rev()
+                .map(|&i| entries[i].middleware.name())
+                .collect();
+            cycle.push(cycle[0]);
+            return Err(format!(
+                "Middleware order has a cycle: {}",
+                cycle.join(" -> ")
+            ));
+        }
+        path.push(prev);
+    }
+}
+
+/// Timing and error counters for a single middleware
+#[derive(Default)]
+pub struct MiddlewareTiming {
+    calls: AtomicU64,
+    errors: AtomicU64,
+    total_us: AtomicU64,
+    max_us: AtomicU64,
+}
+
+/// Point-in-time copy of a middleware's counters
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MiddlewareTimingSnapshot {
+    pub calls: u64,
+    pub errors: u64,
+    pub total_us: u64,
+    pub max_us: u64,
+}
+
+impl MiddlewareTimingSnapshot {
+    /// Average time per call in microseconds
+    pub fn avg_us(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.total_us as f64 / self.calls as f64
+        }
+    }
+}
+
+impl MiddlewareTiming {
+    fn record(&self, elapsed: Duration) {
+        let us = elapsed.as_micros() as u64;
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        self.total_us.fetch_add(us, Ordering::Relaxed);
+        self.max_us.fetch_max(us, Ordering::Relaxed);
+    }
+
+    fn record_error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> MiddlewareTimingSnapshot {
+        MiddlewareTimingSnapshot {
+            calls: self.calls.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            total_us: self.total_us.load(Ordering::Relaxed),
+            max_us: self.max_us.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Per-middleware timing registry, keyed by `RustMiddleware::name()`
+#[derive(Default)]
+pub struct MiddlewareMetrics {
+    timings: DashMap<&'static str, Arc<MiddlewareTiming>>,
+}
+
+impl MiddlewareMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get (or create) the counters for a middleware
+    pub fn timing(&self, name: &'static str) -> Arc<MiddlewareTiming> {
+        if let Some(timing) = self.timings.get(name) {
+            return timing.clone();
+        }
+        self.timings.entry(name).or_default().clone()
+    }
+
+    /// Snapshot all counters, sorted by middleware name
+    pub fn snapshot(&self) -> Vec<(&'static str, MiddlewareTimingSnapshot)> {
+        let mut out: Vec<_> = self
+            .timings
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().snapshot()))
+            .collect();
+        out.sort_by_key(|(name, _)| *name);
+        out
+    }
+
+    /// Clear all counters
+    pub fn reset(&self) {
+        self.timings.clear();
+    }
+}
+
+/// `slow_threshold_us` value with the slow-middleware warning disabled
+const SLOW_THRESHOLD_OFF: u64 = u64::MAX;
+
 /// The middleware chain that executes middleware in order
 pub struct MiddlewareChain {
-    /// Middleware that runs before the handler
-    before: Vec<BoxedMiddleware>,
+    /// Middleware that runs before the handler, in execution order once resolved
+    before: Vec<Entry>,
     /// Middleware that runs after the handler (for response modification)
-    after: Vec<BoxedMiddleware>,
+    after: Vec<Entry>,
     /// Error handling middleware
     error_handlers: Vec<BoxedMiddleware>,
+    /// Per-middleware timing and error counters
+    metrics: Arc<MiddlewareMetrics>,
+    /// Treat panics and 5xx errors in non-critical middleware as Continue
+    isolate_errors: bool,
+    /// Log a warning when a single middleware takes longer than this many
+    /// microseconds (`SLOW_THRESHOLD_OFF` disables); changed at runtime
+    slow_threshold_us: AtomicU64,
+    /// Registrations so far, for stable ordering of equal priorities
+    registered: usize,
 }
 
 impl Default for MiddlewareChain {
@@ -683,6 +1180,10 @@ impl Clone for MiddlewareChain {
             before: self.before.clone(),
             after: self.after.clone(),
             error_handlers: self.error_handlers.clone(),
+            metrics: self.metrics.clone(),
+            isolate_errors: self.isolate_errors,
+            slow_threshold_us: AtomicU64::new(self.slow_threshold_us.load(Ordering::Relaxed)),
+            registered: self.registered,
         }
     }
 }
@@ -693,17 +1194,175 @@ impl MiddlewareChain {
             before: Vec::new(),
             after: Vec::new(),
             error_handlers: Vec::new(),
+            metrics: Arc::new(MiddlewareMetrics::new()),
+            isolate_errors: false,
+            slow_threshold_us: AtomicU64::new(SLOW_THRESHOLD_OFF),
+            registered: 0,
+        }
+    }
+
+    /// Enable or disable error isolation for non-critical middleware
+    pub fn set_isolate_errors(&mut self, isolate: bool) {
+        self.isolate_errors = isolate;
+    }
+
+    /// Set the slow-middleware warning threshold (None disables the warning)
+    pub fn set_slow_threshold(&self, threshold: Option<Duration>) {
+        let micros = threshold.map_or(SLOW_THRESHOLD_OFF, |t| {
+            (t.as_micros() as u64).min(SLOW_THRESHOLD_OFF - 1)
+        });
+        self.slow_threshold_us.store(micros, Ordering::Relaxed);
+    }
+
+    /// The slow-middleware warning threshold, None when disabled
+    pub fn slow_threshold(&self) -> Option<Duration> {
+        match self.slow_threshold_us.load(Ordering::Relaxed) {
+            SLOW_THRESHOLD_OFF => None,
+            micros => Some(Duration::from_micros(micros)),
+        }
+    }
+
+    /// Apply settings changed at runtime to the chain and its middleware
+    pub fn reconfigure(&self, settings: &LiveSettings) {
+        if let Some(threshold_ms) = settings.slow_threshold_ms {
+            self.set_slow_threshold(threshold_ms.map(Duration::from_millis));
+        }
+        for entry in self.before.iter().chain(&self.after) {
+            entry.middleware.reconfigure(settings);
+        }
+    }
+
+    /// Whether error isolation is enabled
+    pub fn isolate_errors(&self) -> bool {
+        self.isolate_errors
+    }
+
+    /// Per-middleware timing and error counters
+    pub fn metrics(&self) -> &Arc<MiddlewareMetrics> {
+        &self.metrics
+    }
+
+    /// Run a single middleware with timing, panic capture and error isolation
+    async fn run_one(&self, middleware: &BoxedMiddleware, ctx: &MiddlewareContext) -> MiddlewareResult {
+        let name = middleware.name();
+        let start = Instant::now();
+        let outcome = AssertUnwindSafe(async { middleware.execute(ctx).await })
+            .catch_unwind()
+            .await;
+        let elapsed = start.elapsed();
+
+        let timing = self.metrics.timing(name);
+        timing.record(elapsed);
+
+        if let Some(threshold) = self.slow_threshold() {
+            if elapsed > threshold {
+                hlog_warn!(
+                    "Slow middleware '{}': {:.2}ms (threshold {}ms)",
+                    name,
+                    elapsed.as_secs_f64() * 1000.0,
+                    threshold.as_millis()
+                );
+            }
+        }
+
+        let skippable = self.isolate_errors && !middleware.is_critical();
+        match outcome {
+            Ok(MiddlewareResult::Error(err)) if err.status >= 500 => {
+                timing.record_error();
+                if skippable {
+                    hlog_error!("Middleware '{}' failed, skipping: {}", name, err.message);
+                    MiddlewareResult::Continue()
+                } else {
+                    MiddlewareResult::Error(err)
+                }
+            }
+            Ok(result) => result,
+            Err(payload) => {
+                timing.record_error();
+                crate::http::panic::count();
+                let message = panic_message(payload.as_ref());
+                if skippable {
+                    hlog_error!("Middleware '{}' panicked, skipping: {}", name, message);
+                    MiddlewareResult::Continue()
+                } else {
+                    hlog_error!("Middleware '{}' panicked: {}", name, message);
+                    MiddlewareResult::Error(MiddlewareError::new(
+                        "middleware_panic".to_string(),
+                        "Internal Server Error".to_string(),
+                        500,
+                    ))
+                }
+            }
+        }
+    }
+
+    fn entry(
+        &mut self,
+        middleware: BoxedMiddleware,
+        priority: Option<i32>,
+        anchors: Vec<Anchor>,
+    ) -> Entry {
+        self.registered += 1;
+        Entry {
+            priority: priority.unwrap_or_else(|| middleware.priority()),
+            middleware,
+            anchors,
+            seq: self.registered,
         }
     }
 
     /// Add middleware that runs before the handler
     pub fn use_before<M: RustMiddleware + 'static>(&mut self, middleware: M) {
-        self.before.push(Arc::new(middleware));
+        self.use_before_anchored(Arc::new(middleware), None, Vec::new());
     }
 
-    /// Add middleware that runs after the handler  
+    /// Add middleware that runs after the handler
     pub fn use_after<M: RustMiddleware + 'static>(&mut self, middleware: M) {
-        self.after.push(Arc::new(middleware));
+        self.use_after_anchored(Arc::new(middleware), None, Vec::new());
+    }
+
+    /// Add middleware that runs before the handler at `priority` instead of
+    /// its default; lower runs earlier
+    pub fn use_before_with_priority<M: RustMiddleware + 'static>(
+        &mut self,
+        middleware: M,
+        priority: i32,
+    ) {
+        self.use_before_anchored(Arc::new(middleware), Some(priority), Vec::new());
+    }
+
+    /// Add middleware that runs after the handler at `priority` instead of
+    /// its default; lower runs earlier
+    pub fn use_after_with_priority<M: RustMiddleware + 'static>(
+        &mut self,
+        middleware: M,
+        priority: i32,
+    ) {
+        self.use_after_anchored(Arc::new(middleware), Some(priority), Vec::new());
+    }
+
+    /// Add boxed middleware before the handler, placed relative to other
+    /// middleware by name. Anchors are checked by `resolve`
+    pub fn use_before_anchored(
+        &mut self,
+        middleware: BoxedMiddleware,
+        priority: Option<i32>,
+        anchors: Vec<Anchor>,
+    ) {
+        let entry = self.entry(middleware, priority, anchors);
+        self.before.push(entry);
+    }
+
+    /// Add boxed middleware after the handler, placed relative to other
+    /// middleware by name. Anchors are checked by `resolve`
+    pub fn use_after_anchored(
+        &mut self,
+        middleware: BoxedMiddleware,
+        priority: Option<i32>,
+        anchors: Vec<Anchor>,
+    ) {
+        let entry = self.entry(middleware, priority, anchors);
+        self.after.push(entry);
     }
 
     /// Add error handling middleware
@@ -713,12 +1372,34 @@ impl MiddlewareChain {
 
     /// Add boxed middleware before handler
     pub fn use_before_boxed(&mut self, middleware: BoxedMiddleware) {
-        self.before.push(middleware);
+        self.use_before_anchored(middleware, None, Vec::new());
     }
 
     /// Add boxed middleware after handler
     pub fn use_after_boxed(&mut self, middleware: BoxedMiddleware) {
-        self.after.push(middleware);
+        self.use_after_anchored(middleware, None, Vec::new());
+    }
+
+    /// Put the middleware in execution order: anchors first, then priority,
+    /// then registration order. Fails on an anchor naming middleware that
+    /// is not registered, or on anchors that contradict each other.
+    /// Until this runs, middleware executes in registration order
+    pub fn resolve(&mut self) -> Result<(), String> {
+        let before = resolve_order(&self.before)?;
+        let after = resolve_order(&self.after)?;
+        self.before = before.iter().map(|&i| self.before[i].clone()).collect();
+        self.after = after.iter().map(|&i| self.after[i].clone()).collect();
+        Ok(())
+    }
+
+    /// The order `resolve` puts the middleware in, with priorities and
+    /// anchors, without changing the chain
+    pub fn describe(&self) -> Result<ChainDescription, String> {
+        Ok(ChainDescription {
+            before: describe_entries(&self.before, &resolve_order(&self.before)?),
+            after: describe_entries(&self.after, &resolve_order(&self.after)?),
+            error_handlers: self.error_handlers.len(),
+        })
     }
 
     /// Execute all "before" middleware in order
@@ -732,13 +1413,32 @@ impl MiddlewareChain {
         let path = ctx.path.read().clone();
         let method = ctx.method;
 
-        for middleware in &self.before {
+        for middleware in self.before.iter().map(|entry| &entry.middleware) {
             // Check if middleware applies to this request
-            if !middleware.applies_to(&path) || !middleware.applies_to_method(method) {
+            if !applies(middleware, ctx, &path, method) {
+                continue;
+            }
+
+            match self.run_one(middleware, ctx).await {
+                MiddlewareResult::Continue() => continue,
+                result => return result,
+            }
+        }
+        MiddlewareResult::Continue()
+    }
+
+    /// Execute the "before" middleware marked `runs_before_body`, for a
+    /// request whose body has not been read yet
+    pub async fn execute_before_body(&self, ctx: &MiddlewareContext) -> MiddlewareResult {
+        let path = ctx.path.read().clone();
+        let method = ctx.method;
+
+        for middleware in self.before.iter().map(|entry| &entry.middleware) {
+            if !middleware.runs_before_body() || !applies(middleware, ctx, &path, method) {
                 continue;
             }
 
-            match middleware.execute(ctx).await {
+            match self.run_one(middleware, ctx).await {
                 MiddlewareResult::Continue() => continue,
                 result => return result,
             }
@@ -755,12 +1455,12 @@ impl MiddlewareChain {
         let path = ctx.path.read().clone();
         let method = ctx.method;
 
-        for middleware in &self.after {
-            if !middleware.applies_to(&path) || !middleware.applies_to_method(method) {
+        for middleware in self.after.iter().map(|entry| &entry.middleware) {
+            if !applies(middleware, ctx, &path, method) {
                 continue;
             }
 
-            match middleware.execute(ctx).await {
+            match self.run_one(middleware, ctx).await {
                 MiddlewareResult::Continue() => continue,
                 result => return result,
             }
@@ -812,6 +1512,16 @@ impl MiddlewareChain {
         )
     }
 
+    /// Names of the "before" middleware in their current order
+    pub fn before_names(&self) -> Vec<&'static str> {
+        self.before.iter().map(|entry| entry.middleware.name()).collect()
+    }
+
+    /// Names of the "after" middleware in their current order
+    pub fn after_names(&self) -> Vec<&'static str> {
+        self.after.iter().map(|entry| entry.middleware.name()).collect()
+    }
+
     /// Check if there are no before middleware (for fast-path optimization)
     #[inline]
     pub fn is_empty_before(&self) -> bool {
@@ -823,6 +1533,26 @@ impl MiddlewareChain {
     pub fn is_empty_after(&self) -> bool {
         self.after.is_empty()
     }
+
+    /// Check if any middleware runs for built-in endpoints
+    pub fn has_internal(&self) -> bool {
+        self.before
+            .iter()
+            .chain(&self.after)
+            .any(|entry| entry.middleware.applies_to_internal())
+    }
+}
+
+/// Whether `middleware` runs for the request in `ctx`
+fn applies(
+    middleware: &BoxedMiddleware,
+    ctx: &MiddlewareContext,
+    path: &str,
+    method: HttpMethod,
+) -> bool {
+    (!ctx.internal || middleware.applies_to_internal())
+        && middleware.applies_to(path)
+        && middleware.applies_to_method(method)
 }
 
 /// Builder pattern for creating middleware chains
@@ -849,6 +1579,26 @@ impl MiddlewareChainBuilder {
         self
     }
 
+    /// Add middleware that runs before handlers at an explicit priority
+    pub fn before_with_priority<M: RustMiddleware + 'static>(
+        mut self,
+        middleware: M,
+        priority: i32,
+    ) -> Self {
+        self.chain.use_before_with_priority(middleware, priority);
+        self
+    }
+
+    /// Add middleware that runs before handlers, placed relative to others
+    pub fn before_anchored<M: RustMiddleware + 'static>(
+        mut self,
+        middleware: M,
+        anchors: Vec<Anchor>,
+    ) -> Self {
+        self.chain.use_before_anchored(Arc::new(middleware), None, anchors);
+        self
+    }
+
     /// Add middleware that runs after handlers
     pub fn after<M: RustMiddleware + 'static>(mut self, middleware: M) -> Self {
         self.chain.use_after(middleware);
@@ -861,8 +1611,21 @@ impl MiddlewareChainBuilder {
         self
     }
 
-    /// Build the chain
-    pub fn build(self) -> MiddlewareChain {
-        self.chain
+    /// Treat failures in non-critical middleware as Continue
+    pub fn isolate_errors(mut self, isolate: bool) -> Self {
+        self.chain.set_isolate_errors(isolate);
+        self
+    }
+
+    /// Warn when a single middleware exceeds the given duration
+    pub fn slow_threshold(self, threshold: Duration) -> Self {
+        self.chain.set_slow_threshold(Some(threshold));
+        self
+    }
+
+    /// Build the chain in resolved order
+    pub fn build(mut self) -> Result<MiddlewareChain, String> {
+        self.chain.resolve()?;
+        Ok(self.chain)
     }
 }
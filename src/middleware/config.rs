@@ -0,0 +1,276 @@
+//! Middleware chain assembled from a declarative description.
+//!
+//! A config is a dict (or the list under its `middleware` key) of entries
+//! such as `{"type": "rate_limit", "max_requests": 100, "paths": ["/api"]}`.
+//! Every key other than the reserved ones below is passed to the builtin's
+//! constructor. All entries are checked before anything is registered, and
+//! every problem found is reported in one `ValueError`.
+
+use std::sync::Arc;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyType};
+
+use crate::http::method::MethodSet;
+
+use super::builtin::PathMiddleware;
+use super::chain::{Anchor, RustMiddleware};
+use super::{
+    builtin_middleware, PyBasicAuthMiddleware, PyCompressionMiddleware, PyCorsMiddleware,
+    PyIdempotencyMiddleware, PyLogMiddleware, PyRateLimitMiddleware, PyRequestIdMiddleware,
+    PySecurityHeadersMiddleware, PySingleflightMiddleware, PyTimeoutMiddleware,
+};
+
+/// Middleware types a config entry may name
+pub const TYPES: [&str; 10] = [
+    "request_id",
+    "log",
+    "security_headers",
+    "cors",
+    "timeout",
+    "basic_auth",
+    "rate_limit",
+    "idempotency",
+    "compression",
+    "singleflight",
+];
+
+/// Entry keys that are not constructor options
+const ENTRY_KEYS: [&str; 8] = [
+    "type", "name", "enabled", "paths", "methods", "priority", "before", "after",
+];
+
+/// Top-level keys of a config dict
+const CONFIG_KEYS: [&str; 3] = ["middleware", "isolate_errors", "slow_threshold_ms"];
+
+/// One enabled entry, built and scoped, ready to register
+pub struct ConfiguredMiddleware {
+    pub middleware: Arc<dyn RustMiddleware>,
+    /// Runs after the handler (idempotency, singleflight)
+    pub recorder: Option<Arc<dyn RustMiddleware>>,
+    pub priority: Option<i32>,
+    pub anchors: Vec<Anchor>,
+    pub methods: Option<MethodSet>,
+}
+
+/// A validated middleware config
+#[derive(Default)]
+pub struct MiddlewareConfig {
+    pub entries: Vec<ConfiguredMiddleware>,
+    pub isolate_errors: Option<bool>,
+    pub slow_threshold_ms: Option<u64>,
+}
+
+impl MiddlewareConfig {
+    /// Build every enabled entry of `config`, a dict or a list of entries.
+    ///
+    /// Raises `ValueError` listing every invalid entry, unknown type and
+    /// rejected option.
+    pub fn from_py(config: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let mut parsed = Self::default();
+        let mut problems = Vec::new();
+
+        let entries = if let Ok(dict) = config.cast::<PyDict>() {
+            for (key, value) in dict.iter() {
+                let key = key.str()?.to_string();
+                match key.as_str() {
+                    "middleware" => {}
+                    "isolate_errors" => match value.extract() {
+                        Ok(flag) => parsed.isolate_errors = Some(flag),
+                        Err(_) => problems.push("isolate_errors: expected a bool".to_string()),
+                    },
+                    "slow_threshold_ms" => match value.extract::<Option<u64>>() {
+                        Ok(ms) => parsed.slow_threshold_ms = ms,
+                        Err(_) => problems
+                            .push("slow_threshold_ms: expected a non-negative integer".to_string()),
+                    },
+                    _ => problems.push(format!(
+                        "unknown key '{}' (expected one of: {})",
+                        key,
+                        CONFIG_KEYS.join(", ")
+                    )),
+                }
+            }
+            dict.get_item("middleware")?
+        } else {
+            Some(config.clone())
+        };
+
+        if let Some(entries) = entries {
+            match entries.cast::<PyList>() {
+                Ok(list) => {
+                    for (index, entry) in list.iter().enumerate() {
+                        if let Some(entry) = parse_entry(index, &entry, &mut problems)? {
+                            parsed.entries.push(entry);
+                        }
+                    }
+                }
+                Err(_) => problems.push("middleware: expected a list of entries".to_string()),
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(parsed)
+        } else {
+            Err(PyValueError::new_err(format!(
+                "Invalid middleware config ({} problem{}):\n  - {}",
+                problems.len(),
+                if problems.len() == 1 { "" } else { "s" },
+                problems.join("\n  - ")
+            )))
+        }
+    }
+}
+
+/// The builtin class a `type` names
+fn middleware_class<'py>(py: Python<'py>, kind: &str) -> Option<Bound<'py, PyType>> {
+    Some(match kind {
+        "request_id" => py.get_type::<PyRequestIdMiddleware>(),
+        "log" => py.get_type::<PyLogMiddleware>(),
+        "security_headers" => py.get_type::<PySecurityHeadersMiddleware>(),
+        "cors" => py.get_type::<PyCorsMiddleware>(),
+        "timeout" => py.get_type::<PyTimeoutMiddleware>(),
+        "basic_auth" => py.get_type::<PyBasicAuthMiddleware>(),
+        "rate_limit" => py.get_type::<PyRateLimitMiddleware>(),
+        "idempotency" => py.get_type::<PyIdempotencyMiddleware>(),
+        "compression" => py.get_type::<PyCompressionMiddleware>(),
+        "singleflight" => py.get_type::<PySingleflightMiddleware>(),
+        _ => return None,
+    })
+}
+
+/// Build one entry, recording its problems. `None` when it is disabled or
+/// invalid.
+fn parse_entry(
+    index: usize,
+    entry: &Bound<'_, PyAny>,
+    problems: &mut Vec<String>,
+) -> PyResult<Option<ConfiguredMiddleware>> {
+    let py = entry.py();
+    let Ok(entry) = entry.cast::<PyDict>() else {
+        problems.push(format!("middleware[{}]: expected a dict", index));
+        return Ok(None);
+    };
+    let kind = match entry.get_item("type")? {
+        Some(kind) => match kind.extract::<String>() {
+            Ok(kind) => kind,
+            Err(_) => {
+                problems.push(format!("middleware[{}]: 'type' must be a string", index));
+                return Ok(None);
+            }
+        },
+        None => {
+            problems.push(format!("middleware[{}]: missing 'type'", index));
+            return Ok(None);
+        }
+    };
+    let label = format!("middleware[{}] ({})", index, kind);
+    let reported = problems.len();
+
+    let Some(class) = middleware_class(py, &kind) else {
+        problems.push(format!(
+            "{}: unknown type '{}' (expected one of: {})",
+            label,
+            kind,
+            TYPES.join(", ")
+        ));
+        return Ok(None);
+    };
+
+    let mut field = |key: &str, expected: &str, ok: bool| {
+        if !ok {
+            problems.push(format!("{}: '{}' must be {}", label, key, expected));
+        }
+    };
+
+    let enabled = match entry.get_item("enabled")? {
+        Some(value) => value.extract::<bool>().ok(),
+        None => Some(true),
+    };
+    field("enabled", "a bool", enabled.is_some());
+    if enabled == Some(false) {
+        return Ok(None);
+    }
+    if let Some(name) = entry.get_item("name")? {
+        field("name", "a string", name.extract::<String>().is_ok());
+    }
+
+    let paths = match entry.get_item("paths")? {
+        Some(value) => {
+            let paths = value
+                .extract::<Vec<String>>()
+                .ok()
+                .filter(|paths| !paths.is_empty() && paths.iter().all(|p| p.starts_with('/')));
+            field(
+                "paths",
+                "a non-empty list of paths starting with '/'",
+                paths.is_some(),
+            );
+            paths
+        }
+        None => None,
+    };
+    let priority = match entry.get_item("priority")? {
+        Some(value) => {
+            let priority = value.extract::<i32>().ok();
+            field("priority", "an integer", priority.is_some());
+            priority
+        }
+        None => None,
+    };
+    let mut anchors = Vec::new();
+    for (key, anchor) in [
+        ("before", Anchor::Before as fn(String) -> Anchor),
+        ("after", Anchor::After),
+    ] {
+        if let Some(value) = entry.get_item(key)? {
+            match value.extract::<String>() {
+                Ok(name) => anchors.push(anchor(name)),
+                Err(_) => field(key, "a middleware name", false),
+            }
+        }
+    }
+    let methods = match entry.get_item("methods")? {
+        Some(value) => match MethodSet::extract(&value) {
+            Ok(methods) => Some(methods),
+            Err(e) => {
+                problems.push(format!("{}: methods: {}", label, e.value(py)));
+                None
+            }
+        },
+        None => None,
+    };
+
+    let options = PyDict::new(py);
+    for (key, value) in entry.iter() {
+        if !ENTRY_KEYS.contains(&key.str()?.to_str()?) {
+            options.set_item(key, value)?;
+        }
+    }
+    let built = match class.call((), Some(&options)) {
+        Ok(instance) => builtin_middleware(&instance),
+        Err(e) => {
+            problems.push(format!("{}: {}", label, e.value(py)));
+            None
+        }
+    };
+
+    if problems.len() > reported {
+        return Ok(None);
+    }
+    let Some((middleware, recorder)) = built else {
+        return Ok(None);
+    };
+    let middleware = match paths {
+        Some(paths) => Arc::new(PathMiddleware::new(middleware, paths)),
+        None => middleware,
+    };
+    Ok(Some(ConfiguredMiddleware {
+        middleware,
+        recorder,
+        priority,
+        anchors,
+        methods,
+    }))
+}
@@ -0,0 +1,348 @@
+//! Idempotency-Key handling.
+//!
+//! A POST or PATCH carrying an `Idempotency-Key` header runs its handler
+//! once; repeats with the same key, route and user get the stored response
+//! back with `Idempotency-Replayed: true`. The "before" half reserves the
+//! key and asks for the handler response; the "after" half
+//! (`IdempotencyRecorder`) stores it.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use dashmap::DashMap;
+
+use crate::http::method::HttpMethod;
+
+use super::chain::{
+    priority, MiddlewareContext, MiddlewareResponse, MiddlewareResult, RustMiddleware, StateValue,
+};
+
+/// State key holding the reserved key between the two halves
+const KEY_STATE: &str = "idempotency_key";
+/// Response headers never stored; they are recomputed on replay
+const SKIPPED_HEADERS: [&str; 4] = ["content-length", "transfer-encoding", "connection", "date"];
+/// How often a waiting duplicate checks whether the first request finished
+const WAIT_POLL: Duration = Duration::from_millis(20);
+
+/// A completed response kept for replay
+#[derive(Debug, Clone)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Bytes,
+}
+
+/// Outcome of reserving a key
+#[derive(Debug, Clone)]
+pub enum Reservation {
+    /// The caller runs the request and must `complete` or `release` the key
+    Acquired,
+    /// A request with this key already completed
+    Completed(StoredResponse),
+    /// A request with this key is still running
+    InFlight,
+}
+
+/// Storage for idempotency keys; in memory by default, shared stores (e.g.
+/// Redis) plug in here
+pub trait IdempotencyStore: Send + Sync {
+    /// Reserve `key`. A reservation older than `in_flight_timeout` is
+    /// treated as abandoned and taken over
+    fn reserve(&self, key: &str, in_flight_timeout: Duration) -> Reservation;
+
+    /// Store the response for a reserved key for `ttl`
+    fn complete(&self, key: &str, response: StoredResponse, ttl: Duration);
+
+    /// Drop a reservation without storing anything, so the key can be retried
+    fn release(&self, key: &str);
+}
+
+enum Entry {
+    InFlight {
+        since: Instant,
+    },
+    Completed {
+        response: StoredResponse,
+        expires: Instant,
+    },
+}
+
+/// In-process store; keys are not shared between worker processes
+#[derive(Default)]
+pub struct MemoryIdempotencyStore {
+    entries: DashMap<String, Entry>,
+    completions: AtomicU64,
+}
+
+impl MemoryIdempotencyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn sweep(&self) {
+        let now = Instant::now();
+        self.entries.retain(|_, entry| match entry {
+            Entry::Completed { expires, .. } => *expires > now,
+            Entry::InFlight { .. } => true,
+        });
+    }
+}
+
+impl IdempotencyStore for MemoryIdempotencyStore {
+    fn reserve(&self, key: &str, in_flight_timeout: Duration) -> Reservation {
+        let now = Instant::now();
+        let mut entry = self
+            .entries
+            .entry(key.to_string())
+            .or_insert(Entry::InFlight { since: now });
+        match &*entry {
+            Entry::InFlight { since } if *since == now => Reservation::Acquired,
+            Entry::InFlight { since } if now.duration_since(*since) < in_flight_timeout => {
+                Reservation::InFlight
+            }
+            Entry::Completed { response, expires } if *expires > now => {
+                Reservation::Completed(response.clone())
+            }
+            // Expired or abandoned
+            _ => {
+                *entry = Entry::InFlight { since: now };
+                Reservation::Acquired
+            }
+        }
+    }
+
+    fn complete(&self, key: &str, response: StoredResponse, ttl: Duration) {
+        self.entries.insert(
+            key.to_string(),
+            Entry::Completed {
+                response,
+                expires: Instant::now() + ttl,
+            },
+        );
+        // Expired entries are otherwise only replaced when their key returns
+        if self.completions.fetch_add(1, Ordering::Relaxed) % 1024 == 1023 {
+            self.sweep();
+        }
+    }
+
+    fn release(&self, key: &str) {
+        self.entries
+            .remove_if(key, |_, entry| matches!(entry, Entry::InFlight { .. }));
+    }
+}
+
+/// What a request does when its key is still in flight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConcurrentPolicy {
+    /// Answer 409 Conflict at once
+    Reject,
+    /// Wait up to this long for the first request, then replay it (or 409)
+    Wait(Duration),
+}
+
+/// Configuration for idempotency middleware
+#[derive(Debug, Clone)]
+pub struct IdempotencyConfig {
+    /// Request header carrying the key
+    pub header_name: String,
+    /// Methods the middleware applies to
+    pub methods: Vec<HttpMethod>,
+    /// How long completed responses are replayed
+    pub ttl: Duration,
+    /// Longest accepted key
+    pub max_key_length: usize,
+    /// Larger (or streamed) responses are passed through without being stored
+    pub max_body_size: usize,
+    /// Handling of duplicates arriving while the first is still running
+    pub concurrent: ConcurrentPolicy,
+    /// After this long a reservation is considered abandoned
+    pub in_flight_timeout: Duration,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            header_name: "idempotency-key".to_string(),
+            methods: vec![HttpMethod::POST, HttpMethod::PATCH],
+            ttl: Duration::from_secs(24 * 60 * 60),
+            max_key_length: 255,
+            max_body_size: 1024 * 1024,
+            concurrent: ConcurrentPolicy::Reject,
+            in_flight_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Replays the stored response for a repeated Idempotency-Key
+pub struct IdempotencyMiddleware {
+    config: IdempotencyConfig,
+    store: Arc<dyn IdempotencyStore>,
+}
+
+impl IdempotencyMiddleware {
+    pub fn new(config: IdempotencyConfig) -> Self {
+        Self::with_store(config, Arc::new(MemoryIdempotencyStore::new()))
+    }
+
+    pub fn with_store(config: IdempotencyConfig, store: Arc<dyn IdempotencyStore>) -> Self {
+        Self { config, store }
+    }
+
+    /// Keys are printable ASCII without spaces
+    fn valid_key(&self, key: &str) -> bool {
+        !key.is_empty()
+            && key.len() <= self.config.max_key_length
+            && key.bytes().all(|b| b.is_ascii_graphic())
+    }
+
+    /// Store key: the same header value is independent per route and user
+    fn store_key(ctx: &MiddlewareContext, key: &str) -> String {
+        format!(
+            "{}\u{0}{} {}\u{0}{}",
+            ctx.user_id().unwrap_or_default(),
+            ctx.method.as_str(),
+            ctx.get_path(),
+            key
+        )
+    }
+
+    fn replay(response: StoredResponse) -> MiddlewareResult {
+        let replayed = MiddlewareResponse::replay(response.status, response.headers, response.body);
+        MiddlewareResult::Response(replayed.with_header("Idempotency-Replayed", "true"))
+    }
+
+    fn conflict() -> MiddlewareResult {
+        MiddlewareResult::Response(
+            MiddlewareResponse::new(409)
+                .with_text_body("A request with this Idempotency-Key is still being processed"),
+        )
+    }
+
+    /// "After" half storing the handler response
+    pub fn recorder(self: &Arc<Self>) -> IdempotencyRecorder {
+        IdempotencyRecorder {
+            inner: self.clone(),
+        }
+    }
+}
+
+impl RustMiddleware for IdempotencyMiddleware {
+    fn name(&self) -> &'static str {
+        "idempotency"
+    }
+
+    fn priority(&self) -> i32 {
+        priority::IDEMPOTENCY
+    }
+
+    fn applies_to_method(&self, method: HttpMethod) -> bool {
+        self.config.methods.contains(&method)
+    }
+
+    fn execute<'a>(
+        &'a self,
+        ctx: &'a MiddlewareContext,
+    ) -> Pin<Box<dyn Future<Output = MiddlewareResult> + Send + 'a>> {
+        Box::pin(async move {
+            let Some(key) = ctx.get_header(&self.config.header_name) else {
+                return MiddlewareResult::Continue();
+            };
+            if !self.valid_key(&key) {
+                return MiddlewareResult::Response(MiddlewareResponse::bad_request(format!(
+                    "Invalid {}: expected 1-{} printable ASCII characters",
+                    self.config.header_name, self.config.max_key_length
+                )));
+            }
+
+            let store_key = Self::store_key(ctx, &key);
+            let deadline = match self.config.concurrent {
+                ConcurrentPolicy::Reject => None,
+                ConcurrentPolicy::Wait(wait) => Some(Instant::now() + wait),
+            };
+            loop {
+                match self
+                    .store
+                    .reserve(&store_key, self.config.in_flight_timeout)
+                {
+                    Reservation::Acquired => break,
+                    Reservation::Completed(response) => return Self::replay(response),
+                    Reservation::InFlight => match deadline {
+                        Some(deadline) if Instant::now() < deadline => {
+                            tokio::time::sleep(WAIT_POLL).await;
+                        }
+                        _ => return Self::conflict(),
+                    },
+                }
+            }
+
+            ctx.set_state(KEY_STATE, StateValue::String(store_key));
+            ctx.capture_response(self.config.max_body_size);
+            MiddlewareResult::Continue()
+        })
+    }
+}
+
+/// Stores responses for keys reserved by `IdempotencyMiddleware`
+pub struct IdempotencyRecorder {
+    inner: Arc<IdempotencyMiddleware>,
+}
+
+impl RustMiddleware for IdempotencyRecorder {
+    fn name(&self) -> &'static str {
+        "idempotency_recorder"
+    }
+
+    fn priority(&self) -> i32 {
+        priority::IDEMPOTENCY
+    }
+
+    fn applies_to_method(&self, method: HttpMethod) -> bool {
+        self.inner.applies_to_method(method)
+    }
+
+    fn execute<'a>(
+        &'a self,
+        ctx: &'a MiddlewareContext,
+    ) -> Pin<Box<dyn Future<Output = MiddlewareResult> + Send + 'a>> {
+        Box::pin(async move {
+            let Some(StateValue::String(key)) = ctx.get_state(KEY_STATE) else {
+                return MiddlewareResult::Continue();
+            };
+            let store = &self.inner.store;
+            match ctx.captured_response() {
+                // Server errors are not stored so the client can retry
+                Some(captured) if captured.status >= 500 => store.release(&key),
+                Some(captured) => match captured.body {
+                    Some(body) => {
+                        let headers = captured
+                            .headers
+                            .into_iter()
+                            .filter(|(name, _)| {
+                                !SKIPPED_HEADERS.iter().any(|s| name.eq_ignore_ascii_case(s))
+                            })
+                            .collect();
+                        let response = StoredResponse {
+                            status: captured.status,
+                            headers,
+                            body,
+                        };
+                        store.complete(&key, response, self.inner.config.ttl);
+                    }
+                    None => {
+                        store.release(&key);
+                        ctx.add_response_header(
+                            "Idempotency-Warning",
+                            "response not stored: body too large or streamed",
+                        );
+                    }
+                },
+                None => store.release(&key),
+            }
+            MiddlewareResult::Continue()
+        })
+    }
+}
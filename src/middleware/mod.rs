@@ -1,13 +1,20 @@
 pub mod builtin;
 pub mod chain;
+pub mod config;
+pub mod idempotency;
+pub mod singleflight;
+#[cfg(feature = "test-hooks")]
+pub mod test_hooks;
 
 use axum::body::Body;
 use pyo3::prelude::*;
 
 // Re-export pure Rust middleware types
 pub use chain::{
-    BoxedMiddleware, MiddlewareChain, MiddlewareChainBuilder, MiddlewareContext, MiddlewareError,
-    MiddlewareResponse, MiddlewareResult, MiddlewareState, RustMiddleware, StateValue,
+    priority, Anchor, BoxedMiddleware, CapturedResponse, ChainDescription, MiddlewareChain,
+    MiddlewareChainBuilder, MiddlewareContext, MiddlewareError, MiddlewareInfo, MiddlewareMetrics,
+    MiddlewareResponse, MiddlewareResult, MiddlewareState, MiddlewareTimingSnapshot,
+    ResponseCapture, RustMiddleware, StateValue,
 };
 
 // Re-export built-in middleware
@@ -15,8 +22,17 @@ pub use builtin::{
     BasicAuthMiddleware, CacheConfig, CacheMiddleware, CircuitBreakerConfig,
     CircuitBreakerMiddleware, CircuitState, CompressionMiddleware, CorsConfig, CorsMiddleware,
     LogAfterMiddleware, LogConfig, LogLevel, LogMiddleware, MethodMiddleware, PathMiddleware,
-    RateLimitAlgorithm, RateLimitConfig, RateLimitMiddleware, RequestIdMiddleware,
-    SecurityHeadersConfig, SecurityHeadersMiddleware, TimeoutMiddleware,
+    RateLimitAlgorithm, RateLimitConfig, RateLimitKey, RateLimitMiddleware, RateLimitTier,
+    RateLimitTierResolver, RequestIdMiddleware, SecurityHeadersConfig, SecurityHeadersMiddleware,
+    TimeoutMiddleware,
+};
+pub use idempotency::{
+    ConcurrentPolicy, IdempotencyConfig, IdempotencyMiddleware, IdempotencyRecorder,
+    IdempotencyStore, MemoryIdempotencyStore, Reservation, StoredResponse,
+};
+pub use singleflight::{
+    Singleflight, SingleflightConfig, SingleflightMiddleware, SingleflightRecorder,
+    SingleflightStats,
 };
 
 /// Convert a MiddlewareResponse to a hyper Response - optimized
@@ -51,6 +67,17 @@ pub struct PyCorsMiddleware {
 #[pymethods]
 impl PyCorsMiddleware {
     /// Create a new CORS middleware with default permissive settings
+    ///
+    /// Args:
+    ///     allowed_origins: Allowed origins, "*" for any
+    ///     allowed_methods: Methods listed in preflight responses
+    ///     allowed_headers: Request headers allowed by preflights; with
+    ///         allow_credentials, ["*"] echoes the requested headers
+    ///     expose_headers: Response headers readable by the client
+    ///     allow_credentials: Send Access-Control-Allow-Credentials
+    ///     max_age: Seconds browsers may cache a preflight
+    ///     max_age_overrides: Path prefix -> max_age; the longest match wins
+    ///     allow_private_network: Answer Private Network Access preflights
     #[new]
     #[pyo3(signature = (
         allowed_origins = None,
@@ -58,8 +85,11 @@ impl PyCorsMiddleware {
         allowed_headers = None,
         expose_headers = None,
         allow_credentials = false,
-        max_age = 86400
+        max_age = 86400,
+        max_age_overrides = None,
+        allow_private_network = false
     ))]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         allowed_origins: Option<Vec<String>>,
         allowed_methods: Option<Vec<String>>,
@@ -67,6 +97,8 @@ impl PyCorsMiddleware {
         expose_headers: Option<Vec<String>>,
         allow_credentials: bool,
         max_age: u32,
+        max_age_overrides: Option<std::collections::HashMap<String, u32>>,
+        allow_private_network: bool,
     ) -> Self {
         let mut config = CorsConfig::default();
 
@@ -91,6 +123,10 @@ impl PyCorsMiddleware {
 
         config.allow_credentials = allow_credentials;
         config.max_age = max_age;
+        config.max_age_overrides = max_age_overrides
+            .map(|overrides| overrides.into_iter().collect())
+            .unwrap_or_default();
+        config.allow_private_network = allow_private_network;
 
         Self {
             inner: Arc::new(CorsMiddleware::new(config)),
@@ -116,6 +152,47 @@ pub struct PyRateLimitMiddleware {
     pub(crate) inner: Arc<RateLimitMiddleware>,
 }
 
+/// Parse a `key` argument: "ip", "user", "header:<name>", a callable or a
+/// list of these for a composite key
+fn rate_limit_key(key: &Bound<'_, PyAny>) -> PyResult<RateLimitKey> {
+    if let Ok(name) = key.extract::<String>() {
+        let lower = name.to_lowercase();
+        return match lower.as_str() {
+            "ip" => Ok(RateLimitKey::Ip),
+            "user" => Ok(RateLimitKey::AuthenticatedUser),
+            _ => match name.split_once(':') {
+                Some((kind, header))
+                    if kind.eq_ignore_ascii_case("header") && !header.is_empty() =>
+                {
+                    Ok(RateLimitKey::Header(header.to_string()))
+                }
+                _ => Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "Unknown rate limit key '{}': use 'ip', 'user', 'header:<name>', a callable or a list of these",
+                    name
+                ))),
+            },
+        };
+    }
+    if key.is_callable() {
+        return Ok(RateLimitKey::PythonCallable(Arc::new(key.clone().unbind())));
+    }
+    let parts = key
+        .try_iter()
+        .map_err(|_| {
+            pyo3::exceptions::PyTypeError::new_err(
+                "key must be a str, a callable or a list of these",
+            )
+        })?
+        .map(|part| rate_limit_key(&part?))
+        .collect::<PyResult<Vec<_>>>()?;
+    if parts.is_empty() {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "A composite rate limit key needs at least one part",
+        ));
+    }
+    Ok(RateLimitKey::Composite(parts))
+}
+
 #[pymethods]
 impl PyRateLimitMiddleware {
     /// Create a new rate limiting middleware
@@ -126,20 +203,37 @@ impl PyRateLimitMiddleware {
     ///     algorithm: "fixed", "sliding", or "token_bucket"
     ///     key_header: Header to use for client identification (optional)
     ///     skip_paths: Paths to skip rate limiting (default: /health, /metrics)
+    ///     key: Client key strategy: "ip", "user" (authenticated user id,
+    ///         else the IP), "header:<name>", a callable taking the
+    ///         context (slower: it takes the GIL per request), or a list of
+    ///         these joined into one key. Overrides key_header.
+    ///     tiers: Tier name -> (max_requests, window_secs)
+    ///     tier: Key prefix -> tier name (longest prefix wins), or a callable
+    ///         taking the context and key and returning a tier name or None
+    ///     limit_internal: Also count requests to the built-in health probes
     #[new]
     #[pyo3(signature = (
         max_requests = 100,
         window_secs = 60,
         algorithm = "sliding",
         key_header = None,
-        skip_paths = None
+        skip_paths = None,
+        key = None,
+        tiers = None,
+        tier = None,
+        limit_internal = false
     ))]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         max_requests: u32,
         window_secs: u64,
         algorithm: &str,
         key_header: Option<String>,
         skip_paths: Option<Vec<String>>,
+        key: Option<&Bound<'_, PyAny>>,
+        tiers: Option<std::collections::HashMap<String, (u32, u64)>>,
+        tier: Option<&Bound<'_, PyAny>>,
+        limit_internal: bool,
     ) -> PyResult<Self> {
         let algo = match algorithm.to_lowercase().as_str() {
             "fixed" | "fixed_window" => RateLimitAlgorithm::FixedWindow,
@@ -155,12 +249,51 @@ impl PyRateLimitMiddleware {
             }
         };
 
-        let mut config = RateLimitConfig::new(max_requests, window_secs).with_algorithm(algo);
+        let mut config = RateLimitConfig::new(max_requests, window_secs)
+            .with_algorithm(algo)
+            .with_limit_internal(limit_internal);
 
         if let Some(header) = key_header {
             config = config.with_key_header(header);
         }
 
+        if let Some(key) = key {
+            config = config.with_key(rate_limit_key(key)?);
+        }
+
+        let tiers = tiers.unwrap_or_default();
+        for (name, (max_requests, window_secs)) in &tiers {
+            if *max_requests == 0 || *window_secs == 0 {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "Tier '{}' needs positive max_requests and window_secs",
+                    name
+                )));
+            }
+            config = config.with_tier(
+                name.clone(),
+                RateLimitTier {
+                    max_requests: *max_requests,
+                    window: std::time::Duration::from_secs(*window_secs),
+                },
+            );
+        }
+
+        if let Some(tier) = tier {
+            let resolver = if tier.is_callable() {
+                RateLimitTierResolver::PythonCallable(Arc::new(tier.clone().unbind()))
+            } else {
+                let prefixes: std::collections::HashMap<String, String> = tier.extract()?;
+                if let Some(name) = prefixes.values().find(|name| !tiers.contains_key(*name)) {
+                    return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                        "Unknown rate limit tier '{}'",
+                        name
+                    )));
+                }
+                RateLimitTierResolver::Prefix(prefixes.into_iter().collect())
+            };
+            config = config.with_tier_resolver(resolver);
+        }
+
         if let Some(paths) = skip_paths {
             config.skip_paths = paths;
         }
@@ -424,16 +557,29 @@ impl PyBasicAuthMiddleware {
     /// Args:
     ///     realm: Authentication realm shown in browser dialog (default: "Restricted")
     ///     users: Dictionary of username -> password pairs
+    ///     roles: Dictionary of username -> role list, checked by routes
+    ///         registered with `auth=[...]`
+    ///     optional: Let requests without credentials through
+    ///         unauthenticated; routes registered with `auth="required"`
+    ///         still answer them 401 (default: False)
     #[new]
-    #[pyo3(signature = (realm = "Restricted", users = None))]
-    pub fn new(realm: &str, users: Option<std::collections::HashMap<String, String>>) -> Self {
-        let mut middleware = BasicAuthMiddleware::new(realm);
+    #[pyo3(signature = (realm = "Restricted", users = None, roles = None, optional = false))]
+    pub fn new(
+        realm: &str,
+        users: Option<std::collections::HashMap<String, String>>,
+        roles: Option<std::collections::HashMap<String, Vec<String>>>,
+        optional: bool,
+    ) -> Self {
+        let mut middleware = BasicAuthMiddleware::new(realm).optional(optional);
 
         if let Some(user_map) = users {
             for (username, password) in user_map {
                 middleware = middleware.add_user(username, password);
             }
         }
+        for (username, user_roles) in roles.unwrap_or_default() {
+            middleware = middleware.with_roles(username, user_roles);
+        }
 
         Self {
             inner: Arc::new(middleware),
@@ -574,3 +720,242 @@ impl PyCacheMiddleware {
         )
     }
 }
+
+// ─── Python wrapper: IdempotencyMiddleware ───────────────────────
+
+/// Python-accessible idempotency middleware
+///
+/// Runs a request with an `Idempotency-Key` header once and replays the
+/// stored response for repeats.
+#[pyclass(name = "IdempotencyMiddleware", from_py_object)]
+#[derive(Clone)]
+pub struct PyIdempotencyMiddleware {
+    pub(crate) inner: Arc<IdempotencyMiddleware>,
+}
+
+#[pymethods]
+impl PyIdempotencyMiddleware {
+    /// Create an idempotency middleware
+    ///
+    /// Args:
+    ///     ttl_seconds: How long completed responses are replayed (default: 86400)
+    ///     methods: Methods the key is honoured on (default: POST, PATCH)
+    ///     header_name: Request header carrying the key (default: "Idempotency-Key")
+    ///     max_key_length: Longer keys are rejected with 400 (default: 255)
+    ///     max_body_size: Larger responses are not stored; they pass through
+    ///         with an `Idempotency-Warning` header (default: 1 MiB)
+    ///     wait_secs: How long a duplicate waits for an in-flight request
+    ///         before getting 409; None answers 409 at once (default: None)
+    ///     in_flight_timeout_secs: After this long an unfinished request no
+    ///         longer holds its key (default: 60)
+    #[new]
+    #[pyo3(signature = (
+        ttl_seconds = 86400,
+        methods = None,
+        header_name = "Idempotency-Key",
+        max_key_length = 255,
+        max_body_size = 1048576,
+        wait_secs = None,
+        in_flight_timeout_secs = 60
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        ttl_seconds: u64,
+        methods: Option<Vec<String>>,
+        header_name: &str,
+        max_key_length: usize,
+        max_body_size: usize,
+        wait_secs: Option<f64>,
+        in_flight_timeout_secs: u64,
+    ) -> PyResult<Self> {
+        let mut config = IdempotencyConfig {
+            header_name: header_name.to_lowercase(),
+            ttl: std::time::Duration::from_secs(ttl_seconds),
+            max_key_length,
+            max_body_size,
+            in_flight_timeout: std::time::Duration::from_secs(in_flight_timeout_secs),
+            ..IdempotencyConfig::default()
+        };
+        if let Some(methods) = methods {
+            config.methods = methods
+                .iter()
+                .map(|m| {
+                    HttpMethod::from_str(&m.to_uppercase()).ok_or_else(|| {
+                        pyo3::exceptions::PyValueError::new_err(format!(
+                            "Unknown HTTP method: {}",
+                            m
+                        ))
+                    })
+                })
+                .collect::<PyResult<_>>()?;
+        }
+        if let Some(secs) = wait_secs {
+            if !secs.is_finite() || secs < 0.0 {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "wait_secs must be a non-negative number",
+                ));
+            }
+            config.concurrent = ConcurrentPolicy::Wait(std::time::Duration::from_secs_f64(secs));
+        }
+        Ok(Self {
+            inner: Arc::new(IdempotencyMiddleware::new(config)),
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        "IdempotencyMiddleware(...)".to_string()
+    }
+}
+
+fn singleflight_stats_dict<'py>(
+    py: Python<'py>,
+    stats: SingleflightStats,
+) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+    let dict = pyo3::types::PyDict::new(py);
+    dict.set_item("leaders", stats.leaders)?;
+    dict.set_item("coalesced", stats.coalesced)?;
+    dict.set_item("timed_out", stats.timed_out)?;
+    dict.set_item("not_shared", stats.not_shared)?;
+    dict.set_item("in_flight", stats.in_flight)?;
+    Ok(dict)
+}
+
+/// Request coalescing: identical concurrent GET/HEAD requests wait for the
+/// first one and get a copy of its response.
+#[pyclass(name = "SingleflightMiddleware", from_py_object)]
+#[derive(Clone)]
+pub struct PySingleflightMiddleware {
+    pub(crate) inner: Arc<SingleflightMiddleware>,
+}
+
+#[pymethods]
+impl PySingleflightMiddleware {
+    /// Create a request coalescing middleware
+    ///
+    /// Args:
+    ///     max_wait_secs: How long a duplicate waits for the running request
+    ///         before running the handler itself (default: 10)
+    ///     max_body_size: Larger or streamed responses are not shared
+    ///         (default: 1 MiB)
+    ///     vary_headers: Request headers that are part of the key, besides
+    ///         method, path and query (default: Accept, Accept-Encoding,
+    ///         Authorization, Cookie)
+    #[new]
+    #[pyo3(signature = (max_wait_secs = 10.0, max_body_size = 1048576, vary_headers = None))]
+    pub fn new(
+        max_wait_secs: f64,
+        max_body_size: usize,
+        vary_headers: Option<Vec<String>>,
+    ) -> PyResult<Self> {
+        if !max_wait_secs.is_finite() || max_wait_secs <= 0.0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "max_wait_secs must be a positive number",
+            ));
+        }
+        let mut config = SingleflightConfig {
+            max_wait: std::time::Duration::from_secs_f64(max_wait_secs),
+            max_body_size,
+            ..SingleflightConfig::default()
+        };
+        if let Some(headers) = vary_headers {
+            config.vary_headers = headers.iter().map(|h| h.to_lowercase()).collect();
+        }
+        Ok(Self {
+            inner: Arc::new(SingleflightMiddleware::new(config)),
+        })
+    }
+
+    /// Counters: `leaders`, `coalesced`, `timed_out`, `not_shared` and `in_flight`
+    pub fn stats<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        singleflight_stats_dict(py, self.inner.stats())
+    }
+
+    fn __repr__(&self) -> String {
+        "SingleflightMiddleware(...)".to_string()
+    }
+}
+
+/// Coalescing counters of routes registered with `coalesce=True`, for this
+/// process: `leaders`, `coalesced`, `timed_out`, `not_shared` and `in_flight`.
+#[pyfunction]
+pub fn singleflight_stats(py: Python<'_>) -> PyResult<Bound<'_, pyo3::types::PyDict>> {
+    singleflight_stats_dict(py, singleflight::process().stats())
+}
+
+/// Middleware that runs after the handler on behalf of a builtin
+type Recorder = Option<Arc<dyn RustMiddleware>>;
+
+/// The chain middleware behind a builtin middleware object, with the
+/// recorder idempotency and singleflight need once the handler has run.
+/// `None` for anything that is not a builtin.
+pub(crate) fn builtin_middleware(
+    middleware: &Bound<'_, PyAny>,
+) -> Option<(Arc<dyn RustMiddleware>, Recorder)> {
+    if let Ok(req_id) = middleware.extract::<PyRequestIdMiddleware>() {
+        Some((req_id.inner, None))
+    } else if let Ok(cors) = middleware.extract::<PyCorsMiddleware>() {
+        Some((cors.inner, None))
+    } else if let Ok(sec) = middleware.extract::<PySecurityHeadersMiddleware>() {
+        Some((sec.inner, None))
+    } else if let Ok(comp) = middleware.extract::<PyCompressionMiddleware>() {
+        Some((comp.inner, None))
+    } else if let Ok(rate) = middleware.extract::<PyRateLimitMiddleware>() {
+        Some((rate.inner, None))
+    } else if let Ok(timeout) = middleware.extract::<PyTimeoutMiddleware>() {
+        Some((timeout.inner, None))
+    } else if let Ok(log) = middleware.extract::<PyLogMiddleware>() {
+        Some((log.inner, None))
+    } else if let Ok(auth) = middleware.extract::<PyBasicAuthMiddleware>() {
+        Some((auth.inner, None))
+    } else if let Ok(idem) = middleware.extract::<PyIdempotencyMiddleware>() {
+        // Stores the handler response once the route has run
+        let recorder: Arc<dyn RustMiddleware> = Arc::new(idem.inner.recorder());
+        Some((idem.inner, Some(recorder)))
+    } else if let Ok(flight) = middleware.extract::<PySingleflightMiddleware>() {
+        // Hands the handler response to the requests waiting on it
+        let recorder: Arc<dyn RustMiddleware> = Arc::new(flight.inner.recorder());
+        Some((flight.inner, Some(recorder)))
+    } else {
+        test_hook_middleware(middleware)
+    }
+}
+
+#[cfg(feature = "test-hooks")]
+fn test_hook_middleware(
+    middleware: &Bound<'_, PyAny>,
+) -> Option<(Arc<dyn RustMiddleware>, Recorder)> {
+    let panicking = middleware.extract::<test_hooks::PyPanicMiddleware>().ok()?;
+    Some((panicking.inner, None))
+}
+
+#[cfg(not(feature = "test-hooks"))]
+fn test_hook_middleware(_: &Bound<'_, PyAny>) -> Option<(Arc<dyn RustMiddleware>, Recorder)> {
+    None
+}
+
+/// Register Rust middleware wrappers and the middleware context types.
+pub fn register_all(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyCorsMiddleware>()?;
+    m.add_class::<PyRateLimitMiddleware>()?;
+    m.add_class::<PySecurityHeadersMiddleware>()?;
+    m.add_class::<PyTimeoutMiddleware>()?;
+    m.add_class::<PyCompressionMiddleware>()?;
+    m.add_class::<PyRequestIdMiddleware>()?;
+    m.add_class::<PyLogMiddleware>()?;
+    m.add_class::<PyBasicAuthMiddleware>()?;
+    m.add_class::<PyCircuitBreakerMiddleware>()?;
+    m.add_class::<PyCacheMiddleware>()?;
+    m.add_class::<PyIdempotencyMiddleware>()?;
+    m.add_class::<PySingleflightMiddleware>()?;
+    m.add_function(wrap_pyfunction!(singleflight_stats, m)?)?;
+    #[cfg(feature = "test-hooks")]
+    m.add_class::<test_hooks::PyPanicMiddleware>()?;
+
+    m.add_class::<MiddlewareContext>()?;
+    m.add_class::<MiddlewareResponse>()?;
+    m.add_class::<MiddlewareError>()?;
+    m.add_class::<MiddlewareResult>()?;
+    m.add_class::<MiddlewareState>()?;
+    m.add_class::<StateValue>()?;
+    Ok(())
+}
@@ -0,0 +1,432 @@
+//! Request coalescing ("singleflight") for identical concurrent GETs.
+//!
+//! While the first request for a key is running, later requests with the
+//! same key wait for it and get a copy of its response instead of running
+//! the handler again. The key is the method, path, query (sorted) and the
+//! configured request headers. Waiters give up after `max_wait` and run on
+//! their own; responses that are too large, streamed or set cookies are
+//! never shared, so their waiters run on their own at once. Error responses
+//! are shared like any other.
+//!
+//! `SingleflightMiddleware` (with `SingleflightRecorder` as its "after"
+//! half) coalesces every route it runs for; routes registered with
+//! `coalesce=True` use the process-wide instance from `process()`.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use axum::body::Body;
+use bytes::Bytes;
+use dashmap::DashMap;
+use tokio::sync::watch;
+
+use crate::http::method::HttpMethod;
+use crate::http::urlencoded;
+
+use super::chain::{
+    priority, MiddlewareContext, MiddlewareResponse, MiddlewareResult, RustMiddleware, StateValue,
+};
+use super::idempotency::StoredResponse;
+
+/// State keys linking the two middleware halves
+const KEY_STATE: &str = "singleflight_key";
+const ID_STATE: &str = "singleflight_id";
+/// Response headers not copied to waiters; they are recomputed per response
+const SKIPPED_HEADERS: [&str; 4] = ["content-length", "transfer-encoding", "connection", "date"];
+
+/// Configuration for request coalescing
+#[derive(Debug, Clone)]
+pub struct SingleflightConfig {
+    /// Longest a request waits for the one running; also the age after
+    /// which an unfinished flight is considered abandoned
+    pub max_wait: Duration,
+    /// Larger (or streamed) responses are not shared
+    pub max_body_size: usize,
+    /// Request headers (lowercase) that are part of the key
+    pub vary_headers: Vec<String>,
+}
+
+impl Default for SingleflightConfig {
+    fn default() -> Self {
+        Self {
+            max_wait: Duration::from_secs(10),
+            max_body_size: 1024 * 1024,
+            vary_headers: ["accept", "accept-encoding", "authorization", "cookie"]
+                .iter()
+                .map(|h| h.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Result of the first request, as seen by its waiters
+#[derive(Clone)]
+pub enum Outcome {
+    Shared(Arc<StoredResponse>),
+    /// Not shareable; every waiter runs the handler itself
+    NotShared,
+}
+
+struct Flight {
+    id: u64,
+    started: Instant,
+    outcome: watch::Sender<Option<Outcome>>,
+}
+
+/// How a request takes part in coalescing
+pub enum Join {
+    /// No identical request is running: run the handler, then `finish`
+    Leader(u64),
+    /// An identical request is running: `wait` for its response
+    Follower(watch::Receiver<Option<Outcome>>),
+}
+
+/// Point-in-time copy of the counters
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SingleflightStats {
+    /// Requests that ran the handler for waiters
+    pub leaders: u64,
+    /// Requests answered with another request's response
+    pub coalesced: u64,
+    /// Waiters that gave up after `max_wait`
+    pub timed_out: u64,
+    /// Leader responses that could not be shared
+    pub not_shared: u64,
+    /// Flights currently running
+    pub in_flight: usize,
+}
+
+/// In-flight requests of one coalescing scope (per worker process)
+pub struct Singleflight {
+    config: SingleflightConfig,
+    flights: DashMap<String, Arc<Flight>>,
+    next_id: AtomicU64,
+    leaders: AtomicU64,
+    coalesced: AtomicU64,
+    timed_out: AtomicU64,
+    not_shared: AtomicU64,
+}
+
+static PROCESS: LazyLock<Arc<Singleflight>> =
+    LazyLock::new(|| Arc::new(Singleflight::new(SingleflightConfig::default())));
+
+/// Instance used by routes registered with `coalesce=True`
+pub fn process() -> &'static Arc<Singleflight> {
+    &PROCESS
+}
+
+/// Only safe methods are coalesced
+pub fn coalescable(method: &str) -> bool {
+    matches!(method, "GET" | "HEAD")
+}
+
+impl Singleflight {
+    pub fn new(config: SingleflightConfig) -> Self {
+        Self {
+            config,
+            flights: DashMap::new(),
+            next_id: AtomicU64::new(1),
+            leaders: AtomicU64::new(0),
+            coalesced: AtomicU64::new(0),
+            timed_out: AtomicU64::new(0),
+            not_shared: AtomicU64::new(0),
+        }
+    }
+
+    pub fn config(&self) -> &SingleflightConfig {
+        &self.config
+    }
+
+    /// Coalescing key: method, path, sorted query pairs and the vary headers
+    pub fn key(
+        &self,
+        method: &str,
+        path: &str,
+        query: &str,
+        header: impl Fn(&str) -> Option<String>,
+    ) -> String {
+        let mut pairs = urlencoded::parse(query.as_bytes());
+        pairs.sort();
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        query.extend_pairs(pairs);
+        let mut key = format!("{} {}?{}", method, path, query.finish());
+        for name in &self.config.vary_headers {
+            key.push('\u{0}');
+            key.push_str(name);
+            key.push('=');
+            key.push_str(&header(name).unwrap_or_default());
+        }
+        key
+    }
+
+    /// Lead the flight for `key`, or follow the one running. A flight older
+    /// than `max_wait` is taken over
+    pub fn join(&self, key: &str) -> Join {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut flight = self
+            .flights
+            .entry(key.to_string())
+            .or_insert_with(|| Self::flight(id));
+        if flight.id != id {
+            if flight.started.elapsed() < self.config.max_wait {
+                return Join::Follower(flight.outcome.subscribe());
+            }
+            *flight = Self::flight(id);
+        }
+        self.leaders.fetch_add(1, Ordering::Relaxed);
+        Join::Leader(id)
+    }
+
+    fn flight(id: u64) -> Arc<Flight> {
+        Arc::new(Flight {
+            id,
+            started: Instant::now(),
+            outcome: watch::channel(None).0,
+        })
+    }
+
+    /// Wait for the leader's response. None when it cannot be shared or
+    /// did not arrive within `max_wait`; the caller then runs the handler
+    pub async fn wait(
+        &self,
+        mut outcome: watch::Receiver<Option<Outcome>>,
+    ) -> Option<Arc<StoredResponse>> {
+        let waited = tokio::time::timeout(
+            self.config.max_wait,
+            outcome.wait_for(|outcome| outcome.is_some()),
+        )
+        .await;
+        match waited {
+            Ok(Ok(outcome)) => match outcome.clone() {
+                Some(Outcome::Shared(response)) => {
+                    self.coalesced.fetch_add(1, Ordering::Relaxed);
+                    Some(response)
+                }
+                _ => None,
+            },
+            // The flight was replaced after being abandoned
+            Ok(Err(_)) => None,
+            Err(_) => {
+                self.timed_out.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// End the flight led by `id`, handing `response` to its waiters
+    pub fn finish(&self, key: &str, id: u64, response: Option<StoredResponse>) {
+        let Some((_, flight)) = self.flights.remove_if(key, |_, flight| flight.id == id) else {
+            return;
+        };
+        let outcome = match response {
+            Some(response) => Outcome::Shared(Arc::new(response)),
+            None => {
+                self.not_shared.fetch_add(1, Ordering::Relaxed);
+                Outcome::NotShared
+            }
+        };
+        flight.outcome.send_replace(Some(outcome));
+    }
+
+    /// A captured response in the form waiters get, None when it must not
+    /// be shared
+    pub fn shareable(
+        status: u16,
+        headers: Vec<(String, String)>,
+        body: Option<Bytes>,
+    ) -> Option<StoredResponse> {
+        let body = body?;
+        if headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("set-cookie"))
+        {
+            return None;
+        }
+        let headers = headers
+            .into_iter()
+            .filter(|(name, _)| !SKIPPED_HEADERS.iter().any(|s| name.eq_ignore_ascii_case(s)))
+            .collect();
+        Some(StoredResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+
+    /// Finish the flight with the leader's own response (route flag),
+    /// buffering it when small enough to share
+    pub async fn share(
+        &self,
+        key: &str,
+        id: u64,
+        res: axum::http::Response<Body>,
+    ) -> axum::http::Response<Body> {
+        let (parts, body) = res.into_parts();
+        let fits = axum::body::HttpBody::size_hint(&body)
+            .exact()
+            .is_some_and(|len| len <= self.config.max_body_size as u64);
+        if !fits {
+            self.finish(key, id, None);
+            return axum::http::Response::from_parts(parts, body);
+        }
+        let bytes = match axum::body::to_bytes(body, self.config.max_body_size).await {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                self.finish(key, id, None);
+                crate::hlog_error!("Failed to read response body: {}", err);
+                return axum::http::Response::builder()
+                    .status(500)
+                    .body(Body::from("Internal Server Error"))
+                    .unwrap();
+            }
+        };
+        let headers = parts
+            .headers
+            .iter()
+            .filter_map(|(name, value)| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|value| (name.as_str().to_string(), value.to_string()))
+            })
+            .collect();
+        let shared = Self::shareable(parts.status.as_u16(), headers, Some(bytes.clone()));
+        self.finish(key, id, shared);
+        axum::http::Response::from_parts(parts, Body::from(bytes))
+    }
+
+    pub fn stats(&self) -> SingleflightStats {
+        SingleflightStats {
+            leaders: self.leaders.load(Ordering::Relaxed),
+            coalesced: self.coalesced.load(Ordering::Relaxed),
+            timed_out: self.timed_out.load(Ordering::Relaxed),
+            not_shared: self.not_shared.load(Ordering::Relaxed),
+            in_flight: self.flights.len(),
+        }
+    }
+}
+
+/// A shared response for one waiter
+pub fn to_response(response: &StoredResponse) -> axum::http::Response<Body> {
+    let mut builder = axum::http::Response::builder().status(response.status);
+    for (name, value) in &response.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+    builder
+        .body(Body::from(response.body.clone()))
+        .unwrap_or_else(|_| {
+            axum::http::Response::builder()
+                .status(500)
+                .body(Body::from("Internal Server Error"))
+                .unwrap()
+        })
+}
+
+/// Coalesces identical concurrent GET and HEAD requests
+pub struct SingleflightMiddleware {
+    inner: Arc<Singleflight>,
+}
+
+impl SingleflightMiddleware {
+    pub fn new(config: SingleflightConfig) -> Self {
+        Self {
+            inner: Arc::new(Singleflight::new(config)),
+        }
+    }
+
+    pub fn stats(&self) -> SingleflightStats {
+        self.inner.stats()
+    }
+
+    /// "After" half handing the leader's response to its waiters
+    pub fn recorder(self: &Arc<Self>) -> SingleflightRecorder {
+        SingleflightRecorder {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl RustMiddleware for SingleflightMiddleware {
+    fn name(&self) -> &'static str {
+        "singleflight"
+    }
+
+    fn priority(&self) -> i32 {
+        priority::SINGLEFLIGHT
+    }
+
+    fn applies_to_method(&self, method: HttpMethod) -> bool {
+        coalescable(method.as_str())
+    }
+
+    fn execute<'a>(
+        &'a self,
+        ctx: &'a MiddlewareContext,
+    ) -> Pin<Box<dyn Future<Output = MiddlewareResult> + Send + 'a>> {
+        Box::pin(async move {
+            let key = self.inner.key(
+                ctx.method.as_str(),
+                &ctx.get_path(),
+                &ctx.get_query_string(),
+                |name| ctx.get_header(name),
+            );
+            match self.inner.join(&key) {
+                Join::Leader(id) => {
+                    ctx.set_state(KEY_STATE, StateValue::String(key));
+                    ctx.set_state(ID_STATE, StateValue::Int(id as i64));
+                    ctx.capture_response(self.inner.config.max_body_size);
+                    MiddlewareResult::Continue()
+                }
+                Join::Follower(outcome) => match self.inner.wait(outcome).await {
+                    Some(shared) => MiddlewareResult::Response(MiddlewareResponse::replay(
+                        shared.status,
+                        shared.headers.clone(),
+                        shared.body.clone(),
+                    )),
+                    None => MiddlewareResult::Continue(),
+                },
+            }
+        })
+    }
+}
+
+/// Hands responses of flights led through `SingleflightMiddleware` to the
+/// requests waiting on them
+pub struct SingleflightRecorder {
+    inner: Arc<Singleflight>,
+}
+
+impl RustMiddleware for SingleflightRecorder {
+    fn name(&self) -> &'static str {
+        "singleflight_recorder"
+    }
+
+    fn priority(&self) -> i32 {
+        priority::SINGLEFLIGHT
+    }
+
+    fn applies_to_method(&self, method: HttpMethod) -> bool {
+        coalescable(method.as_str())
+    }
+
+    fn execute<'a>(
+        &'a self,
+        ctx: &'a MiddlewareContext,
+    ) -> Pin<Box<dyn Future<Output = MiddlewareResult> + Send + 'a>> {
+        Box::pin(async move {
+            let (Some(StateValue::String(key)), Some(StateValue::Int(id))) =
+                (ctx.get_state(KEY_STATE), ctx.get_state(ID_STATE))
+            else {
+                return MiddlewareResult::Continue();
+            };
+            let shared = ctx.captured_response().and_then(|captured| {
+                Singleflight::shareable(captured.status, captured.headers, captured.body)
+            });
+            self.inner.finish(&key, id as u64, shared);
+            MiddlewareResult::Continue()
+        })
+    }
+}
@@ -0,0 +1,53 @@
+//! Middleware that misbehaves on purpose, for exercising the chain's panic
+//! capture and error isolation. Only built with the `test-hooks` feature.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use pyo3::prelude::*;
+
+use super::chain::{MiddlewareContext, MiddlewareResult, RustMiddleware};
+
+/// Panics on every request it runs for
+pub struct PanicMiddleware;
+
+impl RustMiddleware for PanicMiddleware {
+    fn name(&self) -> &'static str {
+        "panic"
+    }
+
+    fn execute<'a>(
+        &'a self,
+        ctx: &'a MiddlewareContext,
+    ) -> Pin<Box<dyn Future<Output = MiddlewareResult> + Send + 'a>> {
+        Box::pin(async move { panic!("injected middleware panic for {}", ctx.path()) })
+    }
+}
+
+/// Python-accessible panicking middleware, for testing only
+#[pyclass(name = "PanicMiddleware", from_py_object)]
+#[derive(Clone)]
+pub struct PyPanicMiddleware {
+    pub(crate) inner: Arc<PanicMiddleware>,
+}
+
+#[pymethods]
+impl PyPanicMiddleware {
+    #[new]
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(PanicMiddleware),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        "PanicMiddleware()".to_string()
+    }
+}
+
+impl Default for PyPanicMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -7,6 +7,13 @@ use parking_lot::RwLock;
 use pyo3::prelude::*;
 use tokio::sync::broadcast;
 
+use crate::realtime::channel::PublishError;
+use crate::realtime::queue::{
+    ClientQueue, ClientQueueConfig, ClientQueueStats, Outbound, Priority,
+};
+use crate::realtime::receiver::SubscriberState;
+use crate::realtime::schema::{envelope, MessageSchema};
+
 /// Policy for handling backpressure when subscribers are slow
 #[pyclass(eq, eq_int, from_py_object)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -33,30 +40,40 @@ pub struct BroadcastConfig {
     /// Maximum number of recent message IDs to track for dedup
     #[pyo3(get, set)]
     pub dedup_window: usize,
+    /// Schema every sent message must pass (None for no checks)
+    #[pyo3(get, set)]
+    pub message_schema: Option<MessageSchema>,
+    /// Deliver messages as `{"seq", "ts", "channel", "data"}`
+    #[pyo3(get, set)]
+    pub envelope: bool,
 }
 
 #[pymethods]
 impl BroadcastConfig {
     #[new]
-    #[pyo3(signature = (buffer_size=256, policy=BackpressurePolicy::DropOldest, dedup_enabled=false, dedup_window=1000))]
+    #[pyo3(signature = (buffer_size=256, policy=BackpressurePolicy::DropOldest, dedup_enabled=false, dedup_window=1000, message_schema=None, envelope=false))]
     pub fn new(
         buffer_size: usize,
         policy: BackpressurePolicy,
         dedup_enabled: bool,
         dedup_window: usize,
+        message_schema: Option<MessageSchema>,
+        envelope: bool,
     ) -> Self {
         Self {
             buffer_size,
             policy,
             dedup_enabled,
             dedup_window,
+            message_schema,
+            envelope,
         }
     }
 
     fn __repr__(&self) -> String {
         format!(
-            "BroadcastConfig(buffer={}, policy={:?}, dedup={})",
-            self.buffer_size, self.policy, self.dedup_enabled
+            "BroadcastConfig(buffer={}, policy={:?}, dedup={}, envelope={})",
+            self.buffer_size, self.policy, self.dedup_enabled, self.envelope
         )
     }
 }
@@ -68,6 +85,8 @@ impl Default for BroadcastConfig {
             policy: BackpressurePolicy::DropOldest,
             dedup_enabled: false,
             dedup_window: 1000,
+            message_schema: None,
+            envelope: false,
         }
     }
 }
@@ -85,6 +104,9 @@ pub struct BroadcastStats {
     /// Total messages deduplicated (skipped)
     #[pyo3(get)]
     pub total_deduped: u64,
+    /// Total messages rejected by the message schema
+    #[pyo3(get)]
+    pub total_invalid: u64,
     /// Current number of active subscribers
     #[pyo3(get)]
     pub active_subscribers: usize,
@@ -97,10 +119,11 @@ pub struct BroadcastStats {
 impl BroadcastStats {
     fn __repr__(&self) -> String {
         format!(
-            "BroadcastStats(sent={}, dropped={}, deduped={}, subs={}, channels={})",
+            "BroadcastStats(sent={}, dropped={}, deduped={}, invalid={}, subs={}, channels={})",
             self.total_sent,
             self.total_dropped,
             self.total_deduped,
+            self.total_invalid,
             self.active_subscribers,
             self.channel_count,
         )
@@ -109,14 +132,44 @@ impl BroadcastStats {
 
 /// Internal broadcast channel data
 struct BroadcastInner {
-    sender: broadcast::Sender<String>,
+    sender: broadcast::Sender<Outbound>,
     config: BroadcastConfig,
     total_sent: AtomicU64,
     total_dropped: AtomicU64,
     total_deduped: AtomicU64,
+    total_invalid: AtomicU64,
     subscriber_count: AtomicU64,
     /// Ring buffer of recent message IDs for deduplication
     recent_ids: RwLock<Vec<String>>,
+    /// Sequence number of the latest enveloped message
+    last_seq: AtomicU64,
+}
+
+impl BroadcastInner {
+    /// Check a message against the schema, counting rejections
+    fn check(&self, name: &str, message: &str) -> PyResult<()> {
+        let Some(schema) = &self.config.message_schema else {
+            return Ok(());
+        };
+        schema.check(message).map_err(|reason| {
+            self.total_invalid.fetch_add(1, Ordering::Relaxed);
+            PublishError::new_err(format!(
+                "Message rejected by broadcast channel '{}' schema: {}",
+                name, reason
+            ))
+        })
+    }
+
+    /// The message as subscribers receive it
+    fn wrap(&self, name: &str, message: &str, priority: Priority) -> Outbound {
+        let text = if self.config.envelope {
+            let seq = self.last_seq.fetch_add(1, Ordering::Relaxed) + 1;
+            envelope(seq, name, message, priority)
+        } else {
+            message.to_string()
+        };
+        Outbound { priority, text }
+    }
 }
 
 /// Backpressure-aware broadcast system
@@ -140,9 +193,7 @@ pub struct RealtimeBroadcast {
 #[pyclass]
 pub struct BroadcastSubscriber {
     channel_name: String,
-    receiver: Arc<RwLock<broadcast::Receiver<String>>>,
-    received: AtomicU64,
-    lagged: AtomicU64,
+    state: Arc<SubscriberState>,
 }
 
 #[pymethods]
@@ -155,65 +206,62 @@ impl BroadcastSubscriber {
 
     /// Try to receive the next message (non-blocking)
     pub fn try_recv(&self) -> PyResult<Option<String>> {
-        let mut rx = self.receiver.write();
-        match rx.try_recv() {
-            Ok(msg) => {
-                self.received.fetch_add(1, Ordering::Relaxed);
-                Ok(Some(msg))
-            }
-            Err(broadcast::error::TryRecvError::Empty) => Ok(None),
-            Err(broadcast::error::TryRecvError::Lagged(n)) => {
-                self.lagged.fetch_add(n, Ordering::Relaxed);
-                match rx.try_recv() {
-                    Ok(msg) => {
-                        self.received.fetch_add(1, Ordering::Relaxed);
-                        Ok(Some(msg))
-                    }
-                    _ => Ok(None),
-                }
-            }
-            Err(broadcast::error::TryRecvError::Closed) => Ok(None),
-        }
+        Ok(self.state.try_recv())
     }
 
     /// Drain all pending messages
     pub fn drain(&self) -> Vec<String> {
-        let mut messages = Vec::new();
-        let mut rx = self.receiver.write();
-        loop {
-            match rx.try_recv() {
-                Ok(msg) => {
-                    self.received.fetch_add(1, Ordering::Relaxed);
-                    messages.push(msg);
-                }
-                Err(broadcast::error::TryRecvError::Lagged(n)) => {
-                    self.lagged.fetch_add(n, Ordering::Relaxed);
-                    continue;
-                }
-                Err(_) => break,
-            }
-        }
-        messages
+        self.state.drain()
+    }
+
+    /// Wait for the next message without blocking the event loop.
+    ///
+    /// Returns an awaitable resolving with the message, or None after
+    /// `timeout` seconds. Raises ChannelClosed once the channel is removed.
+    /// Messages dropped while lagging are skipped and counted in
+    /// `lagged_count`.
+    #[pyo3(signature = (timeout=None))]
+    pub fn recv_async<'py>(
+        &self,
+        py: Python<'py>,
+        timeout: Option<f64>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        self.state.recv_async(py, timeout, false)
+    }
+
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// `async for` yields messages until the channel is removed
+    fn __anext__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.state.recv_async(py, None, true)
     }
 
     /// Get count of received messages
     #[getter]
     pub fn received_count(&self) -> u64 {
-        self.received.load(Ordering::Relaxed)
+        self.state.received()
     }
 
-    /// Get count of messages missed due to lag
+    /// Get count of messages missed due to lag or the queue's overflow policy
     #[getter]
     pub fn lagged_count(&self) -> u64 {
-        self.lagged.load(Ordering::Relaxed)
+        self.state.missed()
+    }
+
+    /// Client queue counters, None when subscribed without a queue
+    #[getter]
+    pub fn queue_stats(&self) -> Option<ClientQueueStats> {
+        self.state.queue_stats()
     }
 
     fn __repr__(&self) -> String {
         format!(
             "BroadcastSubscriber(channel={:?}, received={}, lagged={})",
             self.channel_name,
-            self.received.load(Ordering::Relaxed),
-            self.lagged.load(Ordering::Relaxed),
+            self.state.received(),
+            self.state.missed(),
         )
     }
 }
@@ -245,8 +293,10 @@ impl RealtimeBroadcast {
                 total_sent: AtomicU64::new(0),
                 total_dropped: AtomicU64::new(0),
                 total_deduped: AtomicU64::new(0),
+                total_invalid: AtomicU64::new(0),
                 subscriber_count: AtomicU64::new(0),
                 recent_ids: RwLock::new(Vec::with_capacity(cfg.dedup_window)),
+                last_seq: AtomicU64::new(0),
             },
         );
         true
@@ -257,8 +307,14 @@ impl RealtimeBroadcast {
         self.channels.remove(name).is_some()
     }
 
-    /// Subscribe to a broadcast channel
-    pub fn subscribe(&self, name: &str) -> PyResult<BroadcastSubscriber> {
+    /// Subscribe to a broadcast channel, optionally through a per-client
+    /// queue with its own capacity and overflow policy
+    #[pyo3(signature = (name, queue=None))]
+    pub fn subscribe(
+        &self,
+        name: &str,
+        queue: Option<ClientQueueConfig>,
+    ) -> PyResult<BroadcastSubscriber> {
         let channel = self.channels.get(name).ok_or_else(|| {
             pyo3::exceptions::PyKeyError::new_err(format!(
                 "Broadcast channel '{}' does not exist",
@@ -269,29 +325,38 @@ impl RealtimeBroadcast {
         let rx = channel.sender.subscribe();
         channel.subscriber_count.fetch_add(1, Ordering::Relaxed);
 
+        let state = match queue {
+            Some(config) => SubscriberState::queued(ClientQueue::spawn(config, rx, || {})),
+            None => SubscriberState::new(rx),
+        };
+
         Ok(BroadcastSubscriber {
             channel_name: name.to_string(),
-            receiver: Arc::new(RwLock::new(rx)),
-            received: AtomicU64::new(0),
-            lagged: AtomicU64::new(0),
+            state,
         })
     }
 
     /// Send a message to a broadcast channel
-    /// Returns number of receivers, or raises on error if policy is Error
-    #[pyo3(signature = (name, message, message_id=None))]
+    /// Returns number of receivers, or raises on error if policy is Error.
+    /// Raises PublishError when the message fails the channel's schema.
+    /// `priority` ("high", "normal" or "low") orders delivery through
+    /// per-client queues.
+    #[pyo3(signature = (name, message, message_id=None, priority=None))]
     pub fn send(
         &self,
         name: &str,
         message: &str,
         message_id: Option<&str>,
+        priority: Option<&Bound<'_, PyAny>>,
     ) -> PyResult<usize> {
+        let priority = Priority::extract(priority)?;
         let channel = self.channels.get(name).ok_or_else(|| {
             pyo3::exceptions::PyKeyError::new_err(format!(
                 "Broadcast channel '{}' does not exist",
                 name
             ))
         })?;
+        channel.check(name, message)?;
 
         // Deduplication check
         if channel.config.dedup_enabled {
@@ -312,7 +377,7 @@ impl RealtimeBroadcast {
 
         channel.total_sent.fetch_add(1, Ordering::Relaxed);
 
-        match channel.sender.send(message.to_string()) {
+        match channel.sender.send(channel.wrap(name, message, priority)) {
             Ok(n) => Ok(n),
             Err(_) => {
                 // No receivers
@@ -329,13 +394,20 @@ impl RealtimeBroadcast {
         }
     }
 
-    /// Send a message to multiple broadcast channels at once
+    /// Send a message to multiple broadcast channels at once.
+    /// Channels whose schema rejects the message are left out of the result.
     pub fn send_many(&self, names: Vec<String>, message: &str) -> HashMap<String, usize> {
         let mut results = HashMap::new();
         for name in &names {
             if let Some(channel) = self.channels.get(name.as_str()) {
+                if channel.check(name, message).is_err() {
+                    continue;
+                }
                 channel.total_sent.fetch_add(1, Ordering::Relaxed);
-                let count = channel.sender.send(message.to_string()).unwrap_or(0);
+                let count = channel
+                    .sender
+                    .send(channel.wrap(name, message, Priority::Normal))
+                    .unwrap_or(0);
                 results.insert(name.clone(), count);
             }
         }
@@ -355,6 +427,7 @@ impl RealtimeBroadcast {
             total_sent: channel.total_sent.load(Ordering::Relaxed),
             total_dropped: channel.total_dropped.load(Ordering::Relaxed),
             total_deduped: channel.total_deduped.load(Ordering::Relaxed),
+            total_invalid: channel.total_invalid.load(Ordering::Relaxed),
             active_subscribers: channel.subscriber_count.load(Ordering::Relaxed) as usize,
             channel_count: 1,
         })
@@ -369,6 +442,7 @@ impl RealtimeBroadcast {
             stats.total_sent += entry.total_sent.load(Ordering::Relaxed);
             stats.total_dropped += entry.total_dropped.load(Ordering::Relaxed);
             stats.total_deduped += entry.total_deduped.load(Ordering::Relaxed);
+            stats.total_invalid += entry.total_invalid.load(Ordering::Relaxed);
             stats.active_subscribers += entry.subscriber_count.load(Ordering::Relaxed) as usize;
         }
 
@@ -1,12 +1,39 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use dashmap::DashMap;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
 use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyString};
 use tokio::sync::broadcast;
 
+use crate::realtime::handoff::{self, ChannelSnapshot, ManagerSnapshot, RestoreStats};
+use crate::realtime::identity::RealtimeIdentity;
+use crate::realtime::queue::{
+    ClientQueue, ClientQueueConfig, ClientQueueStats, Outbound, Priority,
+};
+use crate::realtime::receiver::SubscriberState;
+use crate::realtime::replay::ReplayBuffer;
+use crate::realtime::schema::{envelope, MessagePolicy, MessageSchema};
+
+create_exception!(
+    _hypern,
+    SubscriptionError,
+    PyException,
+    "A subscription was refused: the channel is full or the subscribe hook denied it."
+);
+
+create_exception!(
+    _hypern,
+    PublishError,
+    PyException,
+    "A publish was refused by the publish hook."
+);
+
 /// Statistics for a single channel
 #[pyclass(from_py_object)]
 #[derive(Clone, Debug)]
@@ -26,25 +53,95 @@ pub struct ChannelStats {
     /// Channel metadata
     #[pyo3(get)]
     pub metadata: HashMap<String, String>,
+    /// Subscriber limit set at creation (None for unlimited)
+    #[pyo3(get)]
+    pub max_subscribers: Option<usize>,
+    /// Subscriptions refused by the limit or the subscribe hook
+    #[pyo3(get)]
+    pub denied_subscriptions: u64,
+    /// Publishes refused by the publish hook
+    #[pyo3(get)]
+    pub denied_publishes: u64,
+    /// Publishes rejected by the channel's message schema
+    #[pyo3(get)]
+    pub invalid_messages: u64,
+    /// Messages kept for replay (0 when the channel keeps none)
+    #[pyo3(get)]
+    pub replay_size: usize,
+    /// Sequence number of the latest message
+    #[pyo3(get)]
+    pub last_seq: u64,
 }
 
 #[pymethods]
 impl ChannelStats {
     fn __repr__(&self) -> String {
         format!(
-            "ChannelStats(name={:?}, subscribers={}, total_msgs={}, dropped={}, metadata={:?})",
-            self.name, self.subscriber_count, self.total_messages, self.dropped_messages, self.metadata
+            "ChannelStats(name={:?}, subscribers={}, total_msgs={}, dropped={}, denied_subs={}, denied_pubs={}, invalid={}, last_seq={}, metadata={:?})",
+            self.name,
+            self.subscriber_count,
+            self.total_messages,
+            self.dropped_messages,
+            self.denied_subscriptions,
+            self.denied_publishes,
+            self.invalid_messages,
+            self.last_seq,
+            self.metadata
         )
     }
 }
 
 /// Internal channel data
 struct ChannelInner {
-    sender: broadcast::Sender<String>,
+    sender: broadcast::Sender<Outbound>,
     subscribers: HashSet<String>,
     total_messages: AtomicU64,
     dropped_messages: AtomicU64,
     metadata: HashMap<String, String>,
+    max_subscribers: Option<usize>,
+    denied_subscriptions: AtomicU64,
+    denied_publishes: AtomicU64,
+    invalid_messages: AtomicU64,
+    replay: Arc<ReplayBuffer>,
+    /// Schema check and envelope applied on publish
+    messages: MessagePolicy,
+    /// Removed by the idle sweep after this long without subscribers or
+    /// publishes; None keeps the channel until it is removed explicitly
+    idle_ttl: Option<Duration>,
+    /// Last publish, or last change to the subscriber set
+    last_active: Mutex<Instant>,
+}
+
+impl ChannelInner {
+    fn touch(&self) {
+        *self.last_active.lock() = Instant::now();
+    }
+
+    /// Number, retain and send one message; returns the receiver count
+    fn deliver(&self, channel_name: &str, message: &str, priority: Priority) -> usize {
+        self.touch();
+        self.total_messages.fetch_add(1, Ordering::Relaxed);
+        let text = if self.messages.envelope {
+            self.replay.record_with(priority, |seq| {
+                envelope(seq, channel_name, message, priority)
+            })
+        } else {
+            self.replay.record(message, priority);
+            message.to_string()
+        };
+        // Err means no active receivers
+        self.sender.send(Outbound { priority, text }).unwrap_or(0)
+    }
+
+    fn is_idle(&self, now: Instant) -> bool {
+        match self.idle_ttl {
+            Some(ttl) => {
+                self.subscribers.is_empty()
+                    && now.saturating_duration_since(*self.last_active.lock()) >= ttl
+            }
+            None => false,
+        }
+    }
 }
 
 /// A subscriber handle that receives messages from a channel
@@ -52,9 +149,7 @@ struct ChannelInner {
 pub struct Subscriber {
     channel_name: String,
     client_id: String,
-    receiver: Arc<RwLock<broadcast::Receiver<String>>>,
-    received_count: AtomicU64,
-    missed_count: AtomicU64,
+    state: Arc<SubscriberState>,
 }
 
 #[pymethods]
@@ -74,58 +169,54 @@ impl Subscriber {
     /// Try to receive the next message (non-blocking)
     /// Returns None if no message is available
     pub fn try_recv(&self) -> PyResult<Option<String>> {
-        let mut rx = self.receiver.write();
-        match rx.try_recv() {
-            Ok(msg) => {
-                self.received_count.fetch_add(1, Ordering::Relaxed);
-                Ok(Some(msg))
-            }
-            Err(broadcast::error::TryRecvError::Empty) => Ok(None),
-            Err(broadcast::error::TryRecvError::Lagged(n)) => {
-                self.missed_count.fetch_add(n, Ordering::Relaxed);
-                // Try again after lag
-                match rx.try_recv() {
-                    Ok(msg) => {
-                        self.received_count.fetch_add(1, Ordering::Relaxed);
-                        Ok(Some(msg))
-                    }
-                    _ => Ok(None),
-                }
-            }
-            Err(broadcast::error::TryRecvError::Closed) => Ok(None),
-        }
+        Ok(self.state.try_recv())
     }
 
     /// Receive all pending messages (non-blocking drain)
     pub fn drain(&self) -> PyResult<Vec<String>> {
-        let mut messages = Vec::new();
-        let mut rx = self.receiver.write();
-        loop {
-            match rx.try_recv() {
-                Ok(msg) => {
-                    self.received_count.fetch_add(1, Ordering::Relaxed);
-                    messages.push(msg);
-                }
-                Err(broadcast::error::TryRecvError::Lagged(n)) => {
-                    self.missed_count.fetch_add(n, Ordering::Relaxed);
-                    continue;
-                }
-                Err(_) => break,
-            }
-        }
-        Ok(messages)
+        Ok(self.state.drain())
+    }
+
+    /// Wait for the next message without blocking the event loop.
+    ///
+    /// Returns an awaitable resolving with the message, or None after
+    /// `timeout` seconds. Raises ChannelClosed once the channel is removed.
+    /// Messages dropped while lagging are skipped and counted in
+    /// `missed_count`.
+    #[pyo3(signature = (timeout=None))]
+    pub fn recv_async<'py>(
+        &self,
+        py: Python<'py>,
+        timeout: Option<f64>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        self.state.recv_async(py, timeout, false)
+    }
+
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// `async for` yields messages until the channel is removed
+    fn __anext__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.state.recv_async(py, None, true)
     }
 
     /// Get count of received messages
     #[getter]
     pub fn received_count(&self) -> u64 {
-        self.received_count.load(Ordering::Relaxed)
+        self.state.received()
     }
 
-    /// Get count of missed messages (due to lag)
+    /// Get count of missed messages (due to lag or the queue's overflow policy)
     #[getter]
     pub fn missed_count(&self) -> u64 {
-        self.missed_count.load(Ordering::Relaxed)
+        self.state.missed()
+    }
+
+    /// Client queue counters, None when subscribed without a queue
+    #[getter]
+    pub fn queue_stats(&self) -> Option<ClientQueueStats> {
+        self.state.queue_stats()
     }
 
     fn __repr__(&self) -> String {
@@ -133,8 +224,8 @@ impl Subscriber {
             "Subscriber(channel={:?}, client={:?}, received={}, missed={})",
             self.channel_name,
             self.client_id,
-            self.received_count.load(Ordering::Relaxed),
-            self.missed_count.load(Ordering::Relaxed),
+            self.state.received(),
+            self.state.missed(),
         )
     }
 }
@@ -281,11 +372,83 @@ impl Default for TopicMatcher {
     }
 }
 
+/// Outcome of asking an authorization hook
+enum HookDecision {
+    Allow,
+    Deny,
+    Failed(PyErr),
+}
+
+/// Call `hook` with `args`; a truthy return allows the operation
+fn ask_hook<'py>(
+    py: Python<'py>,
+    hook: &RwLock<Option<Py<PyAny>>>,
+    args: impl pyo3::call::PyCallArgs<'py>,
+) -> HookDecision {
+    let Some(hook) = hook.read().as_ref().map(|h| h.clone_ref(py)) else {
+        return HookDecision::Allow;
+    };
+    match hook.bind(py).call1(args).and_then(|r| r.is_truthy()) {
+        Ok(true) => HookDecision::Allow,
+        Ok(false) => HookDecision::Deny,
+        Err(err) => HookDecision::Failed(err),
+    }
+}
+
+/// Longest gap between idle sweeps
+const SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// When channels are created on first use and collected once idle
+struct CreationPolicy {
+    auto_create: bool,
+    /// Topic patterns a name must match to be created on first use; empty
+    /// allows any name
+    patterns: Vec<String>,
+    default_ttl: Option<Duration>,
+    /// Minimum gap between two sweeps
+    sweep_every: Duration,
+    last_sweep: Mutex<Instant>,
+    created: AtomicU64,
+    auto_created: AtomicU64,
+    expired: AtomicU64,
+}
+
+impl CreationPolicy {
+    fn allows(&self, channel_name: &str) -> bool {
+        self.auto_create
+            && (self.patterns.is_empty()
+                || self
+                    .patterns
+                    .iter()
+                    .any(|pattern| TopicMatcher::pattern_matches(pattern, channel_name)))
+    }
+}
+
+fn ttl_from_secs(name: &str, secs: Option<f64>) -> PyResult<Option<Duration>> {
+    match secs {
+        None => Ok(None),
+        Some(secs) if secs > 0.0 && secs.is_finite() => Ok(Some(Duration::from_secs_f64(secs))),
+        Some(secs) => Err(PyValueError::new_err(format!(
+            "{} must be a positive number of seconds, got {}",
+            name, secs
+        ))),
+    }
+}
+
 /// High-performance channel manager for pub/sub messaging
 ///
 /// Manages named channels with configurable buffer sizes.
 /// Uses tokio broadcast channels internally for efficient fan-out.
 ///
+/// Subscriptions can be capped per channel (`max_subscribers`) and vetted
+/// by hooks registered with `set_subscribe_hook` / `set_publish_hook`.
+///
+/// With `auto_create=True`, subscribing or publishing to a missing channel
+/// whose name matches `auto_create_pattern` creates it with the default
+/// config. Channels with a TTL (auto-created ones get `default_ttl_secs`)
+/// are removed by a lazy sweep once they have had no subscribers and no
+/// publishes for that long.
+///
 /// Example (Python):
 ///     manager = ChannelManager(default_buffer_size=256)
 ///     manager.create_channel("chat:general")
@@ -297,47 +460,438 @@ pub struct ChannelManager {
     channels: Arc<DashMap<String, ChannelInner>>,
     default_buffer_size: usize,
     topic_matcher: TopicMatcher,
+    subscribe_hook: Arc<RwLock<Option<Py<PyAny>>>>,
+    publish_hook: Arc<RwLock<Option<Py<PyAny>>>>,
+    policy: CreationPolicy,
+    channel_hook: RwLock<Option<Py<PyAny>>>,
+    handoff_max_bytes: usize,
+    restored: Arc<RestoreStats>,
+}
+
+/// A manager's channels as seen by the long-poll endpoint, usable without
+/// the GIL
+#[derive(Clone)]
+pub(crate) struct ChannelSource {
+    channels: Arc<DashMap<String, ChannelInner>>,
+    subscribe_hook: Arc<RwLock<Option<Py<PyAny>>>>,
+}
+
+impl ChannelSource {
+    /// The channel's replay buffer, None for an unknown channel
+    pub(crate) fn replay(&self, channel_name: &str) -> Option<Arc<ReplayBuffer>> {
+        self.channels
+            .get(channel_name)
+            .map(|channel| channel.replay.clone())
+    }
+
+    pub(crate) fn has_subscribe_hook(&self) -> bool {
+        self.subscribe_hook.read().is_some()
+    }
+
+    /// Ask the subscribe hook whether `identity` may read the channel; the
+    /// hook sees its client ID and `{"transport": "poll", "user_id": ...}`
+    /// as metadata. Runs with the GIL, so call it off the async runtime.
+    pub(crate) fn authorize(&self, channel_name: &str, identity: &RealtimeIdentity) -> bool {
+        let client_id = identity.client();
+        let allowed = Python::attach(|py| {
+            let metadata = PyDict::new(py);
+            let _ = metadata.set_item("transport", "poll");
+            let _ = metadata.set_item("user_id", identity.user());
+            match ask_hook(
+                py,
+                &self.subscribe_hook,
+                (channel_name, client_id, metadata),
+            ) {
+                HookDecision::Allow => true,
+                HookDecision::Deny => false,
+                HookDecision::Failed(cause) => {
+                    crate::hlog_warn!(
+                        "Subscribe hook raised for poll on channel '{}', client '{}': {}",
+                        channel_name,
+                        client_id,
+                        cause
+                    );
+                    false
+                }
+            }
+        });
+        if !allowed {
+            if let Some(channel) = self.channels.get(channel_name) {
+                channel.denied_subscriptions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        allowed
+    }
+}
+
+/// A manager's replay buffers as saved and restored across a graceful
+/// reload, usable without the GIL
+#[derive(Clone)]
+pub(crate) struct ReplayStore {
+    channels: Arc<DashMap<String, ChannelInner>>,
+    max_bytes: usize,
+    restored: Arc<RestoreStats>,
+}
+
+impl ReplayStore {
+    /// Sequence counters and newest messages of every channel, within
+    /// `max_bytes` of message text (default: the manager's cap)
+    pub(crate) fn export(&self, max_bytes: Option<usize>) -> ManagerSnapshot {
+        let channels = self
+            .channels
+            .iter()
+            .filter_map(|entry| {
+                let (last_seq, messages) = entry.replay.snapshot();
+                (last_seq > 0).then(|| ChannelSnapshot {
+                    name: entry.key().clone(),
+                    last_seq,
+                    messages,
+                })
+            })
+            .collect();
+        ManagerSnapshot::bounded(channels, max_bytes.unwrap_or(self.max_bytes))
+    }
+
+    /// Restore channels that exist here and have not published yet; others
+    /// are skipped. Returns the channels and messages restored.
+    pub(crate) fn import(&self, snapshot: ManagerSnapshot) -> (usize, usize) {
+        let (mut channels, mut messages) = (0, 0);
+        for channel in snapshot.channels {
+            let Some(inner) = self.channels.get(&channel.name) else {
+                crate::hlog_debug!(
+                    "Replay history of channel '{}' skipped: the channel does not exist",
+                    channel.name
+                );
+                continue;
+            };
+            match inner.replay.restore(channel.last_seq, channel.messages) {
+                Some(restored) => {
+                    channels += 1;
+                    messages += restored;
+                }
+                None => crate::hlog_debug!(
+                    "Replay history of channel '{}' skipped: messages were already published",
+                    channel.name
+                ),
+            }
+        }
+        self.restored
+            .channels
+            .fetch_add(channels as u64, Ordering::Relaxed);
+        self.restored
+            .messages
+            .fetch_add(messages as u64, Ordering::Relaxed);
+        (channels, messages)
+    }
+}
+
+impl ChannelManager {
+    pub(crate) fn source(&self) -> ChannelSource {
+        ChannelSource {
+            channels: self.channels.clone(),
+            subscribe_hook: self.subscribe_hook.clone(),
+        }
+    }
+
+    fn replay_store(&self) -> ReplayStore {
+        ReplayStore {
+            channels: self.channels.clone(),
+            max_bytes: self.handoff_max_bytes,
+            restored: self.restored.clone(),
+        }
+    }
+
+    fn missing_channel(channel_name: &str) -> PyErr {
+        pyo3::exceptions::PyKeyError::new_err(format!("Channel '{}' does not exist", channel_name))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn insert_channel(
+        &self,
+        name: &str,
+        buffer_size: usize,
+        metadata: HashMap<String, String>,
+        max_subscribers: Option<usize>,
+        replay_size: usize,
+        idle_ttl: Option<Duration>,
+        messages: MessagePolicy,
+    ) -> bool {
+        match self.channels.entry(name.to_string()) {
+            dashmap::Entry::Occupied(_) => false,
+            dashmap::Entry::Vacant(entry) => {
+                let (sender, _) = broadcast::channel(buffer_size);
+                entry.insert(ChannelInner {
+                    sender,
+                    subscribers: HashSet::new(),
+                    total_messages: AtomicU64::new(0),
+                    dropped_messages: AtomicU64::new(0),
+                    metadata,
+                    max_subscribers,
+                    denied_subscriptions: AtomicU64::new(0),
+                    denied_publishes: AtomicU64::new(0),
+                    invalid_messages: AtomicU64::new(0),
+                    replay: ReplayBuffer::new(replay_size),
+                    messages,
+                    idle_ttl,
+                    last_active: Mutex::new(Instant::now()),
+                });
+                true
+            }
+        }
+    }
+
+    /// Tell the channel hook about a creation or removal; errors are logged
+    fn notify(&self, py: Python<'_>, event: &str, channel_name: &str) {
+        let Some(hook) = self.channel_hook.read().as_ref().map(|h| h.clone_ref(py)) else {
+            return;
+        };
+        if let Err(err) = hook.bind(py).call1((event, channel_name)) {
+            crate::hlog_warn!(
+                "Channel hook raised for '{}' event on channel '{}': {}",
+                event,
+                channel_name,
+                err
+            );
+        }
+    }
+
+    /// Fail with KeyError unless the channel exists or the policy may create it
+    fn check_exists(&self, channel_name: &str) -> PyResult<()> {
+        if self.channels.contains_key(channel_name) || self.policy.allows(channel_name) {
+            Ok(())
+        } else {
+            Err(Self::missing_channel(channel_name))
+        }
+    }
+
+    /// Create the channel with the default config if the policy allows it
+    fn auto_create(&self, py: Python<'_>, channel_name: &str) {
+        if self.channels.contains_key(channel_name) || !self.policy.allows(channel_name) {
+            return;
+        }
+        let created = self.insert_channel(
+            channel_name,
+            self.default_buffer_size,
+            HashMap::new(),
+            None,
+            0,
+            self.policy.default_ttl,
+            MessagePolicy::default(),
+        );
+        if created {
+            self.policy.auto_created.fetch_add(1, Ordering::Relaxed);
+            crate::hlog_debug!("Channel '{}' created on first use", channel_name);
+            self.notify(py, "auto_created", channel_name);
+        }
+    }
+
+    /// Run the idle sweep if it has not run within `sweep_every`
+    fn maybe_sweep(&self, py: Python<'_>) {
+        {
+            let mut last = self.policy.last_sweep.lock();
+            if last.elapsed() < self.policy.sweep_every {
+                return;
+            }
+            *last = Instant::now();
+        }
+        self.sweep(py);
+    }
+
+    fn sweep(&self, py: Python<'_>) -> usize {
+        let now = Instant::now();
+        let candidates: Vec<String> = self
+            .channels
+            .iter()
+            .filter(|entry| entry.is_idle(now))
+            .map(|entry| entry.key().clone())
+            .collect();
+        let mut removed = 0;
+        for name in candidates {
+            // Checked again under the shard lock, so a subscriber arriving in
+            // between keeps the channel
+            if self
+                .channels
+                .remove_if(&name, |_, channel| channel.is_idle(now))
+                .is_some()
+            {
+                removed += 1;
+                self.policy.expired.fetch_add(1, Ordering::Relaxed);
+                crate::hlog_debug!("Channel '{}' removed after being idle", name);
+                self.notify(py, "expired", &name);
+            }
+        }
+        removed
+    }
+
+    fn count_denied_subscription(&self, channel_name: &str) {
+        if let Some(channel) = self.channels.get(channel_name) {
+            channel.denied_subscriptions.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn count_denied_publish(&self, channel_name: &str) {
+        if let Some(channel) = self.channels.get(channel_name) {
+            channel.denied_publishes.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Check a message against the channel's schema, counting rejections.
+    /// A channel that does not exist yet has no schema.
+    fn check_message(&self, channel_name: &str, message: &str) -> PyResult<()> {
+        let Some(channel) = self.channels.get(channel_name) else {
+            return Ok(());
+        };
+        channel.messages.check(message).map_err(|reason| {
+            channel.invalid_messages.fetch_add(1, Ordering::Relaxed);
+            PublishError::new_err(format!(
+                "Message rejected by channel '{}' schema: {}",
+                channel_name, reason
+            ))
+        })
+    }
+
+    /// Ask the publish hook about one channel; the hook runs with no channel lock held
+    fn check_publish(
+        &self,
+        py: Python<'_>,
+        channel_name: &str,
+        client_id: Option<&str>,
+        message: &str,
+    ) -> PyResult<()> {
+        match ask_hook(py, &self.publish_hook, (channel_name, client_id, message)) {
+            HookDecision::Allow => Ok(()),
+            HookDecision::Deny => {
+                self.count_denied_publish(channel_name);
+                Err(PublishError::new_err(format!(
+                    "Publish to channel '{}' denied for client {:?}",
+                    channel_name, client_id
+                )))
+            }
+            HookDecision::Failed(cause) => {
+                self.count_denied_publish(channel_name);
+                crate::hlog_warn!(
+                    "Publish hook raised for channel '{}': {}",
+                    channel_name,
+                    cause
+                );
+                let err = PublishError::new_err(format!(
+                    "Publish hook failed for channel '{}'",
+                    channel_name
+                ));
+                err.set_cause(py, Some(cause));
+                Err(err)
+            }
+        }
+    }
 }
 
 #[pymethods]
 impl ChannelManager {
+    /// Args:
+    ///     default_buffer_size: Broadcast buffer of channels created without one
+    ///     auto_create: Create missing channels on `subscribe` / `publish`
+    ///     auto_create_pattern: Topic pattern, or list of patterns, a name
+    ///         must match to be created on first use (default: any name)
+    ///     default_ttl_secs: Idle time after which an auto-created channel
+    ///         is removed (default: never)
+    ///     handoff_key: Save replay history when a worker drains for a
+    ///         graceful reload and restore it in the next generation's
+    ///         manager created with the same key (default: no handoff)
+    ///     handoff_max_bytes: Cap on the message text saved; older messages
+    ///         are dropped first, sequence counters are always kept
     #[new]
-    #[pyo3(signature = (default_buffer_size=256))]
-    pub fn new(default_buffer_size: usize) -> Self {
-        Self {
+    #[pyo3(signature = (default_buffer_size=256, auto_create=false, auto_create_pattern=None, default_ttl_secs=None, handoff_key=None, handoff_max_bytes=handoff::DEFAULT_MAX_BYTES))]
+    pub fn new(
+        default_buffer_size: usize,
+        auto_create: bool,
+        auto_create_pattern: Option<Bound<'_, PyAny>>,
+        default_ttl_secs: Option<f64>,
+        handoff_key: Option<String>,
+        handoff_max_bytes: usize,
+    ) -> PyResult<Self> {
+        let patterns = match auto_create_pattern {
+            None => Vec::new(),
+            Some(pattern) if pattern.is_instance_of::<PyString>() => vec![pattern.extract()?],
+            Some(patterns) => patterns.extract()?,
+        };
+        if !patterns.is_empty() && !auto_create {
+            return Err(PyValueError::new_err(
+                "auto_create_pattern requires auto_create=True",
+            ));
+        }
+        let default_ttl = ttl_from_secs("default_ttl_secs", default_ttl_secs)?;
+        let manager = Self {
             channels: Arc::new(DashMap::new()),
             default_buffer_size,
             topic_matcher: TopicMatcher::new(),
+            subscribe_hook: Arc::new(RwLock::new(None)),
+            publish_hook: Arc::new(RwLock::new(None)),
+            policy: CreationPolicy {
+                auto_create,
+                patterns,
+                default_ttl,
+                sweep_every: default_ttl.map_or(SWEEP_INTERVAL, |ttl| ttl.min(SWEEP_INTERVAL)),
+                last_sweep: Mutex::new(Instant::now()),
+                created: AtomicU64::new(0),
+                auto_created: AtomicU64::new(0),
+                expired: AtomicU64::new(0),
+            },
+            channel_hook: RwLock::new(None),
+            handoff_max_bytes,
+            restored: Arc::new(RestoreStats::default()),
+        };
+        if let Some(key) = handoff_key {
+            handoff::register(&key, manager.replay_store());
         }
+        Ok(manager)
     }
 
     /// Create a new channel with optional custom buffer size
-    #[pyo3(signature = (name, buffer_size=None, metadata=None))]
+    ///
+    /// Args:
+    ///     name: Channel name
+    ///     buffer_size: Broadcast buffer; defaults to the manager's
+    ///     metadata: Free-form labels reported in `ChannelStats`
+    ///     max_subscribers: Subscriptions beyond this many raise
+    ///         `SubscriptionError` (default: unlimited)
+    ///     replay_size: Newest messages kept for `messages_since` and long
+    ///         polls (default: none)
+    ///     idle_ttl_secs: Let the idle sweep remove the channel after this
+    ///         long without subscribers or publishes (default: never)
+    ///     message_schema: `MessageSchema` every published message must
+    ///         pass; others raise `PublishError` (default: no checks)
+    ///     envelope: Deliver messages as `{"seq", "ts", "channel", "data"}`,
+    ///         with `seq` matching the replay sequence
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (name, buffer_size=None, metadata=None, max_subscribers=None, replay_size=None, idle_ttl_secs=None, message_schema=None, envelope=false))]
     pub fn create_channel(
         &self,
+        py: Python<'_>,
         name: &str,
         buffer_size: Option<usize>,
         metadata: Option<HashMap<String, String>>,
-    ) -> bool {
-        if self.channels.contains_key(name) {
-            return false;
-        }
-
-        let buf_size = buffer_size.unwrap_or(self.default_buffer_size);
-        let (sender, _) = broadcast::channel(buf_size);
-
-        self.channels.insert(
-            name.to_string(),
-            ChannelInner {
-                sender,
-                subscribers: HashSet::new(),
-                total_messages: AtomicU64::new(0),
-                dropped_messages: AtomicU64::new(0),
-                metadata: metadata.unwrap_or_default(),
-            },
+        max_subscribers: Option<usize>,
+        replay_size: Option<usize>,
+        idle_ttl_secs: Option<f64>,
+        message_schema: Option<MessageSchema>,
+        envelope: bool,
+    ) -> PyResult<bool> {
+        let idle_ttl = ttl_from_secs("idle_ttl_secs", idle_ttl_secs)?;
+        self.maybe_sweep(py);
+        let created = self.insert_channel(
+            name,
+            buffer_size.unwrap_or(self.default_buffer_size),
+            metadata.unwrap_or_default(),
+            max_subscribers,
+            replay_size.unwrap_or(0),
+            idle_ttl,
+            MessagePolicy::new(message_schema, envelope),
         );
-
-        true
+        if created {
+            self.policy.created.fetch_add(1, Ordering::Relaxed);
+            self.notify(py, "created", name);
+        }
+        Ok(created)
     }
 
     /// Remove a channel
@@ -350,28 +904,181 @@ impl ChannelManager {
         self.channels.contains_key(name)
     }
 
+    /// Register a callback for channel lifecycle events, or clear it with None.
+    ///
+    /// The hook is called as `hook(event, channel_name)`, where `event` is
+    /// "created", "auto_created" or "expired", after the change and with no
+    /// channel lock held. Exceptions are logged and ignored.
+    #[pyo3(signature = (hook))]
+    pub fn set_channel_hook(&self, hook: Option<Py<PyAny>>) {
+        *self.channel_hook.write() = hook;
+    }
+
+    /// Remove idle channels now instead of waiting for the lazy sweep.
+    /// Returns the number removed.
+    pub fn sweep_idle(&self, py: Python<'_>) -> usize {
+        *self.policy.last_sweep.lock() = Instant::now();
+        self.sweep(py)
+    }
+
+    /// Channel creation and collection counters: `created` (by
+    /// `create_channel`), `auto_created` (on first use), `expired`
+    /// (removed by the idle sweep), and `restored_channels` /
+    /// `restored_messages` (replay history from the previous generation)
+    pub fn lifecycle_stats<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let stats = PyDict::new(py);
+        stats.set_item("created", self.policy.created.load(Ordering::Relaxed))?;
+        stats.set_item(
+            "auto_created",
+            self.policy.auto_created.load(Ordering::Relaxed),
+        )?;
+        stats.set_item("expired", self.policy.expired.load(Ordering::Relaxed))?;
+        stats.set_item(
+            "restored_channels",
+            self.restored.channels.load(Ordering::Relaxed),
+        )?;
+        stats.set_item(
+            "restored_messages",
+            self.restored.messages.load(Ordering::Relaxed),
+        )?;
+        Ok(stats)
+    }
+
+    /// Register the hook vetting subscriptions, or clear it with None.
+    ///
+    /// The hook is called as `hook(channel_name, client_id, metadata)` before
+    /// a subscription is accepted and returns True to allow it. It runs with
+    /// the GIL held on the subscribing thread, so keep it fast: look up a
+    /// cached permission rather than making network calls. Returning False
+    /// or raising makes `subscribe` raise `SubscriptionError`.
+    #[pyo3(signature = (hook))]
+    pub fn set_subscribe_hook(&self, hook: Option<Py<PyAny>>) {
+        *self.subscribe_hook.write() = hook;
+    }
+
+    /// Register the hook vetting publishes, or clear it with None.
+    ///
+    /// The hook is called as `hook(channel_name, client_id, message)`, where
+    /// `client_id` is the one passed to `publish` (None when omitted), and
+    /// returns True to allow the message. Same constraints as the subscribe
+    /// hook; a refusal makes `publish` raise `PublishError`.
+    #[pyo3(signature = (hook))]
+    pub fn set_publish_hook(&self, hook: Option<Py<PyAny>>) {
+        *self.publish_hook.write() = hook;
+    }
+
     /// Subscribe a client to a channel, returns a Subscriber handle
-    pub fn subscribe(&self, channel_name: &str, client_id: &str) -> PyResult<Subscriber> {
+    ///
+    /// Args:
+    ///     channel_name: Channel to subscribe to
+    ///     client_id: Subscribing client
+    ///     metadata: Dict passed to the subscribe hook, e.g. the user's roles
+    ///     bypass_hooks: Skip the subscribe hook, for server-internal
+    ///         subscriptions; `max_subscribers` still applies
+    ///     queue: Deliver through a per-client queue with this capacity and
+    ///         overflow policy instead of the shared ring buffer. A client
+    ///         closed by `OverflowPolicy.Disconnect` is unsubscribed.
+    ///
+    /// Raises:
+    ///     KeyError: The channel does not exist and may not be auto-created
+    ///     SubscriptionError: The channel is full or the hook refused
+    #[pyo3(signature = (channel_name, client_id, metadata=None, bypass_hooks=false, queue=None))]
+    pub fn subscribe(
+        &self,
+        py: Python<'_>,
+        channel_name: &str,
+        client_id: &str,
+        metadata: Option<Bound<'_, PyDict>>,
+        bypass_hooks: bool,
+        queue: Option<ClientQueueConfig>,
+    ) -> PyResult<Subscriber> {
+        self.maybe_sweep(py);
+        self.check_exists(channel_name)?;
+        if !bypass_hooks {
+            // Called without holding the channel's lock, so the hook may use
+            // this manager
+            let metadata = metadata.unwrap_or_else(|| PyDict::new(py));
+            match ask_hook(
+                py,
+                &self.subscribe_hook,
+                (channel_name, client_id, metadata),
+            ) {
+                HookDecision::Allow => {}
+                HookDecision::Deny => {
+                    self.count_denied_subscription(channel_name);
+                    return Err(SubscriptionError::new_err(format!(
+                        "Subscription to channel '{}' denied for client '{}'",
+                        channel_name, client_id
+                    )));
+                }
+                HookDecision::Failed(cause) => {
+                    self.count_denied_subscription(channel_name);
+                    crate::hlog_warn!(
+                        "Subscribe hook raised for channel '{}', client '{}': {}",
+                        channel_name,
+                        client_id,
+                        cause
+                    );
+                    let err = SubscriptionError::new_err(format!(
+                        "Subscribe hook failed for channel '{}', client '{}'",
+                        channel_name, client_id
+                    ));
+                    err.set_cause(py, Some(cause));
+                    return Err(err);
+                }
+            }
+        }
+
+        // Created only once the hook has accepted, so refused names leave
+        // nothing behind
+        self.auto_create(py, channel_name);
         let receiver = {
-            let mut channel = self.channels.get_mut(channel_name).ok_or_else(|| {
-                pyo3::exceptions::PyKeyError::new_err(format!(
-                    "Channel '{}' does not exist",
-                    channel_name
-                ))
-            })?;
+            let mut channel = self
+                .channels
+                .get_mut(channel_name)
+                .ok_or_else(|| Self::missing_channel(channel_name))?;
+            if let Some(limit) = channel.max_subscribers {
+                if channel.subscribers.len() >= limit && !channel.subscribers.contains(client_id) {
+                    channel.denied_subscriptions.fetch_add(1, Ordering::Relaxed);
+                    return Err(SubscriptionError::new_err(format!(
+                        "Channel '{}' is full (max_subscribers={})",
+                        channel_name, limit
+                    )));
+                }
+            }
             channel.subscribers.insert(client_id.to_string());
+            channel.touch();
             channel.sender.subscribe()
         };
 
         // Also register with topic matcher for pattern-based routing
         self.topic_matcher.subscribe(channel_name, client_id);
 
+        let state = match queue {
+            Some(config) => {
+                let channels = self.channels.clone();
+                let topic_matcher = self.topic_matcher.clone();
+                let (channel, client) = (channel_name.to_string(), client_id.to_string());
+                SubscriberState::queued(ClientQueue::spawn(config, receiver, move || {
+                    topic_matcher.unsubscribe(&channel, &client);
+                    if let Some(mut inner) = channels.get_mut(&channel) {
+                        inner.subscribers.remove(&client);
+                        inner.touch();
+                    }
+                    crate::hlog_debug!(
+                        "Client '{}' disconnected from channel '{}': queue full",
+                        client,
+                        channel
+                    );
+                }))
+            }
+            None => SubscriberState::new(receiver),
+        };
+
         Ok(Subscriber {
             channel_name: channel_name.to_string(),
             client_id: client_id.to_string(),
-            receiver: Arc::new(RwLock::new(receiver)),
-            received_count: AtomicU64::new(0),
-            missed_count: AtomicU64::new(0),
+            state,
         })
     }
 
@@ -380,6 +1087,7 @@ impl ChannelManager {
         self.topic_matcher.unsubscribe(channel_name, client_id);
 
         if let Some(mut channel) = self.channels.get_mut(channel_name) {
+            channel.touch();
             channel.subscribers.remove(client_id)
         } else {
             false
@@ -388,40 +1096,86 @@ impl ChannelManager {
 
     /// Publish a message to a channel
     /// Returns the number of receivers that got the message
-    pub fn publish(&self, channel_name: &str, message: &str) -> PyResult<usize> {
-        let channel = self.channels.get(channel_name).ok_or_else(|| {
-            pyo3::exceptions::PyKeyError::new_err(format!(
-                "Channel '{}' does not exist",
-                channel_name
-            ))
-        })?;
-
-        channel.total_messages.fetch_add(1, Ordering::Relaxed);
-
-        match channel.sender.send(message.to_string()) {
-            Ok(n) => Ok(n),
-            Err(_) => {
-                // No active receivers
-                Ok(0)
-            }
+    ///
+    /// Args:
+    ///     channel_name: Channel to publish to
+    ///     message: Message text
+    ///     client_id: Publishing client, passed to the publish hook
+    ///     bypass_hooks: Skip the publish hook, for server-internal messages
+    ///     priority: `Priority` or `"high"`, `"normal"` (default) or `"low"`;
+    ///         per-client queues deliver more urgent messages first
+    ///
+    /// Raises:
+    ///     KeyError: The channel does not exist and may not be auto-created
+    ///     PublishError: The message failed the channel's schema, or the
+    ///         publish hook refused
+    ///     ValueError: Unknown priority
+    #[pyo3(signature = (channel_name, message, client_id=None, bypass_hooks=false, priority=None))]
+    pub fn publish(
+        &self,
+        py: Python<'_>,
+        channel_name: &str,
+        message: &str,
+        client_id: Option<&str>,
+        bypass_hooks: bool,
+        priority: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<usize> {
+        let priority = Priority::extract(priority)?;
+        self.maybe_sweep(py);
+        self.check_exists(channel_name)?;
+        self.check_message(channel_name, message)?;
+        if !bypass_hooks {
+            self.check_publish(py, channel_name, client_id, message)?;
         }
+        self.auto_create(py, channel_name);
+        let channel = self
+            .channels
+            .get(channel_name)
+            .ok_or_else(|| Self::missing_channel(channel_name))?;
+
+        Ok(channel.deliver(channel_name, message, priority))
     }
 
     /// Publish a message to all channels matching a topic pattern
     /// Returns total number of receivers across all matched channels
-    pub fn publish_to_topic(&self, topic: &str, message: &str) -> usize {
+    ///
+    /// Channels the publish hook refuses are skipped and counted in their
+    /// `denied_publishes`; channels whose schema rejects the message are
+    /// skipped and counted in their `invalid_messages`.
+    #[pyo3(signature = (topic, message, client_id=None, bypass_hooks=false, priority=None))]
+    pub fn publish_to_topic(
+        &self,
+        py: Python<'_>,
+        topic: &str,
+        message: &str,
+        client_id: Option<&str>,
+        bypass_hooks: bool,
+        priority: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<usize> {
+        let priority = Priority::extract(priority)?;
+        let matched: Vec<String> = self
+            .channels
+            .iter()
+            .filter(|entry| {
+                TopicMatcher::pattern_matches(topic, entry.key())
+                    || TopicMatcher::pattern_matches(entry.key(), topic)
+            })
+            .map(|entry| entry.key().clone())
+            .collect();
+
         let mut total = 0;
-        for entry in self.channels.iter() {
-            if TopicMatcher::pattern_matches(topic, entry.key())
-                || TopicMatcher::pattern_matches(entry.key(), topic)
-            {
-                entry.total_messages.fetch_add(1, Ordering::Relaxed);
-                if let Ok(n) = entry.sender.send(message.to_string()) {
-                    total += n;
-                }
+        for name in matched {
+            if self.check_message(&name, message).is_err() {
+                continue;
+            }
+            if !bypass_hooks && self.check_publish(py, &name, client_id, message).is_err() {
+                continue;
+            }
+            if let Some(channel) = self.channels.get(&name) {
+                total += channel.deliver(&name, message, priority);
             }
         }
-        total
+        Ok(total)
     }
 
     /// Get stats for a channel
@@ -439,11 +1193,75 @@ impl ChannelManager {
             total_messages: channel.total_messages.load(Ordering::Relaxed),
             dropped_messages: channel.dropped_messages.load(Ordering::Relaxed),
             metadata: channel.metadata.clone(),
+            max_subscribers: channel.max_subscribers,
+            denied_subscriptions: channel.denied_subscriptions.load(Ordering::Relaxed),
+            denied_publishes: channel.denied_publishes.load(Ordering::Relaxed),
+            invalid_messages: channel.invalid_messages.load(Ordering::Relaxed),
+            replay_size: channel.replay.capacity(),
+            last_seq: channel.replay.last_seq(),
         })
     }
 
+    /// Retained messages published after `cursor`.
+    ///
+    /// Returns `(messages, next_cursor)`; pass `next_cursor` back to read on
+    /// from there. Messages older than the channel's `replay_size` are gone,
+    /// and a cursor ahead of the channel reads from its latest message.
+    /// With `max_low`, only the newest `max_low` low-priority messages are
+    /// returned, so a reconnecting client is not flooded with stale ones.
+    ///
+    /// Raises:
+    ///     KeyError: The channel does not exist
+    #[pyo3(signature = (channel_name, cursor=0, max_low=None))]
+    pub fn messages_since(
+        &self,
+        channel_name: &str,
+        cursor: u64,
+        max_low: Option<usize>,
+    ) -> PyResult<(Vec<String>, u64)> {
+        let channel = self
+            .channels
+            .get(channel_name)
+            .ok_or_else(|| Self::missing_channel(channel_name))?;
+        Ok(channel.replay.since(cursor, max_low))
+    }
+
+    /// Sequence number of the latest message published on a channel
+    pub fn last_seq(&self, channel_name: &str) -> PyResult<u64> {
+        let channel = self
+            .channels
+            .get(channel_name)
+            .ok_or_else(|| Self::missing_channel(channel_name))?;
+        Ok(channel.replay.last_seq())
+    }
+
+    /// Serialize every channel's sequence counter and newest retained
+    /// messages, as saved for the next generation on a graceful reload.
+    /// `max_bytes` caps the message text (default: `handoff_max_bytes`).
+    #[pyo3(signature = (max_bytes=None))]
+    pub fn export_replay<'py>(
+        &self,
+        py: Python<'py>,
+        max_bytes: Option<usize>,
+    ) -> Bound<'py, PyBytes> {
+        let snapshot = py.detach(|| self.replay_store().export(max_bytes).encode());
+        PyBytes::new(py, &snapshot)
+    }
+
+    /// Restore history from `export_replay` into channels that exist and
+    /// have not published yet, so sequence numbers continue where the
+    /// snapshot left off. Returns `(channels, messages)` restored.
+    ///
+    /// Raises:
+    ///     ValueError: The snapshot is corrupt or from another version
+    pub fn import_replay(&self, data: &[u8]) -> PyResult<(usize, usize)> {
+        let snapshot = ManagerSnapshot::decode(data).map_err(PyValueError::new_err)?;
+        Ok(self.replay_store().import(snapshot))
+    }
+
     /// List all channel names
-    pub fn list_channels(&self) -> Vec<String> {
+    pub fn list_channels(&self, py: Python<'_>) -> Vec<String> {
+        self.maybe_sweep(py);
         self.channels.iter().map(|e| e.key().clone()).collect()
     }
 
@@ -466,7 +1284,8 @@ impl ChannelManager {
     }
 
     /// Get total channel count
-    pub fn channel_count(&self) -> usize {
+    pub fn channel_count(&self, py: Python<'_>) -> usize {
+        self.maybe_sweep(py);
         self.channels.len()
     }
 
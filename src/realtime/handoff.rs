@@ -0,0 +1,277 @@
+//! Replay history handed from one worker generation to the next.
+//!
+//! A graceful reload replaces every worker, and the new ones would start
+//! with empty channels: sequence numbers restart at 1 and cursors taken
+//! before the reload point at messages that no longer exist. Managers
+//! created with a `handoff_key` avoid that. A draining worker writes the
+//! sequence counters and newest retained messages of their channels to a
+//! file in a tmpfs directory, and its replacement in the same slot reads it
+//! back before it is marked healthy.
+//!
+//! Files are named after the server process, the generation that wrote them
+//! and the worker slot, and are removed once read; the server removes any
+//! left over when it exits. A missing or corrupt file is logged and the
+//! worker starts with empty history.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::realtime::channel::ReplayStore;
+use crate::realtime::replay::ReplayEntry;
+
+/// Bumped whenever the snapshot layout changes; other versions are ignored
+pub const FORMAT_VERSION: u32 = 2;
+
+/// Default cap on the message text saved per manager
+pub const DEFAULT_MAX_BYTES: usize = 4 * 1024 * 1024;
+
+/// Generation of the workers this process spawns; the server bumps it
+/// before each graceful respawn and forked workers inherit it
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Pid of the server process, shared by every generation of its workers
+static OWNER: AtomicU32 = AtomicU32::new(0);
+
+/// Managers taking part in the handoff, by key
+static STORES: Mutex<Vec<(String, ReplayStore)>> = Mutex::new(Vec::new());
+
+/// One channel's sequence counter and retained messages
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChannelSnapshot {
+    pub name: String,
+    pub last_seq: u64,
+    /// `(seq, priority, message)`, oldest first
+    pub messages: Vec<ReplayEntry>,
+}
+
+/// The replay history of one manager
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManagerSnapshot {
+    pub version: u32,
+    pub channels: Vec<ChannelSnapshot>,
+}
+
+impl ManagerSnapshot {
+    /// Keep every channel's sequence counter and as many of its newest
+    /// messages as fit in `max_bytes` of message text. Channels take one
+    /// message each in turn, so a busy channel cannot crowd out the others,
+    /// and what a channel keeps is always its newest run of messages.
+    pub fn bounded(mut channels: Vec<ChannelSnapshot>, max_bytes: usize) -> Self {
+        channels.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut kept = vec![0usize; channels.len()];
+        let mut open: Vec<bool> = channels.iter().map(|c| !c.messages.is_empty()).collect();
+        let mut budget = max_bytes;
+        while open.iter().any(|open| *open) {
+            for (i, channel) in channels.iter().enumerate() {
+                if !open[i] {
+                    continue;
+                }
+                let total = channel.messages.len();
+                let size = channel.messages[total - 1 - kept[i]].2.len();
+                if size > budget {
+                    open[i] = false;
+                    continue;
+                }
+                budget -= size;
+                kept[i] += 1;
+                open[i] = kept[i] < total;
+            }
+        }
+        for (channel, kept) in channels.iter_mut().zip(kept) {
+            let dropped = channel.messages.len() - kept;
+            channel.messages.drain(..dropped);
+        }
+        Self {
+            version: FORMAT_VERSION,
+            channels,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, String> {
+        let snapshot: Self =
+            serde_json::from_slice(data).map_err(|err| format!("corrupt snapshot: {}", err))?;
+        snapshot.check_version()?;
+        Ok(snapshot)
+    }
+
+    fn check_version(&self) -> Result<(), String> {
+        if self.version == FORMAT_VERSION {
+            Ok(())
+        } else {
+            Err(format!(
+                "snapshot version {} is not supported (expected {})",
+                self.version, FORMAT_VERSION
+            ))
+        }
+    }
+}
+
+/// Channels restored from a snapshot and the messages they got back
+#[derive(Default)]
+pub struct RestoreStats {
+    pub channels: AtomicU64,
+    pub messages: AtomicU64,
+}
+
+/// Include a manager's channels in the handoff, replacing any manager
+/// registered under the same key
+pub(crate) fn register(key: &str, store: ReplayStore) {
+    let mut stores = STORES.lock();
+    stores.retain(|(existing, _)| existing != key);
+    stores.push((key.to_string(), store));
+}
+
+/// Record the server process that owns the handoff files; called before
+/// the first workers are forked
+pub fn begin() {
+    OWNER.store(std::process::id(), Ordering::Relaxed);
+}
+
+/// Move to the next generation; called before a graceful respawn so the
+/// new workers read what the old ones saved
+pub fn next_generation() {
+    GENERATION.fetch_add(1, Ordering::AcqRel);
+}
+
+fn dir() -> PathBuf {
+    let shm = PathBuf::from("/dev/shm");
+    if shm.is_dir() {
+        shm
+    } else {
+        std::env::temp_dir()
+    }
+}
+
+fn prefix() -> String {
+    format!("hypern-replay-{}-", OWNER.load(Ordering::Relaxed))
+}
+
+fn path(generation: u64, worker_id: usize) -> PathBuf {
+    dir().join(format!("{}g{}-w{}.json", prefix(), generation, worker_id))
+}
+
+/// Write the history of every registered manager for this worker's
+/// replacement; called once the worker has drained
+pub fn save(worker_id: usize) {
+    let stores: Vec<(String, ReplayStore)> = STORES.lock().clone();
+    if stores.is_empty() {
+        return;
+    }
+    let mut managers = HashMap::with_capacity(stores.len());
+    let mut channels = 0;
+    for (key, store) in stores {
+        let snapshot = store.export(None);
+        channels += snapshot.channels.len();
+        managers.insert(key, snapshot);
+    }
+    let target = path(GENERATION.load(Ordering::Acquire), worker_id);
+    match write(&target, &managers) {
+        Ok(()) => crate::hlog_info!(
+            "Worker {} saved replay history of {} channel(s) for the next generation",
+            worker_id,
+            channels
+        ),
+        Err(err) => crate::hlog_warn!(
+            "Worker {} could not save replay history to {}: {}",
+            worker_id,
+            target.display(),
+            err
+        ),
+    }
+}
+
+fn write(target: &PathBuf, managers: &HashMap<String, ManagerSnapshot>) -> std::io::Result<()> {
+    let data = serde_json::to_vec(managers)?;
+    // Written aside and renamed, so a reader never sees half a file
+    let partial = target.with_extension("partial");
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    std::io::Write::write_all(&mut options.open(&partial)?, &data)?;
+    std::fs::rename(&partial, target)
+}
+
+/// Load the history the previous generation saved for this worker slot
+/// into the registered managers; called before the worker is marked healthy
+pub fn restore(worker_id: usize) {
+    let generation = GENERATION.load(Ordering::Acquire);
+    if generation == 0 || STORES.lock().is_empty() {
+        return;
+    }
+    let source = path(generation - 1, worker_id);
+    let data = match std::fs::read(&source) {
+        Ok(data) => data,
+        Err(err) => {
+            crate::hlog_warn!(
+                "Worker {} starts with empty replay history: cannot read {}: {}",
+                worker_id,
+                source.display(),
+                err
+            );
+            return;
+        }
+    };
+    let _ = std::fs::remove_file(&source);
+    let managers: HashMap<String, ManagerSnapshot> = match serde_json::from_slice(&data) {
+        Ok(managers) => managers,
+        Err(err) => {
+            crate::hlog_warn!(
+                "Worker {} starts with empty replay history: corrupt snapshot {}: {}",
+                worker_id,
+                source.display(),
+                err
+            );
+            return;
+        }
+    };
+    let stores: Vec<(String, ReplayStore)> = STORES.lock().clone();
+    let (mut channels, mut messages) = (0, 0);
+    for (key, snapshot) in managers {
+        let Some((_, store)) = stores.iter().find(|(existing, _)| *existing == key) else {
+            continue;
+        };
+        if let Err(err) = snapshot.check_version() {
+            crate::hlog_warn!(
+                "Worker {} skipped replay history of '{}': {}",
+                worker_id,
+                key,
+                err
+            );
+            continue;
+        }
+        let (restored_channels, restored_messages) = store.import(snapshot);
+        channels += restored_channels;
+        messages += restored_messages;
+    }
+    crate::hlog_info!(
+        "Worker {} restored {} message(s) on {} channel(s) from the previous generation",
+        worker_id,
+        messages,
+        channels
+    );
+}
+
+/// Remove handoff files nobody read; called by the server when it exits
+pub fn discard() {
+    if OWNER.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    let prefix = prefix();
+    let Ok(entries) = std::fs::read_dir(dir()) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().starts_with(&prefix) {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
@@ -1,9 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use dashmap::DashMap;
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 
 /// Configuration for heartbeat monitoring
@@ -25,36 +26,79 @@ pub struct HeartbeatConfig {
     /// Whether to send keepalive comments for SSE
     #[pyo3(get, set)]
     pub send_keepalive: bool,
+    /// Widen a client's timeout when its pongs arrive irregularly
+    #[pyo3(get, set)]
+    pub adaptive: bool,
+    /// Ceiling of an adaptive timeout (seconds); None for 3x the base timeout
+    #[pyo3(get, set)]
+    pub max_timeout_secs: Option<f64>,
+    /// Number of recent pong inter-arrival times kept per client
+    #[pyo3(get, set)]
+    pub jitter_window: usize,
 }
 
 #[pymethods]
 impl HeartbeatConfig {
+    /// Create a heartbeat configuration.
+    ///
+    /// Args:
+    ///     adaptive: Widen each client's timeout from the jitter of its recent
+    ///         pongs, up to `max_timeout_secs`, instead of declaring a client
+    ///         on a flaky network dead at the first late pong
+    ///     max_timeout_secs: Ceiling of an adaptive timeout; defaults to three
+    ///         times the client's base timeout
+    ///     jitter_window: Recent pong inter-arrival times kept per client
     #[new]
-    #[pyo3(signature = (interval_secs=30.0, timeout_secs=90.0, max_retries=5, sse_retry_ms=3000, send_keepalive=true))]
+    #[pyo3(signature = (
+        interval_secs=30.0,
+        timeout_secs=90.0,
+        max_retries=5,
+        sse_retry_ms=3000,
+        send_keepalive=true,
+        adaptive=false,
+        max_timeout_secs=None,
+        jitter_window=8,
+    ))]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         interval_secs: f64,
         timeout_secs: f64,
         max_retries: u32,
         sse_retry_ms: u64,
         send_keepalive: bool,
-    ) -> Self {
-        Self {
+        adaptive: bool,
+        max_timeout_secs: Option<f64>,
+        jitter_window: usize,
+    ) -> PyResult<Self> {
+        if max_timeout_secs.is_some_and(|max| max < timeout_secs) {
+            return Err(PyValueError::new_err(
+                "max_timeout_secs must be at least timeout_secs",
+            ));
+        }
+        if jitter_window < 2 {
+            return Err(PyValueError::new_err("jitter_window must be at least 2"));
+        }
+        Ok(Self {
             interval_secs,
             timeout_secs,
             max_retries,
             sse_retry_ms,
             send_keepalive,
-        }
+            adaptive,
+            max_timeout_secs,
+            jitter_window,
+        })
     }
 
     fn __repr__(&self) -> String {
         format!(
-            "HeartbeatConfig(interval={}s, timeout={}s, retries={}, sse_retry={}ms, keepalive={})",
+            "HeartbeatConfig(interval={}s, timeout={}s, retries={}, sse_retry={}ms, keepalive={}, adaptive={})",
             self.interval_secs,
             self.timeout_secs,
             self.max_retries,
             self.sse_retry_ms,
             self.send_keepalive,
+            self.adaptive,
         )
     }
 }
@@ -67,6 +111,9 @@ impl Default for HeartbeatConfig {
             max_retries: 5,
             sse_retry_ms: 3000,
             send_keepalive: true,
+            adaptive: false,
+            max_timeout_secs: None,
+            jitter_window: 8,
         }
     }
 }
@@ -90,6 +137,9 @@ pub struct HeartbeatStats {
     /// Currently timed-out clients
     #[pyo3(get)]
     pub timed_out_clients: usize,
+    /// Per-client timing, by client ID
+    #[pyo3(get)]
+    pub clients: HashMap<String, HeartbeatClientStats>,
 }
 
 #[pymethods]
@@ -106,6 +156,51 @@ impl HeartbeatStats {
     }
 }
 
+/// Observed timing of one client, for debugging timeouts
+#[pyclass(from_py_object)]
+#[derive(Clone, Debug, Default)]
+pub struct HeartbeatClientStats {
+    /// Ping interval in effect for the client (seconds)
+    #[pyo3(get)]
+    pub interval_secs: f64,
+    /// Mean time between the client's recent pongs; None before two pongs
+    #[pyo3(get)]
+    pub mean_interval_secs: Option<f64>,
+    /// Standard deviation of the time between recent pongs
+    #[pyo3(get)]
+    pub jitter_secs: Option<f64>,
+    /// Silence after which the client times out (seconds)
+    #[pyo3(get)]
+    pub effective_timeout_secs: f64,
+    /// Inter-arrival times the statistics are based on
+    #[pyo3(get)]
+    pub samples: usize,
+    /// Whether `set_client_config` overrides the monitor's config
+    #[pyo3(get)]
+    pub overridden: bool,
+}
+
+#[pymethods]
+impl HeartbeatClientStats {
+    fn __repr__(&self) -> String {
+        format!(
+            "HeartbeatClientStats(interval={}s, mean={:?}, jitter={:?}, timeout={}s, samples={})",
+            self.interval_secs,
+            self.mean_interval_secs,
+            self.jitter_secs,
+            self.effective_timeout_secs,
+            self.samples,
+        )
+    }
+}
+
+/// Interval and missed-beat allowance set for one client
+#[derive(Clone, Copy, Debug)]
+struct ClientOverride {
+    interval_secs: f64,
+    max_missed: u32,
+}
+
 /// Per-client heartbeat state
 struct ClientHeartbeat {
     last_ping: f64,
@@ -113,6 +208,81 @@ struct ClientHeartbeat {
     retry_count: u32,
     is_alive: AtomicBool,
     last_event_id: Option<String>,
+    config: Option<ClientOverride>,
+    /// When the last pong arrived; None until the first one
+    last_beat: Option<f64>,
+    /// Recent pong inter-arrival times, oldest first
+    arrivals: VecDeque<f64>,
+    /// Silence after which the client times out, updated on each pong
+    timeout: f64,
+}
+
+impl ClientHeartbeat {
+    fn interval(&self, config: &HeartbeatConfig) -> f64 {
+        self.config
+            .map_or(config.interval_secs, |own| own.interval_secs)
+    }
+
+    fn base_timeout(&self, config: &HeartbeatConfig) -> f64 {
+        self.config.map_or(config.timeout_secs, |own| {
+            own.interval_secs * own.max_missed as f64
+        })
+    }
+
+    /// Mean and standard deviation of the recent inter-arrival times
+    fn jitter(&self) -> Option<(f64, f64)> {
+        if self.arrivals.len() < 2 {
+            return None;
+        }
+        let n = self.arrivals.len() as f64;
+        let mean = self.arrivals.iter().sum::<f64>() / n;
+        let variance = self
+            .arrivals
+            .iter()
+            .map(|t| (t - mean).powi(2))
+            .sum::<f64>()
+            / n;
+        Some((mean, variance.sqrt()))
+    }
+
+    /// The base timeout, or in adaptive mode the base scaled to the
+    /// client's observed pace plus four deviations, as TCP sizes its
+    /// retransmission timeout, capped at the ceiling
+    fn effective_timeout(&self, config: &HeartbeatConfig) -> f64 {
+        let base = self.base_timeout(config);
+        if !config.adaptive {
+            return base;
+        }
+        let Some((mean, jitter)) = self.jitter() else {
+            return base;
+        };
+        let ceiling = config.max_timeout_secs.unwrap_or(base * 3.0).max(base);
+        let pace = (mean / self.interval(config)).max(1.0);
+        (base * pace + 4.0 * jitter).clamp(base, ceiling)
+    }
+
+    fn record_beat(&mut self, now: f64, config: &HeartbeatConfig) {
+        if let Some(previous) = self.last_beat {
+            if self.arrivals.len() >= config.jitter_window {
+                self.arrivals.pop_front();
+            }
+            self.arrivals.push_back(now - previous);
+        }
+        self.last_beat = Some(now);
+        self.timeout = self.effective_timeout(config);
+    }
+
+    fn stats(&self, config: &HeartbeatConfig) -> HeartbeatClientStats {
+        let jitter = self.jitter();
+        HeartbeatClientStats {
+            interval_secs: self.interval(config),
+            mean_interval_secs: jitter.map(|(mean, _)| mean),
+            jitter_secs: jitter.map(|(_, jitter)| jitter),
+            effective_timeout_secs: self.timeout,
+            samples: self.arrivals.len(),
+            overridden: self.config.is_some(),
+        }
+    }
 }
 
 /// Server-side heartbeat monitor for SSE and WebSocket connections
@@ -168,10 +338,76 @@ impl HeartbeatMonitor {
                 retry_count: 0,
                 is_alive: AtomicBool::new(true),
                 last_event_id,
+                config: None,
+                last_beat: None,
+                arrivals: VecDeque::with_capacity(self.config.jitter_window),
+                timeout: self.config.timeout_secs,
             },
         );
     }
 
+    /// Give one client its own ping interval and allow it `max_missed`
+    /// intervals without a pong before it times out; in adaptive mode that
+    /// is the base its timeout widens from. Returns False for an unknown
+    /// client. The override goes away with the client.
+    pub fn set_client_config(
+        &self,
+        client_id: &str,
+        interval_secs: f64,
+        max_missed: u32,
+    ) -> PyResult<bool> {
+        if !interval_secs.is_finite() || interval_secs <= 0.0 {
+            return Err(PyValueError::new_err("interval_secs must be positive"));
+        }
+        if max_missed == 0 {
+            return Err(PyValueError::new_err("max_missed must be at least 1"));
+        }
+        let Some(mut client) = self.clients.get_mut(client_id) else {
+            return Ok(false);
+        };
+        client.config = Some(ClientOverride {
+            interval_secs,
+            max_missed,
+        });
+        client.timeout = client.effective_timeout(&self.config);
+        Ok(true)
+    }
+
+    /// The client's override as `{"interval_secs", "max_missed"}`, None
+    /// when it uses the monitor's config or is unknown
+    pub fn get_client_config(&self, client_id: &str) -> Option<HashMap<String, f64>> {
+        let own = self.clients.get(client_id)?.config?;
+        Some(HashMap::from([
+            ("interval_secs".to_string(), own.interval_secs),
+            ("max_missed".to_string(), own.max_missed as f64),
+        ]))
+    }
+
+    /// Return a client to the monitor's config; False if it had no override
+    pub fn clear_client_config(&self, client_id: &str) -> bool {
+        let Some(mut client) = self.clients.get_mut(client_id) else {
+            return false;
+        };
+        let had = client.config.take().is_some();
+        client.timeout = client.effective_timeout(&self.config);
+        had
+    }
+
+    /// Observed timing of one client, None if it is not registered
+    pub fn client_stats(&self, client_id: &str) -> Option<HeartbeatClientStats> {
+        self.clients
+            .get(client_id)
+            .map(|client| client.stats(&self.config))
+    }
+
+    /// Shortest ping interval of any client, for the loop driving the monitor
+    pub fn loop_interval_secs(&self) -> f64 {
+        self.clients
+            .iter()
+            .filter_map(|e| e.config.map(|own| own.interval_secs))
+            .fold(self.config.interval_secs, f64::min)
+    }
+
     /// Unregister a client
     pub fn unregister(&self, client_id: &str) -> bool {
         self.clients.remove(client_id).is_some()
@@ -191,7 +427,9 @@ impl HeartbeatMonitor {
     /// Record that a pong was received from a client
     pub fn pong(&self, client_id: &str) -> bool {
         if let Some(mut client) = self.clients.get_mut(client_id) {
-            client.last_pong = now_secs();
+            let now = now_secs();
+            client.last_pong = now;
+            client.record_beat(now, &self.config);
             client.retry_count = 0;
             client.is_alive.store(true, Ordering::Relaxed);
             self.total_pongs.fetch_add(1, Ordering::Relaxed);
@@ -202,15 +440,14 @@ impl HeartbeatMonitor {
     }
 
     /// Check for timed-out clients
-    /// Returns list of client IDs that have exceeded the timeout
+    /// Returns list of client IDs that have exceeded their effective timeout
     pub fn check_timeouts(&self) -> Vec<String> {
         let now = now_secs();
-        let timeout = self.config.timeout_secs;
         let mut timed_out = Vec::new();
 
         for mut entry in self.clients.iter_mut() {
             let client = entry.value_mut();
-            if now - client.last_pong > timeout {
+            if now - client.last_pong > client.timeout {
                 if client.is_alive.load(Ordering::Relaxed) {
                     client.is_alive.store(false, Ordering::Relaxed);
                     client.retry_count += 1;
@@ -275,16 +512,15 @@ impl HeartbeatMonitor {
             .and_then(|c| c.last_event_id.clone())
     }
 
-    /// Get all clients that need a ping (last_ping older than interval)
+    /// Get all clients that need a ping (last_ping older than their interval)
     pub fn clients_needing_ping(&self) -> Vec<String> {
         let now = now_secs();
-        let interval = self.config.interval_secs;
 
         self.clients
             .iter()
             .filter(|e| {
                 let c = e.value();
-                c.is_alive.load(Ordering::Relaxed) && (now - c.last_ping > interval)
+                c.is_alive.load(Ordering::Relaxed) && (now - c.last_ping > c.interval(&self.config))
             })
             .map(|e| e.key().clone())
             .collect()
@@ -321,11 +557,10 @@ impl HeartbeatMonitor {
     /// Get monitor statistics
     pub fn stats(&self) -> HeartbeatStats {
         let now = now_secs();
-        let timeout = self.config.timeout_secs;
         let timed_out = self
             .clients
             .iter()
-            .filter(|e| now - e.last_pong > timeout)
+            .filter(|e| now - e.last_pong > e.timeout)
             .count();
 
         HeartbeatStats {
@@ -334,6 +569,11 @@ impl HeartbeatMonitor {
             total_pongs: self.total_pongs.load(Ordering::Relaxed),
             total_timeouts: self.total_timeouts.load(Ordering::Relaxed),
             timed_out_clients: timed_out,
+            clients: self
+                .clients
+                .iter()
+                .map(|e| (e.key().clone(), e.stats(&self.config)))
+                .collect(),
         }
     }
 
@@ -0,0 +1,91 @@
+//! Identity of a realtime client.
+//!
+//! Clients never choose their own ID, or anyone could take over another
+//! user's presence. When the auth middleware authenticated the request the
+//! client ID is `{user_id}:{nonce}`, otherwise `anon:{nonce}`, with a nonce
+//! drawn per connection (per request for long polls). Two tabs of one user
+//! are therefore two clients sharing a user ID.
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::http::request::Request;
+
+/// Who a realtime connection belongs to
+#[pyclass(frozen, skip_from_py_object)]
+#[derive(Clone, Debug)]
+pub struct RealtimeIdentity {
+    user_id: Option<String>,
+    client_id: String,
+}
+
+impl RealtimeIdentity {
+    /// The identity of a connection opened by `request`, from the user its
+    /// scope records when the auth middleware authenticated one
+    pub fn resolve(request: &Request) -> Self {
+        let user_id = request.scope().and_then(|scope| scope.user_id.clone());
+        let nonce = format!("{:016x}", rand::random::<u64>());
+        let client_id = match &user_id {
+            Some(user_id) => format!("{}:{}", user_id, nonce),
+            None => format!("anon:{}", nonce),
+        };
+        Self { user_id, client_id }
+    }
+
+    pub fn user(&self) -> Option<&str> {
+        self.user_id.as_deref()
+    }
+
+    pub fn client(&self) -> &str {
+        &self.client_id
+    }
+}
+
+#[pymethods]
+impl RealtimeIdentity {
+    /// The authenticated user, None for anonymous clients
+    #[getter]
+    pub fn user_id(&self) -> Option<String> {
+        self.user_id.clone()
+    }
+
+    /// Connection-scoped ID to subscribe and track presence with
+    #[getter]
+    pub fn client_id(&self) -> String {
+        self.client_id.clone()
+    }
+
+    #[getter]
+    pub fn is_authenticated(&self) -> bool {
+        self.user_id.is_some()
+    }
+
+    /// `{"user_id": ..., "client_id": ...}`
+    pub fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("user_id", &self.user_id)?;
+        dict.set_item("client_id", &self.client_id)?;
+        Ok(dict)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "RealtimeIdentity(user_id={:?}, client_id={:?})",
+            self.user_id, self.client_id
+        )
+    }
+}
+
+/// Resolve the identity of a realtime connection opened by `request`.
+///
+/// Use it in SSE and WebSocket handlers instead of an ID sent by the client.
+#[pyfunction]
+pub fn realtime_identity(request: &Bound<'_, Request>) -> RealtimeIdentity {
+    RealtimeIdentity::resolve(request.get())
+}
+
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<RealtimeIdentity>()?;
+    m.add_function(wrap_pyfunction!(realtime_identity, m)?)?;
+    Ok(())
+}
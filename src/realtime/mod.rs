@@ -1,17 +1,74 @@
 //! Live SSE/WebSocket Realtime Infrastructure
 //!
 //! Provides channel/topic abstractions, presence tracking,
-//! backpressure-aware broadcast, and heartbeat/auto-reconnect helpers.
+//! backpressure-aware broadcast, per-client send queues, replay buffers
+//! with a long-poll fallback, and heartbeat/auto-reconnect helpers.
 //!
 //! All types are exposed to Python via PyO3.
 
 pub mod broadcast;
 pub mod channel;
+pub mod handoff;
 pub mod heartbeat;
+pub mod identity;
+pub mod poll;
 pub mod presence;
+pub mod queue;
+pub mod receiver;
+pub mod replay;
+pub mod schema;
 
 // Re-export main types for convenience
 pub use broadcast::{BackpressurePolicy, BroadcastConfig, BroadcastStats, RealtimeBroadcast};
-pub use channel::{ChannelManager, ChannelStats, Subscriber, TopicMatcher};
-pub use heartbeat::{HeartbeatConfig, HeartbeatMonitor, HeartbeatStats};
-pub use presence::{PresenceDiff, PresenceInfo, PresenceTracker};
+pub use channel::{
+    ChannelManager, ChannelStats, PublishError, Subscriber, SubscriptionError, TopicMatcher,
+};
+pub use heartbeat::{HeartbeatClientStats, HeartbeatConfig, HeartbeatMonitor, HeartbeatStats};
+pub use identity::RealtimeIdentity;
+pub use presence::{PresenceDiff, PresenceInfo, PresenceTracker, PresenceUpdate};
+pub use queue::{ClientQueueConfig, ClientQueueStats, OverflowPolicy, Priority};
+pub use schema::MessageSchema;
+
+use pyo3::prelude::*;
+
+/// Register channel, presence, broadcast and heartbeat classes.
+pub fn register_all(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    // Channel / Topic
+    m.add_class::<ChannelManager>()?;
+    m.add_class::<ChannelStats>()?;
+    m.add_class::<Subscriber>()?;
+    m.add_class::<TopicMatcher>()?;
+    m.add("SubscriptionError", m.py().get_type::<SubscriptionError>())?;
+    m.add("PublishError", m.py().get_type::<PublishError>())?;
+    m.add_class::<MessageSchema>()?;
+
+    // Presence
+    m.add_class::<PresenceTracker>()?;
+    m.add_class::<PresenceInfo>()?;
+    m.add_class::<PresenceDiff>()?;
+    m.add_class::<PresenceUpdate>()?;
+
+    // Broadcast
+    m.add_class::<RealtimeBroadcast>()?;
+    m.add_class::<BroadcastConfig>()?;
+    m.add_class::<BroadcastStats>()?;
+    m.add_class::<broadcast::BroadcastSubscriber>()?;
+    m.add_class::<BackpressurePolicy>()?;
+
+    // Per-client queues
+    m.add_class::<ClientQueueConfig>()?;
+    m.add_class::<ClientQueueStats>()?;
+    m.add_class::<OverflowPolicy>()?;
+    m.add_class::<Priority>()?;
+
+    // Heartbeat
+    m.add_class::<HeartbeatMonitor>()?;
+    m.add_class::<HeartbeatConfig>()?;
+    m.add_class::<HeartbeatStats>()?;
+    m.add_class::<HeartbeatClientStats>()?;
+
+    identity::register(m)?;
+    receiver::register(m)?;
+    poll::register(m)?;
+    Ok(())
+}
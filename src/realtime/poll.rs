@@ -0,0 +1,311 @@
+//! Long-poll fallback for realtime channels.
+//!
+//! Some proxies break SSE and WebSockets outright. A long poll,
+//! `GET <path>?channel=X&cursor=N&wait=S`, is answered from the channel's
+//! replay buffer: at once when messages after the cursor are retained,
+//! otherwise on the next publish or when `wait` seconds pass. The wait is a
+//! Tokio timer, so a held poll costs no thread. Responses are
+//! `{"messages": [...], "next_cursor": M}`. With `max_low=K`, only the
+//! newest K low-priority messages after the cursor are included.
+//!
+//! Each poll gets a `RealtimeIdentity` from the auth middleware state; a
+//! `client_id` the client sends is ignored. The per-client poll limit
+//! counts per authenticated user, else per peer address.
+
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::Body;
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use tokio::time::Instant;
+
+use crate::core::reload::ReloadManager;
+use crate::http::request::Request;
+use crate::realtime::channel::ChannelSource;
+use crate::realtime::identity::RealtimeIdentity;
+
+/// Default cap on `wait`, below the 30s idle timeout common in proxies
+pub const DEFAULT_MAX_WAIT_SECS: f64 = 25.0;
+/// Default number of polls one client may hold at once
+pub const DEFAULT_MAX_POLLS_PER_CLIENT: usize = 4;
+
+static ENDPOINT: RwLock<Option<Arc<PollEndpoint>>> = RwLock::new(None);
+
+static ACTIVE: AtomicI64 = AtomicI64::new(0);
+static HELD: AtomicU64 = AtomicU64::new(0);
+static IMMEDIATE: AtomicU64 = AtomicU64::new(0);
+static EXPIRED: AtomicU64 = AtomicU64::new(0);
+static REJECTED: AtomicU64 = AtomicU64::new(0);
+
+/// Long-poll endpoint serving one `ChannelManager`
+pub struct PollEndpoint {
+    path: String,
+    source: ChannelSource,
+    max_wait: Duration,
+    max_polls_per_client: usize,
+    /// Answer polls without an authenticated user with a 401
+    require_auth: bool,
+    /// Called with the `RealtimeIdentity` of every poll that is served
+    on_connect: Option<Arc<Py<PyAny>>>,
+    /// Polls currently held per client
+    polls: DashMap<String, usize>,
+}
+
+/// One held poll; releases the client's slot when the response is done or
+/// the client goes away
+struct PollSlot {
+    endpoint: Arc<PollEndpoint>,
+    client: String,
+}
+
+impl Drop for PollSlot {
+    fn drop(&mut self) {
+        ACTIVE.fetch_sub(1, Ordering::Relaxed);
+        self.endpoint.polls.remove_if_mut(&self.client, |_, count| {
+            *count -= 1;
+            *count == 0
+        });
+    }
+}
+
+impl PollEndpoint {
+    pub(crate) fn new(
+        source: ChannelSource,
+        path: String,
+        max_wait: Duration,
+        max_polls_per_client: usize,
+        require_auth: bool,
+        on_connect: Option<Py<PyAny>>,
+    ) -> Self {
+        Self {
+            path,
+            source,
+            max_wait,
+            max_polls_per_client,
+            require_auth,
+            on_connect: on_connect.map(Arc::new),
+            polls: DashMap::new(),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn acquire(self: &Arc<Self>, client: &str) -> Option<PollSlot> {
+        let mut count = self.polls.entry(client.to_string()).or_insert(0);
+        if *count >= self.max_polls_per_client {
+            return None;
+        }
+        *count += 1;
+        drop(count);
+        ACTIVE.fetch_add(1, Ordering::Relaxed);
+        Some(PollSlot {
+            endpoint: self.clone(),
+            client: client.to_string(),
+        })
+    }
+
+    /// Answer one poll request
+    pub async fn respond(
+        self: &Arc<Self>,
+        req: &Request,
+        rm: &ReloadManager,
+    ) -> axum::http::Response<Body> {
+        let identity = RealtimeIdentity::resolve(req);
+        if self.require_auth && identity.user().is_none() {
+            return error_response(401, "unauthorized", "Authentication required".to_string());
+        }
+        if req.query("client_id").is_some() {
+            crate::hlog_debug!(
+                "Ignoring the client_id sent with a poll; the client is '{}'",
+                identity.client()
+            );
+        }
+
+        let Some(channel) = req.query("channel") else {
+            return error_response(400, "bad_request", "channel is required".to_string());
+        };
+        let cursor = match req.query("cursor").map(|value| value.parse::<u64>()) {
+            None => 0,
+            Some(Ok(cursor)) => cursor,
+            Some(Err(_)) => {
+                return error_response(
+                    400,
+                    "bad_request",
+                    "cursor must be a non-negative integer".to_string(),
+                )
+            }
+        };
+        let max_low = match req.query("max_low").map(|value| value.parse::<usize>()) {
+            None => None,
+            Some(Ok(max_low)) => Some(max_low),
+            Some(Err(_)) => {
+                return error_response(
+                    400,
+                    "bad_request",
+                    "max_low must be a non-negative integer".to_string(),
+                )
+            }
+        };
+        let wait = match req.query("wait").map(|value| value.parse::<f64>()) {
+            None => self.max_wait,
+            Some(Ok(secs)) if secs.is_finite() && secs >= 0.0 => {
+                Duration::from_secs_f64(secs).min(self.max_wait)
+            }
+            Some(_) => {
+                return error_response(
+                    400,
+                    "bad_request",
+                    "wait must be a non-negative number of seconds".to_string(),
+                )
+            }
+        };
+
+        let Some(replay) = self.source.replay(&channel) else {
+            return error_response(
+                404,
+                "not_found",
+                format!("Channel '{}' does not exist", channel),
+            );
+        };
+        if replay.capacity() == 0 {
+            return error_response(
+                400,
+                "bad_request",
+                format!("Channel '{}' keeps no messages for replay", channel),
+            );
+        }
+
+        let client = match identity.user() {
+            Some(user_id) => format!("user:{}", user_id),
+            None => req
+                .connection()
+                .and_then(|c| c.peer_addr())
+                .map(|addr| addr.ip().to_string())
+                .unwrap_or_default(),
+        };
+
+        if self.source.has_subscribe_hook() {
+            let source = self.source.clone();
+            let (hook_channel, hook_identity) = (channel.clone(), identity.clone());
+            let allowed = tokio::task::spawn_blocking(move || {
+                source.authorize(&hook_channel, &hook_identity)
+            })
+            .await
+            .unwrap_or(false);
+            if !allowed {
+                return error_response(
+                    403,
+                    "forbidden",
+                    format!("Poll on channel '{}' denied", channel),
+                );
+            }
+        }
+
+        let Some(_slot) = self.acquire(&client) else {
+            REJECTED.fetch_add(1, Ordering::Relaxed);
+            let mut response = error_response(
+                429,
+                "too_many_polls",
+                format!(
+                    "At most {} concurrent polls per client",
+                    self.max_polls_per_client
+                ),
+            );
+            response
+                .headers_mut()
+                .insert("retry-after", axum::http::HeaderValue::from_static("1"));
+            return response;
+        };
+
+        if let Some(on_connect) = &self.on_connect {
+            let on_connect = on_connect.clone();
+            let _ = tokio::task::spawn_blocking(move || {
+                Python::attach(|py| {
+                    if let Err(e) = on_connect.call1(py, (identity, channel)) {
+                        crate::hlog_warn!("Realtime on_connect callback raised: {}", e);
+                    }
+                })
+            })
+            .await;
+        }
+
+        let (messages, next_cursor) = replay.since(cursor, max_low);
+        if next_cursor != cursor.min(replay.last_seq()) || wait.is_zero() {
+            IMMEDIATE.fetch_add(1, Ordering::Relaxed);
+            return envelope(messages, next_cursor);
+        }
+
+        HELD.fetch_add(1, Ordering::Relaxed);
+        let deadline = Instant::now() + wait;
+        let (messages, next_cursor) = tokio::select! {
+            received = replay.wait_since(cursor, max_low, deadline) => received,
+            // Answer now rather than hold up the drain
+            _ = rm.drain_started() => replay.since(cursor, max_low),
+        };
+        if messages.is_empty() {
+            EXPIRED.fetch_add(1, Ordering::Relaxed);
+        }
+        envelope(messages, next_cursor)
+    }
+}
+
+fn envelope(messages: Vec<String>, next_cursor: u64) -> axum::http::Response<Body> {
+    let body = serde_json::json!({ "messages": messages, "next_cursor": next_cursor });
+    axum::http::Response::builder()
+        .status(200)
+        .header(axum::http::header::CONTENT_TYPE, "application/json")
+        .header(axum::http::header::CACHE_CONTROL, "no-store")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+fn error_response(status: u16, error: &str, message: String) -> axum::http::Response<Body> {
+    let body = serde_json::json!({ "error": error, "message": message });
+    axum::http::Response::builder()
+        .status(status)
+        .header(axum::http::header::CONTENT_TYPE, "application/json")
+        .header(axum::http::header::CACHE_CONTROL, "no-store")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+/// Set the endpoint served by this process; inherited by forked workers
+pub fn install(endpoint: Option<Arc<PollEndpoint>>) {
+    *ENDPOINT.write() = endpoint;
+}
+
+/// The endpoint to answer a request with `method` and `path`, if any
+pub fn endpoint_for(method: &str, path: &str) -> Option<Arc<PollEndpoint>> {
+    let endpoint = ENDPOINT.read();
+    match endpoint.as_ref() {
+        Some(endpoint) if method == "GET" && endpoint.path == path => Some(endpoint.clone()),
+        _ => None,
+    }
+}
+
+/// Long-poll counters for this worker.
+///
+/// Returns a dict with `active` (polls open now), `held` (polls that had to
+/// wait), `immediate` (answered without waiting), `expired` (waited and
+/// returned no messages) and `rejected` (refused by the per-client limit).
+#[pyfunction]
+pub fn realtime_poll_stats(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let stats = PyDict::new(py);
+    stats.set_item("active", ACTIVE.load(Ordering::Relaxed).max(0))?;
+    stats.set_item("held", HELD.load(Ordering::Relaxed))?;
+    stats.set_item("immediate", IMMEDIATE.load(Ordering::Relaxed))?;
+    stats.set_item("expired", EXPIRED.load(Ordering::Relaxed))?;
+    stats.set_item("rejected", REJECTED.load(Ordering::Relaxed))?;
+    Ok(stats)
+}
+
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(realtime_poll_stats, m)?)?;
+    Ok(())
+}
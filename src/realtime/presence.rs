@@ -4,6 +4,10 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use dashmap::DashMap;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde_json::{Map, Value};
+
+use crate::utils::json::{json_value_to_py, py_to_json_value};
 
 /// Information about a connected client's presence
 #[pyclass(from_py_object)]
@@ -15,15 +19,50 @@ pub struct PresenceInfo {
     /// Channel name
     #[pyo3(get)]
     pub channel: String,
-    /// Arbitrary metadata (e.g., username, avatar, status)
-    #[pyo3(get)]
-    pub metadata: HashMap<String, String>,
+    /// Arbitrary metadata (e.g., username, status, cursor); values are any
+    /// JSON-compatible value
+    pub metadata: Map<String, Value>,
     /// Unix timestamp when the client joined
     #[pyo3(get)]
     pub joined_at: f64,
     /// Unix timestamp of last activity
     #[pyo3(get)]
     pub last_seen: f64,
+    /// Unix timestamp of the last metadata change; `joined_at` until one
+    #[pyo3(get)]
+    pub last_update: f64,
+    /// 1 at join, bumped by every metadata update
+    #[pyo3(get)]
+    pub version: u64,
+}
+
+impl PresenceInfo {
+    /// Merge `partial` into the metadata, removing keys set to None, and
+    /// bump the version. Returns the keys whose value changed, with None
+    /// for removed ones.
+    fn merge(&mut self, partial: &Map<String, Value>) -> Map<String, Value> {
+        let mut changes = Map::new();
+        for (key, value) in partial {
+            let changed = if value.is_null() {
+                self.metadata.remove(key).is_some()
+            } else if self.metadata.get(key) != Some(value) {
+                self.metadata.insert(key.clone(), value.clone());
+                true
+            } else {
+                false
+            };
+            if changed {
+                changes.insert(key.clone(), value.clone());
+            }
+        }
+        let now = now_secs();
+        self.version += 1;
+        self.last_seen = now;
+        if !changes.is_empty() {
+            self.last_update = now;
+        }
+        changes
+    }
 }
 
 #[pymethods]
@@ -33,27 +72,77 @@ impl PresenceInfo {
     pub fn new(
         client_id: String,
         channel: String,
-        metadata: Option<HashMap<String, String>>,
-    ) -> Self {
+        metadata: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Self> {
         let now = now_secs();
-        Self {
+        Ok(Self {
             client_id,
             channel,
-            metadata: metadata.unwrap_or_default(),
+            metadata: metadata.map(metadata_map).transpose()?.unwrap_or_default(),
             joined_at: now,
             last_seen: now,
-        }
+            last_update: now,
+            version: 1,
+        })
+    }
+
+    /// The full metadata map
+    #[getter]
+    fn metadata(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        map_to_py(py, &self.metadata)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "PresenceInfo(client={:?}, channel={:?}, meta={}, version={})",
+            self.client_id,
+            self.channel,
+            Value::Object(self.metadata.clone()),
+            self.version
+        )
+    }
+}
+
+/// A metadata change of one client, carrying only the keys that changed
+#[pyclass(from_py_object)]
+#[derive(Clone, Debug)]
+pub struct PresenceUpdate {
+    /// Client identifier
+    #[pyo3(get)]
+    pub client_id: String,
+    /// Channel name
+    #[pyo3(get)]
+    pub channel: String,
+    /// Changed keys and their new values; None for removed keys
+    pub changes: Map<String, Value>,
+    /// Version of the entry after the change
+    #[pyo3(get)]
+    pub version: u64,
+    /// Unix timestamp of the change
+    #[pyo3(get)]
+    pub updated_at: f64,
+}
+
+#[pymethods]
+impl PresenceUpdate {
+    #[getter]
+    fn changes(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        map_to_py(py, &self.changes)
     }
 
     fn __repr__(&self) -> String {
         format!(
-            "PresenceInfo(client={:?}, channel={:?}, meta={:?})",
-            self.client_id, self.channel, self.metadata
+            "PresenceUpdate(client={:?}, channel={:?}, changes={}, version={})",
+            self.client_id,
+            self.channel,
+            Value::Object(self.changes.clone()),
+            self.version
         )
     }
 }
 
-/// Diff of presence changes (joins and leaves) for incremental updates
+/// Diff of presence changes (joins, leaves and metadata updates) for
+/// incremental updates
 #[pyclass(from_py_object)]
 #[derive(Clone, Debug, Default)]
 pub struct PresenceDiff {
@@ -63,6 +152,10 @@ pub struct PresenceDiff {
     /// Client IDs who left since last diff
     #[pyo3(get)]
     pub leaves: Vec<String>,
+    /// Metadata changes since last diff, one per client with the changes
+    /// of several updates merged
+    #[pyo3(get)]
+    pub updated: Vec<PresenceUpdate>,
 }
 
 #[pymethods]
@@ -74,19 +167,20 @@ impl PresenceDiff {
 
     /// Check if the diff has any changes
     pub fn has_changes(&self) -> bool {
-        !self.joins.is_empty() || !self.leaves.is_empty()
+        !self.joins.is_empty() || !self.leaves.is_empty() || !self.updated.is_empty()
     }
 
     /// Total number of changes
     pub fn change_count(&self) -> usize {
-        self.joins.len() + self.leaves.len()
+        self.joins.len() + self.leaves.len() + self.updated.len()
     }
 
     fn __repr__(&self) -> String {
         format!(
-            "PresenceDiff(joins={}, leaves={})",
+            "PresenceDiff(joins={}, leaves={}, updated={})",
             self.joins.len(),
-            self.leaves.len()
+            self.leaves.len(),
+            self.updated.len()
         )
     }
 }
@@ -97,6 +191,7 @@ struct ChannelPresence {
     // Accumulate changes for diff-based updates
     pending_joins: Vec<PresenceInfo>,
     pending_leaves: Vec<String>,
+    pending_updates: Vec<PresenceUpdate>,
 }
 
 impl ChannelPresence {
@@ -105,7 +200,55 @@ impl ChannelPresence {
             members: HashMap::new(),
             pending_joins: Vec::new(),
             pending_leaves: Vec::new(),
+            pending_updates: Vec::new(),
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.members.is_empty()
+            && self.pending_joins.is_empty()
+            && self.pending_leaves.is_empty()
+            && self.pending_updates.is_empty()
+    }
+
+    /// Remove a member, recording the leave; its pending updates are moot
+    fn leave(&mut self, client_id: &str) -> bool {
+        if self.members.remove(client_id).is_none() {
+            return false;
+        }
+        self.pending_leaves.push(client_id.to_string());
+        self.pending_updates.retain(|u| u.client_id != client_id);
+        true
+    }
+
+    /// Merge `partial` into a member's metadata and record the delta.
+    /// None when the client is not a member.
+    fn update(&mut self, client_id: &str, partial: &Map<String, Value>) -> Option<u64> {
+        let info = self.members.get_mut(client_id)?;
+        let changes = info.merge(partial);
+        let (version, updated_at) = (info.version, info.last_update);
+        if changes.is_empty() {
+            return Some(version);
         }
+        match self
+            .pending_updates
+            .iter_mut()
+            .find(|u| u.client_id == client_id)
+        {
+            Some(pending) => {
+                pending.changes.extend(changes);
+                pending.version = version;
+                pending.updated_at = updated_at;
+            }
+            None => self.pending_updates.push(PresenceUpdate {
+                client_id: client_id.to_string(),
+                channel: info.channel.clone(),
+                changes,
+                version,
+                updated_at,
+            }),
+        }
+        Some(version)
     }
 }
 
@@ -140,13 +283,9 @@ impl PresenceTracker {
         &self,
         channel: &str,
         client_id: &str,
-        metadata: Option<HashMap<String, String>>,
-    ) -> PresenceInfo {
-        let info = PresenceInfo::new(
-            client_id.to_string(),
-            channel.to_string(),
-            metadata,
-        );
+        metadata: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PresenceInfo> {
+        let info = PresenceInfo::new(client_id.to_string(), channel.to_string(), metadata)?;
 
         // Add to channel
         self.channels
@@ -166,29 +305,18 @@ impl PresenceTracker {
             .or_default()
             .push(channel.to_string());
 
-        info
+        Ok(info)
     }
 
     /// Remove a client's presence from a channel
     pub fn untrack(&self, channel: &str, client_id: &str) -> bool {
-        let removed = if let Some(mut cp) = self.channels.get_mut(channel) {
-            let existed = cp.members.remove(client_id).is_some();
-            if existed {
-                cp.pending_leaves.push(client_id.to_string());
-            }
-            existed
-        } else {
-            false
+        let removed = match self.channels.get_mut(channel) {
+            Some(mut cp) => cp.leave(client_id),
+            None => false,
         };
 
         // Clean up empty channels
-        if let Some(cp) = self.channels.get(channel) {
-            if cp.members.is_empty() && cp.pending_joins.is_empty() && cp.pending_leaves.is_empty()
-            {
-                drop(cp);
-                self.channels.remove(channel);
-            }
-        }
+        self.channels.remove_if(channel, |_, cp| cp.is_idle());
 
         // Update client → channels mapping
         if let Some(mut channels) = self.client_channels.get_mut(client_id) {
@@ -212,31 +340,47 @@ impl PresenceTracker {
 
         for channel in &channels_left {
             if let Some(mut cp) = self.channels.get_mut(channel) {
-                if cp.members.remove(client_id).is_some() {
-                    cp.pending_leaves.push(client_id.to_string());
-                }
+                cp.leave(client_id);
             }
         }
 
         channels_left
     }
 
-    /// Update a client's metadata (e.g., status change)
+    /// Merge metadata keys into a client's entry in one channel (e.g., a
+    /// status change); keys set to None are removed. Bumps the entry's
+    /// version and records the changed keys for the next diff.
     #[pyo3(signature = (channel, client_id, metadata))]
     pub fn update(
         &self,
         channel: &str,
         client_id: &str,
-        metadata: HashMap<String, String>,
-    ) -> bool {
-        if let Some(mut cp) = self.channels.get_mut(channel) {
-            if let Some(info) = cp.members.get_mut(client_id) {
-                info.metadata = metadata;
-                info.last_seen = now_secs();
-                return true;
+        metadata: &Bound<'_, PyDict>,
+    ) -> PyResult<bool> {
+        let partial = metadata_map(metadata)?;
+        Ok(self
+            .channels
+            .get_mut(channel)
+            .and_then(|mut cp| cp.update(client_id, &partial))
+            .is_some())
+    }
+
+    /// `update` for every channel the client is present in; returns False
+    /// when it is in none
+    pub fn update_client(
+        &self,
+        client_id: &str,
+        partial_meta: &Bound<'_, PyDict>,
+    ) -> PyResult<bool> {
+        let partial = metadata_map(partial_meta)?;
+        let channels = self.client_channels(client_id);
+        let mut updated = false;
+        for channel in &channels {
+            if let Some(mut cp) = self.channels.get_mut(channel) {
+                updated |= cp.update(client_id, &partial).is_some();
             }
         }
-        false
+        Ok(updated)
     }
 
     /// Touch a client's last_seen timestamp (heartbeat)
@@ -279,7 +423,12 @@ impl PresenceTracker {
         if let Some(mut cp) = self.channels.get_mut(channel) {
             let joins = std::mem::take(&mut cp.pending_joins);
             let leaves = std::mem::take(&mut cp.pending_leaves);
-            PresenceDiff { joins, leaves }
+            let updated = std::mem::take(&mut cp.pending_updates);
+            PresenceDiff {
+                joins,
+                leaves,
+                updated,
+            }
         } else {
             PresenceDiff::default()
         }
@@ -323,8 +472,7 @@ impl PresenceTracker {
                 .collect();
 
             for client_id in &stale {
-                cp.members.remove(client_id);
-                cp.pending_leaves.push(client_id.clone());
+                cp.leave(client_id);
                 evicted.push((channel.clone(), client_id.clone()));
             }
         }
@@ -364,6 +512,22 @@ impl Default for PresenceTracker {
     }
 }
 
+/// Metadata passed from Python; values must be JSON-compatible
+fn metadata_map(metadata: &Bound<'_, PyDict>) -> PyResult<Map<String, Value>> {
+    match py_to_json_value(metadata.as_any())? {
+        Value::Object(map) => Ok(map),
+        _ => unreachable!("a dict converts to an object"),
+    }
+}
+
+fn map_to_py(py: Python<'_>, map: &Map<String, Value>) -> PyResult<Py<PyAny>> {
+    let dict = PyDict::new(py);
+    for (key, value) in map {
+        dict.set_item(key, json_value_to_py(py, value)?)?;
+    }
+    Ok(dict.into_any().unbind())
+}
+
 fn now_secs() -> f64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -0,0 +1,488 @@
+//! Per-client outbound queues.
+//!
+//! A `tokio::broadcast` channel is shared by every subscriber, so a slow one
+//! can only lag and lose whatever the ring buffer overwrote. A subscription
+//! made with a `ClientQueueConfig` instead gets a bounded queue of its own,
+//! filled by a fan-out task that applies the configured `OverflowPolicy`
+//! when the client falls behind.
+//!
+//! Messages carry a `Priority`. A client queue keeps one FIFO per priority
+//! and hands out the most urgent message first, so a high-priority message
+//! does not wait behind a backlog of normal ones. After `max_burst` messages
+//! in a row have gone ahead of waiting lower-priority ones, the next
+//! lower-priority message goes out, so a flood of urgent messages cannot
+//! starve the rest. Subscribers reading the shared ring buffer directly get
+//! every message in publish order.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, Notify};
+
+use crate::core::global::get_runtime;
+
+/// How urgently a message should reach clients
+#[pyclass(eq, eq_int, from_py_object)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    /// Delivered before any queued normal or low message
+    High = 0,
+    /// The default
+    #[default]
+    Normal = 1,
+    /// Delivered once nothing more urgent is queued
+    Low = 2,
+}
+
+impl Priority {
+    /// A `Priority`, or its name: `"high"`, `"normal"` or `"low"`
+    pub fn extract(value: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        let Some(value) = value.filter(|value| !value.is_none()) else {
+            return Ok(Self::Normal);
+        };
+        if let Ok(priority) = value.extract::<Self>() {
+            return Ok(priority);
+        }
+        let name = value
+            .extract::<String>()
+            .map_err(|_| PyTypeError::new_err("priority must be a Priority or its name"))?;
+        Self::parse(&name).ok_or_else(|| {
+            PyValueError::new_err(format!(
+                "priority must be 'high', 'normal' or 'low', got '{}'",
+                name
+            ))
+        })
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "high" => Some(Self::High),
+            "normal" => Some(Self::Normal),
+            "low" => Some(Self::Low),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::High => "high",
+            Self::Normal => "normal",
+            Self::Low => "low",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// A message as sent through a channel's broadcast ring buffer
+#[derive(Clone, Debug)]
+pub struct Outbound {
+    pub priority: Priority,
+    pub text: String,
+}
+
+/// What a client queue does with a message that arrives while it is full
+#[pyclass(eq, eq_int, from_py_object)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Evict the oldest queued message (default)
+    DropOldest = 0,
+    /// Discard the incoming message
+    DropNewest = 1,
+    /// Close the client; queued messages are discarded
+    Disconnect = 2,
+    /// Replace the queued message with the same key, else drop the oldest
+    CoalesceByKey = 3,
+}
+
+/// Default `ClientQueueConfig.max_burst`
+pub const DEFAULT_MAX_BURST: usize = 8;
+
+/// Configuration for a per-client queue
+#[pyclass(from_py_object)]
+#[derive(Clone, Debug)]
+pub struct ClientQueueConfig {
+    /// Maximum number of queued messages
+    #[pyo3(get, set)]
+    pub capacity: usize,
+    /// Overflow policy
+    #[pyo3(get, set)]
+    pub policy: OverflowPolicy,
+    /// Top-level JSON field used as the key by `CoalesceByKey`
+    #[pyo3(get, set)]
+    pub coalesce_key: Option<String>,
+    /// Messages that may go ahead of waiting lower-priority ones in a row
+    #[pyo3(get, set)]
+    pub max_burst: usize,
+}
+
+#[pymethods]
+impl ClientQueueConfig {
+    /// Args:
+    ///     capacity: Maximum number of queued messages
+    ///     policy: Applied when a message arrives while the queue is full
+    ///     coalesce_key: Top-level field of JSON messages naming the key for
+    ///         `CoalesceByKey`; messages without it are never coalesced
+    ///     max_burst: After this many messages in a row have gone ahead of
+    ///         waiting lower-priority ones, the next lower-priority message
+    ///         goes out
+    #[new]
+    #[pyo3(signature = (capacity=64, policy=OverflowPolicy::DropOldest, coalesce_key=None, max_burst=DEFAULT_MAX_BURST))]
+    pub fn new(
+        capacity: usize,
+        policy: OverflowPolicy,
+        coalesce_key: Option<String>,
+        max_burst: usize,
+    ) -> PyResult<Self> {
+        if capacity == 0 {
+            return Err(PyValueError::new_err("capacity must be at least 1"));
+        }
+        if max_burst == 0 {
+            return Err(PyValueError::new_err("max_burst must be at least 1"));
+        }
+        if policy == OverflowPolicy::CoalesceByKey && coalesce_key.is_none() {
+            return Err(PyValueError::new_err(
+                "OverflowPolicy.CoalesceByKey requires coalesce_key",
+            ));
+        }
+        Ok(Self {
+            capacity,
+            policy,
+            coalesce_key,
+            max_burst,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ClientQueueConfig(capacity={}, policy={:?}, coalesce_key={:?}, max_burst={})",
+            self.capacity, self.policy, self.coalesce_key, self.max_burst
+        )
+    }
+}
+
+/// Counters for one client queue
+#[pyclass(from_py_object)]
+#[derive(Clone, Debug, Default)]
+pub struct ClientQueueStats {
+    /// Messages waiting to be received
+    #[pyo3(get)]
+    pub queued: usize,
+    /// Largest number of messages queued at once
+    #[pyo3(get)]
+    pub high_water: usize,
+    /// Messages taken from the channel by the fan-out task
+    #[pyo3(get)]
+    pub forwarded: u64,
+    /// Messages lost to the overflow policy
+    #[pyo3(get)]
+    pub dropped: u64,
+    /// Queued messages replaced by a newer one with the same key
+    #[pyo3(get)]
+    pub coalesced: u64,
+    /// Messages handed out ahead of an older, less urgent one
+    #[pyo3(get)]
+    pub reordered: u64,
+    /// Queue capacity
+    #[pyo3(get)]
+    pub capacity: usize,
+    /// Whether the client was closed for falling behind
+    #[pyo3(get)]
+    pub disconnected: bool,
+}
+
+#[pymethods]
+impl ClientQueueStats {
+    fn __repr__(&self) -> String {
+        format!(
+            "ClientQueueStats(queued={}, high_water={}, dropped={}, coalesced={}, disconnected={})",
+            self.queued, self.high_water, self.dropped, self.coalesced, self.disconnected,
+        )
+    }
+}
+
+struct Entry {
+    key: Option<String>,
+    message: String,
+    /// Arrival order across all priorities
+    order: u64,
+}
+
+/// Queued messages, one FIFO per priority
+#[derive(Default)]
+struct Lanes {
+    lanes: [VecDeque<Entry>; 3],
+    /// Messages handed out in a row while a less urgent one waited
+    burst: usize,
+    next_order: u64,
+}
+
+impl Lanes {
+    fn len(&self) -> usize {
+        self.lanes.iter().map(VecDeque::len).sum()
+    }
+
+    fn push(&mut self, priority: Priority, key: Option<String>, message: String) {
+        self.next_order += 1;
+        self.lanes[priority.index()].push_back(Entry {
+            key,
+            message,
+            order: self.next_order,
+        });
+    }
+
+    fn find_key(&mut self, priority: Priority, key: &str) -> Option<&mut Entry> {
+        self.lanes[priority.index()]
+            .iter_mut()
+            .find(|entry| entry.key.as_deref() == Some(key))
+    }
+
+    /// Lane of the least urgent queued messages
+    fn least_urgent(&self) -> Option<usize> {
+        self.lanes.iter().rposition(|lane| !lane.is_empty())
+    }
+
+    /// Evict the oldest (or newest) message of the least urgent lane, if
+    /// that lane is `lane` or less urgent. False when nothing was evicted.
+    fn evict(&mut self, lane: usize, oldest: bool) -> bool {
+        match self.least_urgent() {
+            Some(least) if least >= lane => {
+                let least = &mut self.lanes[least];
+                if oldest {
+                    least.pop_front();
+                } else {
+                    least.pop_back();
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The most urgent message, unless `max_burst` messages in a row already
+    /// went ahead of a less urgent one; also whether an older message was
+    /// left waiting
+    fn pop(&mut self, max_burst: usize) -> Option<(String, bool)> {
+        let first = self.lanes.iter().position(|lane| !lane.is_empty())?;
+        let waiting = (first + 1..self.lanes.len()).find(|&lane| !self.lanes[lane].is_empty());
+        let lane = match waiting {
+            None => {
+                self.burst = 0;
+                first
+            }
+            Some(lower) if self.burst >= max_burst => {
+                self.burst = 0;
+                lower
+            }
+            Some(_) => {
+                self.burst += 1;
+                first
+            }
+        };
+        let entry = self.lanes[lane].pop_front()?;
+        let reordered = self.lanes[lane + 1..]
+            .iter()
+            .any(|lower| lower.front().is_some_and(|e| e.order < entry.order));
+        Some((entry.message, reordered))
+    }
+
+    fn clear(&mut self) {
+        self.lanes.iter_mut().for_each(VecDeque::clear);
+    }
+}
+
+/// Bounded queue between the fan-out task and one client
+pub struct ClientQueue {
+    config: ClientQueueConfig,
+    lanes: Mutex<Lanes>,
+    /// Wakes a receiver waiting for a message or for the close
+    ready: Notify,
+    /// Wakes the fan-out task once the client is gone
+    shutdown: Notify,
+    closed: AtomicBool,
+    disconnected: AtomicBool,
+    high_water: AtomicU64,
+    forwarded: AtomicU64,
+    dropped: AtomicU64,
+    coalesced: AtomicU64,
+    reordered: AtomicU64,
+}
+
+impl ClientQueue {
+    /// Queue fed from `receiver` by a task on the shared runtime.
+    ///
+    /// `on_disconnect` runs once if the `Disconnect` policy closes the client.
+    pub fn spawn(
+        config: ClientQueueConfig,
+        mut receiver: broadcast::Receiver<Outbound>,
+        on_disconnect: impl FnOnce() + Send + 'static,
+    ) -> Arc<Self> {
+        let queue = Arc::new(Self {
+            config,
+            lanes: Mutex::new(Lanes::default()),
+            ready: Notify::new(),
+            shutdown: Notify::new(),
+            closed: AtomicBool::new(false),
+            disconnected: AtomicBool::new(false),
+            high_water: AtomicU64::new(0),
+            forwarded: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            coalesced: AtomicU64::new(0),
+            reordered: AtomicU64::new(0),
+        });
+
+        let fan_out = queue.clone();
+        get_runtime().spawn(async move {
+            loop {
+                let shutdown = fan_out.shutdown.notified();
+                if fan_out.is_closed() {
+                    return;
+                }
+                tokio::select! {
+                    received = receiver.recv() => match received {
+                        Ok(message) => {
+                            if !fan_out.push(message) {
+                                on_disconnect();
+                                fan_out.forwarded.fetch_add(1, Ordering::Release);
+                                return;
+                            }
+                            // Counted once handled, so stats never run ahead
+                            fan_out.forwarded.fetch_add(1, Ordering::Release);
+                        }
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            fan_out.dropped.fetch_add(n, Ordering::Relaxed);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {
+                            fan_out.close();
+                            return;
+                        }
+                    },
+                    _ = shutdown => return,
+                }
+            }
+        });
+        queue
+    }
+
+    /// Apply the overflow policy; false once the client was disconnected
+    fn push(&self, message: Outbound) -> bool {
+        let Outbound {
+            priority,
+            text: message,
+        } = message;
+        let key = match (&self.config.policy, &self.config.coalesce_key) {
+            (OverflowPolicy::CoalesceByKey, Some(field)) => message_key(&message, field),
+            _ => None,
+        };
+
+        let mut lanes = self.lanes.lock();
+        if let Some(key) = &key {
+            if let Some(entry) = lanes.find_key(priority, key) {
+                entry.message = message;
+                self.coalesced.fetch_add(1, Ordering::Relaxed);
+                return true;
+            }
+        }
+        if lanes.len() >= self.config.capacity {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            match self.config.policy {
+                // A message never makes way for a less urgent one
+                OverflowPolicy::DropOldest | OverflowPolicy::CoalesceByKey => {
+                    if !lanes.evict(priority.index(), true) {
+                        return true;
+                    }
+                }
+                OverflowPolicy::DropNewest => {
+                    if !lanes.evict(priority.index() + 1, false) {
+                        return true;
+                    }
+                }
+                OverflowPolicy::Disconnect => {
+                    self.dropped
+                        .fetch_add(lanes.len() as u64, Ordering::Relaxed);
+                    lanes.clear();
+                    drop(lanes);
+                    self.disconnected.store(true, Ordering::Relaxed);
+                    self.close();
+                    return false;
+                }
+            }
+        }
+        lanes.push(priority, key, message);
+        self.high_water
+            .fetch_max(lanes.len() as u64, Ordering::Relaxed);
+        drop(lanes);
+        self.ready.notify_one();
+        true
+    }
+
+    /// Next queued message, if any
+    pub fn pop(&self) -> Option<String> {
+        let (message, reordered) = self.lanes.lock().pop(self.config.max_burst)?;
+        if reordered {
+            self.reordered.fetch_add(1, Ordering::Relaxed);
+        }
+        Some(message)
+    }
+
+    /// Next message, or None once the queue is closed and empty
+    pub async fn recv(&self) -> Option<String> {
+        loop {
+            let ready = self.ready.notified();
+            if let Some(message) = self.pop() {
+                return Some(message);
+            }
+            if self.is_closed() {
+                return None;
+            }
+            ready.await;
+        }
+    }
+
+    /// Stop the fan-out task and wake any waiting receiver
+    pub fn close(&self) {
+        if !self.closed.swap(true, Ordering::AcqRel) {
+            self.shutdown.notify_waiters();
+            self.ready.notify_waiters();
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn stats(&self) -> ClientQueueStats {
+        ClientQueueStats {
+            queued: self.lanes.lock().len(),
+            high_water: self.high_water.load(Ordering::Relaxed) as usize,
+            forwarded: self.forwarded.load(Ordering::Acquire),
+            dropped: self.dropped(),
+            coalesced: self.coalesced.load(Ordering::Relaxed),
+            reordered: self.reordered.load(Ordering::Relaxed),
+            capacity: self.config.capacity,
+            disconnected: self.disconnected.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Coalescing key: the `field` of a JSON object message, strings unquoted
+fn message_key(message: &str, field: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(message).ok()?;
+    match value.get(field)? {
+        serde_json::Value::String(key) => Some(key.clone()),
+        serde_json::Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
@@ -0,0 +1,300 @@
+//! Receiving side shared by `Subscriber` and `BroadcastSubscriber`.
+//!
+//! The `tokio::broadcast` receiver sits behind a Tokio mutex so an awaiting
+//! `recv_async()` can hold it across `.await`; subscriptions with a client
+//! queue read from that queue instead. The wait runs on the shared Tokio
+//! runtime and resolves an asyncio future on the caller's event loop, so
+//! Python consumers never poll.
+
+use parking_lot::Mutex;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyBaseException, PyException, PyStopAsyncIteration, PyValueError};
+use pyo3::prelude::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, oneshot};
+
+use crate::core::global::{get_asyncio, get_runtime};
+use crate::realtime::queue::{ClientQueue, ClientQueueStats, Outbound};
+
+create_exception!(
+    _hypern,
+    ChannelClosed,
+    PyException,
+    "The channel was removed while a subscriber was waiting on it."
+);
+
+/// Result of one awaited receive
+enum Received {
+    Message(String),
+    Timeout,
+    Closed,
+}
+
+/// Where a subscriber's messages come from
+enum Source {
+    /// Straight from the channel's broadcast ring buffer
+    Direct(tokio::sync::Mutex<broadcast::Receiver<Outbound>>),
+    /// From a per-client queue filled by a fan-out task
+    Queued(Arc<ClientQueue>),
+}
+
+/// Receiver plus the counters reported by the subscriber handles
+pub struct SubscriberState {
+    source: Source,
+    received: AtomicU64,
+    missed: AtomicU64,
+}
+
+impl SubscriberState {
+    pub fn new(receiver: broadcast::Receiver<Outbound>) -> Arc<Self> {
+        Self::with_source(Source::Direct(tokio::sync::Mutex::new(receiver)))
+    }
+
+    pub fn queued(queue: Arc<ClientQueue>) -> Arc<Self> {
+        Self::with_source(Source::Queued(queue))
+    }
+
+    fn with_source(source: Source) -> Arc<Self> {
+        Arc::new(Self {
+            source,
+            received: AtomicU64::new(0),
+            missed: AtomicU64::new(0),
+        })
+    }
+
+    pub fn received(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    /// Messages lost to lag, or to the overflow policy of a client queue
+    pub fn missed(&self) -> u64 {
+        match &self.source {
+            Source::Direct(_) => self.missed.load(Ordering::Relaxed),
+            Source::Queued(queue) => queue.dropped(),
+        }
+    }
+
+    /// Client queue counters, None without a client queue
+    pub fn queue_stats(&self) -> Option<ClientQueueStats> {
+        match &self.source {
+            Source::Direct(_) => None,
+            Source::Queued(queue) => Some(queue.stats()),
+        }
+    }
+
+    /// Next message if one is ready. Returns None while a `recv_async()` is
+    /// waiting, since that call gets the next message.
+    pub fn try_recv(&self) -> Option<String> {
+        let receiver = match &self.source {
+            Source::Direct(receiver) => receiver,
+            Source::Queued(queue) => {
+                let msg = queue.pop()?;
+                self.received.fetch_add(1, Ordering::Relaxed);
+                return Some(msg);
+            }
+        };
+        let mut rx = receiver.try_lock().ok()?;
+        match rx.try_recv() {
+            Ok(msg) => {
+                self.received.fetch_add(1, Ordering::Relaxed);
+                Some(msg.text)
+            }
+            Err(broadcast::error::TryRecvError::Lagged(n)) => {
+                self.missed.fetch_add(n, Ordering::Relaxed);
+                // The receiver now points at the oldest retained message
+                let msg = rx.try_recv().ok()?;
+                self.received.fetch_add(1, Ordering::Relaxed);
+                Some(msg.text)
+            }
+            Err(_) => None,
+        }
+    }
+
+    /// All messages that are ready
+    pub fn drain(&self) -> Vec<String> {
+        let mut messages = Vec::new();
+        let receiver = match &self.source {
+            Source::Direct(receiver) => receiver,
+            Source::Queued(queue) => {
+                while let Some(msg) = queue.pop() {
+                    messages.push(msg);
+                }
+                self.received
+                    .fetch_add(messages.len() as u64, Ordering::Relaxed);
+                return messages;
+            }
+        };
+        let Ok(mut rx) = receiver.try_lock() else {
+            return messages;
+        };
+        loop {
+            match rx.try_recv() {
+                Ok(msg) => {
+                    self.received.fetch_add(1, Ordering::Relaxed);
+                    messages.push(msg.text);
+                }
+                Err(broadcast::error::TryRecvError::Lagged(n)) => {
+                    self.missed.fetch_add(n, Ordering::Relaxed);
+                }
+                Err(_) => break,
+            }
+        }
+        messages
+    }
+
+    async fn recv(&self, timeout: Option<Duration>) -> Received {
+        let wait = async {
+            let receiver = match &self.source {
+                Source::Direct(receiver) => receiver,
+                Source::Queued(queue) => {
+                    return match queue.recv().await {
+                        Some(msg) => {
+                            self.received.fetch_add(1, Ordering::Relaxed);
+                            Received::Message(msg)
+                        }
+                        None => Received::Closed,
+                    };
+                }
+            };
+            let mut rx = receiver.lock().await;
+            loop {
+                match rx.recv().await {
+                    Ok(msg) => {
+                        self.received.fetch_add(1, Ordering::Relaxed);
+                        return Received::Message(msg.text);
+                    }
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        self.missed.fetch_add(n, Ordering::Relaxed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return Received::Closed,
+                }
+            }
+        };
+        match timeout {
+            Some(limit) => tokio::time::timeout(limit, wait)
+                .await
+                .unwrap_or(Received::Timeout),
+            None => wait.await,
+        }
+    }
+
+    /// Awaitable for the next message on the running event loop.
+    ///
+    /// Resolves with the message, or None once `timeout` seconds pass. When
+    /// the channel is closed it raises `ChannelClosed`, or
+    /// `StopAsyncIteration` for `__anext__`.
+    pub fn recv_async<'py>(
+        self: &Arc<Self>,
+        py: Python<'py>,
+        timeout: Option<f64>,
+        iterating: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let timeout = match timeout {
+            Some(secs) if secs.is_finite() && secs >= 0.0 => Some(Duration::from_secs_f64(secs)),
+            Some(_) => {
+                return Err(PyValueError::new_err(
+                    "timeout must be a non-negative number of seconds",
+                ))
+            }
+            None => None,
+        };
+        let event_loop = get_asyncio(py).bind(py).call_method0("get_running_loop")?;
+        let future = event_loop.call_method0("create_future")?;
+
+        // A cancelled future (e.g. asyncio.wait_for) stops the wait so the
+        // message it would have taken stays queued
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        future.call_method1(
+            "add_done_callback",
+            (CancelWait(Mutex::new(Some(cancel_tx))),),
+        )?;
+
+        let state = self.clone();
+        let event_loop = event_loop.unbind();
+        let target = future.clone().unbind();
+        get_runtime().spawn(async move {
+            let received = tokio::select! {
+                received = state.recv(timeout) => received,
+                _ = cancel_rx => return,
+            };
+            Python::attach(|py| {
+                let outcome: Outcome = match received {
+                    Received::Message(msg) => Ok(msg.into_pyobject(py)?.into_any().unbind()),
+                    Received::Timeout => Ok(py.None()),
+                    Received::Closed if iterating => {
+                        Err(PyStopAsyncIteration::new_err(()).into_value(py))
+                    }
+                    Received::Closed => Err(ChannelClosed::new_err(
+                        "channel closed while waiting for a message",
+                    )
+                    .into_value(py)),
+                };
+                let resolve = ResolveFuture(Mutex::new(Some((target, outcome))));
+                event_loop
+                    .bind(py)
+                    .call_method1("call_soon_threadsafe", (resolve,))
+                    .map(|_| ())
+            })
+            .unwrap_or_else(|err| {
+                crate::hlog_debug!("Failed to deliver subscriber message: {}", err);
+            });
+        });
+        Ok(future)
+    }
+}
+
+impl Drop for SubscriberState {
+    /// Stops the fan-out task once the subscriber handle is gone
+    fn drop(&mut self) {
+        if let Source::Queued(queue) = &self.source {
+            queue.close();
+        }
+    }
+}
+
+/// Done callback releasing the Tokio wait behind a cancelled future
+#[pyclass]
+struct CancelWait(Mutex<Option<oneshot::Sender<()>>>);
+
+#[pymethods]
+impl CancelWait {
+    fn __call__(&self, future: &Bound<'_, PyAny>) -> PyResult<()> {
+        if future.call_method0("cancelled")?.is_truthy()? {
+            if let Some(tx) = self.0.lock().take() {
+                let _ = tx.send(());
+            }
+        }
+        Ok(())
+    }
+}
+
+type Outcome = Result<Py<PyAny>, Py<PyBaseException>>;
+
+/// Event loop callback completing the future unless it was cancelled
+#[pyclass]
+struct ResolveFuture(Mutex<Option<(Py<PyAny>, Outcome)>>);
+
+#[pymethods]
+impl ResolveFuture {
+    fn __call__(&self, py: Python<'_>) -> PyResult<()> {
+        let Some((future, outcome)) = self.0.lock().take() else {
+            return Ok(());
+        };
+        let future = future.bind(py);
+        if future.call_method0("done")?.is_truthy()? {
+            return Ok(());
+        }
+        match outcome {
+            Ok(value) => future.call_method1("set_result", (value,))?,
+            Err(exc) => future.call_method1("set_exception", (exc,))?,
+        };
+        Ok(())
+    }
+}
+
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("ChannelClosed", m.py().get_type::<ChannelClosed>())?;
+    Ok(())
+}
@@ -0,0 +1,168 @@
+//! Per-channel replay buffers.
+//!
+//! Every message published on a channel is numbered with the next sequence
+//! number. A channel created with a `replay_size` also keeps its newest
+//! messages, so readers that were not subscribed when they went out — long
+//! polls, reconnecting clients — can catch up from a cursor. Each retained
+//! message keeps its `Priority`, so a catch-up can leave out all but the
+//! newest low-priority messages.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+use crate::realtime::queue::Priority;
+
+/// A retained message: `(seq, priority, message)`
+pub type ReplayEntry = (u64, Priority, String);
+
+/// Sequence numbers and retained messages of one channel
+pub struct ReplayBuffer {
+    capacity: usize,
+    last_seq: AtomicU64,
+    entries: Mutex<VecDeque<ReplayEntry>>,
+    /// Wakes readers waiting for a message past their cursor
+    published: Notify,
+}
+
+impl ReplayBuffer {
+    pub fn new(capacity: usize) -> Arc<Self> {
+        Arc::new(Self {
+            capacity,
+            last_seq: AtomicU64::new(0),
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            published: Notify::new(),
+        })
+    }
+
+    /// Number of messages retained; 0 keeps only the sequence counter
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn last_seq(&self) -> u64 {
+        self.last_seq.load(Ordering::Acquire)
+    }
+
+    /// Number `message` and retain it; returns its sequence number
+    pub fn record(&self, message: &str, priority: Priority) -> u64 {
+        if self.capacity == 0 {
+            return self.last_seq.fetch_add(1, Ordering::AcqRel) + 1;
+        }
+        let mut entries = self.entries.lock();
+        // Assigned under the lock so retained messages stay in order
+        let seq = self.last_seq.fetch_add(1, Ordering::AcqRel) + 1;
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back((seq, priority, message.to_string()));
+        drop(entries);
+        self.published.notify_waiters();
+        seq
+    }
+
+    /// Like `record`, but the message is built from its sequence number;
+    /// returns the built message
+    pub fn record_with(&self, priority: Priority, build: impl FnOnce(u64) -> String) -> String {
+        if self.capacity == 0 {
+            return build(self.last_seq.fetch_add(1, Ordering::AcqRel) + 1);
+        }
+        let mut entries = self.entries.lock();
+        let seq = self.last_seq.fetch_add(1, Ordering::AcqRel) + 1;
+        let message = build(seq);
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back((seq, priority, message.clone()));
+        drop(entries);
+        self.published.notify_waiters();
+        message
+    }
+
+    /// The sequence counter and retained messages, oldest first
+    pub fn snapshot(&self) -> (u64, Vec<ReplayEntry>) {
+        let entries = self.entries.lock();
+        (self.last_seq(), entries.iter().cloned().collect())
+    }
+
+    /// Continue from a snapshot taken by the previous generation: set the
+    /// sequence counter to `last_seq` and retain the newest `entries` that
+    /// fit. Returns the number of messages retained, or None when messages
+    /// were already published here.
+    pub fn restore(&self, last_seq: u64, entries: Vec<ReplayEntry>) -> Option<usize> {
+        let mut retained = self.entries.lock();
+        if self.last_seq() != 0 || !retained.is_empty() {
+            return None;
+        }
+        let mut entries: Vec<ReplayEntry> = entries
+            .into_iter()
+            .filter(|(seq, _, _)| *seq > 0 && *seq <= last_seq)
+            .collect();
+        entries.sort_by_key(|(seq, _, _)| *seq);
+        entries.dedup_by_key(|(seq, _, _)| *seq);
+        let skip = entries.len().saturating_sub(self.capacity);
+        retained.extend(entries.into_iter().skip(skip));
+        self.last_seq.store(last_seq, Ordering::Release);
+        Some(retained.len())
+    }
+
+    /// Retained messages numbered after `cursor`, and the cursor to resume
+    /// from. With `max_low`, only the newest `max_low` low-priority messages
+    /// are included; the cursor still moves past the others.
+    ///
+    /// A cursor ahead of the channel (e.g. from before a restart) is treated
+    /// as the current position, so the reader picks up new messages instead
+    /// of waiting for a sequence number that will only come much later.
+    pub fn since(&self, cursor: u64, max_low: Option<usize>) -> (Vec<String>, u64) {
+        let entries = self.entries.lock();
+        let cursor = cursor.min(self.last_seq());
+        let pending = entries.iter().filter(|(seq, _, _)| *seq > cursor);
+        let mut skip_low = match max_low {
+            Some(keep) => pending
+                .clone()
+                .filter(|(_, priority, _)| *priority == Priority::Low)
+                .count()
+                .saturating_sub(keep),
+            None => 0,
+        };
+        let mut next_cursor = cursor;
+        let mut messages = Vec::new();
+        for (seq, priority, message) in pending {
+            next_cursor = *seq;
+            if *priority == Priority::Low && skip_low > 0 {
+                skip_low -= 1;
+                continue;
+            }
+            messages.push(message.clone());
+        }
+        (messages, next_cursor)
+    }
+
+    /// Like `since`, but waits until `deadline` for a message past `cursor`.
+    /// Returns no messages when the deadline passes first.
+    pub async fn wait_since(
+        &self,
+        cursor: u64,
+        max_low: Option<usize>,
+        deadline: Instant,
+    ) -> (Vec<String>, u64) {
+        let cursor = cursor.min(self.last_seq());
+        loop {
+            let published = self.published.notified();
+            tokio::pin!(published);
+            // Registered before checking, so a publish in between still wakes us
+            published.as_mut().enable();
+            let (messages, next_cursor) = self.since(cursor, max_low);
+            if next_cursor != cursor {
+                return (messages, next_cursor);
+            }
+            if tokio::time::timeout_at(deadline, published).await.is_err() {
+                return (messages, next_cursor);
+            }
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! Publish-time validation and envelopes for realtime messages.
+//!
+//! A channel created with a `MessageSchema` checks every message before it
+//! is numbered or delivered: its size, that it is JSON, and that required
+//! top-level fields are present with the right types. A channel created
+//! with `envelope=True` delivers each message wrapped as
+//! `{"seq": n, "ts": <unix ms>, "channel": name, "data": <payload>}`, plus
+//! `"priority"` for messages published as high or low.
+//! Channels configured with neither never parse their messages.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde_json::Value;
+
+use crate::realtime::queue::Priority;
+
+/// JSON type a required field must have
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FieldType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Object,
+    Array,
+    Null,
+    Any,
+}
+
+impl FieldType {
+    const NAMES: &'static str = "string, number, integer, boolean, object, array, null, any";
+
+    /// JSON Schema names, plus the Python type names for convenience
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "string" | "str" => Self::String,
+            "number" | "float" => Self::Number,
+            "integer" | "int" => Self::Integer,
+            "boolean" | "bool" => Self::Boolean,
+            "object" | "dict" => Self::Object,
+            "array" | "list" => Self::Array,
+            "null" | "none" => Self::Null,
+            "any" => Self::Any,
+            _ => return None,
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Number => "number",
+            Self::Integer => "integer",
+            Self::Boolean => "boolean",
+            Self::Object => "object",
+            Self::Array => "array",
+            Self::Null => "null",
+            Self::Any => "any",
+        }
+    }
+
+    fn matches(self, value: &Value) -> bool {
+        match self {
+            Self::String => value.is_string(),
+            Self::Number => value.is_number(),
+            Self::Integer => value.is_i64() || value.is_u64(),
+            Self::Boolean => value.is_boolean(),
+            Self::Object => value.is_object(),
+            Self::Array => value.is_array(),
+            Self::Null => value.is_null(),
+            Self::Any => true,
+        }
+    }
+}
+
+fn value_type(value: &Value) -> &'static str {
+    match value {
+        Value::String(_) => "string",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::Bool(_) => "boolean",
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::Null => "null",
+    }
+}
+
+/// Rules every message published on a channel must follow.
+///
+/// Example (Python):
+///     schema = MessageSchema(fields={"user": "string", "text": "string"}, max_bytes=4096)
+///     manager.create_channel("chat:general", message_schema=schema)
+#[pyclass(from_py_object)]
+#[derive(Clone, Debug)]
+pub struct MessageSchema {
+    fields: Vec<(String, FieldType)>,
+    /// Largest payload accepted, in bytes (None for no limit)
+    #[pyo3(get)]
+    pub max_bytes: Option<usize>,
+    /// Whether payloads must be JSON
+    #[pyo3(get)]
+    pub json: bool,
+}
+
+#[pymethods]
+impl MessageSchema {
+    /// Args:
+    ///     fields: Required top-level fields of a JSON object payload, mapped
+    ///         to their type: "string", "number", "integer", "boolean",
+    ///         "object", "array", "null" or "any"
+    ///     max_bytes: Reject payloads larger than this many bytes
+    ///     json: Require payloads to be valid JSON (default: True; needed
+    ///         for `fields`)
+    #[new]
+    #[pyo3(signature = (fields=None, max_bytes=None, json=true))]
+    pub fn new(
+        fields: Option<Bound<'_, PyDict>>,
+        max_bytes: Option<usize>,
+        json: bool,
+    ) -> PyResult<Self> {
+        let mut parsed = Vec::new();
+        for (name, kind) in fields.iter().flat_map(|fields| fields.iter()) {
+            let name: String = name.extract()?;
+            let kind: String = kind.extract()?;
+            let Some(kind) = FieldType::parse(&kind) else {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown type '{}' for field '{}' (expected one of: {})",
+                    kind,
+                    name,
+                    FieldType::NAMES
+                )));
+            };
+            parsed.push((name, kind));
+        }
+        if !parsed.is_empty() && !json {
+            return Err(PyValueError::new_err("fields require json=True"));
+        }
+        if max_bytes == Some(0) {
+            return Err(PyValueError::new_err("max_bytes must be at least 1"));
+        }
+        Ok(Self {
+            fields: parsed,
+            max_bytes,
+            json,
+        })
+    }
+
+    /// Required fields and their types
+    #[getter]
+    pub fn fields(&self) -> Vec<(String, &'static str)> {
+        self.fields
+            .iter()
+            .map(|(name, kind)| (name.clone(), kind.name()))
+            .collect()
+    }
+
+    /// Why `message` would be rejected, or None when it is valid
+    pub fn validate(&self, message: &str) -> Option<String> {
+        self.check(message).err()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "MessageSchema(fields={:?}, max_bytes={:?}, json={})",
+            self.fields(),
+            self.max_bytes,
+            self.json
+        )
+    }
+}
+
+impl MessageSchema {
+    /// Check `message`, describing the first problem found
+    pub fn check(&self, message: &str) -> Result<(), String> {
+        if let Some(limit) = self.max_bytes {
+            if message.len() > limit {
+                return Err(format!(
+                    "message is {} bytes, over the limit of {} bytes",
+                    message.len(),
+                    limit
+                ));
+            }
+        }
+        if !self.json {
+            return Ok(());
+        }
+        if self.fields.is_empty() {
+            return serde_json::from_str::<serde::de::IgnoredAny>(message)
+                .map(|_| ())
+                .map_err(|e| format!("message is not valid JSON: {}", e));
+        }
+
+        let value: Value = serde_json::from_str(message)
+            .map_err(|e| format!("message is not valid JSON: {}", e))?;
+        let Value::Object(object) = value else {
+            return Err(format!(
+                "message must be a JSON object, got {}",
+                value_type(&value)
+            ));
+        };
+        for (name, kind) in &self.fields {
+            match object.get(name) {
+                None => return Err(format!("missing required field '{}'", name)),
+                Some(value) if !kind.matches(value) => {
+                    return Err(format!(
+                        "field '{}' must be {}, got {}",
+                        name,
+                        kind.name(),
+                        value_type(value)
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What a channel does to messages on publish
+#[derive(Clone, Debug, Default)]
+pub struct MessagePolicy {
+    pub schema: Option<MessageSchema>,
+    pub envelope: bool,
+}
+
+impl MessagePolicy {
+    pub fn new(schema: Option<MessageSchema>, envelope: bool) -> Self {
+        Self { schema, envelope }
+    }
+
+    /// Check `message` against the schema, if any
+    pub fn check(&self, message: &str) -> Result<(), String> {
+        match &self.schema {
+            Some(schema) => schema.check(message),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Wrap `message` as `{"seq", "ts", "channel", "data"}`, adding `"priority"`
+/// unless it is normal. A JSON payload is embedded as is; anything else
+/// becomes a JSON string.
+pub fn envelope(seq: u64, channel: &str, message: &str, priority: Priority) -> String {
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or(0);
+    let channel = Value::String(channel.to_string());
+    let data = if serde_json::from_str::<serde::de::IgnoredAny>(message).is_ok() {
+        message.trim().to_string()
+    } else {
+        Value::String(message.to_string()).to_string()
+    };
+    match priority {
+        Priority::Normal => format!(
+            r#"{{"seq":{},"ts":{},"channel":{},"data":{}}}"#,
+            seq, ts, channel, data
+        ),
+        _ => format!(
+            r#"{{"seq":{},"ts":{},"channel":{},"priority":"{}","data":{}}}"#,
+            seq,
+            ts,
+            channel,
+            priority.name(),
+            data
+        ),
+    }
+}
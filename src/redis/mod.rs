@@ -166,3 +166,9 @@ impl RedisPool {
         )
     }
 }
+
+/// Register Redis classes.
+pub fn register_all(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<RedisPool>()?;
+    Ok(())
+}
@@ -0,0 +1,174 @@
+//! Authentication requirements declared on routes.
+//!
+//! A route registered with `auth="required"` or a list of roles is checked
+//! once the "before" middleware has run and before its handler or any
+//! route-level middleware: a request no auth middleware authenticated gets a
+//! 401, one whose user holds none of the listed roles a 403. Routes without
+//! their own requirement use the server's `default_auth`, and
+//! `auth="public"` opts a route out of it.
+//!
+//! The 401 carries the challenge the auth middleware recorded as
+//! `auth_challenge` state (`Basic realm="..."` for `BasicAuthMiddleware`),
+//! or `Bearer` when none did.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use axum::body::Body;
+use axum::http::{header, Response, StatusCode};
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::middleware::{MiddlewareContext, StateValue};
+
+/// Challenge sent when no auth middleware recorded one
+const DEFAULT_CHALLENGE: &str = "Bearer";
+
+/// Who may call a route
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AuthRequirement {
+    /// Anyone, whatever the server default
+    Public,
+    /// Anyone; the handler sees the user when there is one
+    Optional,
+    /// An authenticated user
+    Required,
+    /// An authenticated user holding at least one of these roles
+    Roles(Vec<String>),
+}
+
+impl AuthRequirement {
+    /// `"public"`, `"optional"`, `"required"` or a list of role names
+    pub fn extract(spec: &Bound<'_, PyAny>) -> PyResult<Self> {
+        if let Ok(text) = spec.extract::<String>() {
+            return Self::parse(&text).ok_or_else(|| {
+                PyValueError::new_err(format!(
+                    "auth must be 'public', 'optional', 'required' or a list of roles, got '{}'",
+                    text
+                ))
+            });
+        }
+        let roles = spec
+            .extract::<Vec<String>>()
+            .map_err(|_| PyTypeError::new_err("auth must be a string or a list of role names"))?;
+        if roles.is_empty() {
+            return Err(PyValueError::new_err("auth role list must not be empty"));
+        }
+        Ok(Self::Roles(roles))
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "public" => Some(Self::Public),
+            "optional" => Some(Self::Optional),
+            "required" => Some(Self::Required),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Public => "public",
+            Self::Optional => "optional",
+            Self::Required => "required",
+            Self::Roles(_) => "roles",
+        }
+    }
+
+    /// The requirement as given at registration
+    pub fn to_object<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        match self {
+            Self::Roles(roles) => Ok(roles.clone().into_pyobject(py)?.into_any()),
+            _ => Ok(self.name().into_pyobject(py)?.into_any()),
+        }
+    }
+
+    /// Role lists are per route; a server default is one of the others
+    fn code(&self) -> u8 {
+        match self {
+            Self::Public | Self::Roles(_) => 0,
+            Self::Optional => 1,
+            Self::Required => 2,
+        }
+    }
+
+    fn from_code(code: u8) -> Self {
+        match code {
+            1 => Self::Optional,
+            2 => Self::Required,
+            _ => Self::Public,
+        }
+    }
+}
+
+static DEFAULT: AtomicU8 = AtomicU8::new(0);
+
+/// Set the requirement of routes registered without one, for this process
+pub fn configure(default: &AuthRequirement) {
+    DEFAULT.store(default.code(), Ordering::Relaxed);
+}
+
+/// The requirement of routes registered without one
+pub fn default_requirement() -> AuthRequirement {
+    AuthRequirement::from_code(DEFAULT.load(Ordering::Relaxed))
+}
+
+#[inline]
+fn default_is_public() -> bool {
+    DEFAULT.load(Ordering::Relaxed) == 0
+}
+
+/// The 401 or 403 for a request not meeting `requirement`, None to go on.
+/// `ctx` is None when no "before" middleware ran, so nobody is authenticated.
+pub fn check(
+    requirement: Option<&AuthRequirement>,
+    ctx: Option<&MiddlewareContext>,
+) -> Option<Response<Body>> {
+    let fallback;
+    let requirement = match requirement {
+        Some(requirement) => requirement,
+        None if default_is_public() => return None,
+        None => {
+            fallback = default_requirement();
+            &fallback
+        }
+    };
+    let roles = match requirement {
+        AuthRequirement::Public | AuthRequirement::Optional => return None,
+        AuthRequirement::Required => None,
+        AuthRequirement::Roles(roles) => Some(roles),
+    };
+    let Some(ctx) = ctx.filter(|ctx| ctx.is_authenticated()) else {
+        let challenge = match ctx.and_then(|ctx| ctx.get_state("auth_challenge")) {
+            Some(StateValue::String(challenge)) => challenge,
+            _ => DEFAULT_CHALLENGE.to_string(),
+        };
+        let mut response = error_response(
+            StatusCode::UNAUTHORIZED,
+            "unauthorized",
+            "Authentication required",
+        );
+        if let Ok(value) = challenge.parse() {
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, value);
+        }
+        return Some(response);
+    };
+    match roles {
+        Some(roles) if !roles.iter().any(|role| ctx.has_role(role)) => Some(error_response(
+            StatusCode::FORBIDDEN,
+            "forbidden",
+            &format!("Requires one of the roles: {}", roles.join(", ")),
+        )),
+        _ => None,
+    }
+}
+
+fn error_response(status: StatusCode, error: &str, message: &str) -> Response<Body> {
+    let body = serde_json::json!({ "error": error, "message": message }).to_string();
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap()
+}
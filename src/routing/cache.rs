@@ -3,6 +3,20 @@ use std::sync::atomic::{AtomicU64, Ordering};
 
 use super::route::Route;
 
+/// Bumped whenever a router gains or loses a route
+static ROUTE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Current route table generation
+#[inline]
+pub fn route_generation() -> u64 {
+    ROUTE_GENERATION.load(Ordering::Acquire)
+}
+
+/// Invalidate cached matches after the route table changed
+pub fn bump_route_generation() {
+    ROUTE_GENERATION.fetch_add(1, Ordering::AcqRel);
+}
+
 /// Cached route entry with hit count
 #[derive(Clone)]
 pub struct CachedRoute {
@@ -10,6 +24,8 @@ pub struct CachedRoute {
     pub path_params: HashMap<String, String>,
     pub hits: u64,
     pub last_access: u64,
+    /// Route generation the match was made under
+    pub generation: u64,
 }
 
 impl CachedRoute {
@@ -19,6 +35,7 @@ impl CachedRoute {
             path_params,
             hits: 1,
             last_access: 0,
+            generation: route_generation(),
         }
     }
 }
@@ -39,9 +56,13 @@ impl RouteCache {
         }
     }
 
-    /// Get a cached route by path hash
+    /// Get a cached route by path hash, ignoring matches made before the
+    /// route table last changed
     #[inline]
     pub fn get(&self, path_hash: u64) -> Option<CachedRoute> {
+        let generation = route_generation();
+        self.cache
+            .remove_if(&path_hash, |_, entry| entry.generation != generation);
         if let Some(mut entry) = self.cache.get_mut(&path_hash) {
             let access_time = self.access_counter.fetch_add(1, Ordering::Relaxed);
             entry.hits += 1;
@@ -0,0 +1,216 @@
+//! Conflicts between route templates.
+//!
+//! Two routes on the same host conflict when a request path could match
+//! both for a method they share:
+//!
+//! - `duplicate`: the same template twice, `/users/{id}` and `/users/{id}`.
+//!   Always rejected.
+//! - `ambiguous`: templates that differ only in parameter names,
+//!   `/users/{id}` and `/users/{name}`. The route registered first answers.
+//! - `catch_all`: a catch-all where the other template has a parameter,
+//!   `/files/{*path}` and `/files/{id}/raw`. The later route is not routed.
+//!
+//! Static segments take precedence over parameters and catch-alls, so
+//! `/users/me` next to `/users/{id}`, or `/files/readme` next to
+//! `/files/{*path}`, is not a conflict. Neither are parameters with
+//! different type constraints: `/users/{id:int}` is tried before
+//! `/users/{slug}`, which gets the values that are not integers.
+
+use super::params::ParamType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    Duplicate,
+    Ambiguous,
+    CatchAll,
+}
+
+impl ConflictKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConflictKind::Duplicate => "duplicate",
+            ConflictKind::Ambiguous => "ambiguous",
+            ConflictKind::CatchAll => "catch_all",
+        }
+    }
+}
+
+/// A parameter name and its type constraint; `str` counts as none since it
+/// accepts every value
+type Param = (String, Option<ParamType>);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Static(String),
+    /// One or more parameters and the text around them, `{}` marking each
+    Params {
+        shape: String,
+        params: Vec<Param>,
+    },
+    CatchAll(String),
+}
+
+/// A route template split into path segments
+#[derive(Debug, Clone)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    /// Split `path`, written in matchit syntax (`{id}`, `{*rest}`), with
+    /// the `{name:type}` constraints of the original template
+    pub fn parse(path: &str, types: &[(String, ParamType)]) -> Self {
+        let constraint = |name: &str| {
+            types
+                .iter()
+                .find(|(param, _)| param == name)
+                .map(|(_, param_type)| *param_type)
+                .filter(|param_type| *param_type != ParamType::Str)
+        };
+        let segments = path
+            .split('/')
+            .map(|segment| {
+                if let Some(name) = segment.strip_prefix("{*").and_then(|s| s.strip_suffix('}')) {
+                    return Segment::CatchAll(name.to_string());
+                }
+                if !segment.contains('{') {
+                    return Segment::Static(segment.to_string());
+                }
+                let mut shape = String::with_capacity(segment.len());
+                let mut params = Vec::new();
+                let mut rest = segment;
+                while let Some(start) = rest.find('{') {
+                    let Some(len) = rest[start..].find('}') else {
+                        break;
+                    };
+                    let name = &rest[start + 1..start + len];
+                    shape.push_str(&rest[..start]);
+                    shape.push_str("{}");
+                    params.push((name.to_string(), constraint(name)));
+                    rest = &rest[start + len + 1..];
+                }
+                shape.push_str(rest);
+                Segment::Params { shape, params }
+            })
+            .collect();
+        Self { segments }
+    }
+
+    /// The template without parameter names. Templates with the same shape
+    /// share one entry in the radix tree.
+    pub fn shape(&self) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Static(text) => text.as_str(),
+                Segment::Params { shape, .. } => shape.as_str(),
+                Segment::CatchAll(_) => "{*}",
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Parameter names in the order they appear
+    pub fn param_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Static(_) => {}
+                Segment::Params { params, .. } => {
+                    names.extend(params.iter().map(|(name, _)| name.clone()))
+                }
+                Segment::CatchAll(name) => names.push(name.clone()),
+            }
+        }
+        names
+    }
+
+    /// Whether a parameter is constrained to a type other than `str`
+    pub fn is_constrained(&self) -> bool {
+        self.segments.iter().any(|segment| match segment {
+            Segment::Params { params, .. } => params.iter().any(|(_, t)| t.is_some()),
+            _ => false,
+        })
+    }
+
+    /// How this template conflicts with `other`, if a path matches both
+    pub fn conflict(&self, other: &Template) -> Option<ConflictKind> {
+        let mut renamed = false;
+        let mut ours = self.segments.iter();
+        let mut theirs = other.segments.iter();
+        loop {
+            match (ours.next(), theirs.next()) {
+                (None, None) => break,
+                (Some(Segment::CatchAll(a)), Some(Segment::CatchAll(b))) => {
+                    renamed |= a != b;
+                    break;
+                }
+                (Some(Segment::CatchAll(_)), Some(Segment::Params { .. }))
+                | (Some(Segment::Params { .. }), Some(Segment::CatchAll(_))) => {
+                    return Some(ConflictKind::CatchAll)
+                }
+                (Some(Segment::Static(a)), Some(Segment::Static(b))) if a == b => {}
+                (
+                    Some(Segment::Params {
+                        shape: a,
+                        params: a_params,
+                    }),
+                    Some(Segment::Params {
+                        shape: b,
+                        params: b_params,
+                    }),
+                ) if a == b => {
+                    for ((a_name, a_type), (b_name, b_type)) in a_params.iter().zip(b_params) {
+                        if a_type != b_type {
+                            return None;
+                        }
+                        renamed |= a_name != b_name;
+                    }
+                }
+                // Different text, or a static segment taking precedence
+                _ => return None,
+            }
+        }
+        Some(if renamed {
+            ConflictKind::Ambiguous
+        } else {
+            ConflictKind::Duplicate
+        })
+    }
+}
+
+/// A route conflicting with one registered before it
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub kind: ConflictKind,
+    /// Methods both routes are bound to
+    pub method: String,
+    pub host: Option<String>,
+    pub path: String,
+    pub handler: String,
+    pub other_path: String,
+    pub other_handler: String,
+}
+
+impl Conflict {
+    pub fn message(&self) -> String {
+        let route = match self.host {
+            Some(ref host) => format!("{} {}{} ({})", self.method, host, self.path, self.handler),
+            None => format!("{} {} ({})", self.method, self.path, self.handler),
+        };
+        match self.kind {
+            ConflictKind::Duplicate => format!(
+                "{} duplicates the route registered by {}",
+                route, self.other_handler
+            ),
+            ConflictKind::Ambiguous => format!(
+                "{} differs from {} ({}) only in parameter names; requests go to {}",
+                route, self.other_path, self.other_handler, self.other_handler
+            ),
+            ConflictKind::CatchAll => format!(
+                "{} and {} ({}) have a parameter and a catch-all in the same segment; {} is not routed",
+                route, self.other_path, self.other_handler, self.path
+            ),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! ETag providers for conditional GETs on dynamic routes.
+//!
+//! A provider is a version token registered under a name, or a Python
+//! callable returning the current one. A route registered with
+//! `etag_provider="name"` reads the token before dispatch: a GET or HEAD
+//! whose `If-None-Match` matches it is answered 304 without running the
+//! handler. Otherwise the handler runs and its successful response is tagged
+//! with the token, unless it set an ETag itself.
+//!
+//! Tokens live in the process that set them; with several workers, set them
+//! before the server starts or use a callable that reads shared state.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{header, HeaderValue, Response};
+use parking_lot::RwLock;
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use pyo3::types::PyString;
+
+use crate::http::conditional;
+use crate::http::request::Request;
+
+/// Where a provider's token comes from
+enum Source {
+    Unset,
+    Token(String),
+    /// Shared so reading it needs no GIL while the lock is held
+    Callable(Arc<Py<PyAny>>),
+}
+
+/// A named source of ETags
+pub struct EtagProvider {
+    name: String,
+    source: RwLock<Source>,
+}
+
+impl std::fmt::Debug for EtagProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EtagProvider")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl EtagProvider {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The current ETag, quoted; None when nothing provides one or the
+    /// callable failed
+    pub fn current(&self) -> Option<String> {
+        let callable = match &*self.source.read() {
+            Source::Unset => return None,
+            Source::Token(token) => return Some(token.clone()),
+            Source::Callable(callable) => callable.clone(),
+        };
+        Python::attach(|py| {
+            let token = callable
+                .call0(py)
+                .and_then(|value| token_of(value.bind(py)));
+            token.unwrap_or_else(|err| {
+                crate::hlog_warn!("ETag provider '{}' failed: {}", self.name, err);
+                None
+            })
+        })
+    }
+}
+
+/// A token as an ETag: strings and integers are quoted, None is no token
+fn token_of(value: &Bound<'_, PyAny>) -> PyResult<Option<String>> {
+    if value.is_none() {
+        return Ok(None);
+    }
+    if let Ok(text) = value.cast::<PyString>() {
+        return Ok(Some(conditional::quote(text.to_str()?)));
+    }
+    if let Ok(number) = value.extract::<i64>() {
+        return Ok(Some(conditional::quote(&number.to_string())));
+    }
+    Err(PyTypeError::new_err(
+        "an ETag token must be a string, an integer or None",
+    ))
+}
+
+/// Every provider referenced by a route or set from Python
+static PROVIDERS: RwLock<Option<HashMap<String, Arc<EtagProvider>>>> = RwLock::new(None);
+
+/// The provider called `name`, created unset when nothing referenced it
+/// before. Routes resolve their provider once, at registration.
+pub fn handle(name: &str) -> Arc<EtagProvider> {
+    if let Some(provider) = PROVIDERS.read().as_ref().and_then(|all| all.get(name)) {
+        return provider.clone();
+    }
+    PROVIDERS
+        .write()
+        .get_or_insert_with(HashMap::new)
+        .entry(name.to_string())
+        .or_insert_with(|| {
+            Arc::new(EtagProvider {
+                name: name.to_string(),
+                source: RwLock::new(Source::Unset),
+            })
+        })
+        .clone()
+}
+
+/// 304 for a GET or HEAD whose `If-None-Match` matches `etag`
+pub fn check(etag: Option<&str>, request: &Request) -> Option<Response<Body>> {
+    let etag = etag?;
+    if !matches!(request.method_name(), "GET" | "HEAD") {
+        return None;
+    }
+    let if_none_match = request.header("if-none-match")?;
+    conditional::none_match(&if_none_match, etag).then(|| conditional::not_modified(etag))
+}
+
+/// Tag a successful GET or HEAD response with the provider's `etag`, unless
+/// the handler set one
+pub fn tag(res: &mut Response<Body>, etag: Option<&str>, method: &str) {
+    let Some(etag) = etag else {
+        return;
+    };
+    if !matches!(method, "GET" | "HEAD")
+        || !res.status().is_success()
+        || res.headers().contains_key(header::ETAG)
+    {
+        return;
+    }
+    if let Ok(value) = HeaderValue::from_str(etag) {
+        res.headers_mut().insert(header::ETAG, value);
+    }
+}
+
+/// Set the ETag provider `name`: a version token (string or integer), a
+/// callable returning the current one or None, or None to unset it
+#[pyfunction]
+pub fn set_etag_provider(name: &str, provider: Option<&Bound<'_, PyAny>>) -> PyResult<()> {
+    let source = match provider {
+        None => Source::Unset,
+        Some(provider) if provider.is_callable() => {
+            Source::Callable(Arc::new(provider.clone().unbind()))
+        }
+        Some(provider) => match token_of(provider)? {
+            Some(token) => Source::Token(token),
+            None => Source::Unset,
+        },
+    };
+    *handle(name).source.write() = source;
+    Ok(())
+}
+
+/// The current ETag of the provider `name`, None when it has none
+#[pyfunction]
+pub fn current_etag(name: &str) -> Option<String> {
+    handle(name).current()
+}
@@ -0,0 +1,289 @@
+//! Feature flags evaluated per request.
+//!
+//! A flag is on for a percentage of requests, 0 (off) to 100 (on). Partial
+//! rollouts hash the flag name with a sticky key, the authenticated user id
+//! by default, falling back to the client IP and then the request id, so a
+//! given user lands in the same bucket on every request and every worker.
+//! Evaluating a flag is that hash plus an atomic load.
+//!
+//! A route registered with `feature_flag="name"` answers the flag's
+//! `off_status` (404 unless set to 503) without running its handler while
+//! the flag is off for the request. Flags are set through the runtime config
+//! (`feature_flags`), so `Server.set_flag()` reaches every worker; flags no
+//! one has set are off.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU16, AtomicU64, AtomicU8, Ordering};
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{header, Response, StatusCode};
+use parking_lot::RwLock;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde_json::{Map, Value};
+
+use crate::core::request_scope::RequestScope;
+use crate::http::request::Request;
+use crate::http::error_envelope::{self, ErrorCode};
+
+/// Statuses a route answers while its flag is off
+pub const OFF_STATUSES: [u16; 2] = [404, 503];
+
+/// The key partial rollouts bucket requests by
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StickyBy {
+    /// The authenticated user, else the client IP, else the request
+    UserId = 0,
+    /// The client IP, else the request
+    ClientIp = 1,
+    /// Each request on its own
+    RequestId = 2,
+}
+
+impl StickyBy {
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "user_id" => Some(Self::UserId),
+            "client_ip" => Some(Self::ClientIp),
+            "request_id" => Some(Self::RequestId),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::UserId => "user_id",
+            Self::ClientIp => "client_ip",
+            Self::RequestId => "request_id",
+        }
+    }
+
+    fn from_code(code: u8) -> Self {
+        match code {
+            1 => Self::ClientIp,
+            2 => Self::RequestId,
+            _ => Self::UserId,
+        }
+    }
+}
+
+/// Who a request is for, as far as rollouts are concerned
+pub struct Subject<'a> {
+    pub user_id: Option<&'a str>,
+    pub client_ip: Option<&'a str>,
+    pub request_id: &'a str,
+}
+
+impl Subject<'_> {
+    fn key(&self, sticky_by: StickyBy) -> &str {
+        let user_id = match sticky_by {
+            StickyBy::UserId => self.user_id,
+            _ => None,
+        };
+        let client_ip = match sticky_by {
+            StickyBy::RequestId => None,
+            _ => self.client_ip,
+        };
+        user_id.or(client_ip).unwrap_or(self.request_id)
+    }
+}
+
+/// New settings for a flag; None keeps the current value
+#[derive(Clone, Debug, Default)]
+pub struct FlagUpdate {
+    pub percentage: Option<u8>,
+    pub sticky_by: Option<StickyBy>,
+    pub off_status: Option<u16>,
+}
+
+/// One flag's settings and evaluation counters
+#[derive(Debug)]
+pub struct Flag {
+    name: String,
+    percentage: AtomicU8,
+    sticky_by: AtomicU8,
+    off_status: AtomicU16,
+    on: AtomicU64,
+    off: AtomicU64,
+}
+
+impl Flag {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            percentage: AtomicU8::new(0),
+            sticky_by: AtomicU8::new(StickyBy::UserId as u8),
+            off_status: AtomicU16::new(404),
+            on: AtomicU64::new(0),
+            off: AtomicU64::new(0),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn percentage(&self) -> u8 {
+        self.percentage.load(Ordering::Relaxed)
+    }
+
+    pub fn sticky_by(&self) -> StickyBy {
+        StickyBy::from_code(self.sticky_by.load(Ordering::Relaxed))
+    }
+
+    pub fn off_status(&self) -> u16 {
+        self.off_status.load(Ordering::Relaxed)
+    }
+
+    /// Whether the flag is on for `subject`, counted in the flag's stats
+    pub fn evaluate(&self, subject: &Subject<'_>) -> bool {
+        let on = match self.percentage() {
+            0 => false,
+            100.. => true,
+            percentage => bucket(&self.name, subject.key(self.sticky_by())) < percentage,
+        };
+        let counter = if on { &self.on } else { &self.off };
+        counter.fetch_add(1, Ordering::Relaxed);
+        on
+    }
+
+    fn apply(&self, update: &FlagUpdate) {
+        if let Some(percentage) = update.percentage {
+            self.percentage
+                .store(percentage.min(100), Ordering::Relaxed);
+        }
+        if let Some(sticky_by) = update.sticky_by {
+            self.sticky_by.store(sticky_by as u8, Ordering::Relaxed);
+        }
+        if let Some(status) = update.off_status {
+            self.off_status.store(status, Ordering::Relaxed);
+        }
+    }
+
+    fn settings(&self) -> Value {
+        serde_json::json!({
+            "percentage": self.percentage(),
+            "sticky_by": self.sticky_by().name(),
+            "off_status": self.off_status(),
+        })
+    }
+}
+
+/// Bucket 0-99 of `key` for the flag `name`: FNV-1a, so it is the same in
+/// every process and across restarts
+pub fn bucket(name: &str, key: &str) -> u8 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in name.bytes().chain([0xff]).chain(key.bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    (hash % 100) as u8
+}
+
+/// Every flag referenced by a route, a handler or the runtime config
+static FLAGS: RwLock<Option<HashMap<String, Arc<Flag>>>> = RwLock::new(None);
+
+/// The flag called `name`, created off when nothing referenced it before.
+/// Routes resolve their flag once, at registration.
+pub fn handle(name: &str) -> Arc<Flag> {
+    if let Some(flag) = lookup(name) {
+        return flag;
+    }
+    FLAGS
+        .write()
+        .get_or_insert_with(HashMap::new)
+        .entry(name.to_string())
+        .or_insert_with(|| Arc::new(Flag::new(name)))
+        .clone()
+}
+
+/// The flag called `name`, None when nothing referenced it
+pub fn lookup(name: &str) -> Option<Arc<Flag>> {
+    FLAGS.read().as_ref()?.get(name).cloned()
+}
+
+/// Apply a runtime config update; a None update turns the flag off
+pub fn set(name: &str, update: Option<&FlagUpdate>) {
+    let flag = handle(name);
+    match update {
+        Some(update) => flag.apply(update),
+        None => flag.percentage.store(0, Ordering::Relaxed),
+    }
+}
+
+/// Settings of every flag, keyed by name, as the runtime config reports them
+pub fn current() -> Map<String, Value> {
+    FLAGS
+        .read()
+        .iter()
+        .flatten()
+        .map(|(name, flag)| (name.clone(), flag.settings()))
+        .collect()
+}
+
+/// Evaluate `flag` for `request`, keyed by the user its scope records when
+/// the auth middleware authenticated one
+pub fn evaluate_for(flag: &Flag, request: &Request) -> bool {
+    let scope = request.scope().unwrap_or_else(|| {
+        let scope = Arc::new(RequestScope::from_request(request));
+        request.set_scope(scope.clone());
+        scope
+    });
+    let peer = scope
+        .client_ip
+        .is_none()
+        .then(|| request.peer_addr().map(|(ip, _)| ip))
+        .flatten();
+    flag.evaluate(&Subject {
+        user_id: scope.user_id.as_deref(),
+        client_ip: scope.client_ip.as_deref().or(peer.as_deref()),
+        request_id: &scope.request_id,
+    })
+}
+
+/// The response for a request to a route whose flag is off, None to go on
+pub fn check(flag: Option<&Arc<Flag>>, request: &Request) -> Option<Response<Body>> {
+    let flag = flag?;
+    if evaluate_for(flag, request) {
+        return None;
+    }
+    if flag.off_status() == 404 {
+        // Indistinguishable from a route that does not exist
+        return Some(error_envelope::response(
+            StatusCode::NOT_FOUND,
+            ErrorCode::NotFound,
+            "Not Found",
+            request.header("accept").as_deref(),
+        ));
+    }
+    let body = serde_json::json!({
+        "error": "feature_disabled",
+        "message": format!("Feature '{}' is not available", flag.name()),
+    })
+    .to_string();
+    Some(
+        Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap(),
+    )
+}
+
+/// Evaluation counts and settings of every flag in this process: a dict of
+/// flag name to `on`, `off`, `percentage`, `sticky_by` and `off_status`
+#[pyfunction]
+pub fn feature_flag_stats(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let stats = PyDict::new(py);
+    for (name, flag) in FLAGS.read().iter().flatten() {
+        let entry = PyDict::new(py);
+        entry.set_item("on", flag.on.load(Ordering::Relaxed))?;
+        entry.set_item("off", flag.off.load(Ordering::Relaxed))?;
+        entry.set_item("percentage", flag.percentage())?;
+        entry.set_item("sticky_by", flag.sticky_by().name())?;
+        entry.set_item("off_status", flag.off_status())?;
+        stats.set_item(name, entry)?;
+    }
+    Ok(stats)
+}
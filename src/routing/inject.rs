@@ -0,0 +1,364 @@
+//! Handler arguments filled by parameter name.
+//!
+//! Handlers registered with the `Router` decorators declare what they need
+//! in their signature instead of reading it off the request:
+//!
+//! ```python
+//! @router.get("/users/{id:int}")
+//! def get_user(id: int, verbose: bool = False, request=None): ...
+//! ```
+//!
+//! The signature is read once, when the route is added, into an
+//! [`ArgPlan`]. Each parameter is filled from the first source that claims
+//! it:
+//!
+//! - the request (`Request` annotation, or named `request`/`req`) or the
+//!   response (`Response` annotation, or named `response`/`res`)
+//! - the path parameter of that name, converted by its `{name:type}`
+//!   constraint or else by the annotation
+//! - the dependency provided under that name, which must be provided before
+//!   the route is added
+//! - the query parameter of that name when the parameter has a default,
+//!   converted by the annotation or the type of the default; a missing one
+//!   leaves the default in place
+//!
+//! A parameter none of these claims fails registration. A path or query
+//! value that does not convert is answered with 400 before the handler
+//! runs. Handlers taking exactly `(req, res)` get no plan and are called as
+//! before.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDate, PyDict, PyFloat, PyInt, PyString};
+
+use super::params::{ParamType, TypedValue};
+use crate::core::deps::{self, RequestDeps};
+use crate::http::request::Request;
+use crate::http::response::Response;
+
+/// How a handler's parameters are filled, resolved when its route is added
+#[derive(Debug)]
+pub struct ArgPlan {
+    params: Vec<Param>,
+}
+
+#[derive(Debug)]
+struct Param {
+    name: String,
+    source: Source,
+}
+
+#[derive(Debug)]
+enum Source {
+    Request,
+    Response,
+    Path(Convert),
+    Dep(usize),
+    Query(Convert),
+}
+
+/// Conversion of a path or query string
+#[derive(Debug, Clone, Copy)]
+enum Convert {
+    Typed(ParamType),
+    Bool,
+}
+
+impl Convert {
+    fn convert(self, value: &str) -> Option<Value> {
+        match self {
+            Self::Typed(param_type) => param_type.convert(value).map(Value::Typed),
+            Self::Bool => parse_bool(value).map(Value::Bool),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Typed(ParamType::Str) => "a str",
+            Self::Typed(ParamType::Int) => "an int",
+            Self::Typed(ParamType::Float) => "a float",
+            Self::Typed(ParamType::Uuid) => "a UUID",
+            Self::Typed(ParamType::Date) => "a YYYY-MM-DD date",
+            Self::Bool => "a bool",
+        }
+    }
+}
+
+/// `?flag`, `?flag=true|1|yes|on` or `?flag=false|0|no|off`
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "" | "true" | "1" | "yes" | "on" => Some(true),
+        "false" | "0" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// A path or query value converted for one request
+#[derive(Debug)]
+enum Value {
+    Typed(TypedValue),
+    Bool(bool),
+}
+
+/// What an annotation asks for
+enum Annotation {
+    Request,
+    Response,
+    Convert(Convert),
+    Other,
+}
+
+impl ArgPlan {
+    /// Read the signature of `handler` for the route `label` (`GET /users/{id}`),
+    /// whose path parameters are `names` with the constraints `types`.
+    ///
+    /// Returns None for a plain `(req, res)` handler.
+    pub fn build(
+        handler: &Bound<'_, PyAny>,
+        label: &str,
+        names: &[String],
+        types: &[(String, ParamType)],
+    ) -> PyResult<Option<Self>> {
+        let py = handler.py();
+        let inspect = py.import("inspect")?;
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("eval_str", true)?;
+        // Annotations naming something the handler's module cannot resolve
+        // are left as strings
+        let signature = inspect
+            .call_method("signature", (handler,), Some(&kwargs))
+            .or_else(|_| inspect.call_method1("signature", (handler,)))?;
+        let parameter = inspect.getattr("Parameter")?;
+        let empty = parameter.getattr("empty")?;
+        let positional_only = parameter.getattr("POSITIONAL_ONLY")?;
+        let var_positional = parameter.getattr("VAR_POSITIONAL")?;
+        let var_keyword = parameter.getattr("VAR_KEYWORD")?;
+        let handler_name = handler
+            .getattr("__name__")
+            .map(|name| name.to_string())
+            .unwrap_or_else(|_| "handler".to_string());
+
+        let mut params = Vec::new();
+        let mut positional = true;
+        for item in signature
+            .getattr("parameters")?
+            .call_method0("values")?
+            .try_iter()?
+        {
+            let item = item?;
+            let name: String = item.getattr("name")?.extract()?;
+            let kind = item.getattr("kind")?;
+            if kind.eq(&var_positional)? || kind.eq(&var_keyword)? {
+                continue;
+            }
+            let context = || {
+                format!(
+                    "Cannot add {}: parameter '{}' of {}()",
+                    label, name, handler_name
+                )
+            };
+            if kind.eq(&positional_only)? {
+                return Err(PyValueError::new_err(format!(
+                    "{} is positional-only and cannot be injected",
+                    context()
+                )));
+            }
+            positional &= params.len() < 2;
+            let annotation = item.getattr("annotation")?;
+            let annotation = match annotation.is(&empty) {
+                true => None,
+                false => Some(annotation_kind(&annotation)?),
+            };
+            let default = item.getattr("default")?;
+            let has_default = !default.is(&empty);
+
+            let source = match (&annotation, name.as_str()) {
+                (Some(Annotation::Request), _) => Source::Request,
+                (Some(Annotation::Response), _) => Source::Response,
+                _ if names.contains(&name) => {
+                    let constraint = types
+                        .iter()
+                        .find(|(param, _)| *param == name)
+                        .map(|(_, param_type)| Convert::Typed(*param_type));
+                    let convert = match (constraint, &annotation) {
+                        (Some(convert), _) => convert,
+                        (None, Some(Annotation::Convert(convert))) => *convert,
+                        (None, None) => Convert::Typed(ParamType::Str),
+                        (None, Some(_)) => {
+                            return Err(unsupported(&context(), &item, "path"));
+                        }
+                    };
+                    Source::Path(convert)
+                }
+                (None, "request" | "req") => Source::Request,
+                (None, "response" | "res") => Source::Response,
+                _ => match deps::index_of(&name) {
+                    Some(index) => Source::Dep(index),
+                    None if has_default => {
+                        let convert = match &annotation {
+                            Some(Annotation::Convert(convert)) => *convert,
+                            Some(_) => return Err(unsupported(&context(), &item, "query")),
+                            None => default_convert(&default),
+                        };
+                        Source::Query(convert)
+                    }
+                    None => {
+                        return Err(PyValueError::new_err(format!(
+                            "{} is not a path parameter, a provided dependency or the request, and has no default to read it from the query",
+                            context()
+                        )));
+                    }
+                },
+            };
+            params.push(Param { name, source });
+        }
+
+        let raw = positional
+            && matches!(
+                params.as_slice(),
+                [
+                    Param {
+                        source: Source::Request,
+                        ..
+                    },
+                    Param {
+                        source: Source::Response,
+                        ..
+                    }
+                ]
+            );
+        Ok((!raw).then_some(Self { params }))
+    }
+
+    /// Convert the path and query values of `request`; the error is the
+    /// message of a 400 response
+    pub fn bind(&self, request: &Request) -> Result<BoundArgs, String> {
+        let values = self
+            .params
+            .iter()
+            .map(|param| {
+                let (raw, convert, what) = match param.source {
+                    Source::Path(convert) => (request.param(&param.name), convert, "Path"),
+                    Source::Query(convert) => (request.query(&param.name), convert, "Query"),
+                    _ => return Ok(None),
+                };
+                let Some(raw) = raw else {
+                    return Ok(None);
+                };
+                convert.convert(&raw).map(Some).ok_or_else(|| {
+                    format!(
+                        "{} parameter '{}' must be {}",
+                        what,
+                        param.name,
+                        convert.name()
+                    )
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(BoundArgs { values })
+    }
+
+    /// Keyword arguments for one call of the handler
+    pub fn kwargs<'py>(
+        &self,
+        py: Python<'py>,
+        bound: BoundArgs,
+        req: &Py<PyAny>,
+        res: &Py<PyAny>,
+        request_deps: &RequestDeps,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let kwargs = PyDict::new(py);
+        for (param, value) in self.params.iter().zip(bound.values) {
+            let name = PyString::intern(py, &param.name);
+            match (&param.source, value) {
+                (Source::Request, _) => kwargs.set_item(name, req.bind(py))?,
+                (Source::Response, _) => kwargs.set_item(name, res.bind(py))?,
+                (Source::Dep(index), _) => {
+                    kwargs.set_item(name, deps::resolve(py, *index, Some(request_deps))?)?
+                }
+                (_, Some(Value::Typed(value))) => kwargs.set_item(name, value.to_py(py)?)?,
+                (_, Some(Value::Bool(value))) => kwargs.set_item(name, value)?,
+                // A missing query value leaves the default in place
+                (_, None) => {}
+            }
+        }
+        Ok(kwargs)
+    }
+}
+
+/// Path and query values converted for one request, in plan order
+pub struct BoundArgs {
+    values: Vec<Option<Value>>,
+}
+
+fn annotation_kind(annotation: &Bound<'_, PyAny>) -> PyResult<Annotation> {
+    let py = annotation.py();
+    if let Ok(text) = annotation.cast::<PyString>() {
+        return Ok(match text.to_str()? {
+            "Request" => Annotation::Request,
+            "Response" => Annotation::Response,
+            "str" => Annotation::Convert(Convert::Typed(ParamType::Str)),
+            "int" => Annotation::Convert(Convert::Typed(ParamType::Int)),
+            "float" => Annotation::Convert(Convert::Typed(ParamType::Float)),
+            "bool" => Annotation::Convert(Convert::Bool),
+            _ => Annotation::Other,
+        });
+    }
+    let kind = if annotation.is(py.get_type::<Request>()) {
+        Annotation::Request
+    } else if annotation.is(py.get_type::<Response>()) {
+        Annotation::Response
+    } else if annotation.is(py.get_type::<PyString>()) {
+        Annotation::Convert(Convert::Typed(ParamType::Str))
+    } else if annotation.is(py.get_type::<PyBool>()) {
+        Annotation::Convert(Convert::Bool)
+    } else if annotation.is(py.get_type::<PyInt>()) {
+        Annotation::Convert(Convert::Typed(ParamType::Int))
+    } else if annotation.is(py.get_type::<PyFloat>()) {
+        Annotation::Convert(Convert::Typed(ParamType::Float))
+    } else if annotation.is(py.get_type::<PyDate>()) {
+        Annotation::Convert(Convert::Typed(ParamType::Date))
+    } else if annotation.is(py.import("uuid")?.getattr("UUID")?) {
+        Annotation::Convert(Convert::Typed(ParamType::Uuid))
+    } else {
+        // `Optional[X]` and `X | None` convert like `X`
+        let typing = py.import("typing")?;
+        let members: Vec<Bound<'_, PyAny>> = typing
+            .call_method1("get_args", (annotation,))?
+            .try_iter()?
+            .collect::<PyResult<_>>()?;
+        let none_type = py.None().into_bound(py).get_type();
+        let inner: Vec<&Bound<'_, PyAny>> = members.iter().filter(|m| !m.is(&none_type)).collect();
+        let is_union = members.len() == 2 && inner.len() == 1;
+        match is_union {
+            true => annotation_kind(inner[0])?,
+            false => Annotation::Other,
+        }
+    };
+    Ok(kind)
+}
+
+/// Conversion implied by the type of a query parameter's default
+fn default_convert(default: &Bound<'_, PyAny>) -> Convert {
+    if default.is_exact_instance_of::<PyBool>() {
+        Convert::Bool
+    } else if default.is_exact_instance_of::<PyInt>() {
+        Convert::Typed(ParamType::Int)
+    } else if default.is_exact_instance_of::<PyFloat>() {
+        Convert::Typed(ParamType::Float)
+    } else {
+        Convert::Typed(ParamType::Str)
+    }
+}
+
+fn unsupported(context: &str, item: &Bound<'_, PyAny>, what: &str) -> PyErr {
+    let annotation = item
+        .getattr("annotation")
+        .and_then(|annotation| annotation.repr())
+        .map(|repr| repr.to_string())
+        .unwrap_or_default();
+    PyValueError::new_err(format!(
+        "{} is a {} parameter; those convert to str, int, float, bool, uuid.UUID or datetime.date, not {}",
+        context, what, annotation
+    ))
+}
@@ -0,0 +1,238 @@
+//! Per-route memoization of constant responses.
+//!
+//! A route registered with `memoize_ttl_secs` keeps the first 200 response
+//! its handler gives to a GET and answers later GET and HEAD requests with
+//! it until the TTL expires or `Server.invalidate_route_cache(path)` drops
+//! it. The key is the request path, so each value of a path parameter gets
+//! its own entry, but not the query or body; `memoize_vary` names request
+//! headers whose values get their own entry too. Responses that
+//! set cookies, are streamed or are larger than `MAX_BODY_BYTES` are never
+//! kept, and neither is any status other than 200.
+//!
+//! Entries live in the process that stored them; invalidation reaches the
+//! calling process only, so with several workers the TTL bounds how long
+//! the others keep an old response.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+use axum::body::Body;
+use axum::http::Response;
+use parking_lot::{Mutex, RwLock};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::http::conditional::ConditionalEtag;
+use crate::http::request::Request;
+use crate::http::stream_drain::LiveStream;
+use crate::middleware::idempotency::StoredResponse;
+use crate::middleware::singleflight::{self, Singleflight};
+
+/// Larger responses are not kept
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+/// Entries kept per route across paths and `memoize_vary` values
+const MAX_VARIANTS: usize = 64;
+
+struct Entry {
+    response: Arc<StoredResponse>,
+    /// `Response.with_etag` status, so replays still answer conditionals
+    conditional: Option<ConditionalEtag>,
+    expires: Instant,
+}
+
+/// Memoized responses of one route
+pub struct RouteMemo {
+    ttl: Duration,
+    /// Request headers (lowercase) that are part of the key
+    vary: Vec<String>,
+    entries: Mutex<HashMap<String, Entry>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    stores: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+impl std::fmt::Debug for RouteMemo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RouteMemo")
+            .field("ttl", &self.ttl)
+            .field("vary", &self.vary)
+            .finish()
+    }
+}
+
+impl RouteMemo {
+    pub fn new(ttl: Duration, vary: Vec<String>) -> Self {
+        Self {
+            ttl,
+            vary: vary.iter().map(|h| h.to_ascii_lowercase()).collect(),
+            entries: Mutex::default(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            stores: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    pub fn vary(&self) -> &[String] {
+        &self.vary
+    }
+
+    /// The entry key of `request`, None for methods never memoized
+    pub fn key(&self, request: &Request) -> Option<String> {
+        if !matches!(request.method_name(), "GET" | "HEAD") {
+            return None;
+        }
+        let mut key = request.path().to_string();
+        for name in &self.vary {
+            key.push('\n');
+            key.push_str(&request.header(name).unwrap_or_default());
+        }
+        Some(key)
+    }
+
+    /// The kept response for `key`, while it is fresh
+    pub fn lookup(&self, key: &str) -> Option<Response<Body>> {
+        let mut entries = self.entries.lock();
+        let fresh = entries
+            .get(key)
+            .filter(|entry| entry.expires > Instant::now())
+            .map(|entry| (entry.response.clone(), entry.conditional));
+        let Some((response, conditional)) = fresh else {
+            entries.remove(key);
+            drop(entries);
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        drop(entries);
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        let mut res = singleflight::to_response(&response);
+        if let Some(conditional) = conditional {
+            res.extensions_mut().insert(conditional);
+        }
+        Some(res)
+    }
+
+    /// Keep `res` under `key` when it qualifies, returning it to send
+    pub async fn store(&self, key: String, method: &str, res: Response<Body>) -> Response<Body> {
+        let fits = axum::body::HttpBody::size_hint(res.body())
+            .exact()
+            .is_some_and(|len| len <= MAX_BODY_BYTES as u64);
+        if method != "GET"
+            || res.status() != axum::http::StatusCode::OK
+            || res.extensions().get::<LiveStream>().is_some()
+            || !fits
+        {
+            return res;
+        }
+        let (parts, body) = res.into_parts();
+        let bytes = match axum::body::to_bytes(body, MAX_BODY_BYTES).await {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                crate::hlog_error!("Failed to read response body: {}", err);
+                return Response::builder()
+                    .status(500)
+                    .body(Body::from("Internal Server Error"))
+                    .unwrap();
+            }
+        };
+        let headers = parts
+            .headers
+            .iter()
+            .filter_map(|(name, value)| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|value| (name.as_str().to_string(), value.to_string()))
+            })
+            .collect();
+        // Refuses responses that set cookies
+        if let Some(stored) = Singleflight::shareable(200, headers, Some(bytes.clone())) {
+            let now = Instant::now();
+            let mut entries = self.entries.lock();
+            if entries.len() >= MAX_VARIANTS && !entries.contains_key(&key) {
+                entries.retain(|_, entry| entry.expires > now);
+            }
+            if entries.len() < MAX_VARIANTS || entries.contains_key(&key) {
+                entries.insert(
+                    key,
+                    Entry {
+                        response: Arc::new(stored),
+                        conditional: parts.extensions.get::<ConditionalEtag>().copied(),
+                        expires: now + self.ttl,
+                    },
+                );
+                self.stores.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        Response::from_parts(parts, Body::from(bytes))
+    }
+
+    /// Drop every entry
+    pub fn clear(&self) {
+        self.entries.lock().clear();
+        self.invalidations.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Memos of routes added to a router, by full path
+static MEMOS: RwLock<Vec<(String, Weak<RouteMemo>)>> = RwLock::new(Vec::new());
+
+/// Make the memo of a route added under `path` reachable for invalidation
+/// and stats
+pub fn register(path: &str, memo: &Arc<RouteMemo>) {
+    let mut memos = MEMOS.write();
+    memos.retain(|(_, memo)| memo.strong_count() > 0);
+    memos.push((path.to_string(), Arc::downgrade(memo)));
+}
+
+/// Drop the kept responses of the routes registered under `path`,
+/// returning how many routes had a memo
+pub fn invalidate(path: &str) -> usize {
+    let memos: Vec<Arc<RouteMemo>> = MEMOS
+        .read()
+        .iter()
+        .filter(|(registered, _)| registered == path)
+        .filter_map(|(_, memo)| memo.upgrade())
+        .collect();
+    for memo in &memos {
+        memo.clear();
+    }
+    memos.len()
+}
+
+/// Counters of every memoized route in this process: a dict of route path
+/// to `hits`, `misses`, `stores`, `invalidations` and `entries`. Routes
+/// sharing a path are summed.
+#[pyfunction]
+pub fn route_memo_stats(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let mut totals: HashMap<String, [u64; 5]> = HashMap::new();
+    for (path, memo) in MEMOS.read().iter() {
+        let Some(memo) = memo.upgrade() else {
+            continue;
+        };
+        let counters = totals.entry(path.clone()).or_default();
+        counters[0] += memo.hits.load(Ordering::Relaxed);
+        counters[1] += memo.misses.load(Ordering::Relaxed);
+        counters[2] += memo.stores.load(Ordering::Relaxed);
+        counters[3] += memo.invalidations.load(Ordering::Relaxed);
+        counters[4] += memo.entries.lock().len() as u64;
+    }
+    let stats = PyDict::new(py);
+    for (path, [hits, misses, stores, invalidations, entries]) in totals {
+        let entry = PyDict::new(py);
+        entry.set_item("hits", hits)?;
+        entry.set_item("misses", misses)?;
+        entry.set_item("stores", stores)?;
+        entry.set_item("invalidations", invalidations)?;
+        entry.set_item("entries", entries)?;
+        stats.set_item(path, entry)?;
+    }
+    Ok(stats)
+}
@@ -1,7 +1,30 @@
+pub mod auth;
 pub mod cache;
+pub mod conflicts;
+pub mod etags;
+pub mod flags;
+pub mod inject;
+pub mod memo;
+pub mod params;
+pub mod retry;
 pub mod route;
 pub mod router;
 
 pub use cache::RouteCache;
+pub use retry::RetryPolicy;
 pub use route::Route;
 pub use router::Router;
+
+use pyo3::prelude::*;
+
+/// Register routing classes.
+pub fn register_all(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Route>()?;
+    m.add_class::<Router>()?;
+    m.add_class::<RetryPolicy>()?;
+    m.add_function(wrap_pyfunction!(flags::feature_flag_stats, m)?)?;
+    m.add_function(wrap_pyfunction!(etags::set_etag_provider, m)?)?;
+    m.add_function(wrap_pyfunction!(etags::current_etag, m)?)?;
+    m.add_function(wrap_pyfunction!(memo::route_memo_stats, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,125 @@
+//! Typed route parameters.
+//!
+//! A parameter written as `{name:type}` only matches values of that type and
+//! is handed to handlers converted: `int` and `float` as Python numbers,
+//! `uuid` as `uuid.UUID`, `date` (`YYYY-MM-DD`) as `datetime.date`, `str`
+//! unchanged. A value that does not convert is a no-match, so handlers
+//! never see one. `:name`, `{name}` and `*rest` parameters stay strings.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use chrono::{Datelike, NaiveDate};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDate;
+use pyo3::IntoPyObjectExt;
+
+/// Type constraint on a route parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamType {
+    Str,
+    Int,
+    Float,
+    Uuid,
+    Date,
+}
+
+impl ParamType {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "str" => Some(Self::Str),
+            "int" => Some(Self::Int),
+            "float" => Some(Self::Float),
+            "uuid" => Some(Self::Uuid),
+            "date" => Some(Self::Date),
+            _ => None,
+        }
+    }
+
+    /// Parse `value`, `None` when it is not of this type
+    pub fn convert(&self, value: &str) -> Option<TypedValue> {
+        match self {
+            Self::Str => Some(TypedValue::Str(value.to_string())),
+            Self::Int => i64::from_str(value).ok().map(TypedValue::Int),
+            Self::Float => f64::from_str(value)
+                .ok()
+                .filter(|f| f.is_finite())
+                .map(TypedValue::Float),
+            Self::Uuid => uuid::Uuid::parse_str(value).ok().map(TypedValue::Uuid),
+            Self::Date => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .map(TypedValue::Date),
+        }
+    }
+}
+
+/// A route parameter converted according to its constraint
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypedValue {
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Uuid(uuid::Uuid),
+    Date(NaiveDate),
+}
+
+impl TypedValue {
+    pub fn to_py<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        match self {
+            Self::Str(value) => value.into_bound_py_any(py),
+            Self::Int(value) => value.into_bound_py_any(py),
+            Self::Float(value) => value.into_bound_py_any(py),
+            Self::Uuid(value) => py
+                .import("uuid")?
+                .getattr("UUID")?
+                .call1((value.hyphenated().to_string(),)),
+            Self::Date(value) => {
+                Ok(
+                    PyDate::new(py, value.year(), value.month() as u8, value.day() as u8)?
+                        .into_any(),
+                )
+            }
+        }
+    }
+}
+
+/// Constrained parameters in `path`, in order of appearance.
+///
+/// Fails on an unknown type name, so typos surface when the route is added.
+pub fn param_types(path: &str) -> PyResult<Vec<(String, ParamType)>> {
+    let mut types = Vec::new();
+    let mut rest = path;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let inner = &rest[start + 1..start + len];
+        rest = &rest[start + len + 1..];
+        let Some((name, type_name)) = inner.split_once(':') else {
+            continue;
+        };
+        let param_type = ParamType::parse(type_name).ok_or_else(|| {
+            PyValueError::new_err(format!(
+                "Unknown type '{}' for route parameter '{}' in '{}'; expected str, int, float, uuid or date",
+                type_name, name, path
+            ))
+        })?;
+        types.push((name.to_string(), param_type));
+    }
+    Ok(types)
+}
+
+/// Convert the constrained parameters of a match, `None` if any fails
+pub fn convert(
+    types: &[(String, ParamType)],
+    params: &HashMap<String, String>,
+) -> Option<HashMap<String, TypedValue>> {
+    types
+        .iter()
+        .map(|(name, param_type)| {
+            let value = params.get(name)?;
+            Some((name.clone(), param_type.convert(value)?))
+        })
+        .collect()
+}
@@ -0,0 +1,147 @@
+//! Re-running a route's handler after a transient failure (`Route(retry=...)`).
+//!
+//! The worker runs the handler again while the response status or the
+//! exception it raised matches the route's `RetryPolicy`. Request bodies are
+//! read in full before dispatch, so every attempt sees the same body; only
+//! idempotent methods are retried unless the route sets `retry_unsafe`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyType;
+
+use crate::http::response::RaisedException;
+
+static RETRIES: AtomicU64 = AtomicU64::new(0);
+
+/// Count a handler run again under a retry policy
+pub fn count() {
+    RETRIES.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Handler retries since the process started
+pub fn retries_total() -> u64 {
+    RETRIES.load(Ordering::Relaxed)
+}
+
+/// When, and how often, a route's handler runs again after a transient failure.
+#[pyclass(frozen, from_py_object)]
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Handler runs in total, the first included
+    #[pyo3(get)]
+    pub max_attempts: u32,
+    /// Response statuses that trigger another attempt
+    #[pyo3(get)]
+    pub on_statuses: Vec<u16>,
+    /// Names of exception classes that trigger another attempt; subclasses match
+    #[pyo3(get)]
+    pub on_exceptions: Vec<String>,
+    /// Delay before the first retry; doubles with each further retry
+    #[pyo3(get)]
+    pub backoff_ms: u64,
+    /// Wait a random half to all of each delay
+    #[pyo3(get)]
+    pub jitter: bool,
+}
+
+#[pymethods]
+impl RetryPolicy {
+    /// Create a retry policy.
+    ///
+    /// Args:
+    ///     max_attempts: Handler runs in total, the first included (default: 3)
+    ///     on_statuses: Response statuses to retry (default: 502, 503, 504)
+    ///     on_exceptions: Exception classes, or their names, to retry when
+    ///         the handler raises them; subclasses match too
+    ///     backoff_ms: Delay before the first retry, doubled for each
+    ///         further one (default: 50)
+    ///     jitter: Wait a random half to all of each delay, so requests
+    ///         failing together do not retry in lockstep (default: True)
+    #[new]
+    #[pyo3(signature = (
+        max_attempts = 3,
+        on_statuses = None,
+        on_exceptions = None,
+        backoff_ms = 50,
+        jitter = true,
+    ))]
+    fn new(
+        max_attempts: u32,
+        on_statuses: Option<Vec<u16>>,
+        on_exceptions: Option<Vec<Bound<'_, PyAny>>>,
+        backoff_ms: u64,
+        jitter: bool,
+    ) -> PyResult<Self> {
+        if max_attempts == 0 {
+            return Err(PyValueError::new_err("max_attempts must be at least 1"));
+        }
+        let on_statuses = on_statuses.unwrap_or_else(|| vec![502, 503, 504]);
+        if let Some(status) = on_statuses.iter().find(|s| !(100..=599).contains(*s)) {
+            return Err(PyValueError::new_err(format!(
+                "{} is not an HTTP status code",
+                status
+            )));
+        }
+        let on_exceptions = on_exceptions
+            .unwrap_or_default()
+            .iter()
+            .map(|exc| match exc.cast::<PyType>() {
+                Ok(cls) => cls.name()?.extract::<String>(),
+                Err(_) => exc.extract::<String>().map_err(|_| {
+                    PyTypeError::new_err("on_exceptions takes exception classes or their names")
+                }),
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(Self {
+            max_attempts,
+            on_statuses,
+            on_exceptions,
+            backoff_ms,
+            jitter,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "RetryPolicy(max_attempts={}, on_statuses={:?}, on_exceptions={:?}, backoff_ms={}, jitter={})",
+            self.max_attempts,
+            self.on_statuses,
+            self.on_exceptions,
+            self.backoff_ms,
+            if self.jitter { "True" } else { "False" }
+        )
+    }
+}
+
+impl RetryPolicy {
+    /// Why a response with `status` (and the exception behind it, if any)
+    /// should be retried, or `None` when it should not
+    pub fn reason(&self, status: u16, raised: Option<&RaisedException>) -> Option<String> {
+        if let Some(raised) = raised {
+            if let Some(name) = raised
+                .0
+                .iter()
+                .find(|name| self.on_exceptions.contains(name))
+            {
+                return Some(name.clone());
+            }
+        }
+        self.on_statuses
+            .contains(&status)
+            .then(|| format!("status {}", status))
+    }
+
+    /// Wait before retry `retry` (1-based)
+    pub fn delay(&self, retry: u32) -> Duration {
+        let ceiling =
+            Duration::from_millis(self.backoff_ms).saturating_mul(1 << (retry - 1).min(16));
+        if self.jitter {
+            ceiling.mul_f64(0.5 + rand::random::<f64>() / 2.0)
+        } else {
+            ceiling
+        }
+    }
+}
@@ -1,4 +1,80 @@
+use pyo3::exceptions::{PyTypeError, PyValueError};
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::auth::AuthRequirement;
+use super::etags::{self, EtagProvider};
+use super::flags::{self, Flag};
+use super::inject::ArgPlan;
+use super::memo::RouteMemo;
+use super::params::{self, ParamType, TypedValue};
+use super::retry::RetryPolicy;
+use crate::http::method::MethodSet;
+use crate::http::response_fields::ResponseFields;
+use crate::utils::time_utils::{parse_duration, parse_size};
+
+/// Per-route overrides consulted by middleware and the server dispatch
+#[derive(Clone, Debug)]
+pub struct RouteConfig {
+    /// Handler deadline; overrides a global `TimeoutMiddleware`
+    pub timeout_secs: Option<f64>,
+    /// Largest request body accepted, within the transport limit
+    pub max_body_size: Option<usize>,
+    /// `Cache-Control: max-age` for successful GET/HEAD responses that set none
+    pub cache_ttl_secs: Option<u64>,
+    /// Write access log lines for this route
+    pub log: bool,
+    /// Identical concurrent GET/HEAD requests share one handler run
+    pub coalesce: bool,
+    /// Free-form labels, exposed to middleware as `route_meta_<key>` state
+    pub metadata: HashMap<String, String>,
+    /// Fields kept in or left out of JSON responses
+    pub response_fields: Option<Arc<ResponseFields>>,
+    /// Run the handler again on transient failures
+    pub retry: Option<Arc<RetryPolicy>>,
+    /// Retry non-idempotent methods too
+    pub retry_unsafe: bool,
+    /// Who may call the route; None for the server's `default_auth`
+    pub auth: Option<AuthRequirement>,
+    /// Flag that must be on for the request to reach the handler
+    pub feature_flag: Option<Arc<Flag>>,
+    /// Version token source answering conditional GETs before dispatch
+    pub etag_provider: Option<Arc<EtagProvider>>,
+    /// Kept 200 responses answering GET and HEAD without the handler
+    pub memo: Option<Arc<RouteMemo>>,
+    /// Handler arguments filled by name; None for a `(req, res)` handler
+    pub args: Option<Arc<ArgPlan>>,
+}
+
+impl Default for RouteConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: None,
+            max_body_size: None,
+            cache_ttl_secs: None,
+            log: true,
+            coalesce: false,
+            metadata: HashMap::new(),
+            response_fields: None,
+            retry: None,
+            retry_unsafe: false,
+            auth: None,
+            feature_flag: None,
+            etag_provider: None,
+            memo: None,
+            args: None,
+        }
+    }
+}
+
+impl RouteConfig {
+    /// The timeout as a `Duration`
+    pub fn timeout(&self) -> Option<std::time::Duration> {
+        self.timeout_secs.map(std::time::Duration::from_secs_f64)
+    }
+}
 
 #[pyclass(from_py_object)]
 pub struct Route {
@@ -8,11 +84,30 @@ pub struct Route {
     #[pyo3(get, set)]
     pub function: Py<PyAny>,
 
-    #[pyo3(get, set)]
+    /// Canonical method spec: one method, a group like `GET,POST`, or `*`
+    #[pyo3(get)]
     pub method: String,
 
     #[pyo3(get, set)]
     pub doc: Option<String>,
+
+    /// Upper bound on bodies parsed by `Request.json()` for this route
+    #[pyo3(get, set)]
+    pub max_json_bytes: Option<usize>,
+
+    /// Host this route is restricted to (lowercase, no port); `None` matches any host
+    #[pyo3(get)]
+    pub host: Option<String>,
+
+    /// OpenAPI tags recorded for this route
+    #[pyo3(get, set)]
+    pub tags: Vec<String>,
+
+    /// Timeout, body limit, caching and logging overrides
+    pub config: Arc<RouteConfig>,
+
+    /// `{name:type}` parameters of the full path, set when the route is added
+    pub param_types: Arc<Vec<(String, ParamType)>>,
 }
 
 impl Clone for Route {
@@ -22,6 +117,11 @@ impl Clone for Route {
             function: self.function.clone_ref(py),
             method: self.method.clone(),
             doc: self.doc.clone(),
+            max_json_bytes: self.max_json_bytes,
+            host: self.host.clone(),
+            tags: self.tags.clone(),
+            config: self.config.clone(),
+            param_types: self.param_types.clone(),
         })
     }
 }
@@ -34,21 +134,291 @@ impl Route {
             function: py.None(),
             method: String::new(),
             doc: None,
+            max_json_bytes: None,
+            host: None,
+            tags: Vec::new(),
+            config: Arc::default(),
+            param_types: Arc::default(),
         })
     }
+
+    /// The parsed method spec
+    pub fn method_set(&self) -> PyResult<MethodSet> {
+        MethodSet::parse(&self.method)
+    }
+
+    /// Constrained parameters of a match converted to their types; `None`
+    /// when a value does not convert
+    pub fn typed_params(
+        &self,
+        params: &HashMap<String, String>,
+    ) -> Option<HashMap<String, TypedValue>> {
+        params::convert(&self.param_types, params)
+    }
 }
 
 #[pymethods]
 impl Route {
+    /// Create a route.
+    ///
+    /// Args:
+    ///     timeout: Handler deadline in seconds or as "500ms"/"5s"; slower
+    ///         handlers get a 504. Overrides a global TimeoutMiddleware
+    ///     max_body_size: Largest request body in bytes or as "2MB"; larger
+    ///         bodies get a 413 (cannot exceed the 10 MiB transport limit)
+    ///     cache_ttl: Seconds (or "1h") sent as `Cache-Control: max-age` on
+    ///         successful GET/HEAD responses that set no Cache-Control
+    ///     log: Write access log lines for this route (default: True)
+    ///     coalesce: Identical concurrent GET/HEAD requests wait for the
+    ///         first one and get a copy of its response (default: False)
+    ///     metadata: Labels exposed to middleware as `route_meta_<key>`
+    ///         state and to handlers as `req.route_meta`
+    ///     response_fields: `{"include": [...], "exclude": [...]}` keys kept
+    ///         in or dropped from JSON responses, top-level or one level
+    ///         down (`"user.email"`); `"strict": True` makes include fields
+    ///         missing from a response an error
+    ///     methods: A list of methods or `"*"` for any method not claimed by
+    ///         an exact route on the same path; an alternative to `method`,
+    ///         which also takes `"GET,POST"` or `"*"`
+    ///     retry: A RetryPolicy; the handler runs again while its response
+    ///         status or raised exception matches, for idempotent methods
+    ///     retry_unsafe: Retry POST and PATCH requests too (default: False)
+    ///     auth: "required" (401 without an authenticated user), "optional",
+    ///         "public" (exempt from the server's `default_auth`) or a list
+    ///         of roles, one of which the user must hold (403 otherwise).
+    ///         Checked after the "before" middleware, before the handler
+    ///     feature_flag: Name of a flag (see `Server.set_flag`); while it is
+    ///         off for a request the route answers the flag's `off_status`
+    ///         (404 by default) without running the handler
+    ///     etag_provider: Name of an ETag provider (see `set_etag_provider`);
+    ///         a GET or HEAD whose If-None-Match matches its current token
+    ///         gets 304 without running the handler, other successful
+    ///         responses are tagged with it
+    ///     memoize_ttl_secs: Seconds (or "30s") the first 200 response to a
+    ///         GET is kept and sent to GET and HEAD requests without running
+    ///         the handler; each request path gets its own, the query and
+    ///         body are not part of the key.
+    ///         Responses setting cookies are never kept. See
+    ///         `Server.invalidate_route_cache`
+    ///     memoize_vary: Request headers whose values get their own kept
+    ///         response (default: none)
     #[new]
-    #[pyo3(signature = (path, function, method, doc = None))]
-    pub fn new(path: &str, function: Py<PyAny>, method: String, doc: Option<String>) -> Self {
-        Self {
+    #[pyo3(signature = (
+        path,
+        function,
+        method = None,
+        doc = None,
+        max_json_bytes = None,
+        host = None,
+        tags = None,
+        timeout = None,
+        max_body_size = None,
+        cache_ttl = None,
+        log = true,
+        metadata = None,
+        coalesce = false,
+        response_fields = None,
+        methods = None,
+        retry = None,
+        retry_unsafe = false,
+        auth = None,
+        feature_flag = None,
+        etag_provider = None,
+        memoize_ttl_secs = None,
+        memoize_vary = None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        path: &str,
+        function: Py<PyAny>,
+        method: Option<&Bound<'_, PyAny>>,
+        doc: Option<String>,
+        max_json_bytes: Option<usize>,
+        host: Option<&str>,
+        tags: Option<Vec<String>>,
+        timeout: Option<&Bound<'_, PyAny>>,
+        max_body_size: Option<&Bound<'_, PyAny>>,
+        cache_ttl: Option<&Bound<'_, PyAny>>,
+        log: bool,
+        metadata: Option<HashMap<String, String>>,
+        coalesce: bool,
+        response_fields: Option<&Bound<'_, PyDict>>,
+        methods: Option<&Bound<'_, PyAny>>,
+        retry: Option<RetryPolicy>,
+        retry_unsafe: bool,
+        auth: Option<&Bound<'_, PyAny>>,
+        feature_flag: Option<&str>,
+        etag_provider: Option<&str>,
+        memoize_ttl_secs: Option<&Bound<'_, PyAny>>,
+        memoize_vary: Option<Vec<String>>,
+    ) -> PyResult<Self> {
+        let method = match (method, methods) {
+            (Some(spec), None) | (None, Some(spec)) => MethodSet::extract(spec)?.label(),
+            (None, None) => return Err(PyTypeError::new_err("Route requires method or methods")),
+            (Some(_), Some(_)) => {
+                return Err(PyTypeError::new_err(
+                    "Route takes method or methods, not both",
+                ))
+            }
+        };
+        let config = RouteConfig {
+            timeout_secs: timeout.map(|t| duration_arg(t, "timeout")).transpose()?,
+            max_body_size: max_body_size
+                .map(|size| match size.extract::<String>() {
+                    Ok(text) => parse_size(&text),
+                    Err(_) => size.extract::<usize>().map_err(|_| {
+                        PyTypeError::new_err("max_body_size must be a byte count or a size string")
+                    }),
+                })
+                .transpose()?,
+            cache_ttl_secs: cache_ttl
+                .map(|ttl| duration_arg(ttl, "cache_ttl").map(|secs| secs as u64))
+                .transpose()?,
+            log,
+            coalesce,
+            metadata: metadata.unwrap_or_default(),
+            response_fields: response_fields
+                .map(ResponseFields::from_spec)
+                .transpose()?
+                .map(Arc::new),
+            retry: retry.map(Arc::new),
+            retry_unsafe,
+            auth: auth.map(AuthRequirement::extract).transpose()?,
+            feature_flag: feature_flag.map(flags::handle),
+            etag_provider: etag_provider.map(etags::handle),
+            memo: memoize_ttl_secs
+                .map(|ttl| duration_arg(ttl, "memoize_ttl_secs"))
+                .transpose()?
+                .map(|secs| {
+                    Arc::new(RouteMemo::new(
+                        std::time::Duration::from_secs_f64(secs),
+                        memoize_vary.unwrap_or_default(),
+                    ))
+                }),
+            args: None,
+        };
+        Ok(Self {
             path: path.to_string(),
             function,
             method,
             doc,
-        }
+            max_json_bytes,
+            host: host.map(normalize_host).filter(|h| !h.is_empty()),
+            tags: tags.unwrap_or_default(),
+            config: Arc::new(config),
+            param_types: Arc::new(params::param_types(path)?),
+        })
+    }
+
+    #[setter]
+    fn set_method(&mut self, method: &Bound<'_, PyAny>) -> PyResult<()> {
+        self.method = MethodSet::extract(method)?.label();
+        Ok(())
+    }
+
+    /// The bound methods as a list; `["*"]` for a wildcard route
+    #[getter]
+    fn methods(&self) -> Vec<String> {
+        self.method.split(',').map(str::to_string).collect()
+    }
+
+    /// Handler timeout in seconds
+    #[getter]
+    fn timeout(&self) -> Option<f64> {
+        self.config.timeout_secs
+    }
+
+    /// Request body limit in bytes
+    #[getter]
+    fn max_body_size(&self) -> Option<usize> {
+        self.config.max_body_size
+    }
+
+    /// Default `Cache-Control: max-age` in seconds
+    #[getter]
+    fn cache_ttl(&self) -> Option<u64> {
+        self.config.cache_ttl_secs
+    }
+
+    /// Whether access log lines are written for this route
+    #[getter]
+    fn log(&self) -> bool {
+        self.config.log
+    }
+
+    /// Whether identical concurrent GET/HEAD requests are coalesced
+    #[getter]
+    fn coalesce(&self) -> bool {
+        self.config.coalesce
+    }
+
+    #[getter]
+    fn metadata(&self) -> HashMap<String, String> {
+        self.config.metadata.clone()
+    }
+
+    /// The response field spec, None when responses are not filtered
+    #[getter]
+    fn response_fields<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        self.config
+            .response_fields
+            .as_ref()
+            .map(|fields| fields.to_spec(py))
+            .transpose()
+    }
+
+    /// The retry policy, None when failures are not retried
+    #[getter]
+    fn retry(&self) -> Option<RetryPolicy> {
+        self.config.retry.as_deref().cloned()
+    }
+
+    /// Whether non-idempotent methods are retried too
+    #[getter]
+    fn retry_unsafe(&self) -> bool {
+        self.config.retry_unsafe
+    }
+
+    /// The declared auth requirement: "public", "optional", "required", a
+    /// list of roles, or None when the server default applies
+    #[getter]
+    fn auth<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        self.config
+            .auth
+            .as_ref()
+            .map(|auth| auth.to_object(py))
+            .transpose()
+    }
+
+    /// Name of the flag gating the route, None when it is not gated
+    #[getter]
+    fn feature_flag(&self) -> Option<&str> {
+        self.config.feature_flag.as_deref().map(Flag::name)
+    }
+
+    /// Name of the ETag provider answering conditional GETs, None without one
+    #[getter]
+    fn etag_provider(&self) -> Option<&str> {
+        self.config.etag_provider.as_deref().map(EtagProvider::name)
+    }
+
+    /// Seconds responses are memoized for, None when they are not
+    #[getter]
+    fn memoize_ttl_secs(&self) -> Option<f64> {
+        self.config
+            .memo
+            .as_ref()
+            .map(|memo| memo.ttl().as_secs_f64())
+    }
+
+    /// Request headers that are part of the memo key
+    #[getter]
+    fn memoize_vary(&self) -> Vec<String> {
+        self.config
+            .memo
+            .as_ref()
+            .map(|memo| memo.vary().to_vec())
+            .unwrap_or_default()
     }
 
     // Get a formatted string representation of the route
@@ -58,10 +428,16 @@ impl Route {
 
     // Get a formatted representation for debugging
     pub fn __repr__(&self) -> PyResult<String> {
-        Ok(format!(
-            "Route(path='{}', method='{}')",
-            self.path, self.method
-        ))
+        match self.host {
+            Some(ref host) => Ok(format!(
+                "Route(path='{}', method='{}', host='{}')",
+                self.path, self.method, host
+            )),
+            None => Ok(format!(
+                "Route(path='{}', method='{}')",
+                self.path, self.method
+            )),
+        }
     }
 
     // Update the route path
@@ -81,27 +457,28 @@ impl Route {
             return false;
         }
 
-        // Method should be a valid HTTP method
-        let valid_methods = ["GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS"];
-        if !valid_methods.contains(&self.method.to_uppercase().as_str()) {
-            return false;
-        }
-
-        true
+        // Method should be a routable method group or `*`
+        self.method_set().is_ok()
     }
 
     // Get route parameters from path
     pub fn get_path_params(&self) -> Vec<String> {
         self.path
             .split('/')
-            .filter(|segment| segment.starts_with(':'))
-            .map(|param| param[1..].to_string())
+            .filter_map(|segment| {
+                if let Some(name) = segment.strip_prefix(':') {
+                    return Some(name.to_string());
+                }
+                let inner = segment.strip_prefix('{')?.strip_suffix('}')?;
+                let name = inner.split(':').next().unwrap_or(inner);
+                Some(name.trim_start_matches('*').to_string())
+            })
             .collect()
     }
 
     // Check if route has path parameters
     pub fn has_parameters(&self) -> bool {
-        self.path.contains(':')
+        self.path.contains(':') || self.path.contains('{')
     }
 
     // Generate a normalized version of the path
@@ -116,7 +493,8 @@ impl Route {
 
     // Get method name for sorting and comparison
     pub fn get_method_priority(&self) -> u8 {
-        match self.method.to_uppercase().as_str() {
+        let first = self.method.split(',').next().unwrap_or_default();
+        match first.to_uppercase().as_str() {
             "GET" => 1,
             "POST" => 2,
             "PUT" => 3,
@@ -129,7 +507,10 @@ impl Route {
     }
 
     pub fn matches(&self, path: &str, method: &str) -> bool {
-        if self.method.to_uppercase() != method.to_uppercase() {
+        if !self
+            .method_set()
+            .is_ok_and(|methods| methods.contains_str(method))
+        {
             return false;
         }
 
@@ -141,7 +522,10 @@ impl Route {
         }
 
         for (route_part, path_part) in route_parts.iter().zip(path_parts.iter()) {
-            if !route_part.starts_with(':') && route_part != path_part {
+            if !route_part.starts_with(':')
+                && !route_part.starts_with('{')
+                && route_part != path_part
+            {
                 return false;
             }
         }
@@ -151,6 +535,39 @@ impl Route {
 
     pub fn handler_hash(&self) -> u64 {
         use xxhash_rust::xxh3::xxh3_64;
-        xxh3_64(self.path.as_bytes()) ^ (xxh3_64(self.method.as_bytes()))
+        let hash = xxh3_64(self.path.as_bytes()) ^ (xxh3_64(self.method.as_bytes()));
+        match self.host {
+            // Same path and method on another host is a different handler
+            Some(ref host) => hash ^ xxh3_64(host.as_bytes()).rotate_left(1),
+            None => hash,
+        }
+    }
+}
+
+/// Seconds from a number or a duration string; must be positive
+fn duration_arg(value: &Bound<'_, PyAny>, name: &str) -> PyResult<f64> {
+    let secs = match value.extract::<String>() {
+        Ok(text) => parse_duration(&text)?,
+        Err(_) => value.extract::<f64>().map_err(|_| {
+            PyTypeError::new_err(format!(
+                "{} must be a number of seconds or a duration string",
+                name
+            ))
+        })?,
+    };
+    if secs <= 0.0 {
+        return Err(PyValueError::new_err(format!("{} must be positive", name)));
     }
+    Ok(secs)
+}
+
+/// Lowercase a Host value and strip any port (`API.example.com:8080` -> `api.example.com`)
+pub fn normalize_host(host: &str) -> String {
+    let host = host.trim();
+    let host = match host.strip_prefix('[') {
+        // Bracketed IPv6 literal
+        Some(rest) => rest.split(']').next().unwrap_or(rest),
+        None => host.split(':').next().unwrap_or(host),
+    };
+    host.trim_end_matches('.').to_ascii_lowercase()
 }
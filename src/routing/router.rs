@@ -1,17 +1,40 @@
 use std::collections::HashMap;
-
-use super::route::Route;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use super::cache::{bump_route_generation, route_generation};
+use super::conflicts::{Conflict, ConflictKind, Template};
+use super::inject::ArgPlan;
+use super::route::{normalize_host, Route, RouteConfig};
+use crate::http::method::MethodSet;
+use parking_lot::RwLock;
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyString};
 
 /// :param -> {param}
 /// *wildcard -> {*wildcard}
+/// {param:type} -> {param}
 fn convert_to_matchit_path(path: &str) -> String {
     let mut result = String::with_capacity(path.len() + 4);
     let mut chars = path.chars().peekable();
 
     while let Some(c) = chars.next() {
-        if c == ':' {
+        if c == '{' {
+            // Already in matchit syntax; drop the type constraint
+            result.push('{');
+            let mut in_type = false;
+            for next in chars.by_ref() {
+                if next == '}' {
+                    break;
+                }
+                in_type |= next == ':';
+                if !in_type {
+                    result.push(next);
+                }
+            }
+            result.push('}');
+        } else if c == ':' {
             // Convert :param to {param}
             result.push('{');
             while let Some(&next) = chars.peek() {
@@ -40,64 +63,220 @@ fn convert_to_matchit_path(path: &str) -> String {
     result
 }
 
-/// Contains routers for each HTTP method using matchit
+/// Contains routers for each HTTP method using matchit.
+///
+/// Clones share one route table, so a router handed to the server can still
+/// be changed from Python while serving. Mutations copy the table when
+/// requests hold a snapshot of it; readers never wait on a writer beyond the
+/// pointer swap, and requests already matched keep the route they matched.
 #[pyclass(from_py_object)]
 #[derive(Clone)]
 pub struct Router {
     #[pyo3(get, set)]
     path: String,
 
-    #[pyo3(get)]
-    routes: Vec<Route>,
-
-    // One matchit router per HTTP method for efficient lookups
-    #[pyo3(get)]
-    get_router: MatchitRouter,
-    #[pyo3(get)]
-    post_router: MatchitRouter,
-    #[pyo3(get)]
-    put_router: MatchitRouter,
-    #[pyo3(get)]
-    delete_router: MatchitRouter,
-    #[pyo3(get)]
-    patch_router: MatchitRouter,
-    #[pyo3(get)]
-    head_router: MatchitRouter,
-    #[pyo3(get)]
-    options_router: MatchitRouter,
+    table: Arc<RwLock<Arc<RouteTable>>>,
+
+    /// Reject ambiguous templates instead of warning about them
+    strict: Arc<AtomicBool>,
+}
+
+/// A registered route and the path it was inserted under
+#[derive(Clone)]
+struct RouteEntry {
+    route: Route,
+    full_path: String,
+    template: Arc<Template>,
+}
+
+/// Immutable-while-shared routing state
+#[derive(Clone, Default)]
+struct RouteTable {
+    entries: Vec<RouteEntry>,
+    methods: MethodRouters,
+    // Routes restricted to a host, matched before the host-less routers
+    host_routers: HashMap<String, MethodRouters>,
+    // Whether any route opted out of access logging
+    has_unlogged: bool,
+}
+
+/// One matchit router per HTTP method for efficient lookups. A route for a
+/// method group is inserted into each of its methods' routers.
+#[derive(Clone, Default)]
+struct MethodRouters {
+    get: MatchitRouter,
+    post: MatchitRouter,
+    put: MatchitRouter,
+    delete: MatchitRouter,
+    patch: MatchitRouter,
+    head: MatchitRouter,
+    options: MatchitRouter,
+    // `*` routes, matched when no exact route claims the method
+    any: MatchitRouter,
+}
+
+impl MethodRouters {
+    fn by_method(&self) -> [(&'static str, &MatchitRouter); 7] {
+        [
+            ("GET", &self.get),
+            ("POST", &self.post),
+            ("PUT", &self.put),
+            ("DELETE", &self.delete),
+            ("PATCH", &self.patch),
+            ("HEAD", &self.head),
+            ("OPTIONS", &self.options),
+        ]
+    }
+
+    /// Insert `route` for each of `methods`. With `skip_conflicts`, a method
+    /// whose tree already holds a route the template conflicts with is left
+    /// to that route.
+    fn insert(
+        &mut self,
+        methods: &MethodSet,
+        path: &str,
+        template: &Template,
+        route: &Route,
+        skip_conflicts: bool,
+    ) -> PyResult<()> {
+        let insert = |router: &mut MatchitRouter| match router.insert(path, template, route.clone())
+        {
+            Err(matchit::InsertError::Conflict { .. }) if skip_conflicts => Ok(()),
+            result => {
+                result.map_err(|e| PyValueError::new_err(format!("Failed to add route: {}", e)))
+            }
+        };
+        match methods.methods() {
+            None => insert(&mut self.any),
+            Some(list) => list
+                .iter()
+                .try_for_each(|method| insert(self.for_method_mut(method.as_str())?)),
+        }
+    }
+
+    /// Methods with an exact route for `path`
+    fn allowed(&self, path: &str, allowed: &mut Vec<&'static str>) {
+        for (method, router) in self.by_method() {
+            if !allowed.contains(&method) && router.at(path).is_some() {
+                allowed.push(method);
+            }
+        }
+    }
+
+    fn for_method(&self, method: &str) -> Option<&MatchitRouter> {
+        // Fast method dispatch without allocation - methods from HTTP are already uppercase
+        match method {
+            "GET" => Some(&self.get),
+            "POST" => Some(&self.post),
+            "PUT" => Some(&self.put),
+            "DELETE" => Some(&self.delete),
+            "PATCH" => Some(&self.patch),
+            "HEAD" => Some(&self.head),
+            "OPTIONS" => Some(&self.options),
+            _ => None,
+        }
+    }
+
+    fn for_method_mut(&mut self, method: &str) -> PyResult<&mut MatchitRouter> {
+        match method.to_uppercase().as_str() {
+            "GET" => Ok(&mut self.get),
+            "POST" => Ok(&mut self.post),
+            "PUT" => Ok(&mut self.put),
+            "DELETE" => Ok(&mut self.delete),
+            "PATCH" => Ok(&mut self.patch),
+            "HEAD" => Ok(&mut self.head),
+            "OPTIONS" => Ok(&mut self.options),
+            other => Err(PyValueError::new_err(format!(
+                "Unknown HTTP method: {}",
+                other
+            ))),
+        }
+    }
+
+    fn find(&self, path: &str, method: &str) -> Option<(Route, HashMap<String, String>)> {
+        let exact = match self.for_method(method) {
+            Some(router) => router.at(path),
+            // Fallback for non-standard methods - do the uppercase conversion
+            None => self
+                .for_method(&method.to_uppercase())
+                .and_then(|router| router.at(path)),
+        };
+        exact.or_else(|| self.any.at(path))
+    }
+}
+
+/// A route and its parameter names, which may differ from those of the
+/// template that holds its shape in the tree
+#[derive(Clone)]
+struct Candidate {
+    route: Route,
+    names: Arc<[String]>,
+    constrained: bool,
 }
 
 /// Wrapper around matchit::Router to make it Clone and PyO3 compatible
 #[derive(Clone, Default)]
 #[pyclass(from_py_object)]
 pub struct MatchitRouter {
-    inner: matchit::Router<Route>,
+    /// Routes sharing a shape, tried in order
+    inner: matchit::Router<Vec<Candidate>>,
+    /// Template inserted into the tree for each shape
+    shapes: HashMap<String, String>,
 }
 
 impl MatchitRouter {
-    fn new() -> Self {
-        Self {
-            inner: matchit::Router::new(),
+    fn insert(
+        &mut self,
+        path: &str,
+        template: &Template,
+        route: Route,
+    ) -> Result<(), matchit::InsertError> {
+        let candidate = Candidate {
+            route,
+            names: template.param_names().into(),
+            constrained: template.is_constrained(),
+        };
+        let shape = template.shape();
+        if let Some(key) = self.shapes.get(&shape).cloned() {
+            // Typed parameters are tried first, then in registration order
+            let mut candidates = self.inner.remove(key.as_str()).unwrap_or_default();
+            let at = match candidate.constrained {
+                true => candidates
+                    .iter()
+                    .position(|c| !c.constrained)
+                    .unwrap_or(candidates.len()),
+                false => candidates.len(),
+            };
+            candidates.insert(at, candidate);
+            return self.inner.insert(key, candidates);
         }
-    }
-
-    fn insert(&mut self, path: &str, route: Route) -> Result<(), matchit::InsertError> {
-        let matchit_path = convert_to_matchit_path(path);
-        self.inner.insert(&matchit_path, route)
+        let key = convert_to_matchit_path(path);
+        self.inner.insert(key.as_str(), vec![candidate])?;
+        self.shapes.insert(shape, key);
+        Ok(())
     }
 
     fn at(&self, path: &str) -> Option<(Route, HashMap<String, String>)> {
-        match self.inner.at(path) {
-            Ok(matched) => {
-                let params: HashMap<String, String> = matched
-                    .params
-                    .iter()
-                    .map(|(k, v)| (k.to_string(), v.to_string()))
-                    .collect();
-                Some((matched.value.clone(), params))
+        let matched = self.inner.at(path).ok()?;
+        matched.value.iter().find_map(|candidate| {
+            let params: HashMap<String, String> = candidate
+                .names
+                .iter()
+                .cloned()
+                .zip(
+                    matched
+                        .params
+                        .iter()
+                        .map(|(_, v)| crate::http::path::decode_param(v)),
+                )
+                .collect();
+            // A value that fails its type constraint is not a match
+            let route = &candidate.route;
+            if !route.param_types.is_empty() && route.typed_params(&params).is_none() {
+                return None;
             }
-            Err(_) => None,
-        }
+            Some((route.clone(), params))
+        })
     }
 }
 
@@ -105,14 +284,8 @@ impl Default for Router {
     fn default() -> Self {
         Self {
             path: String::new(),
-            routes: Vec::new(),
-            get_router: MatchitRouter::new(),
-            post_router: MatchitRouter::new(),
-            put_router: MatchitRouter::new(),
-            delete_router: MatchitRouter::new(),
-            patch_router: MatchitRouter::new(),
-            head_router: MatchitRouter::new(),
-            options_router: MatchitRouter::new(),
+            table: Arc::new(RwLock::new(Arc::new(RouteTable::default()))),
+            strict: Arc::default(),
         }
     }
 }
@@ -120,89 +293,306 @@ impl Default for Router {
 #[pymethods]
 impl Router {
     #[new]
-    pub fn new(path: &str) -> Self {
+    #[pyo3(signature = (path, strict_routes=false))]
+    pub fn new(path: &str, strict_routes: bool) -> Self {
         Self {
             path: path.to_string(),
+            strict: Arc::new(AtomicBool::new(strict_routes)),
             ..Default::default()
         }
     }
 
-    /// Add a new route to the router
-    pub fn add_route(&mut self, route: Route) -> PyResult<()> {
+    /// Reject templates that differ from a registered one only in parameter
+    /// names, or clash with its catch-all, instead of logging a warning.
+    /// Shared by clones of this router.
+    #[getter]
+    fn strict_routes(&self) -> bool {
+        self.strict.load(Ordering::Relaxed)
+    }
+
+    #[setter]
+    fn set_strict_routes(&self, strict: bool) {
+        self.strict.store(strict, Ordering::Relaxed);
+    }
+
+    /// Registered routes, in registration order
+    #[getter(routes)]
+    fn routes_py(&self) -> Vec<Route> {
+        self.snapshot()
+            .entries
+            .iter()
+            .map(|e| e.route.clone())
+            .collect()
+    }
+
+    /// Incremented by every route addition or removal
+    #[getter]
+    fn generation(&self) -> u64 {
+        route_generation()
+    }
+
+    /// Add a new route to the router.
+    ///
+    /// Safe while serving: the route is visible to requests matched after
+    /// this returns.
+    pub fn add_route(&self, route: Route) -> PyResult<()> {
         // Validate route
         if !route.is_valid() {
             return Err(PyValueError::new_err("Invalid route configuration"));
         }
 
         let full_path = self.get_full_path(&route.path);
-        let method = route.method.to_uppercase();
-
-        // Add to appropriate matchit router
-        let router = match method.as_str() {
-            "GET" => &mut self.get_router,
-            "POST" => &mut self.post_router,
-            "PUT" => &mut self.put_router,
-            "DELETE" => &mut self.delete_router,
-            "PATCH" => &mut self.patch_router,
-            "HEAD" => &mut self.head_router,
-            "OPTIONS" => &mut self.options_router,
-            _ => {
+        // The prefix may declare typed parameters too
+        let mut route = route;
+        route.param_types = Arc::new(super::params::param_types(&full_path)?);
+        let methods = route.method_set()?;
+        let template = Arc::new(Template::parse(
+            &convert_to_matchit_path(&full_path),
+            &route.param_types,
+        ));
+        let strict = self.strict_routes();
+        let conflicts = self.mutate(|table| {
+            let conflicts: Vec<Conflict> = table
+                .entries
+                .iter()
+                .filter_map(|earlier| conflict(earlier, &route, &full_path, &template))
+                .collect();
+            if let Some(rejected) = conflicts
+                .iter()
+                .find(|c| strict || c.kind == ConflictKind::Duplicate)
+            {
                 return Err(PyValueError::new_err(format!(
-                    "Unknown HTTP method: {}",
-                    method
-                )))
+                    "Failed to add route: {}",
+                    rejected.message()
+                )));
             }
+            let routers = match route.host {
+                Some(ref host) => table.host_routers.entry(host.clone()).or_default(),
+                None => &mut table.methods,
+            };
+            let skip_conflicts = conflicts.iter().any(|c| c.kind == ConflictKind::CatchAll);
+            routers.insert(&methods, &full_path, &template, &route, skip_conflicts)?;
+            if let Some(memo) = &route.config.memo {
+                super::memo::register(&full_path, memo);
+            }
+            table.entries.push(RouteEntry {
+                route: route.clone(),
+                full_path: full_path.clone(),
+                template: template.clone(),
+            });
+            Ok(conflicts)
+        })?;
+        for conflict in conflicts {
+            crate::hlog_warn!("Route conflict: {}", conflict.message());
+        }
+
+        // Routes added after workers start need their handler registered here
+        Python::attach(|py| {
+            crate::core::interpreter::register_handler(
+                route.handler_hash(),
+                route.function.clone_ref(py),
+            )
+        });
+        Ok(())
+    }
+
+    /// Decorator adding the handler for `methods` on `path`, with its
+    /// arguments filled by name: path parameters (converted by their
+    /// `{name:type}` constraint or annotation), the request and response,
+    /// provided dependencies, and query parameters for arguments with a
+    /// default. `options` are those of `Route`.
+    ///
+    /// Raises ValueError when the handler has an argument none of these
+    /// fills and no default.
+    ///
+    /// Example:
+    ///     @router.get("/users/{id:int}")
+    ///     def get_user(id: int, verbose: bool = False, request=None):
+    ///         return {"id": id, "verbose": verbose}
+    #[pyo3(signature = (path, methods, **options))]
+    pub fn route(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        methods: &Bound<'_, PyAny>,
+        options: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<RouteDecorator> {
+        let options = match options {
+            Some(options) => options.copy()?,
+            None => PyDict::new(py),
         };
+        options.set_item("methods", methods)?;
+        Ok(RouteDecorator {
+            router: self.clone(),
+            path: path.to_string(),
+            options: options.unbind(),
+        })
+    }
 
-        router
-            .insert(&full_path, route.clone())
-            .map_err(|e| PyValueError::new_err(format!("Failed to add route: {}", e)))?;
+    /// Decorator adding a GET handler; see `route()`
+    #[pyo3(signature = (path, **options))]
+    pub fn get(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        options: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<RouteDecorator> {
+        self.route(py, path, PyString::new(py, "GET").as_any(), options)
+    }
 
-        // Keep the routes vector for backwards compatibility and iteration
-        self.routes.push(route);
+    /// Decorator adding a POST handler; see `route()`
+    #[pyo3(signature = (path, **options))]
+    pub fn post(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        options: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<RouteDecorator> {
+        self.route(py, path, PyString::new(py, "POST").as_any(), options)
+    }
 
-        Ok(())
+    /// Decorator adding a PUT handler; see `route()`
+    #[pyo3(signature = (path, **options))]
+    pub fn put(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        options: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<RouteDecorator> {
+        self.route(py, path, PyString::new(py, "PUT").as_any(), options)
+    }
+
+    /// Decorator adding a PATCH handler; see `route()`
+    #[pyo3(signature = (path, **options))]
+    pub fn patch(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        options: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<RouteDecorator> {
+        self.route(py, path, PyString::new(py, "PATCH").as_any(), options)
+    }
+
+    /// Decorator adding a DELETE handler; see `route()`
+    #[pyo3(signature = (path, **options))]
+    pub fn delete(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        options: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<RouteDecorator> {
+        self.route(py, path, PyString::new(py, "DELETE").as_any(), options)
     }
 
     // extend list route
-    pub fn extend_route(&mut self, routes: Vec<Route>) -> PyResult<()> {
+    pub fn extend_route(&self, routes: Vec<Route>) -> PyResult<()> {
         for route in routes {
             let _ = self.add_route(route);
         }
         Ok(())
     }
 
-    /// Remove a route by path and method
-    pub fn remove_route(&mut self, path: &str, method: &str) -> PyResult<bool> {
-        if let Some(index) = self
-            .routes
-            .iter()
-            .position(|r| r.path == path && r.method.to_uppercase() == method.to_uppercase())
-        {
-            self.routes.remove(index);
-            Ok(true)
-        } else {
-            Ok(false)
+    /// Remove the route registered for `method` and `path`.
+    ///
+    /// `path` is the template the route was added with (e.g. "/users/:id"),
+    /// relative to this router or in full. `method` is the method, group
+    /// (`["GET", "POST"]` or `"GET,POST"`) or `"*"` the route was added
+    /// with; one method of a group does not remove it. `host` restricts
+    /// removal to routes bound to that host; by default matching routes are
+    /// removed for every host. Requests already matched are unaffected.
+    ///
+    /// Returns:
+    ///     True if a route was removed
+    #[pyo3(signature = (method, path, host=None))]
+    pub fn remove_route(
+        &self,
+        method: &Bound<'_, PyAny>,
+        path: &str,
+        host: Option<&str>,
+    ) -> PyResult<bool> {
+        let method = MethodSet::extract(method)?.label();
+        let full_path = self.get_full_path(path);
+        self.remove_where(host, |entry| {
+            entry.route.method == method
+                && (entry.route.path == path
+                    || entry.full_path == path
+                    || entry.full_path == full_path)
+        })
+    }
+
+    /// Remove every route under `prefix` (segment-aligned, so "/t/a" does
+    /// not remove "/t/ab"), e.g. to unmount a tenant router.
+    ///
+    /// Returns:
+    ///     True if any route was removed
+    #[pyo3(signature = (prefix, host=None))]
+    pub fn remove_prefix(&self, prefix: &str, host: Option<&str>) -> PyResult<bool> {
+        let prefix = prefix.trim_end_matches('/');
+        self.remove_where(host, |entry| {
+            prefix.is_empty()
+                || entry.full_path == prefix
+                || entry
+                    .full_path
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+
+    /// Report conflicting routes: `duplicate` templates, `ambiguous` ones
+    /// differing only in parameter names, and `catch_all` clashes between a
+    /// catch-all and a parameter in the same segment. Each conflict names
+    /// the later route (`path`, `handler`) and the earlier one it conflicts
+    /// with (`other_path`, `other_handler`). Duplicates, and every conflict
+    /// under `strict_routes`, are errors; the rest are warnings.
+    ///
+    /// Returns:
+    ///     {"ok": bool, "errors": [conflict, ...], "warnings": [conflict, ...]}
+    pub fn check<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let strict = self.strict_routes();
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        for conflict in self.conflicts() {
+            let item = PyDict::new(py);
+            item.set_item("kind", conflict.kind.as_str())?;
+            item.set_item("method", &conflict.method)?;
+            item.set_item("host", &conflict.host)?;
+            item.set_item("path", &conflict.path)?;
+            item.set_item("handler", &conflict.handler)?;
+            item.set_item("other_path", &conflict.other_path)?;
+            item.set_item("other_handler", &conflict.other_handler)?;
+            item.set_item("message", conflict.message())?;
+            if strict || conflict.kind == ConflictKind::Duplicate {
+                errors.push(item);
+            } else {
+                warnings.push(item);
+            }
         }
+        let report = PyDict::new(py);
+        report.set_item("ok", errors.is_empty())?;
+        report.set_item("errors", errors)?;
+        report.set_item("warnings", warnings)?;
+        Ok(report)
     }
 
     /// Get route by path and method
     #[pyo3(name = "get_route")]
     pub fn get_route_py(&self, path: &str, method: &str) -> PyResult<Option<Route>> {
         Ok(self
-            .routes
+            .snapshot()
+            .entries
             .iter()
-            .find(|r| r.matches(path, method))
-            .map(|r| r.clone()))
+            .find(|e| e.route.matches(path, method))
+            .map(|e| e.route.clone()))
     }
 
     /// Get all routes for a specific path
     #[pyo3(name = "get_routes_by_path")]
     pub fn get_routes_by_path_py(&self, path: &str) -> Vec<Route> {
-        self.routes
+        self.snapshot()
+            .entries
             .iter()
-            .filter(|r| r.path == path)
-            .map(|r| r.clone())
+            .filter(|e| e.route.path == path)
+            .map(|e| e.route.clone())
             .collect()
     }
 
@@ -223,16 +613,17 @@ impl Router {
         Ok(format!(
             "Router(base_path='{}', routes={})",
             self.path,
-            self.routes.len()
+            self.routes_count()
         ))
     }
 
     /// Get detailed representation of router
     fn __repr__(&self) -> PyResult<String> {
         let routes_str: Vec<String> = self
-            .routes
+            .snapshot()
+            .entries
             .iter()
-            .map(|r| format!("\n  {} {}", r.method, r.path))
+            .map(|e| format!("\n  {} {}", e.route.method, e.route.path))
             .collect();
         Ok(format!(
             "Router(base_path='{}', routes:[{}]\n])",
@@ -252,67 +643,256 @@ impl Router {
         path: &str,
         method: &str,
     ) -> Option<(Route, HashMap<String, String>)> {
-        // Fast method dispatch without allocation - methods from HTTP are already uppercase
-        let router = match method {
-            "GET" => &self.get_router,
-            "POST" => &self.post_router,
-            "PUT" => &self.put_router,
-            "DELETE" => &self.delete_router,
-            "PATCH" => &self.patch_router,
-            "HEAD" => &self.head_router,
-            "OPTIONS" => &self.options_router,
-            _ => {
-                // Fallback for non-standard methods - do the uppercase conversion
-                let method = method.to_uppercase();
-                return match method.as_str() {
-                    "GET" => self.get_router.at(path),
-                    "POST" => self.post_router.at(path),
-                    "PUT" => self.put_router.at(path),
-                    "DELETE" => self.delete_router.at(path),
-                    "PATCH" => self.patch_router.at(path),
-                    "HEAD" => self.head_router.at(path),
-                    "OPTIONS" => self.options_router.at(path),
-                    _ => None,
+        self.snapshot().methods.find(path, method)
+    }
+}
+
+impl Router {
+    /// Add `route` with an argument plan read from its handler's signature
+    fn add_injected(&self, py: Python<'_>, mut route: Route) -> PyResult<()> {
+        let full_path = self.get_full_path(&route.path);
+        let types = super::params::param_types(&full_path)?;
+        let names = Template::parse(&convert_to_matchit_path(&full_path), &types).param_names();
+        let label = format!("{} {}", route.method, full_path);
+        if let Some(plan) = ArgPlan::build(route.function.bind(py), &label, &names, &types)? {
+            let mut config = RouteConfig::clone(&route.config);
+            config.args = Some(Arc::new(plan));
+            route.config = Arc::new(config);
+        }
+        self.add_route(route)
+    }
+
+    /// Current route table; stays valid however the router changes later
+    #[inline]
+    fn snapshot(&self) -> Arc<RouteTable> {
+        self.table.read().clone()
+    }
+
+    /// Apply a change to a copy of the table and publish it on success, so
+    /// snapshots held by in-flight requests are never modified
+    fn mutate<T>(&self, f: impl FnOnce(&mut RouteTable) -> PyResult<T>) -> PyResult<T> {
+        let mut guard = self.table.write();
+        let mut next = RouteTable::clone(&guard);
+        let result = f(&mut next)?;
+        next.has_unlogged = next.entries.iter().any(|e| !e.route.config.log);
+        *guard = Arc::new(next);
+        bump_route_generation();
+        Ok(result)
+    }
+
+    fn remove_where(
+        &self,
+        host: Option<&str>,
+        matches: impl Fn(&RouteEntry) -> bool,
+    ) -> PyResult<bool> {
+        let host = host.map(normalize_host);
+        let wanted =
+            |entry: &RouteEntry| (host.is_none() || entry.route.host == host) && matches(entry);
+        if !self.snapshot().entries.iter().any(wanted) {
+            return Ok(false);
+        }
+
+        self.mutate(|table| {
+            let (removed, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut table.entries)
+                .into_iter()
+                .partition(wanted);
+            table.entries = kept;
+            // Rebuilt rather than pruned: a route the removed one shadowed,
+            // or that shared its tree entry, takes its place
+            table.methods = MethodRouters::default();
+            table.host_routers = HashMap::new();
+            for entry in &table.entries {
+                let routers = match entry.route.host {
+                    Some(ref host) => table.host_routers.entry(host.clone()).or_default(),
+                    None => &mut table.methods,
                 };
+                routers.insert(
+                    &entry.route.method_set()?,
+                    &entry.full_path,
+                    &entry.template,
+                    &entry.route,
+                    true,
+                )?;
             }
-        };
+            Ok(!removed.is_empty())
+        })
+    }
 
-        router.at(path)
+    /// Conflicts between registered routes, each reported against the
+    /// earlier route
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let table = self.snapshot();
+        let mut conflicts = Vec::new();
+        for (i, later) in table.entries.iter().enumerate() {
+            conflicts.extend(table.entries[..i].iter().filter_map(|earlier| {
+                conflict(earlier, &later.route, &later.full_path, &later.template)
+            }));
+        }
+        conflicts
     }
-}
 
-impl Router {
-    pub fn iter(&'_ self) -> std::slice::Iter<'_, Route> {
-        self.routes.iter()
+    /// Check the routes before serving: conflicts fail under
+    /// `strict_routes`, and are summarized in a warning otherwise
+    pub fn validate(&self) -> PyResult<()> {
+        let conflicts = self.conflicts();
+        if conflicts.is_empty() {
+            return Ok(());
+        }
+        if self.strict_routes() || conflicts.iter().any(|c| c.kind == ConflictKind::Duplicate) {
+            let messages: Vec<String> = conflicts.iter().map(Conflict::message).collect();
+            return Err(PyValueError::new_err(format!(
+                "Conflicting routes:\n  {}",
+                messages.join("\n  ")
+            )));
+        }
+        crate::hlog_warn!(
+            "{} route conflict(s); see Router.check() for details",
+            conflicts.len()
+        );
+        Ok(())
+    }
+
+    /// Whether any route is restricted to a host
+    pub fn has_host_routes(&self) -> bool {
+        !self.snapshot().host_routers.is_empty()
+    }
+
+    /// Match a route for a request `Host`, preferring routes bound to that
+    /// host and falling back to host-less routes
+    pub fn find_matching_route_for_host(
+        &self,
+        host: Option<&str>,
+        path: &str,
+        method: &str,
+    ) -> Option<(Route, HashMap<String, String>)> {
+        let table = self.snapshot();
+        if let Some(host) = host.filter(|_| !table.host_routers.is_empty()) {
+            if let Some(routers) = table.host_routers.get(&normalize_host(host)) {
+                if let Some(found) = routers.find(path, method) {
+                    return Some(found);
+                }
+            }
+        }
+        table.methods.find(path, method)
+    }
+
+    /// Methods with a route for `path` when a request's method has none,
+    /// for the `Allow` header of a 405; empty when nothing matches the path
+    pub fn allowed_methods(&self, host: Option<&str>, path: &str) -> Vec<&'static str> {
+        let table = self.snapshot();
+        let mut allowed = Vec::new();
+        if let Some(host) = host.filter(|_| !table.host_routers.is_empty()) {
+            if let Some(routers) = table.host_routers.get(&normalize_host(host)) {
+                routers.allowed(path, &mut allowed);
+            }
+        }
+        table.methods.allowed(path, &mut allowed);
+        let order = table.methods.by_method().map(|(method, _)| method);
+        allowed.sort_by_key(|method| order.iter().position(|m| m == method));
+        allowed
+    }
+
+    /// Whether the request should be access-logged; only looks the route up
+    /// when some route disabled logging
+    pub fn should_log(&self, host: Option<&str>, path: &str, method: &str) -> bool {
+        if !self.snapshot().has_unlogged {
+            return true;
+        }
+        self.find_matching_route_for_host(host, path, method)
+            .is_none_or(|(route, _)| route.config.log)
+    }
+
+    /// Snapshot of the registered routes
+    pub fn iter(&self) -> std::vec::IntoIter<Route> {
+        self.routes_py().into_iter()
     }
 
     pub fn routes_count(&self) -> usize {
-        self.routes.len()
+        self.snapshot().entries.len()
     }
 
     /// Get route info as a list of dicts for the `hypern routes` CLI command
     pub fn get_routes_info(&self) -> Vec<HashMap<String, String>> {
-        self.routes
+        self.snapshot()
+            .entries
             .iter()
-            .map(|r| {
+            .map(|e| {
+                let r = &e.route;
                 let mut info = HashMap::new();
                 info.insert("method".to_string(), r.method.clone());
-                info.insert("path".to_string(), self.get_full_path(&r.path));
-                info.insert(
-                    "handler".to_string(),
-                    Python::attach(|py| {
-                        r.function
-                            .bind(py)
-                            .getattr("__name__")
-                            .map(|n| n.to_string())
-                            .unwrap_or_else(|_| "unknown".to_string())
-                    }),
-                );
+                info.insert("path".to_string(), e.full_path.clone());
+                info.insert("handler".to_string(), handler_name(r));
                 if let Some(ref doc) = r.doc {
                     info.insert("doc".to_string(), doc.clone());
                 }
+                if let Some(ref host) = r.host {
+                    info.insert("host".to_string(), host.clone());
+                }
                 info
             })
             .collect()
     }
 }
+
+/// Decorator returned by `Router.route()`, `Router.get()` and the like
+#[pyclass(frozen)]
+pub struct RouteDecorator {
+    router: Router,
+    path: String,
+    options: Py<PyDict>,
+}
+
+#[pymethods]
+impl RouteDecorator {
+    /// Add the route for `handler` and return the handler unchanged
+    fn __call__(&self, py: Python<'_>, handler: Py<PyAny>) -> PyResult<Py<PyAny>> {
+        let route: Route = py
+            .get_type::<Route>()
+            .call(
+                (self.path.as_str(), handler.clone_ref(py)),
+                Some(self.options.bind(py)),
+            )?
+            .extract()?;
+        self.router.add_injected(py, route)?;
+        Ok(handler)
+    }
+}
+
+/// Name of the route's handler function
+fn handler_name(route: &Route) -> String {
+    Python::attach(|py| {
+        route
+            .function
+            .bind(py)
+            .getattr("__name__")
+            .map(|n| n.to_string())
+            .unwrap_or_else(|_| "unknown".to_string())
+    })
+}
+
+/// How a route being added conflicts with an `earlier` one on the same host
+/// and a shared method
+fn conflict(
+    earlier: &RouteEntry,
+    route: &Route,
+    full_path: &str,
+    template: &Template,
+) -> Option<Conflict> {
+    if earlier.route.host != route.host {
+        return None;
+    }
+    let methods = route
+        .method_set()
+        .ok()?
+        .overlap(&earlier.route.method_set().ok()?)?;
+    let kind = template.conflict(&earlier.template)?;
+    Some(Conflict {
+        kind,
+        method: methods.label(),
+        host: route.host.clone(),
+        path: full_path.to_string(),
+        handler: handler_name(route),
+        other_path: earlier.full_path.clone(),
+        other_handler: handler_name(&earlier.route),
+    })
+}
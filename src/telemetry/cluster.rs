@@ -0,0 +1,435 @@
+//! Request metrics across worker processes.
+//!
+//! Each forked worker has its own `ServerMetrics`, so what one worker
+//! reports depends on which one answered. Workers therefore publish a
+//! snapshot of their totals every `metrics_publish_interval` to a slot of a
+//! shared-memory segment mapped before they fork. Readers merge their own
+//! live totals with the latest snapshot of every other live worker:
+//! counters are summed, histograms merged bucket by bucket and maxima take
+//! the largest.
+//!
+//! A snapshot is serialized before its slot is touched. Slots are
+//! seqlocks: only the owner writes, and a reader retries a copy that
+//! overlapped a write a bounded number of times, then skips the slot, so no
+//! process ever waits on another. Slots of workers that exited are reclaimed,
+//! and snapshots older than a few publication intervals are ignored, so a
+//! worker that stopped publishing drops out of the totals.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::server_metrics::{self, Totals};
+
+/// Default time between two snapshots of a worker
+pub const DEFAULT_PUBLISH_INTERVAL_SECS: f64 = 1.0;
+/// Snapshots older than this many intervals are ignored
+const STALE_INTERVALS: u64 = 3;
+/// ... but never sooner than this, so a slow event loop is not mistaken
+/// for a dead worker
+const MIN_STALE_MS: u64 = 5_000;
+/// Routes a snapshot carries, the most requested first
+const PUBLISHED_ROUTES: usize = 200;
+
+/// Milliseconds between snapshots; 0 when workers do not publish
+static PUBLISH_INTERVAL_MS: AtomicU64 = AtomicU64::new(1_000);
+
+/// Set how often workers publish, None to turn periodic publication off,
+/// and map the shared segment. Must run before workers fork.
+pub fn configure(interval: Option<Duration>) {
+    let ms = interval.map_or(0, |interval| (interval.as_millis() as u64).max(1));
+    PUBLISH_INTERVAL_MS.store(ms, Ordering::Relaxed);
+    install();
+}
+
+/// Map the shared segment, so processes forked from now on share it
+pub fn install() {
+    shared::segment();
+}
+
+/// The publication interval, None when off
+pub fn interval() -> Option<Duration> {
+    match PUBLISH_INTERVAL_MS.load(Ordering::Relaxed) {
+        0 => None,
+        ms => Some(Duration::from_millis(ms)),
+    }
+}
+
+/// Age past which a snapshot no longer counts
+fn stale_after() -> Duration {
+    let interval = PUBLISH_INTERVAL_MS.load(Ordering::Relaxed);
+    Duration::from_millis((interval * STALE_INTERVALS).max(MIN_STALE_MS))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_millis() as u64)
+}
+
+/// What a worker writes to its slot
+#[derive(Serialize, Deserialize)]
+struct Published {
+    pid: u32,
+    published_ms: u64,
+    totals: Totals,
+}
+
+/// One worker's totals, as merged by `collect`
+pub struct WorkerTotals {
+    pub pid: u32,
+    /// Time since the worker published them; zero for this process
+    pub age: Duration,
+    pub totals: Totals,
+}
+
+/// Publish this process's totals to its slot. Returns false when there is
+/// no shared segment (non-Unix, or mapping failed) or no free slot.
+pub fn publish() -> bool {
+    let Some(segment) = shared::segment() else {
+        return false;
+    };
+    let mut totals = server_metrics::process_totals();
+    totals.routes.sort_by_key(|route| std::cmp::Reverse(route.count));
+    totals.routes.truncate(PUBLISHED_ROUTES);
+    let pid = std::process::id();
+    let mut published = Published {
+        pid,
+        published_ms: now_ms(),
+        totals,
+    };
+    // Serialized before the slot is touched
+    let bytes = loop {
+        let bytes = serde_json::to_vec(&published).unwrap_or_default();
+        if bytes.len() <= shared::SLOT_BYTES || published.totals.routes.is_empty() {
+            break bytes;
+        }
+        let keep = published.totals.routes.len() / 2;
+        published.totals.routes.truncate(keep);
+    };
+    if bytes.len() > shared::SLOT_BYTES {
+        return false;
+    }
+    match segment.claim(pid) {
+        Some(slot) => {
+            slot.write(&bytes);
+            true
+        }
+        None => {
+            crate::hlog_warn!(
+                "No free metrics slot for worker pid {}; it is left out of cluster totals",
+                pid
+            );
+            false
+        }
+    }
+}
+
+/// This process's live totals first, then the latest snapshot of every
+/// other live worker
+pub fn collect() -> Vec<WorkerTotals> {
+    let own_pid = std::process::id();
+    let mut workers = vec![WorkerTotals {
+        pid: own_pid,
+        age: Duration::ZERO,
+        totals: server_metrics::process_totals(),
+    }];
+    let Some(segment) = shared::segment() else {
+        return workers;
+    };
+    let now = now_ms();
+    let stale_after = stale_after();
+    for slot in segment.occupied() {
+        if slot.pid == own_pid {
+            continue;
+        }
+        if !shared::alive(slot.pid) {
+            segment.release(slot.index, slot.pid);
+            continue;
+        }
+        let Some(bytes) = slot.bytes else {
+            continue;
+        };
+        let Ok(published) = serde_json::from_slice::<Published>(&bytes) else {
+            continue;
+        };
+        let age = Duration::from_millis(now.saturating_sub(published.published_ms));
+        if published.pid != slot.pid || age > stale_after {
+            continue;
+        }
+        workers.push(WorkerTotals {
+            pid: published.pid,
+            age,
+            totals: published.totals,
+        });
+    }
+    workers
+}
+
+/// The totals of every worker in `collect`, merged
+pub fn merged(workers: &[WorkerTotals]) -> Totals {
+    let mut merged = workers[0].totals.clone();
+    for worker in &workers[1..] {
+        merged.merge(&worker.totals);
+    }
+    merged
+}
+
+/// Publish every interval until the runtime shuts down
+pub async fn publish_periodically() {
+    while let Some(interval) = interval() {
+        tokio::time::sleep(interval).await;
+        publish();
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Shared segment
+// ---------------------------------------------------------------------------
+
+#[cfg(unix)]
+mod shared {
+    use std::sync::atomic::{fence, AtomicU64, AtomicUsize, Ordering};
+    use std::sync::OnceLock;
+
+    use parking_lot::Mutex;
+
+    /// Workers that can publish at once
+    const SLOTS: usize = 128;
+    /// Room for one snapshot as JSON
+    pub const SLOT_BYTES: usize = 32 * 1024;
+    const WORDS: usize = SLOT_BYTES / 8;
+    /// Reads of a slot that keep overlapping a write before it is skipped;
+    /// a worker killed mid-write leaves its slot odd until it is reclaimed
+    const READ_ATTEMPTS: u32 = 64;
+
+    /// Written only by its owner. `seq` is odd while a write is under way;
+    /// readers copy the data and keep the copy if `seq` was even and the
+    /// same before and after. Nobody waits on another process, so a worker
+    /// dying at any point cannot block the others.
+    #[repr(C)]
+    struct Slot {
+        /// Process owning the slot; 0 when free
+        pid: AtomicU64,
+        seq: AtomicU64,
+        len: AtomicUsize,
+        data: [AtomicU64; WORDS],
+    }
+
+    /// Lives in a `MAP_SHARED` mapping, so every forked worker sees the
+    /// same memory. Zero-filled by the kernel, which is a valid empty segment.
+    #[repr(C)]
+    pub struct Segment {
+        slots: [Slot; SLOTS],
+    }
+
+    impl Slot {
+        /// Store `bytes`; only the owner calls this
+        fn store(&self, bytes: &[u8]) {
+            // Odd even if a previous owner died mid-write
+            let seq = self.seq.load(Ordering::Relaxed) | 1;
+            self.seq.store(seq, Ordering::Relaxed);
+            fence(Ordering::Release);
+            for (word, chunk) in self.data.iter().zip(bytes.chunks(8)) {
+                let mut buf = [0u8; 8];
+                buf[..chunk.len()].copy_from_slice(chunk);
+                word.store(u64::from_ne_bytes(buf), Ordering::Relaxed);
+            }
+            self.len.store(bytes.len(), Ordering::Relaxed);
+            self.seq.store(seq.wrapping_add(1), Ordering::Release);
+        }
+
+        /// A consistent copy of the bytes, None if nothing was published
+        /// or every attempt overlapped a write
+        fn load(&self) -> Option<Vec<u8>> {
+            for _ in 0..READ_ATTEMPTS {
+                let before = self.seq.load(Ordering::Acquire);
+                if before & 1 == 1 {
+                    std::thread::yield_now();
+                    continue;
+                }
+                let len = self.len.load(Ordering::Relaxed).min(SLOT_BYTES);
+                let mut bytes = Vec::with_capacity(len.next_multiple_of(8));
+                for word in &self.data[..len.div_ceil(8)] {
+                    bytes.extend_from_slice(&word.load(Ordering::Relaxed).to_ne_bytes());
+                }
+                fence(Ordering::Acquire);
+                if self.seq.load(Ordering::Relaxed) == before {
+                    bytes.truncate(len);
+                    return (len > 0).then_some(bytes);
+                }
+            }
+            None
+        }
+    }
+
+    /// A claimed slot of this process
+    pub struct Claimed(&'static Slot);
+
+    impl Claimed {
+        pub fn write(&self, bytes: &[u8]) {
+            self.0.store(bytes);
+        }
+    }
+
+    /// A slot held by some process, with a copy of its snapshot
+    pub struct Occupied {
+        pub index: usize,
+        pub pid: u32,
+        /// None until the owner published once, or while it is stuck mid-write
+        pub bytes: Option<Vec<u8>>,
+    }
+
+    static SEGMENT: OnceLock<Option<&'static Segment>> = OnceLock::new();
+
+    /// (pid, slot index) this process claimed; the pid tells a forked child
+    /// apart from the parent whose memory it inherited
+    static CLAIMED: Mutex<Option<(u32, usize)>> = Mutex::new(None);
+
+    /// The shared segment, mapped on first use (None if mapping failed)
+    pub fn segment() -> Option<&'static Segment> {
+        *SEGMENT.get_or_init(|| {
+            // SAFETY: a fresh anonymous mapping, zero-filled by the kernel,
+            // which is a valid empty `Segment`; it is never unmapped
+            let ptr = unsafe {
+                libc::mmap(
+                    std::ptr::null_mut(),
+                    std::mem::size_of::<Segment>(),
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+                    -1,
+                    0,
+                )
+            };
+            if ptr == libc::MAP_FAILED {
+                crate::hlog_warn!(
+                    "Could not map shared metrics; each worker reports only its own: {}",
+                    std::io::Error::last_os_error()
+                );
+                return None;
+            }
+            Some(unsafe { &*(ptr as *const Segment) })
+        })
+    }
+
+    /// Whether process `pid` still exists (zombies included)
+    pub fn alive(pid: u32) -> bool {
+        // SAFETY: signal 0 only checks that the process exists
+        let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+        result == 0 || std::io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+    }
+
+    impl Segment {
+        /// This process's slot, claiming a free one (or one of an exited
+        /// process) the first time
+        pub fn claim(&'static self, pid: u32) -> Option<Claimed> {
+            let mut claimed = CLAIMED.lock();
+            if let Some((owner, index)) = *claimed {
+                if owner == pid && self.slots[index].pid.load(Ordering::Acquire) == pid as u64 {
+                    return Some(Claimed(&self.slots[index]));
+                }
+            }
+            let index = self
+                .slots
+                .iter()
+                .position(|slot| {
+                    slot.pid
+                        .compare_exchange(0, pid as u64, Ordering::AcqRel, Ordering::Acquire)
+                        .is_ok()
+                })
+                .or_else(|| {
+                    self.slots.iter().position(|slot| {
+                        let owner = slot.pid.load(Ordering::Acquire);
+                        !alive(owner as u32)
+                            && slot
+                                .pid
+                                .compare_exchange(
+                                    owner,
+                                    pid as u64,
+                                    Ordering::AcqRel,
+                                    Ordering::Acquire,
+                                )
+                                .is_ok()
+                    })
+                })?;
+            let slot = &self.slots[index];
+            slot.store(&[]);
+            *claimed = Some((pid, index));
+            Some(Claimed(slot))
+        }
+
+        /// Every slot with an owner, with a copy of its bytes
+        pub fn occupied(&self) -> Vec<Occupied> {
+            self.slots
+                .iter()
+                .enumerate()
+                .filter_map(|(index, slot)| {
+                    let pid = slot.pid.load(Ordering::Acquire);
+                    if pid == 0 {
+                        return None;
+                    }
+                    Some(Occupied {
+                        index,
+                        pid: pid as u32,
+                        bytes: slot.load(),
+                    })
+                })
+                .collect()
+        }
+
+        /// Free the slot of exited process `pid`
+        pub fn release(&self, index: usize, pid: u32) {
+            let _ = self.slots[index].pid.compare_exchange(
+                pid as u64,
+                0,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            );
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod shared {
+    pub const SLOT_BYTES: usize = 0;
+
+    pub enum Segment {}
+
+    pub struct Claimed(Segment);
+
+    impl Claimed {
+        pub fn write(&self, _bytes: &[u8]) {
+            match self.0 {}
+        }
+    }
+
+    pub struct Occupied {
+        pub index: usize,
+        pub pid: u32,
+        pub bytes: Option<Vec<u8>>,
+    }
+
+    impl Segment {
+        pub fn claim(&'static self, _pid: u32) -> Option<Claimed> {
+            match *self {}
+        }
+
+        pub fn occupied(&self) -> Vec<Occupied> {
+            match *self {}
+        }
+
+        pub fn release(&self, _index: usize, _pid: u32) {
+            match *self {}
+        }
+    }
+
+    pub fn alive(_pid: u32) -> bool {
+        true
+    }
+
+    /// Workers are threads of one process here; its metrics cover them all
+    pub fn segment() -> Option<&'static Segment> {
+        None
+    }
+}
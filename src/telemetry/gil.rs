@@ -0,0 +1,280 @@
+//! GIL wait and hold accounting.
+//!
+//! Off by default; `Server(gil_metrics=True)` turns it on for the workers.
+//! Each instrumented call site reads the clock when it is ready to run
+//! Python, once it holds the GIL and when it lets go. The first gap is the
+//! wait, the second the hold. A hold counts the whole call as seen from
+//! Rust, including stretches where Python code released the GIL itself
+//! (blocking I/O, `time.sleep`). Disabled, a call site costs one atomic load.
+//!
+//! Holds longer than the warning threshold log a WARN naming the call site,
+//! at most once a second per site.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde::{Deserialize, Serialize};
+
+use super::server_metrics;
+
+/// Hold histogram bucket upper bounds, in milliseconds; a last bucket
+/// counts everything longer
+pub const HOLD_BUCKETS_MS: [f64; 8] = [1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 1000.0];
+const DEFAULT_WARN_MS: u64 = 100;
+const WARN_INTERVAL_MS: u64 = 1000;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static WARN_US: AtomicU64 = AtomicU64::new(DEFAULT_WARN_MS * 1000);
+static EPOCH: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+/// Where Rust takes the GIL to run Python
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Site {
+    /// Route handlers, with the Python middleware and hooks they run,
+    /// and streaming response generators
+    Handler,
+    /// `BlockingExecutor` worker threads
+    Executor,
+    /// Turning database rows into Python objects
+    RowConversion,
+}
+
+impl Site {
+    pub const ALL: [Site; 3] = [Site::Handler, Site::Executor, Site::RowConversion];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Site::Handler => "handler",
+            Site::Executor => "executor",
+            Site::RowConversion => "row_conversion",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Turn the accounting on or off for this process; inherited by forked workers
+pub fn configure(enabled: bool, warn_after: Duration) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+    WARN_US.store(warn_after.as_micros() as u64, Ordering::Relaxed);
+    LazyLock::force(&EPOCH);
+}
+
+/// The current time when accounting is on, None when it is off
+#[inline]
+pub fn now() -> Option<Instant> {
+    if ENABLED.load(Ordering::Relaxed) {
+        Some(Instant::now())
+    } else {
+        None
+    }
+}
+
+/// Record one acquisition by `site`: waiting for the GIL from `ready`,
+/// holding it from `acquired` until now. Either being None skips it.
+#[inline]
+pub fn record(site: Site, ready: Option<Instant>, acquired: Option<Instant>) {
+    let (Some(ready), Some(acquired)) = (ready, acquired) else {
+        return;
+    };
+    let hold = acquired.elapsed();
+    let wait = acquired.saturating_duration_since(ready);
+    server_metrics::record_gil(site, wait, hold);
+    if hold.as_micros() as u64 >= WARN_US.load(Ordering::Relaxed) {
+        server_metrics::gil_long_hold(site, hold);
+    }
+}
+
+/// `py.detach(work)`, then `then` on its result with the GIL back, recorded
+/// as one acquisition by `site`
+pub fn detach_then<T, R, F, G>(py: Python<'_>, site: Site, work: F, then: G) -> R
+where
+    F: FnOnce() -> T + Send,
+    T: Send,
+    G: FnOnce(T) -> R,
+{
+    let (value, ready) = py.detach(|| {
+        let value = work();
+        (value, now())
+    });
+    let acquired = now();
+    let result = then(value);
+    record(site, ready, acquired);
+    result
+}
+
+#[derive(Default)]
+struct SiteStats {
+    acquisitions: AtomicU64,
+    wait_us: AtomicU64,
+    hold_us: AtomicU64,
+    max_wait_us: AtomicU64,
+    max_hold_us: AtomicU64,
+    long_holds: AtomicU64,
+    /// Long holds not warned about yet
+    unreported: AtomicU64,
+    /// Milliseconds since `EPOCH` of the last warning, plus one
+    last_warn_ms: AtomicU64,
+    histogram: [AtomicU64; HOLD_BUCKETS_MS.len() + 1],
+}
+
+/// Wait and hold totals per call site
+#[derive(Default)]
+pub struct GilStats {
+    sites: [SiteStats; 3],
+}
+
+impl GilStats {
+    pub fn record(&self, site: Site, wait: Duration, hold: Duration) {
+        let stats = &self.sites[site.index()];
+        let wait_us = wait.as_micros() as u64;
+        let hold_us = hold.as_micros() as u64;
+        stats.acquisitions.fetch_add(1, Ordering::Relaxed);
+        stats.wait_us.fetch_add(wait_us, Ordering::Relaxed);
+        stats.hold_us.fetch_add(hold_us, Ordering::Relaxed);
+        stats.max_wait_us.fetch_max(wait_us, Ordering::Relaxed);
+        stats.max_hold_us.fetch_max(hold_us, Ordering::Relaxed);
+        let bucket = HOLD_BUCKETS_MS
+            .iter()
+            .position(|&le| hold_us as f64 <= le * 1000.0)
+            .unwrap_or(HOLD_BUCKETS_MS.len());
+        stats.histogram[bucket].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a long hold; Some(holds since the last warning) when one
+    /// should be logged now
+    pub fn long_hold(&self, site: Site) -> Option<u64> {
+        let stats = &self.sites[site.index()];
+        stats.long_holds.fetch_add(1, Ordering::Relaxed);
+        let pending = stats.unreported.fetch_add(1, Ordering::Relaxed) + 1;
+        let now_ms = EPOCH.elapsed().as_millis() as u64 + 1;
+        let last = stats.last_warn_ms.load(Ordering::Relaxed);
+        if last != 0 && now_ms < last + WARN_INTERVAL_MS {
+            return None;
+        }
+        stats
+            .last_warn_ms
+            .compare_exchange(last, now_ms, Ordering::Relaxed, Ordering::Relaxed)
+            .ok()?;
+        stats.unreported.fetch_sub(pending, Ordering::Relaxed);
+        Some(pending)
+    }
+
+    pub fn reset(&self) {
+        for stats in &self.sites {
+            stats.acquisitions.store(0, Ordering::Relaxed);
+            stats.wait_us.store(0, Ordering::Relaxed);
+            stats.hold_us.store(0, Ordering::Relaxed);
+            stats.max_wait_us.store(0, Ordering::Relaxed);
+            stats.max_hold_us.store(0, Ordering::Relaxed);
+            stats.long_holds.store(0, Ordering::Relaxed);
+            stats.unreported.store(0, Ordering::Relaxed);
+            for bucket in &stats.histogram {
+                bucket.store(0, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// This collector's counts for `site`
+    pub fn site(&self, site: Site) -> SiteTotals {
+        let stats = &self.sites[site.index()];
+        SiteTotals {
+            acquisitions: stats.acquisitions.load(Ordering::Relaxed),
+            wait_us: stats.wait_us.load(Ordering::Relaxed),
+            hold_us: stats.hold_us.load(Ordering::Relaxed),
+            max_wait_us: stats.max_wait_us.load(Ordering::Relaxed),
+            max_hold_us: stats.max_hold_us.load(Ordering::Relaxed),
+            long_holds: stats.long_holds.load(Ordering::Relaxed),
+            histogram: stats
+                .histogram
+                .iter()
+                .map(|bucket| bucket.load(Ordering::Relaxed))
+                .collect(),
+        }
+    }
+
+    /// Counts of every site, in `Site::ALL` order
+    pub fn sites(&self) -> Vec<SiteTotals> {
+        Site::ALL.iter().map(|&site| self.site(site)).collect()
+    }
+}
+
+/// One site's counts at a point in time; plain numbers, so the snapshots of
+/// several workers can be merged
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SiteTotals {
+    pub acquisitions: u64,
+    pub wait_us: u64,
+    pub hold_us: u64,
+    pub max_wait_us: u64,
+    pub max_hold_us: u64,
+    pub long_holds: u64,
+    /// Holds per `HOLD_BUCKETS_MS` bucket, not cumulative; one more for +Inf
+    pub histogram: Vec<u64>,
+}
+
+impl SiteTotals {
+    /// Add `other`'s counts; maxima take the larger
+    pub fn merge(&mut self, other: &SiteTotals) {
+        self.acquisitions += other.acquisitions;
+        self.wait_us += other.wait_us;
+        self.hold_us += other.hold_us;
+        self.max_wait_us = self.max_wait_us.max(other.max_wait_us);
+        self.max_hold_us = self.max_hold_us.max(other.max_hold_us);
+        self.long_holds += other.long_holds;
+        if self.histogram.len() < other.histogram.len() {
+            self.histogram.resize(other.histogram.len(), 0);
+        }
+        for (count, n) in self.histogram.iter_mut().zip(&other.histogram) {
+            *count += n;
+        }
+    }
+
+    /// Cumulative hold counts as (upper bound in ms, count), ending with
+    /// None for +Inf
+    pub fn cumulative(&self) -> Vec<(Option<f64>, u64)> {
+        let mut total = 0;
+        self.histogram
+            .iter()
+            .enumerate()
+            .map(|(i, n)| {
+                total += n;
+                (HOLD_BUCKETS_MS.get(i).copied(), total)
+            })
+            .collect()
+    }
+
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let entry = PyDict::new(py);
+        entry.set_item("acquisitions", self.acquisitions)?;
+        entry.set_item("wait_ms", ms(self.wait_us))?;
+        entry.set_item("hold_ms", ms(self.hold_us))?;
+        entry.set_item("max_wait_ms", ms(self.max_wait_us))?;
+        entry.set_item("max_hold_ms", ms(self.max_hold_us))?;
+        entry.set_item("long_holds", self.long_holds)?;
+        entry.set_item("hold_histogram", self.cumulative())?;
+        Ok(entry)
+    }
+}
+
+/// `enabled` and `warn_ms`, then per site (`sites` in `Site::ALL` order):
+/// `acquisitions`, `wait_ms`, `hold_ms`, `max_wait_ms`, `max_hold_ms`,
+/// `long_holds` and `hold_histogram`
+pub fn to_dict<'py>(py: Python<'py>, sites: &[SiteTotals]) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("enabled", ENABLED.load(Ordering::Relaxed))?;
+    dict.set_item("warn_ms", WARN_US.load(Ordering::Relaxed) as f64 / 1000.0)?;
+    for (site, totals) in Site::ALL.iter().zip(sites) {
+        dict.set_item(site.as_str(), totals.to_dict(py)?)?;
+    }
+    Ok(dict)
+}
+
+fn ms(us: u64) -> f64 {
+    us as f64 / 1000.0
+}
@@ -1,3 +1,7 @@
+pub mod cluster;
+pub mod gil;
+pub mod server_metrics;
+
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
@@ -5,6 +9,8 @@ use dashmap::DashMap;
 use parking_lot::RwLock;
 use pyo3::prelude::*;
 
+pub use server_metrics::ServerMetrics;
+
 /// A single counter metric
 struct Counter {
     value: AtomicU64,
@@ -325,3 +331,11 @@ impl MetricsRegistry {
         )
     }
 }
+
+/// Register metrics classes.
+pub fn register_all(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<MetricsRegistry>()?;
+    m.add_class::<ServerMetrics>()?;
+    m.add_function(wrap_pyfunction!(server_metrics::server_metrics, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,940 @@
+//! Windowed request metrics.
+//!
+//! Every response is counted in a ring of fixed-width time buckets covering
+//! the last few minutes, from which current request and error rates are
+//! computed, and in per-route totals ranked for the slowest and most erroring
+//! routes. Buckets rotate lazily: a record landing in a slot that still holds
+//! an older interval clears it first, and reads skip slots outside the
+//! window, so no background thread is involved. Errors are 5xx responses.
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use serde::{Deserialize, Serialize};
+
+use super::cluster;
+use super::gil::{self, GilStats, Site, SiteTotals};
+use crate::fast_path::static_files::{self, CacheStats};
+use crate::http::admission::{self, Rejection};
+use crate::http::header_limits::{self, Limit};
+
+const DEFAULT_BUCKET_SECS: f64 = 1.0;
+const DEFAULT_RETAIN_SECS: f64 = 300.0;
+const DEFAULT_TOP_K: usize = 10;
+/// Distinct routes tracked; templates come from the router, so this is only
+/// reached by apps registering routes at runtime
+const MAX_ROUTES: usize = 4096;
+/// Bucket epoch while a slot is being cleared
+const ROTATING: u64 = u64::MAX;
+
+static PROCESS_METRICS: LazyLock<Arc<WindowedMetrics>> = LazyLock::new(|| {
+    Arc::new(WindowedMetrics::new(
+        Duration::from_secs_f64(DEFAULT_BUCKET_SECS),
+        Duration::from_secs_f64(DEFAULT_RETAIN_SECS),
+        DEFAULT_TOP_K,
+        Clock::System(Instant::now()),
+    ))
+});
+
+/// Count a response in this process's metrics; `route` is the matched route
+/// template, None when no route matched
+pub fn record(method: &str, route: Option<&str>, status: u16, duration: Duration) {
+    PROCESS_METRICS.record(method, route, status, duration);
+}
+
+/// This process's totals, as published to the other workers
+pub fn process_totals() -> Totals {
+    PROCESS_METRICS.totals()
+}
+
+/// Count one GIL acquisition in this process's metrics
+pub fn record_gil(site: Site, wait: Duration, hold: Duration) {
+    PROCESS_METRICS.gil.record(site, wait, hold);
+}
+
+/// Count a hold past the warning threshold, warning at most once a second
+/// per site
+pub fn gil_long_hold(site: Site, hold: Duration) {
+    if let Some(holds) = PROCESS_METRICS.gil.long_hold(site) {
+        crate::hlog_warn!(
+            "GIL held for {:.1} ms by {} ({} long hold(s) since the last warning)",
+            hold.as_secs_f64() * 1000.0,
+            site.as_str(),
+            holds
+        );
+    }
+}
+
+enum Clock {
+    System(Instant),
+    /// Milliseconds, moved only by `advance`
+    Manual(AtomicU64),
+}
+
+impl Clock {
+    fn now_ms(&self) -> u64 {
+        match self {
+            Clock::System(start) => start.elapsed().as_millis() as u64,
+            Clock::Manual(ms) => ms.load(Ordering::Relaxed),
+        }
+    }
+}
+
+struct Bucket {
+    /// Interval index + 1; 0 when never used
+    epoch: AtomicU64,
+    requests: AtomicU64,
+    errors: AtomicU64,
+    duration_us: AtomicU64,
+}
+
+impl Bucket {
+    fn new() -> Self {
+        Self {
+            epoch: AtomicU64::new(0),
+            requests: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            duration_us: AtomicU64::new(0),
+        }
+    }
+}
+
+#[derive(Default)]
+struct RouteStats {
+    count: AtomicU64,
+    errors: AtomicU64,
+    total_us: AtomicU64,
+    max_us: AtomicU64,
+}
+
+/// Per-route totals at one point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteSnapshot {
+    pub method: String,
+    pub route: String,
+    pub count: u64,
+    pub errors: u64,
+    pub total_us: u64,
+    pub max_us: u64,
+}
+
+impl RouteSnapshot {
+    pub fn avg_ms(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.total_us as f64 / self.count as f64 / 1000.0
+        }
+    }
+
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("method", &self.method)?;
+        dict.set_item("route", &self.route)?;
+        dict.set_item("count", self.count)?;
+        dict.set_item("errors", self.errors)?;
+        dict.set_item("avg_ms", self.avg_ms())?;
+        dict.set_item("max_ms", self.max_us as f64 / 1000.0)?;
+        Ok(dict)
+    }
+}
+
+/// Totals over a trailing window
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct WindowRate {
+    /// Window actually covered, after clamping to the retained history
+    pub window: Duration,
+    pub requests: u64,
+    pub errors: u64,
+    pub duration_us: u64,
+}
+
+impl WindowRate {
+    pub fn request_rate(&self) -> f64 {
+        self.requests as f64 / self.window.as_secs_f64()
+    }
+
+    pub fn error_rate(&self) -> f64 {
+        self.errors as f64 / self.window.as_secs_f64()
+    }
+
+    /// Add the counts of another worker over the same window
+    fn merge(&mut self, other: &WindowRate) {
+        self.window = self.window.max(other.window);
+        self.requests += other.requests;
+        self.errors += other.errors;
+        self.duration_us += other.duration_us;
+    }
+
+    fn to_dict<'py>(self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("window_secs", self.window.as_secs_f64())?;
+        dict.set_item("requests", self.requests)?;
+        dict.set_item("errors", self.errors)?;
+        dict.set_item("request_rate", self.request_rate())?;
+        dict.set_item("error_rate", self.error_rate())?;
+        let avg_ms = if self.requests == 0 {
+            0.0
+        } else {
+            self.duration_us as f64 / self.requests as f64 / 1000.0
+        };
+        dict.set_item("avg_ms", avg_ms)?;
+        Ok(dict)
+    }
+}
+
+/// The `k` routes with the highest average duration
+fn slowest(routes: &[RouteSnapshot], k: usize) -> Vec<RouteSnapshot> {
+    let mut routes = routes.to_vec();
+    routes.sort_by(|a, b| b.avg_ms().total_cmp(&a.avg_ms()));
+    routes.truncate(k);
+    routes
+}
+
+/// The `k` routes with the most 5xx responses; routes without any are left out
+fn most_erroring(routes: &[RouteSnapshot], k: usize) -> Vec<RouteSnapshot> {
+    let mut routes: Vec<_> = routes.iter().filter(|r| r.errors > 0).cloned().collect();
+    routes.sort_by(|a, b| b.errors.cmp(&a.errors).then(b.count.cmp(&a.count)));
+    routes.truncate(k);
+    routes
+}
+
+/// Everything `snapshot` and `render` report, read from one collector or
+/// merged from the snapshots of several workers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Totals {
+    pub total_requests: u64,
+    pub total_errors: u64,
+    pub rate_1m: WindowRate,
+    pub rate_5m: WindowRate,
+    pub routes: Vec<RouteSnapshot>,
+    /// In `Site::ALL` order
+    pub gil: Vec<SiteTotals>,
+    pub panics_total: u64,
+    pub retries_total: u64,
+    /// In `Rejection::ALL` order
+    pub connections_rejected: Vec<u64>,
+    /// In `Limit::ALL` order
+    pub header_limit_rejections: Vec<u64>,
+}
+
+impl Totals {
+    /// Add another worker's totals: counters are summed, per-route totals
+    /// merged by method and template, maxima take the larger
+    pub fn merge(&mut self, other: &Totals) {
+        self.total_requests += other.total_requests;
+        self.total_errors += other.total_errors;
+        self.rate_1m.merge(&other.rate_1m);
+        self.rate_5m.merge(&other.rate_5m);
+        for route in &other.routes {
+            match self
+                .routes
+                .iter_mut()
+                .find(|r| r.method == route.method && r.route == route.route)
+            {
+                Some(existing) => {
+                    existing.count += route.count;
+                    existing.errors += route.errors;
+                    existing.total_us += route.total_us;
+                    existing.max_us = existing.max_us.max(route.max_us);
+                }
+                None => self.routes.push(route.clone()),
+            }
+        }
+        if self.gil.len() < other.gil.len() {
+            self.gil.resize(other.gil.len(), SiteTotals::default());
+        }
+        for (site, theirs) in self.gil.iter_mut().zip(&other.gil) {
+            site.merge(theirs);
+        }
+        self.panics_total += other.panics_total;
+        self.retries_total += other.retries_total;
+        add_counts(&mut self.connections_rejected, &other.connections_rejected);
+        add_counts(
+            &mut self.header_limit_rejections,
+            &other.header_limit_rejections,
+        );
+    }
+
+    /// The keys of `ServerMetrics.snapshot()` but `static_cache`
+    fn to_dict<'py>(&self, py: Python<'py>, k: usize) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        let slowest = slowest(&self.routes, k)
+            .iter()
+            .map(|route| route.to_dict(py))
+            .collect::<PyResult<Vec<_>>>()?;
+        let erroring = most_erroring(&self.routes, k)
+            .iter()
+            .map(|route| route.to_dict(py))
+            .collect::<PyResult<Vec<_>>>()?;
+        dict.set_item("slowest", slowest)?;
+        dict.set_item("erroring", erroring)?;
+        dict.set_item("total_requests", self.total_requests)?;
+        dict.set_item("total_errors", self.total_errors)?;
+        dict.set_item("rate_1m", self.rate_1m.to_dict(py)?)?;
+        dict.set_item("rate_5m", self.rate_5m.to_dict(py)?)?;
+        dict.set_item("gil", gil::to_dict(py, &self.gil)?)?;
+        dict.set_item("panics_total", self.panics_total)?;
+        dict.set_item("retries_total", self.retries_total)?;
+        let rejected = PyDict::new(py);
+        for (reason, count) in Rejection::ALL.iter().zip(&self.connections_rejected) {
+            rejected.set_item(reason.as_str(), count)?;
+        }
+        dict.set_item("connections_rejected", rejected)?;
+        let rejected = PyDict::new(py);
+        for (limit, count) in Limit::ALL.iter().zip(&self.header_limit_rejections) {
+            rejected.set_item(limit.as_str(), count)?;
+        }
+        dict.set_item("header_limit_rejections", rejected)?;
+        Ok(dict)
+    }
+
+    /// Prometheus text for everything but the static file caches
+    fn render(&self, k: usize, out: &mut String) {
+        let rates = [("1m", &self.rate_1m), ("5m", &self.rate_5m)];
+        out.push_str("# HELP hypern_request_rate Requests per second over the window\n");
+        out.push_str("# TYPE hypern_request_rate gauge\n");
+        for (window, rate) in &rates {
+            let _ = writeln!(
+                out,
+                "hypern_request_rate{{window=\"{}\"}} {}",
+                window,
+                rate.request_rate()
+            );
+        }
+        out.push_str("# HELP hypern_error_rate 5xx responses per second over the window\n");
+        out.push_str("# TYPE hypern_error_rate gauge\n");
+        for (window, rate) in &rates {
+            let _ = writeln!(
+                out,
+                "hypern_error_rate{{window=\"{}\"}} {}",
+                window,
+                rate.error_rate()
+            );
+        }
+        out.push_str(
+            "# HELP hypern_route_avg_duration_seconds Average duration of the slowest routes\n",
+        );
+        out.push_str("# TYPE hypern_route_avg_duration_seconds gauge\n");
+        for route in slowest(&self.routes, k) {
+            let _ = writeln!(
+                out,
+                "hypern_route_avg_duration_seconds{{method=\"{}\",route=\"{}\"}} {}",
+                route.method,
+                escape_label(&route.route),
+                route.avg_ms() / 1000.0
+            );
+        }
+        let sites = Site::ALL.iter().zip(&self.gil);
+        out.push_str("# HELP hypern_gil_wait_seconds_total Time spent waiting for the GIL\n");
+        out.push_str("# TYPE hypern_gil_wait_seconds_total counter\n");
+        for (site, totals) in sites.clone() {
+            let _ = writeln!(
+                out,
+                "hypern_gil_wait_seconds_total{{site=\"{}\"}} {}",
+                site.as_str(),
+                totals.wait_us as f64 / 1_000_000.0
+            );
+        }
+        out.push_str("# HELP hypern_gil_hold_seconds Time the GIL was held per acquisition\n");
+        out.push_str("# TYPE hypern_gil_hold_seconds histogram\n");
+        for (site, totals) in sites {
+            for (le, n) in totals.cumulative() {
+                let le = match le {
+                    Some(ms) => (ms / 1000.0).to_string(),
+                    None => "+Inf".to_string(),
+                };
+                let _ = writeln!(
+                    out,
+                    "hypern_gil_hold_seconds_bucket{{site=\"{}\",le=\"{}\"}} {}",
+                    site.as_str(),
+                    le,
+                    n
+                );
+            }
+            let _ = writeln!(
+                out,
+                "hypern_gil_hold_seconds_sum{{site=\"{}\"}} {}",
+                site.as_str(),
+                totals.hold_us as f64 / 1_000_000.0
+            );
+            let _ = writeln!(
+                out,
+                "hypern_gil_hold_seconds_count{{site=\"{}\"}} {}",
+                site.as_str(),
+                totals.acquisitions
+            );
+        }
+        out.push_str("# HELP hypern_panics_total Panics caught on the request path\n");
+        out.push_str("# TYPE hypern_panics_total counter\n");
+        let _ = writeln!(out, "hypern_panics_total {}", self.panics_total);
+        out.push_str(
+            "# HELP hypern_handler_retries_total Handler runs repeated by a retry policy\n",
+        );
+        out.push_str("# TYPE hypern_handler_retries_total counter\n");
+        let _ = writeln!(out, "hypern_handler_retries_total {}", self.retries_total);
+        out.push_str(
+            "# HELP hypern_connections_rejected_total Connections closed by the server, by reason\n",
+        );
+        out.push_str("# TYPE hypern_connections_rejected_total counter\n");
+        for (reason, count) in Rejection::ALL.iter().zip(&self.connections_rejected) {
+            let _ = writeln!(
+                out,
+                "hypern_connections_rejected_total{{reason=\"{}\"}} {}",
+                reason.as_str(),
+                count
+            );
+        }
+        out.push_str(
+            "# HELP hypern_header_limit_rejections_total Requests refused for oversized headers or URI, by limit\n",
+        );
+        out.push_str("# TYPE hypern_header_limit_rejections_total counter\n");
+        for (limit, count) in Limit::ALL.iter().zip(&self.header_limit_rejections) {
+            let _ = writeln!(
+                out,
+                "hypern_header_limit_rejections_total{{reason=\"{}\"}} {}",
+                limit.as_str(),
+                count
+            );
+        }
+    }
+}
+
+/// Sum counters kept in the same order, growing `into` as needed
+fn add_counts(into: &mut Vec<u64>, from: &[u64]) {
+    if into.len() < from.len() {
+        into.resize(from.len(), 0);
+    }
+    for (count, n) in into.iter_mut().zip(from) {
+        *count += n;
+    }
+}
+
+pub struct WindowedMetrics {
+    clock: Clock,
+    bucket_ms: u64,
+    buckets: Box<[Bucket]>,
+    top_k: usize,
+    routes: DashMap<String, (String, String, Arc<RouteStats>)>,
+    total_requests: AtomicU64,
+    total_errors: AtomicU64,
+    gil: GilStats,
+}
+
+impl WindowedMetrics {
+    fn new(bucket: Duration, retain: Duration, top_k: usize, clock: Clock) -> Self {
+        let bucket_ms = (bucket.as_millis() as u64).max(1);
+        let slots = (retain.as_millis() as u64).div_ceil(bucket_ms).max(1) as usize;
+        Self {
+            clock,
+            bucket_ms,
+            buckets: (0..slots).map(|_| Bucket::new()).collect(),
+            top_k,
+            routes: DashMap::new(),
+            total_requests: AtomicU64::new(0),
+            total_errors: AtomicU64::new(0),
+            gil: GilStats::default(),
+        }
+    }
+
+    fn current_interval(&self) -> u64 {
+        self.clock.now_ms() / self.bucket_ms
+    }
+
+    /// The slot for `interval`, cleared first if it holds an older one
+    fn bucket_for(&self, interval: u64) -> &Bucket {
+        let bucket = &self.buckets[(interval % self.buckets.len() as u64) as usize];
+        let epoch = interval + 1;
+        loop {
+            let current = bucket.epoch.load(Ordering::Acquire);
+            // A late record for an interval already rotated out lands in the
+            // newer one rather than being dropped
+            if current == epoch || (current != ROTATING && current > epoch) {
+                return bucket;
+            }
+            if current == ROTATING {
+                std::hint::spin_loop();
+                continue;
+            }
+            if bucket
+                .epoch
+                .compare_exchange(current, ROTATING, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                bucket.requests.store(0, Ordering::Relaxed);
+                bucket.errors.store(0, Ordering::Relaxed);
+                bucket.duration_us.store(0, Ordering::Relaxed);
+                bucket.epoch.store(epoch, Ordering::Release);
+                return bucket;
+            }
+        }
+    }
+
+    pub fn record(&self, method: &str, route: Option<&str>, status: u16, duration: Duration) {
+        let error = status >= 500;
+        let duration_us = duration.as_micros() as u64;
+        let bucket = self.bucket_for(self.current_interval());
+        bucket.requests.fetch_add(1, Ordering::Relaxed);
+        bucket.duration_us.fetch_add(duration_us, Ordering::Relaxed);
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+        if error {
+            bucket.errors.fetch_add(1, Ordering::Relaxed);
+            self.total_errors.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let Some(route) = route else {
+            return;
+        };
+        let key = format!("{} {}", method, route);
+        let stats = match self.routes.get(&key) {
+            Some(entry) => entry.2.clone(),
+            None if self.routes.len() >= MAX_ROUTES => return,
+            None => self
+                .routes
+                .entry(key)
+                .or_insert_with(|| {
+                    (
+                        method.to_string(),
+                        route.to_string(),
+                        Arc::new(RouteStats::default()),
+                    )
+                })
+                .2
+                .clone(),
+        };
+        stats.count.fetch_add(1, Ordering::Relaxed);
+        stats.total_us.fetch_add(duration_us, Ordering::Relaxed);
+        stats.max_us.fetch_max(duration_us, Ordering::Relaxed);
+        if error {
+            stats.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Totals over the last `window`, including the current partial bucket
+    pub fn rate(&self, window: Duration) -> WindowRate {
+        let wanted = (window.as_millis() as u64).div_ceil(self.bucket_ms).max(1);
+        let count = wanted.min(self.buckets.len() as u64);
+        let current = self.current_interval();
+        let mut rate = WindowRate {
+            window: Duration::from_millis(count * self.bucket_ms),
+            requests: 0,
+            errors: 0,
+            duration_us: 0,
+        };
+        for interval in current.saturating_sub(count - 1)..=current {
+            let bucket = &self.buckets[(interval % self.buckets.len() as u64) as usize];
+            if bucket.epoch.load(Ordering::Acquire) != interval + 1 {
+                continue;
+            }
+            rate.requests += bucket.requests.load(Ordering::Relaxed);
+            rate.errors += bucket.errors.load(Ordering::Relaxed);
+            rate.duration_us += bucket.duration_us.load(Ordering::Relaxed);
+        }
+        rate
+    }
+
+    pub fn routes(&self) -> Vec<RouteSnapshot> {
+        self.routes
+            .iter()
+            .map(|entry| {
+                let (method, route, stats) = entry.value();
+                RouteSnapshot {
+                    method: method.clone(),
+                    route: route.clone(),
+                    count: stats.count.load(Ordering::Relaxed),
+                    errors: stats.errors.load(Ordering::Relaxed),
+                    total_us: stats.total_us.load(Ordering::Relaxed),
+                    max_us: stats.max_us.load(Ordering::Relaxed),
+                }
+            })
+            .collect()
+    }
+
+    /// Everything this collector reports, at one point in time
+    pub fn totals(&self) -> Totals {
+        Totals {
+            total_requests: self.total_requests.load(Ordering::Relaxed),
+            total_errors: self.total_errors.load(Ordering::Relaxed),
+            rate_1m: self.rate(Duration::from_secs(60)),
+            rate_5m: self.rate(Duration::from_secs(300)),
+            routes: self.routes(),
+            gil: self.gil.sites(),
+            panics_total: crate::http::panic::panics_total(),
+            retries_total: crate::routing::retry::retries_total(),
+            connections_rejected: Rejection::ALL
+                .iter()
+                .map(|&reason| admission::rejected_total(reason))
+                .collect(),
+            header_limit_rejections: Limit::ALL
+                .iter()
+                .map(|&limit| header_limits::rejected_total(limit))
+                .collect(),
+        }
+    }
+
+    pub fn reset(&self) {
+        for bucket in self.buckets.iter() {
+            bucket.epoch.store(0, Ordering::Release);
+        }
+        self.routes.clear();
+        self.total_requests.store(0, Ordering::Relaxed);
+        self.total_errors.store(0, Ordering::Relaxed);
+        self.gil.reset();
+    }
+
+    fn retained(&self) -> Duration {
+        Duration::from_millis(self.bucket_ms * self.buckets.len() as u64)
+    }
+}
+
+/// Request rates over a sliding window and the slowest and most erroring
+/// routes.
+///
+/// `server_metrics()` returns the collector the server records every
+/// response in (per worker process). Instances created directly are
+/// independent and only hold what is passed to `record`; with
+/// `manual_clock=True` their time only moves through `advance`.
+///
+/// With several worker processes, `snapshot(cluster=True)` and
+/// `render(cluster=True)` on the server's collector merge the totals every
+/// live worker published (see `Server(metrics_publish_interval=...)`).
+///
+/// Args:
+///     bucket_secs: Width of one time bucket
+///     retain_secs: History kept; longer windows are clamped to it
+///     top_k: Routes returned by `top_routes` by default
+///     manual_clock: Start the clock at 0 and move it only with `advance`
+#[pyclass(name = "ServerMetrics")]
+pub struct ServerMetrics {
+    inner: Arc<WindowedMetrics>,
+    /// Whether this is the server's collector, the one workers publish
+    process: bool,
+}
+
+#[pymethods]
+impl ServerMetrics {
+    #[new]
+    #[pyo3(signature = (bucket_secs=DEFAULT_BUCKET_SECS, retain_secs=DEFAULT_RETAIN_SECS, top_k=DEFAULT_TOP_K, manual_clock=false))]
+    pub fn new(
+        bucket_secs: f64,
+        retain_secs: f64,
+        top_k: usize,
+        manual_clock: bool,
+    ) -> PyResult<Self> {
+        if !bucket_secs.is_finite() || bucket_secs < 0.001 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "bucket_secs must be at least 0.001",
+            ));
+        }
+        if !retain_secs.is_finite() || retain_secs < bucket_secs {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "retain_secs must be at least bucket_secs",
+            ));
+        }
+        let clock = if manual_clock {
+            Clock::Manual(AtomicU64::new(0))
+        } else {
+            Clock::System(Instant::now())
+        };
+        Ok(Self {
+            inner: Arc::new(WindowedMetrics::new(
+                Duration::from_secs_f64(bucket_secs),
+                Duration::from_secs_f64(retain_secs),
+                top_k,
+                clock,
+            )),
+            process: false,
+        })
+    }
+
+    /// Count one response.
+    ///
+    /// Args:
+    ///     method: Request method
+    ///     route: Matched route template, or None when no route matched
+    ///     status: Response status; 5xx counts as an error
+    ///     duration_ms: Time taken to respond
+    #[pyo3(signature = (method, route, status, duration_ms))]
+    pub fn record(&self, method: &str, route: Option<&str>, status: u16, duration_ms: f64) {
+        let duration = Duration::from_secs_f64(duration_ms.max(0.0) / 1000.0);
+        self.inner.record(method, route, status, duration);
+    }
+
+    /// Request and error rates over the last `window_secs`.
+    ///
+    /// Returns a dict with `window_secs` (clamped to the retained history),
+    /// `requests`, `errors`, `request_rate` and `error_rate` (per second)
+    /// and `avg_ms`. The current, partly elapsed bucket is included.
+    #[pyo3(signature = (window_secs=60.0))]
+    pub fn rate<'py>(&self, py: Python<'py>, window_secs: f64) -> PyResult<Bound<'py, PyDict>> {
+        if !window_secs.is_finite() || window_secs <= 0.0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "window_secs must be a positive number",
+            ));
+        }
+        self.inner
+            .rate(Duration::from_secs_f64(window_secs))
+            .to_dict(py)
+    }
+
+    /// Slowest routes by average duration and routes with the most 5xx
+    /// responses since the last reset.
+    ///
+    /// Returns a dict with `slowest` and `erroring`, lists of dicts holding
+    /// `method`, `route`, `count`, `errors`, `avg_ms` and `max_ms`.
+    #[pyo3(signature = (k=None))]
+    pub fn top_routes<'py>(
+        &self,
+        py: Python<'py>,
+        k: Option<usize>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let k = k.unwrap_or(self.inner.top_k);
+        let routes = self.inner.routes();
+        let dict = PyDict::new(py);
+        let slowest = slowest(&routes, k)
+            .iter()
+            .map(|route| route.to_dict(py))
+            .collect::<PyResult<Vec<_>>>()?;
+        let erroring = most_erroring(&routes, k)
+            .iter()
+            .map(|route| route.to_dict(py))
+            .collect::<PyResult<Vec<_>>>()?;
+        dict.set_item("slowest", slowest)?;
+        dict.set_item("erroring", erroring)?;
+        Ok(dict)
+    }
+
+    /// GIL acquisitions since the last reset, per call site (`handler`,
+    /// `executor`, `row_conversion`).
+    ///
+    /// Each site holds `acquisitions`, `wait_ms` and `hold_ms` totals,
+    /// `max_wait_ms`, `max_hold_ms`, `long_holds` (holds past the warning
+    /// threshold) and `hold_histogram`, cumulative `(le_ms, count)` pairs;
+    /// the last has `le_ms` None (+Inf). `enabled` and `warn_ms` give the
+    /// settings; all counts stay zero unless the server runs with
+    /// `gil_metrics=True`.
+    pub fn gil<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        gil::to_dict(py, &self.inner.gil.sites())
+    }
+
+    /// File cache counters of every live `StaticFileHandler`: a list of
+    /// dicts with its `prefix` and the keys of `StaticFileHandler.cache_stats()`
+    pub fn static_cache<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        let list = PyList::empty(py);
+        for (prefix, stats) in static_files::cache_stats() {
+            let dict = stats.to_dict(py)?;
+            dict.set_item("prefix", prefix)?;
+            list.append(dict)?;
+        }
+        Ok(list)
+    }
+
+    /// Everything at once: `total_requests` and `total_errors` since the
+    /// last reset, `rate_1m`, `rate_5m` (as from `rate`), `slowest` and
+    /// `erroring` (as from `top_routes`), `gil` (as from `gil`),
+    /// `static_cache` (as from `static_cache`), `panics_total` (panics
+    /// the worker caught since it started), `retries_total` (handler runs
+    /// repeated under a route's retry policy since it started) and
+    /// `connections_rejected` (connections the worker closed since it
+    /// started, by reason: `max_connections`, `max_connections_per_ip` or
+    /// `header_read_timeout`) and `header_limit_rejections` (requests
+    /// refused since it started, by the limit they went over:
+    /// `max_header_bytes`, `max_header_count` or `max_uri_length`).
+    ///
+    /// With `cluster=True` (server collector only), every key but
+    /// `static_cache` covers all live workers: this process's totals merged
+    /// with the latest snapshot each other worker published. `workers`
+    /// counts them, and `per_worker=True` adds `per_worker`, a list of
+    /// dicts with each worker's `pid`, `age_secs` (since it published),
+    /// `total_requests`, `total_errors`, `rate_1m` and `rate_5m`.
+    #[pyo3(signature = (cluster=false, per_worker=false))]
+    pub fn snapshot<'py>(
+        &self,
+        py: Python<'py>,
+        cluster: bool,
+        per_worker: bool,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        if !cluster {
+            let dict = self.inner.totals().to_dict(py, self.inner.top_k)?;
+            dict.set_item("static_cache", self.static_cache(py)?)?;
+            return Ok(dict);
+        }
+        let workers = self.cluster_workers()?;
+        let dict = cluster::merged(&workers).to_dict(py, self.inner.top_k)?;
+        dict.set_item("static_cache", self.static_cache(py)?)?;
+        dict.set_item("workers", workers.len())?;
+        if per_worker {
+            let list = PyList::empty(py);
+            for worker in &workers {
+                let entry = PyDict::new(py);
+                entry.set_item("pid", worker.pid)?;
+                entry.set_item("age_secs", worker.age.as_secs_f64())?;
+                entry.set_item("total_requests", worker.totals.total_requests)?;
+                entry.set_item("total_errors", worker.totals.total_errors)?;
+                entry.set_item("rate_1m", worker.totals.rate_1m.to_dict(py)?)?;
+                entry.set_item("rate_5m", worker.totals.rate_5m.to_dict(py)?)?;
+                list.append(entry)?;
+            }
+            dict.set_item("per_worker", list)?;
+        }
+        Ok(dict)
+    }
+
+    /// Publish this process's totals for the other workers now, instead of
+    /// waiting for the next interval. Returns False when there is nowhere
+    /// to publish (non-Unix, or every slot taken).
+    ///
+    /// Raises ValueError on a collector other than `server_metrics()`.
+    pub fn publish(&self) -> PyResult<bool> {
+        self.require_process("publish()")?;
+        Ok(cluster::publish())
+    }
+
+    /// Drop all counts, e.g. between tests
+    pub fn reset(&self) {
+        self.inner.reset();
+    }
+
+    /// Move a manual clock forward.
+    ///
+    /// Raises ValueError on a collector using the system clock.
+    pub fn advance(&self, secs: f64) -> PyResult<()> {
+        match &self.inner.clock {
+            Clock::Manual(ms) if secs.is_finite() && secs >= 0.0 => {
+                ms.fetch_add((secs * 1000.0) as u64, Ordering::Relaxed);
+                Ok(())
+            }
+            Clock::Manual(_) => Err(pyo3::exceptions::PyValueError::new_err(
+                "secs must be a non-negative number",
+            )),
+            Clock::System(_) => Err(pyo3::exceptions::PyValueError::new_err(
+                "advance() needs a collector created with manual_clock=True",
+            )),
+        }
+    }
+
+    /// Prometheus text exposition of the rates (1m and 5m windows) and the
+    /// top routes' average durations, as gauges, the GIL wait and hold
+    /// totals and hold histogram per call site, the panic, retry and
+    /// rejection counters, and the static file caches.
+    ///
+    /// With `cluster=True` (server collector only) the values cover all live
+    /// workers, as in `snapshot(cluster=True)`, and `hypern_workers` gives
+    /// their number; `per_worker=True` adds `hypern_worker_requests_total`,
+    /// `hypern_worker_errors_total` and `hypern_worker_request_rate`
+    /// labelled by `worker` (the pid).
+    #[pyo3(signature = (cluster=false, per_worker=false))]
+    pub fn render(&self, cluster: bool, per_worker: bool) -> PyResult<String> {
+        let mut out = String::with_capacity(1024);
+        if !cluster {
+            self.inner.totals().render(self.inner.top_k, &mut out);
+        } else {
+            let workers = self.cluster_workers()?;
+            cluster::merged(&workers).render(self.inner.top_k, &mut out);
+            out.push_str("# HELP hypern_workers Workers whose metrics are included\n");
+            out.push_str("# TYPE hypern_workers gauge\n");
+            let _ = writeln!(out, "hypern_workers {}", workers.len());
+            if per_worker {
+                render_per_worker(&workers, &mut out);
+            }
+        }
+        let caches = static_files::cache_stats();
+        for (i, (name, kind, help)) in CacheStats::METRICS.iter().enumerate() {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            for (prefix, stats) in &caches {
+                let _ = writeln!(
+                    out,
+                    "{}{{prefix=\"{}\"}} {}",
+                    name,
+                    escape_label(prefix),
+                    stats.metric_values()[i]
+                );
+            }
+        }
+        Ok(out)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ServerMetrics(bucket_secs={}, retain_secs={}, routes={})",
+            self.inner.bucket_ms as f64 / 1000.0,
+            self.inner.retained().as_secs_f64(),
+            self.inner.routes.len()
+        )
+    }
+}
+
+impl ServerMetrics {
+    fn require_process(&self, what: &str) -> PyResult<()> {
+        if self.process {
+            Ok(())
+        } else {
+            Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "{} needs the server's collector, from server_metrics()",
+                what
+            )))
+        }
+    }
+
+    fn cluster_workers(&self) -> PyResult<Vec<cluster::WorkerTotals>> {
+        self.require_process("cluster=True")?;
+        Ok(cluster::collect())
+    }
+}
+
+fn render_per_worker(workers: &[cluster::WorkerTotals], out: &mut String) {
+    out.push_str("# HELP hypern_worker_requests_total Responses counted by each worker\n");
+    out.push_str("# TYPE hypern_worker_requests_total counter\n");
+    for worker in workers {
+        let _ = writeln!(
+            out,
+            "hypern_worker_requests_total{{worker=\"{}\"}} {}",
+            worker.pid, worker.totals.total_requests
+        );
+    }
+    out.push_str("# HELP hypern_worker_errors_total 5xx responses counted by each worker\n");
+    out.push_str("# TYPE hypern_worker_errors_total counter\n");
+    for worker in workers {
+        let _ = writeln!(
+            out,
+            "hypern_worker_errors_total{{worker=\"{}\"}} {}",
+            worker.pid, worker.totals.total_errors
+        );
+    }
+    out.push_str("# HELP hypern_worker_request_rate Requests per second of each worker\n");
+    out.push_str("# TYPE hypern_worker_request_rate gauge\n");
+    for worker in workers {
+        for (window, rate) in [("1m", &worker.totals.rate_1m), ("5m", &worker.totals.rate_5m)] {
+            let _ = writeln!(
+                out,
+                "hypern_worker_request_rate{{worker=\"{}\",window=\"{}\"}} {}",
+                worker.pid,
+                window,
+                rate.request_rate()
+            );
+        }
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// The collector this worker process records every response in
+#[pyfunction]
+pub fn server_metrics() -> ServerMetrics {
+    cluster::install();
+    ServerMetrics {
+        inner: PROCESS_METRICS.clone(),
+        process: true,
+    }
+}
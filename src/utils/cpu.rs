@@ -1,5 +1,223 @@
+//! CPU topology helpers: container-aware CPU counts and worker affinity.
+
+use pyo3::prelude::*;
+use std::path::Path;
+
 pub fn num_cpus(default: usize) -> usize {
     std::thread::available_parallelism()
         .map(|p| p.get())
         .unwrap_or(default)
 }
+
+// ──────────────────────────── cgroup quotas ──────────────────────────────── //
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Parse a cgroup v2 `cpu.max` file (`"<quota> <period>"` or `"max <period>"`).
+///
+/// Returns the CPU limit as a fraction of cores, or `None` when unlimited.
+pub fn parse_cpu_max(content: &str) -> Option<f64> {
+    let mut parts = content.split_whitespace();
+    let quota = parts.next()?;
+    let period: f64 = parts.next().unwrap_or("100000").parse().ok()?;
+    if quota == "max" || period <= 0.0 {
+        return None;
+    }
+    let quota: f64 = quota.parse().ok()?;
+    (quota > 0.0).then(|| quota / period)
+}
+
+/// Parse cgroup v1 `cpu.cfs_quota_us` / `cpu.cfs_period_us` contents.
+///
+/// A quota of `-1` means unlimited.
+pub fn parse_cfs_quota(quota: &str, period: &str) -> Option<f64> {
+    let quota: i64 = quota.trim().parse().ok()?;
+    let period: i64 = period.trim().parse().ok()?;
+    if quota <= 0 || period <= 0 {
+        return None;
+    }
+    Some(quota as f64 / period as f64)
+}
+
+/// Read the CPU limit from a cgroup mount root (v2 first, then v1).
+pub fn cgroup_cpu_limit_at(root: &Path) -> Option<f64> {
+    if let Ok(content) = std::fs::read_to_string(root.join("cpu.max")) {
+        return parse_cpu_max(&content);
+    }
+    for dir in [root.join("cpu"), root.join("cpu,cpuacct"), root.to_path_buf()] {
+        let quota = std::fs::read_to_string(dir.join("cpu.cfs_quota_us"));
+        let period = std::fs::read_to_string(dir.join("cpu.cfs_period_us"));
+        if let (Ok(quota), Ok(period)) = (quota, period) {
+            return parse_cfs_quota(&quota, &period);
+        }
+    }
+    None
+}
+
+/// CPU limit imposed by the container's cgroup, if any.
+pub fn cgroup_cpu_limit() -> Option<f64> {
+    cgroup_cpu_limit_at(Path::new(CGROUP_ROOT))
+}
+
+/// Number of CPUs this process can actually use: the smaller of the
+/// visible cores and the cgroup quota (rounded up), at least 1.
+pub fn effective_cpus() -> usize {
+    let visible = num_cpus(1);
+    match cgroup_cpu_limit() {
+        Some(limit) => visible.min(limit.ceil() as usize).max(1),
+        None => visible,
+    }
+}
+
+// ──────────────────────────── affinity ───────────────────────────────────── //
+
+/// CPU affinity policy for worker processes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum CpuAffinity {
+    /// Leave scheduling to the OS
+    #[default]
+    None,
+    /// Pin worker `i` to allowed core `i % cores`
+    Auto,
+    /// Explicit core set per worker (wraps around when there are more workers)
+    Explicit(Vec<Vec<usize>>),
+}
+
+impl CpuAffinity {
+    /// Parse `None`, `"auto"`/`"none"`, or a list of core lists.
+    pub fn from_py(value: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        let Some(value) = value else {
+            return Ok(CpuAffinity::None);
+        };
+        if value.is_none() {
+            return Ok(CpuAffinity::None);
+        }
+        if let Ok(mode) = value.extract::<String>() {
+            return match mode.to_lowercase().as_str() {
+                "auto" => Ok(CpuAffinity::Auto),
+                "none" | "off" => Ok(CpuAffinity::None),
+                other => Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "Invalid cpu_affinity '{}': expected 'auto', 'none' or a list of core lists",
+                    other
+                ))),
+            };
+        }
+        let sets: Vec<Vec<usize>> = value.extract().map_err(|_| {
+            pyo3::exceptions::PyTypeError::new_err(
+                "cpu_affinity must be 'auto', 'none' or a list of core lists, e.g. [[0, 1], [2, 3]]",
+            )
+        })?;
+        if sets.is_empty() || sets.iter().any(|s| s.is_empty()) {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "cpu_affinity core sets must not be empty",
+            ));
+        }
+        Ok(CpuAffinity::Explicit(sets))
+    }
+
+    /// Core set for each worker; empty when affinity is disabled.
+    pub fn layout(&self, num_workers: usize, cores: &[usize]) -> Vec<Vec<usize>> {
+        match self {
+            CpuAffinity::None => Vec::new(),
+            CpuAffinity::Auto if cores.is_empty() => Vec::new(),
+            CpuAffinity::Auto => (0..num_workers)
+                .map(|i| vec![cores[i % cores.len()]])
+                .collect(),
+            CpuAffinity::Explicit(sets) => (0..num_workers)
+                .map(|i| sets[i % sets.len()].clone())
+                .collect(),
+        }
+    }
+}
+
+/// Cores this process is allowed to run on.
+#[cfg(target_os = "linux")]
+pub fn allowed_cpus() -> Vec<usize> {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) == 0 {
+            let cores: Vec<usize> = (0..libc::CPU_SETSIZE as usize)
+                .filter(|&cpu| libc::CPU_ISSET(cpu, &set))
+                .collect();
+            if !cores.is_empty() {
+                return cores;
+            }
+        }
+    }
+    (0..num_cpus(1)).collect()
+}
+
+/// Cores this process is allowed to run on.
+#[cfg(not(target_os = "linux"))]
+pub fn allowed_cpus() -> Vec<usize> {
+    (0..num_cpus(1)).collect()
+}
+
+/// Pin the calling process (and threads it spawns later) to `cores`.
+///
+/// Returns false when pinning failed or is unsupported on this platform.
+#[cfg(target_os = "linux")]
+pub fn pin_current_process(cores: &[usize]) -> bool {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &cpu in cores {
+            if cpu < libc::CPU_SETSIZE as usize {
+                libc::CPU_SET(cpu, &mut set);
+            }
+        }
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) == 0
+    }
+}
+
+/// Pin the calling process (and threads it spawns later) to `cores`.
+///
+/// Returns false when pinning failed or is unsupported on this platform.
+#[cfg(not(target_os = "linux"))]
+pub fn pin_current_process(_cores: &[usize]) -> bool {
+    false
+}
+
+// ──────────────────────────── python api ─────────────────────────────────── //
+
+/// Number of CPUs usable by this process, honouring cgroup CPU quotas.
+///
+/// Example (Python):
+///     cpu_count()  # 2 inside a container limited to 2 CPUs on a 64-core host
+#[pyfunction]
+pub fn cpu_count() -> usize {
+    effective_cpus()
+}
+
+/// CPU limit from the cgroup mounted at ``root`` (default ``/sys/fs/cgroup``).
+///
+/// Returns ``None`` when no quota is set.
+#[pyfunction]
+#[pyo3(signature = (root=None))]
+pub fn cgroup_cpu_quota(root: Option<&str>) -> Option<f64> {
+    cgroup_cpu_limit_at(Path::new(root.unwrap_or(CGROUP_ROOT)))
+}
+
+/// Core set each worker would be pinned to.
+///
+/// Args:
+///     num_workers: Number of worker processes
+///     affinity: ``"auto"``, ``"none"`` or a list of core lists
+///     cores: Available cores (default: cores this process may run on)
+#[pyfunction]
+#[pyo3(signature = (num_workers, affinity=None, cores=None))]
+pub fn cpu_affinity_layout(
+    num_workers: usize,
+    affinity: Option<&Bound<'_, PyAny>>,
+    cores: Option<Vec<usize>>,
+) -> PyResult<Vec<Vec<usize>>> {
+    let affinity = CpuAffinity::from_py(affinity)?;
+    let cores = cores.unwrap_or_else(allowed_cpus);
+    Ok(affinity.layout(num_workers, &cores))
+}
+
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(cpu_count, m)?)?;
+    m.add_function(wrap_pyfunction!(cgroup_cpu_quota, m)?)?;
+    m.add_function(wrap_pyfunction!(cpu_affinity_layout, m)?)?;
+    Ok(())
+}
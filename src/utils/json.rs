@@ -150,9 +150,13 @@ pub fn parse_json_to_py(py: Python<'_>, bytes: &[u8]) -> PyResult<Py<PyAny>> {
 
 /// Serialize Python object to JSON bytes using simd-json when possible.
 pub fn serialize_py_to_json(obj: &Bound<'_, PyAny>) -> PyResult<Vec<u8>> {
-    let value = py_to_json_value(obj)?;
+    serialize_json_value(&py_to_json_value(obj)?)
+}
+
+/// Serialize an already converted value to JSON bytes.
+pub fn serialize_json_value(value: &JsonValue) -> PyResult<Vec<u8>> {
     // Use simd-json for serialization - faster than serde_json
-    simd_json::to_vec(&value).map_err(|e| {
+    simd_json::to_vec(value).map_err(|e| {
         pyo3::exceptions::PyValueError::new_err(format!("JSON serialization error: {}", e))
     })
 }
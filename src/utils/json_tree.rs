@@ -0,0 +1,233 @@
+//! JSON serialization with the GIL released.
+//!
+//! `serialize_py_to_json` converts and writes a Python object while holding
+//! the GIL, so a large response body stalls every other Python thread for
+//! as long as it takes. Here the work is split in two: one walk over the
+//! object, with the GIL held, builds an owned tree of Rust values with its
+//! keys already encoded; the tree is then written out with the GIL
+//! released, into a buffer reused by the thread.
+//!
+//! Building the tree costs an allocation per value, which small bodies do
+//! not win back, so `serialize` keeps them on the existing path. Objects are
+//! converted exactly as `py_to_json_value` does, and the output matches
+//! `serialize_py_to_json` byte for byte except that dicts keep their
+//! insertion order.
+
+use std::cell::RefCell;
+
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyBytes, PyDict, PyFloat, PyInt, PyList, PyString};
+use serde_json::Value as JsonValue;
+
+use crate::utils::json::{py_to_json_value, serialize_py_to_json};
+
+/// Objects with at least this many entries in their top two levels are
+/// serialized with the GIL released
+pub const DETACH_MIN_ENTRIES: usize = 512;
+
+/// Pooled buffers growing past this are not kept for the next body
+const MAX_POOLED_CAPACITY: usize = 4 * 1024 * 1024;
+
+thread_local! {
+    static BUFFER: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// An owned JSON value, built under the GIL and written without it
+enum Node {
+    Null,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Str(String),
+    List(Vec<Node>),
+    /// Keys written out as JSON strings, quotes included
+    Map(Vec<(Vec<u8>, Node)>),
+    /// Bytes, tuples, sets, objects and the rest, converted by
+    /// `py_to_json_value`
+    Other(JsonValue),
+}
+
+fn serialization_error(err: impl std::fmt::Display) -> PyErr {
+    pyo3::exceptions::PyValueError::new_err(format!("JSON serialization error: {}", err))
+}
+
+fn encode_key(key: &str) -> PyResult<Vec<u8>> {
+    let mut encoded = Vec::with_capacity(key.len() + 2);
+    simd_json::to_writer(&mut encoded, key).map_err(serialization_error)?;
+    Ok(encoded)
+}
+
+/// Walk `obj` once, with the GIL held. Checks run in the same order as in
+/// `py_to_json_value`, so both agree on every type.
+fn convert(obj: &Bound<'_, PyAny>) -> PyResult<Node> {
+    if obj.is_none() {
+        return Ok(Node::Null);
+    }
+    if obj.is_instance_of::<PyBool>() {
+        return Ok(Node::Bool(obj.extract::<bool>()?));
+    }
+    if obj.is_instance_of::<PyInt>() {
+        if let Ok(i) = obj.extract::<i64>() {
+            return Ok(Node::Int(i));
+        }
+        if let Ok(u) = obj.extract::<u64>() {
+            return Ok(Node::UInt(u));
+        }
+        return Ok(Node::Str(obj.str()?.to_string()));
+    }
+    if obj.is_instance_of::<PyFloat>() {
+        let f = obj.extract::<f64>()?;
+        return Ok(if f.is_finite() {
+            Node::Float(f)
+        } else {
+            Node::Null
+        });
+    }
+    if obj.is_instance_of::<PyString>() {
+        return Ok(Node::Str(obj.extract::<String>()?));
+    }
+    if let Ok(dict) = obj.cast::<PyDict>() {
+        let mut entries = Vec::with_capacity(dict.len());
+        for (key, value) in dict.iter() {
+            // Other keys are stringified, and may then collide with a
+            // string key; leave merging them to `py_to_json_value`
+            let Ok(key) = key.cast::<PyString>() else {
+                return Ok(Node::Other(py_to_json_value(obj)?));
+            };
+            entries.push((encode_key(key.to_str()?)?, convert(&value)?));
+        }
+        return Ok(Node::Map(entries));
+    }
+    if let Ok(list) = obj.cast::<PyList>() {
+        let mut items = Vec::with_capacity(list.len());
+        for item in list.iter() {
+            items.push(convert(&item)?);
+        }
+        return Ok(Node::List(items));
+    }
+    Ok(Node::Other(py_to_json_value(obj)?))
+}
+
+/// Write `node` to `out`; runs without the GIL
+fn write(node: &Node, out: &mut Vec<u8>) -> simd_json::Result<()> {
+    match node {
+        Node::Null => out.extend_from_slice(b"null"),
+        Node::Bool(true) => out.extend_from_slice(b"true"),
+        Node::Bool(false) => out.extend_from_slice(b"false"),
+        Node::Int(i) => simd_json::to_writer(&mut *out, i)?,
+        Node::UInt(u) => simd_json::to_writer(&mut *out, u)?,
+        Node::Float(f) => simd_json::to_writer(&mut *out, f)?,
+        Node::Str(s) => simd_json::to_writer(&mut *out, s.as_str())?,
+        Node::List(items) => {
+            out.push(b'[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write(item, out)?;
+            }
+            out.push(b']');
+        }
+        Node::Map(entries) => {
+            out.push(b'{');
+            for (i, (key, value)) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                out.extend_from_slice(key);
+                out.push(b':');
+                write(value, out)?;
+            }
+            out.push(b'}');
+        }
+        Node::Other(value) => simd_json::to_writer(&mut *out, value)?,
+    }
+    Ok(())
+}
+
+/// Entries of `obj` and of the containers directly inside it, counted up
+/// to `limit`; a cheap guess at how long serializing it will take
+pub fn estimated_entries(obj: &Bound<'_, PyAny>, limit: usize) -> usize {
+    fn len(obj: &Bound<'_, PyAny>) -> usize {
+        if let Ok(dict) = obj.cast::<PyDict>() {
+            dict.len()
+        } else if let Ok(list) = obj.cast::<PyList>() {
+            list.len()
+        } else {
+            0
+        }
+    }
+    let mut total = len(obj);
+    if let Ok(dict) = obj.cast::<PyDict>() {
+        for value in dict.values().iter() {
+            if total >= limit {
+                break;
+            }
+            total += len(&value);
+        }
+    } else if let Ok(list) = obj.cast::<PyList>() {
+        for item in list.iter() {
+            if total >= limit {
+                break;
+            }
+            total += len(&item);
+        }
+    }
+    total
+}
+
+/// Convert `obj` with the GIL held, then write it with the GIL released
+pub fn serialize_detached(obj: &Bound<'_, PyAny>) -> PyResult<Vec<u8>> {
+    let tree = convert(obj)?;
+    obj.py().detach(move || {
+        BUFFER.with(|buffer| {
+            let mut buffer = buffer.borrow_mut();
+            buffer.clear();
+            write(&tree, &mut buffer).map_err(serialization_error)?;
+            let body = buffer.clone();
+            if buffer.capacity() > MAX_POOLED_CAPACITY {
+                *buffer = Vec::new();
+            }
+            Ok(body)
+        })
+    })
+}
+
+/// Serialize `obj` to JSON bytes, releasing the GIL for large objects
+pub fn serialize(obj: &Bound<'_, PyAny>) -> PyResult<Vec<u8>> {
+    if estimated_entries(obj, DETACH_MIN_ENTRIES) >= DETACH_MIN_ENTRIES {
+        serialize_detached(obj)
+    } else {
+        serialize_py_to_json(obj)
+    }
+}
+
+/// Serialize `obj` to JSON bytes the way `res.json()` does.
+///
+/// `detach=None` picks by size: objects with at least 512 entries in their
+/// top two levels are converted under the GIL and written with it
+/// released, smaller ones entirely under the GIL. `True` / `False` force one
+/// path. Dicts keep insertion order on the released path.
+///
+/// Example (Python):
+///     json_dumps({"id": 1, "tags": ["a"]})  # b'{"id":1,"tags":["a"]}'
+#[pyfunction]
+#[pyo3(signature = (obj, detach=None))]
+pub fn json_dumps<'py>(
+    py: Python<'py>,
+    obj: &Bound<'py, PyAny>,
+    detach: Option<bool>,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let body = match detach {
+        None => serialize(obj)?,
+        Some(true) => serialize_detached(obj)?,
+        Some(false) => serialize_py_to_json(obj)?,
+    };
+    Ok(PyBytes::new(py, &body))
+}
+
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(json_dumps, m)?)?;
+    Ok(())
+}
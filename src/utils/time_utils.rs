@@ -122,8 +122,72 @@ pub fn sec_to_ms(sec: i64) -> i64 {
     sec * 1000
 }
 
+// ──────────────────────── durations / sizes ─────────────────────────────── //
+
+/// Parse a duration string to seconds.
+///
+/// A bare number is seconds; suffixes ``ms``, ``s``, ``m``, ``h`` and ``d``
+/// are accepted.
+///
+/// Example (Python):
+///     parse_duration("500ms")  # 0.5
+///     parse_duration("2m")     # 120.0
+#[pyfunction]
+pub fn parse_duration(s: &str) -> PyResult<f64> {
+    let (number, unit) = split_unit(s);
+    let scale = match unit.to_ascii_lowercase().as_str() {
+        "ms" => 0.001,
+        "" | "s" => 1.0,
+        "m" => 60.0,
+        "h" => 3600.0,
+        "d" => 86400.0,
+        _ => return Err(invalid("duration", s)),
+    };
+    match number.parse::<f64>() {
+        Ok(n) if n.is_finite() && n >= 0.0 => Ok(n * scale),
+        _ => Err(invalid("duration", s)),
+    }
+}
+
+/// Parse a size string to bytes.
+///
+/// A bare number is bytes; ``KB``/``MB``/``GB`` (and ``KiB``/``MiB``/``GiB``)
+/// are powers of 1024.
+///
+/// Example (Python):
+///     parse_size("2MB")    # 2097152
+///     parse_size("512kb")  # 524288
+#[pyfunction]
+pub fn parse_size(s: &str) -> PyResult<usize> {
+    let (number, unit) = split_unit(s);
+    let scale: f64 = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1.0,
+        "k" | "kb" | "kib" => 1024.0,
+        "m" | "mb" | "mib" => 1024.0 * 1024.0,
+        "g" | "gb" | "gib" => 1024.0 * 1024.0 * 1024.0,
+        _ => return Err(invalid("size", s)),
+    };
+    match number.parse::<f64>() {
+        Ok(n) if n.is_finite() && n >= 0.0 => Ok((n * scale) as usize),
+        _ => Err(invalid("size", s)),
+    }
+}
+
 // ───────────────────────── internal helpers ──────────────────────────────── //
 
+/// Split "2.5MB" into ("2.5", "MB")
+fn split_unit(s: &str) -> (&str, &str) {
+    let s = s.trim();
+    let end = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    (&s[..end], s[end..].trim())
+}
+
+fn invalid(kind: &str, s: &str) -> PyErr {
+    pyo3::exceptions::PyValueError::new_err(format!("Invalid {}: '{}'", kind, s))
+}
+
 fn format_relative(diff_secs: i64) -> String {
     let abs = diff_secs.unsigned_abs();
 
@@ -191,5 +255,7 @@ pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(elapsed_ms, m)?)?;
     m.add_function(wrap_pyfunction!(ms_to_sec, m)?)?;
     m.add_function(wrap_pyfunction!(sec_to_ms, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_duration, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_size, m)?)?;
     Ok(())
 }
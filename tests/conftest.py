@@ -3,6 +3,7 @@ Pytest configuration and fixtures for Hypern framework tests.
 
 This module provides:
 - Test server management via subprocess
+- Servers of a test's own on free ports (``server_process``)
 - HTTP client fixtures
 - Helper functions for assertions
 """
@@ -12,15 +13,20 @@ import sys
 import time
 import subprocess
 import socket
+import tempfile
 import httpx
 import pytest
-from typing import Optional, Dict, Any
+from contextlib import contextmanager
+from typing import Optional, Dict, Any, Iterator
 
 
 # ============================================================================
 # Test Configuration
 # ============================================================================
 
+ROOT = os.path.dirname(os.path.dirname(os.path.abspath(__file__)))
+SERVER_SCRIPT = os.path.join(ROOT, "tests", "test_server.py")
+
 TEST_HOST = "127.0.0.1"
 TEST_PORT = 8765
 TEST_BASE_URL = f"http://{TEST_HOST}:{TEST_PORT}"
@@ -121,6 +127,100 @@ class TestServerProcess:
         return self.process is not None and self.process.poll() is None
 
 
+def free_port() -> int:
+    """A free port on the test host, for a server a test starts itself."""
+    with socket.socket(socket.AF_INET, socket.SOCK_STREAM) as s:
+        s.bind((TEST_HOST, 0))
+        return s.getsockname()[1]
+
+
+class ServerProcess:
+    """A server on a port of its own, started by ``server_process``."""
+
+    def __init__(self, args: list, port: int, scheme: str = "http"):
+        self.port = port
+        self.base_url = f"{scheme}://{TEST_HOST}:{port}"
+        self._output = tempfile.TemporaryFile()
+        self.process = subprocess.Popen(
+            args, stdout=self._output, stderr=subprocess.STDOUT, cwd=ROOT
+        )
+
+    @property
+    def pid(self) -> int:
+        return self.process.pid
+
+    def logs(self) -> str:
+        """Everything the server has written to stdout and stderr."""
+        self._output.seek(0)
+        return self._output.read().decode("utf-8", errors="replace")
+
+    def wait_ready(
+        self,
+        path: Optional[str] = "/health",
+        status: Optional[int] = None,
+        timeout: float = SERVER_STARTUP_TIMEOUT,
+    ) -> bool:
+        """Wait until ``path`` answers (with ``status``, when given), or until
+        the port accepts connections when ``path`` is None."""
+        deadline = time.time() + timeout
+        while time.time() < deadline:
+            if path is None:
+                with socket.socket(socket.AF_INET, socket.SOCK_STREAM) as s:
+                    if s.connect_ex((TEST_HOST, self.port)) == 0:
+                        return True
+            else:
+                try:
+                    response = httpx.get(f"{self.base_url}{path}", timeout=1.0)
+                    if status is None or response.status_code == status:
+                        return True
+                except httpx.TransportError:
+                    pass
+            time.sleep(0.1)
+        return False
+
+    def stop(self, timeout: float = 15.0) -> None:
+        """Terminate the server and wait for it; its logs stay readable."""
+        if self.process.poll() is None:
+            self.process.terminate()
+        try:
+            self.process.wait(timeout=timeout)
+        except subprocess.TimeoutExpired:
+            self.process.kill()
+            self.process.wait()
+
+
+@contextmanager
+def server_process(
+    *args: str,
+    app_script: Optional[str] = None,
+    ready_path: Optional[str] = "/health",
+    ready_status: Optional[int] = None,
+    wait: bool = True,
+    scheme: str = "http",
+) -> Iterator[ServerProcess]:
+    """Run a server on a free port for the duration of the block.
+
+    Without ``app_script`` this is test_server.py, with ``args`` after its
+    ``--port``. With it, the script runs via ``python -c`` and gets the
+    repository root, the port and ``args`` as ``sys.argv[1:]``. Unless
+    ``wait`` is False the block starts once ``ready_path`` answers (see
+    ``ServerProcess.wait_ready``); the server is stopped when it ends.
+    """
+    port = free_port()
+    if app_script is None:
+        command = [sys.executable, SERVER_SCRIPT, "--host", TEST_HOST, "--port", str(port), *args]
+    else:
+        command = [sys.executable, "-c", app_script, ROOT, str(port), *args]
+    server = ServerProcess(command, port, scheme)
+    try:
+        if wait:
+            server.wait_ready(ready_path, ready_status)
+        yield server
+    finally:
+        server.stop()
+        server._output.close()
+
+
 # Global server instance
 _server: Optional[TestServerProcess] = None
 
@@ -0,0 +1,133 @@
+"""
+Test cases for access log format strings.
+
+Tests cover:
+- The combined preset rendering the exact Apache line for a known request
+- Custom templates with missing values rendered as '-'
+- Preset expansion, literal braces and rejection of unknown placeholders
+"""
+
+import re
+import time
+from contextlib import contextmanager
+
+import httpx
+import pytest
+
+from hypern._hypern import LogConfig
+
+from tests.conftest import server_process
+
+
+# argv[3] is the log format
+APP_SCRIPT = """
+import sys
+sys.path.insert(0, sys.argv[1])
+from hypern import Hypern
+
+app = Hypern()
+app.setup_logging(log_request=False, format=sys.argv[3])
+
+@app.get("/items/42")
+def item(req, res, ctx):
+    res.header("X-Request-ID", "req-42")
+    res.text("hello world")
+
+app.start(host="127.0.0.1", port=int(sys.argv[2]), num_processes=1)
+"""
+
+CLF_TIME = r"\d{2}/[A-Z][a-z]{2}/\d{4}:\d{2}:\d{2}:\d{2} \+0000"
+
+
+@contextmanager
+def logging_server(format: str):
+    with server_process(format, app_script=APP_SCRIPT, ready_path="/_health/live") as server:
+        yield server.base_url, server.logs
+
+
+def wait_for_line(logs, marker: str, timeout: float = 5.0) -> str:
+    deadline = time.time() + timeout
+    while time.time() < deadline:
+        for line in logs().splitlines():
+            if marker in line:
+                return line
+        time.sleep(0.1)
+    raise AssertionError(f"no log line containing {marker!r} in:\n{logs()}")
+
+
+class TestCombined:
+    """Test the combined preset against a known request."""
+
+    def test_exact_line(self):
+        with logging_server("combined") as (base_url, logs):
+            response = httpx.get(
+                f"{base_url}/items/42",
+                headers={"User-Agent": "probe/1.0", "Referer": "https://example.com/list"},
+            )
+            assert response.text == "hello world"
+            line = wait_for_line(logs, "/items/42")
+            match = re.search(r"\[(" + CLF_TIME + r")\]", line)
+            assert match, line
+            expected = (
+                f'127.0.0.1 - - [{match.group(1)}] "GET /items/42 HTTP/1.1" 200 11 '
+                '"https://example.com/list" "probe/1.0"'
+            )
+            assert line == expected
+
+    def test_missing_referer(self):
+        with logging_server("combined") as (base_url, logs):
+            httpx.get(f"{base_url}/items/42", headers={"User-Agent": "probe/1.0"})
+            line = wait_for_line(logs, "/items/42")
+            assert line.endswith('200 11 "-" "probe/1.0"')
+
+
+class TestCustom:
+    """Test a custom template."""
+
+    def test_fields(self):
+        template = "{method} {path} {status} {bytes_out} {host} {request_id} {referer} {{x}}"
+        with logging_server(template) as (base_url, logs):
+            httpx.get(f"{base_url}/items/42")
+            line = wait_for_line(logs, "/items/42")
+            host = base_url.removeprefix("http://")
+            assert line == f"GET /items/42 200 11 {host} req-42 - {{x}}"
+
+    def test_duration(self):
+        with logging_server("{path} {duration_ms}") as (base_url, logs):
+            httpx.get(f"{base_url}/items/42")
+            line = wait_for_line(logs, "/items/42")
+            assert re.fullmatch(r"/items/42 \d+\.\d{3}", line), line
+
+    def test_quotes_escaped(self):
+        with logging_server('"{user_agent}"') as (base_url, logs):
+            httpx.get(f"{base_url}/items/42", headers={"User-Agent": 'evil" agent'})
+            line = wait_for_line(logs, "evil")
+            assert line == '"evil\\" agent"'
+
+
+class TestConfig:
+    """Test LogConfig format handling."""
+
+    def test_default_is_colored(self):
+        assert LogConfig().format is None
+
+    def test_preset_expanded(self):
+        config = LogConfig(format="combined")
+        assert config.format.startswith("{client_ip} - - [{time}]")
+        assert LogConfig.combined().format == config.format
+        assert "{duration_ms}" in LogConfig.short().format
+
+    def test_custom_kept(self):
+        assert LogConfig(format="{method} {{literal}}").format == "{method} {{literal}}"
+
+    def test_unknown_placeholder(self):
+        with pytest.raises(ValueError, match="unknown placeholder '{latency}'"):
+            LogConfig(format="{method} {latency}")
+
+    def test_unclosed_placeholder(self):
+        with pytest.raises(ValueError, match="unclosed"):
+            LogConfig(format="{method")
+
+    def test_stray_brace(self):
+        with pytest.raises(ValueError, match="single '}'"):
+            LogConfig(format="{method} }")
@@ -0,0 +1,103 @@
+"""
+Test cases for Host header validation (allowed_hosts).
+
+Tests cover:
+- Exact hostnames and rejection of unknown hosts
+- ``*.domain`` wildcards matching subdomains only
+- Port suffixes stripped before comparison
+- IPv6 literal hosts
+- Missing Host on HTTP/1.1
+- Health probe paths exempt from the check
+- Server constructor validation and stats()
+"""
+
+import socket
+from urllib.parse import urlparse
+
+import httpx
+import pytest
+
+from hypern._hypern import Server
+
+
+def get_with_host(client: httpx.Client, host: str, path: str = "/health") -> httpx.Response:
+    return client.get(path, headers={"Host": host})
+
+
+class TestExactHosts:
+    """Test exact hostname matching."""
+
+    def test_allowed_host(self, client: httpx.Client):
+        assert get_with_host(client, "hypern.test").status_code == 200
+
+    def test_case_insensitive(self, client: httpx.Client):
+        assert get_with_host(client, "HYPERN.Test").status_code == 200
+
+    def test_unknown_host_rejected(self, client: httpx.Client):
+        response = get_with_host(client, "evil.com")
+        assert response.status_code == 400
+        assert response.text == "Invalid Host header"
+
+    def test_port_stripped(self, client: httpx.Client):
+        assert get_with_host(client, "hypern.test:9999").status_code == 200
+        assert get_with_host(client, "evil.com:8765").status_code == 400
+
+
+class TestWildcardHosts:
+    """Test ``*.example.com`` patterns."""
+
+    @pytest.mark.parametrize("host", ["api.example.com", "a.b.example.com", "x.example.com:443"])
+    def test_subdomains_allowed(self, client: httpx.Client, host):
+        assert get_with_host(client, host).status_code == 200
+
+    @pytest.mark.parametrize("host", ["example.com", "badexample.com", "example.com.evil.org"])
+    def test_non_subdomains_rejected(self, client: httpx.Client, host):
+        assert get_with_host(client, host).status_code == 400
+
+
+class TestIpv6Hosts:
+    """Test bracketed IPv6 literal hosts."""
+
+    def test_allowed_ipv6(self, client: httpx.Client):
+        assert get_with_host(client, "[::1]").status_code == 200
+        assert get_with_host(client, "[::1]:8765").status_code == 200
+
+    def test_other_ipv6_rejected(self, client: httpx.Client):
+        assert get_with_host(client, "[::2]:8765").status_code == 400
+
+
+class TestMissingHost:
+    """Test requests without a Host header."""
+
+    def test_missing_host_on_http11(self, base_url):
+        url = urlparse(base_url)
+        with socket.create_connection((url.hostname, url.port), timeout=5) as sock:
+            sock.sendall(b"GET /health HTTP/1.1\r\nConnection: close\r\n\r\n")
+            status_line = sock.recv(1024).split(b"\r\n", 1)[0]
+        assert b" 400 " in status_line
+
+
+class TestProbeExemption:
+    """Test that health probes answer any host."""
+
+    def test_probe_with_pod_ip(self, client: httpx.Client):
+        assert get_with_host(client, "10.1.2.3:8765", "/_health/live").status_code == 200
+
+    def test_app_route_with_pod_ip_rejected(self, client: httpx.Client):
+        assert get_with_host(client, "10.1.2.3:8765").status_code == 400
+
+
+class TestServerConfig:
+    """Test the allowed_hosts constructor argument."""
+
+    def test_stats_expose_allowed_hosts(self):
+        hosts = ["example.com", "*.example.com", "[::1]"]
+        assert Server(allowed_hosts=hosts).stats()["allowed_hosts"] == hosts
+
+    def test_disabled_by_default(self):
+        assert Server().stats()["allowed_hosts"] is None
+
+    @pytest.mark.parametrize("pattern", ["", "*.", "api.*.com", "exa*mple.com"])
+    def test_invalid_pattern_rejected(self, pattern):
+        with pytest.raises(ValueError):
+            Server(allowed_hosts=[pattern])
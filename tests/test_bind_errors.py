@@ -0,0 +1,134 @@
+"""
+Test cases for listener bind failures.
+
+Tests cover:
+- HypernBindError raised from start() when the port is already in use
+- errno, host and port carried on the exception
+- The same error with several workers and with reuse_port
+- Permission denied for privileged ports when not running as root
+- Invalid host addresses
+"""
+
+import errno
+import json
+import os
+import socket
+import subprocess
+import sys
+import time
+
+import pytest
+
+
+ROOT = os.path.dirname(os.path.dirname(os.path.abspath(__file__)))
+
+# Starts an app and prints the bind error as JSON
+BIND_SCRIPT = """
+import json, sys
+sys.path.insert(0, sys.argv[5])
+from hypern import Hypern, HypernBindError
+
+app = Hypern()
+
+@app.get("/")
+def index(req, res, ctx):
+    res.text("ok")
+
+try:
+    app.start(
+        host=sys.argv[1],
+        port=int(sys.argv[2]),
+        num_processes=int(sys.argv[3]),
+        reuse_port=sys.argv[4] == "1",
+    )
+except HypernBindError as exc:
+    print(json.dumps({
+        "type": type(exc).__name__,
+        "is_oserror": isinstance(exc, OSError),
+        "errno": exc.errno,
+        "strerror": exc.strerror,
+        "host": exc.host,
+        "port": exc.port,
+    }))
+    sys.exit(3)
+except ValueError as exc:
+    print(json.dumps({"type": type(exc).__name__, "message": str(exc)}))
+    sys.exit(4)
+"""
+
+
+def start_app(host: str, port: int, processes: int = 1, reuse_port: bool = False):
+    """Run the app in a subprocess; returns (exit code, reported error, seconds taken)."""
+    started = time.time()
+    result = subprocess.run(
+        [
+            sys.executable,
+            "-c",
+            BIND_SCRIPT,
+            host,
+            str(port),
+            str(processes),
+            "1" if reuse_port else "0",
+            ROOT,
+        ],
+        capture_output=True,
+        text=True,
+        timeout=60,
+    )
+    elapsed = time.time() - started
+    lines = [line for line in result.stdout.splitlines() if line.startswith("{")]
+    return result.returncode, json.loads(lines[-1]) if lines else None, elapsed
+
+
+@pytest.fixture
+def busy_port():
+    """A port held by a listening socket for the duration of the test."""
+    with socket.socket() as listener:
+        listener.bind(("127.0.0.1", 0))
+        listener.listen(1)
+        yield listener.getsockname()[1]
+
+
+class TestAddressInUse:
+    """Test starting on a port another socket is listening on."""
+
+    def test_exception(self, busy_port: int):
+        code, error, _ = start_app("127.0.0.1", busy_port)
+        assert code == 3
+        assert error["type"] == "HypernBindError"
+        assert error["is_oserror"]
+        assert error["errno"] == errno.EADDRINUSE
+        assert error["strerror"].startswith(f"Address already in use: 127.0.0.1:{busy_port}")
+        assert (error["host"], error["port"]) == ("127.0.0.1", busy_port)
+
+    def test_multiple_workers(self, busy_port: int):
+        code, error, elapsed = start_app("127.0.0.1", busy_port, processes=4)
+        assert code == 3
+        assert error["errno"] == errno.EADDRINUSE
+        assert elapsed < 15
+
+    def test_reuse_port(self, busy_port: int):
+        code, error, _ = start_app("127.0.0.1", busy_port, processes=2, reuse_port=True)
+        assert code == 3
+        assert error["errno"] == errno.EADDRINUSE
+
+
+class TestPermissionDenied:
+    """Test binding a privileged port."""
+
+    @pytest.mark.skipif(os.name != "posix" or os.geteuid() == 0, reason="needs a non-root user")
+    def test_privileged_port(self):
+        code, error, _ = start_app("127.0.0.1", 1)
+        assert code == 3
+        assert error["errno"] == errno.EACCES
+        assert error["strerror"].startswith("Permission denied for port 1 on 127.0.0.1")
+        assert error["port"] == 1
+
+
+class TestInvalidHost:
+    """Test hosts that are not IP addresses."""
+
+    def test_value_error(self):
+        code, error, _ = start_app("not-an-ip", 0)
+        assert code == 4
+        assert error["message"] == "Invalid host address: not-an-ip"
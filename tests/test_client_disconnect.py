@@ -0,0 +1,178 @@
+"""
+Test cases for client disconnects.
+
+Tests cover:
+- is_disconnected() polled by a streaming generator and a sync handler
+- wait_disconnected() resolving in an async handler
+- Async handlers cancelled at their next await
+- The abort counters and the in-flight count after a disconnect
+- The aborted outcome logged with status 499
+"""
+
+import asyncio
+import os
+import subprocess
+import sys
+import time
+
+import pytest
+
+from hypern import Hypern, disconnect_stats
+
+
+ROOT = os.path.dirname(os.path.dirname(os.path.abspath(__file__)))
+
+# The client timeout drops the request like a client closing the connection
+CLIENT_TIMEOUT = 0.2
+
+LOG_SCRIPT = """
+import sys
+import time
+sys.path.insert(0, sys.argv[1])
+from hypern import Hypern
+
+app = Hypern()
+
+@app.get("/hang")
+def hang(req, res, ctx):
+    while not req.is_disconnected():
+        time.sleep(0.01)
+
+@app.get("/feed")
+def feed(req, res, ctx):
+    def body():
+        while not req.is_disconnected():
+            yield "tick;"
+            time.sleep(0.01)
+    return body()
+
+client = app.test_client(timeout=0.2)
+for path in ("/hang", "/feed"):
+    try:
+        client.get(path)
+    except TimeoutError:
+        pass
+time.sleep(0.5)
+"""
+
+
+events = []
+
+
+def build_app() -> Hypern:
+    app = Hypern()
+
+    @app.get("/ok")
+    def ok(req, res, ctx):
+        res.json({"disconnected": req.is_disconnected()})
+
+    @app.get("/stream")
+    def stream(req, res, ctx):
+        def body():
+            sent = 0
+            try:
+                while not req.is_disconnected():
+                    yield f"{sent};"
+                    sent += 1
+                    time.sleep(0.01)
+            finally:
+                events.append(("stream", req.is_disconnected()))
+        return body()
+
+    @app.get("/poll")
+    def poll(req, res, ctx):
+        while not req.is_disconnected():
+            time.sleep(0.01)
+        events.append(("poll", True))
+
+    @app.get("/wait")
+    async def wait(req, res, ctx):
+        await req.wait_disconnected()
+        events.append(("wait", req.is_disconnected()))
+
+    @app.get("/spin")
+    async def spin(req, res, ctx):
+        try:
+            while True:
+                await asyncio.sleep(0)
+        except asyncio.CancelledError:
+            events.append(("cancelled", True))
+            raise
+
+    return app
+
+
+@pytest.fixture(scope="module")
+def client():
+    return build_app().test_client(timeout=CLIENT_TIMEOUT)
+
+
+def abandon(client, path: str) -> None:
+    with pytest.raises(TimeoutError):
+        client.get(path)
+
+
+def wait_for_event(name: str, timeout: float = 2.0):
+    deadline = time.time() + timeout
+    while time.time() < deadline:
+        for event in events:
+            if event[0] == name:
+                return event
+        time.sleep(0.02)
+    raise AssertionError(f"no {name!r} event in {events}")
+
+
+class TestDisconnectFlag:
+    """Test handlers noticing the client leave."""
+
+    def test_not_disconnected_while_connected(self, client):
+        assert client.get("/ok").json() == {"disconnected": False}
+
+    def test_streaming_generator_stops(self, client):
+        before = disconnect_stats()["aborted_streams"]
+        started = time.time()
+        abandon(client, "/stream")
+        assert wait_for_event("stream") == ("stream", True)
+        assert time.time() - started < 1.0
+        assert disconnect_stats()["aborted_streams"] == before + 1
+
+    def test_sync_handler_polls(self, client):
+        before = disconnect_stats()["aborted"]
+        abandon(client, "/poll")
+        assert wait_for_event("poll") == ("poll", True)
+        assert disconnect_stats()["aborted"] == before + 1
+
+    def test_wait_disconnected(self, client):
+        abandon(client, "/wait")
+        assert wait_for_event("wait") == ("wait", True)
+
+
+class TestCancellation:
+    """Test async handlers cancelled on disconnect."""
+
+    def test_cancelled_at_await(self, client):
+        abandon(client, "/spin")
+        assert wait_for_event("cancelled") == ("cancelled", True)
+
+    def test_in_flight_released(self, client):
+        abandon(client, "/poll")
+        assert client.get("/_health/live").json()["in_flight"] == 0
+
+
+class TestAccessLog:
+    """Test the aborted outcome in the access log."""
+
+    def test_logged_as_499(self):
+        result = subprocess.run(
+            [sys.executable, "-c", LOG_SCRIPT, ROOT],
+            capture_output=True,
+            text=True,
+            timeout=30,
+        )
+        output = result.stdout + result.stderr
+        lines = [line for line in output.splitlines() if "<--" in line]
+        assert any("GET /hang" in line and "499" in line for line in lines), output
+        # The stream's headers were sent with 200 before the client left
+        feed = [line for line in lines if "GET /feed" in line]
+        assert len(feed) == 2, output
+        assert "200" in feed[0] and "499" in feed[1]
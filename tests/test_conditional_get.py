@@ -0,0 +1,216 @@
+"""
+Test cases for conditional GETs on dynamic routes.
+
+Tests cover:
+- etag_provider routes answering 304 before the handler runs
+- Mismatched or missing If-None-Match reaching the handler
+- Provider tokens tagging successful responses
+- Callable providers and tokens changed at runtime
+- Response.with_etag answering 304 after the handler
+- req.if_none_match() parsing
+- Weak comparison, tag lists and "*" per RFC 9110
+"""
+
+import uuid
+
+import pytest
+
+from hypern import Hypern
+from hypern._hypern import Route, current_etag, set_etag_provider
+
+
+def provider_name() -> str:
+    """Providers live for the whole process; each test uses its own"""
+    return f"etag_{uuid.uuid4().hex[:8]}"
+
+
+def build_app(provider: str, calls: list) -> Hypern:
+    app = Hypern()
+
+    @app.get("/dashboard", etag_provider=provider)
+    def dashboard(req, res, ctx):
+        calls.append("dashboard")
+        res.json({"total": 42})
+
+    @app.post("/dashboard", etag_provider=provider)
+    def refresh(req, res, ctx):
+        calls.append("refresh")
+        res.json({"ok": True})
+
+    @app.get("/report")
+    def report(req, res, ctx):
+        calls.append("report")
+        res.json({"rows": 3}).with_etag(req.query("tag") or "r1")
+
+    @app.get("/created")
+    def created(req, res, ctx):
+        res.json({"rows": 3}).with_etag("c1", status_if_match=412)
+
+    @app.get("/tags")
+    def tags(req, res, ctx):
+        res.json(req.if_none_match())
+
+    return app
+
+
+class TestProvider:
+    """Test routes registered with etag_provider."""
+
+    def test_match_skips_handler(self):
+        name = provider_name()
+        calls = []
+        client = build_app(name, calls).test_client()
+        set_etag_provider(name, "v7")
+        response = client.get("/dashboard", headers={"If-None-Match": '"v7"'})
+        assert response.status == 304
+        assert response.headers["etag"] == '"v7"'
+        assert response.content == b""
+        assert calls == []
+
+    def test_mismatch_runs_handler(self):
+        name = provider_name()
+        calls = []
+        client = build_app(name, calls).test_client()
+        set_etag_provider(name, "v7")
+        response = client.get("/dashboard", headers={"If-None-Match": '"v6"'})
+        assert response.status == 200
+        assert response.json() == {"total": 42}
+        assert response.headers["etag"] == '"v7"'
+        assert calls == ["dashboard"]
+
+    def test_no_header_runs_handler(self):
+        name = provider_name()
+        calls = []
+        client = build_app(name, calls).test_client()
+        set_etag_provider(name, 3)
+        response = client.get("/dashboard")
+        assert response.status == 200
+        assert response.headers["etag"] == '"3"'
+        assert calls == ["dashboard"]
+
+    def test_unset_provider(self):
+        calls = []
+        client = build_app(provider_name(), calls).test_client()
+        response = client.get("/dashboard", headers={"If-None-Match": "*"})
+        assert response.status == 200
+        assert "etag" not in response.headers
+        assert calls == ["dashboard"]
+
+    def test_only_get_and_head(self):
+        name = provider_name()
+        calls = []
+        client = build_app(name, calls).test_client()
+        set_etag_provider(name, "v1")
+        response = client.post("/dashboard", headers={"If-None-Match": '"v1"'})
+        assert response.status == 200
+        assert "etag" not in response.headers
+        assert calls == ["refresh"]
+
+    def test_token_changed_at_runtime(self):
+        name = provider_name()
+        calls = []
+        client = build_app(name, calls).test_client()
+        set_etag_provider(name, "v1")
+        assert client.get("/dashboard", headers={"If-None-Match": '"v1"'}).status == 304
+        set_etag_provider(name, "v2")
+        assert client.get("/dashboard", headers={"If-None-Match": '"v1"'}).status == 200
+        assert client.get("/dashboard", headers={"If-None-Match": '"v2"'}).status == 304
+        assert calls == ["dashboard"]
+
+    def test_callable(self):
+        name = provider_name()
+        calls = []
+        version = {"value": 1}
+        client = build_app(name, calls).test_client()
+        Hypern().set_etag_provider(name, lambda: version["value"])
+        assert client.get("/dashboard", headers={"If-None-Match": '"1"'}).status == 304
+        version["value"] = 2
+        assert client.get("/dashboard", headers={"If-None-Match": '"1"'}).status == 200
+        version["value"] = None
+        assert client.get("/dashboard", headers={"If-None-Match": "*"}).status == 200
+        assert calls == ["dashboard", "dashboard"]
+
+    def test_current_etag(self):
+        name = provider_name()
+        assert current_etag(name) is None
+        set_etag_provider(name, "abc")
+        assert current_etag(name) == '"abc"'
+        assert Hypern().current_etag(name) == '"abc"'
+        set_etag_provider(name, 'W/"abc"')
+        assert current_etag(name) == 'W/"abc"'
+        set_etag_provider(name, None)
+        assert current_etag(name) is None
+
+    def test_invalid_token(self):
+        with pytest.raises(TypeError, match="ETag token"):
+            set_etag_provider(provider_name(), 1.5)
+
+    def test_route_attribute(self):
+        route = Route("/x", lambda req, res, ctx: None, "GET", etag_provider="dashboard_v")
+        assert route.etag_provider == "dashboard_v"
+        assert Route("/x", lambda req, res, ctx: None, "GET").etag_provider is None
+
+
+class TestWithEtag:
+    """Test Response.with_etag."""
+
+    def test_match(self):
+        calls = []
+        client = build_app(provider_name(), calls).test_client()
+        response = client.get("/report", headers={"If-None-Match": '"r1"'})
+        assert response.status == 304
+        assert response.headers["etag"] == '"r1"'
+        assert response.content == b""
+        # The handler computes the tag, so it always runs
+        assert calls == ["report"]
+
+    def test_mismatch(self):
+        client = build_app(provider_name(), []).test_client()
+        response = client.get("/report", headers={"If-None-Match": '"r0"'})
+        assert response.status == 200
+        assert response.json() == {"rows": 3}
+        assert response.headers["etag"] == '"r1"'
+
+    def test_status_if_match(self):
+        client = build_app(provider_name(), []).test_client()
+        assert client.get("/created", headers={"If-None-Match": '"c1"'}).status == 412
+        assert client.get("/created").status == 200
+
+
+class TestComparison:
+    """Test If-None-Match matching per RFC 9110."""
+
+    @pytest.mark.parametrize(
+        "if_none_match",
+        ['"v1"', 'W/"v1"', '"a", "v1"', '"a",W/"v1" , "b"', "*"],
+    )
+    def test_matches(self, if_none_match):
+        name = provider_name()
+        client = build_app(name, []).test_client()
+        set_etag_provider(name, "v1")
+        assert client.get("/dashboard", headers={"If-None-Match": if_none_match}).status == 304
+
+    @pytest.mark.parametrize("if_none_match", ['"v2"', 'W/"v10"', '"V1"', "v1", '"a", "b"'])
+    def test_mismatches(self, if_none_match):
+        name = provider_name()
+        client = build_app(name, []).test_client()
+        set_etag_provider(name, "v1")
+        assert client.get("/dashboard", headers={"If-None-Match": if_none_match}).status == 200
+
+    def test_weak_provider_token(self):
+        name = provider_name()
+        client = build_app(name, []).test_client()
+        set_etag_provider(name, 'W/"v1"')
+        assert client.get("/dashboard", headers={"If-None-Match": '"v1"'}).status == 304
+
+    def test_weak_with_etag(self):
+        client = build_app(provider_name(), []).test_client()
+        response = client.get("/report?tag=W/%22r2%22", headers={"If-None-Match": '"r2"'})
+        assert response.status == 304
+        assert response.headers["etag"] == 'W/"r2"'
+
+    def test_if_none_match_list(self):
+        client = build_app(provider_name(), []).test_client()
+        response = client.get("/tags", headers={"If-None-Match": '"a", W/"b,c" ,*'})
+        assert response.json() == ['"a"', 'W/"b,c"', "*"]
+        assert client.get("/tags").json() == []
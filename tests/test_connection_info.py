@@ -0,0 +1,229 @@
+"""
+Test cases for connection metadata and TLS client certificates.
+
+The TLS tests generate a throwaway CA with server and client certificates and
+run extra copies of the test server with the TLS options.
+
+Tests cover:
+- scheme, peer_addr, alpn_protocol and peer_cert on plaintext connections
+- HTTPS termination with ALPN and ``req.secure``
+- Parsed client certificate fields (subject, issuer, SANs, validity, fingerprint)
+- Optional vs required client certificates
+- The hsts_preload header on TLS responses
+- Server option validation
+"""
+
+import datetime
+import hashlib
+import ipaddress
+from contextlib import contextmanager
+
+import httpx
+import pytest
+
+from hypern._hypern import Server
+
+from tests.conftest import server_process
+
+x509 = pytest.importorskip("cryptography.x509")
+from cryptography.hazmat.primitives import hashes, serialization  # noqa: E402
+from cryptography.hazmat.primitives.asymmetric import ec  # noqa: E402
+from cryptography.x509.oid import ExtendedKeyUsageOID, NameOID  # noqa: E402
+
+
+NOT_AFTER = datetime.datetime(2035, 1, 1, tzinfo=datetime.timezone.utc)
+
+
+def issue(common_name, issuer=None, issuer_key=None, sans=(), usage=None, ca=False):
+    key = ec.generate_private_key(ec.SECP256R1())
+    name = x509.Name([x509.NameAttribute(NameOID.COMMON_NAME, common_name)])
+    builder = (
+        x509.CertificateBuilder()
+        .subject_name(name)
+        .issuer_name(issuer or name)
+        .public_key(key.public_key())
+        .serial_number(x509.random_serial_number())
+        .not_valid_before(datetime.datetime(2024, 1, 1, tzinfo=datetime.timezone.utc))
+        .not_valid_after(NOT_AFTER)
+        .add_extension(x509.BasicConstraints(ca=ca, path_length=None), critical=True)
+    )
+    if sans:
+        builder = builder.add_extension(x509.SubjectAlternativeName(list(sans)), critical=False)
+    if usage:
+        builder = builder.add_extension(x509.ExtendedKeyUsage([usage]), critical=False)
+    cert = builder.sign(issuer_key or key, hashes.SHA256())
+    return cert, key
+
+
+def write_pem(path, cert, key=None):
+    with open(path, "wb") as f:
+        f.write(cert.public_bytes(serialization.Encoding.PEM))
+    if key is not None:
+        with open(path + ".key", "wb") as f:
+            f.write(key.private_bytes(
+                serialization.Encoding.PEM,
+                serialization.PrivateFormat.PKCS8,
+                serialization.NoEncryption(),
+            ))
+
+
+@pytest.fixture(scope="module")
+def pki(tmp_path_factory):
+    """CA, server and client certificate files."""
+    root = tmp_path_factory.mktemp("pki")
+    ca, ca_key = issue("Hypern Test CA", ca=True)
+    server, server_key = issue(
+        "localhost", ca.subject, ca_key,
+        sans=[x509.DNSName("localhost"), x509.IPAddress(ipaddress.ip_address("127.0.0.1"))],
+        usage=ExtendedKeyUsageOID.SERVER_AUTH,
+    )
+    client, client_key = issue(
+        "svc-client", ca.subject, ca_key,
+        sans=[x509.DNSName("client.internal"), x509.UniformResourceIdentifier("spiffe://test/client")],
+        usage=ExtendedKeyUsageOID.CLIENT_AUTH,
+    )
+    paths = {name: str(root / f"{name}.pem") for name in ("ca", "server", "client")}
+    write_pem(paths["ca"], ca)
+    write_pem(paths["server"], server, server_key)
+    write_pem(paths["client"], client, client_key)
+    paths["client_der"] = client.public_bytes(serialization.Encoding.DER)
+    return paths
+
+
+@contextmanager
+def tls_server(pki, *extra):
+    with server_process(
+        "--tls-cert", pki["server"], "--tls-key", pki["server"] + ".key",
+        "--tls-client-ca", pki["ca"], *extra,
+        wait=False,
+        scheme="https",
+    ) as server:
+        if not server.wait_ready(None):
+            pytest.fail("TLS test server did not start")
+        yield server.base_url
+
+
+@pytest.fixture(scope="module")
+def tls_url(pki):
+    with tls_server(pki) as url:
+        yield url
+
+
+@pytest.fixture(scope="module")
+def mtls_url(pki):
+    with tls_server(pki, "--tls-require-client-cert") as url:
+        yield url
+
+
+def tls_client(pki, base_url, with_cert=True) -> httpx.Client:
+    cert = (pki["client"], pki["client"] + ".key") if with_cert else None
+    return httpx.Client(base_url=base_url, verify=pki["ca"], cert=cert, timeout=10.0)
+
+
+class TestPlaintextConnection:
+    """Test connection metadata without TLS."""
+
+    def test_plain_fields(self, client: httpx.Client):
+        data = client.get("/conn/info").json()
+        assert data["scheme"] == "http"
+        assert data["secure"] is False
+        assert data["peer_addr"][0] == "127.0.0.1"
+        assert isinstance(data["peer_addr"][1], int)
+        assert data["alpn_protocol"] is None
+        assert data["peer_cert"] is None
+
+
+class TestTlsConnection:
+    """Test HTTPS termination and client certificates."""
+
+    def test_https_with_client_cert(self, pki, tls_url):
+        with tls_client(pki, tls_url) as client:
+            data = client.get("/conn/info").json()
+        assert data["scheme"] == "https"
+        assert data["secure"] is True
+        assert data["alpn_protocol"] == "http/1.1"
+
+        cert = data["peer_cert"]
+        assert cert["subject"] == "CN=svc-client"
+        assert cert["issuer"] == "CN=Hypern Test CA"
+        assert "DNS:client.internal" in cert["sans"]
+        assert "URI:spiffe://test/client" in cert["sans"]
+        assert cert["not_after"] == "2035-01-01T00:00:00Z"
+        assert cert["fingerprint"] == hashlib.sha256(pki["client_der"]).hexdigest()
+
+    def test_cert_shared_across_keepalive_requests(self, pki, tls_url):
+        with tls_client(pki, tls_url) as client:
+            first = client.get("/conn/info").json()
+            second = client.get("/conn/info").json()
+        assert first["peer_addr"] == second["peer_addr"]
+        assert first["peer_cert"] == second["peer_cert"]
+
+    def test_client_cert_optional(self, pki, tls_url):
+        with tls_client(pki, tls_url, with_cert=False) as client:
+            response = client.get("/conn/info")
+        assert response.status_code == 200
+        assert response.json()["scheme"] == "https"
+        assert response.json()["peer_cert"] is None
+
+    def test_client_cert_required(self, pki, mtls_url):
+        with tls_client(pki, mtls_url) as client:
+            assert client.get("/conn/info").json()["peer_cert"]["subject"] == "CN=svc-client"
+        with tls_client(pki, mtls_url, with_cert=False) as client:
+            with pytest.raises(httpx.TransportError):
+                client.get("/conn/info")
+
+
+@pytest.fixture(scope="module")
+def hsts_url(pki):
+    with tls_server(pki, "--hsts-preload") as url:
+        yield url
+
+
+class TestHstsPreload:
+    """Test hsts_preload on TLS connections."""
+
+    def test_header_on_tls(self, pki, hsts_url):
+        with tls_client(pki, hsts_url) as client:
+            response = client.get("/conn/info")
+        assert response.headers["strict-transport-security"] == (
+            "max-age=63072000; includeSubDomains; preload"
+        )
+
+    def test_header_on_probes(self, pki, hsts_url):
+        with tls_client(pki, hsts_url) as client:
+            response = client.get("/_health/live")
+        assert "preload" in response.headers["strict-transport-security"]
+
+    def test_absent_without_option(self, pki, tls_url):
+        with tls_client(pki, tls_url) as client:
+            assert "strict-transport-security" not in client.get("/conn/info").headers
+
+
+class TestTlsOptions:
+    """Test validation of the TLS server options."""
+
+    def test_stats(self, pki):
+        server = Server(
+            tls_cert_path=pki["server"],
+            tls_key_path=pki["server"] + ".key",
+            tls_client_ca_path=pki["ca"],
+        )
+        assert server.stats()["tls"] is True
+        assert server.stats()["tls_client_auth"] == "optional"
+        assert Server().stats()["tls"] is False
+
+    def test_cert_without_key(self, pki):
+        with pytest.raises(ValueError, match="tls_cert_path and tls_key_path"):
+            Server(tls_cert_path=pki["server"])
+
+    def test_required_without_ca(self, pki):
+        with pytest.raises(ValueError, match="tls_client_ca_path"):
+            Server(
+                tls_cert_path=pki["server"],
+                tls_key_path=pki["server"] + ".key",
+                tls_require_client_cert=True,
+            )
+
+    def test_missing_file(self, pki):
+        with pytest.raises(ValueError, match="Failed to read"):
+            Server(tls_cert_path="/nonexistent.pem", tls_key_path=pki["server"] + ".key")
@@ -0,0 +1,258 @@
+"""
+Test cases for connection limits.
+
+Tests cover:
+- Constructor validation of the limits and exempt networks
+- Limits listed in stats() and the runtime config
+- Connections over max_connections_per_ip closed while other addresses
+  still connect, and the slot freed when a connection closes
+- Exempt networks not subject to the per-IP cap
+- Clients sending headers too slowly dropped at header_read_timeout, while
+  idle kept-alive connections stay open
+- Rejections counted by reason in ServerMetrics
+- The per-IP cap lowered at runtime with update_config
+"""
+
+import json
+import os
+import socket
+import subprocess
+import sys
+import time
+
+import pytest
+
+from hypern import Hypern
+from hypern._hypern import Server, server_metrics
+
+
+ROOT = os.path.dirname(os.path.dirname(os.path.abspath(__file__)))
+
+APP_SCRIPT = """
+import sys
+sys.path.insert(0, sys.argv[1])
+from hypern import Hypern
+from hypern._hypern import server_metrics
+
+app = Hypern()
+
+@app.get("/ping")
+def ping(req, res, ctx):
+    res.json({"ok": True})
+
+@app.get("/rejected")
+def rejected(req, res, ctx):
+    res.json(server_metrics().snapshot()["connections_rejected"])
+
+@app.post("/config")
+def config(req, res, ctx):
+    res.json({"generation": app.update_config(req.json())})
+
+app.start(
+    host="127.0.0.1",
+    port=int(sys.argv[2]),
+    num_processes=1,
+    max_connections_per_ip=2,
+    header_read_timeout=1.0,
+    connection_limit_exempt=["127.0.0.3"],
+)
+"""
+
+REQUEST = b"GET /ping HTTP/1.1\r\nHost: localhost\r\n\r\n"
+
+
+@pytest.fixture(scope="module")
+def server():
+    with socket.socket() as s:
+        s.bind(("127.0.0.1", 0))
+        port = s.getsockname()[1]
+    process = subprocess.Popen([sys.executable, "-c", APP_SCRIPT, ROOT, str(port)])
+    try:
+        deadline = time.monotonic() + 20
+        while True:
+            try:
+                with connect(port) as conn:
+                    if b"200 OK" in exchange(conn):
+                        break
+            except OSError:
+                pass
+            if time.monotonic() > deadline:
+                raise RuntimeError("server did not start")
+            time.sleep(0.2)
+        yield port
+    finally:
+        process.terminate()
+        process.wait(timeout=30)
+
+
+def connect(port: int, source: str = "127.0.0.1") -> socket.socket:
+    return socket.create_connection(("127.0.0.1", port), timeout=5, source_address=(source, 0))
+
+
+def exchange(conn: socket.socket, request: bytes = REQUEST) -> bytes:
+    """Send a request and read one response, or b"" if the server closed"""
+    try:
+        conn.sendall(request)
+        data = b""
+        while b"\r\n\r\n" not in data or not data.endswith(b"}"):
+            chunk = conn.recv(4096)
+            if not chunk:
+                break
+            data += chunk
+        return data
+    except (ConnectionResetError, BrokenPipeError):
+        return b""
+
+
+def closed_by_server(conn: socket.socket) -> bool:
+    try:
+        return conn.recv(1) == b""
+    except ConnectionResetError:
+        return True
+
+
+def call(port: int, method: str, path: str, payload=None) -> dict:
+    """One request from an address no test limits, on its own connection"""
+    body = b"" if payload is None else json.dumps(payload).encode()
+    head = (
+        f"{method} {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n"
+        f"Content-Type: application/json\r\nContent-Length: {len(body)}\r\n\r\n"
+    )
+    with connect(port, "127.0.0.4") as conn:
+        response = exchange(conn, head.encode() + body)
+    return json.loads(response.split(b"\r\n\r\n", 1)[1])
+
+
+class TestOptions:
+    """Test the Server constructor options."""
+
+    def test_defaults_in_stats(self):
+        stats = Server().stats()
+        assert stats["max_connections_per_ip"] is None
+        assert stats["header_read_timeout"] == 30.0
+        assert stats["connection_limit_exempt"] == []
+
+    def test_values_in_stats(self):
+        stats = Server(
+            max_connections_per_ip=5,
+            header_read_timeout=2.5,
+            connection_limit_exempt=["10.0.0.0/8", "fd00::1"],
+        ).stats()
+        assert stats["max_connections_per_ip"] == 5
+        assert stats["header_read_timeout"] == 2.5
+        assert stats["connection_limit_exempt"] == ["10.0.0.0/8", "fd00::1/128"]
+
+    def test_invalid(self):
+        with pytest.raises(ValueError, match="max_connections_per_ip"):
+            Server(max_connections_per_ip=0)
+        with pytest.raises(ValueError, match="header_read_timeout"):
+            Server(header_read_timeout=0)
+        with pytest.raises(ValueError, match="10.0.0.0/33"):
+            Server(connection_limit_exempt=["10.0.0.0/33"])
+
+    def test_metrics_keys(self):
+        rejected = server_metrics().snapshot()["connections_rejected"]
+        assert set(rejected) == {"max_connections", "max_connections_per_ip", "header_read_timeout"}
+        assert 'hypern_connections_rejected_total{reason="header_read_timeout"}' in server_metrics().render()
+
+
+class TestRuntimeConfig:
+    """Test the limits as hot-reloadable settings."""
+
+    def test_update(self):
+        app = Hypern()
+        app.update_config({"max_connections_per_ip": 50, "header_read_timeout": 5})
+        current = app.live_config()
+        assert current["max_connections_per_ip"] == 50
+        assert current["header_read_timeout"] == 5.0
+        app.update_config({"max_connections_per_ip": None, "header_read_timeout": None})
+        assert app.live_config()["max_connections_per_ip"] is None
+
+    def test_invalid(self):
+        with pytest.raises(ValueError) as err:
+            Hypern().update_config({"max_connections": 0, "header_read_timeout": -1})
+        assert "max_connections: expected a positive integer" in str(err.value)
+        assert "header_read_timeout: expected a positive number" in str(err.value)
+
+
+class TestPerIpLimit:
+    """Test max_connections_per_ip against a running server."""
+
+    def test_over_the_cap_closed(self, server):
+        before = call(server, "GET", "/rejected")["max_connections_per_ip"]
+        first, second = connect(server), connect(server)
+        try:
+            assert b"200 OK" in exchange(first)
+            assert b"200 OK" in exchange(second)
+
+            with connect(server) as third:
+                assert closed_by_server(third)
+
+            # Another address is not affected
+            with connect(server, "127.0.0.2") as other:
+                assert b"200 OK" in exchange(other)
+        finally:
+            first.close()
+            second.close()
+
+        assert call(server, "GET", "/rejected")["max_connections_per_ip"] == before + 1
+
+    def test_slot_freed_on_close(self, server):
+        first, second = connect(server, "127.0.0.5"), connect(server, "127.0.0.5")
+        assert b"200 OK" in exchange(first)
+        assert b"200 OK" in exchange(second)
+        first.close()
+
+        deadline = time.monotonic() + 5
+        while True:
+            with connect(server, "127.0.0.5") as again:
+                if b"200 OK" in exchange(again):
+                    break
+            assert time.monotonic() < deadline
+            time.sleep(0.1)
+        second.close()
+
+    def test_exempt_network(self, server):
+        conns = [connect(server, "127.0.0.3") for _ in range(4)]
+        try:
+            for conn in conns:
+                assert b"200 OK" in exchange(conn)
+        finally:
+            for conn in conns:
+                conn.close()
+
+    def test_lowered_at_runtime(self, server):
+        call(server, "POST", "/config", {"max_connections_per_ip": 1})
+        try:
+            with connect(server, "127.0.0.7") as first:
+                assert b"200 OK" in exchange(first)
+                with connect(server, "127.0.0.7") as second:
+                    assert closed_by_server(second)
+        finally:
+            call(server, "POST", "/config", {"max_connections_per_ip": 2})
+
+
+class TestHeaderReadTimeout:
+    """Test header_read_timeout against a running server."""
+
+    def test_slow_headers_dropped(self, server):
+        before = call(server, "GET", "/rejected")["header_read_timeout"]
+        with connect(server, "127.0.0.8") as conn:
+            conn.sendall(b"GET /ping HTTP/1.1\r\n")
+            time.sleep(0.5)
+            conn.sendall(b"Host: localhost\r\n")
+            started = time.monotonic()
+            assert closed_by_server(conn)
+            assert time.monotonic() - started < 2
+
+        assert call(server, "GET", "/rejected")["header_read_timeout"] == before + 1
+
+    def test_silent_connection_dropped(self, server):
+        with connect(server, "127.0.0.8") as conn:
+            assert closed_by_server(conn)
+
+    def test_idle_keepalive_kept(self, server):
+        with connect(server, "127.0.0.9") as conn:
+            assert b"200 OK" in exchange(conn)
+            time.sleep(1.5)
+            assert b"200 OK" in exchange(conn)
@@ -0,0 +1,157 @@
+"""
+Test cases for CORS preflight handling.
+
+Each configuration gets its own app, so the shared test server keeps its
+permissive CORS.
+
+Tests cover:
+- Vary on preflight responses
+- Per-path max-age overrides
+- Private Network Access preflights
+- OPTIONS requests without Access-Control-Request-Method reaching the route
+- Wildcard allowed headers and origins with credentials
+"""
+
+import pytest
+
+from hypern import Hypern
+from hypern.middleware import CorsMiddleware
+
+
+ORIGIN = "https://app.example.com"
+
+
+def create_cors_app(cors: CorsMiddleware) -> Hypern:
+    app = Hypern()
+    app.use(cors)
+
+    @app.get("/resource")
+    def resource(req, res, ctx):
+        res.json({"cors": "enabled"})
+
+    @app.get("/short/resource")
+    def short_resource(req, res, ctx):
+        res.json({"cors": "short"})
+
+    @app.get("/short/long/resource")
+    def long_resource(req, res, ctx):
+        res.json({"cors": "long"})
+
+    @app.options("/resource")
+    def resource_options(req, res, ctx):
+        res.header("X-Route-Options", "handled")
+        res.status(204).send(None)
+
+    return app
+
+
+# Override autouse conftest fixtures that need a test server
+@pytest.fixture(autouse=True)
+def reset_database():
+    yield
+
+
+@pytest.fixture(scope="module")
+def client():
+    cors = CorsMiddleware(
+        max_age_overrides={"/short": 60, "/short/long": 600},
+        allow_private_network=True,
+    )
+    return create_cors_app(cors).test_client()
+
+
+@pytest.fixture(scope="module")
+def credentials_client():
+    cors = CorsMiddleware(allowed_headers=["*"], allow_credentials=True)
+    return create_cors_app(cors).test_client()
+
+
+def preflight(client, path: str, **headers: str):
+    request_headers = {"Origin": ORIGIN, "Access-Control-Request-Method": "POST"}
+    request_headers.update({k.replace("_", "-"): v for k, v in headers.items()})
+    return client.options(path, headers=request_headers)
+
+
+class TestPreflight:
+    """Test preflight responses."""
+
+    def test_vary(self, client):
+        response = preflight(client, "/resource")
+        assert response.status == 204
+        assert response.headers["vary"] == (
+            "Origin, Access-Control-Request-Method, Access-Control-Request-Headers"
+        )
+
+    def test_default_max_age(self, client):
+        response = preflight(client, "/resource")
+        assert response.headers["access-control-max-age"] == "86400"
+
+    def test_max_age_override(self, client):
+        response = preflight(client, "/short/resource")
+        assert response.headers["access-control-max-age"] == "60"
+
+    def test_longest_prefix_wins(self, client):
+        response = preflight(client, "/short/long/resource")
+        assert response.headers["access-control-max-age"] == "600"
+
+    def test_listed_headers_without_credentials(self, client):
+        response = preflight(client, "/resource", Access_Control_Request_Headers="X-Custom")
+        assert response.headers["access-control-allow-origin"] == "*"
+        assert response.headers["access-control-allow-headers"] == (
+            "Content-Type, Authorization, X-Requested-With"
+        )
+
+
+class TestPrivateNetwork:
+    """Test Private Network Access preflights."""
+
+    def test_allowed_when_requested(self, client):
+        response = preflight(
+            client, "/resource", Access_Control_Request_Private_Network="true"
+        )
+        assert response.status == 204
+        assert response.headers["access-control-allow-private-network"] == "true"
+
+    def test_absent_when_not_requested(self, client):
+        response = preflight(client, "/resource")
+        assert "access-control-allow-private-network" not in response.headers
+
+
+class TestNonPreflightOptions:
+    """Test OPTIONS requests that are not preflights."""
+
+    def test_reaches_route(self, client):
+        response = client.options("/resource", headers={"Origin": ORIGIN})
+        assert response.status == 204
+        assert response.headers["x-route-options"] == "handled"
+        assert response.headers["access-control-allow-origin"] == "*"
+
+    def test_preflight_answered_by_middleware(self, client):
+        response = preflight(client, "/resource")
+        assert response.status == 204
+        assert "x-route-options" not in response.headers
+        assert "access-control-allow-methods" in response.headers
+
+
+class TestCredentials:
+    """Test wildcard configuration with credentials."""
+
+    def test_wildcards_reflected(self, credentials_client):
+        response = preflight(
+            credentials_client,
+            "/resource",
+            Access_Control_Request_Headers="X-Custom, Content-Type",
+        )
+        assert response.status == 204
+        assert response.headers["access-control-allow-headers"] == "X-Custom, Content-Type"
+        assert response.headers["access-control-allow-origin"] == ORIGIN
+        assert response.headers["access-control-allow-credentials"] == "true"
+
+    def test_nothing_requested_nothing_allowed(self, credentials_client):
+        response = preflight(credentials_client, "/resource")
+        assert "access-control-allow-headers" not in response.headers
+
+    def test_simple_request(self, credentials_client):
+        response = credentials_client.get("/resource", headers={"Origin": ORIGIN})
+        assert response.headers["access-control-allow-origin"] == ORIGIN
+        assert response.headers["access-control-allow-credentials"] == "true"
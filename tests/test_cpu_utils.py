@@ -0,0 +1,96 @@
+"""
+Tests for container-aware CPU sizing and worker affinity layouts.
+
+Tests cover:
+- cgroup v2 ``cpu.max`` and v1 CFS quota parsing
+- Affinity mask computation for "auto" and explicit core sets
+- Server worker status before start
+"""
+
+import pytest
+
+from hypern._hypern import Server
+from hypern.utils import cgroup_cpu_quota, cpu_affinity_layout, cpu_count
+
+
+# Override autouse conftest fixtures that need a test server
+@pytest.fixture(autouse=True)
+def reset_database():
+    yield
+
+
+class TestCgroupQuota:
+    """Test CPU quota parsing from fixture cgroup trees."""
+
+    def test_v2_quota(self, tmp_path):
+        (tmp_path / "cpu.max").write_text("200000 100000\n")
+        assert cgroup_cpu_quota(str(tmp_path)) == pytest.approx(2.0)
+
+    def test_v2_fractional_quota(self, tmp_path):
+        (tmp_path / "cpu.max").write_text("150000 100000\n")
+        assert cgroup_cpu_quota(str(tmp_path)) == pytest.approx(1.5)
+
+    def test_v2_unlimited(self, tmp_path):
+        (tmp_path / "cpu.max").write_text("max 100000\n")
+        assert cgroup_cpu_quota(str(tmp_path)) is None
+
+    def test_v1_quota(self, tmp_path):
+        cpu_dir = tmp_path / "cpu"
+        cpu_dir.mkdir()
+        (cpu_dir / "cpu.cfs_quota_us").write_text("400000\n")
+        (cpu_dir / "cpu.cfs_period_us").write_text("100000\n")
+        assert cgroup_cpu_quota(str(tmp_path)) == pytest.approx(4.0)
+
+    def test_v1_unlimited(self, tmp_path):
+        cpu_dir = tmp_path / "cpu"
+        cpu_dir.mkdir()
+        (cpu_dir / "cpu.cfs_quota_us").write_text("-1\n")
+        (cpu_dir / "cpu.cfs_period_us").write_text("100000\n")
+        assert cgroup_cpu_quota(str(tmp_path)) is None
+
+    def test_missing_files(self, tmp_path):
+        assert cgroup_cpu_quota(str(tmp_path)) is None
+
+    def test_cpu_count_is_positive(self):
+        assert cpu_count() >= 1
+
+
+class TestAffinityLayout:
+    """Test worker-to-core mask computation."""
+
+    def test_auto_round_robin(self):
+        assert cpu_affinity_layout(3, "auto", cores=[0, 1]) == [[0], [1], [0]]
+
+    def test_auto_uses_allowed_cores(self):
+        assert cpu_affinity_layout(2, "auto", cores=[4, 6]) == [[4], [6]]
+
+    def test_explicit_sets_wrap(self):
+        layout = cpu_affinity_layout(3, [[0, 1], [2, 3]], cores=[0, 1, 2, 3])
+        assert layout == [[0, 1], [2, 3], [0, 1]]
+
+    def test_disabled(self):
+        assert cpu_affinity_layout(4) == []
+        assert cpu_affinity_layout(4, "none") == []
+
+    def test_invalid_mode(self):
+        with pytest.raises(ValueError):
+            cpu_affinity_layout(2, "fastest")
+
+    def test_empty_core_set(self):
+        with pytest.raises(ValueError):
+            cpu_affinity_layout(2, [[0], []])
+
+
+class TestServerWorkerStatus:
+    """Test Server affinity configuration."""
+
+    def test_status_before_start(self):
+        server = Server(cpu_affinity="auto")
+        status = server.worker_status()
+        assert status["num_workers"] == 0
+        assert status["pids"] == []
+        assert status["cpu_count"] >= 1
+
+    def test_invalid_affinity_type(self):
+        with pytest.raises(TypeError):
+            Server(cpu_affinity=42)
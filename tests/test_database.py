@@ -8,12 +8,24 @@ Tests cover:
 - CRUD operations with real database
 - Error handling and edge cases
 - Concurrent request handling
+- Concurrent calls on one session queuing on its connection
+- Rows constructed as classes (query_as) or returned as tuples (query_tuples)
+- Large results converted in GIL batches, and interrupted by signals
+- Bulk COPY in (binary and text) and out (tuples and CSV)
+- Streaming rows in chunks with bounded prefetch, early close and mid-stream errors
 """
 
+import asyncio
 import pytest
+import signal
 import threading
+import time
 import json
 import uuid as uuid_module
+from dataclasses import dataclass
+from datetime import datetime, timedelta
+from decimal import Decimal
+from typing import Optional
 
 # Import from hypern
 from hypern.database import Database, db, finalize_db
@@ -591,6 +603,298 @@ class TestDataTypes:
             finalize_db(request_id)
 
 
+@dataclass
+class SeriesRow:
+    id: int
+    name: str
+    created_at: datetime
+    amount: Decimal
+    note: Optional[str] = None
+
+
+class SlotsRow:
+    __slots__ = ("id", "name")
+
+    def __init__(self, id, name):
+        self.id = id
+        self.name = name
+
+
+SERIES_SQL = """
+    SELECT g AS id,
+           'user-' || g AS name,
+           timestamp '2024-01-01' + g * interval '1 minute' AS created_at,
+           (g * 1.25)::numeric(10, 2) AS amount,
+           CASE WHEN g % 2 = 0 THEN NULL ELSE 'odd' END AS note
+    FROM generate_series(1, $1::int) AS g
+"""
+
+
+class TestTypedRows:
+    """Tests for query_as and query_tuples."""
+    
+    def test_dataclass_rows(self, setup_database):
+        """Every row becomes a dataclass with correctly typed fields."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        try:
+            rows = session.query_as(SERIES_SQL, [1000], SeriesRow)
+            assert len(rows) == 1000
+            assert all(isinstance(row, SeriesRow) for row in rows)
+            assert rows[0] == SeriesRow(
+                id=1,
+                name="user-1",
+                created_at=datetime(2024, 1, 1, 0, 1),
+                amount=Decimal("1.25"),
+                note="odd",
+            )
+            last = rows[-1]
+            assert last.id == 1000
+            assert last.created_at == datetime(2024, 1, 1) + timedelta(minutes=1000)
+            assert last.amount == Decimal("1250.00")
+            assert last.note is None
+        finally:
+            finalize_db(request_id)
+    
+    def test_named_params(self, setup_database):
+        """Named params work with query_as."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        try:
+            rows = session.query_as(
+                "SELECT :id::int AS id, :name::text AS name", {"id": 7, "name": "x"}, SlotsRow
+            )
+            assert (rows[0].id, rows[0].name) == (7, "x")
+        finally:
+            finalize_db(request_id)
+    
+    def test_missing_required_field(self, setup_database):
+        """A required field without a column names the column."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        try:
+            with pytest.raises(TypeError, match="'amount'"):
+                session.query_as("SELECT 1 AS id, 'a' AS name, now()::timestamp AS created_at", None, SeriesRow)
+        finally:
+            finalize_db(request_id)
+    
+    def test_extra_column_strict(self, setup_database):
+        """Extra columns raise by default and are ignored with strict=False."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        sql = "SELECT 1 AS id, 'a' AS name, true AS extra"
+        try:
+            with pytest.raises(TypeError, match="'extra'"):
+                session.query_as(sql, None, SlotsRow)
+            rows = session.query_as(sql, None, SlotsRow, strict=False)
+            assert (rows[0].id, rows[0].name) == (1, "a")
+        finally:
+            finalize_db(request_id)
+    
+    def test_kwargs_class_gets_every_column(self, setup_database):
+        """A class taking **kwargs receives all columns."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        try:
+            rows = session.query_as("SELECT 1 AS a, 2 AS b", None, dict)
+            assert rows == [{"a": 1, "b": 2}]
+        finally:
+            finalize_db(request_id)
+    
+    def test_empty_result(self, setup_database):
+        """No rows gives an empty list."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        try:
+            assert session.query_as(SERIES_SQL, [0], SeriesRow) == []
+        finally:
+            finalize_db(request_id)
+    
+    def test_tuples(self, setup_database):
+        """query_tuples returns plain tuples in column order."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        try:
+            rows = session.query_tuples(SERIES_SQL, [3])
+            assert rows[1] == (2, "user-2", datetime(2024, 1, 1, 0, 2), Decimal("2.50"), None)
+            assert all(type(row) is tuple for row in rows)
+        finally:
+            finalize_db(request_id)
+
+
+
+class TestRowConversionBatching:
+    """Tests for converting large results in GIL batches."""
+    
+    @pytest.mark.parametrize("batch", [1000, 7, 0])
+    def test_large_result(self, setup_database, batch):
+        """Every batch size, including 0 (one hold), gives the same rows."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        try:
+            rows = session.query(SERIES_SQL, [50000], gil_batch_size=batch)
+            assert len(rows) == 50000
+            assert [row["id"] for row in rows] == list(range(1, 50001))
+            assert rows[-1]["amount"] == Decimal("62500.00")
+            tuples = session.query_tuples(SERIES_SQL, [50000], gil_batch_size=batch)
+            assert tuples[12344] == (
+                12345,
+                "user-12345",
+                datetime(2024, 1, 1) + timedelta(minutes=12345),
+                Decimal("15431.25"),
+                "odd",
+            )
+            typed = session.query_as(SERIES_SQL, [2500], SeriesRow, gil_batch_size=batch)
+            assert typed[-1].id == 2500
+        finally:
+            finalize_db(request_id)
+    
+    def test_other_threads_run(self, setup_database):
+        """A thread keeps making progress while a large result converts."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        ticks = []
+        stop = threading.Event()
+        
+        def ticker():
+            while not stop.is_set():
+                ticks.append(1)
+        
+        thread = threading.Thread(target=ticker)
+        thread.start()
+        try:
+            before = len(ticks)
+            session.query_tuples(SERIES_SQL, [200000], gil_batch_size=100)
+            assert len(ticks) > before
+        finally:
+            stop.set()
+            thread.join()
+            finalize_db(request_id)
+    
+    def test_keyboard_interrupt(self, setup_database):
+        """A signal raised during conversion aborts it promptly."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        def interrupt(signum, frame):
+            raise KeyboardInterrupt
+        
+        previous = signal.signal(signal.SIGALRM, interrupt)
+        try:
+            signal.setitimer(signal.ITIMER_REAL, 0.05)
+            with pytest.raises(KeyboardInterrupt):
+                # Far more rows than can be converted before the timer fires
+                for _ in range(50):
+                    session.query_tuples(SERIES_SQL, [200000], gil_batch_size=100)
+            # The session is still usable afterwards
+            assert session.query_tuples("SELECT 1", None) == [(1,)]
+        finally:
+            signal.setitimer(signal.ITIMER_REAL, 0)
+            signal.signal(signal.SIGALRM, previous)
+            finalize_db(request_id)
+
+
+class TestNamedParams:
+    """Tests for :name placeholders with dict params."""
+    
+    def test_same_named_param_twice(self, setup_database):
+        """A name used twice binds to a single positional parameter."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        unique_email = f"named-{uuid_module.uuid4()}@test.com"
+        
+        try:
+            session.execute(
+                "INSERT INTO test_users (name, email) VALUES (:name, :email)",
+                {"name": "NamedUser", "email": unique_email}
+            )
+            rows = session.query(
+                "SELECT name FROM test_users WHERE email = :email OR name = :email",
+                {"email": unique_email}
+            )
+            assert len(rows) == 1
+            assert rows[0]["name"] == "NamedUser"
+        finally:
+            finalize_db(request_id)
+    
+    def test_cast_is_not_a_placeholder(self, setup_database):
+        """A ::jsonb cast after a placeholder is left untouched."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        unique_email = f"named-cast-{uuid_module.uuid4()}@test.com"
+        
+        try:
+            session.execute(
+                "INSERT INTO test_users (name, email, metadata) "
+                "VALUES (:name, :email, '{\"role\": \":admin\"}'::jsonb)",
+                {"name": "CastUser", "email": unique_email}
+            )
+            user = session.query_one(
+                "SELECT metadata->>'role' AS role FROM test_users WHERE email = :email",
+                {"email": unique_email}
+            )
+            assert user["role"] == ":admin"
+        finally:
+            finalize_db(request_id)
+    
+    def test_array_slice_is_not_a_placeholder(self, setup_database):
+        """A :n slice bound inside a subscript is left untouched."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        try:
+            row = session.query_one(
+                "SELECT (ARRAY[10, 20, 30, 40])[2:n] AS part "
+                "FROM (SELECT :upper::int AS n) AS bounds",
+                {"upper": 3}
+            )
+            assert row["part"] == [20, 30]
+        finally:
+            finalize_db(request_id)
+    
+    def test_array_constructor_placeholders(self, setup_database):
+        """Placeholders inside ARRAY[...] are still bound."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        try:
+            row = session.query_one(
+                "SELECT ARRAY[:first, :second]::int[] AS pair",
+                {"first": 1, "second": 2}
+            )
+            assert row["pair"] == [1, 2]
+        finally:
+            finalize_db(request_id)
+    
+    def test_missing_key_error(self, setup_database):
+        """Missing and unexpected keys are listed in the error."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        try:
+            with pytest.raises(ValueError) as exc_info:
+                session.query(
+                    "SELECT * FROM test_users WHERE name = :name AND age = :age",
+                    {"name": "Alice", "agee": 30}
+                )
+            message = str(exc_info.value)
+            assert "missing named parameters: age" in message
+            assert "unexpected named parameters: agee" in message
+        finally:
+            finalize_db(request_id)
+    
+    def test_positional_params_unchanged(self, setup_database):
+        """Positional list params keep working."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        try:
+            rows = session.query("SELECT $1::int AS n", [7])
+            assert rows[0]["n"] == 7
+        finally:
+            finalize_db(request_id)
+
+
 class TestEdgeCases:
     """Tests for edge cases and error handling."""
     
@@ -765,6 +1069,258 @@ class TestExecuteMany:
             finalize_db(request_id)
 
 
+def copy_row(i):
+    """Row for the COPY tests: every 7th name NULL, others with a tab and newline."""
+    name = None if i % 7 == 0 else f"row\t{i}\nline \\ end"
+    score = None if i % 11 == 0 else i / 4
+    return (i, name, score)
+
+
+@pytest.fixture
+def copy_table(setup_database):
+    """Empty table for COPY tests, dropped afterwards."""
+    session = db("copy-setup")
+    try:
+        session.execute("DROP TABLE IF EXISTS test_copy")
+        session.execute(
+            "CREATE TABLE test_copy (id INTEGER PRIMARY KEY, name TEXT, score DOUBLE PRECISION)"
+        )
+    finally:
+        finalize_db("copy-setup")
+    yield "test_copy"
+    session = db("copy-cleanup")
+    try:
+        session.execute("DROP TABLE IF EXISTS test_copy")
+    finally:
+        finalize_db("copy-cleanup")
+
+
+class TestCopy:
+    """Tests for bulk COPY in and out."""
+    
+    @pytest.mark.parametrize("fmt", ["binary", "text"])
+    def test_round_trip(self, copy_table, fmt):
+        """100k rows from a generator come back intact, NULLs and escapes included."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        total = 100_000
+        
+        try:
+            copied = session.copy_in(
+                copy_table,
+                ["id", "name", "score"],
+                (copy_row(i) for i in range(total)),
+                format=fmt,
+            )
+            assert copied == total
+            
+            rows = list(session.copy_out("SELECT id, name, score FROM test_copy ORDER BY id"))
+            assert len(rows) == total
+            for i in (0, 1, 7, 11, 12345, total - 1):
+                assert rows[i] == copy_row(i)
+        finally:
+            finalize_db(request_id)
+    
+    def test_copy_out_csv(self, copy_table):
+        """CSV export yields one line per row, after an optional header."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        try:
+            session.copy_in(copy_table, ["id", "name", "score"], [(1, "a,b", 0.5), (2, None, None)])
+            lines = list(
+                session.copy_out("SELECT * FROM test_copy ORDER BY id", csv=True, header=True)
+            )
+            assert lines == ["id,name,score\n", '1,"a,b",0.5\n', "2,,\n"]
+        finally:
+            finalize_db(request_id)
+    
+    def test_copy_out_close(self, copy_table):
+        """Closing an export early frees the connection for the session."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        try:
+            session.copy_in(copy_table, ["id", "name", "score"], (copy_row(i) for i in range(5000)))
+            rows = session.copy_out("SELECT * FROM test_copy", batch_size=10)
+            next(rows)
+            rows.close()
+            assert session.query_one("SELECT count(*) AS n FROM test_copy")["n"] == 5000
+        finally:
+            finalize_db(request_id)
+    
+    def test_failing_iterator_aborts(self, copy_table):
+        """An error raised by the rows iterable aborts the copy and keeps the connection."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        def rows():
+            for i in range(20_000):
+                if i == 15_000:
+                    raise KeyError("bad row")
+                yield copy_row(i)
+        
+        try:
+            with pytest.raises(KeyError, match="bad row"):
+                session.copy_in(copy_table, ["id", "name", "score"], rows(), chunk_size=1000)
+            assert session.query_one("SELECT count(*) AS n FROM test_copy")["n"] == 0
+        finally:
+            finalize_db(request_id)
+    
+    def test_server_error_keeps_connection(self, copy_table):
+        """A rejected row surfaces the PostgreSQL error; the session stays usable."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        try:
+            with pytest.raises(RuntimeError, match="duplicate key"):
+                session.copy_in(copy_table, ["id", "name", "score"], [(1, "a", 1.0), (1, "b", 2.0)])
+            with pytest.raises(RuntimeError, match="COPY failed"):
+                session.copy_in(copy_table, ["id", "missing"], [(1, "a")])
+            assert session.query_one("SELECT 1 AS ok")["ok"] == 1
+        finally:
+            finalize_db(request_id)
+    
+    def test_row_width_checked(self, copy_table):
+        """Rows must have one value per column."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        try:
+            with pytest.raises(ValueError, match="2 values for 3 columns"):
+                session.copy_in(copy_table, ["id", "name", "score"], [(1, "a")])
+            with pytest.raises(ValueError, match="Unknown COPY format"):
+                session.copy_in(copy_table, ["id"], [(1,)], format="csv")
+        finally:
+            finalize_db(request_id)
+    
+    def test_respects_transaction(self, copy_table):
+        """Copied rows are part of the open transaction and roll back with it."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        try:
+            session.begin()
+            session.copy_in(copy_table, ["id", "name", "score"], [copy_row(i) for i in range(100)])
+            assert len(list(session.copy_out("SELECT id FROM test_copy"))) == 100
+            session.rollback()
+            assert session.query_one("SELECT count(*) AS n FROM test_copy")["n"] == 0
+        finally:
+            finalize_db(request_id)
+
+
+class TestRowStream:
+    """Tests for streaming a query's rows in chunks."""
+    
+    def test_prefetch_bounded(self, setup_database):
+        """A large result is fetched at most prefetch chunks ahead of the consumer."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        try:
+            stream = session.stream(
+                "SELECT g AS n FROM generate_series(1, $1) g", [200_000], chunk_size=500, prefetch=2
+            )
+            first = next(stream)
+            assert [row["n"] for row in first[:3]] == [1, 2, 3]
+            
+            # The fetch task waits for the consumer instead of running ahead
+            time.sleep(0.3)
+            assert stream.prefetched() == 2
+            
+            total = len(first) + sum(len(chunk) for chunk in stream)
+            assert total == 200_000
+            assert stream.chunk_count() == 400
+            assert stream.peak_prefetched() <= 2
+            assert stream.is_exhausted()
+        finally:
+            finalize_db(request_id)
+    
+    def test_early_drop_releases_connection(self, setup_database):
+        """Dropping or closing a stream part way frees the session's connection."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        try:
+            stream = session.stream("SELECT g FROM generate_series(1, 10000000) g", chunk_size=100)
+            next(stream)
+            del stream
+            assert session.query_one("SELECT 1 AS one")["one"] == 1
+            
+            stream = session.stream("SELECT g FROM generate_series(1, 10000000) g", chunk_size=100)
+            next(stream)
+            stream.close()
+            assert next(stream, None) is None
+            assert session.query_one("SELECT 2 AS two")["two"] == 2
+        finally:
+            finalize_db(request_id)
+    
+    def test_mid_stream_error(self, setup_database):
+        """An error after some rows were returned is raised by the next step."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        try:
+            stream = session.stream(
+                "SELECT 1 / (g - 5000) AS n FROM generate_series(1, 10000) g", chunk_size=100
+            )
+            received = 0
+            with pytest.raises(RuntimeError, match="division by zero"):
+                for chunk in stream:
+                    received += len(chunk)
+            assert received > 0
+            assert next(stream, None) is None
+            assert session.query_one("SELECT 1 AS one")["one"] == 1
+        finally:
+            finalize_db(request_id)
+    
+    def test_async_iteration(self, setup_database):
+        """Chunks arrive in order with async for."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        async def collect():
+            stream = session.stream(
+                "SELECT g AS n FROM generate_series(1, :count) g", {"count": 250}, chunk_size=100
+            )
+            return [[row["n"] for row in chunk] async for chunk in stream]
+        
+        try:
+            chunks = asyncio.run(collect())
+            assert [len(chunk) for chunk in chunks] == [100, 100, 50]
+            assert chunks[2][-1] == 250
+        finally:
+            finalize_db(request_id)
+    
+    def test_in_transaction(self, setup_database):
+        """Inside a transaction the stream sees its changes and leaves it open."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        try:
+            session.begin()
+            session.execute("CREATE TEMP TABLE stream_rows (x INTEGER)")
+            session.execute("INSERT INTO stream_rows SELECT generate_series(1, 10)")
+            sizes = [len(chunk) for chunk in session.stream("SELECT x FROM stream_rows", chunk_size=4)]
+            assert sizes == [4, 4, 2]
+            assert session.query_one("SELECT count(*) AS n FROM stream_rows")["n"] == 10
+            session.commit()
+        finally:
+            finalize_db(request_id)
+    
+    def test_invalid_sizes(self, setup_database):
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        try:
+            with pytest.raises(ValueError, match="chunk_size and prefetch"):
+                session.stream("SELECT 1", chunk_size=0)
+            with pytest.raises(ValueError, match="chunk_size and prefetch"):
+                session.stream("SELECT 1", prefetch=0)
+        finally:
+            finalize_db(request_id)
+
+
 class TestConcurrentRequests:
     """Tests for concurrent request handling."""
     
@@ -904,5 +1460,138 @@ class TestAutoCommit:
             finalize_db(request_id2)
 
 
+
+class TestSessionConcurrency:
+    """Tests for concurrent calls sharing one session's connection."""
+    
+    def test_concurrent_query_async(self, setup_database):
+        """Test 50 concurrent query_async calls on one session all succeed."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        async def run():
+            return await asyncio.gather(*(
+                session.query_async(
+                    "SELECT $1::int AS n, pg_backend_pid() AS pid, nextval('query_order') AS seq",
+                    [i],
+                )
+                for i in range(50)
+            ))
+        
+        try:
+            # Temporary, so only visible on this session's connection
+            session.execute("CREATE TEMPORARY SEQUENCE query_order")
+            results = asyncio.run(run())
+            rows = [result[0] for result in results]
+            assert [row["n"] for row in rows] == list(range(50))
+            # One connection, each statement run after the previous one
+            assert len({row["pid"] for row in rows}) == 1
+            assert sorted(row["seq"] for row in rows) == list(range(1, 51))
+        finally:
+            finalize_db(request_id)
+    
+    def test_concurrent_execute_async_in_transaction(self, setup_database):
+        """Test concurrent execute_async calls inside one transaction."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        tag = f"async-{uuid_module.uuid4()}"
+        
+        async def run():
+            return await asyncio.gather(*(
+                session.execute_async(
+                    "INSERT INTO test_users (name, email) VALUES ($1, $2)",
+                    [tag, f"{tag}-{i}@test.com"],
+                )
+                for i in range(20)
+            ))
+        
+        try:
+            session.begin()
+            assert asyncio.run(run()) == [1] * 20
+            session.commit()
+            count = session.query_one(
+                "SELECT COUNT(*) AS count FROM test_users WHERE name = $1", [tag]
+            )
+            assert count["count"] == 20
+        finally:
+            finalize_db(request_id)
+    
+    def test_query_one_async(self, setup_database):
+        """Test query_one_async returns a row and raises on no rows."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        async def run():
+            row = await session.query_one_async("SELECT $1::text AS value", ["x"])
+            with pytest.raises(RuntimeError):
+                await session.query_one_async("SELECT 1 WHERE false")
+            return row
+        
+        try:
+            assert asyncio.run(run()) == {"value": "x"}
+        finally:
+            finalize_db(request_id)
+    
+    def test_query_async_error(self, setup_database):
+        """Test a failing query_async raises and leaves the session usable."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        async def run():
+            with pytest.raises(RuntimeError):
+                await session.query_async("SELECT * FROM no_such_table")
+            return await session.query_async("SELECT 1 AS one")
+        
+        try:
+            assert asyncio.run(run()) == [{"one": 1}]
+        finally:
+            finalize_db(request_id)
+    
+    def test_query_async_without_event_loop(self, setup_database):
+        """Test awaiting query_async by stepping the coroutine, as handlers are run."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        
+        async def handler():
+            return await session.query_async("SELECT 2 AS two")
+        
+        try:
+            coro = handler()
+            while True:
+                try:
+                    coro.send(None)
+                except StopIteration as stop:
+                    assert stop.value == [{"two": 2}]
+                    break
+        finally:
+            finalize_db(request_id)
+    
+    def test_concurrent_sync_calls_from_threads(self, setup_database):
+        """Test blocking calls on one session from many threads queue instead of failing."""
+        request_id = f"test-{uuid_module.uuid4()}"
+        session = db(request_id)
+        errors = []
+        results = []
+        
+        def worker(index: int):
+            try:
+                for j in range(5):
+                    row = session.query_one("SELECT $1::int AS n", [index * 10 + j])
+                    results.append(row["n"])
+            except Exception as e:
+                errors.append(str(e))
+        
+        try:
+            threads = [threading.Thread(target=worker, args=(i,)) for i in range(10)]
+            for t in threads:
+                t.start()
+            for t in threads:
+                t.join()
+            assert errors == []
+            assert sorted(results) == sorted(i * 10 + j for i in range(10) for j in range(5))
+        finally:
+            finalize_db(request_id)
+
+
 if __name__ == "__main__":
     pytest.main([__file__, "-v"])
@@ -0,0 +1,126 @@
+"""
+Test cases for app- and request-scoped dependency providers.
+
+The test server provides an app-scoped ``counter`` (built by a coroutine
+factory at worker startup) and a request-scoped ``unit_of_work`` that depends
+on it and counts its teardowns.
+
+Tests cover:
+- App-scoped instances shared across requests
+- Request-scoped instances created once per request and torn down once
+- Dependencies inferred from factory parameters
+- Registration errors for missing dependencies, cycles, app -> request
+  dependencies and async request-scoped factories
+"""
+
+import time
+import uuid
+
+import httpx
+import pytest
+
+from hypern._hypern import Server
+
+
+def unique(name: str) -> str:
+    """Provider names are process-wide; keep each test's names distinct."""
+    return f"{name}_{uuid.uuid4().hex[:8]}"
+
+
+class TestAppScope:
+    """Test app-scoped providers."""
+
+    def test_shared_across_requests(self, client: httpx.Client):
+        first = client.get("/deps/counter").json()
+        second = client.get("/deps/counter").json()
+        assert second["id"] == first["id"]
+        assert second["value"] == first["value"] + 1
+
+
+class TestRequestScope:
+    """Test request-scoped providers."""
+
+    def test_cached_within_request(self, client: httpx.Client):
+        data = client.get("/deps/unit-of-work").json()
+        assert data["same_in_request"] is True
+        assert data["shares_counter"] is True
+
+    def test_fresh_per_request(self, client: httpx.Client):
+        first = client.get("/deps/unit-of-work").json()
+        second = client.get("/deps/unit-of-work").json()
+        assert second["serial"] > first["serial"]
+
+    def test_torn_down_once_per_request(self, client: httpx.Client):
+        before = client.get("/deps/teardowns").json()
+        for _ in range(3):
+            assert client.get("/deps/unit-of-work").status_code == 200
+        time.sleep(0.1)
+        after = client.get("/deps/teardowns").json()
+        assert after["created"] - before["created"] == 3
+        assert after["unit_of_work"] - before["unit_of_work"] == 3
+        assert after["unit_of_work"] == after["created"]
+
+    def test_not_created_when_unused(self, client: httpx.Client):
+        before = client.get("/deps/teardowns").json()
+        client.get("/deps/counter")
+        after = client.get("/deps/teardowns").json()
+        assert after["created"] == before["created"]
+
+
+class TestRegistration:
+    """Test dependency graph validation at registration."""
+
+    def test_missing_dependency(self):
+        with pytest.raises(ValueError, match="has not been provided"):
+            Server.provide(unique("needs"), lambda not_provided_anywhere: None)
+
+    def test_optional_parameters_are_not_dependencies(self):
+        Server.provide(unique("opt"), lambda retries=3, *args, **kwargs: retries)
+
+    def test_cycle_rejected(self):
+        a, b = unique("a"), unique("b")
+        Server.provide(a, lambda: 1)
+        ns = {}
+        exec(f"def make_b({a}): return 2", ns)
+        Server.provide(b, ns["make_b"])
+        exec(f"def make_a({b}): return 3", ns)
+        with pytest.raises(ValueError, match="Circular dependency"):
+            Server.provide(a, ns["make_a"])
+
+    def test_self_dependency_rejected(self):
+        name = unique("self")
+        Server.provide(name, lambda: 1)
+        ns = {}
+        exec(f"def make({name}): return 2", ns)
+        with pytest.raises(ValueError, match="Circular dependency"):
+            Server.provide(name, ns["make"])
+
+    def test_app_cannot_depend_on_request(self):
+        req_name, app_name = unique("req"), unique("app")
+        Server.provide(req_name, lambda: object(), scope="request")
+        ns = {}
+        exec(f"def make({req_name}): return 1", ns)
+        with pytest.raises(ValueError, match="cannot depend on request-scoped"):
+            Server.provide(app_name, ns["make"], scope="app")
+
+    def test_request_may_depend_on_app(self):
+        app_name = unique("app")
+        Server.provide(app_name, lambda: 1)
+        ns = {}
+        exec(f"def make({app_name}): return {app_name} + 1", ns)
+        Server.provide(unique("req"), ns["make"], scope="request")
+
+    def test_async_request_factory_rejected(self):
+        async def factory():
+            return 1
+
+        with pytest.raises(TypeError, match="synchronous factory"):
+            Server.provide(unique("req"), factory, scope="request")
+
+    def test_unknown_scope(self):
+        with pytest.raises(ValueError, match="Unknown dependency scope"):
+            Server.provide(unique("x"), lambda: 1, scope="session")
+
+    def test_factory_must_be_callable(self):
+        with pytest.raises(TypeError, match="must be callable"):
+            Server.provide(unique("x"), 42)
@@ -0,0 +1,89 @@
+"""
+Test cases for per-stage request timing.
+
+The test server runs with ``server_timing=True``.
+
+Tests cover:
+- Server-Timing header with route, queue, app, write and total stages
+- Stage order and stages summing to the total duration
+- Per-stage histograms from dispatch_timing_stats()
+- The server_timing option
+"""
+
+import httpx
+
+from hypern._hypern import Server
+
+STAGES = ["route", "queue", "app", "write"]
+
+
+def parse_server_timing(value: str) -> list:
+    """Return [(name, dur_ms), ...] in header order."""
+    entries = []
+    for part in value.split(","):
+        name, _, dur = part.strip().partition(";dur=")
+        entries.append((name, float(dur)))
+    return entries
+
+
+class TestServerTimingHeader:
+    """Test the Server-Timing response header."""
+
+    def test_stages_present_and_ordered(self, client: httpx.Client):
+        response = client.get("/health")
+        assert response.status_code == 200
+        entries = parse_server_timing(response.headers["server-timing"])
+        assert [name for name, _ in entries] == STAGES + ["total"]
+        assert all(dur >= 0 for _, dur in entries)
+
+    def test_app_stage_covers_handler(self, client: httpx.Client):
+        response = client.get("/timing/slow")
+        timings = dict(parse_server_timing(response.headers["server-timing"]))
+        assert timings["app"] >= 50
+        assert timings["app"] < timings["total"]
+
+    def test_stages_sum_to_total(self, client: httpx.Client):
+        response = client.get("/timing/slow")
+        timings = dict(parse_server_timing(response.headers["server-timing"]))
+        stage_sum = sum(timings[name] for name in STAGES)
+        # Only the few steps between stages are unaccounted for
+        assert stage_sum <= timings["total"] + 0.01
+        assert stage_sum >= timings["total"] * 0.9
+
+    def test_absent_without_handler(self, client: httpx.Client):
+        response = client.get("/no/such/route")
+        assert response.status_code == 404
+        assert "server-timing" not in response.headers
+
+
+class TestDispatchTimingStats:
+    """Test the per-stage histograms."""
+
+    def test_counts_increase(self, client: httpx.Client):
+        before = client.get("/timing/stats").json()
+        client.get("/timing/slow")
+        after = client.get("/timing/stats").json()
+        assert after["server_timing"] is True
+        for stage in STAGES:
+            # The stats request itself is recorded after its response is built
+            assert after[stage]["count"] >= before[stage]["count"] + 2
+        assert after["app"]["sum_ms"] - before["app"]["sum_ms"] >= 50
+
+    def test_buckets_cumulative(self, client: httpx.Client):
+        client.get("/timing/slow")
+        stats = client.get("/timing/stats").json()
+        buckets = stats["app"]["buckets"]
+        counts = [count for _, count in buckets]
+        assert counts == sorted(counts)
+        assert buckets[-1][0] is None
+        assert buckets[-1][1] == stats["app"]["count"]
+        slow = [count for le, count in buckets if le is not None and le < 50]
+        assert slow[-1] < stats["app"]["count"]
+
+
+class TestServerTimingOption:
+    """Test the Server option."""
+
+    def test_stats(self):
+        assert Server(server_timing=True).stats()["server_timing"] is True
+        assert Server().stats()["server_timing"] is False
@@ -0,0 +1,147 @@
+"""
+Test cases for closing streaming responses during a graceful drain.
+
+Tests cover:
+- Open SSE streams counted as in-flight until they end
+- The shutdown event (with retry hint) sent when the worker drains
+- Streams closed once the grace period passes
+- New requests refused with 503 + Retry-After while draining
+- In-flight reaching zero so the drain completes early
+- Keep-alive connections closed when the drain starts: idle ones at once,
+  busy ones after their response, which carries Connection: close
+"""
+
+import os
+import signal
+import socket
+import time
+
+import httpx
+
+from tests.conftest import server_process
+
+
+STREAM_GRACE_MS = 1000
+
+
+def send_get(sock: socket.socket, *paths: str) -> None:
+    """Send GET requests on a keep-alive connection, pipelined when several"""
+    sock.sendall(b"".join(f"GET {p} HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n".encode() for p in paths))
+
+
+def read_response(sock: socket.socket):
+    """Status, headers (lowercased) and body of the next response"""
+    data = b""
+    while b"\r\n\r\n" not in data:
+        chunk = sock.recv(4096)
+        assert chunk, "connection closed before a response"
+        data += chunk
+    head, body = data.split(b"\r\n\r\n", 1)
+    status_line, *lines = head.decode().split("\r\n")
+    headers = {}
+    for line in lines:
+        name, _, value = line.partition(":")
+        headers[name.strip().lower()] = value.strip()
+    length = int(headers.get("content-length", 0))
+    while len(body) < length:
+        body += sock.recv(4096)
+    return int(status_line.split()[1]), headers, body[:length]
+
+
+def read_to_end(sock: socket.socket) -> bytes:
+    data = b""
+    while chunk := sock.recv(4096):
+        data += chunk
+    return data
+
+
+class TestStreamDrain:
+    """Test SIGUSR1 against a server holding an open SSE stream."""
+
+    def test_sse_stream_closed_by_drain(self):
+        with server_process(
+            "--drain-timeout", "5", "--stream-grace-ms", str(STREAM_GRACE_MS)
+        ) as server:
+            base_url = server.base_url
+            lines = []
+            with httpx.Client(base_url=base_url, timeout=10.0) as probe, httpx.stream(
+                "GET", f"{base_url}/sse/live/idle?close_after=30", timeout=10.0
+            ) as stream:
+                assert stream.status_code == 200
+                assert probe.get("/sse/live/drain-stats").json()["live"] >= 1
+                assert probe.get("/_health").json()["in_flight"] >= 1
+
+                os.kill(server.pid, signal.SIGUSR1)
+                drain_started = time.monotonic()
+
+                refused = None
+                for line in stream.iter_lines():
+                    lines.append(line)
+                    if line == "event: server-shutdown" and refused is None:
+                        refused = httpx.get(f"{base_url}/health", timeout=5.0)
+                stream_ended = time.monotonic() - drain_started
+
+            assert "event: server-shutdown" in lines
+            assert "retry: 2000" in lines
+            assert refused is not None
+            assert refused.status_code == 503
+            assert "retry-after" in refused.headers
+            # Closed after the grace period, long before close_after=30
+            assert stream_ended < STREAM_GRACE_MS / 1000 + 2.0
+            time.sleep(0.5)
+            server.stop()
+            assert "All in-flight requests drained" in server.logs()
+
+
+class TestKeepAliveDrain:
+    """Test SIGUSR1 against a server with open keep-alive connections."""
+
+    def test_connections_closed_by_drain(self):
+        with server_process("--drain-timeout", "10") as server:
+            base_url, port = server.base_url, server.port
+            busy = socket.create_connection(("127.0.0.1", port), timeout=10)
+            idle = socket.create_connection(("127.0.0.1", port), timeout=10)
+            pipelined = socket.create_connection(("127.0.0.1", port), timeout=10)
+            for sock in (busy, idle, pipelined):
+                send_get(sock, "/health")
+                status, headers, _ = read_response(sock)
+                assert status == 200
+                assert headers.get("connection") != "close"
+
+            send_get(busy, "/drain/slow?secs=1")
+            send_get(pipelined, "/drain/slow?secs=1", "/health")
+            time.sleep(0.3)
+            os.kill(server.pid, signal.SIGUSR1)
+            drain_started = time.monotonic()
+
+            # Idle connections are closed at once
+            assert read_to_end(idle) == b""
+
+            # The request in flight finishes and closes its connection
+            status, headers, body = read_response(busy)
+            assert status == 200
+            assert headers["connection"] == "close"
+            assert read_to_end(busy) == b""
+
+            # A request pipelined behind it is refused or left unanswered
+            status, headers, _ = read_response(pipelined)
+            assert status == 200
+            assert headers["connection"] == "close"
+            rest = read_to_end(pipelined)
+            assert rest == b"" or rest.startswith(b"HTTP/1.1 503")
+
+            # New connections are answered 503 and closed
+            fresh = socket.create_connection(("127.0.0.1", port), timeout=10)
+            send_get(fresh, "/health")
+            status, headers, _ = read_response(fresh)
+            assert status == 503
+            assert headers["connection"] == "close"
+            assert read_to_end(fresh) == b""
+
+            # Nothing is left in flight long before the 10s drain timeout
+            while httpx.get(f"{base_url}/_health", timeout=5.0).json()["in_flight"] > 0:
+                assert time.monotonic() - drain_started < 5.0
+                time.sleep(0.1)
+            assert time.monotonic() - drain_started < 5.0
+            server.stop(timeout=30)
+            assert "All in-flight requests drained" in server.logs()
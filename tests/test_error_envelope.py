@@ -0,0 +1,193 @@
+"""
+Test cases for the error envelope of framework-generated errors.
+
+Tests cover:
+- Router 404 and 405 as {"error": {"status", "code", "message"}} for clients
+  accepting JSON, plain text for curl-like and browser clients
+- error_format forcing one format, and its validation
+- Handler responses left as written
+- App error handlers, by exception class or status, winning over the
+  envelope for router misses and routes hidden by a feature flag
+"""
+
+import uuid
+
+import pytest
+
+from hypern import ErrorCode, Hypern, MethodNotAllowed, NotFound
+from hypern._hypern import Server
+
+JSON = {"Accept": "application/json"}
+CURL = {"Accept": "*/*"}
+BROWSER = {"Accept": "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8"}
+
+
+def build_app() -> Hypern:
+    app = Hypern()
+
+    @app.get("/items")
+    def items(req, res, ctx):
+        res.json([])
+
+    @app.post("/items")
+    def create_item(req, res, ctx):
+        res.status(201).json({"created": True})
+
+    @app.get("/missing-item")
+    def missing_item(req, res, ctx):
+        res.status(404).text("")
+
+    return app
+
+
+@pytest.fixture(scope="module")
+def client():
+    return build_app().test_client()
+
+
+class TestJsonClients:
+    """Test the envelope for clients accepting JSON."""
+
+    def test_not_found(self, client):
+        response = client.get("/nowhere", headers=JSON)
+        assert response.status == 404
+        assert response.headers["content-type"] == "application/json"
+        assert response.json() == {
+            "error": {"status": 404, "code": "not_found", "message": "Not Found"}
+        }
+
+    def test_method_not_allowed(self, client):
+        response = client.delete("/items", headers=JSON)
+        assert response.status == 405
+        assert response.headers["allow"] == "GET, POST"
+        assert response.json() == {
+            "error": {"status": 405, "code": "method_not_allowed", "message": "Method Not Allowed"}
+        }
+
+    def test_json_named_before_wildcard(self, client):
+        response = client.get("/nowhere", headers={"Accept": "application/json, text/plain, */*"})
+        assert response.json()["error"]["code"] == ErrorCode.NOT_FOUND
+
+    def test_limit_rejection(self):
+        client = build_app().test_client(max_uri_length=64)
+        response = client.get("/items?q=" + "a" * 100, headers=JSON)
+        assert response.status == 414
+        assert response.json()["error"]["code"] == "uri_too_long"
+
+
+class TestPlainTextClients:
+    """Test plain text for clients not asking for JSON."""
+
+    @pytest.mark.parametrize("headers", [CURL, BROWSER, {}])
+    def test_not_found(self, client, headers):
+        response = client.get("/nowhere", headers=headers)
+        assert response.status == 404
+        assert response.headers["content-type"] == "text/plain; charset=utf-8"
+        assert response.text == "Not Found"
+
+    def test_method_not_allowed(self, client):
+        response = client.delete("/items", headers=CURL)
+        assert response.status == 405
+        assert response.headers["allow"] == "GET, POST"
+        assert response.text == "Method Not Allowed"
+
+
+class TestErrorFormat:
+    """Test the error_format server option."""
+
+    def test_json_for_every_client(self):
+        response = build_app().test_client(error_format="json").get("/nowhere", headers=CURL)
+        assert response.json()["error"]["code"] == "not_found"
+
+    def test_text_for_every_client(self):
+        response = build_app().test_client(error_format="text").get("/nowhere", headers=JSON)
+        assert response.text == "Not Found"
+
+    def test_invalid(self):
+        with pytest.raises(ValueError, match="error_format"):
+            Server(error_format="xml")
+
+    def test_stats(self):
+        assert Server().stats()["error_format"] == "auto"
+        assert Server(error_format="json").stats()["error_format"] == "json"
+
+
+class TestHandlerResponses:
+    """Test responses written by handlers."""
+
+    def test_handler_404_untouched(self, client):
+        response = client.get("/missing-item", headers=JSON)
+        assert response.status == 404
+        assert response.content == b""
+
+    def test_success_untouched(self, client):
+        assert client.get("/items", headers=JSON).json() == []
+
+
+class TestErrorHandlers:
+    """Test app error handlers winning over the envelope."""
+
+    def test_custom_not_found_wins(self):
+        app = build_app()
+
+        @app.errorhandler(NotFound)
+        def not_found(req, res, exc):
+            res.status(404).json({"missing": req.path, "code": exc.code})
+
+        response = app.test_client().get("/nowhere", headers=JSON)
+        assert response.status == 404
+        assert response.json() == {"missing": "/nowhere", "code": "not_found"}
+
+    def test_handler_by_status(self):
+        app = build_app()
+
+        @app.errorhandler(405)
+        def wrong_method(req, res, exc):
+            assert isinstance(exc, MethodNotAllowed)
+            res.json({"method": req.method})
+
+        response = app.test_client().delete("/items", headers=CURL)
+        # The status is kept unless the handler sets another
+        assert response.status == 405
+        assert response.headers["allow"] == "GET, POST"
+        assert response.json() == {"method": "DELETE"}
+
+    def test_other_statuses_keep_envelope(self):
+        app = build_app()
+
+        @app.errorhandler(NotFound)
+        def not_found(req, res, exc):
+            res.status(404).text("gone")
+
+        response = app.test_client().delete("/items", headers=JSON)
+        assert response.json()["error"]["code"] == "method_not_allowed"
+
+    def test_catch_all_does_not_take_over(self):
+        app = build_app()
+
+        @app.errorhandler(Exception)
+        def crashed(req, res, exc):
+            res.status(500).text("crashed")
+
+        response = app.test_client().get("/nowhere", headers=JSON)
+        assert response.status == 404
+        assert response.json()["error"]["code"] == "not_found"
+
+    def test_route_hidden_by_flag(self):
+        app = build_app()
+
+        @app.get("/beta", feature_flag=f"flag_{uuid.uuid4().hex[:8]}")
+        def beta(req, res, ctx):
+            res.json({"beta": True})
+
+        @app.errorhandler(NotFound)
+        def not_found(req, res, exc):
+            res.status(404).text("custom")
+
+        response = app.test_client().get("/beta")
+        assert response.status == 404
+        assert response.text == "custom"
+
+    def test_invalid_status(self):
+        with pytest.raises(ValueError, match="between 400 and 599"):
+            Server().set_error_handler(302, lambda req, res: None)
@@ -0,0 +1,181 @@
+"""
+Test cases for Expect: 100-continue handling.
+
+Tests cover:
+- 100 Continue sent before the body for accepted requests
+- Final 413/404/417 answered without the body being sent
+- Basic auth checked before the body
+- Keep-alive after a continued request, close after a refused one
+- expect_continue=False reading the body before any check
+"""
+
+import base64
+import socket
+from contextlib import contextmanager
+from urllib.parse import urlparse
+
+import httpx
+
+from tests.conftest import server_process
+
+
+SMALL_BODY_PATH = "/route-config/small-body"  # max_body_size="64b"
+
+
+@contextmanager
+def expect_server(*args: str):
+    with server_process(*args) as server:
+        yield server.base_url
+
+
+class RawConnection:
+    """HTTP/1.1 connection that sends the head and body separately."""
+
+    def __init__(self, base_url: str):
+        url = urlparse(base_url)
+        self.host = f"{url.hostname}:{url.port}"
+        self.sock = socket.create_connection((url.hostname, url.port), timeout=5)
+        self.buffer = b""
+
+    def close(self):
+        self.sock.close()
+
+    def send_head(self, path: str, length: int, expect: str = "100-continue", **headers: str):
+        lines = [
+            f"POST {path} HTTP/1.1",
+            f"Host: {self.host}",
+            f"Content-Length: {length}",
+            f"Expect: {expect}",
+        ]
+        lines += [f"{name.replace('_', '-')}: {value}" for name, value in headers.items()]
+        self.sock.sendall(("\r\n".join(lines) + "\r\n\r\n").encode())
+
+    def send_body(self, body: bytes):
+        self.sock.sendall(body)
+
+    def read_response(self):
+        """Read one response; returns (status, headers, body)."""
+        while b"\r\n\r\n" not in self.buffer:
+            chunk = self.sock.recv(65536)
+            if not chunk:
+                raise ConnectionError("connection closed before a response")
+            self.buffer += chunk
+        head, _, self.buffer = self.buffer.partition(b"\r\n\r\n")
+        status_line, *header_lines = head.decode().split("\r\n")
+        headers = {}
+        for line in header_lines:
+            name, _, value = line.partition(":")
+            headers[name.strip().lower()] = value.strip()
+        status = int(status_line.split(" ", 2)[1])
+        length = int(headers.get("content-length", 0))
+        while len(self.buffer) < length:
+            chunk = self.sock.recv(65536)
+            if not chunk:
+                break
+            self.buffer += chunk
+        body, self.buffer = self.buffer[:length], self.buffer[length:]
+        return status, headers, body
+
+    def is_closed(self) -> bool:
+        try:
+            return self.sock.recv(1) == b""
+        except ConnectionResetError:
+            return True
+
+
+@contextmanager
+def raw_connection(base_url: str):
+    conn = RawConnection(base_url)
+    try:
+        yield conn
+    finally:
+        conn.close()
+
+
+class TestContinue:
+    """Test requests that are told to send their body."""
+
+    def test_continue_before_body(self, base_url: str):
+        with raw_connection(base_url) as conn:
+            conn.send_head(SMALL_BODY_PATH, 10, Content_Type="application/octet-stream")
+            status, _, _ = conn.read_response()
+            assert status == 100
+            conn.send_body(b"0123456789")
+            status, _, body = conn.read_response()
+            assert status == 200
+            assert body == b'{"size":10}'
+
+    def test_keep_alive_after_continue(self, base_url: str):
+        with raw_connection(base_url) as conn:
+            for _ in range(2):
+                conn.send_head(SMALL_BODY_PATH, 4)
+                assert conn.read_response()[0] == 100
+                conn.send_body(b"abcd")
+                assert conn.read_response()[0] == 200
+
+    def test_header_case_insensitive(self, base_url: str):
+        with raw_connection(base_url) as conn:
+            conn.send_head(SMALL_BODY_PATH, 2, expect="100-Continue")
+            assert conn.read_response()[0] == 100
+
+
+class TestRefusedBeforeBody:
+    """Test final responses sent without waiting for the body."""
+
+    def test_payload_too_large(self, base_url: str):
+        with raw_connection(base_url) as conn:
+            conn.send_head(SMALL_BODY_PATH, 1000)
+            status, headers, body = conn.read_response()
+            assert status == 413
+            assert headers["connection"] == "close"
+            assert body.startswith(b"Request body exceeds")
+            assert conn.is_closed()
+
+    def test_unknown_route(self, base_url: str):
+        with raw_connection(base_url) as conn:
+            conn.send_head("/no/such/route", 100)
+            status, _, _ = conn.read_response()
+            assert status == 404
+
+    def test_unsupported_expectation(self, base_url: str):
+        with raw_connection(base_url) as conn:
+            conn.send_head(SMALL_BODY_PATH, 10, expect="something-else")
+            status, _, body = conn.read_response()
+            assert status == 417
+            assert body == b"Only 'Expect: 100-continue' is supported"
+
+    def test_server_usable_after_refusal(self, client: httpx.Client):
+        response = client.post(SMALL_BODY_PATH, content=b"x" * 8)
+        assert response.status_code == 200
+
+
+class TestAuthBeforeBody:
+    """Test Basic auth checked before the body is sent."""
+
+    def test_unauthorized_without_body(self):
+        with expect_server("--basic-auth") as base_url:
+            with raw_connection(base_url) as conn:
+                conn.send_head(SMALL_BODY_PATH, 10)
+                status, headers, _ = conn.read_response()
+                assert status == 401
+                assert headers["www-authenticate"] == 'Basic realm="Test Area"'
+
+            credentials = base64.b64encode(b"admin:secret").decode()
+            with raw_connection(base_url) as conn:
+                conn.send_head(SMALL_BODY_PATH, 10, Authorization=f"Basic {credentials}")
+                assert conn.read_response()[0] == 100
+                conn.send_body(b"0123456789")
+                assert conn.read_response()[0] == 200
+
+
+class TestDisabled:
+    """Test expect_continue=False."""
+
+    def test_body_requested_before_checks(self):
+        with expect_server("--no-expect-continue") as base_url:
+            with raw_connection(base_url) as conn:
+                conn.send_head(SMALL_BODY_PATH, 100)
+                # The body is asked for and read before the route's limit applies
+                assert conn.read_response()[0] == 100
+                conn.send_body(b"x" * 100)
+                assert conn.read_response()[0] == 413
@@ -0,0 +1,209 @@
+"""
+Test cases for route-scoped feature flags.
+
+Tests cover:
+- Percentage rollouts bucketing a given key the same way every time
+- Sticky keys: the authenticated user, else the client IP, else the request
+- Gated routes answering the flag's off_status without running the handler
+- Flags flipped at runtime taking effect on the next request
+- req.flag() in handlers
+- Per-flag evaluation counters
+- Validation of flag settings in update_config
+"""
+
+import base64
+import uuid
+
+import pytest
+
+from hypern import Hypern
+from hypern._hypern import Route, feature_flag_stats
+from hypern.middleware import BasicAuthMiddleware
+
+
+def flag_name() -> str:
+    """Flags live for the whole process; each test uses its own"""
+    return f"flag_{uuid.uuid4().hex[:8]}"
+
+
+def basic(user: str) -> dict:
+    token = base64.b64encode(f"{user}:secret".encode()).decode()
+    return {"Authorization": f"Basic {token}"}
+
+
+def build_app(flag: str, calls=None, auth: bool = False) -> Hypern:
+    app = Hypern()
+    if auth:
+        users = {f"user{i}": "secret" for i in range(40)}
+        app.use(BasicAuthMiddleware(realm="api", users=users, optional=True))
+
+    @app.get("/gated", feature_flag=flag)
+    def gated(req, res, ctx):
+        if calls is not None:
+            calls.append("gated")
+        res.json({"ok": True})
+
+    @app.get("/check")
+    def check(req, res, ctx):
+        res.json({"on": req.flag(flag)})
+
+    return app
+
+
+def from_ip(ip: str) -> dict:
+    return {"X-Forwarded-For": ip}
+
+
+class TestBucketing:
+    """Test percentage rollouts."""
+
+    def test_same_key_same_answer(self):
+        flag = flag_name()
+        client = build_app(flag).test_client()
+        Hypern().set_flag(flag, percentage=50, sticky_by="client_ip")
+        for i in range(20):
+            ip = f"10.0.0.{i}"
+            first = client.get("/check", headers=from_ip(ip)).json()["on"]
+            for _ in range(3):
+                assert client.get("/check", headers=from_ip(ip)).json()["on"] == first
+            # The gated route agrees with req.flag
+            assert (client.get("/gated", headers=from_ip(ip)).status == 200) == first
+
+    def test_share_of_keys(self):
+        flag = flag_name()
+        client = build_app(flag).test_client()
+        Hypern().set_flag(flag, percentage=25, sticky_by="client_ip")
+        on = sum(client.get("/check", headers=from_ip(f"10.1.{i // 250}.{i % 250}")).json()["on"] for i in range(1000))
+        assert 180 < on < 320
+
+    def test_bounds(self):
+        flag = flag_name()
+        client = build_app(flag).test_client()
+        app = Hypern()
+        app.set_flag(flag, percentage=0, sticky_by="client_ip")
+        assert not any(client.get("/check", headers=from_ip(f"10.2.0.{i}")).json()["on"] for i in range(50))
+        app.set_flag(flag, percentage=100, sticky_by="client_ip")
+        assert all(client.get("/check", headers=from_ip(f"10.2.0.{i}")).json()["on"] for i in range(50))
+
+    def test_sticky_by_user(self):
+        flag = flag_name()
+        client = build_app(flag, auth=True).test_client()
+        Hypern().set_flag(flag, percentage=50)
+        answers = set()
+        for i in range(40):
+            headers = basic(f"user{i}")
+            first = client.get("/check", headers={**headers, **from_ip("10.3.0.1")}).json()["on"]
+            # The user decides, not the address they come from
+            second = client.get("/check", headers={**headers, **from_ip("10.3.0.2")}).json()["on"]
+            assert first == second
+            answers.add(first)
+        assert answers == {True, False}
+
+    def test_sticky_by_request(self):
+        flag = flag_name()
+        client = build_app(flag).test_client()
+        Hypern().set_flag(flag, percentage=50, sticky_by="request_id")
+        answers = {client.get("/check", headers=from_ip("10.4.0.1")).json()["on"] for _ in range(60)}
+        assert answers == {True, False}
+
+
+class TestRouteGating:
+    """Test routes registered with feature_flag."""
+
+    def test_unset_flag_is_off(self):
+        calls = []
+        client = build_app(flag_name(), calls).test_client()
+        assert client.get("/gated").status == 404
+        assert client.get("/check").json() == {"on": False}
+        assert calls == []
+
+    def test_off_status_503(self):
+        flag = flag_name()
+        calls = []
+        client = build_app(flag, calls).test_client()
+        Hypern().set_flag(flag, enabled=False, off_status=503)
+        response = client.get("/gated")
+        assert response.status == 503
+        assert response.json()["error"] == "feature_disabled"
+        assert flag in response.json()["message"]
+        assert calls == []
+
+    def test_on(self):
+        flag = flag_name()
+        calls = []
+        client = build_app(flag, calls).test_client()
+        Hypern().set_flag(flag)
+        assert client.get("/gated").status == 200
+        assert calls == ["gated"]
+
+    def test_route_attribute(self):
+        route = Route("/x", lambda req, res, ctx: None, "GET", feature_flag="checkout")
+        assert route.feature_flag == "checkout"
+        assert Route("/x", lambda req, res, ctx: None, "GET").feature_flag is None
+
+
+class TestRuntimeFlip:
+    """Test flags changed while the app serves requests."""
+
+    def test_set_flag(self):
+        flag = flag_name()
+        client = build_app(flag).test_client()
+        app = Hypern()
+        assert client.get("/gated").status == 404
+        app.set_flag(flag, enabled=True)
+        assert client.get("/gated").status == 200
+        app.set_flag(flag, enabled=False)
+        assert client.get("/gated").status == 404
+
+    def test_update_config(self):
+        flag = flag_name()
+        client = build_app(flag).test_client()
+        app = Hypern()
+        app.update_config({"feature_flags": {flag: True}})
+        assert client.get("/gated").status == 200
+        app.update_config({"feature_flags": {flag: {"enabled": False, "off_status": 503}}})
+        assert client.get("/gated").status == 503
+        app.update_config({"feature_flags": {flag: None}})
+        assert client.get("/check").json() == {"on": False}
+
+    def test_live_config(self):
+        flag = flag_name()
+        app = Hypern()
+        app.set_flag(flag, percentage=30, sticky_by="client_ip", off_status=503)
+        assert app.live_config()["feature_flags"][flag] == {
+            "percentage": 30,
+            "sticky_by": "client_ip",
+            "off_status": 503,
+        }
+
+    def test_invalid(self):
+        app = Hypern()
+        with pytest.raises(ValueError, match="percentage"):
+            app.set_flag(flag_name(), percentage=150)
+        with pytest.raises(ValueError, match="sticky_by"):
+            app.set_flag(flag_name(), percentage=10, sticky_by="session")
+        with pytest.raises(ValueError, match="off_status"):
+            app.set_flag(flag_name(), off_status=500)
+        with pytest.raises(ValueError, match="unknown key 'rollout'"):
+            app.update_config({"feature_flags": {flag_name(): {"rollout": 5}}})
+        with pytest.raises(ValueError, match="feature_flags"):
+            app.update_config({"feature_flags": ["a"]})
+
+
+class TestStats:
+    """Test per-flag evaluation counters."""
+
+    def test_counters(self):
+        flag = flag_name()
+        client = build_app(flag).test_client()
+        app = Hypern()
+        client.get("/gated")
+        client.get("/check")
+        app.set_flag(flag)
+        client.get("/gated")
+        stats = app.flag_stats()[flag]
+        assert stats["on"] == 1
+        assert stats["off"] == 2
+        assert stats["percentage"] == 100
+        assert stats["sticky_by"] == "user_id"
+        assert feature_flag_stats()[flag] == stats
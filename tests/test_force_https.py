@@ -0,0 +1,208 @@
+"""
+Test cases for force_https and hsts_preload.
+
+Requests go through the in-process test client, which connects from
+127.0.0.1 over plain HTTP; TLS responses are covered in
+test_connection_info.py.
+
+Tests cover:
+- 308 to the https URL with path and query kept exactly
+- Health probes and ACME challenge paths exempt, exemptions configurable
+- X-Forwarded-Proto honoured from trusted proxies only, by its last entry
+- Hosts outside allowed_hosts never reflected
+- The HSTS preload header only on secure responses
+- Server option validation and stats()
+"""
+
+import pytest
+
+from hypern import Hypern
+from hypern._hypern import Server
+
+HOSTS = ["example.com", "*.example.com"]
+PRELOAD = "max-age=63072000; includeSubDomains; preload"
+
+
+def build_app() -> Hypern:
+    app = Hypern()
+
+    @app.get("/orders")
+    def orders(req, res, ctx):
+        res.json({"secure": req.secure})
+
+    @app.post("/orders")
+    def create_order(req, res, ctx):
+        res.status(201).json({"created": True})
+
+    @app.get("/.well-known/acme-challenge/{token}")
+    def acme(req, res, ctx):
+        res.text(req.param("token"))
+
+    @app.get("/hsts")
+    def own_hsts(req, res, ctx):
+        res.header("Strict-Transport-Security", "max-age=60").json({})
+
+    return app
+
+
+def client(**options):
+    options.setdefault("allowed_hosts", HOSTS)
+    return build_app().test_client(**options)
+
+
+class TestRedirect:
+    """Test the 308 to https."""
+
+    def test_plain_request_redirected(self):
+        response = client(force_https=True).get("/orders", headers={"Host": "example.com"})
+        assert response.status == 308
+        assert response.headers["location"] == "https://example.com/orders"
+        assert response.content == b""
+
+    def test_path_and_query_preserved(self):
+        response = client(force_https=True).get(
+            "/orders?b=2&a=%2F%20x&a=1", headers={"Host": "api.example.com"}
+        )
+        assert response.headers["location"] == "https://api.example.com/orders?b=2&a=%2F%20x&a=1"
+
+    def test_port_dropped(self):
+        response = client(force_https=True).get("/orders", headers={"Host": "Example.com:8080"})
+        assert response.headers["location"] == "https://example.com/orders"
+
+    def test_method_not_followed(self):
+        response = client(force_https=True).post(
+            "/orders", json={"id": 1}, headers={"Host": "example.com"}
+        )
+        assert response.status == 308
+
+    def test_disallowed_host_not_reflected(self):
+        response = client(force_https=True).get("/orders", headers={"Host": "evil.com"})
+        assert response.status == 400
+        assert "location" not in response.headers
+
+    def test_off_by_default(self):
+        response = client().get("/orders", headers={"Host": "example.com"})
+        assert response.status == 200
+
+
+class TestExemptions:
+    """Test paths left on plain HTTP."""
+
+    def test_health_probe(self):
+        response = client(force_https=True).get("/_health/live", headers={"Host": "example.com"})
+        assert response.status == 200
+
+    def test_acme_challenge(self):
+        response = client(force_https=True).get(
+            "/.well-known/acme-challenge/tok3n", headers={"Host": "example.com"}
+        )
+        assert response.status == 200
+        assert response.text == "tok3n"
+
+    def test_custom_exemptions(self):
+        c = client(force_https=True, https_exempt_paths=["/orders"])
+        assert c.get("/orders", headers={"Host": "example.com"}).status == 200
+        acme = c.get("/.well-known/acme-challenge/tok3n", headers={"Host": "example.com"})
+        assert acme.status == 308
+
+
+class TestForwardedProto:
+    """Test X-Forwarded-Proto from trusted and untrusted peers."""
+
+    def test_untrusted_ignored(self):
+        response = client(force_https=True).get(
+            "/orders", headers={"Host": "example.com", "X-Forwarded-Proto": "https"}
+        )
+        assert response.status == 308
+
+    def test_trusted_https(self):
+        c = client(force_https=True, trusted_proxies=["127.0.0.0/8"])
+        response = c.get("/orders", headers={"Host": "example.com", "X-Forwarded-Proto": "https"})
+        assert response.status == 200
+
+    def test_trusted_http(self):
+        c = client(force_https=True, trusted_proxies=["127.0.0.1"])
+        response = c.get("/orders", headers={"Host": "example.com", "X-Forwarded-Proto": "http"})
+        assert response.status == 308
+
+    def test_client_entry_ignored(self):
+        # The client sent "https"; the trusted proxy appended "http"
+        c = client(force_https=True, trusted_proxies=["127.0.0.1"])
+        response = c.get(
+            "/orders", headers={"Host": "example.com", "X-Forwarded-Proto": "https, http"}
+        )
+        assert response.status == 308
+
+    def test_proxy_entry_decides(self):
+        c = client(force_https=True, trusted_proxies=["127.0.0.1"])
+        response = c.get(
+            "/orders", headers={"Host": "example.com", "X-Forwarded-Proto": "http, https"}
+        )
+        assert response.status == 200
+
+    def test_other_network_untrusted(self):
+        c = client(force_https=True, trusted_proxies=["10.0.0.0/8"])
+        response = c.get("/orders", headers={"Host": "example.com", "X-Forwarded-Proto": "https"})
+        assert response.status == 308
+
+
+class TestHstsPreload:
+    """Test the preload header on secure and plain responses."""
+
+    def test_absent_on_plain(self):
+        response = client(hsts_preload=True).get("/orders", headers={"Host": "example.com"})
+        assert "strict-transport-security" not in response.headers
+
+    def test_plain_handler_header_removed(self):
+        response = client(hsts_preload=True).get("/hsts", headers={"Host": "example.com"})
+        assert "strict-transport-security" not in response.headers
+
+    def test_on_secure_via_trusted_proxy(self):
+        c = client(hsts_preload=True, trusted_proxies=["127.0.0.1"])
+        headers = {"Host": "example.com", "X-Forwarded-Proto": "https"}
+        assert c.get("/orders", headers=headers).headers["strict-transport-security"] == PRELOAD
+        # Replaces the weaker value the handler set
+        assert c.get("/hsts", headers=headers).headers["strict-transport-security"] == PRELOAD
+
+    def test_client_entry_gets_none(self):
+        c = client(hsts_preload=True, trusted_proxies=["127.0.0.1"])
+        response = c.get(
+            "/orders", headers={"Host": "example.com", "X-Forwarded-Proto": "https, http"}
+        )
+        assert "strict-transport-security" not in response.headers
+
+    def test_untrusted_proxy_gets_none(self):
+        response = client(hsts_preload=True).get(
+            "/orders", headers={"Host": "example.com", "X-Forwarded-Proto": "https"}
+        )
+        assert "strict-transport-security" not in response.headers
+
+    def test_off_keeps_handler_header(self):
+        response = client().get("/hsts", headers={"Host": "example.com"})
+        assert response.headers["strict-transport-security"] == "max-age=60"
+
+
+class TestOptions:
+    """Test validation and stats()."""
+
+    def test_requires_allowed_hosts(self):
+        with pytest.raises(ValueError, match="allowed_hosts"):
+            Server(force_https=True)
+
+    def test_invalid_proxy(self):
+        with pytest.raises(ValueError, match="trusted_proxies"):
+            Server(trusted_proxies=["10.0.0.0/40"])
+
+    def test_relative_exempt_path(self):
+        with pytest.raises(ValueError, match="must start with '/'"):
+            Server(https_exempt_paths=["health"])
+
+    def test_stats(self):
+        stats = Server(
+            allowed_hosts=HOSTS, force_https=True, hsts_preload=True, trusted_proxies=["10.0.0.0/8"]
+        ).stats()
+        assert stats["force_https"] is True
+        assert stats["hsts_preload"] is True
+        assert stats["trusted_proxies"] == ["10.0.0.0/8"]
+        assert stats["https_exempt_paths"] == ["/.well-known/acme-challenge/"]
+        assert Server().stats()["force_https"] is False
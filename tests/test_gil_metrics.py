@@ -0,0 +1,147 @@
+"""
+Test cases for GIL wait and hold metrics.
+
+Tests cover:
+- A slow handler logging a long-hold warning naming the call site
+- Hold histogram, totals and maximum reflecting the slow handler
+- Counters staying zero with gil_metrics disabled
+- Server configuration and validation of gil_hold_warn_ms
+"""
+
+import time
+from contextlib import contextmanager
+
+import httpx
+import pytest
+
+from hypern._hypern import Server, ServerMetrics
+
+from tests.conftest import server_process
+
+
+# App holding the GIL for 300 ms on /slow; argv[3] turns the metrics on
+APP_SCRIPT = """
+import sys
+import time
+sys.path.insert(0, sys.argv[1])
+from hypern import Hypern
+from hypern._hypern import server_metrics
+
+app = Hypern()
+
+@app.get("/slow")
+def slow(req, res, ctx):
+    deadline = time.perf_counter() + 0.3
+    while time.perf_counter() < deadline:
+        pass
+    res.text("done")
+
+@app.get("/gil")
+def gil(req, res, ctx):
+    res.json(server_metrics().gil())
+
+@app.get("/prometheus")
+def prometheus(req, res, ctx):
+    res.text(server_metrics().render())
+
+app.start(
+    host="127.0.0.1",
+    port=int(sys.argv[2]),
+    num_processes=1,
+    gil_metrics=sys.argv[3] == "on",
+    gil_hold_warn_ms=50,
+)
+"""
+
+
+@contextmanager
+def gil_server(enabled: bool):
+    with server_process(
+        "on" if enabled else "off", app_script=APP_SCRIPT, ready_path="/gil"
+    ) as server:
+        yield server.base_url, server.logs
+
+
+def wait_for(predicate, timeout: float = 5.0) -> bool:
+    deadline = time.time() + timeout
+    while time.time() < deadline:
+        if predicate():
+            return True
+        time.sleep(0.1)
+    return predicate()
+
+
+class TestEnabled:
+    """Test a server started with gil_metrics=True."""
+
+    def test_long_hold_warning(self):
+        with gil_server(enabled=True) as (base_url, logs):
+            assert httpx.get(f"{base_url}/slow", timeout=5.0).text == "done"
+            assert wait_for(lambda: "GIL held for" in logs())
+            line = next(l for l in logs().splitlines() if "GIL held for" in l)
+            assert "by handler" in line
+            assert "WARN" in line.upper()
+
+    def test_histogram_reflects_hold(self):
+        with gil_server(enabled=True) as (base_url, _):
+            httpx.get(f"{base_url}/slow", timeout=5.0)
+            stats = httpx.get(f"{base_url}/gil").json()
+            assert stats["enabled"] is True
+            assert stats["warn_ms"] == 50.0
+            handler = stats["handler"]
+            assert handler["acquisitions"] >= 1
+            assert handler["long_holds"] >= 1
+            assert handler["max_hold_ms"] >= 290
+            assert handler["hold_ms"] >= handler["max_hold_ms"]
+            histogram = dict(handler["hold_histogram"])
+            # Cumulative: the 300 ms hold is above the 250 ms bucket
+            assert histogram[None] - histogram[250] >= 1
+            assert histogram[None] == handler["acquisitions"]
+
+    def test_prometheus(self):
+        with gil_server(enabled=True) as (base_url, _):
+            httpx.get(f"{base_url}/slow", timeout=5.0)
+            text = httpx.get(f"{base_url}/prometheus").text
+            assert 'hypern_gil_wait_seconds_total{site="handler"}' in text
+            assert 'hypern_gil_hold_seconds_bucket{site="handler",le="+Inf"}' in text
+            assert 'hypern_gil_hold_seconds_count{site="executor"} 0' in text
+
+
+class TestDisabled:
+    """Test that nothing is counted by default."""
+
+    def test_counters_stay_zero(self):
+        with gil_server(enabled=False) as (base_url, logs):
+            httpx.get(f"{base_url}/slow", timeout=5.0)
+            stats = httpx.get(f"{base_url}/gil").json()
+            assert stats["enabled"] is False
+            for site in ("handler", "executor", "row_conversion"):
+                assert stats[site]["acquisitions"] == 0
+                assert stats[site]["hold_ms"] == 0
+                assert stats[site]["long_holds"] == 0
+                assert all(count == 0 for _, count in stats[site]["hold_histogram"])
+            assert "GIL held for" not in logs()
+
+    def test_standalone_collector(self):
+        stats = ServerMetrics().gil()
+        assert set(stats) == {"enabled", "warn_ms", "handler", "executor", "row_conversion"}
+        assert stats["handler"]["hold_histogram"][-1] == (None, 0)
+
+
+class TestConfig:
+    """Test the Server options."""
+
+    def test_stats(self):
+        stats = Server(gil_metrics=True, gil_hold_warn_ms=25).stats()
+        assert stats["gil_metrics"] is True
+        assert stats["gil_hold_warn_ms"] == 25.0
+        assert "gil" in stats["metrics"]
+
+    def test_defaults(self):
+        stats = Server().stats()
+        assert stats["gil_metrics"] is False
+        assert stats["gil_hold_warn_ms"] == 100.0
+
+    def test_negative_threshold(self):
+        with pytest.raises(ValueError, match="gil_hold_warn_ms"):
+            Server(gil_hold_warn_ms=-1)
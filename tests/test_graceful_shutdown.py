@@ -0,0 +1,89 @@
+"""
+Test cases for stopping a server whose workers drain first.
+
+Tests cover:
+- An idle server exiting as soon as its workers have, not after a fixed wait
+- A handler stuck past the drain timeout: the worker is terminated once the
+  timeout passes, and the logs name its PID and what was left in flight
+"""
+
+import threading
+import time
+
+import httpx
+
+from tests.conftest import server_process
+
+
+# One worker with a configurable drain timeout and a handler that never
+# finishes in time
+APP_SCRIPT = """
+import os
+import sys
+import time
+sys.path.insert(0, sys.argv[1])
+from hypern import Hypern
+
+app = Hypern()
+app.setup_reload(drain_timeout_secs=int(sys.argv[3]), startup_grace_secs=0)
+
+@app.get("/pid")
+def pid(req, res, ctx):
+    res.json({"pid": os.getpid()})
+
+@app.get("/stuck")
+def stuck(req, res, ctx):
+    time.sleep(60)
+    res.json({"done": True})
+
+app.start(host="127.0.0.1", port=int(sys.argv[2]), num_processes=1)
+"""
+
+
+def start_stuck_request(base_url: str) -> None:
+    def request():
+        try:
+            httpx.get(f"{base_url}/stuck", timeout=60.0)
+        except httpx.TransportError:
+            pass
+
+    threading.Thread(target=request, daemon=True).start()
+    deadline = time.time() + 5
+    while time.time() < deadline:
+        if httpx.get(f"{base_url}/_health", timeout=1.0).json()["in_flight"] > 0:
+            return
+        time.sleep(0.05)
+    raise AssertionError("the stuck request never started")
+
+
+class TestIdleShutdown:
+    """Test stopping a server with nothing in flight."""
+
+    def test_exits_without_waiting_for_timeout(self):
+        with server_process("30", app_script=APP_SCRIPT, ready_path="/_health/live") as server:
+            started = time.monotonic()
+            server.stop(timeout=30)
+            # Well under both the 30s drain timeout and the old 2s grace
+            assert time.monotonic() - started < 1.5
+            assert "All in-flight requests drained" not in server.logs()
+            assert "Drain timeout" not in server.logs()
+
+
+class TestStuckHandler:
+    """Test stopping a server whose handler outlives the drain timeout."""
+
+    def test_timeout_reported(self):
+        with server_process("1", app_script=APP_SCRIPT, ready_path="/_health/live") as server:
+            worker_pid = httpx.get(f"{server.base_url}/pid").json()["pid"]
+            start_stuck_request(server.base_url)
+
+            started = time.monotonic()
+            server.stop(timeout=30)
+            elapsed = time.monotonic() - started
+            logs = server.logs()
+
+        assert elapsed >= 1.0
+        # The drain timeout, then at most the kill grace; not the 60s sleep
+        assert elapsed < 10.0
+        assert f"workers still running (PIDs {worker_pid})" in logs
+        assert f"(PID {worker_pid}) drain timeout reached with 1 requests still in-flight" in logs
@@ -0,0 +1,215 @@
+"""
+Test cases for request header limits.
+
+Tests cover:
+- Constructor validation of the limits and their defaults in stats()
+- Oversized header sections and too many fields answered with 431 over raw
+  HTTP/1 connections, an absurd URI with 414, and the connection closed
+- Requests just under every limit served normally, on kept-alive
+  connections too
+- The same statuses from the request check HTTP/2 relies on, through the
+  in-process test client
+- Rejections counted by limit in ServerMetrics
+"""
+
+import json
+import os
+import socket
+import subprocess
+import sys
+import time
+
+import pytest
+
+from hypern import Hypern
+from hypern._hypern import Server
+
+
+ROOT = os.path.dirname(os.path.dirname(os.path.abspath(__file__)))
+
+MAX_HEADER_BYTES = 8192
+MAX_HEADER_COUNT = 20
+MAX_URI_LENGTH = 1024
+
+APP_SCRIPT = """
+import sys
+sys.path.insert(0, sys.argv[1])
+from hypern import Hypern
+from hypern._hypern import server_metrics
+
+app = Hypern()
+
+@app.get("/ping")
+def ping(req, res, ctx):
+    res.json({"ok": True})
+
+@app.get("/rejected")
+def rejected(req, res, ctx):
+    res.json(server_metrics().snapshot()["header_limit_rejections"])
+
+app.start(
+    host="127.0.0.1",
+    port=int(sys.argv[2]),
+    num_processes=1,
+    max_header_bytes=%d,
+    max_header_count=%d,
+    max_uri_length=%d,
+)
+""" % (MAX_HEADER_BYTES, MAX_HEADER_COUNT, MAX_URI_LENGTH)
+
+
+@pytest.fixture(scope="module")
+def server():
+    with socket.socket() as s:
+        s.bind(("127.0.0.1", 0))
+        port = s.getsockname()[1]
+    process = subprocess.Popen([sys.executable, "-c", APP_SCRIPT, ROOT, str(port)])
+    try:
+        deadline = time.monotonic() + 20
+        while True:
+            try:
+                if b"200 OK" in exchange(port, head()):
+                    break
+            except OSError:
+                pass
+            if time.monotonic() > deadline:
+                raise RuntimeError("server did not start")
+            time.sleep(0.2)
+        yield port
+    finally:
+        process.terminate()
+        process.wait(timeout=30)
+
+
+def head(path: str = "/ping", fields=(), close: bool = True) -> bytes:
+    lines = [f"GET {path} HTTP/1.1", "Host: localhost"]
+    lines += [f"{name}: {value}" for name, value in fields]
+    if close:
+        lines.append("Connection: close")
+    return ("\r\n".join(lines) + "\r\n\r\n").encode()
+
+
+def read_all(conn: socket.socket) -> bytes:
+    data = b""
+    while True:
+        try:
+            chunk = conn.recv(65536)
+        except ConnectionResetError:
+            break
+        if not chunk:
+            break
+        data += chunk
+    return data
+
+
+def exchange(port: int, request: bytes) -> bytes:
+    """Send a request on its own connection and read until the server closes"""
+    with socket.create_connection(("127.0.0.1", port), timeout=5) as conn:
+        conn.sendall(request)
+        return read_all(conn)
+
+
+def status(response: bytes) -> int:
+    return int(response.split(b" ", 2)[1])
+
+
+def rejected(port: int) -> dict:
+    response = exchange(port, head("/rejected"))
+    return json.loads(response.split(b"\r\n\r\n", 1)[1])
+
+
+class TestOptions:
+    """Test the Server constructor options."""
+
+    def test_defaults_in_stats(self):
+        stats = Server().stats()
+        assert stats["max_header_bytes"] == 64 * 1024
+        assert stats["max_header_count"] == 100
+        assert stats["max_uri_length"] == 8 * 1024
+
+    def test_values_in_stats(self):
+        stats = Server(max_header_bytes=4096, max_header_count=None, max_uri_length=512).stats()
+        assert stats["max_header_bytes"] == 4096
+        assert stats["max_header_count"] is None
+        assert stats["max_uri_length"] == 512
+
+    @pytest.mark.parametrize("option", ["max_header_bytes", "max_header_count", "max_uri_length"])
+    def test_zero_rejected(self, option):
+        with pytest.raises(ValueError, match=option):
+            Server(**{option: 0})
+
+
+class TestRawConnections:
+    """Test limits on HTTP/1 heads as they are read."""
+
+    def test_oversized_header_section(self, server):
+        response = exchange(server, head(fields=[("Cookie", "a" * 200 * 1024)]))
+        assert status(response) == 431
+        assert b"connection: close" in response.lower()
+        assert response.endswith(b"Request header fields too large")
+
+    def test_too_many_fields(self, server):
+        fields = [(f"X-Field-{i}", "1") for i in range(MAX_HEADER_COUNT + 5)]
+        response = exchange(server, head(fields=fields))
+        assert status(response) == 431
+
+    def test_uri_too_long(self, server):
+        response = exchange(server, head("/ping?q=" + "a" * 100_000))
+        assert status(response) == 414
+        assert response.endswith(b"URI too long")
+
+    def test_just_under_limits(self, server):
+        fields = [(f"X-Field-{i}", "1") for i in range(MAX_HEADER_COUNT - 4)]
+        lines = ["Host: localhost", "Connection: close"] + [f"{n}: {v}" for n, v in fields]
+        used = sum(len(line) + 2 for line in lines) + len("X-Fill: \r\n")
+        fields.append(("X-Fill", "a" * (MAX_HEADER_BYTES - used - 16)))
+        path = "/ping?q=" + "a" * (MAX_URI_LENGTH - len("/ping?q="))
+        response = exchange(server, head(path, fields=fields))
+        assert status(response) == 200
+
+    def test_limits_apply_per_request(self, server):
+        fields = [("X-Fill", "a" * (MAX_HEADER_BYTES // 2))]
+        with socket.create_connection(("127.0.0.1", server), timeout=5) as conn:
+            for _ in range(3):
+                conn.sendall(head(fields=fields, close=False))
+                data = b""
+                while not data.endswith(b"}"):
+                    data += conn.recv(65536)
+                assert status(data) == 200
+
+    def test_counted_by_limit(self, server):
+        before = rejected(server)
+        exchange(server, head(fields=[("Cookie", "a" * 100_000)]))
+        exchange(server, head("/" + "a" * 5000))
+        after = rejected(server)
+        assert after["max_header_bytes"] == before["max_header_bytes"] + 1
+        assert after["max_uri_length"] == before["max_uri_length"] + 1
+
+
+class TestRequestCheck:
+    """Test the check on decoded requests, as HTTP/2 ones get it."""
+
+    @pytest.fixture
+    def client(self):
+        app = Hypern()
+
+        @app.get("/ping")
+        def ping(req, res, ctx):
+            res.json({"ok": True})
+
+        return app.test_client(max_header_bytes=1024, max_header_count=10, max_uri_length=256)
+
+    def test_header_bytes(self, client):
+        response = client.get("/ping", headers={"Cookie": "a" * 2000})
+        assert response.status == 431
+        assert response.text == "Request header fields too large"
+
+    def test_header_count(self, client):
+        response = client.get("/ping", headers={f"X-Field-{i}": "1" for i in range(12)})
+        assert response.status == 431
+
+    def test_uri_length(self, client):
+        assert client.get("/ping?q=" + "a" * 300).status == 414
+
+    def test_under_limits(self, client):
+        assert client.get("/ping?q=" + "a" * 200, headers={"Cookie": "a" * 500}).status == 200
@@ -0,0 +1,176 @@
+"""
+Test cases for the server-wide response header policy.
+
+Tests cover:
+- Default headers on handler, 404, static file and health probe responses
+- Precedence: handler headers over middleware headers over defaults
+- Removal of headers set by handlers
+- Custom and suppressed Server header
+- HeaderPolicy validation and Server.stats() reporting
+"""
+
+import os
+import tempfile
+from contextlib import contextmanager
+
+import httpx
+import pytest
+
+from hypern._hypern import HeaderPolicy, Server
+
+from tests.conftest import server_process
+
+
+# argv[3] is the Server header ("-" for none), argv[4] a static directory
+APP_SCRIPT = """
+import sys
+sys.path.insert(0, sys.argv[1])
+from hypern import Hypern
+from hypern.middleware import SecurityHeadersMiddleware
+
+app = Hypern()
+app.use(SecurityHeadersMiddleware(hsts=False, frame_options="SAMEORIGIN"))
+app.header_policy(
+    default_headers={"X-Frame-Options": "DENY", "X-Api-Version": "3"},
+    remove_headers=["X-Powered-By"],
+    server_header=None if sys.argv[3] == "-" else sys.argv[3],
+)
+app.static("/assets", sys.argv[4])
+
+@app.get("/plain")
+def plain(req, res, ctx):
+    res.text("ok")
+
+@app.get("/handler-frame")
+def handler_frame(req, res, ctx):
+    res.header("X-Frame-Options", "ALLOW-FROM https://example.com")
+    res.header("X-Api-Version", "4")
+    res.text("ok")
+
+@app.get("/powered")
+def powered(req, res, ctx):
+    res.header("X-Powered-By", "python")
+    res.text("ok")
+
+@app.get("/own-server")
+def own_server(req, res, ctx):
+    res.header("Server", "handler/2.0")
+    res.text("ok")
+
+app.start(host="127.0.0.1", port=int(sys.argv[2]))
+"""
+
+
+@contextmanager
+def policy_server(server_header: str = "Edge/1.0"):
+    with tempfile.TemporaryDirectory() as static_dir:
+        with open(os.path.join(static_dir, "app.css"), "w") as f:
+            f.write("body { color: red; }")
+        with server_process(
+            server_header, static_dir, app_script=APP_SCRIPT, ready_path="/plain"
+        ) as server:
+            yield server.base_url
+
+
+class TestDefaults:
+    """Test defaults reaching every kind of response."""
+
+    def test_not_found(self):
+        with policy_server() as base_url:
+            response = httpx.get(f"{base_url}/missing")
+            assert response.status_code == 404
+            assert response.headers["x-api-version"] == "3"
+            assert response.headers["x-frame-options"] == "DENY"
+
+    def test_static_file(self):
+        with policy_server() as base_url:
+            response = httpx.get(f"{base_url}/assets/app.css")
+            assert response.status_code == 200
+            assert response.text == "body { color: red; }"
+            assert response.headers["x-api-version"] == "3"
+
+    def test_health_probe(self):
+        with policy_server() as base_url:
+            response = httpx.get(f"{base_url}/_health/live")
+            assert response.headers["x-api-version"] == "3"
+            assert response.headers["server"] == "Edge/1.0"
+
+
+class TestPrecedence:
+    """Test handler > middleware > default."""
+
+    def test_middleware_over_default(self):
+        with policy_server() as base_url:
+            response = httpx.get(f"{base_url}/plain")
+            assert response.headers["x-frame-options"] == "SAMEORIGIN"
+            assert response.headers["x-api-version"] == "3"
+
+    def test_handler_over_middleware_and_default(self):
+        with policy_server() as base_url:
+            response = httpx.get(f"{base_url}/handler-frame")
+            assert response.headers["x-frame-options"] == "ALLOW-FROM https://example.com"
+            assert response.headers["x-api-version"] == "4"
+
+
+class TestRemoval:
+    """Test stripping headers."""
+
+    def test_handler_header_removed(self):
+        with policy_server() as base_url:
+            response = httpx.get(f"{base_url}/powered")
+            assert response.status_code == 200
+            assert "x-powered-by" not in response.headers
+
+
+class TestServerHeader:
+    """Test the Server header."""
+
+    def test_custom_value(self):
+        with policy_server("Edge/1.0") as base_url:
+            assert httpx.get(f"{base_url}/plain").headers["server"] == "Edge/1.0"
+            assert httpx.get(f"{base_url}/missing").headers["server"] == "Edge/1.0"
+
+    def test_handler_value_kept(self):
+        with policy_server("Edge/1.0") as base_url:
+            assert httpx.get(f"{base_url}/own-server").headers["server"] == "handler/2.0"
+
+    def test_suppressed(self):
+        with policy_server("-") as base_url:
+            for path in ("/plain", "/own-server", "/missing", "/_health/live"):
+                assert "server" not in httpx.get(f"{base_url}{path}").headers
+            assert "date" in httpx.get(f"{base_url}/plain").headers
+
+
+class TestConfig:
+    """Test HeaderPolicy and Server.stats()."""
+
+    def test_defaults(self):
+        policy = HeaderPolicy()
+        assert policy.default_headers == []
+        assert policy.remove_headers == []
+        assert policy.server_header == "Hypern"
+
+    def test_names_normalized(self):
+        policy = HeaderPolicy(default_headers=[("X-Api-Version", "3")], remove_headers=["X-Powered-By"])
+        assert policy.default_headers == [("x-api-version", "3")]
+        assert policy.remove_headers == ["x-powered-by"]
+
+    def test_invalid_name(self):
+        with pytest.raises(ValueError, match="invalid header name"):
+            HeaderPolicy(default_headers=[("Bad Name", "1")])
+
+    def test_invalid_value(self):
+        with pytest.raises(ValueError, match="invalid value"):
+            HeaderPolicy(server_header="line\nbreak")
+
+    def test_date_not_removable(self):
+        with pytest.raises(ValueError, match="Date"):
+            HeaderPolicy(remove_headers=["Date"])
+
+    def test_stats(self):
+        server = Server()
+        assert server.stats()["header_policy"]["server_header"] == "Hypern"
+        server.set_header_policy(HeaderPolicy(remove_headers=["X-Powered-By"], server_header=None))
+        policy = server.stats()["header_policy"]
+        assert policy["remove_headers"] == ["x-powered-by"]
+        assert policy["server_header"] is None
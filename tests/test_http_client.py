@@ -0,0 +1,318 @@
+"""
+Test cases for the outbound HTTP client.
+
+Tests cover:
+- Sequential requests reusing one pooled connection
+- Concurrent requests from one client capped at max_connections
+- JSON, form and raw bodies, query params and response accessors
+- Timeouts raising TimeoutError, per client and per request
+- Idempotent requests retried until the server comes up; POST never retried
+- Async variants awaited under asyncio
+- traceparent continuing the current request's trace
+"""
+
+import asyncio
+import gzip
+import json
+import threading
+import time
+from concurrent.futures import ThreadPoolExecutor
+from contextlib import contextmanager
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+
+import pytest
+
+from hypern import Hypern
+from hypern.client import ClientResponse, HttpClient
+
+from tests.conftest import free_port
+
+
+class Upstream(ThreadingHTTPServer):
+    """Local server counting the connections it accepts."""
+
+    daemon_threads = True
+
+    def __init__(self, address):
+        super().__init__(address, Handler)
+        self.connections = 0
+        self.active = 0
+        self.peak = 0
+        self.lock = threading.Lock()
+
+    def get_request(self):
+        conn = super().get_request()
+        with self.lock:
+            self.connections += 1
+        return conn
+
+    def handle_error(self, request, client_address):
+        # Clients that timed out close the socket mid-reply
+        pass
+
+
+class Handler(BaseHTTPRequestHandler):
+    protocol_version = "HTTP/1.1"
+
+    def log_message(self, *args):
+        pass
+
+    def reply(self, status, body, headers=None):
+        self.send_response(status)
+        for name, value in (headers or {}).items():
+            self.send_header(name, value)
+        self.send_header("Content-Length", str(len(body)))
+        self.end_headers()
+        self.wfile.write(body)
+
+    def echo(self):
+        length = int(self.headers.get("Content-Length") or 0)
+        body = self.rfile.read(length).decode()
+        payload = {
+            "method": self.command,
+            "path": self.path,
+            "headers": {k.lower(): v for k, v in self.headers.items()},
+            "body": body,
+        }
+        self.reply(200, json.dumps(payload).encode(), {"Content-Type": "application/json"})
+
+    def do_GET(self):
+        if self.path.startswith("/slow"):
+            server = self.server
+            with server.lock:
+                server.active += 1
+                server.peak = max(server.peak, server.active)
+            time.sleep(float(self.path.split("=")[1]) if "=" in self.path else 0.2)
+            with server.lock:
+                server.active -= 1
+            self.reply(200, b"slow")
+        elif self.path == "/gzip":
+            self.reply(200, gzip.compress(b"x" * 1000), {"Content-Encoding": "gzip"})
+        elif self.path == "/multi":
+            self.send_response(200)
+            self.send_header("Set-Cookie", "a=1")
+            self.send_header("Set-Cookie", "b=2")
+            self.send_header("Content-Length", "0")
+            self.end_headers()
+        else:
+            self.echo()
+
+    do_POST = do_PUT = do_PATCH = do_DELETE = echo
+
+
+@contextmanager
+def upstream(port: int = 0):
+    server = Upstream(("127.0.0.1", port))
+    thread = threading.Thread(target=server.serve_forever, daemon=True)
+    thread.start()
+    try:
+        yield server, f"http://127.0.0.1:{server.server_address[1]}"
+    finally:
+        server.shutdown()
+        server.server_close()
+
+
+class TestPooling:
+    """Test connection reuse and per-host limits."""
+
+    def test_sequential_requests_reuse_connection(self):
+        with upstream() as (server, base_url):
+            client = HttpClient(base_url=base_url)
+            for n in range(10):
+                assert client.get(f"/item/{n}").status == 200
+            assert server.connections == 1
+
+    def test_concurrency_capped(self):
+        with upstream() as (server, base_url):
+            client = HttpClient(base_url=base_url, max_connections=4)
+            with ThreadPoolExecutor(max_workers=16) as pool:
+                statuses = list(pool.map(lambda _: client.get("/slow?d=0.1").status, range(24)))
+
+            assert statuses == [200] * 24
+            assert server.peak <= 4
+            assert server.connections <= 4
+
+    def test_clients_share_pool(self):
+        with upstream() as (server, base_url):
+            HttpClient(base_url=base_url).get("/a")
+            HttpClient(base_url=base_url).get("/b")
+            assert server.connections == 1
+
+
+class TestRequests:
+    """Test request bodies and response accessors."""
+
+    def test_json_body_and_params(self):
+        with upstream() as (_server, base_url):
+            response = HttpClient(base_url=base_url).post(
+                "/users", json={"name": "Alice", "tags": [1, 2]}, params={"page": "2"}
+            )
+
+            assert isinstance(response, ClientResponse)
+            assert response.ok
+            echoed = response.json()
+            assert echoed["method"] == "POST"
+            assert echoed["path"] == "/users?page=2"
+            assert echoed["headers"]["content-type"] == "application/json"
+            assert json.loads(echoed["body"]) == {"name": "Alice", "tags": [1, 2]}
+
+    def test_form_and_raw_bodies(self):
+        with upstream() as (_server, base_url):
+            client = HttpClient(base_url=base_url)
+
+            form = client.put("/f", data={"q": "a b", "n": 1}).json()
+            assert form["headers"]["content-type"] == "application/x-www-form-urlencoded"
+            assert form["body"] == "q=a+b&n=1"
+
+            raw = client.patch("/r", data=b"raw", headers={"Content-Type": "text/x-raw"}).json()
+            assert raw["headers"]["content-type"] == "text/x-raw"
+            assert raw["body"] == "raw"
+
+    def test_json_and_data_exclusive(self):
+        with pytest.raises(ValueError, match="either json or data"):
+            HttpClient().post("http://127.0.0.1:1/", json={}, data=b"")
+
+    def test_response_accessors(self):
+        with upstream() as (_server, base_url):
+            client = HttpClient(base_url=base_url)
+
+            response = client.get("/gzip")
+            assert response.content == b"x" * 1000
+            assert response.text == "x" * 1000
+
+            response = client.get("/multi")
+            assert response.headers["set-cookie"] == "a=1, b=2"
+
+    def test_other_methods(self):
+        with upstream() as (_server, base_url):
+            response = HttpClient(base_url=base_url).request("delete", "/thing")
+            assert response.json()["method"] == "DELETE"
+
+
+class TestTimeouts:
+    """Test timeout handling."""
+
+    def test_client_timeout(self):
+        with upstream() as (_server, base_url):
+            client = HttpClient(base_url=base_url, timeout=0.3)
+            started = time.monotonic()
+            with pytest.raises(TimeoutError, match="timed out after 0.300s"):
+                client.get("/slow?d=2")
+            assert time.monotonic() - started < 1.5
+
+    def test_per_request_timeout(self):
+        with upstream() as (_server, base_url):
+            client = HttpClient(base_url=base_url, timeout=0.1)
+            assert client.get("/slow?d=0.3", timeout=2).text == "slow"
+
+    def test_invalid_timeout(self):
+        with pytest.raises(ValueError, match="timeout must be a positive number"):
+            HttpClient(timeout=0)
+
+
+class TestRetries:
+    """Test retries of idempotent requests."""
+
+    def test_retry_until_server_starts(self):
+        port = free_port()
+        started = threading.Event()
+
+        def start_later():
+            time.sleep(0.4)
+            with upstream(port):
+                started.set()
+                time.sleep(3)
+
+        threading.Thread(target=start_later, daemon=True).start()
+        client = HttpClient(retries=8, backoff=0.1)
+
+        response = client.get(f"http://127.0.0.1:{port}/late")
+
+        assert started.is_set()
+        assert response.json()["path"] == "/late"
+
+    def test_connect_error_without_retries(self):
+        port = free_port()
+        with pytest.raises(ConnectionError, match=f"127.0.0.1:{port}"):
+            HttpClient().get(f"http://127.0.0.1:{port}/")
+
+    def test_post_not_retried(self):
+        port = free_port()
+        client = HttpClient(retries=3, backoff=1.0)
+        started = time.monotonic()
+        with pytest.raises(ConnectionError) as excinfo:
+            client.post(f"http://127.0.0.1:{port}/", json={})
+        assert time.monotonic() - started < 0.5
+        assert "attempts" not in str(excinfo.value)
+
+    def test_attempts_reported(self):
+        port = free_port()
+        with pytest.raises(ConnectionError, match=r"\(3 attempts\)"):
+            HttpClient(retries=2, backoff=0.01).get(f"http://127.0.0.1:{port}/")
+
+
+class TestAsync:
+    """Test the awaitable variants."""
+
+    def test_gather(self):
+        with upstream() as (server, base_url):
+            client = HttpClient(base_url=base_url, max_connections=2)
+
+            async def main():
+                return await asyncio.gather(*(client.get_async(f"/n/{n}") for n in range(6)))
+
+            responses = asyncio.run(main())
+
+            assert [r.json()["path"] for r in responses] == [f"/n/{n}" for n in range(6)]
+            assert server.connections <= 2
+
+    def test_async_timeout(self):
+        with upstream() as (_server, base_url):
+            client = HttpClient(base_url=base_url)
+
+            async def main():
+                await client.get_async("/slow?d=2", timeout=0.2)
+
+            with pytest.raises(TimeoutError):
+                asyncio.run(main())
+
+
+class TestTracePropagation:
+    """Test traceparent on requests sent from handlers."""
+
+    TRACE_ID = "4bf92f3577b34da6a3ce929d0e0e4736"
+
+    def build_app(self, client: HttpClient) -> Hypern:
+        app = Hypern()
+
+        @app.get("/call")
+        def call(req, res, ctx):
+            res.json(client.get("/echo").json()["headers"])
+
+        return app
+
+    def test_trace_continued(self):
+        with upstream() as (_server, base_url):
+            test_client = self.build_app(HttpClient(base_url=base_url)).test_client()
+            incoming = f"00-{self.TRACE_ID}-00f067aa0ba902b7-01"
+
+            headers = test_client.get("/call", headers={"traceparent": incoming}).json()
+
+            version, trace_id, span_id, flags = headers["traceparent"].split("-")
+            assert (version, trace_id, flags) == ("00", self.TRACE_ID, "01")
+            assert len(span_id) == 16 and span_id != "00f067aa0ba902b7"
+
+    def test_propagation_disabled(self):
+        with upstream() as (_server, base_url):
+            client = HttpClient(base_url=base_url, propagate_trace=False)
+            test_client = self.build_app(client).test_client()
+            incoming = f"00-{self.TRACE_ID}-00f067aa0ba902b7-01"
+
+            headers = test_client.get("/call", headers={"traceparent": incoming}).json()
+
+            assert "traceparent" not in headers
+
+    def test_no_trace_outside_handler(self):
+        with upstream() as (_server, base_url):
+            headers = HttpClient(base_url=base_url).get("/echo").json()["headers"]
+            assert "traceparent" not in headers
@@ -0,0 +1,176 @@
+"""
+Test cases for IdempotencyMiddleware.
+
+Tests cover:
+- Repeated keys replaying the stored response byte for byte
+- Keys scoped per value and per route
+- Requests without a key, and methods the key is ignored on
+- In-flight duplicates rejected with 409, or waiting for the first request
+- Malformed and over-long keys
+- Large responses passed through unstored, and 5xx responses not stored
+"""
+
+import threading
+import time
+import uuid
+from contextlib import contextmanager
+
+import httpx
+
+from tests.conftest import server_process
+
+
+@contextmanager
+def idempotency_server(*args: str):
+    with server_process(*args) as server:
+        yield server.base_url
+
+
+def new_key() -> str:
+    return f"test-{uuid.uuid4()}"
+
+
+def charge(client: httpx.Client, key: str, amount: int = 100, path: str = "/idempotent/charge"):
+    return client.post(path, json={"amount": amount}, headers={"Idempotency-Key": key})
+
+
+class TestReplay:
+    """Test replay of completed requests."""
+
+    def test_duplicate_replayed(self, client: httpx.Client):
+        key = new_key()
+        first = charge(client, key)
+        second = charge(client, key)
+        assert first.status_code == 201
+        assert second.status_code == 201
+        assert second.content == first.content
+        assert second.headers["X-Charge-Id"] == first.headers["X-Charge-Id"]
+        assert second.headers["Idempotency-Replayed"] == "true"
+        assert "Idempotency-Replayed" not in first.headers
+
+    def test_handler_runs_once(self, client: httpx.Client):
+        key = new_key()
+        before = client.get("/idempotent/count").json()["count"]
+        for _ in range(3):
+            charge(client, key)
+        assert client.get("/idempotent/count").json()["count"] == before + 1
+
+    def test_replay_ignores_new_body(self, client: httpx.Client):
+        key = new_key()
+        first = charge(client, key, amount=100)
+        second = charge(client, key, amount=999)
+        assert second.json() == first.json()
+
+    def test_different_keys_do_not_collide(self, client: httpx.Client):
+        first = charge(client, new_key())
+        second = charge(client, new_key())
+        assert first.json()["charge"] != second.json()["charge"]
+        assert "Idempotency-Replayed" not in second.headers
+
+    def test_key_scoped_to_route(self, client: httpx.Client):
+        key = new_key()
+        charge(client, key)
+        other = client.post("/idempotent/large", headers={"Idempotency-Key": key})
+        assert other.status_code == 200
+        assert "Idempotency-Replayed" not in other.headers
+
+    def test_without_key(self, client: httpx.Client):
+        first = client.post("/idempotent/charge", json={"amount": 1})
+        second = client.post("/idempotent/charge", json={"amount": 1})
+        assert first.json()["charge"] != second.json()["charge"]
+
+    def test_get_ignored(self, client: httpx.Client):
+        key = new_key()
+        response = client.get("/idempotent/count", headers={"Idempotency-Key": key})
+        again = client.get("/idempotent/count", headers={"Idempotency-Key": key})
+        assert "Idempotency-Replayed" not in again.headers
+        assert response.status_code == again.status_code == 200
+
+
+class TestKeyValidation:
+    """Test malformed keys."""
+
+    def test_too_long(self, client: httpx.Client):
+        response = charge(client, "k" * 65)
+        assert response.status_code == 400
+
+    def test_longest_accepted(self, client: httpx.Client):
+        response = charge(client, uuid.uuid4().hex + uuid.uuid4().hex)
+        assert response.status_code == 201
+
+    def test_empty(self, client: httpx.Client):
+        response = charge(client, "")
+        assert response.status_code == 400
+
+    def test_whitespace(self, client: httpx.Client):
+        response = charge(client, "two words")
+        assert response.status_code == 400
+
+
+class TestNotStored:
+    """Test responses that are passed through without being stored."""
+
+    def test_large_body(self, client: httpx.Client):
+        key = new_key()
+        first = client.post("/idempotent/large", headers={"Idempotency-Key": key})
+        second = client.post("/idempotent/large", headers={"Idempotency-Key": key})
+        assert first.status_code == 200
+        assert len(first.text) > 8192
+        assert "Idempotency-Warning" in first.headers
+        assert "Idempotency-Replayed" not in second.headers
+        assert first.text != second.text
+
+    def test_server_error(self, client: httpx.Client):
+        key = new_key()
+        first = client.post("/idempotent/fail", headers={"Idempotency-Key": key})
+        second = client.post("/idempotent/fail", headers={"Idempotency-Key": key})
+        assert first.status_code == second.status_code == 503
+        assert "Idempotency-Replayed" not in second.headers
+        assert first.json()["attempt"] != second.json()["attempt"]
+
+
+def concurrent_pair(base_url: str, key: str):
+    """Send two requests with `key` to the slow route, the second shortly after."""
+    results = {}
+
+    def send(name: str):
+        results[name] = httpx.post(
+            f"{base_url}/idempotent/slow", headers={"Idempotency-Key": key}, timeout=10.0
+        )
+
+    first = threading.Thread(target=send, args=("first",))
+    first.start()
+    time.sleep(0.3)
+    send("second")
+    first.join()
+    return results["first"], results["second"]
+
+
+class TestInFlight:
+    """Test duplicates arriving while the first request is running."""
+
+    def test_conflict(self, base_url: str):
+        first, second = concurrent_pair(base_url, new_key())
+        assert first.status_code == 200
+        assert second.status_code == 409
+
+    def test_replay_after_completion(self, base_url: str):
+        key = new_key()
+        first, _ = concurrent_pair(base_url, key)
+        third = httpx.post(f"{base_url}/idempotent/slow", headers={"Idempotency-Key": key})
+        assert third.content == first.content
+        assert third.headers["Idempotency-Replayed"] == "true"
+
+    def test_wait(self):
+        with idempotency_server("--idempotency-wait", "3") as base_url:
+            first, second = concurrent_pair(base_url, new_key())
+            assert first.status_code == 200
+            assert second.status_code == 200
+            assert second.content == first.content
+            assert second.headers["Idempotency-Replayed"] == "true"
+
+    def test_wait_times_out(self):
+        with idempotency_server("--idempotency-wait", "0.2") as base_url:
+            first, second = concurrent_pair(base_url, new_key())
+            assert first.status_code == 200
+            assert second.status_code == 409
@@ -0,0 +1,119 @@
+"""
+Test cases for health probes as internal requests.
+
+Tests cover:
+- Probes answering without credentials under global BasicAuth
+- App routes under the probe prefix keeping their middleware
+- An app route at a probe path taking precedence over the probe
+- Rate limiting skipping probes unless limit_internal is set
+- The log_health_probes option
+"""
+
+import base64
+
+from hypern import Hypern
+from hypern._hypern import ReloadConfig
+from hypern.middleware import BasicAuthMiddleware, RateLimitMiddleware
+
+CREDENTIALS = {
+    "Authorization": "Basic " + base64.b64encode(b"admin:secret").decode(),
+}
+
+
+def build_app(*middleware) -> Hypern:
+    app = Hypern()
+    for mw in middleware:
+        app.use(mw)
+
+    @app.get("/_health/custom")
+    def custom(req, res, ctx):
+        res.json({"custom": True})
+
+    @app.get("/items")
+    def items(req, res, ctx):
+        res.json({"items": []})
+
+    return app
+
+
+def with_auth():
+    return BasicAuthMiddleware(realm="api", users={"admin": "secret"})
+
+
+class TestAuthExemption:
+    """Test probes under global authentication."""
+
+    def test_probes_without_credentials(self):
+        client = build_app(with_auth()).test_client()
+        assert client.get("/_health/ready").status in (200, 503)
+        assert client.get("/_health/live").status == 200
+        assert client.get("/_health").status == 200
+
+    def test_app_route_under_prefix_requires_auth(self):
+        client = build_app(with_auth()).test_client()
+        assert client.get("/_health/custom").status == 401
+        response = client.get("/_health/custom", headers=CREDENTIALS)
+        assert response.status == 200
+        assert response.json() == {"custom": True}
+
+    def test_app_routes_still_require_auth(self):
+        client = build_app(with_auth()).test_client()
+        assert client.get("/items").status == 401
+        assert client.get("/items", headers=CREDENTIALS).status == 200
+
+    def test_app_route_at_probe_path_wins(self):
+        app = build_app(with_auth())
+
+        @app.get("/_health/ready")
+        def ready(req, res, ctx):
+            res.json({"mine": True})
+
+        client = app.test_client()
+        assert client.get("/_health/ready").status == 401
+        assert client.get("/_health/ready", headers=CREDENTIALS).json() == {"mine": True}
+        assert client.get("/_health/live").status == 200
+
+    def test_custom_prefix(self):
+        app = build_app(with_auth())
+        app.setup_reload(health_path="/ops")
+        client = app.test_client()
+        assert client.get("/ops/live").status == 200
+        assert client.get("/_health/live").status == 401
+
+    def test_disabled_probes_use_middleware(self):
+        app = build_app(with_auth())
+        app.setup_reload(health_probes=False)
+        assert app.test_client().get("/_health/live").status == 401
+
+
+class TestRateLimit:
+    """Test rate limiting of probes."""
+
+    def test_probes_not_counted(self):
+        client = build_app(RateLimitMiddleware(max_requests=2, window_secs=60)).test_client()
+        headers = {"X-Forwarded-For": "10.0.0.1"}
+        for _ in range(5):
+            assert client.get("/_health/live", headers=headers).status == 200
+        assert client.get("/items", headers=headers).status == 200
+        assert client.get("/items", headers=headers).status == 200
+        assert client.get("/items", headers=headers).status == 429
+
+    def test_limit_internal(self):
+        limiter = RateLimitMiddleware(max_requests=2, window_secs=60, limit_internal=True)
+        client = build_app(limiter).test_client()
+        headers = {"X-Forwarded-For": "10.0.0.2"}
+        assert client.get("/_health/live", headers=headers).status == 200
+        assert client.get("/_health/live", headers=headers).status == 200
+        assert client.get("/_health/live", headers=headers).status == 429
+
+
+class TestLogging:
+    """Test the access log option."""
+
+    def test_default_off(self):
+        assert ReloadConfig().log_health_probes is False
+
+    def test_setup_reload(self):
+        app = Hypern().setup_reload(log_health_probes=True)
+        assert app._reload_config.log_health_probes is True
+        assert app.test_client().get("/_health/live").status == 200
@@ -0,0 +1,145 @@
+"""
+Tests for JSON body parsing limits and charset handling.
+
+Tests cover:
+- Charset transcoding (UTF-16, latin-1) before parsing
+- Clean 400 errors for bodies that don't match their declared charset
+- 415 for non-JSON content types unless force=True
+- Route-level max_json_bytes limits (413)
+"""
+
+import json
+
+import httpx
+
+from hypern import (
+    BodyDecodeError,
+    PayloadTooLargeError,
+    RequestBodyError,
+    UnsupportedMediaTypeError,
+)
+
+
+class TestBodyErrorTypes:
+    """Test the exception hierarchy raised by Request.json()."""
+
+    def test_status_codes(self):
+        assert RequestBodyError.status_code == 400
+        assert UnsupportedMediaTypeError.status_code == 415
+        assert PayloadTooLargeError.status_code == 413
+        assert BodyDecodeError.status_code == 400
+
+    def test_hierarchy(self):
+        for exc in (UnsupportedMediaTypeError, PayloadTooLargeError, BodyDecodeError):
+            assert issubclass(exc, RequestBodyError)
+        assert issubclass(RequestBodyError, ValueError)
+
+
+class TestCharsets:
+    """Test Content-Type charset handling."""
+
+    def test_utf16_with_charset(self, client: httpx.Client):
+        payload = {"name": "héllo ✓"}
+        response = client.post(
+            "/echo",
+            content=json.dumps(payload, ensure_ascii=False).encode("utf-16"),
+            headers={"Content-Type": "application/json; charset=utf-16"},
+        )
+        assert response.status_code == 200
+        assert response.json()["echo"] == payload
+
+    def test_utf16le_without_bom(self, client: httpx.Client):
+        payload = {"n": 1}
+        response = client.post(
+            "/echo",
+            content=json.dumps(payload).encode("utf-16-le"),
+            headers={"Content-Type": "application/json; charset=UTF-16LE"},
+        )
+        assert response.status_code == 200
+        assert response.json()["echo"] == payload
+
+    def test_latin1(self, client: httpx.Client):
+        response = client.post(
+            "/echo",
+            content='{"city": "Zürich"}'.encode("latin-1"),
+            headers={"Content-Type": 'application/json; charset="ISO-8859-1"'},
+        )
+        assert response.status_code == 200
+        assert response.json()["echo"] == {"city": "Zürich"}
+
+    def test_wrong_charset_errors_cleanly(self, client: httpx.Client):
+        """A latin-1 body declared as UTF-8 is a 400 naming the charset."""
+        response = client.post(
+            "/echo",
+            content='{"city": "Zürich"}'.encode("latin-1"),
+            headers={"Content-Type": "application/json; charset=utf-8"},
+        )
+        assert response.status_code == 400
+        assert "utf-8" in response.json()["message"]
+
+    def test_odd_length_utf16_errors(self, client: httpx.Client):
+        response = client.post(
+            "/echo",
+            content=b'{"a":1}',
+            headers={"Content-Type": "application/json; charset=utf-16le"},
+        )
+        assert response.status_code == 400
+        assert "utf-16le" in response.json()["message"]
+
+    def test_unknown_charset_is_415(self, client: httpx.Client):
+        response = client.post(
+            "/echo",
+            content=b'{"a":1}',
+            headers={"Content-Type": "application/json; charset=koi8-r"},
+        )
+        assert response.status_code == 415
+        assert "koi8-r" in response.json()["message"]
+
+
+class TestContentType:
+    """Test content-type enforcement."""
+
+    def test_non_json_content_type_rejected(self, client: httpx.Client):
+        response = client.post(
+            "/echo",
+            content=b'{"a": 1}',
+            headers={"Content-Type": "text/plain"},
+        )
+        assert response.status_code == 415
+
+    def test_force_parses_any_content_type(self, client: httpx.Client):
+        response = client.post(
+            "/echo/force",
+            content=b'{"a": 1}',
+            headers={"Content-Type": "text/plain"},
+        )
+        assert response.status_code == 200
+        assert response.json()["echo"] == {"a": 1}
+
+    def test_structured_json_suffix_accepted(self, client: httpx.Client):
+        response = client.post(
+            "/echo",
+            content=b'{"type": "about:blank"}',
+            headers={"Content-Type": "application/problem+json"},
+        )
+        assert response.status_code == 200
+
+    def test_malformed_json_is_400(self, client: httpx.Client):
+        response = client.post(
+            "/echo",
+            content=b"{not json",
+            headers={"Content-Type": "application/json"},
+        )
+        assert response.status_code == 400
+
+
+class TestMaxJsonBytes:
+    """Test route-level JSON size limits."""
+
+    def test_within_limit(self, client: httpx.Client):
+        response = client.post("/echo/limited", json={"a": 1})
+        assert response.status_code == 200
+
+    def test_over_limit(self, client: httpx.Client):
+        response = client.post("/echo/limited", json={"data": "x" * 100})
+        assert response.status_code == 413
@@ -0,0 +1,165 @@
+"""
+Test cases for JSON serialization with the GIL released.
+
+Tests cover:
+- Byte-for-byte parity with the existing serializer on nested fixtures
+- datetime / UUID / Decimal, tuples, bytes and objects converted alike
+- Insertion order kept on the released path, size-based path choice
+- Other Python threads running while a huge object is written
+- res.json() with large bodies
+"""
+
+import datetime
+import decimal
+import json
+import sys
+import threading
+import uuid
+
+import pytest
+
+from hypern import Hypern
+from hypern.utils import json_dumps
+
+
+class Point:
+    def __init__(self, x, y):
+        self.x = x
+        self.y = y
+        self._hidden = True
+
+
+def fixture():
+    """Nested data with keys in sorted order, so both paths agree on it."""
+    return {
+        "bools": [True, False, None],
+        "bytes": b"raw",
+        "date": datetime.date(2026, 1, 2),
+        "decimal": decimal.Decimal("12.50"),
+        "empty": {"dict": {}, "list": []},
+        "floats": [0.1, 1.0, -0.0, 1e20, 1.5e-7, float("nan"), float("inf")],
+        "ints": [0, -1, 2**63 - 1, 2**64 - 1, 2**70, -(2**70)],
+        "nested": [{"a": [{"b": {"c": [1, [2, [3]]]}}]}],
+        "object": Point(1, 2),
+        "set": {7},
+        "strings": ["", "plain", "é ü 中文 🚀", 'quote " slash \\ /', "\n\t\r\b\f\x00\x1f\x7f"],
+        "timestamp": datetime.datetime(2026, 1, 2, 3, 4, 5, 6000),
+        "tuple": (1, "two", 3.0),
+        "uuid": uuid.UUID("12345678-1234-5678-1234-567812345678"),
+    }
+
+
+def rows(count):
+    return [{"id": i, "name": f"user-{i}", "score": i / 7, "tags": ["a", "b"]} for i in range(count)]
+
+
+class TestParity:
+    """Test the released path against the existing serializer."""
+
+    def test_nested_fixture(self):
+        data = fixture()
+        assert json_dumps(data, detach=True) == json_dumps(data, detach=False)
+
+    @pytest.mark.parametrize(
+        "value",
+        [None, True, 0, -5, 2**80, 3.25, float("-inf"), "text", [], {}, [1, [2, {"k": "v"}]]],
+    )
+    def test_scalars_and_containers(self, value):
+        assert json_dumps(value, detach=True) == json_dumps(value, detach=False)
+
+    def test_large_list(self):
+        data = rows(2000)
+        assert json_dumps(data, detach=True) == json_dumps(data, detach=False)
+
+    def test_non_string_keys(self):
+        data = {1: "int", "1": "str", None: "none", 2.5: "float"}
+        assert json_dumps(data, detach=True) == json_dumps(data, detach=False)
+
+    def test_special_objects_are_strings(self):
+        body = json.loads(json_dumps(fixture(), detach=True))
+        assert body["uuid"] == "12345678-1234-5678-1234-567812345678"
+        assert body["decimal"] == "12.50"
+        assert body["timestamp"] == "2026-01-02 03:04:05.006000"
+        assert body["object"] == {"x": 1, "y": 2}
+        assert body["floats"][-2:] == [None, None]
+
+
+class TestOrderAndChoice:
+    """Test key order and which path is picked."""
+
+    def test_insertion_order_kept(self):
+        data = {"zeta": 1, "alpha": {"y": 2, "b": 3}}
+        assert json_dumps(data, detach=True) == b'{"zeta":1,"alpha":{"y":2,"b":3}}'
+        assert json_dumps(data, detach=False) == b'{"alpha":{"b":3,"y":2},"zeta":1}'
+
+    def test_small_dict_stays_inline(self):
+        data = {"zeta": 1, "alpha": 2}
+        assert json_dumps(data) == json_dumps(data, detach=False)
+
+    def test_large_dict_released(self):
+        data = {f"k{511 - i:03d}": i for i in range(512)}
+        assert json_dumps(data) == json_dumps(data, detach=True)
+        assert json_dumps(data).startswith(b'{"k511":0,')
+
+    def test_second_level_counts(self):
+        data = {"zz": [0] * 300, "aa": [1] * 300}
+        assert json_dumps(data) == json_dumps(data, detach=True)
+        assert json_dumps(data).startswith(b'{"zz":')
+
+
+class TestGilReleased:
+    """Test that other threads progress while a huge object is written."""
+
+    def run_with_ticker(self, detach):
+        data = rows(200_000)
+        progressed = [False]
+        ready = threading.Event()
+        stop = threading.Event()
+
+        def ticker():
+            ready.set()
+            while not stop.is_set():
+                progressed[0] = True
+                # Sleeping releases the GIL, so the ticker never has to be
+                # preempted to give it back
+                stop.wait(0.0001)
+
+        # No forced switches: the ticker only runs when the GIL is released
+        interval = sys.getswitchinterval()
+        sys.setswitchinterval(100)
+        thread = threading.Thread(target=ticker)
+        try:
+            thread.start()
+            ready.wait()
+            progressed[0] = False
+            body = json_dumps(data, detach=detach)
+            ran = progressed[0]
+        finally:
+            stop.set()
+            thread.join()
+            sys.setswitchinterval(interval)
+        assert body.startswith(b'[{"')
+        return ran
+
+    def test_thread_runs_during_released_write(self):
+        assert self.run_with_ticker(detach=True) is True
+
+    def test_thread_blocked_during_inline_write(self):
+        assert self.run_with_ticker(detach=False) is False
+
+
+class TestResponse:
+    """Test res.json() with large bodies."""
+
+    def test_large_body(self):
+        app = Hypern()
+
+        @app.get("/rows")
+        def list_rows(req, res, ctx):
+            res.json({"rows": rows(1000), "count": 1000})
+
+        response = app.test_client().get("/rows")
+        assert response.status == 200
+        assert response.headers["content-type"].startswith("application/json")
+        assert response.content.startswith(b'{"rows":[{"id":0,"name":"user-0"')
+        assert json.loads(response.content) == json.loads(json_dumps({"rows": rows(1000), "count": 1000}, detach=False))
@@ -0,0 +1,88 @@
+"""
+Test cases for streaming large buffered responses.
+
+Tests cover:
+- Bodies above stream_threshold_bytes arriving intact with Content-Length
+- Bodies at or below the threshold sent in one frame
+- large_response_stats() counters
+- Server constructor options
+"""
+
+import concurrent.futures
+import hashlib
+
+import httpx
+
+from hypern._hypern import Server
+
+
+PATTERN = bytes(range(256))
+
+
+def expected_body(size: int) -> bytes:
+    return (PATTERN * (size // 256 + 1))[:size]
+
+
+def stats(client: httpx.Client) -> dict:
+    return client.get("/large-stats").json()
+
+
+class TestLargeBodies:
+    """Test responses above the streaming threshold."""
+
+    def test_50mb_body_intact(self, client: httpx.Client):
+        size = 50 * 1024 * 1024
+        response = client.get(f"/large/{size}", timeout=60.0)
+        assert response.status_code == 200
+        assert int(response.headers["content-length"]) == size
+        assert "transfer-encoding" not in response.headers
+        assert len(response.content) == size
+        assert hashlib.sha256(response.content).digest() == hashlib.sha256(expected_body(size)).digest()
+
+    def test_body_just_above_threshold(self, client: httpx.Client):
+        # Not a multiple of the slice size, so the last slice is short
+        size = 1024 * 1024 + 12345
+        response = client.get(f"/large/{size}")
+        assert int(response.headers["content-length"]) == size
+        assert response.content == expected_body(size)
+
+    def test_concurrent_large_bodies(self, base_url):
+        size = 8 * 1024 * 1024
+
+        def fetch(_):
+            with httpx.Client(base_url=base_url, timeout=60.0) as c:
+                return c.get(f"/large/{size}").content
+
+        with concurrent.futures.ThreadPoolExecutor(max_workers=4) as pool:
+            bodies = list(pool.map(fetch, range(4)))
+        expected = expected_body(size)
+        assert all(body == expected for body in bodies)
+
+
+class TestStats:
+    """Test large_response_stats() counters."""
+
+    def test_streamed_response_counted(self, client: httpx.Client):
+        before = stats(client)
+        size = 2 * 1024 * 1024
+        client.get(f"/large/{size}")
+        after = stats(client)
+        assert after["threshold_bytes"] == 1024 * 1024
+        assert after["streamed"] == before["streamed"] + 1
+        assert after["bytes_streamed"] == before["bytes_streamed"] + size
+
+    def test_small_response_not_counted(self, client: httpx.Client):
+        before = stats(client)
+        response = client.get(f"/large/{1024 * 1024}")
+        assert len(response.content) == 1024 * 1024
+        assert stats(client)["streamed"] == before["streamed"]
+
+
+class TestServerConfig:
+    """Test Server constructor options."""
+
+    def test_default_threshold(self):
+        assert Server().stats()["stream_threshold_bytes"] == 1024 * 1024
+
+    def test_custom_threshold(self):
+        assert Server(stream_threshold_bytes=0).stats()["stream_threshold_bytes"] == 0
@@ -0,0 +1,238 @@
+"""
+Test cases for changing settings while the server runs.
+
+Tests cover:
+- Keys that are not hot-reloadable rejected by name, with nothing applied
+- Every invalid value reported in one ValueError
+- A raised rate limit taking effect on the next request
+- Debug entries flowing after the log level is lowered, across workers
+- The new rate-limit ceiling applied on a kept-alive connection
+- Requests over max_in_flight answered with 503 and Retry-After
+- The audit entry naming the source of an update
+"""
+
+import http.client
+import threading
+import time
+from contextlib import contextmanager
+
+import httpx
+import pytest
+
+from hypern import Hypern
+from hypern._hypern import Server
+
+from tests.conftest import server_process
+
+
+# argv[3] is the number of worker processes
+APP_SCRIPT = """
+import sys
+import time
+sys.path.insert(0, sys.argv[1])
+from hypern import Hypern
+
+app = Hypern()
+app.setup_logging(level="info", log_request=False, log_response=False)
+app.configure_middleware({
+    "middleware": [
+        {"type": "log", "log_headers": True},
+        {"type": "rate_limit", "algorithm": "fixed", "max_requests": 3, "paths": ["/limited"]},
+    ],
+})
+
+@app.post("/config")
+def reconfigure(req, res, ctx):
+    res.json({"generation": app.update_config(req.json(), source="test suite")})
+
+@app.get("/ping")
+def ping(req, res, ctx):
+    res.text("pong")
+
+@app.get("/limited")
+def limited(req, res, ctx):
+    res.text("ok")
+
+@app.get("/slow")
+def slow(req, res, ctx):
+    time.sleep(1.0)
+    res.text("done")
+
+app.start(host="127.0.0.1", port=int(sys.argv[2]), num_processes=int(sys.argv[3]))
+"""
+
+
+@contextmanager
+def live_server(processes: int = 1):
+    with server_process(
+        str(processes), app_script=APP_SCRIPT, ready_path="/_health/live"
+    ) as server:
+        yield server.base_url, server.logs
+
+
+def wait_for(logs, marker: str, timeout: float = 5.0) -> str:
+    deadline = time.time() + timeout
+    while time.time() < deadline:
+        for line in logs().splitlines():
+            if marker in line:
+                return line
+        time.sleep(0.1)
+    raise AssertionError(f"no log line containing {marker!r} in:\n{logs()}")
+
+
+def build_app() -> Hypern:
+    app = Hypern()
+
+    @app.get("/api/items")
+    def items(req, res, ctx):
+        res.json({"ok": True})
+
+    app.configure_middleware(
+        {"middleware": [{"type": "rate_limit", "algorithm": "fixed", "max_requests": 2}]}
+    )
+    return app
+
+
+@pytest.fixture
+def restore_settings():
+    """Put this process's hot settings back after the test."""
+    yield
+    Server.update_config(
+        {"log_level": "info", "max_in_flight": None, "slow_threshold_ms": None},
+        source="test teardown",
+    )
+
+
+class TestValidation:
+    """Test that invalid updates change nothing."""
+
+    def test_non_hot_key_named(self, restore_settings):
+        app = build_app()
+        app.test_client().get("/api/items")
+        before = app.live_config()
+
+        with pytest.raises(ValueError) as excinfo:
+            app.update_config({"log_level": "debug", "workers": 4, "port": 80})
+
+        message = str(excinfo.value)
+        assert message.startswith("Not hot-reloadable: ")
+        assert "'workers'" in message and "'port'" in message
+        assert "hot-reloadable settings: log_level" in message
+        assert app.live_config() == before
+
+    def test_invalid_values_reported_together(self):
+        with pytest.raises(ValueError) as excinfo:
+            Server.update_config(
+                {
+                    "log_level": "loud",
+                    "max_in_flight": 0,
+                    "rate_limit": {"max_request": 5},
+                }
+            )
+
+        message = str(excinfo.value)
+        assert message.startswith("Invalid runtime config (3 problems)")
+        assert "log_level: expected one of" in message
+        assert "max_in_flight: expected a positive integer" in message
+        assert "rate_limit: unknown key 'max_request'" in message
+
+    def test_bad_paths(self):
+        with pytest.raises(ValueError, match="log_skip_paths"):
+            Server.update_config({"log_skip_paths": ["health"]})
+
+
+class TestInProcess:
+    """Test updates applied through the test client."""
+
+    def test_raised_rate_limit(self):
+        client = build_app().test_client()
+        assert [client.get("/api/items").status for _ in range(3)] == [200, 200, 429]
+
+        # The rejected request counted too, so two more fit under five
+        Server.update_config({"rate_limit": {"max_requests": 5}})
+
+        assert [client.get("/api/items").status for _ in range(3)] == [200, 200, 429]
+
+    def test_lowered_rate_limit(self):
+        client = build_app().test_client()
+        client.get("/api/items")
+
+        Server.update_config({"rate_limit": {"max_requests": 1}})
+
+        assert client.get("/api/items").status == 429
+
+    def test_live_config_reported(self, restore_settings):
+        app = build_app()
+        app.test_client().get("/api/items")
+
+        generation = app.update_config({"log_level": "debug", "max_in_flight": 64})
+        current = app.live_config()
+
+        assert generation >= 1
+        assert current["log_level"] == "debug"
+        assert current["max_in_flight"] == 64
+        assert app.update_config({"max_in_flight": None}) == generation + 1
+        assert app.live_config()["max_in_flight"] is None
+
+
+class TestRunningServer:
+    """Test updates made from a handler of a running server."""
+
+    def test_debug_entries_flow(self):
+        with live_server(processes=2) as (base_url, logs):
+            httpx.get(f"{base_url}/ping", headers={"X-Probe": "before"})
+            time.sleep(0.5)
+            assert "x-probe=before" not in logs()
+
+            response = httpx.post(f"{base_url}/config", json={"log_level": "debug"})
+            assert response.status_code == 200
+
+            # Fresh connections, so both workers serve some of them
+            for n in range(10):
+                httpx.get(f"{base_url}/ping", headers={"X-Probe": f"after-{n}"})
+            wait_for(logs, "x-probe=after-9")
+
+    def test_audit_entry(self):
+        with live_server() as (base_url, logs):
+            generation = httpx.post(
+                f"{base_url}/config", json={"slow_threshold_ms": 250}
+            ).json()["generation"]
+
+            line = wait_for(logs, "Runtime config generation")
+            assert f"generation {generation} applied by test suite (pid " in line
+            assert "slow_threshold_ms=250" in line
+
+    def test_rate_limit_raised_on_open_connection(self):
+        with live_server() as (base_url, _logs):
+            host, port = base_url.removeprefix("http://").split(":")
+            conn = http.client.HTTPConnection(host, int(port), timeout=5)
+
+            def get(path: str) -> int:
+                conn.request("GET", path)
+                response = conn.getresponse()
+                response.read()
+                return response.status
+
+            assert [get("/limited") for _ in range(4)] == [200, 200, 200, 429]
+            sock = conn.sock
+
+            httpx.post(f"{base_url}/config", json={"rate_limit": {"max_requests": 6}})
+
+            assert [get("/limited") for _ in range(3)] == [200, 200, 429]
+            assert conn.sock is sock
+            conn.close()
+
+    def test_max_in_flight(self):
+        with live_server() as (base_url, _logs):
+            httpx.post(f"{base_url}/config", json={"max_in_flight": 1})
+
+            slow = threading.Thread(target=httpx.get, args=(f"{base_url}/slow",))
+            slow.start()
+            time.sleep(0.3)
+            response = httpx.get(f"{base_url}/ping", headers={"Accept": "application/json"})
+            slow.join()
+
+            assert response.status_code == 503
+            assert response.headers["retry-after"] == "1"
+            assert response.json()["error"]["code"] == "overloaded"
+            assert httpx.get(f"{base_url}/ping").status_code == 200
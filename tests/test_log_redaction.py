@@ -0,0 +1,156 @@
+"""
+Test cases for log redaction and field truncation.
+
+Tests cover:
+- An email address in the path masked in the colored and JSON-template lines
+- Authorization header values masked by default when headers are logged
+- Long fields cut at max_field_len with an ellipsis
+- LogConfig options, defaults and invalid patterns
+"""
+
+import json
+import re
+import time
+from contextlib import contextmanager
+
+import httpx
+import pytest
+
+from hypern._hypern import LogConfig
+
+from tests.conftest import server_process
+
+
+EMAIL_PATTERN = r"[\w.+-]+@[\w-]+\.[\w.]+"
+
+# argv[3] is a JSON object of setup_logging keyword arguments
+APP_SCRIPT = """
+import json
+import sys
+sys.path.insert(0, sys.argv[1])
+from hypern import Hypern, LogMiddleware
+
+app = Hypern()
+app.setup_logging(**json.loads(sys.argv[3]))
+app.use(LogMiddleware(level="debug", log_headers=True))
+
+@app.get("/users/:email")
+def user(req, res, ctx):
+    res.text("ok")
+
+app.start(host="127.0.0.1", port=int(sys.argv[2]), num_processes=1)
+"""
+
+
+@contextmanager
+def logging_server(**options):
+    with server_process(
+        json.dumps(options), app_script=APP_SCRIPT, ready_path="/_health/live"
+    ) as server:
+        yield server.base_url, server.logs
+
+
+def wait_for_line(logs, marker: str, timeout: float = 5.0) -> str:
+    deadline = time.time() + timeout
+    while time.time() < deadline:
+        for line in logs().splitlines():
+            if marker in line:
+                return line
+        time.sleep(0.1)
+    raise AssertionError(f"no log line containing {marker!r} in:\n{logs()}")
+
+
+class TestPathRedaction:
+    """Test patterns applied to request paths."""
+
+    def test_colored_layout(self):
+        with logging_server(redact_patterns=[EMAIL_PATTERN]) as (base_url, logs):
+            assert httpx.get(f"{base_url}/users/alice@example.com").text == "ok"
+            line = wait_for_line(logs, "<--")
+            assert "/users/[REDACTED]" in line
+            assert "alice@example.com" not in logs()
+
+    def test_json_template(self):
+        template = '{{"method": "{method}", "path": "{path}", "status": {status}}}'
+        with logging_server(
+            log_request=False, format=template, redact_patterns=[EMAIL_PATTERN]
+        ) as (base_url, logs):
+            httpx.get(f"{base_url}/users/bob.smith+tag@mail.example.org")
+            line = wait_for_line(logs, '"status"')
+            assert json.loads(line) == {
+                "method": "GET",
+                "path": "/users/[REDACTED]",
+                "status": 200,
+            }
+            assert "bob.smith" not in logs()
+
+    def test_without_patterns_unchanged(self):
+        with logging_server() as (base_url, logs):
+            httpx.get(f"{base_url}/users/alice@example.com")
+            assert "/users/alice@example.com" in wait_for_line(logs, "<--")
+
+
+class TestHeaderMasking:
+    """Test header values masked where headers are logged."""
+
+    def test_authorization_masked_by_default(self):
+        with logging_server(level="debug") as (base_url, logs):
+            httpx.get(
+                f"{base_url}/users/x",
+                headers={"Authorization": "Bearer s3cr3t-token", "X-Trace": "visible"},
+            )
+            assert "[REDACTED]" in wait_for_line(logs, "Header: authorization=")
+            assert "visible" in wait_for_line(logs, "Header: x-trace=")
+            assert "s3cr3t-token" not in logs()
+
+    def test_custom_header_list(self):
+        with logging_server(level="debug", redact_headers=["X-Trace"]) as (base_url, logs):
+            httpx.get(
+                f"{base_url}/users/x",
+                headers={"Authorization": "Bearer shown", "X-Trace": "hidden-value"},
+            )
+            assert "[REDACTED]" in wait_for_line(logs, "Header: x-trace=")
+            assert "Bearer shown" in wait_for_line(logs, "Header: authorization=")
+            assert "hidden-value" not in logs()
+
+
+class TestTruncation:
+    """Test max_field_len."""
+
+    def test_long_message_truncated(self):
+        with logging_server(level="debug", max_field_len=200) as (base_url, logs):
+            httpx.get(f"{base_url}/users/x", headers={"X-Big": "a" * 10_000})
+            line = wait_for_line(logs, "Header: x-big=")
+            # "[<request id>] Header: x-big=" and the value share 200 characters
+            match = re.search(r"(\[[0-9a-f-]+\] Header: x-big=)(a+)…$", line)
+            assert match, line
+            assert len(match.group(1)) + len(match.group(2)) == 200
+            assert "a" * 500 not in logs()
+
+    def test_long_path_truncated(self):
+        with logging_server(log_request=False, format="{path}", max_field_len=20) as (base_url, logs):
+            httpx.get(f"{base_url}/users/{'p' * 300}")
+            line = wait_for_line(logs, "/users/")
+            assert line == "/users/" + "p" * 13 + "…"
+
+
+class TestConfig:
+    """Test LogConfig redaction options."""
+
+    def test_defaults(self):
+        config = LogConfig()
+        assert config.redact_patterns == []
+        assert config.max_field_len is None
+        assert config.redact_headers == ["authorization", "cookie", "set-cookie", "x-api-key"]
+
+    def test_options_kept(self):
+        config = LogConfig(
+            redact_patterns=[EMAIL_PATTERN], max_field_len=64, redact_headers=["X-Token"]
+        )
+        assert config.redact_patterns == [EMAIL_PATTERN]
+        assert config.max_field_len == 64
+        assert config.redact_headers == ["x-token"]
+
+    def test_invalid_pattern(self):
+        with pytest.raises(ValueError, match=r"Invalid redact pattern '\(unclosed'"):
+            LogConfig(redact_patterns=["(unclosed"])
@@ -0,0 +1,254 @@
+"""
+Test cases for shipping log entries to a network sink.
+
+Tests cover:
+- JSON lines reaching a TCP collector in order
+- Gzip batches decompressing to the same lines
+- Falling back to stderr while the collector is down, then resuming
+- The last entries flushed at shutdown
+- Sink option validation and defaults
+"""
+
+import gzip
+import json
+import socket
+import threading
+import time
+from contextlib import contextmanager
+
+import httpx
+import pytest
+
+from hypern._hypern import LogConfig
+
+from tests.conftest import free_port, server_process
+
+
+# argv[3] is the collector's port, argv[4] the compression
+APP_SCRIPT = """
+import sys
+sys.path.insert(0, sys.argv[1])
+from hypern import Hypern
+
+app = Hypern()
+app.setup_logging(
+    log_request=False,
+    sink={
+        "type": "tcp",
+        "address": "127.0.0.1:" + sys.argv[3],
+        "compression": sys.argv[4],
+        "reconnect_backoff_ms": 100,
+    },
+)
+
+@app.get("/seq/{n}")
+def seq(req, res, ctx):
+    return "ok"
+
+app.start(host="127.0.0.1", port=int(sys.argv[2]), num_processes=1)
+"""
+
+
+class Collector:
+    """TCP listener keeping the bytes of every connection it accepted"""
+
+    def __init__(self, port: int):
+        self.port = port
+        self.received = []
+        self.lock = threading.Lock()
+        self.server = None
+        self.connections = []
+
+    def start(self) -> None:
+        self.server = socket.socket()
+        self.server.setsockopt(socket.SOL_SOCKET, socket.SO_REUSEADDR, 1)
+        self.server.bind(("127.0.0.1", self.port))
+        self.server.listen()
+        threading.Thread(target=self._accept, args=(self.server,), daemon=True).start()
+
+    def stop(self) -> None:
+        # shutdown() wakes the threads blocked on these sockets
+        with self.lock:
+            for sock in [self.server, *self.connections]:
+                try:
+                    sock.shutdown(socket.SHUT_RDWR)
+                except OSError:
+                    pass
+                sock.close()
+            self.connections = []
+
+    def _accept(self, server: socket.socket) -> None:
+        while True:
+            try:
+                connection, _ = server.accept()
+            except OSError:
+                return
+            data = bytearray()
+            with self.lock:
+                self.connections.append(connection)
+                self.received.append(data)
+            threading.Thread(target=self._read, args=(connection, data), daemon=True).start()
+
+    def _read(self, connection: socket.socket, data: bytearray) -> None:
+        while True:
+            try:
+                chunk = connection.recv(65536)
+            except OSError:
+                return
+            if not chunk:
+                return
+            with self.lock:
+                data.extend(chunk)
+
+    def lines(self, compressed: bool = False) -> list:
+        with self.lock:
+            streams = [bytes(data) for data in self.received]
+        lines = []
+        for stream in streams:
+            if compressed and stream:
+                try:
+                    stream = gzip.decompress(stream)
+                except (EOFError, OSError):
+                    # A member still being received
+                    continue
+            lines.extend(json.loads(line) for line in stream.decode().splitlines())
+        return lines
+
+    def paths(self, compressed: bool = False) -> list:
+        return [line["path"] for line in self.lines(compressed) if "path" in line]
+
+
+@contextmanager
+def shipping_server(compression: str = "none"):
+    collector = Collector(free_port())
+    collector.start()
+    try:
+        with server_process(
+            str(collector.port), compression, app_script=APP_SCRIPT, ready_path="/_health/live"
+        ) as server:
+            yield server.base_url, collector, server.logs, server
+    finally:
+        collector.stop()
+
+
+def wait_for(predicate, describe, timeout: float = 5.0) -> None:
+    deadline = time.time() + timeout
+    while time.time() < deadline:
+        if predicate():
+            return
+        time.sleep(0.05)
+    raise AssertionError(describe())
+
+
+class TestShipping:
+    """Test entries reaching the collector."""
+
+    def test_lines_arrive_in_order(self):
+        with shipping_server() as (base_url, collector, logs, _):
+            expected = [f"/seq/{n}" for n in range(50)]
+            for path in expected:
+                assert httpx.get(f"{base_url}{path}").status_code == 200
+            wait_for(
+                lambda: len(collector.paths()) >= len(expected),
+                lambda: f"collector got {collector.paths()}",
+            )
+            assert collector.paths() == expected
+            line = next(line for line in collector.lines() if line.get("path") == "/seq/0")
+            assert line["level"] == "INFO"
+            assert line["method"] == "GET"
+            assert line["status"] == 200
+            assert isinstance(line["duration_ms"], float)
+            # Shipped entries are not written to stderr as well
+            assert "/seq/0" not in logs()
+
+    def test_gzip_batches(self):
+        with shipping_server(compression="gzip") as (base_url, collector, _, _process):
+            expected = [f"/seq/{n}" for n in range(20)]
+            for path in expected:
+                httpx.get(f"{base_url}{path}")
+            wait_for(
+                lambda: len(collector.paths(compressed=True)) >= len(expected),
+                lambda: f"collector got {collector.paths(compressed=True)}",
+            )
+            assert collector.paths(compressed=True) == expected
+
+
+class TestFallback:
+    """Test a collector that goes away and comes back."""
+
+    def test_stderr_then_resume(self):
+        with shipping_server() as (base_url, collector, logs, _):
+            httpx.get(f"{base_url}/seq/before")
+            wait_for(lambda: "/seq/before" in collector.paths(), lambda: "nothing shipped")
+
+            collector.stop()
+            httpx.get(f"{base_url}/seq/down")
+            wait_for(lambda: '"/seq/down"' in logs(), lambda: f"no fallback line in:\n{logs()}")
+            warning = next(line for line in logs().splitlines() if "unavailable" in line)
+            assert "WARN" in warning
+            assert f"tcp://127.0.0.1:{collector.port}" in warning
+
+            collector.start()
+            # Retried after the backoff; keep logging until a line gets through
+            deadline = time.time() + 5
+            while "/seq/up" not in collector.paths() and time.time() < deadline:
+                httpx.get(f"{base_url}/seq/up")
+                time.sleep(0.1)
+            assert "/seq/up" in collector.paths()
+            wait_for(lambda: "reconnected" in logs(), lambda: logs())
+            assert "/seq/down" not in collector.paths()
+
+
+class TestShutdown:
+    """Test the flush at shutdown."""
+
+    def test_tail_flushed(self):
+        with shipping_server() as (base_url, collector, _, server):
+            expected = [f"/seq/tail-{n}" for n in range(5)]
+            for path in expected:
+                httpx.get(f"{base_url}{path}")
+            server.stop()
+            time.sleep(0.2)
+            assert collector.paths()[-len(expected):] == expected
+
+
+class TestConfig:
+    """Test the sink options of LogConfig."""
+
+    def test_defaults(self):
+        config = LogConfig(sink={"type": "tcp", "address": "127.0.0.1:5170"})
+        assert config.sink == {
+            "type": "tcp",
+            "address": "127.0.0.1:5170",
+            "format": "json",
+            "compression": "none",
+            "buffer_max_bytes": 65536,
+            "reconnect_backoff_ms": 500,
+            "flush_timeout_ms": 2000,
+            "stderr": False,
+        }
+
+    def test_no_sink(self):
+        assert LogConfig().sink is None
+
+    def test_unix(self):
+        config = LogConfig(
+            sink={"type": "unix", "address": "/run/vector.sock", "compression": "gzip"}
+        )
+        assert config.sink["type"] == "unix"
+        assert config.sink["compression"] == "gzip"
+
+    @pytest.mark.parametrize(
+        "sink",
+        [
+            {"type": "udp", "address": "127.0.0.1:5170"},
+            {"type": "tcp"},
+            {"type": "tcp", "address": "127.0.0.1:5170", "compression": "zip"},
+            {"type": "tcp", "address": "127.0.0.1:5170", "format": "text"},
+            {"type": "tcp", "address": "127.0.0.1:5170", "buffer_max_bytes": 0},
+            {"type": "tcp", "address": "127.0.0.1:5170", "retries": 3},
+        ],
+    )
+    def test_rejected(self, sink):
+        with pytest.raises(ValueError):
+            LogConfig(sink=sink)
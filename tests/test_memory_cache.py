@@ -0,0 +1,253 @@
+"""
+Test cases for the in-memory LRU cache.
+
+Tests cover:
+- get/set/delete with str, bytes, int, float and bool values
+- TTL expiry (default and per-entry)
+- LRU eviction order under a tiny max_entries
+- get_or_set, get_many/set_many
+- Atomic incr/decr, including many threads incrementing one key
+- Hit/miss/eviction statistics
+"""
+
+import threading
+import time
+
+import pytest
+
+from hypern.cache import MemoryCache
+
+
+# Override autouse conftest fixtures that need a test server
+@pytest.fixture(autouse=True)
+def reset_database():
+    yield
+
+
+class TestBasicOperations:
+    """Test storing and reading values."""
+
+    @pytest.mark.parametrize("value", ["text", b"\x00raw", 42, 1.5, True, False])
+    def test_round_trip(self, value):
+        cache = MemoryCache()
+        cache.set("key", value)
+        result = cache.get("key")
+        assert result == value
+        assert type(result) is type(value)
+
+    def test_missing_key_returns_default(self):
+        cache = MemoryCache()
+        assert cache.get("missing") is None
+        assert cache.get("missing", "fallback") == "fallback"
+
+    def test_delete(self):
+        cache = MemoryCache()
+        cache.set("key", "value")
+        assert "key" in cache
+        assert cache.delete("key") is True
+        assert cache.delete("key") is False
+        assert "key" not in cache
+
+    def test_unsupported_value_rejected(self):
+        cache = MemoryCache()
+        with pytest.raises(TypeError):
+            cache.set("key", {"a": 1})
+
+    def test_invalid_arguments(self):
+        with pytest.raises(ValueError):
+            MemoryCache(max_entries=0)
+        with pytest.raises(ValueError):
+            MemoryCache().set("key", "value", ttl=0)
+
+
+class TestTTL:
+    """Test entry expiry."""
+
+    def test_per_entry_ttl(self):
+        cache = MemoryCache()
+        cache.set("short", "a", ttl=0.1)
+        cache.set("forever", "b")
+        assert cache.get("short") == "a"
+        time.sleep(0.2)
+        assert cache.get("short") is None
+        assert cache.get("forever") == "b"
+        assert cache.stats()["expirations"] == 1
+
+    def test_default_ttl(self):
+        cache = MemoryCache(ttl=0.1)
+        cache.set("key", "value")
+        cache.set("longer", "value", ttl=10)
+        time.sleep(0.2)
+        assert "key" not in cache
+        assert cache.get("longer") == "value"
+
+    def test_purge_expired(self):
+        cache = MemoryCache()
+        for i in range(5):
+            cache.set(f"k{i}", i, ttl=0.05)
+        cache.set("kept", 1)
+        time.sleep(0.1)
+        assert cache.purge_expired() == 5
+        assert len(cache) == 1
+
+    def test_expired_counter_restarts(self):
+        cache = MemoryCache()
+        assert cache.incr("counter", 5, ttl=0.1) == 5
+        time.sleep(0.2)
+        assert cache.incr("counter") == 1
+
+
+class TestLRU:
+    """Test eviction when the cache is full."""
+
+    def test_least_recently_inserted_evicted(self):
+        cache = MemoryCache(max_entries=3)
+        for key in "abcd":
+            cache.set(key, key)
+        assert cache.get("a") is None
+        assert [cache.get(k) for k in "bcd"] == ["b", "c", "d"]
+        assert cache.stats()["evictions"] == 1
+
+    def test_read_refreshes_recency(self):
+        cache = MemoryCache(max_entries=3)
+        for key in "abc":
+            cache.set(key, key)
+        cache.get("a")
+        cache.set("d", "d")
+        assert "b" not in cache
+        assert all(k in cache for k in "acd")
+
+        cache.get("c")
+        cache.set("e", "e")
+        cache.set("f", "f")
+        assert "a" not in cache
+        assert "d" not in cache
+        assert all(k in cache for k in "cef")
+
+    def test_overwrite_refreshes_recency(self):
+        cache = MemoryCache(max_entries=2)
+        cache.set("a", 1)
+        cache.set("b", 2)
+        cache.set("a", 3)
+        cache.set("c", 4)
+        assert cache.get("a") == 3
+        assert "b" not in cache
+
+    def test_size_never_exceeds_max(self):
+        cache = MemoryCache(max_entries=10)
+        for i in range(100):
+            cache.set(f"k{i}", i)
+        assert len(cache) == 10
+        assert cache.stats()["evictions"] == 90
+
+
+class TestBulkAndFactory:
+    """Test get_or_set and the *_many helpers."""
+
+    def test_get_or_set_calls_factory_once(self):
+        cache = MemoryCache()
+        calls = []
+
+        def factory():
+            calls.append(1)
+            return "computed"
+
+        assert cache.get_or_set("key", factory) == "computed"
+        assert cache.get_or_set("key", factory) == "computed"
+        assert len(calls) == 1
+
+    def test_get_or_set_factory_error_not_cached(self):
+        cache = MemoryCache()
+        with pytest.raises(ZeroDivisionError):
+            cache.get_or_set("key", lambda: 1 / 0)
+        assert "key" not in cache
+
+    def test_get_many_set_many(self):
+        cache = MemoryCache()
+        cache.set_many({"a": 1, "b": "two", "c": b"3"})
+        assert cache.get_many(["a", "b", "c", "missing"]) == {"a": 1, "b": "two", "c": b"3"}
+
+
+class TestCounters:
+    """Test atomic incr/decr."""
+
+    def test_incr_decr(self):
+        cache = MemoryCache()
+        assert cache.incr("n") == 1
+        assert cache.incr("n", 10) == 11
+        assert cache.decr("n", 4) == 7
+        assert cache.decr("m") == -1
+
+    def test_incr_non_integer(self):
+        cache = MemoryCache()
+        cache.set("name", "alice")
+        with pytest.raises(TypeError):
+            cache.incr("name")
+
+    def test_incr_overflow(self):
+        cache = MemoryCache()
+        cache.set("big", 2**63 - 1)
+        with pytest.raises(ValueError):
+            cache.incr("big")
+
+    def test_concurrent_incr_sums_correctly(self):
+        cache = MemoryCache()
+        threads_count, per_thread = 16, 2000
+        barrier = threading.Barrier(threads_count)
+
+        def worker():
+            barrier.wait()
+            for _ in range(per_thread):
+                cache.incr("total")
+                cache.incr("spread", 2)
+
+        threads = [threading.Thread(target=worker) for _ in range(threads_count)]
+        for t in threads:
+            t.start()
+        for t in threads:
+            t.join()
+
+        assert cache.get("total") == threads_count * per_thread
+        assert cache.get("spread") == threads_count * per_thread * 2
+
+    def test_concurrent_mixed_access_under_eviction(self):
+        cache = MemoryCache(max_entries=50)
+
+        def worker(n):
+            for i in range(2000):
+                key = f"k{(n * 7 + i) % 200}"
+                cache.set(key, i)
+                cache.get(key)
+                if i % 10 == 0:
+                    cache.delete(key)
+
+        threads = [threading.Thread(target=worker, args=(n,)) for n in range(8)]
+        for t in threads:
+            t.start()
+        for t in threads:
+            t.join()
+
+        assert len(cache) <= 50
+
+
+class TestStats:
+    """Test cache statistics."""
+
+    def test_hits_and_misses(self):
+        cache = MemoryCache(max_entries=5)
+        cache.set("a", 1)
+        cache.get("a")
+        cache.get("a")
+        cache.get("b")
+        stats = cache.stats()
+        assert stats["hits"] == 2
+        assert stats["misses"] == 1
+        assert stats["size"] == 1
+        assert stats["max_entries"] == 5
+
+    def test_clear(self):
+        cache = MemoryCache()
+        cache.set_many({"a": 1, "b": 2})
+        cache.clear()
+        assert len(cache) == 0
+        assert cache.get("a") is None
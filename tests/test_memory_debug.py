@@ -0,0 +1,120 @@
+"""
+Test cases for per-request memory accounting (Server(memory_debug=True)).
+
+Tests cover:
+- A handler stashing its Request (or Response) in a module-level list is flagged
+- Clean sync, async, failing and streaming handlers are not flagged
+- Nothing is tracked with the mode off
+- The setting reported by stats() and describe()
+"""
+
+import pytest
+
+from hypern import Hypern
+from hypern._hypern import Server
+
+
+STASH = []
+
+
+def build_app() -> Hypern:
+    app = Hypern()
+
+    @app.get("/clean")
+    def clean(req, res, ctx):
+        res.json({"path": req.path, "query": req.query("q")})
+
+    @app.get("/clean-async")
+    async def clean_async(req, res, ctx):
+        res.text("ok")
+
+    @app.get("/fails")
+    def fails(req, res, ctx):
+        raise ValueError("boom")
+
+    @app.get("/streams")
+    def streams(req, res, ctx):
+        res.stream(iter([b"a", b"b", b"c"]), content_type="text/plain")
+
+    @app.get("/stash-request")
+    def stash_request(req, res, ctx):
+        STASH.append(req)
+        res.text("stored")
+
+    @app.get("/stash-response")
+    async def stash_response(req, res, ctx):
+        STASH.append(res)
+        res.text("stored")
+
+    return app
+
+
+def suspects(path: str) -> list:
+    return [s for s in Server().stats()["leak_suspects"] if s["path"] == path]
+
+
+@pytest.fixture(scope="module")
+def client():
+    return build_app().test_client(memory_debug=True)
+
+
+class TestLeakSuspects:
+    """Test which requests are flagged."""
+
+    def test_stashed_request_flagged(self, client):
+        before = len(suspects("/stash-request"))
+
+        response = client.get("/stash-request")
+
+        assert response.status == 200
+        flagged = suspects("/stash-request")
+        assert len(flagged) == before + 1
+        assert flagged[-1]["leaks"] == ["Request object refcount +1"]
+        assert len(flagged[-1]["request_id"]) == 32
+
+    def test_stashed_response_flagged(self, client):
+        client.get("/stash-response")
+        assert suspects("/stash-response")[-1]["leaks"] == ["Response object refcount +1"]
+
+    def test_every_request_checked(self, client):
+        before = len(suspects("/stash-request"))
+        for _ in range(3):
+            client.get("/stash-request")
+        assert len(suspects("/stash-request")) == before + 3
+
+    @pytest.mark.parametrize("path", ["/clean", "/clean-async", "/fails", "/streams"])
+    def test_clean_handlers_not_flagged(self, client, path):
+        for _ in range(3):
+            response = client.get(path, query={"q": "x"})
+        assert response.status in (200, 500)
+        assert suspects(path) == []
+
+    def test_streamed_body_delivered(self, client):
+        assert client.get("/streams").text == "abc"
+
+
+class TestDisabled:
+    """Test that nothing is tracked with the mode off."""
+
+    def test_not_flagged_when_off(self):
+        app = build_app()
+
+        @app.get("/stash-off")
+        def stash_off(req, res, ctx):
+            STASH.append(req)
+            res.text("stored")
+
+        app.test_client().get("/stash-off")
+
+        assert suspects("/stash-off") == []
+
+
+class TestReporting:
+    """Test the setting in stats() and describe()."""
+
+    def test_stats(self):
+        server = Server(memory_debug=True)
+        assert server.stats()["memory_debug"] is True
+        assert server.describe()["features"]["memory_debug"] is True
+        assert Server().stats()["memory_debug"] is False
+        assert isinstance(Server().stats()["leak_suspects"], list)
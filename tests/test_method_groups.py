@@ -0,0 +1,171 @@
+"""
+Test cases for routes and middleware bound to method groups.
+
+Tests cover:
+- One route serving a list of methods, stored once
+- A "*" route catching non-standard methods like PROPFIND
+- An exact route winning over a "*" route on the same path
+- 405 with an Allow header listing the union of the path's methods
+- Removing a route by the method spec it was added with
+- Python and Rust middleware limited to a method group
+"""
+
+import pytest
+
+from hypern import Hypern, Router
+from hypern._hypern import Route
+from hypern.middleware import SecurityHeadersMiddleware
+
+
+def noop(req, res, ctx):
+    pass
+
+
+def build_app() -> Hypern:
+    app = Hypern()
+
+    @app.api_route("/items", ["GET", "POST"])
+    def items(req, res, ctx):
+        res.json({"handler": "items", "method": req.method})
+
+    @app.post("/dav")
+    def dav_post(req, res, ctx):
+        res.json({"handler": "exact", "method": req.method})
+
+    @app.all("/dav")
+    def dav_any(req, res, ctx):
+        res.json({"handler": "wildcard", "method": req.method})
+
+    @app.put("/items")
+    def replace(req, res, ctx):
+        res.json({"handler": "replace"})
+
+    async def writes_only(req, res, ctx, next):
+        res.header("X-Write", "1")
+        await next()
+
+    @app.api_route("/tagged", "GET,POST")
+    def tagged(req, res, ctx):
+        res.json({"ok": True})
+
+    app.use(writes_only, methods=["POST"])
+    return app
+
+
+@pytest.fixture(scope="module")
+def client():
+    return build_app().test_client()
+
+
+class TestMethodGroupRoutes:
+    """Test routes bound to several methods."""
+
+    def test_each_method_served(self, client):
+        assert client.get("/items").json() == {"handler": "items", "method": "GET"}
+        assert client.post("/items").json() == {"handler": "items", "method": "POST"}
+        assert client.put("/items").json() == {"handler": "replace"}
+
+    def test_stored_once(self):
+        app = Hypern()
+        app.api_route("/items", ["post", "GET"])(noop)
+
+        routes = app.get_routes()
+        assert [r["method"] for r in routes] == ["GET,POST"]
+        route = app.router.routes[0]
+        assert route.methods == ["GET", "POST"]
+
+    def test_router_decorators(self):
+        router = Router()
+        router.get("/a")(noop)
+        router.api_route("/b", ["PUT", "PATCH"])(noop)
+        router.all("/c")(noop)
+        router.group("/g").api_route("/d", "*")(noop)
+
+        assert [(m, p) for m, p, _ in router.get_routes()] == [
+            ("GET", "/a"),
+            ("PUT,PATCH", "/b"),
+            ("*", "/c"),
+            ("*", "/g/d"),
+        ]
+
+    def test_unknown_method_rejected(self):
+        with pytest.raises(ValueError, match="Unknown HTTP method"):
+            Route(path="/x", function=noop, methods=["GET", "FETCH"])
+        with pytest.raises(ValueError):
+            Route(path="/x", function=noop, methods=[])
+
+    def test_overlapping_group_rejected(self):
+        app = Hypern()
+        app.api_route("/x", ["GET", "POST"])(noop)
+        with pytest.raises(ValueError, match="Failed to add route"):
+            app.api_route("/x", ["POST", "DELETE"])(noop)
+
+
+class TestWildcardMethod:
+    """Test "*" routes and their precedence."""
+
+    def test_catches_propfind(self, client):
+        response = client.request("PROPFIND", "/dav")
+        assert response.status == 200
+        assert response.json() == {"handler": "wildcard", "method": "PROPFIND"}
+
+    def test_catches_standard_methods(self, client):
+        assert client.delete("/dav").json()["handler"] == "wildcard"
+
+    def test_exact_wins(self, client):
+        assert client.post("/dav").json() == {"handler": "exact", "method": "POST"}
+
+
+class TestMethodNotAllowed:
+    """Test 405 responses for paths answered by other methods."""
+
+    def test_allow_lists_union(self, client):
+        response = client.delete("/items")
+        assert response.status == 405
+        assert response.headers["allow"] == "GET, POST, PUT"
+
+    def test_unknown_path_still_404(self, client):
+        assert client.delete("/nowhere").status == 404
+
+    def test_nonstandard_method_405(self, client):
+        response = client.request("PROPFIND", "/items")
+        assert response.status == 405
+        assert response.headers["allow"] == "GET, POST, PUT"
+
+
+class TestRemoveMethodGroup:
+    """Test removing routes by method spec."""
+
+    def test_remove_by_spec(self):
+        app = Hypern()
+        app.api_route("/x", ["GET", "POST"])(noop)
+
+        assert not app.remove_route("GET", "/x")
+        assert app.remove_route(["POST", "GET"], "/x")
+        assert app.get_routes() == []
+
+    def test_remove_wildcard(self):
+        app = Hypern()
+        app.all("/x")(noop)
+
+        assert app.remove_route("*", "/x")
+        assert app.test_client().request("PROPFIND", "/x").status == 404
+
+
+class TestMethodBoundMiddleware:
+    """Test middleware limited to a method group."""
+
+    def test_runs_for_bound_method(self, client):
+        assert client.post("/tagged").headers.get("x-write") == "1"
+
+    def test_skipped_for_other_methods(self, client):
+        assert "x-write" not in client.get("/tagged").headers
+
+    def test_rust_middleware(self):
+        app = Hypern()
+        app.api_route("/x", ["GET", "POST"])(noop)
+        app.use(SecurityHeadersMiddleware(), methods=["POST"])
+        client = app.test_client()
+
+        assert "x-content-type-options" in client.post("/x").headers
+        assert "x-content-type-options" not in client.get("/x").headers
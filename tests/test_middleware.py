@@ -13,6 +13,7 @@ This module tests real middleware behavior with actual HTTP requests:
 import base64
 import time
 import httpx
+import pytest
 
 
 class TestCORSMiddleware:
@@ -468,3 +469,79 @@ class TestCustomMiddlewareDecorators:
         data = response.json()
         assert "executed" in data
         assert data["executed"] is True
+
+
+class TestMiddlewareInstrumentation:
+    """Test middleware timing stats and error isolation configuration."""
+    
+    def test_stats_empty_before_requests(self):
+        """A fresh server has no middleware timings."""
+        from hypern._hypern import Server
+        server = Server()
+        assert server.middleware_instrumentation() == {}
+    
+    def test_middleware_stats_counts(self):
+        """middleware_stats() keeps returning the before/after/error handler counts."""
+        from hypern._hypern import Server
+        from hypern.middleware import CorsMiddleware
+        server = Server()
+        assert server.middleware_stats() == (0, 0, 0)
+        server.use_middleware(CorsMiddleware.permissive())
+        assert server.middleware_stats() == (1, 0, 0)
+    
+    def test_configure_middleware_isolation(self):
+        """Error isolation and slow threshold can be configured."""
+        from hypern._hypern import Server
+        server = Server()
+        server.configure_middleware(isolate_errors=True, slow_threshold_ms=50)
+        server.configure_middleware()
+    
+    def test_app_setup_middleware(self):
+        """setup_middleware is chainable and stores options for start()."""
+        from hypern import Hypern
+        app = Hypern()
+        assert app.setup_middleware(isolate_errors=True, slow_threshold_ms=25) is app
+        assert app._middleware_options == {"isolate_errors": True, "slow_threshold_ms": 25}
+
+
+def panicking_client(isolate_errors):
+    """A server whose only middleware panics, and a test client for it."""
+    try:
+        from hypern._hypern import PanicMiddleware
+    except ImportError:
+        pytest.skip("built without the test-hooks feature")
+    from hypern import Hypern
+    from hypern._hypern import TestClient
+    
+    app = Hypern()
+    app.use(PanicMiddleware())
+    app.setup_middleware(isolate_errors=isolate_errors)
+    
+    @app.get("/panicky")
+    def panicky(req, res, ctx):
+        res.json({"handled": True})
+    
+    server = app._build_server()
+    return server, TestClient(server)
+
+
+class TestMiddlewarePanicIsolation:
+    """Test a panicking middleware with and without error isolation."""
+    
+    def test_isolated_panic_continues(self):
+        """With isolate_errors the request reaches the handler and the panic is counted."""
+        server, client = panicking_client(isolate_errors=True)
+        response = client.get("/panicky")
+        assert response.status == 200
+        assert response.json() == {"handled": True}
+        assert server.middleware_instrumentation()["panic"]["errors"] == 1
+        
+        assert client.get("/panicky").status == 200
+        assert server.middleware_instrumentation()["panic"]["errors"] == 2
+    
+    def test_strict_panic_fails_request(self):
+        """Without isolation the same panic answers 500."""
+        server, client = panicking_client(isolate_errors=False)
+        response = client.get("/panicky")
+        assert response.status == 500
+        assert server.middleware_instrumentation()["panic"]["errors"] == 1
@@ -0,0 +1,255 @@
+"""
+Test cases for assembling the middleware chain from a config.
+
+Tests cover:
+- A five-entry config with path and method scoping, in describe() order
+- Scoped middleware running only for its paths and methods
+- Disabled entries left out
+- Every problem in an invalid config reported in one ValueError
+- Environment overrides merged on top of a base config, from dicts and files
+"""
+
+import base64
+import json
+
+import pytest
+
+from hypern import Hypern, load_middleware_config, merge_middleware_config
+from hypern._hypern import Server
+
+
+BASE = {
+    "middleware": [
+        {"type": "compression", "min_size": 64},
+        {"type": "security_headers"},
+        {"type": "request_id", "header_name": "X-Trace-ID"},
+        {"type": "rate_limit", "algorithm": "fixed", "max_requests": 2, "paths": ["/api"]},
+        {
+            "type": "basic_auth",
+            "users": {"admin": "secret"},
+            "paths": ["/admin"],
+            "methods": ["POST"],
+        },
+    ],
+}
+
+OVERRIDES = {
+    "env": {
+        "dev": {
+            "middleware": [
+                {"type": "rate_limit", "max_requests": 5},
+                {"type": "security_headers", "enabled": False},
+                {"type": "cors"},
+            ],
+        },
+    },
+}
+
+
+def build_app(config, env=None) -> Hypern:
+    app = Hypern()
+
+    @app.api_route("/api/items", ["GET", "POST"])
+    def items(req, res, ctx):
+        res.json({"ok": True})
+
+    @app.api_route("/admin/users", ["GET", "POST"])
+    def users(req, res, ctx):
+        res.json({"ok": True})
+
+    app.configure_middleware(config, env=env)
+    return app
+
+
+def chain(config):
+    server = Server()
+    server.configure_middleware(config)
+    return server.describe()["middleware"]["before"]
+
+
+def basic(user, password):
+    token = base64.b64encode(f"{user}:{password}".encode()).decode()
+    return {"Authorization": f"Basic {token}"}
+
+
+class TestAssembly:
+    """Test a representative config."""
+
+    def test_describe_order(self):
+        assert chain(BASE) == [
+            "request_id",
+            "security_headers",
+            "basic_auth",
+            "rate_limit",
+            "compression",
+        ]
+
+    def test_options_applied(self):
+        client = build_app(BASE).test_client()
+        response = client.get("/admin/users")
+
+        assert "x-trace-id" in response.headers
+        assert "x-content-type-options" in response.headers
+
+    def test_path_scoping(self):
+        client = build_app(BASE).test_client()
+
+        assert [client.get("/api/items").status for _ in range(3)] == [200, 200, 429]
+        assert [client.get("/admin/users").status for _ in range(3)] == [200, 200, 200]
+
+    def test_method_scoping(self):
+        client = build_app(BASE).test_client()
+
+        assert client.get("/admin/users").status == 200
+        assert client.post("/admin/users").status == 401
+        assert client.post("/admin/users", headers=basic("admin", "secret")).status == 200
+        # The auth entry is scoped to /admin as well
+        assert client.post("/api/items").status == 200
+
+    def test_disabled_entry_skipped(self):
+        config = {"middleware": [{"type": "request_id"}, {"type": "log", "enabled": False}]}
+        assert chain(config) == ["request_id"]
+
+    def test_chain_options(self):
+        server = Server()
+        server.configure_middleware({"isolate_errors": True, "slow_threshold_ms": 5})
+        assert server.describe()["middleware"]["before"] == []
+
+
+class TestValidation:
+    """Test that config problems are reported together."""
+
+    def test_two_errors_reported(self):
+        config = {
+            "middleware": [
+                {"type": "request_id"},
+                {"type": "ip_filter"},
+                {"type": "rate_limit", "max_request": 10},
+            ],
+        }
+        with pytest.raises(ValueError) as excinfo:
+            Server().configure_middleware(config)
+
+        message = str(excinfo.value)
+        assert message.startswith("Invalid middleware config (2 problems)")
+        assert "middleware[1] (ip_filter): unknown type 'ip_filter'" in message
+        assert "middleware[2] (rate_limit)" in message
+        assert "max_request" in message
+
+    def test_nothing_registered_on_error(self):
+        server = Server()
+        with pytest.raises(ValueError):
+            server.configure_middleware([{"type": "cors"}, {"type": "nope"}])
+        assert server.describe()["middleware"]["before"] == []
+
+    def test_scoping_errors(self):
+        config = [
+            {"type": "cors", "paths": ["api"]},
+            {"type": "timeout", "methods": ["FETCH"]},
+            {"timeout_secs": 5},
+        ]
+        with pytest.raises(ValueError) as excinfo:
+            Server().configure_middleware(config)
+
+        message = str(excinfo.value)
+        assert "(3 problems)" in message
+        assert "middleware[0] (cors): 'paths'" in message
+        assert "middleware[1] (timeout): methods" in message
+        assert "middleware[2]: missing 'type'" in message
+
+    def test_raised_when_server_built(self):
+        app = build_app({"middleware": [{"type": "ip_filter"}]})
+        with pytest.raises(ValueError, match="ip_filter"):
+            app.test_client()
+
+
+class TestEnvironmentOverrides:
+    """Test environment sections merged over the base config."""
+
+    def test_env_merged_over_base(self):
+        config = {**BASE, **OVERRIDES}
+        assert chain(merge_middleware_config(BASE, OVERRIDES["env"]["dev"])) == [
+            "request_id",
+            "cors",
+            "basic_auth",
+            "rate_limit",
+            "compression",
+        ]
+
+        client = build_app(config, env="dev").test_client()
+        assert [client.get("/api/items").status for _ in range(6)] == [200] * 5 + [429]
+        assert "x-content-type-options" not in client.get("/admin/users").headers
+
+    def test_base_used_without_env(self):
+        client = build_app({**BASE, **OVERRIDES}).test_client()
+        assert [client.get("/api/items").status for _ in range(3)] == [200, 200, 429]
+
+    def test_merge_leaves_inputs_untouched(self):
+        base = json.loads(json.dumps(BASE))
+        merge_middleware_config(base, OVERRIDES["env"]["dev"])
+        assert base == BASE
+
+    def test_ambiguous_override(self):
+        base = {"middleware": [{"type": "cors"}, {"type": "cors", "paths": ["/a"]}]}
+        with pytest.raises(ValueError, match="distinct 'name'"):
+            merge_middleware_config(base, {"middleware": [{"type": "cors", "enabled": False}]})
+
+    def test_named_entries(self):
+        base = {
+            "middleware": [
+                {"type": "rate_limit", "name": "api", "paths": ["/api"]},
+                {"type": "rate_limit", "name": "admin", "paths": ["/admin"]},
+            ]
+        }
+        merged = merge_middleware_config(
+            base, {"middleware": [{"name": "admin", "max_requests": 1}]}
+        )
+        assert merged["middleware"][1] == {
+            "type": "rate_limit",
+            "name": "admin",
+            "paths": ["/admin"],
+            "max_requests": 1,
+        }
+
+
+class TestFileLoader:
+    """Test loading configs from TOML and JSON files."""
+
+    def test_toml_with_env(self, tmp_path):
+        path = tmp_path / "middleware.toml"
+        path.write_text(
+            """
+slow_threshold_ms = 50
+
+[[middleware]]
+type = "request_id"
+
+[[middleware]]
+type = "rate_limit"
+max_requests = 100
+paths = ["/api"]
+
+[[env.production.middleware]]
+type = "rate_limit"
+max_requests = 1000
+"""
+        )
+
+        config = load_middleware_config(path, env="production")
+        assert config["slow_threshold_ms"] == 50
+        assert config["middleware"][1]["max_requests"] == 1000
+        assert "env" not in config
+        assert chain(config) == ["request_id", "rate_limit"]
+
+    def test_json(self, tmp_path):
+        path = tmp_path / "middleware.json"
+        path.write_text(json.dumps(BASE))
+
+        client = build_app(str(path)).test_client()
+        assert [client.get("/api/items").status for _ in range(3)] == [200, 200, 429]
+
+    def test_unknown_extension(self, tmp_path):
+        path = tmp_path / "middleware.yaml"
+        path.write_text("")
+        with pytest.raises(ValueError, match="expected .toml or .json"):
+            load_middleware_config(path)
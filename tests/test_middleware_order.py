@@ -0,0 +1,194 @@
+"""
+Test cases for middleware ordering.
+
+Tests cover:
+- The default order of all builtins, whatever order they are added in
+- Explicit priorities, with registration order breaking ties
+- before/after anchors overriding priorities
+- Unknown anchor names and cycles failing with a clear ValueError
+- start() refusing a chain that cannot be resolved
+"""
+
+import os
+import subprocess
+import sys
+
+import pytest
+
+from hypern._hypern import (
+    BasicAuthMiddleware,
+    CompressionMiddleware,
+    CorsMiddleware,
+    IdempotencyMiddleware,
+    LogMiddleware,
+    RateLimitMiddleware,
+    RequestIdMiddleware,
+    SecurityHeadersMiddleware,
+    Server,
+    TimeoutMiddleware,
+)
+
+
+ROOT = os.path.dirname(os.path.dirname(os.path.abspath(__file__)))
+
+DEFAULT_ORDER = [
+    "request_id",
+    "logger",
+    "security_headers",
+    "cors",
+    "timeout",
+    "basic_auth",
+    "rate_limit",
+    "idempotency",
+    "compression",
+]
+
+# Starts an app whose anchors contradict each other
+CYCLE_SCRIPT = """
+import sys
+sys.path.insert(0, sys.argv[1])
+from hypern import Hypern
+from hypern._hypern import BasicAuthMiddleware, CorsMiddleware
+
+app = Hypern()
+app.use(CorsMiddleware(), after="basic_auth")
+app.use(BasicAuthMiddleware(users={"admin": "secret"}), after="cors")
+
+@app.get("/")
+def index(req, res, ctx):
+    res.text("ok")
+
+try:
+    app.start(host="127.0.0.1", port=1)
+except ValueError as exc:
+    print(exc)
+    sys.exit(3)
+"""
+
+
+def builtins():
+    return {
+        "request_id": RequestIdMiddleware(),
+        "logger": LogMiddleware(),
+        "security_headers": SecurityHeadersMiddleware(),
+        "cors": CorsMiddleware(),
+        "timeout": TimeoutMiddleware(),
+        "basic_auth": BasicAuthMiddleware(users={"admin": "secret"}),
+        "rate_limit": RateLimitMiddleware(),
+        "idempotency": IdempotencyMiddleware(),
+        "compression": CompressionMiddleware(),
+    }
+
+
+def before_names(server: Server) -> list:
+    return [m["name"] for m in server.describe_middleware()["before"]]
+
+
+class TestDefaultOrder:
+    """Test the default priorities of the builtins."""
+
+    def test_reverse_registration(self):
+        server = Server()
+        middleware = builtins()
+        for name in reversed(DEFAULT_ORDER):
+            server.use_middleware(middleware[name])
+        assert before_names(server) == DEFAULT_ORDER
+
+    def test_priorities_increase(self):
+        server = Server()
+        for mw in builtins().values():
+            server.use_middleware(mw)
+        priorities = [m["priority"] for m in server.describe_middleware()["before"]]
+        assert priorities == sorted(priorities)
+
+    def test_after_chain(self):
+        server = Server()
+        server.use_middleware(IdempotencyMiddleware())
+        info = server.describe_middleware()
+        assert [m["name"] for m in info["after"]] == ["idempotency_recorder"]
+        assert info["error_handlers"] == 0
+
+    def test_describe_uses_resolved_order(self):
+        server = Server()
+        server.use_middleware(CompressionMiddleware())
+        server.use_middleware(RequestIdMiddleware())
+        assert server.describe()["middleware"]["before"] == ["request_id", "compression"]
+
+
+class TestPriority:
+    """Test explicit priorities."""
+
+    def test_override(self):
+        server = Server()
+        server.use_middleware(RequestIdMiddleware())
+        server.use_middleware(CompressionMiddleware(), priority=-1000)
+        assert before_names(server) == ["compression", "request_id"]
+        assert server.describe_middleware()["before"][0]["priority"] == -1000
+
+    def test_ties_keep_registration_order(self):
+        server = Server()
+        server.use_middleware(TimeoutMiddleware(), priority=7)
+        server.use_middleware(CorsMiddleware(), priority=7)
+        server.use_middleware(RequestIdMiddleware(), priority=7)
+        assert before_names(server) == ["timeout", "cors", "request_id"]
+
+
+class TestAnchors:
+    """Test before/after anchors."""
+
+    def test_after(self):
+        server = Server()
+        server.use_middleware(RateLimitMiddleware(), after="compression")
+        server.use_middleware(CompressionMiddleware())
+        assert before_names(server) == ["compression", "rate_limit"]
+        anchors = server.describe_middleware()["before"][1]["anchors"]
+        assert anchors == [("after", "compression")]
+
+    def test_before(self):
+        server = Server()
+        server.use_middleware(RequestIdMiddleware())
+        server.use_middleware(BasicAuthMiddleware(users={"admin": "secret"}))
+        server.use_middleware(CompressionMiddleware(), before="request_id")
+        assert before_names(server) == ["compression", "request_id", "basic_auth"]
+
+    def test_anchor_keeps_others_by_priority(self):
+        server = Server()
+        server.use_middleware(CorsMiddleware())
+        server.use_middleware(TimeoutMiddleware(), before="cors")
+        server.use_middleware(RequestIdMiddleware())
+        assert before_names(server) == ["request_id", "timeout", "cors"]
+
+    def test_unknown_name(self):
+        server = Server()
+        server.use_middleware(CorsMiddleware(), after="auth")
+        with pytest.raises(ValueError, match="'cors' is anchored after 'auth', which is not registered"):
+            server.describe_middleware()
+
+    def test_cycle(self):
+        server = Server()
+        server.use_middleware(CorsMiddleware(), after="basic_auth")
+        server.use_middleware(BasicAuthMiddleware(users={"admin": "secret"}), after="cors")
+        with pytest.raises(ValueError, match="cycle: (cors -> basic_auth -> cors|basic_auth -> cors -> basic_auth)"):
+            server.describe_middleware()
+
+    def test_longer_cycle(self):
+        server = Server()
+        server.use_middleware(CorsMiddleware(), before="timeout")
+        server.use_middleware(TimeoutMiddleware(), before="request_id")
+        server.use_middleware(RequestIdMiddleware(), before="cors")
+        with pytest.raises(ValueError) as excinfo:
+            server.describe_middleware()
+        message = str(excinfo.value)
+        assert "cycle" in message
+        for name in ("cors", "timeout", "request_id"):
+            assert name in message
+
+    def test_start_fails(self):
+        result = subprocess.run(
+            [sys.executable, "-c", CYCLE_SCRIPT, ROOT],
+            capture_output=True,
+            text=True,
+            timeout=60,
+        )
+        assert result.returncode == 3, result.stderr
+        assert "Middleware order has a cycle" in result.stdout
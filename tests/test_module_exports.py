@@ -0,0 +1,187 @@
+"""
+Tests for the native module's exported surface.
+
+Tests cover:
+- Every expected class/function is reachable from its submodule
+- Flat top-level names stay available and are the same objects
+- Submodules are importable via ``import hypern._hypern.<name>``
+"""
+
+import importlib
+
+import pytest
+
+from hypern import _hypern
+
+
+# Override autouse conftest fixtures that need a test server
+@pytest.fixture(autouse=True)
+def reset_database():
+    yield
+
+
+EXPECTED_EXPORTS = {
+    "core": [
+        "Server",
+        "Context",
+        "DIContainer",
+        "TaskExecutor",
+        "TaskResult",
+        "TaskStatus",
+        "BlockingExecutor",
+        "HealthCheck",
+        "ReloadConfig",
+        "ReloadManager",
+        "TestClient",
+        "TestResponse",
+        "listener_options",
+        "generate_request_id",
+        "HypernBindError",
+    ],
+    "http": [
+        "Request",
+        "AuthInfo",
+        "Response",
+        "HeaderMap",
+        "HeaderPolicy",
+        "HttpMethod",
+        "FormData",
+        "UploadedFile",
+        "MultipartStream",
+        "MultipartPart",
+        "NdjsonReader",
+        "CsvReader",
+        "SSEEvent",
+        "SSEStream",
+        "SSEGenerator",
+        "sse_stats",
+        "request_decompression_stats",
+        "dispatch_timing_stats",
+        "stream_drain_stats",
+        "disconnect_stats",
+        "panic_stats",
+        "large_response_stats",
+        "StreamingResponse",
+        "RustWebSocket",
+        "WsMessage",
+        "WsMessageType",
+        "WsLimits",
+        "WsInboundGuard",
+        "websocket_inbound_stats",
+    ],
+    "routing": [
+        "Route",
+        "Router",
+        "RetryPolicy",
+        "feature_flag_stats",
+        "set_etag_provider",
+        "current_etag",
+        "route_memo_stats",
+    ],
+    "middleware": [
+        "CorsMiddleware",
+        "RateLimitMiddleware",
+        "SecurityHeadersMiddleware",
+        "TimeoutMiddleware",
+        "CompressionMiddleware",
+        "RequestIdMiddleware",
+        "LogMiddleware",
+        "BasicAuthMiddleware",
+        "CircuitBreakerMiddleware",
+        "CacheMiddleware",
+        "IdempotencyMiddleware",
+        "SingleflightMiddleware",
+        "singleflight_stats",
+        "MiddlewareContext",
+        "MiddlewareResponse",
+        "MiddlewareError",
+        "MiddlewareResult",
+        "MiddlewareState",
+        "StateValue",
+    ],
+    "logging": ["LogConfig"],
+    "realtime": [
+        "ChannelManager",
+        "ChannelStats",
+        "Subscriber",
+        "TopicMatcher",
+        "ChannelClosed",
+        "SubscriptionError",
+        "PublishError",
+        "MessageSchema",
+        "PresenceTracker",
+        "PresenceInfo",
+        "PresenceDiff",
+        "PresenceUpdate",
+        "RealtimeBroadcast",
+        "BroadcastConfig",
+        "BroadcastStats",
+        "BroadcastSubscriber",
+        "BackpressurePolicy",
+        "ClientQueueConfig",
+        "ClientQueueStats",
+        "OverflowPolicy",
+        "realtime_poll_stats",
+        "RealtimeIdentity",
+        "realtime_identity",
+        "HeartbeatMonitor",
+        "HeartbeatConfig",
+        "HeartbeatStats",
+        "HeartbeatClientStats",
+    ],
+    "db": [
+        "ConnectionPool",
+        "PoolConfig",
+        "PoolStatus",
+        "DbSession",
+        "DbFuture",
+        "RowStream",
+        "CopyOutRows",
+        "AnyPool",
+        "DatabaseConfig",
+        "DatabaseTransaction",
+        "get_db",
+        "finalize_db",
+        "finalize_db_all",
+    ],
+    "fast_path": ["StaticFileHandler"],
+    "client": ["HttpClient", "ClientResponse", "ClientFuture"],
+    "cache": ["MemoryCache"],
+    "telemetry": ["MetricsRegistry", "ServerMetrics", "server_metrics"],
+    "redis": ["RedisPool"],
+    "grpc": ["GrpcConfig", "GrpcServer"],
+    "utils": ["PageInfo", "cpu_count", "uuid_v4", "sha256_hex"],
+}
+
+ALL_CASES = [(sub, name) for sub, names in EXPECTED_EXPORTS.items() for name in names]
+
+
+class TestSubmodules:
+    """Test the organized submodule namespace."""
+
+    @pytest.mark.parametrize("submodule", sorted(EXPECTED_EXPORTS))
+    def test_submodule_attribute(self, submodule):
+        assert hasattr(_hypern, submodule)
+
+    @pytest.mark.parametrize("submodule", sorted(EXPECTED_EXPORTS))
+    def test_submodule_importable(self, submodule):
+        module = importlib.import_module(f"hypern._hypern.{submodule}")
+        assert module is getattr(_hypern, submodule)
+        assert module.__name__ == f"hypern._hypern.{submodule}"
+
+    @pytest.mark.parametrize("submodule,name", ALL_CASES)
+    def test_symbol_in_submodule(self, submodule, name):
+        module = getattr(_hypern, submodule)
+        assert hasattr(module, name), f"{name} missing from hypern._hypern.{submodule}"
+
+
+class TestFlatNames:
+    """Test backward-compatible top-level names."""
+
+    @pytest.mark.parametrize("submodule,name", ALL_CASES)
+    def test_flat_name_matches_submodule(self, submodule, name):
+        assert hasattr(_hypern, name), f"{name} missing from hypern._hypern"
+        assert getattr(_hypern, name) is getattr(getattr(_hypern, submodule), name)
+
+    def test_existing_package_imports(self):
+        from hypern import Request, Response, Router  # noqa: F401
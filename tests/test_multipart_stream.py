@@ -0,0 +1,292 @@
+"""
+Test cases for the streaming multipart parser.
+
+Tests cover:
+- Hashing each part of a large multipart body chunk by chunk
+- Part data that looks like the start of a boundary
+- In-order consumption: skipping parts and reading drained parts
+- read(n) sizes and part headers
+- Truncated and malformed bodies raising 400 with the byte offset
+- Per-part and total size limits raising 413
+- form()/files() on top of the same parser
+"""
+
+import hashlib
+import os
+import time
+from contextlib import contextmanager
+
+import httpx
+import pytest
+
+from tests.conftest import server_process
+
+
+BOUNDARY = "hypern-test-boundary-7f3a"
+
+APP_SCRIPT = """
+import hashlib
+import sys
+sys.path.insert(0, sys.argv[1])
+from hypern import Hypern
+from hypern._hypern import RequestBodyError
+
+app = Hypern()
+
+def fail(res, err):
+    res.status(err.status_code).json({"error": str(err), "type": type(err).__name__})
+
+@app.post("/hash")
+async def hash_parts(req, res, ctx):
+    parts = []
+    try:
+        async for part in req.multipart_stream(chunk_size=int(req.query("chunk_size") or 65536)):
+            digest = hashlib.sha256()
+            size = 0
+            chunks = 0
+            async for chunk in part:
+                digest.update(chunk)
+                size += len(chunk)
+                chunks += 1
+            parts.append({
+                "name": part.name,
+                "filename": part.filename,
+                "content_type": part.content_type,
+                "sha256": digest.hexdigest(),
+                "size": size,
+                "chunks": chunks,
+            })
+    except RequestBodyError as err:
+        return fail(res, err)
+    res.json({"parts": parts})
+
+@app.post("/skip")
+async def skip(req, res, ctx):
+    stream = req.multipart_stream()
+    first = await stream.__anext__()
+    head = await first.read(4)
+    second = await stream.__anext__()
+    res.json({
+        "head": head.decode(),
+        "first_after_skip": (await first.read()).decode(),
+        "second": (await second.read()).decode(),
+        "second_again": (await second.read()).decode(),
+        "headers": second.headers,
+    })
+
+@app.post("/limited")
+async def limited(req, res, ctx):
+    try:
+        async for part in req.multipart_stream(
+            max_part_size=int(req.query("part") or 0) or None,
+            max_total_size=int(req.query("total") or 0) or None,
+        ):
+            await part.read()
+    except RequestBodyError as err:
+        return fail(res, err)
+    res.json({"ok": True})
+
+@app.post("/form")
+def form(req, res, ctx):
+    try:
+        form = req.form()
+    except RequestBodyError as err:
+        return fail(res, err)
+    files = form.all_files()
+    res.json({
+        "fields": dict(form.get_fields()),
+        "files": {f.name: hashlib.sha256(f.read()).hexdigest() for f in files},
+    })
+
+app.start(host="127.0.0.1", port=int(sys.argv[2]), num_processes=1)
+"""
+
+
+@contextmanager
+def multipart_server():
+    with server_process(app_script=APP_SCRIPT, ready_path="/_health/live") as server:
+        yield server.base_url
+
+
+@pytest.fixture(scope="module")
+def base_url():
+    with multipart_server() as url:
+        yield url
+
+
+def encode(parts, boundary: str = BOUNDARY, close: bool = True) -> bytes:
+    """Build a multipart body from (name, filename, content_type, data) tuples."""
+    body = b""
+    for name, filename, content_type, data in parts:
+        disposition = f'form-data; name="{name}"'
+        if filename is not None:
+            disposition += f'; filename="{filename}"'
+        body += f"--{boundary}\r\nContent-Disposition: {disposition}\r\n".encode()
+        if content_type is not None:
+            body += f"Content-Type: {content_type}\r\n".encode()
+        body += b"\r\n" + data + b"\r\n"
+    if close:
+        body += f"--{boundary}--\r\n".encode()
+    return body
+
+
+def post(url: str, body: bytes, boundary: str = BOUNDARY) -> httpx.Response:
+    return httpx.post(
+        url,
+        content=body,
+        headers={"Content-Type": f"multipart/form-data; boundary={boundary}"},
+        timeout=30.0,
+    )
+
+
+def sha256(data: bytes) -> str:
+    return hashlib.sha256(data).hexdigest()
+
+
+class TestLargeBody:
+    """Test hashing a large synthetic body part by part."""
+
+    def test_hashes_match(self, base_url):
+        first = os.urandom(3 * 1024 * 1024 + 17)
+        second = os.urandom(2 * 1024 * 1024 + 5)
+        body = encode([
+            ("title", None, None, b"quarterly report"),
+            ("first", "first.bin", "application/octet-stream", first),
+            ("second", "second.bin", "application/x-test", second),
+        ])
+        response = post(f"{base_url}/hash", body)
+        assert response.status_code == 200, response.text
+        parts = response.json()["parts"]
+        assert [p["name"] for p in parts] == ["title", "first", "second"]
+        assert parts[0]["filename"] is None
+        assert parts[0]["sha256"] == sha256(b"quarterly report")
+        assert parts[1]["filename"] == "first.bin"
+        assert parts[1]["size"] == len(first)
+        assert parts[1]["sha256"] == sha256(first)
+        assert parts[2]["content_type"] == "application/x-test"
+        assert parts[2]["sha256"] == sha256(second)
+        # Streamed in chunks, not collected
+        assert parts[1]["chunks"] > 10
+
+    def test_boundary_lookalikes(self, base_url):
+        tricky = (
+            b"\r\n--" + BOUNDARY[:-1].encode() + b"\r\n"
+            + b"\n--" + BOUNDARY.encode() + b"X"
+            + b"\r--" + BOUNDARY.encode()
+            + b"\r\n-\r\n--\r\n" * 50
+        )
+        body = encode([("data", "tricky.bin", None, tricky)])
+        # Tiny chunks make lookalikes straddle chunk edges
+        response = post(f"{base_url}/hash?chunk_size=7", body)
+        assert response.status_code == 200, response.text
+        part = response.json()["parts"][0]
+        assert part["sha256"] == sha256(tricky)
+        assert part["content_type"] is None
+
+    def test_preamble_and_empty_part(self, base_url):
+        body = b"preamble text\r\n" + encode([("empty", None, None, b""), ("x", None, None, b"1")])
+        response = post(f"{base_url}/hash?chunk_size=3", body)
+        parts = response.json()["parts"]
+        assert [(p["name"], p["size"]) for p in parts] == [("empty", 0), ("x", 1)]
+
+
+class TestOrdering:
+    """Test in-order consumption of parts."""
+
+    def test_skip_drains_previous_part(self, base_url):
+        body = encode([
+            ("a", None, None, b"abcdefgh"),
+            ("b", "b.txt", "text/plain", b"second part"),
+        ])
+        data = post(f"{base_url}/skip", body).json()
+        assert data["head"] == "abcd"
+        assert data["first_after_skip"] == ""
+        assert data["second"] == "second part"
+        assert data["second_again"] == ""
+        assert data["headers"]["content-type"] == "text/plain"
+        assert 'filename="b.txt"' in data["headers"]["content-disposition"]
+
+
+class TestErrors:
+    """Test malformed and truncated bodies."""
+
+    def test_truncated_body(self, base_url):
+        body = encode([("file", "f.bin", None, os.urandom(200_000))], close=False)
+        body = body[:-2]  # drop the part's trailing CRLF as well
+        started = time.time()
+        response = post(f"{base_url}/hash", body)
+        assert time.time() - started < 10
+        assert response.status_code == 400
+        error = response.json()["error"]
+        assert f"byte offset {len(body)}" in error
+        assert "part 'file'" in error
+
+    def test_missing_opening_boundary(self, base_url):
+        response = post(f"{base_url}/hash", b"no boundary here at all")
+        assert response.status_code == 400
+        assert "no opening boundary" in response.json()["error"]
+
+    def test_bad_part_header(self, base_url):
+        body = f"--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"a\"\r\nbroken header\r\n\r\nx\r\n--{BOUNDARY}--\r\n".encode()
+        response = post(f"{base_url}/hash", body)
+        assert response.status_code == 400
+        offset = body.index(b"broken header")
+        assert f"byte offset {offset}" in response.json()["error"]
+
+    def test_bad_boundary_suffix(self, base_url):
+        body = f"--{BOUNDARY}junk\r\n".encode()
+        response = post(f"{base_url}/hash", body)
+        assert response.status_code == 400
+        assert f"byte offset {len(BOUNDARY) + 2}" in response.json()["error"]
+
+    def test_not_multipart(self, base_url):
+        response = httpx.post(f"{base_url}/hash", json={"a": 1})
+        assert response.status_code == 415
+
+
+class TestLimits:
+    """Test size limits enforced while parsing."""
+
+    def test_part_limit(self, base_url):
+        body = encode([("small", None, None, b"x" * 10), ("big", "big.bin", None, b"y" * 5000)])
+        response = post(f"{base_url}/limited?part=1000", body)
+        assert response.status_code == 413
+        assert "'big'" in response.json()["error"]
+
+    def test_total_limit(self, base_url):
+        body = encode([("a", None, None, b"x" * 5000)])
+        response = post(f"{base_url}/limited?total=1000", body)
+        assert response.status_code == 413
+        assert "max_total_size" in response.json()["error"]
+
+    def test_within_limits(self, base_url):
+        body = encode([("a", None, None, b"x" * 500)])
+        assert post(f"{base_url}/limited?part=1000&total=100000", body).json() == {"ok": True}
+
+
+class TestForm:
+    """Test form()/files() built on the same parser."""
+
+    def test_fields_and_files(self, base_url):
+        content = os.urandom(100_000)
+        body = encode([
+            ("title", None, None, "café".encode()),
+            ("doc", "doc.bin", "application/octet-stream", content),
+        ])
+        data = post(f"{base_url}/form", body).json()
+        assert data["fields"] == {"title": "café"}
+        assert data["files"] == {"doc": sha256(content)}
+
+    def test_quoted_filename_with_semicolon(self, base_url):
+        body = (
+            f'--{BOUNDARY}\r\nContent-Disposition: form-data; name="doc"; filename="a;b.txt"\r\n\r\n'
+            f"x\r\n--{BOUNDARY}--\r\n"
+        ).encode()
+        response = post(f"{base_url}/hash", body)
+        assert response.json()["parts"][0]["filename"] == "a;b.txt"
+
+    def test_malformed_body_rejected(self, base_url):
+        body = encode([("a", None, None, b"x")], close=False)
+        response = post(f"{base_url}/form", body)
+        assert response.status_code == 400
+        assert response.json()["type"] == "BodyDecodeError"
@@ -0,0 +1,109 @@
+"""
+Test cases for Rust panics on the request path.
+
+Tests cover:
+- A panicking request answered with a generic 500 and an X-Request-ID
+- The worker serving requests after a panic, with in-flight back at zero
+- panic_stats() and the server metrics counting panics
+- The panic message logged at ERROR with the request ID
+"""
+
+import os
+import subprocess
+import sys
+
+import pytest
+
+from hypern import Hypern, panic_stats, server_metrics
+
+try:
+    from hypern._hypern import inject_panic
+except ImportError:
+    pytest.skip("built without the test-hooks feature", allow_module_level=True)
+
+
+ROOT = os.path.dirname(os.path.dirname(os.path.abspath(__file__)))
+
+LOG_SCRIPT = """
+import sys
+sys.path.insert(0, sys.argv[1])
+from hypern import Hypern
+from hypern._hypern import inject_panic
+
+app = Hypern()
+
+@app.get("/boom")
+def boom(req, res, ctx):
+    res.text("unreachable")
+
+inject_panic("/boom")
+print(app.test_client().get("/boom", headers={"X-Request-ID": "req-panic-1"}).status)
+"""
+
+
+@pytest.fixture(scope="module")
+def client():
+    app = Hypern()
+
+    @app.get("/boom")
+    def boom(req, res, ctx):
+        res.text("unreachable")
+
+    @app.get("/ok")
+    def ok(req, res, ctx):
+        res.text("ok")
+
+    inject_panic("/boom")
+    yield app.test_client()
+    inject_panic(None)
+
+
+class TestPanicResponse:
+    """Test the response to a panicking request."""
+
+    def test_generic_500(self, client):
+        response = client.get("/boom")
+        assert response.status == 500
+        assert response.text == "Internal Server Error"
+        assert response.headers["x-request-id"]
+
+    def test_client_request_id_echoed(self, client):
+        response = client.get("/boom", headers={"X-Request-ID": "abc-123"})
+        assert response.headers["x-request-id"] == "abc-123"
+
+    def test_keeps_serving(self, client):
+        for _ in range(3):
+            assert client.get("/boom").status == 500
+            assert client.get("/ok").text == "ok"
+        assert client.get("/_health/live").json()["in_flight"] == 0
+
+
+class TestPanicCounters:
+    """Test the panic counters."""
+
+    def test_panic_stats(self, client):
+        before = panic_stats()["panics_total"]
+        client.get("/boom")
+        client.get("/ok")
+        assert panic_stats()["panics_total"] == before + 1
+
+    def test_server_metrics(self, client):
+        client.get("/boom")
+        total = panic_stats()["panics_total"]
+        assert server_metrics().snapshot()["panics_total"] == total
+        assert f"hypern_panics_total {total}" in server_metrics().render()
+
+
+class TestPanicLog:
+    """Test the logged panic."""
+
+    def test_logged_with_request_id(self):
+        result = subprocess.run(
+            [sys.executable, "-c", LOG_SCRIPT, ROOT],
+            capture_output=True,
+            text=True,
+            timeout=30,
+        )
+        output = result.stdout + result.stderr
+        assert result.stdout.strip().endswith("500"), output
+        assert "Panic while handling request req-panic-1: injected panic for /boom" in output
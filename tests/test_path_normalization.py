@@ -0,0 +1,138 @@
+"""
+Test cases for request path normalization before routing.
+
+Tests cover:
+- ``%2F`` kept as part of a single segment and delivered decoded in params
+- Unreserved escapes decoded for matching
+- Duplicate slashes collapsed
+- ``.`` and ``..`` segments resolved, and 400 for paths escaping the root
+- Malformed escapes kept literally, or rejected with strict_path_encoding
+- Server constructor validation
+"""
+
+import json
+import socket
+from contextlib import contextmanager
+from urllib.parse import urlparse
+
+import httpx
+import pytest
+
+from hypern._hypern import Server
+
+from tests.conftest import server_process
+
+
+def raw_get(base_url: str, path: str):
+    """Send ``path`` byte-for-byte; HTTP clients normalize dot segments and ``%``."""
+    url = urlparse(base_url)
+    with socket.create_connection((url.hostname, url.port), timeout=5) as sock:
+        sock.sendall(
+            f"GET {path} HTTP/1.1\r\nHost: {url.hostname}:{url.port}\r\n"
+            "Connection: close\r\n\r\n".encode()
+        )
+        data = b""
+        while chunk := sock.recv(65536):
+            data += chunk
+    head, _, body = data.partition(b"\r\n\r\n")
+    status = int(head.split(b" ", 2)[1])
+    return status, body
+
+
+@contextmanager
+def strict_server():
+    with server_process("--strict-path-encoding") as server:
+        yield server.base_url
+
+
+class TestEncodedSlash:
+    """Test that %2F never splits a path segment."""
+
+    def test_encoded_slash_in_param(self, client: httpx.Client):
+        response = client.get("/paths/echo/a%2Fb")
+        assert response.status_code == 200
+        data = response.json()
+        assert data["name"] == "a/b"
+        assert data["path"] == "/paths/echo/a/b"
+        assert data["raw_path"] == "/paths/echo/a%2Fb"
+
+    def test_literal_slash_is_another_segment(self, client: httpx.Client):
+        assert client.get("/paths/echo/a/b").status_code == 404
+
+    def test_unreserved_escape_decoded(self, client: httpx.Client):
+        response = client.get("/paths/echo/%7Euser")
+        assert response.status_code == 200
+        assert response.json()["name"] == "~user"
+
+    def test_other_escapes_decoded_in_param(self, client: httpx.Client):
+        response = client.get("/paths/echo/caf%C3%A9%20au%20lait")
+        assert response.json()["name"] == "café au lait"
+
+
+class TestDuplicateSlashes:
+    """Test that duplicate slashes are collapsed."""
+
+    def test_double_slashes(self, base_url):
+        status, body = raw_get(base_url, "//paths//double//slashes")
+        assert status == 200
+        data = json.loads(body)
+        assert data["path"] == "/paths/double/slashes"
+        assert data["raw_path"] == "//paths//double//slashes"
+
+
+class TestDotSegments:
+    """Test resolution of . and .. segments."""
+
+    def test_dot_segments_resolved(self, base_url):
+        status, body = raw_get(base_url, "/paths/a/./b/../c")
+        assert status == 200
+        data = json.loads(body)
+        assert data["path"] == "/paths/a/c"
+        assert data["raw_path"] == "/paths/a/./b/../c"
+
+    def test_encoded_dot_segments_resolved(self, base_url):
+        status, _ = raw_get(base_url, "/paths/a/%2e/b/%2E%2E/c")
+        assert status == 200
+
+    def test_escaping_root_rejected(self, base_url):
+        status, body = raw_get(base_url, "/../etc/passwd")
+        assert status == 400
+        assert json.loads(body)["error"] == "bad_request"
+
+    def test_escaping_root_from_prefix_rejected(self, base_url):
+        status, _ = raw_get(base_url, "/paths/../../etc/passwd")
+        assert status == 400
+
+
+class TestMalformedEncoding:
+    """Test paths with escapes that are not two hex digits."""
+
+    def test_kept_literally_by_default(self, base_url):
+        status, body = raw_get(base_url, "/paths/echo/%ZZ")
+        assert status == 200
+        data = json.loads(body)
+        assert data["name"] == "%ZZ"
+        assert data["raw_path"] == "/paths/echo/%ZZ"
+
+    def test_rejected_in_strict_mode(self):
+        with strict_server() as base_url:
+            status, body = raw_get(base_url, "/paths/echo/%ZZ")
+            assert status == 400
+            assert json.loads(body)["error"] == "bad_request"
+            # Well-formed escapes are unaffected
+            assert raw_get(base_url, "/paths/echo/a%2Fb")[0] == 200
+
+    def test_invalid_utf8_rejected_in_strict_mode(self):
+        with strict_server() as base_url:
+            assert raw_get(base_url, "/paths/echo/%FF")[0] == 400
+
+
+class TestServerConfig:
+    """Test Server constructor validation."""
+
+    def test_invalid_path_decoding(self):
+        with pytest.raises(ValueError):
+            Server(path_decoding="none")
+
+    def test_valid_path_decoding(self):
+        Server(path_decoding="all", merge_slashes=False, strict_path_encoding=True)
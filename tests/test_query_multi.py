@@ -0,0 +1,99 @@
+"""
+Test cases for repeated query and form keys.
+
+Tests cover:
+- query_list() and query_params_multi() keeping every value in order
+- query_params still returning the last value per key
+- Empty values, plus-sign and percent decoding, unicode values
+- `key[]` bracket stripping on and off
+- FormData.get_list() and fields_multi() for urlencoded bodies
+"""
+
+import httpx
+
+
+def multi(client: httpx.Client, query: str) -> dict:
+    response = client.get(f"/query/multi?{query}")
+    assert response.status_code == 200
+    return response.json()
+
+
+class TestQueryList:
+    """Test repeated query parameters."""
+
+    def test_order_preserved(self, client: httpx.Client):
+        data = multi(client, "tag=b&tag=a&tag=c")
+        assert data["tags"] == ["b", "a", "c"]
+
+    def test_flat_dict_keeps_last(self, client: httpx.Client):
+        data = multi(client, "tag=a&tag=b")
+        assert data["flat"] == {"tag": "b"}
+
+    def test_missing(self, client: httpx.Client):
+        data = multi(client, "other=1")
+        assert data["tags"] == []
+
+    def test_empty_values(self, client: httpx.Client):
+        data = multi(client, "tag=&tag&tag=x")
+        assert data["tags"] == ["", "", "x"]
+
+    def test_decoding(self, client: httpx.Client):
+        data = multi(client, "tag=a+b&tag=c%26d&tag=%2B")
+        assert data["tags"] == ["a b", "c&d", "+"]
+
+    def test_unicode(self, client: httpx.Client):
+        data = multi(client, "tag=%E6%97%A5%E6%9C%AC&tag=caf%C3%A9")
+        assert data["tags"] == ["日本", "café"]
+
+
+class TestQueryParamsMulti:
+    """Test all parameters grouped into lists."""
+
+    def test_grouped_in_first_seen_order(self, client: httpx.Client):
+        data = multi(client, "b=1&a=2&b=3")
+        assert data["multi_order"] == ["b", "a"]
+        assert data["multi"] == {"b": ["1", "3"], "a": ["2"]}
+
+    def test_empty_query(self, client: httpx.Client):
+        assert multi(client, "")["multi"] == {}
+
+
+class TestBrackets:
+    """Test the `key[]` convention."""
+
+    def test_stripped_by_default(self, client: httpx.Client):
+        data = multi(client, "tag[]=a&tag=b&tag%5B%5D=c")
+        assert data["tags"] == ["a", "b", "c"]
+        assert data["multi"] == {"tag": ["a", "b", "c"]}
+
+    def test_stripping_off(self, client: httpx.Client):
+        data = multi(client, "tag[]=a&tag=b")
+        assert data["tags_raw"] == ["b"]
+        assert data["multi_raw"] == {"tag[]": ["a"], "tag": ["b"]}
+
+    def test_flat_dict_unchanged(self, client: httpx.Client):
+        data = multi(client, "tag[]=a")
+        assert data["flat"] == {"tag[]": "a"}
+
+
+class TestFormMulti:
+    """Test repeated fields in urlencoded form bodies."""
+
+    def post(self, client: httpx.Client, body: str) -> dict:
+        response = client.post(
+            "/form/multi",
+            content=body.encode(),
+            headers={"Content-Type": "application/x-www-form-urlencoded"},
+        )
+        assert response.status_code == 200
+        return response.json()
+
+    def test_repeated_fields(self, client: httpx.Client):
+        data = self.post(client, "tag=x&name=n&tag=y")
+        assert data["tags"] == ["x", "y"]
+        assert data["last"] == "y"
+        assert data["multi"] == {"tag": ["x", "y"], "name": ["n"]}
+
+    def test_brackets_and_decoding(self, client: httpx.Client):
+        data = self.post(client, "tag[]=a+b&tag[]=%C3%A9")
+        assert data["tags"] == ["a b", "é"]
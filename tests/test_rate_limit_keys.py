@@ -0,0 +1,216 @@
+"""
+Test cases for rate limit key strategies and tiers.
+
+Tests cover:
+- Authenticated users and anonymous clients counted in separate buckets
+- Composite keys isolating each combination of parts
+- Python callables choosing the key
+- Tiers picked by key prefix or callable, with their limits in the headers
+- Invalid key and tier arguments rejected
+"""
+
+import base64
+
+import pytest
+
+from hypern import Hypern
+from hypern.middleware import BasicAuthMiddleware, RateLimitMiddleware
+
+
+def build_app(limiter, auth=False) -> Hypern:
+    """App with GET /public and /private; with `auth`, POSTs need basic auth."""
+    app = Hypern()
+
+    @app.api_route("/public", ["GET", "POST"])
+    def public(req, res, ctx):
+        res.json({"ok": True})
+
+    @app.get("/private")
+    def private(req, res, ctx):
+        res.json({"ok": True})
+
+    if auth:
+        app.use(BasicAuthMiddleware(users={"alice": "secret"}), methods=["POST"])
+    app.use(limiter)
+    return app
+
+
+def basic(user, password):
+    token = base64.b64encode(f"{user}:{password}".encode()).decode()
+    return {"Authorization": f"Basic {token}"}
+
+
+def statuses(client, path, count, headers):
+    return [client.get(path, headers=headers).status for _ in range(count)]
+
+
+class TestAuthenticatedUserKey:
+    """Test the "user" key strategy."""
+
+    def test_user_and_anonymous_buckets(self):
+        client = build_app(
+            RateLimitMiddleware(algorithm="fixed", max_requests=2, window_secs=60, key="user"),
+            auth=True,
+        ).test_client()
+        ip = {"X-Forwarded-For": "10.0.0.1"}
+        user = {**ip, **basic("alice", "secret")}
+
+        assert [client.post("/public", headers=user).status for _ in range(3)] == [200, 200, 429]
+        # Same IP, no user: its own bucket
+        assert statuses(client, "/public", 3, ip) == [200, 200, 429]
+
+    def test_user_followed_across_ips(self):
+        client = build_app(
+            RateLimitMiddleware(algorithm="fixed", max_requests=2, window_secs=60, key="user"),
+            auth=True,
+        ).test_client()
+        auth = basic("alice", "secret")
+
+        def post(ip):
+            return client.post("/public", headers={**auth, "X-Forwarded-For": ip}).status
+
+        assert post("10.0.0.1") == 200
+        assert post("10.0.0.2") == 200
+        assert post("10.0.0.3") == 429
+
+
+class TestCompositeKey:
+    """Test keys built from several parts."""
+
+    def test_parts_isolated(self):
+        client = build_app(
+            RateLimitMiddleware(
+                algorithm="fixed", max_requests=1, window_secs=60, key=["ip", "header:X-Tenant"]
+            )
+        ).test_client()
+
+        def get(ip, tenant):
+            return client.get("/public", headers={"X-Forwarded-For": ip, "X-Tenant": tenant}).status
+
+        assert get("10.0.0.1", "a") == 200
+        assert get("10.0.0.1", "b") == 200
+        assert get("10.0.0.2", "a") == 200
+        assert get("10.0.0.1", "a") == 429
+
+    def test_callable_key(self):
+        def by_path(ctx):
+            return ctx.path
+
+        client = build_app(
+            RateLimitMiddleware(algorithm="fixed", max_requests=1, window_secs=60, key=by_path)
+        ).test_client()
+
+        assert client.get("/public").status == 200
+        assert client.get("/private").status == 200
+        assert client.get("/public").status == 429
+
+    def test_callable_returning_none_uses_ip(self):
+        client = build_app(
+            RateLimitMiddleware(
+                algorithm="fixed", max_requests=1, window_secs=60, key=lambda ctx: None
+            )
+        ).test_client()
+
+        assert client.get("/public", headers={"X-Forwarded-For": "10.0.0.1"}).status == 200
+        assert client.get("/public", headers={"X-Forwarded-For": "10.0.0.2"}).status == 200
+        assert client.get("/public", headers={"X-Forwarded-For": "10.0.0.1"}).status == 429
+
+
+class TestTiers:
+    """Test per-tier limits."""
+
+    def test_prefix_tiers(self):
+        client = build_app(
+            RateLimitMiddleware(
+                algorithm="fixed",
+                max_requests=2,
+                window_secs=60,
+                key="header:X-API-Key",
+                tiers={"premium": (5, 120)},
+                tier={"pk_premium_": "premium"},
+            )
+        ).test_client()
+        premium = {"X-API-Key": "pk_premium_123"}
+        free = {"X-API-Key": "pk_free_123"}
+
+        assert statuses(client, "/public", 6, premium) == [200] * 5 + [429]
+        assert statuses(client, "/public", 3, free) == [200, 200, 429]
+
+    def test_headers_show_tier_limit(self):
+        client = build_app(
+            RateLimitMiddleware(
+                algorithm="fixed",
+                max_requests=2,
+                window_secs=60,
+                key="header:X-API-Key",
+                tiers={"premium": (5, 120)},
+                tier={"pk_premium_": "premium"},
+            )
+        ).test_client()
+
+        premium = client.get("/public", headers={"X-API-Key": "pk_premium_1"})
+        assert premium.headers["x-ratelimit-limit"] == "5"
+        assert premium.headers["x-ratelimit-remaining"] == "4"
+        assert premium.headers["x-ratelimit-reset"] == "120"
+
+        free = client.get("/public", headers={"X-API-Key": "pk_free_1"})
+        assert free.headers["x-ratelimit-limit"] == "2"
+        assert free.headers["x-ratelimit-reset"] == "60"
+
+    def test_callable_tier(self):
+        seen = []
+
+        def resolve(ctx, key):
+            seen.append(key)
+            return "gold" if ctx.get_header("x-plan") == "gold" else None
+
+        client = build_app(
+            RateLimitMiddleware(
+                algorithm="fixed",
+                max_requests=1,
+                window_secs=60,
+                key="header:X-API-Key",
+                tiers={"gold": (3, 60)},
+                tier=resolve,
+            )
+        ).test_client()
+
+        gold = {"X-API-Key": "k1", "X-Plan": "gold"}
+        assert statuses(client, "/public", 4, gold) == [200, 200, 200, 429]
+        assert statuses(client, "/public", 2, {"X-API-Key": "k2"}) == [200, 429]
+        assert seen[0] == "k1"
+
+    def test_longest_prefix_wins(self):
+        client = build_app(
+            RateLimitMiddleware(
+                algorithm="fixed",
+                max_requests=1,
+                window_secs=60,
+                key="header:X-API-Key",
+                tiers={"basic": (2, 60), "premium": (4, 60)},
+                tier={"pk_": "basic", "pk_premium_": "premium"},
+            )
+        ).test_client()
+
+        response = client.get("/public", headers={"X-API-Key": "pk_premium_9"})
+        assert response.headers["x-ratelimit-limit"] == "4"
+
+
+class TestInvalidArguments:
+    """Test argument validation."""
+
+    def test_unknown_key(self):
+        with pytest.raises(ValueError, match="Unknown rate limit key"):
+            RateLimitMiddleware(key="cookie")
+
+    def test_empty_composite(self):
+        with pytest.raises(ValueError, match="at least one part"):
+            RateLimitMiddleware(key=[])
+
+    def test_unknown_tier_name(self):
+        with pytest.raises(ValueError, match="Unknown rate limit tier 'gold'"):
+            RateLimitMiddleware(tiers={"premium": (10, 60)}, tier={"pk_": "gold"})
+
+    def test_zero_tier_limit(self):
+        with pytest.raises(ValueError, match="positive"):
+            RateLimitMiddleware(tiers={"premium": (0, 60)}, tier={"pk_": "premium"})
@@ -4,13 +4,21 @@ Comprehensive tests for Hypern Realtime module.
 Tests cover:
 - Channel/Topic abstractions (ChannelManager, TopicMatcher)
 - Presence tracking (PresenceTracker)
+- Partial presence metadata updates, versions and update deltas
 - Backpressure-aware broadcast (RealtimeBroadcast)
 - Heartbeat/auto-reconnect helpers (HeartbeatMonitor)
+- Per-client heartbeat overrides and adaptive, jitter-based timeouts
+- Awaitable receive and ``async for`` on subscribers
+- Channel access control (max_subscribers, subscribe/publish hooks)
+- Channels created on first use and collected once idle
+- Per-client send queues and their overflow policies
+- Message priorities: urgent messages first, starvation protection, replay
 - RealtimeHub convenience wrapper
 """
 
 import asyncio
 import json
+import threading
 import time
 
 import pytest
@@ -21,20 +29,30 @@ from hypern.realtime import (
     ChannelStats,
     Subscriber,
     TopicMatcher,
+    ChannelClosed,
+    SubscriptionError,
+    PublishError,
     # Presence
     PresenceTracker,
     PresenceInfo,
     PresenceDiff,
+    PresenceUpdate,
     # Broadcast
     RealtimeBroadcast,
     BroadcastConfig,
     BroadcastStats,
     BroadcastSubscriber,
     BackpressurePolicy,
+    # Per-client queues
+    ClientQueueConfig,
+    ClientQueueStats,
+    OverflowPolicy,
+    Priority,
     # Heartbeat
     HeartbeatMonitor,
     HeartbeatConfig,
     HeartbeatStats,
+    HeartbeatClientStats,
     # Hub
     RealtimeHub,
 )
@@ -308,6 +326,240 @@ class TestChannelManager:
         assert tm is not None
 
 
+class TestChannelAccessControl:
+    """Test subscriber limits and subscribe/publish hooks."""
+
+    def test_max_subscribers_boundary(self):
+        mgr = ChannelManager()
+        mgr.create_channel("room", max_subscribers=2)
+        mgr.subscribe("room", "c1")
+        mgr.subscribe("room", "c2")
+        with pytest.raises(SubscriptionError, match="full"):
+            mgr.subscribe("room", "c3")
+        stats = mgr.get_stats("room")
+        assert stats.subscriber_count == 2
+        assert stats.max_subscribers == 2
+        assert stats.denied_subscriptions == 1
+
+    def test_resubscribe_at_limit_allowed(self):
+        mgr = ChannelManager()
+        mgr.create_channel("room", max_subscribers=1)
+        mgr.subscribe("room", "c1")
+        mgr.subscribe("room", "c1")
+        assert mgr.get_stats("room").subscriber_count == 1
+
+    def test_slot_freed_by_unsubscribe(self):
+        mgr = ChannelManager()
+        mgr.create_channel("room", max_subscribers=1)
+        mgr.subscribe("room", "c1")
+        mgr.unsubscribe("room", "c1")
+        mgr.subscribe("room", "c2")
+        assert mgr.get_subscribers("room") == ["c2"]
+
+    def test_unlimited_by_default(self):
+        mgr = ChannelManager()
+        mgr.create_channel("room")
+        for i in range(50):
+            mgr.subscribe("room", f"c{i}")
+        assert mgr.get_stats("room").max_subscribers is None
+
+    def test_hook_denies_client(self):
+        mgr = ChannelManager()
+        mgr.create_channel("room")
+        calls = []
+
+        def hook(channel, client_id, metadata):
+            calls.append((channel, client_id, metadata))
+            return client_id != "mallory"
+
+        mgr.set_subscribe_hook(hook)
+        mgr.subscribe("room", "alice", {"role": "member"})
+        with pytest.raises(SubscriptionError, match="mallory"):
+            mgr.subscribe("room", "mallory")
+        assert calls == [("room", "alice", {"role": "member"}), ("room", "mallory", {})]
+        assert mgr.get_subscribers("room") == ["alice"]
+        assert mgr.get_stats("room").denied_subscriptions == 1
+
+    def test_hook_exception_becomes_subscription_error(self):
+        mgr = ChannelManager()
+        mgr.create_channel("room")
+
+        def hook(channel, client_id, metadata):
+            raise RuntimeError("auth backend down")
+
+        mgr.set_subscribe_hook(hook)
+        with pytest.raises(SubscriptionError) as exc_info:
+            mgr.subscribe("room", "alice")
+        assert isinstance(exc_info.value.__cause__, RuntimeError)
+
+        # The channel keeps working once the hook recovers
+        mgr.set_subscribe_hook(lambda *args: True)
+        sub = mgr.subscribe("room", "alice")
+        mgr.publish("room", "hello")
+        assert sub.try_recv() == "hello"
+        assert mgr.get_stats("room").denied_subscriptions == 1
+
+    def test_bypass_hooks(self):
+        mgr = ChannelManager()
+        mgr.create_channel("room", max_subscribers=1)
+        mgr.set_subscribe_hook(lambda *args: False)
+        mgr.subscribe("room", "server", bypass_hooks=True)
+        # The limit still applies to internal subscriptions
+        with pytest.raises(SubscriptionError):
+            mgr.subscribe("room", "server-2", bypass_hooks=True)
+
+    def test_clear_hook(self):
+        mgr = ChannelManager()
+        mgr.create_channel("room")
+        mgr.set_subscribe_hook(lambda *args: False)
+        mgr.set_subscribe_hook(None)
+        mgr.subscribe("room", "alice")
+
+    def test_hook_can_use_manager(self):
+        mgr = ChannelManager()
+        mgr.create_channel("room")
+        mgr.set_subscribe_hook(lambda channel, *_: len(mgr.get_subscribers(channel)) < 1)
+        mgr.subscribe("room", "alice")
+        with pytest.raises(SubscriptionError):
+            mgr.subscribe("room", "bob")
+
+    def test_missing_channel_checked_before_hook(self):
+        mgr = ChannelManager()
+        calls = []
+        mgr.set_subscribe_hook(lambda *args: calls.append(args) or True)
+        with pytest.raises(KeyError):
+            mgr.subscribe("nope", "alice")
+        assert calls == []
+
+    def test_publish_hook(self):
+        mgr = ChannelManager()
+        mgr.create_channel("room")
+        sub = mgr.subscribe("room", "alice")
+        mgr.set_publish_hook(lambda channel, client_id, message: client_id == "alice")
+        assert mgr.publish("room", "hi", client_id="alice") == 1
+        with pytest.raises(PublishError):
+            mgr.publish("room", "spam", client_id="mallory")
+        with pytest.raises(PublishError):
+            mgr.publish("room", "anonymous")
+        assert mgr.publish("room", "system", bypass_hooks=True) == 1
+        assert sub.drain() == ["hi", "system"]
+        stats = mgr.get_stats("room")
+        assert stats.denied_publishes == 2
+        assert stats.total_messages == 2
+
+    def test_publish_hook_exception(self):
+        mgr = ChannelManager()
+        mgr.create_channel("room")
+
+        def hook(channel, client_id, message):
+            raise ValueError("bad")
+
+        mgr.set_publish_hook(hook)
+        with pytest.raises(PublishError) as exc_info:
+            mgr.publish("room", "hi")
+        assert isinstance(exc_info.value.__cause__, ValueError)
+
+    def test_publish_to_topic_skips_denied_channels(self):
+        mgr = ChannelManager()
+        mgr.create_channel("chat:general")
+        mgr.create_channel("chat:admin")
+        general = mgr.subscribe("chat:general", "u1")
+        admin = mgr.subscribe("chat:admin", "u2")
+        mgr.set_publish_hook(lambda channel, client_id, message: channel != "chat:admin")
+        assert mgr.publish_to_topic("chat:*", "hello", client_id="u1") == 1
+        assert general.try_recv() == "hello"
+        assert admin.try_recv() is None
+        assert mgr.get_stats("chat:admin").denied_publishes == 1
+
+    def test_hub_join_denied(self):
+        hub = RealtimeHub()
+        hub.create_channel("room", max_subscribers=1)
+        hub.join("room", "alice")
+        with pytest.raises(SubscriptionError):
+            hub.join("room", "bob")
+        assert [p.client_id for p in hub.get_presence("room")] == ["alice"]
+
+
+class TestChannelAutoCreate:
+    """Test the auto-create policy and the idle sweep."""
+
+    def test_subscribe_creates_matching_channel(self):
+        mgr = ChannelManager(auto_create=True, auto_create_pattern="chat:*")
+        sub = mgr.subscribe("chat:room-1", "alice")
+        assert mgr.has_channel("chat:room-1")
+        assert mgr.publish("chat:room-1", "hi") == 1
+        assert sub.try_recv() == "hi"
+        assert mgr.lifecycle_stats() == {
+            "created": 0,
+            "auto_created": 1,
+            "expired": 0,
+            "restored_channels": 0,
+            "restored_messages": 0,
+        }
+
+    def test_publish_creates_matching_channel(self):
+        mgr = ChannelManager(auto_create=True, auto_create_pattern=["chat:*", "feed:#"])
+        assert mgr.publish("feed:user:7", "x") == 0
+        assert mgr.get_stats("feed:user:7").total_messages == 1
+
+    def test_non_matching_name_raises(self):
+        mgr = ChannelManager(auto_create=True, auto_create_pattern="chat:*")
+        with pytest.raises(KeyError):
+            mgr.subscribe("admin:ops", "alice")
+        with pytest.raises(KeyError):
+            mgr.publish("chat:a:b", "x")
+        assert mgr.channel_count() == 0
+
+    def test_disabled_by_default(self):
+        with pytest.raises(KeyError):
+            ChannelManager().subscribe("chat:room-1", "alice")
+        with pytest.raises(ValueError, match="auto_create=True"):
+            ChannelManager(auto_create_pattern="chat:*")
+
+    def test_refused_subscription_creates_nothing(self):
+        mgr = ChannelManager(auto_create=True)
+        mgr.set_subscribe_hook(lambda *args: False)
+        with pytest.raises(SubscriptionError):
+            mgr.subscribe("probe", "scanner")
+        assert not mgr.has_channel("probe")
+
+    def test_idle_channel_expires(self):
+        mgr = ChannelManager(auto_create=True, auto_create_pattern="chat:*", default_ttl_secs=0.1)
+        mgr.create_channel("chat:lobby")
+        events = []
+        mgr.set_channel_hook(lambda event, name: events.append((event, name)))
+        mgr.subscribe("chat:room-1", "alice")
+        mgr.unsubscribe("chat:room-1", "alice")
+        time.sleep(0.2)
+        assert mgr.list_channels() == ["chat:lobby"]
+        assert events == [("auto_created", "chat:room-1"), ("expired", "chat:room-1")]
+        assert mgr.lifecycle_stats() == {
+            "created": 1,
+            "auto_created": 1,
+            "expired": 1,
+            "restored_channels": 0,
+            "restored_messages": 0,
+        }
+
+    def test_subscribed_channel_survives(self):
+        mgr = ChannelManager(auto_create=True, default_ttl_secs=0.1)
+        sub = mgr.subscribe("room", "alice")
+        time.sleep(0.2)
+        assert mgr.sweep_idle() == 0
+        mgr.publish("room", "still here")
+        assert sub.try_recv() == "still here"
+
+    def test_explicit_channel_with_ttl(self):
+        mgr = ChannelManager()
+        mgr.create_channel("tmp", idle_ttl_secs=0.05)
+        mgr.create_channel("kept")
+        time.sleep(0.1)
+        assert mgr.sweep_idle() == 1
+        assert mgr.list_channels() == ["kept"]
+        with pytest.raises(ValueError):
+            mgr.create_channel("bad", idle_ttl_secs=0)
+
+
 # ============================================================================
 # PresenceTracker Tests
 # ============================================================================
@@ -474,6 +726,128 @@ class TestPresenceTracker:
         assert d["leaves"] == []
 
 
+class TestPresenceUpdates:
+    """Test partial metadata updates and their deltas."""
+
+    def test_merge_and_delete(self):
+        tracker = PresenceTracker()
+        tracker.track("doc", "alice", {"name": "Alice", "status": "online", "doc": "a.md"})
+        assert tracker.update("alice", {"status": "typing", "doc": None, "cursor": {"line": 3, "col": 7}})
+
+        info = tracker.get("doc", "alice")
+        assert info.metadata == {"name": "Alice", "status": "typing", "cursor": {"line": 3, "col": 7}}
+        assert info.version == 2
+        assert info.last_update >= info.joined_at
+
+    def test_structured_values_at_join(self):
+        tracker = PresenceTracker()
+        info = tracker.track("doc", "alice", {"tags": ["a", "b"], "idle": False, "score": 1.5})
+        assert info.metadata == {"tags": ["a", "b"], "idle": False, "score": 1.5}
+        assert info.version == 1
+        assert info.last_update == info.joined_at
+
+    def test_update_every_channel(self):
+        tracker = PresenceTracker()
+        tracker.track("doc1", "alice")
+        tracker.track("doc2", "alice")
+        tracker.track("doc1", "bob")
+        tracker.update("alice", {"status": "away"})
+        assert tracker.get("doc1", "alice").metadata == {"status": "away"}
+        assert tracker.get("doc2", "alice").metadata == {"status": "away"}
+        assert tracker.get("doc1", "bob").metadata == {}
+
+    def test_update_one_channel(self):
+        tracker = PresenceTracker()
+        tracker.track("doc1", "alice", {"status": "online"})
+        tracker.track("doc2", "alice", {"status": "online"})
+        assert tracker.update("doc1", "alice", {"cursor": 5}) is True
+        assert tracker.get("doc1", "alice").metadata == {"status": "online", "cursor": 5}
+        assert tracker.get("doc2", "alice").metadata == {"status": "online"}
+
+    def test_update_absent_client(self):
+        tracker = PresenceTracker()
+        assert tracker.update("nobody", {"status": "away"}) is False
+        assert tracker.update("doc", "nobody", {"status": "away"}) is False
+
+    def test_delta_carries_changed_keys_only(self):
+        tracker = PresenceTracker()
+        tracker.track("doc", "alice", {"name": "Alice", "status": "online", "doc": "a.md"})
+        tracker.flush_diff("doc")
+
+        tracker.update("alice", {"name": "Alice", "status": "typing", "doc": None})
+        diff = tracker.flush_diff("doc")
+        assert diff.joins == [] and diff.leaves == []
+        assert len(diff.updated) == 1
+        update = diff.updated[0]
+        assert isinstance(update, PresenceUpdate)
+        assert update.client_id == "alice"
+        assert update.channel == "doc"
+        assert update.changes == {"status": "typing", "doc": None}
+        assert update.version == 2
+        assert diff.change_count() == 1
+
+        assert not tracker.flush_diff("doc").has_changes()
+
+    def test_deltas_between_flushes_merged(self):
+        tracker = PresenceTracker()
+        tracker.track("doc", "alice")
+        tracker.flush_diff("doc")
+        tracker.update("alice", {"status": "typing", "cursor": 1})
+        tracker.update("alice", {"cursor": 2})
+        [update] = tracker.flush_diff("doc").updated
+        assert update.changes == {"status": "typing", "cursor": 2}
+        assert update.version == 3
+
+    def test_unchanged_values_emit_nothing(self):
+        tracker = PresenceTracker()
+        tracker.track("doc", "alice", {"status": "online"})
+        tracker.flush_diff("doc")
+        tracker.update("alice", {"status": "online", "gone": None})
+        assert tracker.flush_diff("doc").updated == []
+        assert tracker.get("doc", "alice").version == 2
+
+    def test_leave_drops_pending_updates(self):
+        tracker = PresenceTracker()
+        tracker.track("doc", "alice")
+        tracker.flush_diff("doc")
+        tracker.update("alice", {"status": "typing"})
+        tracker.untrack("doc", "alice")
+        diff = tracker.flush_diff("doc")
+        assert diff.updated == []
+        assert diff.leaves == ["alice"]
+
+    def test_diff_as_dict_updated(self):
+        tracker = PresenceTracker()
+        tracker.track("doc", "alice")
+        tracker.flush_diff("doc")
+        tracker.update("alice", {"status": "away"})
+        d = tracker.diff_as_dict("doc")
+        assert d["updated"] == [{"client_id": "alice", "changes": {"status": "away"}, "version": 2}]
+
+    def test_version_strictly_increases_under_concurrency(self):
+        tracker = PresenceTracker()
+        tracker.track("doc", "alice")
+        threads, per_thread = 8, 200
+        seen = [[] for _ in range(threads)]
+
+        def worker(n):
+            for i in range(per_thread):
+                tracker.update("doc", "alice", {f"t{n}": i})
+                seen[n].append(tracker.get("doc", "alice").version)
+
+        pool = [threading.Thread(target=worker, args=(n,)) for n in range(threads)]
+        for t in pool:
+            t.start()
+        for t in pool:
+            t.join()
+
+        info = tracker.get("doc", "alice")
+        assert info.version == 1 + threads * per_thread
+        assert info.metadata == {f"t{n}": per_thread - 1 for n in range(threads)}
+        for versions in seen:
+            assert versions == sorted(set(versions))
+
+
 # ============================================================================
 # RealtimeBroadcast Tests
 # ============================================================================
@@ -821,6 +1195,146 @@ class TestHeartbeatMonitor:
         assert config.send_keepalive is True
 
 
+def beat(monitors, client_id, gaps):
+    """Pong on every monitor after each gap, checking timeouts just before"""
+    timed_out = {id(m): [] for m in monitors}
+    for gap in gaps:
+        time.sleep(gap)
+        for monitor in monitors:
+            timed_out[id(monitor)] += monitor.check_timeouts()
+            monitor.pong(client_id)
+    return [timed_out[id(m)] for m in monitors]
+
+
+class TestHeartbeatAdaptive:
+    """Test per-client overrides and adaptive timeouts."""
+
+    def test_jittery_client_kept_only_in_adaptive_mode(self):
+        fixed = HeartbeatMonitor(HeartbeatConfig(interval_secs=0.1, timeout_secs=0.2))
+        adaptive = HeartbeatMonitor(HeartbeatConfig(interval_secs=0.1, timeout_secs=0.2, adaptive=True))
+        for monitor in (fixed, adaptive):
+            monitor.register("mobile")
+
+        # Alive but irregular: every gap within the base timeout at first,
+        # then one long stall
+        warmup = [0.01, 0.15] * 3
+        fixed_out, adaptive_out = beat([fixed, adaptive], "mobile", warmup)
+        assert fixed_out == adaptive_out == []
+        assert adaptive.client_stats("mobile").effective_timeout_secs > 0.4
+
+        time.sleep(0.35)
+        assert fixed.check_timeouts() == ["mobile"]
+        assert adaptive.check_timeouts() == []
+        assert adaptive.is_alive("mobile")
+
+    def test_timeout_capped_at_ceiling(self):
+        hb = HeartbeatMonitor(
+            HeartbeatConfig(interval_secs=0.1, timeout_secs=0.2, adaptive=True, max_timeout_secs=0.25)
+        )
+        hb.register("c1")
+        beat([hb], "c1", [0.01, 0.15] * 3)
+        assert hb.client_stats("c1").effective_timeout_secs == pytest.approx(0.25)
+        time.sleep(0.3)
+        assert hb.check_timeouts() == ["c1"]
+
+    def test_steady_client_keeps_base_timeout(self):
+        hb = HeartbeatMonitor(HeartbeatConfig(interval_secs=0.05, timeout_secs=0.2, adaptive=True))
+        hb.register("c1")
+        beat([hb], "c1", [0.02] * 4)
+        stats = hb.client_stats("c1")
+        assert stats.samples == 3
+        assert stats.mean_interval_secs == pytest.approx(0.02, abs=0.01)
+        assert stats.effective_timeout_secs == pytest.approx(0.2, abs=0.02)
+
+    def test_history_window(self):
+        hb = HeartbeatMonitor(HeartbeatConfig(adaptive=True, jitter_window=3))
+        hb.register("c1")
+        for _ in range(6):
+            hb.pong("c1")
+        assert hb.client_stats("c1").samples == 3
+
+    def test_override_round_trip(self):
+        hb = HeartbeatMonitor(HeartbeatConfig(interval_secs=30, timeout_secs=90))
+        hb.register("c1")
+        assert hb.get_client_config("c1") is None
+        assert hb.set_client_config("c1", 60.0, 4) is True
+        assert hb.get_client_config("c1") == {"interval_secs": 60.0, "max_missed": 4}
+
+        stats = hb.client_stats("c1")
+        assert isinstance(stats, HeartbeatClientStats)
+        assert stats.overridden is True
+        assert stats.interval_secs == 60.0
+        assert stats.effective_timeout_secs == 240.0
+
+        assert hb.clear_client_config("c1") is True
+        assert hb.get_client_config("c1") is None
+        assert hb.client_stats("c1").effective_timeout_secs == 90.0
+
+    def test_override_timeout_applies(self):
+        hb = HeartbeatMonitor(HeartbeatConfig(interval_secs=30, timeout_secs=90))
+        hb.register("fast")
+        hb.register("slow")
+        hb.set_client_config("fast", 0.005, 2)
+        time.sleep(0.03)
+        assert hb.check_timeouts() == ["fast"]
+        assert hb.clients_needing_ping() == []
+        assert hb.loop_interval_secs() == 0.005
+
+    def test_override_unknown_client(self):
+        hb = HeartbeatMonitor()
+        assert hb.set_client_config("nobody", 10.0, 3) is False
+        assert hb.get_client_config("nobody") is None
+        assert hb.client_stats("nobody") is None
+
+    def test_override_invalid(self):
+        hb = HeartbeatMonitor()
+        hb.register("c1")
+        with pytest.raises(ValueError, match="interval_secs"):
+            hb.set_client_config("c1", 0.0, 3)
+        with pytest.raises(ValueError, match="max_missed"):
+            hb.set_client_config("c1", 5.0, 0)
+        with pytest.raises(ValueError, match="max_timeout_secs"):
+            HeartbeatConfig(timeout_secs=90, max_timeout_secs=30)
+
+    def test_unregister_drops_override_and_history(self):
+        hb = HeartbeatMonitor(HeartbeatConfig(adaptive=True))
+        hb.register("c1")
+        hb.set_client_config("c1", 5.0, 2)
+        hb.pong("c1")
+        hb.pong("c1")
+        hb.unregister("c1")
+        assert "c1" not in hb.stats().clients
+
+        hb.register("c1")
+        assert hb.get_client_config("c1") is None
+        assert hb.client_stats("c1").samples == 0
+
+    def test_stats_per_client(self):
+        hb = HeartbeatMonitor(HeartbeatConfig(timeout_secs=90))
+        hb.register("c1")
+        hb.register("c2")
+        hb.set_client_config("c2", 10.0, 3)
+        clients = hb.stats().clients
+        assert set(clients) == {"c1", "c2"}
+        assert clients["c1"].effective_timeout_secs == 90.0
+        assert clients["c1"].mean_interval_secs is None
+        assert clients["c2"].effective_timeout_secs == 30.0
+
+    def test_loop_uses_effective_timeout(self):
+        hb = HeartbeatMonitor(HeartbeatConfig(interval_secs=30, timeout_secs=90))
+        hb.register("c1")
+        hb.set_client_config("c1", 0.01, 2)
+        timed_out = []
+
+        async def run():
+            task = asyncio.ensure_future(hb.run_heartbeat_loop(on_timeout=timed_out.append))
+            await asyncio.sleep(0.1)
+            task.cancel()
+
+        asyncio.run(run())
+        assert timed_out == ["c1"]
+
+
 # ============================================================================
 # RealtimeHub Tests
 # ============================================================================
@@ -977,3 +1491,379 @@ class TestAsyncFeatures:
             timeout=5.0,
         )
         assert received == ["a", "b"]
+
+
+class TestAsyncReceive:
+    """Test recv_async and async iteration on subscribers."""
+
+    @pytest.mark.asyncio
+    async def test_recv_from_other_thread(self):
+        mgr = ChannelManager()
+        mgr.create_channel("ch")
+        sub = mgr.subscribe("ch", "c1")
+
+        def publish():
+            for i in range(3):
+                time.sleep(0.01)
+                mgr.publish("ch", f"msg-{i}")
+
+        thread = threading.Thread(target=publish)
+        thread.start()
+        received = [await asyncio.wait_for(sub.recv_async(), 5.0) for _ in range(3)]
+        thread.join()
+        assert received == ["msg-0", "msg-1", "msg-2"]
+        assert sub.received_count == 3
+
+    @pytest.mark.asyncio
+    async def test_recv_timeout_returns_none(self):
+        bc = RealtimeBroadcast()
+        bc.create("ch")
+        rx = bc.subscribe("ch")
+        start = time.monotonic()
+        assert await rx.recv_async(timeout=0.05) is None
+        assert time.monotonic() - start < 1.0
+
+    @pytest.mark.asyncio
+    async def test_recv_raises_when_closed(self):
+        mgr = ChannelManager()
+        mgr.create_channel("ch")
+        sub = mgr.subscribe("ch", "c1")
+        waiter = asyncio.ensure_future(sub.recv_async())
+        await asyncio.sleep(0.01)
+        mgr.remove_channel("ch")
+        with pytest.raises(ChannelClosed):
+            await asyncio.wait_for(waiter, 5.0)
+
+    @pytest.mark.asyncio
+    async def test_lag_skips_to_oldest(self):
+        bc = RealtimeBroadcast()
+        bc.create("ch", BroadcastConfig(buffer_size=4))
+        rx = bc.subscribe("ch")
+        for i in range(10):
+            bc.send("ch", str(i))
+        assert await rx.recv_async(timeout=1.0) == "6"
+        assert rx.lagged_count == 6
+
+    @pytest.mark.asyncio
+    async def test_cancelled_recv_keeps_message(self):
+        bc = RealtimeBroadcast()
+        bc.create("ch")
+        rx = bc.subscribe("ch")
+        with pytest.raises(asyncio.TimeoutError):
+            await asyncio.wait_for(rx.recv_async(), 0.05)
+        await asyncio.sleep(0.01)
+        bc.send("ch", "kept")
+        assert await rx.recv_async(timeout=1.0) == "kept"
+
+    @pytest.mark.asyncio
+    async def test_async_for_ends_on_close(self):
+        bc = RealtimeBroadcast()
+        bc.create("ch")
+        rx = bc.subscribe("ch")
+
+        def publish():
+            for msg in ("a", "b"):
+                time.sleep(0.01)
+                bc.send("ch", msg)
+            time.sleep(0.01)
+            bc.remove("ch")
+
+        thread = threading.Thread(target=publish)
+        thread.start()
+
+        async def consume():
+            return [msg async for msg in rx]
+
+        received = await asyncio.wait_for(consume(), 5.0)
+        thread.join()
+        assert received == ["a", "b"]
+
+
+# ============================================================================
+# Per-Client Queue Tests
+# ============================================================================
+
+def wait_forwarded(sub, count, timeout=5.0):
+    """Wait until the fan-out task has moved ``count`` messages to the queue."""
+    deadline = time.monotonic() + timeout
+    while sub.queue_stats.forwarded < count:
+        assert time.monotonic() < deadline, sub.queue_stats
+        time.sleep(0.001)
+
+
+class TestClientQueues:
+    """Test per-client queues with a consumer that falls behind."""
+
+    def slow_subscriber(self, config, messages):
+        mgr = ChannelManager()
+        mgr.create_channel("ch")
+        sub = mgr.subscribe("ch", "slow", queue=config)
+        for msg in messages:
+            mgr.publish("ch", msg)
+        wait_forwarded(sub, len(messages))
+        return mgr, sub
+
+    def test_config_defaults(self):
+        config = ClientQueueConfig()
+        assert config.capacity == 64
+        assert config.policy == OverflowPolicy.DropOldest
+        assert config.coalesce_key is None
+
+    def test_config_validation(self):
+        with pytest.raises(ValueError):
+            ClientQueueConfig(capacity=0)
+        with pytest.raises(ValueError):
+            ClientQueueConfig(policy=OverflowPolicy.CoalesceByKey)
+
+    def test_direct_subscriber_has_no_queue(self):
+        mgr = ChannelManager()
+        mgr.create_channel("ch")
+        assert mgr.subscribe("ch", "c1").queue_stats is None
+
+    def test_within_capacity_nothing_dropped(self):
+        _, sub = self.slow_subscriber(ClientQueueConfig(capacity=8), ["a", "b", "c"])
+        assert sub.drain() == ["a", "b", "c"]
+        stats = sub.queue_stats
+        assert isinstance(stats, ClientQueueStats)
+        assert (stats.queued, stats.high_water, stats.dropped) == (0, 3, 0)
+        assert sub.received_count == 3
+
+    def test_drop_oldest(self):
+        config = ClientQueueConfig(capacity=3, policy=OverflowPolicy.DropOldest)
+        _, sub = self.slow_subscriber(config, [str(i) for i in range(6)])
+        assert sub.drain() == ["3", "4", "5"]
+        stats = sub.queue_stats
+        assert stats.dropped == 3
+        assert stats.high_water == 3
+        assert sub.missed_count == 3
+
+    def test_drop_newest(self):
+        config = ClientQueueConfig(capacity=3, policy=OverflowPolicy.DropNewest)
+        _, sub = self.slow_subscriber(config, [str(i) for i in range(6)])
+        assert sub.drain() == ["0", "1", "2"]
+        assert sub.queue_stats.dropped == 3
+
+    def test_disconnect(self):
+        config = ClientQueueConfig(capacity=3, policy=OverflowPolicy.Disconnect)
+        mgr, sub = self.slow_subscriber(config, [str(i) for i in range(4)])
+        stats = sub.queue_stats
+        assert stats.disconnected
+        assert stats.queued == 0
+        assert stats.dropped == 4
+        assert sub.try_recv() is None
+        assert "slow" not in mgr.get_subscribers("ch")
+        # Later messages are not delivered
+        mgr.publish("ch", "late")
+        time.sleep(0.02)
+        assert sub.drain() == []
+
+    @pytest.mark.asyncio
+    async def test_disconnect_closes_receiver(self):
+        config = ClientQueueConfig(capacity=1, policy=OverflowPolicy.Disconnect)
+        _, sub = self.slow_subscriber(config, ["a", "b"])
+        with pytest.raises(ChannelClosed):
+            await asyncio.wait_for(sub.recv_async(), 5.0)
+
+    def test_disconnect_spares_fast_client(self):
+        mgr = ChannelManager()
+        mgr.create_channel("ch")
+        config = ClientQueueConfig(capacity=2, policy=OverflowPolicy.Disconnect)
+        sub = mgr.subscribe("ch", "fast", queue=config)
+        received = []
+        for i in range(10):
+            mgr.publish("ch", str(i))
+            wait_forwarded(sub, i + 1)
+            received.extend(sub.drain())
+        assert received == [str(i) for i in range(10)]
+        assert not sub.queue_stats.disconnected
+
+    def test_coalesce_by_key(self):
+        config = ClientQueueConfig(
+            capacity=8, policy=OverflowPolicy.CoalesceByKey, coalesce_key="user"
+        )
+        messages = [
+            json.dumps({"user": "a", "status": "typing"}),
+            json.dumps({"user": "b", "status": "online"}),
+            json.dumps({"user": "a", "status": "idle"}),
+            json.dumps({"user": 7, "status": "away"}),
+            json.dumps({"user": 7, "status": "online"}),
+            "not json",
+        ]
+        _, sub = self.slow_subscriber(config, messages)
+        received = [json.loads(m) if m != "not json" else m for m in sub.drain()]
+        assert received == [
+            {"user": "a", "status": "idle"},
+            {"user": "b", "status": "online"},
+            {"user": 7, "status": "online"},
+            "not json",
+        ]
+        stats = sub.queue_stats
+        assert stats.coalesced == 2
+        assert stats.dropped == 0
+
+    def test_coalesce_falls_back_to_drop_oldest(self):
+        config = ClientQueueConfig(
+            capacity=2, policy=OverflowPolicy.CoalesceByKey, coalesce_key="k"
+        )
+        messages = [json.dumps({"k": k}) for k in ("x", "y", "z")]
+        _, sub = self.slow_subscriber(config, messages)
+        assert [json.loads(m)["k"] for m in sub.drain()] == ["y", "z"]
+        assert sub.queue_stats.dropped == 1
+
+    @pytest.mark.asyncio
+    async def test_recv_async_from_queue(self):
+        mgr = ChannelManager()
+        mgr.create_channel("ch")
+        sub = mgr.subscribe("ch", "c1", queue=ClientQueueConfig(capacity=4))
+
+        def publish():
+            for i in range(3):
+                time.sleep(0.01)
+                mgr.publish("ch", f"msg-{i}")
+
+        thread = threading.Thread(target=publish)
+        thread.start()
+        received = [await asyncio.wait_for(sub.recv_async(), 5.0) for _ in range(3)]
+        thread.join()
+        assert received == ["msg-0", "msg-1", "msg-2"]
+
+    @pytest.mark.asyncio
+    async def test_queue_closed_with_channel(self):
+        bc = RealtimeBroadcast()
+        bc.create("ch")
+        rx = bc.subscribe("ch", queue=ClientQueueConfig(capacity=4))
+        bc.send("ch", "last")
+        await asyncio.sleep(0.02)
+        bc.remove("ch")
+        assert await asyncio.wait_for(rx.recv_async(), 5.0) == "last"
+        with pytest.raises(ChannelClosed):
+            await asyncio.wait_for(rx.recv_async(), 5.0)
+
+
+class TestMessagePriority:
+    """Test priority delivery through per-client queues."""
+
+    def backlog(self, config, messages):
+        """Subscribe a client that reads nothing until every message is queued"""
+        mgr = ChannelManager()
+        mgr.create_channel("ch", replay_size=100)
+        sub = mgr.subscribe("ch", "slow", queue=config)
+        for msg, priority in messages:
+            mgr.publish("ch", msg, priority=priority)
+        wait_forwarded(sub, len(messages))
+        return mgr, sub
+
+    def test_high_jumps_backlog(self):
+        messages = [(f"chat-{i}", None) for i in range(50)] + [("call-ended", "high")]
+        _, sub = self.backlog(ClientQueueConfig(capacity=100), messages)
+        received = sub.drain()
+        assert received[0] == "call-ended"
+        assert received[1:] == [f"chat-{i}" for i in range(50)]
+        assert sub.queue_stats.reordered == 1
+
+    def test_fifo_within_priority(self):
+        messages = [
+            ("n1", "normal"),
+            ("l1", "low"),
+            ("h1", "high"),
+            ("n2", None),
+            ("h2", Priority.High),
+            ("l2", Priority.Low),
+        ]
+        _, sub = self.backlog(ClientQueueConfig(capacity=16), messages)
+        assert sub.drain() == ["h1", "h2", "n1", "n2", "l1", "l2"]
+
+    def test_starvation_protection(self):
+        messages = [("n1", None), ("n2", None)] + [(f"h{i}", "high") for i in range(20)]
+        _, sub = self.backlog(ClientQueueConfig(capacity=64, max_burst=8), messages)
+        received = sub.drain()
+        assert received[:9] == [f"h{i}" for i in range(8)] + ["n1"]
+        assert received[9:18] == [f"h{i}" for i in range(8, 16)] + ["n2"]
+        assert received[18:] == [f"h{i}" for i in range(16, 20)]
+
+    def test_low_not_starved_by_normals(self):
+        messages = [("l1", "low")] + [(f"n{i}", None) for i in range(5)]
+        _, sub = self.backlog(ClientQueueConfig(capacity=16, max_burst=2), messages)
+        assert sub.drain() == ["n0", "n1", "l1", "n2", "n3", "n4"]
+
+    def test_overflow_spares_urgent_messages(self):
+        messages = [("h1", "high"), ("n1", None), ("l1", "low"), ("h2", "high"), ("l2", "low")]
+        _, sub = self.backlog(ClientQueueConfig(capacity=3), messages)
+        assert sub.drain() == ["h1", "h2", "n1"]
+        assert sub.queue_stats.dropped == 2
+
+    def test_overflow_drops_incoming_less_urgent(self):
+        messages = [("h1", "high"), ("h2", "high"), ("n1", None)]
+        _, sub = self.backlog(ClientQueueConfig(capacity=2), messages)
+        assert sub.drain() == ["h1", "h2"]
+
+    def test_drop_newest_evicts_less_urgent(self):
+        config = ClientQueueConfig(capacity=2, policy=OverflowPolicy.DropNewest)
+        messages = [("n1", None), ("l1", "low"), ("l2", "low"), ("h1", "high")]
+        _, sub = self.backlog(config, messages)
+        assert sub.drain() == ["h1", "n1"]
+        assert sub.queue_stats.dropped == 2
+
+    def test_direct_subscriber_keeps_publish_order(self):
+        mgr = ChannelManager()
+        mgr.create_channel("ch")
+        sub = mgr.subscribe("ch", "c1")
+        mgr.publish("ch", "a")
+        mgr.publish("ch", "b", priority="high")
+        assert sub.drain() == ["a", "b"]
+
+    def test_invalid_priority(self):
+        mgr = ChannelManager()
+        mgr.create_channel("ch")
+        with pytest.raises(ValueError, match="priority"):
+            mgr.publish("ch", "a", priority="urgent")
+        with pytest.raises(TypeError):
+            mgr.publish("ch", "a", priority=3)
+        with pytest.raises(ValueError):
+            ClientQueueConfig(max_burst=0)
+
+    def test_envelope_carries_priority(self):
+        mgr = ChannelManager()
+        mgr.create_channel("ch", envelope=True)
+        sub = mgr.subscribe("ch", "c1")
+        mgr.publish("ch", '{"a": 1}')
+        mgr.publish("ch", '{"a": 2}', priority="high")
+        mgr.publish("ch", '{"a": 3}', priority="low")
+        first, second, third = [json.loads(m) for m in sub.drain()]
+        assert "priority" not in first
+        assert second["priority"] == "high"
+        assert third["priority"] == "low"
+        assert third["data"] == {"a": 3}
+
+    def test_catch_up_skips_old_lows(self):
+        messages = [("n1", None), ("l1", "low"), ("l2", "low"), ("h1", "high"), ("l3", "low")]
+        mgr, _ = self.backlog(ClientQueueConfig(), messages)
+        assert mgr.messages_since("ch", 0) == (["n1", "l1", "l2", "h1", "l3"], 5)
+        assert mgr.messages_since("ch", 0, max_low=1) == (["n1", "h1", "l3"], 5)
+        assert mgr.messages_since("ch", 2, max_low=0) == (["h1"], 5)
+
+    def test_priority_survives_handoff(self):
+        mgr, _ = self.backlog(ClientQueueConfig(), [("l1", "low"), ("n1", None), ("l2", "low")])
+        new = ChannelManager()
+        new.create_channel("ch", replay_size=100)
+        assert new.import_replay(mgr.export_replay()) == (1, 3)
+        assert new.messages_since("ch", 0, max_low=1) == (["n1", "l2"], 3)
+
+    def test_broadcast_priority(self):
+        bc = RealtimeBroadcast()
+        bc.create("ch")
+        rx = bc.subscribe("ch", queue=ClientQueueConfig(capacity=8))
+        bc.send("ch", "a")
+        bc.send("ch", "b")
+        bc.send("ch", "urgent", priority="high")
+        wait_forwarded(rx, 3)
+        assert rx.drain() == ["urgent", "a", "b"]
+
+    def test_hub_publish_priority(self):
+        hub = RealtimeHub()
+        hub.channels.create_channel("room")
+        sub = hub.channels.subscribe("room", "c1", queue=ClientQueueConfig())
+        hub.publish("room", "a")
+        hub.publish_json("room", {"b": 1}, priority="high")
+        wait_forwarded(sub, 2)
+        assert sub.drain() == ['{"b":1}', "a"]
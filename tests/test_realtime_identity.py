@@ -0,0 +1,145 @@
+"""
+Test cases for realtime client identity.
+
+Tests cover:
+- Poll client IDs derived from the authenticated user, or anonymous
+- user_id passed to the subscribe hook and on_connect
+- require_auth_for_realtime rejecting anonymous polls with 401
+- Client-chosen client_id query parameters being ignored
+- realtime_identity and RealtimeHub.join_as tracking presence
+"""
+
+import base64
+
+from hypern import Hypern
+from hypern.middleware import BasicAuthMiddleware
+from hypern.realtime import ChannelManager, RealtimeHub, realtime_identity
+
+POLL_PATH = "/realtime/poll"
+
+
+def basic(user: str) -> dict:
+    token = base64.b64encode(f"{user}:secret".encode()).decode()
+    return {"Authorization": f"Basic {token}"}
+
+
+def build_app(seen: list, connected: list, **options) -> Hypern:
+    app = Hypern()
+    app.use(BasicAuthMiddleware(realm="api", users={"alice": "secret", "bob": "secret"}, optional=True))
+
+    manager = ChannelManager()
+    manager.create_channel("news", replay_size=10)
+    manager.set_subscribe_hook(
+        lambda channel, client_id, metadata: seen.append((client_id, metadata.get("user_id"))) or True
+    )
+    app.realtime_poll(manager, on_connect=lambda identity, channel: connected.append(identity), **options)
+    return app
+
+
+def poll(client, headers=None, **params):
+    return client.get(POLL_PATH, headers=headers, query={"channel": "news", "wait": 0, **params})
+
+
+class TestPollIdentity:
+    """Test identities of long polls."""
+
+    def test_authenticated_id(self):
+        seen, connected = [], []
+        client = build_app(seen, connected).test_client()
+        assert poll(client, headers=basic("alice")).status == 200
+        client_id, user_id = seen[0]
+        assert client_id.startswith("alice:") and len(client_id) > len("alice:")
+        assert user_id == "alice"
+        assert connected[0].client_id == client_id
+        assert connected[0].user_id == "alice"
+        assert connected[0].is_authenticated
+
+    def test_anonymous_id(self):
+        seen, connected = [], []
+        client = build_app(seen, connected).test_client()
+        assert poll(client).status == 200
+        client_id, user_id = seen[0]
+        assert client_id.startswith("anon:")
+        assert user_id is None
+        assert not connected[0].is_authenticated
+
+    def test_nonce_per_poll(self):
+        seen, connected = [], []
+        client = build_app(seen, connected).test_client()
+        poll(client, headers=basic("alice"))
+        poll(client, headers=basic("alice"))
+        assert seen[0][0] != seen[1][0]
+
+    def test_spoofed_id_ignored(self):
+        seen, connected = [], []
+        client = build_app(seen, connected).test_client()
+        assert poll(client, headers=basic("alice"), client_id="bob").status == 200
+        assert poll(client, client_id="bob").status == 200
+        ids = [client_id for client_id, _ in seen] + [i.client_id for i in connected]
+        assert all("bob" not in client_id for client_id in ids)
+        assert all(i.user_id != "bob" for i in connected)
+
+
+class TestRequireAuth:
+    """Test require_auth_for_realtime."""
+
+    def test_anonymous_rejected(self):
+        seen, connected = [], []
+        client = build_app(seen, connected, require_auth_for_realtime=True).test_client()
+        response = poll(client)
+        assert response.status == 401
+        assert response.json()["error"] == "unauthorized"
+        assert seen == [] and connected == []
+
+    def test_rejected_before_channel_lookup(self):
+        seen, connected = [], []
+        client = build_app(seen, connected, require_auth_for_realtime=True).test_client()
+        assert poll(client, channel="missing").status == 401
+
+    def test_authenticated_allowed(self):
+        seen, connected = [], []
+        client = build_app(seen, connected, require_auth_for_realtime=True).test_client()
+        assert poll(client, headers=basic("bob")).status == 200
+        assert connected[0].user_id == "bob"
+
+
+class TestHubPresence:
+    """Test identities resolved in handlers."""
+
+    def build(self):
+        app = Hypern()
+        app.use(BasicAuthMiddleware(realm="api", users={"alice": "secret"}, optional=True))
+        hub = RealtimeHub()
+        hub.create_channel("room")
+
+        @app.get("/join")
+        def join(req, res, ctx):
+            identity = realtime_identity(req, require_auth=True)
+            hub.join_as("room", identity, {"user_id": req.query("client_id") or "", "tab": "1"})
+            res.json(identity.to_dict())
+
+        return app.test_client(), hub
+
+    def test_presence_uses_derived_id(self):
+        client, hub = self.build()
+        response = client.get("/join", headers=basic("alice"))
+        assert response.status == 200
+        body = response.json()
+        assert body["user_id"] == "alice"
+        assert body["client_id"].startswith("alice:")
+        (info,) = hub.get_presence("room")
+        assert info.client_id == body["client_id"]
+        assert info.metadata["user_id"] == "alice"
+        assert info.metadata["tab"] == "1"
+
+    def test_unauthenticated_rejected(self):
+        client, hub = self.build()
+        assert client.get("/join").status == 401
+        assert hub.get_presence("room") == []
+
+    def test_spoofed_id_not_tracked(self):
+        client, hub = self.build()
+        client.get("/join", headers=basic("alice"), query={"client_id": "mallory"})
+        (info,) = hub.get_presence("room")
+        assert "mallory" not in info.client_id
+        assert info.metadata["user_id"] == "alice"
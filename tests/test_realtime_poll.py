@@ -0,0 +1,194 @@
+"""
+Test cases for channel replay buffers and the long-poll endpoint.
+
+Tests cover:
+- ChannelManager replay: sequence numbers, messages_since and cursor clamping
+- Polls answered immediately from retained messages
+- Held polls released by a publish, or expiring empty after the wait
+- Cursor monotonicity across consecutive polls
+- Per-client poll limit, wait cap and request errors
+- Subscribe hook applied to polls
+"""
+
+import threading
+import time
+
+import httpx
+import pytest
+
+from hypern import ChannelManager
+
+
+POLL_PATH = "/realtime/poll"
+
+
+def publish(base_url: str, channel: str, message: str) -> int:
+    response = httpx.post(f"{base_url}/poll/publish/{channel}", content=message)
+    assert response.status_code == 200
+    return response.json()["last_seq"]
+
+
+def poll(base_url: str, timeout: float = 10.0, **params) -> httpx.Response:
+    return httpx.get(f"{base_url}{POLL_PATH}", params=params, timeout=timeout)
+
+
+class TestReplayBuffer:
+    """Test replay buffers on ChannelManager directly."""
+
+    def test_sequence_numbers(self):
+        manager = ChannelManager()
+        manager.create_channel("news", replay_size=10)
+        assert manager.last_seq("news") == 0
+        manager.publish("news", "a")
+        manager.publish("news", "b")
+        assert manager.last_seq("news") == 2
+        assert manager.messages_since("news") == (["a", "b"], 2)
+        assert manager.messages_since("news", 1) == (["b"], 2)
+        assert manager.messages_since("news", 2) == ([], 2)
+
+    def test_oldest_evicted(self):
+        manager = ChannelManager()
+        manager.create_channel("news", replay_size=2)
+        for message in ("a", "b", "c"):
+            manager.publish("news", message)
+        assert manager.messages_since("news") == (["b", "c"], 3)
+
+    def test_cursor_ahead_clamped(self):
+        manager = ChannelManager()
+        manager.create_channel("news", replay_size=5)
+        manager.publish("news", "a")
+        # A cursor from before a restart resumes from the current position
+        assert manager.messages_since("news", 99) == ([], 1)
+
+    def test_without_replay_size(self):
+        manager = ChannelManager()
+        manager.create_channel("live")
+        manager.publish("live", "a")
+        assert manager.last_seq("live") == 1
+        assert manager.messages_since("live") == ([], 0)
+        stats = manager.get_stats("live")
+        assert (stats.replay_size, stats.last_seq) == (0, 1)
+
+    def test_missing_channel(self):
+        manager = ChannelManager()
+        with pytest.raises(Exception):
+            manager.messages_since("nope")
+
+
+class TestPollImmediate:
+    """Test polls answered from retained messages."""
+
+    def test_pending_messages_returned(self, base_url: str):
+        seq = publish(base_url, "poll:news", "hello")
+        response = poll(base_url, channel="poll:news", cursor=seq - 1, wait=5)
+        assert response.status_code == 200
+        assert response.headers["cache-control"] == "no-store"
+        assert response.json() == {"messages": ["hello"], "next_cursor": seq}
+
+    def test_zero_wait(self, base_url: str):
+        seq = publish(base_url, "poll:news", "x")
+        started = time.time()
+        body = poll(base_url, channel="poll:news", cursor=seq, wait=0).json()
+        assert time.time() - started < 1.0
+        assert body == {"messages": [], "next_cursor": seq}
+
+
+class TestPollHeld:
+    """Test polls held until a publish or the wait runs out."""
+
+    def test_released_by_publish(self, base_url: str):
+        seq = publish(base_url, "poll:news", "before")
+        timer = threading.Timer(0.5, publish, args=(base_url, "poll:news", "after"))
+        started = time.time()
+        timer.start()
+        try:
+            body = poll(base_url, channel="poll:news", cursor=seq, wait=5).json()
+        finally:
+            timer.join()
+        assert time.time() - started < 3.0
+        assert body == {"messages": ["after"], "next_cursor": seq + 1}
+
+    def test_expires_empty(self, base_url: str):
+        seq = publish(base_url, "poll:news", "x")
+        expired = httpx.get(f"{base_url}/poll/stats").json()["expired"]
+        started = time.time()
+        body = poll(base_url, channel="poll:news", cursor=seq, wait=1).json()
+        assert 0.9 <= time.time() - started < 3.0
+        assert body == {"messages": [], "next_cursor": seq}
+        assert httpx.get(f"{base_url}/poll/stats").json()["expired"] == expired + 1
+
+    def test_wait_capped(self, base_url: str):
+        seq = publish(base_url, "poll:news", "x")
+        started = time.time()
+        body = poll(base_url, channel="poll:news", cursor=seq, wait=3600).json()
+        # The test server caps waits at 5 seconds
+        assert time.time() - started < 8.0
+        assert body["messages"] == []
+
+    def test_cursor_monotonic(self, base_url: str):
+        cursor = publish(base_url, "poll:news", "start")
+        received = []
+        for i in range(3):
+            publish(base_url, "poll:news", f"m{i}")
+            body = poll(base_url, channel="poll:news", cursor=cursor, wait=2).json()
+            assert body["next_cursor"] > cursor
+            cursor = body["next_cursor"]
+            received.extend(body["messages"])
+        assert received == ["m0", "m1", "m2"]
+
+
+class TestPollLimits:
+    """Test the per-client limit and request validation."""
+
+    def test_per_client_limit(self, base_url: str):
+        seq = publish(base_url, "poll:news", "x")
+        held = [
+            threading.Thread(
+                target=poll,
+                args=(base_url,),
+                kwargs={"channel": "poll:news", "cursor": seq, "wait": 3},
+            )
+            for _ in range(2)
+        ]
+        for thread in held:
+            thread.start()
+        time.sleep(0.5)
+        try:
+            response = poll(base_url, channel="poll:news", cursor=seq, wait=3)
+            assert response.status_code == 429
+            assert response.headers["retry-after"] == "1"
+            assert response.json()["error"] == "too_many_polls"
+            # A client-chosen ID does not make a new client
+            other = poll(base_url, channel="poll:news", cursor=seq, wait=0, client_id="other")
+            assert other.status_code == 429
+        finally:
+            for thread in held:
+                thread.join()
+        # Slots are released once the held polls return
+        assert poll(base_url, channel="poll:news", wait=0).status_code == 200
+
+    def test_missing_channel_param(self, base_url: str):
+        assert poll(base_url).status_code == 400
+
+    def test_bad_cursor(self, base_url: str):
+        assert poll(base_url, channel="poll:news", cursor="-1").status_code == 400
+
+    def test_bad_wait(self, base_url: str):
+        assert poll(base_url, channel="poll:news", wait="soon").status_code == 400
+
+    def test_unknown_channel(self, base_url: str):
+        response = poll(base_url, channel="poll:nope", wait=0)
+        assert response.status_code == 404
+        assert response.json()["error"] == "not_found"
+
+    def test_channel_without_replay(self, base_url: str):
+        assert poll(base_url, channel="poll:live", wait=0).status_code == 400
+
+    def test_hook_denies(self, base_url: str):
+        response = poll(base_url, channel="poll:private", wait=0)
+        assert response.status_code == 403
+        assert response.json()["error"] == "forbidden"
+
+    def test_post_not_served(self, base_url: str):
+        response = httpx.post(f"{base_url}{POLL_PATH}", params={"channel": "poll:news"})
+        assert response.status_code != 200
@@ -0,0 +1,250 @@
+"""
+Test cases for realtime message schemas and envelopes.
+
+Tests cover:
+- Invalid messages rejected with the offending field named, and counted
+- Payloads rejected one byte over ``max_bytes`` and accepted at it
+- Enveloped messages carrying the replay sequence, channel and payload
+- Topic publishes skipping channels whose schema rejects the message
+- The same checks and envelope on broadcast channels
+"""
+
+import json
+import time
+
+import pytest
+
+from hypern.realtime import (
+    BroadcastConfig,
+    ChannelManager,
+    MessageSchema,
+    PublishError,
+    RealtimeBroadcast,
+)
+
+
+CHAT = {"user": "string", "text": "string", "count": "integer"}
+
+
+class TestMessageSchema:
+    """Test MessageSchema on its own."""
+
+    def test_valid_message(self):
+        schema = MessageSchema(fields=CHAT)
+        assert schema.validate('{"user": "a", "text": "hi", "count": 1, "extra": null}') is None
+
+    def test_missing_field_named(self):
+        schema = MessageSchema(fields=CHAT)
+        assert schema.validate('{"user": "a", "count": 1}') == "missing required field 'text'"
+
+    def test_wrong_type_named(self):
+        schema = MessageSchema(fields=CHAT)
+        reason = schema.validate('{"user": "a", "text": "hi", "count": 1.5}')
+        assert reason == "field 'count' must be integer, got number"
+
+    def test_not_an_object(self):
+        schema = MessageSchema(fields=CHAT)
+        assert schema.validate("[1, 2]") == "message must be a JSON object, got array"
+        assert schema.validate("hello").startswith("message is not valid JSON")
+
+    def test_plain_text_allowed_without_json(self):
+        schema = MessageSchema(max_bytes=10, json=False)
+        assert schema.validate("plain text") is None
+
+    def test_invalid_config(self):
+        with pytest.raises(ValueError, match="Unknown type 'text' for field 'user'"):
+            MessageSchema(fields={"user": "text"})
+        with pytest.raises(ValueError, match="json=True"):
+            MessageSchema(fields={"user": "string"}, json=False)
+
+    def test_fields_reported(self):
+        schema = MessageSchema(fields={"user": "str", "n": "int"})
+        assert schema.fields == [("user", "string"), ("n", "integer")]
+
+
+class TestChannelValidation:
+    """Test schema checks on ChannelManager.publish."""
+
+    def test_invalid_message_rejected(self):
+        manager = ChannelManager()
+        manager.create_channel("chat", message_schema=MessageSchema(fields=CHAT))
+        sub = manager.subscribe("chat", "c1")
+
+        with pytest.raises(PublishError) as excinfo:
+            manager.publish("chat", '{"user": "alice", "count": 1}')
+
+        assert "'text'" in str(excinfo.value)
+        assert "channel 'chat'" in str(excinfo.value)
+        assert sub.try_recv() is None
+
+        stats = manager.get_stats("chat")
+        assert stats.invalid_messages == 1
+        assert stats.total_messages == 0
+        assert stats.last_seq == 0
+
+    def test_valid_message_delivered(self):
+        manager = ChannelManager()
+        manager.create_channel("chat", message_schema=MessageSchema(fields=CHAT))
+        sub = manager.subscribe("chat", "c1")
+
+        message = '{"user": "alice", "text": "hi", "count": 1}'
+        assert manager.publish("chat", message) == 1
+        assert sub.try_recv() == message
+        assert manager.get_stats("chat").invalid_messages == 0
+
+    def test_oversized_payload_rejected_at_cap(self):
+        manager = ChannelManager()
+        manager.create_channel("logs", message_schema=MessageSchema(max_bytes=16, json=False))
+
+        manager.publish("logs", "x" * 16)
+        with pytest.raises(PublishError, match="17 bytes, over the limit of 16 bytes"):
+            manager.publish("logs", "x" * 17)
+
+        stats = manager.get_stats("logs")
+        assert stats.total_messages == 1
+        assert stats.invalid_messages == 1
+
+    def test_checked_before_publish_hook(self):
+        manager = ChannelManager()
+        manager.create_channel("chat", message_schema=MessageSchema(fields=CHAT))
+        calls = []
+        manager.set_publish_hook(lambda *args: calls.append(args) or True)
+
+        with pytest.raises(PublishError):
+            manager.publish("chat", "{}")
+        assert calls == []
+
+    def test_topic_publish_skips_invalid(self):
+        manager = ChannelManager()
+        manager.create_channel("room:strict", message_schema=MessageSchema(fields=CHAT))
+        manager.create_channel("room:open")
+        strict = manager.subscribe("room:strict", "c1")
+        open_ = manager.subscribe("room:open", "c2")
+
+        assert manager.publish_to_topic("room:*", "not json") == 1
+        assert strict.try_recv() is None
+        assert open_.try_recv() == "not json"
+        assert manager.get_stats("room:strict").invalid_messages == 1
+
+    def test_unconfigured_channel_untouched(self):
+        manager = ChannelManager()
+        manager.create_channel("raw")
+        sub = manager.subscribe("raw", "c1")
+
+        manager.publish("raw", "not json")
+        assert sub.try_recv() == "not json"
+
+
+class TestChannelEnvelope:
+    """Test enveloped delivery on ChannelManager."""
+
+    def test_envelope_structure(self):
+        manager = ChannelManager()
+        manager.create_channel("chat", envelope=True, replay_size=10)
+        sub = manager.subscribe("chat", "c1")
+
+        before = int(time.time() * 1000)
+        manager.publish("chat", '{"user": "alice", "text": "hi"}')
+        manager.publish("chat", "plain text")
+        after = int(time.time() * 1000)
+
+        first, second = (json.loads(m) for m in sub.drain())
+        assert set(first) == {"seq", "ts", "channel", "data"}
+        assert first["seq"] == 1
+        assert first["channel"] == "chat"
+        assert first["data"] == {"user": "alice", "text": "hi"}
+        assert before <= first["ts"] <= after
+
+        assert second["seq"] == 2
+        assert second["data"] == "plain text"
+
+    def test_seq_matches_replay(self):
+        manager = ChannelManager()
+        manager.create_channel("news", envelope=True, replay_size=10)
+        sub = manager.subscribe("news", "c1")
+        for n in range(3):
+            manager.publish("news", json.dumps({"n": n}))
+
+        delivered = sub.drain()
+        replayed, cursor = manager.messages_since("news", 1)
+
+        assert cursor == manager.last_seq("news") == 3
+        assert replayed == delivered[1:]
+        assert [json.loads(m)["seq"] for m in delivered] == [1, 2, 3]
+
+    def test_seq_without_replay_buffer(self):
+        manager = ChannelManager()
+        manager.create_channel("news", envelope=True)
+        sub = manager.subscribe("news", "c1")
+        manager.publish("news", "a")
+        manager.publish("news", "b")
+
+        assert [json.loads(m)["seq"] for m in sub.drain()] == [1, 2]
+
+    def test_rejected_message_takes_no_seq(self):
+        manager = ChannelManager()
+        manager.create_channel(
+            "chat", envelope=True, message_schema=MessageSchema(fields={"text": "string"})
+        )
+        sub = manager.subscribe("chat", "c1")
+
+        manager.publish("chat", '{"text": "a"}')
+        with pytest.raises(PublishError):
+            manager.publish("chat", '{"text": 1}')
+        manager.publish("chat", '{"text": "b"}')
+
+        envelopes = [json.loads(m) for m in sub.drain()]
+        assert [(e["seq"], e["data"]["text"]) for e in envelopes] == [(1, "a"), (2, "b")]
+
+
+class TestBroadcastSchema:
+    """Test schema checks and envelopes on RealtimeBroadcast."""
+
+    def test_invalid_message_rejected(self):
+        broadcast = RealtimeBroadcast()
+        config = BroadcastConfig(message_schema=MessageSchema(fields={"level": "string"}))
+        broadcast.create("alerts", config)
+        rx = broadcast.subscribe("alerts")
+
+        with pytest.raises(PublishError, match="missing required field 'level'"):
+            broadcast.send("alerts", '{"msg": "x"}')
+
+        assert rx.try_recv() is None
+        stats = broadcast.stats("alerts")
+        assert stats.total_invalid == 1
+        assert stats.total_sent == 0
+
+    def test_oversized_payload_rejected(self):
+        broadcast = RealtimeBroadcast()
+        broadcast.create("alerts", BroadcastConfig(message_schema=MessageSchema(max_bytes=8)))
+        rx = broadcast.subscribe("alerts")
+
+        assert broadcast.send("alerts", '"123456"') == 1
+        with pytest.raises(PublishError, match="9 bytes"):
+            broadcast.send("alerts", '"1234567"')
+        assert rx.drain() == ['"123456"']
+
+    def test_envelope(self):
+        broadcast = RealtimeBroadcast()
+        broadcast.create("alerts", BroadcastConfig(envelope=True))
+        rx = broadcast.subscribe("alerts")
+
+        broadcast.send("alerts", '{"level": "warn"}')
+        broadcast.send_many(["alerts"], "second")
+
+        first, second = (json.loads(m) for m in rx.drain())
+        assert first["seq"] == 1
+        assert first["channel"] == "alerts"
+        assert first["data"] == {"level": "warn"}
+        assert second["seq"] == 2
+        assert second["data"] == "second"
+
+    def test_send_many_skips_invalid(self):
+        broadcast = RealtimeBroadcast()
+        broadcast.create("strict", BroadcastConfig(message_schema=MessageSchema()))
+        broadcast.create("open")
+        subscribers = [broadcast.subscribe("strict"), broadcast.subscribe("open")]
+
+        assert broadcast.send_many(["strict", "open"], "not json") == {"open": 1}
+        assert [rx.try_recv() for rx in subscribers] == [None, "not json"]
+        assert broadcast.global_stats().total_invalid == 1
@@ -0,0 +1,240 @@
+"""
+Test cases for replay history handed across graceful reloads.
+
+Tests cover:
+- Snapshots restoring sequence counters and retained messages
+- Readers resuming with a cursor taken before the handoff
+- The size cap dropping the oldest messages first, counters kept
+- Channels that are missing or already in use left alone
+- Corrupt snapshots rejected, restore counters in lifecycle_stats
+- A real SIGUSR1 reload with a handoff_key manager
+"""
+
+import json
+import os
+import signal
+import socket
+import subprocess
+import sys
+import time
+
+import httpx
+import pytest
+
+from hypern import ChannelManager
+
+
+ROOT = os.path.dirname(os.path.dirname(os.path.abspath(__file__)))
+
+
+def old_generation(count: int = 5, replay_size: int = 10) -> ChannelManager:
+    manager = ChannelManager()
+    manager.create_channel("news", replay_size=replay_size)
+    for i in range(1, count + 1):
+        manager.publish("news", f"m{i}")
+    return manager
+
+
+class TestHandoff:
+    """Test export_replay / import_replay between two managers."""
+
+    def test_sequence_continues(self):
+        old = old_generation()
+        cursor = 3
+
+        new = ChannelManager()
+        new.create_channel("news", replay_size=10)
+        assert new.import_replay(old.export_replay()) == (1, 5)
+
+        assert new.last_seq("news") == 5
+        assert new.messages_since("news", cursor) == (["m4", "m5"], 5)
+        new.publish("news", "m6")
+        assert new.messages_since("news", 5) == (["m6"], 6)
+
+    def test_smaller_buffer_keeps_newest(self):
+        old = old_generation()
+        new = ChannelManager()
+        new.create_channel("news", replay_size=2)
+
+        assert new.import_replay(old.export_replay()) == (1, 2)
+        assert new.messages_since("news") == (["m4", "m5"], 5)
+
+    def test_sequence_without_retained_messages(self):
+        old = old_generation(replay_size=0)
+        new = ChannelManager()
+        new.create_channel("news")
+
+        assert new.import_replay(old.export_replay()) == (1, 0)
+        assert new.last_seq("news") == 5
+
+    def test_envelopes_continue(self):
+        old = ChannelManager()
+        old.create_channel("chat", replay_size=5, envelope=True)
+        old.publish("chat", '{"text": "hi"}')
+
+        new = ChannelManager()
+        new.create_channel("chat", replay_size=5, envelope=True)
+        new.import_replay(old.export_replay())
+        new.publish("chat", '{"text": "again"}')
+
+        messages, cursor = new.messages_since("chat")
+        assert cursor == 2
+        assert [json.loads(m)["seq"] for m in messages] == [1, 2]
+
+
+class TestSizeCap:
+    """Test the cap on saved message text."""
+
+    def test_oldest_dropped(self):
+        old = old_generation()
+        new = ChannelManager()
+        new.create_channel("news", replay_size=10)
+
+        # Two messages of two bytes each fit
+        assert new.import_replay(old.export_replay(max_bytes=5)) == (1, 2)
+        assert new.last_seq("news") == 5
+        assert new.messages_since("news") == (["m4", "m5"], 5)
+
+    def test_channels_share_the_cap(self):
+        old = ChannelManager()
+        old.create_channel("busy", replay_size=100)
+        old.create_channel("quiet", replay_size=100)
+        for i in range(50):
+            old.publish("busy", f"b{i:02d}")
+        old.publish("quiet", "q01")
+
+        new = ChannelManager()
+        new.create_channel("busy", replay_size=100)
+        new.create_channel("quiet", replay_size=100)
+        new.import_replay(old.export_replay(max_bytes=12))
+
+        assert new.messages_since("quiet") == (["q01"], 1)
+        assert new.messages_since("busy") == (["b47", "b48", "b49"], 50)
+
+    def test_manager_default(self):
+        old = ChannelManager(handoff_max_bytes=4)
+        old.create_channel("news", replay_size=10)
+        for message in ("aa", "bb", "cc"):
+            old.publish("news", message)
+
+        new = ChannelManager()
+        new.create_channel("news", replay_size=10)
+        new.import_replay(old.export_replay())
+        assert new.messages_since("news") == (["bb", "cc"], 3)
+
+
+class TestRestoreRules:
+    """Test which channels a snapshot is restored into."""
+
+    def test_missing_channel_skipped(self):
+        old = old_generation()
+        new = ChannelManager()
+
+        assert new.import_replay(old.export_replay()) == (0, 0)
+        assert not new.has_channel("news")
+
+    def test_channel_in_use_kept(self):
+        old = old_generation()
+        new = ChannelManager()
+        new.create_channel("news", replay_size=10)
+        new.publish("news", "fresh")
+
+        assert new.import_replay(old.export_replay()) == (0, 0)
+        assert new.messages_since("news") == (["fresh"], 1)
+
+    def test_corrupt_snapshot(self):
+        new = ChannelManager()
+        new.create_channel("news", replay_size=10)
+
+        with pytest.raises(ValueError, match="corrupt snapshot"):
+            new.import_replay(b"{not json")
+        with pytest.raises(ValueError, match="version 99"):
+            new.import_replay(b'{"version": 99, "channels": []}')
+        assert new.last_seq("news") == 0
+
+    def test_lifecycle_stats(self):
+        old = old_generation()
+        old.create_channel("idle", replay_size=10)
+        new = ChannelManager()
+        new.create_channel("news", replay_size=10)
+        new.create_channel("idle", replay_size=10)
+
+        new.import_replay(old.export_replay())
+
+        stats = new.lifecycle_stats()
+        assert stats["restored_channels"] == 1
+        assert stats["restored_messages"] == 5
+
+
+class TestGracefulReload:
+    """Test the handoff through a real SIGUSR1 reload."""
+
+    def test_history_survives_reload(self):
+        script = """
+import os
+import sys
+sys.path.insert(0, sys.argv[1])
+from hypern import Hypern
+from hypern.realtime import ChannelManager
+
+manager = ChannelManager(handoff_key="news")
+manager.create_channel("news", replay_size=10)
+
+app = Hypern()
+app.setup_reload(drain_timeout_secs=1, startup_grace_secs=0)
+
+@app.post("/publish")
+def publish(req, res, ctx):
+    manager.publish("news", req.body_bytes().decode())
+    res.json({"last_seq": manager.last_seq("news"), "pid": os.getpid()})
+
+@app.get("/since/{cursor}")
+def since(req, res, ctx):
+    messages, cursor = manager.messages_since("news", int(req.path_params["cursor"]))
+    res.json({"messages": messages, "cursor": cursor, "pid": os.getpid(),
+              "stats": manager.lifecycle_stats()})
+
+app.start(host="127.0.0.1", port=int(sys.argv[2]), num_processes=1)
+"""
+        with socket.socket() as s:
+            s.bind(("127.0.0.1", 0))
+            port = s.getsockname()[1]
+        base = f"http://127.0.0.1:{port}"
+
+        server = subprocess.Popen([sys.executable, "-c", script, ROOT, str(port)])
+        try:
+            old_pid = None
+            for message in ("a", "b", "c"):
+                old_pid = wait_for(lambda: httpx.post(f"{base}/publish", content=message))["pid"]
+
+            server.send_signal(signal.SIGUSR1)
+
+            def reloaded():
+                body = httpx.get(f"{base}/since/1").json()
+                assert body["pid"] != old_pid
+                return body
+
+            body = wait_for(reloaded)
+            assert body["messages"] == ["b", "c"]
+            assert body["cursor"] == 3
+            assert body["stats"]["restored_channels"] == 1
+            assert body["stats"]["restored_messages"] == 3
+            assert wait_for(lambda: httpx.post(f"{base}/publish", content="d"))["last_seq"] == 4
+        finally:
+            server.terminate()
+            server.wait(timeout=30)
+
+
+def wait_for(request, timeout: float = 20.0):
+    deadline = time.monotonic() + timeout
+    while True:
+        try:
+            result = request()
+            if isinstance(result, httpx.Response):
+                assert result.status_code == 200
+                return result.json()
+            return result
+        except (httpx.HTTPError, AssertionError):
+            if time.monotonic() > deadline:
+                raise
+            time.sleep(0.2)
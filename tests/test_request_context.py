@@ -0,0 +1,69 @@
+"""
+Tests for request-scoped context propagation.
+
+Tests cover:
+- current_request_id()/current_context() inside handlers
+- Logging filters reading the contextvar see the response's request ID
+- req.context fallback carries the same values
+- Accessors return None outside a request
+"""
+
+import httpx
+
+import hypern
+from hypern import current_context, current_request_id
+
+
+class TestOutsideRequest:
+    """Test accessors when no request is being handled."""
+
+    def test_current_request_id_is_none(self):
+        assert current_request_id() is None
+
+    def test_current_context_is_none(self):
+        assert current_context() is None
+
+    def test_context_var_default(self):
+        assert hypern.context.name == "hypern.context"
+        assert hypern.context.get() is None
+
+
+class TestHandlerContext:
+    """Test the context published to handlers."""
+
+    def test_logging_filter_sees_response_request_id(self, client: httpx.Client):
+        """A logging filter reading the contextvar matches X-Request-ID."""
+        response = client.get("/context/request-id")
+        assert response.status_code == 200
+        header_id = response.headers.get("x-request-id")
+        assert header_id
+        data = response.json()
+        assert data["logged"] == header_id
+        assert data["current"] == header_id
+
+    def test_context_fields(self, client: httpx.Client):
+        response = client.get(
+            "/context/request-id",
+            headers={"X-Forwarded-For": "203.0.113.7, 10.0.0.1"},
+        )
+        context = response.json()["context"]
+        assert context["path"] == "/context/request-id"
+        assert context["client_ip"] == "203.0.113.7"
+        assert "trace_id" in context
+
+    def test_trace_id_from_traceparent(self, client: httpx.Client):
+        trace_id = "4bf92f3577b34da6a3ce929d0e0e4736"
+        response = client.get(
+            "/context/request-id",
+            headers={"traceparent": f"00-{trace_id}-00f067aa0ba902b7-01"},
+        )
+        assert response.json()["context"]["trace_id"] == trace_id
+
+    def test_req_context_matches_contextvar(self, client: httpx.Client):
+        data = client.get("/context/request-id").json()
+        assert data["req_context"] == data["context"]
+
+    def test_request_ids_differ_between_requests(self, client: httpx.Client):
+        first = client.get("/context/request-id").json()["current"]
+        second = client.get("/context/request-id").json()["current"]
+        assert first != second
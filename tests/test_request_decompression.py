@@ -0,0 +1,136 @@
+"""
+Test cases for transparent request body decompression.
+
+The test server runs with ``decompress_requests=True`` and a 1 MiB
+``max_decompressed_size``.
+
+Tests cover:
+- gzip, x-gzip and deflate (zlib and raw) JSON bodies parsed via ``.json()``
+- Content-Encoding stripped and Content-Length updated for the handler
+- Decompression bombs rejected with 413 at the cap
+- Unknown encodings rejected with 415, corrupt bodies with 400
+- Original vs inflated size counters
+"""
+
+import gzip
+import json
+import time
+import zlib
+
+import httpx
+import pytest
+
+from hypern._hypern import Server
+
+
+PAYLOAD = {"name": "Alice", "tags": ["a"] * 200}
+
+
+def encoded_post(client: httpx.Client, path: str, body: bytes, encoding: str) -> httpx.Response:
+    return client.post(
+        path,
+        content=body,
+        headers={"Content-Type": "application/json", "Content-Encoding": encoding},
+    )
+
+
+def raw_deflate(data: bytes) -> bytes:
+    compressor = zlib.compressobj(wbits=-15)
+    return compressor.compress(data) + compressor.flush()
+
+
+def gzip_bomb(inflated_size: int) -> bytes:
+    """Concatenated gzip members of zeros; ~1KB per MiB inflated."""
+    member = gzip.compress(b"\0" * (1024 * 1024), compresslevel=9)
+    return member * (inflated_size // (1024 * 1024))
+
+
+class TestDecompressedBodies:
+    """Test that encoded bodies reach handlers decoded."""
+
+    @pytest.mark.parametrize("encoding", ["gzip", "x-gzip", "GZIP"])
+    def test_gzip_json(self, client: httpx.Client, encoding):
+        body = gzip.compress(json.dumps(PAYLOAD).encode())
+        response = encoded_post(client, "/echo", body, encoding)
+        assert response.status_code == 200
+        assert response.json()["echo"] == PAYLOAD
+
+    @pytest.mark.parametrize("compress", [zlib.compress, raw_deflate])
+    def test_deflate_json(self, client: httpx.Client, compress):
+        body = compress(json.dumps(PAYLOAD).encode())
+        response = encoded_post(client, "/echo", body, "deflate")
+        assert response.json()["echo"] == PAYLOAD
+
+    def test_stacked_encodings(self, client: httpx.Client):
+        body = gzip.compress(zlib.compress(json.dumps(PAYLOAD).encode()))
+        response = encoded_post(client, "/echo", body, "deflate, gzip")
+        assert response.json()["echo"] == PAYLOAD
+
+    def test_headers_rewritten(self, client: httpx.Client):
+        raw = json.dumps(PAYLOAD).encode()
+        response = encoded_post(client, "/echo/body-info", gzip.compress(raw), "gzip")
+        info = response.json()
+        assert info["size"] == len(raw)
+        assert info["content_encoding"] is None
+        assert info["content_length"] == str(len(raw))
+
+    def test_identity_passthrough(self, client: httpx.Client):
+        response = encoded_post(client, "/echo", json.dumps(PAYLOAD).encode(), "identity")
+        assert response.json()["echo"] == PAYLOAD
+
+    def test_route_limit_applies_to_inflated_body(self, client: httpx.Client):
+        body = gzip.compress(json.dumps({"data": "x" * 200}).encode())
+        assert len(body) < 64
+        response = encoded_post(client, "/echo/limited", body, "gzip")
+        assert response.status_code == 413
+
+
+class TestRejectedBodies:
+    """Test bombs, unknown encodings and corrupt data."""
+
+    def test_bomb_rejected_at_cap(self, client: httpx.Client):
+        # ~1MB on the wire that would inflate to 1 GiB
+        body = gzip_bomb(1024 * 1024 * 1024)
+        start = time.monotonic()
+        response = encoded_post(client, "/echo", body, "gzip")
+        assert response.status_code == 413
+        assert response.text.startswith("Decompressed request body exceeds")
+        assert time.monotonic() - start < 5.0
+
+    def test_body_at_cap_accepted(self, client: httpx.Client):
+        body = gzip_bomb(1024 * 1024)
+        response = encoded_post(client, "/echo/body-info", body, "gzip")
+        assert response.json()["size"] == 1024 * 1024
+
+    @pytest.mark.parametrize("encoding", ["br", "zstd", "gzip, compress"])
+    def test_unknown_encoding(self, client: httpx.Client, encoding):
+        response = encoded_post(client, "/echo", b"{}", encoding)
+        assert response.status_code == 415
+        assert response.text.startswith("Unsupported Content-Encoding")
+
+    def test_corrupt_gzip(self, client: httpx.Client):
+        response = encoded_post(client, "/echo", b"not gzip at all", "gzip")
+        assert response.status_code == 400
+
+
+class TestDecompressionStats:
+    """Test the original vs inflated size counters."""
+
+    def test_counts_sizes(self, client: httpx.Client):
+        before = client.get("/decompression/stats").json()
+        raw = json.dumps(PAYLOAD).encode()
+        body = gzip.compress(raw)
+        encoded_post(client, "/echo", body, "gzip")
+        after = client.get("/decompression/stats").json()
+
+        assert after["enabled"] is True
+        assert after["max_decompressed_size"] == 1024 * 1024
+        assert after["decompressed"] == before["decompressed"] + 1
+        assert after["compressed_bytes"] - before["compressed_bytes"] == len(body)
+        assert after["inflated_bytes"] - before["inflated_bytes"] == len(raw)
+
+    def test_server_options(self):
+        stats = Server(decompress_requests=True, max_decompressed_size=4096).stats()
+        assert stats["decompress_requests"] is True
+        assert stats["max_decompressed_size"] == 4096
+        assert Server().stats()["decompress_requests"] is False
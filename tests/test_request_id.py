@@ -0,0 +1,60 @@
+"""
+Test cases for request ID generation.
+
+Tests cover:
+- Format of generated IDs
+- Uniqueness across a million IDs generated from several threads
+- Context and back-to-back identical requests getting distinct IDs
+"""
+
+import re
+import threading
+
+import httpx
+
+from hypern import Context, generate_request_id
+
+
+class TestGenerator:
+    """Test the request ID generator directly."""
+
+    def test_format(self):
+        assert re.fullmatch(r"[0-9a-f]{32}", generate_request_id())
+
+    def test_unique_across_threads(self):
+        per_thread = 125_000
+        results = [None] * 8
+
+        def generate(index: int):
+            results[index] = [generate_request_id() for _ in range(per_thread)]
+
+        threads = [threading.Thread(target=generate, args=(i,)) for i in range(len(results))]
+        for thread in threads:
+            thread.start()
+        for thread in threads:
+            thread.join()
+
+        ids = set()
+        for batch in results:
+            ids.update(batch)
+        assert len(ids) == per_thread * len(results)
+
+    def test_same_process_tag(self):
+        first, second = generate_request_id(), generate_request_id()
+        assert first[:16] == second[:16]
+        assert int(second[16:], 16) > int(first[16:], 16)
+
+    def test_context_ids_differ(self):
+        assert Context().request_id != Context().request_id
+
+
+class TestRequestIds:
+    """Test IDs assigned to requests."""
+
+    def test_back_to_back_identical_requests(self, client: httpx.Client):
+        first = client.get("/middleware/requestid/test?same=1")
+        second = client.get("/middleware/requestid/test?same=1")
+        first_id = first.headers["X-Request-ID"]
+        second_id = second.headers["X-Request-ID"]
+        assert first_id != second_id
+        assert re.fullmatch(r"[0-9a-f]{32}", first_id)
@@ -0,0 +1,83 @@
+"""
+Test cases for the request object handlers receive.
+
+Tests cover:
+- path_params populated from the router match, on static and cached lookups
+- json() and cookies() on the handler's request
+- The body still readable by the handler after middleware read it
+- query_list() returning repeated query values
+"""
+
+import pytest
+
+from hypern import Hypern
+
+
+def build_app() -> Hypern:
+    app = Hypern()
+
+    @app.get("/users/{user_id}/posts/{post_id}")
+    def post_route(req, res, ctx):
+        res.json({"params": req.path_params, "user": req.param("user_id")})
+
+    @app.post("/users/{user_id}")
+    def update_user(req, res, ctx):
+        res.json({"id": req.path_params["user_id"], "body": req.json()})
+
+    @app.get("/session")
+    def session(req, res, ctx):
+        res.json({"cookies": req.cookies(), "tags": req.query_list("tag")})
+
+    @app.post("/audited")
+    def audited(req, res, ctx):
+        res.json({"body": req.json()})
+
+    async def read_body(req, res, ctx, next):
+        res.header("X-Seen-Bytes", str(len(req.body_bytes())))
+        await next()
+
+    app.use(read_body, methods=["POST"])
+    return app
+
+
+@pytest.fixture(scope="module")
+def client():
+    return build_app().test_client()
+
+
+class TestPathParams:
+    """Test path_params after routing."""
+
+    def test_populated(self, client):
+        response = client.get("/users/7/posts/42")
+
+        assert response.json() == {"params": {"user_id": "7", "post_id": "42"}, "user": "7"}
+
+    def test_populated_on_repeat(self, client):
+        # The second lookup is served from the route cache
+        client.get("/users/8/posts/1")
+        assert client.get("/users/8/posts/1").json()["params"] == {"user_id": "8", "post_id": "1"}
+
+
+class TestBody:
+    """Test json() and cookies() on the handler request."""
+
+    def test_json_with_params(self, client):
+        response = client.post("/users/3", json={"name": "Ada"})
+
+        assert response.json() == {"id": "3", "body": {"name": "Ada"}}
+
+    def test_cookies_and_query_list(self, client):
+        response = client.get(
+            "/session",
+            headers={"Cookie": "sid=abc; theme=dark"},
+            query=[("tag", "a"), ("tag", "b")],
+        )
+
+        assert response.json() == {"cookies": {"sid": "abc", "theme": "dark"}, "tags": ["a", "b"]}
+
+    def test_body_after_middleware_read(self, client):
+        response = client.post("/audited", json={"n": 1})
+
+        assert response.headers["x-seen-bytes"] == "7"
+        assert response.json() == {"body": {"n": 1}}
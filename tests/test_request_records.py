@@ -0,0 +1,209 @@
+"""
+Test cases for NDJSON and CSV request bodies.
+
+Tests cover:
+- A 100k-line NDJSON body parsed with its two corrupt lines reported
+- Blank lines, CRLF endings and lines split across chunks
+- Too many bad lines, overlong lines and record limits
+- CSV with quoted commas, doubled quotes, embedded newlines and a BOM
+- Header-less CSV, custom delimiters, ragged rows and field/row limits
+- Content-type checks and charset transcoding
+"""
+
+import json
+
+import pytest
+
+from hypern import Hypern
+
+
+NDJSON = {"Content-Type": "application/x-ndjson"}
+CSV = {"Content-Type": "text/csv"}
+
+
+def build_app() -> Hypern:
+    app = Hypern()
+
+    @app.post("/ndjson")
+    def ndjson(req, res, ctx):
+        options = json.loads(req.query("options") or "{}")
+        reader = req.ndjson(**options)
+        records = list(reader)
+        res.json(
+            {
+                "count": len(records),
+                "first": records[:2],
+                "last": records[-1:],
+                "bad_lines": reader.bad_lines,
+                "lines": reader.lines,
+            }
+        )
+
+    @app.post("/csv")
+    def csv(req, res, ctx):
+        options = json.loads(req.query("options") or "{}")
+        reader = req.csv(**options)
+        rows = list(reader)
+        res.json({"rows": rows, "header": reader.header, "count": reader.rows})
+
+    @app.post("/csv/first")
+    def csv_first(req, res, ctx):
+        res.json(next(req.csv()))
+
+    return app
+
+
+@pytest.fixture(scope="module")
+def client():
+    return build_app().test_client()
+
+
+def post(client, path, body, headers, **options):
+    query = {"options": json.dumps(options)} if options else None
+    return client.post(path, data=body, headers=headers, query=query)
+
+
+class TestNdjson:
+    """Test Request.ndjson()."""
+
+    def test_large_body_with_corrupt_lines(self, client):
+        lines = [
+            json.dumps({"id": n, "name": f"user-{n}", "tags": ["a", "b"]}) for n in range(100_000)
+        ]
+        lines[41_999] = '{"id": 42000, "name": "broken"'
+        lines[99_998] = "not json at all"
+        body = "\n".join(lines) + "\n"
+
+        response = post(client, "/ndjson", body, NDJSON, max_bad_lines=2)
+
+        assert response.status == 200
+        result = response.json()
+        assert result["count"] == 99_998
+        assert result["lines"] == 100_000
+        assert result["first"][0] == {"id": 0, "name": "user-0", "tags": ["a", "b"]}
+        assert result["last"] == [{"id": 99_999, "name": "user-99999", "tags": ["a", "b"]}]
+        assert [line for line, _ in result["bad_lines"]] == [42_000, 99_999]
+
+    def test_too_many_bad_lines(self, client):
+        body = '{"a": 1}\n{bad\n{"a": 2}\n{worse\n'
+
+        response = post(client, "/ndjson", body, NDJSON, max_bad_lines=1)
+
+        assert response.status == 400
+        assert "NDJSON line 4 is not valid JSON" in response.text
+        assert "(1 bad line allowed)" in response.text
+
+    def test_no_bad_lines_by_default(self, client):
+        response = post(client, "/ndjson", '{"a": 1}\n[1,\n', NDJSON)
+        assert response.status == 400
+        assert "line 2" in response.text
+
+    def test_blank_lines_and_crlf(self, client):
+        body = '{"a": 1}\r\n\r\n   \n{"a": 2}\r\n{"a": 3}'
+
+        result = post(client, "/ndjson", body, NDJSON).json()
+
+        assert result["count"] == 3
+        assert result["last"] == [{"a": 3}]
+        assert result["lines"] == 5
+
+    def test_lines_split_across_chunks(self, client):
+        body = "\n".join(json.dumps({"n": n, "pad": "x" * n}) for n in range(50))
+
+        result = post(client, "/ndjson", body, NDJSON, chunk_size=7).json()
+
+        assert result["count"] == 50
+        assert result["last"] == [{"n": 49, "pad": "x" * 49}]
+
+    def test_line_limit(self, client):
+        body = '{"a": 1}\n{"b": "' + "x" * 200 + '"}\n'
+
+        response = post(client, "/ndjson", body, NDJSON, max_line_bytes=100, chunk_size=16)
+
+        assert response.status == 413
+        assert "NDJSON line 2 is over the 100 byte limit" in response.text
+
+    def test_record_limit(self, client):
+        body = "1\n2\n3\n"
+        response = post(client, "/ndjson", body, NDJSON, max_records=2)
+        assert response.status == 413
+        assert "more than 2 records" in response.text
+
+    def test_content_type(self, client):
+        assert post(client, "/ndjson", "1\n", {"Content-Type": "text/plain"}).status == 415
+        assert post(client, "/ndjson", "1\n", {"Content-Type": "text/plain"}, force=True).status == 200
+        assert post(client, "/ndjson", "1\n", {"Content-Type": "application/jsonl"}).status == 200
+
+    def test_bom_stripped(self, client):
+        result = post(client, "/ndjson", '\ufeff{"a": 1}\n'.encode(), NDJSON).json()
+        assert result["first"] == [{"a": 1}]
+
+
+class TestCsv:
+    """Test Request.csv()."""
+
+    def test_quoting_newlines_and_bom(self, client):
+        body = (
+            "\ufeffname,address,note\r\n"
+            '"Smith, John","12 Main St\nApt 4","said ""hi"""\r\n'
+            'Jane,"Paris, France",\r\n'
+        ).encode()
+
+        result = post(client, "/csv", body, CSV).json()
+
+        assert result["header"] == ["name", "address", "note"]
+        assert result["rows"] == [
+            {"name": "Smith, John", "address": "12 Main St\nApt 4", "note": 'said "hi"'},
+            {"name": "Jane", "address": "Paris, France", "note": ""},
+        ]
+        assert result["count"] == 2
+
+    def test_without_header(self, client):
+        body = "1,2,3\n4,5,6\n"
+
+        result = post(client, "/csv", body, CSV, has_header=False).json()
+
+        assert result["rows"] == [["1", "2", "3"], ["4", "5", "6"]]
+        assert result["header"] is None
+
+    def test_delimiter(self, client):
+        body = "a;b\n1;2,5\n"
+        result = post(client, "/csv", body, CSV, delimiter=";").json()
+        assert result["rows"] == [{"a": "1", "b": "2,5"}]
+
+    def test_invalid_delimiter(self, client):
+        response = post(client, "/csv", "a\n", CSV, delimiter="::")
+        assert response.status == 500
+        assert "delimiter must be a single ASCII character" in response.text
+
+    def test_ragged_row(self, client):
+        response = post(client, "/csv", "a,b\n1,2\n3\n", CSV)
+        assert response.status == 400
+        assert "Malformed CSV body" in response.text
+
+    def test_field_limit(self, client):
+        body = "a,b\n1," + "x" * 64 + "\n"
+        response = post(client, "/csv", body, CSV, max_field_bytes=32)
+        assert response.status == 413
+        assert "CSV field 2 on line 2 is over the 32 byte limit" in response.text
+
+    def test_row_limit(self, client):
+        body = "a\n" + "1\n" * 5
+        response = post(client, "/csv", body, CSV, max_rows=4)
+        assert response.status == 413
+        assert "more than 4 rows" in response.text
+
+    def test_stops_early(self, client):
+        body = "a\n1\n" + "x,y\n" * 10
+        # The ragged rows after the first are never read
+        assert post(client, "/csv/first", body, CSV).json() == {"a": "1"}
+
+    def test_latin1_charset(self, client):
+        body = "name\nJosé\n".encode("latin-1")
+        headers = {"Content-Type": "text/csv; charset=iso-8859-1"}
+        assert post(client, "/csv", body, headers).json()["rows"] == [{"name": "José"}]
+
+    def test_content_type(self, client):
+        response = post(client, "/csv", "a\n1\n", {"Content-Type": "application/json"})
+        assert response.status == 415
+        assert "Expected a CSV content type" in response.text
@@ -0,0 +1,155 @@
+"""
+Test cases for middleware state read by handlers.
+
+Tests cover:
+- Values the middleware marked visible, through state() and typed getters
+- Internal keys not visible to the handler
+- TypeError from typed getters for values of another type
+- req.auth round-tripping from BasicAuthMiddleware
+"""
+
+import base64
+
+import pytest
+
+from hypern import AuthInfo, Hypern
+from hypern.middleware import (
+    BasicAuthMiddleware,
+    CompressionMiddleware,
+    RequestIdMiddleware,
+    TimeoutMiddleware,
+)
+
+
+def basic(user: str, password: str = "secret") -> dict:
+    token = base64.b64encode(f"{user}:{password}".encode()).decode()
+    return {"Authorization": f"Basic {token}"}
+
+
+@pytest.fixture
+def client():
+    app = Hypern()
+    app.use(CompressionMiddleware())
+    app.use(RequestIdMiddleware())
+    app.use(TimeoutMiddleware(timeout_secs=5))
+
+    @app.get("/state")
+    def state(req, res, ctx):
+        return {
+            "compression": req.state_str("compression"),
+            "request_id": req.state("request_id"),
+            "timeout_ms": req.state_int("request_timeout_ms"),
+            "min_size": req.state("compression_min_size"),
+            "min_size_default": req.state("compression_min_size", "hidden"),
+            "missing": req.state_bool("missing", False),
+        }
+
+    @app.get("/wrong-type")
+    def wrong_type(req, res, ctx):
+        try:
+            req.state_int("compression")
+        except TypeError as error:
+            return {"error": str(error)}
+        return {"error": None}
+
+    @app.get("/auth")
+    def auth(req, res, ctx):
+        return {
+            "type": type(req.auth).__name__,
+            "authenticated": req.auth.is_authenticated,
+        }
+
+    return app.test_client()
+
+
+class TestVisibleState:
+    """Test values the middleware exposed."""
+
+    def test_handler_reads_middleware_value(self, client):
+        response = client.get("/state", headers={"Accept-Encoding": "gzip"})
+        data = response.json()
+        assert data["compression"] == "gzip"
+        assert data["timeout_ms"] == 5000
+
+    def test_request_id_matches_header(self, client):
+        response = client.get("/state")
+        assert response.json()["request_id"] == response.headers["x-request-id"]
+
+    def test_default_for_missing_key(self, client):
+        data = client.get("/state").json()
+        assert data["compression"] is None
+        assert data["missing"] is False
+
+    def test_typed_getter_rejects_other_type(self, client):
+        error = client.get("/wrong-type", headers={"Accept-Encoding": "br"}).json()["error"]
+        assert error == 'state "compression" is str, not int'
+
+
+class TestInternalState:
+    """Test keys the middleware kept internal."""
+
+    def test_internal_key_not_visible(self, client):
+        data = client.get("/state", headers={"Accept-Encoding": "gzip"}).json()
+        assert data["min_size"] is None
+        assert data["min_size_default"] == "hidden"
+
+    def test_no_auth_without_middleware(self, client):
+        assert client.get("/auth").json() == {
+            "type": "AuthInfo",
+            "authenticated": False,
+        }
+
+
+class TestAuth:
+    """Test req.auth set by BasicAuthMiddleware."""
+
+    @pytest.fixture
+    def auth_client(self):
+        app = Hypern()
+        app.use(
+            BasicAuthMiddleware(
+                users={"alice": "secret", "bob": "secret"},
+                roles={"alice": ["admin", "billing"]},
+                optional=True,
+            )
+        )
+
+        @app.get("/me")
+        def me(req, res, ctx):
+            auth = req.auth
+            return {
+                "user_id": auth.user_id,
+                "roles": auth.roles,
+                "authenticated": auth.is_authenticated,
+                "truthy": bool(auth),
+                "admin": auth.has_role("admin"),
+            }
+
+        return app.test_client()
+
+    def test_round_trip(self, auth_client):
+        assert auth_client.get("/me", headers=basic("alice")).json() == {
+            "user_id": "alice",
+            "roles": ["admin", "billing"],
+            "authenticated": True,
+            "truthy": True,
+            "admin": True,
+        }
+
+    def test_user_without_roles(self, auth_client):
+        data = auth_client.get("/me", headers=basic("bob")).json()
+        assert data["user_id"] == "bob"
+        assert data["roles"] == []
+        assert data["admin"] is False
+
+    def test_anonymous(self, auth_client):
+        assert auth_client.get("/me").json() == {
+            "user_id": None,
+            "roles": [],
+            "authenticated": False,
+            "truthy": False,
+            "admin": False,
+        }
+
+    def test_auth_info_exported(self):
+        assert AuthInfo.__name__ == "AuthInfo"
@@ -0,0 +1,155 @@
+"""
+Test cases for per-route response field filtering.
+
+Tests cover:
+- Top-level and nested exclusion, including lists of dicts
+- Include lists with nested fields, and fields listed whole
+- Strict include specs raising for missing fields
+- Non-dict responses passing through untouched
+- Route spec validation and the response_fields getter
+"""
+
+import pytest
+
+from hypern import Hypern
+from hypern._hypern import Route
+
+
+USER = {
+    "id": 1,
+    "name": "Ada",
+    "password_hash": "pbkdf2$...",
+    "user": {"email": "ada@example.com", "ssn": "123-45-6789"},
+}
+
+
+@pytest.fixture(scope="module")
+def client():
+    app = Hypern()
+
+    @app.get("/user", response_fields={"exclude": ["password_hash", "user.ssn"]})
+    def user(req, res, ctx):
+        res.json(USER)
+
+    @app.get("/users", response_fields={"exclude": ["password_hash", "user.ssn"]})
+    def users(req, res, ctx):
+        res.json([USER, dict(USER, id=2), "not a dict"])
+
+    @app.get("/orders", response_fields={"exclude": ["items.cost"]})
+    def orders(req, res, ctx):
+        res.json({"id": 7, "items": [{"sku": "a", "cost": 3}, {"sku": "b", "cost": 4}]})
+
+    @app.get("/include", response_fields={"include": ["id", "user.email", "missing"]})
+    def include(req, res, ctx):
+        res.json(USER)
+
+    @app.get("/include-whole", response_fields={"include": ["user"], "exclude": ["user.ssn"]})
+    def include_whole(req, res, ctx):
+        res.json(USER)
+
+    @app.get("/strict", response_fields={"include": ["id", "user.phone"], "strict": True})
+    def strict(req, res, ctx):
+        try:
+            res.json(USER)
+        except ValueError as err:
+            # The spec applies to every JSON body of the route
+            res.status(500).text(str(err))
+
+    @app.get("/text", response_fields={"exclude": ["password_hash"]})
+    def text(req, res, ctx):
+        res.send("password_hash stays in text")
+
+    @app.get("/scalar", response_fields={"exclude": ["id"]})
+    def scalar(req, res, ctx):
+        res.json(42)
+
+    @app.get("/send", response_fields={"exclude": ["password_hash"]})
+    def send(req, res, ctx):
+        res.send(USER)
+
+    @app.get("/plain")
+    def plain(req, res, ctx):
+        res.json(USER)
+
+    return app.test_client()
+
+
+class TestExclude:
+    """Test exclude specs."""
+
+    def test_nested_exclusion(self, client):
+        assert client.get("/user").json() == {
+            "id": 1,
+            "name": "Ada",
+            "user": {"email": "ada@example.com"},
+        }
+
+    def test_list_of_dicts(self, client):
+        data = client.get("/users").json()
+        assert [item["id"] for item in data[:2]] == [1, 2]
+        for item in data[:2]:
+            assert "password_hash" not in item
+            assert item["user"] == {"email": "ada@example.com"}
+        assert data[2] == "not a dict"
+
+    def test_nested_list_of_dicts(self, client):
+        assert client.get("/orders").json() == {
+            "id": 7,
+            "items": [{"sku": "a"}, {"sku": "b"}],
+        }
+
+    def test_send_filtered(self, client):
+        assert "password_hash" not in client.get("/send").json()
+
+
+class TestInclude:
+    """Test include specs."""
+
+    def test_nested_include_ignores_unknown(self, client):
+        assert client.get("/include").json() == {
+            "id": 1,
+            "user": {"email": "ada@example.com"},
+        }
+
+    def test_whole_field_with_exclusion(self, client):
+        assert client.get("/include-whole").json() == {
+            "user": {"email": "ada@example.com"},
+        }
+
+    def test_strict_missing_field(self, client):
+        response = client.get("/strict")
+        assert response.status == 500
+        assert "'user.phone'" in response.text
+
+
+class TestPassThrough:
+    """Test responses the spec does not apply to."""
+
+    def test_text(self, client):
+        assert client.get("/text").text == "password_hash stays in text"
+
+    def test_scalar(self, client):
+        assert client.get("/scalar").json() == 42
+
+    def test_route_without_spec(self, client):
+        assert "password_hash" in client.get("/plain").json()
+
+
+class TestRouteSpec:
+    """Test Route(response_fields=...) validation."""
+
+    def handler(self, req, res, ctx):
+        pass
+
+    def test_getter(self):
+        route = Route("/x", self.handler, "GET", response_fields={"exclude": ["a", "b.c"]})
+        assert route.response_fields == {"include": None, "exclude": ["a", "b.c"], "strict": False}
+        assert Route("/x", self.handler, "GET").response_fields is None
+
+    def test_too_deep(self):
+        with pytest.raises(ValueError, match="'a.b.c'"):
+            Route("/x", self.handler, "GET", response_fields={"exclude": ["a.b.c"]})
+
+    def test_unknown_key(self):
+        with pytest.raises(ValueError, match="Unknown response_fields key 'only'"):
+            Route("/x", self.handler, "GET", response_fields={"only": ["a"]})
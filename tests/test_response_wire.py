@@ -0,0 +1,178 @@
+"""
+Test cases for the bytes a response puts on the wire.
+
+Tests cover:
+- Small JSON and text responses, byte for byte
+- HEAD responses with the GET Content-Length and no body
+- 204 and 304 responses without a body or Content-Length
+- Repeated headers kept as separate lines
+- Large bodies taking the general assembly path
+- The Date header as an IMF-fixdate, refreshed every second
+"""
+
+import email.utils
+import re
+import socket
+import time
+
+import pytest
+
+from tests.conftest import server_process
+
+
+APP_SCRIPT = """
+import sys
+sys.path.insert(0, sys.argv[1])
+from hypern import Hypern
+
+app = Hypern()
+
+@app.get("/json")
+def json_route(req, res, ctx):
+    res.json({"ok": True})
+
+@app.api_route("/both", ["GET", "HEAD"])
+def both(req, res, ctx):
+    res.text("hello")
+
+@app.get("/no-content")
+def no_content(req, res, ctx):
+    res.status(204).text("dropped")
+
+@app.get("/not-modified")
+def not_modified(req, res, ctx):
+    res.status(304).header("ETag", '"v1"').text("dropped")
+
+@app.get("/cookies")
+def cookies(req, res, ctx):
+    res.header("Set-Cookie", "a=1").header("Set-Cookie", "b=2").text("hi")
+
+@app.get("/large")
+def large(req, res, ctx):
+    res.text("x" * 10000)
+
+@app.get("/own-date")
+def own_date(req, res, ctx):
+    res.header("Date", "Sun, 06 Nov 1994 08:49:37 GMT").text("old")
+
+app.start(host="127.0.0.1", port=int(sys.argv[2]), num_processes=1)
+"""
+
+DATE = re.compile(rb"date: ([^\r]+)\r\n")
+
+
+def raw_request(port: int, method: str, path: str) -> bytes:
+    with socket.create_connection(("127.0.0.1", port), timeout=5) as sock:
+        sock.sendall(f"{method} {path} HTTP/1.1\r\nHost: test\r\nConnection: close\r\n\r\n".encode())
+        chunks = []
+        while chunk := sock.recv(65536):
+            chunks.append(chunk)
+    return b"".join(chunks)
+
+
+def wire(port: int, method: str, path: str) -> bytes:
+    """The raw response with the Date value replaced by DATE."""
+    return DATE.sub(b"date: DATE\r\n", raw_request(port, method, path), count=1)
+
+
+@pytest.fixture(scope="module")
+def port():
+    with server_process(app_script=APP_SCRIPT, ready_path="/_health/live") as server:
+        yield server.port
+
+
+class TestSmallResponses:
+    """Test responses assembled on the small-response path."""
+
+    def test_json(self, port):
+        assert wire(port, "GET", "/json") == (
+            b"HTTP/1.1 200 OK\r\n"
+            b"content-type: application/json\r\n"
+            b"date: DATE\r\n"
+            b"content-length: 11\r\n"
+            b"server: Hypern\r\n"
+            b"connection: close\r\n"
+            b"\r\n"
+            b'{"ok":true}'
+        )
+
+    def test_head_keeps_length_without_body(self, port):
+        head = (
+            b"HTTP/1.1 200 OK\r\n"
+            b"content-type: text/plain; charset=utf-8\r\n"
+            b"date: DATE\r\n"
+            b"content-length: 5\r\n"
+            b"server: Hypern\r\n"
+            b"connection: close\r\n"
+            b"\r\n"
+        )
+        assert wire(port, "GET", "/both") == head + b"hello"
+        assert wire(port, "HEAD", "/both") == head
+
+    def test_no_content(self, port):
+        assert wire(port, "GET", "/no-content") == (
+            b"HTTP/1.1 204 No Content\r\n"
+            b"content-type: text/plain; charset=utf-8\r\n"
+            b"date: DATE\r\n"
+            b"server: Hypern\r\n"
+            b"connection: close\r\n"
+            b"\r\n"
+        )
+
+    def test_not_modified(self, port):
+        assert wire(port, "GET", "/not-modified") == (
+            b"HTTP/1.1 304 Not Modified\r\n"
+            b'etag: "v1"\r\n'
+            b"content-type: text/plain; charset=utf-8\r\n"
+            b"date: DATE\r\n"
+            b"server: Hypern\r\n"
+            b"connection: close\r\n"
+            b"\r\n"
+        )
+
+    def test_repeated_headers(self, port):
+        assert wire(port, "GET", "/cookies") == (
+            b"HTTP/1.1 200 OK\r\n"
+            b"set-cookie: a=1\r\n"
+            b"set-cookie: b=2\r\n"
+            b"content-type: text/plain; charset=utf-8\r\n"
+            b"date: DATE\r\n"
+            b"content-length: 2\r\n"
+            b"server: Hypern\r\n"
+            b"connection: close\r\n"
+            b"\r\n"
+            b"hi"
+        )
+
+    def test_handler_date_kept(self, port):
+        response = raw_request(port, "GET", "/own-date")
+        assert DATE.findall(response) == [b"Sun, 06 Nov 1994 08:49:37 GMT"]
+
+
+class TestLargeResponses:
+    """Test bodies over the small-response limit."""
+
+    def test_large_body(self, port):
+        response = wire(port, "GET", "/large")
+        head, body = response.split(b"\r\n\r\n", 1)
+        assert head.split(b"\r\n")[:4] == [
+            b"HTTP/1.1 200 OK",
+            b"content-type: text/plain; charset=utf-8",
+            b"date: DATE",
+            b"content-length: 10000",
+        ]
+        assert body == b"x" * 10000
+
+
+class TestDateHeader:
+    """Test the cached Date value."""
+
+    def test_format_and_refresh(self, port):
+        first = DATE.search(raw_request(port, "GET", "/json")).group(1).decode()
+        parsed = email.utils.parsedate_to_datetime(first)
+        assert abs(parsed.timestamp() - time.time()) < 3
+        assert re.fullmatch(r"[A-Z][a-z]{2}, \d{2} [A-Z][a-z]{2} \d{4} \d{2}:\d{2}:\d{2} GMT", first)
+
+        time.sleep(1.2)
+        second = DATE.search(raw_request(port, "GET", "/json")).group(1).decode()
+        assert email.utils.parsedate_to_datetime(second) > parsed
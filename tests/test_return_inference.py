@@ -0,0 +1,251 @@
+"""
+Test cases for responses inferred from handler return values.
+
+Every case runs through each dispatch path: sync and async app handlers,
+and sync and async handlers registered on a bare Server.
+
+Tests cover:
+- dict/list → JSON, str → text/plain, bytes → application/octet-stream
+- None → 204, unless the handler wrote to res (status, headers, cookies or body)
+- (body, status) and (body, status, headers) tuples
+- Response, MiddlewareResponse, SSEEvent and SSEStream passed through
+- TypeError naming the type and the route for anything else
+"""
+
+import pytest
+
+from hypern import Hypern, TestClient
+from hypern._hypern import MiddlewareResponse, Route, Router, Server, SSEEvent
+
+PATHS = ["app-sync", "app-async", "server-sync", "server-async"]
+
+
+def client_for(dispatch: str, produce, errors: list = None):
+    """A client whose GET /r handler returns ``produce(res)``"""
+    if dispatch.startswith("app"):
+        app = Hypern()
+        if errors is not None:
+
+            @app.errorhandler(TypeError)
+            def type_error(req, res, error):
+                errors.append(str(error))
+                res.status(500).json({"error": str(error)})
+
+        if dispatch == "app-sync":
+            app.get("/r")(lambda req, res, ctx: produce(res))
+        else:
+
+            async def handler(req, res, ctx):
+                return produce(res)
+
+            app.get("/r")(handler)
+        return app.test_client()
+
+    router = Router(path="/")
+    if dispatch == "server-sync":
+        router.add_route(Route("/r", lambda req, res: produce(res), "GET"))
+    else:
+
+        async def handler(req, res):
+            return produce(res)
+
+        router.add_route(Route("/r", handler, "GET"))
+    server = Server()
+    server.set_router(router)
+    return TestClient(server)
+
+
+def get(dispatch: str, produce):
+    return client_for(dispatch, produce).get("/r")
+
+
+@pytest.mark.parametrize("dispatch", PATHS)
+class TestBodies:
+    """Test plain values."""
+
+    def test_dict(self, dispatch):
+        response = get(dispatch, lambda res: {"ok": True, "n": [1, 2]})
+        assert response.status == 200
+        assert response.headers["content-type"] == "application/json"
+        assert response.json() == {"ok": True, "n": [1, 2]}
+
+    def test_list(self, dispatch):
+        response = get(dispatch, lambda res: [1, "two", None])
+        assert response.status == 200
+        assert response.headers["content-type"] == "application/json"
+        assert response.json() == [1, "two", None]
+
+    def test_str(self, dispatch):
+        response = get(dispatch, lambda res: "héllo")
+        assert response.status == 200
+        assert response.headers["content-type"].startswith("text/plain")
+        assert response.text == "héllo"
+
+    def test_str_keeps_content_type(self, dispatch):
+        response = get(dispatch, lambda res: res.content_type("text/csv") and "a,b\n")
+        assert response.headers["content-type"] == "text/csv"
+        assert response.text == "a,b\n"
+
+    def test_bytes(self, dispatch):
+        response = get(dispatch, lambda res: b"\x00\x01")
+        assert response.status == 200
+        assert response.headers["content-type"] == "application/octet-stream"
+        assert response.content == b"\x00\x01"
+
+    def test_bytearray(self, dispatch):
+        response = get(dispatch, lambda res: bytearray(b"raw"))
+        assert response.headers["content-type"] == "application/octet-stream"
+        assert response.content == b"raw"
+
+    def test_dict_replaces_content_type(self, dispatch):
+        response = get(dispatch, lambda res: res.text("ignored") and {"a": 1})
+        assert response.headers["content-type"] == "application/json"
+        assert response.json() == {"a": 1}
+
+
+@pytest.mark.parametrize("dispatch", PATHS)
+class TestNone:
+    """Test handlers returning None."""
+
+    def test_no_content(self, dispatch):
+        response = get(dispatch, lambda res: None)
+        assert response.status == 204
+        assert response.content == b""
+
+    def test_written_response_kept(self, dispatch):
+        response = get(dispatch, lambda res: res.status(201).json({"id": 1}) and None)
+        assert response.status == 201
+        assert response.json() == {"id": 1}
+
+    def test_headers_only(self, dispatch):
+        # Writing a header counts as writing the response: 200, as before
+        response = get(dispatch, lambda res: res.header("X-Trace", "1") and None)
+        assert response.status == 200
+        assert response.headers["x-trace"] == "1"
+
+    def test_cookie_only(self, dispatch):
+        response = get(dispatch, lambda res: res.cookie("session", "abc") and None)
+        assert response.status == 200
+        assert response.headers["set-cookie"].startswith("session=abc")
+
+
+@pytest.mark.parametrize("dispatch", PATHS)
+class TestTuples:
+    """Test (body, status[, headers]) tuples."""
+
+    def test_body_status(self, dispatch):
+        response = get(dispatch, lambda res: ({"id": 7}, 201))
+        assert response.status == 201
+        assert response.json() == {"id": 7}
+
+    def test_text_status(self, dispatch):
+        response = get(dispatch, lambda res: ("gone", 410))
+        assert response.status == 410
+        assert response.text == "gone"
+
+    def test_none_body(self, dispatch):
+        response = get(dispatch, lambda res: (None, 202))
+        assert response.status == 202
+        assert response.content == b""
+
+    def test_header_dict(self, dispatch):
+        response = get(dispatch, lambda res: ("made", 201, {"Location": "/r/1", "X-Id": "1"}))
+        assert response.status == 201
+        assert response.headers["location"] == "/r/1"
+        assert response.headers["x-id"] == "1"
+        assert response.text == "made"
+
+    def test_header_pairs(self, dispatch):
+        response = get(dispatch, lambda res: ([], 200, [("X-A", "1"), ("X-A", "2")]))
+        assert response.header_values("x-a") == ["1", "2"]
+        assert response.json() == []
+
+
+@pytest.mark.parametrize("dispatch", PATHS)
+class TestPassThrough:
+    """Test response objects."""
+
+    def test_response(self, dispatch):
+        response = get(dispatch, lambda res: res.status(203).json({"chained": True}))
+        assert response.status == 203
+        assert response.json() == {"chained": True}
+
+    def test_middleware_response(self, dispatch):
+        def produce(res):
+            reply = MiddlewareResponse(418)
+            reply.with_text_body("teapot")
+            return reply
+
+        response = get(dispatch, produce)
+        assert response.status == 418
+        assert response.headers["content-type"] == "text/plain"
+        assert response.text == "teapot"
+
+    def test_sse_event(self, dispatch):
+        response = get(dispatch, lambda res: SSEEvent("hi", event="greet"))
+        assert response.status == 200
+        assert response.headers["content-type"] == "text/event-stream"
+        assert "event: greet" in response.text
+        assert "data: hi" in response.text
+
+    def test_sse_stream(self, dispatch):
+        def produce(res):
+            stream = res.sse_live()
+            stream.send_data("one")
+            stream.close()
+            return stream
+
+        response = get(dispatch, produce)
+        assert response.headers["content-type"] == "text/event-stream"
+        assert "data: one" in response.text
+
+
+@pytest.mark.parametrize("dispatch", PATHS)
+class TestTypeError:
+    """Test values that cannot be a response."""
+
+    @pytest.mark.parametrize(
+        "value,named",
+        [
+            ({1, 2}, "returned set"),
+            (42, "returned int"),
+            (object(), "returned object"),
+            (("a", 200, {}, "extra"), "tuple of 4 items"),
+            (("a",), "tuple of 1 items"),
+            (("a", "200"), "returned status '200'"),
+            (("a", 999), "returned status 999"),
+            (("a", 200, "X-A: 1"), "headers of type str"),
+        ],
+    )
+    def test_rejected(self, dispatch, value, named):
+        errors = []
+        response = client_for(dispatch, lambda res: value, errors).get("/r")
+        assert response.status == 500
+        if dispatch.startswith("app"):
+            (message,) = errors
+            assert named in message
+            assert "GET /r" in message
+            assert response.json() == {"error": message}
+        else:
+            assert response.text == "Internal Server Error"
+
+    def test_default_exception_handler(self, dispatch):
+        if not dispatch.startswith("app"):
+            pytest.skip("bare servers have no exception handlers")
+        response = client_for(dispatch, lambda res: {1}).get("/r")
+        assert response.status == 500
+        assert "returned set" in response.json()["detail"]
+
+
+class TestStreams:
+    """Test generators still streaming in app handlers."""
+
+    def test_generator(self):
+        app = Hypern()
+
+        @app.get("/g")
+        def chunks(req, res, ctx):
+            yield "a"
+            yield b"b"
+
+        assert app.test_client().get("/g").text == "ab"
@@ -0,0 +1,228 @@
+"""
+Test cases for per-route authentication requirements.
+
+Tests cover:
+- Public routes answering anonymous requests
+- Required routes answering 401 with the auth middleware's challenge
+- Role lists answering 403 for users without one of the roles
+- The server default_auth and routes opting out with auth="public"
+- The handler and route middleware not running for rejected requests
+- Security requirements in the OpenAPI spec
+"""
+
+import base64
+
+import pytest
+
+from hypern import Hypern, Router
+from hypern._hypern import Route
+from hypern.middleware import BasicAuthMiddleware
+from hypern.openapi import OpenAPIGenerator
+
+
+def basic(user: str, password: str = "secret") -> dict:
+    token = base64.b64encode(f"{user}:{password}".encode()).decode()
+    return {"Authorization": f"Basic {token}"}
+
+
+def build_app(calls=None) -> Hypern:
+    app = Hypern()
+    app.use(
+        BasicAuthMiddleware(
+            realm="api",
+            users={"alice": "secret", "bob": "secret"},
+            roles={"alice": ["admin"], "bob": ["billing"]},
+            optional=True,
+        )
+    )
+
+    @app.get("/public")
+    def public(req, res, ctx):
+        res.json({"ok": True})
+
+    @app.get("/me", auth="required")
+    def me(req, res, ctx):
+        if calls is not None:
+            calls.append("me")
+        res.json({"ok": True})
+
+    @app.get("/admin", auth=["admin"])
+    def admin(req, res, ctx):
+        if calls is not None:
+            calls.append("admin")
+        res.json({"ok": True})
+
+    @app.get("/finance", auth=["admin", "billing"])
+    def finance(req, res, ctx):
+        res.json({"ok": True})
+
+    return app
+
+
+class TestEnforcement:
+    """Test 401 and 403 before the handler."""
+
+    def test_public_route_anonymous(self):
+        client = build_app().test_client()
+        assert client.get("/public").status == 200
+
+    def test_required_route_anonymous(self):
+        calls = []
+        client = build_app(calls).test_client()
+
+        response = client.get("/me")
+        assert response.status == 401
+        assert response.headers["www-authenticate"] == 'Basic realm="api"'
+        assert response.json() == {"error": "unauthorized", "message": "Authentication required"}
+        assert calls == []
+
+    def test_required_route_authenticated(self):
+        client = build_app().test_client()
+        assert client.get("/me", headers=basic("bob")).status == 200
+
+    def test_wrong_role(self):
+        calls = []
+        client = build_app(calls).test_client()
+
+        response = client.get("/admin", headers=basic("bob"))
+        assert response.status == 403
+        assert response.json()["error"] == "forbidden"
+        assert calls == []
+
+    def test_right_role(self):
+        client = build_app().test_client()
+        assert client.get("/admin", headers=basic("alice")).status == 200
+
+    def test_any_listed_role(self):
+        client = build_app().test_client()
+        assert client.get("/finance", headers=basic("alice")).status == 200
+        assert client.get("/finance", headers=basic("bob")).status == 200
+
+    def test_role_route_anonymous(self):
+        client = build_app().test_client()
+        assert client.get("/admin").status == 401
+
+    def test_route_middleware_skipped(self):
+        app = Hypern()
+        calls = []
+
+        def audit(req, res, ctx, next):
+            calls.append("audit")
+            return next()
+
+        @app.get("/audited", middleware=[audit], auth="required")
+        def audited(req, res, ctx):
+            res.json({"ok": True})
+
+        assert app.test_client().get("/audited").status == 401
+        assert calls == []
+
+    def test_without_auth_middleware(self):
+        app = Hypern()
+
+        @app.get("/me", auth="required")
+        def me(req, res, ctx):
+            res.json({"ok": True})
+
+        response = app.test_client().get("/me")
+        assert response.status == 401
+        assert response.headers["www-authenticate"] == "Bearer"
+
+
+class TestDefaultAuth:
+    """Test the server default and opting out of it."""
+
+    def build(self) -> Hypern:
+        app = Hypern()
+        app.use(BasicAuthMiddleware(users={"alice": "secret"}, optional=True))
+
+        @app.get("/health", auth="public")
+        def health(req, res, ctx):
+            res.json({"ok": True})
+
+        @app.get("/data")
+        def data(req, res, ctx):
+            res.json({"ok": True})
+
+        @app.get("/feed", auth="optional")
+        def feed(req, res, ctx):
+            res.json({"ok": True})
+
+        return app
+
+    def test_default_required(self):
+        client = self.build().test_client(default_auth="required")
+        assert client.get("/data").status == 401
+        assert client.get("/data", headers=basic("alice")).status == 200
+        assert client.get("/health").status == 200
+        assert client.get("/feed").status == 200
+
+    def test_default_public(self):
+        client = self.build().test_client()
+        assert client.get("/data").status == 200
+
+    def test_mounted_router(self):
+        app = Hypern()
+        api = Router()
+
+        @api.get("/secret", auth="required")
+        def secret(req, res, ctx):
+            res.json({"ok": True})
+
+        app.use("/api", api)
+        assert app.test_client().get("/api/secret").status == 401
+
+    def test_invalid_default(self):
+        with pytest.raises(ValueError, match="default_auth"):
+            Hypern().test_client(default_auth="sometimes")
+
+
+class TestDeclaration:
+    """Test the auth argument of Route."""
+
+    def test_getter(self):
+        handler = lambda req, res: None
+        assert Route("/a", handler, "GET").auth is None
+        assert Route("/a", handler, "GET", auth="required").auth == "required"
+        assert Route("/a", handler, "GET", auth=["admin"]).auth == ["admin"]
+
+    def test_invalid(self):
+        handler = lambda req, res: None
+        with pytest.raises(ValueError, match="auth must be"):
+            Route("/a", handler, "GET", auth="sometimes")
+        with pytest.raises(ValueError, match="must not be empty"):
+            Route("/a", handler, "GET", auth=[])
+
+
+class TestOpenAPI:
+    """Test security requirements in the generated spec."""
+
+    def test_security(self):
+        app = build_app()
+        spec = OpenAPIGenerator(title="API").generate(app)
+        paths = spec["paths"]
+
+        assert "security" not in paths["/public"]["get"]
+        assert paths["/me"]["get"]["security"] == [{"bearerAuth": []}]
+        assert paths["/admin"]["get"]["security"] == [{"bearerAuth": ["admin"]}]
+        assert "403" in paths["/admin"]["get"]["responses"]
+        assert "401" in paths["/me"]["get"]["responses"]
+
+    def test_registered_scheme_and_default(self):
+        app = Hypern()
+
+        @app.get("/data")
+        def data(req, res, ctx):
+            res.json({"ok": True})
+
+        @app.get("/feed", auth="optional")
+        def feed(req, res, ctx):
+            res.json({"ok": True})
+
+        app.test_client(default_auth="required")
+        generator = OpenAPIGenerator(title="API")
+        generator.add_security_scheme("basicAuth", "http", scheme="basic")
+        paths = generator.generate(app)["paths"]
+
+        assert paths["/data"]["get"]["security"] == [{"basicAuth": []}]
+        assert paths["/feed"]["get"]["security"] == [{}, {"basicAuth": []}]
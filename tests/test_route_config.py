@@ -0,0 +1,193 @@
+"""
+Test cases for per-route configuration.
+
+Tests cover:
+- Route timeouts overriding the global TimeoutMiddleware deadline
+- Per-route request body limits, enforced while the body is read
+- Cache-Control from cache_ttl
+- log=False keeping a route out of the access log
+- Route metadata exposed as req.route_meta
+- Duration and size string parsing
+"""
+
+import socket
+import time
+
+import httpx
+import pytest
+
+from hypern._hypern import Route
+from hypern.utils import parse_duration, parse_size
+
+from tests.conftest import server_process
+
+
+def raw_status(client: httpx.Client, request: bytes) -> int:
+    """Send raw request bytes to the test server and read the status code"""
+    with socket.create_connection((client.base_url.host, client.base_url.port), timeout=5) as sock:
+        sock.sendall(request)
+        status_line = sock.recv(1024).split(b"\r\n", 1)[0]
+    return int(status_line.split()[1])
+
+
+def handler(req, res):
+    res.text("ok")
+
+
+class TestRouteTimeout:
+    """Test per-route handler deadlines."""
+
+    def test_route_timeout_shorter_than_global(self, client: httpx.Client):
+        start = time.monotonic()
+        response = client.get("/route-config/slow", headers={"Accept": "application/json"})
+        assert response.status_code == 504
+        assert response.json()["error"]["code"] == "gateway_timeout"
+        # The route's 100ms wins over the global 30s deadline
+        assert time.monotonic() - start < 0.45
+
+    def test_global_timeout_applies_without_route_timeout(self, client: httpx.Client):
+        response = client.get("/route-config/inherit")
+        assert response.status_code == 200
+        assert response.json() == {"finished": True}
+
+
+class TestRouteBodyLimit:
+    """Test per-route max_body_size."""
+
+    def test_body_within_limit(self, client: httpx.Client):
+        response = client.post("/route-config/small-body", content=b"x" * 64)
+        assert response.status_code == 200
+        assert response.json() == {"size": 64}
+
+    def test_body_over_limit(self, client: httpx.Client):
+        response = client.post(
+            "/route-config/small-body",
+            content=b"x" * 65,
+            headers={"Accept": "application/json"},
+        )
+        assert response.status_code == 413
+        assert response.json()["error"]["code"] == "payload_too_large"
+
+    def test_chunked_body_within_limit(self, client: httpx.Client):
+        response = client.post("/route-config/small-body", content=iter([b"x" * 32, b"x" * 32]))
+        assert response.status_code == 200
+        assert response.json() == {"size": 64}
+
+    def test_chunked_body_over_limit(self, client: httpx.Client):
+        response = client.post("/route-config/small-body", content=iter([b"x" * 40, b"x" * 40]))
+        assert response.status_code == 413
+
+    def test_declared_length_rejected_before_body(self, client: httpx.Client):
+        # Nothing past the head is sent; the answer cannot wait for the body
+        head = (
+            "POST /route-config/small-body HTTP/1.1\r\nHost: test\r\n"
+            "Content-Length: 100000000\r\n\r\n"
+        )
+        assert raw_status(client, head.encode()) == 413
+
+    def test_unfinished_chunked_body_rejected(self, client: httpx.Client):
+        # The body never ends; the limit is hit while it is read
+        request = (
+            b"POST /route-config/small-body HTTP/1.1\r\nHost: test\r\n"
+            b"Transfer-Encoding: chunked\r\n\r\n"
+            + b"40\r\n" + b"x" * 64 + b"\r\n"
+            + b"10\r\n" + b"x" * 16 + b"\r\n"
+        )
+        assert raw_status(client, request) == 413
+
+
+class TestRouteCacheTtl:
+    """Test Cache-Control added from cache_ttl."""
+
+    def test_cache_control_added(self, client: httpx.Client):
+        response = client.get("/route-config/cached")
+        assert response.headers["cache-control"] == "max-age=60"
+
+    def test_handler_cache_control_kept(self, client: httpx.Client):
+        response = client.get("/route-config/cached-override")
+        assert response.headers["cache-control"] == "no-store"
+
+    def test_no_cache_control_without_ttl(self, client: httpx.Client):
+        assert "cache-control" not in client.get("/route-config/loud").headers
+
+
+class TestRouteMetadata:
+    """Test route metadata on the request."""
+
+    def test_route_meta(self, client: httpx.Client):
+        response = client.get("/route-config/meta")
+        assert response.json() == {"team": "payments", "tier": "1"}
+
+
+class TestRouteLogging:
+    """Test log=False on a separate server whose output is captured."""
+
+    def test_log_false_silences_route_only(self):
+        with server_process() as server:
+            base_url = server.base_url
+            assert httpx.get(f"{base_url}/route-config/quiet").status_code == 200
+            assert httpx.get(f"{base_url}/route-config/loud").status_code == 200
+            time.sleep(0.5)
+            server.stop()
+            output = server.logs()
+
+        assert "/route-config/loud" in output
+        assert "/route-config/quiet" not in output
+
+
+class TestRouteOptions:
+    """Test Route keyword validation and getters."""
+
+    def test_defaults(self):
+        route = Route("/a", handler, "GET")
+        assert route.timeout is None
+        assert route.max_body_size is None
+        assert route.cache_ttl is None
+        assert route.log is True
+        assert route.metadata == {}
+
+    def test_string_values(self):
+        route = Route(
+            "/a", handler, "GET",
+            timeout="500ms", max_body_size="2MB", cache_ttl="1h",
+            log=False, metadata={"team": "payments"},
+        )
+        assert route.timeout == 0.5
+        assert route.max_body_size == 2 * 1024 * 1024
+        assert route.cache_ttl == 3600
+        assert route.log is False
+        assert route.metadata == {"team": "payments"}
+
+    def test_invalid_values(self):
+        with pytest.raises(ValueError):
+            Route("/a", handler, "GET", timeout=0)
+        with pytest.raises(ValueError):
+            Route("/a", handler, "GET", max_body_size="lots")
+
+
+class TestParsers:
+    """Test duration and size string parsing."""
+
+    @pytest.mark.parametrize("value,expected", [
+        ("250ms", 0.25), ("5s", 5.0), ("2m", 120.0), ("1h", 3600.0),
+        ("1d", 86400.0), ("1.5", 1.5), (" 10 s ", 10.0),
+    ])
+    def test_parse_duration(self, value, expected):
+        assert parse_duration(value) == expected
+
+    @pytest.mark.parametrize("value,expected", [
+        ("512", 512), ("64b", 64), ("1k", 1024), ("2KB", 2048),
+        ("2MB", 2 * 1024 ** 2), ("1GiB", 1024 ** 3),
+    ])
+    def test_parse_size(self, value, expected):
+        assert parse_size(value) == expected
+
+    @pytest.mark.parametrize("value", ["", "abc", "5x", "-1s"])
+    def test_parse_duration_invalid(self, value):
+        with pytest.raises(ValueError):
+            parse_duration(value)
+
+    @pytest.mark.parametrize("value", ["", "mb", "1.5.2k", "10tb"])
+    def test_parse_size_invalid(self, value):
+        with pytest.raises(ValueError):
+            parse_size(value)
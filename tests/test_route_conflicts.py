@@ -0,0 +1,238 @@
+"""
+Test cases for route conflict detection.
+
+Tests cover:
+- Identical templates for a method rejected with both handler names
+- Templates differing only in parameter names warned about, or rejected
+  with strict_routes
+- Typed parameters next to untyped ones, both routed
+- Catch-alls clashing with a parameter, and static routes beside them
+- Routes mounted from two routers
+- Router.check() reports and the startup check under strict_routes
+"""
+
+import os
+import socket
+import subprocess
+import sys
+
+import pytest
+
+from hypern import Hypern, Router
+
+
+ROOT = os.path.dirname(os.path.dirname(os.path.abspath(__file__)))
+
+
+def handler(name: str):
+    """Handler answering with its name and path params."""
+
+    def route(req, res, ctx):
+        res.json({"handler": name, "params": req.path_params})
+
+    route.__name__ = name
+    return route
+
+
+def kinds(report, key="warnings"):
+    return [(c["kind"], c["path"], c["other_path"]) for c in report[key]]
+
+
+class TestDuplicates:
+    """Test identical templates."""
+
+    def test_same_method_rejected(self):
+        app = Hypern()
+        app.get("/users/{id}")(handler("get_user"))
+
+        with pytest.raises(ValueError) as error:
+            app.get("/users/{id}")(handler("load_user"))
+
+        message = str(error.value)
+        assert "GET /users/{id} (load_user) duplicates" in message
+        assert "registered by get_user" in message
+
+    def test_other_method_allowed(self):
+        app = Hypern()
+        app.get("/users/{id}")(handler("get_user"))
+        app.put("/users/{id}")(handler("put_user"))
+
+        assert app.router.check() == {"ok": True, "errors": [], "warnings": []}
+
+    def test_colon_syntax_same_template(self):
+        app = Hypern()
+        app.get("/users/:id")(handler("get_user"))
+        with pytest.raises(ValueError, match="duplicates"):
+            app.get("/users/{id}")(handler("load_user"))
+
+    def test_mounted_routers(self):
+        app = Hypern()
+        users = Router()
+        users.get("/users/{id}")(handler("get_user"))
+        legacy = Router()
+        legacy.get("/users/{id}")(handler("legacy_user"))
+
+        app.mount(users)
+        with pytest.raises(ValueError, match=r"\(legacy_user\) duplicates .* get_user"):
+            app.mount(legacy)
+
+
+class TestAmbiguous:
+    """Test templates differing only in parameter names."""
+
+    def test_warns_and_first_wins(self):
+        app = Hypern()
+        app.get("/users/{id}")(handler("by_id"))
+        app.get("/users/{name}")(handler("by_name"))
+
+        report = app.router.check()
+        assert report["ok"] is True
+        assert kinds(report) == [("ambiguous", "/users/{name}", "/users/{id}")]
+        assert report["warnings"][0]["handler"] == "by_name"
+        assert report["warnings"][0]["other_handler"] == "by_id"
+        assert app.test_client().get("/users/7").json() == {"handler": "by_id", "params": {"id": "7"}}
+
+    def test_strict_rejects(self):
+        app = Hypern(strict_routes=True)
+        app.get("/users/{id}")(handler("by_id"))
+
+        with pytest.raises(ValueError, match="only in parameter names"):
+            app.get("/users/{name}")(handler("by_name"))
+        assert [r["path"] for r in app.get_routes()] == ["/users/{id}"]
+
+    def test_same_constraint_is_ambiguous(self):
+        app = Hypern()
+        app.get("/orders/{id:int}")(handler("a"))
+        app.get("/orders/{number:int}")(handler("b"))
+
+        assert kinds(app.router.check()) == [("ambiguous", "/orders/{number:int}", "/orders/{id:int}")]
+
+    def test_host_routes_separate(self):
+        app = Hypern()
+        app.get("/users/{id}")(handler("a"))
+        app.get("/users/{name}", host="admin.example.com")(handler("b"))
+
+        assert app.router.check()["warnings"] == []
+
+    def test_wildcard_method_separate(self):
+        app = Hypern()
+        app.get("/users/{id}")(handler("a"))
+        app.all("/users/{name}")(handler("b"))
+
+        assert app.router.check()["warnings"] == []
+
+
+class TestConstraints:
+    """Test typed parameters next to untyped ones."""
+
+    def test_typed_and_untyped_both_routed(self):
+        app = Hypern(strict_routes=True)
+        app.get("/users/{slug}")(handler("by_slug"))
+        app.get("/users/{id:int}")(handler("by_id"))
+        client = app.test_client()
+
+        assert app.router.check()["ok"] is True
+        assert client.get("/users/42").json() == {"handler": "by_id", "params": {"id": "42"}}
+        assert client.get("/users/ada").json() == {"handler": "by_slug", "params": {"slug": "ada"}}
+
+    def test_str_constraint_is_untyped(self):
+        app = Hypern()
+        app.get("/tags/{tag}")(handler("a"))
+        app.get("/tags/{name:str}")(handler("b"))
+
+        assert kinds(app.router.check()) == [("ambiguous", "/tags/{name:str}", "/tags/{tag}")]
+
+    def test_removal_keeps_the_other(self):
+        app = Hypern()
+        app.get("/users/{slug}")(handler("by_slug"))
+        app.get("/users/{id:int}")(handler("by_id"))
+
+        assert app.router.remove_route("GET", "/users/{slug}") is True
+        client = app.test_client()
+        assert client.get("/users/42").json()["handler"] == "by_id"
+        assert client.get("/users/ada").status == 404
+
+
+class TestCatchAll:
+    """Test catch-all precedence."""
+
+    def test_static_beside_catch_all(self):
+        app = Hypern(strict_routes=True)
+        app.get("/files/{*path}")(handler("files"))
+        app.get("/files/readme")(handler("readme"))
+        client = app.test_client()
+
+        assert app.router.check()["ok"] is True
+        assert client.get("/files/readme").json()["handler"] == "readme"
+        assert client.get("/files/a/b").json() == {"handler": "files", "params": {"path": "a/b"}}
+
+    def test_parameter_under_catch_all(self):
+        app = Hypern()
+        app.get("/files/{*path}")(handler("files"))
+        app.get("/files/{id}/raw")(handler("raw"))
+
+        assert kinds(app.router.check()) == [("catch_all", "/files/{id}/raw", "/files/{*path}")]
+        assert app.test_client().get("/files/1/raw").json()["handler"] == "files"
+
+    def test_strict_rejects(self):
+        app = Hypern(strict_routes=True)
+        app.get("/files/{id}")(handler("file"))
+        with pytest.raises(ValueError, match="catch-all in the same segment"):
+            app.get("/files/{*path}")(handler("files"))
+
+    def test_renamed_catch_all_is_ambiguous(self):
+        app = Hypern()
+        app.get("/assets/{*path}")(handler("a"))
+        app.get("/assets/{*rest}")(handler("b"))
+
+        assert kinds(app.router.check()) == [("ambiguous", "/assets/{*rest}", "/assets/{*path}")]
+
+
+class TestCheck:
+    """Test on-demand and startup checks."""
+
+    def test_strict_set_later_reports_errors(self):
+        app = Hypern()
+        app.get("/users/{id}")(handler("by_id"))
+        app.get("/users/{name}")(handler("by_name"))
+
+        app.router.strict_routes = True
+        report = app.router.check()
+
+        assert report["ok"] is False
+        assert kinds(report, "errors") == [("ambiguous", "/users/{name}", "/users/{id}")]
+        assert report["warnings"] == []
+
+    def test_router_check(self):
+        router = Router(prefix="/api")
+        router.get("/items/{id}")(handler("a"))
+        router.get("/items/{key}")(handler("b"))
+
+        assert kinds(router.check()) == [("ambiguous", "/api/items/{key}", "/api/items/{id}")]
+
+    def test_strict_startup_fails(self):
+        script = """
+import sys
+sys.path.insert(0, sys.argv[1])
+from hypern import Hypern
+
+app = Hypern()
+app.get("/users/{id}")(lambda req, res, ctx: None)
+app.get("/users/{name}")(lambda req, res, ctx: None)
+app.router.strict_routes = True
+app.start(host="127.0.0.1", port=int(sys.argv[2]), num_processes=1)
+"""
+        with socket.socket() as s:
+            s.bind(("127.0.0.1", 0))
+            port = s.getsockname()[1]
+
+        result = subprocess.run(
+            [sys.executable, "-c", script, ROOT, str(port)],
+            capture_output=True,
+            text=True,
+            timeout=30,
+        )
+
+        assert result.returncode != 0
+        assert "Conflicting routes" in result.stderr
+        assert "only in parameter names" in result.stderr
@@ -0,0 +1,103 @@
+"""
+Test cases for route groups on the Python Router.
+
+Tests cover:
+- Nested groups composing path prefixes
+- Group middleware running before route middleware, outermost first
+- Group tags recorded on routes
+- Host-constrained routes matching only the right Host header
+"""
+
+import httpx
+
+from hypern import Router, RouteGroup
+
+
+def noop(req, res, ctx):
+    pass
+
+
+class TestGroupComposition:
+    """Test how nested groups compose prefixes, middleware and tags."""
+
+    def test_nested_group_path_templates(self):
+        router = Router()
+        api = router.group("/api")
+        v1 = api.group("v1/")
+        v1.get("/users/:id")(noop)
+        v1.post("/")(noop)
+
+        assert isinstance(v1, RouteGroup)
+        assert router.get_routes() == [
+            ("GET", "/api/v1/users/:id", noop),
+            ("POST", "/api/v1", noop),
+        ]
+
+    def test_middleware_composed_in_order(self):
+        def outer(req, res, ctx, next): ...
+        def inner(req, res, ctx, next): ...
+        def route(req, res, ctx, next): ...
+
+        router = Router()
+        router.group("/a", middlewares=[outer]).group("/b", middlewares=[inner]).get(
+            "/c", middleware=[route]
+        )(noop)
+
+        _, _, _, options = router._routes[0]
+        assert options["middleware"] == [outer, inner, route]
+
+    def test_tags_and_host_recorded(self):
+        router = Router()
+        group = router.group("/admin", tags=["admin"], host="API.example.com")
+        group.group("/audit", tags=["audit"]).get("/log", tags=["admin", "log"])(noop)
+
+        route = router.get_rust_router().routes[0]
+        assert route.tags == ["admin", "audit", "log"]
+        assert route.host == "api.example.com"
+
+    def test_route_builder_in_group(self):
+        router = Router()
+        router.group("/items").route("/:id").get(noop).delete(noop)
+        assert [(m, p) for m, p, _ in router.get_routes()] == [
+            ("GET", "/items/:id"),
+            ("DELETE", "/items/:id"),
+        ]
+
+
+class TestGroupRequests:
+    """Test grouped routes mounted on the test server."""
+
+    def test_nested_group_route(self, client: httpx.Client):
+        response = client.get("/groups/v1/admin/users/42")
+        assert response.status_code == 200
+        assert response.json()["id"] == "42"
+
+    def test_group_middleware_runs_before_route_middleware(self, client: httpx.Client):
+        response = client.get("/groups/v1/admin/users/1")
+        assert response.json()["trace"] == ["v1", "admin", "route"]
+
+    def test_group_index_route(self, client: httpx.Client):
+        response = client.get("/groups/v1")
+        assert response.status_code == 200
+        assert response.json()["trace"] == ["v1"]
+
+
+class TestHostConstraints:
+    """Test routes restricted to a Host header."""
+
+    def test_matching_host(self, client: httpx.Client):
+        response = client.get("/groups/host/whoami", headers={"Host": "api.example.com"})
+        assert response.json() == {"host": "api"}
+
+    def test_host_match_ignores_port_and_case(self, client: httpx.Client):
+        response = client.get("/groups/host/whoami", headers={"Host": "API.Example.com:8443"})
+        assert response.json() == {"host": "api"}
+
+    def test_other_host_falls_back(self, client: httpx.Client):
+        response = client.get("/groups/host/whoami", headers={"Host": "www.example.com"})
+        assert response.json() == {"host": "default"}
+
+    def test_host_only_route_not_found_elsewhere(self, client: httpx.Client):
+        assert client.get("/groups/host/only").status_code == 404
+        response = client.get("/groups/host/only", headers={"Host": "api.example.com"})
+        assert response.status_code == 200
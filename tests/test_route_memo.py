@@ -0,0 +1,222 @@
+"""
+Test cases for memoized routes.
+
+Tests cover:
+- The handler running once for many requests within memoize_ttl_secs
+- The kept response expiring after the TTL
+- invalidate_route_cache forcing the handler to run again
+- Errors and responses setting cookies never kept
+- One kept response per path parameter value and per memoize_vary header value
+- HEAD answered from a kept GET response, POST never memoized
+- Per-path hit counters and option validation
+"""
+
+import time
+import uuid
+
+import pytest
+
+from hypern import Hypern
+from hypern._hypern import Route, Server, route_memo_stats
+
+
+def unique_path(name: str) -> str:
+    """Memos are registered by path for the whole process; each test uses its own"""
+    return f"/{name}-{uuid.uuid4().hex[:8]}"
+
+
+def build_app(path: str, calls: list, **options) -> Hypern:
+    app = Hypern()
+
+    @app.get(path, **options)
+    def constant(req, res, ctx):
+        calls.append(req.header("accept-language"))
+        res.header("X-Source", "handler").json({"countries": ["VN", "FR"], "call": len(calls)})
+
+    @app.post(path, memoize_ttl_secs=60)
+    def update(req, res, ctx):
+        calls.append("post")
+        res.json({"call": len(calls)})
+
+    return app
+
+
+class TestMemoized:
+    """Test responses kept within the TTL."""
+
+    def test_handler_runs_once(self):
+        path, calls = unique_path("countries"), []
+        client = build_app(path, calls, memoize_ttl_secs=60).test_client()
+        responses = [client.get(path, query={"page": i}) for i in range(5)]
+        assert len(calls) == 1
+        assert all(r.status == 200 for r in responses)
+        assert all(r.json() == {"countries": ["VN", "FR"], "call": 1} for r in responses)
+        assert all(r.headers["x-source"] == "handler" for r in responses)
+
+    def test_expires_after_ttl(self):
+        path, calls = unique_path("short"), []
+        client = build_app(path, calls, memoize_ttl_secs=0.2).test_client()
+        client.get(path)
+        client.get(path)
+        time.sleep(0.3)
+        assert client.get(path).json()["call"] == 2
+        assert len(calls) == 2
+
+    def test_head_uses_kept_response(self):
+        path, calls = unique_path("head"), []
+        app = Hypern()
+
+        @app.api_route(path, ["GET", "HEAD"], memoize_ttl_secs=60)
+        def constant(req, res, ctx):
+            calls.append(req.method)
+            res.json({"call": len(calls)})
+
+        client = app.test_client()
+        client.get(path)
+        assert client.head(path).status == 200
+        assert len(calls) == 1
+
+    def test_post_not_memoized(self):
+        path, calls = unique_path("post"), []
+        client = build_app(path, calls).test_client()
+        client.post(path)
+        client.post(path)
+        assert calls == ["post", "post"]
+
+    def test_vary_headers(self):
+        path, calls = unique_path("vary"), []
+        client = build_app(
+            path, calls, memoize_ttl_secs=60, memoize_vary=["Accept-Language"]
+        ).test_client()
+        for language in ("vi", "fr", "vi", "fr"):
+            client.get(path, headers={"Accept-Language": language})
+        assert calls == ["vi", "fr"]
+
+    def test_path_params_kept_separately(self):
+        path, calls = unique_path("users"), []
+        app = Hypern()
+
+        @app.get(path + "/{id}", memoize_ttl_secs=60)
+        def user(req, res, ctx):
+            calls.append(req.param("id"))
+            res.json({"id": req.param("id")})
+
+        client = app.test_client()
+        for user_id in ("1", "2", "1", "2"):
+            assert client.get(f"{path}/{user_id}").json() == {"id": user_id}
+        assert calls == ["1", "2"]
+
+    def test_not_memoized_without_option(self):
+        path, calls = unique_path("plain"), []
+        client = build_app(path, calls).test_client()
+        client.get(path)
+        client.get(path)
+        assert len(calls) == 2
+
+
+class TestInvalidation:
+    """Test dropping kept responses."""
+
+    def test_invalidation_reruns_handler(self):
+        path, calls = unique_path("invalidate"), []
+        app = build_app(path, calls, memoize_ttl_secs=60)
+        client = app.test_client()
+        client.get(path)
+        client.get(path)
+        # The GET route and the POST route at the same path
+        assert app.invalidate_route_cache(path) == 2
+        assert client.get(path).json()["call"] == 2
+        client.get(path)
+        assert len(calls) == 2
+
+    def test_from_server(self):
+        path, calls = unique_path("server"), []
+        client = build_app(path, calls, memoize_ttl_secs=60).test_client()
+        client.get(path)
+        Server.invalidate_route_cache(path)
+        client.get(path)
+        assert len(calls) == 2
+
+    def test_unknown_path(self):
+        assert Server.invalidate_route_cache(unique_path("nowhere")) == 0
+
+
+class TestNeverKept:
+    """Test responses that are never memoized."""
+
+    def test_error_not_kept(self):
+        path, calls = unique_path("error"), []
+        app = Hypern()
+
+        @app.get(path, memoize_ttl_secs=60)
+        def flaky(req, res, ctx):
+            calls.append(1)
+            if len(calls) == 1:
+                res.status(500).json({"error": "boom"})
+            else:
+                res.json({"ok": True})
+
+        client = app.test_client()
+        assert client.get(path).status == 500
+        assert client.get(path).status == 200
+        assert client.get(path).status == 200
+        assert len(calls) == 2
+
+    def test_raised_exception_not_kept(self):
+        path, calls = unique_path("raised"), []
+        app = Hypern()
+
+        @app.get(path, memoize_ttl_secs=60)
+        def crash(req, res, ctx):
+            calls.append(1)
+            raise RuntimeError("boom")
+
+        client = app.test_client()
+        assert client.get(path).status == 500
+        assert client.get(path).status == 500
+        assert len(calls) == 2
+
+    def test_set_cookie_not_kept(self):
+        path, calls = unique_path("cookie"), []
+        app = Hypern()
+
+        @app.get(path, memoize_ttl_secs=60)
+        def session(req, res, ctx):
+            calls.append(1)
+            res.header("Set-Cookie", "sid=abc").json({"ok": True})
+
+        client = app.test_client()
+        client.get(path)
+        client.get(path)
+        assert len(calls) == 2
+
+
+class TestStats:
+    """Test hit counters and options."""
+
+    def test_counters(self):
+        path, calls = unique_path("stats"), []
+        app = build_app(path, calls, memoize_ttl_secs=60)
+        client = app.test_client()
+        for _ in range(4):
+            client.get(path)
+        app.invalidate_route_cache(path)
+        stats = route_memo_stats()[path]
+        assert stats["hits"] == 3
+        assert stats["misses"] == 1
+        assert stats["stores"] == 1
+        # Summed over the GET and POST routes at the path
+        assert stats["invalidations"] == 2
+        assert stats["entries"] == 0
+        assert app.route_memo_stats()[path] == stats
+
+    def test_route_options(self):
+        route = Route("/c", lambda req, res, ctx: None, method="GET", memoize_ttl_secs="30s",
+                      memoize_vary=["Accept-Language"])
+        assert route.memoize_ttl_secs == 30.0
+        assert route.memoize_vary == ["accept-language"]
+        assert Route("/c", lambda req, res, ctx: None, method="GET").memoize_ttl_secs is None
+
+    def test_invalid_ttl(self):
+        with pytest.raises(ValueError, match="memoize_ttl_secs"):
+            Route("/c", lambda req, res, ctx: None, method="GET", memoize_ttl_secs=0)
@@ -0,0 +1,282 @@
+"""
+Test cases for route retry policies (Route(retry=RetryPolicy(...))).
+
+Tests cover:
+- A handler failing twice then succeeding answers 200 after three attempts
+- Retries on raised exceptions, by name or class, subclasses included
+- Giving up after max_attempts; non-matching statuses not retried
+- POST retried only with retry_unsafe, with the same body each attempt
+- Streamed responses never retried
+- The route deadline stopping retries early
+- Attempt log lines and the retries_total metric
+- RetryPolicy defaults and validation
+"""
+
+import time
+from collections import Counter
+from contextlib import contextmanager
+
+import httpx
+import pytest
+
+from hypern import Hypern, Route, server_metrics
+from hypern.router import RetryPolicy
+
+from tests.conftest import server_process
+
+
+CALLS = Counter()
+BODIES = []
+
+
+def flaky(name: str, failures: int, status: int = 503):
+    """Handler answering `status` for its first `failures` calls."""
+
+    def handler(req, res, ctx):
+        CALLS[name] += 1
+        if CALLS[name] <= failures:
+            res.status(status).text("unavailable")
+        else:
+            res.json({"attempts": CALLS[name]})
+
+    return handler
+
+
+def recorder(req, res, ctx):
+    """Handler recording each body it gets, failing every other call."""
+    BODIES.append(req.body_bytes())
+    if len(BODIES) % 2:
+        res.status(503).text("unavailable")
+    else:
+        res.text("stored")
+
+
+def build_app() -> Hypern:
+    app = Hypern()
+    policy = RetryPolicy(max_attempts=3, on_statuses=[502, 503], backoff_ms=10)
+
+    app.get("/flaky", retry=policy)(flaky("flaky", 2))
+    app.get("/down", retry=policy)(flaky("down", 100))
+    app.get("/broken", retry=policy)(flaky("broken", 1, status=500))
+    app.post("/post", retry=policy)(flaky("post", 1))
+
+    app.put("/put", retry=policy)(recorder)
+    app.post("/post-unsafe", retry=policy, retry_unsafe=True)(recorder)
+
+    @app.get(
+        "/raises",
+        retry=RetryPolicy(on_statuses=[], on_exceptions=["TimeoutError"], backoff_ms=10),
+    )
+    async def raises(req, res, ctx):
+        CALLS["raises"] += 1
+        if CALLS["raises"] <= 2:
+            raise TimeoutError("upstream timed out")
+        res.text("recovered")
+
+    @app.get("/raises-subclass", retry=RetryPolicy(on_exceptions=[OSError], backoff_ms=10))
+    def raises_subclass(req, res, ctx):
+        CALLS["raises-subclass"] += 1
+        if CALLS["raises-subclass"] == 1:
+            raise ConnectionRefusedError("refused")
+        res.text("recovered")
+
+    @app.get("/raises-other", retry=RetryPolicy(on_exceptions=["TimeoutError"], backoff_ms=10))
+    def raises_other(req, res, ctx):
+        CALLS["raises-other"] += 1
+        raise ValueError("bad input")
+
+    @app.get("/streams", retry=policy)
+    def streams(req, res, ctx):
+        CALLS["streams"] += 1
+        res.status(503)
+        res.stream(iter([b"partial"]), content_type="text/plain")
+
+    @app.get(
+        "/deadline",
+        timeout=0.5,
+        retry=RetryPolicy(max_attempts=10, backoff_ms=200, jitter=False),
+    )
+    def deadline(req, res, ctx):
+        CALLS["deadline"] += 1
+        res.status(503).text("unavailable")
+
+    return app
+
+
+@pytest.fixture(scope="module")
+def client():
+    return build_app().test_client()
+
+
+class TestRetries:
+    """Test when the handler runs again."""
+
+    def test_fails_twice_then_succeeds(self, client):
+        response = client.get("/flaky")
+
+        assert response.status == 200
+        assert response.json() == {"attempts": 3}
+
+    def test_gives_up_after_max_attempts(self, client):
+        before = CALLS["down"]
+
+        response = client.get("/down")
+
+        assert response.status == 503
+        assert CALLS["down"] == before + 3
+
+    def test_other_status_not_retried(self, client):
+        assert client.get("/broken").status == 500
+        assert CALLS["broken"] == 1
+
+    def test_exception_by_name(self, client):
+        response = client.get("/raises")
+
+        assert response.status == 200
+        assert response.text == "recovered"
+        assert CALLS["raises"] == 3
+
+    def test_exception_subclass(self, client):
+        assert client.get("/raises-subclass").text == "recovered"
+        assert CALLS["raises-subclass"] == 2
+
+    def test_other_exception_not_retried(self, client):
+        assert client.get("/raises-other").status == 500
+        assert CALLS["raises-other"] == 1
+
+
+class TestUnsafeMethods:
+    """Test that only idempotent methods are retried by default."""
+
+    def test_post_not_retried(self, client):
+        assert client.post("/post", data=b"x").status == 503
+        assert CALLS["post"] == 1
+
+    def test_retry_unsafe_replays_body(self, client):
+        BODIES.clear()
+
+        response = client.post("/post-unsafe", data=b'{"id": 7}')
+
+        assert response.status == 200
+        assert BODIES == [b'{"id": 7}', b'{"id": 7}']
+
+    def test_put_retried(self, client):
+        BODIES.clear()
+        assert client.put("/put", data=b"v").text == "stored"
+        assert len(BODIES) == 2
+
+
+class TestLimits:
+    """Test responses and deadlines that stop retries."""
+
+    def test_streamed_response_not_retried(self, client):
+        response = client.get("/streams")
+
+        assert response.status == 503
+        assert response.text == "partial"
+        assert CALLS["streams"] == 1
+
+    def test_deadline_stops_retries(self, client):
+        # Attempt 1 fails at once and waits 200ms; attempt 2 would wait
+        # 400ms, past the 500ms route timeout, so it answers instead
+        started = time.monotonic()
+
+        response = client.get("/deadline")
+
+        assert response.status == 503
+        assert CALLS["deadline"] == 2
+        assert time.monotonic() - started < 0.5
+
+
+class TestMetrics:
+    """Test the retries counter."""
+
+    def test_retries_counted(self, client):
+        before = server_metrics().snapshot()["retries_total"]
+
+        client.get("/down")
+
+        total = server_metrics().snapshot()["retries_total"]
+        assert total >= before + 2
+        assert "hypern_handler_retries_total" in server_metrics().render()
+
+
+class TestRetryPolicy:
+    """Test policy defaults, validation and the route accessors."""
+
+    def test_defaults(self):
+        policy = RetryPolicy()
+        assert policy.max_attempts == 3
+        assert policy.on_statuses == [502, 503, 504]
+        assert policy.on_exceptions == []
+        assert policy.backoff_ms == 50
+        assert policy.jitter is True
+
+    def test_exception_classes_stored_by_name(self):
+        policy = RetryPolicy(on_exceptions=[TimeoutError, "ConnectionError"])
+        assert policy.on_exceptions == ["TimeoutError", "ConnectionError"]
+
+    @pytest.mark.parametrize(
+        "kwargs, error",
+        [
+            ({"max_attempts": 0}, ValueError),
+            ({"on_statuses": [99]}, ValueError),
+            ({"on_exceptions": [42]}, TypeError),
+        ],
+    )
+    def test_invalid(self, kwargs, error):
+        with pytest.raises(error):
+            RetryPolicy(**kwargs)
+
+    def test_route_accessors(self):
+        policy = RetryPolicy(max_attempts=5)
+        route = Route("/r", lambda req, res: None, method="POST", retry=policy, retry_unsafe=True)
+        assert route.retry.max_attempts == 5
+        assert route.retry_unsafe is True
+        assert Route("/r", lambda req, res: None, method="GET").retry is None
+
+
+APP_SCRIPT = """
+import sys
+sys.path.insert(0, sys.argv[1])
+from hypern import Hypern
+from hypern.router import RetryPolicy
+
+app = Hypern()
+app.setup_logging(log_request=False, log_response=False)
+calls = []
+
+@app.get("/flaky", retry=RetryPolicy(max_attempts=3, backoff_ms=10))
+def flaky(req, res, ctx):
+    calls.append(1)
+    if len(calls) < 3:
+        res.status(502).text("bad gateway")
+    else:
+        res.text("ok")
+
+app.start(host="127.0.0.1", port=int(sys.argv[2]), num_processes=1)
+"""
+
+
+@contextmanager
+def logging_server():
+    with server_process(app_script=APP_SCRIPT, ready_path="/_health/live") as server:
+        yield server.base_url, server.logs
+
+
+class TestAttemptLogs:
+    """Test that every attempt is logged."""
+
+    def test_three_attempts_logged(self):
+        with logging_server() as (base_url, logs):
+            assert httpx.get(f"{base_url}/flaky").text == "ok"
+
+            deadline = time.time() + 5
+            while "completed" not in logs() and time.time() < deadline:
+                time.sleep(0.1)
+            lines = [line for line in logs().splitlines() if "GET /flaky attempt=" in line]
+
+            assert len(lines) == 3, logs()
+            assert "attempt=1 of 3 failed with status 502; retrying in" in lines[0]
+            assert "attempt=2 of 3 failed with status 502; retrying in" in lines[1]
+            assert "attempt=3 of 3 completed with status 200" in lines[2]
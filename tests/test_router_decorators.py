@@ -0,0 +1,156 @@
+"""
+Test cases for the Router decorators and handler arguments filled by name.
+
+Tests cover:
+- Typed path parameters, query parameters with defaults and the request
+- Sync and async handlers
+- Provided dependencies and the response object
+- 400 for query values that do not convert
+- ValueError at registration for arguments without a source
+- Plain (req, res) handlers called as before
+"""
+
+import uuid
+from typing import Optional
+
+import pytest
+
+from hypern import TestClient
+from hypern._hypern import Request, Response, Router, Server
+
+
+def client_for(router: Router) -> TestClient:
+    server = Server()
+    server.set_router(router)
+    return TestClient(server)
+
+
+@pytest.fixture
+def client():
+    Server.provide("decorator_greeting", lambda: "hello")
+    router = Router(path="/")
+
+    @router.get("/users/{id:int}")
+    def get_user(id: int, verbose: bool = False, request=None):
+        return {
+            "id": id,
+            "id_type": type(id).__name__,
+            "verbose": verbose,
+            "request": type(request).__name__,
+            "path": request.path,
+        }
+
+    @router.get("/async/{name}")
+    async def greet(name, decorator_greeting, times: int = 1, req: Request = None):
+        return {"text": " ".join([f"{decorator_greeting} {name}"] * times), "method": req.method}
+
+    @router.route("/items/{item:uuid}", ["GET", "POST"])
+    def item(item, page: Optional[int] = None, response: Response = None):
+        response.header("X-Item", str(item))
+        return {"type": type(item).__name__, "page": page}
+
+    @router.post("/raw")
+    def raw(req, res):
+        res.status(201).json({"method": req.method})
+
+    return client_for(router)
+
+
+class TestInjection:
+    """Test arguments filled from the request."""
+
+    def test_typed_path_query_and_request(self, client):
+        response = client.get("/users/42?verbose=true")
+        assert response.status == 200
+        assert response.json() == {
+            "id": 42,
+            "id_type": "int",
+            "verbose": True,
+            "request": "Request",
+            "path": "/users/42",
+        }
+
+    def test_query_default_kept_when_missing(self, client):
+        assert client.get("/users/42").json()["verbose"] is False
+
+    @pytest.mark.parametrize(
+        "query, expected",
+        [("verbose", True), ("verbose=1", True), ("verbose=off", False), ("verbose=no", False)],
+    )
+    def test_bool_spellings(self, client, query, expected):
+        assert client.get(f"/users/1?{query}").json()["verbose"] is expected
+
+    def test_async_handler_with_dependency(self, client):
+        response = client.get("/async/ada?times=2")
+        assert response.json() == {"text": "hello ada hello ada", "method": "GET"}
+
+    def test_uuid_optional_and_response(self, client):
+        item = uuid.uuid4()
+        response = client.post(f"/items/{item}?page=3")
+        assert response.json() == {"type": "UUID", "page": 3}
+        assert response.headers["x-item"] == str(item)
+        assert client.get(f"/items/{item}").json()["page"] is None
+
+
+class TestBadValues:
+    """Test values that do not convert."""
+
+    def test_query_not_converted(self, client):
+        response = client.get("/async/ada?times=many")
+        assert response.status == 400
+        assert "Query parameter 'times' must be an int" in response.text
+
+    def test_path_constraint_still_404s(self, client):
+        assert client.get("/users/abc").status == 404
+
+
+class TestRegistration:
+    """Test errors raised when the route is added."""
+
+    def test_unresolvable_parameter(self):
+        router = Router(path="/api")
+
+        def profile(user):
+            return {}
+
+        with pytest.raises(ValueError) as error:
+            router.get("/profile")(profile)
+        message = str(error.value)
+        assert "GET /api/profile" in message
+        assert "parameter 'user' of profile()" in message
+        assert "no default" in message
+        assert router.routes == []
+
+    def test_unsupported_query_annotation(self):
+        router = Router(path="/")
+
+        def search(tags: list = None):
+            return {}
+
+        with pytest.raises(ValueError, match="is a query parameter"):
+            router.get("/search")(search)
+
+    def test_decorator_returns_handler(self):
+        router = Router(path="/")
+
+        def index():
+            return "ok"
+
+        assert router.get("/")(index) is index
+        assert [route.method for route in router.routes] == ["GET"]
+
+    def test_route_options_forwarded(self):
+        router = Router(path="/")
+        router.get("/tagged", tags=["users"], timeout=2)(lambda: "ok")
+        route = router.routes[0]
+        assert route.tags == ["users"]
+        assert route.timeout == 2.0
+
+
+class TestPlainHandlers:
+    """Test (req, res) handlers registered with the decorators."""
+
+    def test_raw_handler(self, client):
+        response = client.post("/raw")
+        assert response.status == 201
+        assert response.json() == {"method": "POST"}
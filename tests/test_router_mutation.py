@@ -0,0 +1,159 @@
+"""
+Test cases for runtime route registration and removal.
+
+Tests cover:
+- remove_route / remove_prefix on the Rust Router, including host-bound routes
+- Generation bumps on every change
+- Concurrent lookups while routes are added and removed
+- Mounting and unmounting routes on a running server (removed routes 404)
+"""
+
+import threading
+
+import httpx
+import pytest
+
+from hypern._hypern import Route, Router
+
+
+def handler(req, res, ctx):
+    res.json({"ok": True})
+
+
+def make_router(*routes, prefix="/"):
+    router = Router(prefix)
+    for method, path, *host in routes:
+        router.add_route(Route(path=path, function=handler, method=method, host=host[0] if host else None))
+    return router
+
+
+class TestRouterRemoval:
+    """Test removing routes from a Router."""
+
+    def test_remove_route(self):
+        router = make_router(("GET", "/users/:id"), ("POST", "/users/:id"))
+        assert router.remove_route("GET", "/users/:id") is True
+        assert router.find_matching_route("/users/1", "GET") is None
+        assert router.find_matching_route("/users/1", "POST") is not None
+        assert router.remove_route("GET", "/users/:id") is False
+
+    def test_remove_route_is_case_insensitive_on_method(self):
+        router = make_router(("DELETE", "/items/:id"))
+        assert router.remove_route("delete", "/items/:id") is True
+        assert router.routes == []
+
+    def test_remove_route_with_router_prefix(self):
+        router = make_router(("GET", "/status"), prefix="/api")
+        assert router.find_matching_route("/api/status", "GET") is not None
+        assert router.remove_route("GET", "/api/status") is True
+        assert router.find_matching_route("/api/status", "GET") is None
+
+    def test_path_can_be_added_again(self):
+        router = make_router(("GET", "/reload"))
+        router.remove_route("GET", "/reload")
+        router.add_route(Route(path="/reload", function=handler, method="GET"))
+        assert router.find_matching_route("/reload", "GET") is not None
+
+    def test_remove_prefix(self):
+        router = make_router(
+            ("GET", "/tenants/acme"),
+            ("GET", "/tenants/acme/users/:id"),
+            ("POST", "/tenants/acme/users"),
+            ("GET", "/tenants/acmecorp/users"),
+        )
+        assert router.remove_prefix("/tenants/acme/") is True
+        assert [r.path for r in router.routes] == ["/tenants/acmecorp/users"]
+        assert router.find_matching_route("/tenants/acme/users/1", "GET") is None
+        assert router.remove_prefix("/tenants/acme") is False
+
+    def test_remove_host_route(self):
+        router = make_router(("GET", "/", "a.example.com"), ("GET", "/", "b.example.com"))
+        assert router.remove_route("GET", "/", "A.example.com") is True
+        assert [r.host for r in router.routes] == ["b.example.com"]
+
+    def test_generation_bumps(self):
+        router = make_router(("GET", "/a"))
+        before = router.generation
+        router.add_route(Route(path="/b", function=handler, method="GET"))
+        router.remove_route("GET", "/a")
+        assert router.generation >= before + 2
+
+    def test_failed_add_leaves_router_unchanged(self):
+        router = make_router(("GET", "/a"))
+        before = router.generation
+        with pytest.raises(ValueError):
+            router.add_route(Route(path="/a", function=handler, method="GET"))
+        assert len(router.routes) == 1
+        assert router.generation == before
+
+
+class TestConcurrentMutation:
+    """Test lookups racing with route changes."""
+
+    def test_hammer_lookups_while_mutating(self):
+        router = make_router(("GET", "/stable/:id"))
+        stop = threading.Event()
+        errors = []
+
+        def reader():
+            while not stop.is_set():
+                try:
+                    assert router.find_matching_route("/stable/7", "GET") is not None
+                    router.find_matching_route("/churn/3/item", "GET")
+                    router.get_route("/stable/:id", "GET")
+                except Exception as exc:  # pragma: no cover - reported below
+                    errors.append(exc)
+                    return
+
+        def writer(worker):
+            for i in range(200):
+                path = f"/churn/{worker}-{i}/item"
+                router.add_route(Route(path=path, function=handler, method="GET"))
+                assert router.find_matching_route(f"/churn/{worker}-{i}/item", "GET") is not None
+                assert router.remove_route("GET", path) is True
+
+        readers = [threading.Thread(target=reader) for _ in range(4)]
+        writers = [threading.Thread(target=writer, args=(w,)) for w in range(3)]
+        for thread in readers + writers:
+            thread.start()
+        for thread in writers:
+            thread.join()
+        stop.set()
+        for thread in readers:
+            thread.join()
+
+        assert errors == []
+        assert [r.path for r in router.routes] == ["/stable/:id"]
+
+
+class TestRuntimeRoutes:
+    """Test mounting and unmounting routes on the running server."""
+
+    def test_mount_and_unmount(self, client: httpx.Client):
+        assert client.get("/runtime/hello").status_code == 404
+
+        response = client.post("/admin/routes/mount", json={"path": "/runtime/hello"})
+        assert response.status_code == 201
+        assert client.get("/runtime/hello").json()["mounted"] == "/runtime/hello"
+
+        response = client.post("/admin/routes/unmount", json={"path": "/runtime/hello"})
+        assert response.json() == {"removed": True}
+        assert client.get("/runtime/hello").status_code == 404
+
+        response = client.post("/admin/routes/unmount", json={"path": "/runtime/hello"})
+        assert response.json() == {"removed": False}
+
+    def test_unmount_prefix(self, client: httpx.Client):
+        for path in ("/runtime/t/:tenant/a", "/runtime/t/:tenant/b"):
+            client.post("/admin/routes/mount", json={"path": path})
+        assert client.get("/runtime/t/acme/a").json()["tenant"] == "acme"
+
+        response = client.post("/admin/routes/unmount", json={"prefix": "/runtime/t"})
+        assert response.json() == {"removed": True}
+        assert client.get("/runtime/t/acme/a").status_code == 404
+        assert client.get("/runtime/t/acme/b").status_code == 404
+
+    def test_existing_routes_unaffected(self, client: httpx.Client):
+        client.post("/admin/routes/mount", json={"path": "/runtime/tmp"})
+        client.post("/admin/routes/unmount", json={"prefix": "/runtime"})
+        assert client.get("/health").status_code == 200
@@ -5,8 +5,10 @@ This server runs in a separate process to avoid threading issues with signals.
 """
 
 import json
+import logging
 import os
 import sys
+import threading
 import time
 from typing import Dict, Any, Optional
 
@@ -19,16 +21,31 @@ from hypern import (
     Hypern, 
     Router, 
     SSEEvent,
+    BlockingExecutor,
+    sse_stats,
+    request_decompression_stats,
+    dispatch_timing_stats,
+    stream_drain_stats,
+    server_metrics,
+    large_response_stats,
+    listener_options,
+    ChannelManager,
+    realtime_poll_stats,
     NotFound, 
     BadRequest, 
     Unauthorized,
     HTTPException,
     inject,
+    current_context,
+    current_request_id,
+    stream,
 )
+from hypern.database import Database, db
 from hypern.validation import validate, validate_body, validate_query
 from hypern.middleware import (
     CorsMiddleware, RateLimitMiddleware, SecurityHeadersMiddleware, CompressionMiddleware,
-    RequestIdMiddleware, BasicAuthMiddleware
+    RequestIdMiddleware, BasicAuthMiddleware, TimeoutMiddleware, IdempotencyMiddleware,
+    singleflight_stats,
 )
 
 
@@ -135,7 +152,10 @@ class MockDatabase:
 test_db = MockDatabase()
 
 
-def create_test_app() -> Hypern:
+def create_test_app(
+    idempotency_wait: Optional[float] = None,
+    basic_auth: bool = False,
+) -> Hypern:
     """Create and configure the test application with all features."""
     
     app = Hypern(debug=True)
@@ -156,6 +176,16 @@ def create_test_app() -> Hypern:
     # Compression for responses > 100 bytes
     app.use(CompressionMiddleware(min_size=100))
     
+    # Default handler deadline; routes may set their own timeout
+    app.use(TimeoutMiddleware(timeout_secs=30))
+
+    # Replays POST/PATCH responses for repeated Idempotency-Key headers
+    app.use(IdempotencyMiddleware(max_key_length=64, max_body_size=4096, wait_secs=idempotency_wait))
+
+    # Every route behind Basic auth (checked before Expect: 100-continue bodies)
+    if basic_auth:
+        app.use(BasicAuthMiddleware(realm="Test Area", users={"admin": "secret"}))
+    
     # ========================================================================
     # Dependency Injection Setup
     # ========================================================================
@@ -175,6 +205,35 @@ def create_test_app() -> Hypern:
     
     app.factory("request_logger", create_request_logger)
     
+    # Typed providers resolved with req.dep(); the app counter is built by a
+    # coroutine factory at worker startup
+    dep_teardowns = {"unit_of_work": 0, "created": 0}
+    
+    class AppCounter:
+        def __init__(self):
+            self.value = 0
+            self.lock = threading.Lock()
+        
+        def next(self) -> int:
+            with self.lock:
+                self.value += 1
+                return self.value
+    
+    async def create_counter():
+        return AppCounter()
+    
+    class UnitOfWork:
+        def __init__(self, counter):
+            self.counter = counter
+            dep_teardowns["created"] += 1
+            self.serial = dep_teardowns["created"]
+    
+    def close_unit_of_work(uow):
+        dep_teardowns["unit_of_work"] += 1
+    
+    app.provide("counter", create_counter)
+    app.provide("unit_of_work", UnitOfWork, scope="request", teardown=close_unit_of_work)
+    
     # ========================================================================
     # Basic Routes - HTTP Methods
     # ========================================================================
@@ -193,6 +252,272 @@ def create_test_app() -> Hypern:
         body = req.json()
         res.json({"echo": body})
     
+    @app.post("/echo/force")
+    def echo_force(req, res, ctx):
+        """Echo back the body parsed as JSON regardless of Content-Type."""
+        res.json({"echo": req.json(force=True)})
+
+    @app.post("/echo/limited", max_json_bytes=64)
+    def echo_limited(req, res, ctx):
+        """Echo back a JSON body capped at 64 bytes by the route."""
+        res.json({"echo": req.json()})
+
+    @app.post("/echo/body-info")
+    def echo_body_info(req, res, ctx):
+        """Report the body size and encoding headers seen by the handler."""
+        res.json({
+            "size": len(req.body_bytes()),
+            "content_encoding": req.header("content-encoding"),
+            "content_length": req.header("content-length"),
+        })
+
+    @app.get("/decompression/stats")
+    def decompression_stats(req, res, ctx):
+        res.json(request_decompression_stats())
+
+    @app.get("/conn/info")
+    def connection_info(req, res, ctx):
+        """Report the transport details of the connection."""
+        res.json({
+            "scheme": req.scheme,
+            "secure": req.secure,
+            "peer_addr": req.peer_addr,
+            "alpn_protocol": req.alpn_protocol,
+            "peer_cert": req.peer_cert,
+        })
+
+    @app.get("/deps/counter")
+    def deps_counter(req, res, ctx):
+        """Increment the app-scoped counter."""
+        counter = req.dep("counter")
+        res.json({"value": counter.next(), "id": id(counter)})
+
+    @app.get("/deps/unit-of-work")
+    def deps_unit_of_work(req, res, ctx):
+        """Resolve the request-scoped unit of work twice."""
+        uow = req.dep("unit_of_work")
+        res.json({
+            "serial": uow.serial,
+            "same_in_request": req.dep("unit_of_work") is uow,
+            "shares_counter": uow.counter is req.dep("counter"),
+        })
+
+    @app.get("/deps/teardowns")
+    def deps_teardown_counts(req, res, ctx):
+        res.json(dep_teardowns)
+
+    # Per-stage timing (the server runs with server_timing=True)
+    @app.get("/timing/slow")
+    def timing_slow(req, res, ctx):
+        time.sleep(0.05)
+        res.json({"slept_ms": 50})
+
+    @app.get("/timing/stats")
+    def timing_stats(req, res, ctx):
+        res.json(dispatch_timing_stats())
+
+    # Windowed request metrics
+    @app.get("/metrics/server")
+    def metrics_server(req, res, ctx):
+        res.json(server_metrics().snapshot())
+
+    @app.get("/metrics/slow/{id}")
+    def metrics_slow(req, res, ctx):
+        time.sleep(0.03)
+        res.json({"id": req.param("id")})
+
+    @app.get("/metrics/fail")
+    def metrics_fail(req, res, ctx):
+        res.status(502).json({"error": "upstream"})
+
+    @app.get("/metrics/prometheus")
+    def metrics_prometheus(req, res, ctx):
+        res.text(server_metrics().render())
+
+    # Per-route config (timeout, body limit, cache TTL, logging, metadata)
+    @app.get("/route-config/slow", timeout="100ms")
+    def route_config_slow(req, res, ctx):
+        time.sleep(0.5)
+        res.json({"finished": True})
+
+    @app.get("/route-config/inherit")
+    def route_config_inherit(req, res, ctx):
+        time.sleep(0.2)
+        res.json({"finished": True})
+
+    @app.post("/route-config/small-body", max_body_size="64b")
+    def route_config_small_body(req, res, ctx):
+        res.json({"size": len(req.body_bytes())})
+
+    @app.get("/route-config/cached", cache_ttl="1m")
+    def route_config_cached(req, res, ctx):
+        res.json({"cached": True})
+
+    @app.get("/route-config/cached-override", cache_ttl=60)
+    def route_config_cached_override(req, res, ctx):
+        res.cache_control(no_store=True)
+        res.json({"cached": False})
+
+    @app.get("/route-config/quiet", log=False)
+    def route_config_quiet(req, res, ctx):
+        res.json({"quiet": True})
+
+    @app.get("/route-config/loud")
+    def route_config_loud(req, res, ctx):
+        res.json({"quiet": False})
+
+    @app.get("/route-config/meta", metadata={"team": "payments", "tier": "1"})
+    def route_config_meta(req, res, ctx):
+        res.json(req.route_meta)
+
+    # Request deadlines carried into downstream work
+    deadline_results: Dict[str, Any] = {}
+    deadline_executors: list = []
+
+    def deadline_executor() -> BlockingExecutor:
+        # Created in the worker: pool threads do not survive the fork
+        if not deadline_executors:
+            deadline_executors.append(BlockingExecutor(max_threads=2))
+        return deadline_executors[0]
+
+    @app.get("/deadline/remaining", timeout="2s")
+    def deadline_remaining(req, res, ctx):
+        res.json({"remaining": req.deadline_remaining()})
+
+    @app.get("/deadline/global")
+    def deadline_global(req, res, ctx):
+        res.json({"remaining": req.deadline_remaining()})
+
+    @app.get("/deadline/executor", timeout="100ms")
+    def deadline_executor_late(req, res, ctx):
+        # Outlives its timeout, so the executor must refuse new work
+        time.sleep(0.2)
+        try:
+            deadline_results["executor"] = deadline_executor().run_sync(lambda: "ran")
+        except TimeoutError:
+            deadline_results["executor"] = "timeout"
+
+    @app.get("/deadline/executor-in-time", timeout="2s")
+    def deadline_executor_in_time(req, res, ctx):
+        res.json({"result": deadline_executor().run_sync(lambda: "ran")})
+
+    @app.get("/deadline/db-sleep", timeout="1s")
+    def deadline_db_sleep(req, res, ctx):
+        session = db(ctx)
+        started = time.monotonic()
+        try:
+            session.query("SELECT pg_sleep(10)")
+            outcome = "finished"
+        except TimeoutError:
+            outcome = "timeout"
+        deadline_results["db"] = {"outcome": outcome, "elapsed": time.monotonic() - started}
+
+    @app.get("/deadline/db-check")
+    def deadline_db_check(req, res, ctx):
+        session = db(ctx)
+        row = session.query_one(
+            "SELECT count(*) AS sleeping FROM pg_stat_activity "
+            "WHERE query LIKE 'SELECT pg_sleep%' AND state = 'active'"
+        )
+        res.json({"sleeping": row["sleeping"]})
+
+    @app.get("/deadline/results")
+    def deadline_results_view(req, res, ctx):
+        res.json(deadline_results)
+
+    # Path normalization
+    @app.get("/paths/echo/:name")
+    def path_param_echo(req, res, ctx):
+        res.json({
+            "name": req.param("name"),
+            "path": req.path,
+            "raw_path": req.raw_path,
+        })
+
+    @app.get("/paths/a/c")
+    def path_dot_segments(req, res, ctx):
+        res.json({"path": req.path, "raw_path": req.raw_path})
+
+    @app.get("/paths/double/slashes")
+    def path_double_slashes(req, res, ctx):
+        res.json({"path": req.path, "raw_path": req.raw_path})
+
+    # Typed route parameters
+    def describe_typed(req, name):
+        value = req.param_typed(name)
+        return {"type": type(value).__name__, "value": str(value), "raw": req.param(name)}
+
+    @app.get("/typed/int/{id:int}")
+    def typed_int(req, res, ctx):
+        res.json(describe_typed(req, "id"))
+
+    @app.get("/typed/float/{value:float}")
+    def typed_float(req, res, ctx):
+        res.json(describe_typed(req, "value"))
+
+    @app.get("/typed/uuid/{uid:uuid}")
+    def typed_uuid(req, res, ctx):
+        res.json(describe_typed(req, "uid"))
+
+    @app.get("/typed/date/{day:date}")
+    def typed_date(req, res, ctx):
+        data = describe_typed(req, "day")
+        data["weekday"] = req.param_typed("day").isoweekday()
+        res.json(data)
+
+    @app.get("/typed/str/{name:str}")
+    def typed_str(req, res, ctx):
+        res.json(describe_typed(req, "name"))
+
+    @app.get("/typed/mixed/{id:int}/:slug")
+    def typed_mixed(req, res, ctx):
+        params = req.params_typed()
+        res.json({
+            "id": describe_typed(req, "id"),
+            "slug": describe_typed(req, "slug"),
+            "types": {name: type(value).__name__ for name, value in params.items()},
+            "missing": req.param_typed("nope"),
+        })
+
+    # Worker warm-up: the module behind this route is imported lazily
+    app.get("/warmup/lazy")("warmup_handlers:imported_at")
+
+    warmup_hits = {"count": 0}
+
+    @app.get("/warmup/ping")
+    def warmup_ping(req, res, ctx):
+        if req.header("x-hypern-warmup") == "1":
+            warmup_hits["count"] += 1
+        res.json({"ok": True})
+
+    @app.get("/warmup/hits")
+    def warmup_hits_view(req, res, ctx):
+        res.json(warmup_hits)
+
+    @app.get("/warmup/fail")
+    def warmup_fail(req, res, ctx):
+        raise RuntimeError("warm-up dependency unavailable")
+
+    # Runtime route mounting (single worker, so changes apply to every request)
+    @app.post("/admin/routes/mount")
+    def mount_runtime_route(req, res, ctx):
+        path = req.json()["path"]
+
+        @app.get(path)
+        def runtime_route(req, res, ctx):
+            res.json({"mounted": path, "tenant": req.param("tenant")})
+
+        res.status(201).json({"mounted": path, "generation": app.router.generation})
+
+    @app.post("/admin/routes/unmount")
+    def unmount_runtime_route(req, res, ctx):
+        body = req.json()
+        if "prefix" in body:
+            removed = app.remove_prefix(body["prefix"])
+        else:
+            removed = app.remove_route("GET", body["path"])
+        res.json({"removed": removed})
+
     @app.put("/echo")
     def echo_put(req, res, ctx):
         """Echo back the request body for PUT."""
@@ -265,6 +590,26 @@ def create_test_app() -> Hypern:
             "limit": int(limit),
             "all_queries": all_queries
         })
+
+    @app.get("/query/multi")
+    def query_multi(req, res, ctx):
+        res.json({
+            "tags": req.query_list("tag"),
+            "tags_raw": req.query_list("tag", strip_brackets=False),
+            "multi": req.query_params_multi(),
+            "multi_order": list(req.query_params_multi()),
+            "multi_raw": req.query_params_multi(strip_brackets=False),
+            "flat": req.query_params,
+        })
+
+    @app.post("/form/multi")
+    def form_multi(req, res, ctx):
+        form = req.form()
+        res.json({
+            "tags": form.get_list("tag"),
+            "multi": form.fields_multi(),
+            "last": form.get("tag"),
+        })
     
     # ========================================================================
     # Request Data Access
@@ -492,6 +837,108 @@ def create_test_app() -> Hypern:
         res.attachment("data.json")
         res.json(data)
     
+    @app.get("/large/:size")
+    def large_response(req, res, ctx):
+        """Deterministic body of the requested size (capped at 64 MiB)."""
+        size = min(int(req.param("size")), 64 * 1024 * 1024)
+        pattern = bytes(range(256))
+        res.header("Content-Type", "application/octet-stream")
+        res.body((pattern * (size // 256 + 1))[:size])
+
+    @app.get("/large-stats")
+    def large_stats(req, res, ctx):
+        res.json(large_response_stats())
+
+    @app.get("/socket/options")
+    def socket_options(req, res, ctx):
+        res.json(listener_options())
+
+    # ========================================================================
+    # Realtime long polling
+    # ========================================================================
+
+    poll_channels = ChannelManager()
+    poll_channels.create_channel("poll:news", replay_size=100)
+    poll_channels.create_channel("poll:live")
+    poll_channels.create_channel("poll:private", replay_size=10)
+    poll_channels.set_subscribe_hook(
+        lambda channel, client_id, metadata: channel != "poll:private"
+    )
+    app.realtime_poll(poll_channels, max_wait_secs=5, max_polls_per_client=2)
+
+    @app.post("/poll/publish/:channel")
+    def poll_publish(req, res, ctx):
+        channel = req.param("channel")
+        poll_channels.publish(channel, req.body_bytes().decode(), bypass_hooks=True)
+        res.json({"last_seq": poll_channels.last_seq(channel)})
+
+    @app.get("/poll/stats")
+    def poll_stats(req, res, ctx):
+        res.json(realtime_poll_stats())
+
+    idempotent_calls = {"count": 0}
+
+    @app.post("/idempotent/charge")
+    def idempotent_charge(req, res, ctx):
+        idempotent_calls["count"] += 1
+        res.header("X-Charge-Id", f"ch_{idempotent_calls['count']}")
+        res.status(201).json({"charge": idempotent_calls["count"], "amount": req.json().get("amount")})
+
+    @app.post("/idempotent/slow")
+    def idempotent_slow(req, res, ctx):
+        time.sleep(1.0)
+        idempotent_calls["count"] += 1
+        res.json({"charge": idempotent_calls["count"]})
+
+    @app.post("/idempotent/large")
+    def idempotent_large(req, res, ctx):
+        idempotent_calls["count"] += 1
+        res.text(f"{idempotent_calls['count']}:" + "x" * 8192)
+
+    @app.post("/idempotent/fail")
+    def idempotent_fail(req, res, ctx):
+        idempotent_calls["count"] += 1
+        res.status(503).json({"attempt": idempotent_calls["count"]})
+
+    @app.get("/idempotent/count")
+    def idempotent_count(req, res, ctx):
+        res.json(idempotent_calls)
+
+    coalesce_calls: Dict[str, int] = {}
+
+    @app.get("/coalesce/slow/{name}", coalesce=True)
+    def coalesce_slow(req, res, ctx):
+        name = req.param("name")
+        coalesce_calls[name] = coalesce_calls.get(name, 0) + 1
+        call = coalesce_calls[name]
+        time.sleep(0.5)
+        res.json({"name": name, "call": call, "query": req.query_string})
+
+    @app.get("/coalesce/cookie/{name}", coalesce=True)
+    def coalesce_cookie(req, res, ctx):
+        name = req.param("name")
+        coalesce_calls[name] = coalesce_calls.get(name, 0) + 1
+        call = coalesce_calls[name]
+        time.sleep(0.5)
+        res.cookie("session", f"s{call}")
+        res.json({"call": call})
+
+    @app.get("/coalesce/fail/{name}", coalesce=True)
+    def coalesce_fail(req, res, ctx):
+        name = req.param("name")
+        coalesce_calls[name] = coalesce_calls.get(name, 0) + 1
+        call = coalesce_calls[name]
+        time.sleep(0.5)
+        res.status(500).json({"call": call})
+
+    @app.get("/coalesce/count/{name}")
+    def coalesce_count(req, res, ctx):
+        res.json({"count": coalesce_calls.get(req.param("name"), 0)})
+
+    @app.get("/coalesce/stats")
+    def coalesce_stats(req, res, ctx):
+        res.json(singleflight_stats())
+
     @app.get("/download/binary")
     def download_binary_file(req, res, ctx):
         """Download binary data."""
@@ -796,6 +1243,106 @@ def create_test_app() -> Hypern:
         ]
         res.sse(events)
     
+    @app.get("/sse/live/idle")
+    def sse_live_idle(req, res, ctx):
+        # No events; the server keepalive task is the only writer
+        stream = res.sse_live(keepalive_secs=0.2)
+        threading.Timer(float(req.query("close_after") or 1.5), stream.close).start()
+    
+    @app.get("/sse/live/events")
+    def sse_live_events(req, res, ctx):
+        stream = res.sse_live(keepalive_secs=0.2)
+        
+        def produce():
+            # Activity every 100ms keeps the stream from looking idle
+            for i in range(6):
+                stream.send_event("tick", str(i))
+                time.sleep(0.1)
+            stream.close()
+        
+        threading.Thread(target=produce, daemon=True).start()
+    
+    @app.get("/sse/live/late")
+    def sse_live_late(req, res, ctx):
+        # The handler keeps running after the stream starts
+        stream = res.sse_live()
+        time.sleep(1.0)
+        stream.send_event("late", "done")
+        stream.close()
+    
+    @app.get("/sse/live/connected")
+    def sse_live_connected(req, res, ctx):
+        stream = res.sse_live(connected=True)
+        threading.Timer(1.0, stream.close).start()
+    
+    @app.get("/sse/live/send-close")
+    def sse_live_send_close(req, res, ctx):
+        stream = res.sse_live()
+        for i in range(3):
+            stream.send_event("item", str(i))
+        stream.close()
+    
+    @app.get("/sse/generator/slow")
+    def sse_generator_slow(req, res, ctx):
+        def events():
+            for i in range(3):
+                time.sleep(0.4)
+                yield SSEEvent(str(i), event="tick")
+        res.sse_stream(events())
+    
+    @app.get("/sse/live/stats")
+    def sse_live_stats(req, res, ctx):
+        res.json(sse_stats())
+    
+    @app.get("/sse/live/drain-stats")
+    def sse_live_drain_stats(req, res, ctx):
+        res.json(stream_drain_stats())
+
+    @app.get("/drain/slow")
+    def drain_slow(req, res, ctx):
+        time.sleep(float(req.query("secs") or "1"))
+        res.json({"slow": True})
+    
+    # ========================================================================
+    # Generator Streaming Routes
+    # ========================================================================
+    
+    @app.get("/stream/async")
+    async def stream_async(req, res, ctx):
+        # Async generator handler: the return value is the body
+        for i in range(1000):
+            yield f"{i:04d}\n"
+    
+    @app.get("/stream/sync")
+    def stream_sync(req, res, ctx):
+        def chunks():
+            for i in range(100):
+                yield f"{i:03d}\n".encode()
+        return stream(chunks(), content_type="text/plain; charset=utf-8")
+    
+    @app.get("/stream/ndjson")
+    async def stream_ndjson(req, res, ctx):
+        async def rows():
+            for i in range(5):
+                yield json.dumps({"row": i}) + "\n"
+        return stream(rows(), content_type="application/x-ndjson")
+    
+    @app.get("/stream/raises")
+    def stream_raises(req, res, ctx):
+        def chunks():
+            for i in range(500):
+                if i == 250:
+                    raise RuntimeError("generator failed halfway")
+                yield f"{i:04d}\n"
+        return stream(chunks(), content_type="text/plain")
+    
+    @app.get("/stream/async-raises")
+    async def stream_async_raises(req, res, ctx):
+        for i in range(500):
+            if i == 250:
+                raise RuntimeError("async generator failed halfway")
+            yield f"{i:04d}\n"
+    
     # ========================================================================
     # Background Tasks Routes
     # ========================================================================
@@ -927,7 +1474,47 @@ def create_test_app() -> Hypern:
     # Mount routers - use app.mount() with router's own prefix
     app.mount(api_v1)
     app.mount(api_v2)
-    
+
+    # ========================================================================
+    # Route Groups (shared prefix, middleware, tags, host)
+    # ========================================================================
+
+    def trace_middleware(name):
+        async def record(req, res, ctx, next):
+            ctx.set("trace", (ctx.get("trace") or []) + [name])
+            await next()
+        return record
+
+    grouped = Router(prefix="/groups")
+    groups_v1 = grouped.group("/v1", middlewares=[trace_middleware("v1")], tags=["v1"])
+    groups_admin = groups_v1.group(
+        "/admin", middlewares=[trace_middleware("admin")], tags=["admin"]
+    )
+
+    @groups_admin.get("/users/:id", middleware=[trace_middleware("route")])
+    def group_admin_user(req, res, ctx):
+        res.json({"id": req.param("id"), "trace": ctx.get("trace")})
+
+    @groups_v1.get("/")
+    def group_v1_index(req, res, ctx):
+        res.json({"trace": ctx.get("trace")})
+
+    groups_api_host = grouped.group("/host", host="api.example.com")
+
+    @groups_api_host.get("/whoami")
+    def group_host_api(req, res, ctx):
+        res.json({"host": "api"})
+
+    @groups_api_host.get("/only")
+    def group_host_only(req, res, ctx):
+        res.json({"host": "api"})
+
+    @grouped.get("/host/whoami")
+    def group_host_default(req, res, ctx):
+        res.json({"host": "default"})
+
+    app.mount(grouped)
+
     # ========================================================================
     # Router with Validation (tests Router + @validate_body together)
     # ========================================================================
@@ -1130,6 +1717,31 @@ def create_test_app() -> Hypern:
     def cors_preflight(req, res, ctx):
         res.status(204).send(None)
     
+    # Request context endpoint - a logging filter reads the contextvar
+    context_logger = logging.getLogger("hypern.tests.context")
+    context_logger.propagate = False
+    context_logger.setLevel(logging.INFO)
+    seen_request_ids = []
+
+    class RequestIdFilter(logging.Filter):
+        def filter(self, record):
+            record.request_id = current_request_id()
+            seen_request_ids.append(record.request_id)
+            return True
+
+    context_logger.addFilter(RequestIdFilter())
+    context_logger.addHandler(logging.NullHandler())
+
+    @app.get("/context/request-id")
+    def context_request_id(req, res, ctx):
+        context_logger.info("handling request")
+        res.json({
+            "logged": seen_request_ids[-1],
+            "current": current_request_id(),
+            "context": current_context(),
+            "req_context": req.context,
+        })
+
     # Rate limiting endpoint - strict limit for testing
     RateLimitMiddleware(max_requests=3, window_secs=10)
     @app.get("/middleware/ratelimit/strict")
@@ -1314,15 +1926,69 @@ if __name__ == "__main__":
     parser = argparse.ArgumentParser(description="Run Hypern test server")
     parser.add_argument("--host", default="127.0.0.1", help="Host to bind to")
     parser.add_argument("--port", type=int, default=8765, help="Port to listen on")
+    parser.add_argument("--tls-cert", help="PEM certificate; enables HTTPS with --tls-key")
+    parser.add_argument("--tls-key", help="PEM private key")
+    parser.add_argument("--tls-client-ca", help="PEM CA for client certificates")
+    parser.add_argument("--tls-require-client-cert", action="store_true")
+    parser.add_argument("--drain-timeout", type=int, help="Graceful reload drain timeout in seconds")
+    parser.add_argument("--stream-grace-ms", type=int, default=500)
+    parser.add_argument("--database-url", help="PostgreSQL URL for the /deadline/db-* routes")
+    parser.add_argument("--warmup-path", action="append", help="Path requested during worker warm-up")
+    parser.add_argument("--eager-import", action="store_true")
+    parser.add_argument("--warmup-strict", action="store_true")
+    parser.add_argument("--strict-path-encoding", action="store_true")
+    parser.add_argument("--workers", type=int, default=1, help="Worker processes")
+    parser.add_argument("--backlog", type=int, default=1024)
+    parser.add_argument("--reuse-port", action="store_true")
+    parser.add_argument("--no-tcp-nodelay", action="store_true")
+    parser.add_argument("--keepalive-idle", type=int, default=60)
+    parser.add_argument("--keepalive-interval", type=int)
+    parser.add_argument("--keepalive-count", type=int)
+    parser.add_argument("--idempotency-wait", type=float, help="Seconds duplicates wait for in-flight requests")
+    parser.add_argument("--basic-auth", action="store_true")
+    parser.add_argument("--no-expect-continue", action="store_true")
+    parser.add_argument("--hsts-preload", action="store_true")
     
     args = parser.parse_args()
     
-    app = create_test_app()
+    app = create_test_app(
+        idempotency_wait=args.idempotency_wait,
+        basic_auth=args.basic_auth,
+    )
+    if args.database_url:
+        # One connection, so the follow-up check reuses the cancelled one
+        Database.configure(url=args.database_url, max_size=1)
+    if args.drain_timeout is not None:
+        app.setup_reload(
+            drain_timeout_secs=args.drain_timeout,
+            stream_retry_ms=2000,
+            stream_grace_ms=args.stream_grace_ms,
+        )
     print(f"Starting test server on {args.host}:{args.port}")
     app.start(
         host=args.host,
         port=args.port,
-        num_processes=1,
+        num_processes=args.workers,
         workers_threads=2,
         max_blocking_threads=8,
+        allowed_hosts=[args.host, "localhost", "::1", "hypern.test", "*.example.com"],
+        decompress_requests=True,
+        max_decompressed_size=1024 * 1024,
+        tls_cert_path=args.tls_cert,
+        tls_key_path=args.tls_key,
+        tls_client_ca_path=args.tls_client_ca,
+        tls_require_client_cert=args.tls_require_client_cert,
+        server_timing=True,
+        warmup_paths=args.warmup_path,
+        eager_import=args.eager_import,
+        warmup_strict=args.warmup_strict,
+        strict_path_encoding=args.strict_path_encoding,
+        backlog=args.backlog,
+        reuse_port=args.reuse_port,
+        tcp_nodelay=not args.no_tcp_nodelay,
+        keepalive_idle=args.keepalive_idle,
+        keepalive_interval=args.keepalive_interval,
+        keepalive_count=args.keepalive_count,
+        expect_continue=not args.no_expect_continue,
+        hsts_preload=args.hsts_preload,
     )
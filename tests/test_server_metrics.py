@@ -0,0 +1,266 @@
+"""
+Test cases for windowed request metrics.
+
+Tests cover:
+- Request and error rates over a window, decaying as the clock moves on
+- Windows clamped to the retained history
+- Slowest and most erroring routes
+- reset() and manual clock validation
+- The server's own collector: server_metrics(), Server.stats() and render()
+- Totals merged across forked workers, per-worker breakdowns and exited workers
+"""
+
+import os
+
+import httpx
+import pytest
+
+from hypern import ServerMetrics, server_metrics
+from hypern._hypern import Server
+
+
+def burst(metrics: ServerMetrics, count: int, route: str = "/users/{id}", status: int = 200, duration_ms: float = 5.0):
+    for _ in range(count):
+        metrics.record("GET", route, status, duration_ms)
+
+
+class TestRate:
+    """Test rates over a sliding window."""
+
+    def test_burst_rate(self):
+        metrics = ServerMetrics(manual_clock=True)
+        burst(metrics, 100)
+        rate = metrics.rate(10)
+        assert rate["requests"] == 100
+        assert rate["window_secs"] == 10
+        assert rate["request_rate"] == pytest.approx(10.0)
+        assert rate["avg_ms"] == pytest.approx(5.0)
+
+    def test_rate_decays(self):
+        metrics = ServerMetrics(manual_clock=True)
+        burst(metrics, 100)
+        metrics.advance(5)
+        assert metrics.rate(10)["requests"] == 100
+        metrics.advance(5)
+        assert metrics.rate(10)["requests"] == 0
+        # Still inside a longer window
+        assert metrics.rate(60)["requests"] == 100
+
+    def test_buckets_rotate(self):
+        metrics = ServerMetrics(bucket_secs=1, retain_secs=10, manual_clock=True)
+        burst(metrics, 10)
+        metrics.advance(10)
+        burst(metrics, 3)
+        # The slot reused for the new interval holds only the new records
+        assert metrics.rate(1)["requests"] == 3
+        assert metrics.rate(10)["requests"] == 3
+
+    def test_errors(self):
+        metrics = ServerMetrics(manual_clock=True)
+        burst(metrics, 8)
+        burst(metrics, 2, status=503)
+        burst(metrics, 5, status=404)
+        rate = metrics.rate(10)
+        assert rate["errors"] == 2
+        assert rate["error_rate"] == pytest.approx(0.2)
+
+    def test_window_clamped(self):
+        metrics = ServerMetrics(retain_secs=30, manual_clock=True)
+        burst(metrics, 30)
+        rate = metrics.rate(3600)
+        assert rate["window_secs"] == 30
+        assert rate["request_rate"] == pytest.approx(1.0)
+
+    def test_unmatched_requests_counted(self):
+        metrics = ServerMetrics(manual_clock=True)
+        metrics.record("GET", None, 404, 1.0)
+        assert metrics.rate(10)["requests"] == 1
+        assert metrics.top_routes()["slowest"] == []
+
+    def test_invalid_window(self):
+        with pytest.raises(ValueError):
+            ServerMetrics().rate(0)
+
+
+class TestTopRoutes:
+    """Test the slowest and most erroring routes."""
+
+    def test_slowest(self):
+        metrics = ServerMetrics(manual_clock=True)
+        burst(metrics, 10, route="/fast", duration_ms=1)
+        burst(metrics, 3, route="/slow", duration_ms=300)
+        burst(metrics, 5, route="/medium", duration_ms=50)
+        slowest = metrics.top_routes(2)["slowest"]
+        assert [r["route"] for r in slowest] == ["/slow", "/medium"]
+        assert slowest[0]["method"] == "GET"
+        assert slowest[0]["count"] == 3
+        assert slowest[0]["avg_ms"] == pytest.approx(300)
+        assert slowest[0]["max_ms"] == pytest.approx(300)
+
+    def test_erroring(self):
+        metrics = ServerMetrics(manual_clock=True)
+        burst(metrics, 4, route="/flaky", status=500)
+        burst(metrics, 1, route="/rare", status=502)
+        burst(metrics, 10, route="/fine")
+        erroring = metrics.top_routes()["erroring"]
+        assert [(r["route"], r["errors"]) for r in erroring] == [("/flaky", 4), ("/rare", 1)]
+
+    def test_default_k(self):
+        metrics = ServerMetrics(top_k=3, manual_clock=True)
+        for i in range(6):
+            burst(metrics, 1, route=f"/r{i}", duration_ms=i)
+        assert len(metrics.top_routes()["slowest"]) == 3
+
+    def test_methods_kept_apart(self):
+        metrics = ServerMetrics(manual_clock=True)
+        metrics.record("GET", "/items", 200, 1)
+        metrics.record("POST", "/items", 200, 100)
+        slowest = metrics.top_routes()["slowest"]
+        assert [(r["method"], r["route"]) for r in slowest] == [("POST", "/items"), ("GET", "/items")]
+
+
+class TestReset:
+    """Test reset() and the manual clock."""
+
+    def test_reset(self):
+        metrics = ServerMetrics(manual_clock=True)
+        burst(metrics, 10, status=500)
+        metrics.reset()
+        snapshot = metrics.snapshot()
+        assert snapshot["total_requests"] == 0
+        assert snapshot["rate_1m"]["requests"] == 0
+        assert snapshot["slowest"] == snapshot["erroring"] == []
+
+    def test_advance_needs_manual_clock(self):
+        with pytest.raises(ValueError):
+            ServerMetrics().advance(1)
+
+    def test_invalid_config(self):
+        with pytest.raises(ValueError):
+            ServerMetrics(bucket_secs=10, retain_secs=5)
+
+
+class TestServerCollector:
+    """Test the collector the server records responses in."""
+
+    def test_routes_recorded(self, client: httpx.Client):
+        for i in range(3):
+            client.get(f"/metrics/slow/{i}")
+        client.get("/metrics/fail")
+        snapshot = client.get("/metrics/server").json()
+        assert snapshot["rate_1m"]["requests"] >= 4
+        slow = [r for r in snapshot["slowest"] if r["route"] == "/metrics/slow/{id}"]
+        assert slow and slow[0]["count"] >= 3
+        assert slow[0]["avg_ms"] >= 30
+        failing = [r for r in snapshot["erroring"] if r["route"] == "/metrics/fail"]
+        assert failing and failing[0]["errors"] >= 1
+
+    def test_prometheus(self, client: httpx.Client):
+        client.get("/metrics/slow/1")
+        text = client.get("/metrics/prometheus").text
+        assert "# TYPE hypern_request_rate gauge" in text
+        assert 'hypern_request_rate{window="1m"}' in text
+        assert 'hypern_error_rate{window="5m"}' in text
+        assert 'hypern_route_avg_duration_seconds{method="GET",route="/metrics/slow/{id}"}' in text
+
+    def test_server_stats(self):
+        stats = Server().stats()
+        assert set(stats["metrics"]) >= {"total_requests", "rate_1m", "rate_5m", "slowest", "erroring"}
+
+
+def fork_worker(requests: int, errors: int):
+    """Fork a worker that records traffic, publishes it and waits to be released"""
+    ready_r, ready_w = os.pipe()
+    done_r, done_w = os.pipe()
+    pid = os.fork()
+    if pid == 0:
+        try:
+            metrics = server_metrics()
+            for _ in range(requests):
+                metrics.record("GET", "/orders", 200, 4.0)
+            for _ in range(errors):
+                metrics.record("GET", "/orders", 503, 4.0)
+            os.write(ready_w, b"1" if metrics.publish() else b"0")
+            os.read(done_r, 1)
+        finally:
+            os._exit(0)
+    os.close(ready_w)
+    os.close(done_r)
+    assert os.read(ready_r, 1) == b"1"
+    os.close(ready_r)
+    return pid, done_w
+
+
+def release_worker(pid: int, done_w: int):
+    os.write(done_w, b"1")
+    os.close(done_w)
+    os.waitpid(pid, 0)
+
+
+@pytest.mark.skipif(not hasattr(os, "fork"), reason="needs fork")
+class TestCluster:
+    """Test totals merged across worker processes."""
+
+    def test_merged_totals(self):
+        metrics = server_metrics()
+        metrics.reset()
+        workers = [fork_worker(30, 2), fork_worker(12, 0)]
+        try:
+            snapshot = metrics.snapshot(cluster=True, per_worker=True)
+            assert snapshot["workers"] == 3
+            assert snapshot["total_requests"] == 44
+            assert snapshot["total_errors"] == 2
+            by_pid = {worker["pid"]: worker for worker in snapshot["per_worker"]}
+            assert by_pid[os.getpid()]["total_requests"] == 0
+            assert by_pid[workers[0][0]]["total_requests"] == 32
+            assert by_pid[workers[0][0]]["total_errors"] == 2
+            assert by_pid[workers[1][0]]["total_requests"] == 12
+            assert by_pid[workers[1][0]]["age_secs"] >= 0
+            orders = [r for r in snapshot["slowest"] if r["route"] == "/orders"]
+            assert orders and orders[0]["count"] == 44
+        finally:
+            for worker in workers:
+                release_worker(*worker)
+
+    def test_local_snapshot_unchanged(self):
+        metrics = server_metrics()
+        metrics.reset()
+        worker = fork_worker(5, 0)
+        try:
+            assert metrics.snapshot()["total_requests"] == 0
+            assert "workers" not in metrics.snapshot()
+        finally:
+            release_worker(*worker)
+
+    def test_prometheus(self):
+        metrics = server_metrics()
+        metrics.reset()
+        pid, done_w = fork_worker(7, 1)
+        try:
+            text = metrics.render(cluster=True, per_worker=True)
+            assert "hypern_workers 2" in text
+            assert f'hypern_worker_requests_total{{worker="{pid}"}} 8' in text
+            assert f'hypern_worker_errors_total{{worker="{pid}"}} 1' in text
+            assert f'hypern_worker_request_rate{{worker="{pid}",window="1m"}}' in text
+        finally:
+            release_worker(pid, done_w)
+
+    def test_exited_worker_dropped(self):
+        metrics = server_metrics()
+        metrics.reset()
+        release_worker(*fork_worker(9, 0))
+        snapshot = metrics.snapshot(cluster=True)
+        assert snapshot["workers"] == 1
+        assert snapshot["total_requests"] == 0
+
+    def test_standalone_collector(self):
+        with pytest.raises(ValueError):
+            ServerMetrics().snapshot(cluster=True)
+        with pytest.raises(ValueError):
+            ServerMetrics().render(cluster=True)
+        with pytest.raises(ValueError):
+            ServerMetrics().publish()
+
+    def test_server_stats(self):
+        stats = Server().stats()
+        assert stats["cluster_metrics"]["workers"] >= 1
@@ -0,0 +1,190 @@
+"""
+Test cases for request coalescing (singleflight).
+
+Tests cover:
+- Identical concurrent GETs on a ``coalesce=True`` route running the handler once
+- Query parameter order not splitting a flight, different queries running separately
+- Responses setting cookies not shared, error responses propagated to every waiter
+- Sequential requests and other methods never coalesced
+- singleflight_stats() counters
+- SingleflightMiddleware applied to every route with app.use()
+"""
+
+import threading
+import uuid
+from contextlib import contextmanager
+
+import httpx
+
+from hypern._hypern import SingleflightMiddleware
+
+from tests.conftest import server_process
+
+
+# App with the middleware on every route, no per-route flag
+MIDDLEWARE_SCRIPT = """
+import sys
+import time
+sys.path.insert(0, sys.argv[1])
+from hypern import Hypern
+from hypern.middleware import SingleflightMiddleware
+
+app = Hypern()
+app.use(SingleflightMiddleware(max_wait_secs=5.0))
+calls = {"count": 0}
+
+@app.get("/slow")
+def slow(req, res, ctx):
+    calls["count"] += 1
+    time.sleep(0.5)
+    res.json({"call": calls["count"]})
+
+@app.get("/count")
+def count(req, res, ctx):
+    res.json(calls)
+
+@app.get("/health")
+def health(req, res, ctx):
+    res.text("ok")
+
+app.start(host="127.0.0.1", port=int(sys.argv[2]))
+"""
+
+
+@contextmanager
+def middleware_server():
+    with server_process(app_script=MIDDLEWARE_SCRIPT) as server:
+        yield server.base_url
+
+
+def concurrent(method: str, urls: list) -> list:
+    """Send all requests at once, each on its own connection."""
+    results = [None] * len(urls)
+    barrier = threading.Barrier(len(urls))
+
+    def send(i: int, url: str):
+        barrier.wait()
+        results[i] = httpx.request(method, url, timeout=10.0)
+
+    threads = [threading.Thread(target=send, args=(i, url)) for i, url in enumerate(urls)]
+    for t in threads:
+        t.start()
+    for t in threads:
+        t.join()
+    return results
+
+
+def calls(base_url: str, name: str) -> int:
+    return httpx.get(f"{base_url}/coalesce/count/{name}").json()["count"]
+
+
+class TestCoalescing:
+    """Test concurrent identical GETs sharing one handler run."""
+
+    def test_single_invocation(self, base_url):
+        name = uuid.uuid4().hex
+        responses = concurrent("GET", [f"{base_url}/coalesce/slow/{name}"] * 5)
+        assert all(r.status_code == 200 for r in responses)
+        assert {r.json()["call"] for r in responses} == {1}
+        assert len({r.content for r in responses}) == 1
+        assert calls(base_url, name) == 1
+
+    def test_query_order_ignored(self, base_url):
+        name = uuid.uuid4().hex
+        urls = [
+            f"{base_url}/coalesce/slow/{name}?a=1&b=2",
+            f"{base_url}/coalesce/slow/{name}?b=2&a=1",
+        ]
+        responses = concurrent("GET", urls)
+        assert [r.json()["call"] for r in responses] == [1, 1]
+        assert calls(base_url, name) == 1
+
+    def test_different_query_runs_separately(self, base_url):
+        name = uuid.uuid4().hex
+        urls = [f"{base_url}/coalesce/slow/{name}?page=1", f"{base_url}/coalesce/slow/{name}?page=2"]
+        responses = concurrent("GET", urls)
+        assert sorted(r.json()["call"] for r in responses) == [1, 2]
+        assert calls(base_url, name) == 2
+
+    def test_vary_header_runs_separately(self, base_url):
+        name = uuid.uuid4().hex
+        url = f"{base_url}/coalesce/slow/{name}"
+        results = [None, None]
+        barrier = threading.Barrier(2)
+
+        def send(i: int, token: str):
+            barrier.wait()
+            results[i] = httpx.get(url, headers={"Authorization": f"Bearer {token}"}, timeout=10.0)
+
+        threads = [threading.Thread(target=send, args=(i, t)) for i, t in enumerate(["a", "b"])]
+        for t in threads:
+            t.start()
+        for t in threads:
+            t.join()
+        assert sorted(r.json()["call"] for r in results) == [1, 2]
+
+    def test_sequential_not_coalesced(self, base_url):
+        name = uuid.uuid4().hex
+        first = httpx.get(f"{base_url}/coalesce/slow/{name}")
+        second = httpx.get(f"{base_url}/coalesce/slow/{name}")
+        assert [first.json()["call"], second.json()["call"]] == [1, 2]
+
+    def test_post_not_coalesced(self, base_url):
+        name = uuid.uuid4().hex
+        responses = concurrent("POST", [f"{base_url}/coalesce/slow/{name}"] * 2)
+        assert all(r.status_code in (404, 405) for r in responses)
+        assert calls(base_url, name) == 0
+
+
+class TestNotShared:
+    """Test responses that followers must not reuse."""
+
+    def test_set_cookie(self, base_url):
+        name = uuid.uuid4().hex
+        responses = concurrent("GET", [f"{base_url}/coalesce/cookie/{name}"] * 3)
+        assert all(r.status_code == 200 for r in responses)
+        cookies = {r.headers.get("set-cookie") for r in responses}
+        assert len(cookies) == 3
+        assert calls(base_url, name) == 3
+
+
+class TestErrors:
+    """Test error responses reaching every waiter."""
+
+    def test_server_error(self, base_url):
+        name = uuid.uuid4().hex
+        responses = concurrent("GET", [f"{base_url}/coalesce/fail/{name}"] * 3)
+        assert all(r.status_code == 500 for r in responses)
+        assert {r.json()["call"] for r in responses} == {1}
+        assert calls(base_url, name) == 1
+
+
+class TestStats:
+    """Test singleflight_stats()."""
+
+    def test_counters(self, base_url):
+        before = httpx.get(f"{base_url}/coalesce/stats").json()
+        concurrent("GET", [f"{base_url}/coalesce/slow/{uuid.uuid4().hex}"] * 4)
+        after = httpx.get(f"{base_url}/coalesce/stats").json()
+        assert after["leaders"] - before["leaders"] >= 1
+        assert after["coalesced"] - before["coalesced"] >= 3
+        assert after["in_flight"] == 0
+
+    def test_keys(self, base_url):
+        stats = httpx.get(f"{base_url}/coalesce/stats").json()
+        assert set(stats) == {"leaders", "coalesced", "timed_out", "not_shared", "in_flight"}
+
+
+class TestMiddleware:
+    """Test SingleflightMiddleware."""
+
+    def test_construct(self):
+        mw = SingleflightMiddleware(max_wait_secs=2.5, max_body_size=1024, vary_headers=["x-tenant"])
+        assert mw.stats()["leaders"] == 0
+        assert "SingleflightMiddleware" in repr(mw)
+
+    def test_app_use(self):
+        with middleware_server() as base_url:
+            responses = concurrent("GET", [f"{base_url}/slow"] * 4)
+            assert {r.json()["call"] for r in responses} == {1}
+            assert httpx.get(f"{base_url}/count").json()["count"] == 1
@@ -0,0 +1,158 @@
+"""
+Test cases for listening socket options.
+
+Tests cover:
+- Default options read back from the worker's listener
+- backlog, tcp_nodelay and keepalive options applied to the listener
+- reuse_port giving each worker its own listener and serving from all of them
+- Server constructor options and validation
+"""
+
+import shutil
+import subprocess
+import sys
+import time
+from contextlib import contextmanager
+
+import httpx
+import pytest
+
+from hypern._hypern import Server
+
+from tests.conftest import server_process
+
+
+@contextmanager
+def socket_server(*args: str):
+    with server_process(*args) as server:
+        yield server.port, server.base_url
+
+
+def listen_backlogs(port: int) -> list:
+    """Backlog of each listener on ``port``, as reported by ``ss``."""
+    output = subprocess.run(
+        ["ss", "-ltnH", f"sport = :{port}"], capture_output=True, text=True, check=True
+    ).stdout
+    # State Recv-Q Send-Q Local Peer; Send-Q is the backlog for listeners
+    return [int(line.split()[2]) for line in output.splitlines() if line.strip()]
+
+
+def serving_pids(base_url: str, attempts: int = 200) -> set:
+    """PIDs of the workers answering fresh connections."""
+    pids = set()
+    for _ in range(attempts):
+        pids.add(httpx.get(f"{base_url}/socket/options", timeout=5.0).json()["pid"])
+    return pids
+
+
+needs_ss = pytest.mark.skipif(shutil.which("ss") is None, reason="requires ss")
+linux_only = pytest.mark.skipif(not sys.platform.startswith("linux"), reason="Linux only")
+
+
+class TestDefaultOptions:
+    """Test the options on the shared test server's listener."""
+
+    @linux_only
+    def test_defaults_applied(self, client: httpx.Client):
+        options = client.get("/socket/options").json()
+        assert options["reuse_port"] is False
+        assert options["tcp_nodelay"] is True
+        assert options["keepalive"] is True
+        assert options["keepalive_idle"] == 60
+
+
+@linux_only
+class TestCustomOptions:
+    """Test non-default options applied to the listener."""
+
+    def test_keepalive_and_nodelay(self):
+        with socket_server(
+            "--no-tcp-nodelay",
+            "--keepalive-idle", "15",
+            "--keepalive-interval", "5",
+            "--keepalive-count", "4",
+        ) as (_, base_url):
+            options = httpx.get(f"{base_url}/socket/options").json()
+            assert options["tcp_nodelay"] is False
+            assert options["keepalive"] is True
+            assert options["keepalive_idle"] == 15
+            assert options["keepalive_interval"] == 5
+            assert options["keepalive_count"] == 4
+            # Requests are still served with Nagle enabled
+            assert httpx.get(f"{base_url}/health").status_code == 200
+
+    @needs_ss
+    def test_backlog(self):
+        with socket_server("--backlog", "77") as (port, _):
+            assert listen_backlogs(port) == [77]
+
+
+@linux_only
+class TestReusePort:
+    """Test per-worker SO_REUSEPORT listeners."""
+
+    def test_each_worker_serves(self):
+        with socket_server("--workers", "2", "--reuse-port") as (_, base_url):
+            options = httpx.get(f"{base_url}/socket/options").json()
+            assert options["reuse_port"] is True
+            # Wait for the second worker, then expect the kernel to use both
+            deadline = time.time() + 15
+            pids = set()
+            while len(pids) < 2 and time.time() < deadline:
+                pids |= serving_pids(base_url, attempts=50)
+            assert len(pids) == 2
+
+    @needs_ss
+    def test_one_listener_per_worker(self):
+        with socket_server("--workers", "2", "--reuse-port", "--backlog", "64") as (port, base_url):
+            deadline = time.time() + 15
+            while len(listen_backlogs(port)) < 2 and time.time() < deadline:
+                time.sleep(0.1)
+            assert listen_backlogs(port) == [64, 64]
+            assert httpx.get(f"{base_url}/health").status_code == 200
+
+    @needs_ss
+    def test_shared_listener_without_reuse_port(self):
+        with socket_server("--workers", "2") as (port, base_url):
+            assert listen_backlogs(port) == [1024]
+            assert len(serving_pids(base_url, attempts=20)) >= 1
+
+
+class TestServerConfig:
+    """Test Server constructor options."""
+
+    def test_defaults(self):
+        stats = Server().stats()
+        assert stats["backlog"] == 1024
+        assert stats["reuse_port"] is False
+        assert stats["tcp_nodelay"] is True
+        assert stats["keepalive_idle"] == 60
+        assert stats["keepalive_interval"] is None
+        assert stats["keepalive_count"] is None
+
+    def test_custom_options(self):
+        stats = Server(
+            backlog=128,
+            reuse_port=True,
+            tcp_nodelay=False,
+            keepalive_idle=30,
+            keepalive_interval=10,
+            keepalive_count=3,
+        ).stats()
+        assert stats["backlog"] == 128
+        assert stats["reuse_port"] is True
+        assert stats["tcp_nodelay"] is False
+        assert (stats["keepalive_idle"], stats["keepalive_interval"], stats["keepalive_count"]) == (30, 10, 3)
+
+    def test_keepalive_disabled(self):
+        assert Server(keepalive_idle=None).stats()["keepalive_idle"] is None
+
+    def test_invalid_backlog(self):
+        with pytest.raises(ValueError):
+            Server(backlog=0)
+
+    def test_invalid_keepalive(self):
+        with pytest.raises(ValueError):
+            Server(keepalive_idle=0)
+        with pytest.raises(ValueError):
+            Server(keepalive_idle=None, keepalive_count=3)
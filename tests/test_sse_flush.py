@@ -0,0 +1,75 @@
+"""
+Test cases for SSE headers and events being flushed as soon as they exist.
+
+Tests cover:
+- Live stream headers sent while the handler is still running
+- The ``: connected`` comment written on open
+- Events sent right before close() delivered
+- Generator streams sending headers before the first event and each event as yielded
+"""
+
+import time
+
+import httpx
+
+
+def read_lines(client: httpx.Client, url: str, timeout: float = 5.0):
+    """Stream `url`, returning (header_elapsed, [(elapsed, line), ...])."""
+    lines = []
+    start = time.monotonic()
+    with client.stream("GET", url, timeout=timeout) as response:
+        headers_at = time.monotonic() - start
+        assert response.status_code == 200
+        assert response.headers["content-type"].startswith("text/event-stream")
+        for line in response.iter_lines():
+            if line:
+                lines.append((time.monotonic() - start, line))
+    return headers_at, lines
+
+
+class TestLiveStreamHeaders:
+    """Test that sse_live() sends headers immediately."""
+
+    def test_headers_before_handler_returns(self, client: httpx.Client):
+        headers_at, lines = read_lines(client, "/sse/live/late")
+
+        # The handler sleeps 1s before its only event
+        assert headers_at < 0.5
+        assert [line for _, line in lines if line.startswith("data:")] == ["data: done"]
+        assert lines[0][0] >= 0.9
+
+    def test_connected_comment(self, client: httpx.Client):
+        headers_at, lines = read_lines(client, "/sse/live/connected")
+
+        assert headers_at < 0.5
+        assert lines[0][1] == ": connected"
+        assert lines[0][0] < 0.5
+
+
+class TestLiveStreamClose:
+    """Test that closing a stream keeps already-sent events."""
+
+    def test_events_before_close_delivered(self, client: httpx.Client):
+        _, lines = read_lines(client, "/sse/live/send-close")
+        data = [line for _, line in lines if line.startswith("data:")]
+
+        assert data == ["data: 0", "data: 1", "data: 2"]
+
+
+class TestGeneratorStream:
+    """Test that sse_stream() writes events as they are yielded."""
+
+    def test_headers_before_first_event(self, client: httpx.Client):
+        headers_at, lines = read_lines(client, "/sse/generator/slow")
+
+        assert headers_at < 0.3
+        data = [(t, line) for t, line in lines if line.startswith("data:")]
+        assert [line for _, line in data] == ["data: 0", "data: 1", "data: 2"]
+
+    def test_events_spaced_as_yielded(self, client: httpx.Client):
+        _, lines = read_lines(client, "/sse/generator/slow")
+        times = [t for t, line in lines if line.startswith("data:")]
+
+        # One event every 0.4s, not all at the end
+        assert times[0] < 0.8
+        assert times[2] - times[0] >= 0.6
@@ -0,0 +1,84 @@
+"""
+Test cases for the server-owned SSE keepalive task.
+
+Tests cover:
+- Idle live streams receive ``: keepalive`` at the configured cadence
+- Keepalives stop and the response ends after the stream is closed
+- Streams with regular activity are not sent keepalives
+- The active SSE connection gauge
+"""
+
+import time
+
+import httpx
+
+
+def read_stream(client: httpx.Client, url: str, timeout: float = 5.0) -> list:
+    """Read an SSE response to completion, returning (elapsed, line) pairs."""
+    lines = []
+    start = time.monotonic()
+    with client.stream("GET", url, timeout=timeout) as response:
+        assert response.status_code == 200
+        assert response.headers["content-type"].startswith("text/event-stream")
+        for line in response.iter_lines():
+            if line:
+                lines.append((time.monotonic() - start, line))
+    return lines
+
+
+class TestKeepaliveCadence:
+    """Test keepalives on streams that send no events."""
+
+    def test_idle_stream_receives_keepalives(self, client: httpx.Client):
+        lines = read_stream(client, "/sse/live/idle?close_after=1.2")
+        keepalives = [t for t, line in lines if line == ": keepalive"]
+
+        # 0.2s interval over 1.2s, allowing for tick granularity
+        assert 3 <= len(keepalives) <= 7
+        assert keepalives[0] >= 0.15
+        gaps = [b - a for a, b in zip(keepalives, keepalives[1:])]
+        assert all(0.15 <= gap <= 0.5 for gap in gaps)
+
+    def test_keepalives_stop_after_close(self, client: httpx.Client):
+        start = time.monotonic()
+        lines = read_stream(client, "/sse/live/idle?close_after=0.5")
+        elapsed = time.monotonic() - start
+
+        # The response ends promptly once the handler closes the stream
+        assert elapsed < 2.0
+        assert all(t < 0.5 + 0.3 for t, _ in lines)
+
+
+class TestKeepaliveActivity:
+    """Test that activity postpones keepalives."""
+
+    def test_active_stream_skips_keepalives(self, client: httpx.Client):
+        lines = read_stream(client, "/sse/live/events")
+        data = [line for _, line in lines if line.startswith("data:")]
+
+        assert data == [f"data: {i}" for i in range(6)]
+        assert ": keepalive" not in [line for _, line in lines]
+
+
+class TestSseGauge:
+    """Test the active SSE connection gauge."""
+
+    def test_stats_shape(self, client: httpx.Client):
+        stats = client.get("/sse/live/stats").json()
+        assert {"active", "keepalive_registered", "keepalives_sent",
+                "keepalive_interval_secs"} <= set(stats)
+
+    def test_active_gauge_tracks_open_streams(self, client: httpx.Client):
+        before = client.get("/sse/live/stats").json()["active"]
+
+        with client.stream("GET", "/sse/live/idle?close_after=1.0", timeout=5.0) as response:
+            next(response.iter_lines())
+            during = client.get("/sse/live/stats").json()
+            for _ in response.iter_lines():
+                pass
+
+        time.sleep(0.1)
+        after = client.get("/sse/live/stats").json()
+        assert during["active"] >= before + 1
+        assert during["keepalives_sent"] >= 1
+        assert after["active"] == before
@@ -0,0 +1,331 @@
+"""
+Tests for StaticFileHandler SPA fallback and the file cache.
+
+Tests cover:
+- Client-side routes served with the index document
+- Missing assets still returning 404
+- Excluded prefixes never falling back
+- spa=True alone falling back for every missing path
+- Fallback cache headers
+- Least recently served files evicted past the cache budget
+- Files over the per-file limit served but never cached
+- Cache stats per handler and in ServerMetrics
+- Bundles served from memory with the headers and 304s of disk files
+- Overlays winning over disk files, and missing entries falling through to
+  disk, then 404
+- Directory, zip and base64 bundle loaders
+"""
+
+import pytest
+
+from hypern._hypern import StaticFileHandler, server_metrics
+from hypern.static import dump_base64_bundle, load_base64_bundle, load_bundle, load_zip_bundle
+
+HTML_ACCEPT = "text/html,application/xhtml+xml,*/*;q=0.8"
+
+
+# Override autouse conftest fixtures that need a test server
+@pytest.fixture(autouse=True)
+def reset_database():
+    yield
+
+
+@pytest.fixture
+def site(tmp_path):
+    (tmp_path / "index.html").write_text("<html>app</html>")
+    (tmp_path / "app.css").write_text("body {}")
+    return tmp_path
+
+
+@pytest.fixture
+def spa(site):
+    return StaticFileHandler(str(site), prefix="", spa_fallback=True)
+
+
+class TestSpaFallback:
+    """Test SPA fallback resolution."""
+
+    def test_client_route_serves_index(self, spa):
+        body, content_type, _, status = spa.serve_file("/app/user/42", accept=HTML_ACCEPT)
+        assert status == 200
+        assert body == b"<html>app</html>"
+        assert content_type.startswith("text/html")
+
+    def test_extensionless_path_without_accept(self, spa):
+        _, _, _, status = spa.serve_file("/app/user/42")
+        assert status == 200
+
+    def test_missing_asset_is_404(self, spa):
+        with pytest.raises(FileNotFoundError):
+            spa.serve_file("/app/main.js", accept="*/*")
+
+    def test_excluded_prefix_is_404(self, spa):
+        with pytest.raises(FileNotFoundError):
+            spa.serve_file("/api/x", accept=HTML_ACCEPT)
+        with pytest.raises(FileNotFoundError):
+            spa.serve_file("/assets/logo", accept=HTML_ACCEPT)
+
+    def test_custom_exclude_prefixes(self, site):
+        handler = StaticFileHandler(
+            str(site), prefix="", spa_fallback=True, fallback_exclude_prefixes=["/rpc"]
+        )
+        with pytest.raises(FileNotFoundError):
+            handler.serve_file("/rpc/call", accept=HTML_ACCEPT)
+        _, _, _, status = handler.serve_file("/api/x", accept=HTML_ACCEPT)
+        assert status == 200
+
+    def test_non_get_is_404(self, spa):
+        with pytest.raises(FileNotFoundError):
+            spa.serve_file("/app/user/42", method="POST", accept=HTML_ACCEPT)
+        _, _, _, status = spa.serve_file("/app/user/42", method="HEAD", accept=HTML_ACCEPT)
+        assert status == 200
+
+    def test_plain_spa_falls_back_everywhere(self, site):
+        # spa=True keeps answering every missing path with the index
+        handler = StaticFileHandler(str(site), prefix="", spa=True)
+        for path, method in [("/api/x", "GET"), ("/app/main.js", "GET"), ("/app/user/42", "POST")]:
+            body, _, _, status = handler.serve_file(path, method=method, accept="*/*")
+            assert status == 200
+            assert body == b"<html>app</html>"
+
+    def test_spa_with_exclude_prefixes_checks(self, site):
+        handler = StaticFileHandler(str(site), prefix="", spa=True, fallback_exclude_prefixes=["/rpc"])
+        with pytest.raises(FileNotFoundError):
+            handler.serve_file("/rpc/call", accept=HTML_ACCEPT)
+        with pytest.raises(FileNotFoundError):
+            handler.serve_file("/app/main.js", accept="*/*")
+
+    def test_disabled_by_default(self, site):
+        handler = StaticFileHandler(str(site), prefix="")
+        with pytest.raises(FileNotFoundError):
+            handler.serve_file("/app/user/42", accept=HTML_ACCEPT)
+
+    def test_existing_file_is_served(self, spa):
+        body, content_type, _, status = spa.serve_file("/app.css")
+        assert status == 200
+        assert body == b"body {}"
+        assert content_type.startswith("text/css")
+
+
+class TestSpaFallbackHeaders:
+    """Test cache headers for fallback responses."""
+
+    def test_fallback_is_not_cached(self, site):
+        handler = StaticFileHandler(
+            str(site), prefix="", spa_fallback=True, cache_max_age=3600
+        )
+        headers = dict(handler.response_headers("/app/user/42", accept=HTML_ACCEPT))
+        assert "no-cache" in headers["Cache-Control"]
+
+    def test_real_file_uses_max_age(self, site):
+        handler = StaticFileHandler(
+            str(site), prefix="", spa_fallback=True, cache_max_age=3600
+        )
+        headers = dict(handler.response_headers("/app.css"))
+        assert headers["Cache-Control"] == "public, max-age=3600"
+
+    def test_fallback_does_not_shadow_later_file(self, spa, site):
+        _, _, _, status = spa.serve_file("/late.txt", accept=HTML_ACCEPT)
+        assert status == 200
+        (site / "late.txt").write_text("late")
+        body, _, _, status = spa.serve_file("/late.txt", accept=HTML_ACCEPT)
+        assert status == 200
+        assert body == b"late"
+
+
+@pytest.fixture
+def assets(tmp_path):
+    for name in ("a.txt", "b.txt", "c.txt"):
+        (tmp_path / name).write_text(name[0] * 100)
+    (tmp_path / "big.bin").write_bytes(b"x" * 5000)
+    return tmp_path
+
+
+class TestFileCache:
+    """Test the byte-budgeted file cache."""
+
+    def test_oldest_evicted(self, assets):
+        # Each 100-byte file costs a little under 300 bytes with its metadata
+        handler = StaticFileHandler(str(assets), prefix="", max_cache_bytes=600)
+        handler.serve_file("/a.txt")
+        handler.serve_file("/b.txt")
+        assert handler.cached_paths() == ["a.txt", "b.txt"]
+        handler.serve_file("/c.txt")
+        assert handler.cached_paths() == ["b.txt", "c.txt"]
+        stats = handler.cache_stats()
+        assert stats["entries"] == 2
+        assert stats["evictions"] == 1
+        assert 400 < stats["bytes"] <= stats["max_bytes"] == 600
+
+    def test_recently_served_kept(self, assets):
+        handler = StaticFileHandler(str(assets), prefix="", max_cache_bytes=600)
+        handler.serve_file("/a.txt")
+        handler.serve_file("/b.txt")
+        handler.serve_file("/a.txt")
+        handler.serve_file("/c.txt")
+        assert handler.cached_paths() == ["a.txt", "c.txt"]
+
+    def test_oversized_file_not_cached(self, assets):
+        handler = StaticFileHandler(str(assets), prefix="", max_file_cache_bytes=1000)
+        for _ in range(2):
+            body, _, _, status = handler.serve_file("/big.bin")
+            assert status == 200
+            assert body == b"x" * 5000
+        assert handler.cached_paths() == []
+        assert handler.cache_stats()["misses"] == 2
+
+    def test_hits_and_misses(self, assets):
+        handler = StaticFileHandler(str(assets), prefix="")
+        handler.serve_file("/a.txt")
+        handler.serve_file("/a.txt")
+        stats = handler.cache_stats()
+        assert (stats["hits"], stats["misses"], stats["entries"]) == (1, 1, 1)
+        handler.clear_cache_py()
+        assert handler.cache_stats()["bytes"] == 0
+
+    def test_server_metrics(self, assets):
+        handler = StaticFileHandler(str(assets), prefix="/assets-metrics")
+        handler.serve_file("/a.txt")
+        caches = [c for c in server_metrics().static_cache() if c["prefix"] == "/assets-metrics"]
+        assert len(caches) == 1
+        assert caches[0]["entries"] == 1
+        assert 'hypern_static_cache_entries{prefix="/assets-metrics"} 1' in server_metrics().render()
+
+
+BUNDLE = {
+    "index.html": b"<html>bundled</html>",
+    "assets/app.js": b"console.log(1)",
+}
+
+
+class TestBundle:
+    """Test handlers serving files from memory."""
+
+    def test_served_with_headers(self):
+        handler = StaticFileHandler.from_bundle(BUNDLE, prefix="/ui", cache_max_age=60)
+        body, content_type, etag, status = handler.serve_file("/ui/assets/app.js")
+        assert (body, status) == (b"console.log(1)", 200)
+        assert content_type.startswith("application/javascript")
+        headers = dict(handler.response_headers("/ui/assets/app.js"))
+        assert headers["ETag"] == etag
+        assert headers["Cache-Control"] == "public, max-age=60"
+        assert handler.directory is None
+
+    def test_index_for_prefix(self):
+        handler = StaticFileHandler.from_bundle(BUNDLE, prefix="/ui")
+        body, content_type, _, _ = handler.serve_file("/ui/")
+        assert body == b"<html>bundled</html>"
+        assert content_type.startswith("text/html")
+
+    def test_matching_etag_is_304(self):
+        handler = StaticFileHandler.from_bundle(BUNDLE, prefix="")
+        _, _, etag, _ = handler.serve_file("/index.html")
+        body, _, _, status = handler.serve_file("/index.html", if_none_match=etag)
+        assert (body, status) == (b"", 304)
+
+    def test_etag_follows_content(self):
+        first = StaticFileHandler.from_bundle(BUNDLE, prefix="").serve_file("/index.html")[2]
+        again = StaticFileHandler.from_bundle(BUNDLE, prefix="").serve_file("/index.html")[2]
+        changed = StaticFileHandler.from_bundle(
+            {"index.html": b"<html>other</html>"}, prefix=""
+        ).serve_file("/index.html")[2]
+        assert first == again != changed
+
+    def test_missing_entry_is_404(self):
+        handler = StaticFileHandler.from_bundle(BUNDLE, prefix="")
+        with pytest.raises(FileNotFoundError):
+            handler.serve_file("/missing.js")
+
+    def test_spa_fallback(self):
+        handler = StaticFileHandler.from_bundle(BUNDLE, prefix="", spa_fallback=True)
+        body, _, _, _ = handler.serve_file("/settings/profile", accept=HTML_ACCEPT)
+        assert body == b"<html>bundled</html>"
+
+    def test_str_entries(self):
+        handler = StaticFileHandler.from_bundle({"config.js": "window.X = 1;"}, prefix="")
+        assert handler.serve_file("/config.js")[0] == b"window.X = 1;"
+
+    @pytest.mark.parametrize("path", ["../secret", "", "/"])
+    def test_invalid_path(self, path):
+        with pytest.raises(ValueError, match="Bundle paths"):
+            StaticFileHandler.from_bundle({path: b"x"})
+
+    def test_counted_in_cache_budget(self, assets):
+        # The pinned bundle leaves room for only one of the disk files
+        handler = StaticFileHandler.from_bundle(
+            {"pinned.txt": b"p" * 100}, directory=str(assets), prefix="", max_cache_bytes=600
+        )
+        assert handler.cache_stats()["entries"] == 1
+        handler.serve_file("/a.txt")
+        handler.serve_file("/b.txt")
+        handler.serve_file("/pinned.txt")
+        assert handler.cached_paths() == ["b.txt"]
+        stats = handler.cache_stats()
+        assert stats["entries"] == 2
+        assert stats["evictions"] == 1
+        assert stats["bytes"] <= stats["max_bytes"]
+        handler.clear_cache_py()
+        assert handler.cache_stats()["entries"] == 1
+        assert handler.serve_file("/pinned.txt")[0] == b"p" * 100
+
+
+class TestOverlay:
+    """Test in-memory files over a directory."""
+
+    @pytest.fixture
+    def handler(self, site):
+        (site / "config.js").write_text("window.API = 'disk';")
+        return StaticFileHandler(
+            str(site), prefix="", overlay={"config.js": b"window.API = 'generated';"}
+        )
+
+    def test_overlay_wins_over_disk(self, handler):
+        body, content_type, _, _ = handler.serve_file("/config.js")
+        assert body == b"window.API = 'generated';"
+        assert content_type.startswith("application/javascript")
+
+    def test_missing_entry_falls_through_to_disk(self, handler):
+        assert handler.serve_file("/app.css")[0] == b"body {}"
+
+    def test_missing_everywhere_is_404(self, handler):
+        with pytest.raises(FileNotFoundError):
+            handler.serve_file("/nowhere.js")
+
+    def test_from_bundle_with_directory(self, site):
+        handler = StaticFileHandler.from_bundle(
+            {"index.html": b"<html>overlay</html>"}, directory=str(site), prefix=""
+        )
+        assert handler.serve_file("/index.html")[0] == b"<html>overlay</html>"
+        assert handler.serve_file("/app.css")[0] == b"body {}"
+
+
+class TestLoaders:
+    """Test building bundles from a directory and from archives."""
+
+    @pytest.fixture
+    def dist(self, tmp_path):
+        dist = tmp_path / "dist"
+        (dist / "assets").mkdir(parents=True)
+        (dist / "index.html").write_bytes(b"<html></html>")
+        (dist / "assets" / "app.js").write_bytes(b"app()")
+        return dist
+
+    def test_load_bundle(self, dist):
+        assert load_bundle(dist) == {"index.html": b"<html></html>", "assets/app.js": b"app()"}
+
+    def test_load_bundle_missing_directory(self, tmp_path):
+        with pytest.raises(ValueError):
+            load_bundle(tmp_path / "missing")
+
+    def test_base64_round_trip(self, dist):
+        assert load_base64_bundle(dump_base64_bundle(dist)) == load_bundle(dist)
+
+    def test_zip_bundle(self, dist, tmp_path):
+        import base64
+
+        archive = base64.b64decode(dump_base64_bundle(dist))
+        assert load_zip_bundle(archive) == load_bundle(dist)
+        path = tmp_path / "ui.zip"
+        path.write_bytes(archive)
+        handler = StaticFileHandler.from_bundle(load_zip_bundle(path), prefix="")
+        assert handler.serve_file("/assets/app.js")[0] == b"app()"
@@ -0,0 +1,245 @@
+"""
+Test cases for flow control on live streaming responses.
+
+Tests cover:
+- A client that stops reading holding write_async() and drain() back
+- Buffered counts going back to zero once the body is sent
+- Every chunk arriving, in order, once the client catches up
+- Counts reset when the client leaves mid-stream
+- Watermark defaults, validation and set_write_buffer_limits()
+"""
+
+import socket
+import time
+from contextlib import contextmanager
+
+import httpx
+import pytest
+
+from hypern._hypern import StreamingResponse
+
+from tests.conftest import server_process
+
+
+CHUNK = 64 * 1024
+# Well past what the kernel's socket buffers can hold for a stalled client
+COUNT = 256
+HIGH = 256 * 1024
+
+APP_SCRIPT = """
+import sys
+sys.path.insert(0, sys.argv[1])
+from hypern import Hypern
+
+CHUNK = 64 * 1024
+COUNT = 256
+HIGH = 256 * 1024
+
+app = Hypern()
+stats = {}
+
+
+def chunk(i):
+    return i.to_bytes(4, "big") * (CHUNK // 4)
+
+
+def track(stream):
+    stats.clear()
+    stats.update(stream=stream, written=0, peak=0, draining=False, done=False)
+
+
+@app.get("/write-async")
+async def write_async(req, res, ctx):
+    stream = res.stream_live(high_watermark=HIGH)
+    track(stream)
+    for i in range(COUNT):
+        if not await stream.write_async(chunk(i)):
+            break
+        stats["written"] += 1
+        stats["peak"] = max(stats["peak"], stream.buffered_bytes())
+    stream.close()
+    stats["done"] = True
+
+
+@app.get("/drain")
+async def drain(req, res, ctx):
+    stream = res.stream_live(buffer_size=COUNT, high_watermark=HIGH)
+    track(stream)
+    for i in range(COUNT):
+        assert stream.write(chunk(i))
+        stats["written"] += 1
+        stats["peak"] = max(stats["peak"], stream.buffered_bytes())
+        if stream.buffered_bytes() > stream.high_watermark:
+            stats["draining"] = True
+            await stream.drain()
+            stats["draining"] = False
+            assert stream.buffered_bytes() <= stream.low_watermark
+    stream.close()
+    stats["done"] = True
+
+
+@app.get("/stats")
+def get_stats(req, res, ctx):
+    stream = stats["stream"]
+    return {
+        "written": stats["written"],
+        "peak": stats["peak"],
+        "draining": stats["draining"],
+        "done": stats["done"],
+        "buffered_bytes": stream.buffered_bytes(),
+        "buffered_chunks": stream.buffered_chunks(),
+    }
+
+
+app.start(host="127.0.0.1", port=int(sys.argv[2]), num_processes=1)
+"""
+
+
+@contextmanager
+def flow_server():
+    with server_process(app_script=APP_SCRIPT, ready_path="/_health/live") as server:
+        yield server.port, server.base_url
+
+
+def throttled_request(port: int, path: str) -> socket.socket:
+    """Send a request from a socket with a tiny receive buffer, reading nothing"""
+    sock = socket.socket()
+    sock.setsockopt(socket.SOL_SOCKET, socket.SO_RCVBUF, 4096)
+    sock.connect(("127.0.0.1", port))
+    sock.sendall(f"GET {path} HTTP/1.1\r\nHost: test\r\nConnection: close\r\n\r\n".encode())
+    return sock
+
+
+def read_body(sock: socket.socket) -> bytes:
+    """Read the response to its end and undo the chunked framing"""
+    sock.settimeout(30)
+    data = bytearray()
+    while True:
+        received = sock.recv(1 << 20)
+        if not received:
+            break
+        data.extend(received)
+    head, _, rest = bytes(data).partition(b"\r\n\r\n")
+    assert head.startswith(b"HTTP/1.1 200"), head
+    assert b"transfer-encoding: chunked" in head.lower()
+    body = bytearray()
+    while True:
+        size_line, _, rest = rest.partition(b"\r\n")
+        size = int(size_line, 16)
+        if size == 0:
+            return bytes(body)
+        body.extend(rest[:size])
+        rest = rest[size + 2:]
+
+
+def expected_body() -> bytes:
+    return b"".join(i.to_bytes(4, "big") * (CHUNK // 4) for i in range(COUNT))
+
+
+def stalled_stats(base_url: str) -> dict:
+    """Stats once the producer has stopped making progress"""
+    previous = None
+    deadline = time.time() + 10
+    while time.time() < deadline:
+        time.sleep(0.5)
+        stats = httpx.get(f"{base_url}/stats").json()
+        if previous is not None and stats["written"] == previous["written"]:
+            return stats
+        previous = stats
+    raise AssertionError(f"producer never stalled: {previous}")
+
+
+def finished_stats(base_url: str) -> dict:
+    deadline = time.time() + 10
+    while time.time() < deadline:
+        stats = httpx.get(f"{base_url}/stats").json()
+        if stats["done"]:
+            return stats
+        time.sleep(0.05)
+    raise AssertionError("producer never finished")
+
+
+class TestThrottledClient:
+    """Test a client that reads slower than the handler writes."""
+
+    @pytest.mark.parametrize("path", ["/write-async", "/drain"])
+    def test_producer_waits_then_catches_up(self, path):
+        with flow_server() as (port, base_url):
+            sock = throttled_request(port, path)
+            try:
+                stalled = stalled_stats(base_url)
+                assert not stalled["done"]
+                assert stalled["written"] < COUNT
+                # Held at the high watermark, not dropping chunks
+                assert stalled["buffered_bytes"] > HIGH // 4
+                assert stalled["peak"] <= HIGH + CHUNK
+                if path == "/drain":
+                    assert stalled["draining"] is True
+
+                body = read_body(sock)
+            finally:
+                sock.close()
+            assert len(body) == COUNT * CHUNK
+            assert body == expected_body()
+
+            finished = finished_stats(base_url)
+            assert finished["written"] == COUNT
+            assert finished["buffered_bytes"] == 0
+            assert finished["buffered_chunks"] == 0
+
+    def test_fast_client_not_held_back(self):
+        with flow_server() as (_, base_url):
+            start = time.monotonic()
+            response = httpx.get(f"{base_url}/write-async", timeout=30.0)
+            assert response.content == expected_body()
+            assert time.monotonic() - start < 10
+            stats = finished_stats(base_url)
+            assert stats["buffered_bytes"] == 0
+            assert stats["buffered_chunks"] == 0
+
+    def test_client_leaving_resets_counts(self):
+        with flow_server() as (port, base_url):
+            sock = throttled_request(port, "/write-async")
+            assert stalled_stats(base_url)["buffered_bytes"] > 0
+            sock.close()
+            # Queued chunks are discarded; the waiting handler is cancelled
+            # like any async handler whose client left
+            deadline = time.time() + 10
+            while time.time() < deadline:
+                stats = httpx.get(f"{base_url}/stats").json()
+                if stats["buffered_bytes"] == 0:
+                    break
+                time.sleep(0.05)
+            assert stats["buffered_bytes"] == 0
+            assert stats["buffered_chunks"] == 0
+            assert stats["written"] < COUNT
+            assert httpx.get(f"{base_url}/_health/live").status_code == 200
+
+
+class TestWatermarks:
+    """Test the watermark settings."""
+
+    def test_defaults(self):
+        stream = StreamingResponse()
+        assert stream.high_watermark == 65536
+        assert stream.low_watermark == 16384
+        assert stream.buffered_bytes() == 0
+        assert stream.buffered_chunks() == 0
+
+    def test_low_defaults_to_quarter(self):
+        stream = StreamingResponse(high_watermark=1000)
+        assert stream.low_watermark == 250
+
+    def test_set_write_buffer_limits(self):
+        stream = StreamingResponse()
+        stream.set_write_buffer_limits(high=4096, low=1024)
+        assert (stream.high_watermark, stream.low_watermark) == (4096, 1024)
+        stream.set_write_buffer_limits(high=100)
+        assert stream.low_watermark == 25
+
+    @pytest.mark.parametrize("high, low", [(100, 200), (0, 1)])
+    def test_low_above_high_rejected(self, high, low):
+        with pytest.raises(ValueError):
+            StreamingResponse(high_watermark=high, low_watermark=low)
+        with pytest.raises(ValueError):
+            StreamingResponse().set_write_buffer_limits(high=high, low=low)
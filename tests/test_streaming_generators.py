@@ -0,0 +1,95 @@
+"""
+Test cases for streaming responses from generator handlers.
+
+Tests cover:
+- Async generator handlers streaming many chunks in order
+- Sync generators returned via stream() with a content type
+- Async generators wrapped with stream()
+- Generators raising halfway produce a truncated body, not a hang
+- The stream() helper and return-value detection
+"""
+
+import time
+
+import httpx
+import pytest
+
+from hypern.streaming import Stream, is_stream_result, stream
+
+
+class TestGeneratorStreaming:
+    """Test bodies streamed from generators."""
+
+    def test_async_generator_chunks_in_order(self, client: httpx.Client):
+        response = client.get("/stream/async")
+        assert response.status_code == 200
+        assert response.headers.get("transfer-encoding") == "chunked"
+        lines = response.text.splitlines()
+        assert lines == [f"{i:04d}" for i in range(1000)]
+
+    def test_sync_generator_with_content_type(self, client: httpx.Client):
+        response = client.get("/stream/sync")
+        assert response.status_code == 200
+        assert response.headers["content-type"] == "text/plain; charset=utf-8"
+        assert response.text == "".join(f"{i:03d}\n" for i in range(100))
+
+    def test_wrapped_async_generator(self, client: httpx.Client):
+        response = client.get("/stream/ndjson")
+        assert response.headers["content-type"] == "application/x-ndjson"
+        rows = [line for line in response.text.splitlines() if line]
+        assert rows == [f'{{"row": {i}}}' for i in range(5)]
+
+    def test_default_content_type(self, client: httpx.Client):
+        response = client.get("/stream/async")
+        assert response.headers["content-type"] == "application/octet-stream"
+
+
+class TestGeneratorErrors:
+    """Test generators that raise after streaming has started."""
+
+    @pytest.mark.parametrize("path", ["/stream/raises", "/stream/async-raises"])
+    def test_raise_truncates_body(self, client: httpx.Client, path):
+        received = b""
+        start = time.monotonic()
+        with pytest.raises(httpx.RemoteProtocolError):
+            with client.stream("GET", path, timeout=5.0) as response:
+                assert response.status_code == 200
+                for chunk in response.iter_raw():
+                    received += chunk
+
+        # Only chunks from before the failure can arrive, and no hang
+        assert time.monotonic() - start < 3.0
+        assert "".join(f"{i:04d}\n" for i in range(250)).encode().startswith(received)
+
+    def test_server_healthy_after_failed_stream(self, client: httpx.Client):
+        with pytest.raises(httpx.RemoteProtocolError):
+            client.get("/stream/raises")
+        assert client.get("/health").status_code == 200
+
+
+class TestStreamHelper:
+    """Test the stream() helper and return-value detection."""
+
+    def test_stream_wraps_source(self):
+        source = iter([b"a"])
+        wrapped = stream(source, content_type="text/csv", buffer_size=4)
+        assert isinstance(wrapped, Stream)
+        assert wrapped.source is source
+        assert wrapped.content_type == "text/csv"
+        assert wrapped.buffer_size == 4
+
+    def test_detects_generators(self):
+        def gen():
+            yield b""
+
+        async def agen():
+            yield b""
+
+        assert is_stream_result(gen())
+        assert is_stream_result(agen())
+        assert is_stream_result(stream([b"x"]))
+
+    def test_ignores_plain_values(self):
+        assert not is_stream_result(None)
+        assert not is_stream_result({"a": 1})
+        assert not is_stream_result([b"a", b"b"])
@@ -0,0 +1,290 @@
+"""
+Test cases for the in-process TestClient.
+
+Tests cover:
+- Routing, path and query parameters, and 404s
+- JSON, form, raw and multipart request bodies
+- Error handlers and unhandled exceptions answered with 500
+- Rust middleware short-circuits and response headers
+- Per-route limits (body size, timeout, cache_ttl)
+- Sync and async generator bodies collected into bytes
+- Health probes, the client timeout and argument validation
+- Clients of different apps in one process
+"""
+
+import base64
+import time
+
+import pytest
+
+from hypern import BasicAuthMiddleware, Hypern, SecurityHeadersMiddleware, TestClient, TestResponse
+
+
+class NotFound(Exception):
+    pass
+
+
+def build_app() -> Hypern:
+    app = Hypern()
+
+    @app.get("/users/:id")
+    def user(req, res, ctx):
+        res.json({"id": req.param("id"), "page": req.query("page")})
+
+    @app.post("/echo")
+    async def echo(req, res, ctx):
+        res.json({"body": req.json(), "content_type": req.header("content-type")})
+
+    @app.post("/form")
+    def form(req, res, ctx):
+        data = req.form()
+        res.json({"a": data.get("a"), "b": data.get_list("b", False)})
+
+    @app.put("/raw")
+    def raw(req, res, ctx):
+        res.send(req.body_bytes())
+
+    @app.post("/upload")
+    def upload(req, res, ctx):
+        data = req.form()
+        res.json({
+            "field": data.get("kind"),
+            "files": [[f.name, f.filename, f.content_type, f.read_text()] for f in sorted(req.files(), key=lambda f: f.name)],
+        })
+
+    @app.get("/missing")
+    def missing(req, res, ctx):
+        raise NotFound("no such thing")
+
+    @app.errorhandler(NotFound)
+    def handle_not_found(req, res, error):
+        res.status(404).json({"error": str(error)})
+
+    @app.get("/boom")
+    def boom(req, res, ctx):
+        raise RuntimeError("exploded")
+
+    @app.post("/small", max_body_size=16)
+    def small(req, res, ctx):
+        res.text("ok")
+
+    @app.get("/slow", timeout="50ms")
+    def slow(req, res, ctx):
+        time.sleep(0.3)
+        res.text("late")
+
+    @app.get("/sleepy")
+    def sleepy(req, res, ctx):
+        time.sleep(0.5)
+        res.text("awake")
+
+    @app.get("/cached", cache_ttl=60)
+    def cached(req, res, ctx):
+        res.text("cached")
+
+    @app.get("/chunks")
+    def chunks(req, res, ctx):
+        def body():
+            for i in range(3):
+                yield f"chunk{i};"
+        return body()
+
+    @app.get("/async-chunks")
+    async def async_chunks(req, res, ctx):
+        async def body():
+            for i in range(3):
+                yield f"async{i};".encode()
+        return body()
+
+    @app.get("/cookies")
+    def cookies(req, res, ctx):
+        res.header("Set-Cookie", "a=1").header("Set-Cookie", "b=2").text("ok")
+
+    return app
+
+
+@pytest.fixture(scope="module")
+def client() -> TestClient:
+    return build_app().test_client()
+
+
+@pytest.fixture(scope="module")
+def secured() -> TestClient:
+    app = build_app()
+    app.use(BasicAuthMiddleware(users={"ada": "secret"}))
+    app.use(SecurityHeadersMiddleware())
+    return app.test_client()
+
+
+class TestRouting:
+    """Test requests reaching handlers."""
+
+    def test_path_and_query(self, client):
+        response = client.get("/users/42", query={"page": 3})
+        assert isinstance(response, TestResponse)
+        assert response.status == 200
+        assert response.json() == {"id": "42", "page": "3"}
+        assert response.headers["content-type"] == "application/json"
+
+    def test_query_in_path_combined(self, client):
+        assert client.get("/users/1?page=2", query={"x": "y"}).json()["page"] == "2"
+
+    def test_not_found(self, client):
+        response = client.get("/nowhere")
+        assert response.status == 404
+        assert response.text == "Not Found"
+
+    def test_repr(self, client):
+        assert repr(client.get("/nowhere")) == "<TestResponse [404]>"
+
+
+class TestRequestBodies:
+    """Test json, data and files."""
+
+    def test_json(self, client):
+        response = client.post("/echo", json={"items": [1, 2], "ok": True})
+        assert response.json() == {
+            "body": {"items": [1, 2], "ok": True},
+            "content_type": "application/json",
+        }
+
+    def test_form(self, client):
+        response = client.post("/form", data={"a": "1", "b": ["x", "y"]})
+        assert response.json() == {"a": "1", "b": ["x", "y"]}
+
+    def test_raw_bytes_and_text(self, client):
+        assert client.put("/raw", data=b"\x00\x01raw").content == b"\x00\x01raw"
+        assert client.put("/raw", data="text body").text == "text body"
+
+    def test_files(self, client):
+        response = client.post(
+            "/upload",
+            data={"kind": "docs"},
+            files={"doc": ("a.txt", b"hello", "text/plain"), "blob": b"bytes"},
+        )
+        assert response.json() == {
+            "field": "docs",
+            "files": [
+                ["blob", "blob", "application/octet-stream", "bytes"],
+                ["doc", "a.txt", "text/plain", "hello"],
+            ],
+        }
+
+    def test_json_with_data_rejected(self, client):
+        with pytest.raises(ValueError, match="not both"):
+            client.post("/echo", json={}, data=b"x")
+
+
+class TestErrors:
+    """Test error handling in the pipeline."""
+
+    def test_error_handler(self, client):
+        response = client.get("/missing")
+        assert response.status == 404
+        assert response.json() == {"error": "no such thing"}
+
+    def test_unhandled_exception(self, client):
+        response = client.get("/boom")
+        assert response.status == 500
+        assert response.json()["detail"] == "exploded"
+
+
+class TestMiddleware:
+    """Test Rust middleware running around handlers."""
+
+    def test_short_circuit(self, secured):
+        response = secured.get("/users/1")
+        assert response.status == 401
+        assert response.headers["www-authenticate"].startswith("Basic")
+
+    def test_passes_with_credentials(self, secured):
+        token = base64.b64encode(b"ada:secret").decode()
+        response = secured.get("/users/1", headers={"Authorization": f"Basic {token}"})
+        assert response.status == 200
+        assert response.headers["x-content-type-options"] == "nosniff"
+
+
+class TestRouteConfig:
+    """Test per-route limits."""
+
+    def test_body_limit(self, client):
+        assert client.post("/small", data=b"x" * 16).status == 200
+        response = client.post("/small", data=b"x" * 17)
+        assert response.status == 413
+        assert response.text == "Request body exceeds 16 bytes"
+
+    def test_route_timeout(self, client):
+        response = client.get("/slow")
+        assert response.status == 504
+        assert response.text.startswith("Handler did not respond within")
+
+    def test_cache_ttl(self, client):
+        assert client.get("/cached").headers["cache-control"] == "max-age=60"
+
+
+class TestResponses:
+    """Test how responses are collected."""
+
+    def test_sync_generator(self, client):
+        response = client.get("/chunks")
+        assert response.status == 200
+        assert response.content == b"chunk0;chunk1;chunk2;"
+
+    def test_async_generator(self, client):
+        assert client.get("/async-chunks").text == "async0;async1;async2;"
+
+    def test_repeated_headers(self, client):
+        response = client.get("/cookies")
+        assert response.header_values("set-cookie") == ["a=1", "b=2"]
+        assert response.headers["set-cookie"] == "a=1, b=2"
+
+    def test_invalid_json(self, client):
+        with pytest.raises(ValueError):
+            client.get("/cookies").json()
+
+    def test_health_probe(self, client):
+        response = client.get("/_health/live")
+        assert response.status == 200
+        assert response.json()["live"] is True
+
+
+class TestClientOptions:
+    """Test client settings and validation."""
+
+    def test_timeout(self):
+        client = build_app().test_client(timeout=0.1)
+        with pytest.raises(TimeoutError):
+            client.get("/sleepy")
+
+    def test_invalid_timeout(self):
+        with pytest.raises(ValueError, match="positive"):
+            build_app().test_client(timeout=0)
+
+    def test_relative_path_rejected(self, client):
+        with pytest.raises(ValueError, match="must start with '/'"):
+            client.get("users/1")
+
+    def test_startup_handlers_run(self):
+        app = build_app()
+        started = []
+        app.on_startup(lambda: started.append(True))
+        app.test_client()
+        assert started == [True]
+
+    def test_server_options(self):
+        client = build_app().test_client(allowed_hosts=["example.com"])
+        assert client.get("/users/1").status == 400
+        assert client.get("/users/1", headers={"Host": "example.com"}).status == 200
+
+    def test_clients_of_different_apps(self, client):
+        other = Hypern()
+
+        @other.get("/users/:id")
+        def other_user(req, res, ctx):
+            res.text("other app")
+
+        other_client = other.test_client(allowed_hosts=["example.com"])
+        assert other_client.get("/users/1", headers={"Host": "example.com"}).text == "other app"
+        # Each client runs with its own handlers and settings
+        assert client.get("/users/1").json()["id"] == "1"
+        assert other_client.get("/users/1").status == 400
@@ -0,0 +1,109 @@
+"""
+Test cases for typed route parameters.
+
+Tests cover:
+- int, float, uuid, date and str constraints converted by param_typed()
+- Values that do not convert falling through to 404
+- param() still returning the raw string
+- params_typed() on a route mixing constrained and unconstrained parameters
+- Unknown constraint types rejected when the route is created
+"""
+
+import pytest
+import httpx
+
+from hypern import Route
+
+
+class TestIntParam:
+    """Test {name:int} parameters."""
+
+    def test_converted(self, client: httpx.Client):
+        response = client.get("/typed/int/42")
+        assert response.status_code == 200
+        assert response.json() == {"type": "int", "value": "42", "raw": "42"}
+
+    def test_negative(self, client: httpx.Client):
+        assert client.get("/typed/int/-7").json()["value"] == "-7"
+
+    @pytest.mark.parametrize("value", ["abc", "4.2", "99999999999999999999"])
+    def test_invalid_is_not_found(self, client: httpx.Client, value):
+        assert client.get(f"/typed/int/{value}").status_code == 404
+
+
+class TestFloatParam:
+    """Test {name:float} parameters."""
+
+    def test_converted(self, client: httpx.Client):
+        data = client.get("/typed/float/2.5").json()
+        assert data["type"] == "float"
+        assert data["value"] == "2.5"
+
+    def test_integer_literal(self, client: httpx.Client):
+        assert client.get("/typed/float/3").json()["value"] == "3.0"
+
+    @pytest.mark.parametrize("value", ["abc", "inf", "nan"])
+    def test_invalid_is_not_found(self, client: httpx.Client, value):
+        assert client.get(f"/typed/float/{value}").status_code == 404
+
+
+class TestUuidParam:
+    """Test {name:uuid} parameters."""
+
+    def test_converted(self, client: httpx.Client):
+        uid = "3f2504e0-4f89-11d3-9a0c-0305e82c3301"
+        data = client.get(f"/typed/uuid/{uid}").json()
+        assert data["type"] == "UUID"
+        assert data["value"] == uid
+
+    def test_uppercase_normalized(self, client: httpx.Client):
+        uid = "3F2504E0-4F89-11D3-9A0C-0305E82C3301"
+        data = client.get(f"/typed/uuid/{uid}").json()
+        assert data["value"] == uid.lower()
+        assert data["raw"] == uid
+
+    def test_invalid_is_not_found(self, client: httpx.Client):
+        assert client.get("/typed/uuid/not-a-uuid").status_code == 404
+
+
+class TestDateParam:
+    """Test {name:date} parameters."""
+
+    def test_converted(self, client: httpx.Client):
+        data = client.get("/typed/date/2024-02-29").json()
+        assert data["type"] == "date"
+        assert data["value"] == "2024-02-29"
+        assert data["weekday"] == 4
+
+    @pytest.mark.parametrize("value", ["2023-02-29", "2024-13-01", "20240101", "today"])
+    def test_invalid_is_not_found(self, client: httpx.Client, value):
+        assert client.get(f"/typed/date/{value}").status_code == 404
+
+
+class TestStrParam:
+    """Test {name:str} parameters."""
+
+    def test_unchanged(self, client: httpx.Client):
+        assert client.get("/typed/str/hello").json() == {"type": "str", "value": "hello", "raw": "hello"}
+
+
+class TestMixedParams:
+    """Test a route with constrained and unconstrained parameters."""
+
+    def test_params_typed(self, client: httpx.Client):
+        data = client.get("/typed/mixed/7/widget").json()
+        assert data["id"] == {"type": "int", "value": "7", "raw": "7"}
+        assert data["slug"] == {"type": "str", "value": "widget", "raw": "widget"}
+        assert data["types"] == {"id": "int", "slug": "str"}
+        assert data["missing"] is None
+
+    def test_invalid_constrained_param(self, client: httpx.Client):
+        assert client.get("/typed/mixed/seven/widget").status_code == 404
+
+
+class TestRouteValidation:
+    """Test constraint validation when routes are created."""
+
+    def test_unknown_type_rejected(self):
+        with pytest.raises(ValueError, match="bogus"):
+            Route("/items/{id:bogus}", lambda req, res, ctx: None, "GET")
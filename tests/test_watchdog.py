@@ -0,0 +1,182 @@
+"""
+Test cases for the stuck-handler watchdog.
+
+Tests cover:
+- A handler blocked forever failing readiness within the threshold
+- The ERROR log naming the stuck path, with Python thread stacks
+- Slow handlers not tripping it while other requests complete
+- watchdog_restart replacing the stuck worker
+- ReloadConfig defaults
+"""
+
+import threading
+import time
+from contextlib import contextmanager
+
+import httpx
+import pytest
+
+from hypern._hypern import ReloadConfig
+
+from tests.conftest import server_process
+
+
+# argv[3] is watchdog_stuck_ms, argv[4] "1" to enable watchdog_restart
+APP_SCRIPT = """
+import os
+import sys
+import threading
+import time
+sys.path.insert(0, sys.argv[1])
+from hypern import Hypern
+
+app = Hypern()
+app.setup_reload(
+    startup_grace_secs=0,
+    watchdog_stuck_ms=int(sys.argv[3]),
+    watchdog_dump_stacks=True,
+    watchdog_restart=sys.argv[4] == "1",
+    watchdog_restart_grace_ms=500,
+)
+lock = threading.Lock()
+
+@app.get("/deadlock")
+def deadlock_handler(req, res, ctx):
+    # Acquires a lock it already holds and never returns
+    lock.acquire()
+    lock.acquire()
+
+@app.get("/slow")
+def slow(req, res, ctx):
+    time.sleep(float(req.query("secs")))
+    return "done"
+
+@app.get("/fast")
+def fast(req, res, ctx):
+    return {"ok": True}
+
+@app.get("/pid")
+def pid(req, res, ctx):
+    return {"pid": os.getpid()}
+
+app.start(host="127.0.0.1", port=int(sys.argv[2]), num_processes=1)
+"""
+
+
+@contextmanager
+def watchdog_server(stuck_ms: int = 1000, restart: bool = False):
+    with server_process(
+        str(stuck_ms), "1" if restart else "0", app_script=APP_SCRIPT, wait=False
+    ) as server:
+        if not server.wait_ready("/_health/ready", 200):
+            raise AssertionError("server never became ready")
+        yield server.base_url, server.logs
+
+
+def start_deadlock(base_url: str) -> None:
+    """Send a request to the deadlocking handler without waiting for it"""
+    try:
+        httpx.get(f"{base_url}/deadlock", timeout=0.3)
+    except httpx.TimeoutException:
+        pass
+
+
+def wait_for_status(base_url: str, status: int, timeout: float) -> float:
+    """Seconds until readiness answers ``status``"""
+    started = time.time()
+    while time.time() - started < timeout:
+        try:
+            if httpx.get(f"{base_url}/_health/ready", timeout=1.0).status_code == status:
+                return time.time() - started
+        except httpx.TransportError:
+            pass
+        time.sleep(0.05)
+    raise AssertionError(f"readiness never answered {status}")
+
+
+def wait_for_log(logs, marker: str, timeout: float = 5.0) -> str:
+    deadline = time.time() + timeout
+    while time.time() < deadline:
+        if marker in logs():
+            return logs()
+        time.sleep(0.1)
+    raise AssertionError(f"no log containing {marker!r} in:\n{logs()}")
+
+
+class TestStuckHandler:
+    """Test a handler that never returns."""
+
+    def test_readiness_fails_within_threshold(self):
+        with watchdog_server(stuck_ms=1000) as (base_url, logs):
+            started = time.time()
+            start_deadlock(base_url)
+            wait_for_status(base_url, 503, timeout=5.0)
+            # Threshold plus one monitor tick, with scheduling slack
+            assert time.time() - started < 2.5
+            assert httpx.get(f"{base_url}/_health").json()["status"] == "unhealthy"
+
+    def test_log_names_stuck_path(self):
+        with watchdog_server(stuck_ms=1000) as (base_url, logs):
+            start_deadlock(base_url)
+            wait_for_status(base_url, 503, timeout=5.0)
+            output = wait_for_log(logs, "stuck request(s)")
+            line = next(line for line in output.splitlines() if "stuck request(s)" in line)
+            assert "ERROR" in line
+            assert "GET /deadlock (" in line
+
+    def test_stacks_dumped(self):
+        with watchdog_server(stuck_ms=1000) as (base_url, logs):
+            start_deadlock(base_url)
+            output = wait_for_log(logs, "Python thread stacks")
+            assert "deadlock_handler" in output
+            assert "lock.acquire()" in output
+
+
+class TestSlowWorkload:
+    """Test slow handlers while the worker keeps completing requests."""
+
+    def test_slow_handler_does_not_trip(self):
+        with watchdog_server(stuck_ms=500) as (base_url, logs):
+            slow = threading.Thread(
+                target=lambda: httpx.get(f"{base_url}/slow?secs=2", timeout=10.0)
+            )
+            slow.start()
+            statuses = set()
+            while slow.is_alive():
+                assert httpx.get(f"{base_url}/fast").status_code == 200
+                statuses.add(httpx.get(f"{base_url}/_health/ready").status_code)
+                time.sleep(0.1)
+            slow.join()
+            assert statuses == {200}
+            assert "watchdog" not in logs()
+
+
+class TestRestart:
+    """Test watchdog_restart."""
+
+    def test_stuck_worker_replaced(self):
+        with watchdog_server(stuck_ms=500, restart=True) as (base_url, logs):
+            first = httpx.get(f"{base_url}/pid").json()["pid"]
+            start_deadlock(base_url)
+            wait_for_log(logs, "starting a replacement", timeout=10.0)
+            wait_for_status(base_url, 200, timeout=10.0)
+            assert httpx.get(f"{base_url}/pid").json()["pid"] != first
+            assert httpx.get(f"{base_url}/fast").json() == {"ok": True}
+
+
+class TestConfig:
+    """Test the ReloadConfig fields."""
+
+    def test_defaults(self):
+        config = ReloadConfig()
+        assert config.watchdog_stuck_ms is None
+        assert config.watchdog_idle_ms is None
+        assert config.watchdog_dump_stacks is False
+        assert config.watchdog_restart is False
+        assert config.watchdog_restart_grace_ms == 5000
+
+    @pytest.mark.parametrize("idle_ms", [None, 250])
+    def test_values(self, idle_ms):
+        config = ReloadConfig(watchdog_stuck_ms=1000, watchdog_idle_ms=idle_ms)
+        assert config.watchdog_stuck_ms == 1000
+        assert config.watchdog_idle_ms == idle_ms
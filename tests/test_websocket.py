@@ -2,11 +2,14 @@
 Tests for Hypern WebSocket module.
 
 These are unit tests that exercise the Python-side WebSocket abstraction
-(queues, rooms, router) without requiring a live server.
+(queues, rooms, router, inbound limits) without requiring a live server.
+Inbound limits are tested by feeding raw frames as the transport would.
 """
 
 import asyncio
 import json
+import time
+import zlib
 import pytest
 
 from hypern.websocket import (
@@ -18,7 +21,9 @@ from hypern.websocket import (
     WebSocketRoom,
     WebSocketRoute,
     WebSocketRouter,
+    WsLimits,
 )
+from hypern._hypern import WsInboundGuard, WsMessageType, websocket_inbound_stats
 
 
 # Override autouse fixtures from conftest that require the test server
@@ -422,3 +427,305 @@ class TestWebSocketRouter:
         assert len(routes) == 3
         paths = {r.path for r in routes}
         assert paths == {"/ws/a", "/ws/b", "/ws/c"}
+
+
+# ============================================================================
+# Inbound Limit Tests
+# ============================================================================
+
+TEXT, BINARY, CONTINUATION, CLOSE, PING = 0x1, 0x2, 0x0, 0x8, 0x9
+
+
+def deflate(data: bytes) -> bytes:
+    """Compress one message as a permessage-deflate client does."""
+    compressor = zlib.compressobj(wbits=-15)
+    out = compressor.compress(data) + compressor.flush(zlib.Z_SYNC_FLUSH)
+    assert out.endswith(b"\x00\x00\xff\xff")
+    return out[:-4]
+
+
+def close_of(message) -> tuple:
+    assert message.msg_type == WsMessageType.Close
+    data = bytes(message.data)
+    return int.from_bytes(data[:2], "big"), data[2:].decode()
+
+
+def closed(reason: str) -> int:
+    return websocket_inbound_stats()["closed"][reason]
+
+
+class TestInboundLimits:
+    """Test WsInboundGuard against raw frames."""
+
+    def test_message_passes(self):
+        guard = WsInboundGuard(WsLimits())
+        message = guard.feed(TEXT, "héllo".encode())
+        assert message.msg_type == WsMessageType.Text
+        assert message.text() == "héllo"
+        assert guard.close_code is None
+
+    def test_oversized_message_1009(self):
+        before = closed("message_too_big")
+        guard = WsInboundGuard(WsLimits(max_message_size=10))
+        assert guard.feed(BINARY, b"x" * 10).data == b"x" * 10
+        code, reason = close_of(guard.feed(BINARY, b"x" * 11))
+        assert code == 1009
+        assert reason == "Message too big"
+        assert guard.close_code == 1009
+        assert guard.feed(BINARY, b"x") is None
+        assert closed("message_too_big") == before + 1
+
+    def test_oversized_across_fragments(self):
+        guard = WsInboundGuard(WsLimits(max_message_size=10))
+        assert guard.feed(BINARY, b"x" * 6, fin=False) is None
+        assert close_of(guard.feed(CONTINUATION, b"x" * 6))[0] == 1009
+
+    def test_too_many_fragments(self):
+        before = closed("too_many_fragments")
+        guard = WsInboundGuard(WsLimits(max_fragments=3))
+        assert guard.feed(TEXT, b"a", fin=False) is None
+        assert guard.feed(CONTINUATION, b"b", fin=False) is None
+        assert guard.feed(CONTINUATION, b"c", fin=False) is None
+        assert close_of(guard.feed(CONTINUATION, b"d"))[0] == 1009
+        assert closed("too_many_fragments") == before + 1
+
+    def test_fragmented_message_joined(self):
+        guard = WsInboundGuard(WsLimits())
+        snowman = "☃".encode()
+        # The character is split across frames
+        assert guard.feed(TEXT, b"a" + snowman[:1], fin=False) is None
+        assert guard.feed(CONTINUATION, snowman[1:]).text() == "a☃"
+
+    def test_invalid_utf8_1007(self):
+        before = closed("invalid_utf8")
+        guard = WsInboundGuard(WsLimits())
+        code, reason = close_of(guard.feed(TEXT, b"ok \xff"))
+        assert code == 1007
+        assert reason == "Invalid UTF-8"
+        assert closed("invalid_utf8") == before + 1
+
+    def test_invalid_utf8_detected_before_last_fragment(self):
+        guard = WsInboundGuard(WsLimits())
+        assert close_of(guard.feed(TEXT, b"\xc3\x28", fin=False))[0] == 1007
+
+    def test_truncated_utf8_at_end_1007(self):
+        guard = WsInboundGuard(WsLimits())
+        assert guard.feed(TEXT, b"\xe2\x98", fin=False) is None
+        assert close_of(guard.feed(CONTINUATION, b""))[0] == 1007
+
+    def test_binary_not_validated(self):
+        guard = WsInboundGuard(WsLimits())
+        assert guard.feed(BINARY, b"\xff\xfe").data == b"\xff\xfe"
+
+    def test_close_frame_passed_through(self):
+        guard = WsInboundGuard(WsLimits())
+        message = guard.feed(CLOSE, (1000).to_bytes(2, "big") + b"bye")
+        assert close_of(message) == (1000, "bye")
+        assert guard.close_code is None
+
+    def test_close_reason_invalid_utf8(self):
+        guard = WsInboundGuard(WsLimits())
+        assert close_of(guard.feed(CLOSE, (1000).to_bytes(2, "big") + b"\xff"))[0] == 1007
+
+    def test_protocol_errors_1002(self):
+        before = closed("protocol_error")
+        cases = [
+            [(CONTINUATION, b"x", True, False)],
+            [(TEXT, b"a", False, False), (TEXT, b"b", True, False)],
+            [(PING, b"x" * 126, True, False)],
+            [(PING, b"", False, False)],
+            [(TEXT, b"x", True, True)],
+            [(0x3, b"", True, False)],
+            [(CLOSE, (999).to_bytes(2, "big"), True, False)],
+        ]
+        for frames in cases:
+            guard = WsInboundGuard(WsLimits())
+            for frame in frames:
+                message = guard.feed(*frame)
+            assert close_of(message)[0] == 1002
+        assert closed("protocol_error") == before + len(cases)
+
+
+class TestPermessageDeflate:
+    """Test compressed messages and negotiation."""
+
+    def guard(self, **limits) -> WsInboundGuard:
+        limits = WsLimits(permessage_deflate=True, **limits)
+        return WsInboundGuard(limits, limits.negotiate("permessage-deflate"))
+
+    def test_compressed_message(self):
+        guard = self.guard()
+        assert guard.compressed
+        before = websocket_inbound_stats()["inflated"]
+        assert guard.feed(TEXT, deflate(b"hello hello hello"), rsv1=True).text() == "hello hello hello"
+        # Context carries over to the next message
+        assert guard.feed(TEXT, deflate(b"second"), rsv1=True) is not None
+        assert websocket_inbound_stats()["inflated"] == before + 2
+
+    def test_context_takeover(self):
+        guard = self.guard()
+        compressor = zlib.compressobj(wbits=-15)
+        for text in [b"repeat me " * 10, b"repeat me " * 10]:
+            payload = (compressor.compress(text) + compressor.flush(zlib.Z_SYNC_FLUSH))[:-4]
+            assert guard.feed(BINARY, payload, rsv1=True).data == text
+
+    def test_compressed_fragments(self):
+        guard = self.guard()
+        payload = deflate(b"fragmented " * 50)
+        assert guard.feed(TEXT, payload[:5], fin=False, rsv1=True) is None
+        assert guard.feed(CONTINUATION, payload[5:]).text() == "fragmented " * 50
+
+    def test_deflate_bomb_stops_at_cap(self):
+        before = closed("decompressed_too_big")
+        guard = self.guard(max_message_size=64 * 1024, max_decompressed_size=100_000)
+        bomb = deflate(b"\0" * (50 * 1024 * 1024))
+        assert len(bomb) < 64 * 1024
+        started = time.monotonic()
+        code, reason = close_of(guard.feed(BINARY, bomb, rsv1=True))
+        assert code == 1009
+        assert reason == "Decompressed message too big"
+        # Inflating stops at the cap instead of producing 50 MiB
+        assert time.monotonic() - started < 1
+        assert closed("decompressed_too_big") == before + 1
+
+    def test_exactly_at_cap(self):
+        guard = self.guard(max_decompressed_size=1000)
+        assert len(guard.feed(BINARY, deflate(b"a" * 1000), rsv1=True).data) == 1000
+
+    def test_compressed_invalid_utf8(self):
+        guard = self.guard()
+        assert close_of(guard.feed(TEXT, deflate(b"\xff\xfe"), rsv1=True))[0] == 1007
+
+    def test_corrupt_data(self):
+        guard = self.guard()
+        assert close_of(guard.feed(BINARY, b"\xff\xff\xff\xff", rsv1=True))[0] == 1007
+
+    def test_compressed_without_negotiation(self):
+        guard = WsInboundGuard(WsLimits(permessage_deflate=True))
+        assert close_of(guard.feed(TEXT, deflate(b"x"), rsv1=True))[0] == 1002
+
+    def test_negotiate(self):
+        limits = WsLimits(permessage_deflate=True)
+        assert limits.negotiate("permessage-deflate") == "permessage-deflate"
+        assert limits.negotiate("permessage-deflate; client_max_window_bits") == "permessage-deflate"
+        assert (
+            limits.negotiate("permessage-deflate; server_no_context_takeover; client_no_context_takeover")
+            == "permessage-deflate; server_no_context_takeover; client_no_context_takeover"
+        )
+        assert limits.negotiate(None) is None
+        assert limits.negotiate("x-webkit-deflate-frame") is None
+        assert WsLimits().negotiate("permessage-deflate") is None
+
+    def test_negotiate_window_bits(self):
+        limits = WsLimits(permessage_deflate=True, server_max_window_bits=10)
+        assert limits.negotiate("permessage-deflate") == "permessage-deflate; server_max_window_bits=10"
+        assert (
+            limits.negotiate("permessage-deflate; server_max_window_bits=9")
+            == "permessage-deflate; server_max_window_bits=9"
+        )
+
+    def test_negotiate_skips_invalid_offers(self):
+        limits = WsLimits(permessage_deflate=True)
+        offers = "permessage-deflate; server_max_window_bits=20, permessage-deflate; unknown, permessage-deflate"
+        assert limits.negotiate(offers) == "permessage-deflate"
+        assert limits.negotiate("permessage-deflate; client_no_context_takeover; client_no_context_takeover") is None
+
+
+class TestRateLimit:
+    """Test max_messages_per_second."""
+
+    def test_close(self):
+        before = closed("rate_limited")
+        guard = WsInboundGuard(WsLimits(max_messages_per_second=3))
+        for _ in range(3):
+            assert guard.feed(TEXT, b"x").msg_type == WsMessageType.Text
+        code, reason = close_of(guard.feed(TEXT, b"x"))
+        assert code == 1008
+        assert reason == "Message rate limit exceeded"
+        assert closed("rate_limited") == before + 1
+
+    def test_refills(self):
+        guard = WsInboundGuard(WsLimits(max_messages_per_second=20))
+        for _ in range(20):
+            guard.feed(TEXT, b"x")
+        time.sleep(0.2)
+        assert guard.feed(TEXT, b"x").msg_type == WsMessageType.Text
+
+    def test_control_frames_not_counted(self):
+        guard = WsInboundGuard(WsLimits(max_messages_per_second=1))
+        for _ in range(5):
+            assert guard.feed(PING, b"").msg_type == WsMessageType.Ping
+        assert guard.feed(TEXT, b"x").msg_type == WsMessageType.Text
+
+    def test_throttle(self):
+        before = websocket_inbound_stats()["throttled"]
+        guard = WsInboundGuard(WsLimits(max_messages_per_second=10, rate_limit_action="throttle"))
+        for _ in range(10):
+            guard.feed(TEXT, b"x")
+            assert guard.delay == 0
+        assert guard.feed(TEXT, b"x").msg_type == WsMessageType.Text
+        assert 0 < guard.delay <= 0.1
+        assert guard.feed(TEXT, b"x") is not None
+        assert guard.delay > 0.1
+        assert guard.close_code is None
+        assert websocket_inbound_stats()["throttled"] == before + 2
+
+
+class TestRouteLimits:
+    """Test limits configured on route registration."""
+
+    def test_options_build_limits(self):
+        router = WebSocketRouter()
+
+        @router.route("/ws/chat", max_message_size=4096, permessage_deflate=True, max_messages_per_second=5)
+        async def chat(ws):
+            pass
+
+        limits = router.get_routes()[0].limits
+        assert limits.max_message_size == 4096
+        assert limits.max_decompressed_size == 4096
+        assert limits.permessage_deflate
+        assert limits.max_messages_per_second == 5
+
+    def test_defaults(self):
+        limits = WebSocketRoute("/ws", lambda ws: None).limits
+        assert limits.max_message_size == 1024 * 1024
+        assert limits.max_fragments == 128
+        assert not limits.permessage_deflate
+        assert limits.max_messages_per_second is None
+
+    def test_invalid_options(self):
+        with pytest.raises(ValueError, match="max_message_size"):
+            WebSocketRoute("/ws", lambda ws: None, max_message_size=0)
+        with pytest.raises(ValueError, match="server_max_window_bits"):
+            WebSocketRoute("/ws", lambda ws: None, server_max_window_bits=16)
+        with pytest.raises(ValueError, match="rate_limit_action"):
+            WebSocketRoute("/ws", lambda ws: None, rate_limit_action="ban")
+        with pytest.raises(ValueError, match="max_messages_per_second"):
+            WebSocketRoute("/ws", lambda ws: None, max_messages_per_second=0)
+
+    @pytest.mark.asyncio
+    async def test_frames_reach_handler(self):
+        route = WebSocketRoute("/ws", lambda ws: None, permessage_deflate=True, max_message_size=100)
+        ws = route.connect(extensions_offer="permessage-deflate; client_max_window_bits")
+        assert ws.extensions == "permessage-deflate"
+        await ws.accept()
+
+        assert ws.feed_frame(TEXT, deflate(b"hi"), rsv1=True) is None
+        assert ws.feed_frame(BINARY, b"\x01") is None
+        assert await ws.receive_text() == "hi"
+        assert await ws.receive_bytes() == b"\x01"
+
+        assert ws.feed_frame(BINARY, b"x" * 101) == (1009, "Message too big")
+        with pytest.raises(WebSocketDisconnect) as err:
+            await ws.receive()
+        assert err.value.code == 1009
+
+    @pytest.mark.asyncio
+    async def test_client_close(self):
+        ws = WebSocket()
+        await ws.accept()
+        assert ws.feed_frame(CLOSE, (1001).to_bytes(2, "big") + b"away") is None
+        with pytest.raises(WebSocketDisconnect) as err:
+            await ws.receive()
+        assert (err.value.code, err.value.reason) == (1001, "away")
@@ -0,0 +1,78 @@
+"""
+Test cases for worker warm-up at startup.
+
+Tests cover:
+- eager_import loading lazily registered handler modules before the first request
+- Lazy handlers imported by their first request without eager_import
+- warmup_paths sent in-process with the X-Hypern-Warmup header
+- A failing warm-up path keeping readiness at 503 with warmup_strict
+- A failing warm-up path only logged without warmup_strict
+"""
+
+import time
+from contextlib import contextmanager
+
+import httpx
+
+from tests.conftest import server_process
+
+
+@contextmanager
+def warmup_server(*args: str):
+    with server_process(*args, ready_path="/_health") as server:
+        with httpx.Client(base_url=server.base_url, timeout=5.0) as client:
+            yield client
+
+
+def wait_until_ready(client: httpx.Client, timeout: float = 10.0):
+    deadline = time.time() + timeout
+    while time.time() < deadline:
+        if client.get("/_health/ready").status_code == 200:
+            return
+        time.sleep(0.1)
+    raise AssertionError("worker never became ready")
+
+
+class TestEagerImport:
+    """Test when lazily registered handler modules are imported."""
+
+    def test_module_imported_before_first_request(self):
+        with warmup_server("--eager-import") as client:
+            wait_until_ready(client)
+            first_request_at = time.time()
+            imported_at = client.get("/warmup/lazy").json()["imported_at"]
+            assert imported_at < first_request_at
+
+    def test_lazy_module_imported_on_first_request(self):
+        with warmup_server() as client:
+            wait_until_ready(client)
+            first_request_at = time.time()
+            imported_at = client.get("/warmup/lazy").json()["imported_at"]
+            assert imported_at >= first_request_at
+
+
+class TestWarmupPaths:
+    """Test synthetic warm-up requests."""
+
+    def test_paths_requested_before_ready(self):
+        with warmup_server("--warmup-path", "/warmup/ping") as client:
+            wait_until_ready(client)
+            assert client.get("/warmup/hits").json() == {"count": 1}
+            # Real requests do not carry the warm-up header
+            client.get("/warmup/ping")
+            assert client.get("/warmup/hits").json() == {"count": 1}
+
+    def test_strict_failure_keeps_worker_unready(self):
+        with warmup_server("--warmup-path", "/warmup/fail", "--warmup-strict") as client:
+            # Well past the 2s startup grace period
+            time.sleep(3.5)
+            assert client.get("/_health/ready").status_code == 503
+            assert client.get("/_health/startup").status_code == 503
+            assert client.get("/_health").json()["status"] == "starting"
+            # The worker still answers requests sent to it directly
+            assert client.get("/warmup/ping").status_code == 200
+
+    def test_failure_without_strict_still_ready(self):
+        with warmup_server("--warmup-path", "/warmup/fail") as client:
+            wait_until_ready(client)
+            assert client.get("/_health").json()["status"] == "healthy"
@@ -0,0 +1,14 @@
+"""
+Handlers registered lazily by the test server (``"warmup_handlers:..."``).
+
+Importing this module records when it happened, so tests can tell whether
+the worker imported it at startup (``eager_import``) or on first request.
+"""
+
+import time
+
+IMPORTED_AT = time.time()
+
+
+def imported_at(req, res, ctx):
+    res.json({"imported_at": IMPORTED_AT})